@@ -1,28 +1,80 @@
+/// Enum fields rendered by variant name in JSON/YAML output
+const ENUM_FIELDS: &[(&str, &str)] = &[
+    ("VMStatus.state", "VmState"),
+    ("VMLogEntry.source", "VmLogSource"),
+    ("NetworkSpec.mode", "NetworkMode"),
+    ("PortForwardSpec.protocol", "PortProtocol"),
+    ("DeviceSpec.bus", "DeviceBus"),
+    ("VolumeSpec.kind", "VolumeKind"),
+    ("DownloadStatus.state", "DownloadState"),
+    ("BackupStatus.kind", "BackupKind"),
+    ("Event.type", "EventType"),
+    ("Event.vm_state", "VmState"),
+    ("ApplianceSpec.type", "ApplianceType"),
+    ("JobSpec.kind", "JobKind"),
+    ("JobStatus.state", "JobState"),
+];
+
+/// Bytes fields rendered as hex in JSON/YAML output
+const BYTES_FIELDS: &[&str] = &[
+    "IntegrityConfig.public_key",
+    "IntegrityConfig.signature",
+    "VolumeEncryption.secret",
+    "BenchmarkReceipt.signature",
+    "AttestationReport.signature",
+    "TpmQuote.quoted",
+    "TpmQuote.signature",
+    "TpmQuote.ak_public",
+    "TpmQuote.nonce",
+    "TpmEvent.data",
+    "LoRaDeviceSpec.app_key",
+    "GetPipelineLogsResponse.data",
+];
+
+/// Client-only codegen with serde support for the output module
+fn builder() -> tonic_build::Builder {
+    let mut builder = tonic_build::configure()
+        .build_server(false)
+        .build_client(true)
+        .out_dir("src/generated")
+        .message_attribute(".infrasim.v1", "#[derive(serde::Serialize)]");
+
+    for (field, enum_type) in ENUM_FIELDS {
+        builder = builder.field_attribute(
+            format!(".infrasim.v1.{}", field),
+            format!(
+                "#[serde(serialize_with = \"crate::output::proto_enum::<{}, _>\")]",
+                enum_type
+            ),
+        );
+    }
+    for field in BYTES_FIELDS {
+        builder = builder.field_attribute(
+            format!(".infrasim.v1.{}", field),
+            "#[serde(serialize_with = \"crate::output::hex_bytes\")]",
+        );
+    }
+
+    builder
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let proto_file = "../../proto/infrasim.proto";
     let proto_dir = "../../proto";
-    
+
     std::fs::create_dir_all("src/generated")?;
 
     if std::path::Path::new(proto_file).exists() {
         println!("cargo:rerun-if-changed={}", proto_file);
 
-        tonic_build::configure()
-            .build_server(false)
-            .build_client(true)
-            .out_dir("src/generated")
-            .compile(&[proto_file], &[proto_dir])?;
+        builder().compile(&[proto_file], &[proto_dir])?;
     } else {
         // Try alternative path
         let alt_proto_file = "proto/infrasim.proto";
         if std::path::Path::new(alt_proto_file).exists() {
             println!("cargo:rerun-if-changed={}", alt_proto_file);
-            
-            tonic_build::configure()
-                .build_server(false)
-                .build_client(true)
-                .out_dir("src/generated")
-                .compile(&[alt_proto_file], &["proto"])?;
+
+            builder().compile(&[alt_proto_file], &["proto"])?;
         } else {
             // Create empty generated file if proto doesn't exist
             let generated_path = std::path::Path::new("src/generated/infrasim.rs");
@@ -1,6 +1,8 @@
 //! Daemon gRPC Client
 
-use tonic::transport::Channel;
+use std::collections::HashMap;
+use infrasim_common::auth::{AuthChannel, ClientCredentials};
+use tokio_stream::wrappers::ReceiverStream;
 use anyhow::Result;
 
 use crate::generated::infra_sim_daemon_client::InfraSimDaemonClient;
@@ -8,14 +10,14 @@ use crate::generated::*;
 
 /// Client for communicating with the InfraSim daemon
 pub struct DaemonClient {
-    client: InfraSimDaemonClient<Channel>,
+    client: InfraSimDaemonClient<AuthChannel>,
 }
 
 impl DaemonClient {
     /// Create a new daemon client
-    pub async fn new(addr: &str) -> Result<Self> {
-        let client = InfraSimDaemonClient::connect(addr.to_string()).await?;
-        Ok(Self { client })
+    pub async fn new(addr: &str, credentials: &ClientCredentials) -> Result<Self> {
+        let channel = credentials.connect(addr).await?;
+        Ok(Self { client: InfraSimDaemonClient::new(channel) })
     }
 
     /// Check if the daemon is healthy
@@ -24,14 +26,21 @@ impl DaemonClient {
         self.client.get_health(request).await.is_ok()
     }
 
+    /// Get host capacity and what VMs have committed of it
+    pub async fn get_daemon_status(&mut self) -> Result<GetDaemonStatusResponse> {
+        let request = tonic::Request::new(GetDaemonStatusRequest {});
+        let response = self.client.get_daemon_status(request).await?;
+        Ok(response.into_inner())
+    }
+
     // VM operations
 
     /// Create a new VM
-    pub async fn create_vm(&mut self, name: &str, spec: VmSpec) -> Result<Vm> {
+    pub async fn create_vm(&mut self, name: &str, spec: VmSpec, labels: HashMap<String, String>) -> Result<Vm> {
         let request = tonic::Request::new(CreateVmRequest {
             name: name.to_string(),
             spec: Some(spec),
-            labels: Default::default(),
+            labels,
         });
         let response = self.client.create_vm(request).await?;
         response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
@@ -46,13 +55,42 @@ impl DaemonClient {
 
     /// List all VMs
     pub async fn list_vms(&mut self) -> Result<Vec<Vm>> {
-        let request = tonic::Request::new(ListVMsRequest {
-            label_selector: Default::default(),
-        });
+        self.list_vms_matching(HashMap::new()).await
+    }
+
+    /// List VMs whose labels match every `key=value` pair of a selector
+    pub async fn list_vms_matching(&mut self, label_selector: HashMap<String, String>) -> Result<Vec<Vm>> {
+        let request = tonic::Request::new(ListVMsRequest { label_selector });
         let response = self.client.list_v_ms(request).await?;
         Ok(response.into_inner().vms)
     }
 
+    /// Replace a VM's spec
+    pub async fn update_vm(&mut self, id: &str, spec: VmSpec) -> Result<Vm> {
+        let request = tonic::Request::new(UpdateVmRequest {
+            id: id.to_string(),
+            spec: Some(spec),
+        });
+        let response = self.client.update_vm(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
+    /// Set and remove VM labels
+    pub async fn update_vm_labels(
+        &mut self,
+        id: &str,
+        set: HashMap<String, String>,
+        remove: Vec<String>,
+    ) -> Result<Vm> {
+        let request = tonic::Request::new(UpdateVmLabelsRequest {
+            id: id.to_string(),
+            set,
+            remove,
+        });
+        let response = self.client.update_vm_labels(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
     /// Start a VM
     pub async fn start_vm(&mut self, id: &str) -> Result<Vm> {
         let request = tonic::Request::new(StartVmRequest { id: id.to_string() });
@@ -60,16 +98,88 @@ impl DaemonClient {
         response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
     }
 
-    /// Stop a VM
-    pub async fn stop_vm(&mut self, id: &str, force: bool) -> Result<Vm> {
+    /// Stop a VM; a zero timeout uses the daemon's default
+    pub async fn stop_vm(&mut self, id: &str, mode: StopMode, timeout_seconds: u32) -> Result<Vm> {
         let request = tonic::Request::new(StopVmRequest {
             id: id.to_string(),
-            force,
+            force: false,
+            mode: mode as i32,
+            timeout_seconds,
         });
         let response = self.client.stop_vm(request).await?;
         response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
     }
 
+    /// Pause a running VM
+    pub async fn pause_vm(&mut self, id: &str) -> Result<Vm> {
+        let request = tonic::Request::new(PauseVmRequest { id: id.to_string() });
+        let response = self.client.pause_vm(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
+    /// Resume a paused or suspended VM
+    pub async fn resume_vm(&mut self, id: &str) -> Result<Vm> {
+        let request = tonic::Request::new(ResumeVmRequest { id: id.to_string(), if_idle: false });
+        let response = self.client.resume_vm(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
+    /// Save a VM's RAM to disk and power it off
+    pub async fn suspend_vm(&mut self, id: &str) -> Result<Vm> {
+        let request = tonic::Request::new(SuspendVmRequest { id: id.to_string() });
+        let response = self.client.suspend_vm(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
+    /// Stream an export bundle of a stopped VM
+    pub async fn export_vm(
+        &mut self,
+        id: &str,
+        format: ExportFormat,
+    ) -> Result<tonic::Streaming<ExportVmResponse>> {
+        let request = tonic::Request::new(ExportVmRequest {
+            id: id.to_string(),
+            format: format as i32,
+        });
+        let response = self.client.export_vm(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Upload an export bundle, creating a VM and its volumes from it
+    pub async fn import_vm(
+        &mut self,
+        requests: ReceiverStream<ImportVmRequest>,
+    ) -> Result<ImportVmResponse> {
+        let response = self.client.import_vm(requests).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Publish a stopped VM as an appliance artifact in an OCI registry
+    pub async fn push_appliance(&mut self, vm_id: &str, reference: &str) -> Result<PushApplianceResponse> {
+        let request = tonic::Request::new(PushApplianceRequest {
+            vm_id: vm_id.to_string(),
+            reference: reference.to_string(),
+        });
+        let response = self.client.push_appliance(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Create a VM and its volumes from an appliance artifact
+    pub async fn pull_appliance(
+        &mut self,
+        reference: &str,
+        name: Option<String>,
+        labels: HashMap<String, String>,
+    ) -> Result<PullApplianceResponse> {
+        let request = tonic::Request::new(PullApplianceRequest {
+            reference: reference.to_string(),
+            name: name.unwrap_or_default(),
+            labels,
+        });
+        let response = self.client.pull_appliance(request).await?;
+        Ok(response.into_inner())
+    }
+
     /// Delete a VM
     pub async fn delete_vm(&mut self, id: &str, force: bool) -> Result<()> {
         let request = tonic::Request::new(DeleteVmRequest {
@@ -80,14 +190,126 @@ impl DaemonClient {
         Ok(())
     }
 
+    /// Freeze a stopped VM as a template for linked clones
+    pub async fn mark_vm_as_template(&mut self, id: &str) -> Result<Vm> {
+        let request = tonic::Request::new(MarkVmAsTemplateRequest { id: id.to_string() });
+        let response = self.client.mark_vm_as_template(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
+    /// Create `count` linked clones of a template (ID or name)
+    pub async fn clone_vm(
+        &mut self,
+        template: &str,
+        name: &str,
+        count: i32,
+        labels: HashMap<String, String>,
+    ) -> Result<Vec<Vm>> {
+        let request = tonic::Request::new(CloneVmRequest {
+            template_id: template.to_string(),
+            name: name.to_string(),
+            count,
+            labels,
+        });
+        let response = self.client.clone_vm(request).await?;
+        Ok(response.into_inner().vms)
+    }
+
+    /// Attach a raw host block device to a VM
+    pub async fn attach_device(
+        &mut self,
+        vm_id: &str,
+        device_path: &str,
+        name: Option<String>,
+        read_only: bool,
+        force: bool,
+    ) -> Result<AttachDeviceResponse> {
+        let request = tonic::Request::new(AttachDeviceRequest {
+            vm_id: vm_id.to_string(),
+            device_path: device_path.to_string(),
+            name: name.unwrap_or_default(),
+            read_only,
+            force,
+        });
+        let response = self.client.attach_device(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Stream a VM's serial console output and lifecycle events
+    pub async fn stream_vm_logs(
+        &mut self,
+        vm_id: &str,
+        follow: bool,
+        tail_lines: u32,
+        include_events: bool,
+    ) -> Result<tonic::Streaming<VmLogEntry>> {
+        let request = tonic::Request::new(StreamVmLogsRequest {
+            vm_id: vm_id.to_string(),
+            follow,
+            tail_lines,
+            include_events,
+        });
+        let response = self.client.stream_vm_logs(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Get a running VM's resource usage
+    pub async fn get_vm_stats(&mut self, id: &str) -> Result<VmStats> {
+        let request = tonic::Request::new(GetVmStatsRequest { id: id.to_string() });
+        let response = self.client.get_vm_stats(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Send a raw QMP command to a running VM and return QEMU's JSON reply
+    pub async fn execute_qmp(&mut self, id: &str, command_json: &str) -> Result<String> {
+        let request = tonic::Request::new(ExecuteQmpRequest {
+            id: id.to_string(),
+            command_json: command_json.to_string(),
+        });
+        let response = self.client.execute_qmp(request).await?;
+        Ok(response.into_inner().response_json)
+    }
+
+    /// Stream a running VM's resource usage every `interval_ms`
+    pub async fn watch_vm_stats(&mut self, id: &str, interval_ms: u32) -> Result<tonic::Streaming<VmStats>> {
+        let request = tonic::Request::new(WatchVmStatsRequest {
+            id: id.to_string(),
+            interval_ms,
+        });
+        let response = self.client.watch_vm_stats(request).await?;
+        Ok(response.into_inner())
+    }
+
+    // Event operations
+
+    /// Stream daemon events, replaying up to `tail` recent ones first
+    pub async fn watch_events(
+        &mut self,
+        resource_type: String,
+        resource_id: String,
+        types: Vec<EventType>,
+        tail: u32,
+        follow: bool,
+    ) -> Result<tonic::Streaming<Event>> {
+        let request = tonic::Request::new(WatchEventsRequest {
+            resource_type,
+            resource_id,
+            types: types.into_iter().map(|t| t as i32).collect(),
+            tail,
+            follow,
+        });
+        let response = self.client.watch_events(request).await?;
+        Ok(response.into_inner())
+    }
+
     // Network operations
 
     /// Create a network
-    pub async fn create_network(&mut self, name: &str, spec: NetworkSpec) -> Result<Network> {
+    pub async fn create_network(&mut self, name: &str, spec: NetworkSpec, labels: HashMap<String, String>) -> Result<Network> {
         let request = tonic::Request::new(CreateNetworkRequest {
             name: name.to_string(),
             spec: Some(spec),
-            labels: Default::default(),
+            labels,
         });
         let response = self.client.create_network(request).await?;
         response.into_inner().network.ok_or_else(|| anyhow::anyhow!("No network in response"))
@@ -116,14 +338,263 @@ impl DaemonClient {
         Ok(())
     }
 
+    /// Start capturing a network's packets
+    pub async fn start_network_capture(
+        &mut self,
+        network_id: &str,
+        filter: Option<String>,
+        max_bytes: u64,
+        max_seconds: u64,
+    ) -> Result<NetworkCapture> {
+        let request = tonic::Request::new(StartNetworkCaptureRequest {
+            network_id: network_id.to_string(),
+            filter: filter.unwrap_or_default(),
+            max_bytes,
+            max_seconds,
+        });
+        let response = self.client.start_network_capture(request).await?;
+        response.into_inner().capture.ok_or_else(|| anyhow::anyhow!("No capture in response"))
+    }
+
+    /// Stop a network's running capture
+    pub async fn stop_network_capture(&mut self, network_id: &str) -> Result<NetworkCapture> {
+        let request = tonic::Request::new(StopNetworkCaptureRequest {
+            network_id: network_id.to_string(),
+        });
+        let response = self.client.stop_network_capture(request).await?;
+        response.into_inner().capture.ok_or_else(|| anyhow::anyhow!("No capture in response"))
+    }
+
+    /// Stream the pcap file of a network's last stopped capture
+    pub async fn read_network_capture(
+        &mut self,
+        network_id: &str,
+    ) -> Result<tonic::Streaming<ReadNetworkCaptureResponse>> {
+        let request = tonic::Request::new(ReadNetworkCaptureRequest {
+            network_id: network_id.to_string(),
+        });
+        let response = self.client.read_network_capture(request).await?;
+        Ok(response.into_inner())
+    }
+
+    // Port forward operations
+
+    /// Create a port forward
+    pub async fn create_port_forward(&mut self, name: &str, spec: PortForwardSpec, labels: HashMap<String, String>) -> Result<PortForward> {
+        let request = tonic::Request::new(CreatePortForwardRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels,
+        });
+        let response = self.client.create_port_forward(request).await?;
+        response.into_inner().port_forward.ok_or_else(|| anyhow::anyhow!("No port forward in response"))
+    }
+
+    /// List port forwards
+    pub async fn list_port_forwards(&mut self, vm_id: Option<String>, network_id: Option<String>) -> Result<Vec<PortForward>> {
+        let request = tonic::Request::new(ListPortForwardsRequest {
+            vm_id: vm_id.unwrap_or_default(),
+            network_id: network_id.unwrap_or_default(),
+        });
+        let response = self.client.list_port_forwards(request).await?;
+        Ok(response.into_inner().port_forwards)
+    }
+
+    /// Delete a port forward
+    pub async fn delete_port_forward(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeletePortForwardRequest { id: id.to_string() });
+        self.client.delete_port_forward(request).await?;
+        Ok(())
+    }
+
+    // Router operations
+
+    /// Create a router
+    pub async fn create_router(&mut self, name: &str, spec: RouterSpec) -> Result<Router> {
+        let request = tonic::Request::new(CreateRouterRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        });
+        let response = self.client.create_router(request).await?;
+        response.into_inner().router.ok_or_else(|| anyhow::anyhow!("No router in response"))
+    }
+
+    /// Get a router
+    pub async fn get_router(&mut self, id: &str) -> Result<Router> {
+        let request = tonic::Request::new(GetRouterRequest { id: id.to_string() });
+        let response = self.client.get_router(request).await?;
+        response.into_inner().router.ok_or_else(|| anyhow::anyhow!("Router not found"))
+    }
+
+    /// Replace a router's networks, routes and rules
+    pub async fn update_router(&mut self, id: &str, spec: RouterSpec) -> Result<Router> {
+        let request = tonic::Request::new(UpdateRouterRequest {
+            id: id.to_string(),
+            spec: Some(spec),
+        });
+        let response = self.client.update_router(request).await?;
+        response.into_inner().router.ok_or_else(|| anyhow::anyhow!("No router in response"))
+    }
+
+    /// List routers, optionally only those connected to a network
+    pub async fn list_routers(&mut self, network_id: Option<String>) -> Result<Vec<Router>> {
+        let request = tonic::Request::new(ListRoutersRequest {
+            network_id: network_id.unwrap_or_default(),
+            label_selector: Default::default(),
+        });
+        let response = self.client.list_routers(request).await?;
+        Ok(response.into_inner().routers)
+    }
+
+    /// Delete a router
+    pub async fn delete_router(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteRouterRequest { id: id.to_string() });
+        self.client.delete_router(request).await?;
+        Ok(())
+    }
+
+    // QoS profile operations
+
+    /// Create a QoS profile
+    pub async fn create_qos_profile(&mut self, name: &str, spec: QoSProfileSpec) -> Result<QoSProfile> {
+        let request = tonic::Request::new(CreateQoSProfileRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        });
+        let response = self.client.create_qo_s_profile(request).await?;
+        response.into_inner().profile.ok_or_else(|| anyhow::anyhow!("No QoS profile in response"))
+    }
+
+    /// Get a QoS profile by ID
+    pub async fn get_qos_profile(&mut self, id: &str) -> Result<QoSProfile> {
+        let request = tonic::Request::new(GetQoSProfileRequest { id: id.to_string() });
+        let response = self.client.get_qo_s_profile(request).await?;
+        response.into_inner().profile.ok_or_else(|| anyhow::anyhow!("QoS profile not found"))
+    }
+
+    /// List QoS profiles
+    pub async fn list_qos_profiles(&mut self) -> Result<Vec<QoSProfile>> {
+        let request = tonic::Request::new(ListQoSProfilesRequest {
+            label_selector: Default::default(),
+        });
+        let response = self.client.list_qo_s_profiles(request).await?;
+        Ok(response.into_inner().profiles)
+    }
+
+    /// Delete a QoS profile
+    pub async fn delete_qos_profile(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteQoSProfileRequest { id: id.to_string() });
+        self.client.delete_qo_s_profile(request).await?;
+        Ok(())
+    }
+
+    // Chaos profile operations
+
+    /// Create a chaos profile, or replace the spec of the one named `name`
+    pub async fn apply_chaos_profile(
+        &mut self,
+        name: &str,
+        spec: ChaosProfileSpec,
+        ttl_seconds: i64,
+    ) -> Result<ChaosProfile> {
+        let request = tonic::Request::new(ApplyChaosProfileRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            ttl_seconds,
+            labels: Default::default(),
+        });
+        let response = self.client.apply_chaos_profile(request).await?;
+        response.into_inner().profile.ok_or_else(|| anyhow::anyhow!("No chaos profile in response"))
+    }
+
+    /// Get a chaos profile by ID
+    pub async fn get_chaos_profile(&mut self, id: &str) -> Result<ChaosProfile> {
+        let request = tonic::Request::new(GetChaosProfileRequest { id: id.to_string() });
+        let response = self.client.get_chaos_profile(request).await?;
+        response.into_inner().profile.ok_or_else(|| anyhow::anyhow!("Chaos profile not found"))
+    }
+
+    /// List chaos profiles, optionally those on a network or affecting a VM
+    pub async fn list_chaos_profiles(
+        &mut self,
+        network_id: Option<&str>,
+        vm_id: Option<&str>,
+    ) -> Result<Vec<ChaosProfile>> {
+        let request = tonic::Request::new(ListChaosProfilesRequest {
+            network_id: network_id.unwrap_or_default().to_string(),
+            vm_id: vm_id.unwrap_or_default().to_string(),
+        });
+        let response = self.client.list_chaos_profiles(request).await?;
+        Ok(response.into_inner().profiles)
+    }
+
+    /// Delete a chaos profile
+    pub async fn delete_chaos_profile(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteChaosProfileRequest { id: id.to_string() });
+        self.client.delete_chaos_profile(request).await?;
+        Ok(())
+    }
+
+    // Scenario run operations
+
+    /// Start running a scenario from its YAML definition
+    pub async fn run_scenario(&mut self, definition: String, labels: HashMap<String, String>) -> Result<ScenarioRun> {
+        let request = tonic::Request::new(RunScenarioRequest { definition, labels });
+        let response = self.client.run_scenario(request).await?;
+        response.into_inner().run.ok_or_else(|| anyhow::anyhow!("No scenario run returned"))
+    }
+
+    /// Get a scenario run and its report
+    pub async fn get_scenario_run(&mut self, id: &str) -> Result<ScenarioRun> {
+        let request = tonic::Request::new(GetScenarioRunRequest { id: id.to_string() });
+        let response = self.client.get_scenario_run(request).await?;
+        response.into_inner().run.ok_or_else(|| anyhow::anyhow!("Scenario run not found"))
+    }
+
+    /// List scenario runs, optionally those of one scenario
+    pub async fn list_scenario_runs(&mut self, scenario: Option<&str>) -> Result<Vec<ScenarioRun>> {
+        let request = tonic::Request::new(ListScenarioRunsRequest {
+            scenario: scenario.unwrap_or_default().to_string(),
+        });
+        let response = self.client.list_scenario_runs(request).await?;
+        Ok(response.into_inner().runs)
+    }
+
+    /// Cancel a running scenario run
+    pub async fn cancel_scenario_run(&mut self, id: &str) -> Result<ScenarioRun> {
+        let request = tonic::Request::new(CancelScenarioRunRequest { id: id.to_string() });
+        let response = self.client.cancel_scenario_run(request).await?;
+        response.into_inner().run.ok_or_else(|| anyhow::anyhow!("No scenario run returned"))
+    }
+
+    /// Delete a finished scenario run
+    pub async fn delete_scenario_run(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteScenarioRunRequest { id: id.to_string() });
+        self.client.delete_scenario_run(request).await?;
+        Ok(())
+    }
+
+    /// Compare the step reports of two scenario runs
+    pub async fn compare_scenario_runs(&mut self, base_id: &str, other_id: &str) -> Result<CompareScenarioRunsResponse> {
+        let request = tonic::Request::new(CompareScenarioRunsRequest {
+            base_id: base_id.to_string(),
+            other_id: other_id.to_string(),
+        });
+        let response = self.client.compare_scenario_runs(request).await?;
+        Ok(response.into_inner())
+    }
+
     // Volume operations
 
     /// Create a volume
-    pub async fn create_volume(&mut self, name: &str, spec: VolumeSpec) -> Result<Volume> {
+    pub async fn create_volume(&mut self, name: &str, spec: VolumeSpec, labels: HashMap<String, String>) -> Result<Volume> {
         let request = tonic::Request::new(CreateVolumeRequest {
             name: name.to_string(),
             spec: Some(spec),
-            labels: Default::default(),
+            labels,
+            build: None,
         });
         let response = self.client.create_volume(request).await?;
         response.into_inner().volume.ok_or_else(|| anyhow::anyhow!("No volume in response"))
@@ -153,6 +624,68 @@ impl DaemonClient {
         Ok(())
     }
 
+    /// Watch the remote import progress of a volume
+    pub async fn watch_volume_download(
+        &mut self,
+        id: &str,
+    ) -> Result<tonic::Streaming<DownloadStatus>> {
+        let request = tonic::Request::new(WatchVolumeDownloadRequest {
+            volume_id: id.to_string(),
+        });
+        let response = self.client.watch_volume_download(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Get a volume's backing-file chain, active image first
+    pub async fn get_volume_chain(&mut self, id: &str) -> Result<Vec<VolumeLayer>> {
+        let request = tonic::Request::new(GetVolumeChainRequest {
+            volume_id: id.to_string(),
+        });
+        let response = self.client.get_volume_chain(request).await?;
+        Ok(response.into_inner().layers)
+    }
+
+    /// Resize a volume to `size_bytes`
+    pub async fn resize_volume(&mut self, id: &str, size_bytes: i64, shrink: bool) -> Result<Volume> {
+        let request = tonic::Request::new(ResizeVolumeRequest {
+            volume_id: id.to_string(),
+            size_bytes,
+            shrink,
+        });
+        let response = self.client.resize_volume(request).await?;
+        response.into_inner().volume.ok_or_else(|| anyhow::anyhow!("No volume in response"))
+    }
+
+    /// Push a volume's image to a remote store
+    pub async fn push_volume(&mut self, id: &str, remote: &str) -> Result<PushVolumeResponse> {
+        let request = tonic::Request::new(PushVolumeRequest {
+            volume_id: id.to_string(),
+            remote: remote.to_string(),
+        });
+        let response = self.client.push_volume(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Create a volume from one pushed to a remote store
+    pub async fn pull_volume(&mut self, id: &str, remote: &str, name: Option<String>) -> Result<Volume> {
+        let request = tonic::Request::new(PullVolumeRequest {
+            volume_id: id.to_string(),
+            remote: remote.to_string(),
+            name: name.unwrap_or_default(),
+        });
+        let response = self.client.pull_volume(request).await?;
+        response.into_inner().volume.ok_or_else(|| anyhow::anyhow!("No volume in response"))
+    }
+
+    /// Flatten a volume's backing chain into a standalone image
+    pub async fn flatten_volume(&mut self, id: &str) -> Result<tonic::Streaming<FlattenProgress>> {
+        let request = tonic::Request::new(FlattenVolumeRequest {
+            volume_id: id.to_string(),
+        });
+        let response = self.client.flatten_volume(request).await?;
+        Ok(response.into_inner())
+    }
+
     // Snapshot operations
 
     /// Create a snapshot
@@ -193,6 +726,25 @@ impl DaemonClient {
         response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
     }
 
+    /// Revert a VM's disk to a snapshot
+    pub async fn revert_snapshot(&mut self, id: &str) -> Result<Vm> {
+        let request = tonic::Request::new(RevertSnapshotRequest {
+            snapshot_id: id.to_string(),
+        });
+        let response = self.client.revert_snapshot(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
+    /// Create a new VM branched from a snapshot
+    pub async fn branch_snapshot(&mut self, id: &str, name: &str) -> Result<Vm> {
+        let request = tonic::Request::new(BranchSnapshotRequest {
+            snapshot_id: id.to_string(),
+            name: name.to_string(),
+        });
+        let response = self.client.branch_snapshot(request).await?;
+        response.into_inner().vm.ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
     /// Delete a snapshot
     pub async fn delete_snapshot(&mut self, id: &str) -> Result<()> {
         let request = tonic::Request::new(DeleteSnapshotRequest { id: id.to_string() });
@@ -200,6 +752,154 @@ impl DaemonClient {
         Ok(())
     }
 
+    // Snapshot policy operations
+
+    /// Create a snapshot policy
+    pub async fn create_snapshot_policy(
+        &mut self,
+        name: &str,
+        spec: SnapshotPolicySpec,
+        labels: HashMap<String, String>,
+    ) -> Result<SnapshotPolicy> {
+        let request = tonic::Request::new(CreateSnapshotPolicyRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels,
+        });
+        let response = self.client.create_snapshot_policy(request).await?;
+        response.into_inner().policy.ok_or_else(|| anyhow::anyhow!("No snapshot policy in response"))
+    }
+
+    /// List snapshot policies
+    pub async fn list_snapshot_policies(&mut self, vm_id: Option<String>) -> Result<Vec<SnapshotPolicy>> {
+        let request = tonic::Request::new(ListSnapshotPoliciesRequest {
+            vm_id: vm_id.unwrap_or_default(),
+        });
+        let response = self.client.list_snapshot_policies(request).await?;
+        Ok(response.into_inner().policies)
+    }
+
+    /// Delete a snapshot policy
+    pub async fn delete_snapshot_policy(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteSnapshotPolicyRequest { id: id.to_string() });
+        self.client.delete_snapshot_policy(request).await?;
+        Ok(())
+    }
+
+    // Backup operations
+
+    /// Create a backup
+    pub async fn create_backup(&mut self, name: &str, spec: BackupSpec) -> Result<Backup> {
+        let request = tonic::Request::new(CreateBackupRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        });
+        let response = self.client.create_backup(request).await?;
+        response.into_inner().backup.ok_or_else(|| anyhow::anyhow!("No backup in response"))
+    }
+
+    /// Get a backup by ID
+    pub async fn get_backup(&mut self, id: &str) -> Result<Backup> {
+        let request = tonic::Request::new(GetBackupRequest { id: id.to_string() });
+        let response = self.client.get_backup(request).await?;
+        response.into_inner().backup.ok_or_else(|| anyhow::anyhow!("Backup not found"))
+    }
+
+    /// List backups
+    pub async fn list_backups(&mut self, vm_id: Option<String>) -> Result<Vec<Backup>> {
+        let request = tonic::Request::new(ListBackupsRequest {
+            vm_id: vm_id.unwrap_or_default(),
+        });
+        let response = self.client.list_backups(request).await?;
+        Ok(response.into_inner().backups)
+    }
+
+    /// Restore a backup into a new VM
+    pub async fn restore_backup(&mut self, id: &str, name: Option<String>) -> Result<RestoreBackupResponse> {
+        let request = tonic::Request::new(RestoreBackupRequest {
+            backup_id: id.to_string(),
+            name: name.unwrap_or_default(),
+        });
+        let response = self.client.restore_backup(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Delete a backup
+    pub async fn delete_backup(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteBackupRequest { id: id.to_string() });
+        self.client.delete_backup(request).await?;
+        Ok(())
+    }
+
+    // Backup policy operations
+
+    /// Create a backup policy
+    pub async fn create_backup_policy(&mut self, name: &str, spec: BackupPolicySpec) -> Result<BackupPolicy> {
+        let request = tonic::Request::new(CreateBackupPolicyRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        });
+        let response = self.client.create_backup_policy(request).await?;
+        response.into_inner().policy.ok_or_else(|| anyhow::anyhow!("No backup policy in response"))
+    }
+
+    /// List backup policies
+    pub async fn list_backup_policies(&mut self, vm_id: Option<String>) -> Result<Vec<BackupPolicy>> {
+        let request = tonic::Request::new(ListBackupPoliciesRequest {
+            vm_id: vm_id.unwrap_or_default(),
+        });
+        let response = self.client.list_backup_policies(request).await?;
+        Ok(response.into_inner().policies)
+    }
+
+    /// Delete a backup policy
+    pub async fn delete_backup_policy(&mut self, id: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteBackupPolicyRequest { id: id.to_string() });
+        self.client.delete_backup_policy(request).await?;
+        Ok(())
+    }
+
+    // Job operations
+
+    /// Queue a background job
+    pub async fn create_job(&mut self, kind: JobKind, target: &str, params: HashMap<String, String>) -> Result<Job> {
+        let request = tonic::Request::new(CreateJobRequest {
+            spec: Some(JobSpec {
+                kind: kind as i32,
+                target: target.to_string(),
+                params,
+            }),
+            labels: Default::default(),
+        });
+        let response = self.client.create_job(request).await?;
+        response.into_inner().job.ok_or_else(|| anyhow::anyhow!("No job in response"))
+    }
+
+    /// Get a job by ID
+    pub async fn get_job(&mut self, id: &str) -> Result<Job> {
+        let request = tonic::Request::new(GetJobRequest { id: id.to_string() });
+        let response = self.client.get_job(request).await?;
+        response.into_inner().job.ok_or_else(|| anyhow::anyhow!("Job not found"))
+    }
+
+    /// List jobs, newest first
+    pub async fn list_jobs(&mut self, state: Option<JobState>) -> Result<Vec<Job>> {
+        let request = tonic::Request::new(ListJobsRequest {
+            state: state.unwrap_or(JobState::Unspecified) as i32,
+        });
+        let response = self.client.list_jobs(request).await?;
+        Ok(response.into_inner().jobs)
+    }
+
+    /// Cancel a pending job or stop a running one
+    pub async fn cancel_job(&mut self, id: &str) -> Result<Job> {
+        let request = tonic::Request::new(CancelJobRequest { id: id.to_string() });
+        let response = self.client.cancel_job(request).await?;
+        response.into_inner().job.ok_or_else(|| anyhow::anyhow!("No job in response"))
+    }
+
     // Console operations
 
     /// Get console URL
@@ -256,4 +956,97 @@ impl DaemonClient {
         let response = self.client.get_attestation(request).await?;
         response.into_inner().report.ok_or_else(|| anyhow::anyhow!("No report in response"))
     }
+
+    /// Get a fresh vTPM quote bound to `nonce`
+    pub async fn get_tpm_quote(&mut self, vm_id: &str, nonce: Vec<u8>, pcrs: Vec<u32>) -> Result<TpmQuote> {
+        let request = tonic::Request::new(GetTpmQuoteRequest {
+            vm_id: vm_id.to_string(),
+            nonce,
+            pcrs,
+        });
+        let response = self.client.get_tpm_quote(request).await?;
+        response.into_inner().quote.ok_or_else(|| anyhow::anyhow!("No quote in response"))
+    }
+
+    /// Get the signed SLSA provenance of a volume's image
+    pub async fn get_volume_provenance(&mut self, volume_id: &str) -> Result<GetVolumeProvenanceResponse> {
+        let request = tonic::Request::new(GetVolumeProvenanceRequest {
+            volume_id: volume_id.to_string(),
+        });
+        let response = self.client.get_volume_provenance(request).await?;
+        Ok(response.into_inner())
+    }
+
+    // Audit operations
+
+    /// List audit log entries, newest first
+    pub async fn list_audit_events(&mut self, filter: ListAuditEventsRequest) -> Result<Vec<AuditEvent>> {
+        let request = tonic::Request::new(filter);
+        let response = self.client.list_audit_events(request).await?;
+        Ok(response.into_inner().events)
+    }
+
+    /// Check the audit log's hash chain and signatures
+    pub async fn verify_audit_log(&mut self) -> Result<VerifyAuditLogResponse> {
+        let request = tonic::Request::new(VerifyAuditLogRequest {});
+        let response = self.client.verify_audit_log(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Versions of a resource's spec from the provenance ledger, oldest first
+    pub async fn get_provenance_chain(&mut self, resource_id: &str) -> Result<GetProvenanceChainResponse> {
+        let request = tonic::Request::new(GetProvenanceChainRequest {
+            resource_id: resource_id.to_string(),
+        });
+        let response = self.client.get_provenance_chain(request).await?;
+        Ok(response.into_inner())
+    }
+
+    // Secret operations
+
+    /// Store a secret, replacing an existing one only with `overwrite`
+    pub async fn create_secret(&mut self, name: &str, value: &str, overwrite: bool) -> Result<Secret> {
+        let request = tonic::Request::new(CreateSecretRequest {
+            name: name.to_string(),
+            value: value.to_string(),
+            overwrite,
+        });
+        let response = self.client.create_secret(request).await?;
+        response.into_inner().secret.ok_or_else(|| anyhow::anyhow!("No secret in response"))
+    }
+
+    /// Get a secret with its value
+    pub async fn get_secret(&mut self, name: &str) -> Result<GetSecretResponse> {
+        let request = tonic::Request::new(GetSecretRequest { name: name.to_string() });
+        let response = self.client.get_secret(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// List secrets, without their values
+    pub async fn list_secrets(&mut self) -> Result<Vec<Secret>> {
+        let request = tonic::Request::new(ListSecretsRequest {});
+        let response = self.client.list_secrets(request).await?;
+        Ok(response.into_inner().secrets)
+    }
+
+    pub async fn delete_secret(&mut self, name: &str) -> Result<()> {
+        let request = tonic::Request::new(DeleteSecretRequest { name: name.to_string() });
+        self.client.delete_secret(request).await?;
+        Ok(())
+    }
+    // Replication
+
+    /// Get the daemon's replication role and a standby's progress
+    pub async fn get_replication_status(&mut self) -> Result<ReplicationStatus> {
+        let request = tonic::Request::new(GetReplicationStatusRequest { include_objects: false });
+        let response = self.client.get_replication_status(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Make a standby the primary, returning the VMs marked stopped
+    pub async fn promote_daemon(&mut self) -> Result<Vec<String>> {
+        let request = tonic::Request::new(PromoteDaemonRequest {});
+        let response = self.client.promote_daemon(request).await?;
+        Ok(response.into_inner().stopped_vm_ids)
+    }
 }
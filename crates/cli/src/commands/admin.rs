@@ -0,0 +1,279 @@
+//! Admin Commands
+//!
+//! Maintenance of the daemon's state database. These open the database file
+//! directly, so they run on the daemon host and don't need a running daemon.
+
+use anyhow::{bail, Result};
+use clap::Subcommand;
+use infrasim_common::db::{MigrationChange, MigrationRecord, STATE_MIGRATIONS};
+use infrasim_common::state_backup::{self, Problem};
+use infrasim_common::{ContentAddressedStore, Database};
+use std::path::{Path, PathBuf};
+
+use crate::output::{
+    print_info, print_item, print_list, print_success, print_warning, OutputFormat, TableDisplay,
+};
+
+#[derive(Subcommand)]
+pub enum AdminCommands {
+    /// Apply, preview or roll back schema migrations of the state database
+    ///
+    /// Without options, applies pending migrations of the daemon's tables.
+    /// The daemon and web server also migrate their tables when they start;
+    /// stop both before rolling back.
+    Migrate {
+        /// Run the changes and roll them back, to see what would happen
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Roll the component back to this version (0 undoes all of it)
+        #[arg(long, value_name = "VERSION")]
+        rollback: Option<u32>,
+
+        /// Tables to migrate: `state`, or a web server component such as
+        /// `web.auth` (those can only be rolled back from here)
+        #[arg(long, default_value = "state")]
+        component: String,
+
+        /// List applied migrations of every component and change nothing
+        #[arg(long, conflicts_with_all = ["dry_run", "rollback"])]
+        status: bool,
+
+        /// State database (default: <store>/state.db)
+        #[arg(long)]
+        db: Option<PathBuf>,
+    },
+
+    /// Back up, restore and check the state database
+    #[command(subcommand)]
+    Db(DbCommands),
+}
+
+#[derive(Subcommand)]
+pub enum DbCommands {
+    /// Copy the state database and a manifest of the CAS objects it names
+    ///
+    /// Safe while the daemon runs. Without --objects only the manifest of
+    /// the objects is written, which is enough to restore onto the same store.
+    Backup {
+        /// New directory for the backup
+        path: PathBuf,
+
+        /// Copy the objects too, for restoring onto a fresh store
+        #[arg(long)]
+        objects: bool,
+
+        /// Daemon store directory
+        #[arg(long)]
+        store: Option<PathBuf>,
+    },
+
+    /// Restore a backup as the store's state database (stop the daemon first)
+    Restore {
+        /// Backup directory
+        path: PathBuf,
+
+        /// Replace an existing database, keeping it as state.db.pre-restore-<time>
+        #[arg(long)]
+        force: bool,
+
+        /// Daemon store directory, created if missing
+        #[arg(long)]
+        store: Option<PathBuf>,
+    },
+
+    /// Check the state database and CAS objects, or a backup, for damage
+    Verify {
+        /// Backup directory to check instead of the store
+        path: Option<PathBuf>,
+
+        /// Don't hash CAS objects, only check the ones needed exist
+        #[arg(long)]
+        skip_hash: bool,
+
+        /// Daemon store directory
+        #[arg(long)]
+        store: Option<PathBuf>,
+    },
+}
+
+impl TableDisplay for MigrationChange {
+    fn headers() -> Vec<&'static str> {
+        vec!["Component", "Version", "Name", "Action"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.component.clone(),
+            self.version.to_string(),
+            self.name.clone(),
+            if self.rollback { "rollback" } else { "apply" }.to_string(),
+        ]
+    }
+}
+
+impl TableDisplay for Problem {
+    fn headers() -> Vec<&'static str> {
+        vec!["Problem", "Detail"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![self.kind.as_str().to_string(), self.detail.clone()]
+    }
+}
+
+impl TableDisplay for state_backup::BackupManifest {
+    fn headers() -> Vec<&'static str> {
+        vec!["Created", "Database", "Schema", "Objects", "Included"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            chrono::DateTime::from_timestamp(self.created_at, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            format!("{} bytes", self.db_size),
+            self.schema
+                .iter()
+                .map(|(component, version)| format!("{} v{}", component, version))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.objects.len().to_string(),
+            self.objects.iter().filter(|o| o.included).count().to_string(),
+        ]
+    }
+}
+
+impl TableDisplay for MigrationRecord {
+    fn headers() -> Vec<&'static str> {
+        vec!["Component", "Version", "Name", "Reversible", "Applied"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.component.clone(),
+            self.version.to_string(),
+            self.name.clone(),
+            if self.reversible { "yes" } else { "no" }.to_string(),
+            chrono::DateTime::from_timestamp(self.applied_at, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+pub async fn execute(cmd: AdminCommands, format: OutputFormat) -> Result<()> {
+    match cmd {
+        AdminCommands::Migrate { dry_run, rollback, component, status, db } => {
+            let path = db.unwrap_or_else(infrasim_common::default_db_path);
+            if !path.exists() {
+                bail!("No state database at {}", path.display());
+            }
+            let db = Database::open_unmigrated(&path)?;
+
+            if status {
+                print_list(&db.applied_migrations()?, format);
+                return Ok(());
+            }
+
+            let changes = match rollback {
+                Some(version) => db.rollback(&component, version, dry_run)?,
+                None if component == STATE_MIGRATIONS.component => {
+                    db.migrate_to(&STATE_MIGRATIONS, STATE_MIGRATIONS.latest(), dry_run)?
+                }
+                None => bail!(
+                    "Migrations of {} are applied by the web server when it starts; only --rollback works here",
+                    component
+                ),
+            };
+
+            if changes.is_empty() {
+                print_info(&format!("{} schema is up to date", component));
+                return Ok(());
+            }
+            print_list(&changes, format);
+            if dry_run {
+                print_info("Dry run: nothing was changed");
+            } else {
+                print_success(&format!(
+                    "{} schema is at v{}",
+                    component,
+                    db.schema_version(&component)?
+                ));
+            }
+        }
+
+        AdminCommands::Db(cmd) => execute_db(cmd, format).await?,
+    }
+
+    Ok(())
+}
+
+async fn execute_db(cmd: DbCommands, format: OutputFormat) -> Result<()> {
+    match cmd {
+        DbCommands::Backup { path, objects, store } => {
+            let store = store.unwrap_or_else(infrasim_common::default_store_path);
+            let db = Database::open_unmigrated(existing_db(&store)?)?;
+            let cas = ContentAddressedStore::new(store.join("store")).await?;
+            let manifest = state_backup::backup(&db, &cas, &path, objects).await?;
+            print_item(&manifest, format);
+            print_success(&format!("Backed up state database to {}", path.display()));
+        }
+
+        DbCommands::Restore { path, force, store } => {
+            let store = store.unwrap_or_else(infrasim_common::default_store_path);
+            let db_path = store.join("state.db");
+            if db_path.exists() && !force {
+                bail!("{} exists; pass --force to replace it", db_path.display());
+            }
+            let cas = ContentAddressedStore::new(store.join("store")).await?;
+            let report = state_backup::restore(&path, &db_path, &cas, force).await?;
+            if let Some(previous) = &report.previous_db {
+                print_info(&format!("Previous database kept as {}", previous.display()));
+            }
+            if !report.objects_missing.is_empty() {
+                print_warning(&format!(
+                    "{} objects named by the backup are not in the store; copy them in or \
+                     restore from a backup taken with --objects: {}",
+                    report.objects_missing.len(),
+                    report.objects_missing.join(", ")
+                ));
+            }
+            print_success(&format!(
+                "Restored {} ({} objects imported)",
+                report.db_path.display(),
+                report.objects_imported
+            ));
+        }
+
+        DbCommands::Verify { path, skip_hash, store } => {
+            let report = match path {
+                Some(path) => state_backup::verify_backup(&path).await?,
+                None => {
+                    let store = store.unwrap_or_else(infrasim_common::default_store_path);
+                    let db = Database::open_unmigrated(existing_db(&store)?)?;
+                    let cas = ContentAddressedStore::new(store.join("store")).await?;
+                    state_backup::verify_store(&db, &cas, !skip_hash).await?
+                }
+            };
+            if !report.is_ok() {
+                print_list(&report.problems, format);
+                bail!("Found {} problems", report.problems.len());
+            }
+            print_success(&format!(
+                "No problems found ({} objects hashed)",
+                report.objects_checked
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn existing_db(store: &Path) -> Result<PathBuf> {
+    let path = store.join("state.db");
+    if !path.exists() {
+        bail!("No state database at {}", path.display());
+    }
+    Ok(path)
+}
@@ -0,0 +1,89 @@
+//! Appliance Commands
+
+use clap::Subcommand;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::client::DaemonClient;
+use crate::commands::job;
+use crate::generated::JobKind;
+use crate::output::{OutputFormat, print_info, print_item, print_success};
+
+#[derive(Subcommand)]
+pub enum ApplianceCommands {
+    /// Publish a stopped VM and its disks to an OCI registry
+    Push {
+        /// VM ID
+        vm_id: String,
+
+        /// Registry reference (e.g., ghcr.io/org/appliance:tag)
+        reference: String,
+
+        /// Queue as a background job and return immediately
+        #[arg(long)]
+        detach: bool,
+    },
+
+    /// Create a stopped VM from an appliance in an OCI registry
+    Pull {
+        /// Registry reference, by tag or @sha256 digest
+        reference: String,
+
+        /// VM name (defaults to the name recorded in the appliance)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Write the appliance's Terraform configuration to this file
+        #[arg(long)]
+        terraform_out: Option<PathBuf>,
+
+        /// Queue as a background job and return immediately
+        #[arg(long, conflicts_with = "terraform_out")]
+        detach: bool,
+    },
+}
+
+pub async fn execute(cmd: ApplianceCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        ApplianceCommands::Push { vm_id, reference, detach: true } => {
+            let params = HashMap::from([("reference".to_string(), reference)]);
+            job::submit(&mut client, JobKind::PushAppliance, &vm_id, params, format).await?;
+        }
+
+        ApplianceCommands::Push { vm_id, reference, detach: false } => {
+            let pushed = client.push_appliance(&vm_id, &reference).await?;
+            print_success(&format!(
+                "VM '{}' pushed as {} ({} of {} disk(s) uploaded)",
+                vm_id, pushed.reference, pushed.uploaded_disks, pushed.disks
+            ));
+            print_info(&format!("Provenance signed with key {}", pushed.signer_key_id));
+        }
+
+        ApplianceCommands::Pull { reference, name, detach: true, .. } => {
+            let params = name.map(|name| ("name".to_string(), name)).into_iter().collect();
+            job::submit(&mut client, JobKind::PullAppliance, &reference, params, format).await?;
+        }
+
+        ApplianceCommands::Pull { reference, name, terraform_out, detach: false } => {
+            let pulled = client.pull_appliance(&reference, name, Default::default()).await?;
+            let vm = pulled.vm.unwrap_or_default();
+            let meta = vm.meta.clone().unwrap_or_default();
+            print_success(&format!(
+                "VM '{}' ({}) created from {} with {} disk(s)",
+                meta.name,
+                meta.id,
+                reference,
+                pulled.volumes.len()
+            ));
+            print_info(&format!("Provenance verified, signed with key {}", pulled.signer_key_id));
+            if let Some(path) = terraform_out {
+                std::fs::write(&path, &pulled.terraform_hcl)?;
+                print_info(&format!("Terraform configuration written to {}", path.display()));
+            }
+            print_item(&vm, format);
+        }
+    }
+
+    Ok(())
+}
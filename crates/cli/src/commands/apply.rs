@@ -0,0 +1,74 @@
+//! Apply Command
+
+use anyhow::{Context, Result};
+use clap::Args;
+use infrasim_common::types::parse_selector;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::client::DaemonClient;
+use crate::manifest::{self, Existing, Manifest, Plan};
+use crate::output::{print_list, print_success, OutputFormat};
+
+#[derive(Args)]
+pub struct ApplyArgs {
+    /// Manifest file of YAML documents or JSON objects, or `-` for stdin
+    #[arg(short, long)]
+    file: PathBuf,
+
+    /// Delete resources matching --selector that the manifests don't name
+    #[arg(long, requires = "selector")]
+    prune: bool,
+
+    /// Labels of the resources --prune considers, e.g. 'pipeline=ci'
+    #[arg(short = 'l', long, value_parser = parse_selector)]
+    selector: Option<HashMap<String, String>>,
+
+    /// Show the changes without making them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub async fn execute(args: ApplyArgs, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    let input = if args.file.as_os_str() == "-" {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read manifests from stdin")?;
+        input
+    } else {
+        std::fs::read_to_string(&args.file)
+            .with_context(|| format!("Failed to read {}", args.file.display()))?
+    };
+
+    let manifests = manifest::parse(&input)?;
+    let prune = if args.prune { args.selector.as_ref() } else { None };
+    converge(&mut client, manifests, prune, args.dry_run, format).await
+}
+
+/// Show and, unless `dry_run`, make the changes that bring the daemon in
+/// line with `manifests`
+pub async fn converge(
+    client: &mut DaemonClient,
+    manifests: Vec<Manifest>,
+    prune: Option<&HashMap<String, String>>,
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let existing = Existing::fetch(client).await?;
+    let plan = Plan::diff(manifests, existing, prune)?;
+    if plan.is_empty() {
+        print_success("Resources are up to date");
+        return Ok(());
+    }
+
+    print_list(&plan.changes, format);
+    if dry_run {
+        return Ok(());
+    }
+    plan.apply(client).await?;
+    print_success(&format!("Applied {} change(s)", plan.changes.len()));
+
+    Ok(())
+}
@@ -1,12 +1,17 @@
 //! Attestation Commands
 
 use clap::Subcommand;
-use anyhow::Result;
-use serde::Serialize;
+use anyhow::{Context, Result};
+use infrasim_common::attestation::vtpm::{self, PcrPolicy, QuoteVerification};
+use infrasim_common::crypto::verifying_key_from_bytes;
+use infrasim_common::pipeline::{GraphFormat, PipelineAnalyzer, SbomFormat};
+use infrasim_common::slsa::{Envelope, Statement};
+use infrasim_common::types;
+use std::path::{Path, PathBuf};
 
 use crate::client::DaemonClient;
-use crate::output::{OutputFormat, TableDisplay, print_item, print_success};
-use crate::generated::AttestationReport;
+use crate::output::{OutputFormat, TableDisplay, print_error, print_info, print_item, print_success};
+use crate::generated::{AttestationReport, TpmQuote};
 
 #[derive(Subcommand)]
 pub enum AttestationCommands {
@@ -25,35 +30,68 @@ pub enum AttestationCommands {
         #[arg(long)]
         expected_digest: Option<String>,
     },
-}
 
-/// Attestation report display wrapper for serialization
-#[derive(Serialize)]
-pub struct AttestationDisplay {
-    pub id: String,
-    pub vm_id: String,
-    pub attestation_type: String,
-    pub digest: String,
-    pub signature: String,
-    pub created_at: String,
-}
+    /// Fetch a fresh vTPM quote and verify it against a PCR policy
+    Quote {
+        /// VM ID
+        vm_id: String,
 
-impl From<AttestationReport> for AttestationDisplay {
-    fn from(report: AttestationReport) -> Self {
-        Self {
-            id: report.id,
-            vm_id: report.vm_id,
-            attestation_type: report.attestation_type,
-            digest: report.digest.clone(),
-            signature: hex::encode(&report.signature),
-            created_at: chrono::DateTime::from_timestamp(report.created_at, 0)
-                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-                .unwrap_or_default(),
-        }
-    }
+        /// PCRs to quote, e.g. "0-7,14" (default: the policy's PCRs, or 0-9)
+        #[arg(long)]
+        pcrs: Option<String>,
+
+        /// Expected PCR policy as JSON: {"pcrs": {"0": "<hex>"}, "ak_public": "<hex>"}
+        /// (default: the VM's boot quote)
+        #[arg(long)]
+        policy: Option<PathBuf>,
+    },
+
+    /// Verify the signed SLSA provenance of a volume's image
+    VerifySlsa {
+        /// Volume ID
+        volume_id: String,
+
+        /// Hex Ed25519 key the provenance must be signed with (default: the daemon's key)
+        #[arg(long)]
+        public_key: Option<String>,
+
+        /// Write the DSSE envelope to a file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export a workspace's dependency graph as an SBOM (no daemon needed)
+    Sbom {
+        /// Workspace directory or lockfile (Cargo, npm, pnpm, Go, Poetry, pip)
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+
+        /// SBOM format (spdx, cyclonedx)
+        #[arg(long, default_value = "cyclonedx")]
+        sbom_format: String,
+
+        /// Write the SBOM to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export a workspace's dependency graph for Graphviz or Gephi (no daemon needed)
+    Graph {
+        /// Workspace directory or lockfile (Cargo, npm, pnpm, Go, Poetry, pip)
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+
+        /// Graph format (dot, graphml)
+        #[arg(long, default_value = "dot")]
+        graph_format: String,
+
+        /// Write the graph to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
-impl TableDisplay for AttestationDisplay {
+impl TableDisplay for AttestationReport {
     fn headers() -> Vec<&'static str> {
         vec!["ID", "VM ID", "Type", "Digest", "Created"]
     }
@@ -64,17 +102,29 @@ impl TableDisplay for AttestationDisplay {
             self.vm_id.chars().take(8).collect::<String>(),
             self.attestation_type.clone(),
             self.digest.chars().take(16).collect::<String>(),
-            self.created_at.clone(),
+            chrono::DateTime::from_timestamp(self.created_at, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
         ]
     }
 }
 
-pub async fn execute(cmd: AttestationCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+pub async fn execute(cmd: AttestationCommands, client: Result<DaemonClient>, format: OutputFormat) -> Result<()> {
+    match cmd {
+        AttestationCommands::Sbom { path, sbom_format, output } => {
+            return sbom(&path, &sbom_format, output.as_deref());
+        }
+        AttestationCommands::Graph { path, graph_format, output } => {
+            return graph(&path, &graph_format, output.as_deref());
+        }
+        _ => {}
+    }
+    let mut client = client?;
+
     match cmd {
         AttestationCommands::Get { vm_id } => {
             let report = client.get_attestation(&vm_id).await?;
-            let display = AttestationDisplay::from(report);
-            print_item(&display, format);
+            print_item(&report, format);
         }
 
         AttestationCommands::Verify { vm_id, expected_digest } => {
@@ -97,7 +147,233 @@ pub async fn execute(cmd: AttestationCommands, mut client: DaemonClient, format:
                 println!("  Actual: {}", report.digest);
             }
         }
+
+        AttestationCommands::Quote { vm_id, pcrs, policy } => {
+            let policy = match policy {
+                Some(path) => Some(load_policy(&path)?),
+                None => boot_policy(&mut client, &vm_id).await,
+            };
+            let selection = match (&pcrs, &policy) {
+                (Some(pcrs), _) => vtpm::parse_pcr_selection(pcrs)?,
+                (None, Some(policy)) if !policy.pcrs.is_empty() => policy.pcrs.keys().copied().collect(),
+                _ => vtpm::default_pcrs(),
+            };
+
+            let nonce = vtpm::random_nonce();
+            let quote = client.get_tpm_quote(&vm_id, nonce.clone(), selection).await?;
+            let quote = quote_from_proto(&quote);
+            let result = vtpm::verify_quote(&quote, Some(&nonce), policy.as_ref())?;
+
+            let output = serde_json::json!({ "quote": quote, "verification": result });
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+                OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&output)?),
+                _ => print_verification(&vm_id, &quote, &result, policy.is_some()),
+            }
+
+            if !result.is_trusted() {
+                anyhow::bail!("vTPM quote for VM '{}' failed verification", vm_id);
+            }
+        }
+
+        AttestationCommands::VerifySlsa { volume_id, public_key, output } => {
+            let provenance = client.get_volume_provenance(&volume_id).await?;
+            let key = hex::decode(public_key.as_deref().unwrap_or(&provenance.public_key))
+                .context("Invalid public key")?;
+            let key = verifying_key_from_bytes(&key)?;
+            let envelope: Envelope = serde_json::from_slice(&provenance.envelope)
+                .context("Invalid provenance envelope")?;
+            let statement = envelope
+                .verify(&key)
+                .with_context(|| format!("Provenance of volume '{}' failed verification", volume_id))?;
+
+            if let Some(output) = &output {
+                std::fs::write(output, &provenance.envelope)
+                    .with_context(|| format!("Failed to write {}", output.display()))?;
+            }
+
+            let image_matches = statement.subject_digest() == Some(provenance.image_digest.as_str());
+            let result = serde_json::json!({
+                "envelope_digest": provenance.envelope_digest,
+                "image_digest": provenance.image_digest,
+                "image_matches": image_matches,
+                "statement": statement,
+            });
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result)?),
+                OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&result)?),
+                _ => print_slsa(&volume_id, &statement, &provenance.image_digest, public_key.is_some()),
+            }
+
+            if !image_matches {
+                anyhow::bail!("Image of volume '{}' no longer matches its provenance", volume_id);
+            }
+        }
+
+        AttestationCommands::Sbom { .. } | AttestationCommands::Graph { .. } => {
+            unreachable!("handled before connecting")
+        }
+    }
+
+    Ok(())
+}
+
+fn sbom(path: &Path, format: &str, output: Option<&Path>) -> Result<()> {
+    let format: SbomFormat = format.parse()?;
+    let report = PipelineAnalyzer::new()
+        .analyze_workspace(path)
+        .with_context(|| format!("Failed to analyze {}", path.display()))?;
+    let json = serde_json::to_string_pretty(&report.to_sbom(format))?;
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, json + "\n")
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            print_success(&format!(
+                "Wrote SBOM for {} packages to {}",
+                report.graph.nodes.len(),
+                output.display()
+            ));
+        }
+        None => println!("{}", json),
     }
+    Ok(())
+}
+
+fn graph(path: &Path, format: &str, output: Option<&Path>) -> Result<()> {
+    let format: GraphFormat = format.parse()?;
+    let report = PipelineAnalyzer::new()
+        .analyze_workspace(path)
+        .with_context(|| format!("Failed to analyze {}", path.display()))?;
+    let graph = report.to_graph(format);
 
+    match output {
+        Some(output) => {
+            std::fs::write(output, graph)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            print_success(&format!(
+                "Wrote graph of {} packages to {}",
+                report.graph.nodes.len(),
+                output.display()
+            ));
+            if format == GraphFormat::Dot {
+                print_info(&format!("Render it with: dot -Tsvg {} -o graph.svg", output.display()));
+            }
+        }
+        None => print!("{}", graph),
+    }
     Ok(())
 }
+
+fn load_policy(path: &PathBuf) -> Result<PcrPolicy> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read policy {}", path.display()))?;
+    serde_json::from_slice(&data).with_context(|| format!("Invalid policy {}", path.display()))
+}
+
+/// Policy pinned by the boot quote the daemon recorded, if any
+async fn boot_policy(client: &mut DaemonClient, vm_id: &str) -> Option<PcrPolicy> {
+    let report = client.get_attestation(vm_id).await.ok()?;
+    report
+        .tpm_quotes
+        .iter()
+        .find(|q| q.label == "boot")
+        .map(|q| PcrPolicy::from_quote(&quote_from_proto(q)))
+}
+
+fn quote_from_proto(quote: &TpmQuote) -> types::TpmQuote {
+    types::TpmQuote {
+        vm_id: quote.vm_id.clone(),
+        label: quote.label.clone(),
+        pcrs: quote.pcrs.iter().map(|(k, v)| (*k, v.clone())).collect(),
+        quoted: quote.quoted.clone(),
+        signature: quote.signature.clone(),
+        ak_public: quote.ak_public.clone(),
+        nonce: quote.nonce.clone(),
+        event_log: quote
+            .event_log
+            .iter()
+            .map(|e| types::TpmEvent {
+                pcr_index: e.pcr_index,
+                digest: e.digest.clone(),
+                command: e.command.clone(),
+                data: e.data.clone(),
+            })
+            .collect(),
+        created_at: quote.created_at,
+    }
+}
+
+fn print_slsa(volume_id: &str, statement: &Statement, image_digest: &str, pinned_key: bool) {
+    let image_matches = statement.subject_digest() == Some(image_digest);
+    if image_matches {
+        print_success(&format!("SLSA provenance for volume '{}' verified", volume_id));
+    } else {
+        print_error(&format!("Image of volume '{}' no longer matches its provenance", volume_id));
+    }
+    println!("  ✓ Signed envelope");
+    println!(
+        "  {} Image digest matches the subject (sha256:{})",
+        if image_matches { "✓" } else { "✗" },
+        image_digest
+    );
+    if !pinned_key {
+        print_info("Checked against the daemon's own key; pin one with --public-key");
+    }
+
+    let provenance = &statement.predicate;
+    let builder = &provenance.run_details.builder;
+    let versions: Vec<String> = builder.version.iter().map(|(k, v)| format!("{} {}", k, v)).collect();
+    println!();
+    println!("  Build type: {}", provenance.build_definition.build_type);
+    println!("  Builder:    {} ({})", builder.id, versions.join(", "));
+    let metadata = &provenance.run_details.metadata;
+    if !metadata.invocation_id.is_empty() {
+        println!("  Invocation: {}", metadata.invocation_id);
+    }
+    if let Some(finished) = &metadata.finished_on {
+        println!("  Finished:   {}", finished);
+    }
+    if !provenance.build_definition.resolved_dependencies.is_empty() {
+        println!("  Inputs:");
+        for input in &provenance.build_definition.resolved_dependencies {
+            let digest = input.sha256().map(|d| format!("sha256:{}", d)).unwrap_or_default();
+            println!("    {:<12} {} {}", input.name, input.uri, digest);
+        }
+    }
+}
+
+fn print_verification(vm_id: &str, quote: &types::TpmQuote, result: &QuoteVerification, has_policy: bool) {
+    let mark = |ok: bool| if ok { "✓" } else { "✗" };
+
+    if result.is_trusted() {
+        print_success(&format!("vTPM quote for VM '{}' verified", vm_id));
+    } else {
+        print_error(&format!("vTPM quote for VM '{}' failed verification", vm_id));
+    }
+    println!("  {} Signature by attestation key", mark(result.signature_valid));
+    println!("  {} Fresh nonce", mark(result.nonce_valid));
+    println!("  {} PCR values match the signed digest", mark(result.pcr_digest_valid));
+    println!(
+        "  {} Event log replays to the quoted PCRs ({} events)",
+        mark(result.event_log_valid),
+        quote.event_log.len()
+    );
+    if !result.event_log_mismatches.is_empty() {
+        println!("      Mismatched PCRs: {:?}", result.event_log_mismatches);
+    }
+    if has_policy {
+        println!("  {} Attestation key matches policy", mark(result.ak_trusted));
+        println!("  {} PCRs match policy", mark(result.policy_mismatches.is_empty()));
+        if !result.policy_mismatches.is_empty() {
+            println!("      Mismatched PCRs: {:?}", result.policy_mismatches);
+        }
+    } else {
+        print_info("No boot quote recorded yet and no --policy given; PCRs were not checked against a policy");
+    }
+
+    println!();
+    for (pcr, value) in &quote.pcrs {
+        println!("  PCR {:>2}: {}", pcr, value);
+    }
+}
@@ -0,0 +1,151 @@
+//! Audit Commands
+
+use clap::Subcommand;
+use anyhow::Result;
+use infrasim_common::types::parse_interval;
+
+use crate::client::DaemonClient;
+use crate::output::{OutputFormat, TableDisplay, print_error, print_info, print_list, print_success};
+use crate::generated::{AuditEvent, ListAuditEventsRequest, ProvenanceRecord};
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// List audited operations, newest first
+    List {
+        /// Only entries from this long ago onwards, e.g. 30m, 24h or 7d
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only calls made by this identity, e.g. token:ci or uid:501
+        #[arg(long)]
+        identity: Option<String>,
+
+        /// Only calls that changed this resource
+        #[arg(long)]
+        resource: Option<String>,
+
+        /// Only entries recorded by this source (daemon or web)
+        #[arg(long)]
+        source: Option<String>,
+
+        /// Most entries to list
+        #[arg(long, default_value = "100")]
+        limit: u32,
+    },
+
+    /// Check the audit log's hash chain and signatures
+    Verify,
+
+    /// Show every version of a resource's spec and check their chain
+    Provenance {
+        /// Resource ID
+        resource: String,
+    },
+}
+
+impl TableDisplay for AuditEvent {
+    fn headers() -> Vec<&'static str> {
+        vec!["Seq", "Time", "Source", "Identity", "From", "Operation", "Resource", "Status"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let time = chrono::DateTime::from_timestamp(self.timestamp, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let resource = if self.resource_kind.is_empty() {
+            self.resource_id.clone()
+        } else {
+            format!("{}/{}", self.resource_kind, self.resource_id)
+        };
+
+        vec![
+            self.seq.to_string(),
+            time,
+            self.source.clone(),
+            self.identity.clone(),
+            self.source_ip.clone(),
+            self.operation.clone(),
+            resource,
+            self.status.clone(),
+        ]
+    }
+}
+
+impl TableDisplay for ProvenanceRecord {
+    fn headers() -> Vec<&'static str> {
+        vec!["Version", "Time", "Change", "Spec Digest", "Parent", "Signer"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let time = chrono::DateTime::from_timestamp(self.timestamp, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let short = |digest: &str| digest.chars().take(12).collect::<String>();
+
+        vec![
+            self.version.to_string(),
+            time,
+            self.change.clone(),
+            short(&self.spec_digest),
+            short(&self.parent_digest),
+            self.key_id.clone(),
+        ]
+    }
+}
+
+pub async fn execute(cmd: AuditCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        AuditCommands::List { since, identity, resource, source, limit } => {
+            let since = match since {
+                Some(age) => chrono::Utc::now().timestamp() - parse_interval(&age)? as i64,
+                None => 0,
+            };
+            let events = client
+                .list_audit_events(ListAuditEventsRequest {
+                    since,
+                    until: 0,
+                    identity: identity.unwrap_or_default(),
+                    resource_id: resource.unwrap_or_default(),
+                    source: source.unwrap_or_default(),
+                    limit,
+                })
+                .await?;
+            print_list(&events, format);
+        }
+
+        AuditCommands::Verify => {
+            let result = client.verify_audit_log().await?;
+            if !result.valid {
+                print_error(&format!(
+                    "Audit log broken at entry {}: {}",
+                    result.broken_at_seq, result.error
+                ));
+                anyhow::bail!("Audit log failed verification");
+            }
+            print_success(&format!("Audit log verified ({} chained entries)", result.verified));
+            if result.unchained > 0 {
+                print_info(&format!(
+                    "{} entries were written without hash chaining and can't be verified",
+                    result.unchained
+                ));
+            }
+            if !result.hash_chain_enabled {
+                print_info("Hash chaining is off; set [audit] hash_chain = true in the daemon config");
+            }
+        }
+
+        AuditCommands::Provenance { resource } => {
+            let chain = client.get_provenance_chain(&resource).await?;
+            print_list(&chain.records, format);
+            if !chain.valid {
+                print_error(&format!(
+                    "Provenance of {} broken at version {}: {}",
+                    resource, chain.broken_at_version, chain.error
+                ));
+                anyhow::bail!("Provenance chain failed verification");
+            }
+        }
+    }
+
+    Ok(())
+}
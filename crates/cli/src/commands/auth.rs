@@ -0,0 +1,162 @@
+//! Auth Commands
+//!
+//! Client certificates and bearer tokens for the daemon's gRPC API. Both are
+//! issued from the daemon's store (`<store>/auth/`), so these commands run on
+//! the daemon host and don't need a running daemon.
+
+use anyhow::{Context as _, Result};
+use clap::Subcommand;
+use infrasim_common::auth::{
+    write_private, CertificateAuthority, TokenEntry, TokenStore, DEFAULT_CLIENT_CERT_DAYS,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::output::{print_info, print_list, print_success, OutputFormat, TableDisplay};
+
+#[derive(Subcommand)]
+pub enum AuthCommands {
+    /// Issue a client certificate from the daemon CA (for `infrasimd --mtls`)
+    IssueClientCert {
+        /// Client name, recorded as the certificate's common name
+        name: String,
+
+        /// Days the certificate is valid
+        #[arg(long, default_value_t = DEFAULT_CLIENT_CERT_DAYS)]
+        days: i64,
+
+        /// Directory for <name>.crt, <name>.key and ca.crt
+        /// (default: <store>/auth/clients/<name>)
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+
+        /// Daemon store directory
+        #[arg(long)]
+        store: Option<PathBuf>,
+    },
+
+    /// Create a bearer token (for `infrasimd --require-token`)
+    CreateToken {
+        /// Token name
+        name: String,
+
+        /// Daemon store directory
+        #[arg(long)]
+        store: Option<PathBuf>,
+    },
+
+    /// List bearer tokens
+    ListTokens {
+        /// Daemon store directory
+        #[arg(long)]
+        store: Option<PathBuf>,
+    },
+
+    /// Revoke a bearer token
+    RevokeToken {
+        /// Token name
+        name: String,
+
+        /// Daemon store directory
+        #[arg(long)]
+        store: Option<PathBuf>,
+    },
+}
+
+/// Token wrapper for serialization
+#[derive(Serialize)]
+pub struct TokenDisplay {
+    pub name: String,
+    pub created_at: i64,
+}
+
+impl From<&TokenEntry> for TokenDisplay {
+    fn from(entry: &TokenEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            created_at: entry.created_at,
+        }
+    }
+}
+
+impl TableDisplay for TokenDisplay {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Created"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            chrono::DateTime::from_timestamp(self.created_at, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+fn auth_dir(store: Option<PathBuf>) -> PathBuf {
+    store
+        .unwrap_or_else(infrasim_common::default_store_path)
+        .join("auth")
+}
+
+pub fn execute(cmd: AuthCommands, format: OutputFormat) -> Result<()> {
+    match cmd {
+        AuthCommands::IssueClientCert { name, days, out, store } => {
+            let dir = auth_dir(store);
+            let ca = CertificateAuthority::load_or_generate(&dir)
+                .with_context(|| format!("Failed to load the daemon CA from {}", dir.display()))?;
+            let cert = ca.issue_client_cert(&name, days)?;
+
+            let out = out.unwrap_or_else(|| dir.join("clients").join(&name));
+            let cert_path = out.join(format!("{}.crt", name));
+            let key_path = out.join(format!("{}.key", name));
+            let ca_path = out.join("ca.crt");
+            write_private(&key_path, cert.key_pem.as_bytes())?;
+            write_file(&cert_path, &cert.cert_pem)?;
+            write_file(&ca_path, &ca.cert_pem)?;
+
+            print_success(&format!(
+                "Issued client certificate '{}' (serial {}, valid until {})",
+                name, cert.serial, cert.not_after
+            ));
+            print_info(&format!(
+                "Use it with: infrasim context add <name> --daemon-addr https://<host>:<port> \
+                 --ca-cert {} --client-cert {} --client-key {}",
+                ca_path.display(),
+                cert_path.display(),
+                key_path.display()
+            ));
+        }
+
+        AuthCommands::CreateToken { name, store } => {
+            let dir = auth_dir(store);
+            let mut tokens = TokenStore::load(&dir)?;
+            let token = tokens.create(&name)?;
+            tokens.save(&dir)?;
+
+            print_success(&format!("Created token '{}'; it is shown only once:", name));
+            println!("{}", token);
+        }
+
+        AuthCommands::ListTokens { store } => {
+            let tokens = TokenStore::load(&auth_dir(store))?;
+            let displays: Vec<TokenDisplay> = tokens.tokens.iter().map(TokenDisplay::from).collect();
+            print_list(&displays, format);
+        }
+
+        AuthCommands::RevokeToken { name, store } => {
+            let dir = auth_dir(store);
+            let mut tokens = TokenStore::load(&dir)?;
+            tokens.revoke(&name)?;
+            tokens.save(&dir)?;
+            print_success(&format!("Token '{}' revoked", name));
+        }
+    }
+
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
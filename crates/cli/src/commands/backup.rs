@@ -0,0 +1,308 @@
+//! Backup Commands
+
+use clap::Subcommand;
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::client::DaemonClient;
+use crate::commands::job;
+use crate::output::{OutputFormat, TableDisplay, print_info, print_item, print_list, print_success};
+use crate::generated::{Backup, BackupKind, BackupPolicy, BackupPolicySpec, BackupSpec, JobKind};
+use infrasim_common::types::{format_interval, format_selector, parse_interval, parse_selector};
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    /// List all backups
+    List {
+        /// Filter by VM ID
+        #[arg(long)]
+        vm_id: Option<String>,
+    },
+
+    /// Get backup details
+    Get {
+        /// Backup ID
+        id: String,
+    },
+
+    /// Back up a VM's disks to a directory or s3://bucket/prefix
+    Create {
+        /// VM ID to back up
+        #[arg(short, long)]
+        vm_id: String,
+
+        /// Backup target: an absolute directory path or s3://bucket/prefix
+        #[arg(short, long)]
+        target: String,
+
+        /// Backup name
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Take a full backup even if an incremental one is possible
+        #[arg(long)]
+        full: bool,
+
+        /// Queue as a background job and return immediately
+        #[arg(long)]
+        detach: bool,
+    },
+
+    /// Delete a backup (backups others build on cannot be deleted)
+    Delete {
+        /// Backup ID
+        id: String,
+    },
+
+    /// Rebuild a VM and its volumes from a backup point
+    Restore {
+        /// Backup ID
+        backup_id: String,
+
+        /// Name of the new VM
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Queue as a background job and return immediately
+        #[arg(long)]
+        detach: bool,
+    },
+
+    /// Manage scheduled backup policies
+    #[command(subcommand)]
+    Schedule(ScheduleCommands),
+}
+
+#[derive(Subcommand)]
+pub enum ScheduleCommands {
+    /// Back up a VM, or every VM matching a label selector, on a fixed interval,
+    /// keeping at least the newest N backups of each
+    Add {
+        /// VM ID
+        #[arg(long, required_unless_present = "selector", conflicts_with = "selector")]
+        vm_id: Option<String>,
+
+        /// Back up every VM with these labels at each run (e.g. env=prod)
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
+
+        /// Backup target: an absolute directory path or s3://bucket/prefix
+        #[arg(long)]
+        target: String,
+
+        /// Interval between backups (e.g. 6h, 1d)
+        #[arg(long, value_parser = parse_interval)]
+        every: u64,
+
+        /// Number of scheduled backups to keep
+        #[arg(long, default_value = "14")]
+        keep: u32,
+
+        /// Take a full backup after this many incrementals (0 = only when needed)
+        #[arg(long, default_value = "7")]
+        full_every: u32,
+
+        /// Policy name
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
+    /// List backup policies
+    List {
+        /// Only policies covering this VM, including selector policies that match it
+        #[arg(long)]
+        vm_id: Option<String>,
+    },
+
+    /// Remove a backup policy (backups it took are kept)
+    Remove {
+        /// Policy ID
+        id: String,
+    },
+}
+
+/// Format a unix timestamp for tables, empty when unset
+fn format_timestamp(ts: i64) -> String {
+    if ts == 0 {
+        return String::new();
+    }
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+fn format_size(size: i64) -> String {
+    if size > 1024 * 1024 * 1024 {
+        format!("{:.1}GB", size as f64 / 1024.0 / 1024.0 / 1024.0)
+    } else if size > 1024 * 1024 {
+        format!("{:.1}MB", size as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{}B", size)
+    }
+}
+
+impl TableDisplay for Backup {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "VM ID", "Kind", "Parent", "Target", "Size", "Status", "Created"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        let kind = match BackupKind::try_from(status.kind) {
+            Ok(BackupKind::Full) => "full",
+            Ok(BackupKind::Incremental) => "incremental",
+            _ => "",
+        };
+        let state = if status.complete {
+            "complete".to_string()
+        } else if !status.error.is_empty() {
+            format!("failed: {}", status.error)
+        } else {
+            "running".to_string()
+        };
+
+        vec![
+            meta.id,
+            meta.name,
+            spec.vm_id,
+            kind.to_string(),
+            status.parent_id,
+            spec.target,
+            format_size(status.size_bytes),
+            state,
+            format_timestamp(meta.created_at),
+        ]
+    }
+}
+
+/// Human-readable schedule of a policy
+fn policy_schedule(policy: &BackupPolicy) -> String {
+    let spec = policy.spec.clone().unwrap_or_default();
+    match &policy.status {
+        Some(status) if !status.schedule.is_empty() => status.schedule.clone(),
+        _ => format!("every {}, keep {}", format_interval(spec.interval_seconds as u64), spec.keep),
+    }
+}
+
+impl TableDisplay for BackupPolicy {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "VM", "Target", "Schedule", "Retained", "Last Run", "Next Run", "Error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        vec![
+            meta.id,
+            meta.name,
+            if spec.vm_id.is_empty() { format_selector(&spec.vm_selector) } else { spec.vm_id.clone() },
+            spec.target.clone(),
+            policy_schedule(self),
+            status.retained.to_string(),
+            format_timestamp(status.last_run_at),
+            if spec.paused { "paused".to_string() } else { format_timestamp(status.next_run_at) },
+            status.last_error,
+        ]
+    }
+}
+
+pub async fn execute(cmd: BackupCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        BackupCommands::List { vm_id } => {
+            let backups = client.list_backups(vm_id).await?;
+            print_list(&backups, format);
+        }
+
+        BackupCommands::Get { id } => {
+            let backup = client.get_backup(&id).await?;
+            print_item(&backup, format);
+        }
+
+        BackupCommands::Create { vm_id, target, name, full, detach: true } => {
+            let mut params = HashMap::from([
+                ("target".to_string(), target),
+                ("incremental".to_string(), (!full).to_string()),
+            ]);
+            params.extend(name.map(|name| ("name".to_string(), name)));
+            job::submit(&mut client, JobKind::CreateBackup, &vm_id, params, format).await?;
+        }
+
+        BackupCommands::Create { vm_id, target, name, full, detach: false } => {
+            let spec = BackupSpec {
+                vm_id: vm_id.clone(),
+                target,
+                incremental: !full,
+            };
+
+            let backup = client.create_backup(&name.unwrap_or_default(), spec).await?;
+            let meta = backup.meta.clone().unwrap_or_default();
+            print_success(&format!("Backup '{}' created for VM '{}'", meta.name, vm_id));
+            print_item(&backup, format);
+        }
+
+        BackupCommands::Delete { id } => {
+            client.delete_backup(&id).await?;
+            print_success(&format!("Backup '{}' deleted", id));
+        }
+
+        BackupCommands::Restore { backup_id, name, detach: true } => {
+            let params = name.map(|name| ("name".to_string(), name)).into_iter().collect();
+            job::submit(&mut client, JobKind::RestoreBackup, &backup_id, params, format).await?;
+        }
+
+        BackupCommands::Restore { backup_id, name, detach: false } => {
+            let restored = client.restore_backup(&backup_id, name).await?;
+            let meta = restored.vm.and_then(|vm| vm.meta).unwrap_or_default();
+            print_success(&format!(
+                "VM '{}' ({}) restored from backup '{}' with {} volume(s)",
+                meta.name,
+                meta.id,
+                backup_id,
+                restored.volumes.len()
+            ));
+            print_info(&format!("Start it with: infrasim vm start {}", meta.id));
+        }
+
+        BackupCommands::Schedule(cmd) => execute_schedule(cmd, client, format).await?,
+    }
+
+    Ok(())
+}
+
+async fn execute_schedule(cmd: ScheduleCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        ScheduleCommands::Add { vm_id, selector, target, every, keep, full_every, name } => {
+            let spec = BackupPolicySpec {
+                vm_id: vm_id.unwrap_or_default(),
+                vm_selector: selector.unwrap_or_default(),
+                target,
+                interval_seconds: every as i64,
+                keep,
+                full_every,
+                paused: false,
+            };
+
+            let policy = client.create_backup_policy(&name.unwrap_or_default(), spec).await?;
+            let meta = policy.meta.clone().unwrap_or_default();
+            print_success(&format!("Backup policy '{}' created ({})", meta.name, policy_schedule(&policy)));
+            print_item(&policy, format);
+        }
+
+        ScheduleCommands::List { vm_id } => {
+            let policies = client.list_backup_policies(vm_id).await?;
+            print_list(&policies, format);
+        }
+
+        ScheduleCommands::Remove { id } => {
+            client.delete_backup_policy(&id).await?;
+            print_success(&format!("Backup policy '{}' removed", id));
+        }
+    }
+
+    Ok(())
+}
@@ -2,7 +2,6 @@
 
 use clap::{Args, Subcommand};
 use anyhow::Result;
-use serde::Serialize;
 
 use crate::client::DaemonClient;
 use crate::output::{OutputFormat, TableDisplay, print_list, print_success};
@@ -42,29 +41,7 @@ pub enum BenchmarkCommands {
     },
 }
 
-/// Benchmark result display wrapper for serialization
-#[derive(Serialize)]
-pub struct BenchmarkDisplay {
-    pub test_name: String,
-    pub passed: bool,
-    pub score: f64,
-    pub unit: String,
-    pub duration_ms: i64,
-}
-
-impl From<BenchmarkResult> for BenchmarkDisplay {
-    fn from(result: BenchmarkResult) -> Self {
-        Self {
-            test_name: result.test_name,
-            passed: result.passed,
-            score: result.score,
-            unit: result.unit,
-            duration_ms: result.duration_ms,
-        }
-    }
-}
-
-impl TableDisplay for BenchmarkDisplay {
+impl TableDisplay for BenchmarkResult {
     fn headers() -> Vec<&'static str> {
         vec!["Test", "Passed", "Score", "Unit", "Duration"]
     }
@@ -80,42 +57,21 @@ impl TableDisplay for BenchmarkDisplay {
     }
 }
 
-/// Benchmark run display wrapper
-#[derive(Serialize)]
-pub struct BenchmarkRunDisplay {
-    pub id: String,
-    pub vm_id: String,
-    pub total_tests: usize,
-    pub passed_tests: usize,
-}
-
-impl From<BenchmarkRun> for BenchmarkRunDisplay {
-    fn from(run: BenchmarkRun) -> Self {
-        let meta = run.meta.unwrap_or_default();
-        let spec = run.spec.unwrap_or_default();
-        
-        let passed = run.results.iter().filter(|r| r.passed).count();
-        
-        Self {
-            id: meta.id,
-            vm_id: spec.vm_id,
-            total_tests: run.results.len(),
-            passed_tests: passed,
-        }
-    }
-}
-
-impl TableDisplay for BenchmarkRunDisplay {
+impl TableDisplay for BenchmarkRun {
     fn headers() -> Vec<&'static str> {
         vec!["ID", "VM ID", "Tests", "Passed"]
     }
 
     fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let passed = self.results.iter().filter(|r| r.passed).count();
+
         vec![
-            self.id.clone(),
-            self.vm_id.clone(),
-            self.total_tests.to_string(),
-            self.passed_tests.to_string(),
+            meta.id,
+            spec.vm_id,
+            self.results.len().to_string(),
+            passed.to_string(),
         ]
     }
 }
@@ -136,27 +92,17 @@ pub async fn execute(args: BenchmarkArgs, mut client: DaemonClient, format: Outp
             
             print_success(&format!("Benchmark completed for VM '{}'", vm_id));
             
-            let displays: Vec<BenchmarkDisplay> = run.results
-                .into_iter()
-                .map(BenchmarkDisplay::from)
-                .collect();
-            print_list(&displays, format);
+            print_list(&run.results, format);
         }
 
         BenchmarkCommands::List { vm_id } => {
             let runs = client.list_benchmark_runs(vm_id).await?;
-            let displays: Vec<BenchmarkRunDisplay> = runs.into_iter().map(BenchmarkRunDisplay::from).collect();
-            print_list(&displays, format);
+            print_list(&runs, format);
         }
 
         BenchmarkCommands::Get { id } => {
             let run = client.get_benchmark_run(&id).await?;
-            
-            let displays: Vec<BenchmarkDisplay> = run.results
-                .into_iter()
-                .map(BenchmarkDisplay::from)
-                .collect();
-            print_list(&displays, format);
+            print_list(&run.results, format);
         }
     }
 
@@ -0,0 +1,287 @@
+//! Context Commands
+//!
+//! Named daemon addresses kept in `~/.infrasim/contexts.toml`, so commands
+//! can target `--context staging` instead of repeating `--daemon-addr`.
+//! A context also carries the credentials the daemon asks for: a CA and
+//! client certificate for mTLS and/or a bearer token.
+
+use anyhow::{Context as _, Result};
+use clap::Subcommand;
+use infrasim_common::auth::ClientCredentials;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::output::{print_list, print_success, OutputFormat, TableDisplay};
+
+#[derive(Subcommand)]
+pub enum ContextCommands {
+    /// Add a context, or update an existing one
+    Add {
+        /// Context name
+        name: String,
+
+        /// Daemon address, e.g. http://10.0.0.5:50051 or unix:///path/to/daemon.sock
+        #[arg(long)]
+        daemon_addr: String,
+
+        /// Free-form description
+        #[arg(long)]
+        description: Option<String>,
+
+        /// CA certificate the daemon's TLS certificate is checked against
+        #[arg(long)]
+        ca_cert: Option<PathBuf>,
+
+        /// Client certificate for mTLS
+        #[arg(long, requires = "client_key")]
+        client_cert: Option<PathBuf>,
+
+        /// Private key of the client certificate
+        #[arg(long, requires = "client_cert")]
+        client_key: Option<PathBuf>,
+
+        /// Bearer token from `infrasim auth create-token`
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Name to verify the daemon certificate against (default: the address host)
+        #[arg(long)]
+        tls_domain: Option<String>,
+
+        /// Also make it the current context
+        #[arg(long = "use")]
+        use_context: bool,
+    },
+
+    /// Make a context the default for later commands
+    Use {
+        /// Context name
+        name: String,
+    },
+
+    /// List contexts
+    List,
+
+    /// Remove a context
+    Remove {
+        /// Context name
+        name: String,
+    },
+}
+
+/// A named daemon endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Context {
+    pub daemon_addr: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub credentials: ClientCredentials,
+}
+
+/// Contents of `contexts.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ContextFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    #[serde(default)]
+    pub contexts: BTreeMap<String, Context>,
+}
+
+impl ContextFile {
+    /// Location of the contexts file
+    pub fn path() -> PathBuf {
+        infrasim_common::default_store_path().join("contexts.toml")
+    }
+
+    /// Load the contexts file, empty if it doesn't exist yet
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Write the contexts file, readable only by its owner since it can hold tokens
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        infrasim_common::auth::write_private(&path, toml::to_string_pretty(self)?.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn get(&self, name: &str) -> Result<&Context> {
+        self.contexts.get(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown context '{}' (see `infrasim context list`)",
+                name
+            )
+        })
+    }
+}
+
+/// Daemon a command talks to
+pub struct Target {
+    /// Context the address came from, if any
+    pub context: Option<String>,
+    pub daemon_addr: String,
+    /// Context credentials, completed from `INFRASIM_*` environment variables
+    pub credentials: ClientCredentials,
+}
+
+/// Pick the daemon address: `--daemon-addr`, then `--context`, then the
+/// current context, then the local daemon's socket or TCP port
+pub fn resolve(context: Option<&str>, daemon_addr: Option<&str>) -> Result<Target> {
+    if let Some(addr) = daemon_addr {
+        return Ok(Target {
+            context: None,
+            daemon_addr: addr.to_string(),
+            credentials: ClientCredentials::default().with_env(),
+        });
+    }
+
+    let file = ContextFile::load()?;
+    let Some(name) = context.or(file.current.as_deref()) else {
+        return Ok(Target {
+            context: None,
+            daemon_addr: infrasim_common::default_daemon_addr(),
+            credentials: ClientCredentials::default().with_env(),
+        });
+    };
+
+    let context = file.get(name)?;
+    Ok(Target {
+        context: Some(name.to_string()),
+        daemon_addr: context.daemon_addr.clone(),
+        credentials: context.credentials.clone().with_env(),
+    })
+}
+
+/// Context wrapper for serialization
+#[derive(Serialize)]
+pub struct ContextDisplay {
+    pub name: String,
+    pub daemon_addr: String,
+    pub description: String,
+    pub auth: String,
+    pub current: bool,
+}
+
+impl TableDisplay for ContextDisplay {
+    fn headers() -> Vec<&'static str> {
+        vec!["", "Name", "Daemon", "Auth", "Description"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            if self.current { "*".to_string() } else { String::new() },
+            self.name.clone(),
+            self.daemon_addr.clone(),
+            self.auth.clone(),
+            self.description.clone(),
+        ]
+    }
+}
+
+/// What a context presents to the daemon, for listings
+fn auth_summary(credentials: &ClientCredentials) -> String {
+    let mut parts = Vec::new();
+    if credentials.client_cert.is_some() {
+        parts.push("mtls");
+    } else if credentials.ca_cert.is_some() {
+        parts.push("tls");
+    }
+    if credentials.token.is_some() {
+        parts.push("token");
+    }
+    parts.join("+")
+}
+
+pub fn execute(cmd: ContextCommands, format: OutputFormat) -> Result<()> {
+    let mut file = ContextFile::load()?;
+
+    match cmd {
+        ContextCommands::Add {
+            name,
+            daemon_addr,
+            description,
+            ca_cert,
+            client_cert,
+            client_key,
+            token,
+            tls_domain,
+            use_context,
+        } => {
+            if !["http://", "https://", "unix://"]
+                .iter()
+                .any(|scheme| daemon_addr.starts_with(scheme))
+            {
+                anyhow::bail!(
+                    "Daemon address must start with http://, https:// or unix://, got '{}'",
+                    daemon_addr
+                );
+            }
+
+            let credentials = ClientCredentials {
+                ca_cert,
+                client_cert,
+                client_key,
+                token,
+                tls_domain,
+            };
+            if credentials.uses_tls() && !daemon_addr.starts_with("https://") {
+                anyhow::bail!("TLS credentials need an https:// daemon address");
+            }
+
+            let existed = file
+                .contexts
+                .insert(name.clone(), Context { daemon_addr, description, credentials })
+                .is_some();
+            if use_context {
+                file.current = Some(name.clone());
+            }
+            file.save()?;
+
+            let verb = if existed { "updated" } else { "added" };
+            print_success(&format!("Context '{}' {}", name, verb));
+        }
+
+        ContextCommands::Use { name } => {
+            file.get(&name)?;
+            file.current = Some(name.clone());
+            file.save()?;
+            print_success(&format!("Switched to context '{}'", name));
+        }
+
+        ContextCommands::List => {
+            let displays: Vec<ContextDisplay> = file
+                .contexts
+                .iter()
+                .map(|(name, ctx)| ContextDisplay {
+                    name: name.clone(),
+                    daemon_addr: ctx.daemon_addr.clone(),
+                    description: ctx.description.clone().unwrap_or_default(),
+                    auth: auth_summary(&ctx.credentials),
+                    current: file.current.as_ref() == Some(name),
+                })
+                .collect();
+            print_list(&displays, format);
+        }
+
+        ContextCommands::Remove { name } => {
+            file.get(&name)?;
+            file.contexts.remove(&name);
+            if file.current.as_ref() == Some(&name) {
+                file.current = None;
+            }
+            file.save()?;
+            print_success(&format!("Context '{}' removed", name));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Daemon Commands
+
+use clap::Subcommand;
+use anyhow::Result;
+
+use crate::client::DaemonClient;
+use crate::output::{OutputFormat, TableDisplay, print_item, print_success, print_warning};
+use crate::generated::ReplicationStatus;
+
+#[derive(Subcommand)]
+pub enum DaemonCommands {
+    /// Show the daemon's replication role and a standby's progress
+    Replication,
+
+    /// Make a standby the primary, e.g. after its primary died
+    ///
+    /// Replication stops for good. VMs that ran on the old primary are
+    /// marked stopped; start them here once their disks are in place.
+    Promote {
+        /// Promote without asking for confirmation
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+impl TableDisplay for ReplicationStatus {
+    fn headers() -> Vec<&'static str> {
+        vec!["Role", "Primary", "Position", "Last Sync", "Objects", "Error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let standby = self.role == "standby";
+        let last_sync = chrono::DateTime::from_timestamp(self.last_sync_at, 0)
+            .filter(|_| self.last_sync_at > 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "never".to_string());
+
+        vec![
+            self.role.clone(),
+            if standby { self.primary.clone() } else { "-".to_string() },
+            if standby { self.synced_position.to_string() } else { self.position.to_string() },
+            if standby { last_sync } else { "-".to_string() },
+            if standby { self.synced_objects.to_string() } else { "-".to_string() },
+            self.last_error.clone(),
+        ]
+    }
+}
+
+pub async fn execute(cmd: DaemonCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        DaemonCommands::Replication => {
+            let status = client.get_replication_status().await?;
+            print_item(&status, format);
+        }
+
+        DaemonCommands::Promote { force } => {
+            if !force {
+                let status = client.get_replication_status().await?;
+                anyhow::bail!(
+                    "Promoting stops replication from {} for good; re-run with --force",
+                    if status.primary.is_empty() { "the primary" } else { &status.primary }
+                );
+            }
+
+            let stopped = client.promote_daemon().await?;
+            print_success("Daemon promoted to primary");
+            if !stopped.is_empty() {
+                print_warning(&format!(
+                    "{} VMs ran on the old primary and are now stopped: {}",
+                    stopped.len(),
+                    stopped.join(", ")
+                ));
+            }
+            println!("Remove replication.primary from this daemon's config before its next restart.");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,457 @@
+//! Drift Commands
+//!
+//! Cross-checks three views of the same resources: what Terraform recorded in
+//! its state files, what the daemon holds in its database, and what actually
+//! exists on the host (QEMU processes and volume images).
+
+use clap::Subcommand;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::client::DaemonClient;
+use crate::generated::{Network, Vm, VmState, Volume};
+use crate::output::{OutputFormat, TableDisplay, print_list, print_success};
+use crate::stack::STACK_LABEL;
+
+#[derive(Subcommand)]
+pub enum DriftCommands {
+    /// Report resources on which Terraform, the daemon and the host disagree
+    Check {
+        /// Terraform state file to compare with; repeat for several workspaces
+        #[arg(long)]
+        tfstate: Vec<PathBuf>,
+
+        /// Skip the process and file checks, e.g. for a daemon on another host
+        #[arg(long)]
+        skip_host: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Issue {
+    /// VM in neither a Terraform state nor a stack
+    Unmanaged,
+    /// In Terraform state, but gone from the daemon
+    MissingFromDaemon,
+    /// Daemon spec differs from the Terraform state
+    SpecDrift,
+    /// VM refers to a volume or network the daemon doesn't have
+    DanglingReference,
+    /// Running according to the daemon, but its QEMU process is gone
+    ProcessMissing,
+    /// QEMU process the daemon doesn't account for
+    StrayProcess,
+    /// Volume image or one of its backing files is missing
+    MissingFile,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unmanaged => "unmanaged",
+            Self::MissingFromDaemon => "missing from daemon",
+            Self::SpecDrift => "spec drift",
+            Self::DanglingReference => "dangling reference",
+            Self::ProcessMissing => "process missing",
+            Self::StrayProcess => "stray process",
+            Self::MissingFile => "missing file",
+        })
+    }
+}
+
+/// One disagreement between the views
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// vm, volume or network
+    pub kind: &'static str,
+    pub id: String,
+    pub name: String,
+    pub issue: Issue,
+    /// Attributes that differ from the Terraform state
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<&'static str>,
+    pub detail: String,
+}
+
+impl TableDisplay for Finding {
+    fn headers() -> Vec<&'static str> {
+        vec!["Kind", "ID", "Name", "Issue", "Detail"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let detail = if self.fields.is_empty() {
+            self.detail.clone()
+        } else {
+            format!("{}: {}", self.detail, self.fields.join(", "))
+        };
+        vec![
+            self.kind.to_string(),
+            self.id.clone(),
+            self.name.clone(),
+            self.issue.to_string(),
+            detail,
+        ]
+    }
+}
+
+/// A resource instance recorded in a Terraform state file
+struct Recorded {
+    type_name: String,
+    address: String,
+    attributes: Value,
+}
+
+impl Recorded {
+    fn id(&self) -> &str {
+        self.attributes["id"].as_str().unwrap_or_default()
+    }
+
+    /// Attributes whose recorded value differs from `have`. Attributes the
+    /// state leaves null aren't managed by the configuration.
+    fn changed(&self, have: &[(&'static str, Value)]) -> Vec<&'static str> {
+        have.iter()
+            .filter(|(name, value)| {
+                self.attributes.get(name).is_some_and(|v| !v.is_null() && v != value)
+            })
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+pub async fn execute(cmd: DriftCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        DriftCommands::Check { tfstate, skip_host } => {
+            let vms = client.list_vms().await?;
+            let volumes = client.list_volumes().await?;
+            let networks = client.list_networks().await?;
+
+            let mut findings = Vec::new();
+            if !tfstate.is_empty() {
+                let mut recorded = Vec::new();
+                for path in &tfstate {
+                    recorded.extend(load_tfstate(path)?);
+                }
+                findings.extend(check_tfstate(&recorded, &vms, &volumes, &networks));
+            }
+            findings.extend(check_references(&vms, &volumes, &networks));
+            if !skip_host {
+                findings.extend(check_processes(&vms, &qemu_processes()?));
+                findings.extend(check_files(&mut client, &volumes).await);
+            }
+
+            if findings.is_empty() {
+                print_success("No drift detected");
+                return Ok(());
+            }
+            print_list(&findings, format);
+            anyhow::bail!("Drift detected: {} finding(s)", findings.len());
+        }
+    }
+}
+
+/// InfraSim resources of a version 4 Terraform state file
+fn load_tfstate(path: &Path) -> Result<Vec<Recorded>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let state: Value = serde_json::from_str(&contents)
+        .with_context(|| format!("Invalid Terraform state {}", path.display()))?;
+    if state["version"].as_u64() != Some(4) {
+        anyhow::bail!("{} is not a version 4 Terraform state file", path.display());
+    }
+
+    let mut recorded = Vec::new();
+    for resource in state["resources"].as_array().into_iter().flatten() {
+        let type_name = resource["type"].as_str().unwrap_or_default();
+        if resource["mode"] != "managed" || !type_name.starts_with("infrasim_") {
+            continue;
+        }
+        let mut address = format!("{}.{}", type_name, resource["name"].as_str().unwrap_or_default());
+        if let Some(module) = resource["module"].as_str() {
+            address = format!("{}.{}", module, address);
+        }
+        for instance in resource["instances"].as_array().into_iter().flatten() {
+            let address = match &instance["index_key"] {
+                Value::Null => address.clone(),
+                key => format!("{}[{}]", address, key),
+            };
+            recorded.push(Recorded {
+                type_name: type_name.to_string(),
+                address,
+                attributes: instance["attributes"].clone(),
+            });
+        }
+    }
+    Ok(recorded)
+}
+
+/// Compare the recorded VMs, volumes and networks with the daemon's
+fn check_tfstate(recorded: &[Recorded], vms: &[Vm], volumes: &[Volume], networks: &[Network]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let missing = |kind, r: &Recorded| Finding {
+        kind,
+        id: r.id().to_string(),
+        name: r.address.clone(),
+        issue: Issue::MissingFromDaemon,
+        fields: vec![],
+        detail: format!("{} is in Terraform state but not in the daemon", r.address),
+    };
+    let drift = |kind, id: &str, name: &str, r: &Recorded, fields: Vec<&'static str>| Finding {
+        kind,
+        id: id.to_string(),
+        name: name.to_string(),
+        issue: Issue::SpecDrift,
+        fields,
+        detail: format!("differs from {}", r.address),
+    };
+
+    for r in recorded {
+        match r.type_name.as_str() {
+            "infrasim_vm" | "infrasim_vm_clone" => {
+                let Some(vm) = vms.iter().find(|vm| vm.meta.as_ref().is_some_and(|m| m.id == r.id())) else {
+                    findings.push(missing("vm", r));
+                    continue;
+                };
+                // Clones take their spec from the template
+                if r.type_name == "infrasim_vm_clone" {
+                    continue;
+                }
+                let spec = vm.spec.clone().unwrap_or_default();
+                let fields = r.changed(&[
+                    ("arch", json!(spec.arch)),
+                    ("machine", json!(spec.machine)),
+                    ("cpu_cores", json!(spec.cpu_cores)),
+                    ("memory_mb", json!(spec.memory_mb)),
+                    ("boot_disk_id", json!(spec.boot_disk_id)),
+                    ("volume_ids", json!(spec.volume_ids)),
+                    ("enable_tpm", json!(spec.enable_tpm)),
+                ]);
+                if !fields.is_empty() {
+                    findings.push(drift("vm", r.id(), &meta_name(vm.meta.as_ref()), r, fields));
+                }
+            }
+            "infrasim_volume" => {
+                let Some(volume) = volumes.iter().find(|v| v.meta.as_ref().is_some_and(|m| m.id == r.id())) else {
+                    findings.push(missing("volume", r));
+                    continue;
+                };
+                let spec = volume.spec.clone().unwrap_or_default();
+                let fields = r.changed(&[
+                    ("source", json!(spec.source)),
+                    ("format", json!(spec.format)),
+                    ("size_bytes", json!(spec.size_bytes)),
+                    ("read_only", json!(spec.read_only)),
+                    ("overlay", json!(spec.overlay)),
+                ]);
+                if !fields.is_empty() {
+                    findings.push(drift("volume", r.id(), &meta_name(volume.meta.as_ref()), r, fields));
+                }
+            }
+            "infrasim_network" => {
+                let Some(network) = networks.iter().find(|n| n.meta.as_ref().is_some_and(|m| m.id == r.id())) else {
+                    findings.push(missing("network", r));
+                    continue;
+                };
+                let spec = network.spec.clone().unwrap_or_default();
+                let fields = r.changed(&[
+                    ("cidr", json!(spec.cidr)),
+                    ("gateway", json!(spec.gateway)),
+                    ("dns", json!(spec.dns)),
+                    ("dhcp_enabled", json!(spec.dhcp_enabled)),
+                    ("mtu", json!(spec.mtu)),
+                ]);
+                if !fields.is_empty() {
+                    findings.push(drift("network", r.id(), &meta_name(network.meta.as_ref()), r, fields));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // VMs belong to Terraform or a stack; anything else was made by hand
+    let managed: HashSet<&str> = recorded.iter().map(Recorded::id).collect();
+    for vm in vms {
+        let Some(meta) = vm.meta.as_ref() else { continue };
+        if !managed.contains(meta.id.as_str()) && !meta.labels.contains_key(STACK_LABEL) {
+            findings.push(Finding {
+                kind: "vm",
+                id: meta.id.clone(),
+                name: meta.name.clone(),
+                issue: Issue::Unmanaged,
+                fields: vec![],
+                detail: "not in any Terraform state or stack".to_string(),
+            });
+        }
+    }
+
+    findings
+}
+
+/// VMs referring to volumes or networks the daemon no longer has
+fn check_references(vms: &[Vm], volumes: &[Volume], networks: &[Network]) -> Vec<Finding> {
+    let volume_ids: HashSet<&str> = volumes.iter().filter_map(|v| v.meta.as_ref()).map(|m| m.id.as_str()).collect();
+    let network_ids: HashSet<&str> = networks.iter().filter_map(|n| n.meta.as_ref()).map(|m| m.id.as_str()).collect();
+
+    let mut findings = Vec::new();
+    for vm in vms {
+        let spec = vm.spec.clone().unwrap_or_default();
+        let missing_volumes = std::iter::once(&spec.boot_disk_id)
+            .chain(&spec.volume_ids)
+            .filter(|id| !id.is_empty() && !volume_ids.contains(id.as_str()))
+            .map(|id| format!("volume {}", id));
+        let missing_networks = spec.network_ids.iter()
+            .filter(|id| !network_ids.contains(id.as_str()))
+            .map(|id| format!("network {}", id));
+        let mut missing: Vec<String> = missing_volumes.chain(missing_networks).collect();
+        missing.dedup();
+        if !missing.is_empty() {
+            findings.push(Finding {
+                kind: "vm",
+                id: meta_id(vm.meta.as_ref()),
+                name: meta_name(vm.meta.as_ref()),
+                issue: Issue::DanglingReference,
+                fields: vec![],
+                detail: format!("refers to missing {}", missing.join(", ")),
+            });
+        }
+    }
+    findings
+}
+
+/// A QEMU process started by the daemon
+struct QemuProcess {
+    pid: String,
+    /// VM ID, from the name of its QMP socket
+    vm_id: String,
+}
+
+/// QEMU processes on this host that have a daemon-style QMP socket
+fn qemu_processes() -> Result<Vec<QemuProcess>> {
+    let output = Command::new("ps")
+        .args(["-A", "-o", "pid=", "-o", "args="])
+        .output()
+        .context("Failed to list processes")?;
+    if !output.status.success() {
+        anyhow::bail!("ps failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, args) = line.trim().split_once(' ')?;
+            let mut args = args.split_whitespace();
+            if !args.next()?.contains("qemu-system-") {
+                return None;
+            }
+            // -qmp unix:<socket dir>/<vm id>.qmp,server,nowait
+            let socket = args.skip_while(|a| *a != "-qmp").nth(1)?;
+            let path = socket.strip_prefix("unix:")?.split(',').next()?;
+            let vm_id = Path::new(path).file_name()?.to_str()?.strip_suffix(".qmp")?;
+            Some(QemuProcess {
+                pid: pid.to_string(),
+                vm_id: vm_id.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Running VMs without QEMU, and QEMU without a running VM
+fn check_processes(vms: &[Vm], processes: &[QemuProcess]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let by_vm: HashMap<&str, &QemuProcess> = processes.iter().map(|p| (p.vm_id.as_str(), p)).collect();
+
+    for vm in vms {
+        let id = meta_id(vm.meta.as_ref());
+        let status = vm.status.clone().unwrap_or_default();
+        let running = matches!(
+            VmState::try_from(status.state),
+            Ok(VmState::Running) | Ok(VmState::Paused)
+        );
+        match (running, by_vm.get(id.as_str())) {
+            (true, None) => findings.push(Finding {
+                kind: "vm",
+                id,
+                name: meta_name(vm.meta.as_ref()),
+                issue: Issue::ProcessMissing,
+                fields: vec![],
+                detail: if status.qemu_pid.is_empty() {
+                    "daemon reports it running but no QEMU process exists".to_string()
+                } else {
+                    format!("QEMU process {} recorded by the daemon is gone", status.qemu_pid)
+                },
+            }),
+            (false, Some(process)) => findings.push(Finding {
+                kind: "vm",
+                id,
+                name: meta_name(vm.meta.as_ref()),
+                issue: Issue::StrayProcess,
+                fields: vec![],
+                detail: format!("QEMU process {} is running but the daemon reports the VM stopped", process.pid),
+            }),
+            _ => {}
+        }
+    }
+
+    let known: HashSet<String> = vms.iter().map(|vm| meta_id(vm.meta.as_ref())).collect();
+    for process in processes.iter().filter(|p| !known.contains(&p.vm_id)) {
+        findings.push(Finding {
+            kind: "vm",
+            id: process.vm_id.clone(),
+            name: String::new(),
+            issue: Issue::StrayProcess,
+            fields: vec![],
+            detail: format!("QEMU process {} belongs to a VM the daemon doesn't have", process.pid),
+        });
+    }
+
+    findings
+}
+
+/// Volumes whose image, or a backing file of it, is gone from disk
+async fn check_files(client: &mut DaemonClient, volumes: &[Volume]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for volume in volumes {
+        let id = meta_id(volume.meta.as_ref());
+        let local_path = volume.status.clone().unwrap_or_default().local_path;
+        // Still downloading, or never materialized
+        if local_path.is_empty() {
+            continue;
+        }
+
+        let missing = if !Path::new(&local_path).exists() {
+            vec![local_path]
+        } else {
+            match client.get_volume_chain(&id).await {
+                Ok(layers) => layers.into_iter()
+                    .map(|layer| layer.path)
+                    .filter(|path| !path.is_empty() && !Path::new(path).exists())
+                    .collect(),
+                Err(e) => vec![format!("backing chain ({})", e)],
+            }
+        };
+        if !missing.is_empty() {
+            findings.push(Finding {
+                kind: "volume",
+                id,
+                name: meta_name(volume.meta.as_ref()),
+                issue: Issue::MissingFile,
+                fields: vec![],
+                detail: format!("missing {}", missing.join(", ")),
+            });
+        }
+    }
+    findings
+}
+
+fn meta_id(meta: Option<&crate::generated::ResourceMeta>) -> String {
+    meta.map(|m| m.id.clone()).unwrap_or_default()
+}
+
+fn meta_name(meta: Option<&crate::generated::ResourceMeta>) -> String {
+    meta.map(|m| m.name.clone()).unwrap_or_default()
+}
@@ -0,0 +1,122 @@
+//! Event Commands
+
+use clap::{Parser, ValueEnum};
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::client::DaemonClient;
+use crate::output::OutputFormat;
+use crate::generated::{Event, EventType};
+
+#[derive(Parser)]
+pub struct EventsArgs {
+    /// Keep streaming new events until interrupted
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// Only show events for this resource type (vm, volume, snapshot, ...)
+    #[arg(long = "type")]
+    pub resource_type: Option<String>,
+
+    /// Only show events for this resource ID
+    #[arg(long)]
+    pub id: Option<String>,
+
+    /// Only show these kinds of events (repeatable)
+    #[arg(short, long, value_enum)]
+    pub kind: Vec<EventKindArg>,
+
+    /// Recent events to show first
+    #[arg(short = 'n', long, default_value = "20")]
+    pub tail: u32,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum EventKindArg {
+    Created,
+    Updated,
+    Deleted,
+    State,
+    Error,
+}
+
+impl From<EventKindArg> for EventType {
+    fn from(kind: EventKindArg) -> Self {
+        match kind {
+            EventKindArg::Created => EventType::Created,
+            EventKindArg::Updated => EventType::Updated,
+            EventKindArg::Deleted => EventType::Deleted,
+            EventKindArg::State => EventType::VmStateChanged,
+            EventKindArg::Error => EventType::ReconcileError,
+        }
+    }
+}
+
+/// Event wrapper for serialization
+#[derive(Serialize)]
+pub struct EventDisplay {
+    pub timestamp: i64,
+    pub kind: String,
+    pub resource_type: String,
+    pub resource_id: String,
+    pub resource_name: String,
+    pub message: String,
+}
+
+impl From<Event> for EventDisplay {
+    fn from(event: Event) -> Self {
+        let kind = match EventType::try_from(event.r#type) {
+            Ok(EventType::Created) => "created",
+            Ok(EventType::Updated) => "updated",
+            Ok(EventType::Deleted) => "deleted",
+            Ok(EventType::VmStateChanged) => "state",
+            Ok(EventType::ReconcileError) => "error",
+            _ => "unknown",
+        };
+
+        Self {
+            timestamp: event.timestamp,
+            kind: kind.to_string(),
+            resource_type: event.resource_type,
+            resource_id: event.resource_id,
+            resource_name: event.resource_name,
+            message: event.message,
+        }
+    }
+}
+
+pub async fn execute(args: EventsArgs, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    let kinds = args.kind.into_iter().map(EventType::from).collect();
+    let mut stream = client
+        .watch_events(
+            args.resource_type.unwrap_or_default(),
+            args.id.unwrap_or_default(),
+            kinds,
+            args.tail,
+            args.follow,
+        )
+        .await?;
+
+    while let Some(event) = stream.message().await? {
+        let display = EventDisplay::from(event);
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string(&display)?),
+            _ => {
+                let time = chrono::DateTime::from_timestamp(display.timestamp, 0)
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                let name = if display.resource_name.is_empty() {
+                    display.resource_id.clone()
+                } else {
+                    display.resource_name.clone()
+                };
+                println!(
+                    "{}  {:<8} {}/{}  {}",
+                    time, display.kind, display.resource_type, name, display.message
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
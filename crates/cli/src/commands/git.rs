@@ -0,0 +1,303 @@
+//! Git Commands
+//!
+//! Keeps lab environments as manifest files (see [`crate::manifest`]) on Git
+//! branches: `env create` commits the daemon's resources to a new branch,
+//! `env checkout` converges the daemon to a branch, and `env diff` compares
+//! two. Branches are written with plumbing commands, so the working tree and
+//! the checked-out branch are never touched.
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use infrasim_common::types::parse_selector;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::client::DaemonClient;
+use crate::commands::apply::converge;
+use crate::manifest::{self, Existing, Kind, Manifest};
+use crate::output::{print_list, print_success, OutputFormat, TableDisplay};
+use crate::watch;
+
+/// Manifest file of an environment branch, relative to the repository root
+const DEFAULT_PATH: &str = "infrasim.yaml";
+
+#[derive(Subcommand)]
+pub enum GitCommands {
+    /// Environments as manifests on Git branches
+    #[command(subcommand)]
+    Env(EnvCommands),
+}
+
+#[derive(Subcommand)]
+pub enum EnvCommands {
+    /// Commit the daemon's resources to a new branch
+    Create {
+        /// Branch to create
+        branch: String,
+
+        /// Commit the branch starts from
+        #[arg(long, default_value = "HEAD")]
+        from: String,
+
+        /// Only export resources with these labels, e.g. 'env=lab'
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
+
+        /// Commit message
+        #[arg(short, long)]
+        message: Option<String>,
+
+        #[command(flatten)]
+        repo: RepoArgs,
+    },
+
+    /// Converge the daemon to a branch's manifest
+    Checkout {
+        /// Branch, tag or commit
+        branch: String,
+
+        /// Delete resources matching --selector that the manifest doesn't name
+        #[arg(long, requires = "selector")]
+        prune: bool,
+
+        /// Labels of the resources --prune considers
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
+
+        /// Show the changes without making them
+        #[arg(long)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        repo: RepoArgs,
+    },
+
+    /// Show how the manifests of two branches differ
+    Diff {
+        /// Branch, tag or commit to compare from
+        from: String,
+
+        /// Branch, tag or commit to compare to
+        to: String,
+
+        #[command(flatten)]
+        repo: RepoArgs,
+    },
+}
+
+#[derive(Args)]
+pub struct RepoArgs {
+    /// Git repository
+    #[arg(long, default_value = ".")]
+    repo: PathBuf,
+
+    /// Manifest file within the repository
+    #[arg(long, default_value = DEFAULT_PATH)]
+    path: String,
+}
+
+/// Difference in one resource between two manifests
+#[derive(Debug, Serialize)]
+pub struct EnvChange {
+    pub kind: Kind,
+    pub name: String,
+    /// added, removed or changed
+    pub change: &'static str,
+    /// Paths of the changed fields
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+impl TableDisplay for EnvChange {
+    fn headers() -> Vec<&'static str> {
+        vec!["Kind", "Name", "Change", "Fields"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.kind.to_string(),
+            self.name.clone(),
+            self.change.to_string(),
+            self.fields.join(", "),
+        ]
+    }
+}
+
+pub async fn execute(cmd: GitCommands, client: Result<DaemonClient>, format: OutputFormat) -> Result<()> {
+    let GitCommands::Env(cmd) = cmd;
+    match cmd {
+        EnvCommands::Create { branch, from, selector, message, repo } => {
+            let mut client = client?;
+            git(&repo.repo, &["check-ref-format", "--branch", &branch], None, None)
+                .with_context(|| format!("Invalid branch name '{}'", branch))?;
+            let reference = format!("refs/heads/{}", branch);
+            if resolve(&repo.repo, &reference)?.is_some() {
+                anyhow::bail!("Branch '{}' already exists", branch);
+            }
+            let parent = resolve(&repo.repo, &from)?;
+            if parent.is_none() && from != "HEAD" {
+                anyhow::bail!("Unknown commit '{}'", from);
+            }
+
+            let existing = Existing::fetch(&mut client).await?;
+            let documents = existing.export(&selector.unwrap_or_default());
+            let content = format!(
+                "# Environment '{}', written by `infrasim git env create`\n{}",
+                branch,
+                manifest::render(&documents)?
+            );
+            let message = message.unwrap_or_else(|| format!("Create environment {}", branch));
+            let commit = commit_file(&repo.repo, parent.as_deref(), &repo.path, &content, &message)?;
+            // An empty old value makes the update fail if the branch appeared meanwhile
+            git(&repo.repo, &["update-ref", &reference, &commit, ""], None, None)?;
+
+            print_success(&format!(
+                "Committed {} resource(s) to branch '{}' ({})",
+                documents.len(),
+                branch,
+                &commit[..commit.len().min(12)]
+            ));
+        }
+
+        EnvCommands::Checkout { branch, prune, selector, dry_run, repo } => {
+            let manifests = load(&repo, &branch)?;
+            let mut client = client?;
+            let prune = if prune { selector.as_ref() } else { None };
+            converge(&mut client, manifests, prune, dry_run, format).await?;
+        }
+
+        EnvCommands::Diff { from, to, repo } => {
+            let changes = diff(&load(&repo, &from)?, &load(&repo, &to)?);
+            if changes.is_empty() {
+                print_success(&format!("'{}' and '{}' declare the same resources", from, to));
+            } else {
+                print_list(&changes, format);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Manifests of the environment at `rev`
+fn load(repo: &RepoArgs, rev: &str) -> Result<Vec<Manifest>> {
+    let object = format!("{}:{}", rev, repo.path.trim_start_matches("./"));
+    let content = git(&repo.repo, &["show", &object], None, None)
+        .with_context(|| format!("No manifest {} on '{}'", repo.path, rev))?;
+    manifest::parse(&content).with_context(|| format!("Invalid manifest on '{}'", rev))
+}
+
+/// Resources added, removed or changed from `from` to `to`
+fn diff(from: &[Manifest], to: &[Manifest]) -> Vec<EnvChange> {
+    let index = |manifests: &[Manifest]| -> BTreeMap<(Kind, String), Value> {
+        manifests
+            .iter()
+            .map(|m| {
+                let labels: BTreeMap<_, _> = m.labels.iter().collect();
+                ((m.kind(), m.name.clone()), json!({"labels": labels, "spec": m.spec}))
+            })
+            .collect()
+    };
+    let from = index(from);
+    let to = index(to);
+
+    let mut keys: Vec<&(Kind, String)> = from.keys().chain(to.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (change, fields) = match (from.get(key), to.get(key)) {
+                (Some(_), None) => ("removed", Vec::new()),
+                (None, Some(_)) => ("added", Vec::new()),
+                (Some(old), Some(new)) => {
+                    let fields: Vec<String> = watch::diff(old, new).into_iter().map(|c| c.path).collect();
+                    if fields.is_empty() {
+                        return None;
+                    }
+                    ("changed", fields)
+                }
+                (None, None) => return None,
+            };
+            Some(EnvChange {
+                kind: key.0,
+                name: key.1.clone(),
+                change,
+                fields,
+            })
+        })
+        .collect()
+}
+
+/// Commit `content` as `path` on top of `parent`, without touching the
+/// working tree or index; returns the new commit
+fn commit_file(repo: &Path, parent: Option<&str>, path: &str, content: &str, message: &str) -> Result<String> {
+    let blob = git(repo, &["hash-object", "-w", "--stdin"], Some(content), None)?;
+    let index = PathBuf::from(git(repo, &["rev-parse", "--git-path", "infrasim-env-index"], None, None)?);
+    let index = if index.is_absolute() { index } else { repo.join(index) };
+    let _ = std::fs::remove_file(&index);
+
+    let result = (|| {
+        if let Some(parent) = parent {
+            git(repo, &["read-tree", parent], None, Some(&index))?;
+        }
+        let entry = format!("100644,{},{}", blob, path.trim_start_matches("./"));
+        git(repo, &["update-index", "--add", "--cacheinfo", &entry], None, Some(&index))?;
+        let tree = git(repo, &["write-tree"], None, Some(&index))?;
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", message];
+        if let Some(parent) = parent {
+            args.extend(["-p", parent]);
+        }
+        git(repo, &args, None, None)
+    })();
+    let _ = std::fs::remove_file(&index);
+    result
+}
+
+/// Commit ID of `rev`, or None if there is no such commit
+fn resolve(repo: &Path, rev: &str) -> Result<Option<String>> {
+    let object = format!("{}^{{commit}}", rev);
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["rev-parse", "--verify", "--quiet", &object])
+        .output()
+        .context("Failed to run git")?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// Run git in `repo`, optionally with `input` on stdin and a separate index
+/// file; returns its trimmed stdout
+fn git(repo: &Path, args: &[&str], input: Option<&str>, index: Option<&Path>) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+
+    let mut child = command.spawn().context("Failed to run git")?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
@@ -0,0 +1,216 @@
+//! Job Commands
+
+use std::collections::HashMap;
+
+use clap::{Subcommand, ValueEnum};
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::client::DaemonClient;
+use crate::output::{OutputFormat, TableDisplay, print_info, print_item, print_list, print_success};
+use crate::generated::{EventType, Job, JobKind, JobState};
+
+#[derive(Subcommand)]
+pub enum JobCommands {
+    /// List background jobs, newest first
+    List {
+        /// Only jobs in this state
+        #[arg(long, value_enum)]
+        state: Option<JobStateArg>,
+    },
+
+    /// Get job details and results
+    Get {
+        /// Job ID
+        id: String,
+    },
+
+    /// Follow a job's progress until it finishes
+    Watch {
+        /// Job ID
+        id: String,
+    },
+
+    /// Cancel a pending job, or stop a running volume or appliance push or pull
+    Cancel {
+        /// Job ID
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum JobStateArg {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl From<JobStateArg> for JobState {
+    fn from(state: JobStateArg) -> Self {
+        match state {
+            JobStateArg::Pending => JobState::Pending,
+            JobStateArg::Running => JobState::Running,
+            JobStateArg::Succeeded => JobState::Succeeded,
+            JobStateArg::Failed => JobState::Failed,
+            JobStateArg::Cancelled => JobState::Cancelled,
+        }
+    }
+}
+
+fn kind_name(kind: i32) -> &'static str {
+    match JobKind::try_from(kind) {
+        Ok(JobKind::PushVolume) => "push_volume",
+        Ok(JobKind::PullVolume) => "pull_volume",
+        Ok(JobKind::FlattenVolume) => "flatten_volume",
+        Ok(JobKind::PushAppliance) => "push_appliance",
+        Ok(JobKind::PullAppliance) => "pull_appliance",
+        Ok(JobKind::CreateBackup) => "create_backup",
+        Ok(JobKind::RestoreBackup) => "restore_backup",
+        Ok(JobKind::Provision) => "provision",
+        _ => "",
+    }
+}
+
+fn state_name(state: i32) -> &'static str {
+    match JobState::try_from(state) {
+        Ok(JobState::Pending) => "pending",
+        Ok(JobState::Running) => "running",
+        Ok(JobState::Succeeded) => "succeeded",
+        Ok(JobState::Failed) => "failed",
+        Ok(JobState::Cancelled) => "cancelled",
+        _ => "",
+    }
+}
+
+impl TableDisplay for Job {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Kind", "Target", "State", "Progress", "Message", "Created"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        let created = chrono::DateTime::from_timestamp(meta.created_at, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let message = if status.error.is_empty() { status.message } else { status.error };
+
+        vec![
+            meta.id,
+            meta.name,
+            kind_name(spec.kind).to_string(),
+            spec.target,
+            state_name(status.state).to_string(),
+            format!("{:.0}%", status.progress),
+            message,
+            created,
+        ]
+    }
+}
+
+pub async fn execute(cmd: JobCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        JobCommands::List { state } => {
+            let jobs = client.list_jobs(state.map(JobState::from)).await?;
+            print_list(&jobs, format);
+        }
+
+        JobCommands::Get { id } => {
+            let job = client.get_job(&id).await?;
+            print_item(&job, format);
+            print_results(&job);
+        }
+
+        JobCommands::Watch { id } => {
+            let job = watch(&mut client, &id).await?;
+            let meta = job.meta.clone().unwrap_or_default();
+            print_success(&format!("Job '{}' succeeded", meta.name));
+            print_results(&job);
+        }
+
+        JobCommands::Cancel { id } => {
+            let job = client.cancel_job(&id).await?;
+            let meta = job.meta.clone().unwrap_or_default();
+            let status = job.status.unwrap_or_default();
+            if JobState::try_from(status.state) == Ok(JobState::Running) {
+                print_success(&format!("Job '{}' is being stopped", meta.name));
+            } else {
+                print_success(&format!("Job '{}' cancelled", meta.name));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Queue a job instead of waiting for the operation, and say how to follow it
+pub async fn submit(
+    client: &mut DaemonClient,
+    kind: JobKind,
+    target: &str,
+    params: HashMap<String, String>,
+    format: OutputFormat,
+) -> Result<()> {
+    let job = client.create_job(kind, target, params).await?;
+    let meta = job.meta.clone().unwrap_or_default();
+    print_success(&format!("Job '{}' queued", meta.name));
+    print_info(&format!("Follow it with: infrasim job watch {}", meta.id));
+    print_item(&job, format);
+    Ok(())
+}
+
+/// Render a job's progress until it finishes; errors unless it succeeded
+pub async fn watch(client: &mut DaemonClient, id: &str) -> Result<Job> {
+    // Subscribe before reading the job so no update in between is missed
+    let mut events = client
+        .watch_events("job".to_string(), id.to_string(), vec![EventType::Updated], 0, true)
+        .await?;
+    let mut job = client.get_job(id).await?;
+
+    let bar = ProgressBar::new(100);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner} [{bar:40}] {pos:>3}% {msg} ({elapsed})")?
+            .progress_chars("=> "),
+    );
+
+    loop {
+        let status = job.status.clone().unwrap_or_default();
+        bar.set_position(status.progress as u64);
+        bar.set_message(status.message.clone());
+
+        match JobState::try_from(status.state) {
+            Ok(JobState::Succeeded) => {
+                bar.finish_with_message("done");
+                return Ok(job);
+            }
+            Ok(JobState::Failed) => {
+                bar.abandon_with_message("failed");
+                anyhow::bail!("Job failed: {}", status.error);
+            }
+            Ok(JobState::Cancelled) => {
+                bar.abandon_with_message("cancelled");
+                anyhow::bail!("Job was cancelled");
+            }
+            _ => {}
+        }
+
+        if events.message().await?.is_none() {
+            bar.abandon();
+            anyhow::bail!("Event stream ended before the job finished");
+        }
+        job = client.get_job(id).await?;
+    }
+}
+
+fn print_results(job: &Job) {
+    let status = job.status.clone().unwrap_or_default();
+    let mut result: Vec<_> = status.result.into_iter().collect();
+    result.sort();
+    for (key, value) in result {
+        print_info(&format!("{}: {}", key, value));
+    }
+}
@@ -3,8 +3,12 @@
 pub mod vm;
 pub mod network;
 pub mod volume;
+pub mod appliance;
 pub mod console;
 pub mod snapshot;
+pub mod backup;
+pub mod job;
+pub mod events;
 pub mod benchmark;
 pub mod attestation;
 pub mod web;
@@ -12,3 +16,14 @@ pub mod artifact;
 pub mod control;
 pub mod pipeline;
 pub mod sdn;
+pub mod context;
+pub mod auth;
+pub mod stack;
+pub mod apply;
+pub mod git;
+pub mod audit;
+pub mod secret;
+pub mod drift;
+pub mod daemon;
+pub mod admin;
+pub mod scenario;
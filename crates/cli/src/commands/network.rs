@@ -1,22 +1,40 @@
 //! Network Commands
 
 use clap::Subcommand;
-use anyhow::Result;
-use serde::Serialize;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 
 use crate::client::DaemonClient;
+use crate::watch::Watch;
 use crate::output::{OutputFormat, TableDisplay, print_item, print_list, print_success};
-use crate::generated::{Network, NetworkSpec, NetworkMode};
+use crate::generated::{
+    CaptureState, ChaosPartition, ChaosProfile, ChaosProfileSpec, FirewallAction, LatencyDistribution, RouterRule, Network, NetworkCapture, NetworkSpec, NetworkMode, PortForward, PortForwardSpec, PortProtocol,
+    QoSProfile, QoSProfileSpec, Router, RouterSpec, RuleProtocol, StaticRoute,
+};
 
 #[derive(Subcommand)]
 pub enum NetworkCommands {
     /// List all networks
-    List,
+    List {
+        /// Print the list again whenever one changes
+        #[arg(short, long)]
+        watch: bool,
+    },
 
     /// Get network details
     Get {
         /// Network ID
         id: String,
+
+        /// Print the network again whenever it changes
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Print only what changed since the network was last shown
+        #[arg(long)]
+        output_diff: bool,
     },
 
     /// Create a new network
@@ -55,69 +73,552 @@ pub enum NetworkCommands {
         /// Network ID
         id: String,
     },
+
+    /// Manage host-to-guest port forwards (user-mode networks)
+    #[command(subcommand)]
+    PortForward(PortForwardCommands),
+
+    /// Manage QoS profiles (latency, jitter, loss, bandwidth) for VM NICs
+    #[command(subcommand)]
+    Qos(QosCommands),
+
+    /// Manage routers that connect vmnet-shared networks
+    #[command(subcommand)]
+    Router(RouterCommands),
+
+    /// Capture a network's packets to a pcap file
+    #[command(subcommand)]
+    Capture(CaptureCommands),
+
+    /// Inject faults (latency, loss, bandwidth caps, partitions) into
+    /// networks or VM NICs until they expire
+    #[command(subcommand)]
+    Chaos(ChaosCommands),
+}
+
+#[derive(Subcommand)]
+pub enum ChaosCommands {
+    /// Apply a chaos profile, replacing the faults of one with the same name
+    Apply {
+        /// Profile name
+        name: String,
+
+        /// Network ID: target every VM NIC on the network
+        #[arg(long)]
+        network: Option<String>,
+
+        /// VM ID: target the VM's NICs, or only its NIC on --network
+        #[arg(long)]
+        vm: Option<String>,
+
+        /// Added latency in milliseconds
+        #[arg(long, default_value = "0")]
+        latency: u32,
+
+        /// Variation of the added latency in milliseconds
+        #[arg(long, default_value = "0")]
+        jitter: u32,
+
+        /// How latency varies within the jitter (uniform, normal, pareto)
+        #[arg(long, default_value = "uniform")]
+        distribution: String,
+
+        /// Packet loss percentage (0-100)
+        #[arg(long, default_value = "0")]
+        loss: f32,
+
+        /// Bandwidth limit in Mbps (0 = unlimited)
+        #[arg(long, default_value = "0")]
+        rate: u32,
+
+        /// Comma-separated VM IDs on one side of a partition; repeat for
+        /// each side
+        #[arg(long = "partition")]
+        partitions: Vec<String>,
+
+        /// Remove the profile after this many seconds
+        #[arg(long)]
+        ttl: Option<u64>,
+    },
+
+    /// List chaos profiles
+    List {
+        /// Filter by network ID
+        #[arg(long)]
+        network: Option<String>,
+
+        /// Filter by VM ID
+        #[arg(long)]
+        vm: Option<String>,
+    },
+
+    /// Get chaos profile details
+    Get {
+        /// Profile ID
+        id: String,
+    },
+
+    /// Remove a chaos profile, stopping its faults
+    Remove {
+        /// Profile ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CaptureCommands {
+    /// Start capturing, replacing the network's previous capture
+    Start {
+        /// Network ID
+        network: String,
+
+        /// Wait for the capture to stop (a limit or Ctrl-C) and download it here
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// pcap filter expression, e.g. "port 443" (vmnet-shared networks only)
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Stop after the capture reaches this many MiB (default 100)
+        #[arg(long)]
+        max_size: Option<u64>,
+
+        /// Stop after this many seconds (default 3600)
+        #[arg(long)]
+        duration: Option<u64>,
+    },
+
+    /// Stop a running capture
+    Stop {
+        /// Network ID
+        network: String,
+
+        /// Download the capture here once stopped
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show the network's latest capture
+    Status {
+        /// Network ID
+        network: String,
+    },
+
+    /// Download the network's last stopped capture
+    Download {
+        /// Network ID
+        network: String,
+
+        /// pcap file to write
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RouterCommands {
+    /// Connect networks with a router
+    Create {
+        /// Router name
+        #[arg(short, long)]
+        name: String,
+
+        /// Network ID to connect (repeat for each network)
+        #[arg(long = "network", required = true)]
+        networks: Vec<String>,
+
+        /// Static route, e.g. "10.9.0.0/16 via 10.1.0.5" (repeatable)
+        #[arg(long = "route")]
+        routes: Vec<String>,
+
+        /// Firewall rule, e.g. "allow tcp src=10.1.0.0/24 dst=10.2.0.0/24 port=443" (repeatable, first match wins)
+        #[arg(long = "rule")]
+        rules: Vec<String>,
+
+        /// Action for forwarded traffic no rule matches (allow, deny)
+        #[arg(long, default_value = "allow")]
+        default_action: String,
+    },
+
+    /// Replace a router's routes or rules
+    Update {
+        /// Router ID
+        id: String,
+
+        /// Static routes, replacing the current ones
+        #[arg(long = "route")]
+        routes: Vec<String>,
+
+        /// Firewall rules, replacing the current ones
+        #[arg(long = "rule")]
+        rules: Vec<String>,
+
+        /// Remove all static routes
+        #[arg(long, conflicts_with = "routes")]
+        clear_routes: bool,
+
+        /// Remove all firewall rules
+        #[arg(long, conflicts_with = "rules")]
+        clear_rules: bool,
+
+        /// Action for forwarded traffic no rule matches (allow, deny)
+        #[arg(long)]
+        default_action: Option<String>,
+    },
+
+    /// List routers
+    List {
+        /// Only routers connected to this network
+        #[arg(long)]
+        network: Option<String>,
+    },
+
+    /// Get router details
+    Get {
+        /// Router ID
+        id: String,
+    },
+
+    /// Delete a router
+    Delete {
+        /// Router ID
+        id: String,
+    },
 }
 
-/// Network display wrapper for serialization
-#[derive(Serialize)]
-pub struct NetworkDisplay {
-    pub id: String,
-    pub name: String,
-    pub mode: String,
-    pub cidr: String,
-    pub gateway: String,
-    pub active: bool,
+#[derive(Subcommand)]
+pub enum QosCommands {
+    /// Create a QoS profile
+    Create {
+        /// Profile name
+        #[arg(short, long)]
+        name: String,
+
+        /// Added latency in milliseconds
+        #[arg(long, default_value = "0")]
+        latency: u32,
+
+        /// Random extra latency of up to this many milliseconds
+        #[arg(long, default_value = "0")]
+        jitter: u32,
+
+        /// Packet loss percentage (0-100)
+        #[arg(long, default_value = "0")]
+        loss: f32,
+
+        /// Bandwidth limit in Mbps (0 = unlimited)
+        #[arg(long, default_value = "0")]
+        rate: u32,
+
+        /// Burst allowance in KB above the rate limit
+        #[arg(long, default_value = "0")]
+        burst: u32,
+
+        /// Bytes of padding added to each frame
+        #[arg(long, default_value = "0")]
+        padding: u32,
+    },
+
+    /// List QoS profiles
+    List,
+
+    /// Get QoS profile details
+    Get {
+        /// Profile ID
+        id: String,
+    },
+
+    /// Delete a QoS profile
+    Delete {
+        /// Profile ID
+        id: String,
+    },
+
+    /// Shape a VM's network traffic with a profile
+    Attach {
+        /// Profile ID
+        id: String,
+
+        /// VM ID
+        #[arg(long)]
+        vm: String,
+    },
+
+    /// Stop shaping a VM's network traffic
+    Detach {
+        /// VM ID
+        #[arg(long)]
+        vm: String,
+    },
 }
 
-impl From<Network> for NetworkDisplay {
-    fn from(net: Network) -> Self {
-        let meta = net.meta.unwrap_or_default();
-        let spec = net.spec.unwrap_or_default();
-        let status = net.status.unwrap_or_default();
-        
+#[derive(Subcommand)]
+pub enum PortForwardCommands {
+    /// Forward a host port to a guest port
+    Add {
+        /// VM ID
+        #[arg(long)]
+        vm: String,
+
+        /// Host port
+        #[arg(long)]
+        host_port: u16,
+
+        /// Guest port
+        #[arg(long)]
+        guest_port: u16,
+
+        /// Protocol (tcp, udp)
+        #[arg(short, long, default_value = "tcp")]
+        protocol: String,
+
+        /// Host address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        host_addr: String,
+
+        /// Network ID (defaults to the VM's first interface)
+        #[arg(long)]
+        network: Option<String>,
+
+        /// Port forward name
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
+    /// List port forwards
+    List {
+        /// Filter by VM ID
+        #[arg(long)]
+        vm: Option<String>,
+
+        /// Filter by network ID
+        #[arg(long)]
+        network: Option<String>,
+    },
+
+    /// Remove a port forward
+    Remove {
+        /// Port forward ID
+        id: String,
+    },
+}
+
+impl TableDisplay for Network {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Mode", "CIDR", "Gateway", "Active"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
         let mode_str = NetworkMode::try_from(spec.mode)
             .map(|m| format!("{:?}", m))
             .unwrap_or_else(|_| "Unknown".to_string());
-        
-        Self {
-            id: meta.id,
-            name: meta.name,
-            mode: mode_str,
-            cidr: spec.cidr,
-            gateway: spec.gateway,
-            active: status.active,
-        }
+
+        vec![
+            meta.id,
+            meta.name,
+            mode_str,
+            spec.cidr,
+            spec.gateway,
+            status.active.to_string(),
+        ]
     }
 }
 
-impl TableDisplay for NetworkDisplay {
+impl TableDisplay for NetworkCapture {
     fn headers() -> Vec<&'static str> {
-        vec!["ID", "Name", "Mode", "CIDR", "Gateway", "Active"]
+        vec!["ID", "State", "Method", "Filter", "Size", "Limits", "Stop Reason"]
     }
 
     fn row(&self) -> Vec<String> {
+        let state = match CaptureState::try_from(self.state) {
+            Ok(CaptureState::Running) => "running",
+            Ok(CaptureState::Stopped) => "stopped",
+            Ok(CaptureState::Failed) => "failed",
+            _ => "unknown",
+        };
+
         vec![
             self.id.clone(),
-            self.name.clone(),
-            self.mode.clone(),
-            self.cidr.clone(),
-            self.gateway.clone(),
-            self.active.to_string(),
+            state.to_string(),
+            self.method.clone(),
+            self.filter.clone(),
+            format_size(self.bytes),
+            format!("{}, {}s", format_size(self.max_bytes), self.max_seconds),
+            self.stop_reason.clone(),
+        ]
+    }
+}
+
+fn format_size(size: u64) -> String {
+    if size > 1024 * 1024 * 1024 {
+        format!("{:.1}GB", size as f64 / 1024.0 / 1024.0 / 1024.0)
+    } else if size > 1024 * 1024 {
+        format!("{:.1}MB", size as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{}B", size)
+    }
+}
+
+impl TableDisplay for PortForward {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "VM", "Protocol", "Host", "Guest Port", "Active", "Error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        let protocol = match PortProtocol::try_from(spec.protocol) {
+            Ok(PortProtocol::Udp) => "udp",
+            _ => "tcp",
+        };
+
+        vec![
+            meta.id,
+            meta.name,
+            spec.vm_id,
+            protocol.to_string(),
+            format!("{}:{}", spec.host_addr, spec.host_port),
+            spec.guest_port.to_string(),
+            status.active.to_string(),
+            status.error_message,
+        ]
+    }
+}
+
+impl TableDisplay for Router {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Networks", "Routes", "Rules", "Default", "Active", "Error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        vec![
+            meta.id,
+            meta.name,
+            spec.network_ids.join(","),
+            spec.routes.len().to_string(),
+            spec.rules.len().to_string(),
+            action_name(spec.default_action).to_string(),
+            status.active.to_string(),
+            status.error_message,
+        ]
+    }
+}
+
+impl TableDisplay for QoSProfile {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Latency", "Jitter", "Loss", "Rate"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+
+        vec![
+            meta.id,
+            meta.name,
+            format!("{}ms", spec.latency_ms),
+            format!("{}ms", spec.jitter_ms),
+            format!("{}%", spec.loss_percent),
+            if spec.rate_limit_mbps > 0 {
+                format!("{} Mbps", spec.rate_limit_mbps)
+            } else {
+                "unlimited".to_string()
+            },
+        ]
+    }
+}
+
+impl TableDisplay for ChaosProfile {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Target", "Latency", "Loss", "Rate", "Partitions", "Applied", "Pending", "Expires"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        let target = match (spec.network_id.is_empty(), spec.vm_id.is_empty()) {
+            (false, false) => format!("vm {} on {}", spec.vm_id, spec.network_id),
+            (false, true) => format!("network {}", spec.network_id),
+            _ => format!("vm {}", spec.vm_id),
+        };
+        let distribution = match LatencyDistribution::try_from(spec.distribution) {
+            Ok(LatencyDistribution::Normal) => "normal",
+            Ok(LatencyDistribution::Pareto) => "pareto",
+            _ => "uniform",
+        };
+
+        vec![
+            meta.id,
+            meta.name,
+            target,
+            format!("{}ms ±{}ms {}", spec.latency_ms, spec.jitter_ms, distribution),
+            format!("{}%", spec.loss_percent),
+            if spec.rate_limit_mbps > 0 {
+                format!("{} Mbps", spec.rate_limit_mbps)
+            } else {
+                "unlimited".to_string()
+            },
+            spec.partitions
+                .iter()
+                .map(|p| p.vm_ids.join(","))
+                .collect::<Vec<_>>()
+                .join(" | "),
+            status.applied_vm_ids.len().to_string(),
+            status.pending_vm_ids.len().to_string(),
+            if spec.expires_at > 0 {
+                chrono::DateTime::from_timestamp(spec.expires_at, 0)
+                    .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default()
+            } else {
+                "never".to_string()
+            },
         ]
     }
 }
 
 pub async fn execute(cmd: NetworkCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
     match cmd {
-        NetworkCommands::List => {
-            let networks = client.list_networks().await?;
-            let displays: Vec<NetworkDisplay> = networks.into_iter().map(NetworkDisplay::from).collect();
-            print_list(&displays, format);
+        NetworkCommands::List { watch } => {
+            let mut watch = Watch::start(&mut client, watch, "network", None, format).await?;
+            loop {
+                let networks = client.list_networks().await?;
+                watch.frame();
+                print_list(&networks, format);
+                if !watch.changed().await? {
+                    break;
+                }
+            }
         }
 
-        NetworkCommands::Get { id } => {
-            let net = client.get_network(&id).await?;
-            let display = NetworkDisplay::from(net);
-            print_item(&display, format);
+        NetworkCommands::Get { id, watch, output_diff } => {
+            let mut net = client.get_network(&id).await?;
+            let net_id = net.meta.clone().unwrap_or_default().id;
+            let mut watch = Watch::start(&mut client, watch, "network", Some(&net_id), format).await?;
+            loop {
+                watch.frame();
+                if output_diff {
+                    watch.print_diff("network", &net_id, &net)?;
+                } else {
+                    print_item(&net, format);
+                }
+                if !watch.changed().await? {
+                    break;
+                }
+                net = client.get_network(&net_id).await?;
+            }
         }
 
         NetworkCommands::Create {
@@ -145,17 +646,423 @@ pub async fn execute(cmd: NetworkCommands, mut client: DaemonClient, format: Out
                 mtu,
             };
 
-            let net = client.create_network(&name, spec).await?;
-            let display = NetworkDisplay::from(net);
-            print_success(&format!("Network '{}' created", display.name));
-            print_item(&display, format);
+            let net = client.create_network(&name, spec, Default::default()).await?;
+            print_success(&format!("Network '{}' created", name));
+            print_item(&net, format);
         }
 
         NetworkCommands::Delete { id } => {
             client.delete_network(&id).await?;
             print_success(&format!("Network '{}' deleted", id));
         }
+
+        NetworkCommands::PortForward(cmd) => execute_port_forward(cmd, client, format).await?,
+        NetworkCommands::Qos(cmd) => execute_qos(cmd, client, format).await?,
+        NetworkCommands::Router(cmd) => execute_router(cmd, client, format).await?,
+        NetworkCommands::Capture(cmd) => execute_capture(cmd, client, format).await?,
+        NetworkCommands::Chaos(cmd) => execute_chaos(cmd, client, format).await?,
     }
 
     Ok(())
 }
+
+async fn execute_capture(cmd: CaptureCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        CaptureCommands::Start {
+            network,
+            output,
+            filter,
+            max_size,
+            duration,
+        } => {
+            let max_bytes = max_size.map_or(0, |mib| mib * 1024 * 1024);
+            let capture = client
+                .start_network_capture(&network, filter, max_bytes, duration.unwrap_or(0))
+                .await?;
+            print_success(&format!("Capturing network '{}' with {}", network, capture.method));
+
+            let Some(output) = output else {
+                print_item(&capture, format);
+                return Ok(());
+            };
+            let capture = wait_for_capture(&mut client, &network).await?;
+            print_item(&capture, format);
+            download_capture(&mut client, &network, &output).await?;
+        }
+
+        CaptureCommands::Stop { network, output } => {
+            let capture = client.stop_network_capture(&network).await?;
+            print_success(&format!("Capture of network '{}' stopped", network));
+            print_item(&capture, format);
+            if let Some(output) = output {
+                download_capture(&mut client, &network, &output).await?;
+            }
+        }
+
+        CaptureCommands::Status { network } => {
+            let status = client.get_network(&network).await?.status.unwrap_or_default();
+            let capture = status
+                .capture
+                .ok_or_else(|| anyhow::anyhow!("Network '{}' has no capture", network))?;
+            print_item(&capture, format);
+        }
+
+        CaptureCommands::Download { network, output } => {
+            download_capture(&mut client, &network, &output).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Wait for a running capture to stop on a limit, stopping it on Ctrl-C
+async fn wait_for_capture(client: &mut DaemonClient, network_id: &str) -> Result<NetworkCapture> {
+    println!("Press Ctrl-C to stop the capture");
+    let mut interrupted = std::pin::pin!(tokio::signal::ctrl_c());
+    loop {
+        tokio::select! {
+            _ = &mut interrupted => {
+                return client.stop_network_capture(network_id).await;
+            }
+            _ = tokio::time::sleep(Duration::from_secs(1)) => {
+                let status = client.get_network(network_id).await?.status.unwrap_or_default();
+                match status.capture {
+                    Some(capture) if capture.state == CaptureState::Running as i32 => {}
+                    Some(capture) => return Ok(capture),
+                    None => anyhow::bail!("Capture of network '{}' disappeared", network_id),
+                }
+            }
+        }
+    }
+}
+
+async fn download_capture(client: &mut DaemonClient, network_id: &str, output: &Path) -> Result<()> {
+    let mut stream = client.read_network_capture(network_id).await?;
+    let mut file = tokio::fs::File::create(output)
+        .await
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut bytes = 0u64;
+    while let Some(chunk) = stream.message().await? {
+        file.write_all(&chunk.data).await?;
+        bytes += chunk.data.len() as u64;
+    }
+    file.flush().await?;
+    print_success(&format!("Wrote {} ({})", output.display(), format_size(bytes)));
+    Ok(())
+}
+
+async fn execute_router(cmd: RouterCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        RouterCommands::Create {
+            name,
+            networks,
+            routes,
+            rules,
+            default_action,
+        } => {
+            let spec = RouterSpec {
+                network_ids: networks,
+                routes: routes.iter().map(|r| parse_route(r)).collect::<Result<_>>()?,
+                rules: rules.iter().map(|r| parse_rule(r)).collect::<Result<_>>()?,
+                default_action: parse_action(&default_action)? as i32,
+            };
+
+            let router = client.create_router(&name, spec).await?;
+            print_success(&format!("Router '{}' created", name));
+            print_item(&router, format);
+        }
+
+        RouterCommands::Update {
+            id,
+            routes,
+            rules,
+            clear_routes,
+            clear_rules,
+            default_action,
+        } => {
+            let mut spec = client.get_router(&id).await?.spec.unwrap_or_default();
+            if clear_routes || !routes.is_empty() {
+                spec.routes = routes.iter().map(|r| parse_route(r)).collect::<Result<_>>()?;
+            }
+            if clear_rules || !rules.is_empty() {
+                spec.rules = rules.iter().map(|r| parse_rule(r)).collect::<Result<_>>()?;
+            }
+            if let Some(action) = default_action {
+                spec.default_action = parse_action(&action)? as i32;
+            }
+
+            let router = client.update_router(&id, spec).await?;
+            print_success(&format!("Router '{}' updated", id));
+            print_item(&router, format);
+        }
+
+        RouterCommands::List { network } => {
+            let routers = client.list_routers(network).await?;
+            print_list(&routers, format);
+        }
+
+        RouterCommands::Get { id } => {
+            let router = client.get_router(&id).await?;
+            print_item(&router, format);
+        }
+
+        RouterCommands::Delete { id } => {
+            client.delete_router(&id).await?;
+            print_success(&format!("Router '{}' deleted", id));
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_action(action: &str) -> Result<FirewallAction> {
+    match action.to_lowercase().as_str() {
+        "allow" | "accept" => Ok(FirewallAction::Allow),
+        "deny" | "drop" => Ok(FirewallAction::Deny),
+        other => anyhow::bail!("Unknown action '{}' (expected allow or deny)", other),
+    }
+}
+
+fn action_name(action: i32) -> &'static str {
+    match FirewallAction::try_from(action) {
+        Ok(FirewallAction::Deny) => "deny",
+        _ => "allow",
+    }
+}
+
+/// Parse "DEST via NEXT_HOP"
+fn parse_route(route: &str) -> Result<StaticRoute> {
+    match route.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [destination, "via", next_hop] => Ok(StaticRoute {
+            destination: destination.to_string(),
+            next_hop: next_hop.to_string(),
+        }),
+        _ => anyhow::bail!("Invalid route '{}' (expected \"DEST via NEXT_HOP\")", route),
+    }
+}
+
+/// Parse "ACTION [PROTOCOL] [src=CIDR] [dst=CIDR] [port=PORT]"
+fn parse_rule(rule: &str) -> Result<RouterRule> {
+    let mut words = rule.split_whitespace();
+    let action = words
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Empty firewall rule"))?;
+    let mut parsed = RouterRule {
+        action: parse_action(action)? as i32,
+        ..Default::default()
+    };
+
+    for word in words {
+        match word.split_once('=') {
+            Some(("src", cidr)) => parsed.source = cidr.to_string(),
+            Some(("dst", cidr)) => parsed.destination = cidr.to_string(),
+            Some(("port", port)) => {
+                parsed.port = port
+                    .parse::<u16>()
+                    .map_err(|_| anyhow::anyhow!("Invalid port '{}' in rule '{}'", port, rule))?
+                    as u32;
+            }
+            None => {
+                parsed.protocol = match word.to_lowercase().as_str() {
+                    "any" => RuleProtocol::Any,
+                    "tcp" => RuleProtocol::Tcp,
+                    "udp" => RuleProtocol::Udp,
+                    "icmp" => RuleProtocol::Icmp,
+                    other => anyhow::bail!("Unknown protocol '{}' in rule '{}'", other, rule),
+                } as i32;
+            }
+            Some((key, _)) => anyhow::bail!("Unknown field '{}' in rule '{}' (expected src, dst or port)", key, rule),
+        }
+    }
+
+    Ok(parsed)
+}
+
+async fn execute_port_forward(cmd: PortForwardCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        PortForwardCommands::Add {
+            vm,
+            host_port,
+            guest_port,
+            protocol,
+            host_addr,
+            network,
+            name,
+        } => {
+            let protocol_enum = match protocol.to_lowercase().as_str() {
+                "tcp" => PortProtocol::Tcp,
+                "udp" => PortProtocol::Udp,
+                other => anyhow::bail!("Unknown protocol '{}' (expected tcp or udp)", other),
+            };
+
+            let spec = PortForwardSpec {
+                vm_id: vm,
+                network_id: network.unwrap_or_default(),
+                protocol: protocol_enum as i32,
+                host_addr,
+                host_port: host_port as u32,
+                guest_port: guest_port as u32,
+            };
+
+            let pf = client
+                .create_port_forward(&name.unwrap_or_default(), spec, Default::default())
+                .await?;
+            let spec = pf.spec.clone().unwrap_or_default();
+            print_success(&format!(
+                "Forwarding {}:{} -> guest port {}",
+                spec.host_addr, spec.host_port, spec.guest_port
+            ));
+            print_item(&pf, format);
+        }
+
+        PortForwardCommands::List { vm, network } => {
+            let forwards = client.list_port_forwards(vm, network).await?;
+            print_list(&forwards, format);
+        }
+
+        PortForwardCommands::Remove { id } => {
+            client.delete_port_forward(&id).await?;
+            print_success(&format!("Port forward '{}' removed", id));
+        }
+    }
+
+    Ok(())
+}
+
+async fn execute_qos(cmd: QosCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        QosCommands::Create {
+            name,
+            latency,
+            jitter,
+            loss,
+            rate,
+            burst,
+            padding,
+        } => {
+            let to_i32 = |value: u32, flag: &str| {
+                i32::try_from(value).map_err(|_| anyhow::anyhow!("--{} is too large", flag))
+            };
+
+            let spec = QoSProfileSpec {
+                latency_ms: to_i32(latency, "latency")?,
+                jitter_ms: to_i32(jitter, "jitter")?,
+                loss_percent: loss,
+                rate_limit_mbps: to_i32(rate, "rate")?,
+                packet_padding_bytes: to_i32(padding, "padding")?,
+                burst_shaping: burst > 0,
+                burst_size_kb: to_i32(burst, "burst")?,
+            };
+
+            let profile = client.create_qos_profile(&name, spec).await?;
+            print_success(&format!("QoS profile '{}' created", name));
+            print_item(&profile, format);
+        }
+
+        QosCommands::List => {
+            let profiles = client.list_qos_profiles().await?;
+            print_list(&profiles, format);
+        }
+
+        QosCommands::Get { id } => {
+            let profile = client.get_qos_profile(&id).await?;
+            print_item(&profile, format);
+        }
+
+        QosCommands::Delete { id } => {
+            client.delete_qos_profile(&id).await?;
+            print_success(&format!("QoS profile '{}' deleted", id));
+        }
+
+        QosCommands::Attach { id, vm } => {
+            let profile = client.get_qos_profile(&id).await?;
+            set_qos_profile(&mut client, &vm, profile.meta.unwrap_or_default().id).await?;
+            print_success(&format!("QoS profile '{}' attached to VM '{}'", id, vm));
+        }
+
+        QosCommands::Detach { vm } => {
+            set_qos_profile(&mut client, &vm, String::new()).await?;
+            print_success(&format!("QoS profile detached from VM '{}'", vm));
+        }
+    }
+
+    Ok(())
+}
+
+async fn execute_chaos(cmd: ChaosCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        ChaosCommands::Apply {
+            name,
+            network,
+            vm,
+            latency,
+            jitter,
+            distribution,
+            loss,
+            rate,
+            partitions,
+            ttl,
+        } => {
+            let to_i32 = |value: u32, flag: &str| {
+                i32::try_from(value).map_err(|_| anyhow::anyhow!("--{} is too large", flag))
+            };
+            let distribution = match distribution.to_lowercase().as_str() {
+                "uniform" => LatencyDistribution::Uniform,
+                "normal" => LatencyDistribution::Normal,
+                "pareto" => LatencyDistribution::Pareto,
+                other => anyhow::bail!("Unknown distribution '{}' (uniform, normal, pareto)", other),
+            };
+            let ttl = i64::try_from(ttl.unwrap_or(0)).map_err(|_| anyhow::anyhow!("--ttl is too large"))?;
+
+            let spec = ChaosProfileSpec {
+                network_id: network.unwrap_or_default(),
+                vm_id: vm.unwrap_or_default(),
+                latency_ms: to_i32(latency, "latency")?,
+                jitter_ms: to_i32(jitter, "jitter")?,
+                distribution: distribution as i32,
+                loss_percent: loss,
+                rate_limit_mbps: to_i32(rate, "rate")?,
+                partitions: partitions
+                    .iter()
+                    .map(|group| ChaosPartition {
+                        vm_ids: group.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect(),
+                    })
+                    .collect(),
+                expires_at: 0,
+            };
+
+            let profile = client.apply_chaos_profile(&name, spec, ttl).await?;
+            print_success(&format!("Chaos profile '{}' applied", name));
+            print_item(&profile, format);
+        }
+
+        ChaosCommands::List { network, vm } => {
+            let profiles = client.list_chaos_profiles(network.as_deref(), vm.as_deref()).await?;
+            print_list(&profiles, format);
+        }
+
+        ChaosCommands::Get { id } => {
+            let profile = client.get_chaos_profile(&id).await?;
+            print_item(&profile, format);
+        }
+
+        ChaosCommands::Remove { id } => {
+            client.delete_chaos_profile(&id).await?;
+            print_success(&format!("Chaos profile '{}' removed", id));
+        }
+    }
+
+    Ok(())
+}
+
+/// Point a VM at a QoS profile (empty to detach)
+///
+/// Running VMs started with a profile switch immediately; others pick up the
+/// change on their next start.
+async fn set_qos_profile(client: &mut DaemonClient, vm_id: &str, profile_id: String) -> Result<()> {
+    let vm = client.get_vm(vm_id).await?;
+    let mut spec = vm.spec.unwrap_or_default();
+    spec.qos_profile_id = profile_id;
+    client.update_vm(vm_id, spec).await?;
+    Ok(())
+}
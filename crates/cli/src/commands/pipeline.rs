@@ -6,13 +6,15 @@
 //! - Manage artifacts and their attestations
 //! - Integrate with CI/CD systems
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use clap::{Args, Subcommand, ValueEnum};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::collections::HashMap;
 
+use infrasim_common::pipeline::{AdvisoryDb, AnalysisError, PipelineAnalyzer, Severity, OSV_API_URL};
+
 use crate::output::{OutputFormat, TableDisplay, print_item, print_list, print_success, print_error};
 
 // ============================================================================
@@ -213,6 +215,9 @@ pub enum PipelineCommands {
 
     /// Create a new pipeline definition
     Create(CreateArgs),
+
+    /// Check a workspace's dependencies against OSV/RustSec advisories
+    Audit(AuditArgs),
 }
 
 #[derive(Args)]
@@ -378,6 +383,35 @@ pub struct CreateArgs {
     pub template: Option<String>,
 }
 
+#[derive(Args)]
+pub struct AuditArgs {
+    /// Workspace directory or lockfile (Cargo, npm, pnpm, Go, Poetry, pip)
+    #[arg(long, default_value = ".")]
+    pub path: PathBuf,
+
+    /// Offline advisory database: a directory or zip of OSV JSON files
+    /// (default: query the OSV API)
+    #[arg(long)]
+    pub advisory_db: Option<PathBuf>,
+
+    /// OSV API base URL
+    #[arg(long, default_value = OSV_API_URL)]
+    pub osv_url: String,
+
+    /// Exit non-zero on advisories at or above a severity, e.g. "severity=high"
+    #[arg(long, value_parser = parse_fail_on)]
+    pub fail_on: Option<Severity>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+fn parse_fail_on(s: &str) -> Result<Severity, String> {
+    let value = s.strip_prefix("severity=").unwrap_or(s);
+    value.parse().map_err(|e: AnalysisError| e.to_string())
+}
+
 // ============================================================================
 // Execution
 // ============================================================================
@@ -396,6 +430,7 @@ pub async fn execute(
         PipelineCommands::Cancel(args) => cancel(args).await,
         PipelineCommands::Retry(args) => retry(args).await,
         PipelineCommands::Create(args) => create(args).await,
+        PipelineCommands::Audit(args) => audit(args).await,
     }
 }
 
@@ -963,3 +998,74 @@ async fn create(args: CreateArgs) -> Result<()> {
 
     Ok(())
 }
+
+async fn audit(args: AuditArgs) -> Result<()> {
+    let path = args.path.clone();
+    let advisory_db = args.advisory_db.clone();
+    let osv_url = args.osv_url.clone();
+    // The OSV client is blocking
+    let report = tokio::task::spawn_blocking(move || -> Result<_> {
+        let mut report = PipelineAnalyzer::new()
+            .analyze_workspace(&path)
+            .with_context(|| format!("Failed to analyze {}", path.display()))?;
+        let db = match advisory_db {
+            Some(db) => AdvisoryDb::load(&db)
+                .with_context(|| format!("Failed to load advisories from {}", db.display()))?,
+            None => AdvisoryDb::fetch(&report.graph, &osv_url)
+                .with_context(|| format!("Failed to query {}", osv_url))?,
+        };
+        report.correlate_advisories(&db);
+        Ok(report)
+    })
+    .await??;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report.advisories)?);
+    } else {
+        println!("{}", "━".repeat(60).dimmed());
+        println!("{}", " Dependency Audit".bold());
+        println!("{}", "━".repeat(60).dimmed());
+        println!();
+
+        for finding in &report.advisories {
+            let severity = match finding.severity {
+                Severity::Critical | Severity::High => finding.severity.to_string().red().bold(),
+                Severity::Medium => finding.severity.to_string().yellow(),
+                _ => finding.severity.to_string().dimmed(),
+            };
+            println!(
+                "  {} {} {}@{}",
+                severity,
+                finding.id.cyan(),
+                finding.package,
+                finding.version
+            );
+            println!("      {}", finding.summary);
+            if !finding.fixed_versions.is_empty() {
+                println!("      Fixed in: {}", finding.fixed_versions.join(", ").green());
+            }
+        }
+        if !report.advisories.is_empty() {
+            println!();
+        }
+        println!(
+            "  {} advisories across {} packages, risk score {:.1}",
+            report.advisories.len(),
+            report.graph.nodes.len(),
+            report.risk_score
+        );
+    }
+
+    if let Some(threshold) = args.fail_on {
+        let failing = report
+            .advisories
+            .iter()
+            .filter(|a| a.severity >= threshold)
+            .count();
+        if failing > 0 {
+            bail!("{} advisories at or above severity {}", failing, threshold);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,280 @@
+//! Scenario Commands
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+
+use crate::client::DaemonClient;
+use crate::generated::{
+    EventType, ScenarioRun, ScenarioRunState, ScenarioStepComparison, ScenarioStepOutcome, ScenarioStepReport,
+};
+use crate::output::{OutputFormat, TableDisplay, print_info, print_item, print_list, print_success, print_warning};
+
+#[derive(Subcommand)]
+pub enum ScenarioCommands {
+    /// Run a scenario file of timed steps on the daemon
+    Run {
+        /// Scenario YAML, or `-` for stdin
+        file: PathBuf,
+
+        /// Print each step as it finishes and wait for the run to end
+        #[arg(long)]
+        follow: bool,
+    },
+
+    /// List scenario runs, newest first
+    List {
+        /// Only runs of the scenario with this name
+        #[arg(long)]
+        scenario: Option<String>,
+    },
+
+    /// Show a run and the report of each step
+    Get {
+        /// Run ID
+        id: String,
+    },
+
+    /// Stop a running scenario
+    Cancel {
+        /// Run ID
+        id: String,
+    },
+
+    /// Delete a finished run and its report
+    Delete {
+        /// Run ID
+        id: String,
+    },
+
+    /// Compare the step outcomes, durations and VM usage of two runs
+    Compare {
+        /// Run to compare against
+        base: String,
+        /// Run to compare
+        other: String,
+    },
+}
+
+fn run_state_name(state: i32) -> &'static str {
+    match ScenarioRunState::try_from(state) {
+        Ok(ScenarioRunState::Pending) => "pending",
+        Ok(ScenarioRunState::Running) => "running",
+        Ok(ScenarioRunState::Passed) => "passed",
+        Ok(ScenarioRunState::Failed) => "failed",
+        Ok(ScenarioRunState::Cancelled) => "cancelled",
+        _ => "",
+    }
+}
+
+fn outcome_name(outcome: i32) -> &'static str {
+    match ScenarioStepOutcome::try_from(outcome) {
+        Ok(ScenarioStepOutcome::Pending) => "pending",
+        Ok(ScenarioStepOutcome::Running) => "running",
+        Ok(ScenarioStepOutcome::Passed) => "passed",
+        Ok(ScenarioStepOutcome::Failed) => "failed",
+        Ok(ScenarioStepOutcome::Skipped) => "skipped",
+        _ => "",
+    }
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
+}
+
+fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .filter(|_| ts > 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+impl TableDisplay for ScenarioRun {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Scenario", "State", "Steps", "Started", "Duration", "Error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let passed = self
+            .steps
+            .iter()
+            .filter(|s| s.outcome == ScenarioStepOutcome::Passed as i32)
+            .count();
+        let duration = if self.started_at > 0 && self.finished_at >= self.started_at {
+            format_ms((self.finished_at - self.started_at) as u64 * 1000)
+        } else {
+            String::new()
+        };
+
+        vec![
+            meta.id,
+            meta.name,
+            self.scenario_name.clone(),
+            run_state_name(self.state).to_string(),
+            format!("{}/{} passed", passed, self.steps.len()),
+            format_timestamp(self.started_at),
+            duration,
+            self.error.clone(),
+        ]
+    }
+}
+
+impl TableDisplay for ScenarioStepReport {
+    fn headers() -> Vec<&'static str> {
+        vec!["Step", "Action", "Outcome", "At", "Duration", "VM Usage", "Detail"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let started = matches!(
+            ScenarioStepOutcome::try_from(self.outcome),
+            Ok(ScenarioStepOutcome::Running | ScenarioStepOutcome::Passed | ScenarioStepOutcome::Failed)
+        );
+        let usage = self
+            .stats
+            .iter()
+            .map(|s| format!("{} {:.0}% {} MiB", s.vm, s.cpu_percent, s.rss_bytes / (1024 * 1024)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        vec![
+            self.name.clone(),
+            self.action.clone(),
+            outcome_name(self.outcome).to_string(),
+            if started { format_ms(self.started_ms) } else { String::new() },
+            if started { format_ms(self.duration_ms) } else { String::new() },
+            usage,
+            self.detail.clone(),
+        ]
+    }
+}
+
+impl TableDisplay for ScenarioStepComparison {
+    fn headers() -> Vec<&'static str> {
+        vec!["Step", "Base", "Other", "Base Duration", "Other Duration", "Changes"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            if self.base_outcome.is_empty() { "-".to_string() } else { self.base_outcome.clone() },
+            if self.other_outcome.is_empty() { "-".to_string() } else { self.other_outcome.clone() },
+            format_ms(self.base_duration_ms),
+            format_ms(self.other_duration_ms),
+            self.changes.join("; "),
+        ]
+    }
+}
+
+pub async fn execute(cmd: ScenarioCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        ScenarioCommands::Run { file, follow } => {
+            let definition = if file.as_os_str() == "-" {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut input)
+                    .context("Failed to read scenario from stdin")?;
+                input
+            } else {
+                std::fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))?
+            };
+
+            let run = client.run_scenario(definition, HashMap::new()).await?;
+            let meta = run.meta.clone().unwrap_or_default();
+            print_success(&format!("Scenario run '{}' started", meta.name));
+            if follow {
+                let run = watch(&mut client, &meta.id).await?;
+                print_list(&run.steps, format);
+                finish(&run)?;
+            } else {
+                print_info(&format!("Follow it with: infrasim scenario get {}", meta.id));
+                print_item(&run, format);
+            }
+        }
+
+        ScenarioCommands::List { scenario } => {
+            let runs = client.list_scenario_runs(scenario.as_deref()).await?;
+            print_list(&runs, format);
+        }
+
+        ScenarioCommands::Get { id } => {
+            let run = client.get_scenario_run(&id).await?;
+            print_item(&run, format);
+            if matches!(format, OutputFormat::Table | OutputFormat::Plain) {
+                print_list(&run.steps, format);
+            }
+        }
+
+        ScenarioCommands::Cancel { id } => {
+            let run = client.cancel_scenario_run(&id).await?;
+            let meta = run.meta.unwrap_or_default();
+            print_success(&format!("Scenario run '{}' is being cancelled", meta.name));
+        }
+
+        ScenarioCommands::Delete { id } => {
+            client.delete_scenario_run(&id).await?;
+            print_success(&format!("Scenario run '{}' deleted", id));
+        }
+
+        ScenarioCommands::Compare { base, other } => {
+            let comparison = client.compare_scenario_runs(&base, &other).await?;
+            if !comparison.same_definition {
+                print_warning("The runs executed different scenario definitions");
+            }
+            print_list(&comparison.steps, format);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print steps as they finish until the run ends
+async fn watch(client: &mut DaemonClient, id: &str) -> Result<ScenarioRun> {
+    // Subscribe before reading the run so no update in between is missed
+    let mut events = client
+        .watch_events("scenario_run".to_string(), id.to_string(), vec![EventType::Updated], 0, true)
+        .await?;
+    let mut run = client.get_scenario_run(id).await?;
+    let mut reported = vec![false; run.steps.len()];
+
+    loop {
+        for (step, reported) in run.steps.iter().zip(reported.iter_mut()) {
+            let outcome = ScenarioStepOutcome::try_from(step.outcome);
+            if *reported || matches!(outcome, Ok(ScenarioStepOutcome::Pending | ScenarioStepOutcome::Running)) {
+                continue;
+            }
+            *reported = true;
+            print_info(&format!("{}: {} {}", step.name, outcome_name(step.outcome), step.detail));
+        }
+
+        if !matches!(
+            ScenarioRunState::try_from(run.state),
+            Ok(ScenarioRunState::Pending | ScenarioRunState::Running)
+        ) {
+            return Ok(run);
+        }
+        if events.message().await?.is_none() {
+            anyhow::bail!("Event stream ended before the scenario run finished");
+        }
+        run = client.get_scenario_run(id).await?;
+    }
+}
+
+/// Error unless the run passed
+fn finish(run: &ScenarioRun) -> Result<()> {
+    match ScenarioRunState::try_from(run.state) {
+        Ok(ScenarioRunState::Passed) => {
+            print_success("Scenario passed");
+            Ok(())
+        }
+        Ok(ScenarioRunState::Cancelled) => anyhow::bail!("Scenario run was cancelled"),
+        _ => anyhow::bail!("Scenario failed: {}", run.error),
+    }
+}
@@ -0,0 +1,118 @@
+//! Secret Commands
+
+use clap::Subcommand;
+use anyhow::Result;
+use std::io::{IsTerminal, Read, Write};
+
+use crate::client::DaemonClient;
+use crate::output::{OutputFormat, TableDisplay, print_list, print_success};
+use crate::generated::Secret;
+
+#[derive(Subcommand)]
+pub enum SecretCommands {
+    /// Store a secret, read from a file or standard input
+    Create {
+        /// Secret name, referenced as ${secret:<name>} in appliance env vars
+        name: String,
+
+        /// Read the value from this file instead of standard input
+        #[arg(long)]
+        from_file: Option<String>,
+
+        /// Replace the value of an existing secret
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Print a secret's value
+    Get {
+        /// Secret name
+        name: String,
+    },
+
+    /// List secrets, without their values
+    List,
+
+    /// Delete a secret
+    Delete {
+        /// Secret name
+        name: String,
+    },
+}
+
+impl TableDisplay for Secret {
+    fn headers() -> Vec<&'static str> {
+        vec!["Name", "Created", "Updated"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let time = |ts: i64| {
+            chrono::DateTime::from_timestamp(ts, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default()
+        };
+        vec![self.name.clone(), time(self.created_at), time(self.updated_at)]
+    }
+}
+
+/// Value of a new secret, without trailing newlines
+///
+/// Values are never taken from the command line, where they would end up in
+/// shell history and process listings.
+fn read_value(name: &str, from_file: Option<String>) -> Result<String> {
+    let value = match from_file {
+        Some(path) => std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?,
+        None => {
+            let stdin = std::io::stdin();
+            if stdin.is_terminal() {
+                eprint!("Value for secret {}: ", name);
+                std::io::stderr().flush()?;
+                let mut line = String::new();
+                stdin.read_line(&mut line)?;
+                line
+            } else {
+                let mut value = String::new();
+                stdin.lock().read_to_string(&mut value)?;
+                value
+            }
+        }
+    };
+    let value = value.trim_end_matches(['\r', '\n']).to_string();
+    if value.is_empty() {
+        anyhow::bail!("Secret value is empty");
+    }
+    Ok(value)
+}
+
+pub async fn execute(cmd: SecretCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        SecretCommands::Create { name, from_file, force } => {
+            let value = read_value(&name, from_file)?;
+            let secret = client.create_secret(&name, &value, force).await?;
+            print_success(&format!("Secret '{}' stored", secret.name));
+        }
+
+        SecretCommands::Get { name } => {
+            let response = client.get_secret(&name).await?;
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&response)?),
+                OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&response)?),
+                // Bare value, for $(infrasim secret get <name>)
+                _ => println!("{}", response.value),
+            }
+        }
+
+        SecretCommands::List => {
+            let secrets = client.list_secrets().await?;
+            print_list(&secrets, format);
+        }
+
+        SecretCommands::Delete { name } => {
+            client.delete_secret(&name).await?;
+            print_success(&format!("Secret '{}' deleted", name));
+        }
+    }
+
+    Ok(())
+}
@@ -2,11 +2,12 @@
 
 use clap::Subcommand;
 use anyhow::Result;
-use serde::Serialize;
+use std::collections::HashMap;
 
 use crate::client::DaemonClient;
-use crate::output::{OutputFormat, TableDisplay, print_item, print_list, print_success};
-use crate::generated::{Snapshot, SnapshotSpec};
+use crate::output::{OutputFormat, TableDisplay, print_info, print_item, print_list, print_success};
+use crate::generated::{Snapshot, SnapshotPolicy, SnapshotPolicySpec, SnapshotSpec};
+use infrasim_common::types::{format_interval, format_selector, parse_interval, parse_selector};
 
 #[derive(Subcommand)]
 pub enum SnapshotCommands {
@@ -53,56 +54,147 @@ pub enum SnapshotCommands {
         #[arg(long)]
         target_vm: Option<String>,
     },
+
+    /// Revert a VM's disk to a snapshot (running VMs are restarted)
+    Revert {
+        /// Snapshot ID
+        snapshot_id: String,
+    },
+
+    /// Show snapshots as a parent/child tree
+    Tree {
+        /// Filter by VM ID
+        #[arg(long)]
+        vm_id: Option<String>,
+    },
+
+    /// Create a new VM whose disk forks from a snapshot
+    Branch {
+        /// Snapshot ID
+        snapshot_id: String,
+
+        /// Name of the new VM
+        #[arg(short, long)]
+        name: String,
+    },
+
+    /// Manage scheduled snapshot policies
+    #[command(subcommand)]
+    Schedule(ScheduleCommands),
 }
 
-/// Snapshot display wrapper for serialization
-#[derive(Serialize)]
-pub struct SnapshotDisplay {
-    pub id: String,
-    pub name: String,
-    pub vm_id: String,
-    pub size: i64,
-    pub created_at: String,
+#[derive(Subcommand)]
+pub enum ScheduleCommands {
+    /// Snapshot a VM, or every VM matching a label selector, on a fixed interval,
+    /// keeping the newest N snapshots of each
+    Add {
+        /// VM ID
+        #[arg(long, required_unless_present = "selector", conflicts_with = "selector")]
+        vm_id: Option<String>,
+
+        /// Snapshot every VM with these labels at each run (e.g. env=ci)
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
+
+        /// Interval between snapshots (e.g. 30m, 6h, 1d)
+        #[arg(long, value_parser = parse_interval)]
+        every: u64,
+
+        /// Number of scheduled snapshots to keep
+        #[arg(long, default_value = "10")]
+        keep: u32,
+
+        /// Policy name
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Also capture memory state while the VM is running
+        #[arg(long)]
+        include_memory: bool,
+    },
+
+    /// List snapshot policies
+    List {
+        /// Only policies covering this VM, including selector policies that match it
+        #[arg(long)]
+        vm_id: Option<String>,
+    },
+
+    /// Remove a snapshot policy (snapshots it took are kept)
+    Remove {
+        /// Policy ID
+        id: String,
+    },
 }
 
-impl From<Snapshot> for SnapshotDisplay {
-    fn from(snap: Snapshot) -> Self {
-        let meta = snap.meta.unwrap_or_default();
-        let spec = snap.spec.unwrap_or_default();
-        let status = snap.status.unwrap_or_default();
-        
-        Self {
-            id: meta.id,
-            name: meta.name,
-            vm_id: spec.vm_id,
-            size: status.size_bytes,
-            created_at: chrono::DateTime::from_timestamp(meta.created_at, 0)
-                .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-                .unwrap_or_default(),
-        }
+/// Format a unix timestamp for tables, empty when unset
+fn format_timestamp(ts: i64) -> String {
+    if ts == 0 {
+        return String::new();
     }
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
 }
 
-impl TableDisplay for SnapshotDisplay {
+impl TableDisplay for Snapshot {
     fn headers() -> Vec<&'static str> {
-        vec!["ID", "Name", "VM ID", "Size", "Created"]
+        vec!["ID", "Name", "VM ID", "Parent", "Size", "Created"]
     }
 
     fn row(&self) -> Vec<String> {
-        let size_str = if self.size > 1024 * 1024 * 1024 {
-            format!("{:.1}GB", self.size as f64 / 1024.0 / 1024.0 / 1024.0)
-        } else if self.size > 1024 * 1024 {
-            format!("{:.1}MB", self.size as f64 / 1024.0 / 1024.0)
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        let size = status.size_bytes;
+        let size_str = if size > 1024 * 1024 * 1024 {
+            format!("{:.1}GB", size as f64 / 1024.0 / 1024.0 / 1024.0)
+        } else if size > 1024 * 1024 {
+            format!("{:.1}MB", size as f64 / 1024.0 / 1024.0)
         } else {
-            format!("{}B", self.size)
+            format!("{}B", size)
         };
-        
+
         vec![
-            self.id.clone(),
-            self.name.clone(),
-            self.vm_id.clone(),
+            meta.id,
+            meta.name,
+            spec.vm_id,
+            status.parent_id,
             size_str,
-            self.created_at.clone(),
+            format_timestamp(meta.created_at),
+        ]
+    }
+}
+
+/// Human-readable schedule of a policy
+fn policy_schedule(policy: &SnapshotPolicy) -> String {
+    let spec = policy.spec.clone().unwrap_or_default();
+    match &policy.status {
+        Some(status) if !status.schedule.is_empty() => status.schedule.clone(),
+        _ => format!("every {}, keep {}", format_interval(spec.interval_seconds as u64), spec.keep),
+    }
+}
+
+impl TableDisplay for SnapshotPolicy {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "VM", "Schedule", "Retained", "Last Run", "Next Run", "Error"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        vec![
+            meta.id,
+            meta.name,
+            if spec.vm_id.is_empty() { format_selector(&spec.vm_selector) } else { spec.vm_id },
+            policy_schedule(self),
+            status.retained.to_string(),
+            format_timestamp(status.last_run_at),
+            if spec.paused { "paused".to_string() } else { format_timestamp(status.next_run_at) },
+            status.last_error,
         ]
     }
 }
@@ -111,14 +203,12 @@ pub async fn execute(cmd: SnapshotCommands, mut client: DaemonClient, format: Ou
     match cmd {
         SnapshotCommands::List { vm_id } => {
             let snapshots = client.list_snapshots(vm_id).await?;
-            let displays: Vec<SnapshotDisplay> = snapshots.into_iter().map(SnapshotDisplay::from).collect();
-            print_list(&displays, format);
+            print_list(&snapshots, format);
         }
 
         SnapshotCommands::Get { id } => {
             let snap = client.get_snapshot(&id).await?;
-            let display = SnapshotDisplay::from(snap);
-            print_item(&display, format);
+            print_item(&snap, format);
         }
 
         SnapshotCommands::Create { vm_id, name, description } => {
@@ -130,9 +220,8 @@ pub async fn execute(cmd: SnapshotCommands, mut client: DaemonClient, format: Ou
             };
 
             let snap = client.create_snapshot(&name, spec).await?;
-            let display = SnapshotDisplay::from(snap);
-            print_success(&format!("Snapshot '{}' created for VM '{}'", display.name, vm_id));
-            print_item(&display, format);
+            print_success(&format!("Snapshot '{}' created for VM '{}'", name, vm_id));
+            print_item(&snap, format);
         }
 
         SnapshotCommands::Delete { id } => {
@@ -145,7 +234,166 @@ pub async fn execute(cmd: SnapshotCommands, mut client: DaemonClient, format: Ou
             let meta = vm.meta.unwrap_or_default();
             print_success(&format!("VM '{}' restored from snapshot '{}'", meta.name, snapshot_id));
         }
+
+        SnapshotCommands::Revert { snapshot_id } => {
+            let vm = client.revert_snapshot(&snapshot_id).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' reverted to snapshot '{}'", meta.name, snapshot_id));
+        }
+
+        SnapshotCommands::Tree { vm_id } => {
+            let snapshots = client.list_snapshots(vm_id).await?;
+
+            match format {
+                OutputFormat::Table | OutputFormat::Plain => {
+                    let current = current_snapshots(&mut client).await?;
+                    print_tree(&snapshots, &current);
+                }
+                _ => print_list(&snapshots, format),
+            }
+        }
+
+        SnapshotCommands::Branch { snapshot_id, name } => {
+            let vm = client.branch_snapshot(&snapshot_id, &name).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' ({}) branched from snapshot '{}'", meta.name, meta.id, snapshot_id));
+            print_info(&format!("Start it with: infrasim vm start {}", meta.id));
+        }
+
+        SnapshotCommands::Schedule(cmd) => execute_schedule(cmd, client, format).await?,
     }
 
     Ok(())
 }
+
+async fn execute_schedule(cmd: ScheduleCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        ScheduleCommands::Add { vm_id, selector, every, keep, name, include_memory } => {
+            let spec = SnapshotPolicySpec {
+                vm_id: vm_id.unwrap_or_default(),
+                vm_selector: selector.unwrap_or_default(),
+                interval_seconds: every as i64,
+                keep,
+                include_memory,
+                paused: false,
+            };
+
+            let policy = client.create_snapshot_policy(&name.unwrap_or_default(), spec, HashMap::new()).await?;
+            let meta = policy.meta.clone().unwrap_or_default();
+            print_success(&format!("Snapshot policy '{}' created ({})", meta.name, policy_schedule(&policy)));
+            print_item(&policy, format);
+        }
+
+        ScheduleCommands::List { vm_id } => {
+            let policies = client.list_snapshot_policies(vm_id).await?;
+            print_list(&policies, format);
+        }
+
+        ScheduleCommands::Remove { id } => {
+            client.delete_snapshot_policy(&id).await?;
+            print_success(&format!("Snapshot policy '{}' removed", id));
+        }
+    }
+
+    Ok(())
+}
+
+/// Map snapshot IDs to the names of VMs whose disk currently builds on them
+async fn current_snapshots(client: &mut DaemonClient) -> Result<HashMap<String, Vec<String>>> {
+    let mut current: HashMap<String, Vec<String>> = HashMap::new();
+    for vm in client.list_vms().await? {
+        let snapshot_id = vm.status.map(|s| s.current_snapshot_id).unwrap_or_default();
+        if !snapshot_id.is_empty() {
+            current
+                .entry(snapshot_id)
+                .or_default()
+                .push(vm.meta.map(|m| m.name).unwrap_or_default());
+        }
+    }
+    Ok(current)
+}
+
+/// Snapshot fields the tree view needs
+struct TreeNode {
+    id: String,
+    name: String,
+    parent_id: String,
+    created_at: i64,
+}
+
+impl From<&Snapshot> for TreeNode {
+    fn from(snap: &Snapshot) -> Self {
+        let meta = snap.meta.clone().unwrap_or_default();
+        Self {
+            id: meta.id,
+            name: meta.name,
+            parent_id: snap.status.clone().unwrap_or_default().parent_id,
+            created_at: meta.created_at,
+        }
+    }
+}
+
+/// Print snapshots as a tree; snapshots whose parent is not listed are roots
+fn print_tree(snapshots: &[Snapshot], current: &HashMap<String, Vec<String>>) {
+    if snapshots.is_empty() {
+        println!("No items found.");
+        return;
+    }
+
+    let nodes: Vec<TreeNode> = snapshots.iter().map(TreeNode::from).collect();
+    let mut children: HashMap<&str, Vec<&TreeNode>> = HashMap::new();
+    let mut roots = Vec::new();
+    for snap in &nodes {
+        if nodes.iter().any(|s| s.id == snap.parent_id) {
+            children.entry(snap.parent_id.as_str()).or_default().push(snap);
+        } else {
+            roots.push(snap);
+        }
+    }
+
+    roots.sort_by_key(|s| s.created_at);
+    for list in children.values_mut() {
+        list.sort_by_key(|s| s.created_at);
+    }
+
+    for root in roots {
+        print_node(root, "", None, &children, current);
+    }
+}
+
+fn print_node(
+    snap: &TreeNode,
+    prefix: &str,
+    last: Option<bool>,
+    children: &HashMap<&str, Vec<&TreeNode>>,
+    current: &HashMap<String, Vec<String>>,
+) {
+    let branch = match last {
+        None => "",
+        Some(true) => "└── ",
+        Some(false) => "├── ",
+    };
+    let marker = current
+        .get(&snap.id)
+        .map(|vms| format!("  <- {}", vms.join(", ")))
+        .unwrap_or_default();
+    println!(
+        "{}{}{} ({}) {}{}",
+        prefix,
+        branch,
+        snap.name,
+        snap.id,
+        format_timestamp(snap.created_at),
+        marker
+    );
+
+    let child_prefix = match last {
+        None => prefix.to_string(),
+        Some(true) => format!("{}    ", prefix),
+        Some(false) => format!("{}│   ", prefix),
+    };
+    let kids = children.get(snap.id.as_str()).map(Vec::as_slice).unwrap_or_default();
+    for (i, child) in kids.iter().enumerate() {
+        print_node(child, &child_prefix, Some(i + 1 == kids.len()), children, current);
+    }
+}
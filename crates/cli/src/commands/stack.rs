@@ -0,0 +1,77 @@
+//! Stack Commands
+
+use clap::Subcommand;
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::client::DaemonClient;
+use crate::output::{OutputFormat, print_list, print_success};
+use crate::stack::{Existing, Plan, StackFile};
+
+#[derive(Subcommand)]
+pub enum StackCommands {
+    /// Show the changes `apply` would make
+    Diff {
+        /// Stack file
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+
+    /// Create, update and delete resources to match a stack file
+    Apply {
+        /// Stack file
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+
+    /// Delete every resource of a stack
+    Destroy {
+        /// Stack file
+        #[arg(short, long, required_unless_present = "name")]
+        file: Option<PathBuf>,
+
+        /// Stack name, instead of a stack file
+        #[arg(long, conflicts_with = "file")]
+        name: Option<String>,
+    },
+}
+
+pub async fn execute(cmd: StackCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    let (file, destroy) = match cmd {
+        StackCommands::Diff { file } => {
+            let plan = plan(&mut client, StackFile::load(&file)?).await?;
+            if plan.is_empty() {
+                print_success(&format!("Stack '{}' is up to date", plan.stack()));
+            } else {
+                print_list(&plan.changes, format);
+            }
+            return Ok(());
+        }
+        StackCommands::Apply { file } => (StackFile::load(&file)?, false),
+        StackCommands::Destroy { file: Some(file), .. } => {
+            (StackFile::empty(&StackFile::load(&file)?.name), true)
+        }
+        StackCommands::Destroy { name, .. } => (StackFile::empty(&name.unwrap_or_default()), true),
+    };
+
+    let plan = plan(&mut client, file).await?;
+    if plan.is_empty() {
+        print_success(&format!("Stack '{}' is up to date", plan.stack()));
+        return Ok(());
+    }
+
+    print_list(&plan.changes, format);
+    plan.apply(&mut client).await?;
+    if destroy {
+        print_success(&format!("Stack '{}' destroyed", plan.stack()));
+    } else {
+        print_success(&format!("Stack '{}' applied", plan.stack()));
+    }
+
+    Ok(())
+}
+
+async fn plan(client: &mut DaemonClient, file: StackFile) -> Result<Plan> {
+    let existing = Existing::fetch(client, &file.name).await?;
+    Plan::diff(file, existing)
+}
@@ -1,22 +1,58 @@
 //! VM Commands
 
-use clap::Subcommand;
-use anyhow::Result;
+use clap::{Subcommand, ValueEnum};
+use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
 use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::client::DaemonClient;
-use crate::output::{OutputFormat, TableDisplay, print_item, print_list, print_success};
-use crate::generated::{Vm, VmSpec, VmState};
+use crate::watch::Watch;
+use crate::output::{
+    OutputFormat, TableDisplay, print_error, print_item, print_list, print_success, print_warning,
+};
+use crate::generated::{
+    ExportFormat, ImportVmOptions, ImportVmRequest, NetworkAttachment, SharedFolder,
+    SharedFolderCache, SharedFolderProtocol, StopMode, Vm, VmInterface, VmLogEntry, VmLogSource,
+    VmSpec, VmState, VmStats,
+};
+use infrasim_common::types::{
+    format_selector, parse_label, parse_selector, SharedFolder as CommonSharedFolder,
+    SharedFolderCache as CommonSharedFolderCache, SharedFolderProtocol as CommonSharedFolderProtocol,
+};
+
+/// Size of each bundle chunk uploaded by `vm import`
+const IMPORT_CHUNK_SIZE: usize = 1024 * 1024;
 
 #[derive(Subcommand)]
 pub enum VmCommands {
     /// List all VMs
-    List,
+    List {
+        /// Only VMs with these labels (e.g. env=ci,tier=web)
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
+
+        /// Print the list again whenever one changes
+        #[arg(short, long)]
+        watch: bool,
+    },
 
     /// Get VM details
     Get {
         /// VM ID
         id: String,
+
+        /// Print the VM again whenever it changes
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Print only what changed since the VM was last shown
+        #[arg(long)]
+        output_diff: bool,
     },
 
     /// Create a new VM
@@ -45,9 +81,10 @@ pub enum VmCommands {
         #[arg(short, long)]
         boot_disk: String,
 
-        /// Network IDs to attach
-        #[arg(long)]
-        network: Vec<String>,
+        /// Network to attach, as ID[,mac=MAC][,ip=IP] to pin the NIC's MAC
+        /// or reserve it a static IP (repeatable)
+        #[arg(long, value_parser = parse_network_attachment)]
+        network: Vec<NetworkAttachment>,
 
         /// Volume IDs to attach
         #[arg(long)]
@@ -64,109 +101,576 @@ pub enum VmCommands {
         /// Compatibility mode (slow raspi emulation)
         #[arg(long)]
         compatibility_mode: bool,
+
+        /// Pause priority under host memory pressure; lower is paused first
+        #[arg(long, default_value = "0", allow_hyphen_values = true)]
+        priority: i32,
+
+        /// Label to set, as key=value (repeatable)
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
     },
 
-    /// Start a VM
+    /// Start a VM, or every VM matching a label selector
     Start {
         /// VM ID
-        id: String,
+        #[arg(required_unless_present = "selector", conflicts_with = "selector")]
+        id: Option<String>,
+
+        /// Start every VM with these labels (e.g. env=ci)
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
     },
 
-    /// Stop a VM
+    /// Stop a VM, or every VM matching a label selector
     Stop {
         /// VM ID
-        id: String,
+        #[arg(required_unless_present = "selector", conflicts_with = "selector")]
+        id: Option<String>,
+
+        /// Stop every VM with these labels (e.g. env=ci)
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
 
-        /// Force stop (SIGKILL)
+        /// How to stop: acpi asks the guest to power off, hard terminates QEMU
+        #[arg(long, value_enum, default_value = "acpi")]
+        mode: StopModeArg,
+
+        /// Seconds the guest gets to power off before QEMU is terminated
+        /// (default: the daemon's stop timeout)
+        #[arg(long)]
+        timeout: Option<u32>,
+
+        /// Same as --mode hard
         #[arg(short, long)]
         force: bool,
     },
 
+    /// Pause a running VM's vCPUs
+    Pause {
+        /// VM ID
+        id: String,
+    },
+
+    /// Resume a paused VM or restore a suspended one
+    Resume {
+        /// VM ID
+        id: String,
+    },
+
+    /// Save a VM's RAM to disk and power it off
+    Suspend {
+        /// VM ID
+        id: String,
+    },
+
+    /// Export a stopped VM's disks and spec as a bundle
+    Export {
+        /// VM ID
+        id: String,
+
+        /// Bundle format (ova, raw, qcow2)
+        #[arg(long, value_enum, default_value = "ova")]
+        bundle_format: BundleFormat,
+
+        /// Output file (defaults to <id>.ova or <id>.tar)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Create a VM from a bundle made by `vm export`
+    Import {
+        /// Bundle file
+        path: PathBuf,
+
+        /// VM name (defaults to the name recorded in the bundle)
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
     /// Delete a VM
     Delete {
         /// VM ID
-        id: String,
+        #[arg(required_unless_present = "selector", conflicts_with = "selector")]
+        id: Option<String>,
+
+        /// Delete every VM with these labels (e.g. env=ci)
+        #[arg(short = 'l', long, value_parser = parse_selector)]
+        selector: Option<HashMap<String, String>>,
 
         /// Force delete (even if running)
         #[arg(short, long)]
         force: bool,
     },
 
+    /// Set or remove VM labels
+    Label {
+        /// VM ID
+        id: String,
+
+        /// Labels to set, as key=value
+        #[arg(value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+
+        /// Label key to remove (repeatable)
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+
+    /// Freeze a stopped VM as an immutable template for linked clones
+    MarkTemplate {
+        /// VM ID
+        id: String,
+    },
+
+    /// Create stopped linked clones of a template
+    Clone {
+        /// Template ID or name
+        template: String,
+
+        /// Clone name; with --count a trailing number is counted up
+        /// (dev-01, dev-02, ...), otherwise -1, -2, ... is appended
+        #[arg(short, long)]
+        name: String,
+
+        /// Number of clones
+        #[arg(short, long, default_value = "1")]
+        count: u32,
+
+        /// Label to add to the template's, as key=value (repeatable)
+        #[arg(long = "label", value_parser = parse_label)]
+        labels: Vec<(String, String)>,
+    },
+
+    /// Attach a raw host block device or partition to a stopped VM
+    AttachDevice {
+        /// Host device path (e.g., /dev/disk4, /dev/sdb1)
+        device: String,
+
+        /// VM ID
+        vm: String,
+
+        /// Volume name (defaults to <vm>-<device>)
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Attach read-only
+        #[arg(long)]
+        read_only: bool,
+
+        /// Attach even if the device is mounted or locked on the host
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Show serial console output for a VM
+    Logs {
+        /// VM ID
+        id: String,
+
+        /// Follow output until interrupted
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Only show the last N lines (0 = all)
+        #[arg(short = 'n', long, default_value = "0")]
+        tail: u32,
+
+        /// Interleave daemon lifecycle events
+        #[arg(short, long)]
+        events: bool,
+    },
+
+    /// Show a running VM's CPU, memory, disk and network usage
+    Stats {
+        /// VM ID
+        id: String,
+
+        /// Keep printing samples until interrupted
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Seconds between samples with --watch
+        #[arg(long, default_value = "2")]
+        interval: u32,
+    },
+
+    /// Send a raw QMP command to a running VM's monitor and print the reply
+    ///
+    /// The daemon only accepts these with `security.allow_qmp_passthrough`
+    /// set, e.g. `infrasim vm qmp web-1 '{"execute":"query-block"}'`.
+    Qmp {
+        /// VM ID
+        id: String,
+
+        /// QMP command as JSON, with `execute` and optional `arguments`
+        command: String,
+    },
+
+    /// Show the MAC and IP address of each of a VM's NICs
+    Ip {
+        /// VM ID
+        id: String,
+
+        /// Only the NIC on this network
+        #[arg(long)]
+        network: Option<String>,
+
+        /// Seconds to wait for the NICs to get an address (0 = don't wait)
+        #[arg(long, default_value = "0")]
+        wait: u64,
+    },
+
     /// Restart a VM
     Restart {
         /// VM ID
         id: String,
 
-        /// Force restart
+        /// How to stop: acpi asks the guest to power off, hard terminates QEMU
+        #[arg(long, value_enum, default_value = "acpi")]
+        mode: StopModeArg,
+
+        /// Seconds the guest gets to power off before QEMU is terminated
+        /// (default: the daemon's stop timeout)
+        #[arg(long)]
+        timeout: Option<u32>,
+
+        /// Same as --mode hard
         #[arg(short, long)]
         force: bool,
     },
+
+    /// Share host folders with a VM (applied when it next starts)
+    #[command(subcommand)]
+    Share(ShareCommands),
 }
 
-/// VM display wrapper for serialization
-#[derive(Serialize)]
-pub struct VmDisplay {
-    pub id: String,
-    pub name: String,
-    pub state: String,
-    pub cpus: i32,
-    pub memory_mb: i64,
-    pub arch: String,
-    pub machine: String,
-}
-
-impl From<Vm> for VmDisplay {
-    fn from(vm: Vm) -> Self {
-        let meta = vm.meta.unwrap_or_default();
-        let spec = vm.spec.unwrap_or_default();
-        let status = vm.status.unwrap_or_default();
-        
-        let state_str = VmState::try_from(status.state)
-            .map(|s| format!("{:?}", s))
-            .unwrap_or_else(|_| "Unknown".to_string());
-        
-        Self {
-            id: meta.id,
-            name: meta.name,
-            state: state_str,
-            cpus: spec.cpu_cores,
-            memory_mb: spec.memory_mb,
-            arch: spec.arch,
-            machine: spec.machine,
+#[derive(Subcommand)]
+pub enum ShareCommands {
+    /// Share a host folder with a VM
+    Add {
+        /// VM ID
+        id: String,
+
+        /// Host directory to share
+        #[arg(long)]
+        host: PathBuf,
+
+        /// Mount point in the guest
+        #[arg(long)]
+        guest: String,
+
+        /// Mount tag in the guest (default: derived from --guest)
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Share the folder read-only
+        #[arg(long)]
+        read_only: bool,
+
+        /// Guest caching of file data and metadata
+        #[arg(long, value_enum, default_value = "auto")]
+        cache: ShareCacheArg,
+
+        /// Transport: virtiofs where the host has virtiofsd, else 9p
+        #[arg(long, value_enum, default_value = "auto")]
+        protocol: ShareProtocolArg,
+    },
+
+    /// Stop sharing a folder
+    Remove {
+        /// VM ID
+        id: String,
+
+        /// Mount tag of the folder
+        tag: String,
+    },
+
+    /// List a VM's shared folders
+    List {
+        /// VM ID
+        id: String,
+    },
+}
+
+/// Guest caching of a shared folder
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ShareCacheArg {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Transport of a shared folder
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ShareProtocolArg {
+    Auto,
+    Virtiofs,
+    #[value(name = "9p")]
+    NineP,
+}
+
+/// How `vm stop` brings a VM down
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StopModeArg {
+    /// ACPI powerdown, escalating to SIGTERM and SIGKILL after the timeout
+    Acpi,
+    /// Terminate QEMU without asking the guest
+    Hard,
+}
+
+impl StopModeArg {
+    fn resolve(self, force: bool) -> StopMode {
+        match self {
+            _ if force => StopMode::Hard,
+            Self::Acpi => StopMode::Acpi,
+            Self::Hard => StopMode::Hard,
         }
     }
 }
 
-impl TableDisplay for VmDisplay {
+/// Export bundle layout
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BundleFormat {
+    /// OVF descriptor with streamOptimized VMDK disks
+    Ova,
+    /// Raw disk images in a tar bundle
+    Raw,
+    /// Compressed qcow2 disk images in a tar bundle
+    Qcow2,
+}
+
+impl BundleFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Ova => "ova",
+            Self::Raw | Self::Qcow2 => "tar",
+        }
+    }
+}
+
+impl From<BundleFormat> for ExportFormat {
+    fn from(format: BundleFormat) -> Self {
+        match format {
+            BundleFormat::Ova => ExportFormat::Ova,
+            BundleFormat::Raw => ExportFormat::Raw,
+            BundleFormat::Qcow2 => ExportFormat::Qcow2,
+        }
+    }
+}
+
+impl TableDisplay for Vm {
     fn headers() -> Vec<&'static str> {
-        vec!["ID", "Name", "State", "CPUs", "Memory", "Arch", "Machine"]
+        vec!["ID", "Name", "State", "CPUs", "Memory", "Arch", "Machine", "Labels"]
     }
 
     fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
+
+        let state_str = if status.template {
+            "Template".to_string()
+        } else {
+            VmState::try_from(status.state)
+                .map(|s| format!("{:?}", s))
+                .unwrap_or_else(|_| "Unknown".to_string())
+        };
+
         vec![
-            self.id.clone(),
-            self.name.clone(),
-            self.state.clone(),
-            self.cpus.to_string(),
-            format!("{}MB", self.memory_mb),
-            self.arch.clone(),
-            self.machine.clone(),
+            meta.id,
+            meta.name,
+            state_str,
+            spec.cpu_cores.to_string(),
+            format!("{}MB", spec.memory_mb),
+            spec.arch,
+            spec.machine,
+            format_selector(&meta.labels),
         ]
     }
 }
 
+impl TableDisplay for VmInterface {
+    fn headers() -> Vec<&'static str> {
+        vec!["NIC", "Network", "MAC", "IP", "Source"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let or_dash = |s: &str| if s.is_empty() { "-".to_string() } else { s.to_string() };
+        vec![
+            format!("net{}", self.index),
+            or_dash(&self.network_id),
+            self.mac_address.clone(),
+            or_dash(&self.ip_address),
+            or_dash(&self.source),
+        ]
+    }
+}
+
+impl TableDisplay for SharedFolder {
+    fn headers() -> Vec<&'static str> {
+        vec!["Tag", "Host", "Guest", "Mode", "Cache", "Protocol"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.tag.clone(),
+            self.host_path.clone(),
+            self.guest_path.clone(),
+            if self.read_only { "ro" } else { "rw" }.to_string(),
+            format!("{:?}", self.cache()).to_lowercase(),
+            match self.protocol() {
+                SharedFolderProtocol::Ninep => "9p".to_string(),
+                protocol => format!("{:?}", protocol).to_lowercase(),
+            },
+        ]
+    }
+}
+
+impl TableDisplay for VmStats {
+    fn headers() -> Vec<&'static str> {
+        vec!["CPU", "Memory", "Disk Read", "Disk Write", "Net RX", "Net TX", "Uptime"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let (rx, tx) = if self.network_counted {
+            (human_bytes(self.net_rx_bytes as f64), human_bytes(self.net_tx_bytes as f64))
+        } else {
+            ("-".to_string(), "-".to_string())
+        };
+
+        vec![
+            format!("{:.1}%", self.cpu_percent),
+            human_bytes(self.rss_bytes as f64),
+            format!("{} ({} ops)", human_bytes(self.disk_read_bytes as f64), self.disk_read_ops),
+            format!("{} ({} ops)", human_bytes(self.disk_write_bytes as f64), self.disk_write_ops),
+            rx,
+            tx,
+            format!("{}s", self.uptime_seconds),
+        ]
+    }
+}
+
+/// Parse `--network ID[,mac=MAC][,ip=IP]`
+fn parse_network_attachment(s: &str) -> Result<NetworkAttachment, String> {
+    let mut parts = s.split(',');
+    let mut attachment = NetworkAttachment {
+        network_id: parts.next().unwrap_or_default().to_string(),
+        ..Default::default()
+    };
+    if attachment.network_id.is_empty() {
+        return Err("expected a network ID".to_string());
+    }
+    for part in parts {
+        match part.split_once('=') {
+            Some(("mac", mac)) => attachment.mac_address = mac.to_string(),
+            Some(("ip", ip)) => attachment.ip_address = ip.to_string(),
+            _ => return Err(format!("unknown option '{}', expected mac=MAC or ip=IP", part)),
+        }
+    }
+    Ok(attachment)
+}
+
+fn human_bytes(bytes: f64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", value)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// One `vm stats --watch` line, with counters turned into per-second rates
+fn stats_line(stats: &VmStats, previous: Option<&VmStats>) -> String {
+    let time = chrono::DateTime::from_timestamp(stats.timestamp, 0)
+        .map(|dt| dt.format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+    let rate = |now: u64, before: Option<u64>| match (previous, before) {
+        (Some(prev), Some(before)) if stats.timestamp > prev.timestamp => {
+            let secs = (stats.timestamp - prev.timestamp) as f64;
+            format!("{}/s", human_bytes(now.saturating_sub(before) as f64 / secs))
+        }
+        _ => "-".to_string(),
+    };
+
+    let mut line = format!(
+        "{}  cpu {:>6.1}%  mem {:>10}  disk r {:>12} w {:>12}",
+        time,
+        stats.cpu_percent,
+        human_bytes(stats.rss_bytes as f64),
+        rate(stats.disk_read_bytes, previous.map(|p| p.disk_read_bytes)),
+        rate(stats.disk_write_bytes, previous.map(|p| p.disk_write_bytes)),
+    );
+    if stats.network_counted {
+        line.push_str(&format!(
+            "  net rx {:>12} tx {:>12}",
+            rate(stats.net_rx_bytes, previous.map(|p| p.net_rx_bytes)),
+            rate(stats.net_tx_bytes, previous.map(|p| p.net_tx_bytes)),
+        ));
+    }
+    line
+}
+
+/// Log line wrapper for serialization
+#[derive(Serialize)]
+pub struct VmLogDisplay {
+    pub timestamp: i64,
+    pub source: String,
+    pub line: String,
+}
+
+impl From<VmLogEntry> for VmLogDisplay {
+    fn from(entry: VmLogEntry) -> Self {
+        let source = match VmLogSource::try_from(entry.source) {
+            Ok(VmLogSource::Event) => "event",
+            _ => "console",
+        };
+
+        Self {
+            timestamp: entry.timestamp,
+            source: source.to_string(),
+            line: entry.line,
+        }
+    }
+}
+
 pub async fn execute(cmd: VmCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
     match cmd {
-        VmCommands::List => {
-            let vms = client.list_vms().await?;
-            let displays: Vec<VmDisplay> = vms.into_iter().map(VmDisplay::from).collect();
-            print_list(&displays, format);
+        VmCommands::List { selector, watch } => {
+            let selector = selector.unwrap_or_default();
+            let mut watch = Watch::start(&mut client, watch, "vm", None, format).await?;
+            loop {
+                let vms = client.list_vms_matching(selector.clone()).await?;
+                watch.frame();
+                print_list(&vms, format);
+                if !watch.changed().await? {
+                    break;
+                }
+            }
         }
 
-        VmCommands::Get { id } => {
-            let vm = client.get_vm(&id).await?;
-            let display = VmDisplay::from(vm);
-            print_item(&display, format);
+        VmCommands::Get { id, watch, output_diff } => {
+            let mut vm = client.get_vm(&id).await?;
+            let vm_id = vm.meta.clone().unwrap_or_default().id;
+            let mut watch = Watch::start(&mut client, watch, "vm", Some(&vm_id), format).await?;
+            loop {
+                watch.frame();
+                if output_diff {
+                    watch.print_diff("vm", &vm_id, &vm)?;
+                } else {
+                    print_item(&vm, format);
+                }
+                if !watch.changed().await? {
+                    break;
+                }
+                vm = client.get_vm(&vm_id).await?;
+            }
         }
 
         VmCommands::Create {
@@ -181,6 +685,8 @@ pub async fn execute(cmd: VmCommands, mut client: DaemonClient, format: OutputFo
             qos_profile,
             enable_tpm,
             compatibility_mode,
+            priority,
+            labels,
         } => {
             let spec = VmSpec {
                 arch,
@@ -188,44 +694,465 @@ pub async fn execute(cmd: VmCommands, mut client: DaemonClient, format: OutputFo
                 cpu_cores: cpus,
                 memory_mb: memory,
                 volume_ids: volume,
-                network_ids: network,
+                network_ids: network.iter().map(|n| n.network_id.clone()).collect(),
                 qos_profile_id: qos_profile.unwrap_or_default(),
                 enable_tpm,
                 boot_disk_id: boot_disk,
                 extra_args: Default::default(),
                 compatibility_mode,
+                network_interfaces: network
+                    .into_iter()
+                    .filter(|n| !n.mac_address.is_empty() || !n.ip_address.is_empty())
+                    .collect(),
+                shared_folders: vec![],
+                priority,
             };
 
-            let vm = client.create_vm(&name, spec).await?;
-            let display = VmDisplay::from(vm);
-            print_success(&format!("VM '{}' created", display.name));
-            print_item(&display, format);
+            let vm = client.create_vm(&name, spec, labels.into_iter().collect()).await?;
+            print_success(&format!("VM '{}' created", name));
+            print_item(&vm, format);
         }
 
-        VmCommands::Start { id } => {
+        VmCommands::Start { id: Some(id), .. } => {
             let vm = client.start_vm(&id).await?;
-            let display = VmDisplay::from(vm);
-            print_success(&format!("VM '{}' started", display.name));
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' started", meta.name));
         }
 
-        VmCommands::Stop { id, force } => {
-            let vm = client.stop_vm(&id, force).await?;
-            let display = VmDisplay::from(vm);
-            print_success(&format!("VM '{}' stopped", display.name));
+        VmCommands::Start { selector, .. } => {
+            bulk(&mut client, selector.unwrap_or_default(), BulkAction::Start).await?;
         }
 
-        VmCommands::Delete { id, force } => {
+        VmCommands::Stop { id: Some(id), mode, timeout, force, .. } => {
+            let vm = client.stop_vm(&id, mode.resolve(force), timeout.unwrap_or(0)).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' stopped", meta.name));
+        }
+
+        VmCommands::Stop { selector, mode, timeout, force, .. } => {
+            let action = BulkAction::Stop(mode.resolve(force), timeout.unwrap_or(0));
+            bulk(&mut client, selector.unwrap_or_default(), action).await?;
+        }
+
+        VmCommands::Pause { id } => {
+            let vm = client.pause_vm(&id).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' paused", meta.name));
+        }
+
+        VmCommands::Resume { id } => {
+            let vm = client.resume_vm(&id).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' resumed", meta.name));
+        }
+
+        VmCommands::Suspend { id } => {
+            let vm = client.suspend_vm(&id).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' suspended to disk", meta.name));
+        }
+
+        VmCommands::Export { id, bundle_format, output } => {
+            let output = output
+                .unwrap_or_else(|| PathBuf::from(format!("{}.{}", id, bundle_format.extension())));
+            let size = export_bundle(&mut client, &id, bundle_format, &output).await?;
+            print_success(&format!(
+                "VM '{}' exported to {} ({} bytes)",
+                id,
+                output.display(),
+                size
+            ));
+        }
+
+        VmCommands::Import { path, name } => {
+            let response = import_bundle(&mut client, &path, name).await?;
+            let vm = response.vm.unwrap_or_default();
+            print_success(&format!(
+                "VM '{}' imported with {} disk(s)",
+                vm.meta.clone().unwrap_or_default().name,
+                response.volumes.len()
+            ));
+            print_item(&vm, format);
+        }
+
+        VmCommands::Delete { id: Some(id), force, .. } => {
             client.delete_vm(&id, force).await?;
             print_success(&format!("VM '{}' deleted", id));
         }
 
-        VmCommands::Restart { id, force } => {
-            client.stop_vm(&id, force).await?;
+        VmCommands::Delete { selector, force, .. } => {
+            bulk(&mut client, selector.unwrap_or_default(), BulkAction::Delete(force)).await?;
+        }
+
+        VmCommands::Label { id, labels, remove } => {
+            if labels.is_empty() && remove.is_empty() {
+                anyhow::bail!("Nothing to change: give key=value labels and/or --remove <key>");
+            }
+            let vm = client.update_vm_labels(&id, labels.into_iter().collect(), remove).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' labels: {}", meta.name, format_selector(&meta.labels)));
+        }
+
+        VmCommands::MarkTemplate { id } => {
+            let vm = client.mark_vm_as_template(&id).await?;
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' is now a template", meta.name));
+        }
+
+        VmCommands::Clone { template, name, count, labels } => {
+            let vms = client
+                .clone_vm(&template, &name, count as i32, labels.into_iter().collect())
+                .await?;
+            print_success(&format!("Created {} clone(s) of '{}'", vms.len(), template));
+            print_list(&vms, format);
+        }
+
+        VmCommands::AttachDevice { device, vm, name, read_only, force } => {
+            let response = client.attach_device(&vm, &device, name, read_only, force).await?;
+            for warning in &response.warnings {
+                print_warning(warning);
+            }
+            let volume = response.volume.unwrap_or_default();
+            let smart = volume.status.map(|s| s.smart_status).unwrap_or_default();
+            let meta = response.vm.and_then(|vm| vm.meta).unwrap_or_default();
+            print_success(&format!(
+                "Device {} attached to VM '{}' (SMART: {})",
+                device,
+                meta.name,
+                if smart.is_empty() { "unknown" } else { smart.as_str() }
+            ));
+        }
+
+        VmCommands::Logs { id, follow, tail, events } => {
+            let mut stream = client.stream_vm_logs(&id, follow, tail, events).await?;
+            while let Some(entry) = stream.message().await? {
+                let display = VmLogDisplay::from(entry);
+                match format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string(&display)?),
+                    _ if display.source == "event" => println!("--- {}", display.line),
+                    _ => println!("{}", display.line),
+                }
+            }
+        }
+
+        VmCommands::Stats { id, watch, interval } => {
+            if !watch {
+                let stats = client.get_vm_stats(&id).await?;
+                print_item(&stats, format);
+                return Ok(());
+            }
+
+            let mut stream = client.watch_vm_stats(&id, interval.max(1) * 1000).await?;
+            let mut previous = None;
+            while let Some(stats) = stream.message().await? {
+                match format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string(&stats)?),
+                    _ => println!("{}", stats_line(&stats, previous.as_ref())),
+                }
+                previous = Some(stats);
+            }
+        }
+
+        VmCommands::Qmp { id, command } => {
+            serde_json::from_str::<serde_json::Value>(&command)
+                .map_err(|e| anyhow::anyhow!("command is not JSON: {}", e))?;
+            let reply: serde_json::Value = serde_json::from_str(&client.execute_qmp(&id, &command).await?)?;
+            println!("{}", serde_json::to_string_pretty(&reply)?);
+            if let Some(error) = reply.get("error") {
+                anyhow::bail!(
+                    "QMP command failed: {}: {}",
+                    error["class"].as_str().unwrap_or_default(),
+                    error["desc"].as_str().unwrap_or_default()
+                );
+            }
+        }
+
+        VmCommands::Ip { id, network, wait } => {
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(wait);
+            let interfaces = loop {
+                let vm = client.get_vm(&id).await?;
+                let interfaces: Vec<VmInterface> = vm
+                    .status
+                    .unwrap_or_default()
+                    .interfaces
+                    .into_iter()
+                    .filter(|i| network.as_ref().map_or(true, |n| &i.network_id == n))
+                    .collect();
+                let pending = interfaces.is_empty() || interfaces.iter().any(|i| i.ip_address.is_empty());
+                if !pending || std::time::Instant::now() >= deadline {
+                    break interfaces;
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            };
+            if interfaces.is_empty() {
+                anyhow::bail!("VM {} has no NIC on network {}", id, network.unwrap_or_default());
+            }
+            print_list(&interfaces, format);
+        }
+
+        VmCommands::Restart { id, mode, timeout, force } => {
+            client.stop_vm(&id, mode.resolve(force), timeout.unwrap_or(0)).await?;
             let vm = client.start_vm(&id).await?;
-            let display = VmDisplay::from(vm);
-            print_success(&format!("VM '{}' restarted", display.name));
+            let meta = vm.meta.unwrap_or_default();
+            print_success(&format!("VM '{}' restarted", meta.name));
+        }
+
+        VmCommands::Share(cmd) => execute_share(cmd, client, format).await?,
+    }
+
+    Ok(())
+}
+
+async fn execute_share(cmd: ShareCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
+    match cmd {
+        ShareCommands::Add { id, host, guest, tag, read_only, cache, protocol } => {
+            let host = host
+                .canonicalize()
+                .with_context(|| format!("Cannot share {}", host.display()))?;
+            let folder = SharedFolder {
+                tag: tag.unwrap_or_else(|| CommonSharedFolder::default_tag(&guest)),
+                host_path: host.to_string_lossy().into_owned(),
+                guest_path: guest,
+                read_only,
+                cache: match cache {
+                    ShareCacheArg::Auto => SharedFolderCache::Auto,
+                    ShareCacheArg::Always => SharedFolderCache::Always,
+                    ShareCacheArg::Never => SharedFolderCache::Never,
+                } as i32,
+                protocol: match protocol {
+                    ShareProtocolArg::Auto => SharedFolderProtocol::Auto,
+                    ShareProtocolArg::Virtiofs => SharedFolderProtocol::Virtiofs,
+                    ShareProtocolArg::NineP => SharedFolderProtocol::Ninep,
+                } as i32,
+            };
+
+            let vm = client.get_vm(&id).await?;
+            let mut spec = vm.spec.unwrap_or_default();
+            if spec.shared_folders.iter().any(|f| f.tag == folder.tag) {
+                anyhow::bail!("VM {} already shares a folder tagged {}", id, folder.tag);
+            }
+            spec.shared_folders.push(folder.clone());
+            client.update_vm(&id, spec).await?;
+
+            print_success(&format!(
+                "Sharing {} with VM '{}' at {} (applied when the VM next starts)",
+                folder.host_path, id, folder.guest_path
+            ));
+            println!("Mount it in the guest with:");
+            for command in mount_commands(&folder) {
+                println!("  {}", command);
+            }
+        }
+
+        ShareCommands::Remove { id, tag } => {
+            let vm = client.get_vm(&id).await?;
+            let mut spec = vm.spec.unwrap_or_default();
+            let before = spec.shared_folders.len();
+            spec.shared_folders.retain(|f| f.tag != tag);
+            if spec.shared_folders.len() == before {
+                anyhow::bail!("VM {} has no shared folder tagged {}", id, tag);
+            }
+            client.update_vm(&id, spec).await?;
+            print_success(&format!(
+                "Stopped sharing {} with VM '{}' (applied when the VM next starts)",
+                tag, id
+            ));
+        }
+
+        ShareCommands::List { id } => {
+            let vm = client.get_vm(&id).await?;
+            print_list(&vm.spec.unwrap_or_default().shared_folders, format);
+        }
+    }
+
+    Ok(())
+}
+
+/// Guest commands mounting a folder; both transports when the daemon picks
+fn mount_commands(folder: &SharedFolder) -> Vec<String> {
+    let common = CommonSharedFolder {
+        tag: folder.tag.clone(),
+        host_path: folder.host_path.clone(),
+        guest_path: folder.guest_path.clone(),
+        read_only: folder.read_only,
+        cache: match folder.cache() {
+            SharedFolderCache::Auto => CommonSharedFolderCache::Auto,
+            SharedFolderCache::Always => CommonSharedFolderCache::Always,
+            SharedFolderCache::Never => CommonSharedFolderCache::Never,
+        },
+        protocol: CommonSharedFolderProtocol::Auto,
+    };
+    match folder.protocol() {
+        SharedFolderProtocol::Virtiofs => vec![common.mount_command(CommonSharedFolderProtocol::Virtiofs)],
+        SharedFolderProtocol::Ninep => vec![common.mount_command(CommonSharedFolderProtocol::NineP)],
+        SharedFolderProtocol::Auto => vec![
+            format!("{}    # virtiofs", common.mount_command(CommonSharedFolderProtocol::Virtiofs)),
+            format!("{}    # 9p", common.mount_command(CommonSharedFolderProtocol::NineP)),
+        ],
+    }
+}
+
+/// Lifecycle action applied to every VM matching a selector
+#[derive(Clone, Copy)]
+enum BulkAction {
+    Start,
+    Stop(StopMode, u32),
+    Delete(bool),
+}
+
+impl BulkAction {
+    fn done(self) -> &'static str {
+        match self {
+            Self::Start => "started",
+            Self::Stop(..) => "stopped",
+            Self::Delete(_) => "deleted",
         }
     }
+}
+
+/// Apply an action to each VM matching `selector`, carrying on past failures
+async fn bulk(client: &mut DaemonClient, selector: HashMap<String, String>, action: BulkAction) -> Result<()> {
+    let vms = client.list_vms_matching(selector.clone()).await?;
+    if vms.is_empty() {
+        print_warning(&format!("No VMs match {}", format_selector(&selector)));
+        return Ok(());
+    }
 
+    let mut failed = 0;
+    for vm in &vms {
+        let meta = vm.meta.clone().unwrap_or_default();
+        let result = match action {
+            BulkAction::Start => client.start_vm(&meta.id).await.map(|_| ()),
+            BulkAction::Stop(mode, timeout) => client.stop_vm(&meta.id, mode, timeout).await.map(|_| ()),
+            BulkAction::Delete(force) => client.delete_vm(&meta.id, force).await,
+        };
+        match result {
+            Ok(()) => print_success(&format!("VM '{}' {}", meta.name, action.done())),
+            Err(e) => {
+                failed += 1;
+                print_error(&format!("VM '{}': {}", meta.name, e));
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} VMs failed", failed, vms.len());
+    }
     Ok(())
 }
+
+fn transfer_bar(length: u64) -> Result<ProgressBar> {
+    let bar = ProgressBar::new(length);
+    let template = if length > 0 {
+        "{spinner} {msg:12} [{bar:40}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})"
+    } else {
+        "{spinner} {msg:12} {bytes} ({bytes_per_sec})"
+    };
+    bar.set_style(ProgressStyle::with_template(template)?.progress_chars("=> "));
+    bar.enable_steady_tick(std::time::Duration::from_millis(100));
+    Ok(bar)
+}
+
+/// Download an export bundle, returning its size
+///
+/// The bundle is written next to `output` and only renamed into place once
+/// complete, so a failed export never leaves a truncated file behind.
+async fn export_bundle(
+    client: &mut DaemonClient,
+    id: &str,
+    format: BundleFormat,
+    output: &Path,
+) -> Result<u64> {
+    let mut stream = client.export_vm(id, format.into()).await?;
+
+    let mut partial = output.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+    let mut file = tokio::fs::File::create(&partial)
+        .await
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+
+    // The daemon converts the disks before the first chunk arrives
+    let bar = transfer_bar(0)?;
+    bar.set_message("Exporting");
+
+    let result = async {
+        while let Some(chunk) = stream.message().await? {
+            bar.set_message("Downloading");
+            file.write_all(&chunk.data).await?;
+            bar.inc(chunk.data.len() as u64);
+        }
+        file.flush().await?;
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        bar.abandon_with_message("Failed");
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e);
+    }
+
+    tokio::fs::rename(&partial, output).await?;
+    bar.finish_with_message("Done");
+    Ok(bar.position())
+}
+
+/// Upload a bundle, sending the options with the first chunk
+async fn import_bundle(
+    client: &mut DaemonClient,
+    path: &Path,
+    name: Option<String>,
+) -> Result<crate::generated::ImportVmResponse> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let bar = transfer_bar(file.metadata().await?.len())?;
+    bar.set_message("Uploading");
+
+    let (tx, rx) = mpsc::channel(4);
+    let reader = {
+        let bar = bar.clone();
+        tokio::spawn(async move {
+            let mut options = Some(ImportVmOptions {
+                name: name.unwrap_or_default(),
+                labels: Default::default(),
+            });
+            let mut buffer = vec![0u8; IMPORT_CHUNK_SIZE];
+            loop {
+                let n = file.read(&mut buffer).await?;
+                if n == 0 && options.is_none() {
+                    break;
+                }
+                let request = ImportVmRequest {
+                    options: options.take(),
+                    data: buffer[..n].to_vec(),
+                };
+                if tx.send(request).await.is_err() {
+                    break;
+                }
+                bar.inc(n as u64);
+                if n == 0 {
+                    break;
+                }
+            }
+            // The daemon unpacks and converts the disks after the upload
+            bar.set_message("Importing");
+            anyhow::Ok(())
+        })
+    };
+
+    let result = client.import_vm(ReceiverStream::new(rx)).await;
+    reader.await??;
+
+    match result {
+        Ok(response) => {
+            bar.finish_with_message("Done");
+            Ok(response)
+        }
+        Err(e) => {
+            bar.abandon_with_message("Failed");
+            Err(e)
+        }
+    }
+}
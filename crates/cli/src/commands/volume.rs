@@ -2,21 +2,39 @@
 
 use clap::Subcommand;
 use anyhow::Result;
-use serde::Serialize;
+use std::collections::HashMap;
+use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::client::DaemonClient;
-use crate::output::{OutputFormat, TableDisplay, print_item, print_list, print_success};
-use crate::generated::{Volume, VolumeSpec, VolumeKind, IntegrityConfig};
+use crate::commands::job;
+use crate::watch::Watch;
+use crate::output::{OutputFormat, TableDisplay, print_item, print_list, print_success, print_warning};
+use crate::generated::{Volume, VolumeLayer, VolumeSpec, VolumeKind, VolumeEncryption, IntegrityConfig, DownloadState, JobKind};
+
+/// Chains longer than this get a hint to flatten
+const LONG_CHAIN_LAYERS: usize = 8;
 
 #[derive(Subcommand)]
 pub enum VolumeCommands {
     /// List all volumes
-    List,
+    List {
+        /// Print the list again whenever one changes
+        #[arg(short, long)]
+        watch: bool,
+    },
 
     /// Get volume details
     Get {
         /// Volume ID
         id: String,
+
+        /// Print the volume again whenever it changes
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Print only what changed since the volume was last shown
+        #[arg(long)]
+        output_diff: bool,
     },
 
     /// Create a new volume
@@ -25,7 +43,7 @@ pub enum VolumeCommands {
         #[arg(short, long)]
         name: String,
 
-        /// Volume kind (disk, weights)
+        /// Volume kind (disk, weights, device)
         #[arg(short, long, default_value = "disk")]
         kind: String,
 
@@ -48,6 +66,36 @@ pub enum VolumeCommands {
         /// Create copy-on-write overlay
         #[arg(long)]
         overlay: bool,
+
+        /// Expected sha256 digest (required for http/https sources)
+        #[arg(long)]
+        sha256: Option<String>,
+
+        /// Return immediately instead of waiting for a remote download
+        #[arg(short, long)]
+        detach: bool,
+
+        /// Encrypt the volume with this daemon keyring key (generated if new)
+        #[arg(long)]
+        encryption_key: Option<String>,
+
+        /// Cipher for encrypted volumes
+        #[arg(long, default_value = "aes-256", requires = "encryption_key")]
+        cipher: String,
+
+        /// Store the passphrase in this file under the new key
+        #[arg(long, requires = "encryption_key", conflicts_with = "key_file")]
+        passphrase_file: Option<String>,
+
+        /// Store this binary key file under the new key
+        #[arg(long, requires = "encryption_key")]
+        key_file: Option<String>,
+    },
+
+    /// Wait for a remote volume download, showing progress
+    Wait {
+        /// Volume ID
+        id: String,
     },
 
     /// Delete a volume
@@ -56,89 +104,225 @@ pub enum VolumeCommands {
         id: String,
     },
 
-    /// Pull a volume from OCI registry
+    /// Show the qcow2 backing-file chain, active image first
+    Chain {
+        /// Volume ID
+        id: String,
+    },
+
+    /// Grow or shrink a volume, online if its VM is running (grow only)
+    Resize {
+        /// Volume ID
+        id: String,
+
+        /// New size (20G) or a change to the current one (+10G, -5G)
+        #[arg(allow_hyphen_values = true)]
+        size: String,
+
+        /// Allow shrinking, which discards data past the new end
+        #[arg(long)]
+        shrink: bool,
+    },
+
+    /// Merge the backing chain into a standalone image (the volume must not be in use)
+    Flatten {
+        /// Volume ID
+        id: String,
+
+        /// Queue as a background job and return immediately
+        #[arg(long)]
+        detach: bool,
+    },
+
+    /// Push a volume's image to a directory or s3://bucket/prefix for other hosts to pull
+    Push {
+        /// Volume ID
+        id: String,
+
+        /// Remote store: an absolute directory path or s3://bucket/prefix
+        #[arg(long)]
+        remote: String,
+
+        /// Queue as a background job and return immediately
+        #[arg(long)]
+        detach: bool,
+    },
+
+    /// Pull a volume from an OCI registry, or one pushed to a remote store
     Pull {
-        /// OCI reference (e.g., ghcr.io/infrasim/kali-xfce:latest)
+        /// OCI reference (e.g., ghcr.io/infrasim/kali-xfce:latest), or the
+        /// pushed volume's ID with --remote
         reference: String,
 
+        /// Remote store the volume was pushed to
+        #[arg(long)]
+        remote: Option<String>,
+
         /// Volume name
         #[arg(short, long)]
         name: Option<String>,
+
+        /// Queue as a background job and return immediately (with --remote)
+        #[arg(long, requires = "remote")]
+        detach: bool,
     },
 }
 
-/// Volume display wrapper for serialization
-#[derive(Serialize)]
-pub struct VolumeDisplay {
-    pub id: String,
-    pub name: String,
-    pub kind: String,
-    pub source: String,
-    pub size: i64,
-    pub ready: bool,
-    pub digest: String,
-}
+impl TableDisplay for Volume {
+    fn headers() -> Vec<&'static str> {
+        vec!["ID", "Name", "Kind", "Source", "Size", "Ready", "Digest", "Download"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let meta = self.meta.clone().unwrap_or_default();
+        let spec = self.spec.clone().unwrap_or_default();
+        let status = self.status.clone().unwrap_or_default();
 
-impl From<Volume> for VolumeDisplay {
-    fn from(vol: Volume) -> Self {
-        let meta = vol.meta.unwrap_or_default();
-        let spec = vol.spec.unwrap_or_default();
-        let status = vol.status.unwrap_or_default();
-        
         let kind_str = VolumeKind::try_from(spec.kind)
             .map(|k| format!("{:?}", k))
             .unwrap_or_else(|_| "Unknown".to_string());
-        
-        Self {
-            id: meta.id,
-            name: meta.name,
-            kind: kind_str,
-            source: spec.source,
-            size: spec.size_bytes,
-            ready: status.ready,
-            digest: status.digest,
-        }
+
+        let download = status
+            .download
+            .as_ref()
+            .map(|d| {
+                DownloadState::try_from(d.state)
+                    .map(|s| format!("{:?}", s))
+                    .unwrap_or_else(|_| "Unknown".to_string())
+            })
+            .unwrap_or_default();
+
+        vec![
+            meta.id,
+            meta.name,
+            kind_str,
+            spec.source.chars().take(30).collect::<String>(),
+            format_size(spec.size_bytes),
+            status.ready.to_string(),
+            status.digest.chars().take(12).collect::<String>(),
+            download,
+        ]
     }
 }
 
-impl TableDisplay for VolumeDisplay {
+impl TableDisplay for VolumeLayer {
     fn headers() -> Vec<&'static str> {
-        vec!["ID", "Name", "Kind", "Source", "Size", "Ready", "Digest"]
+        vec!["Path", "Format", "Virtual Size", "Allocated", "Snapshot"]
     }
 
     fn row(&self) -> Vec<String> {
-        let size_str = if self.size > 1024 * 1024 * 1024 {
-            format!("{:.1}GB", self.size as f64 / 1024.0 / 1024.0 / 1024.0)
-        } else if self.size > 1024 * 1024 {
-            format!("{:.1}MB", self.size as f64 / 1024.0 / 1024.0)
-        } else {
-            format!("{}B", self.size)
-        };
-        
         vec![
-            self.id.clone(),
-            self.name.clone(),
-            self.kind.clone(),
-            self.source.chars().take(30).collect::<String>(),
-            size_str,
-            self.ready.to_string(),
-            self.digest.chars().take(12).collect::<String>(),
+            self.path.clone(),
+            self.format.clone(),
+            format_size(self.virtual_size),
+            format_size(self.actual_size),
+            self.snapshot_id.clone(),
         ]
     }
 }
 
+/// New size from `20G`, `+10G` or `-5G`, rounded up to whole 512-byte sectors
+fn resolve_size(size: &str, current: i64) -> Result<i64> {
+    let bytes = match size.strip_prefix('+') {
+        Some(delta) => current + parse_size(delta)?,
+        None => match size.strip_prefix('-') {
+            Some(delta) => current - parse_size(delta)?,
+            None => parse_size(size)?,
+        },
+    };
+    if bytes <= 0 {
+        anyhow::bail!("Resulting size {} is not positive", bytes);
+    }
+    Ok((bytes + 511) / 512 * 512)
+}
+
+/// Bytes from a size with an optional binary suffix (K, M, G, T)
+fn parse_size(size: &str) -> Result<i64> {
+    let trimmed = size.trim().trim_end_matches(['B', 'b']).trim_end_matches('i');
+    let (digits, multiplier) = match trimmed.chars().last() {
+        Some(c) if c.is_ascii_alphabetic() => {
+            let multiplier: i64 = match c.to_ascii_uppercase() {
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                'T' => 1 << 40,
+                _ => anyhow::bail!("Unknown size suffix in '{}'", size),
+            };
+            (&trimmed[..trimmed.len() - 1], multiplier)
+        }
+        _ => (trimmed, 1),
+    };
+    let value: f64 = digits
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size '{}'", size))?;
+    Ok((value * multiplier as f64) as i64)
+}
+
+/// Secret to store under a new encryption key; empty lets the daemon generate one
+///
+/// Trailing newlines are dropped from passphrase files; key files are used verbatim.
+fn read_secret(passphrase_file: Option<String>, key_file: Option<String>) -> Result<Vec<u8>> {
+    let (path, secret) = match (passphrase_file, key_file) {
+        (Some(path), _) => {
+            let passphrase = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read passphrase file {}: {}", path, e))?;
+            let secret = passphrase.trim_end_matches(['\r', '\n']).as_bytes().to_vec();
+            (path, secret)
+        }
+        (None, Some(path)) => {
+            let secret = std::fs::read(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read key file {}: {}", path, e))?;
+            (path, secret)
+        }
+        (None, None) => return Ok(Vec::new()),
+    };
+    if secret.is_empty() {
+        anyhow::bail!("{} is empty", path);
+    }
+    Ok(secret)
+}
+
+fn format_size(size: i64) -> String {
+    if size > 1024 * 1024 * 1024 {
+        format!("{:.1}GB", size as f64 / 1024.0 / 1024.0 / 1024.0)
+    } else if size > 1024 * 1024 {
+        format!("{:.1}MB", size as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{}B", size)
+    }
+}
+
 pub async fn execute(cmd: VolumeCommands, mut client: DaemonClient, format: OutputFormat) -> Result<()> {
     match cmd {
-        VolumeCommands::List => {
-            let volumes = client.list_volumes().await?;
-            let displays: Vec<VolumeDisplay> = volumes.into_iter().map(VolumeDisplay::from).collect();
-            print_list(&displays, format);
+        VolumeCommands::List { watch } => {
+            let mut watch = Watch::start(&mut client, watch, "volume", None, format).await?;
+            loop {
+                let volumes = client.list_volumes().await?;
+                watch.frame();
+                print_list(&volumes, format);
+                if !watch.changed().await? {
+                    break;
+                }
+            }
         }
 
-        VolumeCommands::Get { id } => {
-            let vol = client.get_volume(&id).await?;
-            let display = VolumeDisplay::from(vol);
-            print_item(&display, format);
+        VolumeCommands::Get { id, watch, output_diff } => {
+            let mut vol = client.get_volume(&id).await?;
+            let vol_id = vol.meta.clone().unwrap_or_default().id;
+            let mut watch = Watch::start(&mut client, watch, "volume", Some(&vol_id), format).await?;
+            loop {
+                watch.frame();
+                if output_diff {
+                    watch.print_diff("volume", &vol_id, &vol)?;
+                } else {
+                    print_item(&vol, format);
+                }
+                if !watch.changed().await? {
+                    break;
+                }
+                vol = client.get_volume(&vol_id).await?;
+            }
         }
 
         VolumeCommands::Create {
@@ -149,27 +333,66 @@ pub async fn execute(cmd: VolumeCommands, mut client: DaemonClient, format: Outp
             size,
             read_only,
             overlay,
+            sha256,
+            detach,
+            encryption_key,
+            cipher,
+            passphrase_file,
+            key_file,
         } => {
+            let remote = source.starts_with("http://") || source.starts_with("https://");
+            if remote && sha256.is_none() {
+                anyhow::bail!("--sha256 is required for http/https sources");
+            }
+
             let kind_enum = match kind.to_lowercase().as_str() {
                 "disk" => VolumeKind::Disk,
                 "weights" => VolumeKind::Weights,
+                "device" => VolumeKind::Device,
                 _ => VolumeKind::Disk,
             };
 
             let spec = VolumeSpec {
                 kind: kind_enum as i32,
                 source,
-                integrity: Some(IntegrityConfig::default()),
+                integrity: Some(match sha256 {
+                    Some(digest) => IntegrityConfig {
+                        scheme: "sha256".to_string(),
+                        expected_digest: digest,
+                        ..Default::default()
+                    },
+                    None => IntegrityConfig::default(),
+                }),
                 read_only,
                 size_bytes: size.unwrap_or(0),
                 format: vol_format,
                 overlay,
+                encryption: match encryption_key {
+                    Some(key_id) => Some(VolumeEncryption {
+                        cipher,
+                        key_id,
+                        secret: read_secret(passphrase_file, key_file)?,
+                    }),
+                    None => None,
+                },
             };
 
-            let vol = client.create_volume(&name, spec).await?;
-            let display = VolumeDisplay::from(vol);
-            print_success(&format!("Volume '{}' created", display.name));
-            print_item(&display, format);
+            let mut vol = client.create_volume(&name, spec, Default::default()).await?;
+            let id = vol.meta.as_ref().map(|m| m.id.clone()).unwrap_or_default();
+            print_success(&format!("Volume '{}' created", name));
+
+            if remote && !detach {
+                wait_for_download(&mut client, &id).await?;
+                vol = client.get_volume(&id).await?;
+            }
+
+            print_item(&vol, format);
+        }
+
+        VolumeCommands::Wait { id } => {
+            wait_for_download(&mut client, &id).await?;
+            let vol = client.get_volume(&id).await?;
+            print_item(&vol, format);
         }
 
         VolumeCommands::Delete { id } => {
@@ -177,7 +400,85 @@ pub async fn execute(cmd: VolumeCommands, mut client: DaemonClient, format: Outp
             print_success(&format!("Volume '{}' deleted", id));
         }
 
-        VolumeCommands::Pull { reference, name } => {
+        VolumeCommands::Chain { id } => {
+            let layers = client.get_volume_chain(&id).await?;
+            print_list(&layers, format);
+
+            if matches!(format, OutputFormat::Table) && layers.len() > LONG_CHAIN_LAYERS {
+                print_warning(&format!(
+                    "{} layers in the chain slow down disk reads; run `infrasim volume flatten {}`",
+                    layers.len(),
+                    id
+                ));
+            }
+        }
+
+        VolumeCommands::Resize { id, size, shrink } => {
+            let current = if size.starts_with(['+', '-']) {
+                client
+                    .get_volume_chain(&id)
+                    .await?
+                    .first()
+                    .map(|layer| layer.virtual_size)
+                    .unwrap_or_default()
+            } else {
+                0
+            };
+            let size_bytes = resolve_size(&size, current)?;
+
+            let vol = client.resize_volume(&id, size_bytes, shrink).await?;
+            print_success(&format!("Volume '{}' resized to {}", id, format_size(size_bytes)));
+            print_item(&vol, format);
+        }
+
+        VolumeCommands::Flatten { id, detach: true } => {
+            job::submit(&mut client, JobKind::FlattenVolume, &id, HashMap::new(), format).await?;
+        }
+
+        VolumeCommands::Flatten { id, detach: false } => {
+            let path = flatten(&mut client, &id).await?;
+            print_success(&format!("Volume '{}' flattened into {}", id, path));
+            let vol = client.get_volume(&id).await?;
+            print_item(&vol, format);
+        }
+
+        VolumeCommands::Push { id, remote, detach: true } => {
+            let params = HashMap::from([("remote".to_string(), remote)]);
+            job::submit(&mut client, JobKind::PushVolume, &id, params, format).await?;
+        }
+
+        VolumeCommands::Push { id, remote, detach: false } => {
+            let pushed = client.push_volume(&id, &remote).await?;
+            if pushed.uploaded {
+                print_success(&format!(
+                    "Volume '{}' pushed to {} ({}, sha256:{})",
+                    id,
+                    remote,
+                    format_size(pushed.size_bytes),
+                    pushed.digest
+                ));
+            } else {
+                print_success(&format!(
+                    "Volume '{}' already in {} (sha256:{}); manifest updated",
+                    id, remote, pushed.digest
+                ));
+            }
+        }
+
+        VolumeCommands::Pull { reference, remote: Some(remote), name, detach: true } => {
+            let mut params = HashMap::from([("remote".to_string(), remote)]);
+            params.extend(name.map(|name| ("name".to_string(), name)));
+            job::submit(&mut client, JobKind::PullVolume, &reference, params, format).await?;
+        }
+
+        VolumeCommands::Pull { reference, remote: Some(remote), name, .. } => {
+            let vol = client.pull_volume(&reference, &remote, name).await?;
+            let meta = vol.meta.clone().unwrap_or_default();
+            print_success(&format!("Volume '{}' pulled from {}", meta.name, remote));
+            print_item(&vol, format);
+        }
+
+        VolumeCommands::Pull { reference, remote: None, name, .. } => {
             let vol_name = name.unwrap_or_else(|| {
                 reference.split('/').last()
                     .and_then(|s| s.split(':').next())
@@ -193,14 +494,76 @@ pub async fn execute(cmd: VolumeCommands, mut client: DaemonClient, format: Outp
                 size_bytes: 0,
                 format: "qcow2".to_string(),
                 overlay: false,
+                encryption: None,
             };
 
-            let vol = client.create_volume(&vol_name, spec).await?;
-            let display = VolumeDisplay::from(vol);
-            print_success(&format!("Volume '{}' pulled from {}", display.name, reference));
-            print_item(&display, format);
+            let vol = client.create_volume(&vol_name, spec, Default::default()).await?;
+            print_success(&format!("Volume '{}' pulled from {}", vol_name, reference));
+            print_item(&vol, format);
         }
     }
 
     Ok(())
 }
+
+/// Render download progress until the import completes or fails
+pub async fn wait_for_download(client: &mut DaemonClient, id: &str) -> Result<()> {
+    let mut stream = client.watch_volume_download(id).await?;
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner} {msg:12} [{bar:40}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+        )?
+        .progress_chars("=> "),
+    );
+
+    while let Some(status) = stream.message().await? {
+        if status.bytes_total > 0 {
+            bar.set_length(status.bytes_total as u64);
+        }
+        bar.set_position(status.bytes_downloaded as u64);
+
+        match DownloadState::try_from(status.state) {
+            Ok(DownloadState::Downloading) if !status.error.is_empty() => {
+                bar.set_message("Retrying");
+            }
+            Ok(DownloadState::Downloading) => bar.set_message("Downloading"),
+            Ok(DownloadState::Verifying) => bar.set_message("Verifying"),
+            Ok(DownloadState::Complete) => {
+                bar.finish_with_message("Verified");
+                return Ok(());
+            }
+            Ok(DownloadState::Failed) => {
+                bar.abandon_with_message("Failed");
+                anyhow::bail!("Download failed: {}", status.error);
+            }
+            _ => bar.set_message("Pending"),
+        }
+    }
+
+    bar.abandon();
+    anyhow::bail!("Download stream ended before completion")
+}
+
+/// Render flatten progress, returning the new standalone image
+async fn flatten(client: &mut DaemonClient, id: &str) -> Result<String> {
+    let mut stream = client.flatten_volume(id).await?;
+
+    let bar = ProgressBar::new(100);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner} Flattening [{bar:40}] {pos}% ({elapsed})")?
+            .progress_chars("=> "),
+    );
+
+    while let Some(progress) = stream.message().await? {
+        bar.set_position(progress.percent as u64);
+        if progress.done {
+            bar.finish();
+            return Ok(progress.local_path);
+        }
+    }
+
+    bar.abandon();
+    anyhow::bail!("Flatten stream ended before completion")
+}
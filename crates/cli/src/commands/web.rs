@@ -28,9 +28,9 @@ pub struct WebServeArgs {
     #[arg(long, default_value = "127.0.0.1:8080")]
     pub addr: String,
 
-    /// Daemon gRPC address
-    #[arg(long, default_value = "http://127.0.0.1:50051")]
-    pub daemon_addr: String,
+    /// Daemon gRPC address (default: the local daemon's socket or TCP port)
+    #[arg(long)]
+    pub daemon_addr: Option<String>,
 
     /// Enable Vite development server with hot reload
     /// In this mode, Rust serves /api/* and /websockify/*
@@ -129,9 +129,14 @@ pub async fn execute(cmd: WebCommands) -> anyhow::Result<()> {
 }
 
 async fn execute_serve(args: WebServeArgs) -> anyhow::Result<()> {
+    let daemon_addr = args
+        .daemon_addr
+        .clone()
+        .unwrap_or_else(infrasim_common::default_daemon_addr);
+
     // Set environment variables for web server configuration
     std::env::set_var("INFRASIM_WEB_ADDR", &args.addr);
-    std::env::set_var("INFRASIM_DAEMON_ADDR", &args.daemon_addr);
+    std::env::set_var("INFRASIM_DAEMON_ADDR", &daemon_addr);
 
     if args.control_enabled {
         std::env::set_var("INFRASIM_WEB_CONTROL_ENABLED", "1");
@@ -204,7 +209,7 @@ async fn execute_serve(args: WebServeArgs) -> anyhow::Result<()> {
     // Import and run the web server
     // Note: This would typically be done via the infrasim-web crate
     info!("Starting InfraSim Web on http://{}", addr);
-    info!("Daemon: {}", args.daemon_addr);
+    info!("Daemon: {}", daemon_addr);
     
     // The actual server startup is handled by infrasim-web
     // For now, print configuration and exit
@@ -212,7 +217,7 @@ async fn execute_serve(args: WebServeArgs) -> anyhow::Result<()> {
     
     println!("Web server configuration:");
     println!("  Address: {}", args.addr);
-    println!("  Daemon: {}", args.daemon_addr);
+    println!("  Daemon: {}", daemon_addr);
     println!("  UI Dev Mode: {}", args.ui_dev);
     println!("  UI Static Dir: {:?}", args.ui_static_dir);
     println!("  Auth Mode: {}", args.auth_mode);
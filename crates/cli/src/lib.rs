@@ -6,6 +6,9 @@
 pub mod commands;
 pub mod client;
 pub mod output;
+pub mod manifest;
+pub mod stack;
+pub mod watch;
 
 mod generated {
     include!("generated/infrasim.v1.rs");
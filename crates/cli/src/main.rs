@@ -9,12 +9,15 @@ use tracing::info;
 mod commands;
 mod client;
 mod output;
+mod manifest;
+mod stack;
+mod watch;
 
 mod generated {
     include!("generated/infrasim.v1.rs");
 }
 
-use commands::{vm, network, volume, console, snapshot, benchmark, attestation, web, artifact, control, pipeline, sdn};
+use commands::{vm, network, volume, appliance, console, snapshot, backup, job, events, benchmark, attestation, web, artifact, control, pipeline, sdn, context, auth, audit, secret, drift, daemon, admin, scenario};
 
 /// InfraSim CLI - Terraform-Compatible QEMU Platform
 #[derive(Parser)]
@@ -22,13 +25,21 @@ use commands::{vm, network, volume, console, snapshot, benchmark, attestation, w
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
-    /// Daemon address
-    #[arg(long, default_value = "http://127.0.0.1:50051", global = true)]
-    daemon_addr: String,
+    /// Daemon address (overrides the context)
+    #[arg(long, global = true)]
+    daemon_addr: Option<String>,
 
-    /// Output format
+    /// Named context from ~/.infrasim/contexts.toml
+    #[arg(long, env = "INFRASIM_CONTEXT", global = true)]
+    context: Option<String>,
+
+    /// Output format: table, json, yaml, csv, plain or jsonpath=<expr>
     #[arg(long, default_value = "table", global = true)]
-    format: output::OutputFormat,
+    format: output::FormatArg,
+
+    /// Print only this field of each item, e.g. '{.status.state}'
+    #[arg(long, global = true)]
+    field: Option<output::FieldPath>,
 
     /// Enable verbose output
     #[arg(short, long, global = true)]
@@ -52,6 +63,10 @@ enum Commands {
     #[command(subcommand)]
     Volume(volume::VolumeCommands),
 
+    /// Push and pull VMs as appliance artifacts in OCI registries
+    #[command(subcommand)]
+    Appliance(appliance::ApplianceCommands),
+
     /// Access VM console
     Console(console::ConsoleArgs),
 
@@ -59,6 +74,17 @@ enum Commands {
     #[command(subcommand)]
     Snapshot(snapshot::SnapshotCommands),
 
+    /// Manage VM backups
+    #[command(subcommand)]
+    Backup(backup::BackupCommands),
+
+    /// Follow and cancel background jobs
+    #[command(subcommand)]
+    Job(job::JobCommands),
+
+    /// Show resource changes, VM state transitions and reconcile errors
+    Events(events::EventsArgs),
+
     /// Run benchmarks
     Benchmark(benchmark::BenchmarkArgs),
 
@@ -86,6 +112,49 @@ enum Commands {
     #[command(subcommand)]
     Sdn(sdn::SdnCommands),
 
+    /// Manage named daemon contexts
+    #[command(subcommand)]
+    Context(context::ContextCommands),
+
+    /// Issue client certificates and API tokens for the daemon
+    #[command(subcommand)]
+    Auth(auth::AuthCommands),
+
+    /// Converge declarative multi-VM stack files
+    #[command(subcommand)]
+    Stack(commands::stack::StackCommands),
+
+    /// Create or update resources from a stream of manifests
+    Apply(commands::apply::ApplyArgs),
+
+    /// Keep lab environments as manifests on Git branches
+    #[command(subcommand)]
+    Git(commands::git::GitCommands),
+
+    /// Review and verify the audit log of mutating operations
+    #[command(subcommand)]
+    Audit(audit::AuditCommands),
+
+    /// Store secrets for guest credentials and API keys
+    #[command(subcommand)]
+    Secret(secret::SecretCommands),
+
+    /// Compare Terraform state, daemon state and the host for drift
+    #[command(subcommand)]
+    Drift(drift::DriftCommands),
+
+    /// Daemon replication to a standby and failover
+    #[command(subcommand)]
+    Daemon(daemon::DaemonCommands),
+
+    /// Maintain the daemon's state database
+    #[command(subcommand)]
+    Admin(admin::AdminCommands),
+
+    /// Run scripted chaos experiments and compare their reports
+    #[command(subcommand)]
+    Scenario(scenario::ScenarioCommands),
+
     /// Check daemon status
     Status,
 
@@ -107,30 +176,84 @@ async fn main() -> anyhow::Result<()> {
         .with_target(false)
         .init();
 
+    let format = output::configure(cli.format.clone(), cli.field.clone());
+    if let Err(e) = run(cli, format).await {
+        std::process::exit(output::print_failure(&e, format));
+    }
+    Ok(())
+}
+
+async fn run(cli: Cli, format: output::OutputFormat) -> anyhow::Result<()> {
+    // Context management must work even when the current context is broken
+    if let Commands::Context(cmd) = cli.command {
+        return context::execute(cmd, format);
+    }
+    // Credentials are issued from the local store and never need the daemon
+    if let Commands::Auth(cmd) = cli.command {
+        return auth::execute(cmd, format);
+    }
+    // Maintenance works on the database file, with or without a daemon
+    if let Commands::Admin(cmd) = cli.command {
+        return admin::execute(cmd, format).await;
+    }
+
+    let target = context::resolve(cli.context.as_deref(), cli.daemon_addr.as_deref())?;
+    if let Some(name) = &target.context {
+        output::set_active_context(name);
+    }
+
     // Create client
-    let client = client::DaemonClient::new(&cli.daemon_addr).await;
+    let client = client::DaemonClient::new(&target.daemon_addr, &target.credentials).await;
 
     match cli.command {
-        Commands::Vm(cmd) => vm::execute(cmd, client?, cli.format).await?,
-        Commands::Network(cmd) => network::execute(cmd, client?, cli.format).await?,
-        Commands::Volume(cmd) => volume::execute(cmd, client?, cli.format).await?,
+        Commands::Vm(cmd) => vm::execute(cmd, client?, format).await?,
+        Commands::Network(cmd) => network::execute(cmd, client?, format).await?,
+        Commands::Volume(cmd) => volume::execute(cmd, client?, format).await?,
+        Commands::Appliance(cmd) => appliance::execute(cmd, client?, format).await?,
         Commands::Console(args) => console::execute(args, client?).await?,
-        Commands::Snapshot(cmd) => snapshot::execute(cmd, client?, cli.format).await?,
-        Commands::Benchmark(args) => benchmark::execute(args, client?, cli.format).await?,
-        Commands::Attestation(cmd) => attestation::execute(cmd, client?, cli.format).await?,
+        Commands::Snapshot(cmd) => snapshot::execute(cmd, client?, format).await?,
+        Commands::Backup(cmd) => backup::execute(cmd, client?, format).await?,
+        Commands::Job(cmd) => job::execute(cmd, client?, format).await?,
+        Commands::Events(args) => events::execute(args, client?, format).await?,
+        Commands::Benchmark(args) => benchmark::execute(args, client?, format).await?,
+        Commands::Attestation(cmd) => attestation::execute(cmd, client, format).await?,
         Commands::Web(cmd) => web::execute(cmd).await?,
-        Commands::Artifact(cmd) => artifact::execute(cmd, client.ok(), cli.format).await?,
-        Commands::Control(cmd) => control::execute(cmd, client.ok(), cli.format).await?,
-        Commands::Pipeline(cmd) => pipeline::execute(cmd, cli.format).await?,
-        Commands::Sdn(cmd) => sdn::execute(cmd, client.ok(), cli.format).await?,
+        Commands::Artifact(cmd) => artifact::execute(cmd, client.ok(), format).await?,
+        Commands::Control(cmd) => control::execute(cmd, client.ok(), format).await?,
+        Commands::Pipeline(cmd) => pipeline::execute(cmd, format).await?,
+        Commands::Sdn(cmd) => sdn::execute(cmd, client.ok(), format).await?,
+        Commands::Stack(cmd) => commands::stack::execute(cmd, client?, format).await?,
+        Commands::Apply(args) => commands::apply::execute(args, client?, format).await?,
+        Commands::Git(cmd) => commands::git::execute(cmd, client, format).await?,
+        Commands::Audit(cmd) => audit::execute(cmd, client?, format).await?,
+        Commands::Secret(cmd) => secret::execute(cmd, client?, format).await?,
+        Commands::Drift(cmd) => drift::execute(cmd, client?, format).await?,
+        Commands::Daemon(cmd) => daemon::execute(cmd, client?, format).await?,
+        Commands::Scenario(cmd) => scenario::execute(cmd, client?, format).await?,
+        Commands::Context(_) | Commands::Auth(_) | Commands::Admin(_) => unreachable!("handled before connecting"),
         Commands::Status => {
             match client {
                 Ok(mut c) => {
                     let healthy = c.health_check().await;
                     if healthy {
-                        println!("✅ Daemon is running at {}", cli.daemon_addr);
+                        println!("✅ Daemon is running at {}", target.daemon_addr);
+                        if let Ok(status) = c.get_daemon_status().await {
+                            println!(
+                                "   VMs: {} running of {}",
+                                status.running_vms, status.total_vms
+                            );
+                            println!(
+                                "   Memory committed: {} of {} MB",
+                                status.memory_used_bytes / (1024 * 1024),
+                                status.host_memory_bytes / (1024 * 1024)
+                            );
+                            println!(
+                                "   vCPUs committed: {} on {} host CPUs",
+                                status.committed_cpu_cores, status.host_cpus
+                            );
+                        }
                     } else {
-                        println!("❌ Daemon is not responding at {}", cli.daemon_addr);
+                        println!("❌ Daemon is not responding at {}", target.daemon_addr);
                         std::process::exit(1);
                     }
                 }
@@ -142,12 +265,19 @@ async fn main() -> anyhow::Result<()> {
         }
         Commands::Version => {
             println!("InfraSim CLI v{}", env!("CARGO_PKG_VERSION"));
-            println!("Terraform-Compatible QEMU Platform for macOS");
+            println!("Terraform-Compatible QEMU Platform for macOS and Linux");
             println!();
             println!("Build info:");
-            println!("  Target: aarch64-apple-darwin");
-            println!("  QEMU: aarch64 with HVF acceleration");
-            println!("  Guest: Raspberry Pi style ARM64");
+            println!(
+                "  Target: {}-{}",
+                std::env::consts::ARCH,
+                std::env::consts::OS
+            );
+            println!(
+                "  Accelerator: {}",
+                infrasim_common::attestation::host_accelerator()
+            );
+            println!("  Guest: ARM64 (virt) by default");
         }
     }
 
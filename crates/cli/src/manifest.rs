@@ -0,0 +1,781 @@
+//! Resource Manifests
+//!
+//! `infrasim apply` reads a stream of manifests, one resource each, as
+//! multi-document YAML or a sequence of JSON objects (or arrays of them):
+//!
+//! ```yaml
+//! kind: volume
+//! name: ci-disk
+//! labels: {pipeline: ci}
+//! spec:
+//!   source: /var/lib/infrasim/images/alpine-aarch64.qcow2
+//!   overlay: true
+//! ---
+//! kind: vm
+//! name: ci-runner
+//! labels: {pipeline: ci}
+//! spec:
+//!   cpus: 4
+//!   boot_disk: ci-disk
+//! ---
+//! kind: snapshot_policy
+//! name: ci-runner-hourly
+//! labels: {pipeline: ci}
+//! spec:
+//!   vm: ci-runner
+//!   every: 1h
+//! ```
+//!
+//! Resources are matched to the daemon's by kind and name: missing ones are
+//! created, changed ones updated (VMs) or replaced, and the rest left alone.
+//! Labels are set when a resource is created; a VM's are also updated in
+//! place.
+//! Network, volume and VM specs take the fields of a stack file (see
+//! [`crate::stack`]); references name a resource in the stream or on the
+//! daemon, or else give its ID. With a prune selector, resources of these
+//! kinds carrying its labels that no manifest names are deleted.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use infrasim_common::types::format_interval;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use crate::client::DaemonClient;
+use crate::commands::volume::wait_for_download;
+use crate::generated::{
+    Network, NetworkMode, ResourceMeta, SnapshotPolicy, SnapshotPolicySpec, StopMode, Vm, VmSpec, VmState, Volume,
+    VolumeKind,
+};
+use crate::output::{print_info, print_success, TableDisplay};
+use crate::stack::{self, id_of, past_tense, Action, Ids, NetworkDef, VmDef, VolumeDef};
+
+/// Kinds of resources a manifest declares, in the order they are applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Network,
+    Volume,
+    Vm,
+    SnapshotPolicy,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Network => "network",
+            Self::Volume => "volume",
+            Self::Vm => "vm",
+            Self::SnapshotPolicy => "snapshot policy",
+        })
+    }
+}
+
+/// Header of a manifest; `spec` is read according to `kind`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Header {
+    kind: Kind,
+    name: String,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    #[serde(default)]
+    spec: Value,
+}
+
+#[derive(Debug)]
+pub enum Resource {
+    Network(NetworkDef),
+    Volume(VolumeDef),
+    Vm(VmDef),
+    SnapshotPolicy(SnapshotPolicyDef),
+}
+
+#[derive(Debug)]
+pub struct Manifest {
+    pub name: String,
+    pub labels: HashMap<String, String>,
+    pub resource: Resource,
+    /// Spec as written, for comparing manifests
+    pub spec: Value,
+}
+
+impl Manifest {
+    pub fn kind(&self) -> Kind {
+        match self.resource {
+            Resource::Network(_) => Kind::Network,
+            Resource::Volume(_) => Kind::Volume,
+            Resource::Vm(_) => Kind::Vm,
+            Resource::SnapshotPolicy(_) => Kind::SnapshotPolicy,
+        }
+    }
+}
+
+/// Snapshot schedule of one VM, by name or ID, or of the VMs a selector
+/// matches
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnapshotPolicyDef {
+    pub vm: Option<String>,
+    #[serde(default)]
+    pub vm_selector: HashMap<String, String>,
+    /// Interval such as `30m`, `6h` or `1d`
+    pub every: String,
+    #[serde(default = "default_keep")]
+    pub keep: u32,
+    #[serde(default)]
+    pub include_memory: bool,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+fn default_keep() -> u32 {
+    10
+}
+
+impl SnapshotPolicyDef {
+    fn spec(&self, ids: &Ids) -> Result<SnapshotPolicySpec> {
+        if self.vm.is_some() != self.vm_selector.is_empty() {
+            anyhow::bail!("give either vm or vm_selector");
+        }
+        let interval = infrasim_common::types::parse_interval(&self.every)?;
+        Ok(SnapshotPolicySpec {
+            vm_id: self
+                .vm
+                .as_ref()
+                .map(|vm| ids.resolve(stack::Kind::Vm, vm))
+                .unwrap_or_default(),
+            interval_seconds: interval as i64,
+            keep: self.keep,
+            include_memory: self.include_memory,
+            paused: self.paused,
+            vm_selector: self.vm_selector.clone(),
+        })
+    }
+
+    fn changed(want: &SnapshotPolicySpec, have: &SnapshotPolicySpec) -> Vec<&'static str> {
+        stack::changed(&[
+            ("vm", want.vm_id == have.vm_id),
+            ("vm_selector", want.vm_selector == have.vm_selector),
+            ("every", want.interval_seconds == have.interval_seconds),
+            ("keep", want.keep == have.keep),
+            ("include_memory", want.include_memory == have.include_memory),
+            ("paused", want.paused == have.paused),
+        ])
+    }
+}
+
+/// Parse a manifest stream: JSON if it starts with `{` or `[`, else YAML
+pub fn parse(input: &str) -> Result<Vec<Manifest>> {
+    let trimmed = input.trim_start();
+    let mut documents = Vec::new();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        for document in serde_json::Deserializer::from_str(trimmed).into_iter::<Value>() {
+            match document.context("Invalid JSON manifest")? {
+                Value::Array(items) => documents.extend(items),
+                value => documents.push(value),
+            }
+        }
+    } else {
+        for document in serde_yaml::Deserializer::from_str(input) {
+            let value = serde_yaml::Value::deserialize(document).context("Invalid YAML manifest")?;
+            if !value.is_null() {
+                documents.push(serde_json::to_value(value).context("Invalid YAML manifest")?);
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut manifests = Vec::with_capacity(documents.len());
+    for (index, document) in documents.into_iter().enumerate() {
+        let header: Header = serde_json::from_value(document)
+            .with_context(|| format!("Invalid manifest #{}", index + 1))?;
+        if header.name.is_empty() {
+            anyhow::bail!("Manifest #{} has no name", index + 1);
+        }
+        if !seen.insert((header.kind, header.name.clone())) {
+            anyhow::bail!("{} '{}' is declared twice", header.kind, header.name);
+        }
+        let spec = header.spec;
+        let context = || format!("Invalid spec of {} '{}'", header.kind, header.name);
+        let resource = match header.kind {
+            Kind::Network => Resource::Network(serde_json::from_value(spec.clone()).with_context(context)?),
+            Kind::Volume => Resource::Volume(serde_json::from_value(spec.clone()).with_context(context)?),
+            Kind::Vm => Resource::Vm(serde_json::from_value(spec.clone()).with_context(context)?),
+            Kind::SnapshotPolicy => {
+                Resource::SnapshotPolicy(serde_json::from_value(spec.clone()).with_context(context)?)
+            }
+        };
+        manifests.push(Manifest {
+            name: header.name,
+            labels: header.labels,
+            resource,
+            spec,
+        });
+    }
+    manifests.sort_by_key(Manifest::kind);
+    Ok(manifests)
+}
+
+/// Multi-document YAML of manifests, as [`Existing::export`] returns them
+pub fn render(documents: &[Value]) -> Result<String> {
+    let mut output = String::new();
+    for document in documents {
+        output.push_str("---\n");
+        output.push_str(&serde_yaml::to_string(document)?);
+    }
+    Ok(output)
+}
+
+/// One step of applying manifests
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub kind: Kind,
+    pub name: String,
+    pub action: Action,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Fields that differ from the manifest
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<&'static str>,
+}
+
+impl TableDisplay for Change {
+    fn headers() -> Vec<&'static str> {
+        vec!["Kind", "Name", "Action", "ID", "Changes"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.kind.to_string(),
+            self.name.clone(),
+            self.action.to_string(),
+            self.id.clone().unwrap_or_default(),
+            self.fields.join(", "),
+        ]
+    }
+}
+
+/// The daemon's resources of the kinds manifests declare, by name
+#[derive(Default)]
+pub struct Existing {
+    networks: BTreeMap<String, Network>,
+    volumes: BTreeMap<String, Volume>,
+    vms: BTreeMap<String, Vm>,
+    policies: BTreeMap<String, SnapshotPolicy>,
+}
+
+impl Existing {
+    pub async fn fetch(client: &mut DaemonClient) -> Result<Self> {
+        Ok(Self {
+            networks: by_name(client.list_networks().await?, |n| n.meta.as_ref()),
+            volumes: by_name(client.list_volumes().await?, |v| v.meta.as_ref()),
+            vms: by_name(client.list_vms().await?, |vm| vm.meta.as_ref()),
+            policies: by_name(client.list_snapshot_policies(None).await?, |p| p.meta.as_ref()),
+        })
+    }
+
+    /// Name, metadata and kind of every resource
+    fn all(&self) -> Vec<(Kind, &String, Option<&ResourceMeta>)> {
+        let networks = self.networks.iter().map(|(n, r)| (Kind::Network, n, r.meta.as_ref()));
+        let volumes = self.volumes.iter().map(|(n, r)| (Kind::Volume, n, r.meta.as_ref()));
+        let vms = self.vms.iter().map(|(n, r)| (Kind::Vm, n, r.meta.as_ref()));
+        let policies = self.policies.iter().map(|(n, r)| (Kind::SnapshotPolicy, n, r.meta.as_ref()));
+        networks.chain(volumes).chain(vms).chain(policies).collect()
+    }
+
+    /// Manifests of the resources whose labels match `selector`, referring
+    /// to each other by name
+    pub fn export(&self, selector: &HashMap<String, String>) -> Vec<Value> {
+        let names = |ids: Vec<(&String, Option<&ResourceMeta>)>| -> HashMap<String, String> {
+            ids.into_iter()
+                .filter_map(|(name, meta)| Some((meta?.id.clone(), name.clone())))
+                .collect()
+        };
+        let volume_names = names(self.volumes.iter().map(|(n, v)| (n, v.meta.as_ref())).collect());
+        let network_names = names(self.networks.iter().map(|(n, net)| (n, net.meta.as_ref())).collect());
+        let vm_names = names(self.vms.iter().map(|(n, vm)| (n, vm.meta.as_ref())).collect());
+        let name_of = |names: &HashMap<String, String>, id: &str| names.get(id).cloned().unwrap_or_else(|| id.to_string());
+
+        let mut documents = Vec::new();
+        let mut push = |kind: Kind, name: &String, meta: Option<&ResourceMeta>, spec: Value| {
+            let labels = meta.map(|m| m.labels.clone()).unwrap_or_default();
+            if selector.iter().all(|(k, v)| labels.get(k) == Some(v)) {
+                let labels: BTreeMap<_, _> = labels.into_iter().collect();
+                documents.push(json!({"kind": kind, "name": name, "labels": labels, "spec": spec}));
+            }
+        };
+
+        for (name, net) in &self.networks {
+            let spec = net.spec.clone().unwrap_or_default();
+            let mode = match NetworkMode::try_from(spec.mode) {
+                Ok(NetworkMode::VmnetShared) => "vmnet-shared",
+                Ok(NetworkMode::VmnetBridged) => "vmnet-bridged",
+                _ => "user",
+            };
+            let mut doc = json!({"mode": mode, "cidr": spec.cidr, "dhcp": spec.dhcp_enabled, "mtu": spec.mtu});
+            insert_nonempty(&mut doc, "gateway", &spec.gateway);
+            insert_nonempty(&mut doc, "dns", &spec.dns);
+            push(Kind::Network, name, net.meta.as_ref(), doc);
+        }
+
+        for (name, vol) in &self.volumes {
+            let spec = vol.spec.clone().unwrap_or_default();
+            let kind = match VolumeKind::try_from(spec.kind) {
+                Ok(VolumeKind::Weights) => "weights",
+                Ok(VolumeKind::Device) => "device",
+                _ => "disk",
+            };
+            let mut doc = json!({
+                "source": spec.source,
+                "kind": kind,
+                "format": spec.format,
+                "read_only": spec.read_only,
+                "overlay": spec.overlay,
+            });
+            if spec.size_bytes > 0 {
+                doc["size"] = json!(spec.size_bytes);
+            }
+            if let Some(integrity) = spec.integrity.as_ref().filter(|i| i.scheme == "sha256") {
+                insert_nonempty(&mut doc, "sha256", &integrity.expected_digest);
+            }
+            if let Some(encryption) = &spec.encryption {
+                insert_nonempty(&mut doc, "encryption_key", &encryption.key_id);
+                insert_nonempty(&mut doc, "cipher", &encryption.cipher);
+            }
+            push(Kind::Volume, name, vol.meta.as_ref(), doc);
+        }
+
+        for (name, vm) in &self.vms {
+            let spec = vm.spec.clone().unwrap_or_default();
+            let mut doc = json!({
+                "arch": spec.arch,
+                "machine": spec.machine,
+                "cpus": spec.cpu_cores,
+                "memory": spec.memory_mb,
+                "boot_disk": name_of(&volume_names, &spec.boot_disk_id),
+                "volumes": spec.volume_ids.iter().map(|id| name_of(&volume_names, id)).collect::<Vec<_>>(),
+                "networks": spec.network_ids.iter().map(|id| name_of(&network_names, id)).collect::<Vec<_>>(),
+                "enable_tpm": spec.enable_tpm,
+                "compatibility_mode": spec.compatibility_mode,
+                "running": is_active(vm),
+            });
+            insert_nonempty(&mut doc, "qos_profile", &spec.qos_profile_id);
+            push(Kind::Vm, name, vm.meta.as_ref(), doc);
+        }
+
+        for (name, policy) in &self.policies {
+            let spec = policy.spec.clone().unwrap_or_default();
+            let mut doc = json!({
+                "every": format_interval(spec.interval_seconds.max(0) as u64),
+                "keep": spec.keep,
+                "include_memory": spec.include_memory,
+                "paused": spec.paused,
+            });
+            if spec.vm_selector.is_empty() {
+                doc["vm"] = json!(name_of(&vm_names, &spec.vm_id));
+            } else {
+                let selector: BTreeMap<_, _> = spec.vm_selector.into_iter().collect();
+                doc["vm_selector"] = json!(selector);
+            }
+            push(Kind::SnapshotPolicy, name, policy.meta.as_ref(), doc);
+        }
+
+        documents
+    }
+}
+
+/// Set `key` of an object to `value` unless it is empty
+fn insert_nonempty(doc: &mut Value, key: &str, value: &str) {
+    if !value.is_empty() {
+        doc[key] = json!(value);
+    }
+}
+
+fn by_name<T>(items: Vec<T>, meta: impl Fn(&T) -> Option<&ResourceMeta>) -> BTreeMap<String, T> {
+    items
+        .into_iter()
+        .filter_map(|item| Some((meta(&item)?.name.clone(), item)))
+        .collect()
+}
+
+/// Kind used to resolve references of a manifest kind
+fn reference_kind(kind: Kind) -> Option<stack::Kind> {
+    match kind {
+        Kind::Network => Some(stack::Kind::Network),
+        Kind::Volume => Some(stack::Kind::Volume),
+        Kind::Vm => Some(stack::Kind::Vm),
+        Kind::SnapshotPolicy => None,
+    }
+}
+
+/// Changes needed to converge the daemon to a set of manifests
+pub struct Plan {
+    manifests: Vec<Manifest>,
+    existing: Existing,
+    ids: Ids,
+    pub changes: Vec<Change>,
+}
+
+impl Plan {
+    /// Compare manifests with the daemon's resources; with `prune`, also
+    /// delete resources carrying its labels that no manifest names
+    pub fn diff(manifests: Vec<Manifest>, existing: Existing, prune: Option<&HashMap<String, String>>) -> Result<Self> {
+        let mut ids = Ids::default();
+        for (kind, name, meta) in existing.all() {
+            if let (Some(kind), Some(meta)) = (reference_kind(kind), meta) {
+                ids.set(kind, name, meta.id.clone());
+            }
+        }
+
+        let mut changes = Vec::new();
+        let mut declared = HashSet::new();
+        for manifest in &manifests {
+            let kind = manifest.kind();
+            let name = &manifest.name;
+            declared.insert((kind, name.clone()));
+            let context = || format!("{} '{}'", kind, name);
+
+            let (current, on_change) = match &manifest.resource {
+                Resource::Network(def) => {
+                    let want = def.spec().with_context(context)?;
+                    let current = existing.networks.get(name).map(|net| {
+                        (id_of(&net.meta), def.changed(&want, &net.spec.clone().unwrap_or_default()))
+                    });
+                    (current, Action::Replace)
+                }
+                Resource::Volume(def) => {
+                    let want = def.spec().with_context(context)?;
+                    let current = existing.volumes.get(name).map(|vol| {
+                        (id_of(&vol.meta), def.changed(&want, &vol.spec.clone().unwrap_or_default()))
+                    });
+                    (current, Action::Replace)
+                }
+                Resource::Vm(def) => {
+                    let vm = existing.vms.get(name);
+                    let current = vm.map(|vm| {
+                        let mut fields = def.changed(&ids, &vm.spec.clone().unwrap_or_default());
+                        let labels = vm.meta.as_ref().map(|m| &m.labels);
+                        if manifest.labels.iter().any(|(k, v)| labels.and_then(|l| l.get(k)) != Some(v)) {
+                            fields.push("labels");
+                        }
+                        (id_of(&vm.meta), fields)
+                    });
+                    if let Some(change) = power_change(def, vm) {
+                        changes.push(Change {
+                            kind,
+                            name: name.clone(),
+                            action: change,
+                            id: vm.map(|vm| id_of(&vm.meta)),
+                            fields: Vec::new(),
+                        });
+                    }
+                    (current, Action::Update)
+                }
+                Resource::SnapshotPolicy(def) => {
+                    let want = def.spec(&ids).with_context(context)?;
+                    let current = existing.policies.get(name).map(|policy| {
+                        let have = policy.spec.clone().unwrap_or_default();
+                        (id_of(&policy.meta), SnapshotPolicyDef::changed(&want, &have))
+                    });
+                    (current, Action::Replace)
+                }
+            };
+
+            match current {
+                Some((_, fields)) if fields.is_empty() => {}
+                Some((id, fields)) => changes.push(Change {
+                    kind,
+                    name: name.clone(),
+                    action: on_change,
+                    id: Some(id),
+                    fields,
+                }),
+                None => {
+                    if let Some(kind) = reference_kind(kind) {
+                        ids.set(kind, name, format!("<new {} {}>", kind, name));
+                    }
+                    changes.push(Change {
+                        kind,
+                        name: name.clone(),
+                        action: Action::Create,
+                        id: None,
+                        fields: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        if let Some(selector) = prune {
+            for (kind, name, meta) in existing.all() {
+                let Some(meta) = meta else { continue };
+                let matches = selector.iter().all(|(k, v)| meta.labels.get(k) == Some(v));
+                if matches && !declared.contains(&(kind, name.clone())) {
+                    changes.push(Change {
+                        kind,
+                        name: name.clone(),
+                        action: Action::Delete,
+                        id: Some(meta.id.clone()),
+                        fields: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        changes.sort_by_key(|c| c.kind);
+        Ok(Self {
+            manifests,
+            existing,
+            ids,
+            changes,
+        })
+    }
+
+    /// True when the daemon already matches the manifests
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Carry out the plan: deletions dependents-first, then creates and
+    /// updates in kind order, then VM starts and stops
+    pub async fn apply(&self, client: &mut DaemonClient) -> Result<()> {
+        let mut ids = self.ids.clone();
+
+        for change in self.changes.iter().rev() {
+            if !matches!(change.action, Action::Delete | Action::Replace) {
+                continue;
+            }
+            let id = change.id.as_deref().unwrap_or_default();
+            match change.kind {
+                Kind::SnapshotPolicy => client.delete_snapshot_policy(id).await?,
+                Kind::Vm => client.delete_vm(id, true).await?,
+                Kind::Volume => client.delete_volume(id).await?,
+                Kind::Network => client.delete_network(id).await?,
+            }
+            if change.action == Action::Delete {
+                print_success(&format!("Deleted {} '{}'", change.kind, change.name));
+            }
+        }
+
+        let manifests: HashMap<(Kind, &str), &Manifest> =
+            self.manifests.iter().map(|m| ((m.kind(), m.name.as_str()), m)).collect();
+        for change in &self.changes {
+            if matches!(change.action, Action::Delete | Action::Start | Action::Stop) {
+                continue;
+            }
+            let manifest = manifests[&(change.kind, change.name.as_str())];
+            let name = manifest.name.as_str();
+            let labels = manifest.labels.clone();
+            let context = || format!("Failed to {} {} '{}'", change.action, change.kind, name);
+
+            let id = match (&manifest.resource, change.action) {
+                (Resource::Network(def), _) => {
+                    let net = client.create_network(name, def.spec()?, labels).await.with_context(context)?;
+                    id_of(&net.meta)
+                }
+                (Resource::Volume(def), _) => {
+                    let vol = client.create_volume(name, def.spec()?, labels).await.with_context(context)?;
+                    let id = id_of(&vol.meta);
+                    if def.is_remote() {
+                        wait_for_download(client, &id).await?;
+                    }
+                    id
+                }
+                (Resource::Vm(def), Action::Update) => {
+                    let id = change.id.clone().unwrap_or_default();
+                    let current = self.existing.vms[name].spec.clone().unwrap_or_default();
+                    let spec = def.spec(&ids, current.clone());
+                    if spec != current {
+                        client.update_vm(&id, spec).await.with_context(context)?;
+                        print_info(&format!("VM '{}' picks up its new spec when it next starts", name));
+                    }
+                    if change.fields.contains(&"labels") {
+                        client.update_vm_labels(&id, labels, Vec::new()).await.with_context(context)?;
+                    }
+                    id
+                }
+                (Resource::Vm(def), _) => {
+                    let spec = def.spec(&ids, VmSpec::default());
+                    let vm = client.create_vm(name, spec, labels).await.with_context(context)?;
+                    id_of(&vm.meta)
+                }
+                (Resource::SnapshotPolicy(def), _) => {
+                    let spec = def.spec(&ids)?;
+                    let policy = client.create_snapshot_policy(name, spec, labels).await.with_context(context)?;
+                    id_of(&policy.meta)
+                }
+            };
+
+            if let Some(kind) = reference_kind(change.kind) {
+                ids.set(kind, name, id);
+            }
+            print_success(&format!("{} {} '{}'", past_tense(change.action), change.kind, name));
+        }
+
+        for change in &self.changes {
+            let id = ids.resolve(stack::Kind::Vm, &change.name);
+            match change.action {
+                Action::Start => {
+                    client.start_vm(&id).await.with_context(|| format!("Failed to start VM '{}'", change.name))?;
+                }
+                Action::Stop => {
+                    client
+                        .stop_vm(&id, StopMode::Acpi, 0)
+                        .await
+                        .with_context(|| format!("Failed to stop VM '{}'", change.name))?;
+                }
+                _ => continue,
+            }
+            print_success(&format!("{} vm '{}'", past_tense(change.action), change.name));
+        }
+
+        Ok(())
+    }
+}
+
+/// Start or stop needed for a VM to match its manifest's `running`
+fn power_change(def: &VmDef, vm: Option<&Vm>) -> Option<Action> {
+    match (def.running, vm.is_some_and(is_active)) {
+        (true, false) => Some(Action::Start),
+        (false, true) => Some(Action::Stop),
+        _ => None,
+    }
+}
+
+/// Whether a VM counts as running: it holds a QEMU process
+fn is_active(vm: &Vm) -> bool {
+    let state = vm.status.as_ref().and_then(|s| VmState::try_from(s.state).ok());
+    matches!(state, Some(VmState::Running | VmState::Paused | VmState::Suspended))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::VolumeSpec;
+
+    const YAML: &str = r#"
+kind: vm
+name: ci-runner
+labels: {pipeline: ci}
+spec:
+  cpus: 4
+  boot_disk: ci-disk
+---
+kind: volume
+name: ci-disk
+spec:
+  source: /var/lib/infrasim/images/alpine-aarch64.qcow2
+  overlay: true
+---
+"#;
+
+    fn names(manifests: &[Manifest]) -> Vec<(Kind, &str)> {
+        manifests.iter().map(|m| (m.kind(), m.name.as_str())).collect()
+    }
+
+    fn error(input: &str) -> String {
+        format!("{:#}", parse(input).unwrap_err())
+    }
+
+    #[test]
+    fn test_parse_multi_document_yaml() {
+        let manifests = parse(YAML).unwrap();
+        assert_eq!(names(&manifests), vec![(Kind::Volume, "ci-disk"), (Kind::Vm, "ci-runner")]);
+
+        let vm = &manifests[1];
+        assert_eq!(vm.labels.get("pipeline").map(String::as_str), Some("ci"));
+        match &vm.resource {
+            Resource::Vm(def) => {
+                assert_eq!(def.cpus, 4);
+                assert_eq!(def.boot_disk, "ci-disk");
+            }
+            other => panic!("expected a VM, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_json_objects_and_arrays() {
+        let input = r#"
+            [
+                {"kind": "network", "name": "lan", "spec": {"cidr": "10.0.0.0/24"}},
+                {"kind": "volume", "name": "disk", "spec": {"source": "/images/a.qcow2"}}
+            ]
+            {"kind": "snapshot_policy", "name": "hourly", "spec": {"vm": "web", "every": "1h"}}
+        "#;
+        let manifests = parse(input).unwrap();
+        assert_eq!(
+            names(&manifests),
+            vec![(Kind::Network, "lan"), (Kind::Volume, "disk"), (Kind::SnapshotPolicy, "hourly")]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_duplicates() {
+        let input = "kind: vm\nname: web\nspec: {boot_disk: a}\n---\nkind: vm\nname: web\nspec: {boot_disk: b}\n";
+        assert!(error(input).contains("vm 'web' is declared twice"));
+
+        // The same name under another kind is a different resource
+        let input = "kind: volume\nname: web\nspec: {source: /a.qcow2}\n---\nkind: vm\nname: web\nspec: {boot_disk: web}\n";
+        assert_eq!(parse(input).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rejects_empty_names() {
+        assert!(error(r#"{"kind": "network", "name": ""}"#).contains("Manifest #1 has no name"));
+        assert!(error("kind: network\nname: lan\nspec: {}\n---\nkind: vm\nname: ''\n").contains("Manifest #2 has no name"));
+    }
+
+    #[test]
+    fn test_render_round_trips_through_parse() {
+        let meta = |id: &str| {
+            Some(ResourceMeta {
+                id: id.to_string(),
+                labels: HashMap::from([("pipeline".to_string(), "ci".to_string())]),
+                ..Default::default()
+            })
+        };
+        let mut existing = Existing::default();
+        existing.volumes.insert(
+            "ci-disk".to_string(),
+            Volume {
+                meta: meta("vol-1"),
+                spec: Some(VolumeSpec {
+                    source: "/var/lib/infrasim/images/alpine-aarch64.qcow2".to_string(),
+                    format: "qcow2".to_string(),
+                    overlay: true,
+                    ..Default::default()
+                }),
+                status: None,
+            },
+        );
+        existing.vms.insert(
+            "ci-runner".to_string(),
+            Vm {
+                meta: meta("vm-1"),
+                spec: Some(VmSpec {
+                    arch: "aarch64".to_string(),
+                    machine: "virt".to_string(),
+                    cpu_cores: 4,
+                    memory_mb: 2048,
+                    boot_disk_id: "vol-1".to_string(),
+                    ..Default::default()
+                }),
+                status: None,
+            },
+        );
+
+        let documents = existing.export(&HashMap::new());
+        let manifests = parse(&render(&documents).unwrap()).unwrap();
+
+        assert_eq!(names(&manifests), vec![(Kind::Volume, "ci-disk"), (Kind::Vm, "ci-runner")]);
+        for (manifest, document) in manifests.iter().zip(&documents) {
+            assert_eq!(manifest.spec, document["spec"]);
+            assert_eq!(manifest.labels.get("pipeline").map(String::as_str), Some("ci"));
+        }
+        match &manifests[1].resource {
+            Resource::Vm(def) => assert_eq!(def.boot_disk, "ci-disk"),
+            other => panic!("expected a VM, got {:?}", other),
+        }
+    }
+}
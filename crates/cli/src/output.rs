@@ -1,8 +1,29 @@
 //! Output formatting for CLI
 
 use clap::ValueEnum;
+use colored::Colorize;
 use comfy_table::{Table, ContentArrangement, presets::UTF8_FULL};
-use serde::Serialize;
+use infrasim_common::{ErrorCategory, ErrorDetail};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Context the CLI is talking to, shown under tables
+static ACTIVE_CONTEXT: OnceLock<String> = OnceLock::new();
+
+/// Record the named context commands are running against
+pub fn set_active_context(name: &str) {
+    let _ = ACTIVE_CONTEXT.set(name.to_string());
+}
+
+/// Print a table, followed by the active context if one is set
+fn print_table(table: &Table) {
+    println!("{table}");
+    if let Some(context) = ACTIVE_CONTEXT.get() {
+        println!("{}", format!("context: {}", context).dimmed());
+    }
+}
 
 /// Output format
 #[derive(Debug, Clone, Copy, ValueEnum, Default)]
@@ -14,10 +35,143 @@ pub enum OutputFormat {
     Json,
     /// YAML format
     Yaml,
+    /// Comma-separated table columns
+    Csv,
     /// Plain text format
     Plain,
 }
 
+/// `--format` value: an output format or `jsonpath=<expr>`
+#[derive(Debug, Clone)]
+pub enum FormatArg {
+    Format(OutputFormat),
+    JsonPath(FieldPath),
+}
+
+impl FromStr for FormatArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(expr) = s.strip_prefix("jsonpath=") {
+            return expr.parse().map(FormatArg::JsonPath);
+        }
+
+        <OutputFormat as ValueEnum>::from_str(s, true).map(FormatArg::Format).map_err(|_| {
+            format!(
+                "unknown format '{}' (expected table, json, yaml, csv, plain or jsonpath=<expr>)",
+                s
+            )
+        })
+    }
+}
+
+/// Field selected from each item by `--field` or `--format jsonpath=`
+static FIELD: OnceLock<FieldPath> = OnceLock::new();
+
+/// Apply `--format` and `--field`, returning the format commands render with
+///
+/// `jsonpath=<expr>` is shorthand for `--format plain --field <expr>`.
+pub fn configure(format: FormatArg, field: Option<FieldPath>) -> OutputFormat {
+    let (format, field) = match format {
+        FormatArg::Format(format) => (format, field),
+        FormatArg::JsonPath(path) => (OutputFormat::Plain, Some(path)),
+    };
+    if let Some(field) = field {
+        let _ = FIELD.set(field);
+    }
+    format
+}
+
+/// Segment of a field path
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// JSONPath-style field selector such as `{.status.state}` or `.meta.labels['app']`
+///
+/// Paths apply to the serialized form of an item, i.e. what `--format json`
+/// prints. Supports `.key`, `['key']`, `[n]` and `[*]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPath {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for FieldPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expr = s.trim();
+        let expr = expr
+            .strip_prefix('{')
+            .and_then(|e| e.strip_suffix('}'))
+            .unwrap_or(expr)
+            .trim();
+        let mut rest = expr.strip_prefix('$').unwrap_or(expr);
+
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let end = after
+                    .find(']')
+                    .ok_or_else(|| format!("unclosed '[' in field path '{}'", s))?;
+                let inner = after[..end].trim();
+                segments.push(if inner == "*" {
+                    Segment::Wildcard
+                } else if let Ok(index) = inner.parse() {
+                    Segment::Index(index)
+                } else {
+                    let key = inner
+                        .strip_prefix('\'')
+                        .and_then(|k| k.strip_suffix('\''))
+                        .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')))
+                        .ok_or_else(|| format!("invalid subscript '[{}]' in field path '{}'", inner, s))?;
+                    Segment::Key(key.to_string())
+                });
+                rest = &after[end + 1..];
+            } else {
+                let after = rest.strip_prefix('.').unwrap_or(rest);
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    return Err(format!("empty key in field path '{}'", s));
+                }
+                segments.push(if &after[..end] == "*" {
+                    Segment::Wildcard
+                } else {
+                    Segment::Key(after[..end].to_string())
+                });
+                rest = &after[end..];
+            }
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl FieldPath {
+    /// Values the path selects from `value`; empty if nothing matches
+    pub fn select(&self, value: &Value) -> Vec<Value> {
+        let mut current = vec![value.clone()];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|v| match (segment, v) {
+                    (Segment::Key(key), Value::Object(mut map)) => map.remove(key).into_iter().collect(),
+                    (Segment::Index(i), Value::Array(mut items)) if *i < items.len() => {
+                        vec![items.swap_remove(*i)]
+                    }
+                    (Segment::Wildcard, Value::Array(items)) => items,
+                    (Segment::Wildcard, Value::Object(map)) => map.into_values().collect(),
+                    _ => Vec::new(),
+                })
+                .collect();
+        }
+        current
+    }
+}
+
 /// Trait for items that can be displayed in a table
 pub trait TableDisplay {
     fn headers() -> Vec<&'static str>;
@@ -26,6 +180,16 @@ pub trait TableDisplay {
 
 /// Print a single item
 pub fn print_item<T: Serialize + TableDisplay>(item: &T, format: OutputFormat) {
+    if let Some(field) = FIELD.get() {
+        let selected = select(field, item);
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&selected).unwrap_or_default()),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&selected).unwrap_or_default()),
+            _ => println!("{}", field_text(&selected)),
+        }
+        return;
+    }
+
     match format {
         OutputFormat::Table => {
             let mut table = Table::new();
@@ -36,13 +200,16 @@ pub fn print_item<T: Serialize + TableDisplay>(item: &T, format: OutputFormat) {
             table.set_header(T::headers());
             table.add_row(item.row());
             
-            println!("{table}");
+            print_table(&table);
         }
         OutputFormat::Json => {
             println!("{}", serde_json::to_string_pretty(item).unwrap_or_default());
         }
         OutputFormat::Yaml => {
-            println!("{}", serde_yaml::to_string(item).unwrap_or_default());
+            print!("{}", serde_yaml::to_string(item).unwrap_or_default());
+        }
+        OutputFormat::Csv => {
+            print_csv::<T>(std::slice::from_ref(item));
         }
         OutputFormat::Plain => {
             let row = item.row();
@@ -55,12 +222,33 @@ pub fn print_item<T: Serialize + TableDisplay>(item: &T, format: OutputFormat) {
 
 /// Print a list of items
 pub fn print_list<T: Serialize + TableDisplay>(items: &[T], format: OutputFormat) {
-    if items.is_empty() {
-        println!("No items found.");
+    if let Some(field) = FIELD.get() {
+        let selected: Vec<Value> = items.iter().map(|item| select(field, item)).collect();
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&selected).unwrap_or_default()),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&selected).unwrap_or_default()),
+            _ => {
+                for value in &selected {
+                    println!("{}", field_text(value));
+                }
+            }
+        }
         return;
     }
 
     match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(items).unwrap_or_default());
+        }
+        OutputFormat::Yaml => {
+            print!("{}", serde_yaml::to_string(items).unwrap_or_default());
+        }
+        OutputFormat::Csv => {
+            print_csv(items);
+        }
+        _ if items.is_empty() => {
+            println!("No items found.");
+        }
         OutputFormat::Table => {
             let mut table = Table::new();
             table
@@ -72,13 +260,7 @@ pub fn print_list<T: Serialize + TableDisplay>(items: &[T], format: OutputFormat
                 table.add_row(item.row());
             }
             
-            println!("{table}");
-        }
-        OutputFormat::Json => {
-            println!("{}", serde_json::to_string_pretty(items).unwrap_or_default());
-        }
-        OutputFormat::Yaml => {
-            println!("{}", serde_yaml::to_string(items).unwrap_or_default());
+            print_table(&table);
         }
         OutputFormat::Plain => {
             for (i, item) in items.iter().enumerate() {
@@ -94,6 +276,65 @@ pub fn print_list<T: Serialize + TableDisplay>(items: &[T], format: OutputFormat
     }
 }
 
+/// Apply the field path to an item's serialized form
+///
+/// A single match is returned as-is, several as an array.
+fn select<T: Serialize>(field: &FieldPath, item: &T) -> Value {
+    let value = serde_json::to_value(item).unwrap_or(Value::Null);
+    let mut selected = field.select(&value);
+    match selected.len() {
+        0 => Value::Null,
+        1 => selected.remove(0),
+        _ => Value::Array(selected),
+    }
+}
+
+/// Selected value as text: strings unquoted, arrays space-separated
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(field_text).collect::<Vec<_>>().join(" "),
+        other => other.to_string(),
+    }
+}
+
+/// Print table headers and rows as CSV
+fn print_csv<T: TableDisplay>(items: &[T]) {
+    let line = |cells: Vec<String>| cells.iter().map(|c| csv_escape(c)).collect::<Vec<_>>().join(",");
+
+    println!("{}", line(T::headers().into_iter().map(String::from).collect()));
+    for item in items {
+        println!("{}", line(item.row()));
+    }
+}
+
+/// Quote a CSV cell if it contains a separator, quote or newline
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Serialize a proto enum field by variant name rather than number
+pub fn proto_enum<E, S>(value: &i32, serializer: S) -> Result<S::Ok, S::Error>
+where
+    E: TryFrom<i32> + std::fmt::Debug,
+    S: Serializer,
+{
+    match E::try_from(*value) {
+        Ok(variant) => serializer.collect_str(&format_args!("{:?}", variant)),
+        Err(_) => serializer.serialize_i32(*value),
+    }
+}
+
+/// Serialize a proto bytes field as hex
+pub fn hex_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
 /// Print a simple message
 pub fn print_message(message: &str, format: OutputFormat) {
     match format {
@@ -116,6 +357,53 @@ pub fn print_error(message: &str) {
     eprintln!("❌ {}", message);
 }
 
+/// Print a failed command's error and return the process exit code
+///
+/// Exit codes follow the error category, so scripts can branch on them:
+/// 2 invalid request, 3 not found, 4 conflict, 5 auth, 6 host capacity or
+/// support, 7 unavailable, 1 anything else.
+pub fn print_failure(e: &anyhow::Error, format: OutputFormat) -> i32 {
+    let detail = ErrorDetail::from_anyhow(e);
+    match format {
+        OutputFormat::Json => {
+            eprintln!("{}", serde_json::json!({ "error": detail }));
+        }
+        _ => {
+            // Keep the context a command added, e.g. "Failed to start VM", but
+            // not the debug dump of the gRPC status under it
+            let from_daemon = |c: &&(dyn std::error::Error + 'static)| {
+                c.is::<tonic::Status>() || c.is::<tonic::transport::Error>()
+            };
+            let message = if e.chain().any(|c| from_daemon(&c)) {
+                e.chain()
+                    .take_while(|c| !from_daemon(c))
+                    .map(|c| c.to_string())
+                    .chain(std::iter::once(detail.message.clone()))
+                    .collect::<Vec<_>>()
+                    .join(": ")
+            } else {
+                format!("{:#}", e)
+            };
+            print_error(&message);
+            if let Some(hint) = &detail.hint {
+                eprintln!("   {} {}", "hint:".dimmed(), hint);
+            }
+            let retry = if detail.retryable { ", retryable" } else { "" };
+            eprintln!("   {}", format!("code: {}{}", detail.code, retry).dimmed());
+        }
+    }
+
+    match detail.category {
+        ErrorCategory::InvalidRequest => 2,
+        ErrorCategory::NotFound => 3,
+        ErrorCategory::Conflict => 4,
+        ErrorCategory::Auth => 5,
+        ErrorCategory::Host => 6,
+        ErrorCategory::Unavailable => 7,
+        ErrorCategory::Internal => 1,
+    }
+}
+
 /// Print warning message
 pub fn print_warning(message: &str) {
     println!("⚠️  {}", message);
@@ -125,12 +413,3 @@ pub fn print_warning(message: &str) {
 pub fn print_info(message: &str) {
     println!("ℹ️  {}", message);
 }
-
-// Add serde_yaml dependency for YAML output
-mod serde_yaml {
-    pub fn to_string<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, ()> {
-        // Simple YAML-like output for now
-        // In production, use the actual serde_yaml crate
-        serde_json::to_string_pretty(value).map_err(|_| ())
-    }
-}
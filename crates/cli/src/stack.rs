@@ -0,0 +1,1023 @@
+//! Stack Files
+//!
+//! A stack file declares networks, volumes, VMs and port forwards in YAML.
+//! [`Plan::diff`] compares it with the resources the daemon holds for the
+//! stack and [`Plan::apply`] converges the daemon to it. Resources belong to
+//! a stack through the [`STACK_LABEL`] and [`KEY_LABEL`] labels, so anything
+//! created outside the stack is never touched.
+//!
+//! ```yaml
+//! name: lab
+//! networks:
+//!   lan:
+//!     cidr: 192.168.100.0/24
+//! volumes:
+//!   disk:
+//!     source: /var/lib/infrasim/images/kali-xfce-aarch64.qcow2
+//!     overlay: true
+//! vms:
+//!   kali:
+//!     cpus: 4
+//!     memory: 4096
+//!     boot_disk: disk
+//!     networks: [lan]
+//! port_forwards:
+//!   kali-ssh:
+//!     vm: kali
+//!     host_port: 2222
+//!     guest_port: 22
+//! ```
+//!
+//! References between resources use the keys of the stack file; anything
+//! that is not a key is passed to the daemon as a resource ID.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use crate::client::DaemonClient;
+use crate::commands::volume::wait_for_download;
+use crate::generated::{
+    IntegrityConfig, Network, NetworkMode, NetworkSpec, PortForward, PortForwardSpec,
+    PortProtocol, ResourceMeta, StopMode, Vm, VmSpec, VmState, Volume, VolumeEncryption, VolumeKind,
+    VolumeSpec,
+};
+use crate::output::{print_info, print_success, TableDisplay};
+
+/// Label naming the stack a resource belongs to
+pub const STACK_LABEL: &str = "infrasim.io/stack";
+
+/// Label holding a resource's key in its stack file
+pub const KEY_LABEL: &str = "infrasim.io/stack-key";
+
+/// Parsed stack file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StackFile {
+    pub name: String,
+    #[serde(default)]
+    pub networks: BTreeMap<String, NetworkDef>,
+    #[serde(default)]
+    pub volumes: BTreeMap<String, VolumeDef>,
+    #[serde(default)]
+    pub vms: BTreeMap<String, VmDef>,
+    #[serde(default)]
+    pub port_forwards: BTreeMap<String, PortForwardDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkDef {
+    /// user, vmnet-shared or vmnet-bridged
+    #[serde(default = "default_network_mode")]
+    pub mode: String,
+    #[serde(default = "default_cidr")]
+    pub cidr: String,
+    pub gateway: Option<String>,
+    pub dns: Option<String>,
+    #[serde(default = "default_true")]
+    pub dhcp: bool,
+    #[serde(default = "default_mtu")]
+    pub mtu: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VolumeDef {
+    /// OCI reference, file path or URL
+    pub source: String,
+    /// disk, weights or device
+    #[serde(default = "default_volume_kind")]
+    pub kind: String,
+    #[serde(default = "default_volume_format")]
+    pub format: String,
+    pub size: Option<i64>,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub overlay: bool,
+    /// Expected sha256 digest, required for http/https sources
+    pub sha256: Option<String>,
+    /// Daemon keyring key to encrypt the volume with; a new key gets a generated passphrase
+    pub encryption_key: Option<String>,
+    /// Cipher for encrypted volumes (default aes-256)
+    pub cipher: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VmDef {
+    #[serde(default = "default_arch")]
+    pub arch: String,
+    #[serde(default = "default_machine")]
+    pub machine: String,
+    #[serde(default = "default_cpus")]
+    pub cpus: i32,
+    /// Memory in MB
+    #[serde(default = "default_memory")]
+    pub memory: i64,
+    pub boot_disk: String,
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    #[serde(default)]
+    pub networks: Vec<String>,
+    /// QoS profile ID
+    pub qos_profile: Option<String>,
+    #[serde(default)]
+    pub enable_tpm: bool,
+    #[serde(default)]
+    pub compatibility_mode: bool,
+    /// Whether the VM should be running after apply
+    #[serde(default = "default_true")]
+    pub running: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PortForwardDef {
+    pub vm: String,
+    /// Defaults to the VM's first interface
+    pub network: Option<String>,
+    pub host_port: u16,
+    pub guest_port: u16,
+    /// tcp or udp
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    #[serde(default = "default_host_addr")]
+    pub host_addr: String,
+}
+
+fn default_network_mode() -> String {
+    "user".to_string()
+}
+
+fn default_cidr() -> String {
+    "192.168.100.0/24".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_mtu() -> i32 {
+    1500
+}
+
+fn default_volume_kind() -> String {
+    "disk".to_string()
+}
+
+fn default_volume_format() -> String {
+    "qcow2".to_string()
+}
+
+fn default_arch() -> String {
+    "aarch64".to_string()
+}
+
+fn default_machine() -> String {
+    "virt".to_string()
+}
+
+fn default_cpus() -> i32 {
+    2
+}
+
+fn default_memory() -> i64 {
+    2048
+}
+
+fn default_protocol() -> String {
+    "tcp".to_string()
+}
+
+fn default_host_addr() -> String {
+    "127.0.0.1".to_string()
+}
+
+impl StackFile {
+    /// Read and parse a stack file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Invalid stack file {}", path.display()))?;
+        if file.name.is_empty() {
+            anyhow::bail!("Stack file {} has no name", path.display());
+        }
+        Ok(file)
+    }
+
+    /// A stack with no resources; applying it destroys the stack
+    pub fn empty(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            networks: BTreeMap::new(),
+            volumes: BTreeMap::new(),
+            vms: BTreeMap::new(),
+            port_forwards: BTreeMap::new(),
+        }
+    }
+
+    /// Daemon-side name of a resource
+    fn resource_name(&self, key: &str) -> String {
+        format!("{}-{}", self.name, key)
+    }
+
+    /// Labels tying a resource to this stack
+    fn labels(&self, key: &str) -> HashMap<String, String> {
+        HashMap::from([
+            (STACK_LABEL.to_string(), self.name.clone()),
+            (KEY_LABEL.to_string(), key.to_string()),
+        ])
+    }
+}
+
+impl NetworkDef {
+    pub(crate) fn spec(&self) -> Result<NetworkSpec> {
+        let mode = match self.mode.as_str() {
+            "user" => NetworkMode::User,
+            "vmnet-shared" => NetworkMode::VmnetShared,
+            "vmnet-bridged" => NetworkMode::VmnetBridged,
+            other => anyhow::bail!(
+                "Unknown network mode '{}' (expected user, vmnet-shared or vmnet-bridged)",
+                other
+            ),
+        };
+
+        Ok(NetworkSpec {
+            mode: mode as i32,
+            cidr: self.cidr.clone(),
+            gateway: self.gateway.clone().unwrap_or_default(),
+            dns: self.dns.clone().unwrap_or_default(),
+            dhcp_enabled: self.dhcp,
+            mtu: self.mtu,
+        })
+    }
+
+    /// Fields of `have` that differ from `want`, this definition's spec
+    pub(crate) fn changed(&self, want: &NetworkSpec, have: &NetworkSpec) -> Vec<&'static str> {
+        changed(&[
+            ("mode", want.mode == have.mode),
+            ("cidr", want.cidr == have.cidr),
+            ("gateway", self.gateway.is_none() || want.gateway == have.gateway),
+            ("dns", self.dns.is_none() || want.dns == have.dns),
+            ("dhcp", want.dhcp_enabled == have.dhcp_enabled),
+            ("mtu", want.mtu == have.mtu),
+        ])
+    }
+}
+
+impl VolumeDef {
+    pub(crate) fn spec(&self) -> Result<VolumeSpec> {
+        let kind = match self.kind.as_str() {
+            "disk" => VolumeKind::Disk,
+            "weights" => VolumeKind::Weights,
+            "device" => VolumeKind::Device,
+            other => anyhow::bail!(
+                "Unknown volume kind '{}' (expected disk, weights or device)",
+                other
+            ),
+        };
+        if self.is_remote() && self.sha256.is_none() {
+            anyhow::bail!("sha256 is required for http/https sources");
+        }
+
+        Ok(VolumeSpec {
+            kind: kind as i32,
+            source: self.source.clone(),
+            integrity: Some(match &self.sha256 {
+                Some(digest) => IntegrityConfig {
+                    scheme: "sha256".to_string(),
+                    expected_digest: digest.clone(),
+                    ..Default::default()
+                },
+                None => IntegrityConfig::default(),
+            }),
+            read_only: self.read_only,
+            size_bytes: self.size.unwrap_or(0),
+            format: self.format.clone(),
+            overlay: self.overlay,
+            encryption: self.encryption_key.as_ref().map(|key_id| VolumeEncryption {
+                cipher: self.cipher.clone().unwrap_or_default(),
+                key_id: key_id.clone(),
+                secret: Vec::new(),
+            }),
+        })
+    }
+
+    pub(crate) fn changed(&self, want: &VolumeSpec, have: &VolumeSpec) -> Vec<&'static str> {
+        let digest = |spec: &VolumeSpec| {
+            spec.integrity
+                .as_ref()
+                .map(|i| i.expected_digest.clone())
+                .unwrap_or_default()
+        };
+        let key = |spec: &VolumeSpec| spec.encryption.as_ref().map(|e| e.key_id.clone());
+        let cipher = |spec: &VolumeSpec| spec.encryption.as_ref().map(|e| e.cipher.clone());
+        changed(&[
+            ("kind", want.kind == have.kind),
+            ("source", want.source == have.source),
+            ("format", want.format == have.format),
+            ("size", self.size.is_none() || want.size_bytes == have.size_bytes),
+            ("read_only", want.read_only == have.read_only),
+            ("overlay", want.overlay == have.overlay),
+            ("sha256", self.sha256.is_none() || digest(want) == digest(have)),
+            ("encryption_key", key(want) == key(have)),
+            ("cipher", self.cipher.is_none() || cipher(want) == cipher(have)),
+        ])
+    }
+
+    pub(crate) fn is_remote(&self) -> bool {
+        self.source.starts_with("http://") || self.source.starts_with("https://")
+    }
+}
+
+impl VmDef {
+    /// Apply this definition on top of `base`, keeping fields stacks don't manage
+    pub(crate) fn spec(&self, ids: &Ids, base: VmSpec) -> VmSpec {
+        VmSpec {
+            arch: self.arch.clone(),
+            machine: self.machine.clone(),
+            cpu_cores: self.cpus,
+            memory_mb: self.memory,
+            volume_ids: self.volumes.iter().map(|v| ids.resolve(Kind::Volume, v)).collect(),
+            network_ids: self.networks.iter().map(|n| ids.resolve(Kind::Network, n)).collect(),
+            qos_profile_id: self.qos_profile.clone().unwrap_or_default(),
+            enable_tpm: self.enable_tpm,
+            boot_disk_id: ids.resolve(Kind::Volume, &self.boot_disk),
+            compatibility_mode: self.compatibility_mode,
+            ..base
+        }
+    }
+
+    pub(crate) fn changed(&self, ids: &Ids, have: &VmSpec) -> Vec<&'static str> {
+        let want = self.spec(ids, have.clone());
+        changed(&[
+            ("arch", want.arch == have.arch),
+            ("machine", want.machine == have.machine),
+            ("cpus", want.cpu_cores == have.cpu_cores),
+            ("memory", want.memory_mb == have.memory_mb),
+            ("boot_disk", want.boot_disk_id == have.boot_disk_id),
+            ("volumes", want.volume_ids == have.volume_ids),
+            ("networks", want.network_ids == have.network_ids),
+            ("qos_profile", want.qos_profile_id == have.qos_profile_id),
+            ("enable_tpm", want.enable_tpm == have.enable_tpm),
+            ("compatibility_mode", want.compatibility_mode == have.compatibility_mode),
+        ])
+    }
+}
+
+impl PortForwardDef {
+    fn spec(&self, ids: &Ids) -> Result<PortForwardSpec> {
+        let protocol = match self.protocol.as_str() {
+            "tcp" => PortProtocol::Tcp,
+            "udp" => PortProtocol::Udp,
+            other => anyhow::bail!("Unknown protocol '{}' (expected tcp or udp)", other),
+        };
+
+        Ok(PortForwardSpec {
+            vm_id: ids.resolve(Kind::Vm, &self.vm),
+            network_id: self
+                .network
+                .as_ref()
+                .map(|n| ids.resolve(Kind::Network, n))
+                .unwrap_or_default(),
+            protocol: protocol as i32,
+            host_addr: self.host_addr.clone(),
+            host_port: self.host_port as u32,
+            guest_port: self.guest_port as u32,
+        })
+    }
+
+    fn changed(&self, want: &PortForwardSpec, have: &PortForwardSpec) -> Vec<&'static str> {
+        changed(&[
+            ("vm", want.vm_id == have.vm_id),
+            ("network", self.network.is_none() || want.network_id == have.network_id),
+            ("protocol", want.protocol == have.protocol),
+            ("host_addr", want.host_addr == have.host_addr),
+            ("host_port", want.host_port == have.host_port),
+            ("guest_port", want.guest_port == have.guest_port),
+        ])
+    }
+}
+
+/// Names of the checks that failed
+pub(crate) fn changed(checks: &[(&'static str, bool)]) -> Vec<&'static str> {
+    checks
+        .iter()
+        .filter(|(_, same)| !same)
+        .map(|(field, _)| *field)
+        .collect()
+}
+
+/// Resource kinds, in the order they are created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Network,
+    Volume,
+    Vm,
+    PortForward,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Network => "network",
+            Self::Volume => "volume",
+            Self::Vm => "vm",
+            Self::PortForward => "port forward",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Create,
+    /// Change in place (VMs only)
+    Update,
+    /// Delete and recreate
+    Replace,
+    Delete,
+    Start,
+    Stop,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Replace => "replace",
+            Self::Delete => "delete",
+            Self::Start => "start",
+            Self::Stop => "stop",
+        })
+    }
+}
+
+/// One step of a plan
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub kind: Kind,
+    pub key: String,
+    pub action: Action,
+    /// Daemon ID of the existing resource
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Fields that differ from the stack file
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<&'static str>,
+}
+
+impl TableDisplay for Change {
+    fn headers() -> Vec<&'static str> {
+        vec!["Kind", "Key", "Action", "ID", "Changes"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.kind.to_string(),
+            self.key.clone(),
+            self.action.to_string(),
+            self.id.clone().unwrap_or_default(),
+            self.fields.join(", "),
+        ]
+    }
+}
+
+/// Resources the daemon holds for a stack, by key
+#[derive(Default)]
+pub struct Existing {
+    networks: BTreeMap<String, Network>,
+    volumes: BTreeMap<String, Volume>,
+    vms: BTreeMap<String, Vm>,
+    port_forwards: BTreeMap<String, PortForward>,
+}
+
+impl Existing {
+    /// Fetch the resources labelled with `stack`
+    pub async fn fetch(client: &mut DaemonClient, stack: &str) -> Result<Self> {
+        Ok(Self {
+            networks: owned(client.list_networks().await?, stack, |n| n.meta.as_ref()),
+            volumes: owned(client.list_volumes().await?, stack, |v| v.meta.as_ref()),
+            vms: owned(client.list_vms().await?, stack, |vm| vm.meta.as_ref()),
+            port_forwards: owned(
+                client.list_port_forwards(None, None).await?,
+                stack,
+                |pf| pf.meta.as_ref(),
+            ),
+        })
+    }
+}
+
+/// Key resources by their stack key, dropping those of other stacks
+fn owned<T>(
+    items: Vec<T>,
+    stack: &str,
+    meta: impl Fn(&T) -> Option<&ResourceMeta>,
+) -> BTreeMap<String, T> {
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let key = meta(&item)
+                .filter(|m| m.labels.get(STACK_LABEL).map(String::as_str) == Some(stack))
+                .and_then(|m| m.labels.get(KEY_LABEL).cloned())?;
+            Some((key, item))
+        })
+        .collect()
+}
+
+pub(crate) fn id_of(meta: &Option<ResourceMeta>) -> String {
+    meta.as_ref().map(|m| m.id.clone()).unwrap_or_default()
+}
+
+/// Daemon IDs of stack resources
+///
+/// Resources that don't exist yet map to a placeholder, which never matches
+/// an ID the daemon holds.
+#[derive(Clone, Default)]
+pub(crate) struct Ids(HashMap<(Kind, String), String>);
+
+impl Ids {
+    /// ID for a reference: a stack key of `kind`, or else a literal ID
+    pub(crate) fn resolve(&self, kind: Kind, reference: &str) -> String {
+        self.0
+            .get(&(kind, reference.to_string()))
+            .cloned()
+            .unwrap_or_else(|| reference.to_string())
+    }
+
+    pub(crate) fn set(&mut self, kind: Kind, key: &str, id: String) {
+        self.0.insert((kind, key.to_string()), id);
+    }
+
+    fn placeholder(&mut self, kind: Kind, key: &str) {
+        self.set(kind, key, format!("<new {} {}>", kind, key));
+    }
+}
+
+/// Changes recorded while diffing
+#[derive(Default)]
+struct Diff {
+    ids: Ids,
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Record the change for a declared resource
+    ///
+    /// `existing` holds the resource's ID and changed fields, if it exists.
+    fn record(
+        &mut self,
+        kind: Kind,
+        key: &str,
+        existing: Option<(String, Vec<&'static str>)>,
+        on_change: Action,
+    ) {
+        let (action, id, fields) = match existing {
+            Some((id, fields)) if fields.is_empty() => {
+                self.ids.set(kind, key, id);
+                return;
+            }
+            Some((id, fields)) => (on_change, Some(id), fields),
+            None => (Action::Create, None, Vec::new()),
+        };
+
+        match (action, &id) {
+            (Action::Update, Some(id)) => self.ids.set(kind, key, id.clone()),
+            _ => self.ids.placeholder(kind, key),
+        }
+
+        self.changes.push(Change {
+            kind,
+            key: key.to_string(),
+            action,
+            id,
+            fields,
+        });
+    }
+
+    /// Record the deletion of a resource that left the stack file
+    fn delete(&mut self, kind: Kind, key: &str, meta: &Option<ResourceMeta>) {
+        self.changes.push(Change {
+            kind,
+            key: key.to_string(),
+            action: Action::Delete,
+            id: Some(id_of(meta)),
+            fields: Vec::new(),
+        });
+    }
+}
+
+/// Changes needed to converge the daemon to a stack file
+pub struct Plan {
+    file: StackFile,
+    existing: Existing,
+    ids: Ids,
+    pub changes: Vec<Change>,
+}
+
+impl Plan {
+    /// Compare a stack file with the stack's existing resources
+    pub fn diff(file: StackFile, existing: Existing) -> Result<Self> {
+        let mut diff = Diff::default();
+
+        for (key, def) in &file.networks {
+            let want = def.spec().with_context(|| format!("network '{}'", key))?;
+            let current = existing.networks.get(key).map(|net| {
+                let fields = def.changed(&want, &net.spec.clone().unwrap_or_default());
+                (id_of(&net.meta), fields)
+            });
+            diff.record(Kind::Network, key, current, Action::Replace);
+        }
+
+        for (key, def) in &file.volumes {
+            let want = def.spec().with_context(|| format!("volume '{}'", key))?;
+            let current = existing.volumes.get(key).map(|vol| {
+                let fields = def.changed(&want, &vol.spec.clone().unwrap_or_default());
+                (id_of(&vol.meta), fields)
+            });
+            diff.record(Kind::Volume, key, current, Action::Replace);
+        }
+
+        for (key, def) in &file.vms {
+            let vm = existing.vms.get(key);
+            let current = vm.map(|vm| {
+                let fields = def.changed(&diff.ids, &vm.spec.clone().unwrap_or_default());
+                (id_of(&vm.meta), fields)
+            });
+            diff.record(Kind::Vm, key, current, Action::Update);
+
+            let state = vm
+                .and_then(|vm| vm.status.as_ref())
+                .and_then(|s| VmState::try_from(s.state).ok());
+            let active = matches!(
+                state,
+                Some(VmState::Running | VmState::Paused | VmState::Suspended)
+            );
+            let action = match (def.running, active) {
+                (true, false) => Action::Start,
+                (false, true) => Action::Stop,
+                _ => continue,
+            };
+            diff.changes.push(Change {
+                kind: Kind::Vm,
+                key: key.clone(),
+                action,
+                id: vm.map(|vm| id_of(&vm.meta)),
+                fields: Vec::new(),
+            });
+        }
+
+        for (key, def) in &file.port_forwards {
+            let want = def
+                .spec(&diff.ids)
+                .with_context(|| format!("port forward '{}'", key))?;
+            let current = existing.port_forwards.get(key).map(|pf| {
+                let fields = def.changed(&want, &pf.spec.clone().unwrap_or_default());
+                (id_of(&pf.meta), fields)
+            });
+            diff.record(Kind::PortForward, key, current, Action::Replace);
+        }
+
+        for (key, net) in &existing.networks {
+            if !file.networks.contains_key(key) {
+                diff.delete(Kind::Network, key, &net.meta);
+            }
+        }
+        for (key, vol) in &existing.volumes {
+            if !file.volumes.contains_key(key) {
+                diff.delete(Kind::Volume, key, &vol.meta);
+            }
+        }
+        for (key, vm) in &existing.vms {
+            if !file.vms.contains_key(key) {
+                diff.delete(Kind::Vm, key, &vm.meta);
+            }
+        }
+        for (key, pf) in &existing.port_forwards {
+            if !file.port_forwards.contains_key(key) {
+                diff.delete(Kind::PortForward, key, &pf.meta);
+            }
+        }
+
+        diff.changes.sort_by_key(|c| c.kind);
+        Ok(Self {
+            file,
+            existing,
+            ids: diff.ids,
+            changes: diff.changes,
+        })
+    }
+
+    /// Name of the stack
+    pub fn stack(&self) -> &str {
+        &self.file.name
+    }
+
+    /// True when the daemon already matches the stack file
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Carry out the plan
+    ///
+    /// Resources are deleted dependents-first before anything is created,
+    /// since names are unique per kind and replacements reuse them.
+    pub async fn apply(&self, client: &mut DaemonClient) -> Result<()> {
+        let mut ids = self.ids.clone();
+
+        for change in self.changes.iter().rev() {
+            if !matches!(change.action, Action::Delete | Action::Replace) {
+                continue;
+            }
+            let id = change.id.as_deref().unwrap_or_default();
+            match change.kind {
+                Kind::PortForward => client.delete_port_forward(id).await?,
+                Kind::Vm => client.delete_vm(id, true).await?,
+                Kind::Volume => client.delete_volume(id).await?,
+                Kind::Network => client.delete_network(id).await?,
+            }
+            if change.action == Action::Delete {
+                print_success(&format!("Deleted {} '{}'", change.kind, change.key));
+            }
+        }
+
+        for change in &self.changes {
+            let key = change.key.as_str();
+            let name = self.file.resource_name(key);
+            let labels = self.file.labels(key);
+            let context = || format!("Failed to {} {} '{}'", change.action, change.kind, key);
+
+            let id = match (change.kind, change.action) {
+                (_, Action::Delete | Action::Start | Action::Stop) => continue,
+                (Kind::Network, _) => {
+                    let spec = self.file.networks[key].spec()?;
+                    let net = client.create_network(&name, spec, labels).await.with_context(context)?;
+                    id_of(&net.meta)
+                }
+                (Kind::Volume, _) => {
+                    let def = &self.file.volumes[key];
+                    let vol = client.create_volume(&name, def.spec()?, labels).await.with_context(context)?;
+                    let id = id_of(&vol.meta);
+                    if def.is_remote() {
+                        wait_for_download(client, &id).await?;
+                    }
+                    id
+                }
+                (Kind::Vm, Action::Update) => {
+                    let id = change.id.clone().unwrap_or_default();
+                    let current = self.existing.vms[key].spec.clone().unwrap_or_default();
+                    let spec = self.file.vms[key].spec(&ids, current);
+                    client.update_vm(&id, spec).await.with_context(context)?;
+                    id
+                }
+                (Kind::Vm, _) => {
+                    let spec = self.file.vms[key].spec(&ids, VmSpec::default());
+                    let vm = client.create_vm(&name, spec, labels).await.with_context(context)?;
+                    id_of(&vm.meta)
+                }
+                (Kind::PortForward, _) => {
+                    let spec = self.file.port_forwards[key].spec(&ids)?;
+                    let pf = client.create_port_forward(&name, spec, labels).await.with_context(context)?;
+                    id_of(&pf.meta)
+                }
+            };
+
+            ids.set(change.kind, key, id);
+            print_success(&format!("{} {} '{}'", past_tense(change.action), change.kind, key));
+            if change.kind == Kind::Vm && change.action == Action::Update {
+                print_info(&format!("VM '{}' picks up its new spec when it next starts", key));
+            }
+        }
+
+        for change in &self.changes {
+            let id = ids.resolve(Kind::Vm, &change.key);
+            match change.action {
+                Action::Start => {
+                    client.start_vm(&id).await.with_context(|| format!("Failed to start VM '{}'", change.key))?;
+                }
+                Action::Stop => {
+                    client.stop_vm(&id, StopMode::Acpi, 0).await.with_context(|| format!("Failed to stop VM '{}'", change.key))?;
+                }
+                _ => continue,
+            }
+            print_success(&format!("{} vm '{}'", past_tense(change.action), change.key));
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn past_tense(action: Action) -> &'static str {
+    match action {
+        Action::Create => "Created",
+        Action::Update => "Updated",
+        Action::Replace => "Replaced",
+        Action::Delete => "Deleted",
+        Action::Start => "Started",
+        Action::Stop => "Stopped",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::VmStatus;
+
+    const STACK: &str = r#"
+name: lab
+networks:
+  lan:
+    cidr: 192.168.100.0/24
+volumes:
+  disk:
+    source: /var/lib/infrasim/images/kali.qcow2
+    overlay: true
+vms:
+  kali:
+    boot_disk: disk
+    networks: [lan]
+port_forwards:
+  kali-ssh:
+    vm: kali
+    host_port: 2222
+    guest_port: 22
+"#;
+
+    fn stack() -> StackFile {
+        serde_yaml::from_str(STACK).unwrap()
+    }
+
+    fn meta(file: &StackFile, key: &str, id: &str) -> Option<ResourceMeta> {
+        Some(ResourceMeta {
+            id: id.to_string(),
+            name: file.resource_name(key),
+            labels: file.labels(key),
+            ..Default::default()
+        })
+    }
+
+    /// The resources a previous apply of `file` left behind, with running VMs
+    fn applied(file: &StackFile) -> Existing {
+        let mut ids = Ids::default();
+        let mut existing = Existing::default();
+
+        for (key, def) in &file.networks {
+            let id = format!("net-{}", key);
+            existing.networks.insert(
+                key.clone(),
+                Network {
+                    meta: meta(file, key, &id),
+                    spec: Some(def.spec().unwrap()),
+                    status: None,
+                },
+            );
+            ids.set(Kind::Network, key, id);
+        }
+        for (key, def) in &file.volumes {
+            let id = format!("vol-{}", key);
+            existing.volumes.insert(
+                key.clone(),
+                Volume {
+                    meta: meta(file, key, &id),
+                    spec: Some(def.spec().unwrap()),
+                    status: None,
+                },
+            );
+            ids.set(Kind::Volume, key, id);
+        }
+        for (key, def) in &file.vms {
+            let id = format!("vm-{}", key);
+            existing.vms.insert(
+                key.clone(),
+                Vm {
+                    meta: meta(file, key, &id),
+                    spec: Some(def.spec(&ids, VmSpec::default())),
+                    status: Some(VmStatus {
+                        state: VmState::Running as i32,
+                        ..Default::default()
+                    }),
+                },
+            );
+            ids.set(Kind::Vm, key, id);
+        }
+        for (key, def) in &file.port_forwards {
+            existing.port_forwards.insert(
+                key.clone(),
+                PortForward {
+                    meta: meta(file, key, &format!("pf-{}", key)),
+                    spec: Some(def.spec(&ids).unwrap()),
+                    status: None,
+                },
+            );
+        }
+
+        existing
+    }
+
+    fn steps(plan: &Plan) -> Vec<(Kind, &str, Action)> {
+        plan.changes
+            .iter()
+            .map(|c| (c.kind, c.key.as_str(), c.action))
+            .collect()
+    }
+
+    fn change<'a>(plan: &'a Plan, kind: Kind, key: &str) -> &'a Change {
+        plan.changes
+            .iter()
+            .find(|c| c.kind == kind && c.key == key)
+            .unwrap_or_else(|| panic!("no change for {} '{}'", kind, key))
+    }
+
+    #[test]
+    fn test_creates_everything_for_a_new_stack() {
+        let plan = Plan::diff(stack(), Existing::default()).unwrap();
+        assert_eq!(
+            steps(&plan),
+            vec![
+                (Kind::Network, "lan", Action::Create),
+                (Kind::Volume, "disk", Action::Create),
+                (Kind::Vm, "kali", Action::Create),
+                (Kind::Vm, "kali", Action::Start),
+                (Kind::PortForward, "kali-ssh", Action::Create),
+            ]
+        );
+        assert!(plan.changes.iter().all(|c| c.id.is_none()));
+    }
+
+    #[test]
+    fn test_applied_stack_is_unchanged() {
+        let file = stack();
+        let existing = applied(&file);
+        let plan = Plan::diff(file, existing).unwrap();
+        assert!(plan.is_empty(), "unexpected changes: {:?}", plan.changes);
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let file = stack();
+        let mut existing = applied(&file);
+        existing.vms.get_mut("kali").unwrap().spec.as_mut().unwrap().cpu_cores = 8;
+        existing.volumes.get_mut("disk").unwrap().spec.as_mut().unwrap().overlay = false;
+
+        let plan = Plan::diff(file, existing).unwrap();
+
+        let volume = change(&plan, Kind::Volume, "disk");
+        assert_eq!(volume.action, Action::Replace);
+        assert_eq!(volume.id.as_deref(), Some("vol-disk"));
+        assert_eq!(volume.fields, vec!["overlay"]);
+
+        // The replaced volume gets a new ID, so the VM's boot disk changes too
+        let vm = change(&plan, Kind::Vm, "kali");
+        assert_eq!(vm.action, Action::Update);
+        assert_eq!(vm.id.as_deref(), Some("vm-kali"));
+        assert_eq!(vm.fields, vec!["cpus", "boot_disk"]);
+
+        assert_eq!(plan.changes.len(), 2);
+    }
+
+    #[test]
+    fn test_deletes_resources_that_left_the_file() {
+        let mut file = stack();
+        let existing = applied(&file);
+        file.port_forwards.clear();
+
+        let plan = Plan::diff(file, existing).unwrap();
+        assert_eq!(steps(&plan), vec![(Kind::PortForward, "kali-ssh", Action::Delete)]);
+        assert_eq!(plan.changes[0].id.as_deref(), Some("pf-kali-ssh"));
+    }
+
+    #[test]
+    fn test_port_forward_changes() {
+        let mut file = stack();
+        let existing = applied(&file);
+        let forward = file.port_forwards.get_mut("kali-ssh").unwrap();
+        forward.host_port = 2200;
+        forward.protocol = "udp".to_string();
+
+        let plan = Plan::diff(file, existing).unwrap();
+        let change = change(&plan, Kind::PortForward, "kali-ssh");
+        assert_eq!(change.action, Action::Replace);
+        assert_eq!(change.id.as_deref(), Some("pf-kali-ssh"));
+        assert_eq!(change.fields, vec!["protocol", "host_port"]);
+        assert_eq!(plan.changes.len(), 1);
+    }
+
+    #[test]
+    fn test_port_forward_follows_a_recreated_vm() {
+        let file = stack();
+        let mut existing = applied(&file);
+        existing.vms.clear();
+
+        let plan = Plan::diff(file, existing).unwrap();
+        let change = change(&plan, Kind::PortForward, "kali-ssh");
+        assert_eq!(change.action, Action::Replace);
+        assert_eq!(change.fields, vec!["vm"]);
+    }
+}
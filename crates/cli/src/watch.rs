@@ -0,0 +1,217 @@
+//! Watch mode and diffs for get and list commands
+//!
+//! `--watch` prints a command's output, then follows the daemon event stream
+//! and prints it again after each change to the resources shown; tables are
+//! redrawn in place. `--output-diff` prints what changed in an item since it
+//! was last shown: the previous render while watching, otherwise the last
+//! `get` of it, remembered under `~/.infrasim/cli-cache/`.
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tonic::Streaming;
+
+use crate::client::DaemonClient;
+use crate::generated::Event;
+use crate::output::{print_info, print_item, OutputFormat, TableDisplay};
+
+/// Changes this close together are shown once
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Directory of the snapshots `--output-diff` compares against
+const CACHE_DIR: &str = "cli-cache";
+
+/// Renders of a command, repeated on change when watching
+pub struct Watch {
+    events: Option<Streaming<Event>>,
+    format: OutputFormat,
+    /// Last item shown per cache key, for diffs
+    last: BTreeMap<String, Value>,
+}
+
+impl Watch {
+    /// Follow changes to resources of `resource_type`, or only to `id`, if
+    /// `enabled`
+    pub async fn start(
+        client: &mut DaemonClient,
+        enabled: bool,
+        resource_type: &str,
+        id: Option<&str>,
+        format: OutputFormat,
+    ) -> Result<Self> {
+        let events = if enabled {
+            let id = id.unwrap_or_default().to_string();
+            Some(client.watch_events(resource_type.to_string(), id, vec![], 0, true).await?)
+        } else {
+            None
+        };
+        Ok(Self {
+            events,
+            format,
+            last: BTreeMap::new(),
+        })
+    }
+
+    /// Start a render: clears the screen for a watched table
+    pub fn frame(&self) {
+        if self.events.is_some() && matches!(self.format, OutputFormat::Table) {
+            print!("\x1b[2J\x1b[H");
+            println!(
+                "{}",
+                format!("{}  (watching, Ctrl-C to stop)", chrono::Local::now().format("%H:%M:%S")).dimmed()
+            );
+        }
+    }
+
+    /// Wait for the next change; false if not watching or the daemon ended
+    /// the stream
+    pub async fn changed(&mut self) -> Result<bool> {
+        let Some(events) = self.events.as_mut() else {
+            return Ok(false);
+        };
+        if events.message().await?.is_none() {
+            return Ok(false);
+        }
+        let mut ended = false;
+        while let Ok(next) = tokio::time::timeout(DEBOUNCE, events.message()).await {
+            if next?.is_none() {
+                ended = true;
+                break;
+            }
+        }
+        if ended {
+            self.events = None;
+        }
+        Ok(true)
+    }
+
+    /// Print what changed in `item` since it was last shown; the whole item
+    /// the first time
+    pub fn print_diff<T: Serialize + TableDisplay>(
+        &mut self,
+        resource_type: &str,
+        id: &str,
+        item: &T,
+    ) -> Result<()> {
+        let key = format!("{}-{}", resource_type, id.replace(['/', '\\'], "_"));
+        let current = serde_json::to_value(item)?;
+        let previous = match self.last.remove(&key) {
+            Some(previous) => Some(previous),
+            None => load_snapshot(&key),
+        };
+        save_snapshot(&key, &current);
+
+        match previous {
+            None => {
+                print_info(&format!("No earlier view of {} {}; showing it in full", resource_type, id));
+                print_item(item, self.format);
+            }
+            Some(previous) => print_changes(&diff(&previous, &current), self.format)?,
+        }
+        self.last.insert(key, current);
+        Ok(())
+    }
+}
+
+/// A field that differs between two views of an item
+#[derive(Debug, Serialize)]
+pub struct Change {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// Leaf fields that were added, removed or changed, by path
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut old_fields = BTreeMap::new();
+    let mut new_fields = BTreeMap::new();
+    flatten(old, String::new(), &mut old_fields);
+    flatten(new, String::new(), &mut new_fields);
+
+    let mut paths: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter(|path| old_fields.get(*path) != new_fields.get(*path))
+        .map(|path| Change {
+            path: path.clone(),
+            old: old_fields.get(path).cloned(),
+            new: new_fields.get(path).cloned(),
+        })
+        .collect()
+}
+
+/// Leaves of `value` keyed by their field path, as `--field` takes them
+fn flatten(value: &Value, path: String, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let is_plain = key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                let child = if is_plain {
+                    format!("{}.{}", path, key)
+                } else {
+                    format!("{}['{}']", path, key)
+                };
+                flatten(value, child, fields);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, value) in items.iter().enumerate() {
+                flatten(value, format!("{}[{}]", path, index), fields);
+            }
+        }
+        _ => {
+            fields.insert(if path.is_empty() { ".".to_string() } else { path }, value.clone());
+        }
+    }
+}
+
+fn print_changes(changes: &[Change], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(changes)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(changes)?),
+        _ if changes.is_empty() => println!("{}", "No changes".dimmed()),
+        _ => {
+            for change in changes {
+                let line = match (&change.old, &change.new) {
+                    (Some(old), Some(new)) => format!("~ {}: {} -> {}", change.path, old, new).yellow(),
+                    (None, Some(new)) => format!("+ {}: {}", change.path, new).green(),
+                    (Some(old), None) => format!("- {}: {}", change.path, old).red(),
+                    (None, None) => continue,
+                };
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn snapshot_path(key: &str) -> PathBuf {
+    infrasim_common::default_store_path()
+        .join(CACHE_DIR)
+        .join(format!("{}.json", key))
+}
+
+fn load_snapshot(key: &str) -> Option<Value> {
+    let content = std::fs::read(snapshot_path(key)).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+/// Remember `value` for the next diff; a CLI that can't write its cache
+/// still shows the diff
+fn save_snapshot(key: &str, value: &Value) {
+    let path = snapshot_path(key);
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(content) = serde_json::to_vec(value) {
+        let _ = std::fs::write(path, content);
+    }
+}
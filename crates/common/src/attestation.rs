@@ -3,14 +3,14 @@
 //! Provides host provenance collection and attestation report generation.
 
 use crate::{
-    crypto::{KeyPair, Signer},
-    types::{AttestationReport, HostProvenance, Vm, Volume},
+    crypto::{KeyPair, Signer, Verifier},
+    types::{AttestationReport, HostProvenance, TpmQuote, Vm, Volume},
     Result,
 };
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::process::Command;
-use tracing::{debug, warn};
+use tracing::debug;
 use uuid::Uuid;
 
 /// Attestation provider
@@ -30,10 +30,26 @@ impl AttestationProvider {
         vm: &Vm,
         volumes: &[Volume],
         qemu_args: &[String],
+    ) -> Result<AttestationReport> {
+        self.generate_report_with_quotes(vm, volumes, qemu_args, Vec::new())
+    }
+
+    /// Generate attestation report for a VM including its vTPM quotes
+    pub fn generate_report_with_quotes(
+        &self,
+        vm: &Vm,
+        volumes: &[Volume],
+        qemu_args: &[String],
+        tpm_quotes: Vec<TpmQuote>,
     ) -> Result<AttestationReport> {
         let provenance = self.collect_host_provenance(vm, volumes, qemu_args)?;
-        let digest = self.compute_provenance_digest(&provenance)?;
+        let digest = report_digest(&provenance, &tpm_quotes)?;
         let signature = self.key_pair.sign(digest.as_bytes());
+        let attestation_type = if tpm_quotes.is_empty() {
+            "host_provenance"
+        } else {
+            "vtpm"
+        };
 
         let report = AttestationReport {
             id: Uuid::new_v4().to_string(),
@@ -42,7 +58,8 @@ impl AttestationProvider {
             digest,
             signature,
             created_at: chrono::Utc::now().timestamp(),
-            attestation_type: "host_provenance".to_string(),
+            attestation_type: attestation_type.to_string(),
+            tpm_quotes,
         };
 
         debug!("Generated attestation report: {}", report.id);
@@ -63,7 +80,7 @@ impl AttestationProvider {
         let hvf_enabled = is_hvf_available();
 
         // Collect volume hashes
-        let mut volume_hashes = HashMap::new();
+        let mut volume_hashes = BTreeMap::new();
         for vol in volumes {
             if let Some(digest) = &vol.status.digest {
                 volume_hashes.insert(vol.meta.id.clone(), digest.clone());
@@ -90,20 +107,10 @@ impl AttestationProvider {
         })
     }
 
-    /// Compute digest of provenance data
-    fn compute_provenance_digest(&self, provenance: &HostProvenance) -> Result<String> {
-        let serialized = serde_json::to_vec(provenance)?;
-        let mut hasher = Sha256::new();
-        hasher.update(&serialized);
-        Ok(hex::encode(hasher.finalize()))
-    }
-
     /// Verify an attestation report
     pub fn verify_report(&self, report: &AttestationReport) -> Result<bool> {
-        use crate::crypto::Verifier;
-
         // Recompute digest
-        let computed_digest = self.compute_provenance_digest(&report.host_provenance)?;
+        let computed_digest = report_digest(&report.host_provenance, &report.tpm_quotes)?;
         if computed_digest != report.digest {
             return Ok(false);
         }
@@ -116,6 +123,49 @@ impl AttestationProvider {
     }
 }
 
+/// Compute the signed digest of a report: provenance alone, or provenance and quotes
+pub fn report_digest(provenance: &HostProvenance, tpm_quotes: &[TpmQuote]) -> Result<String> {
+    let serialized = if tpm_quotes.is_empty() {
+        serde_json::to_vec(provenance)?
+    } else {
+        serde_json::to_vec(&(provenance, tpm_quotes))?
+    };
+    Ok(hex::encode(Sha256::digest(&serialized)))
+}
+
+/// Outcome of checking a report's digest and signature
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportVerification {
+    /// The digest matches the provenance and quotes in the report
+    pub digest_valid: bool,
+    /// The digest is signed by the expected key
+    pub signature_valid: bool,
+}
+
+impl ReportVerification {
+    pub fn is_valid(&self) -> bool {
+        self.digest_valid && self.signature_valid
+    }
+}
+
+/// Verify a report against a daemon's public signing key.
+///
+/// Unlike [`AttestationProvider::verify_report`] this needs no private key,
+/// so relying parties can check reports shipped to them.
+pub fn verify_report_with_key(
+    report: &AttestationReport,
+    key: &impl Verifier,
+) -> Result<ReportVerification> {
+    let digest_valid = report_digest(&report.host_provenance, &report.tpm_quotes)? == report.digest;
+    let signature_valid = key
+        .verify(report.digest.as_bytes(), &report.signature)
+        .is_ok();
+    Ok(ReportVerification {
+        digest_valid,
+        signature_valid,
+    })
+}
+
 /// Get QEMU version
 fn get_qemu_version() -> Result<String> {
     let output = Command::new("qemu-system-aarch64")
@@ -134,6 +184,7 @@ fn get_qemu_version() -> Result<String> {
 }
 
 /// Get macOS version
+#[cfg(target_os = "macos")]
 fn get_macos_version() -> Result<String> {
     let output = Command::new("sw_vers")
         .arg("-productVersion")
@@ -143,7 +194,27 @@ fn get_macos_version() -> Result<String> {
     Ok(version)
 }
 
+#[cfg(not(target_os = "macos"))]
+fn get_macos_version() -> Result<String> {
+    Ok("n/a".to_string())
+}
+
 /// Get CPU model
+#[cfg(target_os = "linux")]
+fn get_cpu_model() -> Result<String> {
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo")?;
+    // x86 reports "model name", arm64 only "CPU implementer"/"CPU part"
+    let model = cpuinfo
+        .lines()
+        .find_map(|l| l.strip_prefix("model name"))
+        .and_then(|l| l.split_once(':'))
+        .map(|(_, model)| model.trim().to_string())
+        .unwrap_or_else(|| std::env::consts::ARCH.to_string());
+    Ok(model)
+}
+
+/// Get CPU model
+#[cfg(not(target_os = "linux"))]
 fn get_cpu_model() -> Result<String> {
     let output = Command::new("sysctl")
         .args(["-n", "machdep.cpu.brand_string"])
@@ -167,6 +238,7 @@ fn get_hostname() -> Result<String> {
 }
 
 /// Check if HVF (Hypervisor.framework) is available
+#[cfg(target_os = "macos")]
 pub fn is_hvf_available() -> bool {
     // Check if we're on Apple Silicon or Intel Mac with HVF
     let output = Command::new("sysctl")
@@ -182,6 +254,39 @@ pub fn is_hvf_available() -> bool {
     }
 }
 
+/// Check if HVF (Hypervisor.framework) is available
+#[cfg(not(target_os = "macos"))]
+pub fn is_hvf_available() -> bool {
+    false
+}
+
+/// Check if KVM is available and usable by this process
+#[cfg(target_os = "linux")]
+pub fn is_kvm_available() -> bool {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/kvm")
+        .is_ok()
+}
+
+/// Check if KVM is available and usable by this process
+#[cfg(not(target_os = "linux"))]
+pub fn is_kvm_available() -> bool {
+    false
+}
+
+/// Hardware accelerator of this host: `hvf`, `kvm`, or `tcg` when there is none
+pub fn host_accelerator() -> &'static str {
+    if is_hvf_available() {
+        "hvf"
+    } else if is_kvm_available() {
+        "kvm"
+    } else {
+        "tcg"
+    }
+}
+
 /// Check if QEMU is available
 pub fn is_qemu_available() -> bool {
     Command::new("which")
@@ -191,6 +296,81 @@ pub fn is_qemu_available() -> bool {
         .unwrap_or(false)
 }
 
+/// Check if swtpm (needed for vTPM) is available
+pub fn is_swtpm_available() -> bool {
+    Command::new("which")
+        .arg("swtpm")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Total physical memory of the host in bytes, 0 if unknown
+pub fn host_memory_bytes() -> u64 {
+    // macOS
+    if let Ok(o) = Command::new("sysctl").args(["-n", "hw.memsize"]).output() {
+        if let Ok(bytes) = String::from_utf8_lossy(&o.stdout).trim().parse() {
+            return bytes;
+        }
+    }
+
+    // Linux
+    std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|info| {
+            info.lines()
+                .find_map(|l| l.strip_prefix("MemTotal:"))
+                .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        })
+        .map(|kb| kb * 1024)
+        .unwrap_or(0)
+}
+
+/// Memory the host can hand out without swapping, in bytes
+pub fn host_available_memory_bytes() -> Option<u64> {
+    // Linux
+    if let Ok(info) = std::fs::read_to_string("/proc/meminfo") {
+        return info
+            .lines()
+            .find_map(|l| l.strip_prefix("MemAvailable:"))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .map(|kb| kb * 1024);
+    }
+
+    // macOS: free, inactive and speculative pages can be reclaimed
+    let output = Command::new("vm_stat").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let page_size = stdout
+        .lines()
+        .next()?
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse::<u64>()
+        .ok()?;
+    let pages = |name: &str| {
+        stdout
+            .lines()
+            .find_map(|l| l.strip_prefix(name))
+            .and_then(|v| v.trim().trim_end_matches('.').parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    Some((pages("Pages free:") + pages("Pages inactive:") + pages("Pages speculative:")) * page_size)
+}
+
+/// Free space on the filesystem holding `path`, in bytes
+pub fn free_disk_bytes(path: &std::path::Path) -> Option<u64> {
+    let output = Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Filesystem 1024-blocks Used Available Capacity Mounted on
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let available_kb = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse::<u64>().ok()?;
+    Some(available_kb * 1024)
+}
+
 /// Get QEMU path
 pub fn get_qemu_path() -> Option<String> {
     let output = Command::new("which")
@@ -205,72 +385,694 @@ pub fn get_qemu_path() -> Option<String> {
     }
 }
 
-/// vTPM attestation (scaffold for future implementation)
+/// vTPM measured boot evidence: TPM 2.0 command marshalling and quote verification
+///
+/// The daemon drives swtpm through these helpers; verification is pure and
+/// can run anywhere the quote is shipped (CLI, CI, relying parties).
 pub mod vtpm {
-    use super::*;
+    use crate::types::{TpmEvent, TpmQuote};
+    use crate::{Error, Result};
+    use serde::{Deserialize, Serialize};
+    use sha2::{Digest, Sha256};
+    use std::collections::BTreeMap;
+
+    pub const TPM_ST_NO_SESSIONS: u16 = 0x8001;
+    pub const TPM_ST_SESSIONS: u16 = 0x8002;
+    const TPM_ST_ATTEST_QUOTE: u16 = 0x8018;
+    const TPM_GENERATED_VALUE: u32 = 0xff54_4347;
+
+    pub const TPM_CC_PCR_EVENT: u32 = 0x013C;
+    pub const TPM_CC_STARTUP: u32 = 0x0144;
+    pub const TPM_CC_PCR_EXTEND: u32 = 0x0182;
+    pub const TPM_CC_EVENT_SEQUENCE_COMPLETE: u32 = 0x0185;
+    const TPM_CC_CREATE_PRIMARY: u32 = 0x0131;
+    const TPM_CC_QUOTE: u32 = 0x0158;
+    const TPM_CC_FLUSH_CONTEXT: u32 = 0x0165;
+    const TPM_CC_PCR_READ: u32 = 0x017E;
+
+    /// Response code returned before the guest has issued TPM2_Startup
+    pub const TPM_RC_INITIALIZE: u32 = 0x0100;
+
+    const TPM_RH_ENDORSEMENT: u32 = 0x4000_000B;
+    const TPM_RS_PW: u32 = 0x4000_0009;
+    const TPM_ALG_SHA1: u16 = 0x0004;
+    const TPM_ALG_SHA256: u16 = 0x000B;
+    const TPM_ALG_SHA384: u16 = 0x000C;
+    const TPM_ALG_SHA512: u16 = 0x000D;
+    const TPM_ALG_NULL: u16 = 0x0010;
+    const TPM_ALG_SM3_256: u16 = 0x0012;
+    const TPM_ALG_ECDSA: u16 = 0x0018;
+    const TPM_ALG_ECC: u16 = 0x0023;
+    const TPM_ECC_NIST_P256: u16 = 0x0003;
+    const TPM_SU_CLEAR: u16 = 0x0000;
 
-    /// vTPM configuration
+    /// fixedTPM | fixedParent | sensitiveDataOrigin | userWithAuth | restricted | sign
+    const AK_ATTRIBUTES: u32 = 0x0005_0072;
+
+    /// PCRs reset to zero on TPM2_Startup(CLEAR) and therefore replayable
+    const RESETTABLE_PCRS: std::ops::RangeInclusive<u32> = 0..=16;
+
+    /// Number of PCRs in a bank
+    pub const PCR_COUNT: u32 = 24;
+
+    /// Expected PCR values and attestation key for a VM
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct PcrPolicy {
+        /// Expected PCR values (hex) keyed by PCR index
+        pub pcrs: BTreeMap<u32, String>,
+        /// Expected attestation key (hex SEC1), any key if unset
+        #[serde(default)]
+        pub ak_public: Option<String>,
+    }
+
+    impl PcrPolicy {
+        /// Build a policy pinning the PCRs and key of a known-good quote
+        pub fn from_quote(quote: &TpmQuote) -> Self {
+            Self {
+                pcrs: quote.pcrs.clone(),
+                ak_public: Some(hex::encode(&quote.ak_public)),
+            }
+        }
+    }
+
+    /// Outcome of verifying a quote
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct QuoteVerification {
+        pub signature_valid: bool,
+        pub nonce_valid: bool,
+        pub pcr_digest_valid: bool,
+        pub event_log_valid: bool,
+        pub ak_trusted: bool,
+        /// PCRs whose quoted value differs from the policy
+        pub policy_mismatches: Vec<u32>,
+        /// PCRs whose quoted value differs from the event log replay
+        pub event_log_mismatches: Vec<u32>,
+    }
+
+    impl QuoteVerification {
+        /// Whether every check passed
+        pub fn is_trusted(&self) -> bool {
+            self.signature_valid
+                && self.nonce_valid
+                && self.pcr_digest_valid
+                && self.event_log_valid
+                && self.ak_trusted
+                && self.policy_mismatches.is_empty()
+        }
+    }
+
+    /// Parsed TPMS_ATTEST for a quote
     #[derive(Debug, Clone)]
-    pub struct VtpmConfig {
-        pub socket_path: String,
-        pub version: String,
+    pub struct QuoteInfo {
+        pub extra_data: Vec<u8>,
+        pub reset_count: u32,
+        pub restart_count: u32,
+        pub pcrs: Vec<u32>,
+        pub pcr_digest: Vec<u8>,
+    }
+
+    /// Parse a comma separated PCR list such as "0-7,14"
+    pub fn parse_pcr_selection(s: &str) -> Result<Vec<u32>> {
+        let mut pcrs = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (start, end) = match part.split_once('-') {
+                Some((a, b)) => (a.trim(), b.trim()),
+                None => (part, part),
+            };
+            let start: u32 = start
+                .parse()
+                .map_err(|_| Error::InvalidConfig(format!("invalid PCR index: {}", part)))?;
+            let end: u32 = end
+                .parse()
+                .map_err(|_| Error::InvalidConfig(format!("invalid PCR index: {}", part)))?;
+            if start > end || end >= PCR_COUNT {
+                return Err(Error::InvalidConfig(format!("invalid PCR range: {}", part)));
+            }
+            pcrs.extend(start..=end);
+        }
+        pcrs.sort_unstable();
+        pcrs.dedup();
+        if pcrs.is_empty() {
+            return Err(Error::InvalidConfig("empty PCR selection".to_string()));
+        }
+        Ok(pcrs)
+    }
+
+    /// Default PCRs covered by quotes (firmware, boot loader, kernel)
+    pub fn default_pcrs() -> Vec<u32> {
+        (0..=9).collect()
+    }
+
+    /// Random 32-byte nonce for a quote request
+    pub fn random_nonce() -> Vec<u8> {
+        use rand::RngCore;
+        let mut nonce = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        nonce
+    }
+
+    /// Command header fields: (tag, size, command code)
+    pub fn parse_header(buf: &[u8]) -> Option<(u16, u32, u32)> {
+        let mut r = Reader::new(buf);
+        Some((r.u16()?, r.u32()?, r.u32()?))
+    }
+
+    // ------------------------------------------------------------------
+    // Command marshalling
+    // ------------------------------------------------------------------
+
+    struct Command {
+        buf: Vec<u8>,
+    }
+
+    impl Command {
+        fn new(tag: u16, cc: u32) -> Self {
+            let mut buf = Vec::with_capacity(64);
+            buf.extend_from_slice(&tag.to_be_bytes());
+            buf.extend_from_slice(&0u32.to_be_bytes());
+            buf.extend_from_slice(&cc.to_be_bytes());
+            Self { buf }
+        }
+
+        fn u8(mut self, v: u8) -> Self {
+            self.buf.push(v);
+            self
+        }
+
+        fn u16(mut self, v: u16) -> Self {
+            self.buf.extend_from_slice(&v.to_be_bytes());
+            self
+        }
+
+        fn u32(mut self, v: u32) -> Self {
+            self.buf.extend_from_slice(&v.to_be_bytes());
+            self
+        }
+
+        fn bytes(mut self, v: &[u8]) -> Self {
+            self.buf.extend_from_slice(v);
+            self
+        }
+
+        fn tpm2b(self, v: &[u8]) -> Self {
+            self.u16(v.len() as u16).bytes(v)
+        }
+
+        /// Empty password authorization for a single handle
+        fn password_session(self) -> Self {
+            self.u32(9).u32(TPM_RS_PW).u16(0).u8(0).u16(0)
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            let len = self.buf.len() as u32;
+            self.buf[2..6].copy_from_slice(&len.to_be_bytes());
+            self.buf
+        }
+    }
+
+    fn pcr_select_bytes(pcrs: &[u32]) -> [u8; 3] {
+        let mut select = [0u8; 3];
+        for &pcr in pcrs.iter().filter(|&&p| p < PCR_COUNT) {
+            select[(pcr / 8) as usize] |= 1 << (pcr % 8);
+        }
+        select
+    }
+
+    fn with_pcr_selection(cmd: Command, pcrs: &[u32]) -> Command {
+        cmd.u32(1)
+            .u16(TPM_ALG_SHA256)
+            .u8(3)
+            .bytes(&pcr_select_bytes(pcrs))
+    }
+
+    /// TPM2_Startup(CLEAR)
+    pub fn startup_command() -> Vec<u8> {
+        Command::new(TPM_ST_NO_SESSIONS, TPM_CC_STARTUP)
+            .u16(TPM_SU_CLEAR)
+            .finish()
+    }
+
+    /// TPM2_CreatePrimary for an ECC P-256 restricted signing key under the
+    /// endorsement hierarchy. The key is derived from the EPS so it is stable
+    /// for the lifetime of the vTPM state.
+    pub fn create_primary_ak_command() -> Vec<u8> {
+        let public = Command { buf: Vec::new() }
+            .u16(TPM_ALG_ECC)
+            .u16(TPM_ALG_SHA256)
+            .u32(AK_ATTRIBUTES)
+            .u16(0) // authPolicy
+            .u16(TPM_ALG_NULL) // symmetric
+            .u16(TPM_ALG_ECDSA)
+            .u16(TPM_ALG_SHA256)
+            .u16(TPM_ECC_NIST_P256)
+            .u16(TPM_ALG_NULL) // kdf
+            .u16(0) // unique.x
+            .u16(0) // unique.y
+            .buf;
+
+        Command::new(TPM_ST_SESSIONS, TPM_CC_CREATE_PRIMARY)
+            .u32(TPM_RH_ENDORSEMENT)
+            .password_session()
+            .u16(4) // inSensitive
+            .u16(0)
+            .u16(0)
+            .tpm2b(&public)
+            .u16(0) // outsideInfo
+            .u32(0) // creationPCR
+            .finish()
+    }
+
+    /// TPM2_Quote over SHA-256 PCRs using the key's own signing scheme
+    pub fn quote_command(ak_handle: u32, nonce: &[u8], pcrs: &[u32]) -> Vec<u8> {
+        let cmd = Command::new(TPM_ST_SESSIONS, TPM_CC_QUOTE)
+            .u32(ak_handle)
+            .password_session()
+            .tpm2b(nonce)
+            .u16(TPM_ALG_NULL);
+        with_pcr_selection(cmd, pcrs).finish()
+    }
+
+    /// TPM2_PCR_Read of the SHA-256 bank
+    pub fn pcr_read_command(pcrs: &[u32]) -> Vec<u8> {
+        with_pcr_selection(Command::new(TPM_ST_NO_SESSIONS, TPM_CC_PCR_READ), pcrs).finish()
+    }
+
+    /// TPM2_FlushContext
+    pub fn flush_context_command(handle: u32) -> Vec<u8> {
+        Command::new(TPM_ST_NO_SESSIONS, TPM_CC_FLUSH_CONTEXT)
+            .u32(handle)
+            .finish()
+    }
+
+    // ------------------------------------------------------------------
+    // Response parsing
+    // ------------------------------------------------------------------
+
+    struct Reader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn new(buf: &'a [u8]) -> Self {
+            Self { buf, pos: 0 }
+        }
+
+        fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+            let end = self.pos.checked_add(n)?;
+            let slice = self.buf.get(self.pos..end)?;
+            self.pos = end;
+            Some(slice)
+        }
+
+        fn u8(&mut self) -> Option<u8> {
+            self.take(1).map(|b| b[0])
+        }
+
+        fn u16(&mut self) -> Option<u16> {
+            self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+        }
+
+        fn u32(&mut self) -> Option<u32> {
+            self.take(4)
+                .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        }
+
+        fn u64(&mut self) -> Option<u64> {
+            self.take(8).map(|b| {
+                let mut v = [0u8; 8];
+                v.copy_from_slice(b);
+                u64::from_be_bytes(v)
+            })
+        }
+
+        fn tpm2b(&mut self) -> Option<&'a [u8]> {
+            let len = self.u16()? as usize;
+            self.take(len)
+        }
+
+        /// TPML_PCR_SELECTION, returning the selected SHA-256 PCRs
+        fn pcr_selection(&mut self) -> Option<Vec<u32>> {
+            let mut pcrs = Vec::new();
+            for _ in 0..self.u32()? {
+                let alg = self.u16()?;
+                let size = self.u8()? as usize;
+                let select = self.take(size)?;
+                if alg != TPM_ALG_SHA256 {
+                    continue;
+                }
+                for (byte, bits) in select.iter().enumerate() {
+                    for bit in 0..8 {
+                        if bits & (1 << bit) != 0 {
+                            pcrs.push(byte as u32 * 8 + bit);
+                        }
+                    }
+                }
+            }
+            Some(pcrs)
+        }
+
+        /// TPML_DIGEST_VALUES, returning the SHA-256 digest if present
+        fn digest_values(&mut self) -> Option<Option<Vec<u8>>> {
+            let mut sha256 = None;
+            for _ in 0..self.u32()? {
+                let alg = self.u16()?;
+                let size = match alg {
+                    TPM_ALG_SHA1 => 20,
+                    TPM_ALG_SHA256 | TPM_ALG_SM3_256 => 32,
+                    TPM_ALG_SHA384 => 48,
+                    TPM_ALG_SHA512 => 64,
+                    _ => return None,
+                };
+                let digest = self.take(size)?;
+                if alg == TPM_ALG_SHA256 {
+                    sha256 = Some(digest.to_vec());
+                }
+            }
+            Some(sha256)
+        }
     }
 
-    /// vTPM attestation provider (scaffold)
-    pub struct VtpmAttestationProvider {
-        config: VtpmConfig,
+    fn malformed(what: &str) -> Error {
+        Error::AttestationError(format!("malformed TPM {}", what))
     }
 
-    impl VtpmAttestationProvider {
-        /// Create new vTPM provider
-        pub fn new(config: VtpmConfig) -> Self {
-            warn!("vTPM attestation is a scaffold - not fully implemented");
-            Self { config }
+    /// Check the response code and return a reader positioned after the header
+    fn response<'a>(buf: &'a [u8], what: &str) -> Result<Reader<'a>> {
+        let mut r = Reader::new(buf);
+        let (_, _, rc) = (
+            r.u16().ok_or_else(|| malformed(what))?,
+            r.u32().ok_or_else(|| malformed(what))?,
+            r.u32().ok_or_else(|| malformed(what))?,
+        );
+        if rc != 0 {
+            return Err(Error::AttestationError(format!(
+                "TPM {} failed with response code {:#06x}",
+                what, rc
+            )));
         }
+        Ok(r)
+    }
 
-        /// Check if vTPM is available
-        pub fn is_available(&self) -> bool {
-            // Would check for swtpm availability
-            false
+    /// Response code of a TPM response
+    pub fn response_code(buf: &[u8]) -> Option<u32> {
+        parse_header(buf).map(|(_, _, rc)| rc)
+    }
+
+    /// Parse a TPM2_CreatePrimary response into (handle, SEC1 public point)
+    pub fn parse_create_primary_response(buf: &[u8]) -> Result<(u32, Vec<u8>)> {
+        let mut r = response(buf, "CreatePrimary")?;
+        let err = || malformed("CreatePrimary response");
+        let handle = r.u32().ok_or_else(err)?;
+        let _param_size = r.u32().ok_or_else(err)?;
+        let public = r.tpm2b().ok_or_else(err)?;
+
+        let mut p = Reader::new(public);
+        if p.u16().ok_or_else(err)? != TPM_ALG_ECC {
+            return Err(Error::AttestationError(
+                "attestation key is not an ECC key".to_string(),
+            ));
+        }
+        let _name_alg = p.u16().ok_or_else(err)?;
+        let _attributes = p.u32().ok_or_else(err)?;
+        p.tpm2b().ok_or_else(err)?;
+        if p.u16().ok_or_else(err)? != TPM_ALG_NULL {
+            p.take(4).ok_or_else(err)?;
+        }
+        if p.u16().ok_or_else(err)? != TPM_ALG_NULL {
+            p.u16().ok_or_else(err)?;
         }
+        if p.u16().ok_or_else(err)? != TPM_ECC_NIST_P256 {
+            return Err(Error::AttestationError(
+                "attestation key is not on P-256".to_string(),
+            ));
+        }
+        if p.u16().ok_or_else(err)? != TPM_ALG_NULL {
+            p.u16().ok_or_else(err)?;
+        }
+        let x = p.tpm2b().ok_or_else(err)?;
+        let y = p.tpm2b().ok_or_else(err)?;
+
+        let mut point = Vec::with_capacity(65);
+        point.push(0x04);
+        point.extend_from_slice(&left_pad(x, 32).ok_or_else(err)?);
+        point.extend_from_slice(&left_pad(y, 32).ok_or_else(err)?);
+        Ok((handle, point))
+    }
 
-        /// Get attestation quote (scaffold)
-        pub async fn get_quote(&self, _nonce: &[u8]) -> Result<Vec<u8>> {
-            // Would implement TPM2_Quote
-            Err(crate::Error::AttestationError(
-                "vTPM not implemented".to_string(),
-            ))
+    /// Parse a TPM2_Quote response into (TPMS_ATTEST bytes, r || s signature)
+    pub fn parse_quote_response(buf: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut r = response(buf, "Quote")?;
+        let err = || malformed("Quote response");
+        let _param_size = r.u32().ok_or_else(err)?;
+        let quoted = r.tpm2b().ok_or_else(err)?.to_vec();
+        if r.u16().ok_or_else(err)? != TPM_ALG_ECDSA {
+            return Err(Error::AttestationError(
+                "quote is not ECDSA signed".to_string(),
+            ));
         }
+        let _hash = r.u16().ok_or_else(err)?;
+        let sig_r = left_pad(r.tpm2b().ok_or_else(err)?, 32).ok_or_else(err)?;
+        let sig_s = left_pad(r.tpm2b().ok_or_else(err)?, 32).ok_or_else(err)?;
+        Ok((quoted, [sig_r, sig_s].concat()))
     }
 
-    /// Document Linux host requirements for vTPM
+    /// Parse a TPM2_PCR_Read response into the PCRs it returned
+    pub fn parse_pcr_read_response(buf: &[u8]) -> Result<BTreeMap<u32, Vec<u8>>> {
+        let mut r = response(buf, "PCR_Read")?;
+        let err = || malformed("PCR_Read response");
+        let _update_counter = r.u32().ok_or_else(err)?;
+        let pcrs = r.pcr_selection().ok_or_else(err)?;
+        let count = r.u32().ok_or_else(err)? as usize;
+        if count != pcrs.len() {
+            return Err(err());
+        }
+        let mut values = BTreeMap::new();
+        for pcr in pcrs {
+            values.insert(pcr, r.tpm2b().ok_or_else(err)?.to_vec());
+        }
+        Ok(values)
+    }
+
+    /// Parse a TPMS_ATTEST quote structure
+    pub fn parse_quote_info(quoted: &[u8]) -> Result<QuoteInfo> {
+        let mut r = Reader::new(quoted);
+        let err = || malformed("attestation structure");
+        if r.u32().ok_or_else(err)? != TPM_GENERATED_VALUE {
+            return Err(Error::AttestationError(
+                "quote was not generated by a TPM".to_string(),
+            ));
+        }
+        if r.u16().ok_or_else(err)? != TPM_ST_ATTEST_QUOTE {
+            return Err(Error::AttestationError(
+                "attestation structure is not a quote".to_string(),
+            ));
+        }
+        r.tpm2b().ok_or_else(err)?; // qualifiedSigner
+        let extra_data = r.tpm2b().ok_or_else(err)?.to_vec();
+        let _clock = r.u64().ok_or_else(err)?;
+        let reset_count = r.u32().ok_or_else(err)?;
+        let restart_count = r.u32().ok_or_else(err)?;
+        let _safe = r.u8().ok_or_else(err)?;
+        let _firmware_version = r.u64().ok_or_else(err)?;
+        let pcrs = r.pcr_selection().ok_or_else(err)?;
+        let pcr_digest = r.tpm2b().ok_or_else(err)?.to_vec();
+
+        Ok(QuoteInfo {
+            extra_data,
+            reset_count,
+            restart_count,
+            pcrs,
+            pcr_digest,
+        })
+    }
+
+    fn left_pad(v: &[u8], len: usize) -> Option<Vec<u8>> {
+        if v.len() > len {
+            return None;
+        }
+        let mut out = vec![0u8; len - v.len()];
+        out.extend_from_slice(v);
+        Some(out)
+    }
+
+    // ------------------------------------------------------------------
+    // Event log
+    // ------------------------------------------------------------------
+
+    /// Skip the handle and authorization areas of a command with one handle
+    fn command_params(r: &mut Reader<'_>, handles: usize) -> Option<u32> {
+        let (tag, _, _) = (r.u16()?, r.u32()?, r.u32()?);
+        let pcr_handle = r.u32()?;
+        for _ in 1..handles {
+            r.u32()?;
+        }
+        if tag == TPM_ST_SESSIONS {
+            let auth_size = r.u32()? as usize;
+            r.take(auth_size)?;
+        }
+        Some(pcr_handle)
+    }
+
+    /// Extract the PCR extension, if any, performed by a successful command
+    pub fn event_from_exchange(command: &[u8], response: &[u8]) -> Option<TpmEvent> {
+        let (_, _, cc) = parse_header(command)?;
+        if response_code(response)? != 0 {
+            return None;
+        }
+
+        let mut cmd = Reader::new(command);
+        let (pcr_index, digest, name, data) = match cc {
+            TPM_CC_PCR_EXTEND => {
+                let pcr = command_params(&mut cmd, 1)?;
+                (pcr, cmd.digest_values()??, "PCR_Extend", Vec::new())
+            }
+            TPM_CC_PCR_EVENT | TPM_CC_EVENT_SEQUENCE_COMPLETE => {
+                let handles = if cc == TPM_CC_PCR_EVENT { 1 } else { 2 };
+                let pcr = command_params(&mut cmd, handles)?;
+                let data = if cc == TPM_CC_PCR_EVENT {
+                    cmd.tpm2b()?.to_vec()
+                } else {
+                    Vec::new()
+                };
+                let mut resp = Reader::new(response);
+                resp.take(10)?;
+                resp.u32()?; // parameterSize
+                let name = if cc == TPM_CC_PCR_EVENT {
+                    "PCR_Event"
+                } else {
+                    "EventSequenceComplete"
+                };
+                (pcr, resp.digest_values()??, name, data)
+            }
+            _ => return None,
+        };
+
+        // TPM_RH_NULL and other non-PCR handles do not extend anything
+        if pcr_index >= PCR_COUNT {
+            return None;
+        }
+
+        Some(TpmEvent {
+            pcr_index,
+            digest: hex::encode(digest),
+            command: name.to_string(),
+            data,
+        })
+    }
+
+    /// Replay an event log, returning the resulting value of every PCR it touched
+    pub fn replay_event_log(events: &[TpmEvent]) -> Result<BTreeMap<u32, [u8; 32]>> {
+        let mut pcrs: BTreeMap<u32, [u8; 32]> = BTreeMap::new();
+        for event in events {
+            let digest = hex::decode(&event.digest)
+                .map_err(|e| Error::AttestationError(format!("bad event digest: {}", e)))?;
+            let value = pcrs.entry(event.pcr_index).or_insert([0u8; 32]);
+            let mut hasher = Sha256::new();
+            hasher.update(*value);
+            hasher.update(&digest);
+            value.copy_from_slice(&hasher.finalize());
+        }
+        Ok(pcrs)
+    }
+
+    // ------------------------------------------------------------------
+    // Verification
+    // ------------------------------------------------------------------
+
+    fn verify_signature(quote: &TpmQuote) -> bool {
+        use p256::ecdsa::signature::Verifier;
+        use p256::ecdsa::{Signature, VerifyingKey};
+
+        let Ok(key) = VerifyingKey::from_sec1_bytes(&quote.ak_public) else {
+            return false;
+        };
+        let Ok(signature) = Signature::from_slice(&quote.signature) else {
+            return false;
+        };
+        key.verify(&quote.quoted, &signature).is_ok()
+    }
+
+    /// Verify a quote's signature, freshness, PCR values and event log.
+    ///
+    /// `expected_nonce` should be the nonce the caller sent with the quote
+    /// request; without it only the nonce recorded in the quote is checked.
+    pub fn verify_quote(
+        quote: &TpmQuote,
+        expected_nonce: Option<&[u8]>,
+        policy: Option<&PcrPolicy>,
+    ) -> Result<QuoteVerification> {
+        let info = parse_quote_info(&quote.quoted)?;
+        let mut result = QuoteVerification {
+            signature_valid: verify_signature(quote),
+            nonce_valid: info.extra_data == quote.nonce
+                && expected_nonce.is_none_or(|n| n == quote.nonce.as_slice()),
+            ak_trusted: true,
+            ..Default::default()
+        };
+
+        // The quoted PCR digest must match the values shipped alongside it
+        let mut hasher = Sha256::new();
+        let mut complete = info.pcrs.len() == quote.pcrs.len();
+        for pcr in &info.pcrs {
+            match quote.pcrs.get(pcr).and_then(|v| hex::decode(v).ok()) {
+                Some(value) => hasher.update(&value),
+                None => complete = false,
+            }
+        }
+        result.pcr_digest_valid = complete && hasher.finalize()[..] == info.pcr_digest[..];
+
+        // Replaying the event log must reproduce every resettable PCR
+        let replayed = replay_event_log(&quote.event_log)?;
+        for (pcr, value) in &quote.pcrs {
+            if !RESETTABLE_PCRS.contains(pcr) {
+                continue;
+            }
+            let expected = replayed.get(pcr).copied().unwrap_or([0u8; 32]);
+            if !value.eq_ignore_ascii_case(&hex::encode(expected)) {
+                result.event_log_mismatches.push(*pcr);
+            }
+        }
+        result.event_log_valid = result.event_log_mismatches.is_empty();
+
+        if let Some(policy) = policy {
+            if let Some(ak) = &policy.ak_public {
+                result.ak_trusted = ak.eq_ignore_ascii_case(&hex::encode(&quote.ak_public));
+            }
+            for (pcr, expected) in &policy.pcrs {
+                let matches = quote
+                    .pcrs
+                    .get(pcr)
+                    .is_some_and(|v| v.eq_ignore_ascii_case(expected));
+                if !matches {
+                    result.policy_mismatches.push(*pcr);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Document host requirements for vTPM
     pub fn linux_vtpm_requirements() -> &'static str {
         r#"
-vTPM Support Requirements (Linux Host):
+vTPM Support Requirements:
 
-1. Install swtpm:
+1. Install swtpm on the host:
    - Ubuntu/Debian: sudo apt install swtpm swtpm-tools
    - Fedora: sudo dnf install swtpm swtpm-tools
-   - macOS: brew install swtpm (limited support)
+   - macOS: brew install swtpm
 
-2. Configure QEMU with TPM:
-   -chardev socket,id=chrtpm,path=/tmp/swtpm.sock
-   -tpmdev emulator,id=tpm0,chardev=chrtpm
-   -device tpm-tis,tpmdev=tpm0
+2. Enable the TPM on the VM (spec.enable_tpm). The daemon starts swtpm,
+   attaches it with -tpmdev emulator and records PCR quotes at boot and
+   snapshot time.
 
 3. Guest kernel requirements:
-   - CONFIG_TCG_TIS=y or CONFIG_TCG_TIS=m
    - CONFIG_TCG_TPM=y
+   - CONFIG_TCG_TIS=y (tpm-tis-device on the virt machine)
 
-4. Guest userspace:
-   - tpm2-tools package
-   - tpm2-tss library
-
-5. IMA (Integrity Measurement Architecture):
-   - CONFIG_IMA=y
-   - Boot with ima_policy=tcb
-
-Note: Full vTPM support requires Linux host. macOS support is limited.
+4. Optional guest measurement:
+   - IMA (CONFIG_IMA=y, boot with ima_policy=tcb) extends PCR 10
 "#
     }
 }
@@ -337,7 +1139,9 @@ This is a placeholder for future cloud deployment scenarios.
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{ResourceMeta, VmSpec, VmStatus, VolumeSpec, VolumeStatus};
+    use crate::types::{
+        ResourceMeta, TpmEvent, VmSpec, VmStatus, VolumeSpec, VolumeStatus,
+    };
 
     #[test]
     fn test_hvf_check() {
@@ -345,6 +1149,15 @@ mod tests {
         let _available = is_hvf_available();
     }
 
+    #[test]
+    fn test_host_accelerator() {
+        let accel = host_accelerator();
+        assert!(["hvf", "kvm", "tcg"].contains(&accel));
+        if cfg!(not(target_os = "macos")) {
+            assert_ne!(accel, "hvf");
+        }
+    }
+
     #[test]
     fn test_attestation_generation() {
         let key_pair = KeyPair::generate();
@@ -389,4 +1202,202 @@ mod tests {
         let report = provider.generate_report(&vm, &[], &[]).unwrap();
         assert!(provider.verify_report(&report).unwrap());
     }
+
+    #[test]
+    fn test_verify_report_with_public_key() {
+        let key_pair = KeyPair::generate();
+        let public = crate::crypto::verifying_key_from_bytes(&key_pair.public_key_bytes()).unwrap();
+        let provider = AttestationProvider::new(key_pair);
+
+        let vm = Vm {
+            meta: ResourceMeta::new("test-vm".to_string()),
+            spec: VmSpec::default(),
+            status: VmStatus::default(),
+        };
+        let volumes: Vec<Volume> = ["vol-b", "vol-a", "vol-c"]
+            .iter()
+            .map(|name| Volume {
+                meta: ResourceMeta::new(name.to_string()),
+                spec: VolumeSpec::default(),
+                status: VolumeStatus {
+                    digest: Some(format!("{}-digest", name)),
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        let mut report = provider.generate_report(&vm, &volumes, &[]).unwrap();
+        assert!(verify_report_with_key(&report, &public).unwrap().is_valid());
+
+        // A round trip through JSON must keep the digest reproducible
+        let json = serde_json::to_string(&report).unwrap();
+        let decoded: AttestationReport = serde_json::from_str(&json).unwrap();
+        assert!(verify_report_with_key(&decoded, &public).unwrap().is_valid());
+
+        let other = KeyPair::generate();
+        let result = verify_report_with_key(&report, &other.verifying_key()).unwrap();
+        assert!(result.digest_valid);
+        assert!(!result.signature_valid);
+
+        report.host_provenance.hostname = "elsewhere".to_string();
+        let result = verify_report_with_key(&report, &public).unwrap();
+        assert!(!result.digest_valid);
+        assert!(result.signature_valid);
+    }
+
+    fn extend_exchange(pcr: u32, digest: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+        let mut cmd = Vec::new();
+        cmd.extend_from_slice(&vtpm::TPM_ST_SESSIONS.to_be_bytes());
+        cmd.extend_from_slice(&0u32.to_be_bytes());
+        cmd.extend_from_slice(&vtpm::TPM_CC_PCR_EXTEND.to_be_bytes());
+        cmd.extend_from_slice(&pcr.to_be_bytes());
+        cmd.extend_from_slice(&9u32.to_be_bytes());
+        cmd.extend_from_slice(&[0x40, 0, 0, 9, 0, 0, 0, 0, 0]);
+        cmd.extend_from_slice(&2u32.to_be_bytes());
+        cmd.extend_from_slice(&0x0004u16.to_be_bytes());
+        cmd.extend_from_slice(&[0xaa; 20]);
+        cmd.extend_from_slice(&0x000Bu16.to_be_bytes());
+        cmd.extend_from_slice(digest);
+        let len = cmd.len() as u32;
+        cmd[2..6].copy_from_slice(&len.to_be_bytes());
+
+        let resp = [0x80, 0x02, 0, 0, 0, 0x13, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0]
+            .to_vec();
+        (cmd, resp)
+    }
+
+    fn synthetic_quote(nonce: &[u8], events: Vec<TpmEvent>) -> TpmQuote {
+        use p256::ecdsa::{signature::Signer as _, Signature, SigningKey};
+
+        let replayed = vtpm::replay_event_log(&events).unwrap();
+        let pcrs: std::collections::BTreeMap<u32, String> = (0..8)
+            .map(|i| (i, hex::encode(replayed.get(&i).copied().unwrap_or([0u8; 32]))))
+            .collect();
+
+        let mut pcr_hasher = Sha256::new();
+        for value in pcrs.values() {
+            pcr_hasher.update(hex::decode(value).unwrap());
+        }
+
+        let mut quoted = Vec::new();
+        quoted.extend_from_slice(&0xff54_4347u32.to_be_bytes());
+        quoted.extend_from_slice(&0x8018u16.to_be_bytes());
+        quoted.extend_from_slice(&0u16.to_be_bytes());
+        quoted.extend_from_slice(&(nonce.len() as u16).to_be_bytes());
+        quoted.extend_from_slice(nonce);
+        quoted.extend_from_slice(&[0u8; 17]);
+        quoted.extend_from_slice(&[0u8; 8]);
+        quoted.extend_from_slice(&1u32.to_be_bytes());
+        quoted.extend_from_slice(&0x000Bu16.to_be_bytes());
+        quoted.extend_from_slice(&[3, 0xff, 0, 0]);
+        quoted.extend_from_slice(&32u16.to_be_bytes());
+        quoted.extend_from_slice(&pcr_hasher.finalize());
+
+        let key = SigningKey::random(&mut rand::rngs::OsRng);
+        let signature: Signature = key.sign(&quoted);
+
+        TpmQuote {
+            vm_id: "vm-1".to_string(),
+            label: "boot".to_string(),
+            pcrs,
+            quoted,
+            signature: signature.to_bytes().to_vec(),
+            ak_public: key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+            nonce: nonce.to_vec(),
+            event_log: events,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_pcr_selection() {
+        assert_eq!(
+            vtpm::parse_pcr_selection("0-3, 7,2").unwrap(),
+            vec![0, 1, 2, 3, 7]
+        );
+        assert!(vtpm::parse_pcr_selection("24").is_err());
+        assert!(vtpm::parse_pcr_selection("5-2").is_err());
+        assert!(vtpm::parse_pcr_selection("").is_err());
+    }
+
+    #[test]
+    fn test_tpm_command_marshalling() {
+        let cmd = vtpm::quote_command(0x8000_0000, b"nonce", &[0, 1, 2, 3, 4, 5, 6, 7]);
+        let (tag, size, cc) = vtpm::parse_header(&cmd).unwrap();
+        assert_eq!(tag, vtpm::TPM_ST_SESSIONS);
+        assert_eq!(size as usize, cmd.len());
+        assert_eq!(cc, 0x0158);
+        assert_eq!(&cmd[cmd.len() - 3..], &[0xff, 0, 0]);
+
+        let cmd = vtpm::create_primary_ak_command();
+        assert_eq!(vtpm::parse_header(&cmd).unwrap().1 as usize, cmd.len());
+
+        let err = [0x80, 0x01, 0, 0, 0, 10, 0, 0, 0x01, 0x00];
+        assert!(vtpm::parse_quote_response(&err).is_err());
+        assert_eq!(vtpm::response_code(&err), Some(vtpm::TPM_RC_INITIALIZE));
+    }
+
+    #[test]
+    fn test_event_from_exchange() {
+        let (cmd, resp) = extend_exchange(4, &[0x11; 32]);
+        let event = vtpm::event_from_exchange(&cmd, &resp).unwrap();
+        assert_eq!(event.pcr_index, 4);
+        assert_eq!(event.digest, hex::encode([0x11; 32]));
+        assert_eq!(event.command, "PCR_Extend");
+
+        // Failed commands extend nothing
+        let failed = [0x80, 0x01, 0, 0, 0, 10, 0, 0, 0x09, 0x22];
+        assert!(vtpm::event_from_exchange(&cmd, &failed).is_none());
+    }
+
+    #[test]
+    fn test_verify_quote() {
+        let events: Vec<TpmEvent> = [(0, [1u8; 32]), (0, [2u8; 32]), (4, [3u8; 32])]
+            .iter()
+            .map(|(pcr, digest)| {
+                let (cmd, resp) = extend_exchange(*pcr, digest);
+                vtpm::event_from_exchange(&cmd, &resp).unwrap()
+            })
+            .collect();
+        let quote = synthetic_quote(b"fresh", events);
+        let policy = vtpm::PcrPolicy::from_quote(&quote);
+
+        let result = vtpm::verify_quote(&quote, Some(b"fresh"), Some(&policy)).unwrap();
+        assert!(result.is_trusted(), "{:?}", result);
+
+        // Stale nonce
+        let result = vtpm::verify_quote(&quote, Some(b"other"), Some(&policy)).unwrap();
+        assert!(!result.nonce_valid);
+
+        // Policy expecting different boot measurements
+        let mut strict = policy.clone();
+        strict.pcrs.insert(4, hex::encode([0u8; 32]));
+        let result = vtpm::verify_quote(&quote, None, Some(&strict)).unwrap();
+        assert_eq!(result.policy_mismatches, vec![4]);
+        assert!(!result.is_trusted());
+
+        // Tampered PCR value no longer matches the signed digest or event log
+        let mut tampered = quote.clone();
+        tampered.pcrs.insert(0, hex::encode([9u8; 32]));
+        let result = vtpm::verify_quote(&tampered, None, None).unwrap();
+        assert!(result.signature_valid);
+        assert!(!result.pcr_digest_valid);
+        assert_eq!(result.event_log_mismatches, vec![0]);
+
+        // Truncated event log
+        let mut truncated = quote.clone();
+        truncated.event_log.pop();
+        let result = vtpm::verify_quote(&truncated, None, None).unwrap();
+        assert_eq!(result.event_log_mismatches, vec![4]);
+
+        // Forged signature
+        let mut forged = quote;
+        forged.quoted[20] ^= 1;
+        let result = vtpm::verify_quote(&forged, None, None).unwrap();
+        assert!(!result.signature_valid);
+    }
 }
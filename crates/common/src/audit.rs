@@ -0,0 +1,399 @@
+//! Append-only audit log of mutating operations
+//!
+//! The daemon records every mutating gRPC call and the web server every
+//! mutating HTTP request: who made it, from where, what it touched and how it
+//! ended. Both write the `audit_log` table of their state database, whose
+//! triggers refuse updates and deletes.
+//!
+//! With hash chaining on, each entry also stores the hash of the previous
+//! chained entry and its own hash, optionally signed, so rows edited or
+//! removed behind SQLite's back break [`AuditLog::verify`].
+
+use crate::crypto::{key_id, KeyPair, Signer, Verifier};
+use crate::{Database, Error, Result};
+use ed25519_dalek::VerifyingKey;
+use rusqlite::{params, OptionalExtension, Row, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Entries [`AuditLog::list`] returns when the filter sets no limit
+pub const DEFAULT_LIST_LIMIT: usize = 1000;
+
+/// Status of an operation that succeeded
+pub const STATUS_OK: &str = "ok";
+
+/// One audited operation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, assigned on append
+    pub seq: i64,
+    /// Unix seconds; set on append when zero
+    pub timestamp: i64,
+    /// Component that carried out the operation: `daemon` or `web`
+    pub source: String,
+    /// Who asked, e.g. `token:ci`, `uid:501` or a web identity
+    pub identity: String,
+    pub source_ip: Option<String>,
+    /// gRPC method, or HTTP method and path
+    pub operation: String,
+    pub resource_kind: Option<String>,
+    pub resource_id: Option<String>,
+    /// [`STATUS_OK`], or the gRPC code or HTTP status it failed with
+    pub status: String,
+    /// [`digest`] of the resource before the operation
+    pub before_digest: Option<String>,
+    /// [`digest`] of the resource after the operation
+    pub after_digest: Option<String>,
+    /// Hash of the previous chained entry
+    pub prev_hash: Option<String>,
+    /// Hash of this entry, including `prev_hash`
+    pub hash: Option<String>,
+    /// Hex Ed25519 signature over `hash`
+    pub signature: Option<String>,
+    /// ID of the key that made `signature`
+    pub key_id: Option<String>,
+}
+
+impl AuditEntry {
+    /// Hash over everything but the hash and signature themselves
+    fn compute_hash(&self) -> String {
+        let fields = (
+            self.seq,
+            self.timestamp,
+            &self.source,
+            &self.identity,
+            &self.source_ip,
+            &self.operation,
+            &self.resource_kind,
+            &self.resource_id,
+            &self.status,
+            &self.before_digest,
+            &self.after_digest,
+            &self.prev_hash,
+        );
+        // Serializing a tuple of plain values can't fail
+        hex::encode(Sha256::digest(serde_json::to_vec(&fields).unwrap_or_default()))
+    }
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            seq: row.get(0)?,
+            timestamp: row.get(1)?,
+            source: row.get(2)?,
+            identity: row.get(3)?,
+            source_ip: row.get(4)?,
+            operation: row.get(5)?,
+            resource_kind: row.get(6)?,
+            resource_id: row.get(7)?,
+            status: row.get(8)?,
+            before_digest: row.get(9)?,
+            after_digest: row.get(10)?,
+            prev_hash: row.get(11)?,
+            hash: row.get(12)?,
+            signature: row.get(13)?,
+            key_id: row.get(14)?,
+        })
+    }
+}
+
+const COLUMNS: &str = "seq, timestamp, source, identity, source_ip, operation, resource_kind, \
+     resource_id, status, before_digest, after_digest, prev_hash, hash, signature, key_id";
+
+/// Which entries [`AuditLog::list`] returns; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    /// Earliest timestamp, inclusive
+    pub since: Option<i64>,
+    /// Latest timestamp, inclusive
+    pub until: Option<i64>,
+    pub source: Option<String>,
+    pub identity: Option<String>,
+    pub resource_id: Option<String>,
+    /// Most entries returned, newest first; 0 means [`DEFAULT_LIST_LIMIT`]
+    pub limit: usize,
+}
+
+/// Outcome of [`AuditLog::verify`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChainVerification {
+    /// Chained entries whose hash, and signature if any, checked out
+    pub verified: u64,
+    /// Entries written with chaining off, which can't be checked
+    pub unchained: u64,
+    /// Sequence number of the first entry that doesn't fit the chain
+    pub broken_at: Option<i64>,
+    /// Why that entry doesn't fit
+    pub error: Option<String>,
+}
+
+impl ChainVerification {
+    pub fn is_valid(&self) -> bool {
+        self.broken_at.is_none()
+    }
+}
+
+/// Audit log in a state database
+#[derive(Clone)]
+pub struct AuditLog {
+    db: Database,
+    chain: bool,
+    signer: Option<KeyPair>,
+}
+
+impl AuditLog {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            chain: false,
+            signer: None,
+        }
+    }
+
+    /// Chain new entries by hash, signing each hash with `signer` if given
+    pub fn with_hash_chain(mut self, signer: Option<KeyPair>) -> Self {
+        self.chain = true;
+        self.signer = signer;
+        self
+    }
+
+    /// Append an entry, returning it with its sequence number and chain fields
+    pub fn append(&self, mut entry: AuditEntry) -> Result<AuditEntry> {
+        let conn_arc = self.db.connection();
+        let mut conn = conn_arc.lock();
+        // The daemon and web server may share the database, so take the write
+        // lock before reading the chain head
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let last: i64 = tx.query_row("SELECT COALESCE(MAX(seq), 0) FROM audit_log", [], |row| row.get(0))?;
+        entry.seq = last + 1;
+        if entry.timestamp == 0 {
+            entry.timestamp = chrono::Utc::now().timestamp();
+        }
+        entry.prev_hash = None;
+        entry.hash = None;
+        entry.signature = None;
+        entry.key_id = None;
+        if self.chain {
+            entry.prev_hash = tx
+                .query_row(
+                    "SELECT hash FROM audit_log WHERE hash IS NOT NULL ORDER BY seq DESC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let hash = entry.compute_hash();
+            if let Some(signer) = &self.signer {
+                entry.signature = Some(hex::encode(signer.sign(hash.as_bytes())));
+                entry.key_id = Some(signer.key_id());
+            }
+            entry.hash = Some(hash);
+        }
+
+        tx.execute(
+            &format!(
+                "INSERT INTO audit_log ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                COLUMNS
+            ),
+            params![
+                entry.seq,
+                entry.timestamp,
+                entry.source,
+                entry.identity,
+                entry.source_ip,
+                entry.operation,
+                entry.resource_kind,
+                entry.resource_id,
+                entry.status,
+                entry.before_digest,
+                entry.after_digest,
+                entry.prev_hash,
+                entry.hash,
+                entry.signature,
+                entry.key_id,
+            ],
+        )?;
+        tx.commit()?;
+        Ok(entry)
+    }
+
+    /// Entries matching `filter`, newest first
+    pub fn list(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        let limit = if filter.limit == 0 { DEFAULT_LIST_LIMIT } else { filter.limit };
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM audit_log
+             WHERE (?1 IS NULL OR timestamp >= ?1) AND (?2 IS NULL OR timestamp <= ?2)
+               AND (?3 IS NULL OR source = ?3) AND (?4 IS NULL OR identity = ?4)
+               AND (?5 IS NULL OR resource_id = ?5)
+             ORDER BY seq DESC LIMIT ?6",
+            COLUMNS
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                filter.since,
+                filter.until,
+                filter.source,
+                filter.identity,
+                filter.resource_id,
+                limit as i64,
+            ],
+            AuditEntry::from_row,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Walk the whole log checking the hash chain
+    ///
+    /// Signatures are checked against `key` when one is given; entries signed
+    /// by any other key then break the chain.
+    pub fn verify(&self, key: Option<&VerifyingKey>) -> Result<ChainVerification> {
+        let expected_key_id = key.map(|k| key_id(k.as_bytes()));
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM audit_log ORDER BY seq", COLUMNS))?;
+        let mut rows = stmt.query_map([], AuditEntry::from_row)?;
+
+        let mut result = ChainVerification::default();
+        let mut head: Option<String> = None;
+        for entry in &mut rows {
+            let entry = entry?;
+            let Some(hash) = &entry.hash else {
+                result.unchained += 1;
+                continue;
+            };
+            let problem = if entry.prev_hash != head {
+                Some("previous hash doesn't match the preceding chained entry".to_string())
+            } else if *hash != entry.compute_hash() {
+                Some("hash doesn't match the entry's contents".to_string())
+            } else {
+                match (key, &entry.signature) {
+                    (Some(key), Some(signature)) => {
+                        if entry.key_id != expected_key_id {
+                            Some(format!("signed by unknown key {}", entry.key_id.as_deref().unwrap_or("?")))
+                        } else {
+                            let signature = hex::decode(signature).map_err(|e| Error::Crypto(e.to_string()));
+                            signature
+                                .and_then(|s| key.verify(hash.as_bytes(), &s))
+                                .err()
+                                .map(|e| format!("bad signature: {}", e))
+                        }
+                    }
+                    _ => None,
+                }
+            };
+            if let Some(problem) = problem {
+                result.broken_at = Some(entry.seq);
+                result.error = Some(problem);
+                return Ok(result);
+            }
+            head = Some(hash.clone());
+            result.verified += 1;
+        }
+        Ok(result)
+    }
+}
+
+/// SHA-256 of a resource's JSON form, for before and after digests
+pub fn digest<T: Serialize>(resource: &T) -> String {
+    hex::encode(Sha256::digest(serde_json::to_vec(resource).unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(identity: &str, resource_id: &str) -> AuditEntry {
+        AuditEntry {
+            source: "daemon".to_string(),
+            identity: identity.to_string(),
+            operation: "CreateVm".to_string(),
+            resource_kind: Some("vm".to_string()),
+            resource_id: Some(resource_id.to_string()),
+            status: STATUS_OK.to_string(),
+            after_digest: Some(digest(&resource_id)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_append_and_list() {
+        let log = AuditLog::new(Database::open_memory().unwrap());
+        log.append(AuditEntry { timestamp: 100, ..entry("token:ci", "vm-1") }).unwrap();
+        log.append(AuditEntry { timestamp: 200, ..entry("uid:501", "vm-2") }).unwrap();
+        let third = log.append(AuditEntry { timestamp: 300, ..entry("token:ci", "vm-2") }).unwrap();
+        assert_eq!(third.seq, 3);
+        assert!(third.hash.is_none());
+
+        let all = log.list(&AuditFilter::default()).unwrap();
+        assert_eq!(all.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(all[0], third);
+
+        let filter = AuditFilter {
+            since: Some(150),
+            identity: Some("token:ci".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(log.list(&filter).unwrap(), vec![third]);
+        let filter = AuditFilter {
+            resource_id: Some("vm-2".to_string()),
+            limit: 1,
+            ..Default::default()
+        };
+        assert_eq!(log.list(&filter).unwrap()[0].seq, 3);
+    }
+
+    #[test]
+    fn test_rows_are_append_only() {
+        let db = Database::open_memory().unwrap();
+        let log = AuditLog::new(db.clone());
+        log.append(entry("token:ci", "vm-1")).unwrap();
+
+        let conn = db.connection();
+        let conn = conn.lock();
+        assert!(conn.execute("UPDATE audit_log SET identity = 'someone-else'", []).is_err());
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+    }
+
+    #[test]
+    fn test_hash_chain_detects_tampering() {
+        let db = Database::open_memory().unwrap();
+        let key = KeyPair::generate();
+        let log = AuditLog::new(db.clone()).with_hash_chain(Some(key.clone()));
+        // Entries from before chaining was turned on are skipped
+        AuditLog::new(db.clone()).append(entry("token:ci", "vm-0")).unwrap();
+        for id in ["vm-1", "vm-2", "vm-3"] {
+            log.append(entry("token:ci", id)).unwrap();
+        }
+
+        let result = log.verify(Some(&key.verifying_key())).unwrap();
+        assert!(result.is_valid(), "{:?}", result);
+        assert_eq!((result.verified, result.unchained), (3, 1));
+
+        let other = KeyPair::generate();
+        let result = log.verify(Some(&other.verifying_key())).unwrap();
+        assert_eq!(result.broken_at, Some(2));
+
+        // Rewrite history the way someone with write access to the file could
+        let conn = db.connection();
+        conn.lock()
+            .execute_batch(
+                "DROP TRIGGER audit_log_no_update; DROP TRIGGER audit_log_no_delete;
+                 UPDATE audit_log SET identity = 'token:other' WHERE seq = 3;",
+            )
+            .unwrap();
+        let result = log.verify(None).unwrap();
+        assert_eq!(result.broken_at, Some(3));
+        assert!(result.error.unwrap().contains("contents"));
+
+        conn.lock()
+            .execute_batch(
+                "UPDATE audit_log SET identity = 'token:ci' WHERE seq = 3;
+                 DELETE FROM audit_log WHERE seq = 3;",
+            )
+            .unwrap();
+        let result = log.verify(None).unwrap();
+        assert_eq!(result.broken_at, Some(4));
+        assert!(result.error.unwrap().contains("previous hash"));
+    }
+}
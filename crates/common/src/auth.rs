@@ -0,0 +1,573 @@
+//! Daemon API authentication
+//!
+//! infrasimd's gRPC listener can require mutual TLS, bearer tokens, or both.
+//! Everything lives under `<store>/auth/`:
+//!
+//! - `ca.crt`, `ca.key`: the daemon-managed CA client certificates chain to
+//! - `server.crt`, `server.key`: the daemon's serving certificate, issued by the same CA
+//! - `tokens.json`: named bearer tokens, stored as SHA-256 hashes
+//!
+//! Clients (CLI, web console, Terraform provider) describe what they present
+//! with [`ClientCredentials`] and connect through [`ClientCredentials::connect`].
+//! The daemon's Unix socket needs neither: it is guarded by file permissions.
+
+use crate::{Error, Result};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use parking_lot::Mutex;
+use rand::{Rng, RngCore};
+use rcgen::{
+    date_time_ymd, BasicConstraints, CertificateParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair as RcgenKeyPair, KeyUsagePurpose, SanType,
+    SerialNumber, PKCS_ECDSA_P256_SHA256,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::net::IpAddr;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Request, Status};
+use tracing::info;
+
+/// Days a client certificate is valid unless another lifetime is asked for
+pub const DEFAULT_CLIENT_CERT_DAYS: i64 = 365;
+
+/// Days the CA and the daemon's serving certificate are valid
+const CA_VALIDITY_DAYS: i64 = 3650;
+
+/// Prefix of generated bearer tokens, so they are recognisable in configs
+const TOKEN_PREFIX: &str = "isk_";
+
+/// A certificate and its private key, PEM-encoded
+#[derive(Debug, Clone)]
+pub struct IssuedCert {
+    pub cert_pem: String,
+    pub key_pem: String,
+    /// Hex-encoded certificate serial number
+    pub serial: String,
+    /// Last day the certificate is valid
+    pub not_after: NaiveDate,
+}
+
+/// The daemon-managed certificate authority
+#[derive(Debug, Clone)]
+pub struct CertificateAuthority {
+    pub cert_pem: String,
+    key_pem: String,
+}
+
+/// CA parameters, also used to rebuild the issuer when signing
+fn ca_params() -> CertificateParams {
+    let mut params = CertificateParams::default();
+    params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+
+    let mut dn = DistinguishedName::new();
+    dn.push(DnType::OrganizationName, "InfraSim");
+    dn.push(DnType::CommonName, "InfraSim Daemon CA");
+    params.distinguished_name = dn;
+    params
+}
+
+impl CertificateAuthority {
+    /// Load the CA from an auth directory, creating it on first use
+    pub fn load_or_generate(dir: &Path) -> Result<Self> {
+        let cert_path = dir.join("ca.crt");
+        let key_path = dir.join("ca.key");
+
+        if cert_path.exists() && key_path.exists() {
+            return Ok(Self {
+                cert_pem: std::fs::read_to_string(&cert_path)?,
+                key_pem: std::fs::read_to_string(&key_path)?,
+            });
+        }
+
+        info!("Generating daemon CA in {}", dir.display());
+        let key = RcgenKeyPair::generate_for(&PKCS_ECDSA_P256_SHA256).map_err(crypto_error)?;
+        let mut params = ca_params();
+        let ymd = |d: NaiveDate| date_time_ymd(d.year(), d.month() as u8, d.day() as u8);
+        let today = Utc::now().date_naive();
+        params.not_before = ymd(today);
+        params.not_after = ymd(today + Duration::days(CA_VALIDITY_DAYS));
+        let cert = params.self_signed(&key).map_err(crypto_error)?;
+
+        let ca = Self {
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+        };
+        std::fs::create_dir_all(dir)?;
+        write_private(&key_path, ca.key_pem.as_bytes())?;
+        std::fs::write(&cert_path, &ca.cert_pem)?;
+        Ok(ca)
+    }
+
+    /// Issue a client certificate identifying `name`
+    pub fn issue_client_cert(&self, name: &str, days: i64) -> Result<IssuedCert> {
+        let mut params = CertificateParams::default();
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+        let mut dn = DistinguishedName::new();
+        dn.push(DnType::OrganizationName, "InfraSim");
+        dn.push(DnType::CommonName, name);
+        params.distinguished_name = dn;
+        self.sign(params, days)
+    }
+
+    /// Issue the daemon's serving certificate for the given DNS names and IPs
+    pub fn issue_server_cert(&self, names: &[String]) -> Result<IssuedCert> {
+        let mut params = CertificateParams::default();
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let mut dn = DistinguishedName::new();
+        dn.push(DnType::OrganizationName, "InfraSim");
+        dn.push(DnType::CommonName, "infrasimd");
+        params.distinguished_name = dn;
+
+        for name in names {
+            let san = match name.parse::<IpAddr>() {
+                Ok(ip) => SanType::IpAddress(ip),
+                Err(_) => SanType::DnsName(name.clone().try_into().map_err(|e| {
+                    Error::InvalidConfig(format!("Invalid server name '{}': {:?}", name, e))
+                })?),
+            };
+            if !params.subject_alt_names.contains(&san) {
+                params.subject_alt_names.push(san);
+            }
+        }
+        self.sign(params, CA_VALIDITY_DAYS)
+    }
+
+    fn sign(&self, mut params: CertificateParams, days: i64) -> Result<IssuedCert> {
+        if days <= 0 {
+            return Err(Error::InvalidConfig(format!(
+                "Certificate lifetime must be positive, got {} days",
+                days
+            )));
+        }
+
+        // Same subject and key as the stored CA, so the result chains to it
+        let ca_key = RcgenKeyPair::from_pem(&self.key_pem).map_err(crypto_error)?;
+        let issuer = ca_params().self_signed(&ca_key).map_err(crypto_error)?;
+
+        params.is_ca = IsCa::NoCa;
+        params.key_usages = vec![
+            KeyUsagePurpose::DigitalSignature,
+            KeyUsagePurpose::KeyEncipherment,
+        ];
+
+        // Positive 128-bit serial without a leading zero byte, so DER keeps all 16 bytes
+        let mut serial: [u8; 16] = rand::thread_rng().gen();
+        serial[0] = (serial[0] & 0x3f) | 0x40;
+        params.serial_number = Some(SerialNumber::from_slice(&serial));
+
+        let ymd = |d: NaiveDate| date_time_ymd(d.year(), d.month() as u8, d.day() as u8);
+        let today = Utc::now().date_naive();
+        let not_after = today + Duration::days(days);
+        params.not_before = ymd(today);
+        params.not_after = ymd(not_after);
+
+        let key = RcgenKeyPair::generate_for(&PKCS_ECDSA_P256_SHA256).map_err(crypto_error)?;
+        let cert = params.signed_by(&key, &issuer, &ca_key).map_err(crypto_error)?;
+
+        Ok(IssuedCert {
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+            serial: hex::encode(serial),
+            not_after,
+        })
+    }
+}
+
+fn crypto_error(e: rcgen::Error) -> Error {
+    Error::Crypto(e.to_string())
+}
+
+/// Write a file only its owner can read
+pub fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(data)?;
+    Ok(())
+}
+
+// ============================================================================
+// Bearer Tokens
+// ============================================================================
+
+/// A named bearer token; only its hash is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenEntry {
+    pub name: String,
+    /// Hex SHA-256 of the token
+    pub sha256: String,
+    pub created_at: i64,
+}
+
+/// Contents of `tokens.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TokenStore {
+    #[serde(default)]
+    pub tokens: Vec<TokenEntry>,
+}
+
+impl TokenStore {
+    /// Location of the token file in an auth directory
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join("tokens.json")
+    }
+
+    /// Load the token file, empty if it doesn't exist yet
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = Self::path(dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_slice(&std::fs::read(&path)?)?)
+    }
+
+    /// Write the token file
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        write_private(&Self::path(dir), &serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Create a token; the plaintext is returned once and never stored
+    pub fn create(&mut self, name: &str) -> Result<String> {
+        if self.tokens.iter().any(|t| t.name == name) {
+            return Err(Error::AlreadyExists {
+                kind: "token".to_string(),
+                id: name.to_string(),
+            });
+        }
+
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        let token = format!("{}{}", TOKEN_PREFIX, hex::encode(bytes));
+        self.tokens.push(TokenEntry {
+            name: name.to_string(),
+            sha256: hash_token(&token),
+            created_at: Utc::now().timestamp(),
+        });
+        Ok(token)
+    }
+
+    /// Remove a token by name
+    pub fn revoke(&mut self, name: &str) -> Result<()> {
+        let before = self.tokens.len();
+        self.tokens.retain(|t| t.name != name);
+        if self.tokens.len() == before {
+            return Err(Error::NotFound {
+                kind: "token".to_string(),
+                id: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Name of the token matching `token`, if any
+    pub fn verify(&self, token: &str) -> Option<&str> {
+        let hash = hash_token(token);
+        self.tokens
+            .iter()
+            .find(|t| t.sha256 == hash)
+            .map(|t| t.name.as_str())
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Server-side interceptor requiring a bearer token from `tokens.json`
+///
+/// The file is re-read when it changes, so tokens created or revoked with
+/// `infrasim auth` take effect without restarting the daemon.
+/// The name of the caller's token is attached to the request as an
+/// [`AuthenticatedToken`] extension.
+#[derive(Clone)]
+pub struct RequireToken {
+    dir: PathBuf,
+    cache: std::sync::Arc<Mutex<(Option<SystemTime>, TokenStore)>>,
+}
+
+impl RequireToken {
+    pub fn new(dir: &Path) -> Result<Self> {
+        let modified = std::fs::metadata(TokenStore::path(dir))
+            .and_then(|m| m.modified())
+            .ok();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            cache: std::sync::Arc::new(Mutex::new((modified, TokenStore::load(dir)?))),
+        })
+    }
+
+    fn check(&self, token: &str) -> Option<String> {
+        let mut cache = self.cache.lock();
+        let modified = std::fs::metadata(TokenStore::path(&self.dir))
+            .and_then(|m| m.modified())
+            .ok();
+        if modified != cache.0 {
+            match TokenStore::load(&self.dir) {
+                Ok(store) => *cache = (modified, store),
+                Err(e) => tracing::warn!("Keeping previous tokens, reload failed: {}", e),
+            }
+        }
+        cache.1.verify(token).map(str::to_string)
+    }
+}
+
+/// Name of the token a request was authenticated with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedToken(pub String);
+
+impl Interceptor for RequireToken {
+    fn call(&mut self, request: Request<()>) -> std::result::Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+
+        match self.check(token.trim()) {
+            Some(name) => {
+                tracing::debug!("Authenticated token '{}'", name);
+                let mut request = request;
+                request.extensions_mut().insert(AuthenticatedToken(name));
+                Ok(request)
+            }
+            None => Err(Status::unauthenticated("Invalid bearer token")),
+        }
+    }
+}
+
+// ============================================================================
+// Client Credentials
+// ============================================================================
+
+/// Channel to the daemon that attaches the configured bearer token
+pub type AuthChannel = InterceptedService<Channel, BearerAuth>;
+
+/// Credentials a client presents to the daemon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientCredentials {
+    /// CA the daemon's certificate is checked against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+    /// Client certificate for mutual TLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    /// Private key of the client certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// Bearer token from `infrasim auth create-token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Name to verify the daemon certificate against (default: the address host)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_domain: Option<String>,
+}
+
+impl ClientCredentials {
+    /// Fill unset fields from `INFRASIM_CA_CERT`, `INFRASIM_CLIENT_CERT`,
+    /// `INFRASIM_CLIENT_KEY`, `INFRASIM_TOKEN` and `INFRASIM_TLS_DOMAIN`
+    pub fn with_env(mut self) -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        self.ca_cert = self.ca_cert.or_else(|| var("INFRASIM_CA_CERT").map(PathBuf::from));
+        self.client_cert = self
+            .client_cert
+            .or_else(|| var("INFRASIM_CLIENT_CERT").map(PathBuf::from));
+        self.client_key = self
+            .client_key
+            .or_else(|| var("INFRASIM_CLIENT_KEY").map(PathBuf::from));
+        self.token = self.token.or_else(|| var("INFRASIM_TOKEN"));
+        self.tls_domain = self.tls_domain.or_else(|| var("INFRASIM_TLS_DOMAIN"));
+        self
+    }
+
+    /// Whether the connection needs TLS
+    pub fn uses_tls(&self) -> bool {
+        self.ca_cert.is_some() || self.client_cert.is_some()
+    }
+
+    /// Connect to the daemon at `addr` presenting these credentials
+    ///
+    /// `unix:///path/to/daemon.sock` dials the daemon's Unix socket, where
+    /// file permissions stand in for TLS.
+    pub async fn connect(&self, addr: &str) -> Result<AuthChannel> {
+        if let Some(path) = addr.strip_prefix("unix://") {
+            if self.uses_tls() {
+                return Err(Error::InvalidConfig(format!(
+                    "TLS credentials can't be used with socket address '{}'",
+                    addr
+                )));
+            }
+            let path = PathBuf::from(path);
+            // The URI is required by the endpoint but never dialed
+            let channel = Endpoint::from_static("http://[::]:50051")
+                .connect_with_connector(tower::service_fn(move |_: tonic::transport::Uri| {
+                    tokio::net::UnixStream::connect(path.clone())
+                }))
+                .await
+                .map_err(|e| Error::NetworkError(format!("{}: {}", addr, e)))?;
+            return Ok(InterceptedService::new(channel, self.bearer()?));
+        }
+
+        let mut endpoint = Endpoint::from_shared(addr.to_string())
+            .map_err(|e| Error::InvalidConfig(format!("Invalid daemon address '{}': {}", addr, e)))?;
+
+        if addr.starts_with("https://") {
+            endpoint = endpoint
+                .tls_config(self.tls_config()?)
+                .map_err(|e| Error::InvalidConfig(format!("TLS configuration: {}", e)))?;
+        } else if self.uses_tls() {
+            return Err(Error::InvalidConfig(format!(
+                "TLS credentials need an https:// daemon address, got '{}'",
+                addr
+            )));
+        }
+
+        let channel = endpoint
+            .connect()
+            .await
+            .map_err(|e| Error::NetworkError(format!("{}: {}", addr, e)))?;
+        Ok(InterceptedService::new(channel, self.bearer()?))
+    }
+
+    fn tls_config(&self) -> Result<ClientTlsConfig> {
+        let read = |path: &Path| {
+            std::fs::read(path).map_err(|e| {
+                Error::InvalidConfig(format!("Failed to read {}: {}", path.display(), e))
+            })
+        };
+
+        let mut tls = ClientTlsConfig::new();
+        if let Some(ca) = &self.ca_cert {
+            tls = tls.ca_certificate(Certificate::from_pem(read(ca)?));
+        }
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                tls = tls.identity(Identity::from_pem(read(cert)?, read(key)?));
+            }
+            (None, None) => {}
+            _ => {
+                return Err(Error::InvalidConfig(
+                    "client_cert and client_key must be set together".to_string(),
+                ))
+            }
+        }
+        if let Some(domain) = &self.tls_domain {
+            tls = tls.domain_name(domain.clone());
+        }
+        Ok(tls)
+    }
+
+    fn bearer(&self) -> Result<BearerAuth> {
+        let header = match &self.token {
+            Some(token) => Some(
+                format!("Bearer {}", token.trim())
+                    .parse()
+                    .map_err(|_| Error::InvalidConfig("Token is not valid ASCII".to_string()))?,
+            ),
+            None => None,
+        };
+        Ok(BearerAuth(header))
+    }
+}
+
+/// Client-side interceptor attaching `authorization: Bearer <token>`
+#[derive(Debug, Clone, Default)]
+pub struct BearerAuth(Option<MetadataValue<Ascii>>);
+
+impl Interceptor for BearerAuth {
+    fn call(&mut self, mut request: Request<()>) -> std::result::Result<Request<()>, Status> {
+        if let Some(header) = &self.0 {
+            request.metadata_mut().insert("authorization", header.clone());
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = TokenStore::load(dir.path()).unwrap();
+        let token = store.create("ci").unwrap();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert!(store.create("ci").is_err());
+        store.save(dir.path()).unwrap();
+
+        let mut interceptor = RequireToken::new(dir.path()).unwrap();
+        let request = |value: &str| {
+            let mut request = Request::new(());
+            request
+                .metadata_mut()
+                .insert("authorization", value.parse().unwrap());
+            request
+        };
+        let authenticated = interceptor.call(request(&format!("Bearer {}", token))).unwrap();
+        assert_eq!(
+            authenticated.extensions().get::<AuthenticatedToken>(),
+            Some(&AuthenticatedToken("ci".to_string()))
+        );
+        assert!(interceptor.call(request("Bearer isk_wrong")).is_err());
+        assert!(interceptor.call(Request::new(())).is_err());
+
+        // Revocation is picked up without a new interceptor
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let mut store = TokenStore::load(dir.path()).unwrap();
+        store.revoke("ci").unwrap();
+        assert!(store.revoke("ci").is_err());
+        store.save(dir.path()).unwrap();
+        assert!(interceptor.call(request(&format!("Bearer {}", token))).is_err());
+    }
+
+    #[test]
+    fn test_issue_certificates() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = CertificateAuthority::load_or_generate(dir.path()).unwrap();
+        let reloaded = CertificateAuthority::load_or_generate(dir.path()).unwrap();
+        assert_eq!(ca.cert_pem, reloaded.cert_pem);
+
+        let client = ca.issue_client_cert("alice", 30).unwrap();
+        assert!(client.cert_pem.starts_with("-----BEGIN CERTIFICATE-----"));
+        assert_eq!(client.serial.len(), 32);
+        assert_eq!(client.not_after, Utc::now().date_naive() + Duration::days(30));
+        assert!(ca.issue_client_cert("alice", 0).is_err());
+
+        let server = ca
+            .issue_server_cert(&["localhost".to_string(), "127.0.0.1".to_string()])
+            .unwrap();
+        assert!(server.key_pem.contains("PRIVATE KEY"));
+    }
+
+    #[test]
+    fn test_credentials_need_https_for_tls() {
+        let creds = ClientCredentials {
+            ca_cert: Some(PathBuf::from("/nonexistent/ca.crt")),
+            ..Default::default()
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let err = runtime
+            .block_on(creds.connect("http://127.0.0.1:50051"))
+            .unwrap_err();
+        assert!(err.to_string().contains("https://"));
+        let err = runtime
+            .block_on(creds.connect("unix:///tmp/daemon.sock"))
+            .unwrap_err();
+        assert!(err.to_string().contains("socket"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Host block device inspection for raw device passthrough
+//!
+//! Provides safety checks before a host disk or partition is handed to a
+//! guest: device type validation, mount detection, exclusive-lock probing
+//! and SMART health reporting.
+
+use crate::{Error, Result};
+use nix::fcntl::{Flock, FlockArg};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::process::Command;
+use tracing::debug;
+
+/// SMART health summary for a host device
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmartStatus {
+    /// Whether a SMART tool produced a usable answer
+    pub available: bool,
+    /// Overall health verdict (None if unknown)
+    pub passed: Option<bool>,
+    /// Human-readable summary
+    pub summary: String,
+}
+
+/// Result of inspecting a host block device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockDeviceInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Mount points of the device or any of its partitions
+    pub mounted_at: Vec<String>,
+    /// Whether an exclusive advisory lock could be taken
+    pub lockable: bool,
+    pub smart: SmartStatus,
+    /// Warnings to surface to the operator before attaching
+    pub warnings: Vec<String>,
+}
+
+impl BlockDeviceInfo {
+    /// Whether the device is safe to hand to a guest without `force`
+    pub fn is_safe(&self) -> bool {
+        self.mounted_at.is_empty() && self.lockable
+    }
+}
+
+/// Inspect a host block device before passthrough
+pub fn inspect_block_device(path: impl AsRef<Path>) -> Result<BlockDeviceInfo> {
+    let path = path.as_ref();
+    let device = path.to_string_lossy().to_string();
+
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| Error::VolumeError(format!("Cannot access device {}: {}", device, e)))?;
+
+    // macOS exposes raw disks as character devices (/dev/rdiskN)
+    let file_type = metadata.file_type();
+    if !file_type.is_block_device() && !file_type.is_char_device() {
+        return Err(Error::VolumeError(format!(
+            "{} is not a block device",
+            device
+        )));
+    }
+
+    let size_bytes = device_size(path).unwrap_or(0);
+    let mounted_at = mount_points(&device);
+    let lockable = probe_exclusive_lock(path);
+    let smart = query_smart_status(&device);
+
+    let mut warnings = vec![format!(
+        "Raw passthrough gives the guest direct write access to {}; existing data may be destroyed",
+        device
+    )];
+    if !mounted_at.is_empty() {
+        warnings.push(format!(
+            "{} is mounted on the host at {}",
+            device,
+            mounted_at.join(", ")
+        ));
+    }
+    if !lockable {
+        warnings.push(format!(
+            "{} is held by another process (exclusive lock failed)",
+            device
+        ));
+    }
+    match smart.passed {
+        Some(false) => warnings.push(format!(
+            "SMART reports {} as failing: {}",
+            device, smart.summary
+        )),
+        None => warnings.push(format!("SMART status unavailable for {}", device)),
+        Some(true) => {}
+    }
+
+    debug!("Inspected block device {}: {} bytes", device, size_bytes);
+
+    Ok(BlockDeviceInfo {
+        path: device,
+        size_bytes,
+        mounted_at,
+        lockable,
+        smart,
+        warnings,
+    })
+}
+
+/// Determine device size by seeking to the end
+fn device_size(path: &Path) -> Result<u64> {
+    let mut file = File::open(path)?;
+    Ok(file.seek(SeekFrom::End(0))?)
+}
+
+/// Try to take (and immediately release) an exclusive advisory lock
+pub fn probe_exclusive_lock(path: &Path) -> bool {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => return false,
+    };
+
+    match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+        Ok(lock) => {
+            let _ = lock.unlock();
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// List mount points for a device or its partitions
+fn mount_points(device: &str) -> Vec<String> {
+    match Command::new("mount").output() {
+        Ok(output) => parse_mount_table(&String::from_utf8_lossy(&output.stdout), device),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Parse `mount` output (Linux and macOS formats) for a device and its partitions
+pub fn parse_mount_table(output: &str, device: &str) -> Vec<String> {
+    let mut mounts = Vec::new();
+
+    for line in output.lines() {
+        let Some((dev, rest)) = line.split_once(" on ") else {
+            continue;
+        };
+        if !is_same_or_partition(dev.trim(), device) {
+            continue;
+        }
+
+        // Linux: "<mp> type ext4 (...)", macOS: "<mp> (apfs, local)"
+        let mount_point = rest
+            .split(" type ")
+            .next()
+            .and_then(|s| s.split(" (").next())
+            .unwrap_or(rest)
+            .trim();
+        mounts.push(mount_point.to_string());
+    }
+
+    mounts
+}
+
+/// Whether `candidate` is `device` itself or one of its partitions
+fn is_same_or_partition(candidate: &str, device: &str) -> bool {
+    // /dev/rdiskN and /dev/diskN refer to the same disk on macOS
+    let normalize = |s: &str| s.replacen("/dev/rdisk", "/dev/disk", 1);
+    let candidate = normalize(candidate);
+    let device = normalize(device);
+
+    if candidate == device {
+        return true;
+    }
+
+    let Some(suffix) = candidate.strip_prefix(device.as_str()) else {
+        return false;
+    };
+    let Some(first) = suffix.chars().next() else {
+        return false;
+    };
+
+    // diskN -> diskNsM, nvme0n1 -> nvme0n1pM, sdb -> sdb1
+    if device.ends_with(|c: char| c.is_ascii_digit()) {
+        matches!(first, 's' | 'p') && suffix[1..].starts_with(|c: char| c.is_ascii_digit())
+    } else {
+        first.is_ascii_digit()
+    }
+}
+
+/// Query SMART health via smartctl, falling back to diskutil on macOS
+pub fn query_smart_status(device: &str) -> SmartStatus {
+    if let Ok(output) = Command::new("smartctl").args(["-H", "-j", device]).output() {
+        if let Some(status) = parse_smartctl_json(&String::from_utf8_lossy(&output.stdout)) {
+            return status;
+        }
+    }
+
+    if let Ok(output) = Command::new("diskutil").args(["info", device]).output() {
+        if let Some(status) = parse_diskutil_smart(&String::from_utf8_lossy(&output.stdout)) {
+            return status;
+        }
+    }
+
+    SmartStatus {
+        available: false,
+        passed: None,
+        summary: "unavailable".to_string(),
+    }
+}
+
+/// Parse `smartctl -H -j` output
+pub fn parse_smartctl_json(output: &str) -> Option<SmartStatus> {
+    let value: serde_json::Value = serde_json::from_str(output).ok()?;
+    let passed = value.get("smart_status")?.get("passed")?.as_bool()?;
+    let model = value
+        .get("model_name")
+        .and_then(|m| m.as_str())
+        .unwrap_or("unknown model");
+
+    Some(SmartStatus {
+        available: true,
+        passed: Some(passed),
+        summary: format!("{} ({})", if passed { "PASSED" } else { "FAILED" }, model),
+    })
+}
+
+/// Parse the "SMART Status:" line of `diskutil info`
+pub fn parse_diskutil_smart(output: &str) -> Option<SmartStatus> {
+    let line = output
+        .lines()
+        .find(|l| l.trim_start().starts_with("SMART Status:"))?;
+    let value = line.split_once(':')?.1.trim().to_string();
+
+    let passed = match value.as_str() {
+        "Verified" => Some(true),
+        "Failing" => Some(false),
+        _ => None,
+    };
+
+    Some(SmartStatus {
+        available: passed.is_some(),
+        passed,
+        summary: value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mount_table_linux() {
+        let output = "\
+/dev/sda1 on / type ext4 (rw,relatime)
+/dev/sdb1 on /mnt/usb type vfat (rw)
+/dev/sdb2 on /mnt/data type ext4 (rw)
+tmpfs on /run type tmpfs (rw)";
+
+        assert_eq!(
+            parse_mount_table(output, "/dev/sdb"),
+            vec!["/mnt/usb", "/mnt/data"]
+        );
+        assert!(parse_mount_table(output, "/dev/sdc").is_empty());
+    }
+
+    #[test]
+    fn test_parse_mount_table_macos() {
+        let output = "\
+/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)
+/dev/disk4s1 on /Volumes/External SSD (apfs, local, nodev, nosuid)
+/dev/disk10s1 on /Volumes/Other (msdos, local)";
+
+        assert_eq!(
+            parse_mount_table(output, "/dev/disk4"),
+            vec!["/Volumes/External SSD"]
+        );
+        assert_eq!(
+            parse_mount_table(output, "/dev/rdisk4"),
+            vec!["/Volumes/External SSD"]
+        );
+        assert!(parse_mount_table(output, "/dev/disk1").is_empty());
+    }
+
+    #[test]
+    fn test_partition_matching() {
+        assert!(is_same_or_partition("/dev/nvme0n1p2", "/dev/nvme0n1"));
+        assert!(!is_same_or_partition("/dev/nvme0n10", "/dev/nvme0n1"));
+        assert!(!is_same_or_partition("/dev/sdbb", "/dev/sdb"));
+    }
+
+    #[test]
+    fn test_parse_smartctl_json() {
+        let ok = r#"{"model_name":"Samsung SSD 980","smart_status":{"passed":true}}"#;
+        let status = parse_smartctl_json(ok).unwrap();
+        assert_eq!(status.passed, Some(true));
+        assert!(status.summary.contains("Samsung SSD 980"));
+
+        let failing = r#"{"smart_status":{"passed":false}}"#;
+        assert_eq!(parse_smartctl_json(failing).unwrap().passed, Some(false));
+
+        assert!(parse_smartctl_json("not json").is_none());
+    }
+
+    #[test]
+    fn test_parse_diskutil_smart() {
+        let output =
+            "   Device Node:              /dev/disk4\n   SMART Status:             Verified\n";
+        assert_eq!(parse_diskutil_smart(output).unwrap().passed, Some(true));
+
+        let unsupported = "   SMART Status:             Not Supported\n";
+        let status = parse_diskutil_smart(unsupported).unwrap();
+        assert!(!status.available);
+        assert_eq!(status.passed, None);
+    }
+
+    #[test]
+    fn test_regular_file_rejected() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(inspect_block_device(file.path()).is_err());
+    }
+}
@@ -4,14 +4,148 @@
 //! - Deduplication
 //! - Integrity verification
 //! - Atomic writes
+//! - Push/pull of objects to a [`RemoteStore`] shared between machines
 
 use crate::{Error, Result};
+use async_trait::async_trait;
 use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use tokio::fs;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tracing::{debug, info, warn};
 
+/// Object storage that store objects can be pushed to and pulled from
+///
+/// Keys are relative, `/`-separated paths; backends map them below their
+/// own root or prefix.
+#[async_trait]
+pub trait RemoteStore: Send + Sync {
+    /// Where the store lives, for logs and errors
+    fn location(&self) -> String;
+
+    /// Key of a store object, sharded like [`ContentAddressedStore::object_path`]
+    fn object_key(&self, digest: &str) -> String {
+        let (prefix, _) = digest.split_at(2.min(digest.len()));
+        format!("objects/sha256/{}/{}", prefix, digest)
+    }
+
+    /// Size of the object at `key`, `None` if it doesn't exist
+    async fn size(&self, key: &str) -> Result<Option<u64>>;
+
+    /// Upload a file to `key`, resuming an interrupted upload of it if possible
+    async fn upload(&self, key: &str, path: &Path) -> Result<()>;
+
+    /// Append the object's bytes from `offset` on to `dest`
+    async fn download(&self, key: &str, dest: &Path, offset: u64) -> Result<()>;
+
+    /// Store a small object held in memory
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()>;
+
+    /// Read a small object into memory
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+}
+
+/// Remote store in a local or mounted directory
+#[derive(Debug, Clone)]
+pub struct DirRemote {
+    root: PathBuf,
+}
+
+impl DirRemote {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl RemoteStore for DirRemote {
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>> {
+        match fs::metadata(self.path(key)).await {
+            Ok(meta) => Ok(Some(meta.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn upload(&self, key: &str, path: &Path) -> Result<()> {
+        let dest = self.path(key);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Copy next to the destination, continuing an earlier partial copy
+        let partial = dest.with_extension("partial");
+        let mut out = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&partial)
+            .await?;
+        let offset = out.metadata().await?.len();
+        let mut src = fs::File::open(path).await?;
+        if offset > src.metadata().await?.len() {
+            out.set_len(0).await?;
+        } else {
+            src.seek(std::io::SeekFrom::Start(offset)).await?;
+        }
+        tokio::io::copy(&mut src, &mut out).await?;
+        out.sync_all().await?;
+
+        fs::rename(&partial, &dest).await?;
+        Ok(())
+    }
+
+    async fn download(&self, key: &str, dest: &Path, offset: u64) -> Result<()> {
+        let mut src = match fs::File::open(self.path(key)).await {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::NotFound {
+                    kind: "remote object".to_string(),
+                    id: key.to_string(),
+                })
+            }
+            Err(e) => return Err(e.into()),
+        };
+        src.seek(std::io::SeekFrom::Start(offset)).await?;
+
+        let mut out = fs::OpenOptions::new().create(true).append(true).open(dest).await?;
+        tokio::io::copy(&mut src, &mut out).await?;
+        out.sync_all().await?;
+        Ok(())
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let dest = self.path(key);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let tmp = dest.with_extension("tmp");
+        fs::write(&tmp, data).await?;
+        fs::rename(&tmp, &dest).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        match fs::read(self.path(key)).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::NotFound {
+                kind: "remote object".to_string(),
+                id: key.to_string(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
 /// Content-addressed store for artifacts
 #[derive(Debug, Clone)]
 pub struct ContentAddressedStore {
@@ -138,6 +272,33 @@ impl ContentAddressedStore {
         Ok(digest)
     }
 
+    /// Get the staging directory for in-progress writes
+    pub fn tmp_dir(&self) -> PathBuf {
+        self.root.join("tmp")
+    }
+
+    /// Move an already-verified file into the store under `digest`
+    ///
+    /// The caller is responsible for having hashed `src`; the file is renamed,
+    /// so it must live on the same filesystem (e.g. in [`Self::tmp_dir`]).
+    pub async fn adopt_file(&self, src: impl AsRef<Path>, digest: &str) -> Result<PathBuf> {
+        let path = self.object_path(digest);
+
+        if path.exists() {
+            debug!("Object {} already exists", digest);
+            fs::remove_file(&src).await?;
+            return Ok(path);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::rename(&src, &path).await?;
+
+        debug!("Adopted object {}", digest);
+        Ok(path)
+    }
+
     /// Get data by digest
     pub async fn get(&self, digest: &str) -> Result<Vec<u8>> {
         let path = self.object_path(digest);
@@ -270,6 +431,109 @@ impl ContentAddressedStore {
         self.put_run_artifact(run_id, "snapshot.mem.enc", &encrypted).await
     }
 
+    /// Push an object to a remote store
+    ///
+    /// The local object is verified first. Returns `false` if the remote
+    /// already holds an object of the same size under its digest.
+    pub async fn push(&self, digest: &str, remote: &dyn RemoteStore) -> Result<bool> {
+        check_digest(digest)?;
+        let path = self.get_path(digest).await?;
+        let actual = Self::hash_file(&path).await?;
+        if actual != digest {
+            return Err(Error::IntegrityError(format!(
+                "Digest mismatch: expected {}, got {}",
+                digest, actual
+            )));
+        }
+
+        let key = remote.object_key(digest);
+        let size = fs::metadata(&path).await?.len();
+        if remote.size(&key).await? == Some(size) {
+            debug!("Object {} already in {}", digest, remote.location());
+            return Ok(false);
+        }
+
+        remote.upload(&key, &path).await?;
+        if remote.size(&key).await? != Some(size) {
+            return Err(Error::IntegrityError(format!(
+                "Object {} in {} does not have the pushed size of {} bytes",
+                digest,
+                remote.location(),
+                size
+            )));
+        }
+
+        info!("Pushed object {} ({} bytes) to {}", digest, size, remote.location());
+        Ok(true)
+    }
+
+    /// Pull an object from a remote store, returning its local path
+    ///
+    /// Partial downloads are kept in [`Self::tmp_dir`] and continued on the
+    /// next attempt; the object is only adopted once its digest matches.
+    pub async fn pull(&self, digest: &str, remote: &dyn RemoteStore) -> Result<PathBuf> {
+        check_digest(digest)?;
+        if self.has(digest).await {
+            debug!("Object {} already in store, skipping pull", digest);
+            return Ok(self.object_path(digest));
+        }
+
+        let key = remote.object_key(digest);
+        let size = remote.size(&key).await?.ok_or_else(|| Error::NotFound {
+            kind: "remote object".to_string(),
+            id: format!("{} in {}", digest, remote.location()),
+        })?;
+
+        let partial = self.tmp_dir().join(format!("{}.partial", digest));
+        let mut offset = match fs::metadata(&partial).await {
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        };
+        if offset > size {
+            fs::remove_file(&partial).await?;
+            offset = 0;
+        }
+        if offset < size {
+            if offset > 0 {
+                info!("Resuming pull of {} at {} bytes", digest, offset);
+            }
+            remote.download(&key, &partial, offset).await?;
+        }
+
+        let actual = Self::hash_file(&partial).await?;
+        if actual != digest {
+            let _ = fs::remove_file(&partial).await;
+            return Err(Error::IntegrityError(format!(
+                "Digest mismatch: expected {}, got {}",
+                digest, actual
+            )));
+        }
+
+        let path = self.adopt_file(&partial, digest).await?;
+        info!("Pulled object {} ({} bytes) from {}", digest, size, remote.location());
+        Ok(path)
+    }
+
+    /// Digests of every object in the store
+    pub fn list(&self) -> Result<Vec<String>> {
+        let objects_dir = self.objects_dir().join("sha256");
+        if !objects_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut digests = Vec::new();
+        for entry in walkdir::WalkDir::new(&objects_dir).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            if let Some(digest) = entry.file_name().to_str().filter(|d| check_digest(d).is_ok()) {
+                digests.push(digest.to_string());
+            }
+        }
+        digests.sort();
+        Ok(digests)
+    }
+
     /// Garbage collect unreferenced objects
     pub async fn gc(&self, referenced: &[String]) -> Result<GcStats> {
         let mut stats = GcStats::default();
@@ -312,6 +576,14 @@ impl ContentAddressedStore {
     }
 }
 
+/// Reject anything but a lowercase hex SHA-256 digest
+pub fn check_digest(digest: &str) -> Result<()> {
+    if digest.len() != 64 || !digest.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+        return Err(Error::IntegrityError(format!("Invalid sha256 digest: '{}'", digest)));
+    }
+    Ok(())
+}
+
 /// Garbage collection statistics
 #[derive(Debug, Default)]
 pub struct GcStats {
@@ -340,6 +612,19 @@ mod tests {
         assert_eq!(data.as_slice(), retrieved.as_slice());
     }
 
+    #[tokio::test]
+    async fn test_list() {
+        let tmp = TempDir::new().unwrap();
+        let cas = ContentAddressedStore::new(tmp.path()).await.unwrap();
+        assert!(cas.list().unwrap().is_empty());
+
+        let a = cas.put(b"a").await.unwrap();
+        let b = cas.put(b"b").await.unwrap();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(cas.list().unwrap(), expected);
+    }
+
     #[tokio::test]
     async fn test_deduplication() {
         let tmp = TempDir::new().unwrap();
@@ -367,4 +652,61 @@ mod tests {
         // Should fail integrity check
         assert!(cas.get(&digest).await.is_err());
     }
+
+    #[tokio::test]
+    async fn test_adopt_file() {
+        let tmp = TempDir::new().unwrap();
+        let cas = ContentAddressedStore::new(tmp.path()).await.unwrap();
+
+        let staged = cas.tmp_dir().join("download.partial");
+        fs::write(&staged, b"downloaded image").await.unwrap();
+        let digest = ContentAddressedStore::hash(b"downloaded image");
+
+        let path = cas.adopt_file(&staged, &digest).await.unwrap();
+        assert_eq!(path, cas.object_path(&digest));
+        assert!(!staged.exists());
+        assert_eq!(cas.get(&digest).await.unwrap(), b"downloaded image");
+    }
+
+    #[tokio::test]
+    async fn test_push_pull() {
+        let tmp = TempDir::new().unwrap();
+        let local = ContentAddressedStore::new(tmp.path().join("a")).await.unwrap();
+        let other = ContentAddressedStore::new(tmp.path().join("b")).await.unwrap();
+        let remote = DirRemote::new(tmp.path().join("remote"));
+
+        let digest = local.put(b"base image").await.unwrap();
+        assert!(local.push(&digest, &remote).await.unwrap());
+        assert!(!local.push(&digest, &remote).await.unwrap());
+
+        // An interrupted pull is continued from the partial file
+        fs::write(other.tmp_dir().join(format!("{}.partial", digest)), b"base")
+            .await
+            .unwrap();
+        other.pull(&digest, &remote).await.unwrap();
+        assert_eq!(other.get(&digest).await.unwrap(), b"base image");
+
+        assert!(other.pull(&"0".repeat(64), &remote).await.is_err());
+        assert!(other.pull("../escape", &remote).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pull_rejects_corrupt_object() {
+        let tmp = TempDir::new().unwrap();
+        let local = ContentAddressedStore::new(tmp.path().join("a")).await.unwrap();
+        let other = ContentAddressedStore::new(tmp.path().join("b")).await.unwrap();
+        let remote = DirRemote::new(tmp.path().join("remote"));
+
+        let digest = local.put(b"appliance archive").await.unwrap();
+        local.push(&digest, &remote).await.unwrap();
+        let key = remote.object_key(&digest);
+        remote.put(&key, b"tampered archive!".to_vec()).await.unwrap();
+
+        assert!(matches!(
+            other.pull(&digest, &remote).await,
+            Err(Error::IntegrityError(_))
+        ));
+        assert!(!other.has(&digest).await);
+        assert!(!other.tmp_dir().join(format!("{}.partial", digest)).exists());
+    }
 }
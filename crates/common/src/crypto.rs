@@ -53,6 +53,11 @@ impl KeyPair {
         hex::encode(self.public_key_bytes())
     }
 
+    /// Short identifier for the public key, see [`key_id`]
+    pub fn key_id(&self) -> String {
+        key_id(&self.public_key_bytes())
+    }
+
     /// Get the verifying key
     pub fn verifying_key(&self) -> VerifyingKey {
         self.signing_key.verifying_key()
@@ -105,6 +110,13 @@ impl Verifier for KeyPair {
     }
 }
 
+/// Short identifier for a public key: the first 16 hex chars of its SHA256
+pub fn key_id(public_key: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    hex::encode(&Sha256::digest(public_key)[..8])
+}
+
 /// Create a verifying key from raw bytes
 pub fn verifying_key_from_bytes(bytes: &[u8]) -> Result<VerifyingKey> {
     if bytes.len() != 32 {
@@ -213,6 +225,15 @@ mod tests {
         assert_eq!(kp.public_key_bytes().len(), 32);
     }
 
+    #[test]
+    fn test_key_id_is_stable() {
+        let kp = KeyPair::generate();
+        assert_eq!(kp.key_id().len(), 16);
+        assert_eq!(kp.key_id(), kp.clone().key_id());
+        assert_ne!(kp.key_id(), KeyPair::generate().key_id());
+        assert_eq!(kp.key_id(), key_id(&kp.public_key_bytes()));
+    }
+
     #[test]
     fn test_sign_verify() {
         let kp = KeyPair::generate();
@@ -1,60 +1,24 @@
 //! SQLite database for InfraSim state persistence
 
+use crate::cas::check_digest;
 use crate::{Error, Result};
 use parking_lot::Mutex;
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::backup::Backup;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::Arc;
-use tracing::{debug, info};
+use std::time::Duration;
+use tracing::{debug, info, warn};
 
-/// Database wrapper for state persistence
-#[derive(Clone)]
-pub struct Database {
-    conn: Arc<Mutex<Connection>>,
-}
-
-impl Database {
-    /// Expose the underlying connection for internal subsystems that need to manage
-    /// their own tables within the shared state DB.
-    pub fn connection(&self) -> Arc<Mutex<Connection>> {
-        self.conn.clone()
-    }
-}
-
-impl Database {
-    /// Open or create database at path
-    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
-        let conn = Connection::open(path.as_ref())?;
-        
-        // Enable WAL mode for better concurrency
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
-        
-        let db = Self {
-            conn: Arc::new(Mutex::new(conn)),
-        };
-        
-        db.init_schema()?;
-        
-        info!("Opened database at {:?}", path.as_ref());
-        Ok(db)
-    }
-
-    /// Open in-memory database (for testing)
-    pub fn open_memory() -> Result<Self> {
-        let conn = Connection::open_in_memory()?;
-        let db = Self {
-            conn: Arc::new(Mutex::new(conn)),
-        };
-        db.init_schema()?;
-        Ok(db)
-    }
-
-    /// Initialize database schema
-    fn init_schema(&self) -> Result<()> {
-        let conn = self.conn.lock();
-        
-        conn.execute_batch(
-            r#"
+/// Migrations of the daemon's state tables; only ever append
+pub const STATE_MIGRATIONS: Migrations = Migrations {
+    component: "state",
+    migrations: &[Migration {
+        version: 1,
+        name: "initial",
+        up: MigrationUp::Sql(r#"
             -- VMs table
             CREATE TABLE IF NOT EXISTS vms (
                 id TEXT PRIMARY KEY,
@@ -154,6 +118,20 @@ impl Database {
             );
             CREATE INDEX IF NOT EXISTS idx_appliance_catalog_name ON appliance_catalog(name);
 
+            -- Filesystems (web-managed storage backed by daemon volumes)
+            CREATE TABLE IF NOT EXISTS filesystems (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_filesystems_name ON filesystems(name);
+
             -- Appliance events (audit trail / future indexing)
             CREATE TABLE IF NOT EXISTS appliance_events (
                 id TEXT PRIMARY KEY,
@@ -212,15 +190,286 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_lora_devices_name ON lora_devices(name);
             CREATE INDEX IF NOT EXISTS idx_lora_devices_vm ON lora_devices(json_extract(spec, '$.vm_id'));
 
+            -- Port forwards table
+            CREATE TABLE IF NOT EXISTS port_forwards (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_port_forwards_name ON port_forwards(name);
+            CREATE INDEX IF NOT EXISTS idx_port_forwards_vm ON port_forwards(json_extract(spec, '$.vm_id'));
+
+            -- Passthrough devices table
+            CREATE TABLE IF NOT EXISTS devices (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_devices_name ON devices(name);
+            CREATE INDEX IF NOT EXISTS idx_devices_vm ON devices(json_extract(spec, '$.vm_id'));
+
+            -- Snapshot policies table
+            CREATE TABLE IF NOT EXISTS snapshot_policies (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_snapshot_policies_name ON snapshot_policies(name);
+            CREATE INDEX IF NOT EXISTS idx_snapshot_policies_vm ON snapshot_policies(json_extract(spec, '$.vm_id'));
+
+            -- Backups table
+            CREATE TABLE IF NOT EXISTS backups (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_backups_name ON backups(name);
+            CREATE INDEX IF NOT EXISTS idx_backups_vm ON backups(json_extract(spec, '$.vm_id'));
+
+            -- Backup policies table
+            CREATE TABLE IF NOT EXISTS backup_policies (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_backup_policies_name ON backup_policies(name);
+            CREATE INDEX IF NOT EXISTS idx_backup_policies_vm ON backup_policies(json_extract(spec, '$.vm_id'));
+
+            -- Background jobs table
+            CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_jobs_state ON jobs(json_extract(status, '$.state'));
+
+            -- Routers table
+            CREATE TABLE IF NOT EXISTS routers (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_routers_name ON routers(name);
+
+            -- Append-only audit log of mutating operations, see crate::audit
+            CREATE TABLE IF NOT EXISTS audit_log (
+                seq INTEGER PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                source TEXT NOT NULL,
+                identity TEXT NOT NULL,
+                source_ip TEXT,
+                operation TEXT NOT NULL,
+                resource_kind TEXT,
+                resource_id TEXT,
+                status TEXT NOT NULL,
+                before_digest TEXT,
+                after_digest TEXT,
+                prev_hash TEXT,
+                hash TEXT,
+                signature TEXT,
+                key_id TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_audit_log_resource ON audit_log(resource_id);
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'audit log is append-only');
+            END;
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'audit log is append-only');
+            END;
+
+            -- Secrets sealed with the daemon's master key, see crate::secrets
+            CREATE TABLE IF NOT EXISTS secrets (
+                name TEXT PRIMARY KEY,
+                nonce BLOB NOT NULL,
+                ciphertext BLOB NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
             -- Key-value store for misc state
             CREATE TABLE IF NOT EXISTS kv_store (
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL,
                 updated_at INTEGER NOT NULL
             );
-            "#,
-        )?;
+            "#),
+        down: None,
+    }, Migration {
+        version: 2,
+        name: "provenance_ledger",
+        up: MigrationUp::Sql(r#"
+            -- Append-only, hash-chained history of resource specs, see crate::provenance
+            CREATE TABLE IF NOT EXISTS provenance_ledger (
+                seq INTEGER PRIMARY KEY,
+                resource_kind TEXT NOT NULL,
+                resource_id TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                change TEXT NOT NULL,
+                spec TEXT,
+                spec_digest TEXT,
+                parent_digest TEXT,
+                hash TEXT NOT NULL,
+                signature TEXT,
+                key_id TEXT,
+                UNIQUE (resource_id, version)
+            );
+            CREATE TRIGGER IF NOT EXISTS provenance_ledger_no_update BEFORE UPDATE ON provenance_ledger
+            BEGIN
+                SELECT RAISE(ABORT, 'provenance ledger is append-only');
+            END;
+            CREATE TRIGGER IF NOT EXISTS provenance_ledger_no_delete BEFORE DELETE ON provenance_ledger
+            BEGIN
+                SELECT RAISE(ABORT, 'provenance ledger is append-only');
+            END;
+            "#),
+        down: Some("DROP TABLE IF EXISTS provenance_ledger;"),
+    }, Migration {
+        version: 3,
+        name: "chaos_profiles",
+        up: MigrationUp::Sql(r#"
+            -- Faults injected into network traffic until they expire, see crate::types::ChaosProfile
+            CREATE TABLE IF NOT EXISTS chaos_profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_chaos_profiles_name ON chaos_profiles(name);
+            "#),
+        down: Some("DROP TABLE IF EXISTS chaos_profiles;"),
+    }, Migration {
+        version: 4,
+        name: "scenario_runs",
+        up: MigrationUp::Sql(r#"
+            -- Runs of scripted experiments and their reports, see crate::scenario
+            CREATE TABLE IF NOT EXISTS scenario_runs (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                spec TEXT NOT NULL,
+                status TEXT NOT NULL,
+                labels TEXT NOT NULL DEFAULT '{}',
+                annotations TEXT NOT NULL DEFAULT '{}',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                generation INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE INDEX IF NOT EXISTS idx_scenario_runs_scenario
+                ON scenario_runs(json_extract(spec, '$.scenario.name'));
+            "#),
+        down: Some("DROP TABLE IF EXISTS scenario_runs;"),
+    }],
+};
+
+/// Database wrapper for state persistence
+#[derive(Clone)]
+pub struct Database {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Database {
+    /// Expose the underlying connection for internal subsystems that need to manage
+    /// their own tables within the shared state DB.
+    pub fn connection(&self) -> Arc<Mutex<Connection>> {
+        self.conn.clone()
+    }
+}
+
+impl Database {
+    /// Open or create database at path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let db = Self::open_unmigrated(path.as_ref())?;
+        db.init_schema()?;
         
+        info!("Opened database at {:?}", path.as_ref());
+        Ok(db)
+    }
+
+    /// Open or create database at path without applying migrations
+    ///
+    /// For tools that inspect or roll back the schema.
+    pub fn open_unmigrated(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path.as_ref())?;
+        
+        // Enable WAL mode for better concurrency
+        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL;")?;
+        
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Open an existing database read-only, such as a backup copy
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open_with_flags(path.as_ref(), OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Open in-memory database (for testing)
+    pub fn open_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        let db = Self {
+            conn: Arc::new(Mutex::new(conn)),
+        };
+        db.init_schema()?;
+        Ok(db)
+    }
+
+    /// Bring the state tables up to date
+    fn init_schema(&self) -> Result<()> {
+        self.migrate(&STATE_MIGRATIONS)?;
         debug!("Database schema initialized");
         Ok(())
     }
@@ -298,6 +547,25 @@ impl Database {
         Ok(())
     }
 
+    /// Replace a resource's labels
+    pub fn update_labels(
+        &self,
+        table: &str,
+        id: &str,
+        labels: &std::collections::HashMap<String, String>,
+    ) -> Result<()> {
+        let conn = self.conn.lock();
+        let now = chrono::Utc::now().timestamp();
+
+        conn.execute(
+            &format!("UPDATE {} SET labels = ?1, updated_at = ?2 WHERE id = ?3", table),
+            params![serde_json::to_string(labels)?, now, id],
+        )?;
+
+        debug!("Updated labels of {} with id {}", table, id);
+        Ok(())
+    }
+
     /// Get a resource by ID
     pub fn get<S: serde::de::DeserializeOwned, T: serde::de::DeserializeOwned>(
         &self,
@@ -484,6 +752,347 @@ impl Database {
         conn.execute("DELETE FROM kv_store WHERE key = ?1", params![key])?;
         Ok(())
     }
+
+    // ========================================================================
+    // Replication
+    // ========================================================================
+
+    /// Count of rows changed through this handle since it was opened
+    ///
+    /// A standby compares it between polls to tell whether anything changed.
+    pub fn change_counter(&self) -> Result<u64> {
+        let conn = self.conn.lock();
+        let changes: i64 = conn.query_row("SELECT total_changes()", [], |row| row.get(0))?;
+        Ok(changes as u64)
+    }
+
+    /// Write a consistent copy of the database to `path`, which must not exist
+    pub fn snapshot_to(&self, path: &Path) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// Replace the rows of every table with those of the database at `path`
+    ///
+    /// Tables only in the copy are created; tables only here are kept. Only
+    /// columns both sides have are copied, so a copy from a daemon a schema
+    /// migration behind or ahead still applies. Runs in one transaction.
+    pub fn restore_from(&self, path: &Path) -> Result<()> {
+        let mut conn = self.conn.lock();
+        conn.execute("ATTACH DATABASE ?1 AS replica", params![path.to_string_lossy()])?;
+        let result = restore_tables(&mut conn);
+        conn.execute("DETACH DATABASE replica", [])?;
+        result?;
+
+        info!("Restored database from {:?}", path);
+        Ok(())
+    }
+}
+
+impl Database {
+    // ========================================================================
+    // Backup and integrity
+    // ========================================================================
+
+    /// Copy the database to `path` with SQLite's online backup API
+    ///
+    /// Safe while other connections write; the copy is consistent and uses a
+    /// rollback journal, so it is a single self-contained file.
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        let conn = self.conn.lock();
+        let mut dst = Connection::open(path)?;
+        Backup::new(&conn, &mut dst)?.run_to_completion(256, Duration::from_millis(10), None)?;
+        dst.execute_batch("PRAGMA journal_mode=DELETE;")?;
+        Ok(())
+    }
+
+    /// Problems found by SQLite's integrity check, empty if there are none
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let problems = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(problems.into_iter().filter(|p| p != "ok").collect())
+    }
+
+    /// Rows whose foreign keys point at missing rows
+    ///
+    /// SQLite doesn't enforce foreign keys unless asked to, so this is the
+    /// only place violations show.
+    pub fn foreign_key_check(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(format!(
+                "{} row {} refers to a missing {} row",
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?.map(|r| r.to_string()).unwrap_or_else(|| "?".to_string()),
+                row.get::<_, String>(2)?
+            ))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// SHA-256 digests named anywhere in the database's text columns
+    ///
+    /// Each maps to whether one of the mentions is a path into a CAS objects
+    /// directory, in which case the object has to exist. Other mentions may
+    /// be hashes of things never stored, like audit log entries.
+    pub fn digest_references(&self) -> Result<BTreeMap<String, bool>> {
+        let conn = self.conn.lock();
+        let tables = {
+            let mut stmt = conn.prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            )?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        let mut found = BTreeMap::new();
+        for table in tables {
+            let mut stmt = conn.prepare(&format!("SELECT * FROM \"{}\"", table))?;
+            let columns = stmt.column_count();
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                for i in 0..columns {
+                    if let ValueRef::Text(text) = row.get_ref(i)? {
+                        scan_digests(&String::from_utf8_lossy(text), &mut found);
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Collect the 64-character lowercase hex words of `text`
+fn scan_digests(text: &str, found: &mut BTreeMap<String, bool>) {
+    let mut start = 0;
+    for (end, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        if c.is_ascii_alphanumeric() {
+            continue;
+        }
+        let word = &text[start..end];
+        if check_digest(word).is_ok() {
+            let as_path = text[..start].ends_with(&format!("sha256/{}/", &word[..2]));
+            *found.entry(word.to_string()).or_default() |= as_path;
+        }
+        start = end + c.len_utf8();
+    }
+}
+
+impl Database {
+    // ========================================================================
+    // Migrations
+    // ========================================================================
+
+    /// Apply the pending migrations of `set`
+    ///
+    /// A schema newer than `set` knows, left by a newer release, is kept
+    /// as it is rather than rolled back.
+    pub fn migrate(&self, set: &Migrations) -> Result<Vec<MigrationChange>> {
+        let current = self.schema_version(set.component)?;
+        if current > set.latest() {
+            warn!(
+                "{} schema is at v{}, newer than this release's v{}",
+                set.component,
+                current,
+                set.latest()
+            );
+            return Ok(Vec::new());
+        }
+        self.migrate_to(set, set.latest(), false)
+    }
+
+    /// Apply or roll back migrations of `set` until its schema is at `target`
+    ///
+    /// All changes run in one transaction; with `dry_run` it is rolled back
+    /// once they have run, so failures still show.
+    pub fn migrate_to(&self, set: &Migrations, target: u32, dry_run: bool) -> Result<Vec<MigrationChange>> {
+        let mut conn = self.conn.lock();
+        let current = current_version(&conn, set.component)?;
+        if target > set.latest() {
+            return Err(Error::MigrationError(format!(
+                "{} has no v{}; the latest is v{}",
+                set.component,
+                target,
+                set.latest()
+            )));
+        }
+        if target < current {
+            drop(conn);
+            return self.rollback(set.component, target, dry_run);
+        }
+
+        let tx = conn.transaction()?;
+        let mut changes = Vec::new();
+        for migration in set.migrations.iter().filter(|m| m.version > current && m.version <= target) {
+            match migration.up {
+                MigrationUp::Sql(sql) => tx.execute_batch(sql)?,
+                MigrationUp::Code(up) => up(&tx)?,
+            }
+            tx.execute(
+                "INSERT INTO schema_version (component, version, name, down_sql, applied_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![set.component, migration.version, migration.name, migration.down, chrono::Utc::now().timestamp()],
+            )?;
+            changes.push(MigrationChange {
+                component: set.component.to_string(),
+                version: migration.version,
+                name: migration.name.to_string(),
+                rollback: false,
+            });
+        }
+        if !dry_run {
+            tx.commit()?;
+            for change in &changes {
+                info!("Applied {} migration v{} ({})", change.component, change.version, change.name);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Undo migrations of `component` newer than `target`, newest first
+    ///
+    /// Uses the rollback SQL recorded when each was applied, so it works for
+    /// components this binary has no migrations for. Refused if any of them
+    /// has none.
+    pub fn rollback(&self, component: &str, target: u32, dry_run: bool) -> Result<Vec<MigrationChange>> {
+        let mut conn = self.conn.lock();
+        ensure_schema_version_table(&conn)?;
+        let applied = {
+            let mut stmt = conn.prepare(
+                "SELECT version, name, down_sql FROM schema_version WHERE component = ?1 AND version > ?2 ORDER BY version DESC",
+            )?;
+            let rows = stmt.query_map(params![component, target], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        if let Some((version, name, _)) = applied.iter().find(|(_, _, down)| down.is_none()) {
+            return Err(Error::MigrationError(format!(
+                "{} v{} ({}) can't be rolled back",
+                component, version, name
+            )));
+        }
+
+        let tx = conn.transaction()?;
+        let mut changes = Vec::new();
+        for (version, name, down) in applied {
+            tx.execute_batch(down.as_deref().unwrap_or_default())?;
+            tx.execute(
+                "DELETE FROM schema_version WHERE component = ?1 AND version = ?2",
+                params![component, version],
+            )?;
+            changes.push(MigrationChange {
+                component: component.to_string(),
+                version,
+                name,
+                rollback: true,
+            });
+        }
+        if !dry_run {
+            tx.commit()?;
+            for change in &changes {
+                info!("Rolled back {} migration v{} ({})", change.component, change.version, change.name);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Highest applied migration of `component`, 0 if none
+    pub fn schema_version(&self, component: &str) -> Result<u32> {
+        let conn = self.conn.lock();
+        current_version(&conn, component)
+    }
+
+    /// Every applied migration, by component and version
+    pub fn applied_migrations(&self) -> Result<Vec<MigrationRecord>> {
+        let conn = self.conn.lock();
+        ensure_schema_version_table(&conn)?;
+        let mut stmt = conn.prepare(
+            "SELECT component, version, name, down_sql IS NOT NULL, applied_at FROM schema_version ORDER BY component, version",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(MigrationRecord {
+                component: row.get(0)?,
+                version: row.get(1)?,
+                name: row.get(2)?,
+                reversible: row.get(3)?,
+                applied_at: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+fn ensure_schema_version_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            component TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            down_sql TEXT,
+            applied_at INTEGER NOT NULL,
+            PRIMARY KEY(component, version)
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Highest applied migration of `component`, creating `schema_version` if needed
+fn current_version(conn: &Connection, component: &str) -> Result<u32> {
+    ensure_schema_version_table(conn)?;
+    let version: Option<u32> = conn.query_row(
+        "SELECT MAX(version) FROM schema_version WHERE component = ?1",
+        params![component],
+        |row| row.get(0),
+    )?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Copy every table of the attached `replica` database into `main`
+fn restore_tables(conn: &mut Connection) -> Result<()> {
+    let tx = conn.transaction()?;
+    let tables = {
+        let mut stmt = tx.prepare(
+            "SELECT name, sql FROM replica.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    for (table, sql) in tables {
+        let columns = |schema: &str| -> rusqlite::Result<Vec<String>> {
+            let mut stmt = tx.prepare("SELECT name FROM pragma_table_info(?1, ?2)")?;
+            let rows = stmt.query_map(params![table, schema], |row| row.get(0))?;
+            rows.collect()
+        };
+        if columns("main")?.is_empty() {
+            tx.execute_batch(&sql)?;
+        }
+        let ours = columns("main")?;
+        let shared = columns("replica")?
+            .into_iter()
+            .filter(|c| ours.contains(c))
+            .map(|c| format!("\"{}\"", c))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        tx.execute(&format!("DELETE FROM main.\"{}\"", table), [])?;
+        tx.execute(
+            &format!(
+                "INSERT INTO main.\"{0}\" ({1}) SELECT {1} FROM replica.\"{0}\"",
+                table, shared
+            ),
+            [],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
 }
 
 /// Raw database row before parsing
@@ -531,6 +1140,70 @@ pub struct ResourceRow<S, T> {
     pub generation: i64,
 }
 
+/// One versioned change to a component's tables
+pub struct Migration {
+    /// Position in its set, counting from 1
+    pub version: u32,
+    pub name: &'static str,
+    pub up: MigrationUp,
+    /// SQL undoing `up`; migrations without it can't be rolled back
+    pub down: Option<&'static str>,
+}
+
+/// How a migration is applied
+pub enum MigrationUp {
+    Sql(&'static str),
+    /// For changes SQL alone can't make conditional, such as adding a column
+    /// that older releases may already have added
+    Code(fn(&Connection) -> rusqlite::Result<()>),
+}
+
+/// The ordered migrations of one component, versioned in `schema_version`
+pub struct Migrations {
+    pub component: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+impl Migrations {
+    /// Version the schema is at once every migration is applied
+    pub fn latest(&self) -> u32 {
+        self.migrations.last().map(|m| m.version).unwrap_or(0)
+    }
+}
+
+/// A migration applied or rolled back by a migrate call
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MigrationChange {
+    pub component: String,
+    pub version: u32,
+    pub name: String,
+    pub rollback: bool,
+}
+
+/// A row of `schema_version`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MigrationRecord {
+    pub component: String,
+    pub version: u32,
+    pub name: String,
+    /// Whether rollback SQL was recorded for it
+    pub reversible: bool,
+    pub applied_at: i64,
+}
+
+/// Add `column` to `table` unless it is already there
+pub fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> rusqlite::Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE \"{}\" ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -592,6 +1265,15 @@ mod tests {
         assert_eq!(row.spec.value, "test");
         assert!(row.status.ready);
 
+        // Labels
+        let labels = std::collections::HashMap::from([("env".to_string(), "ci".to_string())]);
+        db.update_labels("test_resources", "test-id", &labels).unwrap();
+        let row: ResourceRow<TestSpec, TestStatus> = db
+            .get("test_resources", "test-id")
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.labels, labels);
+
         // List
         let rows: Vec<ResourceRow<TestSpec, TestStatus>> = db.list("test_resources").unwrap();
         assert_eq!(rows.len(), 1);
@@ -600,4 +1282,75 @@ mod tests {
         assert!(db.delete("test_resources", "test-id").unwrap());
         assert!(!db.exists("test_resources", "test-id").unwrap());
     }
+    #[test]
+    fn test_snapshot_and_restore() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let primary = Database::open_memory().unwrap();
+        primary.kv_set("owner", "primary").unwrap();
+        primary
+            .connection()
+            .lock()
+            .execute_batch("CREATE TABLE extra (key TEXT PRIMARY KEY, value TEXT); INSERT INTO extra VALUES ('a', 'b');")
+            .unwrap();
+        let before = primary.change_counter().unwrap();
+        primary.kv_set("region", "eu").unwrap();
+        assert!(primary.change_counter().unwrap() > before);
+
+        let copy = tmp.path().join("state.db");
+        primary.snapshot_to(&copy).unwrap();
+
+        let standby = Database::open_memory().unwrap();
+        standby.kv_set("owner", "standby").unwrap();
+        standby.kv_set("stale", "yes").unwrap();
+        standby.restore_from(&copy).unwrap();
+
+        assert_eq!(standby.kv_get("owner").unwrap().as_deref(), Some("primary"));
+        assert_eq!(standby.kv_get("region").unwrap().as_deref(), Some("eu"));
+        assert_eq!(standby.kv_get("stale").unwrap(), None);
+        let value: String = standby
+            .connection()
+            .lock()
+            .query_row("SELECT value FROM extra WHERE key = 'a'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, "b");
+    }
+
+    #[test]
+    fn test_migrations() {
+        const SET: Migrations = Migrations {
+            component: "test",
+            migrations: &[
+                Migration {
+                    version: 1,
+                    name: "widgets",
+                    up: MigrationUp::Sql("CREATE TABLE widgets (id TEXT PRIMARY KEY);"),
+                    down: None,
+                },
+                Migration {
+                    version: 2,
+                    name: "widget_color",
+                    up: MigrationUp::Code(|conn| add_column_if_missing(conn, "widgets", "color", "TEXT")),
+                    down: Some("ALTER TABLE widgets DROP COLUMN color;"),
+                },
+            ],
+        };
+        let db = Database::open_memory().unwrap();
+        assert_eq!(db.schema_version("state").unwrap(), STATE_MIGRATIONS.latest());
+
+        let planned = db.migrate_to(&SET, 2, true).unwrap();
+        assert_eq!(planned.len(), 2);
+        assert_eq!(db.schema_version("test").unwrap(), 0);
+
+        assert_eq!(db.migrate(&SET).unwrap().len(), 2);
+        assert!(db.migrate(&SET).unwrap().is_empty());
+        db.connection().lock().execute("INSERT INTO widgets (id, color) VALUES ('a', 'red')", []).unwrap();
+
+        let undone = db.migrate_to(&SET, 1, false).unwrap();
+        assert!(undone[0].rollback);
+        assert_eq!(db.schema_version("test").unwrap(), 1);
+        assert!(db.connection().lock().prepare("SELECT color FROM widgets").is_err());
+
+        assert!(db.rollback("test", 0, false).is_err());
+        assert!(db.migrate_to(&SET, 3, false).is_err());
+    }
 }
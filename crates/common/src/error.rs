@@ -1,5 +1,11 @@
 //! Error types for InfraSim
+//!
+//! Every [`Error`] maps to a machine-readable [`ErrorCode`]. The daemon sends
+//! it to clients as an [`ErrorDetail`] in the gRPC status details, and the
+//! web API returns the same fields in its JSON error bodies, so scripts can
+//! branch on `code` instead of matching messages.
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Result type alias using InfraSim Error
@@ -50,12 +56,30 @@ pub enum Error {
     #[error("Snapshot error: {0}")]
     SnapshotError(String),
 
+    #[error("Export error: {0}")]
+    ExportError(String),
+
+    #[error("Backup error: {0}")]
+    BackupError(String),
+
+    #[error("Registry error: {0}")]
+    RegistryError(String),
+
     #[error("Benchmark error: {0}")]
     BenchmarkError(String),
 
     #[error("Console error: {0}")]
     ConsoleError(String),
 
+    #[error("Migration error: {0}")]
+    MigrationError(String),
+
+    #[error("Guest agent error: {0}")]
+    GuestAgent(String),
+
+    #[error("Provisioning error: {0}")]
+    Provisioning(String),
+
     #[error("Invalid state transition: {from} -> {to}")]
     InvalidStateTransition { from: String, to: String },
 
@@ -65,6 +89,9 @@ pub enum Error {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    #[error("Insufficient host resources: {0}")]
+    InsufficientResources(String),
+
     #[error("HVF not available on this system")]
     HvfNotAvailable,
 
@@ -84,9 +111,213 @@ impl From<ed25519_dalek::SignatureError> for Error {
     }
 }
 
+impl Error {
+    /// Machine-readable code of this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::NotFound { .. } => ErrorCode::NotFound,
+            Error::AlreadyExists { .. } => ErrorCode::AlreadyExists,
+            Error::InvalidConfig(_) | Error::Serialization(_) | Error::MigrationError(_) => {
+                ErrorCode::InvalidArgument
+            }
+            Error::InvalidStateTransition { .. } => ErrorCode::InvalidState,
+            Error::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            Error::InsufficientResources(_) => ErrorCode::InsufficientResources,
+            Error::Timeout { .. } => ErrorCode::Timeout,
+            Error::GuestAgent(_) => ErrorCode::GuestAgentUnavailable,
+            Error::HvfNotAvailable | Error::QemuNotFound | Error::UnsupportedArch(_) => {
+                ErrorCode::HostUnsupported
+            }
+            Error::IntegrityError(_) | Error::AttestationError(_) | Error::Crypto(_) => {
+                ErrorCode::IntegrityFailed
+            }
+            _ => ErrorCode::Internal,
+        }
+    }
+
+    /// Code, category and hint of this error, as sent to clients
+    pub fn detail(&self) -> ErrorDetail {
+        ErrorDetail::new(self.code(), self.to_string())
+    }
+}
+
+/// Machine-readable error code, stable across releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    NotFound,
+    AlreadyExists,
+    InvalidArgument,
+    /// The resource is in the wrong state for the operation
+    InvalidState,
+    Unauthenticated,
+    PermissionDenied,
+    InsufficientResources,
+    Timeout,
+    /// Too many attempts; wait before trying again
+    RateLimited,
+    GuestAgentUnavailable,
+    /// The host lacks QEMU, an accelerator or the guest architecture
+    HostUnsupported,
+    IntegrityFailed,
+    DaemonUnavailable,
+    Internal,
+}
+
+/// Broad class of an [`ErrorCode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request itself is wrong and will fail the same way again
+    InvalidRequest,
+    NotFound,
+    /// The request conflicts with current state
+    Conflict,
+    Auth,
+    /// The host is out of capacity or lacks a capability
+    Host,
+    /// A service did not answer in time or at all
+    Unavailable,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::AlreadyExists => "ALREADY_EXISTS",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::InvalidState => "INVALID_STATE",
+            ErrorCode::Unauthenticated => "UNAUTHENTICATED",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::InsufficientResources => "INSUFFICIENT_RESOURCES",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::GuestAgentUnavailable => "GUEST_AGENT_UNAVAILABLE",
+            ErrorCode::HostUnsupported => "HOST_UNSUPPORTED",
+            ErrorCode::IntegrityFailed => "INTEGRITY_FAILED",
+            ErrorCode::DaemonUnavailable => "DAEMON_UNAVAILABLE",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ErrorCode::InvalidArgument => ErrorCategory::InvalidRequest,
+            ErrorCode::NotFound => ErrorCategory::NotFound,
+            ErrorCode::AlreadyExists | ErrorCode::InvalidState => ErrorCategory::Conflict,
+            ErrorCode::Unauthenticated | ErrorCode::PermissionDenied => ErrorCategory::Auth,
+            ErrorCode::InsufficientResources | ErrorCode::HostUnsupported => ErrorCategory::Host,
+            ErrorCode::Timeout
+            | ErrorCode::RateLimited
+            | ErrorCode::GuestAgentUnavailable
+            | ErrorCode::DaemonUnavailable => ErrorCategory::Unavailable,
+            ErrorCode::IntegrityFailed | ErrorCode::Internal => ErrorCategory::Internal,
+        }
+    }
+
+    /// Whether the same request may succeed if retried later
+    pub fn retryable(&self) -> bool {
+        // Not InsufficientResources: waiting rarely frees a host
+        self.category() == ErrorCategory::Unavailable
+    }
+
+    /// What a user can do about the error
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            ErrorCode::NotFound => Some("check the ID or name; list resources to see what exists"),
+            ErrorCode::AlreadyExists => Some("pick another name or delete the existing resource"),
+            ErrorCode::InvalidArgument => Some("fix the request and try again"),
+            ErrorCode::InvalidState => Some("start, stop or resume the resource first, then retry"),
+            ErrorCode::Unauthenticated => Some("sign in again or check the client certificate"),
+            ErrorCode::PermissionDenied => Some("ask an administrator for the required role or setting"),
+            ErrorCode::InsufficientResources => {
+                Some("stop or shrink other VMs, or raise the admission overcommit ratios")
+            }
+            ErrorCode::Timeout => Some("the operation may still finish; check its state and retry"),
+            ErrorCode::RateLimited => Some("wait a while before trying again"),
+            ErrorCode::GuestAgentUnavailable => {
+                Some("wait for the guest to boot and make sure qemu-guest-agent runs in it")
+            }
+            ErrorCode::HostUnsupported => Some("install QEMU or run on a host with the needed accelerator"),
+            ErrorCode::IntegrityFailed => Some("re-download or re-sign the artifact and verify it"),
+            ErrorCode::DaemonUnavailable => Some("check that infrasimd is running and reachable"),
+            ErrorCode::Internal => None,
+        }
+    }
+
+    /// Code for a gRPC status that carries no [`ErrorDetail`]
+    pub fn from_grpc(code: tonic::Code) -> Self {
+        match code {
+            tonic::Code::NotFound => ErrorCode::NotFound,
+            tonic::Code::AlreadyExists => ErrorCode::AlreadyExists,
+            tonic::Code::InvalidArgument | tonic::Code::OutOfRange => ErrorCode::InvalidArgument,
+            tonic::Code::FailedPrecondition | tonic::Code::Aborted => ErrorCode::InvalidState,
+            tonic::Code::Unauthenticated => ErrorCode::Unauthenticated,
+            tonic::Code::PermissionDenied => ErrorCode::PermissionDenied,
+            tonic::Code::ResourceExhausted => ErrorCode::InsufficientResources,
+            tonic::Code::DeadlineExceeded => ErrorCode::Timeout,
+            tonic::Code::Unavailable => ErrorCode::DaemonUnavailable,
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error as clients see it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    pub code: ErrorCode,
+    pub category: ErrorCategory,
+    pub retryable: bool,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl ErrorDetail {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            category: code.category(),
+            retryable: code.retryable(),
+            message: message.into(),
+            hint: code.hint().map(str::to_string),
+        }
+    }
+
+    /// Detail of a gRPC status, derived from its code if the daemon sent none
+    pub fn from_status(status: &tonic::Status) -> Self {
+        serde_json::from_slice::<ErrorDetail>(status.details())
+            .unwrap_or_else(|_| Self::new(ErrorCode::from_grpc(status.code()), status.message()))
+    }
+
+    /// Detail of a failed client call
+    pub fn from_anyhow(e: &anyhow::Error) -> Self {
+        for cause in e.chain() {
+            if let Some(status) = cause.downcast_ref::<tonic::Status>() {
+                return Self::from_status(status);
+            }
+            if let Some(error) = cause.downcast_ref::<Error>() {
+                return error.detail();
+            }
+            if cause.is::<tonic::transport::Error>() {
+                return Self::new(ErrorCode::DaemonUnavailable, e.to_string());
+            }
+        }
+        Self::new(ErrorCode::Internal, e.to_string())
+    }
+}
+
 impl From<Error> for tonic::Status {
     fn from(e: Error) -> Self {
-        match e {
+        let detail = e.detail();
+        let status = match e {
             Error::NotFound { kind, id } => {
                 tonic::Status::not_found(format!("{} {} not found", kind, id))
             }
@@ -95,10 +326,55 @@ impl From<Error> for tonic::Status {
             }
             Error::InvalidConfig(msg) => tonic::Status::invalid_argument(msg),
             Error::PermissionDenied(msg) => tonic::Status::permission_denied(msg),
+            // Not resource_exhausted: clients retry that, and waiting rarely frees a host
+            Error::InsufficientResources(msg) => tonic::Status::failed_precondition(msg),
+            Error::GuestAgent(msg) => tonic::Status::failed_precondition(msg),
+            Error::InvalidStateTransition { .. } => {
+                tonic::Status::failed_precondition(e.to_string())
+            }
             Error::Timeout { seconds } => {
                 tonic::Status::deadline_exceeded(format!("Operation timed out after {}s", seconds))
             }
             _ => tonic::Status::internal(e.to_string()),
-        }
+        };
+        let details = serde_json::to_vec(&detail).unwrap_or_default();
+        tonic::Status::with_details(status.code(), status.message(), details.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_carries_detail() {
+        let status = tonic::Status::from(Error::InvalidStateTransition {
+            from: "stopped".to_string(),
+            to: "paused".to_string(),
+        });
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+        let detail = ErrorDetail::from_status(&status);
+        assert_eq!(detail.code, ErrorCode::InvalidState);
+        assert_eq!(detail.category, ErrorCategory::Conflict);
+        assert!(!detail.retryable);
+        assert_eq!(detail.message, "Invalid state transition: stopped -> paused");
+        assert!(detail.hint.is_some());
+    }
+
+    #[test]
+    fn test_detail_falls_back_to_grpc_code() {
+        let detail = ErrorDetail::from_status(&tonic::Status::unavailable("connection refused"));
+        assert_eq!(detail.code, ErrorCode::DaemonUnavailable);
+        assert!(detail.retryable);
+
+        let e = anyhow::Error::from(tonic::Status::not_found("VM not found")).context("get vm");
+        let detail = ErrorDetail::from_anyhow(&e);
+        assert_eq!(detail.code, ErrorCode::NotFound);
+        assert_eq!(detail.message, "VM not found");
+
+        let detail = ErrorDetail::from_anyhow(&anyhow::anyhow!("boom"));
+        assert_eq!(detail.code, ErrorCode::Internal);
+        assert_eq!(serde_json::to_value(&detail).unwrap()["code"], "INTERNAL");
     }
 }
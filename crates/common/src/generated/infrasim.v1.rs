@@ -0,0 +1,15379 @@
+// This file is @generated by prost-build.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResourceMeta {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(map = "string, string", tag = "4")]
+    pub annotations: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(int64, tag = "5")]
+    pub created_at: i64,
+    #[prost(int64, tag = "6")]
+    pub updated_at: i64,
+    #[prost(int64, tag = "7")]
+    pub generation: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IntegrityConfig {
+    /// "signed_manifest", "sha256", etc.
+    #[prost(string, tag = "1")]
+    pub scheme: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, tag = "4")]
+    pub expected_digest: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VmSpec {
+    /// "aarch64"
+    #[prost(string, tag = "1")]
+    pub arch: ::prost::alloc::string::String,
+    /// "virt" or "raspi3b"
+    #[prost(string, tag = "2")]
+    pub machine: ::prost::alloc::string::String,
+    #[prost(int32, tag = "3")]
+    pub cpu_cores: i32,
+    #[prost(int64, tag = "4")]
+    pub memory_mb: i64,
+    #[prost(string, repeated, tag = "5")]
+    pub volume_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "6")]
+    pub network_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "7")]
+    pub qos_profile_id: ::prost::alloc::string::String,
+    #[prost(bool, tag = "8")]
+    pub enable_tpm: bool,
+    #[prost(string, tag = "9")]
+    pub boot_disk_id: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "10")]
+    pub extra_args: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// true = slow raspi emulation
+    #[prost(bool, tag = "11")]
+    pub compatibility_mode: bool,
+    /// Per network in network_ids
+    #[prost(message, repeated, tag = "12")]
+    pub network_interfaces: ::prost::alloc::vec::Vec<NetworkAttachment>,
+    /// Applied when the VM next starts
+    #[prost(message, repeated, tag = "13")]
+    pub shared_folders: ::prost::alloc::vec::Vec<SharedFolder>,
+    /// Lower priorities are paused first under memory pressure
+    #[prost(int32, tag = "14")]
+    pub priority: i32,
+}
+/// Pinned addressing of a VM NIC
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkAttachment {
+    #[prost(string, tag = "1")]
+    pub network_id: ::prost::alloc::string::String,
+    /// Empty = derived from the VM ID
+    #[prost(string, tag = "2")]
+    pub mac_address: ::prost::alloc::string::String,
+    /// Static IPv4 address reserved by the network's DHCP; empty = dynamic
+    #[prost(string, tag = "3")]
+    pub ip_address: ::prost::alloc::string::String,
+}
+/// Host directory mounted into the guest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SharedFolder {
+    /// Mount tag in the guest; empty = derived from guest_path
+    #[prost(string, tag = "1")]
+    pub tag: ::prost::alloc::string::String,
+    /// Absolute directory on the daemon's host
+    #[prost(string, tag = "2")]
+    pub host_path: ::prost::alloc::string::String,
+    /// Absolute mount point in the guest
+    #[prost(string, tag = "3")]
+    pub guest_path: ::prost::alloc::string::String,
+    #[prost(bool, tag = "4")]
+    pub read_only: bool,
+    #[prost(enumeration = "SharedFolderCache", tag = "5")]
+    pub cache: i32,
+    #[prost(enumeration = "SharedFolderProtocol", tag = "6")]
+    pub protocol: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VmStatus {
+    #[prost(enumeration = "VmState", tag = "1")]
+    pub state: i32,
+    #[prost(string, tag = "2")]
+    pub qemu_pid: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub qmp_socket: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub vnc_display: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub error_message: ::prost::alloc::string::String,
+    #[prost(int64, tag = "6")]
+    pub uptime_seconds: i64,
+    #[prost(string, tag = "7")]
+    pub current_snapshot_id: ::prost::alloc::string::String,
+    /// Immutable; set with MarkVMAsTemplate
+    #[prost(bool, tag = "8")]
+    pub template: bool,
+    /// Template ID of a linked clone
+    #[prost(string, tag = "9")]
+    pub cloned_from: ::prost::alloc::string::String,
+    /// Filled in by GetVM
+    #[prost(message, repeated, tag = "10")]
+    pub interfaces: ::prost::alloc::vec::Vec<VmInterface>,
+}
+/// Addressing of a VM NIC
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VmInterface {
+    #[prost(int32, tag = "1")]
+    pub index: i32,
+    /// Empty for the default user-mode NIC
+    #[prost(string, tag = "2")]
+    pub network_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub mac_address: ::prost::alloc::string::String,
+    /// Empty while unknown or the VM is stopped
+    #[prost(string, tag = "4")]
+    pub ip_address: ::prost::alloc::string::String,
+    /// "user-mode", "dhcp" or "neighbor"
+    #[prost(string, tag = "5")]
+    pub source: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Vm {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<VmSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<VmStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateVmRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<VmSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MarkVmAsTemplateRequest {
+    /// Stopped VM; its disks become read-only backing images
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MarkVmAsTemplateResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CloneVmRequest {
+    /// Template ID or name
+    #[prost(string, tag = "1")]
+    pub template_id: ::prost::alloc::string::String,
+    /// With count > 1, a trailing number is counted up, else -1, -2, ...
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// Default 1
+    #[prost(int32, tag = "3")]
+    pub count: i32,
+    /// Added to the template's labels
+    #[prost(map = "string, string", tag = "4")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CloneVmResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub vms: ::prost::alloc::vec::Vec<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExecuteQmpRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// e.g. {"execute": "query-block"}
+    #[prost(string, tag = "2")]
+    pub command_json: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExecuteQmpResponse {
+    /// QEMU's reply: {"return": ...} or {"error": {...}}
+    #[prost(string, tag = "1")]
+    pub response_json: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<VmSpec>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateVmLabelsRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Added or overwritten
+    #[prost(map = "string, string", tag = "2")]
+    pub set: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// Keys removed, applied after set
+    #[prost(string, repeated, tag = "3")]
+    pub remove: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateVmLabelsResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub force: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteVmResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListVMsRequest {
+    #[prost(map = "string, string", tag = "1")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListVMsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub vms: ::prost::alloc::vec::Vec<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StartVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StartVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StopVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Same as STOP_MODE_HARD
+    #[prost(bool, tag = "2")]
+    pub force: bool,
+    #[prost(enumeration = "StopMode", tag = "3")]
+    pub mode: i32,
+    /// Seconds the guest gets to power off; 0 uses the daemon's stop timeout
+    #[prost(uint32, tag = "4")]
+    pub timeout_seconds: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StopVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PauseVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PauseVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+/// Resumes a paused VM, or restores a suspended VM from its saved RAM
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResumeVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Only resume a VM the daemon paused or suspended for being idle;
+    /// anything else is returned unchanged
+    #[prost(bool, tag = "2")]
+    pub if_idle: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResumeVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SuspendVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SuspendVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttachDeviceRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    /// e.g. /dev/disk4 or /dev/sdb1
+    #[prost(string, tag = "2")]
+    pub device_path: ::prost::alloc::string::String,
+    /// Volume name (defaults to <vm>-<device>)
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "4")]
+    pub read_only: bool,
+    /// Attach even if mounted or locked
+    #[prost(bool, tag = "5")]
+    pub force: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttachDeviceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+    #[prost(message, optional, tag = "2")]
+    pub volume: ::core::option::Option<Volume>,
+    #[prost(string, repeated, tag = "3")]
+    pub warnings: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StreamVmLogsRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    /// Keep streaming new output until cancelled
+    #[prost(bool, tag = "2")]
+    pub follow: bool,
+    /// Console lines to replay first (0 = all)
+    #[prost(uint32, tag = "3")]
+    pub tail_lines: u32,
+    /// Interleave daemon lifecycle events
+    #[prost(bool, tag = "4")]
+    pub include_events: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VmLogEntry {
+    #[prost(int64, tag = "1")]
+    pub timestamp: i64,
+    #[prost(enumeration = "VmLogSource", tag = "2")]
+    pub source: i32,
+    #[prost(string, tag = "3")]
+    pub line: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVmStatsRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchVmStatsRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Time between samples (0 = 2000, min 500)
+    #[prost(uint32, tag = "2")]
+    pub interval_ms: u32,
+}
+/// Resource usage of a running VM; counters are cumulative since QEMU started
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VmStats {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+    /// Of one host core; 200 = two busy cores
+    #[prost(double, tag = "3")]
+    pub cpu_percent: f64,
+    #[prost(double, tag = "4")]
+    pub cpu_seconds: f64,
+    #[prost(uint64, tag = "5")]
+    pub rss_bytes: u64,
+    #[prost(uint64, tag = "6")]
+    pub disk_read_bytes: u64,
+    #[prost(uint64, tag = "7")]
+    pub disk_write_bytes: u64,
+    #[prost(uint64, tag = "8")]
+    pub disk_read_ops: u64,
+    #[prost(uint64, tag = "9")]
+    pub disk_write_ops: u64,
+    /// Only VMs with a QoS profile are counted
+    #[prost(bool, tag = "10")]
+    pub network_counted: bool,
+    #[prost(uint64, tag = "11")]
+    pub net_rx_bytes: u64,
+    #[prost(uint64, tag = "12")]
+    pub net_tx_bytes: u64,
+    #[prost(uint64, tag = "13")]
+    pub uptime_seconds: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportVmRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    /// Defaults to OVA
+    #[prost(enumeration = "ExportFormat", tag = "2")]
+    pub format: i32,
+}
+/// Chunk of the exported tar bundle
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportVmResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// The first message carries the options, every message a chunk of the bundle
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportVmRequest {
+    #[prost(message, optional, tag = "1")]
+    pub options: ::core::option::Option<ImportVmOptions>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportVmOptions {
+    /// Defaults to the name recorded in the bundle
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "2")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportVmResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+    #[prost(message, repeated, tag = "2")]
+    pub volumes: ::prost::alloc::vec::Vec<Volume>,
+}
+/// Publish a stopped VM as an OCI appliance artifact
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PushApplianceRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    /// e.g. ghcr.io/org/appliance:tag
+    #[prost(string, tag = "2")]
+    pub reference: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PushApplianceResponse {
+    /// Pinned to the manifest digest
+    #[prost(string, tag = "1")]
+    pub reference: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(int32, tag = "3")]
+    pub disks: i32,
+    /// Disk layers the registry didn't have yet
+    #[prost(int32, tag = "4")]
+    pub uploaded_disks: i32,
+    #[prost(string, tag = "5")]
+    pub signer_key_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PullApplianceRequest {
+    #[prost(string, tag = "1")]
+    pub reference: ::prost::alloc::string::String,
+    /// Defaults to the name recorded in the appliance
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PullApplianceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+    #[prost(message, repeated, tag = "2")]
+    pub volumes: ::prost::alloc::vec::Vec<Volume>,
+    #[prost(string, tag = "3")]
+    pub digest: ::prost::alloc::string::String,
+    /// Key that signed the appliance's provenance
+    #[prost(string, tag = "4")]
+    pub signer_key_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub terraform_hcl: ::prost::alloc::string::String,
+}
+/// Keystrokes for a VM's serial port; the first message names the VM
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SerialInput {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// Raw bytes written by the guest to its serial port
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SerialOutput {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// File written into a running guest through qemu-guest-agent
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CopyToGuestRequest {
+    /// First message only
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    /// Absolute destination in the guest, replaced if it exists; first message only
+    #[prost(string, tag = "2")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CopyToGuestResponse {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub bytes_written: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkSpec {
+    #[prost(enumeration = "NetworkMode", tag = "1")]
+    pub mode: i32,
+    #[prost(string, tag = "2")]
+    pub cidr: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub gateway: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub dns: ::prost::alloc::string::String,
+    #[prost(bool, tag = "5")]
+    pub dhcp_enabled: bool,
+    #[prost(int32, tag = "6")]
+    pub mtu: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkStatus {
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    #[prost(string, tag = "2")]
+    pub bridge_interface: ::prost::alloc::string::String,
+    #[prost(int32, tag = "3")]
+    pub connected_vms: i32,
+    /// Latest capture, if one was started
+    #[prost(message, optional, tag = "4")]
+    pub capture: ::core::option::Option<NetworkCapture>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NetworkCapture {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(enumeration = "CaptureState", tag = "2")]
+    pub state: i32,
+    /// "tcpdump" or "filter-dump"
+    #[prost(string, tag = "3")]
+    pub method: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub filter: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "5")]
+    pub max_bytes: u64,
+    #[prost(uint64, tag = "6")]
+    pub max_seconds: u64,
+    #[prost(int64, tag = "7")]
+    pub started_at: i64,
+    #[prost(int64, tag = "8")]
+    pub stopped_at: i64,
+    #[prost(uint64, tag = "9")]
+    pub bytes: u64,
+    #[prost(string, tag = "10")]
+    pub stop_reason: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Network {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<NetworkSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<NetworkStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateNetworkRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<NetworkSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateNetworkResponse {
+    #[prost(message, optional, tag = "1")]
+    pub network: ::core::option::Option<Network>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNetworkRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNetworkResponse {
+    #[prost(message, optional, tag = "1")]
+    pub network: ::core::option::Option<Network>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteNetworkRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteNetworkResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListNetworksRequest {
+    #[prost(map = "string, string", tag = "1")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListNetworksResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub networks: ::prost::alloc::vec::Vec<Network>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StartNetworkCaptureRequest {
+    #[prost(string, tag = "1")]
+    pub network_id: ::prost::alloc::string::String,
+    /// pcap filter expression; tcpdump captures only
+    #[prost(string, tag = "2")]
+    pub filter: ::prost::alloc::string::String,
+    /// 0 for the daemon default
+    #[prost(uint64, tag = "3")]
+    pub max_bytes: u64,
+    /// 0 for the daemon default
+    #[prost(uint64, tag = "4")]
+    pub max_seconds: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StartNetworkCaptureResponse {
+    #[prost(message, optional, tag = "1")]
+    pub capture: ::core::option::Option<NetworkCapture>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StopNetworkCaptureRequest {
+    #[prost(string, tag = "1")]
+    pub network_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StopNetworkCaptureResponse {
+    #[prost(message, optional, tag = "1")]
+    pub capture: ::core::option::Option<NetworkCapture>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadNetworkCaptureRequest {
+    #[prost(string, tag = "1")]
+    pub network_id: ::prost::alloc::string::String,
+}
+/// Chunk of the pcap file of the network's last stopped capture
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadNetworkCaptureResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PortForwardSpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub network_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "PortProtocol", tag = "3")]
+    pub protocol: i32,
+    #[prost(string, tag = "4")]
+    pub host_addr: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "5")]
+    pub host_port: u32,
+    #[prost(uint32, tag = "6")]
+    pub guest_port: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PortForwardStatus {
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    #[prost(string, tag = "2")]
+    pub error_message: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PortForward {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<PortForwardSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<PortForwardStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreatePortForwardRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<PortForwardSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreatePortForwardResponse {
+    #[prost(message, optional, tag = "1")]
+    pub port_forward: ::core::option::Option<PortForward>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPortForwardRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPortForwardResponse {
+    #[prost(message, optional, tag = "1")]
+    pub port_forward: ::core::option::Option<PortForward>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeletePortForwardRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeletePortForwardResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPortForwardsRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub network_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPortForwardsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub port_forwards: ::prost::alloc::vec::Vec<PortForward>,
+}
+/// Traffic for destination is sent to next_hop, an address on one of the
+/// router's networks
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StaticRoute {
+    #[prost(string, tag = "1")]
+    pub destination: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub next_hop: ::prost::alloc::string::String,
+}
+/// Empty source/destination match any address, port 0 any port
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RouterRule {
+    #[prost(enumeration = "FirewallAction", tag = "1")]
+    pub action: i32,
+    #[prost(enumeration = "RuleProtocol", tag = "2")]
+    pub protocol: i32,
+    #[prost(string, tag = "3")]
+    pub source: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub destination: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "5")]
+    pub port: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RouterSpec {
+    #[prost(string, repeated, tag = "1")]
+    pub network_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "2")]
+    pub routes: ::prost::alloc::vec::Vec<StaticRoute>,
+    /// Checked in order; the first match decides
+    #[prost(message, repeated, tag = "3")]
+    pub rules: ::prost::alloc::vec::Vec<RouterRule>,
+    /// For forwarded traffic no rule matches (unspecified = allow)
+    #[prost(enumeration = "FirewallAction", tag = "4")]
+    pub default_action: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RouterStatus {
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    #[prost(string, tag = "2")]
+    pub error_message: ::prost::alloc::string::String,
+    /// Host interfaces the router forwards between
+    #[prost(string, repeated, tag = "3")]
+    pub interfaces: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Router {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<RouterSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<RouterStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateRouterRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<RouterSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateRouterResponse {
+    #[prost(message, optional, tag = "1")]
+    pub router: ::core::option::Option<Router>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRouterRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetRouterResponse {
+    #[prost(message, optional, tag = "1")]
+    pub router: ::core::option::Option<Router>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateRouterRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<RouterSpec>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateRouterResponse {
+    #[prost(message, optional, tag = "1")]
+    pub router: ::core::option::Option<Router>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteRouterRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteRouterResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRoutersRequest {
+    #[prost(string, tag = "1")]
+    pub network_id: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "2")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListRoutersResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub routers: ::prost::alloc::vec::Vec<Router>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeviceSpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "DeviceBus", tag = "2")]
+    pub bus: i32,
+    /// USB only; matched together with product_id
+    #[prost(uint32, tag = "3")]
+    pub vendor_id: u32,
+    #[prost(uint32, tag = "4")]
+    pub product_id: u32,
+    /// USB "<bus>.<addr>", or PCI "0000:01:00.0"
+    #[prost(string, tag = "5")]
+    pub host_address: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeviceStatus {
+    /// Present in the running QEMU process
+    #[prost(bool, tag = "1")]
+    pub attached: bool,
+    #[prost(string, tag = "2")]
+    pub qemu_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub error_message: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Device {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<DeviceSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<DeviceStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateDeviceRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<DeviceSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateDeviceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub device: ::core::option::Option<Device>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetDeviceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetDeviceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub device: ::core::option::Option<Device>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteDeviceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteDeviceResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListDevicesRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListDevicesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub devices: ::prost::alloc::vec::Vec<Device>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QoSProfileSpec {
+    #[prost(int32, tag = "1")]
+    pub latency_ms: i32,
+    #[prost(int32, tag = "2")]
+    pub jitter_ms: i32,
+    #[prost(float, tag = "3")]
+    pub loss_percent: f32,
+    #[prost(int32, tag = "4")]
+    pub rate_limit_mbps: i32,
+    #[prost(int32, tag = "5")]
+    pub packet_padding_bytes: i32,
+    #[prost(bool, tag = "6")]
+    pub burst_shaping: bool,
+    #[prost(int32, tag = "7")]
+    pub burst_size_kb: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QoSProfile {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<QoSProfileSpec>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateQoSProfileRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<QoSProfileSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateQoSProfileResponse {
+    #[prost(message, optional, tag = "1")]
+    pub profile: ::core::option::Option<QoSProfile>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetQoSProfileRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetQoSProfileResponse {
+    #[prost(message, optional, tag = "1")]
+    pub profile: ::core::option::Option<QoSProfile>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteQoSProfileRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteQoSProfileResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListQoSProfilesRequest {
+    #[prost(map = "string, string", tag = "1")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListQoSProfilesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub profiles: ::prost::alloc::vec::Vec<QoSProfile>,
+}
+/// VMs on one side of a partition
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChaosPartition {
+    #[prost(string, repeated, tag = "1")]
+    pub vm_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChaosProfileSpec {
+    /// Target all NICs on the network
+    #[prost(string, tag = "1")]
+    pub network_id: ::prost::alloc::string::String,
+    /// Target the VM's NICs, or its NIC on network_id
+    #[prost(string, tag = "2")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(int32, tag = "3")]
+    pub latency_ms: i32,
+    #[prost(int32, tag = "4")]
+    pub jitter_ms: i32,
+    #[prost(enumeration = "LatencyDistribution", tag = "5")]
+    pub distribution: i32,
+    #[prost(float, tag = "6")]
+    pub loss_percent: f32,
+    #[prost(int32, tag = "7")]
+    pub rate_limit_mbps: i32,
+    #[prost(message, repeated, tag = "8")]
+    pub partitions: ::prost::alloc::vec::Vec<ChaosPartition>,
+    /// Unix timestamp; 0 never expires
+    #[prost(int64, tag = "9")]
+    pub expires_at: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChaosProfileStatus {
+    #[prost(string, repeated, tag = "1")]
+    pub applied_vm_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Running without relays until restarted
+    #[prost(string, repeated, tag = "2")]
+    pub pending_vm_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChaosProfile {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<ChaosProfileSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<ChaosProfileStatus>,
+}
+/// Create a profile, or replace the spec of the profile with this name
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplyChaosProfileRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<ChaosProfileSpec>,
+    /// Sets spec.expires_at when non-zero
+    #[prost(int64, tag = "3")]
+    pub ttl_seconds: i64,
+    #[prost(map = "string, string", tag = "4")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplyChaosProfileResponse {
+    #[prost(message, optional, tag = "1")]
+    pub profile: ::core::option::Option<ChaosProfile>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetChaosProfileRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetChaosProfileResponse {
+    #[prost(message, optional, tag = "1")]
+    pub profile: ::core::option::Option<ChaosProfile>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteChaosProfileRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteChaosProfileResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListChaosProfilesRequest {
+    #[prost(string, tag = "1")]
+    pub network_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub vm_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListChaosProfilesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub profiles: ::prost::alloc::vec::Vec<ChaosProfile>,
+}
+/// Usage of one VM once a step finished
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScenarioVmStats {
+    #[prost(string, tag = "1")]
+    pub vm: ::prost::alloc::string::String,
+    #[prost(double, tag = "2")]
+    pub cpu_percent: f64,
+    #[prost(uint64, tag = "3")]
+    pub rss_bytes: u64,
+    /// 0 unless the VM's NICs are relayed
+    #[prost(uint64, tag = "4")]
+    pub rx_bytes: u64,
+    #[prost(uint64, tag = "5")]
+    pub tx_bytes: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScenarioStepReport {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub action: ::prost::alloc::string::String,
+    #[prost(enumeration = "ScenarioStepOutcome", tag = "3")]
+    pub outcome: i32,
+    /// After the run started
+    #[prost(uint64, tag = "4")]
+    pub started_ms: u64,
+    #[prost(uint64, tag = "5")]
+    pub duration_ms: u64,
+    #[prost(string, tag = "6")]
+    pub detail: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "7")]
+    pub stats: ::prost::alloc::vec::Vec<ScenarioVmStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScenarioRun {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(string, tag = "2")]
+    pub scenario_name: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub description: ::prost::alloc::string::String,
+    /// SHA-256 of the parsed scenario
+    #[prost(string, tag = "4")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(enumeration = "ScenarioRunState", tag = "5")]
+    pub state: i32,
+    #[prost(int64, tag = "6")]
+    pub started_at: i64,
+    #[prost(int64, tag = "7")]
+    pub finished_at: i64,
+    #[prost(string, tag = "8")]
+    pub error: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "9")]
+    pub steps: ::prost::alloc::vec::Vec<ScenarioStepReport>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RunScenarioRequest {
+    /// Scenario YAML
+    #[prost(string, tag = "1")]
+    pub definition: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "2")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RunScenarioResponse {
+    #[prost(message, optional, tag = "1")]
+    pub run: ::core::option::Option<ScenarioRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetScenarioRunRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetScenarioRunResponse {
+    #[prost(message, optional, tag = "1")]
+    pub run: ::core::option::Option<ScenarioRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListScenarioRunsRequest {
+    /// Only runs of the scenario with this name
+    #[prost(string, tag = "1")]
+    pub scenario: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListScenarioRunsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub runs: ::prost::alloc::vec::Vec<ScenarioRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelScenarioRunRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelScenarioRunResponse {
+    #[prost(message, optional, tag = "1")]
+    pub run: ::core::option::Option<ScenarioRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteScenarioRunRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteScenarioRunResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CompareScenarioRunsRequest {
+    #[prost(string, tag = "1")]
+    pub base_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub other_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScenarioStepComparison {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// Empty if the step isn't in that run
+    #[prost(string, tag = "2")]
+    pub base_outcome: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub other_outcome: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "4")]
+    pub base_duration_ms: u64,
+    #[prost(uint64, tag = "5")]
+    pub other_duration_ms: u64,
+    #[prost(string, repeated, tag = "6")]
+    pub changes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CompareScenarioRunsResponse {
+    /// Both runs executed the same scenario
+    #[prost(bool, tag = "1")]
+    pub same_definition: bool,
+    #[prost(message, repeated, tag = "2")]
+    pub steps: ::prost::alloc::vec::Vec<ScenarioStepComparison>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VolumeSpec {
+    #[prost(enumeration = "VolumeKind", tag = "1")]
+    pub kind: i32,
+    /// OCI reference, file path, etc.
+    #[prost(string, tag = "2")]
+    pub source: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub integrity: ::core::option::Option<IntegrityConfig>,
+    #[prost(bool, tag = "4")]
+    pub read_only: bool,
+    #[prost(int64, tag = "5")]
+    pub size_bytes: i64,
+    /// "qcow2", "raw"
+    #[prost(string, tag = "6")]
+    pub format: ::prost::alloc::string::String,
+    /// Create copy-on-write overlay
+    #[prost(bool, tag = "7")]
+    pub overlay: bool,
+    /// Unset for plaintext volumes
+    #[prost(message, optional, tag = "8")]
+    pub encryption: ::core::option::Option<VolumeEncryption>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VolumeEncryption {
+    /// "aes-256" (default), "aes-128", "serpent-256", ...
+    #[prost(string, tag = "1")]
+    pub cipher: ::prost::alloc::string::String,
+    /// Keyring entry holding the passphrase
+    #[prost(string, tag = "2")]
+    pub key_id: ::prost::alloc::string::String,
+    /// Passphrase or key file stored under key_id on create; never returned
+    #[prost(bytes = "vec", tag = "3")]
+    pub secret: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VolumeStatus {
+    #[prost(bool, tag = "1")]
+    pub ready: bool,
+    #[prost(string, tag = "2")]
+    pub local_path: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(int64, tag = "4")]
+    pub actual_size: i64,
+    #[prost(bool, tag = "5")]
+    pub verified: bool,
+    #[prost(string, repeated, tag = "6")]
+    pub warnings: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "7")]
+    pub smart_status: ::prost::alloc::string::String,
+    /// Set for http(s) sources
+    #[prost(message, optional, tag = "8")]
+    pub download: ::core::option::Option<DownloadStatus>,
+    /// CAS object of the signed SLSA provenance, once attested
+    #[prost(string, tag = "9")]
+    pub provenance_digest: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadStatus {
+    #[prost(enumeration = "DownloadState", tag = "1")]
+    pub state: i32,
+    #[prost(int64, tag = "2")]
+    pub bytes_downloaded: i64,
+    /// 0 if unknown
+    #[prost(int64, tag = "3")]
+    pub bytes_total: i64,
+    #[prost(string, tag = "4")]
+    pub error: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Volume {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<VolumeSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<VolumeStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<VolumeSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// How the source image was built; unset for plain imports
+    #[prost(message, optional, tag = "4")]
+    pub build: ::core::option::Option<VolumeBuild>,
+}
+/// Inputs of a build, recorded in the SLSA provenance of the volume's image
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VolumeBuild {
+    /// URI; defaults to the appliance build type
+    #[prost(string, tag = "1")]
+    pub build_type: ::prost::alloc::string::String,
+    /// Parameters the build was asked for
+    #[prost(string, tag = "2")]
+    pub parameters_json: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub dependencies: ::prost::alloc::vec::Vec<BuildDependency>,
+    /// Component to version
+    #[prost(map = "string, string", tag = "4")]
+    pub builder_version: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(string, tag = "5")]
+    pub invocation_id: ::prost::alloc::string::String,
+    #[prost(int64, tag = "6")]
+    pub started_at: i64,
+    #[prost(int64, tag = "7")]
+    pub finished_at: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BuildDependency {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub uri: ::prost::alloc::string::String,
+    /// Hex; empty if unknown
+    #[prost(string, tag = "3")]
+    pub sha256: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateVolumeResponse {
+    #[prost(message, optional, tag = "1")]
+    pub volume: ::core::option::Option<Volume>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVolumeResponse {
+    #[prost(message, optional, tag = "1")]
+    pub volume: ::core::option::Option<Volume>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteVolumeResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListVolumesRequest {
+    #[prost(map = "string, string", tag = "1")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(enumeration = "VolumeKind", tag = "2")]
+    pub kind_filter: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListVolumesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub volumes: ::prost::alloc::vec::Vec<Volume>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchVolumeDownloadRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+}
+/// One image in a volume's qcow2 backing-file chain
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VolumeLayer {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub format: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub virtual_size: i64,
+    /// Bytes allocated on the host
+    #[prost(int64, tag = "4")]
+    pub actual_size: i64,
+    /// Snapshot that froze this layer, if any
+    #[prost(string, tag = "5")]
+    pub snapshot_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVolumeChainRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVolumeChainResponse {
+    /// Active image first, base image last
+    #[prost(message, repeated, tag = "1")]
+    pub layers: ::prost::alloc::vec::Vec<VolumeLayer>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlattenVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlattenProgress {
+    #[prost(double, tag = "1")]
+    pub percent: f64,
+    #[prost(bool, tag = "2")]
+    pub done: bool,
+    /// New active image, set when done
+    #[prost(string, tag = "3")]
+    pub local_path: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResizeVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+    /// New virtual size, a multiple of 512
+    #[prost(int64, tag = "2")]
+    pub size_bytes: i64,
+    /// Allow shrinking, which discards data past the new end
+    #[prost(bool, tag = "3")]
+    pub shrink: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ResizeVolumeResponse {
+    #[prost(message, optional, tag = "1")]
+    pub volume: ::core::option::Option<Volume>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PushVolumeRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+    /// Absolute directory path or s3://bucket/prefix
+    #[prost(string, tag = "2")]
+    pub remote: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PushVolumeResponse {
+    /// sha256 of the pushed image
+    #[prost(string, tag = "1")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub size_bytes: i64,
+    /// False if the remote already had the image
+    #[prost(bool, tag = "3")]
+    pub uploaded: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PullVolumeRequest {
+    /// ID of the volume on the daemon that pushed it
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub remote: ::prost::alloc::string::String,
+    /// Defaults to the pushed volume's name
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PullVolumeResponse {
+    #[prost(message, optional, tag = "1")]
+    pub volume: ::core::option::Option<Volume>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsoleSpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub enable_vnc: bool,
+    #[prost(int32, tag = "3")]
+    pub vnc_port: i32,
+    #[prost(bool, tag = "4")]
+    pub enable_web: bool,
+    #[prost(int32, tag = "5")]
+    pub web_port: i32,
+    #[prost(string, tag = "6")]
+    pub auth_token: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsoleStatus {
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    #[prost(string, tag = "2")]
+    pub vnc_host: ::prost::alloc::string::String,
+    #[prost(int32, tag = "3")]
+    pub vnc_port: i32,
+    #[prost(string, tag = "4")]
+    pub web_url: ::prost::alloc::string::String,
+    #[prost(int32, tag = "5")]
+    pub connected_clients: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Console {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<ConsoleSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<ConsoleStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateConsoleRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<ConsoleSpec>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateConsoleResponse {
+    #[prost(message, optional, tag = "1")]
+    pub console: ::core::option::Option<Console>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetConsoleRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetConsoleResponse {
+    #[prost(message, optional, tag = "1")]
+    pub console: ::core::option::Option<Console>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteConsoleRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteConsoleResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotSpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub include_memory: bool,
+    #[prost(bool, tag = "3")]
+    pub include_disk: bool,
+    #[prost(string, tag = "4")]
+    pub description: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotStatus {
+    #[prost(bool, tag = "1")]
+    pub complete: bool,
+    #[prost(string, tag = "2")]
+    pub disk_snapshot_path: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub memory_snapshot_path: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(int64, tag = "5")]
+    pub size_bytes: i64,
+    #[prost(bool, tag = "6")]
+    pub encrypted: bool,
+    #[prost(string, tag = "7")]
+    pub parent_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Snapshot {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<SnapshotSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<SnapshotStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateSnapshotRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<SnapshotSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateSnapshotResponse {
+    #[prost(message, optional, tag = "1")]
+    pub snapshot: ::core::option::Option<Snapshot>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnapshotRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnapshotResponse {
+    #[prost(message, optional, tag = "1")]
+    pub snapshot: ::core::option::Option<Snapshot>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteSnapshotRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteSnapshotResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnapshotsRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "2")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnapshotsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub snapshots: ::prost::alloc::vec::Vec<Snapshot>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreSnapshotRequest {
+    #[prost(string, tag = "1")]
+    pub snapshot_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub target_vm_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreSnapshotResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+/// Point the VM's boot disk back at a snapshot's disk layer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevertSnapshotRequest {
+    #[prost(string, tag = "1")]
+    pub snapshot_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevertSnapshotResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+/// Create a new VM whose boot disk forks from a snapshot's disk layer
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BranchSnapshotRequest {
+    #[prost(string, tag = "1")]
+    pub snapshot_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BranchSnapshotResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+}
+/// A file inside a snapshot's disk layer, read through libguestfs
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotFile {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    /// file, directory, symlink or other
+    #[prost(string, tag = "2")]
+    pub r#type: ::prost::alloc::string::String,
+    /// Permission bits
+    #[prost(uint32, tag = "3")]
+    pub mode: u32,
+    #[prost(uint64, tag = "4")]
+    pub size_bytes: u64,
+    #[prost(int64, tag = "5")]
+    pub modified_at: i64,
+    /// Regular files, when checksums were asked for
+    #[prost(string, tag = "6")]
+    pub sha256: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub link_target: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnapshotFilesRequest {
+    #[prost(string, tag = "1")]
+    pub snapshot_id: ::prost::alloc::string::String,
+    /// Directory to list; defaults to /
+    #[prost(string, tag = "2")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub recursive: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnapshotFilesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub files: ::prost::alloc::vec::Vec<SnapshotFile>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadSnapshotFileRequest {
+    #[prost(string, tag = "1")]
+    pub snapshot_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub path: ::prost::alloc::string::String,
+}
+/// Chunk of the file's contents
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadSnapshotFileResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// Files that differ between two snapshots of the same VM
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiffSnapshotsRequest {
+    #[prost(string, tag = "1")]
+    pub snapshot_a: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub snapshot_b: ::prost::alloc::string::String,
+    /// Directory to compare; defaults to /
+    #[prost(string, tag = "3")]
+    pub path: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotFileChange {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(enumeration = "FileChange", tag = "2")]
+    pub change: i32,
+    /// In snapshot_a
+    #[prost(message, optional, tag = "3")]
+    pub before: ::core::option::Option<SnapshotFile>,
+    /// In snapshot_b
+    #[prost(message, optional, tag = "4")]
+    pub after: ::core::option::Option<SnapshotFile>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DiffSnapshotsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub changes: ::prost::alloc::vec::Vec<SnapshotFileChange>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotPolicySpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub interval_seconds: i64,
+    #[prost(uint32, tag = "3")]
+    pub keep: u32,
+    #[prost(bool, tag = "4")]
+    pub include_memory: bool,
+    #[prost(bool, tag = "5")]
+    pub paused: bool,
+    /// Snapshot every VM with these labels instead of vm_id
+    #[prost(map = "string, string", tag = "6")]
+    pub vm_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotPolicyStatus {
+    #[prost(int64, tag = "1")]
+    pub last_run_at: i64,
+    #[prost(int64, tag = "2")]
+    pub next_run_at: i64,
+    #[prost(string, tag = "3")]
+    pub last_snapshot_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub last_error: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "5")]
+    pub retained: u32,
+    /// Human-readable schedule, e.g. "every 6h, keep 10"
+    #[prost(string, tag = "6")]
+    pub schedule: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotPolicy {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<SnapshotPolicySpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<SnapshotPolicyStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateSnapshotPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<SnapshotPolicySpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateSnapshotPolicyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub policy: ::core::option::Option<SnapshotPolicy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnapshotPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSnapshotPolicyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub policy: ::core::option::Option<SnapshotPolicy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateSnapshotPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<SnapshotPolicySpec>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateSnapshotPolicyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub policy: ::core::option::Option<SnapshotPolicy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteSnapshotPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteSnapshotPolicyResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnapshotPoliciesRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSnapshotPoliciesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub policies: ::prost::alloc::vec::Vec<SnapshotPolicy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupSpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    /// Absolute directory or s3://bucket/prefix
+    #[prost(string, tag = "2")]
+    pub target: ::prost::alloc::string::String,
+    /// Copy only blocks changed since the VM's previous backup when possible
+    #[prost(bool, tag = "3")]
+    pub incremental: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupDisk {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub volume_name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub boot: bool,
+    #[prost(bool, tag = "4")]
+    pub read_only: bool,
+    /// qcow2 image in the VM's directory of the target
+    #[prost(string, tag = "5")]
+    pub file: ::prost::alloc::string::String,
+    /// Virtual size
+    #[prost(int64, tag = "6")]
+    pub size_bytes: i64,
+    /// Empty if not encrypted
+    #[prost(string, tag = "7")]
+    pub encryption_key_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupStatus {
+    #[prost(bool, tag = "1")]
+    pub complete: bool,
+    #[prost(enumeration = "BackupKind", tag = "2")]
+    pub kind: i32,
+    #[prost(string, tag = "3")]
+    pub parent_id: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "4")]
+    pub disks: ::prost::alloc::vec::Vec<BackupDisk>,
+    #[prost(string, tag = "5")]
+    pub vm_name: ::prost::alloc::string::String,
+    /// Bytes written to the target
+    #[prost(int64, tag = "6")]
+    pub size_bytes: i64,
+    #[prost(string, tag = "7")]
+    pub error: ::prost::alloc::string::String,
+    #[prost(int64, tag = "8")]
+    pub completed_at: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Backup {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<BackupSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<BackupStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateBackupRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<BackupSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateBackupResponse {
+    #[prost(message, optional, tag = "1")]
+    pub backup: ::core::option::Option<Backup>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBackupRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBackupResponse {
+    #[prost(message, optional, tag = "1")]
+    pub backup: ::core::option::Option<Backup>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteBackupRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteBackupResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBackupsRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBackupsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub backups: ::prost::alloc::vec::Vec<Backup>,
+}
+/// Create a new stopped VM with the disks and spec of a backup point
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreBackupRequest {
+    #[prost(string, tag = "1")]
+    pub backup_id: ::prost::alloc::string::String,
+    /// Defaults to "<vm>-<backup>"
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreBackupResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vm: ::core::option::Option<Vm>,
+    #[prost(message, repeated, tag = "2")]
+    pub volumes: ::prost::alloc::vec::Vec<Volume>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupPolicySpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub target: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub interval_seconds: i64,
+    #[prost(uint32, tag = "4")]
+    pub keep: u32,
+    /// Take a full backup after this many incremental ones; 0 only when needed
+    #[prost(uint32, tag = "5")]
+    pub full_every: u32,
+    #[prost(bool, tag = "6")]
+    pub paused: bool,
+    /// Back up every VM with these labels instead of vm_id
+    #[prost(map = "string, string", tag = "7")]
+    pub vm_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupPolicyStatus {
+    #[prost(int64, tag = "1")]
+    pub last_run_at: i64,
+    #[prost(int64, tag = "2")]
+    pub next_run_at: i64,
+    #[prost(string, tag = "3")]
+    pub last_backup_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub last_error: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "5")]
+    pub retained: u32,
+    /// Human-readable schedule, e.g. "every 1d, keep 14, full every 7"
+    #[prost(string, tag = "6")]
+    pub schedule: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackupPolicy {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<BackupPolicySpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<BackupPolicyStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateBackupPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<BackupPolicySpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateBackupPolicyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub policy: ::core::option::Option<BackupPolicy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBackupPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBackupPolicyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub policy: ::core::option::Option<BackupPolicy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateBackupPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<BackupPolicySpec>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateBackupPolicyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub policy: ::core::option::Option<BackupPolicy>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteBackupPolicyRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteBackupPolicyResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBackupPoliciesRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBackupPoliciesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub policies: ::prost::alloc::vec::Vec<BackupPolicy>,
+}
+/// Pack volumes and snapshot images into a signed tar.gz in the CAS
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateArchiveRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub volume_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "2")]
+    pub snapshot_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Add the memory state of the snapshots
+    #[prost(bool, tag = "3")]
+    pub include_memory: bool,
+    /// Stored as metadata.json, e.g. an appliance description
+    #[prost(bytes = "vec", tag = "4")]
+    pub metadata: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateArchiveResponse {
+    /// SHA-256 of the archive, its CAS key
+    #[prost(string, tag = "1")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub size_bytes: u64,
+    /// Over the digest, by the daemon signing key
+    #[prost(bytes = "vec", tag = "3")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, tag = "4")]
+    pub public_key: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub key_id: ::prost::alloc::string::String,
+}
+/// Read an archive from the CAS, starting at `offset` to resume a download
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadArchiveRequest {
+    #[prost(string, tag = "1")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub offset: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DownloadArchiveResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+    /// Size of the whole archive
+    #[prost(uint64, tag = "2")]
+    pub size_bytes: u64,
+}
+/// Create volumes from the disk images of an archive in the CAS
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreArchiveRequest {
+    #[prost(string, tag = "1")]
+    pub digest: ::prost::alloc::string::String,
+    /// Prepended to the archived volume names
+    #[prost(string, tag = "2")]
+    pub name_prefix: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreArchiveResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub volumes: ::prost::alloc::vec::Vec<Volume>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub metadata: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct JobSpec {
+    #[prost(enumeration = "JobKind", tag = "1")]
+    pub kind: i32,
+    /// Resource ID or reference the job works on
+    #[prost(string, tag = "2")]
+    pub target: ::prost::alloc::string::String,
+    /// Kind-specific arguments, e.g. "remote" for volume push and pull
+    #[prost(map = "string, string", tag = "3")]
+    pub params: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct JobStatus {
+    #[prost(enumeration = "JobState", tag = "1")]
+    pub state: i32,
+    #[prost(double, tag = "2")]
+    pub progress: f64,
+    #[prost(string, tag = "3")]
+    pub message: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub error: ::prost::alloc::string::String,
+    /// What the job produced, e.g. "volume_id" or "digest"
+    #[prost(map = "string, string", tag = "5")]
+    pub result: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(int64, tag = "6")]
+    pub started_at: i64,
+    #[prost(int64, tag = "7")]
+    pub finished_at: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Job {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<JobSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<JobStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateJobRequest {
+    #[prost(message, optional, tag = "1")]
+    pub spec: ::core::option::Option<JobSpec>,
+    #[prost(map = "string, string", tag = "2")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateJobResponse {
+    #[prost(message, optional, tag = "1")]
+    pub job: ::core::option::Option<Job>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetJobRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetJobResponse {
+    #[prost(message, optional, tag = "1")]
+    pub job: ::core::option::Option<Job>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListJobsRequest {
+    /// Only jobs in this state; unspecified lists all
+    #[prost(enumeration = "JobState", tag = "1")]
+    pub state: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListJobsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub jobs: ::prost::alloc::vec::Vec<Job>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelJobRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelJobResponse {
+    #[prost(message, optional, tag = "1")]
+    pub job: ::core::option::Option<Job>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BenchmarkSpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub suite_name: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub test_names: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(int32, tag = "4")]
+    pub timeout_seconds: i32,
+    #[prost(map = "string, string", tag = "5")]
+    pub parameters: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BenchmarkResult {
+    #[prost(string, tag = "1")]
+    pub test_name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub passed: bool,
+    #[prost(double, tag = "3")]
+    pub score: f64,
+    #[prost(string, tag = "4")]
+    pub unit: ::prost::alloc::string::String,
+    #[prost(int64, tag = "5")]
+    pub duration_ms: i64,
+    #[prost(map = "string, string", tag = "6")]
+    pub metadata: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BenchmarkReceipt {
+    #[prost(string, tag = "1")]
+    pub run_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    #[prost(int64, tag = "4")]
+    pub timestamp: i64,
+    #[prost(string, tag = "5")]
+    pub signer_public_key: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BenchmarkRun {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<BenchmarkSpec>,
+    #[prost(message, repeated, tag = "3")]
+    pub results: ::prost::alloc::vec::Vec<BenchmarkResult>,
+    #[prost(message, optional, tag = "4")]
+    pub receipt: ::core::option::Option<BenchmarkReceipt>,
+    #[prost(string, tag = "5")]
+    pub attestation_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateBenchmarkRunRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<BenchmarkSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateBenchmarkRunResponse {
+    #[prost(message, optional, tag = "1")]
+    pub run: ::core::option::Option<BenchmarkRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBenchmarkRunRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetBenchmarkRunResponse {
+    #[prost(message, optional, tag = "1")]
+    pub run: ::core::option::Option<BenchmarkRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBenchmarkRunsRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "2")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBenchmarkRunsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub runs: ::prost::alloc::vec::Vec<BenchmarkRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HostProvenance {
+    #[prost(string, tag = "1")]
+    pub qemu_version: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "2")]
+    pub qemu_args: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "3")]
+    pub base_image_hash: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "4")]
+    pub volume_hashes: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(string, tag = "5")]
+    pub macos_version: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub cpu_model: ::prost::alloc::string::String,
+    #[prost(bool, tag = "7")]
+    pub hvf_enabled: bool,
+    #[prost(string, tag = "8")]
+    pub hostname: ::prost::alloc::string::String,
+    #[prost(int64, tag = "9")]
+    pub timestamp: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttestationReport {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub host_provenance: ::core::option::Option<HostProvenance>,
+    #[prost(string, tag = "4")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    #[prost(int64, tag = "6")]
+    pub created_at: i64,
+    /// "host_provenance", "vtpm", etc.
+    #[prost(string, tag = "7")]
+    pub attestation_type: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "8")]
+    pub tpm_quotes: ::prost::alloc::vec::Vec<TpmQuote>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TpmEvent {
+    #[prost(uint32, tag = "1")]
+    pub pcr_index: u32,
+    /// SHA-256, hex
+    #[prost(string, tag = "2")]
+    pub digest: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub command: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "4")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TpmQuote {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    /// "boot", "snapshot:<id>", "on-demand"
+    #[prost(string, tag = "2")]
+    pub label: ::prost::alloc::string::String,
+    /// SHA-256 bank, hex
+    #[prost(map = "uint32, string", tag = "3")]
+    pub pcrs: ::std::collections::HashMap<u32, ::prost::alloc::string::String>,
+    /// TPMS_ATTEST
+    #[prost(bytes = "vec", tag = "4")]
+    pub quoted: ::prost::alloc::vec::Vec<u8>,
+    /// ECDSA P-256 r || s
+    #[prost(bytes = "vec", tag = "5")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    /// SEC1 uncompressed
+    #[prost(bytes = "vec", tag = "6")]
+    pub ak_public: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub nonce: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, repeated, tag = "8")]
+    pub event_log: ::prost::alloc::vec::Vec<TpmEvent>,
+    #[prost(int64, tag = "9")]
+    pub created_at: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetAttestationRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetAttestationResponse {
+    #[prost(message, optional, tag = "1")]
+    pub report: ::core::option::Option<AttestationReport>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTpmQuoteRequest {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub nonce: ::prost::alloc::vec::Vec<u8>,
+    /// empty = default selection
+    #[prost(uint32, repeated, tag = "3")]
+    pub pcrs: ::prost::alloc::vec::Vec<u32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTpmQuoteResponse {
+    #[prost(message, optional, tag = "1")]
+    pub quote: ::core::option::Option<TpmQuote>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVolumeProvenanceRequest {
+    #[prost(string, tag = "1")]
+    pub volume_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetVolumeProvenanceResponse {
+    /// DSSE envelope (JSON) of an in-toto SLSA provenance statement
+    #[prost(bytes = "vec", tag = "1")]
+    pub envelope: ::prost::alloc::vec::Vec<u8>,
+    /// CAS digest of the envelope
+    #[prost(string, tag = "2")]
+    pub envelope_digest: ::prost::alloc::string::String,
+    /// sha256 of the volume's source image as it is now
+    #[prost(string, tag = "3")]
+    pub image_digest: ::prost::alloc::string::String,
+    /// Hex-encoded daemon signing key
+    #[prost(string, tag = "4")]
+    pub public_key: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub key_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoRaDeviceSpec {
+    #[prost(string, tag = "1")]
+    pub vm_id: ::prost::alloc::string::String,
+    /// "EU868", "US915", etc.
+    #[prost(string, tag = "2")]
+    pub region: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub device_eui: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub app_eui: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub app_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(int32, tag = "6")]
+    pub spreading_factor: i32,
+    #[prost(int32, tag = "7")]
+    pub bandwidth_khz: i32,
+    #[prost(float, tag = "8")]
+    pub loss_rate: f32,
+    #[prost(int32, tag = "9")]
+    pub latency_ms: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoRaDeviceStatus {
+    #[prost(bool, tag = "1")]
+    pub connected: bool,
+    #[prost(int64, tag = "2")]
+    pub packets_sent: i64,
+    #[prost(int64, tag = "3")]
+    pub packets_received: i64,
+    #[prost(float, tag = "4")]
+    pub rssi_dbm: f32,
+    #[prost(float, tag = "5")]
+    pub snr_db: f32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LoRaDevice {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<LoRaDeviceSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<LoRaDeviceStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateLoRaDeviceRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<LoRaDeviceSpec>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateLoRaDeviceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub device: ::core::option::Option<LoRaDevice>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetLoRaDeviceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetLoRaDeviceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub device: ::core::option::Option<LoRaDevice>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteLoRaDeviceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteLoRaDeviceResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchEventsRequest {
+    /// e.g. "vm", "volume", "snapshot" (empty = all)
+    #[prost(string, tag = "1")]
+    pub resource_type: ::prost::alloc::string::String,
+    /// Only events for this resource (empty = all)
+    #[prost(string, tag = "2")]
+    pub resource_id: ::prost::alloc::string::String,
+    /// Only these event types (empty = all)
+    #[prost(enumeration = "EventType", repeated, tag = "3")]
+    pub types: ::prost::alloc::vec::Vec<i32>,
+    /// Recent events to replay first (0 = none)
+    #[prost(uint32, tag = "4")]
+    pub tail: u32,
+    /// Keep streaming new events until cancelled
+    #[prost(bool, tag = "5")]
+    pub follow: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Event {
+    #[prost(enumeration = "EventType", tag = "1")]
+    pub r#type: i32,
+    #[prost(string, tag = "2")]
+    pub resource_type: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub resource_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub resource_name: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub message: ::prost::alloc::string::String,
+    #[prost(int64, tag = "6")]
+    pub timestamp: i64,
+    /// New state for EVENT_TYPE_VM_STATE_CHANGED
+    #[prost(enumeration = "VmState", tag = "7")]
+    pub vm_state: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetHealthRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetHealthResponse {
+    #[prost(bool, tag = "1")]
+    pub healthy: bool,
+    #[prost(string, tag = "2")]
+    pub version: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub uptime_seconds: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetDaemonStatusRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetDaemonStatusResponse {
+    #[prost(int32, tag = "1")]
+    pub running_vms: i32,
+    #[prost(int32, tag = "2")]
+    pub total_vms: i32,
+    /// Committed to running and paused VMs
+    #[prost(int64, tag = "3")]
+    pub memory_used_bytes: i64,
+    #[prost(int64, tag = "4")]
+    pub disk_used_bytes: i64,
+    #[prost(string, tag = "5")]
+    pub store_path: ::prost::alloc::string::String,
+    #[prost(bool, tag = "6")]
+    pub qemu_available: bool,
+    #[prost(string, tag = "7")]
+    pub qemu_version: ::prost::alloc::string::String,
+    #[prost(bool, tag = "8")]
+    pub hvf_available: bool,
+    #[prost(int64, tag = "9")]
+    pub host_memory_bytes: i64,
+    #[prost(bool, tag = "10")]
+    pub swtpm_available: bool,
+    #[prost(bool, tag = "11")]
+    pub kvm_available: bool,
+    /// hvf, kvm, or tcg without hardware acceleration
+    #[prost(string, tag = "12")]
+    pub accelerator: ::prost::alloc::string::String,
+    #[prost(int32, tag = "13")]
+    pub host_cpus: i32,
+    /// vCPUs of running and paused VMs
+    #[prost(int32, tag = "14")]
+    pub committed_cpu_cores: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignPayloadRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    pub payload: ::prost::alloc::vec::Vec<u8>,
+    /// Logged by the daemon, e.g. "appliance-export"
+    #[prost(string, tag = "2")]
+    pub purpose: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignPayloadResponse {
+    /// Ed25519 signature over payload
+    #[prost(bytes = "vec", tag = "1")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+    /// Hex-encoded
+    #[prost(string, tag = "2")]
+    pub public_key: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub key_id: ::prost::alloc::string::String,
+    /// Where the key lives, e.g. "file"
+    #[prost(string, tag = "4")]
+    pub backend: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSigningKeyRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSigningKeyResponse {
+    #[prost(string, tag = "1")]
+    pub public_key: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub key_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub backend: ::prost::alloc::string::String,
+}
+/// One mutating call; string fields are empty when unknown
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuditEvent {
+    #[prost(int64, tag = "1")]
+    pub seq: i64,
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+    /// "daemon" or "web"
+    #[prost(string, tag = "3")]
+    pub source: ::prost::alloc::string::String,
+    /// e.g. "token:ci", "cert:<fingerprint>", "uid:501"
+    #[prost(string, tag = "4")]
+    pub identity: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub source_ip: ::prost::alloc::string::String,
+    /// gRPC method, or HTTP method and path
+    #[prost(string, tag = "6")]
+    pub operation: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub resource_kind: ::prost::alloc::string::String,
+    #[prost(string, tag = "8")]
+    pub resource_id: ::prost::alloc::string::String,
+    /// "ok", or the gRPC code or HTTP status of a failure
+    #[prost(string, tag = "9")]
+    pub status: ::prost::alloc::string::String,
+    /// SHA-256 of the resource's JSON before the call
+    #[prost(string, tag = "10")]
+    pub before_digest: ::prost::alloc::string::String,
+    #[prost(string, tag = "11")]
+    pub after_digest: ::prost::alloc::string::String,
+    /// Set when hash chaining is on
+    #[prost(string, tag = "12")]
+    pub prev_hash: ::prost::alloc::string::String,
+    #[prost(string, tag = "13")]
+    pub hash: ::prost::alloc::string::String,
+    /// Hex Ed25519 signature over hash
+    #[prost(string, tag = "14")]
+    pub signature: ::prost::alloc::string::String,
+    #[prost(string, tag = "15")]
+    pub key_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListAuditEventsRequest {
+    /// Unix seconds, 0 for no bound
+    #[prost(int64, tag = "1")]
+    pub since: i64,
+    #[prost(int64, tag = "2")]
+    pub until: i64,
+    #[prost(string, tag = "3")]
+    pub identity: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub resource_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub source: ::prost::alloc::string::String,
+    /// 0 for the daemon's default
+    #[prost(uint32, tag = "6")]
+    pub limit: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListAuditEventsResponse {
+    /// Newest first
+    #[prost(message, repeated, tag = "1")]
+    pub events: ::prost::alloc::vec::Vec<AuditEvent>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VerifyAuditLogRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VerifyAuditLogResponse {
+    #[prost(bool, tag = "1")]
+    pub valid: bool,
+    /// Chained entries that checked out
+    #[prost(uint64, tag = "2")]
+    pub verified: u64,
+    /// Entries written with hash chaining off
+    #[prost(uint64, tag = "3")]
+    pub unchained: u64,
+    /// First entry that breaks the chain, if not valid
+    #[prost(int64, tag = "4")]
+    pub broken_at_seq: i64,
+    #[prost(string, tag = "5")]
+    pub error: ::prost::alloc::string::String,
+    #[prost(bool, tag = "6")]
+    pub hash_chain_enabled: bool,
+}
+/// One version of a resource's spec
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProvenanceRecord {
+    #[prost(int64, tag = "1")]
+    pub seq: i64,
+    #[prost(string, tag = "2")]
+    pub resource_kind: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub resource_id: ::prost::alloc::string::String,
+    /// Position in the resource's chain, from 1
+    #[prost(int64, tag = "4")]
+    pub version: i64,
+    #[prost(int64, tag = "5")]
+    pub timestamp: i64,
+    /// "created", "updated" or "deleted"
+    #[prost(string, tag = "6")]
+    pub change: ::prost::alloc::string::String,
+    /// Empty for deletions
+    #[prost(string, tag = "7")]
+    pub spec_json: ::prost::alloc::string::String,
+    /// SHA-256 of spec_json
+    #[prost(string, tag = "8")]
+    pub spec_digest: ::prost::alloc::string::String,
+    /// Hash of the previous version, empty for the first
+    #[prost(string, tag = "9")]
+    pub parent_digest: ::prost::alloc::string::String,
+    #[prost(string, tag = "10")]
+    pub hash: ::prost::alloc::string::String,
+    /// Hex Ed25519 signature over hash
+    #[prost(string, tag = "11")]
+    pub signature: ::prost::alloc::string::String,
+    /// Signer
+    #[prost(string, tag = "12")]
+    pub key_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetProvenanceChainRequest {
+    #[prost(string, tag = "1")]
+    pub resource_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetProvenanceChainResponse {
+    /// Oldest first
+    #[prost(message, repeated, tag = "1")]
+    pub records: ::prost::alloc::vec::Vec<ProvenanceRecord>,
+    /// Chain links up and is signed by the daemon's key
+    #[prost(bool, tag = "2")]
+    pub valid: bool,
+    /// First version that doesn't fit, if not valid
+    #[prost(int64, tag = "3")]
+    pub broken_at_version: i64,
+    #[prost(string, tag = "4")]
+    pub error: ::prost::alloc::string::String,
+}
+/// A secret without its value
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Secret {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub created_at: i64,
+    #[prost(int64, tag = "3")]
+    pub updated_at: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateSecretRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+    /// Replace the value of an existing secret
+    #[prost(bool, tag = "3")]
+    pub overwrite: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateSecretResponse {
+    #[prost(message, optional, tag = "1")]
+    pub secret: ::core::option::Option<Secret>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSecretRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetSecretResponse {
+    #[prost(message, optional, tag = "1")]
+    pub secret: ::core::option::Option<Secret>,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSecretsRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListSecretsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub secrets: ::prost::alloc::vec::Vec<Secret>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteSecretRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteSecretResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InspectArtifactRequest {
+    /// Path to .zip or .tar.gz bundle
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InspectArtifactResponse {
+    #[prost(message, optional, tag = "1")]
+    pub report: ::core::option::Option<ArtifactInspectionReport>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArtifactInspectionReport {
+    #[prost(string, tag = "1")]
+    pub input_path: ::prost::alloc::string::String,
+    /// SHA256 verification of outer tarball
+    #[prost(bool, tag = "2")]
+    pub sha256_file_ok: bool,
+    #[prost(string, tag = "3")]
+    pub sha256_expected: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub sha256_actual: ::prost::alloc::string::String,
+    /// Extracted file inventory
+    #[prost(message, repeated, tag = "5")]
+    pub extracted_files: ::prost::alloc::vec::Vec<FileEntry>,
+    /// Manifest verification
+    #[prost(message, optional, tag = "6")]
+    pub manifest: ::core::option::Option<ManifestCheck>,
+    /// Attestation verification
+    #[prost(message, optional, tag = "7")]
+    pub attestations: ::core::option::Option<AttestationCheck>,
+    /// qcow2 image analysis
+    #[prost(message, repeated, tag = "8")]
+    pub qcow2_images: ::prost::alloc::vec::Vec<Qcow2Info>,
+    /// Signature status
+    #[prost(message, optional, tag = "9")]
+    pub signatures: ::core::option::Option<SignatureStatus>,
+    /// Issues found
+    #[prost(string, repeated, tag = "10")]
+    pub warnings: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "11")]
+    pub errors: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Overall status
+    #[prost(bool, tag = "12")]
+    pub passed: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FileEntry {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub size: i64,
+    #[prost(string, tag = "3")]
+    pub sha256: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ManifestCheck {
+    #[prost(bool, tag = "1")]
+    pub found: bool,
+    #[prost(bool, tag = "2")]
+    pub parsed_ok: bool,
+    #[prost(string, tag = "3")]
+    pub manifest_sha256: ::prost::alloc::string::String,
+    #[prost(int32, tag = "4")]
+    pub total_entries: i32,
+    #[prost(int32, tag = "5")]
+    pub verified_entries: i32,
+    #[prost(string, repeated, tag = "6")]
+    pub missing_files: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "7")]
+    pub mismatched_files: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "8")]
+    pub parse_errors: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttestationCheck {
+    #[prost(bool, tag = "1")]
+    pub integrity_attestation_found: bool,
+    #[prost(bool, tag = "2")]
+    pub integrity_attestation_ok: bool,
+    #[prost(string, tag = "3")]
+    pub manifest_sha256_in_attestation: ::prost::alloc::string::String,
+    #[prost(bool, tag = "4")]
+    pub manifest_sha256_matches: bool,
+    #[prost(string, repeated, tag = "5")]
+    pub malformed_json_files: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "6")]
+    pub truncation_detected: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Qcow2Info {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub valid_magic: bool,
+    #[prost(int32, tag = "3")]
+    pub version: i32,
+    #[prost(int64, tag = "4")]
+    pub virtual_size: i64,
+    #[prost(int32, tag = "5")]
+    pub cluster_bits: i32,
+    #[prost(int64, tag = "6")]
+    pub cluster_size: i64,
+    #[prost(string, tag = "7")]
+    pub backing_file: ::prost::alloc::string::String,
+    #[prost(bool, tag = "8")]
+    pub backing_file_exists: bool,
+    #[prost(string, repeated, tag = "9")]
+    pub issues: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignatureStatus {
+    #[prost(bool, tag = "1")]
+    pub signature_file_found: bool,
+    #[prost(bool, tag = "2")]
+    pub signature_info_found: bool,
+    /// "verified", "placeholder", "missing", "invalid"
+    #[prost(string, tag = "3")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub algorithm: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub signer: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "6")]
+    pub remediation_hints: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InterfaceConfig {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub network_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub ip_address: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub mac_address: ::prost::alloc::string::String,
+    #[prost(int32, tag = "5")]
+    pub mtu: i32,
+    #[prost(int32, tag = "6")]
+    pub vlan_id: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RouteConfig {
+    #[prost(string, tag = "1")]
+    pub destination: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub gateway: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub interface: ::prost::alloc::string::String,
+    #[prost(int32, tag = "4")]
+    pub metric: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FirewallRule {
+    /// input, output, forward
+    #[prost(string, tag = "1")]
+    pub chain: ::prost::alloc::string::String,
+    /// accept, drop, reject
+    #[prost(string, tag = "2")]
+    pub action: ::prost::alloc::string::String,
+    /// tcp, udp, icmp, any
+    #[prost(string, tag = "3")]
+    pub protocol: ::prost::alloc::string::String,
+    /// CIDR or any
+    #[prost(string, tag = "4")]
+    pub source: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub destination: ::prost::alloc::string::String,
+    #[prost(int32, tag = "6")]
+    pub port: i32,
+    #[prost(string, tag = "7")]
+    pub comment: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WireGuardPeerConfig {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub public_key: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub endpoint: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "4")]
+    pub allowed_ips: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(int32, tag = "5")]
+    pub persistent_keepalive: i32,
+    #[prost(string, tag = "6")]
+    pub preshared_key: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WireGuardConfig {
+    #[prost(string, tag = "1")]
+    pub interface: ::prost::alloc::string::String,
+    #[prost(int32, tag = "2")]
+    pub listen_port: i32,
+    #[prost(string, tag = "3")]
+    pub private_key: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub address: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub dns: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "6")]
+    pub peers: ::prost::alloc::vec::Vec<WireGuardPeerConfig>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplianceSpec {
+    #[prost(enumeration = "ApplianceType", tag = "1")]
+    pub r#type: i32,
+    #[prost(string, tag = "2")]
+    pub image: ::prost::alloc::string::String,
+    #[prost(int32, tag = "3")]
+    pub cpus: i32,
+    #[prost(int64, tag = "4")]
+    pub memory_mb: i64,
+    #[prost(message, repeated, tag = "5")]
+    pub interfaces: ::prost::alloc::vec::Vec<InterfaceConfig>,
+    #[prost(message, repeated, tag = "6")]
+    pub routes: ::prost::alloc::vec::Vec<RouteConfig>,
+    #[prost(message, repeated, tag = "7")]
+    pub firewall_rules: ::prost::alloc::vec::Vec<FirewallRule>,
+    #[prost(message, optional, tag = "8")]
+    pub wireguard: ::core::option::Option<WireGuardConfig>,
+    /// cloud-init
+    #[prost(string, tag = "9")]
+    pub user_data: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplianceStatus {
+    /// pending, running, stopped, error
+    #[prost(string, tag = "1")]
+    pub state: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub vm_id: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub assigned_ips: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(int64, tag = "4")]
+    pub uptime_seconds: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Appliance {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<ApplianceSpec>,
+    #[prost(message, optional, tag = "3")]
+    pub status: ::core::option::Option<ApplianceStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateApplianceRequest {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spec: ::core::option::Option<ApplianceSpec>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(bool, tag = "4")]
+    pub auto_start: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateApplianceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub appliance: ::core::option::Option<Appliance>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetApplianceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetApplianceResponse {
+    #[prost(message, optional, tag = "1")]
+    pub appliance: ::core::option::Option<Appliance>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteApplianceRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub delete_vm: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteApplianceResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListAppliancesRequest {
+    #[prost(enumeration = "ApplianceType", tag = "1")]
+    pub type_filter: i32,
+    #[prost(string, tag = "2")]
+    pub network_filter: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "3")]
+    pub label_selector: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListAppliancesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub appliances: ::prost::alloc::vec::Vec<Appliance>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreatePeeringRequest {
+    #[prost(string, tag = "1")]
+    pub from_appliance: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub to_appliance: ::prost::alloc::string::String,
+    /// wireguard, tailscale, vxlan, gre
+    #[prost(string, tag = "3")]
+    pub peering_type: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "4")]
+    pub allowed_ips: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(bool, tag = "5")]
+    pub use_tailscale: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreatePeeringResponse {
+    #[prost(string, tag = "1")]
+    pub peering_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub from_public_key: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub to_public_key: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ControlPlaneNode {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub hostname: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub tailscale_ip_v4: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub tailscale_ip_v6: ::prost::alloc::string::String,
+    #[prost(bool, tag = "6")]
+    pub online: bool,
+    #[prost(string, tag = "7")]
+    pub os: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "8")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(bool, tag = "9")]
+    pub exit_node: bool,
+    #[prost(string, tag = "10")]
+    pub relay: ::prost::alloc::string::String,
+    #[prost(int64, tag = "11")]
+    pub last_seen: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetControlPlaneStatusRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetControlPlaneStatusResponse {
+    #[prost(bool, tag = "1")]
+    pub connected: bool,
+    #[prost(message, optional, tag = "2")]
+    pub self_node: ::core::option::Option<ControlPlaneNode>,
+    #[prost(int32, tag = "3")]
+    pub online_peers: i32,
+    #[prost(int32, tag = "4")]
+    pub total_peers: i32,
+    #[prost(string, tag = "5")]
+    pub magic_dns_suffix: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListControlPlaneNodesRequest {
+    #[prost(string, tag = "1")]
+    pub tag_filter: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub include_offline: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListControlPlaneNodesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub nodes: ::prost::alloc::vec::Vec<ControlPlaneNode>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeployToNodeRequest {
+    #[prost(string, tag = "1")]
+    pub artifact_path: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub artifact_sha256: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub target_nodes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "4")]
+    pub terraform_config: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub wireguard_config: ::prost::alloc::string::String,
+    #[prost(bool, tag = "6")]
+    pub wait_for_completion: bool,
+    #[prost(int32, tag = "7")]
+    pub timeout_seconds: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeployToNodeResponse {
+    #[prost(string, tag = "1")]
+    pub deployment_id: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub node_statuses: ::prost::alloc::vec::Vec<NodeDeploymentStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeDeploymentStatus {
+    #[prost(string, tag = "1")]
+    pub node: ::prost::alloc::string::String,
+    /// pending, transferring, deploying, complete, failed
+    #[prost(string, tag = "2")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub error: ::prost::alloc::string::String,
+    #[prost(int64, tag = "4")]
+    pub started_at: i64,
+    #[prost(int64, tag = "5")]
+    pub completed_at: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PushArtifactRequest {
+    #[prost(string, tag = "1")]
+    pub local_path: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "2")]
+    pub target_nodes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "3")]
+    pub remote_path: ::prost::alloc::string::String,
+    #[prost(bool, tag = "4")]
+    pub verify_checksum: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PushArtifactResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub transfers: ::prost::alloc::vec::Vec<ArtifactTransferStatus>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArtifactTransferStatus {
+    #[prost(string, tag = "1")]
+    pub node: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    #[prost(string, tag = "3")]
+    pub error: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub sha256: ::prost::alloc::string::String,
+    #[prost(int64, tag = "5")]
+    pub bytes_transferred: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PipelineStage {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub image: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "3")]
+    pub commands: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "4")]
+    pub depends_on: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(int32, tag = "5")]
+    pub timeout_seconds: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PipelineTrigger {
+    /// push, tag, schedule, manual
+    #[prost(string, tag = "1")]
+    pub kind: ::prost::alloc::string::String,
+    /// branch/tag pattern or cron expression
+    #[prost(string, tag = "2")]
+    pub pattern: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ArtifactDefinition {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub path: ::prost::alloc::string::String,
+    /// qcow2, tarball, binary
+    #[prost(string, tag = "3")]
+    pub kind: ::prost::alloc::string::String,
+    #[prost(int32, tag = "4")]
+    pub retain_days: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Pipeline {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(string, tag = "2")]
+    pub description: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "3")]
+    pub stages: ::prost::alloc::vec::Vec<PipelineStage>,
+    #[prost(message, repeated, tag = "4")]
+    pub triggers: ::prost::alloc::vec::Vec<PipelineTrigger>,
+    #[prost(message, repeated, tag = "5")]
+    pub artifacts: ::prost::alloc::vec::Vec<ArtifactDefinition>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BuildProvenance {
+    #[prost(string, tag = "1")]
+    pub infrasim_version: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub infrasim_sha256: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub cargo_deps_hash: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub builder_identity: ::prost::alloc::string::String,
+    #[prost(int64, tag = "5")]
+    pub build_timestamp: i64,
+    #[prost(bool, tag = "6")]
+    pub reproducible: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StageResult {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// pending, running, success, failed, cancelled
+    #[prost(string, tag = "2")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub started_at: i64,
+    #[prost(int64, tag = "4")]
+    pub finished_at: i64,
+    #[prost(int32, tag = "5")]
+    pub exit_code: i32,
+    #[prost(int64, tag = "6")]
+    pub log_offset: i64,
+    #[prost(int64, tag = "7")]
+    pub log_length: i64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BuildArtifact {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub size_bytes: i64,
+    #[prost(string, tag = "4")]
+    pub sha256: ::prost::alloc::string::String,
+    #[prost(bool, tag = "5")]
+    pub signed: bool,
+    #[prost(string, tag = "6")]
+    pub download_url: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PipelineRun {
+    #[prost(message, optional, tag = "1")]
+    pub meta: ::core::option::Option<ResourceMeta>,
+    #[prost(string, tag = "2")]
+    pub pipeline_name: ::prost::alloc::string::String,
+    /// pending, running, success, failed, cancelled
+    #[prost(string, tag = "3")]
+    pub status: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub trigger_type: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub commit_sha: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub tag: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "7")]
+    pub stages: ::prost::alloc::vec::Vec<StageResult>,
+    #[prost(message, repeated, tag = "8")]
+    pub artifacts: ::prost::alloc::vec::Vec<BuildArtifact>,
+    #[prost(message, optional, tag = "9")]
+    pub provenance: ::core::option::Option<BuildProvenance>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TriggerPipelineRequest {
+    #[prost(string, tag = "1")]
+    pub pipeline_name: ::prost::alloc::string::String,
+    /// git ref (branch, tag, commit)
+    #[prost(string, tag = "2")]
+    pub r#ref: ::prost::alloc::string::String,
+    /// tag to create on success
+    #[prost(string, tag = "3")]
+    pub tag: ::prost::alloc::string::String,
+    #[prost(map = "string, string", tag = "4")]
+    pub env: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(map = "string, string", tag = "5")]
+    pub parameters: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(string, tag = "6")]
+    pub target_node: ::prost::alloc::string::String,
+    #[prost(bool, tag = "7")]
+    pub wait: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TriggerPipelineResponse {
+    #[prost(message, optional, tag = "1")]
+    pub run: ::core::option::Option<PipelineRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineRunRequest {
+    #[prost(string, tag = "1")]
+    pub id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineRunResponse {
+    #[prost(message, optional, tag = "1")]
+    pub run: ::core::option::Option<PipelineRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPipelineRunsRequest {
+    #[prost(string, tag = "1")]
+    pub pipeline_name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub status_filter: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub node_filter: ::prost::alloc::string::String,
+    #[prost(int32, tag = "4")]
+    pub limit: i32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListPipelineRunsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub runs: ::prost::alloc::vec::Vec<PipelineRun>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineLogsRequest {
+    #[prost(string, tag = "1")]
+    pub run_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub stage: ::prost::alloc::string::String,
+    #[prost(int64, tag = "3")]
+    pub offset: i64,
+    #[prost(int32, tag = "4")]
+    pub lines: i32,
+    #[prost(bool, tag = "5")]
+    pub follow: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPipelineLogsResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(int64, tag = "2")]
+    pub offset: i64,
+    #[prost(bool, tag = "3")]
+    pub more_available: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelPipelineRequest {
+    #[prost(string, tag = "1")]
+    pub run_id: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub force: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelPipelineResponse {
+    #[prost(bool, tag = "1")]
+    pub cancelled: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetReplicationStatusRequest {
+    /// List the digests of every CAS object
+    #[prost(bool, tag = "1")]
+    pub include_objects: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReplicationStatus {
+    /// "primary" or "standby"
+    #[prost(string, tag = "1")]
+    pub role: ::prost::alloc::string::String,
+    /// Changes whenever the daemon restarts
+    #[prost(string, tag = "2")]
+    pub epoch: ::prost::alloc::string::String,
+    /// Grows with every write to the state DB
+    #[prost(uint64, tag = "3")]
+    pub position: u64,
+    #[prost(bool, tag = "4")]
+    pub allow_standbys: bool,
+    #[prost(string, repeated, tag = "5")]
+    pub cas_digests: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Standby only
+    ///
+    /// Address replicated from
+    #[prost(string, tag = "6")]
+    pub primary: ::prost::alloc::string::String,
+    /// Primary epoch and position of the last copy
+    #[prost(string, tag = "7")]
+    pub synced_epoch: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "8")]
+    pub synced_position: u64,
+    /// Unix seconds of the last successful sync
+    #[prost(int64, tag = "9")]
+    pub last_sync_at: i64,
+    /// Why the last sync failed, empty if it didn't
+    #[prost(string, tag = "10")]
+    pub last_error: ::prost::alloc::string::String,
+    /// CAS objects copied since the daemon started
+    #[prost(uint64, tag = "11")]
+    pub synced_objects: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StreamStateSnapshotRequest {}
+/// Chunk of a consistent copy of the state DB
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StateSnapshotChunk {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+    /// Set on the first chunk: the position the copy was taken at
+    #[prost(string, tag = "2")]
+    pub epoch: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub position: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadCasObjectRequest {
+    #[prost(string, tag = "1")]
+    pub digest: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadCasObjectResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PromoteDaemonRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PromoteDaemonResponse {
+    /// VMs that ran on the old primary, now marked stopped
+    #[prost(string, repeated, tag = "1")]
+    pub stopped_vm_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum VmState {
+    Unspecified = 0,
+    Pending = 1,
+    Running = 2,
+    Stopped = 3,
+    Paused = 4,
+    Error = 5,
+    /// RAM saved to disk, QEMU not running
+    Suspended = 6,
+}
+impl VmState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            VmState::Unspecified => "VM_STATE_UNSPECIFIED",
+            VmState::Pending => "VM_STATE_PENDING",
+            VmState::Running => "VM_STATE_RUNNING",
+            VmState::Stopped => "VM_STATE_STOPPED",
+            VmState::Paused => "VM_STATE_PAUSED",
+            VmState::Error => "VM_STATE_ERROR",
+            VmState::Suspended => "VM_STATE_SUSPENDED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "VM_STATE_UNSPECIFIED" => Some(Self::Unspecified),
+            "VM_STATE_PENDING" => Some(Self::Pending),
+            "VM_STATE_RUNNING" => Some(Self::Running),
+            "VM_STATE_STOPPED" => Some(Self::Stopped),
+            "VM_STATE_PAUSED" => Some(Self::Paused),
+            "VM_STATE_ERROR" => Some(Self::Error),
+            "VM_STATE_SUSPENDED" => Some(Self::Suspended),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum NetworkMode {
+    Unspecified = 0,
+    User = 1,
+    VmnetShared = 2,
+    VmnetBridged = 3,
+}
+impl NetworkMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            NetworkMode::Unspecified => "NETWORK_MODE_UNSPECIFIED",
+            NetworkMode::User => "NETWORK_MODE_USER",
+            NetworkMode::VmnetShared => "NETWORK_MODE_VMNET_SHARED",
+            NetworkMode::VmnetBridged => "NETWORK_MODE_VMNET_BRIDGED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "NETWORK_MODE_UNSPECIFIED" => Some(Self::Unspecified),
+            "NETWORK_MODE_USER" => Some(Self::User),
+            "NETWORK_MODE_VMNET_SHARED" => Some(Self::VmnetShared),
+            "NETWORK_MODE_VMNET_BRIDGED" => Some(Self::VmnetBridged),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum VolumeKind {
+    Unspecified = 0,
+    Disk = 1,
+    Weights = 2,
+    /// Raw host block device / partition
+    Device = 3,
+}
+impl VolumeKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            VolumeKind::Unspecified => "VOLUME_KIND_UNSPECIFIED",
+            VolumeKind::Disk => "VOLUME_KIND_DISK",
+            VolumeKind::Weights => "VOLUME_KIND_WEIGHTS",
+            VolumeKind::Device => "VOLUME_KIND_DEVICE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "VOLUME_KIND_UNSPECIFIED" => Some(Self::Unspecified),
+            "VOLUME_KIND_DISK" => Some(Self::Disk),
+            "VOLUME_KIND_WEIGHTS" => Some(Self::Weights),
+            "VOLUME_KIND_DEVICE" => Some(Self::Device),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SharedFolderCache {
+    Auto = 0,
+    Always = 1,
+    Never = 2,
+}
+impl SharedFolderCache {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SharedFolderCache::Auto => "SHARED_FOLDER_CACHE_AUTO",
+            SharedFolderCache::Always => "SHARED_FOLDER_CACHE_ALWAYS",
+            SharedFolderCache::Never => "SHARED_FOLDER_CACHE_NEVER",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SHARED_FOLDER_CACHE_AUTO" => Some(Self::Auto),
+            "SHARED_FOLDER_CACHE_ALWAYS" => Some(Self::Always),
+            "SHARED_FOLDER_CACHE_NEVER" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SharedFolderProtocol {
+    /// virtiofs where the host has virtiofsd, else 9p
+    Auto = 0,
+    Virtiofs = 1,
+    Ninep = 2,
+}
+impl SharedFolderProtocol {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SharedFolderProtocol::Auto => "SHARED_FOLDER_PROTOCOL_AUTO",
+            SharedFolderProtocol::Virtiofs => "SHARED_FOLDER_PROTOCOL_VIRTIOFS",
+            SharedFolderProtocol::Ninep => "SHARED_FOLDER_PROTOCOL_NINEP",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SHARED_FOLDER_PROTOCOL_AUTO" => Some(Self::Auto),
+            "SHARED_FOLDER_PROTOCOL_VIRTIOFS" => Some(Self::Virtiofs),
+            "SHARED_FOLDER_PROTOCOL_NINEP" => Some(Self::Ninep),
+            _ => None,
+        }
+    }
+}
+/// How StopVM brings a VM down
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum StopMode {
+    /// ACPI powerdown, or hard when `force` is set
+    Unspecified = 0,
+    /// ACPI powerdown, escalating to SIGTERM and SIGKILL after the timeout
+    Acpi = 1,
+    /// Terminate QEMU without asking the guest
+    Hard = 2,
+}
+impl StopMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            StopMode::Unspecified => "STOP_MODE_UNSPECIFIED",
+            StopMode::Acpi => "STOP_MODE_ACPI",
+            StopMode::Hard => "STOP_MODE_HARD",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "STOP_MODE_UNSPECIFIED" => Some(Self::Unspecified),
+            "STOP_MODE_ACPI" => Some(Self::Acpi),
+            "STOP_MODE_HARD" => Some(Self::Hard),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum VmLogSource {
+    Unspecified = 0,
+    /// QEMU serial console output
+    Console = 1,
+    /// Daemon lifecycle event
+    Event = 2,
+}
+impl VmLogSource {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            VmLogSource::Unspecified => "VM_LOG_SOURCE_UNSPECIFIED",
+            VmLogSource::Console => "VM_LOG_SOURCE_CONSOLE",
+            VmLogSource::Event => "VM_LOG_SOURCE_EVENT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "VM_LOG_SOURCE_UNSPECIFIED" => Some(Self::Unspecified),
+            "VM_LOG_SOURCE_CONSOLE" => Some(Self::Console),
+            "VM_LOG_SOURCE_EVENT" => Some(Self::Event),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ExportFormat {
+    Unspecified = 0,
+    /// OVF descriptor + streamOptimized VMDK disks
+    Ova = 1,
+    /// Raw disk images
+    Raw = 2,
+    /// Compressed qcow2 disk images
+    Qcow2 = 3,
+}
+impl ExportFormat {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Unspecified => "EXPORT_FORMAT_UNSPECIFIED",
+            ExportFormat::Ova => "EXPORT_FORMAT_OVA",
+            ExportFormat::Raw => "EXPORT_FORMAT_RAW",
+            ExportFormat::Qcow2 => "EXPORT_FORMAT_QCOW2",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "EXPORT_FORMAT_UNSPECIFIED" => Some(Self::Unspecified),
+            "EXPORT_FORMAT_OVA" => Some(Self::Ova),
+            "EXPORT_FORMAT_RAW" => Some(Self::Raw),
+            "EXPORT_FORMAT_QCOW2" => Some(Self::Qcow2),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CaptureState {
+    Unspecified = 0,
+    Running = 1,
+    Stopped = 2,
+    Failed = 3,
+}
+impl CaptureState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            CaptureState::Unspecified => "CAPTURE_STATE_UNSPECIFIED",
+            CaptureState::Running => "CAPTURE_STATE_RUNNING",
+            CaptureState::Stopped => "CAPTURE_STATE_STOPPED",
+            CaptureState::Failed => "CAPTURE_STATE_FAILED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CAPTURE_STATE_UNSPECIFIED" => Some(Self::Unspecified),
+            "CAPTURE_STATE_RUNNING" => Some(Self::Running),
+            "CAPTURE_STATE_STOPPED" => Some(Self::Stopped),
+            "CAPTURE_STATE_FAILED" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum PortProtocol {
+    Unspecified = 0,
+    Tcp = 1,
+    Udp = 2,
+}
+impl PortProtocol {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            PortProtocol::Unspecified => "PORT_PROTOCOL_UNSPECIFIED",
+            PortProtocol::Tcp => "PORT_PROTOCOL_TCP",
+            PortProtocol::Udp => "PORT_PROTOCOL_UDP",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "PORT_PROTOCOL_UNSPECIFIED" => Some(Self::Unspecified),
+            "PORT_PROTOCOL_TCP" => Some(Self::Tcp),
+            "PORT_PROTOCOL_UDP" => Some(Self::Udp),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FirewallAction {
+    Unspecified = 0,
+    Allow = 1,
+    Deny = 2,
+}
+impl FirewallAction {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            FirewallAction::Unspecified => "FIREWALL_ACTION_UNSPECIFIED",
+            FirewallAction::Allow => "FIREWALL_ACTION_ALLOW",
+            FirewallAction::Deny => "FIREWALL_ACTION_DENY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "FIREWALL_ACTION_UNSPECIFIED" => Some(Self::Unspecified),
+            "FIREWALL_ACTION_ALLOW" => Some(Self::Allow),
+            "FIREWALL_ACTION_DENY" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum RuleProtocol {
+    Any = 0,
+    Tcp = 1,
+    Udp = 2,
+    Icmp = 3,
+}
+impl RuleProtocol {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            RuleProtocol::Any => "RULE_PROTOCOL_ANY",
+            RuleProtocol::Tcp => "RULE_PROTOCOL_TCP",
+            RuleProtocol::Udp => "RULE_PROTOCOL_UDP",
+            RuleProtocol::Icmp => "RULE_PROTOCOL_ICMP",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "RULE_PROTOCOL_ANY" => Some(Self::Any),
+            "RULE_PROTOCOL_TCP" => Some(Self::Tcp),
+            "RULE_PROTOCOL_UDP" => Some(Self::Udp),
+            "RULE_PROTOCOL_ICMP" => Some(Self::Icmp),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum DeviceBus {
+    Unspecified = 0,
+    Usb = 1,
+    Pci = 2,
+}
+impl DeviceBus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            DeviceBus::Unspecified => "DEVICE_BUS_UNSPECIFIED",
+            DeviceBus::Usb => "DEVICE_BUS_USB",
+            DeviceBus::Pci => "DEVICE_BUS_PCI",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "DEVICE_BUS_UNSPECIFIED" => Some(Self::Unspecified),
+            "DEVICE_BUS_USB" => Some(Self::Usb),
+            "DEVICE_BUS_PCI" => Some(Self::Pci),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LatencyDistribution {
+    Uniform = 0,
+    Normal = 1,
+    Pareto = 2,
+}
+impl LatencyDistribution {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            LatencyDistribution::Uniform => "LATENCY_DISTRIBUTION_UNIFORM",
+            LatencyDistribution::Normal => "LATENCY_DISTRIBUTION_NORMAL",
+            LatencyDistribution::Pareto => "LATENCY_DISTRIBUTION_PARETO",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "LATENCY_DISTRIBUTION_UNIFORM" => Some(Self::Uniform),
+            "LATENCY_DISTRIBUTION_NORMAL" => Some(Self::Normal),
+            "LATENCY_DISTRIBUTION_PARETO" => Some(Self::Pareto),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ScenarioRunState {
+    Pending = 0,
+    Running = 1,
+    Passed = 2,
+    Failed = 3,
+    Cancelled = 4,
+}
+impl ScenarioRunState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ScenarioRunState::Pending => "SCENARIO_RUN_STATE_PENDING",
+            ScenarioRunState::Running => "SCENARIO_RUN_STATE_RUNNING",
+            ScenarioRunState::Passed => "SCENARIO_RUN_STATE_PASSED",
+            ScenarioRunState::Failed => "SCENARIO_RUN_STATE_FAILED",
+            ScenarioRunState::Cancelled => "SCENARIO_RUN_STATE_CANCELLED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SCENARIO_RUN_STATE_PENDING" => Some(Self::Pending),
+            "SCENARIO_RUN_STATE_RUNNING" => Some(Self::Running),
+            "SCENARIO_RUN_STATE_PASSED" => Some(Self::Passed),
+            "SCENARIO_RUN_STATE_FAILED" => Some(Self::Failed),
+            "SCENARIO_RUN_STATE_CANCELLED" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ScenarioStepOutcome {
+    Pending = 0,
+    Running = 1,
+    Passed = 2,
+    Failed = 3,
+    Skipped = 4,
+}
+impl ScenarioStepOutcome {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ScenarioStepOutcome::Pending => "SCENARIO_STEP_OUTCOME_PENDING",
+            ScenarioStepOutcome::Running => "SCENARIO_STEP_OUTCOME_RUNNING",
+            ScenarioStepOutcome::Passed => "SCENARIO_STEP_OUTCOME_PASSED",
+            ScenarioStepOutcome::Failed => "SCENARIO_STEP_OUTCOME_FAILED",
+            ScenarioStepOutcome::Skipped => "SCENARIO_STEP_OUTCOME_SKIPPED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SCENARIO_STEP_OUTCOME_PENDING" => Some(Self::Pending),
+            "SCENARIO_STEP_OUTCOME_RUNNING" => Some(Self::Running),
+            "SCENARIO_STEP_OUTCOME_PASSED" => Some(Self::Passed),
+            "SCENARIO_STEP_OUTCOME_FAILED" => Some(Self::Failed),
+            "SCENARIO_STEP_OUTCOME_SKIPPED" => Some(Self::Skipped),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum DownloadState {
+    Unspecified = 0,
+    Pending = 1,
+    Downloading = 2,
+    Verifying = 3,
+    Complete = 4,
+    Failed = 5,
+}
+impl DownloadState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            DownloadState::Unspecified => "DOWNLOAD_STATE_UNSPECIFIED",
+            DownloadState::Pending => "DOWNLOAD_STATE_PENDING",
+            DownloadState::Downloading => "DOWNLOAD_STATE_DOWNLOADING",
+            DownloadState::Verifying => "DOWNLOAD_STATE_VERIFYING",
+            DownloadState::Complete => "DOWNLOAD_STATE_COMPLETE",
+            DownloadState::Failed => "DOWNLOAD_STATE_FAILED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "DOWNLOAD_STATE_UNSPECIFIED" => Some(Self::Unspecified),
+            "DOWNLOAD_STATE_PENDING" => Some(Self::Pending),
+            "DOWNLOAD_STATE_DOWNLOADING" => Some(Self::Downloading),
+            "DOWNLOAD_STATE_VERIFYING" => Some(Self::Verifying),
+            "DOWNLOAD_STATE_COMPLETE" => Some(Self::Complete),
+            "DOWNLOAD_STATE_FAILED" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FileChange {
+    Unspecified = 0,
+    Added = 1,
+    Removed = 2,
+    Modified = 3,
+}
+impl FileChange {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            FileChange::Unspecified => "FILE_CHANGE_UNSPECIFIED",
+            FileChange::Added => "FILE_CHANGE_ADDED",
+            FileChange::Removed => "FILE_CHANGE_REMOVED",
+            FileChange::Modified => "FILE_CHANGE_MODIFIED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "FILE_CHANGE_UNSPECIFIED" => Some(Self::Unspecified),
+            "FILE_CHANGE_ADDED" => Some(Self::Added),
+            "FILE_CHANGE_REMOVED" => Some(Self::Removed),
+            "FILE_CHANGE_MODIFIED" => Some(Self::Modified),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum BackupKind {
+    Unspecified = 0,
+    Full = 1,
+    Incremental = 2,
+}
+impl BackupKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            BackupKind::Unspecified => "BACKUP_KIND_UNSPECIFIED",
+            BackupKind::Full => "BACKUP_KIND_FULL",
+            BackupKind::Incremental => "BACKUP_KIND_INCREMENTAL",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "BACKUP_KIND_UNSPECIFIED" => Some(Self::Unspecified),
+            "BACKUP_KIND_FULL" => Some(Self::Full),
+            "BACKUP_KIND_INCREMENTAL" => Some(Self::Incremental),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum JobKind {
+    Unspecified = 0,
+    PushVolume = 1,
+    PullVolume = 2,
+    FlattenVolume = 3,
+    PushAppliance = 4,
+    PullAppliance = 5,
+    CreateBackup = 6,
+    RestoreBackup = 7,
+    /// Run an Ansible playbook or SSH script against a booted guest
+    Provision = 8,
+}
+impl JobKind {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            JobKind::Unspecified => "JOB_KIND_UNSPECIFIED",
+            JobKind::PushVolume => "JOB_KIND_PUSH_VOLUME",
+            JobKind::PullVolume => "JOB_KIND_PULL_VOLUME",
+            JobKind::FlattenVolume => "JOB_KIND_FLATTEN_VOLUME",
+            JobKind::PushAppliance => "JOB_KIND_PUSH_APPLIANCE",
+            JobKind::PullAppliance => "JOB_KIND_PULL_APPLIANCE",
+            JobKind::CreateBackup => "JOB_KIND_CREATE_BACKUP",
+            JobKind::RestoreBackup => "JOB_KIND_RESTORE_BACKUP",
+            JobKind::Provision => "JOB_KIND_PROVISION",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "JOB_KIND_UNSPECIFIED" => Some(Self::Unspecified),
+            "JOB_KIND_PUSH_VOLUME" => Some(Self::PushVolume),
+            "JOB_KIND_PULL_VOLUME" => Some(Self::PullVolume),
+            "JOB_KIND_FLATTEN_VOLUME" => Some(Self::FlattenVolume),
+            "JOB_KIND_PUSH_APPLIANCE" => Some(Self::PushAppliance),
+            "JOB_KIND_PULL_APPLIANCE" => Some(Self::PullAppliance),
+            "JOB_KIND_CREATE_BACKUP" => Some(Self::CreateBackup),
+            "JOB_KIND_RESTORE_BACKUP" => Some(Self::RestoreBackup),
+            "JOB_KIND_PROVISION" => Some(Self::Provision),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum JobState {
+    Unspecified = 0,
+    Pending = 1,
+    Running = 2,
+    Succeeded = 3,
+    Failed = 4,
+    Cancelled = 5,
+}
+impl JobState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            JobState::Unspecified => "JOB_STATE_UNSPECIFIED",
+            JobState::Pending => "JOB_STATE_PENDING",
+            JobState::Running => "JOB_STATE_RUNNING",
+            JobState::Succeeded => "JOB_STATE_SUCCEEDED",
+            JobState::Failed => "JOB_STATE_FAILED",
+            JobState::Cancelled => "JOB_STATE_CANCELLED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "JOB_STATE_UNSPECIFIED" => Some(Self::Unspecified),
+            "JOB_STATE_PENDING" => Some(Self::Pending),
+            "JOB_STATE_RUNNING" => Some(Self::Running),
+            "JOB_STATE_SUCCEEDED" => Some(Self::Succeeded),
+            "JOB_STATE_FAILED" => Some(Self::Failed),
+            "JOB_STATE_CANCELLED" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum EventType {
+    Unspecified = 0,
+    Created = 1,
+    Updated = 2,
+    Deleted = 3,
+    VmStateChanged = 4,
+    ReconcileError = 5,
+}
+impl EventType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            EventType::Unspecified => "EVENT_TYPE_UNSPECIFIED",
+            EventType::Created => "EVENT_TYPE_CREATED",
+            EventType::Updated => "EVENT_TYPE_UPDATED",
+            EventType::Deleted => "EVENT_TYPE_DELETED",
+            EventType::VmStateChanged => "EVENT_TYPE_VM_STATE_CHANGED",
+            EventType::ReconcileError => "EVENT_TYPE_RECONCILE_ERROR",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "EVENT_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
+            "EVENT_TYPE_CREATED" => Some(Self::Created),
+            "EVENT_TYPE_UPDATED" => Some(Self::Updated),
+            "EVENT_TYPE_DELETED" => Some(Self::Deleted),
+            "EVENT_TYPE_VM_STATE_CHANGED" => Some(Self::VmStateChanged),
+            "EVENT_TYPE_RECONCILE_ERROR" => Some(Self::ReconcileError),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ApplianceType {
+    Unspecified = 0,
+    Router = 1,
+    Firewall = 2,
+    Vpn = 3,
+    LoadBalancer = 4,
+    Sensor = 5,
+    Dns = 6,
+    Dhcp = 7,
+    Nat = 8,
+    Custom = 9,
+}
+impl ApplianceType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ApplianceType::Unspecified => "APPLIANCE_TYPE_UNSPECIFIED",
+            ApplianceType::Router => "APPLIANCE_TYPE_ROUTER",
+            ApplianceType::Firewall => "APPLIANCE_TYPE_FIREWALL",
+            ApplianceType::Vpn => "APPLIANCE_TYPE_VPN",
+            ApplianceType::LoadBalancer => "APPLIANCE_TYPE_LOAD_BALANCER",
+            ApplianceType::Sensor => "APPLIANCE_TYPE_SENSOR",
+            ApplianceType::Dns => "APPLIANCE_TYPE_DNS",
+            ApplianceType::Dhcp => "APPLIANCE_TYPE_DHCP",
+            ApplianceType::Nat => "APPLIANCE_TYPE_NAT",
+            ApplianceType::Custom => "APPLIANCE_TYPE_CUSTOM",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "APPLIANCE_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
+            "APPLIANCE_TYPE_ROUTER" => Some(Self::Router),
+            "APPLIANCE_TYPE_FIREWALL" => Some(Self::Firewall),
+            "APPLIANCE_TYPE_VPN" => Some(Self::Vpn),
+            "APPLIANCE_TYPE_LOAD_BALANCER" => Some(Self::LoadBalancer),
+            "APPLIANCE_TYPE_SENSOR" => Some(Self::Sensor),
+            "APPLIANCE_TYPE_DNS" => Some(Self::Dns),
+            "APPLIANCE_TYPE_DHCP" => Some(Self::Dhcp),
+            "APPLIANCE_TYPE_NAT" => Some(Self::Nat),
+            "APPLIANCE_TYPE_CUSTOM" => Some(Self::Custom),
+            _ => None,
+        }
+    }
+}
+/// Generated client implementations.
+pub mod infra_sim_daemon_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct InfraSimDaemonClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl InfraSimDaemonClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> InfraSimDaemonClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InfraSimDaemonClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            InfraSimDaemonClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// VM lifecycle
+        pub async fn create_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetVmRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetVmResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn update_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/UpdateVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "UpdateVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn update_vm_labels(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateVmLabelsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateVmLabelsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/UpdateVMLabels",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "UpdateVMLabels"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_v_ms(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListVMsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListVMsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListVMs",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListVMs"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn start_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StartVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StartVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/StartVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "StartVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn stop_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StopVmRequest>,
+        ) -> std::result::Result<tonic::Response<super::StopVmResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/StopVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "StopVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn pause_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PauseVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PauseVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/PauseVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "PauseVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn resume_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ResumeVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ResumeVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ResumeVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ResumeVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn suspend_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SuspendVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SuspendVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/SuspendVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "SuspendVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn attach_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AttachDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::AttachDeviceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/AttachDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "AttachDevice"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn stream_vm_logs(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StreamVmLogsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::VmLogEntry>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/StreamVMLogs",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "StreamVMLogs"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn get_vm_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetVmStatsRequest>,
+        ) -> std::result::Result<tonic::Response<super::VmStats>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetVMStats",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetVMStats"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn watch_vm_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchVmStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::VmStats>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/WatchVMStats",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "WatchVMStats"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn export_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ExportVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ExportVmResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ExportVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ExportVM"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn import_vm(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::ImportVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ImportVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ImportVM",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ImportVM"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        pub async fn push_appliance(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PushApplianceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PushApplianceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/PushAppliance",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "PushAppliance"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn pull_appliance(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PullApplianceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PullApplianceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/PullAppliance",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "PullAppliance"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn attach_serial(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::SerialInput>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::SerialOutput>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/AttachSerial",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "AttachSerial"));
+            self.inner.streaming(req, path, codec).await
+        }
+        pub async fn copy_to_guest(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::CopyToGuestRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<super::CopyToGuestResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CopyToGuest",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CopyToGuest"));
+            self.inner.client_streaming(req, path, codec).await
+        }
+        pub async fn mark_vm_as_template(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MarkVmAsTemplateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MarkVmAsTemplateResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/MarkVMAsTemplate",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "MarkVMAsTemplate"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn clone_vm(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CloneVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CloneVmResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CloneVM",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CloneVM"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Raw QMP command to a running VM's monitor; off unless security.allow_qmp_passthrough
+        pub async fn execute_qmp(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ExecuteQmpRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ExecuteQmpResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ExecuteQMP",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ExecuteQMP"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Network management
+        pub async fn create_network(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateNetworkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateNetworkResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateNetwork",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateNetwork"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_network(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetNetworkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetNetworkResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetNetwork",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetNetwork"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_network(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteNetworkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteNetworkResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteNetwork",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteNetwork"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_networks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListNetworksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListNetworksResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListNetworks",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListNetworks"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Packet capture on a network
+        pub async fn start_network_capture(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StartNetworkCaptureRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StartNetworkCaptureResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/StartNetworkCapture",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "StartNetworkCapture"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn stop_network_capture(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StopNetworkCaptureRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StopNetworkCaptureResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/StopNetworkCapture",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "StopNetworkCapture"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn read_network_capture(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReadNetworkCaptureRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ReadNetworkCaptureResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ReadNetworkCapture",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ReadNetworkCapture"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Port forwarding (user-mode networks)
+        pub async fn create_port_forward(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreatePortForwardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreatePortForwardResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreatePortForward",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreatePortForward"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_port_forward(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetPortForwardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetPortForwardResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetPortForward",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetPortForward"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_port_forward(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeletePortForwardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeletePortForwardResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeletePortForward",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeletePortForward"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_port_forwards(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListPortForwardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListPortForwardsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListPortForwards",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListPortForwards"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Virtual routers between networks
+        pub async fn create_router(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateRouterResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateRouter",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateRouter"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_router(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetRouterResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetRouter",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetRouter"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn update_router(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateRouterResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/UpdateRouter",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "UpdateRouter"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_router(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteRouterResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteRouter",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteRouter"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_routers(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListRoutersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListRoutersResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListRouters",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListRouters"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Host device passthrough (USB, vfio-pci)
+        pub async fn create_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateDeviceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateDevice"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetDeviceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetDevice"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteDeviceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteDevice"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_devices(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListDevicesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListDevicesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListDevices",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListDevices"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// QoS profiles
+        pub async fn create_qo_s_profile(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateQoSProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateQoSProfileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateQoSProfile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateQoSProfile"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_qo_s_profile(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetQoSProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetQoSProfileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetQoSProfile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetQoSProfile"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_qo_s_profile(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteQoSProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteQoSProfileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteQoSProfile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteQoSProfile"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_qo_s_profiles(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListQoSProfilesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListQoSProfilesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListQoSProfiles",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListQoSProfiles"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Chaos profiles: expiring fault injection on networks and VM NICs
+        pub async fn apply_chaos_profile(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ApplyChaosProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ApplyChaosProfileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ApplyChaosProfile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ApplyChaosProfile"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_chaos_profile(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetChaosProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetChaosProfileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetChaosProfile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetChaosProfile"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_chaos_profile(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteChaosProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteChaosProfileResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteChaosProfile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteChaosProfile"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_chaos_profiles(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListChaosProfilesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListChaosProfilesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListChaosProfiles",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListChaosProfiles"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Scenario runs: scripted chaos experiments and their reports
+        pub async fn run_scenario(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RunScenarioRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RunScenarioResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/RunScenario",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "RunScenario"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_scenario_run(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetScenarioRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetScenarioRunResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetScenarioRun",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetScenarioRun"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_scenario_runs(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListScenarioRunsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListScenarioRunsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListScenarioRuns",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListScenarioRuns"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn cancel_scenario_run(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelScenarioRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelScenarioRunResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CancelScenarioRun",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CancelScenarioRun"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_scenario_run(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteScenarioRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteScenarioRunResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteScenarioRun",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteScenarioRun"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn compare_scenario_runs(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CompareScenarioRunsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CompareScenarioRunsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CompareScenarioRuns",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CompareScenarioRuns"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Volume management
+        pub async fn create_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateVolumeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateVolume",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateVolume"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetVolumeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetVolume",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetVolume"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteVolumeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteVolume",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteVolume"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_volumes(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListVolumesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListVolumesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListVolumes",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListVolumes"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn watch_volume_download(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchVolumeDownloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::DownloadStatus>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/WatchVolumeDownload",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "WatchVolumeDownload"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn get_volume_chain(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetVolumeChainRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetVolumeChainResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetVolumeChain",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetVolumeChain"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn flatten_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FlattenVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::FlattenProgress>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/FlattenVolume",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "FlattenVolume"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn resize_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ResizeVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ResizeVolumeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ResizeVolume",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ResizeVolume"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn push_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PushVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PushVolumeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/PushVolume",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "PushVolume"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn pull_volume(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PullVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PullVolumeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/PullVolume",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "PullVolume"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Console management
+        pub async fn create_console(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateConsoleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateConsoleResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateConsole",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateConsole"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_console(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetConsoleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetConsoleResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetConsole",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetConsole"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_console(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteConsoleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteConsoleResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteConsole",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteConsole"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Snapshot management
+        pub async fn create_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateSnapshotResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateSnapshot",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateSnapshot"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSnapshotResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetSnapshot",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetSnapshot"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteSnapshotResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteSnapshot",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteSnapshot"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_snapshots(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListSnapshotsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSnapshotsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListSnapshots",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListSnapshots"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn restore_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreSnapshotResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/RestoreSnapshot",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "RestoreSnapshot"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn revert_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RevertSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RevertSnapshotResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/RevertSnapshot",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "RevertSnapshot"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn branch_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BranchSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BranchSnapshotResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/BranchSnapshot",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "BranchSnapshot"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_snapshot_files(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListSnapshotFilesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSnapshotFilesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListSnapshotFiles",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListSnapshotFiles"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn read_snapshot_file(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReadSnapshotFileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ReadSnapshotFileResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ReadSnapshotFile",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ReadSnapshotFile"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn diff_snapshots(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DiffSnapshotsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DiffSnapshotsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DiffSnapshots",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DiffSnapshots"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Scheduled snapshot policies
+        pub async fn create_snapshot_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateSnapshotPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateSnapshotPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateSnapshotPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_snapshot_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSnapshotPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetSnapshotPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetSnapshotPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn update_snapshot_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateSnapshotPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/UpdateSnapshotPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "UpdateSnapshotPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_snapshot_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteSnapshotPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteSnapshotPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteSnapshotPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_snapshot_policies(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListSnapshotPoliciesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSnapshotPoliciesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListSnapshotPolicies",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListSnapshotPolicies"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Backups
+        pub async fn create_backup(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateBackupResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateBackup",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateBackup"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_backup(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetBackupResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetBackup",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetBackup"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_backup(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteBackupResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteBackup",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteBackup"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_backups(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListBackupsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBackupsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListBackups",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListBackups"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn restore_backup(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreBackupResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/RestoreBackup",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "RestoreBackup"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Scheduled backup policies
+        pub async fn create_backup_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateBackupPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateBackupPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateBackupPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_backup_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetBackupPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetBackupPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetBackupPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn update_backup_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateBackupPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/UpdateBackupPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "UpdateBackupPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_backup_policy(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteBackupPolicyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteBackupPolicy",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteBackupPolicy"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_backup_policies(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListBackupPoliciesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBackupPoliciesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListBackupPolicies",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListBackupPolicies"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Archives
+        pub async fn create_archive(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateArchiveRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateArchiveResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateArchive",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateArchive"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn download_archive(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DownloadArchiveRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::DownloadArchiveResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DownloadArchive",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DownloadArchive"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn restore_archive(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreArchiveRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreArchiveResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/RestoreArchive",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "RestoreArchive"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Background jobs
+        pub async fn create_job(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateJobRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateJobResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateJob",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateJob"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_job(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetJobRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetJobResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetJob",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetJob"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_jobs(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListJobsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListJobsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListJobs",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListJobs"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn cancel_job(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelJobRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelJobResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CancelJob",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CancelJob"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Benchmark management
+        pub async fn create_benchmark_run(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateBenchmarkRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateBenchmarkRunResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateBenchmarkRun",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateBenchmarkRun"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_benchmark_run(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetBenchmarkRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetBenchmarkRunResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetBenchmarkRun",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetBenchmarkRun"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_benchmark_runs(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListBenchmarkRunsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBenchmarkRunsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListBenchmarkRuns",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListBenchmarkRuns"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Attestation
+        pub async fn get_attestation(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetAttestationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetAttestationResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetAttestation",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetAttestation"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_tpm_quote(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetTpmQuoteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetTpmQuoteResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetTpmQuote",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetTpmQuote"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_volume_provenance(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetVolumeProvenanceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetVolumeProvenanceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetVolumeProvenance",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetVolumeProvenance"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Software-defined devices
+        pub async fn create_lo_ra_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateLoRaDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateLoRaDeviceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateLoRaDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateLoRaDevice"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_lo_ra_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetLoRaDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetLoRaDeviceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetLoRaDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetLoRaDevice"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_lo_ra_device(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteLoRaDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteLoRaDeviceResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteLoRaDevice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteLoRaDevice"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Events
+        pub async fn watch_events(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::Event>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/WatchEvents",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "WatchEvents"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Health and status
+        pub async fn get_health(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetHealthRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetHealthResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetHealth",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetHealth"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_daemon_status(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetDaemonStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetDaemonStatusResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetDaemonStatus",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetDaemonStatus"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Artifact inspection
+        pub async fn inspect_artifact(
+            &mut self,
+            request: impl tonic::IntoRequest<super::InspectArtifactRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::InspectArtifactResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/InspectArtifact",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "InspectArtifact"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Signing
+        pub async fn sign_payload(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SignPayloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SignPayloadResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/SignPayload",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "SignPayload"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_signing_key(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetSigningKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSigningKeyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetSigningKey",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetSigningKey"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Audit log
+        pub async fn list_audit_events(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListAuditEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListAuditEventsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListAuditEvents",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListAuditEvents"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn verify_audit_log(
+            &mut self,
+            request: impl tonic::IntoRequest<super::VerifyAuditLogRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::VerifyAuditLogResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/VerifyAuditLog",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "VerifyAuditLog"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Provenance ledger
+        pub async fn get_provenance_chain(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetProvenanceChainRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetProvenanceChainResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetProvenanceChain",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetProvenanceChain"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Secrets
+        pub async fn create_secret(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CreateSecretRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateSecretResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/CreateSecret",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "CreateSecret"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_secret(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetSecretRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSecretResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetSecret",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetSecret"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_secrets(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListSecretsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSecretsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ListSecrets",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ListSecrets"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn delete_secret(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteSecretRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteSecretResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/DeleteSecret",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "DeleteSecret"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Replication to a standby daemon; snapshots and objects are only served
+        /// with replication.allow_standbys
+        pub async fn get_replication_status(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetReplicationStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ReplicationStatus>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/GetReplicationStatus",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "GetReplicationStatus"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn stream_state_snapshot(
+            &mut self,
+            request: impl tonic::IntoRequest<super::StreamStateSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::StateSnapshotChunk>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/StreamStateSnapshot",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("infrasim.v1.InfraSimDaemon", "StreamStateSnapshot"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn read_cas_object(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReadCasObjectRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ReadCasObjectResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/ReadCasObject",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "ReadCasObject"));
+            self.inner.server_streaming(req, path, codec).await
+        }
+        pub async fn promote_daemon(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PromoteDaemonRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PromoteDaemonResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/infrasim.v1.InfraSimDaemon/PromoteDaemon",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("infrasim.v1.InfraSimDaemon", "PromoteDaemon"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod infra_sim_daemon_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with InfraSimDaemonServer.
+    #[async_trait]
+    pub trait InfraSimDaemon: Send + Sync + 'static {
+        /// VM lifecycle
+        async fn create_vm(
+            &self,
+            request: tonic::Request<super::CreateVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateVmResponse>,
+            tonic::Status,
+        >;
+        async fn get_vm(
+            &self,
+            request: tonic::Request<super::GetVmRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetVmResponse>, tonic::Status>;
+        async fn update_vm(
+            &self,
+            request: tonic::Request<super::UpdateVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateVmResponse>,
+            tonic::Status,
+        >;
+        async fn update_vm_labels(
+            &self,
+            request: tonic::Request<super::UpdateVmLabelsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateVmLabelsResponse>,
+            tonic::Status,
+        >;
+        async fn delete_vm(
+            &self,
+            request: tonic::Request<super::DeleteVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteVmResponse>,
+            tonic::Status,
+        >;
+        async fn list_v_ms(
+            &self,
+            request: tonic::Request<super::ListVMsRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListVMsResponse>, tonic::Status>;
+        async fn start_vm(
+            &self,
+            request: tonic::Request<super::StartVmRequest>,
+        ) -> std::result::Result<tonic::Response<super::StartVmResponse>, tonic::Status>;
+        async fn stop_vm(
+            &self,
+            request: tonic::Request<super::StopVmRequest>,
+        ) -> std::result::Result<tonic::Response<super::StopVmResponse>, tonic::Status>;
+        async fn pause_vm(
+            &self,
+            request: tonic::Request<super::PauseVmRequest>,
+        ) -> std::result::Result<tonic::Response<super::PauseVmResponse>, tonic::Status>;
+        async fn resume_vm(
+            &self,
+            request: tonic::Request<super::ResumeVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ResumeVmResponse>,
+            tonic::Status,
+        >;
+        async fn suspend_vm(
+            &self,
+            request: tonic::Request<super::SuspendVmRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SuspendVmResponse>,
+            tonic::Status,
+        >;
+        async fn attach_device(
+            &self,
+            request: tonic::Request<super::AttachDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::AttachDeviceResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the StreamVMLogs method.
+        type StreamVMLogsStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::VmLogEntry, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn stream_vm_logs(
+            &self,
+            request: tonic::Request<super::StreamVmLogsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::StreamVMLogsStream>,
+            tonic::Status,
+        >;
+        async fn get_vm_stats(
+            &self,
+            request: tonic::Request<super::GetVmStatsRequest>,
+        ) -> std::result::Result<tonic::Response<super::VmStats>, tonic::Status>;
+        /// Server streaming response type for the WatchVMStats method.
+        type WatchVMStatsStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::VmStats, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn watch_vm_stats(
+            &self,
+            request: tonic::Request<super::WatchVmStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::WatchVMStatsStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ExportVM method.
+        type ExportVMStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::ExportVmResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn export_vm(
+            &self,
+            request: tonic::Request<super::ExportVmRequest>,
+        ) -> std::result::Result<tonic::Response<Self::ExportVMStream>, tonic::Status>;
+        async fn import_vm(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::ImportVmRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<super::ImportVmResponse>,
+            tonic::Status,
+        >;
+        async fn push_appliance(
+            &self,
+            request: tonic::Request<super::PushApplianceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PushApplianceResponse>,
+            tonic::Status,
+        >;
+        async fn pull_appliance(
+            &self,
+            request: tonic::Request<super::PullApplianceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PullApplianceResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the AttachSerial method.
+        type AttachSerialStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::SerialOutput, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn attach_serial(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::SerialInput>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::AttachSerialStream>,
+            tonic::Status,
+        >;
+        async fn copy_to_guest(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::CopyToGuestRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<super::CopyToGuestResponse>,
+            tonic::Status,
+        >;
+        async fn mark_vm_as_template(
+            &self,
+            request: tonic::Request<super::MarkVmAsTemplateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::MarkVmAsTemplateResponse>,
+            tonic::Status,
+        >;
+        async fn clone_vm(
+            &self,
+            request: tonic::Request<super::CloneVmRequest>,
+        ) -> std::result::Result<tonic::Response<super::CloneVmResponse>, tonic::Status>;
+        /// Raw QMP command to a running VM's monitor; off unless security.allow_qmp_passthrough
+        async fn execute_qmp(
+            &self,
+            request: tonic::Request<super::ExecuteQmpRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ExecuteQmpResponse>,
+            tonic::Status,
+        >;
+        /// Network management
+        async fn create_network(
+            &self,
+            request: tonic::Request<super::CreateNetworkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateNetworkResponse>,
+            tonic::Status,
+        >;
+        async fn get_network(
+            &self,
+            request: tonic::Request<super::GetNetworkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetNetworkResponse>,
+            tonic::Status,
+        >;
+        async fn delete_network(
+            &self,
+            request: tonic::Request<super::DeleteNetworkRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteNetworkResponse>,
+            tonic::Status,
+        >;
+        async fn list_networks(
+            &self,
+            request: tonic::Request<super::ListNetworksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListNetworksResponse>,
+            tonic::Status,
+        >;
+        /// Packet capture on a network
+        async fn start_network_capture(
+            &self,
+            request: tonic::Request<super::StartNetworkCaptureRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StartNetworkCaptureResponse>,
+            tonic::Status,
+        >;
+        async fn stop_network_capture(
+            &self,
+            request: tonic::Request<super::StopNetworkCaptureRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::StopNetworkCaptureResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ReadNetworkCapture method.
+        type ReadNetworkCaptureStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::ReadNetworkCaptureResponse,
+                    tonic::Status,
+                >,
+            >
+            + Send
+            + 'static;
+        async fn read_network_capture(
+            &self,
+            request: tonic::Request<super::ReadNetworkCaptureRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::ReadNetworkCaptureStream>,
+            tonic::Status,
+        >;
+        /// Port forwarding (user-mode networks)
+        async fn create_port_forward(
+            &self,
+            request: tonic::Request<super::CreatePortForwardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreatePortForwardResponse>,
+            tonic::Status,
+        >;
+        async fn get_port_forward(
+            &self,
+            request: tonic::Request<super::GetPortForwardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetPortForwardResponse>,
+            tonic::Status,
+        >;
+        async fn delete_port_forward(
+            &self,
+            request: tonic::Request<super::DeletePortForwardRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeletePortForwardResponse>,
+            tonic::Status,
+        >;
+        async fn list_port_forwards(
+            &self,
+            request: tonic::Request<super::ListPortForwardsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListPortForwardsResponse>,
+            tonic::Status,
+        >;
+        /// Virtual routers between networks
+        async fn create_router(
+            &self,
+            request: tonic::Request<super::CreateRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateRouterResponse>,
+            tonic::Status,
+        >;
+        async fn get_router(
+            &self,
+            request: tonic::Request<super::GetRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetRouterResponse>,
+            tonic::Status,
+        >;
+        async fn update_router(
+            &self,
+            request: tonic::Request<super::UpdateRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateRouterResponse>,
+            tonic::Status,
+        >;
+        async fn delete_router(
+            &self,
+            request: tonic::Request<super::DeleteRouterRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteRouterResponse>,
+            tonic::Status,
+        >;
+        async fn list_routers(
+            &self,
+            request: tonic::Request<super::ListRoutersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListRoutersResponse>,
+            tonic::Status,
+        >;
+        /// Host device passthrough (USB, vfio-pci)
+        async fn create_device(
+            &self,
+            request: tonic::Request<super::CreateDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateDeviceResponse>,
+            tonic::Status,
+        >;
+        async fn get_device(
+            &self,
+            request: tonic::Request<super::GetDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetDeviceResponse>,
+            tonic::Status,
+        >;
+        async fn delete_device(
+            &self,
+            request: tonic::Request<super::DeleteDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteDeviceResponse>,
+            tonic::Status,
+        >;
+        async fn list_devices(
+            &self,
+            request: tonic::Request<super::ListDevicesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListDevicesResponse>,
+            tonic::Status,
+        >;
+        /// QoS profiles
+        async fn create_qo_s_profile(
+            &self,
+            request: tonic::Request<super::CreateQoSProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateQoSProfileResponse>,
+            tonic::Status,
+        >;
+        async fn get_qo_s_profile(
+            &self,
+            request: tonic::Request<super::GetQoSProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetQoSProfileResponse>,
+            tonic::Status,
+        >;
+        async fn delete_qo_s_profile(
+            &self,
+            request: tonic::Request<super::DeleteQoSProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteQoSProfileResponse>,
+            tonic::Status,
+        >;
+        async fn list_qo_s_profiles(
+            &self,
+            request: tonic::Request<super::ListQoSProfilesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListQoSProfilesResponse>,
+            tonic::Status,
+        >;
+        /// Chaos profiles: expiring fault injection on networks and VM NICs
+        async fn apply_chaos_profile(
+            &self,
+            request: tonic::Request<super::ApplyChaosProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ApplyChaosProfileResponse>,
+            tonic::Status,
+        >;
+        async fn get_chaos_profile(
+            &self,
+            request: tonic::Request<super::GetChaosProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetChaosProfileResponse>,
+            tonic::Status,
+        >;
+        async fn delete_chaos_profile(
+            &self,
+            request: tonic::Request<super::DeleteChaosProfileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteChaosProfileResponse>,
+            tonic::Status,
+        >;
+        async fn list_chaos_profiles(
+            &self,
+            request: tonic::Request<super::ListChaosProfilesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListChaosProfilesResponse>,
+            tonic::Status,
+        >;
+        /// Scenario runs: scripted chaos experiments and their reports
+        async fn run_scenario(
+            &self,
+            request: tonic::Request<super::RunScenarioRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RunScenarioResponse>,
+            tonic::Status,
+        >;
+        async fn get_scenario_run(
+            &self,
+            request: tonic::Request<super::GetScenarioRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetScenarioRunResponse>,
+            tonic::Status,
+        >;
+        async fn list_scenario_runs(
+            &self,
+            request: tonic::Request<super::ListScenarioRunsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListScenarioRunsResponse>,
+            tonic::Status,
+        >;
+        async fn cancel_scenario_run(
+            &self,
+            request: tonic::Request<super::CancelScenarioRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelScenarioRunResponse>,
+            tonic::Status,
+        >;
+        async fn delete_scenario_run(
+            &self,
+            request: tonic::Request<super::DeleteScenarioRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteScenarioRunResponse>,
+            tonic::Status,
+        >;
+        async fn compare_scenario_runs(
+            &self,
+            request: tonic::Request<super::CompareScenarioRunsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CompareScenarioRunsResponse>,
+            tonic::Status,
+        >;
+        /// Volume management
+        async fn create_volume(
+            &self,
+            request: tonic::Request<super::CreateVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateVolumeResponse>,
+            tonic::Status,
+        >;
+        async fn get_volume(
+            &self,
+            request: tonic::Request<super::GetVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetVolumeResponse>,
+            tonic::Status,
+        >;
+        async fn delete_volume(
+            &self,
+            request: tonic::Request<super::DeleteVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteVolumeResponse>,
+            tonic::Status,
+        >;
+        async fn list_volumes(
+            &self,
+            request: tonic::Request<super::ListVolumesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListVolumesResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the WatchVolumeDownload method.
+        type WatchVolumeDownloadStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::DownloadStatus, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn watch_volume_download(
+            &self,
+            request: tonic::Request<super::WatchVolumeDownloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::WatchVolumeDownloadStream>,
+            tonic::Status,
+        >;
+        async fn get_volume_chain(
+            &self,
+            request: tonic::Request<super::GetVolumeChainRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetVolumeChainResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the FlattenVolume method.
+        type FlattenVolumeStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::FlattenProgress, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn flatten_volume(
+            &self,
+            request: tonic::Request<super::FlattenVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::FlattenVolumeStream>,
+            tonic::Status,
+        >;
+        async fn resize_volume(
+            &self,
+            request: tonic::Request<super::ResizeVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ResizeVolumeResponse>,
+            tonic::Status,
+        >;
+        async fn push_volume(
+            &self,
+            request: tonic::Request<super::PushVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PushVolumeResponse>,
+            tonic::Status,
+        >;
+        async fn pull_volume(
+            &self,
+            request: tonic::Request<super::PullVolumeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PullVolumeResponse>,
+            tonic::Status,
+        >;
+        /// Console management
+        async fn create_console(
+            &self,
+            request: tonic::Request<super::CreateConsoleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateConsoleResponse>,
+            tonic::Status,
+        >;
+        async fn get_console(
+            &self,
+            request: tonic::Request<super::GetConsoleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetConsoleResponse>,
+            tonic::Status,
+        >;
+        async fn delete_console(
+            &self,
+            request: tonic::Request<super::DeleteConsoleRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteConsoleResponse>,
+            tonic::Status,
+        >;
+        /// Snapshot management
+        async fn create_snapshot(
+            &self,
+            request: tonic::Request<super::CreateSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateSnapshotResponse>,
+            tonic::Status,
+        >;
+        async fn get_snapshot(
+            &self,
+            request: tonic::Request<super::GetSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSnapshotResponse>,
+            tonic::Status,
+        >;
+        async fn delete_snapshot(
+            &self,
+            request: tonic::Request<super::DeleteSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteSnapshotResponse>,
+            tonic::Status,
+        >;
+        async fn list_snapshots(
+            &self,
+            request: tonic::Request<super::ListSnapshotsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSnapshotsResponse>,
+            tonic::Status,
+        >;
+        async fn restore_snapshot(
+            &self,
+            request: tonic::Request<super::RestoreSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreSnapshotResponse>,
+            tonic::Status,
+        >;
+        async fn revert_snapshot(
+            &self,
+            request: tonic::Request<super::RevertSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RevertSnapshotResponse>,
+            tonic::Status,
+        >;
+        async fn branch_snapshot(
+            &self,
+            request: tonic::Request<super::BranchSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BranchSnapshotResponse>,
+            tonic::Status,
+        >;
+        async fn list_snapshot_files(
+            &self,
+            request: tonic::Request<super::ListSnapshotFilesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSnapshotFilesResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ReadSnapshotFile method.
+        type ReadSnapshotFileStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::ReadSnapshotFileResponse,
+                    tonic::Status,
+                >,
+            >
+            + Send
+            + 'static;
+        async fn read_snapshot_file(
+            &self,
+            request: tonic::Request<super::ReadSnapshotFileRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::ReadSnapshotFileStream>,
+            tonic::Status,
+        >;
+        async fn diff_snapshots(
+            &self,
+            request: tonic::Request<super::DiffSnapshotsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DiffSnapshotsResponse>,
+            tonic::Status,
+        >;
+        /// Scheduled snapshot policies
+        async fn create_snapshot_policy(
+            &self,
+            request: tonic::Request<super::CreateSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateSnapshotPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn get_snapshot_policy(
+            &self,
+            request: tonic::Request<super::GetSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSnapshotPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn update_snapshot_policy(
+            &self,
+            request: tonic::Request<super::UpdateSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateSnapshotPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn delete_snapshot_policy(
+            &self,
+            request: tonic::Request<super::DeleteSnapshotPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteSnapshotPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn list_snapshot_policies(
+            &self,
+            request: tonic::Request<super::ListSnapshotPoliciesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSnapshotPoliciesResponse>,
+            tonic::Status,
+        >;
+        /// Backups
+        async fn create_backup(
+            &self,
+            request: tonic::Request<super::CreateBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateBackupResponse>,
+            tonic::Status,
+        >;
+        async fn get_backup(
+            &self,
+            request: tonic::Request<super::GetBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetBackupResponse>,
+            tonic::Status,
+        >;
+        async fn delete_backup(
+            &self,
+            request: tonic::Request<super::DeleteBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteBackupResponse>,
+            tonic::Status,
+        >;
+        async fn list_backups(
+            &self,
+            request: tonic::Request<super::ListBackupsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBackupsResponse>,
+            tonic::Status,
+        >;
+        async fn restore_backup(
+            &self,
+            request: tonic::Request<super::RestoreBackupRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreBackupResponse>,
+            tonic::Status,
+        >;
+        /// Scheduled backup policies
+        async fn create_backup_policy(
+            &self,
+            request: tonic::Request<super::CreateBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateBackupPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn get_backup_policy(
+            &self,
+            request: tonic::Request<super::GetBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetBackupPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn update_backup_policy(
+            &self,
+            request: tonic::Request<super::UpdateBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateBackupPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn delete_backup_policy(
+            &self,
+            request: tonic::Request<super::DeleteBackupPolicyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteBackupPolicyResponse>,
+            tonic::Status,
+        >;
+        async fn list_backup_policies(
+            &self,
+            request: tonic::Request<super::ListBackupPoliciesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBackupPoliciesResponse>,
+            tonic::Status,
+        >;
+        /// Archives
+        async fn create_archive(
+            &self,
+            request: tonic::Request<super::CreateArchiveRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateArchiveResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the DownloadArchive method.
+        type DownloadArchiveStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::DownloadArchiveResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn download_archive(
+            &self,
+            request: tonic::Request<super::DownloadArchiveRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::DownloadArchiveStream>,
+            tonic::Status,
+        >;
+        async fn restore_archive(
+            &self,
+            request: tonic::Request<super::RestoreArchiveRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RestoreArchiveResponse>,
+            tonic::Status,
+        >;
+        /// Background jobs
+        async fn create_job(
+            &self,
+            request: tonic::Request<super::CreateJobRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateJobResponse>,
+            tonic::Status,
+        >;
+        async fn get_job(
+            &self,
+            request: tonic::Request<super::GetJobRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetJobResponse>, tonic::Status>;
+        async fn list_jobs(
+            &self,
+            request: tonic::Request<super::ListJobsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListJobsResponse>,
+            tonic::Status,
+        >;
+        async fn cancel_job(
+            &self,
+            request: tonic::Request<super::CancelJobRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelJobResponse>,
+            tonic::Status,
+        >;
+        /// Benchmark management
+        async fn create_benchmark_run(
+            &self,
+            request: tonic::Request<super::CreateBenchmarkRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateBenchmarkRunResponse>,
+            tonic::Status,
+        >;
+        async fn get_benchmark_run(
+            &self,
+            request: tonic::Request<super::GetBenchmarkRunRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetBenchmarkRunResponse>,
+            tonic::Status,
+        >;
+        async fn list_benchmark_runs(
+            &self,
+            request: tonic::Request<super::ListBenchmarkRunsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBenchmarkRunsResponse>,
+            tonic::Status,
+        >;
+        /// Attestation
+        async fn get_attestation(
+            &self,
+            request: tonic::Request<super::GetAttestationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetAttestationResponse>,
+            tonic::Status,
+        >;
+        async fn get_tpm_quote(
+            &self,
+            request: tonic::Request<super::GetTpmQuoteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetTpmQuoteResponse>,
+            tonic::Status,
+        >;
+        async fn get_volume_provenance(
+            &self,
+            request: tonic::Request<super::GetVolumeProvenanceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetVolumeProvenanceResponse>,
+            tonic::Status,
+        >;
+        /// Software-defined devices
+        async fn create_lo_ra_device(
+            &self,
+            request: tonic::Request<super::CreateLoRaDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateLoRaDeviceResponse>,
+            tonic::Status,
+        >;
+        async fn get_lo_ra_device(
+            &self,
+            request: tonic::Request<super::GetLoRaDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetLoRaDeviceResponse>,
+            tonic::Status,
+        >;
+        async fn delete_lo_ra_device(
+            &self,
+            request: tonic::Request<super::DeleteLoRaDeviceRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteLoRaDeviceResponse>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the WatchEvents method.
+        type WatchEventsStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::Event, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Events
+        async fn watch_events(
+            &self,
+            request: tonic::Request<super::WatchEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::WatchEventsStream>,
+            tonic::Status,
+        >;
+        /// Health and status
+        async fn get_health(
+            &self,
+            request: tonic::Request<super::GetHealthRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetHealthResponse>,
+            tonic::Status,
+        >;
+        async fn get_daemon_status(
+            &self,
+            request: tonic::Request<super::GetDaemonStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetDaemonStatusResponse>,
+            tonic::Status,
+        >;
+        /// Artifact inspection
+        async fn inspect_artifact(
+            &self,
+            request: tonic::Request<super::InspectArtifactRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::InspectArtifactResponse>,
+            tonic::Status,
+        >;
+        /// Signing
+        async fn sign_payload(
+            &self,
+            request: tonic::Request<super::SignPayloadRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SignPayloadResponse>,
+            tonic::Status,
+        >;
+        async fn get_signing_key(
+            &self,
+            request: tonic::Request<super::GetSigningKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSigningKeyResponse>,
+            tonic::Status,
+        >;
+        /// Audit log
+        async fn list_audit_events(
+            &self,
+            request: tonic::Request<super::ListAuditEventsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListAuditEventsResponse>,
+            tonic::Status,
+        >;
+        async fn verify_audit_log(
+            &self,
+            request: tonic::Request<super::VerifyAuditLogRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::VerifyAuditLogResponse>,
+            tonic::Status,
+        >;
+        /// Provenance ledger
+        async fn get_provenance_chain(
+            &self,
+            request: tonic::Request<super::GetProvenanceChainRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetProvenanceChainResponse>,
+            tonic::Status,
+        >;
+        /// Secrets
+        async fn create_secret(
+            &self,
+            request: tonic::Request<super::CreateSecretRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateSecretResponse>,
+            tonic::Status,
+        >;
+        async fn get_secret(
+            &self,
+            request: tonic::Request<super::GetSecretRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetSecretResponse>,
+            tonic::Status,
+        >;
+        async fn list_secrets(
+            &self,
+            request: tonic::Request<super::ListSecretsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListSecretsResponse>,
+            tonic::Status,
+        >;
+        async fn delete_secret(
+            &self,
+            request: tonic::Request<super::DeleteSecretRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteSecretResponse>,
+            tonic::Status,
+        >;
+        /// Replication to a standby daemon; snapshots and objects are only served
+        /// with replication.allow_standbys
+        async fn get_replication_status(
+            &self,
+            request: tonic::Request<super::GetReplicationStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ReplicationStatus>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the StreamStateSnapshot method.
+        type StreamStateSnapshotStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::StateSnapshotChunk, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn stream_state_snapshot(
+            &self,
+            request: tonic::Request<super::StreamStateSnapshotRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::StreamStateSnapshotStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the ReadCasObject method.
+        type ReadCasObjectStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::ReadCasObjectResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn read_cas_object(
+            &self,
+            request: tonic::Request<super::ReadCasObjectRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::ReadCasObjectStream>,
+            tonic::Status,
+        >;
+        async fn promote_daemon(
+            &self,
+            request: tonic::Request<super::PromoteDaemonRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PromoteDaemonResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct InfraSimDaemonServer<T: InfraSimDaemon> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: InfraSimDaemon> InfraSimDaemonServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for InfraSimDaemonServer<T>
+    where
+        T: InfraSimDaemon,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/infrasim.v1.InfraSimDaemon/CreateVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateVmRequest>
+                    for CreateVMSvc<T> {
+                        type Response = super::CreateVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetVmRequest> for GetVMSvc<T> {
+                        type Response = super::GetVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/UpdateVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::UpdateVmRequest>
+                    for UpdateVMSvc<T> {
+                        type Response = super::UpdateVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::update_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/UpdateVMLabels" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateVMLabelsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::UpdateVmLabelsRequest>
+                    for UpdateVMLabelsSvc<T> {
+                        type Response = super::UpdateVmLabelsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateVmLabelsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::update_vm_labels(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateVMLabelsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteVmRequest>
+                    for DeleteVMSvc<T> {
+                        type Response = super::DeleteVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListVMs" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListVMsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListVMsRequest>
+                    for ListVMsSvc<T> {
+                        type Response = super::ListVMsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListVMsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_v_ms(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListVMsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/StartVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct StartVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::StartVmRequest>
+                    for StartVMSvc<T> {
+                        type Response = super::StartVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StartVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::start_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StartVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/StopVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct StopVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::StopVmRequest>
+                    for StopVMSvc<T> {
+                        type Response = super::StopVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StopVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::stop_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StopVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/PauseVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct PauseVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::PauseVmRequest>
+                    for PauseVMSvc<T> {
+                        type Response = super::PauseVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PauseVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::pause_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PauseVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ResumeVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct ResumeVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ResumeVmRequest>
+                    for ResumeVMSvc<T> {
+                        type Response = super::ResumeVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ResumeVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::resume_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ResumeVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/SuspendVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct SuspendVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::SuspendVmRequest>
+                    for SuspendVMSvc<T> {
+                        type Response = super::SuspendVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SuspendVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::suspend_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SuspendVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/AttachDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct AttachDeviceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::AttachDeviceRequest>
+                    for AttachDeviceSvc<T> {
+                        type Response = super::AttachDeviceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AttachDeviceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::attach_device(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AttachDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/StreamVMLogs" => {
+                    #[allow(non_camel_case_types)]
+                    struct StreamVMLogsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<super::StreamVmLogsRequest>
+                    for StreamVMLogsSvc<T> {
+                        type Response = super::VmLogEntry;
+                        type ResponseStream = T::StreamVMLogsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StreamVmLogsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::stream_vm_logs(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StreamVMLogsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetVMStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVMStatsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetVmStatsRequest>
+                    for GetVMStatsSvc<T> {
+                        type Response = super::VmStats;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetVmStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_vm_stats(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetVMStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/WatchVMStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchVMStatsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<super::WatchVmStatsRequest>
+                    for WatchVMStatsSvc<T> {
+                        type Response = super::VmStats;
+                        type ResponseStream = T::WatchVMStatsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchVmStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::watch_vm_stats(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchVMStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ExportVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExportVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<super::ExportVmRequest>
+                    for ExportVMSvc<T> {
+                        type Response = super::ExportVmResponse;
+                        type ResponseStream = T::ExportVMStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExportVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::export_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExportVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ImportVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct ImportVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ClientStreamingService<super::ImportVmRequest>
+                    for ImportVMSvc<T> {
+                        type Response = super::ImportVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::ImportVmRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::import_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ImportVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/PushAppliance" => {
+                    #[allow(non_camel_case_types)]
+                    struct PushApplianceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::PushApplianceRequest>
+                    for PushApplianceSvc<T> {
+                        type Response = super::PushApplianceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PushApplianceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::push_appliance(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PushApplianceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/PullAppliance" => {
+                    #[allow(non_camel_case_types)]
+                    struct PullApplianceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::PullApplianceRequest>
+                    for PullApplianceSvc<T> {
+                        type Response = super::PullApplianceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PullApplianceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::pull_appliance(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PullApplianceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/AttachSerial" => {
+                    #[allow(non_camel_case_types)]
+                    struct AttachSerialSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::StreamingService<super::SerialInput>
+                    for AttachSerialSvc<T> {
+                        type Response = super::SerialOutput;
+                        type ResponseStream = T::AttachSerialStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::SerialInput>>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::attach_serial(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AttachSerialSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CopyToGuest" => {
+                    #[allow(non_camel_case_types)]
+                    struct CopyToGuestSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ClientStreamingService<super::CopyToGuestRequest>
+                    for CopyToGuestSvc<T> {
+                        type Response = super::CopyToGuestResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::CopyToGuestRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::copy_to_guest(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CopyToGuestSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/MarkVMAsTemplate" => {
+                    #[allow(non_camel_case_types)]
+                    struct MarkVMAsTemplateSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::MarkVmAsTemplateRequest>
+                    for MarkVMAsTemplateSvc<T> {
+                        type Response = super::MarkVmAsTemplateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MarkVmAsTemplateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::mark_vm_as_template(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = MarkVMAsTemplateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CloneVM" => {
+                    #[allow(non_camel_case_types)]
+                    struct CloneVMSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CloneVmRequest>
+                    for CloneVMSvc<T> {
+                        type Response = super::CloneVmResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CloneVmRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::clone_vm(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CloneVMSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ExecuteQMP" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExecuteQMPSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ExecuteQmpRequest>
+                    for ExecuteQMPSvc<T> {
+                        type Response = super::ExecuteQmpResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExecuteQmpRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::execute_qmp(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExecuteQMPSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateNetwork" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateNetworkSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateNetworkRequest>
+                    for CreateNetworkSvc<T> {
+                        type Response = super::CreateNetworkResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateNetworkRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_network(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateNetworkSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetNetwork" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNetworkSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetNetworkRequest>
+                    for GetNetworkSvc<T> {
+                        type Response = super::GetNetworkResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetNetworkRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_network(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetNetworkSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteNetwork" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteNetworkSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteNetworkRequest>
+                    for DeleteNetworkSvc<T> {
+                        type Response = super::DeleteNetworkResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteNetworkRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_network(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteNetworkSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListNetworks" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListNetworksSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListNetworksRequest>
+                    for ListNetworksSvc<T> {
+                        type Response = super::ListNetworksResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListNetworksRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_networks(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListNetworksSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/StartNetworkCapture" => {
+                    #[allow(non_camel_case_types)]
+                    struct StartNetworkCaptureSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::StartNetworkCaptureRequest>
+                    for StartNetworkCaptureSvc<T> {
+                        type Response = super::StartNetworkCaptureResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StartNetworkCaptureRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::start_network_capture(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StartNetworkCaptureSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/StopNetworkCapture" => {
+                    #[allow(non_camel_case_types)]
+                    struct StopNetworkCaptureSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::StopNetworkCaptureRequest>
+                    for StopNetworkCaptureSvc<T> {
+                        type Response = super::StopNetworkCaptureResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StopNetworkCaptureRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::stop_network_capture(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StopNetworkCaptureSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ReadNetworkCapture" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReadNetworkCaptureSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<
+                        super::ReadNetworkCaptureRequest,
+                    > for ReadNetworkCaptureSvc<T> {
+                        type Response = super::ReadNetworkCaptureResponse;
+                        type ResponseStream = T::ReadNetworkCaptureStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReadNetworkCaptureRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::read_network_capture(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReadNetworkCaptureSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreatePortForward" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreatePortForwardSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreatePortForwardRequest>
+                    for CreatePortForwardSvc<T> {
+                        type Response = super::CreatePortForwardResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreatePortForwardRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_port_forward(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreatePortForwardSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetPortForward" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetPortForwardSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetPortForwardRequest>
+                    for GetPortForwardSvc<T> {
+                        type Response = super::GetPortForwardResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetPortForwardRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_port_forward(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetPortForwardSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeletePortForward" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeletePortForwardSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeletePortForwardRequest>
+                    for DeletePortForwardSvc<T> {
+                        type Response = super::DeletePortForwardResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeletePortForwardRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_port_forward(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeletePortForwardSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListPortForwards" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListPortForwardsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListPortForwardsRequest>
+                    for ListPortForwardsSvc<T> {
+                        type Response = super::ListPortForwardsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListPortForwardsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_port_forwards(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListPortForwardsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateRouter" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateRouterSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateRouterRequest>
+                    for CreateRouterSvc<T> {
+                        type Response = super::CreateRouterResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateRouterRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_router(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateRouterSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetRouter" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetRouterSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetRouterRequest>
+                    for GetRouterSvc<T> {
+                        type Response = super::GetRouterResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRouterRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_router(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetRouterSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/UpdateRouter" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateRouterSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::UpdateRouterRequest>
+                    for UpdateRouterSvc<T> {
+                        type Response = super::UpdateRouterResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateRouterRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::update_router(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateRouterSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteRouter" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteRouterSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteRouterRequest>
+                    for DeleteRouterSvc<T> {
+                        type Response = super::DeleteRouterResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteRouterRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_router(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteRouterSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListRouters" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListRoutersSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListRoutersRequest>
+                    for ListRoutersSvc<T> {
+                        type Response = super::ListRoutersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRoutersRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_routers(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListRoutersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateDeviceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateDeviceRequest>
+                    for CreateDeviceSvc<T> {
+                        type Response = super::CreateDeviceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateDeviceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_device(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDeviceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetDeviceRequest>
+                    for GetDeviceSvc<T> {
+                        type Response = super::GetDeviceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetDeviceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_device(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteDeviceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteDeviceRequest>
+                    for DeleteDeviceSvc<T> {
+                        type Response = super::DeleteDeviceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteDeviceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_device(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListDevices" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListDevicesSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListDevicesRequest>
+                    for ListDevicesSvc<T> {
+                        type Response = super::ListDevicesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListDevicesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_devices(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListDevicesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateQoSProfile" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateQoSProfileSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateQoSProfileRequest>
+                    for CreateQoSProfileSvc<T> {
+                        type Response = super::CreateQoSProfileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateQoSProfileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_qo_s_profile(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateQoSProfileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetQoSProfile" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetQoSProfileSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetQoSProfileRequest>
+                    for GetQoSProfileSvc<T> {
+                        type Response = super::GetQoSProfileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetQoSProfileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_qo_s_profile(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetQoSProfileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteQoSProfile" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteQoSProfileSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteQoSProfileRequest>
+                    for DeleteQoSProfileSvc<T> {
+                        type Response = super::DeleteQoSProfileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteQoSProfileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_qo_s_profile(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteQoSProfileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListQoSProfiles" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListQoSProfilesSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListQoSProfilesRequest>
+                    for ListQoSProfilesSvc<T> {
+                        type Response = super::ListQoSProfilesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListQoSProfilesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_qo_s_profiles(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListQoSProfilesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ApplyChaosProfile" => {
+                    #[allow(non_camel_case_types)]
+                    struct ApplyChaosProfileSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ApplyChaosProfileRequest>
+                    for ApplyChaosProfileSvc<T> {
+                        type Response = super::ApplyChaosProfileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ApplyChaosProfileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::apply_chaos_profile(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ApplyChaosProfileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetChaosProfile" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetChaosProfileSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetChaosProfileRequest>
+                    for GetChaosProfileSvc<T> {
+                        type Response = super::GetChaosProfileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetChaosProfileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_chaos_profile(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetChaosProfileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteChaosProfile" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteChaosProfileSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteChaosProfileRequest>
+                    for DeleteChaosProfileSvc<T> {
+                        type Response = super::DeleteChaosProfileResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteChaosProfileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_chaos_profile(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteChaosProfileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListChaosProfiles" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListChaosProfilesSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListChaosProfilesRequest>
+                    for ListChaosProfilesSvc<T> {
+                        type Response = super::ListChaosProfilesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListChaosProfilesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_chaos_profiles(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListChaosProfilesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/RunScenario" => {
+                    #[allow(non_camel_case_types)]
+                    struct RunScenarioSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::RunScenarioRequest>
+                    for RunScenarioSvc<T> {
+                        type Response = super::RunScenarioResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RunScenarioRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::run_scenario(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RunScenarioSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetScenarioRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetScenarioRunSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetScenarioRunRequest>
+                    for GetScenarioRunSvc<T> {
+                        type Response = super::GetScenarioRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetScenarioRunRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_scenario_run(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetScenarioRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListScenarioRuns" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListScenarioRunsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListScenarioRunsRequest>
+                    for ListScenarioRunsSvc<T> {
+                        type Response = super::ListScenarioRunsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListScenarioRunsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_scenario_runs(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListScenarioRunsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CancelScenarioRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelScenarioRunSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CancelScenarioRunRequest>
+                    for CancelScenarioRunSvc<T> {
+                        type Response = super::CancelScenarioRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelScenarioRunRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::cancel_scenario_run(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelScenarioRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteScenarioRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteScenarioRunSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteScenarioRunRequest>
+                    for DeleteScenarioRunSvc<T> {
+                        type Response = super::DeleteScenarioRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteScenarioRunRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_scenario_run(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteScenarioRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CompareScenarioRuns" => {
+                    #[allow(non_camel_case_types)]
+                    struct CompareScenarioRunsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CompareScenarioRunsRequest>
+                    for CompareScenarioRunsSvc<T> {
+                        type Response = super::CompareScenarioRunsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CompareScenarioRunsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::compare_scenario_runs(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CompareScenarioRunsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateVolumeSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateVolumeRequest>
+                    for CreateVolumeSvc<T> {
+                        type Response = super::CreateVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_volume(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVolumeSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetVolumeRequest>
+                    for GetVolumeSvc<T> {
+                        type Response = super::GetVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_volume(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteVolumeSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteVolumeRequest>
+                    for DeleteVolumeSvc<T> {
+                        type Response = super::DeleteVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_volume(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListVolumes" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListVolumesSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListVolumesRequest>
+                    for ListVolumesSvc<T> {
+                        type Response = super::ListVolumesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListVolumesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_volumes(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListVolumesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/WatchVolumeDownload" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchVolumeDownloadSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<
+                        super::WatchVolumeDownloadRequest,
+                    > for WatchVolumeDownloadSvc<T> {
+                        type Response = super::DownloadStatus;
+                        type ResponseStream = T::WatchVolumeDownloadStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchVolumeDownloadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::watch_volume_download(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchVolumeDownloadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetVolumeChain" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVolumeChainSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetVolumeChainRequest>
+                    for GetVolumeChainSvc<T> {
+                        type Response = super::GetVolumeChainResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetVolumeChainRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_volume_chain(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetVolumeChainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/FlattenVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct FlattenVolumeSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<super::FlattenVolumeRequest>
+                    for FlattenVolumeSvc<T> {
+                        type Response = super::FlattenProgress;
+                        type ResponseStream = T::FlattenVolumeStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::FlattenVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::flatten_volume(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = FlattenVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ResizeVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct ResizeVolumeSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ResizeVolumeRequest>
+                    for ResizeVolumeSvc<T> {
+                        type Response = super::ResizeVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ResizeVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::resize_volume(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ResizeVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/PushVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct PushVolumeSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::PushVolumeRequest>
+                    for PushVolumeSvc<T> {
+                        type Response = super::PushVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PushVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::push_volume(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PushVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/PullVolume" => {
+                    #[allow(non_camel_case_types)]
+                    struct PullVolumeSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::PullVolumeRequest>
+                    for PullVolumeSvc<T> {
+                        type Response = super::PullVolumeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PullVolumeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::pull_volume(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PullVolumeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateConsole" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateConsoleSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateConsoleRequest>
+                    for CreateConsoleSvc<T> {
+                        type Response = super::CreateConsoleResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateConsoleRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_console(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateConsoleSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetConsole" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetConsoleSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetConsoleRequest>
+                    for GetConsoleSvc<T> {
+                        type Response = super::GetConsoleResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetConsoleRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_console(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetConsoleSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteConsole" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteConsoleSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteConsoleRequest>
+                    for DeleteConsoleSvc<T> {
+                        type Response = super::DeleteConsoleResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteConsoleRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_console(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteConsoleSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateSnapshot" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateSnapshotSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateSnapshotRequest>
+                    for CreateSnapshotSvc<T> {
+                        type Response = super::CreateSnapshotResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateSnapshotRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_snapshot(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateSnapshotSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetSnapshot" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSnapshotSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetSnapshotRequest>
+                    for GetSnapshotSvc<T> {
+                        type Response = super::GetSnapshotResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetSnapshotRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_snapshot(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSnapshotSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteSnapshot" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteSnapshotSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteSnapshotRequest>
+                    for DeleteSnapshotSvc<T> {
+                        type Response = super::DeleteSnapshotResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteSnapshotRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_snapshot(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteSnapshotSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListSnapshots" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSnapshotsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListSnapshotsRequest>
+                    for ListSnapshotsSvc<T> {
+                        type Response = super::ListSnapshotsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListSnapshotsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_snapshots(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSnapshotsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/RestoreSnapshot" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreSnapshotSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::RestoreSnapshotRequest>
+                    for RestoreSnapshotSvc<T> {
+                        type Response = super::RestoreSnapshotResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreSnapshotRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::restore_snapshot(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RestoreSnapshotSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/RevertSnapshot" => {
+                    #[allow(non_camel_case_types)]
+                    struct RevertSnapshotSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::RevertSnapshotRequest>
+                    for RevertSnapshotSvc<T> {
+                        type Response = super::RevertSnapshotResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RevertSnapshotRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::revert_snapshot(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RevertSnapshotSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/BranchSnapshot" => {
+                    #[allow(non_camel_case_types)]
+                    struct BranchSnapshotSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::BranchSnapshotRequest>
+                    for BranchSnapshotSvc<T> {
+                        type Response = super::BranchSnapshotResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BranchSnapshotRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::branch_snapshot(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BranchSnapshotSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListSnapshotFiles" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSnapshotFilesSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListSnapshotFilesRequest>
+                    for ListSnapshotFilesSvc<T> {
+                        type Response = super::ListSnapshotFilesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListSnapshotFilesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_snapshot_files(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSnapshotFilesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ReadSnapshotFile" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReadSnapshotFileSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<
+                        super::ReadSnapshotFileRequest,
+                    > for ReadSnapshotFileSvc<T> {
+                        type Response = super::ReadSnapshotFileResponse;
+                        type ResponseStream = T::ReadSnapshotFileStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReadSnapshotFileRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::read_snapshot_file(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReadSnapshotFileSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DiffSnapshots" => {
+                    #[allow(non_camel_case_types)]
+                    struct DiffSnapshotsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DiffSnapshotsRequest>
+                    for DiffSnapshotsSvc<T> {
+                        type Response = super::DiffSnapshotsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DiffSnapshotsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::diff_snapshots(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DiffSnapshotsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateSnapshotPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateSnapshotPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateSnapshotPolicyRequest>
+                    for CreateSnapshotPolicySvc<T> {
+                        type Response = super::CreateSnapshotPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateSnapshotPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_snapshot_policy(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateSnapshotPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetSnapshotPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSnapshotPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetSnapshotPolicyRequest>
+                    for GetSnapshotPolicySvc<T> {
+                        type Response = super::GetSnapshotPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetSnapshotPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_snapshot_policy(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSnapshotPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/UpdateSnapshotPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateSnapshotPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::UpdateSnapshotPolicyRequest>
+                    for UpdateSnapshotPolicySvc<T> {
+                        type Response = super::UpdateSnapshotPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateSnapshotPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::update_snapshot_policy(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateSnapshotPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteSnapshotPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteSnapshotPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteSnapshotPolicyRequest>
+                    for DeleteSnapshotPolicySvc<T> {
+                        type Response = super::DeleteSnapshotPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteSnapshotPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_snapshot_policy(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteSnapshotPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListSnapshotPolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSnapshotPoliciesSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListSnapshotPoliciesRequest>
+                    for ListSnapshotPoliciesSvc<T> {
+                        type Response = super::ListSnapshotPoliciesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListSnapshotPoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_snapshot_policies(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSnapshotPoliciesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateBackup" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateBackupSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateBackupRequest>
+                    for CreateBackupSvc<T> {
+                        type Response = super::CreateBackupResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateBackupRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_backup(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateBackupSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetBackup" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBackupSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetBackupRequest>
+                    for GetBackupSvc<T> {
+                        type Response = super::GetBackupResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetBackupRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_backup(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBackupSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteBackup" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteBackupSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteBackupRequest>
+                    for DeleteBackupSvc<T> {
+                        type Response = super::DeleteBackupResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteBackupRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_backup(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteBackupSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListBackups" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListBackupsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListBackupsRequest>
+                    for ListBackupsSvc<T> {
+                        type Response = super::ListBackupsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListBackupsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_backups(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListBackupsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/RestoreBackup" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreBackupSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::RestoreBackupRequest>
+                    for RestoreBackupSvc<T> {
+                        type Response = super::RestoreBackupResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreBackupRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::restore_backup(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RestoreBackupSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateBackupPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateBackupPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateBackupPolicyRequest>
+                    for CreateBackupPolicySvc<T> {
+                        type Response = super::CreateBackupPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateBackupPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_backup_policy(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateBackupPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetBackupPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBackupPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetBackupPolicyRequest>
+                    for GetBackupPolicySvc<T> {
+                        type Response = super::GetBackupPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetBackupPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_backup_policy(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBackupPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/UpdateBackupPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateBackupPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::UpdateBackupPolicyRequest>
+                    for UpdateBackupPolicySvc<T> {
+                        type Response = super::UpdateBackupPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateBackupPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::update_backup_policy(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateBackupPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteBackupPolicy" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteBackupPolicySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteBackupPolicyRequest>
+                    for DeleteBackupPolicySvc<T> {
+                        type Response = super::DeleteBackupPolicyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteBackupPolicyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_backup_policy(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteBackupPolicySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListBackupPolicies" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListBackupPoliciesSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListBackupPoliciesRequest>
+                    for ListBackupPoliciesSvc<T> {
+                        type Response = super::ListBackupPoliciesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListBackupPoliciesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_backup_policies(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListBackupPoliciesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateArchive" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateArchiveSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateArchiveRequest>
+                    for CreateArchiveSvc<T> {
+                        type Response = super::CreateArchiveResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateArchiveRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_archive(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateArchiveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DownloadArchive" => {
+                    #[allow(non_camel_case_types)]
+                    struct DownloadArchiveSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<
+                        super::DownloadArchiveRequest,
+                    > for DownloadArchiveSvc<T> {
+                        type Response = super::DownloadArchiveResponse;
+                        type ResponseStream = T::DownloadArchiveStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DownloadArchiveRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::download_archive(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DownloadArchiveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/RestoreArchive" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreArchiveSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::RestoreArchiveRequest>
+                    for RestoreArchiveSvc<T> {
+                        type Response = super::RestoreArchiveResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreArchiveRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::restore_archive(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RestoreArchiveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateJob" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateJobSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateJobRequest>
+                    for CreateJobSvc<T> {
+                        type Response = super::CreateJobResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateJobRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_job(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateJobSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetJob" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetJobSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetJobRequest>
+                    for GetJobSvc<T> {
+                        type Response = super::GetJobResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetJobRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_job(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetJobSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListJobs" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListJobsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListJobsRequest>
+                    for ListJobsSvc<T> {
+                        type Response = super::ListJobsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListJobsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_jobs(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListJobsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CancelJob" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelJobSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CancelJobRequest>
+                    for CancelJobSvc<T> {
+                        type Response = super::CancelJobResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelJobRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::cancel_job(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelJobSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateBenchmarkRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateBenchmarkRunSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateBenchmarkRunRequest>
+                    for CreateBenchmarkRunSvc<T> {
+                        type Response = super::CreateBenchmarkRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateBenchmarkRunRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_benchmark_run(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateBenchmarkRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetBenchmarkRun" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetBenchmarkRunSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetBenchmarkRunRequest>
+                    for GetBenchmarkRunSvc<T> {
+                        type Response = super::GetBenchmarkRunResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetBenchmarkRunRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_benchmark_run(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetBenchmarkRunSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListBenchmarkRuns" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListBenchmarkRunsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListBenchmarkRunsRequest>
+                    for ListBenchmarkRunsSvc<T> {
+                        type Response = super::ListBenchmarkRunsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListBenchmarkRunsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_benchmark_runs(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListBenchmarkRunsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetAttestation" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAttestationSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetAttestationRequest>
+                    for GetAttestationSvc<T> {
+                        type Response = super::GetAttestationResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetAttestationRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_attestation(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetAttestationSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetTpmQuote" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTpmQuoteSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetTpmQuoteRequest>
+                    for GetTpmQuoteSvc<T> {
+                        type Response = super::GetTpmQuoteResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetTpmQuoteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_tpm_quote(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTpmQuoteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetVolumeProvenance" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVolumeProvenanceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetVolumeProvenanceRequest>
+                    for GetVolumeProvenanceSvc<T> {
+                        type Response = super::GetVolumeProvenanceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetVolumeProvenanceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_volume_provenance(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetVolumeProvenanceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateLoRaDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateLoRaDeviceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateLoRaDeviceRequest>
+                    for CreateLoRaDeviceSvc<T> {
+                        type Response = super::CreateLoRaDeviceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateLoRaDeviceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_lo_ra_device(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateLoRaDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetLoRaDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetLoRaDeviceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetLoRaDeviceRequest>
+                    for GetLoRaDeviceSvc<T> {
+                        type Response = super::GetLoRaDeviceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetLoRaDeviceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_lo_ra_device(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetLoRaDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteLoRaDevice" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteLoRaDeviceSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteLoRaDeviceRequest>
+                    for DeleteLoRaDeviceSvc<T> {
+                        type Response = super::DeleteLoRaDeviceResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteLoRaDeviceRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_lo_ra_device(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteLoRaDeviceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/WatchEvents" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchEventsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<super::WatchEventsRequest>
+                    for WatchEventsSvc<T> {
+                        type Response = super::Event;
+                        type ResponseStream = T::WatchEventsStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchEventsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::watch_events(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchEventsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetHealth" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetHealthSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetHealthRequest>
+                    for GetHealthSvc<T> {
+                        type Response = super::GetHealthResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetHealthRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_health(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetHealthSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetDaemonStatus" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetDaemonStatusSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetDaemonStatusRequest>
+                    for GetDaemonStatusSvc<T> {
+                        type Response = super::GetDaemonStatusResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetDaemonStatusRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_daemon_status(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetDaemonStatusSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/InspectArtifact" => {
+                    #[allow(non_camel_case_types)]
+                    struct InspectArtifactSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::InspectArtifactRequest>
+                    for InspectArtifactSvc<T> {
+                        type Response = super::InspectArtifactResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::InspectArtifactRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::inspect_artifact(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = InspectArtifactSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/SignPayload" => {
+                    #[allow(non_camel_case_types)]
+                    struct SignPayloadSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::SignPayloadRequest>
+                    for SignPayloadSvc<T> {
+                        type Response = super::SignPayloadResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SignPayloadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::sign_payload(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SignPayloadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetSigningKey" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSigningKeySvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetSigningKeyRequest>
+                    for GetSigningKeySvc<T> {
+                        type Response = super::GetSigningKeyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetSigningKeyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_signing_key(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSigningKeySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListAuditEvents" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListAuditEventsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListAuditEventsRequest>
+                    for ListAuditEventsSvc<T> {
+                        type Response = super::ListAuditEventsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListAuditEventsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_audit_events(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListAuditEventsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/VerifyAuditLog" => {
+                    #[allow(non_camel_case_types)]
+                    struct VerifyAuditLogSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::VerifyAuditLogRequest>
+                    for VerifyAuditLogSvc<T> {
+                        type Response = super::VerifyAuditLogResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::VerifyAuditLogRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::verify_audit_log(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = VerifyAuditLogSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetProvenanceChain" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetProvenanceChainSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetProvenanceChainRequest>
+                    for GetProvenanceChainSvc<T> {
+                        type Response = super::GetProvenanceChainResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetProvenanceChainRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_provenance_chain(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetProvenanceChainSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/CreateSecret" => {
+                    #[allow(non_camel_case_types)]
+                    struct CreateSecretSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::CreateSecretRequest>
+                    for CreateSecretSvc<T> {
+                        type Response = super::CreateSecretResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CreateSecretRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::create_secret(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CreateSecretSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetSecret" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSecretSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetSecretRequest>
+                    for GetSecretSvc<T> {
+                        type Response = super::GetSecretResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetSecretRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_secret(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSecretSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ListSecrets" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSecretsSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::ListSecretsRequest>
+                    for ListSecretsSvc<T> {
+                        type Response = super::ListSecretsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListSecretsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::list_secrets(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSecretsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/DeleteSecret" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteSecretSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::DeleteSecretRequest>
+                    for DeleteSecretSvc<T> {
+                        type Response = super::DeleteSecretResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeleteSecretRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::delete_secret(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DeleteSecretSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/GetReplicationStatus" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetReplicationStatusSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::GetReplicationStatusRequest>
+                    for GetReplicationStatusSvc<T> {
+                        type Response = super::ReplicationStatus;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetReplicationStatusRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::get_replication_status(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetReplicationStatusSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/StreamStateSnapshot" => {
+                    #[allow(non_camel_case_types)]
+                    struct StreamStateSnapshotSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<
+                        super::StreamStateSnapshotRequest,
+                    > for StreamStateSnapshotSvc<T> {
+                        type Response = super::StateSnapshotChunk;
+                        type ResponseStream = T::StreamStateSnapshotStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::StreamStateSnapshotRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::stream_state_snapshot(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = StreamStateSnapshotSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/ReadCasObject" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReadCasObjectSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::ServerStreamingService<super::ReadCasObjectRequest>
+                    for ReadCasObjectSvc<T> {
+                        type Response = super::ReadCasObjectResponse;
+                        type ResponseStream = T::ReadCasObjectStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReadCasObjectRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::read_cas_object(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReadCasObjectSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/infrasim.v1.InfraSimDaemon/PromoteDaemon" => {
+                    #[allow(non_camel_case_types)]
+                    struct PromoteDaemonSvc<T: InfraSimDaemon>(pub Arc<T>);
+                    impl<
+                        T: InfraSimDaemon,
+                    > tonic::server::UnaryService<super::PromoteDaemonRequest>
+                    for PromoteDaemonSvc<T> {
+                        type Response = super::PromoteDaemonResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PromoteDaemonRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as InfraSimDaemon>::promote_daemon(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PromoteDaemonSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: InfraSimDaemon> Clone for InfraSimDaemonServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: InfraSimDaemon> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: InfraSimDaemon> tonic::server::NamedService for InfraSimDaemonServer<T> {
+        const NAME: &'static str = "infrasim.v1.InfraSimDaemon";
+    }
+}
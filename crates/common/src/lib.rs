@@ -3,12 +3,21 @@
 //! Shared types, utilities, and infrastructure for the InfraSim platform.
 
 pub mod artifact;
+pub mod audit;
+pub mod auth;
+pub mod blockdev;
 pub mod cas;
 pub mod crypto;
 pub mod db;
 pub mod error;
 pub mod pipeline;
+pub mod provenance;
+pub mod qga;
 pub mod qmp;
+pub mod scenario;
+pub mod secrets;
+pub mod slsa;
+pub mod state_backup;
 pub mod types;
 pub mod attestation;
 pub mod traffic_shaper;
@@ -18,10 +27,10 @@ pub use artifact::{ArtifactInspector, ArtifactInspectionReport};
 pub use pipeline::{
     AnalysisReport, DependencyGraph, NetworkFingerprint, PipelineAnalyzer, TimingProbe,
 };
-pub use cas::ContentAddressedStore;
+pub use cas::{ContentAddressedStore, DirRemote, RemoteStore};
 pub use crypto::{KeyPair, Signer, Verifier};
-pub use db::Database;
-pub use error::{Error, Result};
+pub use db::{Database, Migration, MigrationUp, Migrations};
+pub use error::{Error, ErrorCategory, ErrorCode, ErrorDetail, Result};
 pub use types::*;
 
 /// InfraSim version
@@ -39,6 +48,17 @@ pub fn default_socket_path() -> std::path::PathBuf {
     default_store_path().join("daemon.sock")
 }
 
+/// Daemon address clients use when none is configured: the local daemon's
+/// Unix socket if it is serving one, else its loopback TCP port
+pub fn default_daemon_addr() -> String {
+    let socket = default_socket_path();
+    if socket.exists() {
+        format!("unix://{}", socket.display())
+    } else {
+        "http://127.0.0.1:50051".to_string()
+    }
+}
+
 /// Default database path
 pub fn default_db_path() -> std::path::PathBuf {
     default_store_path().join("state.db")
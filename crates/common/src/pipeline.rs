@@ -5,16 +5,22 @@
 //! - Vendor convergence pattern detection
 //! - Confounding pattern identification
 //! - Optional network timing probes (feature-gated, opt-in)
+//! - Vulnerability correlation against OSV/RustSec advisories
+//! - SBOM export as SPDX 2.3 or CycloneDX 1.5 JSON
 //!
 //! # Feature Flags
 //!
 //! - `network-context`: Enables optional network timing collection (OFF by default).
 //!   When enabled, timing probes are still opt-in and require explicit runtime enablement
 //!   via `NetworkTimingConfig`.
+//! - `osv`: Enables `AdvisoryDb::fetch` against the OSV.dev API (OFF by default).
+//!   Offline OSV databases work without it.
 
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[cfg(feature = "network-context")]
@@ -256,6 +262,9 @@ pub struct AnalysisReport {
     pub risk_score: f64,
     pub warnings: Vec<String>,
     pub recommendations: Vec<String>,
+    /// Known vulnerabilities, see [`AnalysisReport::correlate_advisories`]
+    #[serde(default)]
+    pub advisories: Vec<AdvisoryFinding>,
 }
 
 /// Detected dependency cycle
@@ -327,6 +336,37 @@ pub enum Severity {
     Critical,
 }
 
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Severity {
+    type Err = AnalysisError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "low" => Ok(Severity::Low),
+            "medium" | "moderate" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            other => Err(AnalysisError::Parse(format!(
+                "unknown severity '{}' (expected info, low, medium, high or critical)",
+                other
+            ))),
+        }
+    }
+}
+
 // ============================================================================
 // Network Timing Probes (Feature-gated, Opt-in)
 // ============================================================================
@@ -600,7 +640,98 @@ impl PipelineAnalyzer {
     /// Analyze a Cargo workspace
     pub fn analyze_cargo_workspace(&mut self, path: &Path) -> Result<AnalysisReport> {
         info!("Analyzing Cargo workspace: {}", path.display());
+        self.load_cargo_workspace(path)?;
+        Ok(self.analyze(path))
+    }
+
+    /// Analyze every supported manifest and lockfile under `path`: the Cargo
+    /// workspace at its root plus npm, pnpm, Go and Python lockfiles below it
+    pub fn analyze_workspace(&mut self, path: &Path) -> Result<AnalysisReport> {
+        info!("Analyzing workspace: {}", path.display());
+
+        if path.is_file() {
+            self.load_lockfile(path)?;
+            return Ok(self.analyze(path.parent().unwrap_or(path)));
+        }
+
+        let mut loaded = 0;
+        let mut first_error = None;
+        let cargo = path.join("Cargo.toml");
+        let sources = cargo
+            .exists()
+            .then_some(cargo)
+            .into_iter()
+            .chain(find_lockfiles(path));
+        for source in sources {
+            let result = if source.ends_with("Cargo.toml") {
+                self.load_cargo_workspace(path)
+            } else {
+                self.load_lockfile(&source)
+            };
+            match result {
+                Ok(()) => loaded += 1,
+                Err(e) => {
+                    warn!("Skipping {}: {}", source.display(), e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        if loaded == 0 {
+            return Err(first_error.unwrap_or_else(|| {
+                AnalysisError::Parse(format!(
+                    "No Cargo.toml or supported lockfile found in {}",
+                    path.display()
+                ))
+            }));
+        }
+        Ok(self.analyze(path))
+    }
+
+    /// Add a single lockfile's packages to the graph
+    pub fn load_lockfile(&mut self, path: &Path) -> Result<()> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        debug!("Loading lockfile: {}", path.display());
+
+        match file_name.as_str() {
+            "package-lock.json" | "npm-shrinkwrap.json" => {
+                let lock: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)
+                    .map_err(|e| AnalysisError::Parse(format!("{}: {}", path.display(), e)))?;
+                self.parse_package_lock(&lock, dir)
+            }
+            "pnpm-lock.yaml" => {
+                let lock: serde_yaml::Value = serde_yaml::from_str(&std::fs::read_to_string(path)?)
+                    .map_err(|e| AnalysisError::Parse(format!("{}: {}", path.display(), e)))?;
+                self.parse_pnpm_lock(&lock, dir)
+            }
+            "go.mod" | "go.sum" => {
+                let go_mod = std::fs::read_to_string(dir.join("go.mod")).ok();
+                let go_sum = std::fs::read_to_string(dir.join("go.sum")).ok();
+                self.parse_go_module(go_mod.as_deref(), go_sum.as_deref(), dir)
+            }
+            "poetry.lock" => {
+                let lock = read_toml(path)?;
+                let pyproject = read_toml(&dir.join("pyproject.toml")).ok();
+                self.parse_poetry_lock(&lock, pyproject.as_ref(), dir)
+            }
+            name if name.starts_with("requirements") && name.ends_with(".txt") => {
+                let pyproject = read_toml(&dir.join("pyproject.toml")).ok();
+                let root = self.python_root(pyproject.as_ref(), dir);
+                self.parse_requirements(path, &root, &mut HashSet::new())
+            }
+            _ => Err(AnalysisError::Parse(format!(
+                "Unsupported lockfile: {}",
+                path.display()
+            ))),
+        }
+    }
 
+    /// Add the packages of the Cargo workspace at `path` to the graph
+    fn load_cargo_workspace(&mut self, path: &Path) -> Result<()> {
         // Run cargo metadata
         let output = Command::new("cargo")
             .args(["metadata", "--format-version", "1", "--all-features"])
@@ -617,9 +748,26 @@ impl PipelineAnalyzer {
             .map_err(|e| AnalysisError::Parse(e.to_string()))?;
 
         self.parse_cargo_metadata(&metadata)?;
+
+        // Checksums only live in the lockfile
+        let workspace_root = metadata["workspace_root"]
+            .as_str()
+            .map(Path::new)
+            .unwrap_or(path);
+        match read_lock_checksums(&workspace_root.join("Cargo.lock")) {
+            Ok(checksums) => self.apply_checksums(&checksums),
+            Err(e) => warn!("No checksums from Cargo.lock: {}", e),
+        }
+
+        Ok(())
+    }
+
+    /// Run the detections over everything loaded so far
+    fn analyze(&mut self, path: &Path) -> AnalysisReport {
+        self.graph.metadata.source_path = path.display().to_string();
+        self.graph.metadata.git_commit = git_head(path);
         self.graph.compute_stats();
 
-        // Run analysis
         let mut report = AnalysisReport {
             graph: self.graph.clone(),
             ..Default::default()
@@ -628,9 +776,9 @@ impl PipelineAnalyzer {
         self.detect_cycles(&mut report);
         self.detect_vendor_convergence(&mut report);
         self.detect_suspicious_patterns(&mut report);
-        self.calculate_risk_score(&mut report);
+        report.calculate_risk_score();
 
-        Ok(report)
+        report
     }
 
     fn parse_cargo_metadata(&mut self, metadata: &serde_json::Value) -> Result<()> {
@@ -673,10 +821,16 @@ impl PipelineAnalyzer {
                         url: source_str.to_string(),
                     }
                 } else if source_str.starts_with("git+") {
+                    // git+<url>?branch=<b>#<commit>
+                    let branch = source_str
+                        .split('#')
+                        .next()
+                        .and_then(|s| s.split_once("?branch="))
+                        .map(|(_, b)| b.to_string());
                     DependencySource::Git {
                         url: source_str.to_string(),
-                        rev: None,
-                        branch: None,
+                        rev: source_str.split_once('#').map(|(_, rev)| rev.to_string()),
+                        branch,
                     }
                 } else {
                     DependencySource::Unknown
@@ -693,9 +847,15 @@ impl PipelineAnalyzer {
             };
 
             let mut node_metadata = HashMap::new();
+            node_metadata.insert("ecosystem".to_string(), Ecosystem::Cargo.to_string());
             if is_proc_macro {
                 node_metadata.insert("proc_macro".to_string(), "true".to_string());
             }
+            for key in ["license", "repository"] {
+                if let Some(value) = pkg[key].as_str() {
+                    node_metadata.insert(key.to_string(), value.to_string());
+                }
+            }
 
             let node = DependencyNode {
                 id: id.clone(),
@@ -758,15 +918,37 @@ impl PipelineAnalyzer {
                 }
             }
 
-            // Set root nodes
+            // Set root nodes; virtual workspaces have no root package
             if let Some(root) = resolve["root"].as_str() {
                 self.graph.root_nodes.push(root.to_string());
+            } else if let Some(members) = metadata["workspace_members"].as_array() {
+                self.graph.root_nodes.extend(
+                    members.iter().filter_map(|m| m.as_str()).map(|m| m.to_string()),
+                );
             }
         }
 
         Ok(())
     }
 
+    /// Fill node checksums from `(name, version, source) -> sha256`
+    fn apply_checksums(&mut self, checksums: &HashMap<(String, String, String), String>) {
+        for node in self.graph.nodes.values_mut() {
+            let source = match &node.source {
+                DependencySource::Registry { url, .. } | DependencySource::Git { url, .. } => url,
+                _ => continue,
+            };
+            let key = (
+                node.name.clone(),
+                node.version.clone().unwrap_or_default(),
+                source.clone(),
+            );
+            if let Some(checksum) = checksums.get(&key) {
+                node.checksum = Some(checksum.clone());
+            }
+        }
+    }
+
     fn detect_cycles(&self, report: &mut AnalysisReport) {
         let mut visited = HashSet::new();
         let mut rec_stack = HashSet::new();
@@ -912,6 +1094,21 @@ impl PipelineAnalyzer {
             }
         }
 
+        match Ecosystem::of(node) {
+            // npm scopes are owned by one publisher
+            Ecosystem::Npm => {
+                if let Some((scope, _)) = name.strip_prefix('@').and_then(|n| n.split_once('/')) {
+                    return scope.to_string();
+                }
+            }
+            // Go module paths name their host and owner
+            Ecosystem::Go => {
+                return extract_github_org(name)
+                    .unwrap_or_else(|| name.split('/').next().unwrap_or(name).to_string());
+            }
+            _ => {}
+        }
+
         // Check source URL
         match &node.source {
             DependencySource::Git { url, .. } => {
@@ -965,8 +1162,21 @@ impl PipelineAnalyzer {
     }
 
     fn detect_name_confusion(&self, report: &mut AnalysisReport) {
-        let names: Vec<&str> = self.graph.nodes.values().map(|n| n.name.as_str()).collect();
+        // Only names within one ecosystem can be confused for each other
+        let mut by_ecosystem: HashMap<Ecosystem, BTreeSet<&str>> = HashMap::new();
+        for node in self.graph.nodes.values() {
+            by_ecosystem
+                .entry(Ecosystem::of(node))
+                .or_default()
+                .insert(node.name.as_str());
+        }
+        for names in by_ecosystem.values() {
+            let names: Vec<&str> = names.iter().copied().collect();
+            Self::detect_similar_names(&names, report);
+        }
+    }
 
+    fn detect_similar_names(names: &[&str], report: &mut AnalysisReport) {
         // Check for similar names
         for i in 0..names.len() {
             for j in (i + 1)..names.len() {
@@ -1041,12 +1251,15 @@ impl PipelineAnalyzer {
             }
         }
     }
+}
 
-    fn calculate_risk_score(&self, report: &mut AnalysisReport) {
+impl AnalysisReport {
+    /// Score cycles, convergence, suspicious patterns and advisories (0-100)
+    fn calculate_risk_score(&mut self) {
         let mut score = 0.0;
 
         // Cycles
-        for cycle in &report.cycles {
+        for cycle in &self.cycles {
             score += match cycle.severity {
                 Severity::Critical => 30.0,
                 Severity::High => 20.0,
@@ -1057,7 +1270,7 @@ impl PipelineAnalyzer {
         }
 
         // Vendor convergence
-        for conv in &report.vendor_convergence {
+        for conv in &self.vendor_convergence {
             score += match conv.severity {
                 Severity::Critical => 20.0,
                 Severity::High => 15.0,
@@ -1068,7 +1281,7 @@ impl PipelineAnalyzer {
         }
 
         // Suspicious patterns
-        for pattern in &report.suspicious_patterns {
+        for pattern in &self.suspicious_patterns {
             score += pattern.confidence
                 * match pattern.severity {
                     Severity::Critical => 25.0,
@@ -1079,22 +1292,39 @@ impl PipelineAnalyzer {
                 };
         }
 
+        // Known vulnerabilities
+        for advisory in &self.advisories {
+            score += match advisory.severity {
+                Severity::Critical => 25.0,
+                Severity::High => 15.0,
+                Severity::Medium => 8.0,
+                Severity::Low => 3.0,
+                Severity::Info => 0.5,
+            };
+        }
+
         // Normalize to 0-100
-        report.risk_score = (score / 100.0 * 100.0).min(100.0);
+        self.risk_score = (score / 100.0 * 100.0).min(100.0);
 
         // Generate recommendations
-        if !report.cycles.is_empty() {
-            report.recommendations.push(
+        self.recommendations.clear();
+        if !self.cycles.is_empty() {
+            self.recommendations.push(
                 "Review and break dependency cycles to reduce build complexity".to_string(),
             );
         }
-        if !report.vendor_convergence.is_empty() {
-            report.recommendations.push(
+        if !self.vendor_convergence.is_empty() {
+            self.recommendations.push(
                 "Audit vendor-concentrated dependencies for supply chain risk".to_string(),
             );
         }
-        if report.risk_score > 50.0 {
-            report.recommendations.push(
+        if self.advisories.iter().any(|a| a.severity >= Severity::High) {
+            self.recommendations.push(
+                "Upgrade packages with high or critical severity advisories".to_string(),
+            );
+        }
+        if self.risk_score > 50.0 {
+            self.recommendations.push(
                 "Consider using cargo-vet or cargo-crev for dependency auditing".to_string(),
             );
         }
@@ -1108,132 +1338,2550 @@ impl Default for PipelineAnalyzer {
 }
 
 // ============================================================================
-// Utility Functions
+// Lockfile Parsers
 // ============================================================================
 
-/// Normalize a cycle by rotating it to start from the lexicographically smallest element.
-/// This ensures that A -> B -> C -> A and B -> C -> A -> B are detected as the same cycle.
-fn normalize_cycle(cycle: &[String]) -> Vec<String> {
-    if cycle.is_empty() {
-        return Vec::new();
-    }
+/// Package ecosystem of a dependency node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Go,
+    PyPI,
+}
 
-    // Find the index of the lexicographically smallest element
-    let min_idx = cycle
-        .iter()
-        .enumerate()
-        .min_by(|(_, a), (_, b)| a.cmp(b))
-        .map(|(i, _)| i)
-        .unwrap_or(0);
+impl Ecosystem {
+    /// Ecosystem recorded in the node's `ecosystem` metadata (Cargo if absent)
+    pub fn of(node: &DependencyNode) -> Self {
+        node.metadata
+            .get("ecosystem")
+            .and_then(|e| e.parse().ok())
+            .unwrap_or(Ecosystem::Cargo)
+    }
 
-    // Rotate the cycle to start from the smallest element
-    let mut normalized = Vec::with_capacity(cycle.len());
-    normalized.extend_from_slice(&cycle[min_idx..]);
-    normalized.extend_from_slice(&cycle[..min_idx]);
+    /// Package URL type
+    pub fn purl_type(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Go => "golang",
+            Ecosystem::PyPI => "pypi",
+        }
+    }
+}
 
-    normalized
+impl std::fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Go => "go",
+            Ecosystem::PyPI => "pypi",
+        };
+        write!(f, "{}", s)
+    }
 }
 
-fn extract_github_org(url: &str) -> Option<String> {
-    // Extract org from URLs like "https://github.com/org/repo" or "git+https://..."
-    let url = url.trim_start_matches("git+");
-    if url.contains("github.com") {
-        let parts: Vec<&str> = url.split('/').collect();
-        for (i, part) in parts.iter().enumerate() {
-            if *part == "github.com" && i + 1 < parts.len() {
-                return Some(parts[i + 1].to_string());
-            }
+impl FromStr for Ecosystem {
+    type Err = AnalysisError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "cargo" => Ok(Ecosystem::Cargo),
+            "npm" => Ok(Ecosystem::Npm),
+            "go" => Ok(Ecosystem::Go),
+            "pypi" => Ok(Ecosystem::PyPI),
+            other => Err(AnalysisError::Parse(format!("unknown ecosystem '{}'", other))),
         }
     }
-    None
 }
 
-fn levenshtein_distance(a: &str, b: &str) -> usize {
-    let a_chars: Vec<char> = a.chars().collect();
-    let b_chars: Vec<char> = b.chars().collect();
-    let a_len = a_chars.len();
-    let b_len = b_chars.len();
+impl PipelineAnalyzer {
+    /// npm v7+ `package-lock.json` / `npm-shrinkwrap.json`
+    fn parse_package_lock(&mut self, lock: &serde_json::Value, dir: &Path) -> Result<()> {
+        let packages = lock["packages"].as_object().ok_or_else(|| {
+            AnalysisError::Parse(format!(
+                "package-lock.json version {} has no \"packages\"; regenerate it with npm 7 or later",
+                lock["lockfileVersion"]
+            ))
+        })?;
+
+        // Node ids by install location; "" is the project itself
+        let mut ids: HashMap<&str, String> = HashMap::new();
+        for (location, pkg) in packages {
+            if pkg["link"].as_bool() == Some(true) {
+                continue;
+            }
+            let name = pkg["name"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| npm_location_name(location, dir));
+            let source = if location.contains("node_modules/") {
+                npm_source(pkg["resolved"].as_str())
+            } else {
+                DependencySource::Path {
+                    path: dir.join(location).display().to_string(),
+                }
+            };
+            let mut node = package_node(Ecosystem::Npm, &name, pkg["version"].as_str(), source);
+            for key in ["integrity", "license"] {
+                if let Some(value) = pkg[key].as_str() {
+                    node.metadata.insert(key.to_string(), value.to_string());
+                }
+            }
+            ids.insert(location, self.insert_node(node));
+        }
 
-    if a_len == 0 {
-        return b_len;
-    }
-    if b_len == 0 {
-        return a_len;
-    }
+        // Workspace symlinks resolve to the linked package
+        for (location, pkg) in packages {
+            if pkg["link"].as_bool() == Some(true) {
+                if let Some(target) = pkg["resolved"].as_str().and_then(|t| ids.get(t)).cloned() {
+                    ids.insert(location, target);
+                }
+            }
+        }
 
-    let mut matrix = vec![vec![0usize; b_len + 1]; a_len + 1];
+        for (location, pkg) in packages {
+            let Some(from) = ids.get(location.as_str()).cloned() else {
+                continue;
+            };
+            if pkg["link"].as_bool() == Some(true) {
+                continue;
+            }
+            for (field, kind, optional) in [
+                ("dependencies", EdgeKind::Normal, false),
+                ("optionalDependencies", EdgeKind::Normal, true),
+                ("devDependencies", EdgeKind::Dev, false),
+            ] {
+                for name in pkg[field].as_object().into_iter().flat_map(|deps| deps.keys()) {
+                    match npm_resolve(packages, location, name).and_then(|l| ids.get(l)) {
+                        Some(to) => self.add_dependency(&from, to, kind.clone(), optional),
+                        // Optional and platform-specific packages may not be installed
+                        None => debug!("{} is not installed for '{}'", name, location),
+                    }
+                }
+            }
+        }
 
-    for i in 0..=a_len {
-        matrix[i][0] = i;
-    }
-    for j in 0..=b_len {
-        matrix[0][j] = j;
+        if let Some(root) = ids.get("") {
+            self.graph.root_nodes.push(root.clone());
+        }
+        Ok(())
     }
 
-    for i in 1..=a_len {
-        for j in 1..=b_len {
-            let cost = if a_chars[i - 1] == b_chars[j - 1] { 0 } else { 1 };
-            matrix[i][j] = (matrix[i - 1][j] + 1)
-                .min(matrix[i][j - 1] + 1)
-                .min(matrix[i - 1][j - 1] + cost);
+    /// pnpm `pnpm-lock.yaml`, lockfile versions 6 and 9
+    fn parse_pnpm_lock(&mut self, lock: &serde_yaml::Value, dir: &Path) -> Result<()> {
+        let version = match &lock["lockfileVersion"] {
+            serde_yaml::Value::String(s) => s.clone(),
+            serde_yaml::Value::Number(n) => n.to_string(),
+            _ => String::new(),
+        };
+        let major: u32 = version
+            .split('.')
+            .next()
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(0);
+        if major < 6 {
+            return Err(AnalysisError::Parse(format!(
+                "pnpm lockfileVersion '{}' is not supported; regenerate it with pnpm 8 or later",
+                version
+            )));
+        }
+
+        for (key, pkg) in yaml_entries(&lock["packages"]) {
+            let Some((name, version)) = pnpm_package_key(key) else {
+                continue;
+            };
+            let resolution = &pkg["resolution"];
+            let source = if let Some(repo) = resolution["repo"].as_str() {
+                DependencySource::Git {
+                    url: repo.to_string(),
+                    rev: resolution["commit"].as_str().map(str::to_string),
+                    branch: None,
+                }
+            } else {
+                DependencySource::Registry {
+                    name: "npm".to_string(),
+                    url: resolution["tarball"]
+                        .as_str()
+                        .unwrap_or(NPM_REGISTRY)
+                        .to_string(),
+                }
+            };
+            let mut node = package_node(Ecosystem::Npm, name, Some(version), source);
+            if let Some(integrity) = resolution["integrity"].as_str() {
+                node.metadata.insert("integrity".to_string(), integrity.to_string());
+            }
+            self.insert_node(node);
+        }
+
+        // v9 keeps the resolved dependencies in "snapshots", v6 in "packages"
+        let snapshots = if lock["snapshots"].is_mapping() {
+            &lock["snapshots"]
+        } else {
+            &lock["packages"]
+        };
+        for (key, snapshot) in yaml_entries(snapshots) {
+            let Some((name, version)) = pnpm_package_key(key) else {
+                continue;
+            };
+            let from = package_id(Ecosystem::Npm, name, Some(version));
+            for (field, optional) in [("dependencies", false), ("optionalDependencies", true)] {
+                for (dep, dep_version) in yaml_entries(&snapshot[field]) {
+                    let to = dep_version
+                        .as_str()
+                        .and_then(|v| pnpm_dependency_id(dep, v));
+                    if let Some(to) = to.filter(|to| self.graph.nodes.contains_key(to)) {
+                        self.add_dependency(&from, &to, EdgeKind::Normal, optional);
+                    }
+                }
+            }
+        }
+
+        // Workspace projects
+        let mut importers: HashMap<String, String> = HashMap::new();
+        for (path, _) in yaml_entries(&lock["importers"]) {
+            let manifest = std::fs::read_to_string(dir.join(path).join("package.json"))
+                .ok()
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                .unwrap_or_default();
+            let name = manifest["name"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| dir_name(&dir.join(path)));
+            let node = package_node(
+                Ecosystem::Npm,
+                &name,
+                manifest["version"].as_str(),
+                DependencySource::Path {
+                    path: dir.join(path).display().to_string(),
+                },
+            );
+            importers.insert(join_relative(path, ""), self.insert_node(node));
         }
+        for (path, importer) in yaml_entries(&lock["importers"]) {
+            let from = importers[&join_relative(path, "")].clone();
+            for (field, kind, optional) in [
+                ("dependencies", EdgeKind::Normal, false),
+                ("optionalDependencies", EdgeKind::Normal, true),
+                ("devDependencies", EdgeKind::Dev, false),
+            ] {
+                for (dep, spec) in yaml_entries(&importer[field]) {
+                    let Some(version) = spec["version"].as_str() else {
+                        continue;
+                    };
+                    let to = match version.strip_prefix("link:") {
+                        Some(link) => importers.get(&join_relative(path, link)).cloned(),
+                        None => pnpm_dependency_id(dep, version),
+                    };
+                    if let Some(to) = to.filter(|to| self.graph.nodes.contains_key(to)) {
+                        self.add_dependency(&from, &to, kind.clone(), optional);
+                    }
+                }
+            }
+            self.graph.root_nodes.push(from);
+        }
+        Ok(())
     }
 
-    matrix[a_len][b_len]
-}
+    /// Go modules from `go.mod`, with module hashes from `go.sum`
+    fn parse_go_module(&mut self, go_mod: Option<&str>, go_sum: Option<&str>, dir: &Path) -> Result<()> {
+        // (module, version) -> h1: hash of the module tree
+        let mut sums: BTreeMap<(&str, &str), &str> = BTreeMap::new();
+        for line in go_sum.unwrap_or_default().lines() {
+            let mut fields = line.split_whitespace();
+            if let (Some(module), Some(version), Some(hash)) = (fields.next(), fields.next(), fields.next()) {
+                if !version.ends_with("/go.mod") {
+                    sums.insert((module, version), hash);
+                }
+            }
+        }
 
-// ============================================================================
-// Tests
-// ============================================================================
+        let vendored = dir.join("vendor").join("modules.txt").exists();
+        let source = |module: &str| {
+            if vendored {
+                DependencySource::Vendored {
+                    path: dir.join("vendor").join(module).display().to_string(),
+                }
+            } else {
+                DependencySource::Registry {
+                    name: "proxy.golang.org".to_string(),
+                    url: GO_PROXY.to_string(),
+                }
+            }
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let Some(go_mod) = go_mod else {
+            // Without go.mod there is no module graph, only what was downloaded
+            for ((module, version), hash) in &sums {
+                let mut node = package_node(Ecosystem::Go, module, Some(version), source(module));
+                node.metadata.insert("go_sum".to_string(), hash.to_string());
+                self.insert_node(node);
+            }
+            return Ok(());
+        };
 
-    #[test]
-    fn test_levenshtein_distance() {
-        assert_eq!(levenshtein_distance("", ""), 0);
-        assert_eq!(levenshtein_distance("abc", "abc"), 0);
-        assert_eq!(levenshtein_distance("abc", "abd"), 1);
-        assert_eq!(levenshtein_distance("serde", "serda"), 1);
-        assert_eq!(levenshtein_distance("tokio", "tokyo"), 1);
-    }
+        let go_mod = GoMod::parse(go_mod)?;
+        let root = self.insert_node(package_node(
+            Ecosystem::Go,
+            &go_mod.module,
+            None,
+            DependencySource::Path {
+                path: dir.display().to_string(),
+            },
+        ));
+        self.graph.root_nodes.push(root.clone());
+
+        for (module, version, indirect) in &go_mod.requires {
+            let replacement = go_mod
+                .replaces
+                .get(&format!("{} {}", module, version))
+                .or_else(|| go_mod.replaces.get(module.as_str()));
+            let (source, hash_of) = match replacement {
+                Some((target, None)) => (
+                    DependencySource::Path {
+                        path: dir.join(target).display().to_string(),
+                    },
+                    None,
+                ),
+                Some((target, Some(target_version))) => {
+                    (source(target), Some((target.as_str(), target_version.as_str())))
+                }
+                None => (source(module), Some((module.as_str(), version.as_str()))),
+            };
 
-    #[test]
-    fn test_extract_github_org() {
-        assert_eq!(
-            extract_github_org("https://github.com/tokio-rs/tokio"),
-            Some("tokio-rs".to_string())
-        );
-        assert_eq!(
-            extract_github_org("git+https://github.com/serde-rs/serde"),
-            Some("serde-rs".to_string())
-        );
+            let mut node = package_node(Ecosystem::Go, module, Some(version), source);
+            if let Some((target, target_version)) = replacement.and_then(|(t, v)| Some((t, v.as_ref()?))) {
+                node.metadata
+                    .insert("replaced_by".to_string(), format!("{} {}", target, target_version));
+            }
+            if let Some(hash) = hash_of.and_then(|key| sums.get(&key)) {
+                node.metadata.insert("go_sum".to_string(), hash.to_string());
+            }
+            if *indirect {
+                node.metadata.insert("indirect".to_string(), "true".to_string());
+            }
+            let id = self.insert_node(node);
+            self.add_dependency(&root, &id, EdgeKind::Normal, false);
+        }
+        Ok(())
     }
 
-    #[test]
-    fn test_normalize_cycle() {
-        // Cycle A -> B -> C should normalize to A -> B -> C (A is smallest)
-        let cycle = vec!["a".to_string(), "b".to_string(), "c".to_string()];
-        assert_eq!(normalize_cycle(&cycle), vec!["a", "b", "c"]);
+    /// Poetry `poetry.lock`, rooted at the project in `pyproject.toml`
+    fn parse_poetry_lock(&mut self, lock: &toml::Value, pyproject: Option<&toml::Value>, dir: &Path) -> Result<()> {
+        let packages = lock
+            .get("package")
+            .and_then(|p| p.as_array())
+            .ok_or_else(|| AnalysisError::Parse("poetry.lock has no packages".to_string()))?;
 
-        // Cycle C -> A -> B should normalize to A -> B -> C
-        let cycle2 = vec!["c".to_string(), "a".to_string(), "b".to_string()];
-        assert_eq!(normalize_cycle(&cycle2), vec!["a", "b", "c"]);
+        // Node ids by normalized name
+        let mut ids: HashMap<String, String> = HashMap::new();
+        for pkg in packages {
+            let Some(name) = pkg.get("name").and_then(|n| n.as_str()) else {
+                continue;
+            };
+            let version = pkg.get("version").and_then(|v| v.as_str());
+            let source = poetry_source(pkg.get("source"), dir);
+            let mut node = package_node(Ecosystem::PyPI, name, version, source);
+
+            // The sdist hash identifies the release independently of platform wheels
+            node.checksum = pkg
+                .get("files")
+                .and_then(|f| f.as_array())
+                .into_iter()
+                .flatten()
+                .find(|f| {
+                    f.get("file")
+                        .and_then(|f| f.as_str())
+                        .is_some_and(|f| f.ends_with(".tar.gz"))
+                })
+                .and_then(|f| f.get("hash")?.as_str()?.strip_prefix("sha256:"))
+                .map(str::to_string);
+            if pkg.get("category").and_then(|c| c.as_str()) == Some("dev") {
+                node.metadata.insert("dev".to_string(), "true".to_string());
+            }
+            ids.insert(python_name(name), self.insert_node(node));
+        }
 
-        // Cycle B -> C -> A should normalize to A -> B -> C
-        let cycle3 = vec!["b".to_string(), "c".to_string(), "a".to_string()];
-        assert_eq!(normalize_cycle(&cycle3), vec!["a", "b", "c"]);
+        for pkg in packages {
+            let Some(from) = pkg
+                .get("name")
+                .and_then(|n| n.as_str())
+                .and_then(|n| ids.get(&python_name(n)))
+                .cloned()
+            else {
+                continue;
+            };
+            for (dep, spec) in pkg.get("dependencies").and_then(|d| d.as_table()).into_iter().flatten() {
+                let optional = spec.get("optional").and_then(|o| o.as_bool()).unwrap_or(false);
+                if let Some(to) = ids.get(&python_name(dep)) {
+                    self.add_dependency(&from, to, EdgeKind::Normal, optional);
+                }
+            }
+        }
+
+        let root = self.python_root(pyproject, dir);
+        for (name, kind) in pyproject.map(pyproject_dependencies).unwrap_or_default() {
+            if let Some(to) = ids.get(&python_name(&name)) {
+                self.add_dependency(&root, to, kind, false);
+            }
+        }
+        Ok(())
     }
 
-    #[test]
-    fn test_adjacency_lists() {
-        let mut graph = DependencyGraph::new();
+    /// pip `requirements*.txt`, following `-r` includes
+    fn parse_requirements(&mut self, path: &Path, root: &str, seen: &mut HashSet<PathBuf>) -> Result<()> {
+        if !seen.insert(path.to_path_buf()) {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(path)?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let kind = if file_name.contains("dev") || file_name.contains("test") {
+            EdgeKind::Dev
+        } else {
+            EdgeKind::Normal
+        };
 
-        graph.add_node(DependencyNode {
-            id: "a".to_string(),
-            name: "a".to_string(),
-            version: None,
+        for line in content.replace("\\\n", " ").lines() {
+            let line = line.split(" #").next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut tokens = line.split_whitespace();
+            let first = tokens.next().unwrap_or_default();
+
+            let include = match first {
+                "-r" | "--requirement" => tokens.next(),
+                _ => first.strip_prefix("--requirement="),
+            };
+            if let Some(include) = include {
+                let include = path.parent().unwrap_or(Path::new(".")).join(include);
+                self.parse_requirements(&include, root, seen)?;
+                continue;
+            }
+            let spec = match first {
+                "-e" | "--editable" => tokens.collect::<Vec<_>>().join(" "),
+                _ if first.starts_with('-') => continue,
+                _ => line
+                    .split(" --")
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+            let Some((name, version, source)) = parse_requirement(&spec, path) else {
+                debug!("Skipping requirement '{}'", line);
+                continue;
+            };
+
+            let mut node = package_node(Ecosystem::PyPI, &name, version.as_deref(), source);
+            if version.is_none() {
+                let specifier = spec
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches(|c: char| c.is_alphanumeric() || "-_.[]".contains(c));
+                if !specifier.trim().is_empty() {
+                    node.metadata.insert("specifier".to_string(), specifier.trim().to_string());
+                }
+            }
+            node.checksum = line
+                .split_whitespace()
+                .find_map(|t| t.strip_prefix("--hash=sha256:"))
+                .map(str::to_string);
+            let id = self.insert_node(node);
+            self.add_dependency(root, &id, kind.clone(), false);
+        }
+        Ok(())
+    }
+
+    /// Root node for a Python project, named from `pyproject.toml` or its directory
+    fn python_root(&mut self, pyproject: Option<&toml::Value>, dir: &Path) -> String {
+        let project = pyproject.and_then(|p| {
+            p.get("project")
+                .filter(|p| p.get("name").is_some())
+                .or_else(|| p.get("tool")?.get("poetry"))
+        });
+        let field = |key: &str| project.and_then(|p| p.get(key)).and_then(|v| v.as_str());
+        let name = field("name")
+            .map(str::to_string)
+            .unwrap_or_else(|| dir_name(dir));
+        let node = package_node(
+            Ecosystem::PyPI,
+            &name,
+            field("version"),
+            DependencySource::Path {
+                path: dir.display().to_string(),
+            },
+        );
+        let id = self.insert_node(node);
+        if !self.graph.root_nodes.contains(&id) {
+            self.graph.root_nodes.push(id.clone());
+        }
+        id
+    }
+
+    /// Insert a node unless it is already known, returning its id
+    fn insert_node(&mut self, node: DependencyNode) -> String {
+        let id = node.id.clone();
+        self.graph.nodes.entry(id.clone()).or_insert(node);
+        id
+    }
+
+    fn add_dependency(&mut self, from: &str, to: &str, kind: EdgeKind, optional: bool) {
+        if self.graph.outgoing_neighbors(from).iter().any(|n| n == to) {
+            return;
+        }
+        self.graph.add_edge(DependencyEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+            optional,
+            features: vec![],
+        });
+    }
+}
+
+const NPM_REGISTRY: &str = "https://registry.npmjs.org";
+const GO_PROXY: &str = "https://proxy.golang.org";
+
+/// `<ecosystem>:<name>@<version>`, the node id of a non-Cargo package
+fn package_id(ecosystem: Ecosystem, name: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{}:{}@{}", ecosystem, name, version),
+        None => format!("{}:{}", ecosystem, name),
+    }
+}
+
+fn package_node(ecosystem: Ecosystem, name: &str, version: Option<&str>, source: DependencySource) -> DependencyNode {
+    DependencyNode {
+        id: package_id(ecosystem, name, version),
+        name: name.to_string(),
+        version: version.map(str::to_string),
+        source,
+        checksum: None,
+        metadata: HashMap::from([("ecosystem".to_string(), ecosystem.to_string())]),
+    }
+}
+
+/// Package name from an npm install location, or the directory name for the project
+fn npm_location_name(location: &str, dir: &Path) -> String {
+    match location.rfind("node_modules/") {
+        Some(i) => location[i + "node_modules/".len()..].to_string(),
+        None if !location.is_empty() => location.rsplit('/').next().unwrap_or(location).to_string(),
+        None => dir_name(dir),
+    }
+}
+
+fn dir_name(dir: &Path) -> String {
+    dir.canonicalize()
+        .ok()
+        .as_deref()
+        .unwrap_or(dir)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string())
+}
+
+fn npm_source(resolved: Option<&str>) -> DependencySource {
+    match resolved {
+        Some(url) if url.starts_with("git") => DependencySource::Git {
+            url: url.split('#').next().unwrap_or(url).to_string(),
+            rev: url.split_once('#').map(|(_, rev)| rev.to_string()),
+            branch: None,
+        },
+        Some(path) if path.starts_with("file:") => DependencySource::Path {
+            path: path.trim_start_matches("file:").to_string(),
+        },
+        Some(url) => DependencySource::Registry {
+            name: "npm".to_string(),
+            url: url.to_string(),
+        },
+        None => DependencySource::Unknown,
+    }
+}
+
+/// Install location `name` resolves to from `from`, walking up node_modules like Node does
+fn npm_resolve<'a>(
+    packages: &'a serde_json::Map<String, serde_json::Value>,
+    from: &str,
+    name: &str,
+) -> Option<&'a str> {
+    let mut base = from;
+    loop {
+        let candidate = if base.is_empty() {
+            format!("node_modules/{}", name)
+        } else {
+            format!("{}/node_modules/{}", base, name)
+        };
+        if let Some((location, _)) = packages.get_key_value(&candidate) {
+            return Some(location.as_str());
+        }
+        if base.is_empty() {
+            return None;
+        }
+        base = match base.rfind("node_modules/") {
+            Some(i) => base[..i].trim_end_matches('/'),
+            None => "",
+        };
+    }
+}
+
+/// Split a pnpm package key (`/name@1.0.0`, `@scope/name@1.0.0(peer@2.0.0)`) into name and version
+fn pnpm_package_key(key: &str) -> Option<(&str, &str)> {
+    let key = key.trim_start_matches('/');
+    let key = key.split('(').next().unwrap_or(key);
+    let at = key[1..].find('@')? + 1;
+    Some((&key[..at], &key[at + 1..]))
+}
+
+/// Node id a pnpm dependency reference points to; aliases carry their own name
+fn pnpm_dependency_id(name: &str, version: &str) -> Option<String> {
+    if version.starts_with("link:") {
+        return None;
+    }
+    let version = version.split('(').next().unwrap_or(version);
+    let (name, version) = pnpm_package_key(version).unwrap_or((name, version));
+    Some(package_id(Ecosystem::Npm, name, Some(version)))
+}
+
+fn yaml_entries(value: &serde_yaml::Value) -> impl Iterator<Item = (&str, &serde_yaml::Value)> {
+    value
+        .as_mapping()
+        .into_iter()
+        .flatten()
+        .filter_map(|(k, v)| Some((k.as_str()?, v)))
+}
+
+/// Resolve a relative path against a workspace-relative base, e.g. `apps/web` + `../../lib`
+fn join_relative(base: &str, rel: &str) -> String {
+    let mut parts: Vec<&str> = base
+        .split('/')
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+    for part in rel.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+/// The `module`, `require` and `replace` directives of a go.mod
+#[derive(Debug, Default)]
+struct GoMod {
+    module: String,
+    /// (module, version, indirect)
+    requires: Vec<(String, String, bool)>,
+    /// "module" or "module version" -> (target, target version; None for local paths)
+    replaces: HashMap<String, (String, Option<String>)>,
+}
+
+impl GoMod {
+    fn parse(content: &str) -> Result<Self> {
+        let mut go_mod = GoMod::default();
+        let mut block: Option<String> = None;
+
+        for line in content.lines() {
+            let (code, comment) = line.split_once("//").unwrap_or((line, ""));
+            let code = code.trim();
+            if code.is_empty() {
+                continue;
+            }
+            if code == ")" {
+                block = None;
+                continue;
+            }
+            let (directive, args) = match &block {
+                Some(directive) => (directive.as_str(), code),
+                None => code.split_once(char::is_whitespace).unwrap_or((code, "")),
+            };
+            let args = args.trim();
+            if args == "(" {
+                block = Some(directive.to_string());
+                continue;
+            }
+
+            match directive {
+                "module" => go_mod.module = args.trim_matches('"').to_string(),
+                "require" => {
+                    let mut fields = args.split_whitespace();
+                    if let (Some(module), Some(version)) = (fields.next(), fields.next()) {
+                        go_mod.requires.push((
+                            module.to_string(),
+                            version.to_string(),
+                            comment.trim() == "indirect",
+                        ));
+                    }
+                }
+                "replace" => {
+                    let Some((from, to)) = args.split_once("=>") else {
+                        continue;
+                    };
+                    let mut to = to.split_whitespace();
+                    if let Some(target) = to.next() {
+                        go_mod.replaces.insert(
+                            from.split_whitespace().collect::<Vec<_>>().join(" "),
+                            (target.to_string(), to.next().map(str::to_string)),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if go_mod.module.is_empty() {
+            return Err(AnalysisError::Parse("go.mod has no module directive".to_string()));
+        }
+        Ok(go_mod)
+    }
+}
+
+/// PEP 503 normalized Python package name
+fn python_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+fn poetry_source(source: Option<&toml::Value>, dir: &Path) -> DependencySource {
+    let field = |key: &str| source.and_then(|s| s.get(key)).and_then(|v| v.as_str());
+    match (field("type"), field("url")) {
+        (Some("git"), Some(url)) => DependencySource::Git {
+            url: url.to_string(),
+            rev: field("resolved_reference").or(field("reference")).map(str::to_string),
+            branch: field("reference")
+                .filter(|r| Some(*r) != field("resolved_reference"))
+                .map(str::to_string),
+        },
+        (Some("directory" | "file"), Some(path)) => DependencySource::Path {
+            path: dir.join(path).display().to_string(),
+        },
+        (Some(_), Some(url)) => DependencySource::Registry {
+            name: field("reference").unwrap_or("pypi").to_string(),
+            url: url.to_string(),
+        },
+        _ => DependencySource::Registry {
+            name: "pypi".to_string(),
+            url: "https://pypi.org/simple".to_string(),
+        },
+    }
+}
+
+/// Direct dependencies declared in a pyproject.toml (Poetry or PEP 621)
+fn pyproject_dependencies(pyproject: &toml::Value) -> Vec<(String, EdgeKind)> {
+    let mut deps = Vec::new();
+    let table_keys = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_table())
+            .map(|t| t.keys().filter(|k| *k != "python").cloned().collect())
+            .unwrap_or_default()
+    };
+
+    if let Some(poetry) = pyproject.get("tool").and_then(|t| t.get("poetry")) {
+        deps.extend(table_keys(poetry.get("dependencies")).into_iter().map(|d| (d, EdgeKind::Normal)));
+        deps.extend(table_keys(poetry.get("dev-dependencies")).into_iter().map(|d| (d, EdgeKind::Dev)));
+        for group in poetry.get("group").and_then(|g| g.as_table()).into_iter().flat_map(|g| g.values()) {
+            deps.extend(table_keys(group.get("dependencies")).into_iter().map(|d| (d, EdgeKind::Dev)));
+        }
+    }
+    if let Some(project) = pyproject.get("project") {
+        let requirements = project
+            .get("dependencies")
+            .and_then(|d| d.as_array())
+            .into_iter()
+            .flatten()
+            .map(|r| (r, EdgeKind::Normal))
+            .chain(
+                project
+                    .get("optional-dependencies")
+                    .and_then(|d| d.as_table())
+                    .into_iter()
+                    .flat_map(|t| t.values())
+                    .filter_map(|v| v.as_array())
+                    .flatten()
+                    .map(|r| (r, EdgeKind::Dev)),
+            );
+        for (requirement, kind) in requirements {
+            if let Some(name) = requirement.as_str().and_then(requirement_name) {
+                deps.push((name.to_string(), kind));
+            }
+        }
+    }
+    deps
+}
+
+/// Leading distribution name of a PEP 508 requirement
+fn requirement_name(requirement: &str) -> Option<&str> {
+    let requirement = requirement.trim();
+    let end = requirement
+        .find(|c: char| !(c.is_alphanumeric() || "-_.".contains(c)))
+        .unwrap_or(requirement.len());
+    (end > 0).then(|| &requirement[..end])
+}
+
+/// Name, pinned version and source of a pip requirement line
+fn parse_requirement(spec: &str, file: &Path) -> Option<(String, Option<String>, DependencySource)> {
+    let spec = spec.split(';').next().unwrap_or_default().trim();
+
+    // VCS and local requirements: `name @ git+https://...`, `git+https://...#egg=name`, `./pkg`
+    let (name, url) = match spec.split_once('@') {
+        Some((name, url)) if url.contains("://") || url.trim_start().starts_with("file:") => {
+            (requirement_name(name).map(str::to_string), Some(url.trim()))
+        }
+        _ if spec.contains("://") || spec.starts_with('.') || spec.starts_with('/') => {
+            let egg = spec
+                .split_once("#egg=")
+                .map(|(_, egg)| egg.split('&').next().unwrap_or(egg).to_string());
+            (egg, Some(spec))
+        }
+        _ => (None, None),
+    };
+    if let Some(url) = url {
+        let name = name.or_else(|| {
+            let path = url.split('#').next().unwrap_or(url).trim_end_matches('/');
+            requirement_name(path.rsplit('/').next()?).map(str::to_string)
+        })?;
+        let source = if url.starts_with("git+") {
+            pip_vcs_source(url)
+        } else {
+            let path = url.trim_start_matches("file://").trim_start_matches("file:");
+            DependencySource::Path {
+                path: file.parent().unwrap_or(Path::new(".")).join(path).display().to_string(),
+            }
+        };
+        return Some((name, None, source));
+    }
+
+    let name = requirement_name(spec)?;
+    let specifier = spec[name.len()..].trim_start();
+    let specifier = match specifier.strip_prefix('[') {
+        Some(rest) => rest.split_once(']').map(|(_, s)| s.trim_start()).unwrap_or(""),
+        None => specifier,
+    };
+    let version = specifier
+        .strip_prefix("==")
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && !v.contains([',', '*']))
+        .map(str::to_string);
+    Some((
+        name.to_string(),
+        version,
+        DependencySource::Registry {
+            name: "pypi".to_string(),
+            url: "https://pypi.org/simple".to_string(),
+        },
+    ))
+}
+
+/// `git+https://host/org/repo@rev#egg=name` as a git source pinned to `rev`
+fn pip_vcs_source(url: &str) -> DependencySource {
+    let url = url.split('#').next().unwrap_or(url);
+    let scheme_end = url.find("://").map(|i| i + 3).unwrap_or(0);
+    let path_start = url[scheme_end..]
+        .find('/')
+        .map(|i| scheme_end + i)
+        .unwrap_or(url.len());
+    match url[path_start..].rfind('@') {
+        Some(i) => DependencySource::Git {
+            url: url[..path_start + i].to_string(),
+            rev: Some(url[path_start + i + 1..].to_string()),
+            branch: None,
+        },
+        None => DependencySource::Git {
+            url: url.to_string(),
+            rev: None,
+            branch: None,
+        },
+    }
+}
+
+/// Lockfiles below `root`, skipping dependency and build output directories
+fn find_lockfiles(root: &Path) -> Vec<PathBuf> {
+    const SKIP: &[&str] = &["node_modules", "target", "vendor", "venv", "dist", "build", "__pycache__"];
+
+    walkdir::WalkDir::new(root)
+        .max_depth(4)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0
+                || !e.file_type().is_dir()
+                || !(name.starts_with('.') || SKIP.contains(&name.as_ref()))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            let dir = e.path().parent().unwrap_or(root);
+            match e.file_name().to_string_lossy().as_ref() {
+                "package-lock.json" | "npm-shrinkwrap.json" | "pnpm-lock.yaml" | "poetry.lock" | "go.mod" => true,
+                "go.sum" => !dir.join("go.mod").exists(),
+                name if name.starts_with("requirements") && name.ends_with(".txt") => {
+                    !dir.join("poetry.lock").exists()
+                }
+                _ => false,
+            }
+        })
+        .map(|e| e.into_path())
+        .collect()
+}
+
+fn read_toml(path: &Path) -> Result<toml::Value> {
+    toml::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| AnalysisError::Parse(format!("{}: {}", path.display(), e)))
+}
+
+// ============================================================================
+// Advisory Correlation
+// ============================================================================
+
+/// OSV.dev API base URL
+pub const OSV_API_URL: &str = "https://api.osv.dev/v1";
+
+/// A known vulnerability affecting a package in the graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdvisoryFinding {
+    /// Advisory id, e.g. RUSTSEC-2024-0003 or GHSA-xxxx-xxxx-xxxx
+    pub id: String,
+    /// CVE and other ids of the same vulnerability
+    pub aliases: Vec<String>,
+    pub node_id: String,
+    pub package: String,
+    pub version: String,
+    pub summary: String,
+    pub severity: Severity,
+    /// CVSS v3 base score, when the advisory has a vector
+    pub cvss_score: Option<f64>,
+    pub fixed_versions: Vec<String>,
+    pub url: Option<String>,
+}
+
+/// An OSV vulnerability record (https://ossf.github.io/osv-schema/)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OsvRecord {
+    pub id: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub details: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub withdrawn: Option<String>,
+    #[serde(default)]
+    pub severity: Vec<OsvSeverity>,
+    #[serde(default)]
+    pub affected: Vec<OsvAffected>,
+    #[serde(default)]
+    pub references: Vec<OsvReference>,
+    #[serde(default)]
+    pub database_specific: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OsvSeverity {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub score: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OsvAffected {
+    pub package: OsvPackage,
+    #[serde(default)]
+    pub ranges: Vec<OsvRange>,
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub severity: Vec<OsvSeverity>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OsvPackage {
+    pub ecosystem: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OsvRange {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub events: Vec<OsvEvent>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OsvEvent {
+    #[serde(default)]
+    pub introduced: Option<String>,
+    #[serde(default)]
+    pub fixed: Option<String>,
+    #[serde(default)]
+    pub last_affected: Option<String>,
+    #[serde(default)]
+    pub limit: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OsvReference {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: String,
+}
+
+impl Ecosystem {
+    /// Ecosystem name used by OSV
+    pub fn osv_name(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "crates.io",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Go => "Go",
+            Ecosystem::PyPI => "PyPI",
+        }
+    }
+
+    fn package_key(&self, name: &str) -> String {
+        match self {
+            Ecosystem::PyPI => python_name(name),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// OSV records indexed by (ecosystem, package name)
+#[derive(Debug, Default)]
+pub struct AdvisoryDb {
+    records: Vec<OsvRecord>,
+    index: HashMap<(String, String), Vec<usize>>,
+}
+
+impl AdvisoryDb {
+    pub fn from_records(records: impl IntoIterator<Item = OsvRecord>) -> Self {
+        let mut db = AdvisoryDb::default();
+        for record in records {
+            if record.withdrawn.is_some() {
+                continue;
+            }
+            let idx = db.records.len();
+            let mut keys = HashSet::new();
+            for affected in &record.affected {
+                let ecosystem = affected.package.ecosystem.split(':').next().unwrap_or_default();
+                let name = match ecosystem {
+                    "PyPI" => python_name(&affected.package.name),
+                    _ => affected.package.name.clone(),
+                };
+                keys.insert((ecosystem.to_string(), name));
+            }
+            for key in keys {
+                db.index.entry(key).or_default().push(idx);
+            }
+            db.records.push(record);
+        }
+        db
+    }
+
+    /// Load an offline database: a directory of OSV JSON files (e.g. the RustSec
+    /// OSV export) or a zip archive of them (e.g. osv.dev's `<ecosystem>/all.zip`)
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut records = Vec::new();
+        let mut add = |name: &str, data: &[u8]| {
+            match serde_json::from_slice::<OsvRecord>(data) {
+                Ok(record) => records.push(record),
+                Err(e) => debug!("Skipping {}: {}", name, e),
+            }
+        };
+
+        if path.is_dir() {
+            for entry in walkdir::WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+                if entry.file_type().is_file() && entry.path().extension().is_some_and(|e| e == "json") {
+                    add(&entry.path().display().to_string(), &std::fs::read(entry.path())?);
+                }
+            }
+        } else {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)
+                .map_err(|e| AnalysisError::Parse(format!("{}: {}", path.display(), e)))?;
+            for i in 0..archive.len() {
+                let mut file = archive
+                    .by_index(i)
+                    .map_err(|e| AnalysisError::Parse(format!("{}: {}", path.display(), e)))?;
+                if !file.name().ends_with(".json") {
+                    continue;
+                }
+                let name = file.name().to_string();
+                let mut data = Vec::new();
+                std::io::Read::read_to_end(&mut file, &mut data)?;
+                add(&name, &data);
+            }
+        }
+
+        info!("Loaded {} advisories from {}", records.len(), path.display());
+        Ok(Self::from_records(records))
+    }
+
+    /// Fetch the OSV records for the graph's packages from the OSV API
+    #[cfg(feature = "osv")]
+    pub fn fetch(graph: &DependencyGraph, api_url: &str) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .user_agent(concat!("infrasim/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| AnalysisError::Network(e.to_string()))?;
+
+        let packages: Vec<&DependencyNode> = graph.nodes.values().filter(|n| is_published(n)).collect();
+        let mut ids = BTreeSet::new();
+        // The batch endpoint answers with ids only, at most 1000 queries per call
+        for chunk in packages.chunks(1000) {
+            let queries: Vec<serde_json::Value> = chunk
+                .iter()
+                .map(|node| {
+                    serde_json::json!({
+                        "package": {
+                            "ecosystem": Ecosystem::of(node).osv_name(),
+                            "name": node.name,
+                        },
+                        "version": node.version.as_deref().map(|v| v.trim_start_matches('v')),
+                    })
+                })
+                .collect();
+            let response: serde_json::Value = client
+                .post(format!("{}/querybatch", api_url))
+                .json(&serde_json::json!({ "queries": queries }))
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.json())
+                .map_err(|e| AnalysisError::Network(e.to_string()))?;
+            for result in response["results"].as_array().into_iter().flatten() {
+                for vuln in result["vulns"].as_array().into_iter().flatten() {
+                    if let Some(id) = vuln["id"].as_str() {
+                        ids.insert(id.to_string());
+                    }
+                }
+            }
+        }
+
+        let mut records = Vec::with_capacity(ids.len());
+        for id in ids {
+            let record: OsvRecord = client
+                .get(format!("{}/vulns/{}", api_url, id))
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.json())
+                .map_err(|e| AnalysisError::Network(format!("{}: {}", id, e)))?;
+            records.push(record);
+        }
+        info!("Fetched {} advisories from {}", records.len(), api_url);
+        Ok(Self::from_records(records))
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Advisories affecting a package version
+    pub fn affecting(&self, ecosystem: Ecosystem, name: &str, version: &str) -> Vec<&OsvRecord> {
+        let key = (ecosystem.osv_name().to_string(), ecosystem.package_key(name));
+        self.index
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.records[i])
+            .filter(|record| {
+                record.affected.iter().any(|affected| {
+                    affected.package.ecosystem.split(':').next() == Some(ecosystem.osv_name())
+                        && ecosystem.package_key(&affected.package.name) == key.1
+                        && osv_affects(affected, version)
+                })
+            })
+            .collect()
+    }
+}
+
+impl AnalysisReport {
+    /// Attach the advisories affecting the graph's packages and rescore
+    pub fn correlate_advisories(&mut self, db: &AdvisoryDb) {
+        let mut findings = Vec::new();
+        for node in self.graph.nodes.values().filter(|n| is_published(n)) {
+            let Some(version) = node.version.as_deref() else {
+                continue;
+            };
+            let ecosystem = Ecosystem::of(node);
+            for record in db.affecting(ecosystem, &node.name, version) {
+                let cvss_score = record
+                    .severity
+                    .iter()
+                    .chain(record.affected.iter().flat_map(|a| a.severity.iter()))
+                    .filter(|s| s.kind == "CVSS_V3")
+                    .find_map(|s| cvss3_base_score(&s.score));
+                let fixed_versions = record
+                    .affected
+                    .iter()
+                    .filter(|a| ecosystem.package_key(&a.package.name) == ecosystem.package_key(&node.name))
+                    .flat_map(|a| a.ranges.iter().flat_map(|r| r.events.iter()))
+                    .filter_map(|e| e.fixed.clone())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect();
+                let url = record
+                    .references
+                    .iter()
+                    .find(|r| r.kind == "ADVISORY")
+                    .or(record.references.first())
+                    .map(|r| r.url.clone());
+
+                findings.push(AdvisoryFinding {
+                    id: record.id.clone(),
+                    aliases: record.aliases.clone(),
+                    node_id: node.id.clone(),
+                    package: node.name.clone(),
+                    version: version.to_string(),
+                    summary: if record.summary.is_empty() {
+                        record.details.lines().next().unwrap_or_default().to_string()
+                    } else {
+                        record.summary.clone()
+                    },
+                    severity: advisory_severity(record, cvss_score),
+                    cvss_score,
+                    fixed_versions,
+                    url,
+                });
+            }
+        }
+
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.id.cmp(&b.id)));
+        self.advisories = findings;
+        self.calculate_risk_score();
+    }
+}
+
+/// Registry packages only; workspace members can share names with unrelated crates
+fn is_published(node: &DependencyNode) -> bool {
+    node.version.is_some()
+        && !matches!(
+            node.source,
+            DependencySource::Path { .. } | DependencySource::Unknown
+        )
+}
+
+/// OSV affected-range evaluation for one package entry
+fn osv_affects(affected: &OsvAffected, version: &str) -> bool {
+    if affected
+        .versions
+        .iter()
+        .any(|v| compare_versions(v, version) == std::cmp::Ordering::Equal)
+    {
+        return true;
+    }
+
+    affected
+        .ranges
+        .iter()
+        .filter(|r| r.kind == "SEMVER" || r.kind == "ECOSYSTEM")
+        .any(|range| {
+            let mut events: Vec<(&str, &OsvEvent)> = range
+                .events
+                .iter()
+                .filter_map(|e| {
+                    let at = e
+                        .introduced
+                        .as_deref()
+                        .or(e.fixed.as_deref())
+                        .or(e.last_affected.as_deref())
+                        .or(e.limit.as_deref())?;
+                    Some((at, e))
+                })
+                .collect();
+            events.sort_by(|a, b| compare_versions(a.0, b.0));
+
+            let mut affected = false;
+            for (at, event) in events {
+                let cmp = compare_versions(version, at);
+                if event.introduced.is_some() {
+                    if at == "0" || cmp.is_ge() {
+                        affected = true;
+                    }
+                } else if event.fixed.is_some() {
+                    if cmp.is_ge() {
+                        affected = false;
+                    }
+                } else if event.last_affected.is_some() {
+                    if cmp.is_gt() {
+                        affected = false;
+                    }
+                } else if cmp.is_ge() {
+                    // limit
+                    affected = false;
+                }
+            }
+            affected
+        })
+}
+
+/// Compare versions across ecosystems: numeric segments numerically, a
+/// pre-release (`1.0.0-rc.1`, `1.0rc1`) before its release
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    enum Token<'a> {
+        Num(u64),
+        Text(&'a str),
+    }
+
+    fn tokens(s: &str) -> Vec<Token<'_>> {
+        let mut tokens = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            if digits > 0 {
+                tokens.push(Token::Num(rest[..digits].parse().unwrap_or(u64::MAX)));
+                rest = &rest[digits..];
+                continue;
+            }
+            let text = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+            let word = rest[..text].trim_matches(|c: char| !c.is_alphanumeric());
+            if !word.is_empty() {
+                tokens.push(Token::Text(word));
+            }
+            rest = &rest[text..];
+        }
+        tokens
+    }
+
+    fn split(v: &str) -> (Vec<Token<'_>>, Option<Vec<Token<'_>>>) {
+        let v = v.trim().trim_start_matches('v');
+        let v = v.split('+').next().unwrap_or(v);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (v, None),
+        };
+        let mut core = tokens(core);
+        // PEP 440 style pre-releases live in the core: 1.0rc1
+        let pre_at = core.iter().position(|t| matches!(t, Token::Text(_)));
+        let inline_pre = pre_at.map(|i| core.split_off(i));
+        (core, pre.map(tokens).or(inline_pre))
+    }
+
+    let (a_core, a_pre) = split(a);
+    let (b_core, b_pre) = split(b);
+    let len = a_core.len().max(b_core.len());
+    for i in 0..len {
+        let x = a_core.get(i).unwrap_or(&Token::Num(0));
+        let y = b_core.get(i).unwrap_or(&Token::Num(0));
+        match x.cmp(y) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(x), Some(y)) => x.cmp(&y),
+    }
+}
+
+fn advisory_severity(record: &OsvRecord, cvss_score: Option<f64>) -> Severity {
+    if let Some(score) = cvss_score {
+        return match score {
+            s if s >= 9.0 => Severity::Critical,
+            s if s >= 7.0 => Severity::High,
+            s if s >= 4.0 => Severity::Medium,
+            s if s > 0.0 => Severity::Low,
+            _ => Severity::Info,
+        };
+    }
+    let specific = record.database_specific.as_ref();
+    // RustSec marks unmaintained/unsound crates as informational
+    if specific.is_some_and(|d| d.get("informational").is_some_and(|i| !i.is_null())) {
+        return Severity::Info;
+    }
+    match specific
+        .and_then(|d| d.get("severity"))
+        .and_then(|s| s.as_str())
+        .map(|s| s.to_ascii_uppercase())
+        .as_deref()
+    {
+        Some("CRITICAL") => Severity::Critical,
+        Some("HIGH") => Severity::High,
+        Some("LOW") => Severity::Low,
+        _ => Severity::Medium,
+    }
+}
+
+/// CVSS v3.x base score from a vector like `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`
+fn cvss3_base_score(vector: &str) -> Option<f64> {
+    if !vector.starts_with("CVSS:3") {
+        return None;
+    }
+    let metrics: HashMap<&str, &str> = vector
+        .split('/')
+        .skip(1)
+        .filter_map(|m| m.split_once(':'))
+        .collect();
+    let changed = *metrics.get("S")? == "C";
+
+    let av = match *metrics.get("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let ac = match *metrics.get("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let pr = match (*metrics.get("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match *metrics.get("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let cia = |key: &str| match metrics.get(key).copied() {
+        Some("H") => Some(0.56),
+        Some("L") => Some(0.22),
+        Some("N") => Some(0.0),
+        _ => None,
+    };
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02_f64).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let base = if changed {
+        (1.08 * (impact + exploitability)).min(10.0)
+    } else {
+        (impact + exploitability).min(10.0)
+    };
+
+    // Round up to one decimal, as specified (avoids float artifacts)
+    let scaled = (base * 100_000.0).round() as u64;
+    Some(if scaled.is_multiple_of(10_000) {
+        scaled as f64 / 100_000.0
+    } else {
+        (scaled / 10_000 + 1) as f64 / 10.0
+    })
+}
+
+// ============================================================================
+// SBOM Export
+// ============================================================================
+
+/// Standard SBOM document formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SbomFormat {
+    /// SPDX 2.3 JSON
+    Spdx,
+    /// CycloneDX 1.5 JSON
+    CycloneDx,
+}
+
+impl SbomFormat {
+    /// MIME type of the document
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "application/spdx+json",
+            SbomFormat::CycloneDx => "application/vnd.cyclonedx+json",
+        }
+    }
+
+    /// Conventional file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "spdx.json",
+            SbomFormat::CycloneDx => "cdx.json",
+        }
+    }
+}
+
+impl FromStr for SbomFormat {
+    type Err = AnalysisError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "spdx" => Ok(SbomFormat::Spdx),
+            "cyclonedx" | "cdx" => Ok(SbomFormat::CycloneDx),
+            other => Err(AnalysisError::Parse(format!(
+                "unknown SBOM format '{}' (expected spdx or cyclonedx)",
+                other
+            ))),
+        }
+    }
+}
+
+impl DependencyGraph {
+    /// Export as an SPDX 2.3 JSON document
+    pub fn to_spdx(&self) -> serde_json::Value {
+        let nodes = self.sorted_nodes();
+
+        // SPDX IDs only allow letters, digits, '.' and '-'
+        let mut spdx_ids: HashMap<&str, String> = HashMap::new();
+        let mut taken = HashSet::new();
+        for node in &nodes {
+            let base = format!(
+                "SPDXRef-Package-{}-{}",
+                spdx_id_part(&node.name),
+                spdx_id_part(node.version.as_deref().unwrap_or("unknown"))
+            );
+            let mut id = base.clone();
+            let mut n = 1;
+            while !taken.insert(id.clone()) {
+                n += 1;
+                id = format!("{}-{}", base, n);
+            }
+            spdx_ids.insert(node.id.as_str(), id);
+        }
+
+        let packages: Vec<serde_json::Value> = nodes
+            .iter()
+            .map(|node| {
+                let license = node
+                    .metadata
+                    .get("license")
+                    .map(|l| l.replace('/', " OR "))
+                    .unwrap_or_else(|| "NOASSERTION".to_string());
+                let mut package = serde_json::json!({
+                    "SPDXID": spdx_ids[node.id.as_str()],
+                    "name": node.name,
+                    "versionInfo": node.version.clone().unwrap_or_default(),
+                    "downloadLocation": download_location(node),
+                    "filesAnalyzed": false,
+                    "licenseConcluded": "NOASSERTION",
+                    "licenseDeclared": license,
+                    "copyrightText": "NOASSERTION",
+                    "sourceInfo": provenance(node),
+                });
+                if let Some(checksum) = &node.checksum {
+                    package["checksums"] = serde_json::json!([
+                        { "algorithm": "SHA256", "checksumValue": checksum }
+                    ]);
+                }
+                if let Some(purl) = purl(node) {
+                    package["externalRefs"] = serde_json::json!([{
+                        "referenceCategory": "PACKAGE-MANAGER",
+                        "referenceType": "purl",
+                        "referenceLocator": purl,
+                    }]);
+                }
+                package
+            })
+            .collect();
+
+        let mut relationships: Vec<serde_json::Value> = self
+            .root_nodes
+            .iter()
+            .filter_map(|root| spdx_ids.get(root.as_str()))
+            .map(|root| {
+                serde_json::json!({
+                    "spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES",
+                    "relatedSpdxElement": root,
+                })
+            })
+            .collect();
+        let mut seen = BTreeSet::new();
+        for edge in &self.edges {
+            let (Some(from), Some(to)) = (
+                spdx_ids.get(edge.from.as_str()),
+                spdx_ids.get(edge.to.as_str()),
+            ) else {
+                continue;
+            };
+            // "X DEPENDS_ON Y", but "Y DEV_DEPENDENCY_OF X"
+            let (element, kind, related) = match edge.kind {
+                EdgeKind::Normal => (from, "DEPENDS_ON", to),
+                EdgeKind::Dev => (to, "DEV_DEPENDENCY_OF", from),
+                EdgeKind::Build | EdgeKind::Proc => (to, "BUILD_DEPENDENCY_OF", from),
+            };
+            if seen.insert((element.clone(), kind, related.clone())) {
+                relationships.push(serde_json::json!({
+                    "spdxElementId": element,
+                    "relationshipType": kind,
+                    "relatedSpdxElement": related,
+                }));
+            }
+        }
+
+        let name = self.document_name();
+        let mut creation_info = serde_json::json!({
+            "created": self.analyzed_at_rfc3339(),
+            "creators": [format!("Tool: infrasim-{}", env!("CARGO_PKG_VERSION"))],
+        });
+        if let Some(commit) = &self.metadata.git_commit {
+            creation_info["comment"] = serde_json::json!(format!(
+                "Generated from {} at git commit {}",
+                self.metadata.source_path, commit
+            ));
+        }
+
+        serde_json::json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": name,
+            "documentNamespace": format!(
+                "https://infrasim.local/spdx/{}-{}",
+                spdx_id_part(&name),
+                uuid::Uuid::new_v4()
+            ),
+            "creationInfo": creation_info,
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+
+    /// Export as a CycloneDX 1.5 JSON BOM
+    pub fn to_cyclonedx(&self) -> serde_json::Value {
+        let nodes = self.sorted_nodes();
+        let roots: HashSet<&str> = self.root_nodes.iter().map(|r| r.as_str()).collect();
+
+        let components: Vec<serde_json::Value> = nodes
+            .iter()
+            .map(|node| {
+                let mut component = serde_json::json!({
+                    "type": if roots.contains(node.id.as_str()) { "application" } else { "library" },
+                    "bom-ref": node.id,
+                    "name": node.name,
+                    "version": node.version.clone().unwrap_or_default(),
+                });
+                if let Some(purl) = purl(node) {
+                    component["purl"] = serde_json::json!(purl);
+                }
+                if let Some(checksum) = &node.checksum {
+                    component["hashes"] = serde_json::json!([{ "alg": "SHA-256", "content": checksum }]);
+                }
+                if let Some(license) = node.metadata.get("license") {
+                    component["licenses"] =
+                        serde_json::json!([{ "expression": license.replace('/', " OR ") }]);
+                }
+
+                let mut references = Vec::new();
+                match &node.source {
+                    DependencySource::Git { url, .. } => {
+                        references.push(serde_json::json!({ "type": "vcs", "url": git_base_url(url) }));
+                    }
+                    DependencySource::Registry { .. } => {
+                        let url = download_location(node);
+                        if url != "NOASSERTION" {
+                            references.push(serde_json::json!({ "type": "distribution", "url": url }));
+                        }
+                    }
+                    _ => {}
+                }
+                if let Some(repo) = node.metadata.get("repository") {
+                    if !matches!(node.source, DependencySource::Git { .. }) {
+                        references.push(serde_json::json!({ "type": "vcs", "url": repo }));
+                    }
+                }
+                if !references.is_empty() {
+                    component["externalReferences"] = serde_json::json!(references);
+                }
+
+                let mut properties = vec![serde_json::json!({
+                    "name": "infrasim:source",
+                    "value": provenance(node),
+                })];
+                if node.metadata.contains_key("proc_macro") {
+                    properties.push(serde_json::json!({ "name": "infrasim:proc_macro", "value": "true" }));
+                }
+                component["properties"] = serde_json::json!(properties);
+                component
+            })
+            .collect();
+
+        // Workspace component ties the members together
+        let mut depends: BTreeMap<&str, BTreeSet<&str>> = nodes
+            .iter()
+            .map(|n| (n.id.as_str(), BTreeSet::new()))
+            .collect();
+        for edge in &self.edges {
+            if self.nodes.contains_key(&edge.to) {
+                if let Some(deps) = depends.get_mut(edge.from.as_str()) {
+                    deps.insert(edge.to.as_str());
+                }
+            }
+        }
+        depends.insert(
+            WORKSPACE_BOM_REF,
+            self.root_nodes
+                .iter()
+                .filter(|r| self.nodes.contains_key(*r))
+                .map(|r| r.as_str())
+                .collect(),
+        );
+        let dependencies: Vec<serde_json::Value> = depends
+            .into_iter()
+            .map(|(id, deps)| serde_json::json!({ "ref": id, "dependsOn": deps }))
+            .collect();
+
+        let mut properties = vec![serde_json::json!({
+            "name": "infrasim:source_path",
+            "value": self.metadata.source_path,
+        })];
+        if let Some(commit) = &self.metadata.git_commit {
+            properties.push(serde_json::json!({ "name": "infrasim:git_commit", "value": commit }));
+        }
+
+        serde_json::json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+            "version": 1,
+            "metadata": {
+                "timestamp": self.analyzed_at_rfc3339(),
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": "infrasim",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+                "component": {
+                    "type": "application",
+                    "bom-ref": WORKSPACE_BOM_REF,
+                    "name": self.document_name(),
+                },
+                "properties": properties,
+            },
+            "components": components,
+            "dependencies": dependencies,
+        })
+    }
+
+    fn sorted_nodes(&self) -> Vec<&DependencyNode> {
+        let mut nodes: Vec<&DependencyNode> = self.nodes.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes
+    }
+
+    /// Name of the analyzed workspace: its single root package or directory
+    fn document_name(&self) -> String {
+        if let [root] = self.root_nodes.as_slice() {
+            if let Some(node) = self.nodes.get(root) {
+                return node.name.clone();
+            }
+        }
+        Path::new(&self.metadata.source_path)
+            .canonicalize()
+            .ok()
+            .as_deref()
+            .unwrap_or_else(|| Path::new(&self.metadata.source_path))
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "workspace".to_string())
+    }
+
+    fn analyzed_at_rfc3339(&self) -> String {
+        chrono::DateTime::from_timestamp(self.metadata.analyzed_at as i64, 0)
+            .unwrap_or_default()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
+}
+
+impl AnalysisReport {
+    /// Export the dependency graph as an SPDX 2.3 JSON document
+    pub fn to_spdx(&self) -> serde_json::Value {
+        self.graph.to_spdx()
+    }
+
+    /// Export the dependency graph as a CycloneDX 1.5 JSON BOM, with the risk score
+    pub fn to_cyclonedx(&self) -> serde_json::Value {
+        let mut bom = self.graph.to_cyclonedx();
+        if let Some(properties) = bom["metadata"]["properties"].as_array_mut() {
+            properties.push(serde_json::json!({
+                "name": "infrasim:risk_score",
+                "value": format!("{:.1}", self.risk_score),
+            }));
+        }
+        bom
+    }
+
+    /// Export in the given SBOM format
+    pub fn to_sbom(&self, format: SbomFormat) -> serde_json::Value {
+        match format {
+            SbomFormat::Spdx => self.to_spdx(),
+            SbomFormat::CycloneDx => self.to_cyclonedx(),
+        }
+    }
+}
+
+/// CycloneDX bom-ref of the analyzed workspace itself
+const WORKSPACE_BOM_REF: &str = "infrasim:workspace";
+
+/// Package URL of a node, if it has a version
+fn purl(node: &DependencyNode) -> Option<String> {
+    let version = node.version.as_ref()?;
+    let ecosystem = Ecosystem::of(node);
+    let name = match ecosystem {
+        Ecosystem::Npm => node.name.replacen('@', "%40", 1),
+        Ecosystem::PyPI => python_name(&node.name),
+        _ => node.name.clone(),
+    };
+    let purl = format!("pkg:{}/{}@{}", ecosystem.purl_type(), name, version);
+    Some(match &node.source {
+        DependencySource::Git { url, rev, .. } => {
+            let mut vcs = format!("git+{}", git_base_url(url));
+            if let Some(rev) = rev {
+                vcs = format!("{}@{}", vcs, rev);
+            }
+            format!("{}?vcs_url={}", purl, percent_encode(&vcs))
+        }
+        DependencySource::Registry { url, .. } if ecosystem == Ecosystem::Cargo && !is_crates_io(url) => {
+            format!("{}?repository_url={}", purl, percent_encode(url.trim_start_matches("registry+")))
+        }
+        _ => purl,
+    })
+}
+
+/// Where the package's source was fetched from
+fn download_location(node: &DependencyNode) -> String {
+    match (&node.source, &node.version) {
+        (DependencySource::Registry { url, .. }, Some(version)) => match Ecosystem::of(node) {
+            Ecosystem::Cargo if is_crates_io(url) => format!(
+                "https://static.crates.io/crates/{0}/{0}-{1}.crate",
+                node.name, version
+            ),
+            Ecosystem::Npm if url.ends_with(".tgz") => url.clone(),
+            Ecosystem::Npm if url == NPM_REGISTRY => format!(
+                "{}/{}/-/{}-{}.tgz",
+                NPM_REGISTRY,
+                node.name,
+                node.name.rsplit('/').next().unwrap_or(&node.name),
+                version
+            ),
+            Ecosystem::Go if url == GO_PROXY => format!(
+                "{}/{}/@v/{}.zip",
+                GO_PROXY,
+                go_escape(&node.name),
+                go_escape(version)
+            ),
+            _ => "NOASSERTION".to_string(),
+        },
+        (DependencySource::Git { url, rev, .. }, _) => match rev {
+            Some(rev) => format!("git+{}@{}", git_base_url(url), rev),
+            None => format!("git+{}", git_base_url(url)),
+        },
+        _ => "NOASSERTION".to_string(),
+    }
+}
+
+/// Human-readable source provenance, including the pinned git revision
+fn provenance(node: &DependencyNode) -> String {
+    match &node.source {
+        DependencySource::Registry { name, url } => {
+            format!("registry {} ({})", name, url.trim_start_matches("registry+"))
+        }
+        DependencySource::Git { url, rev, branch } => {
+            let mut s = format!("git {}", git_base_url(url));
+            if let Some(branch) = branch {
+                s.push_str(&format!(" branch {}", branch));
+            }
+            if let Some(rev) = rev {
+                s.push_str(&format!(" rev {}", rev));
+            }
+            s
+        }
+        DependencySource::Path { path } => format!("path {}", path),
+        DependencySource::Vendored { path } => format!("vendored {}", path),
+        DependencySource::Unknown => "unknown".to_string(),
+    }
+}
+
+/// Strip the `git+` prefix, query and fragment from a cargo git source
+fn git_base_url(url: &str) -> &str {
+    let url = url.trim_start_matches("git+");
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    &url[..end]
+}
+
+/// Go module proxy path escaping: upper-case letters become `!` + lower-case
+fn go_escape(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+            escaped.push(c.to_ascii_lowercase());
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn is_crates_io(url: &str) -> bool {
+    url.contains("github.com/rust-lang/crates.io-index") || url.contains("index.crates.io")
+}
+
+/// Percent-encode a purl qualifier value
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Replace characters not allowed in SPDX identifiers
+fn spdx_id_part(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect()
+}
+
+// ============================================================================
+// Graph Export
+// ============================================================================
+
+/// Graph formats for rendering the dependency graph in external tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// GraphML, as read by Gephi, yEd and Cytoscape
+    GraphMl,
+}
+
+impl GraphFormat {
+    /// MIME type of the document
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "text/vnd.graphviz",
+            GraphFormat::GraphMl => "application/graphml+xml",
+        }
+    }
+
+    /// Conventional file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::GraphMl => "graphml",
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = AnalysisError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "dot" | "gv" | "graphviz" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            other => Err(AnalysisError::Parse(format!(
+                "unknown graph format '{}' (expected dot or graphml)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Suspicious patterns a node is involved in
+struct NodeFindings<'a> {
+    severity: Severity,
+    patterns: Vec<&'a SuspiciousPattern>,
+}
+
+impl NodeFindings<'_> {
+    /// One line per pattern, for tooltips
+    fn summary(&self) -> String {
+        self.patterns
+            .iter()
+            .map(|p| format!("{} {:?}: {}", p.severity, p.pattern_type, p.description))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl DependencyGraph {
+    /// Export as a Graphviz DOT digraph
+    ///
+    /// Nodes are filled by source type. Nodes involved in any of `patterns`
+    /// get a thick border in the color of the highest severity among them,
+    /// and the patterns in their tooltip.
+    pub fn to_dot(&self, patterns: &[SuspiciousPattern]) -> String {
+        let findings = self.findings(patterns);
+        let roots: HashSet<&str> = self.root_nodes.iter().map(|r| r.as_str()).collect();
+
+        let mut dot = format!("digraph \"{}\" {{\n", dot_escape(&self.document_name()));
+        dot.push_str("  rankdir=LR;\n");
+        dot.push_str("  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\", fontcolor=\"white\"];\n");
+        dot.push_str("  edge [color=\"#607D8B\"];\n\n");
+
+        for node in self.sorted_nodes() {
+            let label = match &node.version {
+                Some(version) => format!("{}\n{}", node.name, version),
+                None => node.name.clone(),
+            };
+            let mut attrs = vec![
+                format!("label=\"{}\"", dot_escape(&label)),
+                format!("fillcolor=\"{}\"", source_color(&node.source)),
+            ];
+            let mut tooltip = provenance(node);
+            if roots.contains(node.id.as_str()) {
+                attrs.push("peripheries=2".to_string());
+            }
+            if let Some(found) = findings.get(node.id.as_str()) {
+                attrs.push(format!("color=\"{}\"", severity_color(found.severity)));
+                attrs.push("penwidth=4".to_string());
+                tooltip = format!("{}\n{}", tooltip, found.summary());
+            }
+            attrs.push(format!("tooltip=\"{}\"", dot_escape(&tooltip)));
+            dot.push_str(&format!("  \"{}\" [{}];\n", dot_escape(&node.id), attrs.join(", ")));
+        }
+
+        if !self.edges.is_empty() {
+            dot.push('\n');
+        }
+        for edge in &self.edges {
+            let mut attrs = Vec::new();
+            match edge.kind {
+                EdgeKind::Normal => {}
+                EdgeKind::Dev => attrs.push("style=dashed"),
+                EdgeKind::Build => attrs.push("style=dotted"),
+                EdgeKind::Proc => attrs.push("style=bold"),
+            }
+            if edge.optional {
+                attrs.push("arrowhead=empty");
+            }
+            let attrs = if attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attrs.join(", "))
+            };
+            dot.push_str(&format!(
+                "  \"{}\" -> \"{}\"{};\n",
+                dot_escape(&edge.from),
+                dot_escape(&edge.to),
+                attrs
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Export as a GraphML document
+    ///
+    /// Source type, color, and the highest severity and patterns of
+    /// `patterns` each node is involved in are node attributes, so tools like
+    /// Gephi can partition and color by them.
+    pub fn to_graphml(&self, patterns: &[SuspiciousPattern]) -> String {
+        let findings = self.findings(patterns);
+        let roots: HashSet<&str> = self.root_nodes.iter().map(|r| r.as_str()).collect();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, domain, name, kind) in [
+            ("label", "node", "label", "string"),
+            ("version", "node", "version", "string"),
+            ("source", "node", "source", "string"),
+            ("provenance", "node", "provenance", "string"),
+            ("color", "node", "color", "string"),
+            ("root", "node", "root", "boolean"),
+            ("severity", "node", "severity", "string"),
+            ("patterns", "node", "patterns", "string"),
+            ("kind", "edge", "kind", "string"),
+            ("optional", "edge", "optional", "boolean"),
+        ] {
+            xml.push_str(&format!(
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>\n",
+                id, domain, name, kind
+            ));
+        }
+        xml.push_str(&format!(
+            "  <graph id=\"{}\" edgedefault=\"directed\">\n",
+            xml_escape(&self.document_name())
+        ));
+
+        for node in self.sorted_nodes() {
+            xml.push_str(&format!("    <node id=\"{}\">\n", xml_escape(&node.id)));
+            let mut data = vec![
+                ("label", node.name.clone()),
+                ("version", node.version.clone().unwrap_or_default()),
+                ("source", source_type(&node.source).to_string()),
+                ("provenance", provenance(node)),
+                ("color", source_color(&node.source).to_string()),
+                ("root", roots.contains(node.id.as_str()).to_string()),
+            ];
+            if let Some(found) = findings.get(node.id.as_str()) {
+                data.push(("severity", found.severity.to_string()));
+                data.push(("patterns", found.summary()));
+            }
+            for (key, value) in data {
+                xml.push_str(&format!(
+                    "      <data key=\"{}\">{}</data>\n",
+                    key,
+                    xml_escape(&value)
+                ));
+            }
+            xml.push_str("    </node>\n");
+        }
+
+        for (n, edge) in self.edges.iter().enumerate() {
+            xml.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n",
+                n,
+                xml_escape(&edge.from),
+                xml_escape(&edge.to)
+            ));
+            let kind = format!("{:?}", edge.kind).to_lowercase();
+            xml.push_str(&format!("      <data key=\"kind\">{}</data>\n", kind));
+            xml.push_str(&format!("      <data key=\"optional\">{}</data>\n", edge.optional));
+            xml.push_str("    </edge>\n");
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    /// Export in the given graph format
+    pub fn to_graph(&self, format: GraphFormat, patterns: &[SuspiciousPattern]) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(patterns),
+            GraphFormat::GraphMl => self.to_graphml(patterns),
+        }
+    }
+
+    /// Patterns by the nodes of this graph they involve
+    fn findings<'a>(&self, patterns: &'a [SuspiciousPattern]) -> HashMap<&'a str, NodeFindings<'a>> {
+        let mut findings: HashMap<&str, NodeFindings> = HashMap::new();
+        for pattern in patterns {
+            for node in &pattern.nodes_involved {
+                if !self.nodes.contains_key(node) {
+                    continue;
+                }
+                let found = findings.entry(node.as_str()).or_insert(NodeFindings {
+                    severity: pattern.severity,
+                    patterns: Vec::new(),
+                });
+                found.severity = found.severity.max(pattern.severity);
+                found.patterns.push(pattern);
+            }
+        }
+        for found in findings.values_mut() {
+            found.patterns.sort_by_key(|p| std::cmp::Reverse(p.severity));
+        }
+        findings
+    }
+}
+
+impl AnalysisReport {
+    /// Export the dependency graph in the given graph format, with the
+    /// suspicious patterns overlaid
+    pub fn to_graph(&self, format: GraphFormat) -> String {
+        self.graph.to_graph(format, &self.suspicious_patterns)
+    }
+}
+
+/// Source type name, as used for grouping in the UI
+fn source_type(source: &DependencySource) -> &'static str {
+    match source {
+        DependencySource::Registry { .. } => "registry",
+        DependencySource::Git { .. } => "git",
+        DependencySource::Path { .. } => "path",
+        DependencySource::Vendored { .. } => "vendored",
+        DependencySource::Unknown => "unknown",
+    }
+}
+
+/// Fill color by source type, matching the web UI
+fn source_color(source: &DependencySource) -> &'static str {
+    match source {
+        DependencySource::Registry { .. } => "#4CAF50",
+        DependencySource::Git { .. } => "#2196F3",
+        DependencySource::Path { .. } => "#FF9800",
+        DependencySource::Vendored { .. } => "#9C27B0",
+        DependencySource::Unknown => "#9E9E9E",
+    }
+}
+
+/// Border color of nodes involved in suspicious patterns
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "#90A4AE",
+        Severity::Low => "#FDD835",
+        Severity::Medium => "#FB8C00",
+        Severity::High => "#E53935",
+        Severity::Critical => "#B71C1C",
+    }
+}
+
+/// Escape a DOT quoted string
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Escape XML text and attribute values
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// ============================================================================
+// Report Diffs
+// ============================================================================
+
+/// A package that appeared in or disappeared from the graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyChange {
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub ecosystem: Ecosystem,
+}
+
+impl DependencyChange {
+    fn of(node: &DependencyNode) -> Self {
+        Self {
+            id: node.id.clone(),
+            name: node.name.clone(),
+            version: node.version.clone(),
+            ecosystem: Ecosystem::of(node),
+        }
+    }
+}
+
+/// A package whose only version moved from one to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionChange {
+    pub name: String,
+    pub ecosystem: Ecosystem,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// What changed between two analyses of the same workspace
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReportDiff {
+    pub from_commit: Option<String>,
+    pub to_commit: Option<String>,
+    pub added: Vec<DependencyChange>,
+    pub removed: Vec<DependencyChange>,
+    pub version_changes: Vec<VersionChange>,
+    /// Advisory ids found only in the newer report
+    pub new_advisories: Vec<String>,
+    /// Advisory ids found only in the older report
+    pub resolved_advisories: Vec<String>,
+    pub risk_before: f64,
+    pub risk_after: f64,
+    pub risk_delta: f64,
+}
+
+impl ReportDiff {
+    /// Whether the dependencies, advisories or risk score changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.version_changes.is_empty()
+            && self.new_advisories.is_empty()
+            && self.resolved_advisories.is_empty()
+            && self.risk_delta == 0.0
+    }
+}
+
+impl AnalysisReport {
+    /// Compare with a later analysis of the same workspace
+    ///
+    /// A package with a single version in each report whose version changed
+    /// is a version change rather than a removal and an addition.
+    pub fn diff(&self, newer: &AnalysisReport) -> ReportDiff {
+        let mut removed: Vec<&DependencyNode> = self
+            .graph
+            .nodes
+            .values()
+            .filter(|node| !newer.graph.nodes.contains_key(&node.id))
+            .collect();
+        let mut added: Vec<&DependencyNode> = newer
+            .graph
+            .nodes
+            .values()
+            .filter(|node| !self.graph.nodes.contains_key(&node.id))
+            .collect();
+
+        let versions = |report: &AnalysisReport, node: &DependencyNode| {
+            report
+                .graph
+                .nodes
+                .values()
+                .filter(|n| n.name == node.name && Ecosystem::of(n) == Ecosystem::of(node))
+                .count()
+        };
+        let mut version_changes = Vec::new();
+        removed.retain(|old| {
+            let single = versions(self, old) == 1;
+            let Some(idx) = added
+                .iter()
+                .position(|new| single && new.name == old.name && Ecosystem::of(new) == Ecosystem::of(old))
+            else {
+                return true;
+            };
+            if versions(newer, added[idx]) != 1 {
+                return true;
+            }
+            let new = added.remove(idx);
+            version_changes.push(VersionChange {
+                name: old.name.clone(),
+                ecosystem: Ecosystem::of(old),
+                from: old.version.clone(),
+                to: new.version.clone(),
+            });
+            false
+        });
+
+        let mut added: Vec<DependencyChange> = added.into_iter().map(DependencyChange::of).collect();
+        let mut removed: Vec<DependencyChange> = removed.into_iter().map(DependencyChange::of).collect();
+        added.sort_by(|a, b| a.id.cmp(&b.id));
+        removed.sort_by(|a, b| a.id.cmp(&b.id));
+        version_changes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let advisory_ids = |report: &AnalysisReport| -> BTreeSet<String> {
+            report.advisories.iter().map(|a| a.id.clone()).collect()
+        };
+        let (old_advisories, new_advisories) = (advisory_ids(self), advisory_ids(newer));
+
+        ReportDiff {
+            from_commit: self.graph.metadata.git_commit.clone(),
+            to_commit: newer.graph.metadata.git_commit.clone(),
+            added,
+            removed,
+            version_changes,
+            new_advisories: new_advisories.difference(&old_advisories).cloned().collect(),
+            resolved_advisories: old_advisories.difference(&new_advisories).cloned().collect(),
+            risk_before: self.risk_score,
+            risk_after: newer.risk_score,
+            risk_delta: newer.risk_score - self.risk_score,
+        }
+    }
+}
+
+/// Whether a change to the file at `path` (relative to the repository root)
+/// can change the analysis: manifests, lockfiles, build scripts and vendored
+/// or Cargo-configured sources
+pub fn affects_analysis(path: &str) -> bool {
+    let path = Path::new(path);
+    let in_dir = |dir: &str| path.components().any(|c| c.as_os_str() == dir);
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    match name.as_ref() {
+        "Cargo.toml" | "Cargo.lock" | "build.rs" | "package.json" | "package-lock.json"
+        | "npm-shrinkwrap.json" | "pnpm-lock.yaml" | "go.mod" | "go.sum" | "poetry.lock"
+        | "pyproject.toml" => true,
+        name if name.starts_with("requirements") && name.ends_with(".txt") => true,
+        _ => in_dir("vendor") || in_dir(".cargo"),
+    }
+}
+
+// ============================================================================
+// Utility Functions
+// ============================================================================
+
+/// Read `(name, version, source) -> sha256` for registry packages from a Cargo.lock
+fn read_lock_checksums(path: &Path) -> Result<HashMap<(String, String, String), String>> {
+    let content = std::fs::read_to_string(path)?;
+    let lock: toml::Value =
+        toml::from_str(&content).map_err(|e| AnalysisError::Parse(e.to_string()))?;
+
+    let mut checksums = HashMap::new();
+    for pkg in lock
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let field = |key: &str| pkg.get(key).and_then(|v| v.as_str());
+        if let (Some(name), Some(version), Some(source), Some(checksum)) = (
+            field("name"),
+            field("version"),
+            field("source"),
+            field("checksum"),
+        ) {
+            checksums.insert(
+                (name.to_string(), version.to_string(), source.to_string()),
+                checksum.to_string(),
+            );
+        }
+    }
+    Ok(checksums)
+}
+
+/// Current git commit of the analyzed tree, if it is a repository
+fn git_head(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(path)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Normalize a cycle by rotating it to start from the lexicographically smallest element.
+/// This ensures that A -> B -> C -> A and B -> C -> A -> B are detected as the same cycle.
+fn normalize_cycle(cycle: &[String]) -> Vec<String> {
+    if cycle.is_empty() {
+        return Vec::new();
+    }
+
+    // Find the index of the lexicographically smallest element
+    let min_idx = cycle
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.cmp(b))
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    // Rotate the cycle to start from the smallest element
+    let mut normalized = Vec::with_capacity(cycle.len());
+    normalized.extend_from_slice(&cycle[min_idx..]);
+    normalized.extend_from_slice(&cycle[..min_idx]);
+
+    normalized
+}
+
+fn extract_github_org(url: &str) -> Option<String> {
+    // Extract org from URLs like "https://github.com/org/repo" or "git+https://..."
+    let url = url.trim_start_matches("git+");
+    if url.contains("github.com") {
+        let parts: Vec<&str> = url.split('/').collect();
+        for (i, part) in parts.iter().enumerate() {
+            if *part == "github.com" && i + 1 < parts.len() {
+                return Some(parts[i + 1].to_string());
+            }
+        }
+    }
+    None
+}
+
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+    let a_len = a_chars.len();
+    let b_len = b_chars.len();
+
+    if a_len == 0 {
+        return b_len;
+    }
+    if b_len == 0 {
+        return a_len;
+    }
+
+    let mut matrix = vec![vec![0usize; b_len + 1]; a_len + 1];
+
+    for i in 0..=a_len {
+        matrix[i][0] = i;
+    }
+    for j in 0..=b_len {
+        matrix[0][j] = j;
+    }
+
+    for i in 1..=a_len {
+        for j in 1..=b_len {
+            let cost = if a_chars[i - 1] == b_chars[j - 1] { 0 } else { 1 };
+            matrix[i][j] = (matrix[i - 1][j] + 1)
+                .min(matrix[i][j - 1] + 1)
+                .min(matrix[i - 1][j - 1] + cost);
+        }
+    }
+
+    matrix[a_len][b_len]
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(levenshtein_distance("", ""), 0);
+        assert_eq!(levenshtein_distance("abc", "abc"), 0);
+        assert_eq!(levenshtein_distance("abc", "abd"), 1);
+        assert_eq!(levenshtein_distance("serde", "serda"), 1);
+        assert_eq!(levenshtein_distance("tokio", "tokyo"), 1);
+    }
+
+    #[test]
+    fn test_extract_github_org() {
+        assert_eq!(
+            extract_github_org("https://github.com/tokio-rs/tokio"),
+            Some("tokio-rs".to_string())
+        );
+        assert_eq!(
+            extract_github_org("git+https://github.com/serde-rs/serde"),
+            Some("serde-rs".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalize_cycle() {
+        // Cycle A -> B -> C should normalize to A -> B -> C (A is smallest)
+        let cycle = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(normalize_cycle(&cycle), vec!["a", "b", "c"]);
+
+        // Cycle C -> A -> B should normalize to A -> B -> C
+        let cycle2 = vec!["c".to_string(), "a".to_string(), "b".to_string()];
+        assert_eq!(normalize_cycle(&cycle2), vec!["a", "b", "c"]);
+
+        // Cycle B -> C -> A should normalize to A -> B -> C
+        let cycle3 = vec!["b".to_string(), "c".to_string(), "a".to_string()];
+        assert_eq!(normalize_cycle(&cycle3), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_adjacency_lists() {
+        let mut graph = DependencyGraph::new();
+
+        graph.add_node(DependencyNode {
+            id: "a".to_string(),
+            name: "a".to_string(),
+            version: None,
             source: DependencySource::Unknown,
             checksum: None,
             metadata: HashMap::new(),
@@ -1353,4 +4001,599 @@ mod tests {
 
         assert!(!report.cycles.is_empty());
     }
+
+    fn sbom_graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        let app = "app 0.1.0 (path+file:///src/app)";
+        let serde = "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200";
+        let fork = "git+https://github.com/acme/fork?branch=main#abc123";
+
+        graph.add_node(DependencyNode {
+            id: app.to_string(),
+            name: "app".to_string(),
+            version: Some("0.1.0".to_string()),
+            source: DependencySource::Path {
+                path: "/src/app/Cargo.toml".to_string(),
+            },
+            checksum: None,
+            metadata: HashMap::new(),
+        });
+        graph.add_node(DependencyNode {
+            id: serde.to_string(),
+            name: "serde".to_string(),
+            version: Some("1.0.200".to_string()),
+            source: DependencySource::Registry {
+                name: "crates.io".to_string(),
+                url: "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+            },
+            checksum: Some("deadbeef".to_string()),
+            metadata: HashMap::from([("license".to_string(), "MIT/Apache-2.0".to_string())]),
+        });
+        graph.add_node(DependencyNode {
+            id: fork.to_string(),
+            name: "fork".to_string(),
+            version: Some("0.2.0".to_string()),
+            source: DependencySource::Git {
+                url: "git+https://github.com/acme/fork?branch=main#abc123".to_string(),
+                rev: Some("abc123".to_string()),
+                branch: Some("main".to_string()),
+            },
+            checksum: None,
+            metadata: HashMap::new(),
+        });
+
+        for (to, kind) in [(serde, EdgeKind::Normal), (fork, EdgeKind::Dev)] {
+            graph.add_edge(DependencyEdge {
+                from: app.to_string(),
+                to: to.to_string(),
+                kind,
+                optional: false,
+                features: vec![],
+            });
+        }
+        graph.root_nodes.push(app.to_string());
+        graph.metadata.source_path = "/src/app".to_string();
+        graph.metadata.git_commit = Some("0123abcd".to_string());
+        graph
+    }
+
+    #[test]
+    fn test_sbom_format_parse() {
+        assert_eq!("spdx".parse::<SbomFormat>().unwrap(), SbomFormat::Spdx);
+        assert_eq!("CycloneDX".parse::<SbomFormat>().unwrap(), SbomFormat::CycloneDx);
+        assert_eq!("cdx".parse::<SbomFormat>().unwrap(), SbomFormat::CycloneDx);
+        assert!("xml".parse::<SbomFormat>().is_err());
+    }
+
+    #[test]
+    fn test_to_spdx() {
+        let doc = sbom_graph().to_spdx();
+        assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+        assert_eq!(doc["name"], "app");
+
+        let packages = doc["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 3);
+        let serde = packages.iter().find(|p| p["name"] == "serde").unwrap();
+        assert_eq!(serde["SPDXID"], "SPDXRef-Package-serde-1.0.200");
+        assert_eq!(serde["checksums"][0]["algorithm"], "SHA256");
+        assert_eq!(serde["checksums"][0]["checksumValue"], "deadbeef");
+        assert_eq!(serde["licenseDeclared"], "MIT OR Apache-2.0");
+        assert_eq!(
+            serde["downloadLocation"],
+            "https://static.crates.io/crates/serde/serde-1.0.200.crate"
+        );
+        assert_eq!(serde["externalRefs"][0]["referenceLocator"], "pkg:cargo/serde@1.0.200");
+
+        let fork = packages.iter().find(|p| p["name"] == "fork").unwrap();
+        assert_eq!(fork["downloadLocation"], "git+https://github.com/acme/fork@abc123");
+        assert_eq!(
+            fork["sourceInfo"],
+            "git https://github.com/acme/fork branch main rev abc123"
+        );
+
+        let relationships = doc["relationships"].as_array().unwrap();
+        let has = |element: &str, kind: &str, related: &str| {
+            relationships.iter().any(|r| {
+                r["spdxElementId"] == element
+                    && r["relationshipType"] == kind
+                    && r["relatedSpdxElement"] == related
+            })
+        };
+        assert!(has("SPDXRef-DOCUMENT", "DESCRIBES", "SPDXRef-Package-app-0.1.0"));
+        assert!(has(
+            "SPDXRef-Package-app-0.1.0",
+            "DEPENDS_ON",
+            "SPDXRef-Package-serde-1.0.200"
+        ));
+        assert!(has(
+            "SPDXRef-Package-fork-0.2.0",
+            "DEV_DEPENDENCY_OF",
+            "SPDXRef-Package-app-0.1.0"
+        ));
+    }
+
+    #[test]
+    fn test_to_cyclonedx() {
+        let bom = sbom_graph().to_cyclonedx();
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["specVersion"], "1.5");
+        assert!(bom["serialNumber"].as_str().unwrap().starts_with("urn:uuid:"));
+        assert!(bom["metadata"]["properties"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|p| p["name"] == "infrasim:git_commit" && p["value"] == "0123abcd"));
+
+        let components = bom["components"].as_array().unwrap();
+        let app = components.iter().find(|c| c["name"] == "app").unwrap();
+        assert_eq!(app["type"], "application");
+        let serde = components.iter().find(|c| c["name"] == "serde").unwrap();
+        assert_eq!(serde["type"], "library");
+        assert_eq!(serde["hashes"][0]["alg"], "SHA-256");
+        assert_eq!(serde["hashes"][0]["content"], "deadbeef");
+        let fork = components.iter().find(|c| c["name"] == "fork").unwrap();
+        assert_eq!(
+            fork["purl"],
+            "pkg:cargo/fork@0.2.0?vcs_url=git%2Bhttps%3A//github.com/acme/fork%40abc123"
+        );
+        assert_eq!(fork["externalReferences"][0]["type"], "vcs");
+
+        let app_deps = bom["dependencies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["ref"] == app["bom-ref"])
+            .unwrap();
+        assert_eq!(app_deps["dependsOn"].as_array().unwrap().len(), 2);
+    }
+
+    fn fork_patterns() -> Vec<SuspiciousPattern> {
+        let fork = "git+https://github.com/acme/fork?branch=main#abc123";
+        [(Severity::Low, PatternType::UnusualPin), (Severity::High, PatternType::NameConfusion)]
+            .into_iter()
+            .map(|(severity, pattern_type)| SuspiciousPattern {
+                pattern_type,
+                nodes_involved: vec![fork.to_string(), "missing".to_string()],
+                severity,
+                description: "fork of \"serde\" <pinned>".to_string(),
+                evidence: vec![],
+                confidence: 0.8,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_graph_format_parse() {
+        assert_eq!("dot".parse::<GraphFormat>().unwrap(), GraphFormat::Dot);
+        assert_eq!("GraphML".parse::<GraphFormat>().unwrap(), GraphFormat::GraphMl);
+        assert!("gexf".parse::<GraphFormat>().is_err());
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = sbom_graph().to_dot(&fork_patterns());
+        assert!(dot.starts_with("digraph \"app\" {\n"));
+        assert!(dot.ends_with("}\n"));
+
+        let line = |id: &str| {
+            dot.lines()
+                .find(|l| l.starts_with(&format!("  \"{}\" [", id)))
+                .unwrap()
+                .to_string()
+        };
+        let app = line("app 0.1.0 (path+file:///src/app)");
+        assert!(app.contains("label=\"app\\n0.1.0\""));
+        assert!(app.contains("fillcolor=\"#FF9800\""));
+        assert!(app.contains("peripheries=2"));
+        assert!(!app.contains("penwidth"));
+
+        let fork = line("git+https://github.com/acme/fork?branch=main#abc123");
+        assert!(fork.contains("fillcolor=\"#2196F3\""));
+        // Highest severity wins the border
+        assert!(fork.contains("color=\"#E53935\""));
+        assert!(fork.contains("penwidth=4"));
+        assert!(fork.contains("high NameConfusion: fork of \\\"serde\\\" <pinned>\\nlow UnusualPin"));
+
+        assert!(dot.contains(
+            "  \"app 0.1.0 (path+file:///src/app)\" -> \"git+https://github.com/acme/fork?branch=main#abc123\" [style=dashed];"
+        ));
+        assert!(!dot.contains("missing"));
+    }
+
+    #[test]
+    fn test_to_graphml() {
+        let xml = sbom_graph().to_graphml(&fork_patterns());
+        assert!(xml.contains("<graph id=\"app\" edgedefault=\"directed\">"));
+        assert_eq!(xml.matches("<node id=").count(), 3);
+        assert_eq!(xml.matches("<edge id=").count(), 2);
+
+        let fork = xml
+            .split("<node id=")
+            .find(|n| n.starts_with("\"git+https://github.com/acme/fork?branch=main#abc123\""))
+            .unwrap();
+        assert!(fork.contains("<data key=\"source\">git</data>"));
+        assert!(fork.contains("<data key=\"color\">#2196F3</data>"));
+        assert!(fork.contains("<data key=\"severity\">high</data>"));
+        assert!(fork.contains("fork of &quot;serde&quot; &lt;pinned&gt;"));
+
+        let serde = xml.split("<node id=").find(|n| n.contains(">serde<")).unwrap();
+        assert!(serde.contains("<data key=\"root\">false</data>"));
+        assert!(!serde.contains("key=\"severity\""));
+        assert!(xml.contains("<data key=\"kind\">dev</data>"));
+    }
+
+    #[test]
+    fn test_parse_package_lock() {
+        let lock = serde_json::json!({
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app", "version": "1.0.0", "dependencies": { "a": "^1.0.0", "lib": "*" },
+                      "devDependencies": { "b": "^2.0.0" } },
+                "node_modules/a": { "version": "1.0.0", "resolved": "https://registry.npmjs.org/a/-/a-1.0.0.tgz",
+                                    "integrity": "sha512-aaa", "dependencies": { "b": "^1.0.0" } },
+                "node_modules/a/node_modules/b": { "version": "1.5.0" },
+                "node_modules/b": { "version": "2.0.0", "dev": true },
+                "node_modules/lib": { "resolved": "packages/lib", "link": true },
+                "packages/lib": { "name": "lib", "version": "0.1.0", "dependencies": { "b": "^2.0.0" } }
+            }
+        });
+        let mut analyzer = PipelineAnalyzer::new();
+        analyzer.parse_package_lock(&lock, Path::new("/src/app")).unwrap();
+        let graph = &analyzer.graph;
+
+        assert_eq!(graph.root_nodes, vec!["npm:app@1.0.0"]);
+        assert_eq!(graph.nodes["npm:a@1.0.0"].metadata["integrity"], "sha512-aaa");
+        // Nested install shadows the hoisted version
+        assert_eq!(graph.outgoing_neighbors("npm:a@1.0.0"), ["npm:b@1.5.0"]);
+        let root_deps = graph.outgoing_neighbors("npm:app@1.0.0");
+        assert!(root_deps.contains(&"npm:lib@0.1.0".to_string()));
+        assert!(root_deps.contains(&"npm:b@2.0.0".to_string()));
+        assert_eq!(graph.outgoing_neighbors("npm:lib@0.1.0"), ["npm:b@2.0.0"]);
+        assert!(matches!(graph.nodes["npm:lib@0.1.0"].source, DependencySource::Path { .. }));
+    }
+
+    #[test]
+    fn test_parse_pnpm_lock() {
+        let lock: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+lockfileVersion: '9.0'
+importers:
+  .:
+    devDependencies:
+      typescript:
+        specifier: 5.5.4
+        version: 5.5.4
+  apps/web:
+    dependencies:
+      '@acme/ui':
+        specifier: workspace:*
+        version: link:../../packages/ui
+      react-dom:
+        specifier: 18.3.1
+        version: 18.3.1(react@18.3.1)
+  packages/ui: {}
+packages:
+  react-dom@18.3.1:
+    resolution: {integrity: sha512-dom}
+  react@18.3.1:
+    resolution: {integrity: sha512-react}
+  typescript@5.5.4:
+    resolution: {integrity: sha512-ts}
+snapshots:
+  react-dom@18.3.1(react@18.3.1):
+    dependencies:
+      react: 18.3.1
+  react@18.3.1: {}
+  typescript@5.5.4: {}
+"#,
+        )
+        .unwrap();
+        let mut analyzer = PipelineAnalyzer::new();
+        analyzer.parse_pnpm_lock(&lock, Path::new("/nonexistent/ui")).unwrap();
+        let graph = &analyzer.graph;
+
+        assert_eq!(graph.root_nodes.len(), 3);
+        assert_eq!(graph.nodes["npm:react@18.3.1"].metadata["integrity"], "sha512-react");
+        assert_eq!(graph.outgoing_neighbors("npm:react-dom@18.3.1"), ["npm:react@18.3.1"]);
+        let web = graph.outgoing_neighbors("npm:web");
+        assert!(web.contains(&"npm:ui".to_string()));
+        assert!(web.contains(&"npm:react-dom@18.3.1".to_string()));
+        assert!(graph
+            .edges
+            .iter()
+            .any(|e| e.to == "npm:typescript@5.5.4" && e.kind == EdgeKind::Dev));
+
+        let old: serde_yaml::Value = serde_yaml::from_str("lockfileVersion: 5.4").unwrap();
+        assert!(PipelineAnalyzer::new().parse_pnpm_lock(&old, Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn test_parse_go_module() {
+        let go_mod = r#"
+module example.com/appliance
+
+go 1.21
+
+require (
+	github.com/gorilla/mux v1.8.0
+	golang.org/x/sys v0.15.0 // indirect
+)
+
+require github.com/acme/tool v1.0.0
+
+replace github.com/acme/tool => ../tool
+"#;
+        let go_sum = "github.com/gorilla/mux v1.8.0 h1:muxhash=\n\
+                      github.com/gorilla/mux v1.8.0/go.mod h1:modhash=\n";
+        let mut analyzer = PipelineAnalyzer::new();
+        analyzer
+            .parse_go_module(Some(go_mod), Some(go_sum), Path::new("/nonexistent/appliance"))
+            .unwrap();
+        let graph = &analyzer.graph;
+
+        assert_eq!(graph.root_nodes, vec!["go:example.com/appliance"]);
+        assert_eq!(graph.outgoing_neighbors("go:example.com/appliance").len(), 3);
+        let mux = &graph.nodes["go:github.com/gorilla/mux@v1.8.0"];
+        assert_eq!(mux.metadata["go_sum"], "h1:muxhash=");
+        assert_eq!(analyzer.infer_vendor(mux), "gorilla");
+        assert_eq!(graph.nodes["go:golang.org/x/sys@v0.15.0"].metadata["indirect"], "true");
+        assert!(matches!(
+            graph.nodes["go:github.com/acme/tool@v1.0.0"].source,
+            DependencySource::Path { .. }
+        ));
+        assert_eq!(
+            download_location(mux),
+            "https://proxy.golang.org/github.com/gorilla/mux/@v/v1.8.0.zip"
+        );
+    }
+
+    #[test]
+    fn test_parse_poetry_lock() {
+        let lock: toml::Value = toml::from_str(
+            r#"
+[[package]]
+name = "Requests"
+version = "2.31.0"
+files = [
+    {file = "requests-2.31.0-py3-none-any.whl", hash = "sha256:wheel"},
+    {file = "requests-2.31.0.tar.gz", hash = "sha256:sdist"},
+]
+
+[package.dependencies]
+idna = ">=2.5,<4"
+PySocks = {version = "!=1.5.7", optional = true}
+
+[[package]]
+name = "idna"
+version = "3.6"
+
+[[package]]
+name = "pysocks"
+version = "1.7.1"
+
+[package.source]
+type = "git"
+url = "https://github.com/Anorov/PySocks.git"
+reference = "master"
+resolved_reference = "abc123"
+"#,
+        )
+        .unwrap();
+        let pyproject: toml::Value = toml::from_str(
+            r#"
+[tool.poetry]
+name = "agent"
+version = "0.2.0"
+
+[tool.poetry.dependencies]
+python = "^3.11"
+requests = "^2.31"
+"#,
+        )
+        .unwrap();
+        let mut analyzer = PipelineAnalyzer::new();
+        analyzer
+            .parse_poetry_lock(&lock, Some(&pyproject), Path::new("/src/agent"))
+            .unwrap();
+        let graph = &analyzer.graph;
+
+        assert_eq!(graph.root_nodes, vec!["pypi:agent@0.2.0"]);
+        assert_eq!(graph.outgoing_neighbors("pypi:agent@0.2.0"), ["pypi:Requests@2.31.0"]);
+        let requests = &graph.nodes["pypi:Requests@2.31.0"];
+        assert_eq!(requests.checksum.as_deref(), Some("sdist"));
+        assert_eq!(purl(requests).unwrap(), "pkg:pypi/requests@2.31.0");
+        assert!(graph
+            .edges
+            .iter()
+            .any(|e| e.to == "pypi:pysocks@1.7.1" && e.optional));
+        assert_eq!(
+            graph.nodes["pypi:pysocks@1.7.1"].source,
+            DependencySource::Git {
+                url: "https://github.com/Anorov/PySocks.git".to_string(),
+                rev: Some("abc123".to_string()),
+                branch: Some("master".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_requirements() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("requirements.txt"),
+            "# runtime\n\
+             flask==3.0.0 \\\n    --hash=sha256:flaskhash\n\
+             requests[socks]>=2.0 ; python_version > \"3.8\"\n\
+             tool @ git+https://github.com/acme/tool@v1.2#egg=tool\n\
+             -r requirements-dev.txt\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("requirements-dev.txt"), "pytest==8.0.0\n").unwrap();
+
+        let mut analyzer = PipelineAnalyzer::new();
+        analyzer
+            .load_lockfile(&dir.path().join("requirements.txt"))
+            .unwrap();
+        let graph = &analyzer.graph;
+
+        let root = &graph.root_nodes[0];
+        assert_eq!(graph.outgoing_neighbors(root).len(), 4);
+        assert_eq!(
+            graph.nodes["pypi:flask@3.0.0"].checksum.as_deref(),
+            Some("flaskhash")
+        );
+        assert_eq!(graph.nodes["pypi:requests"].metadata["specifier"], ">=2.0");
+        assert_eq!(
+            graph.nodes["pypi:tool"].source,
+            DependencySource::Git {
+                url: "git+https://github.com/acme/tool".to_string(),
+                rev: Some("v1.2".to_string()),
+                branch: None,
+            }
+        );
+        assert!(graph
+            .edges
+            .iter()
+            .any(|e| e.to == "pypi:pytest@8.0.0" && e.kind == EdgeKind::Dev));
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        use std::cmp::Ordering::*;
+        assert_eq!(compare_versions("1.2.10", "1.2.9"), Greater);
+        assert_eq!(compare_versions("v1.8.0", "1.8"), Equal);
+        assert_eq!(compare_versions("1.0.0-rc.1", "1.0.0"), Less);
+        assert_eq!(compare_versions("1.0.0-alpha", "1.0.0-beta"), Less);
+        assert_eq!(compare_versions("2.0rc1", "2.0"), Less);
+        assert_eq!(compare_versions("1.0.0+build5", "1.0.0"), Equal);
+    }
+
+    #[test]
+    fn test_cvss3_base_score() {
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Some(9.8)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"),
+            Some(6.1)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.0/AV:L/AC:H/PR:H/UI:N/S:U/C:N/I:N/A:N"),
+            Some(0.0)
+        );
+        assert_eq!(cvss3_base_score("AV:N/AC:L/Au:N/C:P/I:P/A:P"), None);
+    }
+
+    #[test]
+    fn test_correlate_advisories() {
+        let record: OsvRecord = serde_json::from_value(serde_json::json!({
+            "id": "RUSTSEC-2099-0001",
+            "aliases": ["CVE-2099-1234"],
+            "summary": "Memory corruption in serde",
+            "severity": [{ "type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H" }],
+            "affected": [{
+                "package": { "ecosystem": "crates.io", "name": "serde" },
+                "ranges": [{ "type": "SEMVER", "events": [
+                    { "introduced": "0" }, { "fixed": "1.0.100" },
+                    { "introduced": "1.0.150" }, { "fixed": "1.0.201" }
+                ]}]
+            }],
+            "references": [{ "type": "ADVISORY", "url": "https://rustsec.org/advisories/RUSTSEC-2099-0001" }]
+        }))
+        .unwrap();
+        let unmaintained: OsvRecord = serde_json::from_value(serde_json::json!({
+            "id": "RUSTSEC-2099-0002",
+            "summary": "fork is unmaintained",
+            "affected": [{ "package": { "ecosystem": "crates.io", "name": "fork" }, "versions": ["0.2.0"] }],
+            "database_specific": { "informational": "unmaintained" }
+        }))
+        .unwrap();
+        let db = AdvisoryDb::from_records([record, unmaintained]);
+        assert_eq!(db.len(), 2);
+        assert!(db.affecting(Ecosystem::Cargo, "serde", "1.0.99").len() == 1);
+        assert!(db.affecting(Ecosystem::Cargo, "serde", "1.0.120").is_empty());
+        assert!(db.affecting(Ecosystem::Npm, "serde", "1.0.200").is_empty());
+
+        let mut report = AnalysisReport {
+            graph: sbom_graph(),
+            ..Default::default()
+        };
+        report.correlate_advisories(&db);
+
+        // The git-sourced fork is still matched by name and version
+        assert_eq!(report.advisories.len(), 2);
+        let serde = &report.advisories[0];
+        assert_eq!(serde.id, "RUSTSEC-2099-0001");
+        assert_eq!(serde.severity, Severity::Critical);
+        assert_eq!(serde.cvss_score, Some(9.8));
+        assert_eq!(serde.fixed_versions, vec!["1.0.100", "1.0.201"]);
+        assert_eq!(report.advisories[1].severity, Severity::Info);
+        assert_eq!(report.risk_score, 25.5);
+        assert!(report
+            .recommendations
+            .iter()
+            .any(|r| r.contains("high or critical")));
+    }
+
+    #[test]
+    fn test_report_diff() {
+        let old = AnalysisReport {
+            graph: sbom_graph(),
+            risk_score: 10.0,
+            ..Default::default()
+        };
+        let mut new = old.clone();
+        let serde = "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200";
+        let mut upgraded = new.graph.nodes.remove(serde).unwrap();
+        upgraded.id = serde.replace("1.0.200", "1.0.201");
+        upgraded.version = Some("1.0.201".to_string());
+        new.graph.add_node(upgraded);
+        new.graph.nodes.remove("git+https://github.com/acme/fork?branch=main#abc123");
+        new.graph.add_node(DependencyNode {
+            id: "registry+https://github.com/rust-lang/crates.io-index#log@0.4.22".to_string(),
+            name: "log".to_string(),
+            version: Some("0.4.22".to_string()),
+            source: DependencySource::Registry {
+                name: "crates.io".to_string(),
+                url: "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+            },
+            checksum: None,
+            metadata: HashMap::new(),
+        });
+        new.risk_score = 35.5;
+        new.graph.metadata.git_commit = Some("def456".to_string());
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.to_commit.as_deref(), Some("def456"));
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "log");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].name, "fork");
+        assert_eq!(
+            diff.version_changes,
+            vec![VersionChange {
+                name: "serde".to_string(),
+                ecosystem: Ecosystem::Cargo,
+                from: Some("1.0.200".to_string()),
+                to: Some("1.0.201".to_string()),
+            }]
+        );
+        assert_eq!(diff.risk_delta, 25.5);
+        assert!(!diff.is_empty());
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_affects_analysis() {
+        assert!(affects_analysis("Cargo.lock"));
+        assert!(affects_analysis("crates/web/Cargo.toml"));
+        assert!(affects_analysis("crates/web/build.rs"));
+        assert!(affects_analysis("ui/package-lock.json"));
+        assert!(affects_analysis("requirements-dev.txt"));
+        assert!(affects_analysis("vendor/serde/src/lib.rs"));
+        assert!(affects_analysis(".cargo/config.toml"));
+        assert!(!affects_analysis("crates/web/src/server.rs"));
+        assert!(!affects_analysis("README.md"));
+    }
 }
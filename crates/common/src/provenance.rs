@@ -0,0 +1,295 @@
+//! Provenance ledger of resource specs
+//!
+//! Where the audit log records calls, the ledger records what they did to
+//! each resource: every spec a resource is created with or updated to, and
+//! its deletion, in the append-only `provenance_ledger` table.
+//!
+//! Each resource has its own chain. A record stores the spec, its digest and
+//! the hash of the resource's previous record as its parent digest, and is
+//! signed by the daemon's key, so the lineage of a resource can be checked
+//! with [`verify_chain`] long after the fact.
+
+use crate::audit::ChainVerification;
+use crate::crypto::{key_id, KeyPair, Signer, Verifier};
+use crate::{Database, Error, Result};
+use ed25519_dalek::VerifyingKey;
+use rusqlite::{params, OptionalExtension, Row, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Change a ledger record describes
+pub const CHANGE_CREATED: &str = "created";
+pub const CHANGE_UPDATED: &str = "updated";
+pub const CHANGE_DELETED: &str = "deleted";
+
+/// One version of a resource
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceRecord {
+    /// Position in the whole ledger, assigned on append
+    pub seq: i64,
+    pub resource_kind: String,
+    pub resource_id: String,
+    /// Position in the resource's chain, counting from 1
+    pub version: i64,
+    pub timestamp: i64,
+    /// [`CHANGE_CREATED`], [`CHANGE_UPDATED`] or [`CHANGE_DELETED`]
+    pub change: String,
+    /// Spec as JSON; none for deletions
+    pub spec: Option<String>,
+    /// SHA-256 of `spec`
+    pub spec_digest: Option<String>,
+    /// Hash of the resource's previous record
+    pub parent_digest: Option<String>,
+    /// Hash of this record, including `parent_digest`
+    pub hash: String,
+    /// Hex Ed25519 signature over `hash`
+    pub signature: Option<String>,
+    /// ID of the key that made `signature`
+    pub key_id: Option<String>,
+}
+
+impl ProvenanceRecord {
+    /// Hash over everything but the hash and signature themselves
+    fn compute_hash(&self) -> String {
+        let fields = (
+            &self.resource_kind,
+            &self.resource_id,
+            self.version,
+            self.timestamp,
+            &self.change,
+            &self.spec_digest,
+            &self.parent_digest,
+        );
+        // Serializing a tuple of plain values can't fail
+        hex::encode(Sha256::digest(serde_json::to_vec(&fields).unwrap_or_default()))
+    }
+
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            seq: row.get(0)?,
+            resource_kind: row.get(1)?,
+            resource_id: row.get(2)?,
+            version: row.get(3)?,
+            timestamp: row.get(4)?,
+            change: row.get(5)?,
+            spec: row.get(6)?,
+            spec_digest: row.get(7)?,
+            parent_digest: row.get(8)?,
+            hash: row.get(9)?,
+            signature: row.get(10)?,
+            key_id: row.get(11)?,
+        })
+    }
+}
+
+const COLUMNS: &str = "seq, resource_kind, resource_id, version, timestamp, change, spec, spec_digest, \
+     parent_digest, hash, signature, key_id";
+
+/// Provenance ledger in a state database
+#[derive(Clone)]
+pub struct ProvenanceLedger {
+    db: Database,
+    signer: Option<KeyPair>,
+}
+
+impl ProvenanceLedger {
+    pub fn new(db: Database) -> Self {
+        Self { db, signer: None }
+    }
+
+    /// Sign each new record's hash with `signer`
+    pub fn with_signer(mut self, signer: KeyPair) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Append the next version of a resource; `spec` is none for deletions
+    ///
+    /// Updates that leave the spec as it was aren't versions and return none.
+    pub fn record<S: Serialize>(
+        &self,
+        resource_kind: &str,
+        resource_id: &str,
+        change: &str,
+        spec: Option<&S>,
+    ) -> Result<Option<ProvenanceRecord>> {
+        let spec = spec.map(serde_json::to_string).transpose()?;
+        let conn_arc = self.db.connection();
+        let mut conn = conn_arc.lock();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let parent: Option<(i64, String, Option<String>)> = tx
+            .query_row(
+                "SELECT version, hash, spec_digest FROM provenance_ledger
+                 WHERE resource_id = ?1 ORDER BY version DESC LIMIT 1",
+                params![resource_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        let spec_digest = spec.as_deref().map(spec_digest);
+        if change == CHANGE_UPDATED && parent.as_ref().is_some_and(|(_, _, parent)| *parent == spec_digest) {
+            return Ok(None);
+        }
+        let mut record = ProvenanceRecord {
+            resource_kind: resource_kind.to_string(),
+            resource_id: resource_id.to_string(),
+            version: parent.as_ref().map_or(1, |(version, _, _)| version + 1),
+            timestamp: chrono::Utc::now().timestamp(),
+            change: change.to_string(),
+            spec_digest,
+            spec,
+            parent_digest: parent.map(|(_, hash, _)| hash),
+            ..Default::default()
+        };
+        record.hash = record.compute_hash();
+        if let Some(signer) = &self.signer {
+            record.signature = Some(hex::encode(signer.sign(record.hash.as_bytes())));
+            record.key_id = Some(signer.key_id());
+        }
+
+        tx.execute(
+            &format!(
+                "INSERT INTO provenance_ledger ({}) VALUES (NULL, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                COLUMNS
+            ),
+            params![
+                record.resource_kind,
+                record.resource_id,
+                record.version,
+                record.timestamp,
+                record.change,
+                record.spec,
+                record.spec_digest,
+                record.parent_digest,
+                record.hash,
+                record.signature,
+                record.key_id,
+            ],
+        )?;
+        record.seq = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(Some(record))
+    }
+
+    /// Every version of a resource, oldest first
+    pub fn chain(&self, resource_id: &str) -> Result<Vec<ProvenanceRecord>> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM provenance_ledger WHERE resource_id = ?1 ORDER BY version",
+            COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![resource_id], ProvenanceRecord::from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// SHA-256 of a spec's JSON, matching the audit log's digests of it
+fn spec_digest(spec: &str) -> String {
+    hex::encode(Sha256::digest(spec.as_bytes()))
+}
+
+/// Check that `records`, one resource's chain oldest first, link up and
+/// match their specs
+///
+/// Signatures are checked against `key` when one is given; records signed by
+/// any other key, or not at all, then break the chain. `broken_at` is the
+/// version of the first record that doesn't fit.
+pub fn verify_chain(records: &[ProvenanceRecord], key: Option<&VerifyingKey>) -> ChainVerification {
+    let expected_key_id = key.map(|k| key_id(k.as_bytes()));
+    let mut result = ChainVerification::default();
+    let mut parent: Option<&str> = None;
+    for (index, record) in records.iter().enumerate() {
+        let problem = if record.version != index as i64 + 1 {
+            Some(format!("expected version {}", index + 1))
+        } else if record.parent_digest.as_deref() != parent {
+            Some("parent digest doesn't match the previous record".to_string())
+        } else if record.spec_digest != record.spec.as_deref().map(spec_digest) {
+            Some("spec doesn't match its digest".to_string())
+        } else if record.hash != record.compute_hash() {
+            Some("hash doesn't match the record's contents".to_string())
+        } else {
+            key.and_then(|key| match &record.signature {
+                None => Some("not signed".to_string()),
+                Some(_) if record.key_id != expected_key_id => {
+                    Some(format!("signed by unknown key {}", record.key_id.as_deref().unwrap_or("?")))
+                }
+                Some(signature) => hex::decode(signature)
+                    .map_err(|e| Error::Crypto(e.to_string()))
+                    .and_then(|s| key.verify(record.hash.as_bytes(), &s))
+                    .err()
+                    .map(|e| format!("bad signature: {}", e)),
+            })
+        };
+        if let Some(problem) = problem {
+            result.broken_at = Some(record.version);
+            result.error = Some(problem);
+            return result;
+        }
+        parent = Some(&record.hash);
+        result.verified += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_chain_per_resource() {
+        let db = Database::open_memory().unwrap();
+        let key = KeyPair::generate();
+        let ledger = ProvenanceLedger::new(db.clone()).with_signer(key.clone());
+
+        let created = ledger.record("vm", "vm-1", CHANGE_CREATED, Some(&json!({"cpus": 2}))).unwrap().unwrap();
+        ledger.record("vm", "vm-2", CHANGE_CREATED, Some(&json!({"cpus": 1}))).unwrap();
+        let updated = ledger.record("vm", "vm-1", CHANGE_UPDATED, Some(&json!({"cpus": 4}))).unwrap().unwrap();
+        let unchanged = ledger.record("vm", "vm-1", CHANGE_UPDATED, Some(&json!({"cpus": 4}))).unwrap();
+        assert_eq!(unchanged, None);
+        ledger.record::<()>("vm", "vm-1", CHANGE_DELETED, None).unwrap();
+
+        let chain = ledger.chain("vm-1").unwrap();
+        assert_eq!(chain.iter().map(|r| r.version).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(chain[0], created);
+        assert_eq!(created.parent_digest, None);
+        assert_eq!(updated.parent_digest.as_deref(), Some(created.hash.as_str()));
+        assert_eq!(updated.seq, 3);
+        assert_eq!(chain[2].spec, None);
+        assert_eq!(ledger.chain("vm-2").unwrap().len(), 1);
+
+        let result = verify_chain(&chain, Some(&key.verifying_key()));
+        assert!(result.is_valid(), "{:?}", result);
+        assert_eq!(result.verified, 3);
+        let other = KeyPair::generate();
+        assert_eq!(verify_chain(&chain, Some(&other.verifying_key())).broken_at, Some(1));
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let db = Database::open_memory().unwrap();
+        let ledger = ProvenanceLedger::new(db.clone());
+        for cpus in 1..=3 {
+            ledger.record("vm", "vm-1", CHANGE_UPDATED, Some(&json!({"cpus": cpus}))).unwrap();
+        }
+
+        let conn = db.connection();
+        assert!(conn.lock().execute("DELETE FROM provenance_ledger", []).is_err());
+
+        let mut chain = ledger.chain("vm-1").unwrap();
+        assert!(verify_chain(&chain, None).is_valid());
+        // Unsigned records only pass when no key is asked for
+        let key = KeyPair::generate();
+        assert_eq!(verify_chain(&chain, Some(&key.verifying_key())).broken_at, Some(1));
+
+        chain[1].spec = Some(json!({"cpus": 8}).to_string());
+        let result = verify_chain(&chain, None);
+        assert_eq!(result.broken_at, Some(2));
+        assert!(result.error.unwrap().contains("digest"));
+
+        chain.remove(1);
+        let result = verify_chain(&chain, None);
+        assert_eq!(result.broken_at, Some(3));
+    }
+}
@@ -0,0 +1,257 @@
+//! QEMU guest agent client
+//!
+//! Talks to qemu-guest-agent in the guest over the virtio-serial channel QEMU
+//! exposes as a Unix socket. The agent speaks QMP-style JSON without a
+//! greeting; the channel carries no framing, so each session starts with
+//! `guest-sync-delimited` to drop whatever a previous client left behind.
+
+use crate::{Error, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tracing::{debug, trace};
+
+/// Bytes sent per `guest-file-write`; the agent caps a command at 48 MiB of
+/// base64, smaller chunks keep the guest responsive
+pub const FILE_WRITE_CHUNK: usize = 64 * 1024;
+
+/// How long the agent gets to answer the sync, i.e. to show it is running
+const SYNC_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long any other command may take
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Byte the agent resets its parser on, and prefixes the sync reply with
+const DELIMITER: u8 = 0xff;
+
+/// Guest agent session
+pub struct GuestAgentClient {
+    stream: BufReader<UnixStream>,
+}
+
+impl GuestAgentClient {
+    /// Connect to a VM's guest agent socket and sync with the agent
+    pub async fn connect(socket_path: impl AsRef<Path>) -> Result<Self> {
+        let socket_path = socket_path.as_ref();
+        let stream = UnixStream::connect(socket_path).await.map_err(|e| {
+            Error::GuestAgent(format!("Failed to connect to {}: {}", socket_path.display(), e))
+        })?;
+        let mut client = Self {
+            stream: BufReader::new(stream),
+        };
+        tokio::time::timeout(SYNC_TIMEOUT, client.sync())
+            .await
+            .map_err(|_| {
+                Error::GuestAgent(
+                    "No reply from the guest agent; is qemu-guest-agent running in the guest?"
+                        .to_string(),
+                )
+            })??;
+        debug!("Connected to guest agent: {}", socket_path.display());
+        Ok(client)
+    }
+
+    /// Discard stale output and wait for the reply to a fresh sync ID
+    async fn sync(&mut self) -> Result<()> {
+        let id = rand::random::<u32>() as u64;
+        let cmd = serde_json::to_string(&GuestCommand {
+            execute: "guest-sync-delimited",
+            arguments: Some(SyncArgs { id }),
+        })?;
+        let writer = self.stream.get_mut();
+        writer.write_all(&[DELIMITER]).await?;
+        writer.write_all(cmd.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+
+        loop {
+            let mut skipped = Vec::new();
+            self.stream.read_until(DELIMITER, &mut skipped).await?;
+            if skipped.last() != Some(&DELIMITER) {
+                return Err(Error::GuestAgent("Guest agent channel closed".to_string()));
+            }
+            let mut line = String::new();
+            self.stream.read_line(&mut line).await?;
+            trace!("QGA sync response: {}", line.trim());
+            // Replies to an earlier client's sync carry its ID
+            if let Ok(reply) = serde_json::from_str::<GuestResponse<u64>>(&line) {
+                if reply.result == Some(id) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Execute a guest agent command
+    pub async fn execute<A: Serialize, R: DeserializeOwned>(
+        &mut self,
+        command: &str,
+        arguments: Option<A>,
+    ) -> Result<R> {
+        let cmd = serde_json::to_string(&GuestCommand {
+            execute: command,
+            arguments,
+        })?;
+        trace!("QGA command: {}", command);
+
+        let exchange = async {
+            let writer = self.stream.get_mut();
+            writer.write_all(cmd.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+            writer.flush().await?;
+
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(Error::GuestAgent("Guest agent channel closed".to_string()));
+            }
+            trace!("QGA response: {}", line.trim());
+            let response: GuestResponse<R> = serde_json::from_str(&line)
+                .map_err(|e| Error::GuestAgent(format!("Invalid response: {}", e)))?;
+            if let Some(error) = response.error {
+                return Err(Error::GuestAgent(format!("{}: {}", error.class, error.desc)));
+            }
+            response
+                .result
+                .ok_or_else(|| Error::GuestAgent("No return value".to_string()))
+        };
+        tokio::time::timeout(COMMAND_TIMEOUT, exchange)
+            .await
+            .map_err(|_| Error::Timeout {
+                seconds: COMMAND_TIMEOUT.as_secs(),
+            })?
+    }
+
+    /// Open a file in the guest, returning its handle
+    ///
+    /// `mode` is an fopen mode; "w" creates or truncates.
+    pub async fn file_open(&mut self, path: &str, mode: &str) -> Result<i64> {
+        self.execute(
+            "guest-file-open",
+            Some(serde_json::json!({ "path": path, "mode": mode })),
+        )
+        .await
+    }
+
+    /// Write all of `data` to an open guest file
+    pub async fn file_write(&mut self, handle: i64, data: &[u8]) -> Result<()> {
+        for chunk in data.chunks(FILE_WRITE_CHUNK) {
+            let written: FileWriteResult = self
+                .execute(
+                    "guest-file-write",
+                    Some(serde_json::json!({ "handle": handle, "buf-b64": BASE64.encode(chunk) })),
+                )
+                .await?;
+            if written.count != chunk.len() as u64 {
+                return Err(Error::GuestAgent(format!(
+                    "Short write in guest: {} of {} bytes",
+                    written.count,
+                    chunk.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Close a guest file
+    pub async fn file_close(&mut self, handle: i64) -> Result<()> {
+        let _: serde_json::Value = self
+            .execute("guest-file-close", Some(serde_json::json!({ "handle": handle })))
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GuestCommand<'a, A> {
+    execute: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    arguments: Option<A>,
+}
+
+#[derive(Debug, Serialize)]
+struct SyncArgs {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuestResponse<T> {
+    #[serde(rename = "return")]
+    result: Option<T>,
+    error: Option<GuestError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GuestError {
+    class: String,
+    desc: String,
+}
+
+/// Result of `guest-file-write`
+#[derive(Debug, Deserialize)]
+struct FileWriteResult {
+    count: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UnixListener;
+
+    /// Agent that leaves stale output before the sync reply and records writes
+    async fn fake_agent(listener: UnixListener) -> Vec<u8> {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        let mut written = Vec::new();
+        loop {
+            let mut line = Vec::new();
+            if stream.read_until(b'\n', &mut line).await.unwrap() == 0 {
+                return written;
+            }
+            let line = line.strip_prefix(&[DELIMITER]).unwrap_or(&line);
+            let cmd: serde_json::Value = serde_json::from_slice(line).unwrap();
+            let args = &cmd["arguments"];
+            let reply = match cmd["execute"].as_str().unwrap() {
+                "guest-sync-delimited" => {
+                    stream.get_mut().write_all(b"{\"return\": 7}\n\xff").await.unwrap();
+                    serde_json::json!({ "return": args["id"] })
+                }
+                "guest-file-open" => serde_json::json!({ "return": 1000 }),
+                "guest-file-write" => {
+                    let data = BASE64.decode(args["buf-b64"].as_str().unwrap()).unwrap();
+                    written.extend_from_slice(&data);
+                    serde_json::json!({ "return": { "count": data.len(), "eof": false } })
+                }
+                "guest-file-close" => serde_json::json!({ "return": {} }),
+                other => serde_json::json!({ "error": { "class": "CommandNotFound", "desc": other } }),
+            };
+            let reply = format!("{}\n", reply);
+            stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_and_file_write() {
+        let socket = std::env::temp_dir().join(format!("qga-test-{}.sock", rand::random::<u32>()));
+        let listener = UnixListener::bind(&socket).unwrap();
+        let agent = tokio::spawn(fake_agent(listener));
+
+        let mut client = GuestAgentClient::connect(&socket).await.unwrap();
+        let data: Vec<u8> = (0..FILE_WRITE_CHUNK * 2 + 10).map(|i| i as u8).collect();
+        let handle = client.file_open("/tmp/upload", "w").await.unwrap();
+        assert_eq!(handle, 1000);
+        client.file_write(handle, &data).await.unwrap();
+        client.file_close(handle).await.unwrap();
+        let err = client
+            .execute::<(), serde_json::Value>("guest-unknown", None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("CommandNotFound"));
+        drop(client);
+
+        assert_eq!(agent.await.unwrap(), data);
+        let _ = std::fs::remove_file(socket);
+    }
+}
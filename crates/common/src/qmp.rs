@@ -131,6 +131,34 @@ impl QmpClient {
         }
     }
 
+    /// Send a command as given, e.g. `{"execute": "query-block"}`, and return
+    /// QEMU's reply whether it succeeded or not
+    pub async fn execute_raw(&self, command: &serde_json::Value) -> Result<serde_json::Value> {
+        let mut guard = self.stream.lock().await;
+        let reader = guard.as_mut().ok_or_else(|| Error::Qmp("Not connected".to_string()))?;
+
+        let cmd_str = serde_json::to_string(command)?;
+        trace!("QMP command: {}", cmd_str);
+        let writer = reader.get_mut();
+        writer.write_all(cmd_str.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+        writer.flush().await?;
+
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 {
+                return Err(Error::Qmp("Monitor closed the connection".to_string()));
+            }
+            trace!("QMP response: {}", line.trim());
+
+            let response: serde_json::Value = serde_json::from_str(&line)
+                .map_err(|e| Error::Qmp(format!("Invalid response: {}", e)))?;
+            if response.get("event").is_none() {
+                return Ok(response);
+            }
+        }
+    }
+
     /// Execute a command with no return value
     pub async fn execute_void<A: Serialize>(&self, command: &str, arguments: Option<A>) -> Result<()> {
         let _: serde_json::Value = self.execute(command, arguments).await?;
@@ -177,6 +205,11 @@ impl QmpClient {
         self.execute("query-block", None::<()>).await
     }
 
+    /// Query I/O counters of attached block devices
+    pub async fn query_blockstats(&self) -> Result<Vec<BlockStatsEntry>> {
+        self.execute("query-blockstats", None::<()>).await
+    }
+
     /// Save VM memory to file
     pub async fn dump_guest_memory(&self, path: &str, paging: bool) -> Result<()> {
         #[derive(Serialize)]
@@ -211,24 +244,182 @@ impl QmpClient {
         self.execute_hmp(&format!("loadvm {}", name)).await
     }
 
+    /// Freeze a drive's current image and continue writes in a new qcow2 overlay
+    pub async fn blockdev_snapshot_sync(&self, device: &str, overlay: &str) -> Result<()> {
+        self.execute_void(
+            "blockdev-snapshot-sync",
+            Some(serde_json::json!({
+                "device": device,
+                "snapshot-file": overlay,
+                "format": "qcow2",
+                "mode": "absolute-paths",
+            })),
+        )
+        .await
+    }
+
+    /// Make `overlay`, an already added node without a backing image, the new top of `node`
+    pub async fn blockdev_snapshot(&self, node: &str, overlay: &str) -> Result<()> {
+        self.execute_void(
+            "blockdev-snapshot",
+            Some(serde_json::json!({ "node": node, "overlay": overlay })),
+        )
+        .await
+    }
+
+    /// Query all named block nodes, including backing images
+    pub async fn query_named_block_nodes(&self) -> Result<Vec<BlockNode>> {
+        self.execute("query-named-block-nodes", None::<()>).await
+    }
+
+    /// Copy data from backing images into `node` down to `base` (all of them if None)
+    ///
+    /// The job is not auto-dismissed so its outcome can be read with [`Self::query_jobs`].
+    pub async fn block_stream(&self, job_id: &str, node: &str, base: Option<&str>) -> Result<()> {
+        let mut args = serde_json::json!({
+            "job-id": job_id,
+            "device": node,
+            "auto-dismiss": false,
+        });
+        if let Some(base) = base {
+            args["base"] = serde_json::Value::String(base.to_string());
+        }
+        self.execute_void("block-stream", Some(args)).await
+    }
+
+    /// Grow or shrink a block node's virtual size
+    pub async fn block_resize(&self, node: &str, size: u64) -> Result<()> {
+        self.execute_void(
+            "block_resize",
+            Some(serde_json::json!({ "node-name": node, "size": size })),
+        )
+        .await
+    }
+
+    /// Run several actions atomically, e.g. adding dirty bitmaps and starting backup jobs
+    pub async fn transaction(&self, actions: Vec<serde_json::Value>) -> Result<()> {
+        self.execute_void("transaction", Some(serde_json::json!({ "actions": actions })))
+            .await
+    }
+
+    /// Delete a dirty bitmap from a block node
+    pub async fn block_dirty_bitmap_remove(&self, node: &str, name: &str) -> Result<()> {
+        self.execute_void(
+            "block-dirty-bitmap-remove",
+            Some(serde_json::json!({ "node": node, "name": name })),
+        )
+        .await
+    }
+
+    /// Query background jobs
+    pub async fn query_jobs(&self) -> Result<Vec<JobInfo>> {
+        self.execute("query-jobs", None::<()>).await
+    }
+
+    /// Remove a concluded job
+    pub async fn job_dismiss(&self, id: &str) -> Result<()> {
+        self.execute_void("job-dismiss", Some(serde_json::json!({ "id": id })))
+            .await
+    }
+
     /// Execute HMP (Human Monitor Protocol) command
     pub async fn execute_hmp(&self, command: &str) -> Result<()> {
+        self.hmp(command).await.map(|_| ())
+    }
+
+    /// Execute HMP command and return its text output
+    pub async fn hmp(&self, command: &str) -> Result<String> {
         #[derive(Serialize)]
         struct Args {
             #[serde(rename = "command-line")]
             command_line: String,
         }
 
-        let _: serde_json::Value = self.execute(
+        let output: serde_json::Value = self.execute(
             "human-monitor-command",
             Some(Args {
                 command_line: command.to_string(),
             }),
         )
         .await?;
+        Ok(output.as_str().unwrap_or_default().to_string())
+    }
+
+    /// Add a user-mode network host forward (e.g. `tcp:127.0.0.1:8080-:80`)
+    pub async fn hostfwd_add(&self, netdev: &str, rule: &str) -> Result<()> {
+        // HMP reports failures as text output rather than a QMP error
+        let output = self.hmp(&format!("hostfwd_add {} {}", netdev, rule)).await?;
+        if !output.trim().is_empty() {
+            return Err(Error::Qmp(output.trim().to_string()));
+        }
+        Ok(())
+    }
+
+    /// Remove a user-mode network host forward (e.g. `tcp:127.0.0.1:8080`)
+    pub async fn hostfwd_remove(&self, netdev: &str, rule: &str) -> Result<()> {
+        let output = self.hmp(&format!("hostfwd_remove {} {}", netdev, rule)).await?;
+        if !output.trim().is_empty() && !output.contains("removed") {
+            return Err(Error::Qmp(output.trim().to_string()));
+        }
         Ok(())
     }
 
+    /// List CPU slots and whether each is populated
+    pub async fn query_hotpluggable_cpus(&self) -> Result<Vec<HotpluggableCpu>> {
+        self.execute("query-hotpluggable-cpus", None::<()>).await
+    }
+
+    /// Hot-plug a device; `args` holds `driver`, `id` and driver properties
+    pub async fn device_add(&self, args: serde_json::Value) -> Result<()> {
+        self.execute_void("device_add", Some(args)).await
+    }
+
+    /// Request removal of a device (completes asynchronously once the guest acks)
+    pub async fn device_del(&self, id: &str) -> Result<()> {
+        self.execute_void("device_del", Some(serde_json::json!({ "id": id }))).await
+    }
+
+    /// Create a backend object such as a memory backend
+    pub async fn object_add(&self, args: serde_json::Value) -> Result<()> {
+        self.execute_void("object-add", Some(args)).await
+    }
+
+    /// Delete an object created with [`Self::object_add`]
+    pub async fn object_del(&self, id: &str) -> Result<()> {
+        self.execute_void("object-del", Some(serde_json::json!({ "id": id }))).await
+    }
+
+    /// Add a block node graph
+    pub async fn blockdev_add(&self, args: serde_json::Value) -> Result<()> {
+        self.execute_void("blockdev-add", Some(args)).await
+    }
+
+    /// Delete a block node that is no longer attached to a device
+    pub async fn blockdev_del(&self, node_name: &str) -> Result<()> {
+        self.execute_void("blockdev-del", Some(serde_json::json!({ "node-name": node_name })))
+            .await
+    }
+
+    /// Set the balloon target size in bytes
+    pub async fn balloon(&self, bytes: u64) -> Result<()> {
+        self.execute_void("balloon", Some(serde_json::json!({ "value": bytes }))).await
+    }
+
+    /// Query the current balloon size
+    pub async fn query_balloon(&self) -> Result<BalloonInfo> {
+        self.execute("query-balloon", None::<()>).await
+    }
+
+    /// Migrate the VM state to `uri`, e.g. `file:/path` to save RAM to disk
+    pub async fn migrate(&self, uri: &str) -> Result<()> {
+        self.execute_void("migrate", Some(serde_json::json!({ "uri": uri }))).await
+    }
+
+    /// Query the progress of an outgoing or incoming migration
+    pub async fn query_migrate(&self) -> Result<MigrationInfo> {
+        self.execute("query-migrate", None::<()>).await
+    }
+
     /// Query VNC server info
     pub async fn query_vnc(&self) -> Result<VncInfo> {
         self.execute("query-vnc", None::<()>).await
@@ -362,6 +553,87 @@ pub struct BlockInserted {
     pub drv: String,
 }
 
+/// Block device I/O counters from query-blockstats
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockStatsEntry {
+    #[serde(default)]
+    pub device: String,
+    pub stats: BlockStats,
+}
+
+/// Cumulative I/O counters of one block device
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BlockStats {
+    pub rd_bytes: u64,
+    pub wr_bytes: u64,
+    pub rd_operations: u64,
+    pub wr_operations: u64,
+}
+
+/// Block node from query-named-block-nodes
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockNode {
+    #[serde(rename = "node-name")]
+    pub node_name: String,
+    pub file: String,
+    pub drv: String,
+    pub backing_file: Option<String>,
+    #[serde(default, rename = "dirty-bitmaps")]
+    pub dirty_bitmaps: Vec<DirtyBitmap>,
+}
+
+/// Dirty bitmap of a block node
+#[derive(Debug, Clone, Deserialize)]
+pub struct DirtyBitmap {
+    pub name: String,
+    #[serde(default)]
+    pub persistent: bool,
+    /// Set when a persistent bitmap was not saved cleanly and can't be trusted
+    #[serde(default)]
+    pub inconsistent: bool,
+}
+
+/// Background job from query-jobs
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub job_type: String,
+    /// Job state, `concluded` once finished
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// CPU slot from query-hotpluggable-cpus
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HotpluggableCpu {
+    /// Device driver to pass to device_add
+    #[serde(rename = "type")]
+    pub driver: String,
+    pub vcpus_count: u32,
+    /// Topology properties (socket-id, core-id, ...) identifying the slot
+    pub props: serde_json::Map<String, serde_json::Value>,
+    /// Set when the slot is populated
+    pub qom_path: Option<String>,
+}
+
+/// Migration progress from query-migrate
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MigrationInfo {
+    /// `active`, `completed`, `failed`, ...; absent before any migration
+    pub status: Option<String>,
+    pub error_desc: Option<String>,
+}
+
+/// Balloon info from query-balloon
+#[derive(Debug, Clone, Deserialize)]
+pub struct BalloonInfo {
+    /// Current guest memory in bytes
+    pub actual: u64,
+}
+
 /// VNC server info
 #[derive(Debug, Clone, Deserialize)]
 pub struct VncInfo {
@@ -442,6 +714,58 @@ mod tests {
         assert!(response.result.unwrap().running);
     }
 
+    #[test]
+    fn test_hotpluggable_cpus_parsing() {
+        let json = r#"{"return": [
+            {"props": {"core-id": 1, "thread-id": 0, "socket-id": 0}, "vcpus-count": 1, "type": "host-arm-cpu"},
+            {"props": {"core-id": 0, "thread-id": 0, "socket-id": 0}, "vcpus-count": 1, "qom-path": "/machine/unattached/device[0]", "type": "host-arm-cpu"}
+        ]}"#;
+        let response: QmpResponse<Vec<HotpluggableCpu>> = serde_json::from_str(json).unwrap();
+        let cpus = response.result.unwrap();
+        assert_eq!(cpus.len(), 2);
+        assert_eq!(cpus[0].driver, "host-arm-cpu");
+        assert!(cpus[0].qom_path.is_none());
+        assert!(cpus[1].qom_path.is_some());
+        assert_eq!(cpus[0].props["core-id"], 1);
+    }
+
+    #[test]
+    fn test_block_node_and_job_parsing() {
+        let json = r##"{"return": [
+            {"node-name": "#block312", "file": "/store/snapshots/vm/a.qcow2", "drv": "qcow2", "ro": false,
+             "backing_file": "/store/volumes/v/overlay.qcow2", "backing_file_depth": 2},
+            {"node-name": "#block104", "file": "/store/base.raw", "drv": "raw", "ro": true}
+        ]}"##;
+        let response: QmpResponse<Vec<BlockNode>> = serde_json::from_str(json).unwrap();
+        let nodes = response.result.unwrap();
+        assert_eq!(nodes[0].node_name, "#block312");
+        assert_eq!(nodes[0].backing_file.as_deref(), Some("/store/volumes/v/overlay.qcow2"));
+        assert!(nodes[1].backing_file.is_none());
+
+        let json = r#"{"return": [
+            {"current-progress": 10, "status": "concluded", "total-progress": 10, "type": "stream",
+             "id": "stream-1", "error": "No space left on device"}
+        ]}"#;
+        let response: QmpResponse<Vec<JobInfo>> = serde_json::from_str(json).unwrap();
+        let jobs = response.result.unwrap();
+        assert_eq!(jobs[0].job_type, "stream");
+        assert_eq!(jobs[0].status, "concluded");
+        assert!(jobs[0].error.is_some());
+    }
+
+    #[test]
+    fn test_blockstats_parsing() {
+        let json = r#"{"return": [
+            {"device": "", "qdev": "/machine/peripheral/disk0/virtio-backend", "node-name": "disk0",
+             "stats": {"rd_bytes": 4096, "wr_bytes": 512, "rd_operations": 3, "wr_operations": 1,
+                       "flush_operations": 0, "idle_time_ns": 100}}
+        ]}"#;
+        let response: QmpResponse<Vec<BlockStatsEntry>> = serde_json::from_str(json).unwrap();
+        let entries = response.result.unwrap();
+        assert_eq!(entries[0].stats.rd_bytes, 4096);
+        assert_eq!(entries[0].stats.wr_operations, 1);
+    }
+
     #[test]
     fn test_qmp_error_parsing() {
         let json = r#"{"error": {"class": "GenericError", "desc": "Something went wrong"}}"#;
@@ -0,0 +1,741 @@
+//! Scenarios: scripted infrastructure chaos experiments
+//!
+//! A scenario is a YAML list of timed steps that the daemon runs against
+//! existing VMs and networks: start and stop VMs, inject and clear faults
+//! (see [`crate::types::ChaosProfile`]), take snapshots, and assert that
+//! health checks or VM states end up where the experiment expects them.
+//!
+//! ```yaml
+//! name: db-partition
+//! steps:
+//!   - action: start_vm
+//!     vm: db
+//!   - name: db-up
+//!     after: 20s
+//!     action: assert_health
+//!     check: { type: tcp, host: 127.0.0.1, port: 15432 }
+//!     timeout: 60s
+//!   - action: inject_fault
+//!     profile: split
+//!     network: backend
+//!     partitions: [[db], [app-1, app-2]]
+//!   - at: 2m
+//!     action: snapshot
+//!     vm: db
+//! ```
+//!
+//! `at` starts a step that long after the run started, `after` that long
+//! after the previous step finished; without either a step follows the
+//! previous one directly. Every run keeps a report of what each step did,
+//! how long it took and how the scenario's VMs were doing afterwards, and
+//! [`compare`] lines up the reports of two runs.
+
+use crate::types::{ChaosFaults, ChaosProfileSpec, ResourceMeta, VmState};
+use crate::{Error, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+/// Relative change in a step's duration worth pointing out in a comparison
+const DURATION_CHANGE: f64 = 0.25;
+
+/// Smallest change in a step's duration worth pointing out
+const DURATION_CHANGE_MIN_MS: u64 = 100;
+
+/// Change in a VM's CPU use, in percentage points, worth pointing out
+const CPU_CHANGE: f64 = 10.0;
+
+/// Relative change in a VM's memory use worth pointing out
+const MEMORY_CHANGE: f64 = 0.25;
+
+/// A scripted experiment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Keep running the remaining steps after one fails
+    #[serde(default)]
+    pub continue_on_failure: bool,
+    /// Remove the chaos profiles the run injected once it ends
+    #[serde(default = "default_true")]
+    pub cleanup: bool,
+    pub steps: Vec<Step>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One step of a scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// Name in reports, `<number>-<action>` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Start this long after the run started
+    #[serde(default, with = "duration_opt", skip_serializing_if = "Option::is_none")]
+    pub at: Option<Duration>,
+    /// Start this long after the previous step finished
+    #[serde(default, with = "duration_opt", skip_serializing_if = "Option::is_none")]
+    pub after: Option<Duration>,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// What a step does
+///
+/// VMs and networks are referenced by name or ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    StartVm {
+        vm: String,
+    },
+    StopVm {
+        vm: String,
+        /// Pull the plug instead of an ACPI shutdown
+        #[serde(default)]
+        force: bool,
+    },
+    /// Apply a chaos profile, replacing the faults of one with the same name
+    InjectFault {
+        profile: String,
+        #[serde(default)]
+        network: Option<String>,
+        #[serde(default)]
+        vm: Option<String>,
+        #[serde(flatten)]
+        faults: ChaosFaults,
+        #[serde(default)]
+        partitions: Vec<Vec<String>>,
+        /// Remove the profile after this long, even if the run is still going
+        #[serde(default, with = "duration_opt", skip_serializing_if = "Option::is_none")]
+        ttl: Option<Duration>,
+    },
+    /// Remove a chaos profile
+    ClearFault {
+        profile: String,
+    },
+    Snapshot {
+        vm: String,
+        /// Snapshot name, `<run>-<step>` if unset
+        #[serde(default)]
+        snapshot_name: Option<String>,
+        /// Also dump the VM's memory
+        #[serde(default)]
+        memory: bool,
+    },
+    /// Probe a service until it's in the expected state or `timeout` passes
+    AssertHealth {
+        check: HealthProbe,
+        #[serde(default)]
+        expect: HealthState,
+        #[serde(default, with = "duration_opt", skip_serializing_if = "Option::is_none")]
+        timeout: Option<Duration>,
+    },
+    /// Wait until a VM is in `state`, for at most `timeout`
+    AssertVmState {
+        vm: String,
+        state: VmState,
+        #[serde(default, with = "duration_opt", skip_serializing_if = "Option::is_none")]
+        timeout: Option<Duration>,
+    },
+}
+
+/// A service probe, run from the daemon host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HealthProbe {
+    /// Connect to `host:port`
+    Tcp {
+        #[serde(default = "default_host")]
+        host: String,
+        port: u16,
+    },
+    /// GET `url` and expect `expect_status`
+    Http {
+        url: String,
+        #[serde(default = "default_expect_status")]
+        expect_status: u16,
+    },
+}
+
+fn default_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_expect_status() -> u16 {
+    200
+}
+
+impl std::fmt::Display for HealthProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp { host, port } => write!(f, "tcp {}:{}", host, port),
+            Self::Http { url, .. } => write!(f, "http {}", url),
+        }
+    }
+}
+
+/// State a health assertion expects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    #[default]
+    Healthy,
+    Unhealthy,
+}
+
+impl HealthState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+impl Scenario {
+    /// Parse and validate a scenario definition
+    pub fn from_yaml(text: &str) -> Result<Self> {
+        let scenario: Self = serde_yaml::from_str(text)
+            .map_err(|e| Error::InvalidConfig(format!("Invalid scenario: {}", e)))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Check the scenario's shape; references are resolved when it runs
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(Error::InvalidConfig(msg));
+
+        if self.name.trim().is_empty() {
+            return invalid("A scenario needs a name".to_string());
+        }
+        if self.steps.is_empty() {
+            return invalid(format!("Scenario {} has no steps", self.name));
+        }
+
+        let mut names = HashSet::new();
+        for (idx, step) in self.steps.iter().enumerate() {
+            let name = step.name(idx);
+            if !names.insert(name.clone()) {
+                return invalid(format!("Step name {} is used twice", name));
+            }
+            if step.at.is_some() && step.after.is_some() {
+                return invalid(format!("Step {} has both 'at' and 'after'", name));
+            }
+            if let Some(spec) = step.action.chaos_spec() {
+                spec.validate()
+                    .map_err(|e| Error::InvalidConfig(format!("Step {}: {}", name, e)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of the scenario, to tell whether two runs ran the same steps
+    pub fn digest(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        hex::encode(Sha256::digest(&json))
+    }
+
+    /// Every VM the scenario refers to, in order of first use
+    pub fn vm_refs(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        let mut refs = Vec::new();
+        for step in &self.steps {
+            let vms: Vec<&String> = match &step.action {
+                Action::StartVm { vm }
+                | Action::StopVm { vm, .. }
+                | Action::Snapshot { vm, .. }
+                | Action::AssertVmState { vm, .. } => vec![vm],
+                Action::InjectFault { vm, partitions, .. } => {
+                    vm.iter().chain(partitions.iter().flatten()).collect()
+                }
+                Action::ClearFault { .. } | Action::AssertHealth { .. } => Vec::new(),
+            };
+            for vm in vms {
+                if seen.insert(vm.as_str()) {
+                    refs.push(vm.as_str());
+                }
+            }
+        }
+        refs
+    }
+}
+
+impl Step {
+    /// Name of the step at `idx` in reports
+    pub fn name(&self, idx: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{}-{}", idx + 1, self.action.kind()))
+    }
+}
+
+impl Action {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::StartVm { .. } => "start_vm",
+            Self::StopVm { .. } => "stop_vm",
+            Self::InjectFault { .. } => "inject_fault",
+            Self::ClearFault { .. } => "clear_fault",
+            Self::Snapshot { .. } => "snapshot",
+            Self::AssertHealth { .. } => "assert_health",
+            Self::AssertVmState { .. } => "assert_vm_state",
+        }
+    }
+
+    /// Chaos profile spec of an `inject_fault` step, still referencing VMs
+    /// and networks as written
+    pub fn chaos_spec(&self) -> Option<ChaosProfileSpec> {
+        match self {
+            Self::InjectFault {
+                network,
+                vm,
+                faults,
+                partitions,
+                ..
+            } => Some(ChaosProfileSpec {
+                network_id: network.clone(),
+                vm_id: vm.clone(),
+                faults: faults.clone(),
+                partitions: partitions.clone(),
+                expires_at: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a duration like `500ms`, `30s`, `5m` or `1h`; a bare number is
+/// seconds
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| Error::InvalidConfig(format!("Invalid duration '{}'", text)))?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => {
+            return Err(Error::InvalidConfig(format!(
+                "Invalid duration '{}': use ms, s, m or h",
+                text
+            )))
+        }
+    };
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+/// Format a duration the way [`parse_duration`] reads it
+pub fn format_duration(duration: Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+/// Durations written as `30s`-style strings or plain seconds
+mod duration_opt {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Seconds(f64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(value: &Option<Duration>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match value {
+            Some(duration) => serializer.serialize_str(&format_duration(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<Duration>, D::Error> {
+        match Option::<Raw>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Raw::Seconds(seconds)) if seconds >= 0.0 => Ok(Some(Duration::from_secs_f64(seconds))),
+            Some(Raw::Seconds(seconds)) => Err(serde::de::Error::custom(format!(
+                "duration {} must not be negative",
+                seconds
+            ))),
+            Some(Raw::Text(text)) => parse_duration(&text).map(Some).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+// ============================================================================
+// Runs
+// ============================================================================
+
+/// Scenario run state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunState {
+    #[default]
+    Pending,
+    Running,
+    Passed,
+    Failed,
+    Cancelled,
+}
+
+impl RunState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Passed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// How a step of a run went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    #[default]
+    Pending,
+    Running,
+    Passed,
+    Failed,
+    /// Not run, because an earlier step failed or the run was cancelled
+    Skipped,
+}
+
+impl StepOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// Usage of a VM when a step finished
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VmSample {
+    /// Share of one host core since the previous step; 200 = two busy cores
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    /// Bytes received and sent, if the VM's NICs are relayed
+    pub rx_bytes: Option<u64>,
+    pub tx_bytes: Option<u64>,
+}
+
+/// What one step of a run did
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepReport {
+    pub name: String,
+    pub action: String,
+    pub outcome: StepOutcome,
+    /// Milliseconds after the run started that the step started
+    pub started_ms: Option<u64>,
+    pub duration_ms: u64,
+    /// What happened, or why the step failed
+    #[serde(default)]
+    pub detail: String,
+    /// Usage of the scenario's running VMs once the step finished, by VM name
+    #[serde(default)]
+    pub stats: BTreeMap<String, VmSample>,
+}
+
+/// Scenario run specification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioRunSpec {
+    pub scenario: Scenario,
+    /// See [`Scenario::digest`]
+    pub digest: String,
+}
+
+/// Scenario run status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScenarioRunStatus {
+    pub state: RunState,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<StepReport>,
+}
+
+/// One execution of a scenario and its report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioRun {
+    pub meta: ResourceMeta,
+    pub spec: ScenarioRunSpec,
+    pub status: ScenarioRunStatus,
+}
+
+impl ScenarioRunStatus {
+    /// Status of a run that hasn't started, with every step pending
+    pub fn pending(scenario: &Scenario) -> Self {
+        Self {
+            steps: scenario
+                .steps
+                .iter()
+                .enumerate()
+                .map(|(idx, step)| StepReport {
+                    name: step.name(idx),
+                    action: step.action.kind().to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A step lined up across two runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepComparison {
+    pub name: String,
+    /// None if the step isn't in that run
+    pub base_outcome: Option<StepOutcome>,
+    pub other_outcome: Option<StepOutcome>,
+    pub base_duration_ms: u64,
+    pub other_duration_ms: u64,
+    /// Notable differences, e.g. `outcome passed -> failed`
+    pub changes: Vec<String>,
+}
+
+/// Line up the steps of two runs by name and point out what changed
+///
+/// Steps are in the order of `base`, followed by those only `other` has.
+pub fn compare(base: &ScenarioRun, other: &ScenarioRun) -> Vec<StepComparison> {
+    let mut names: Vec<&str> = base.status.steps.iter().map(|s| s.name.as_str()).collect();
+    for step in &other.status.steps {
+        if !names.contains(&step.name.as_str()) {
+            names.push(&step.name);
+        }
+    }
+
+    let find = |run: &'_ ScenarioRun, name: &str| run.status.steps.iter().find(|s| s.name == name).cloned();
+    names
+        .into_iter()
+        .map(|name| {
+            let (a, b) = (find(base, name), find(other, name));
+            StepComparison {
+                name: name.to_string(),
+                base_outcome: a.as_ref().map(|s| s.outcome),
+                other_outcome: b.as_ref().map(|s| s.outcome),
+                base_duration_ms: a.as_ref().map_or(0, |s| s.duration_ms),
+                other_duration_ms: b.as_ref().map_or(0, |s| s.duration_ms),
+                changes: step_changes(a.as_ref(), b.as_ref()),
+            }
+        })
+        .collect()
+}
+
+fn step_changes(base: Option<&StepReport>, other: Option<&StepReport>) -> Vec<String> {
+    let (base, other) = match (base, other) {
+        (Some(base), Some(other)) => (base, other),
+        (Some(_), None) => return vec!["only in the base run".to_string()],
+        (None, Some(_)) => return vec!["only in the other run".to_string()],
+        (None, None) => return Vec::new(),
+    };
+
+    let mut changes = Vec::new();
+    if base.outcome != other.outcome {
+        changes.push(format!("outcome {} -> {}", base.outcome.as_str(), other.outcome.as_str()));
+    }
+
+    let (a, b) = (base.duration_ms, other.duration_ms);
+    if a.abs_diff(b) >= DURATION_CHANGE_MIN_MS && a.abs_diff(b) as f64 >= a.max(1) as f64 * DURATION_CHANGE {
+        changes.push(format!(
+            "duration {} -> {} ({:+.0}%)",
+            format_ms(a),
+            format_ms(b),
+            (b as f64 - a as f64) / a.max(1) as f64 * 100.0
+        ));
+    }
+
+    for (vm, a) in &base.stats {
+        let Some(b) = other.stats.get(vm) else {
+            continue;
+        };
+        if (b.cpu_percent - a.cpu_percent).abs() >= CPU_CHANGE {
+            changes.push(format!("{} cpu {:.0}% -> {:.0}%", vm, a.cpu_percent, b.cpu_percent));
+        }
+        if a.rss_bytes.abs_diff(b.rss_bytes) as f64 >= a.rss_bytes.max(1) as f64 * MEMORY_CHANGE {
+            changes.push(format!(
+                "{} memory {} MiB -> {} MiB",
+                vm,
+                a.rss_bytes / (1024 * 1024),
+                b.rss_bytes / (1024 * 1024)
+            ));
+        }
+    }
+    changes
+}
+
+fn format_ms(ms: u64) -> String {
+    if ms >= 1000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+name: db-partition
+steps:
+  - action: start_vm
+    vm: db
+  - name: db-up
+    after: 20s
+    action: assert_health
+    check: { type: tcp, port: 15432 }
+    timeout: 60
+  - action: inject_fault
+    profile: split
+    network: backend
+    latency_ms: 200
+    distribution: pareto
+    loss_percent: 2.5
+    partitions: [[db], [app-1, app-2]]
+    ttl: 5m
+  - at: 1500ms
+    action: assert_vm_state
+    vm: app-1
+    state: running
+"#;
+
+    #[test]
+    fn test_parse_scenario() {
+        let scenario = Scenario::from_yaml(SCENARIO).unwrap();
+        assert!(scenario.cleanup);
+        assert_eq!(scenario.steps.len(), 4);
+        assert_eq!(scenario.steps[0].name(0), "1-start_vm");
+        assert_eq!(scenario.steps[1].name(1), "db-up");
+        assert_eq!(scenario.steps[1].after, Some(Duration::from_secs(20)));
+        assert_eq!(
+            scenario.steps[1].action,
+            Action::AssertHealth {
+                check: HealthProbe::Tcp {
+                    host: "127.0.0.1".to_string(),
+                    port: 15432
+                },
+                expect: HealthState::Healthy,
+                timeout: Some(Duration::from_secs(60)),
+            }
+        );
+        let spec = scenario.steps[2].action.chaos_spec().unwrap();
+        assert_eq!(spec.faults.latency_ms, 200);
+        assert_eq!(spec.faults.loss_percent, 2.5);
+        assert_eq!(spec.faults.distribution, crate::types::LatencyDistribution::Pareto);
+        assert_eq!(spec.partitions.len(), 2);
+        assert_eq!(scenario.steps[3].at, Some(Duration::from_millis(1500)));
+        assert_eq!(scenario.vm_refs(), vec!["db", "app-1", "app-2"]);
+
+        // Stored runs are read back from JSON
+        let json = serde_json::to_string(&scenario).unwrap();
+        let parsed: Scenario = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, scenario);
+        assert_eq!(parsed.digest(), scenario.digest());
+    }
+
+    #[test]
+    fn test_validate_scenario() {
+        let err = |yaml: &str| Scenario::from_yaml(yaml).unwrap_err().to_string();
+
+        assert!(err("name: x\nsteps: []").contains("no steps"));
+        assert!(err("name: x\nsteps:\n  - {action: start_vm, vm: a, at: 1s, after: 1s}").contains("both"));
+        assert!(err("name: x\nsteps:\n  - {name: a, action: start_vm, vm: a}\n  - {name: a, action: stop_vm, vm: a}")
+            .contains("used twice"));
+        assert!(err("name: x\nsteps:\n  - {action: inject_fault, profile: p, latency_ms: 5}").contains("needs a network"));
+        assert!(err("name: x\nsteps:\n  - {action: reboot_vm, vm: a}").contains("Invalid scenario"));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("5d").is_err());
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+    }
+
+    fn run(steps: Vec<StepReport>) -> ScenarioRun {
+        let scenario = Scenario::from_yaml("name: x\nsteps:\n  - {action: start_vm, vm: a}").unwrap();
+        ScenarioRun {
+            meta: ResourceMeta::new("x-run".to_string()),
+            spec: ScenarioRunSpec {
+                digest: scenario.digest(),
+                scenario,
+            },
+            status: ScenarioRunStatus {
+                steps,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let step = |name: &str, outcome, duration_ms, cpu_percent| StepReport {
+            name: name.to_string(),
+            outcome,
+            duration_ms,
+            stats: BTreeMap::from([(
+                "db".to_string(),
+                VmSample {
+                    cpu_percent,
+                    rss_bytes: 512 * 1024 * 1024,
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let base = run(vec![
+            step("boot", StepOutcome::Passed, 2000, 50.0),
+            step("probe", StepOutcome::Passed, 1000, 5.0),
+            step("old", StepOutcome::Passed, 10, 0.0),
+        ]);
+        let other = run(vec![
+            step("boot", StepOutcome::Passed, 2100, 55.0),
+            step("probe", StepOutcome::Failed, 3000, 90.0),
+            step("new", StepOutcome::Passed, 10, 0.0),
+        ]);
+
+        let comparison = compare(&base, &other);
+        let names: Vec<&str> = comparison.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["boot", "probe", "old", "new"]);
+        assert!(comparison[0].changes.is_empty());
+        assert_eq!(
+            comparison[1].changes,
+            vec![
+                "outcome passed -> failed".to_string(),
+                "duration 1.0s -> 3.0s (+200%)".to_string(),
+                "db cpu 5% -> 90%".to_string(),
+            ]
+        );
+        assert_eq!(comparison[2].other_outcome, None);
+        assert_eq!(comparison[3].changes, vec!["only in the other run".to_string()]);
+    }
+}
@@ -0,0 +1,341 @@
+//! Secrets encrypted at rest
+//!
+//! Secrets are named values such as guest passwords and API keys, kept in
+//! the `secrets` table of the state database. Each value is sealed with
+//! AES-256-GCM under the daemon's master key, a 0600 file in the store that
+//! never enters the database. The secret's name is bound in as associated
+//! data, so a sealed value can't be moved to another name.
+//!
+//! Appliance templates refer to secrets as `${secret:name}` in their
+//! environment values; [`interpolate`] swaps the values in.
+
+use crate::{Database, Error, Result};
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use tracing::info;
+
+/// Length of the master key
+const KEY_LEN: usize = 32;
+
+/// Longest secret name
+const MAX_NAME_LEN: usize = 128;
+
+/// Start of a secret reference; the name runs to the next `}`
+const REF_PREFIX: &str = "${secret:";
+
+/// Key sealing every secret in a store
+#[derive(Clone)]
+pub struct MasterKey([u8; KEY_LEN]);
+
+impl MasterKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// Load the key at `path`, generating it on first use
+    pub fn load_or_create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            let bytes: [u8; KEY_LEN] = fs::read(path)?
+                .try_into()
+                .map_err(|_| Error::Crypto(format!("Invalid master key length in {}", path.display())))?;
+            return Ok(Self(bytes));
+        }
+
+        let key = Self::generate();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?
+            .write_all(&key.0)?;
+        info!("Generated secrets master key {}", path.display());
+        Ok(key)
+    }
+
+    fn cipher(&self) -> LessSafeKey {
+        // A 32-byte key is always valid for AES-256-GCM
+        LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &self.0).expect("AES-256 key length"))
+    }
+
+    /// Seal a value for `name`, returning its nonce and ciphertext
+    fn seal(&self, name: &str, value: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let mut sealed = value.to_vec();
+        self.cipher()
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(name.as_bytes()), &mut sealed)
+            .map_err(|_| Error::Crypto(format!("Failed to encrypt secret {}", name)))?;
+        Ok((nonce.to_vec(), sealed))
+    }
+
+    fn open(&self, name: &str, nonce: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+        let undecryptable = || Error::Crypto(format!("Secret {} can't be decrypted with this master key", name));
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| undecryptable())?;
+        let mut value = sealed.to_vec();
+        let len = self
+            .cipher()
+            .open_in_place(nonce, Aad::from(name.as_bytes()), &mut value)
+            .map_err(|_| undecryptable())?
+            .len();
+        value.truncate(len);
+        Ok(value)
+    }
+}
+
+/// A stored secret, without its value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretMeta {
+    pub name: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Secrets in a state database
+#[derive(Clone)]
+pub struct SecretStore {
+    db: Database,
+    key: MasterKey,
+}
+
+impl SecretStore {
+    pub fn new(db: Database, key: MasterKey) -> Self {
+        Self { db, key }
+    }
+
+    /// Store a secret; an existing one is only replaced with `overwrite`
+    pub fn put(&self, name: &str, value: &str, overwrite: bool) -> Result<SecretMeta> {
+        validate_name(name)?;
+        if value.is_empty() {
+            return Err(Error::InvalidConfig(format!("Secret {} has an empty value", name)));
+        }
+        let (nonce, sealed) = self.key.seal(name, value.as_bytes())?;
+        let now = chrono::Utc::now().timestamp();
+
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let created_at: Option<i64> = conn
+            .query_row("SELECT created_at FROM secrets WHERE name = ?1", params![name], |r| r.get(0))
+            .optional()?;
+        if created_at.is_some() && !overwrite {
+            return Err(Error::AlreadyExists {
+                kind: "secret".to_string(),
+                id: name.to_string(),
+            });
+        }
+        conn.execute(
+            "INSERT INTO secrets (name, nonce, ciphertext, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4) \
+             ON CONFLICT(name) DO UPDATE SET nonce = ?2, ciphertext = ?3, updated_at = ?4",
+            params![name, nonce, sealed, now],
+        )?;
+        Ok(SecretMeta {
+            name: name.to_string(),
+            created_at: created_at.unwrap_or(now),
+            updated_at: now,
+        })
+    }
+
+    /// Decrypt a secret's value
+    pub fn get(&self, name: &str) -> Result<String> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let row: Option<(Vec<u8>, Vec<u8>)> = conn
+            .query_row(
+                "SELECT nonce, ciphertext FROM secrets WHERE name = ?1",
+                params![name],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+        let (nonce, sealed) = row.ok_or_else(|| Error::NotFound {
+            kind: "secret".to_string(),
+            id: name.to_string(),
+        })?;
+        String::from_utf8(self.key.open(name, &nonce, &sealed)?)
+            .map_err(|_| Error::Crypto(format!("Secret {} is not UTF-8", name)))
+    }
+
+    /// Metadata of a secret
+    pub fn metadata(&self, name: &str) -> Result<Option<SecretMeta>> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        Ok(conn
+            .query_row(
+                "SELECT name, created_at, updated_at FROM secrets WHERE name = ?1",
+                params![name],
+                |r| Ok(SecretMeta { name: r.get(0)?, created_at: r.get(1)?, updated_at: r.get(2)? }),
+            )
+            .optional()?)
+    }
+
+    /// All secrets by name, without their values
+    pub fn list(&self) -> Result<Vec<SecretMeta>> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let mut stmt = conn.prepare("SELECT name, created_at, updated_at FROM secrets ORDER BY name")?;
+        let secrets = stmt
+            .query_map([], |r| Ok(SecretMeta { name: r.get(0)?, created_at: r.get(1)?, updated_at: r.get(2)? }))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(secrets)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        if conn.execute("DELETE FROM secrets WHERE name = ?1", params![name])? == 0 {
+            return Err(Error::NotFound {
+                kind: "secret".to_string(),
+                id: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Replace the secret references in `text` with their values
+    pub fn resolve(&self, text: &str) -> Result<String> {
+        interpolate(text, |name| self.get(name))
+    }
+}
+
+/// Check a secret name: up to 128 letters, digits, '-', '_' or '.', not
+/// starting with '.'
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(Error::InvalidConfig(format!(
+            "Invalid secret name {:?}: use up to {} letters, digits, '-', '_' or '.'",
+            name, MAX_NAME_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Replace each `${secret:name}` in `text` with `lookup(name)`
+pub fn interpolate(text: &str, mut lookup: impl FnMut(&str) -> Result<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(REF_PREFIX) {
+        out.push_str(&rest[..start]);
+        let reference = &rest[start + REF_PREFIX.len()..];
+        let end = reference
+            .find('}')
+            .ok_or_else(|| Error::InvalidConfig("Unterminated ${secret:...} reference".to_string()))?;
+        let name = &reference[..end];
+        validate_name(name)?;
+        out.push_str(&lookup(name)?);
+        rest = &reference[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Names of the secrets `text` refers to, in order
+pub fn secret_refs(text: &str) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    interpolate(text, |name| {
+        names.push(name.to_string());
+        Ok(String::new())
+    })?;
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> SecretStore {
+        SecretStore::new(Database::open_memory().unwrap(), MasterKey::generate())
+    }
+
+    #[test]
+    fn test_put_get_list_delete() {
+        let store = store();
+        store.put("keycloak-admin", "s3cret", false).unwrap();
+        assert_eq!(store.get("keycloak-admin").unwrap(), "s3cret");
+        assert!(matches!(
+            store.put("keycloak-admin", "other", false),
+            Err(Error::AlreadyExists { .. })
+        ));
+        store.put("keycloak-admin", "rotated", true).unwrap();
+        assert_eq!(store.get("keycloak-admin").unwrap(), "rotated");
+        store.put("api.key", "k", false).unwrap();
+
+        let names: Vec<String> = store.list().unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["api.key", "keycloak-admin"]);
+
+        store.delete("api.key").unwrap();
+        assert!(matches!(store.get("api.key"), Err(Error::NotFound { .. })));
+        assert!(matches!(store.delete("api.key"), Err(Error::NotFound { .. })));
+        assert!(store.put("../etc", "x", false).is_err());
+        assert!(store.put("empty", "", false).is_err());
+    }
+
+    #[test]
+    fn test_values_are_sealed_to_key_and_name() {
+        let db = Database::open_memory().unwrap();
+        let store = SecretStore::new(db.clone(), MasterKey::generate());
+        store.put("a", "value-a", false).unwrap();
+        store.put("b", "value-b", false).unwrap();
+
+        {
+            let conn_arc = db.connection();
+            let conn = conn_arc.lock();
+            let sealed: Vec<u8> = conn
+                .query_row("SELECT ciphertext FROM secrets WHERE name = 'a'", [], |r| r.get(0))
+                .unwrap();
+            assert!(!sealed.windows(7).any(|w| w == b"value-a"));
+            // Move a's sealed value over b's
+            conn.execute(
+                "UPDATE secrets SET (nonce, ciphertext) = (SELECT nonce, ciphertext FROM secrets WHERE name = 'a') \
+                 WHERE name = 'b'",
+                [],
+            )
+            .unwrap();
+        }
+        assert!(matches!(store.get("b"), Err(Error::Crypto(_))));
+
+        let other = SecretStore::new(db, MasterKey::generate());
+        assert!(matches!(other.get("a"), Err(Error::Crypto(_))));
+    }
+
+    #[test]
+    fn test_master_key_is_created_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys/secrets.key");
+        let key = MasterKey::load_or_create(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions();
+        assert_eq!(std::os::unix::fs::PermissionsExt::mode(&mode) & 0o777, 0o600);
+        assert_eq!(MasterKey::load_or_create(&path).unwrap().0, key.0);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let store = store();
+        store.put("keycloak-admin", "pw", false).unwrap();
+        assert_eq!(store.resolve("plain").unwrap(), "plain");
+        assert_eq!(
+            store.resolve("${secret:keycloak-admin}:${secret:keycloak-admin}$").unwrap(),
+            "pw:pw$"
+        );
+        assert!(matches!(store.resolve("${secret:missing}"), Err(Error::NotFound { .. })));
+        assert!(store.resolve("${secret:keycloak-admin").is_err());
+        assert!(store.resolve("${secret:}").is_err());
+        assert_eq!(
+            secret_refs("${HOME} ${secret:a}/${secret:b.c}").unwrap(),
+            ["a", "b.c"]
+        );
+    }
+}
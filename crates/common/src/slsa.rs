@@ -0,0 +1,311 @@
+//! SLSA provenance for volume images
+//!
+//! Volumes built by the appliance builder or imported from an image carry an
+//! in-toto statement with a [SLSA provenance v1] predicate: the image digest
+//! as its subject, and the build's parameters and resolved inputs (base image,
+//! overlays, source file) as its build definition. The statement is wrapped in
+//! a [DSSE] envelope signed by the daemon's key.
+//!
+//! [SLSA provenance v1]: https://slsa.dev/spec/v1.0/provenance
+//! [DSSE]: https://github.com/secure-systems-lab/dsse/blob/master/protocol.md
+
+use crate::crypto::{KeyPair, Signer, Verifier};
+use crate::{Error, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+pub const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+pub const PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// Builder ID of the daemon, which signs every statement
+pub const BUILDER_ID: &str = "https://infrasim.dev/infrasimd";
+
+/// Build type of images built from a container image and overlays
+pub const BUILD_TYPE_APPLIANCE: &str = "https://infrasim.dev/appliance-build/v1";
+/// Build type of images imported as they are from a file, URL or registry
+pub const BUILD_TYPE_IMPORT: &str = "https://infrasim.dev/volume-import/v1";
+
+/// An artifact, named by URI and/or digest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceDescriptor {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub uri: String,
+    /// Algorithm to hex digest, e.g. `sha256`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub digest: BTreeMap<String, String>,
+}
+
+impl ResourceDescriptor {
+    pub fn new(name: impl Into<String>, uri: impl Into<String>, sha256: Option<&str>) -> Self {
+        Self {
+            name: name.into(),
+            uri: uri.into(),
+            digest: sha256
+                .map(|d| BTreeMap::from([("sha256".to_string(), d.to_string())]))
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn sha256(&self) -> Option<&str> {
+        self.digest.get("sha256").map(String::as_str)
+    }
+}
+
+/// What went into an image, as recorded with its volume until the image is
+/// ready to be attested
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BuildInputs {
+    /// [`BUILD_TYPE_APPLIANCE`], [`BUILD_TYPE_IMPORT`] or another URI
+    pub build_type: String,
+    /// Parameters the build was asked for, such as the appliance spec
+    #[serde(default)]
+    pub parameters: Value,
+    /// Base image, overlays and other inputs, with their digests
+    #[serde(default)]
+    pub dependencies: Vec<ResourceDescriptor>,
+    /// Component to version of whatever ran the build, e.g. `infrasim-web`
+    #[serde(default)]
+    pub builder_version: BTreeMap<String, String>,
+    /// ID of the build run
+    #[serde(default)]
+    pub invocation_id: String,
+    #[serde(default)]
+    pub started_at: Option<i64>,
+    #[serde(default)]
+    pub finished_at: Option<i64>,
+}
+
+/// in-toto statement about one or more artifacts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    #[serde(rename = "_type")]
+    pub statement_type: String,
+    pub subject: Vec<ResourceDescriptor>,
+    pub predicate_type: String,
+    pub predicate: Provenance,
+}
+
+/// SLSA provenance v1 predicate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    pub build_definition: BuildDefinition,
+    pub run_details: RunDetails,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDefinition {
+    pub build_type: String,
+    pub external_parameters: Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolved_dependencies: Vec<ResourceDescriptor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunDetails {
+    pub builder: Builder,
+    #[serde(default)]
+    pub metadata: BuildMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Builder {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub version: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildMetadata {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub invocation_id: String,
+    /// RFC 3339 timestamps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_on: Option<String>,
+}
+
+impl Statement {
+    /// Provenance of `subject` from `inputs`, built by the daemon
+    pub fn new(subject: ResourceDescriptor, inputs: &BuildInputs) -> Self {
+        let rfc3339 = |t: Option<i64>| {
+            t.and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        };
+        let mut version = inputs.builder_version.clone();
+        version
+            .entry("infrasimd".to_string())
+            .or_insert_with(|| crate::VERSION.to_string());
+
+        Self {
+            statement_type: STATEMENT_TYPE.to_string(),
+            subject: vec![subject],
+            predicate_type: PREDICATE_TYPE.to_string(),
+            predicate: Provenance {
+                build_definition: BuildDefinition {
+                    build_type: inputs.build_type.clone(),
+                    external_parameters: inputs.parameters.clone(),
+                    resolved_dependencies: inputs.dependencies.clone(),
+                },
+                run_details: RunDetails {
+                    builder: Builder {
+                        id: BUILDER_ID.to_string(),
+                        version,
+                    },
+                    metadata: BuildMetadata {
+                        invocation_id: inputs.invocation_id.clone(),
+                        started_on: rfc3339(inputs.started_at),
+                        finished_on: rfc3339(inputs.finished_at),
+                    },
+                },
+            },
+        }
+    }
+
+    /// sha256 of the first subject
+    pub fn subject_digest(&self) -> Option<&str> {
+        self.subject.first().and_then(ResourceDescriptor::sha256)
+    }
+}
+
+/// DSSE envelope around a statement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    pub payload_type: String,
+    /// Base64 of the statement's JSON
+    pub payload: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keyid: String,
+    /// Base64 Ed25519 signature over the pre-authentication encoding
+    pub sig: String,
+}
+
+impl Envelope {
+    /// Wrap `statement` and sign it with `signer`
+    pub fn sign(statement: &Statement, signer: &KeyPair) -> Result<Self> {
+        let payload = serde_json::to_vec(statement)?;
+        let signature = signer.sign(&pae(PAYLOAD_TYPE, &payload));
+        Ok(Self {
+            payload_type: PAYLOAD_TYPE.to_string(),
+            payload: BASE64.encode(&payload),
+            signatures: vec![EnvelopeSignature {
+                keyid: signer.key_id(),
+                sig: BASE64.encode(signature),
+            }],
+        })
+    }
+
+    /// The statement, if `key` made one of the signatures
+    pub fn verify(&self, key: &VerifyingKey) -> Result<Statement> {
+        if self.payload_type != PAYLOAD_TYPE {
+            return Err(Error::Crypto(format!("Unexpected payload type '{}'", self.payload_type)));
+        }
+        let payload = BASE64
+            .decode(&self.payload)
+            .map_err(|e| Error::Crypto(format!("Invalid payload: {}", e)))?;
+        let message = pae(&self.payload_type, &payload);
+        let signed = self.signatures.iter().any(|s| {
+            BASE64
+                .decode(&s.sig)
+                .is_ok_and(|sig| key.verify(&message, &sig).is_ok())
+        });
+        if !signed {
+            return Err(Error::Crypto("No valid signature by the given key".to_string()));
+        }
+
+        let statement: Statement = serde_json::from_slice(&payload)?;
+        if statement.statement_type != STATEMENT_TYPE || statement.predicate_type != PREDICATE_TYPE {
+            return Err(Error::Crypto(format!(
+                "Not a SLSA provenance statement: {} / {}",
+                statement.statement_type, statement.predicate_type
+            )));
+        }
+        Ok(statement)
+    }
+}
+
+/// DSSE pre-authentication encoding, what the signatures cover
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!("DSSEv1 {} {} {} ", payload_type.len(), payload_type, payload.len()).into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn inputs() -> BuildInputs {
+        BuildInputs {
+            build_type: BUILD_TYPE_APPLIANCE.to_string(),
+            parameters: json!({"name": "router", "base_image": "alpine:3.19"}),
+            dependencies: vec![ResourceDescriptor::new(
+                "base_image",
+                "docker://alpine:3.19",
+                Some("a".repeat(64).as_str()),
+            )],
+            builder_version: BTreeMap::from([("infrasim-web".to_string(), "0.1.0".to_string())]),
+            invocation_id: "build-1".to_string(),
+            started_at: Some(1_700_000_000),
+            finished_at: Some(1_700_000_060),
+        }
+    }
+
+    #[test]
+    fn test_pae() {
+        assert_eq!(
+            pae("http://example.com/HelloWorld", b"hello world"),
+            b"DSSEv1 29 http://example.com/HelloWorld 11 hello world"
+        );
+    }
+
+    #[test]
+    fn test_statement_format() {
+        let statement = Statement::new(ResourceDescriptor::new("router", "", Some("b".repeat(64).as_str())), &inputs());
+        let value = serde_json::to_value(&statement).unwrap();
+        assert_eq!(value["_type"], STATEMENT_TYPE);
+        assert_eq!(value["predicateType"], PREDICATE_TYPE);
+        assert_eq!(value["subject"][0]["digest"]["sha256"], "b".repeat(64));
+        let predicate = &value["predicate"];
+        assert_eq!(predicate["buildDefinition"]["buildType"], BUILD_TYPE_APPLIANCE);
+        assert_eq!(predicate["buildDefinition"]["resolvedDependencies"][0]["uri"], "docker://alpine:3.19");
+        assert_eq!(predicate["runDetails"]["builder"]["id"], BUILDER_ID);
+        assert_eq!(predicate["runDetails"]["builder"]["version"]["infrasimd"], crate::VERSION);
+        assert_eq!(predicate["runDetails"]["metadata"]["startedOn"], "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn test_envelope_sign_verify() {
+        let key = KeyPair::generate();
+        let statement = Statement::new(ResourceDescriptor::new("router", "", Some("b".repeat(64).as_str())), &inputs());
+        let envelope = Envelope::sign(&statement, &key).unwrap();
+        assert_eq!(envelope.signatures[0].keyid, key.key_id());
+        assert_eq!(envelope.verify(&key.verifying_key()).unwrap(), statement);
+
+        let other = KeyPair::generate();
+        assert!(envelope.verify(&other.verifying_key()).is_err());
+
+        let mut tampered = statement.clone();
+        tampered.subject[0].digest.insert("sha256".to_string(), "c".repeat(64));
+        let mut forged = envelope.clone();
+        forged.payload = BASE64.encode(serde_json::to_vec(&tampered).unwrap());
+        assert!(forged.verify(&key.verifying_key()).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+//! Backups of the daemon's state database
+//!
+//! A backup is a directory holding a consistent copy of `state.db`, made
+//! with SQLite's backup API, and a manifest of the CAS objects the database
+//! names. The objects themselves are only copied on request, since disk
+//! images make up most of a store. Restoring onto a fresh store needs them.
+
+use crate::cas::ContentAddressedStore;
+use crate::{Database, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::info;
+
+/// Version of the manifest layout
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Database copy inside a backup directory
+pub const DB_FILE: &str = "state.db";
+
+/// Manifest inside a backup directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Directory of copied CAS objects inside a backup directory
+pub const OBJECTS_DIR: &str = "objects";
+
+/// What a backup holds, written last so a directory without one is incomplete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at: i64,
+    pub db_sha256: String,
+    pub db_size: u64,
+    /// Applied migration version of each component
+    pub schema: BTreeMap<String, u32>,
+    pub objects: Vec<ManifestObject>,
+}
+
+/// A CAS object the database names
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestObject {
+    pub digest: String,
+    pub size: u64,
+    /// Whether a copy is in the backup's objects directory
+    pub included: bool,
+}
+
+/// Something a verification found wrong
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// SQLite's integrity check failed
+    Corruption,
+    /// A row refers to a missing row
+    ForeignKey,
+    /// A CAS object the database needs is gone
+    MissingObject,
+    /// A CAS object's content doesn't match its digest
+    CorruptObject,
+    /// The backup's manifest or database copy doesn't match
+    Manifest,
+}
+
+impl ProblemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProblemKind::Corruption => "corruption",
+            ProblemKind::ForeignKey => "foreign_key",
+            ProblemKind::MissingObject => "missing_object",
+            ProblemKind::CorruptObject => "corrupt_object",
+            ProblemKind::Manifest => "manifest",
+        }
+    }
+}
+
+/// Outcome of verifying a store or a backup
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    pub problems: Vec<Problem>,
+    /// CAS objects whose content was hashed
+    pub objects_checked: usize,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn problem(&mut self, kind: ProblemKind, detail: impl Into<String>) {
+        self.problems.push(Problem { kind, detail: detail.into() });
+    }
+
+    fn check_database(&mut self, db: &Database) -> Result<()> {
+        for detail in db.integrity_check()? {
+            self.problem(ProblemKind::Corruption, detail);
+        }
+        for detail in db.foreign_key_check()? {
+            self.problem(ProblemKind::ForeignKey, detail);
+        }
+        Ok(())
+    }
+
+    async fn check_object(&mut self, path: &Path, digest: &str) -> Result<()> {
+        let actual = ContentAddressedStore::hash_file(path).await?;
+        self.objects_checked += 1;
+        if actual != digest {
+            self.problem(
+                ProblemKind::CorruptObject,
+                format!("{} hashes to {}", path.display(), actual),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a restore
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub db_path: PathBuf,
+    /// Where the replaced database was moved, if there was one
+    pub previous_db: Option<PathBuf>,
+    pub objects_imported: usize,
+    /// Objects in the manifest the store still lacks
+    pub objects_missing: Vec<String>,
+}
+
+/// Back up `db` and the manifest of its CAS objects into the new directory `dest`
+///
+/// With `include_objects` the objects are copied as well.
+pub async fn backup(
+    db: &Database,
+    cas: &ContentAddressedStore,
+    dest: &Path,
+    include_objects: bool,
+) -> Result<BackupManifest> {
+    if dest.exists() && std::fs::read_dir(dest)?.next().is_some() {
+        return Err(Error::AlreadyExists {
+            kind: "backup directory".to_string(),
+            id: dest.display().to_string(),
+        });
+    }
+    fs::create_dir_all(dest).await?;
+
+    let db_path = dest.join(DB_FILE);
+    db.backup_to(&db_path)?;
+
+    // Read the copy, not the live database, so the manifest matches it
+    let copy = Database::open_read_only(&db_path)?;
+    let schema = schema_versions(&copy);
+    let mut objects = Vec::new();
+    for digest in copy.digest_references()?.into_keys() {
+        let path = cas.object_path(&digest);
+        let Ok(metadata) = fs::metadata(&path).await else {
+            continue;
+        };
+        if include_objects {
+            let target = dest.join(OBJECTS_DIR).join(&digest);
+            fs::create_dir_all(dest.join(OBJECTS_DIR)).await?;
+            fs::copy(&path, &target).await?;
+        }
+        objects.push(ManifestObject {
+            digest,
+            size: metadata.len(),
+            included: include_objects,
+        });
+    }
+    drop(copy);
+
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        created_at: chrono::Utc::now().timestamp(),
+        db_sha256: ContentAddressedStore::hash_file(&db_path).await?,
+        db_size: fs::metadata(&db_path).await?.len(),
+        schema,
+        objects,
+    };
+    fs::write(dest.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?).await?;
+
+    info!(
+        "Backed up state database to {} with {} objects",
+        dest.display(),
+        manifest.objects.len()
+    );
+    Ok(manifest)
+}
+
+/// Read the manifest of the backup in `dir`
+pub async fn read_manifest(dir: &Path) -> Result<BackupManifest> {
+    let path = dir.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Err(Error::NotFound {
+            kind: "backup manifest".to_string(),
+            id: path.display().to_string(),
+        });
+    }
+    let manifest: BackupManifest = serde_json::from_slice(&fs::read(&path).await?)?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(Error::InvalidConfig(format!(
+            "Backup format v{} is newer than this release's v{}",
+            manifest.format_version, FORMAT_VERSION
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Check the backup in `dir`: the database copy against its manifest and
+/// SQLite's checks, and every included object against its digest
+pub async fn verify_backup(dir: &Path) -> Result<VerifyReport> {
+    let manifest = read_manifest(dir).await?;
+    let mut report = VerifyReport::default();
+
+    let db_path = dir.join(DB_FILE);
+    let actual = ContentAddressedStore::hash_file(&db_path).await?;
+    if actual != manifest.db_sha256 {
+        report.problem(
+            ProblemKind::Manifest,
+            format!("{} hashes to {}, not {}", db_path.display(), actual, manifest.db_sha256),
+        );
+        return Ok(report);
+    }
+    report.check_database(&Database::open_read_only(&db_path)?)?;
+
+    for object in manifest.objects.iter().filter(|o| o.included) {
+        let path = dir.join(OBJECTS_DIR).join(&object.digest);
+        if !path.exists() {
+            report.problem(ProblemKind::MissingObject, format!("{} is not in the backup", object.digest));
+            continue;
+        }
+        report.check_object(&path, &object.digest).await?;
+    }
+    Ok(report)
+}
+
+/// Check a live store: the database with SQLite's checks, objects it names
+/// by path for existence and, with `hash_objects`, every object it names
+/// against its digest
+pub async fn verify_store(db: &Database, cas: &ContentAddressedStore, hash_objects: bool) -> Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    report.check_database(db)?;
+
+    for (digest, as_path) in db.digest_references()? {
+        let path = cas.object_path(&digest);
+        if !path.exists() {
+            if as_path {
+                report.problem(ProblemKind::MissingObject, format!("{} is referenced but not in the store", digest));
+            }
+            continue;
+        }
+        if hash_objects {
+            report.check_object(&path, &digest).await?;
+        }
+    }
+    Ok(report)
+}
+
+/// Restore the backup in `dir` as the database at `db_path`, importing its
+/// objects into `cas`
+///
+/// The backup is verified first. An existing database is only replaced
+/// with `replace`, and is kept next to the new one.
+pub async fn restore(dir: &Path, db_path: &Path, cas: &ContentAddressedStore, replace: bool) -> Result<RestoreReport> {
+    let report = verify_backup(dir).await?;
+    if let Some(problem) = report.problems.first() {
+        return Err(Error::IntegrityError(format!(
+            "Backup in {} failed verification: {}",
+            dir.display(),
+            problem.detail
+        )));
+    }
+    let manifest = read_manifest(dir).await?;
+
+    let previous_db = if db_path.exists() {
+        if !replace {
+            return Err(Error::AlreadyExists {
+                kind: "state database".to_string(),
+                id: db_path.display().to_string(),
+            });
+        }
+        let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+        let previous = sibling(db_path, &format!(".pre-restore-{}", stamp));
+        for extra in ["", "-wal", "-shm"] {
+            let from = sibling(db_path, extra);
+            if from.exists() {
+                fs::rename(&from, sibling(&previous, extra)).await?;
+            }
+        }
+        Some(previous)
+    } else {
+        None
+    };
+
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let staged = sibling(db_path, ".restoring");
+    fs::copy(dir.join(DB_FILE), &staged).await?;
+    fs::rename(&staged, db_path).await?;
+
+    let mut objects_imported = 0;
+    let mut objects_missing = Vec::new();
+    for object in &manifest.objects {
+        if cas.has(&object.digest).await {
+            continue;
+        }
+        if !object.included {
+            objects_missing.push(object.digest.clone());
+            continue;
+        }
+        let digest = cas.put_file(dir.join(OBJECTS_DIR).join(&object.digest)).await?;
+        if digest != object.digest {
+            return Err(Error::IntegrityError(format!(
+                "Object {} changed while restoring",
+                object.digest
+            )));
+        }
+        objects_imported += 1;
+    }
+
+    info!(
+        "Restored state database to {} from {} ({} objects imported)",
+        db_path.display(),
+        dir.display(),
+        objects_imported
+    );
+    Ok(RestoreReport {
+        db_path: db_path.to_path_buf(),
+        previous_db,
+        objects_imported,
+        objects_missing,
+    })
+}
+
+fn schema_versions(db: &Database) -> BTreeMap<String, u32> {
+    let mut versions = BTreeMap::new();
+    // Databases from before migrations were versioned have no table to read
+    for migration in db.applied_migrations().unwrap_or_default() {
+        let version = versions.entry(migration.component).or_insert(0);
+        *version = migration.version.max(*version);
+    }
+    versions
+}
+
+/// `path` with `suffix` appended to its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_backup_verify_and_restore() {
+        let tmp = TempDir::new().unwrap();
+        let cas = ContentAddressedStore::new(tmp.path().join("store")).await.unwrap();
+        let digest = cas.put(b"disk image").await.unwrap();
+        let db = Database::open(tmp.path().join("state.db")).unwrap();
+        let object_path = cas.object_path(&digest).display().to_string();
+        db.kv_set("image", &object_path).unwrap();
+        db.kv_set("audit", &"ab".repeat(32)).unwrap();
+
+        assert!(verify_store(&db, &cas, true).await.unwrap().is_ok());
+
+        let dest = tmp.path().join("backup");
+        let manifest = backup(&db, &cas, &dest, true).await.unwrap();
+        assert_eq!(manifest.objects.len(), 1);
+        assert_eq!(manifest.schema.get("state"), Some(&crate::db::STATE_MIGRATIONS.latest()));
+        assert!(verify_backup(&dest).await.unwrap().is_ok());
+        assert!(backup(&db, &cas, &dest, false).await.is_err());
+
+        // Onto a fresh store
+        let fresh = ContentAddressedStore::new(tmp.path().join("fresh/store")).await.unwrap();
+        let fresh_db = tmp.path().join("fresh/state.db");
+        let restored = restore(&dest, &fresh_db, &fresh, false).await.unwrap();
+        assert_eq!(restored.objects_imported, 1);
+        assert!(restored.objects_missing.is_empty());
+        assert!(fresh.has(&digest).await);
+        let reopened = Database::open(&fresh_db).unwrap();
+        assert_eq!(reopened.kv_get("image").unwrap(), Some(object_path));
+        drop(reopened);
+        assert!(restore(&dest, &fresh_db, &fresh, false).await.is_err());
+        let replaced = restore(&dest, &fresh_db, &fresh, true).await.unwrap();
+        assert!(replaced.previous_db.unwrap().exists());
+
+        // Damage is found in the backup and in the store
+        std::fs::write(dest.join(OBJECTS_DIR).join(&digest), b"bit rot").unwrap();
+        let report = verify_backup(&dest).await.unwrap();
+        assert_eq!(report.problems[0].kind, ProblemKind::CorruptObject);
+        assert!(restore(&dest, &fresh_db, &fresh, true).await.is_err());
+
+        std::fs::remove_file(cas.object_path(&digest)).unwrap();
+        let report = verify_store(&db, &cas, true).await.unwrap();
+        assert_eq!(report.problems.len(), 1);
+        assert_eq!(report.problems[0].kind, ProblemKind::MissingObject);
+    }
+}
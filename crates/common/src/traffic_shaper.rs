@@ -6,11 +6,19 @@
 //! - Packet loss
 //! - Bandwidth limiting
 //! - Packet padding
-
-use crate::{types::QosProfileSpec, Result};
+//!
+//! Chaos profiles add [`FaultInjection`] on top of a NIC's QoS profile:
+//! latency drawn from a distribution, loss, a second rate cap, and
+//! partitions that drop frames to and from chosen peers.
+
+use crate::{
+    types::{ChaosFaults, LatencyDistribution, QosProfileSpec},
+    Result,
+};
 use parking_lot::Mutex;
 use rand::Rng;
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::net::Ipv4Addr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -22,32 +30,57 @@ pub struct TrafficShaper {
     config: Arc<Mutex<QosProfileSpec>>,
     stats: Arc<Mutex<TrafficStats>>,
     token_bucket: Arc<Mutex<TokenBucket>>,
+    faults: Arc<Mutex<Option<Arc<FaultInjection>>>>,
+    fault_bucket: Arc<Mutex<TokenBucket>>,
 }
 
 impl TrafficShaper {
     /// Create a new traffic shaper with the given QoS profile
     pub fn new(spec: QosProfileSpec) -> Self {
-        let rate_limit = if spec.rate_limit_mbps > 0 {
-            spec.rate_limit_mbps as u64 * 1_000_000 / 8 // Convert Mbps to bytes/sec
-        } else {
-            u64::MAX // Unlimited
-        };
-
-        let burst_size = if spec.burst_size_kb > 0 {
-            spec.burst_size_kb as u64 * 1024
-        } else {
-            rate_limit / 10 // Default 100ms worth of tokens
-        };
-
         Self {
+            token_bucket: Arc::new(Mutex::new(TokenBucket::for_profile(&spec))),
             config: Arc::new(Mutex::new(spec)),
             stats: Arc::new(Mutex::new(TrafficStats::default())),
-            token_bucket: Arc::new(Mutex::new(TokenBucket::new(rate_limit, burst_size))),
+            faults: Arc::new(Mutex::new(None)),
+            fault_bucket: Arc::new(Mutex::new(TokenBucket::for_rate(0, 0))),
+        }
+    }
+
+    /// Inject chaos faults on top of the QoS profile, or stop injecting them
+    ///
+    /// The fault rate limit's bucket is only refilled when the rate changes,
+    /// so setting the same faults again doesn't hand out a fresh burst.
+    pub fn set_faults(&self, faults: Option<FaultInjection>) {
+        let mut current = self.faults.lock();
+        let rate = |f: Option<&FaultInjection>| f.map_or(0, |f| f.faults.rate_limit_mbps);
+        if rate(current.as_deref()) != rate(faults.as_ref()) {
+            *self.fault_bucket.lock() = TokenBucket::for_rate(rate(faults.as_ref()), 0);
+        }
+        *current = faults.map(Arc::new);
+    }
+
+    /// Chaos faults currently injected
+    pub fn faults(&self) -> Option<Arc<FaultInjection>> {
+        self.faults.lock().clone()
+    }
+
+    /// Process an outgoing Ethernet frame, dropping it if it crosses a
+    /// partition and shaping it otherwise
+    pub async fn shape_frame(&self, frame: &[u8]) -> ShapingDecision {
+        if self.faults().is_some_and(|faults| faults.blocks(frame)) {
+            let mut stats = self.stats.lock();
+            stats.packets_total += 1;
+            stats.bytes_total += frame.len() as u64;
+            stats.packets_dropped += 1;
+            trace!("Dropping frame across a partition");
+            return ShapingDecision::Drop;
         }
+        self.shape_packet(frame.len()).await
     }
 
     /// Update the QoS profile
     pub fn update_config(&self, spec: QosProfileSpec) {
+        *self.token_bucket.lock() = TokenBucket::for_profile(&spec);
         *self.config.lock() = spec;
     }
 
@@ -60,6 +93,7 @@ impl TrafficShaper {
     /// Returns the delay to apply before sending, and whether to drop
     pub async fn shape_packet(&self, packet_size: usize) -> ShapingDecision {
         let config = self.config.lock().clone();
+        let faults = self.faults();
         let mut stats = self.stats.lock();
 
         stats.packets_total += 1;
@@ -74,6 +108,13 @@ impl TrafficShaper {
                 return ShapingDecision::Drop;
             }
         }
+        if let Some(faults) = faults.as_ref().filter(|f| f.faults.loss_percent > 0.0) {
+            if rand::thread_rng().gen::<f32>() * 100.0 < faults.faults.loss_percent {
+                stats.packets_dropped += 1;
+                debug!("Dropping packet due to injected loss");
+                return ShapingDecision::Drop;
+            }
+        }
 
         // Calculate delay (latency + jitter)
         let mut delay_ms = config.latency_ms;
@@ -82,36 +123,42 @@ impl TrafficShaper {
             let jitter = rng.gen_range(0..=config.jitter_ms);
             delay_ms = delay_ms.saturating_add(jitter);
         }
+        let fault_delay = faults
+            .as_ref()
+            .map_or(Duration::ZERO, |f| f.sample_latency(&mut rand::thread_rng()));
 
-        // Apply rate limiting via token bucket
-        let mut token_bucket = self.token_bucket.lock();
-        token_bucket.refill();
+        let actual_size = packet_size + config.packet_padding_bytes as usize;
 
-        let actual_size = if config.packet_padding_bytes > 0 {
-            packet_size + config.packet_padding_bytes as usize
+        // Apply rate limiting via token bucket
+        let rate_delay = if config.rate_limit_mbps > 0 {
+            let mut token_bucket = self.token_bucket.lock();
+            token_bucket.refill();
+            token_bucket.consume(actual_size as u64)
         } else {
-            packet_size
+            Duration::ZERO
         };
-
-        let rate_delay = if !token_bucket.consume(actual_size as u64) {
-            // Calculate how long to wait for tokens
-            let tokens_needed = actual_size as u64 - token_bucket.tokens;
-            let wait_time = (tokens_needed * 1000) / token_bucket.rate.max(1);
-            stats.packets_delayed += 1;
-            wait_time as u32
-        } else {
-            0
+        // Both rate limits apply; the frame waits for the stricter one
+        let rate_delay = match faults.as_ref().filter(|f| f.faults.rate_limit_mbps > 0) {
+            Some(_) => {
+                let mut fault_bucket = self.fault_bucket.lock();
+                fault_bucket.refill();
+                rate_delay.max(fault_bucket.consume(actual_size as u64))
+            }
+            None => rate_delay,
         };
+        if rate_delay > Duration::ZERO {
+            stats.packets_delayed += 1;
+        }
 
-        let total_delay = delay_ms + rate_delay;
+        let total_delay = Duration::from_millis(delay_ms as u64) + fault_delay + rate_delay;
 
         if config.packet_padding_bytes > 0 {
             ShapingDecision::SendPadded {
-                delay: Duration::from_millis(total_delay as u64),
+                delay: total_delay,
                 padding: config.packet_padding_bytes as usize,
             }
-        } else if total_delay > 0 {
-            ShapingDecision::Delay(Duration::from_millis(total_delay as u64))
+        } else if total_delay > Duration::ZERO {
+            ShapingDecision::Delay(total_delay)
         } else {
             ShapingDecision::Send
         }
@@ -146,6 +193,94 @@ pub enum ShapingDecision {
     Drop,
 }
 
+/// Shape of the Pareto distribution of injected latency
+const PARETO_SHAPE: f64 = 3.0;
+
+/// Chaos faults injected into one NIC's frames
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultInjection {
+    pub faults: ChaosFaults,
+    /// MAC addresses of partitioned peers
+    pub blocked_macs: HashSet<[u8; 6]>,
+    /// IPv4 addresses of partitioned peers
+    pub blocked_ips: HashSet<Ipv4Addr>,
+}
+
+impl FaultInjection {
+    /// Add the faults of another profile on the same NIC: latencies add up,
+    /// losses compound, the lower rate limit wins and partitions combine
+    pub fn merge(&mut self, other: FaultInjection) {
+        let faults = &mut self.faults;
+        faults.latency_ms = faults.latency_ms.saturating_add(other.faults.latency_ms);
+        faults.jitter_ms = faults.jitter_ms.saturating_add(other.faults.jitter_ms);
+        if faults.distribution == LatencyDistribution::Uniform {
+            faults.distribution = other.faults.distribution;
+        }
+        faults.loss_percent =
+            100.0 - (100.0 - faults.loss_percent) * (100.0 - other.faults.loss_percent) / 100.0;
+        faults.rate_limit_mbps = match (faults.rate_limit_mbps, other.faults.rate_limit_mbps) {
+            (0, rate) | (rate, 0) => rate,
+            (a, b) => a.min(b),
+        };
+        self.blocked_macs.extend(other.blocked_macs);
+        self.blocked_ips.extend(other.blocked_ips);
+    }
+
+    /// Whether an Ethernet frame goes to or comes from a partitioned peer
+    ///
+    /// Peers are matched by MAC, and by the IPv4 addresses of IP and ARP
+    /// packets for traffic a router or NAT passed on.
+    pub fn blocks(&self, frame: &[u8]) -> bool {
+        if frame.len() < 14 {
+            return false;
+        }
+        let macs = [&frame[0..6], &frame[6..12]];
+        if macs
+            .iter()
+            .any(|mac| <[u8; 6]>::try_from(*mac).is_ok_and(|mac| self.blocked_macs.contains(&mac)))
+        {
+            return true;
+        }
+        if self.blocked_ips.is_empty() {
+            return false;
+        }
+
+        let payload = &frame[14..];
+        let addresses = match u16::from_be_bytes([frame[12], frame[13]]) {
+            0x0800 if payload.len() >= 20 => [&payload[12..16], &payload[16..20]],
+            0x0806 if payload.len() >= 28 => [&payload[14..18], &payload[24..28]],
+            _ => return false,
+        };
+        addresses
+            .iter()
+            .any(|ip| self.blocked_ips.contains(&Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3])))
+    }
+
+    /// Latency added to one frame
+    fn sample_latency(&self, rng: &mut impl Rng) -> Duration {
+        let mean = self.faults.latency_ms as f64;
+        let jitter = self.faults.jitter_ms as f64;
+        let ms = if jitter == 0.0 {
+            mean
+        } else {
+            match self.faults.distribution {
+                LatencyDistribution::Uniform => mean + rng.gen_range(-jitter..=jitter),
+                LatencyDistribution::Normal => {
+                    // Box-Muller transform
+                    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+                    let u2: f64 = rng.gen();
+                    mean + jitter * (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+                }
+                LatencyDistribution::Pareto => {
+                    let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+                    mean + jitter * (u.powf(-1.0 / PARETO_SHAPE) - 1.0)
+                }
+            }
+        };
+        Duration::from_secs_f64(ms.max(0.0) / 1000.0)
+    }
+}
+
 /// Traffic statistics
 #[derive(Debug, Clone, Default)]
 pub struct TrafficStats {
@@ -156,40 +291,65 @@ pub struct TrafficStats {
 }
 
 /// Token bucket for rate limiting
+///
+/// Packets that find the bucket empty still take their tokens, leaving it in
+/// debt, so queued packets are spaced out at the configured rate instead of
+/// all waiting for the same refill.
 struct TokenBucket {
-    tokens: u64,
-    max_tokens: u64,
+    tokens: i64,
+    max_tokens: i64,
     rate: u64, // tokens per second
     last_refill: Instant,
 }
 
 impl TokenBucket {
     fn new(rate: u64, burst_size: u64) -> Self {
+        let burst = i64::try_from(burst_size).unwrap_or(i64::MAX);
         Self {
-            tokens: burst_size,
-            max_tokens: burst_size,
-            rate,
+            tokens: burst,
+            max_tokens: burst,
+            rate: rate.max(1),
             last_refill: Instant::now(),
         }
     }
 
+    /// Bucket for a profile's rate limit (bytes/sec) and burst size
+    fn for_profile(spec: &QosProfileSpec) -> Self {
+        Self::for_rate(spec.rate_limit_mbps, spec.burst_size_kb)
+    }
+
+    /// Bucket for a rate limit in Mbps, with a burst of 100ms if `burst_size_kb` is 0
+    fn for_rate(rate_limit_mbps: u32, burst_size_kb: u32) -> Self {
+        let rate = rate_limit_mbps as u64 * 1_000_000 / 8; // Convert Mbps to bytes/sec
+        let burst_size = if burst_size_kb > 0 {
+            burst_size_kb as u64 * 1024
+        } else {
+            rate / 10 // Default 100ms worth of tokens
+        };
+        Self::new(rate, burst_size)
+    }
+
     fn refill(&mut self) {
         let now = Instant::now();
         let elapsed = now.duration_since(self.last_refill);
-        let new_tokens = (elapsed.as_micros() as u64 * self.rate) / 1_000_000;
-        
+        let new_tokens = (elapsed.as_micros() as u64).saturating_mul(self.rate) / 1_000_000;
+
         if new_tokens > 0 {
-            self.tokens = (self.tokens + new_tokens).min(self.max_tokens);
+            let new_tokens = i64::try_from(new_tokens).unwrap_or(i64::MAX);
+            self.tokens = self.tokens.saturating_add(new_tokens).min(self.max_tokens);
             self.last_refill = now;
         }
     }
 
-    fn consume(&mut self, tokens: u64) -> bool {
-        if self.tokens >= tokens {
-            self.tokens -= tokens;
-            true
+    /// Take `tokens` and return how long until the bucket is out of debt
+    fn consume(&mut self, tokens: u64) -> Duration {
+        self.tokens = self
+            .tokens
+            .saturating_sub(i64::try_from(tokens).unwrap_or(i64::MAX));
+        if self.tokens >= 0 {
+            Duration::ZERO
         } else {
-            false
+            Duration::from_micros(self.tokens.unsigned_abs().saturating_mul(1_000_000) / self.rate)
         }
     }
 }
@@ -450,6 +610,133 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_rate_limit_spaces_packets() {
+        let spec = QosProfileSpec {
+            rate_limit_mbps: 8, // 1 MB/s
+            burst_size_kb: 1,
+            ..Default::default()
+        };
+        let shaper = TrafficShaper::new(spec);
+
+        // The burst covers the first packet; later ones queue behind it
+        assert!(matches!(shaper.shape_packet(1000).await, ShapingDecision::Send));
+        let mut last = Duration::ZERO;
+        for _ in 0..4 {
+            match shaper.shape_packet(1000).await {
+                ShapingDecision::Delay(d) => {
+                    assert!(d > last);
+                    last = d;
+                }
+                other => panic!("Expected Delay decision, got {:?}", other),
+            }
+        }
+        // ~4 KB over the burst at 1 MB/s
+        assert!(last >= Duration::from_millis(3));
+        assert_eq!(shaper.stats().packets_delayed, 4);
+    }
+
+    fn frame(dst: [u8; 6], src: [u8; 6], ip_src: [u8; 4], ip_dst: [u8; 4]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        frame.extend(dst);
+        frame.extend(src);
+        frame.extend([0x08, 0x00]);
+        let mut ip = [0u8; 20];
+        ip[12..16].copy_from_slice(&ip_src);
+        ip[16..20].copy_from_slice(&ip_dst);
+        frame.extend(ip);
+        frame
+    }
+
+    #[tokio::test]
+    async fn test_partition_drops_peer_frames() {
+        let me = [0x52, 0x54, 0, 0, 0, 1];
+        let peer = [0x52, 0x54, 0, 0, 0, 2];
+        let other = [0x52, 0x54, 0, 0, 0, 3];
+        let shaper = TrafficShaper::new(QosProfileSpec::default());
+        shaper.set_faults(Some(FaultInjection {
+            blocked_macs: HashSet::from([peer]),
+            blocked_ips: HashSet::from([Ipv4Addr::new(10, 0, 0, 2)]),
+            ..Default::default()
+        }));
+
+        let to_peer = frame(peer, me, [10, 0, 0, 1], [10, 0, 0, 2]);
+        let from_peer = frame(me, peer, [10, 0, 0, 2], [10, 0, 0, 1]);
+        // Routed through a gateway: only the IP gives the peer away
+        let routed = frame(other, me, [10, 0, 0, 1], [10, 0, 0, 2]);
+        let unrelated = frame(other, me, [10, 0, 0, 1], [10, 0, 0, 3]);
+
+        for blocked in [&to_peer, &from_peer, &routed] {
+            assert!(matches!(shaper.shape_frame(blocked).await, ShapingDecision::Drop));
+        }
+        assert!(matches!(shaper.shape_frame(&unrelated).await, ShapingDecision::Send));
+        assert_eq!(shaper.stats().packets_dropped, 3);
+
+        shaper.set_faults(None);
+        assert!(matches!(shaper.shape_frame(&to_peer).await, ShapingDecision::Send));
+    }
+
+    #[test]
+    fn test_fault_latency_distributions() {
+        let mut rng = rand::thread_rng();
+        for distribution in [
+            LatencyDistribution::Uniform,
+            LatencyDistribution::Normal,
+            LatencyDistribution::Pareto,
+        ] {
+            let faults = FaultInjection {
+                faults: ChaosFaults {
+                    latency_ms: 100,
+                    jitter_ms: 20,
+                    distribution,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let samples: Vec<f64> = (0..2000)
+                .map(|_| faults.sample_latency(&mut rng).as_secs_f64() * 1000.0)
+                .collect();
+            let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+            match distribution {
+                LatencyDistribution::Uniform => {
+                    assert!(samples.iter().all(|ms| (80.0..=120.0).contains(ms)));
+                    assert!((mean - 100.0).abs() < 3.0);
+                }
+                LatencyDistribution::Normal => assert!((mean - 100.0).abs() < 3.0),
+                LatencyDistribution::Pareto => assert!(samples.iter().all(|ms| *ms >= 100.0)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fault_merge() {
+        let mut faults = FaultInjection {
+            faults: ChaosFaults {
+                latency_ms: 50,
+                loss_percent: 50.0,
+                rate_limit_mbps: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        faults.merge(FaultInjection {
+            faults: ChaosFaults {
+                latency_ms: 25,
+                loss_percent: 50.0,
+                rate_limit_mbps: 0,
+                distribution: LatencyDistribution::Normal,
+                ..Default::default()
+            },
+            blocked_macs: HashSet::from([[0; 6]]),
+            ..Default::default()
+        });
+        assert_eq!(faults.faults.latency_ms, 75);
+        assert_eq!(faults.faults.loss_percent, 75.0);
+        assert_eq!(faults.faults.rate_limit_mbps, 10);
+        assert_eq!(faults.faults.distribution, LatencyDistribution::Normal);
+        assert_eq!(faults.blocked_macs.len(), 1);
+    }
+
     #[test]
     fn test_lora_toa() {
         use lora::LoRaPacket;
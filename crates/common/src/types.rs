@@ -1,7 +1,7 @@
 //! Core types for InfraSim
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 /// Resource metadata common to all resources
@@ -41,6 +41,11 @@ impl ResourceMeta {
         self.updated_at = chrono::Utc::now().timestamp();
         self.generation += 1;
     }
+
+    /// Whether every `key=value` pair of a label selector is set; an empty selector matches all
+    pub fn matches(&self, selector: &HashMap<String, String>) -> bool {
+        selector.iter().all(|(k, v)| self.labels.get(k) == Some(v))
+    }
 }
 
 /// VM state
@@ -51,6 +56,8 @@ pub enum VmState {
     Running,
     Stopped,
     Paused,
+    /// RAM saved to disk and QEMU exited; resumes where it left off
+    Suspended,
     Error,
 }
 
@@ -67,6 +74,7 @@ impl std::fmt::Display for VmState {
             VmState::Running => write!(f, "running"),
             VmState::Stopped => write!(f, "stopped"),
             VmState::Paused => write!(f, "paused"),
+            VmState::Suspended => write!(f, "suspended"),
             VmState::Error => write!(f, "error"),
         }
     }
@@ -93,6 +101,8 @@ impl Default for NetworkMode {
 pub enum VolumeKind {
     Disk,
     Weights,
+    /// Raw host block device or partition passed through to the guest
+    Device,
 }
 
 impl Default for VolumeKind {
@@ -101,6 +111,9 @@ impl Default for VolumeKind {
     }
 }
 
+/// Label opting a VM out of idle suspension when set to `false`
+pub const IDLE_SUSPEND_LABEL: &str = "infrasim.io/idle-suspend";
+
 /// VM specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VmSpec {
@@ -120,6 +133,247 @@ pub struct VmSpec {
     pub extra_args: HashMap<String, String>,
     #[serde(default)]
     pub compatibility_mode: bool,
+    /// Immutable source of linked clones; templates never start
+    #[serde(default)]
+    pub template: bool,
+    /// ID of the template this VM was cloned from
+    #[serde(default)]
+    pub cloned_from: Option<String>,
+    /// Pinned MACs and static IPs of NICs on networks in `network_ids`
+    #[serde(default)]
+    pub network_interfaces: Vec<NetworkAttachment>,
+    /// Host directories mounted into the guest
+    #[serde(default)]
+    pub shared_folders: Vec<SharedFolder>,
+    /// Lower priorities are paused first when the host runs short of memory
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl VmSpec {
+    /// Settings of the NIC on `network_id`, if any were given
+    pub fn attachment(&self, network_id: &str) -> Option<&NetworkAttachment> {
+        self.network_interfaces.iter().find(|a| a.network_id == network_id)
+    }
+
+    /// Drop pinned MACs and static IPs, for copies of this VM that would
+    /// otherwise clash with it on the network
+    pub fn without_pinned_addresses(mut self) -> Self {
+        self.network_interfaces.clear();
+        self
+    }
+
+    /// Check attachments name attached networks once, with valid addresses
+    pub fn validate_network_interfaces(&self) -> crate::Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        for attachment in &self.network_interfaces {
+            if !self.network_ids.contains(&attachment.network_id) {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "Network interface names network {}, which the VM is not attached to",
+                    attachment.network_id
+                )));
+            }
+            if !seen.insert(attachment.network_id.as_str()) {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "Network {} has more than one network interface entry",
+                    attachment.network_id
+                )));
+            }
+            if let Some(mac) = &attachment.mac {
+                if parse_unicast_mac(mac).is_none() {
+                    return Err(crate::Error::InvalidConfig(format!(
+                        "'{}' is not a unicast MAC address",
+                        mac
+                    )));
+                }
+            }
+            if let Some(ip) = &attachment.ip {
+                if ip.parse::<std::net::Ipv4Addr>().is_err() {
+                    return Err(crate::Error::InvalidConfig(format!(
+                        "'{}' is not an IPv4 address",
+                        ip
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check shared folders have absolute paths and distinct, valid tags
+    pub fn validate_shared_folders(&self) -> crate::Result<()> {
+        let mut tags = std::collections::HashSet::new();
+        let mut mounts = std::collections::HashSet::new();
+        for folder in &self.shared_folders {
+            if !folder.host_path.starts_with('/') || !folder.guest_path.starts_with('/') {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "Shared folder {} needs absolute host and guest paths",
+                    folder.tag
+                )));
+            }
+            let valid_tag = !folder.tag.is_empty()
+                && folder.tag.len() <= SHARED_FOLDER_TAG_MAX
+                && folder
+                    .tag
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_tag {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "'{}' is not a valid mount tag (up to {} letters, digits, '-' or '_')",
+                    folder.tag, SHARED_FOLDER_TAG_MAX
+                )));
+            }
+            if !tags.insert(folder.tag.as_str()) {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "More than one shared folder has the tag {}",
+                    folder.tag
+                )));
+            }
+            if !mounts.insert(folder.guest_path.trim_end_matches('/')) {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "More than one shared folder is mounted at {}",
+                    folder.guest_path
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Longest mount tag 9p accepts
+pub const SHARED_FOLDER_TAG_MAX: usize = 31;
+
+/// Host directory mounted into a VM's guest over virtiofs or 9p
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedFolder {
+    /// Tag the guest mounts the share by
+    pub tag: String,
+    /// Absolute directory on the daemon's host
+    pub host_path: String,
+    /// Absolute mount point in the guest
+    pub guest_path: String,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub cache: SharedFolderCache,
+    #[serde(default)]
+    pub protocol: SharedFolderProtocol,
+}
+
+impl SharedFolder {
+    /// Mount tag for a guest path: `/mnt/code` becomes `mnt-code`
+    pub fn default_tag(guest_path: &str) -> String {
+        let tag: String = guest_path
+            .trim_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' })
+            .take(SHARED_FOLDER_TAG_MAX)
+            .collect();
+        if tag.is_empty() {
+            "root".to_string()
+        } else {
+            tag
+        }
+    }
+
+    /// Command that mounts the share in a Linux guest, once `protocol` is resolved
+    ///
+    /// virtiofsd applies the cache mode on the host; 9p leaves it to the guest.
+    pub fn mount_command(&self, protocol: SharedFolderProtocol) -> String {
+        let ro = if self.read_only { ",ro" } else { "" };
+        match protocol {
+            SharedFolderProtocol::NineP => {
+                let cache = match self.cache {
+                    SharedFolderCache::Auto => "mmap",
+                    SharedFolderCache::Always => "loose",
+                    SharedFolderCache::Never => "none",
+                };
+                format!(
+                    "mount -t 9p -o trans=virtio,version=9p2000.L,cache={}{} {} {}",
+                    cache, ro, self.tag, self.guest_path
+                )
+            }
+            _ if self.read_only => format!("mount -t virtiofs -o ro {} {}", self.tag, self.guest_path),
+            _ => format!("mount -t virtiofs {} {}", self.tag, self.guest_path),
+        }
+    }
+}
+
+/// Guest caching of shared folder data and metadata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharedFolderCache {
+    /// Cache until the host changes the file (close-to-open)
+    #[default]
+    Auto,
+    /// Cache everything; only for folders the host doesn't change
+    Always,
+    /// Always ask the host
+    Never,
+}
+
+impl SharedFolderCache {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
+}
+
+/// Transport of a shared folder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharedFolderProtocol {
+    /// virtiofs where the host has virtiofsd, 9p otherwise
+    #[default]
+    Auto,
+    Virtiofs,
+    #[serde(rename = "9p")]
+    NineP,
+}
+
+impl SharedFolderProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Virtiofs => "virtiofs",
+            Self::NineP => "9p",
+        }
+    }
+}
+
+/// Pinned addressing of a VM's NIC on one network
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkAttachment {
+    pub network_id: String,
+    /// MAC of the NIC; derived from the VM ID when unset
+    #[serde(default)]
+    pub mac: Option<String>,
+    /// IPv4 address the network's DHCP server reserves for the NIC
+    #[serde(default)]
+    pub ip: Option<String>,
+}
+
+/// Parse a unicast MAC address, lowercased with two digits per octet
+pub fn parse_unicast_mac(mac: &str) -> Option<String> {
+    let octets: Vec<u8> = mac
+        .split(':')
+        .map(|octet| match octet.len() {
+            1 | 2 => u8::from_str_radix(octet, 16).ok(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    if octets.len() != 6 || octets[0] & 1 == 1 || octets.iter().all(|o| *o == 0) {
+        return None;
+    }
+    Some(
+        octets
+            .iter()
+            .map(|o| format!("{:02x}", o))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
 }
 
 impl Default for VmSpec {
@@ -136,6 +390,11 @@ impl Default for VmSpec {
             boot_disk_id: None,
             extra_args: HashMap::new(),
             compatibility_mode: false,
+            template: false,
+            cloned_from: None,
+            network_interfaces: Vec::new(),
+            shared_folders: Vec::new(),
+            priority: 0,
         }
     }
 }
@@ -149,6 +408,12 @@ pub struct VmStatus {
     pub vnc_display: Option<String>,
     pub error_message: Option<String>,
     pub uptime_seconds: u64,
+    /// Snapshot the boot disk currently builds on (parent of the next snapshot)
+    #[serde(default)]
+    pub current_snapshot_id: Option<String>,
+    /// When the running QEMU process was started (Unix seconds)
+    #[serde(default)]
+    pub started_at: Option<i64>,
 }
 
 impl Default for VmStatus {
@@ -160,6 +425,8 @@ impl Default for VmStatus {
             vnc_display: None,
             error_message: None,
             uptime_seconds: 0,
+            current_snapshot_id: None,
+            started_at: None,
         }
     }
 }
@@ -212,6 +479,57 @@ pub struct NetworkStatus {
     pub active: bool,
     pub bridge_interface: Option<String>,
     pub connected_vms: u32,
+    /// Latest packet capture, kept after it stops so it can be downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<NetworkCapture>,
+}
+
+/// State of a packet capture
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureState {
+    #[default]
+    Running,
+    Stopped,
+    Failed,
+}
+
+/// How a network's packets are captured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureMethod {
+    /// `tcpdump` on the host bridge of a shared network
+    #[default]
+    Tcpdump,
+    /// QEMU `filter-dump` on the NIC of each running VM on the network
+    FilterDump,
+}
+
+impl CaptureMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptureMethod::Tcpdump => "tcpdump",
+            CaptureMethod::FilterDump => "filter-dump",
+        }
+    }
+}
+
+/// Packet capture on a network
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkCapture {
+    pub id: String,
+    pub state: CaptureState,
+    pub method: CaptureMethod,
+    /// pcap filter expression
+    pub filter: Option<String>,
+    pub max_bytes: u64,
+    pub max_seconds: u64,
+    pub started_at: i64,
+    pub stopped_at: Option<i64>,
+    /// Size of the pcap file so far
+    pub bytes: u64,
+    /// Why the capture stopped: requested, a limit, or the error it failed with
+    pub stop_reason: Option<String>,
 }
 
 /// Network
@@ -222,6 +540,405 @@ pub struct Network {
     pub status: NetworkStatus,
 }
 
+/// Transport protocol for a port forward
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortProtocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl PortProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+        }
+    }
+}
+
+/// Port forward specification (user-mode NAT networks only)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortForwardSpec {
+    pub vm_id: String,
+    /// Network the forward is attached to (None = the VM's first NIC)
+    pub network_id: Option<String>,
+    #[serde(default)]
+    pub protocol: PortProtocol,
+    #[serde(default = "default_host_addr")]
+    pub host_addr: String,
+    pub host_port: u16,
+    pub guest_port: u16,
+}
+
+fn default_host_addr() -> String {
+    "127.0.0.1".to_string()
+}
+
+impl PortForwardSpec {
+    /// QEMU `hostfwd` rule, e.g. `tcp:127.0.0.1:8080-:80`
+    pub fn hostfwd_rule(&self) -> String {
+        format!(
+            "{}:{}:{}-:{}",
+            self.protocol.as_str(),
+            self.host_addr,
+            self.host_port,
+            self.guest_port
+        )
+    }
+}
+
+/// Port forward status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PortForwardStatus {
+    /// Whether the rule is installed in the running QEMU process
+    pub active: bool,
+    pub error_message: Option<String>,
+}
+
+/// Port forward from a host port to a guest port
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortForward {
+    pub meta: ResourceMeta,
+    pub spec: PortForwardSpec,
+    pub status: PortForwardStatus,
+}
+
+/// What a router does with traffic a firewall rule matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FirewallAction {
+    #[default]
+    Allow,
+    Deny,
+}
+
+impl FirewallAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+/// Protocol a firewall rule matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleProtocol {
+    #[default]
+    Any,
+    Tcp,
+    Udp,
+    Icmp,
+}
+
+impl RuleProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+            Self::Icmp => "icmp",
+        }
+    }
+}
+
+/// Static route: traffic for `destination` goes to `next_hop`, an address on
+/// one of the router's networks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaticRoute {
+    pub destination: String,
+    pub next_hop: String,
+}
+
+/// Firewall rule for traffic forwarded between a router's networks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirewallRule {
+    #[serde(default)]
+    pub action: FirewallAction,
+    #[serde(default)]
+    pub protocol: RuleProtocol,
+    /// Source CIDR (None = any)
+    pub source: Option<String>,
+    /// Destination CIDR (None = any)
+    pub destination: Option<String>,
+    /// Destination port, TCP and UDP only (None = any)
+    pub port: Option<u16>,
+}
+
+impl FirewallRule {
+    /// iptables match and target, e.g. `-d 10.2.0.0/24 -p tcp --dport 443 -j ACCEPT`
+    pub fn iptables_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(source) = &self.source {
+            args.extend(["-s".to_string(), source.clone()]);
+        }
+        if let Some(destination) = &self.destination {
+            args.extend(["-d".to_string(), destination.clone()]);
+        }
+        if self.protocol != RuleProtocol::Any {
+            args.extend(["-p".to_string(), self.protocol.as_str().to_string()]);
+        }
+        if let Some(port) = self.port {
+            args.extend(["--dport".to_string(), port.to_string()]);
+        }
+        args.extend(["-j".to_string(), iptables_target(self.action).to_string()]);
+        args
+    }
+}
+
+/// iptables target for a firewall action
+pub fn iptables_target(action: FirewallAction) -> &'static str {
+    match action {
+        FirewallAction::Allow => "ACCEPT",
+        FirewallAction::Deny => "DROP",
+    }
+}
+
+/// Router specification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouterSpec {
+    /// Networks the router forwards between
+    pub network_ids: Vec<String>,
+    #[serde(default)]
+    pub routes: Vec<StaticRoute>,
+    /// Checked in order; the first match decides
+    #[serde(default)]
+    pub rules: Vec<FirewallRule>,
+    /// For forwarded traffic no rule matches
+    #[serde(default)]
+    pub default_action: FirewallAction,
+}
+
+impl RouterSpec {
+    /// Check addresses and rules before the router is stored
+    pub fn validate(&self) -> crate::Result<()> {
+        let invalid = |msg: String| Err(crate::Error::InvalidConfig(msg));
+
+        if self.network_ids.len() < 2 {
+            return invalid("A router needs at least two networks".to_string());
+        }
+        for (i, id) in self.network_ids.iter().enumerate() {
+            if self.network_ids[..i].contains(id) {
+                return invalid(format!("Network {} is listed twice", id));
+            }
+        }
+        for route in &self.routes {
+            if parse_ipv4_cidr(&route.destination).is_none() {
+                return invalid(format!("Invalid route destination: {}", route.destination));
+            }
+            if route.next_hop.parse::<std::net::Ipv4Addr>().is_err() {
+                return invalid(format!("Invalid route next hop: {}", route.next_hop));
+            }
+        }
+        for rule in &self.rules {
+            for cidr in rule.source.iter().chain(&rule.destination) {
+                if parse_ipv4_cidr(cidr).is_none() {
+                    return invalid(format!("Invalid firewall rule CIDR: {}", cidr));
+                }
+            }
+            if rule.port.is_some() && !matches!(rule.protocol, RuleProtocol::Tcp | RuleProtocol::Udp) {
+                return invalid("Firewall rule ports need protocol tcp or udp".to_string());
+            }
+            if rule.port == Some(0) {
+                return invalid("Firewall rule port must be between 1 and 65535".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Router status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouterStatus {
+    /// Whether routes and rules are installed on the host
+    pub active: bool,
+    pub error_message: Option<String>,
+    /// Host interfaces the router forwards between
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+}
+
+/// Virtual router connecting networks, with static routes and a firewall
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Router {
+    pub meta: ResourceMeta,
+    pub spec: RouterSpec,
+    pub status: RouterStatus,
+}
+
+/// Network address and prefix length of an IPv4 CIDR such as `10.1.0.0/24`
+///
+/// A bare address is taken as a /32.
+pub fn parse_ipv4_cidr(cidr: &str) -> Option<(std::net::Ipv4Addr, u8)> {
+    let (addr, prefix) = match cidr.split_once('/') {
+        Some((addr, prefix)) => (addr, prefix.parse().ok().filter(|p| *p <= 32)?),
+        None => (cidr, 32),
+    };
+    let addr: std::net::Ipv4Addr = addr.parse().ok()?;
+    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+    Some(((u32::from(addr) & mask).into(), prefix))
+}
+
+/// Whether `addr` lies in the IPv4 CIDR `cidr`
+pub fn ipv4_cidr_contains(cidr: &str, addr: std::net::Ipv4Addr) -> bool {
+    match parse_ipv4_cidr(cidr) {
+        Some((network, prefix)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(addr) & mask == u32::from(network)
+        }
+        None => false,
+    }
+}
+
+/// Host bus of a passthrough device
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceBus {
+    #[default]
+    Usb,
+    Pci,
+}
+
+impl DeviceBus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Usb => "usb",
+            Self::Pci => "pci",
+        }
+    }
+}
+
+/// Host device passthrough specification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSpec {
+    pub vm_id: String,
+    #[serde(default)]
+    pub bus: DeviceBus,
+    /// USB vendor/product pair, matched on whichever port the device is in
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// USB `<bus>.<addr>`, or PCI `<domain>:<bus>:<slot>.<function>`
+    pub host_address: Option<String>,
+}
+
+impl DeviceSpec {
+    /// QEMU device driver, `usb-host` or `vfio-pci`
+    pub fn qemu_driver(&self) -> &'static str {
+        match self.bus {
+            DeviceBus::Usb => "usb-host",
+            DeviceBus::Pci => "vfio-pci",
+        }
+    }
+
+    /// QEMU properties selecting the host device, as typed for `device_add`
+    pub fn qemu_properties(&self) -> crate::Result<Vec<(&'static str, serde_json::Value)>> {
+        let invalid = |msg: &str| crate::Error::InvalidConfig(msg.to_string());
+
+        match self.bus {
+            DeviceBus::Usb => match (self.vendor_id, self.product_id, &self.host_address) {
+                (Some(vendor), Some(product), None) => Ok(vec![
+                    ("vendorid", vendor.into()),
+                    ("productid", product.into()),
+                ]),
+                (None, None, Some(addr)) => {
+                    let (bus, dev) = addr
+                        .split_once('.')
+                        .and_then(|(b, d)| Some((b.parse::<u8>().ok()?, d.parse::<u8>().ok()?)))
+                        .ok_or_else(|| {
+                            crate::Error::InvalidConfig(format!(
+                                "Invalid USB address '{}', expected <bus>.<addr>",
+                                addr
+                            ))
+                        })?;
+                    Ok(vec![("hostbus", bus.into()), ("hostaddr", dev.into())])
+                }
+                _ => Err(invalid(
+                    "USB devices need either vendor_id and product_id, or host_address",
+                )),
+            },
+            DeviceBus::Pci => {
+                if self.vendor_id.is_some() || self.product_id.is_some() {
+                    return Err(invalid("PCI devices are selected by host_address only"));
+                }
+                let addr = self
+                    .host_address
+                    .as_deref()
+                    .ok_or_else(|| invalid("PCI devices need a host_address"))?;
+                if !is_pci_address(addr) {
+                    return Err(crate::Error::InvalidConfig(format!(
+                        "Invalid PCI address '{}', expected e.g. 0000:01:00.0",
+                        addr
+                    )));
+                }
+                Ok(vec![("host", addr.into())])
+            }
+        }
+    }
+
+    /// Human-readable host device, e.g. `usb 046d:c52b` or `pci 0000:01:00.0`
+    pub fn describe(&self) -> String {
+        match (self.vendor_id, self.product_id, &self.host_address) {
+            (Some(vendor), Some(product), _) => {
+                format!("{} {:04x}:{:04x}", self.bus.as_str(), vendor, product)
+            }
+            (_, _, Some(addr)) => format!("{} {}", self.bus.as_str(), addr),
+            _ => self.bus.as_str().to_string(),
+        }
+    }
+}
+
+/// Whether `addr` is a full PCI address (`dddd:bb:ss.f`)
+fn is_pci_address(addr: &str) -> bool {
+    let hex = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_hexdigit());
+    match addr.split(':').collect::<Vec<_>>().as_slice() {
+        [domain, bus, slot_fn] => match slot_fn.split_once('.') {
+            Some((slot, func)) => {
+                hex(domain, 4) && hex(bus, 2) && hex(slot, 2) && matches!(func.as_bytes(), [b'0'..=b'7'])
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Device status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceStatus {
+    /// Whether the device is present in the running QEMU process
+    pub attached: bool,
+    pub error_message: Option<String>,
+}
+
+/// Host USB or PCI device passed through to a VM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+    pub meta: ResourceMeta,
+    pub spec: DeviceSpec,
+    pub status: DeviceStatus,
+}
+
+impl Device {
+    /// QEMU device ID, stable across restarts
+    pub fn qemu_id(&self) -> String {
+        format!("hostdev-{}", self.meta.id)
+    }
+
+    /// `-device` argument for the QEMU command line
+    pub fn qemu_arg(&self) -> crate::Result<String> {
+        let mut arg = format!("{},id={}", self.spec.qemu_driver(), self.qemu_id());
+        for (key, value) in self.spec.qemu_properties()? {
+            match value {
+                serde_json::Value::String(s) => arg.push_str(&format!(",{}={}", key, s)),
+                other => arg.push_str(&format!(",{}={}", key, other)),
+            }
+        }
+        Ok(arg)
+    }
+}
+
 /// QoS profile specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QosProfileSpec {
@@ -262,6 +979,112 @@ pub struct QosProfile {
     pub spec: QosProfileSpec,
 }
 
+/// How a chaos profile's added latency varies around its mean, as in netem
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyDistribution {
+    /// Evenly spread within ± jitter
+    #[default]
+    Uniform,
+    /// Normal, with jitter as the standard deviation
+    Normal,
+    /// Heavy-tailed: mostly near the mean, now and then several jitters above it
+    Pareto,
+}
+
+impl LatencyDistribution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LatencyDistribution::Uniform => "uniform",
+            LatencyDistribution::Normal => "normal",
+            LatencyDistribution::Pareto => "pareto",
+        }
+    }
+}
+
+/// Faults a chaos profile injects into the frames of the NICs it targets
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChaosFaults {
+    #[serde(default)]
+    pub latency_ms: u32,
+    #[serde(default)]
+    pub jitter_ms: u32,
+    #[serde(default)]
+    pub distribution: LatencyDistribution,
+    #[serde(default)]
+    pub loss_percent: f32,
+    #[serde(default)]
+    pub rate_limit_mbps: u32,
+}
+
+/// Chaos profile specification
+///
+/// With only `network_id` set, the faults apply to every VM NIC on the
+/// network; with only `vm_id`, to every NIC of the VM; with both, to the
+/// VM's NIC on the network.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChaosProfileSpec {
+    pub network_id: Option<String>,
+    pub vm_id: Option<String>,
+    #[serde(flatten)]
+    pub faults: ChaosFaults,
+    /// Groups of VM IDs; targeted VMs in one group drop frames to and from
+    /// the VMs of the other groups
+    #[serde(default)]
+    pub partitions: Vec<Vec<String>>,
+    /// Unix time the profile is deleted at
+    pub expires_at: Option<i64>,
+}
+
+impl ChaosProfileSpec {
+    /// Check the target, faults and partitions before the profile is stored
+    pub fn validate(&self) -> crate::Result<()> {
+        let invalid = |msg: &str| Err(crate::Error::InvalidConfig(msg.to_string()));
+
+        if self.network_id.is_none() && self.vm_id.is_none() {
+            return invalid("A chaos profile needs a network, a VM, or both");
+        }
+        if !(0.0..=100.0).contains(&self.faults.loss_percent) {
+            return invalid("Packet loss must be between 0 and 100 percent");
+        }
+        if !self.partitions.is_empty() {
+            if self.partitions.len() < 2 || self.partitions.iter().any(|group| group.is_empty()) {
+                return invalid("A partition needs at least two non-empty groups of VMs");
+            }
+            let mut seen = std::collections::HashSet::new();
+            if !self.partitions.iter().flatten().all(|vm| seen.insert(vm)) {
+                return invalid("A VM can only be in one partition group");
+            }
+        }
+        Ok(())
+    }
+
+    /// Partition group `vm_id` is in
+    pub fn partition_of(&self, vm_id: &str) -> Option<usize> {
+        self.partitions.iter().position(|group| group.iter().any(|id| id == vm_id))
+    }
+}
+
+/// Chaos profile status
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChaosProfileStatus {
+    /// Running VMs whose traffic has the faults
+    #[serde(default)]
+    pub applied_vm_ids: Vec<String>,
+    /// Targeted running VMs that started without shaping relays; they get
+    /// the faults on their next start
+    #[serde(default)]
+    pub pending_vm_ids: Vec<String>,
+}
+
+/// Faults injected into a network or a VM's NICs until they expire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChaosProfile {
+    pub meta: ResourceMeta,
+    pub spec: ChaosProfileSpec,
+    pub status: ChaosProfileStatus,
+}
+
 /// Integrity configuration for volumes
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct IntegrityConfig {
@@ -310,22 +1133,82 @@ pub struct VolumeSpec {
     pub format: String,
     #[serde(default)]
     pub overlay: bool,
+    /// LUKS encryption of the image and every snapshot layer above it
+    #[serde(default)]
+    pub encryption: Option<VolumeEncryption>,
+    /// How the source image was built, for its SLSA provenance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<crate::slsa::BuildInputs>,
 }
 
 fn default_format() -> String {
     "qcow2".to_string()
 }
 
-impl Default for VolumeSpec {
-    fn default() -> Self {
-        Self {
-            kind: VolumeKind::Disk,
-            source: String::new(),
-            integrity: IntegrityConfig::default(),
-            read_only: false,
-            size_bytes: None,
-            format: "qcow2".to_string(),
-            overlay: false,
+impl VolumeSpec {
+    /// Whether the source must be downloaded over HTTP(S)
+    pub fn is_remote(&self) -> bool {
+        self.source.starts_with("http://") || self.source.starts_with("https://")
+    }
+}
+
+/// Ciphers accepted for encrypted volumes
+pub const VOLUME_CIPHERS: &[&str] = &["aes-128", "aes-192", "aes-256", "serpent-256", "twofish-256"];
+
+/// Encryption of a volume's qcow2 images
+///
+/// The passphrase lives in the daemon's keyring under `key_id`; only the
+/// reference is stored with the volume.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeEncryption {
+    #[serde(default = "default_cipher")]
+    pub cipher: String,
+    pub key_id: String,
+}
+
+fn default_cipher() -> String {
+    "aes-256".to_string()
+}
+
+impl VolumeEncryption {
+    /// Check the cipher and key ID before any image is created
+    pub fn validate(&self) -> crate::Result<()> {
+        if !VOLUME_CIPHERS.contains(&self.cipher.as_str()) {
+            return Err(crate::Error::InvalidConfig(format!(
+                "Unsupported cipher {}; expected one of {}",
+                self.cipher,
+                VOLUME_CIPHERS.join(", ")
+            )));
+        }
+        let valid_id = !self.key_id.is_empty()
+            && self.key_id.len() <= 64
+            && !self.key_id.starts_with('.')
+            && self
+                .key_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_id {
+            return Err(crate::Error::InvalidConfig(format!(
+                "Invalid encryption key ID {:?}: use up to 64 letters, digits, '-', '_' or '.'",
+                self.key_id
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for VolumeSpec {
+    fn default() -> Self {
+        Self {
+            kind: VolumeKind::Disk,
+            source: String::new(),
+            integrity: IntegrityConfig::default(),
+            read_only: false,
+            size_bytes: None,
+            format: "qcow2".to_string(),
+            overlay: false,
+            encryption: None,
+            build: None,
         }
     }
 }
@@ -338,6 +1221,49 @@ pub struct VolumeStatus {
     pub digest: Option<String>,
     pub actual_size: u64,
     pub verified: bool,
+    /// Operator-facing warnings (e.g. raw device passthrough risks)
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// SMART health summary for device volumes
+    #[serde(default)]
+    pub smart_status: Option<String>,
+    /// Progress of a remote (http/https) source import
+    #[serde(default)]
+    pub download: Option<DownloadStatus>,
+    /// CAS digest of the signed SLSA provenance of the source image
+    #[serde(default)]
+    pub provenance: Option<String>,
+}
+
+/// Remote import state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    #[default]
+    Pending,
+    Downloading,
+    Verifying,
+    Complete,
+    /// Permanent failure (e.g. digest mismatch); not retried
+    Failed,
+}
+
+/// Progress of a remote volume import
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DownloadStatus {
+    pub state: DownloadState,
+    pub bytes_downloaded: u64,
+    /// Total size if the server reported one
+    pub bytes_total: Option<u64>,
+    /// Last error (transient errors are retried and resumed)
+    pub error: Option<String>,
+}
+
+impl DownloadStatus {
+    /// Whether the import has finished, successfully or not
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, DownloadState::Complete | DownloadState::Failed)
+    }
 }
 
 /// Volume
@@ -399,6 +1325,9 @@ pub struct SnapshotStatus {
     pub digest: Option<String>,
     pub size_bytes: u64,
     pub encrypted: bool,
+    /// Snapshot the VM's disk was based on when this one was taken
+    #[serde(default)]
+    pub parent_id: Option<String>,
 }
 
 /// Snapshot
@@ -409,6 +1338,560 @@ pub struct Snapshot {
     pub status: SnapshotStatus,
 }
 
+/// Label carrying the ID of the policy that took a snapshot
+pub const SNAPSHOT_POLICY_LABEL: &str = "infrasim.io/snapshot-policy";
+
+/// Scheduled snapshot policy specification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotPolicySpec {
+    /// VM to snapshot; empty when the policy targets `vm_selector`
+    pub vm_id: String,
+    /// Snapshot every VM whose labels match, as of each run
+    #[serde(default)]
+    pub vm_selector: HashMap<String, String>,
+    /// Seconds between snapshots
+    pub interval_secs: u64,
+    /// Number of policy snapshots to retain; older ones are pruned
+    pub keep: u32,
+    #[serde(default)]
+    pub include_memory: bool,
+    /// Stop taking snapshots without deleting the policy
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl SnapshotPolicySpec {
+    /// Whether the policy applies to a VM, directly or through its selector
+    pub fn covers(&self, vm: &ResourceMeta) -> bool {
+        if self.vm_id.is_empty() {
+            !self.vm_selector.is_empty() && vm.matches(&self.vm_selector)
+        } else {
+            self.vm_id == vm.id
+        }
+    }
+
+    /// Human-readable schedule, e.g. `every 6h, keep 10`
+    pub fn schedule(&self) -> String {
+        format!(
+            "every {}, keep {}",
+            format_interval(self.interval_secs),
+            self.keep
+        )
+    }
+}
+
+/// Snapshot policy status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotPolicyStatus {
+    pub last_run_at: Option<i64>,
+    pub last_snapshot_id: Option<String>,
+    pub last_error: Option<String>,
+    /// Policy snapshots currently retained
+    pub retained: u32,
+}
+
+/// Scheduled snapshot policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotPolicy {
+    pub meta: ResourceMeta,
+    pub spec: SnapshotPolicySpec,
+    pub status: SnapshotPolicyStatus,
+}
+
+const INTERVAL_UNITS: [(char, u64); 5] = [
+    ('w', 7 * 24 * 3600),
+    ('d', 24 * 3600),
+    ('h', 3600),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Parse an interval such as `90s`, `30m`, `6h`, `1d` or `2w` into seconds
+pub fn parse_interval(s: &str) -> crate::Result<u64> {
+    let s = s.trim();
+    let invalid = || crate::Error::InvalidConfig(format!("Invalid interval '{}'", s));
+
+    let unit = s.chars().last().ok_or_else(invalid)?;
+    let (digits, multiplier) = match INTERVAL_UNITS.iter().find(|(u, _)| *u == unit) {
+        Some((_, multiplier)) => (&s[..s.len() - 1], *multiplier),
+        None => (s, 1),
+    };
+
+    let value: u64 = digits.trim().parse().map_err(|_| invalid())?;
+    match value.checked_mul(multiplier) {
+        Some(secs) if secs > 0 => Ok(secs),
+        _ => Err(invalid()),
+    }
+}
+
+/// Format seconds using the largest unit that divides them exactly
+pub fn format_interval(secs: u64) -> String {
+    INTERVAL_UNITS
+        .iter()
+        .find(|(_, multiplier)| secs > 0 && secs.is_multiple_of(*multiplier))
+        .map(|(unit, multiplier)| format!("{}{}", secs / multiplier, unit))
+        .unwrap_or_else(|| format!("{}s", secs))
+}
+
+/// Parse a `key=value` label
+pub fn parse_label(s: &str) -> crate::Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(crate::Error::InvalidConfig(format!(
+            "Invalid label '{}', expected key=value",
+            s
+        ))),
+    }
+}
+
+/// Parse a label selector such as `env=ci,tier=web`
+pub fn parse_selector(s: &str) -> crate::Result<HashMap<String, String>> {
+    s.split(',')
+        .filter(|term| !term.trim().is_empty())
+        .map(parse_label)
+        .collect()
+}
+
+/// Format a label selector as `env=ci,tier=web`, sorted by key
+pub fn format_selector(selector: &HashMap<String, String>) -> String {
+    let mut terms: Vec<String> = selector.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    terms.sort();
+    terms.join(",")
+}
+
+/// Label carrying the ID of the policy that took a backup
+pub const BACKUP_POLICY_LABEL: &str = "infrasim.io/backup-policy";
+
+/// Scheme of storage targets in an S3-compatible bucket
+pub const S3_SCHEME: &str = "s3://";
+
+/// Where backups and pushed store objects are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageTarget {
+    /// Local (or mounted) directory
+    Dir(std::path::PathBuf),
+    /// Bucket and key prefix, without trailing slash
+    S3 { bucket: String, prefix: String },
+}
+
+impl StorageTarget {
+    /// Parse an absolute directory path or `s3://bucket[/prefix]`
+    pub fn parse(target: &str) -> crate::Result<Self> {
+        if let Some(rest) = target.strip_prefix(S3_SCHEME) {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            let valid_bucket = (3..=63).contains(&bucket.len())
+                && bucket
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.'));
+            if !valid_bucket {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "Invalid S3 bucket name in {}",
+                    target
+                )));
+            }
+            return Ok(Self::S3 {
+                bucket: bucket.to_string(),
+                prefix: prefix.trim_matches('/').to_string(),
+            });
+        }
+
+        let path = std::path::Path::new(target);
+        if !path.is_absolute() {
+            return Err(crate::Error::InvalidConfig(format!(
+                "Storage target {} must be an absolute path or {}bucket/prefix",
+                target, S3_SCHEME
+            )));
+        }
+        Ok(Self::Dir(path.to_path_buf()))
+    }
+}
+
+/// Scheme of volume sources and references in an OCI registry
+pub const OCI_SCHEME: &str = "oci://";
+
+/// Registry of references without a registry host, like Docker's
+pub const DOCKER_HUB_REGISTRY: &str = "registry-1.docker.io";
+
+/// An artifact in an OCI registry, like `ghcr.io/org/appliance:tag`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OciReference {
+    /// Registry host, with port if any
+    pub registry: String,
+    pub repository: String,
+    /// Tag, or `sha256:<hex>` digest
+    pub reference: String,
+}
+
+impl OciReference {
+    /// Parse `[registry/]repository[:tag|@sha256:digest]`, optionally prefixed with `oci://`
+    ///
+    /// As with Docker, a first component without `.` or `:` that isn't
+    /// `localhost` names a Docker Hub repository, and the tag defaults to `latest`.
+    pub fn parse(s: &str) -> crate::Result<Self> {
+        let invalid = || crate::Error::InvalidConfig(format!("Invalid OCI reference '{}'", s));
+        let rest = s.strip_prefix(OCI_SCHEME).unwrap_or(s);
+
+        let (name, reference) = match rest.split_once('@') {
+            Some((name, digest)) => {
+                let hex = digest.strip_prefix("sha256:").ok_or_else(invalid)?;
+                if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)) {
+                    return Err(invalid());
+                }
+                (name, digest.to_string())
+            }
+            None => match rest.rsplit_once(':') {
+                Some((name, tag)) if !tag.contains('/') => {
+                    let valid_tag = tag.len() <= 128
+                        && !tag.starts_with(['.', '-'])
+                        && tag.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+                    if !valid_tag {
+                        return Err(invalid());
+                    }
+                    (name, tag.to_string())
+                }
+                _ => (rest, "latest".to_string()),
+            },
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((host, repository)) if host.contains(['.', ':']) || host == "localhost" => {
+                (host.to_string(), repository.to_string())
+            }
+            Some(_) => (DOCKER_HUB_REGISTRY.to_string(), name.to_string()),
+            None => (DOCKER_HUB_REGISTRY.to_string(), format!("library/{}", name)),
+        };
+        let valid_repository = repository.split('/').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-'))
+        });
+        if registry.is_empty() || !valid_repository {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            registry,
+            repository,
+            reference,
+        })
+    }
+
+    /// Hex digest, if the reference pins one
+    pub fn digest(&self) -> Option<&str> {
+        self.reference.strip_prefix("sha256:")
+    }
+
+    /// The same repository at another tag or digest
+    pub fn with_reference(&self, reference: impl Into<String>) -> Self {
+        Self {
+            reference: reference.into(),
+            ..self.clone()
+        }
+    }
+}
+
+impl std::fmt::Display for OciReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let separator = if self.digest().is_some() { '@' } else { ':' };
+        write!(f, "{}/{}{}{}", self.registry, self.repository, separator, self.reference)
+    }
+}
+
+/// Backup specification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSpec {
+    pub vm_id: String,
+    /// Directory or `s3://bucket/prefix` the images are written to
+    pub target: String,
+    /// Copy only blocks changed since the VM's previous backup when possible
+    #[serde(default = "default_true")]
+    pub incremental: bool,
+}
+
+/// Whether a backup holds whole disks or changes since its parent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+    #[default]
+    Full,
+    Incremental,
+}
+
+/// A disk image in a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupDisk {
+    pub volume_id: String,
+    pub volume_name: String,
+    #[serde(default)]
+    pub boot: bool,
+    #[serde(default)]
+    pub read_only: bool,
+    /// qcow2 file in the VM's directory of the target; backed by the parent's file
+    pub file: String,
+    /// Active image of the volume when the backup was taken
+    pub source_path: String,
+    /// Virtual size in bytes
+    pub size_bytes: u64,
+    /// Key the image is encrypted with, that of the volume
+    #[serde(default)]
+    pub encryption: Option<VolumeEncryption>,
+}
+
+/// Backup status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupStatus {
+    pub complete: bool,
+    pub kind: BackupKind,
+    /// Backup an incremental one builds on
+    pub parent_id: Option<String>,
+    pub disks: Vec<BackupDisk>,
+    /// VM name and spec when the backup was taken, for restores
+    pub vm_name: String,
+    pub vm_spec: Option<VmSpec>,
+    /// Bytes written to the target
+    pub size_bytes: u64,
+    pub error: Option<String>,
+    pub completed_at: Option<i64>,
+}
+
+/// Point-in-time copy of a VM's disks outside the store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub meta: ResourceMeta,
+    pub spec: BackupSpec,
+    pub status: BackupStatus,
+}
+
+/// Backups to delete so that at least `keep` of `backups` remain
+///
+/// Incremental backups need every backup before them in their chain, so
+/// whole chains are deleted, oldest first, and only while enough backups
+/// are left. Failed backups other than the newest are always deleted.
+pub fn expired_backups(backups: &[Backup], keep: usize) -> Vec<String> {
+    let mut sorted: Vec<&Backup> = backups.iter().collect();
+    sorted.sort_by_key(|b| b.meta.created_at);
+
+    let newest = sorted.last().map(|b| b.meta.id.clone());
+    let (complete, failed): (Vec<&Backup>, Vec<&Backup>) =
+        sorted.into_iter().partition(|b| b.status.complete);
+    let mut expired: Vec<String> = failed
+        .into_iter()
+        .map(|b| b.meta.id.clone())
+        .filter(|id| Some(id) != newest.as_ref())
+        .collect();
+
+    // An incremental backup joins its parent's chain; anything else starts one
+    let mut chains: Vec<Vec<&Backup>> = Vec::new();
+    for backup in complete {
+        let parent = backup.status.parent_id.as_ref();
+        match chains.last_mut() {
+            Some(chain)
+                if backup.status.kind == BackupKind::Incremental
+                    && chain.iter().any(|b| Some(&b.meta.id) == parent) =>
+            {
+                chain.push(backup)
+            }
+            _ => chains.push(vec![backup]),
+        }
+    }
+
+    let mut remaining: usize = chains.iter().map(Vec::len).sum();
+    for chain in chains.iter().take(chains.len().saturating_sub(1)) {
+        if remaining - chain.len() < keep {
+            break;
+        }
+        remaining -= chain.len();
+        expired.extend(chain.iter().map(|b| b.meta.id.clone()));
+    }
+
+    expired
+}
+
+/// Scheduled backup policy specification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupPolicySpec {
+    /// VM to back up; empty when the policy targets `vm_selector`
+    pub vm_id: String,
+    /// Back up every VM whose labels match, as of each run
+    #[serde(default)]
+    pub vm_selector: HashMap<String, String>,
+    /// Directory or `s3://bucket/prefix` the backups are written to
+    pub target: String,
+    /// Seconds between backups
+    pub interval_secs: u64,
+    /// Minimum number of policy backups to retain; older chains are pruned
+    pub keep: u32,
+    /// Take a full backup after this many incremental ones (0: only when needed)
+    #[serde(default)]
+    pub full_every: u32,
+    /// Stop taking backups without deleting the policy
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl BackupPolicySpec {
+    /// Whether the policy applies to a VM, directly or through its selector
+    pub fn covers(&self, vm: &ResourceMeta) -> bool {
+        if self.vm_id.is_empty() {
+            !self.vm_selector.is_empty() && vm.matches(&self.vm_selector)
+        } else {
+            self.vm_id == vm.id
+        }
+    }
+
+    /// Human-readable schedule, e.g. `every 1d, keep 14, full every 7`
+    pub fn schedule(&self) -> String {
+        let mut schedule = format!(
+            "every {}, keep {}",
+            format_interval(self.interval_secs),
+            self.keep
+        );
+        if self.full_every > 0 {
+            schedule.push_str(&format!(", full every {}", self.full_every));
+        }
+        schedule
+    }
+}
+
+/// Backup policy status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupPolicyStatus {
+    pub last_run_at: Option<i64>,
+    pub last_backup_id: Option<String>,
+    pub last_error: Option<String>,
+    /// Policy backups currently retained
+    pub retained: u32,
+}
+
+/// Scheduled backup policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupPolicy {
+    pub meta: ResourceMeta,
+    pub spec: BackupPolicySpec,
+    pub status: BackupPolicyStatus,
+}
+
+/// Operation a background job runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Target: volume ID; params: `remote`
+    PushVolume,
+    /// Target: ID of the pushed volume; params: `remote`, optional `name`
+    PullVolume,
+    /// Target: volume ID
+    FlattenVolume,
+    /// Target: VM ID; params: `reference`
+    PushAppliance,
+    /// Target: appliance reference; params: optional `name`
+    PullAppliance,
+    /// Target: VM ID; params: `target`, optional `name` and `incremental`
+    CreateBackup,
+    /// Target: backup ID; params: optional `name`
+    RestoreBackup,
+    /// Target: VM ID; params: `playbook` or `script`, optional `host`,
+    /// `port`, `user`, `identity` and `timeout_secs`
+    Provision,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PushVolume => "push_volume",
+            Self::PullVolume => "pull_volume",
+            Self::FlattenVolume => "flatten_volume",
+            Self::PushAppliance => "push_appliance",
+            Self::PullAppliance => "pull_appliance",
+            Self::CreateBackup => "create_backup",
+            Self::RestoreBackup => "restore_backup",
+            Self::Provision => "provision",
+        }
+    }
+
+    /// Params a job of this kind can't run without
+    pub fn required_params(&self) -> &'static [&'static str] {
+        match self {
+            Self::PushVolume | Self::PullVolume => &["remote"],
+            Self::PushAppliance => &["reference"],
+            Self::CreateBackup => &["target"],
+            Self::FlattenVolume | Self::PullAppliance | Self::RestoreBackup | Self::Provision => &[],
+        }
+    }
+
+    /// Whether a running job can be stopped without leaving resources half
+    /// changed; other kinds can only be cancelled before they start
+    pub fn cancellable_while_running(&self) -> bool {
+        matches!(self, Self::PushVolume | Self::PullVolume | Self::PushAppliance | Self::Provision)
+    }
+}
+
+/// Background job specification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub kind: JobKind,
+    /// Resource or reference the job works on
+    pub target: String,
+    /// Kind-specific arguments
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+}
+
+/// Job lifecycle state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    #[default]
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// Job status
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub state: JobState,
+    /// Percent complete
+    pub progress: f64,
+    /// What the job is doing
+    #[serde(default)]
+    pub message: String,
+    pub error: Option<String>,
+    /// What the job produced, e.g. `volume_id` or `digest`
+    #[serde(default)]
+    pub result: HashMap<String, String>,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+/// Long-running operation run by the daemon's worker pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub meta: ResourceMeta,
+    pub spec: JobSpec,
+    pub status: JobStatus,
+}
+
 /// Benchmark specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkSpec {
@@ -465,7 +1948,7 @@ pub struct HostProvenance {
     pub qemu_version: String,
     pub qemu_args: Vec<String>,
     pub base_image_hash: String,
-    pub volume_hashes: HashMap<String, String>,
+    pub volume_hashes: BTreeMap<String, String>,
     pub macos_version: String,
     pub cpu_model: String,
     pub hvf_enabled: bool,
@@ -484,6 +1967,47 @@ pub struct AttestationReport {
     pub signature: Vec<u8>,
     pub created_at: i64,
     pub attestation_type: String,
+    /// vTPM quotes recorded for the VM (boot, snapshots)
+    #[serde(default)]
+    pub tpm_quotes: Vec<TpmQuote>,
+}
+
+/// Signed TPM2_Quote over a set of SHA-256 PCRs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TpmQuote {
+    pub vm_id: String,
+    /// Why the quote was taken: "boot", "snapshot:<id>" or "on-demand"
+    pub label: String,
+    /// Quoted PCR values (hex) keyed by PCR index
+    pub pcrs: BTreeMap<u32, String>,
+    /// Marshalled TPMS_ATTEST structure that was signed
+    #[serde(with = "base64_bytes")]
+    pub quoted: Vec<u8>,
+    /// ECDSA P-256 signature as r || s
+    #[serde(with = "base64_bytes")]
+    pub signature: Vec<u8>,
+    /// Attestation key public point (SEC1 uncompressed)
+    #[serde(with = "base64_bytes")]
+    pub ak_public: Vec<u8>,
+    #[serde(with = "base64_bytes")]
+    pub nonce: Vec<u8>,
+    /// PCR extensions observed since the last TPM2_Startup
+    #[serde(default)]
+    pub event_log: Vec<TpmEvent>,
+    pub created_at: i64,
+}
+
+/// A single PCR extension observed on the vTPM command channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TpmEvent {
+    pub pcr_index: u32,
+    /// SHA-256 digest extended into the PCR (hex)
+    pub digest: String,
+    /// TPM command that caused the extension
+    pub command: String,
+    /// Event data for TPM2_PCR_Event, empty otherwise
+    #[serde(default, with = "base64_bytes")]
+    pub data: Vec<u8>,
 }
 
 /// LoRa device specification
@@ -557,3 +2081,395 @@ impl RunManifest {
         Ok(serde_json::to_string(&sorted)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipv4_cidr() {
+        assert_eq!(
+            parse_ipv4_cidr("10.1.2.3/16"),
+            Some(("10.1.0.0".parse().unwrap(), 16))
+        );
+        assert_eq!(parse_ipv4_cidr("10.1.2.3"), Some(("10.1.2.3".parse().unwrap(), 32)));
+        assert_eq!(parse_ipv4_cidr("0.0.0.0/0"), Some(("0.0.0.0".parse().unwrap(), 0)));
+        assert_eq!(parse_ipv4_cidr("10.1.2.3/33"), None);
+        assert_eq!(parse_ipv4_cidr("fd00::/8"), None);
+
+        assert!(ipv4_cidr_contains("10.1.0.0/24", "10.1.0.9".parse().unwrap()));
+        assert!(!ipv4_cidr_contains("10.1.0.0/24", "10.1.1.9".parse().unwrap()));
+        assert!(ipv4_cidr_contains("0.0.0.0/0", "192.168.1.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_unicast_mac() {
+        assert_eq!(parse_unicast_mac("52:54:0:AB:c:1").as_deref(), Some("52:54:00:ab:0c:01"));
+        assert!(parse_unicast_mac("01:00:5e:00:00:01").is_none());
+        assert!(parse_unicast_mac("00:00:00:00:00:00").is_none());
+        assert!(parse_unicast_mac("52:54:00:ab:cd").is_none());
+        assert!(parse_unicast_mac("52:54:00:ab:cd:123").is_none());
+    }
+
+    #[test]
+    fn test_validate_network_interfaces() {
+        let mut spec = VmSpec {
+            network_ids: vec!["lan".to_string()],
+            network_interfaces: vec![NetworkAttachment {
+                network_id: "lan".to_string(),
+                mac: Some("52:54:00:12:34:56".to_string()),
+                ip: Some("192.168.100.20".to_string()),
+            }],
+            ..Default::default()
+        };
+        assert!(spec.validate_network_interfaces().is_ok());
+
+        spec.network_interfaces[0].ip = Some("192.168.100".to_string());
+        assert!(spec.validate_network_interfaces().is_err());
+        spec.network_interfaces[0].ip = None;
+
+        spec.network_interfaces.push(spec.network_interfaces[0].clone());
+        assert!(spec.validate_network_interfaces().is_err());
+
+        spec.network_interfaces.truncate(1);
+        spec.network_interfaces[0].network_id = "wan".to_string();
+        assert!(spec.validate_network_interfaces().is_err());
+    }
+
+    #[test]
+    fn test_shared_folder_default_tag() {
+        assert_eq!(SharedFolder::default_tag("/mnt/code"), "mnt-code");
+        assert_eq!(SharedFolder::default_tag("/home/dev/my.project/"), "home-dev-my-project");
+        assert_eq!(SharedFolder::default_tag("/"), "root");
+        assert_eq!(SharedFolder::default_tag(&"/a".repeat(40)).len(), SHARED_FOLDER_TAG_MAX);
+    }
+
+    #[test]
+    fn test_validate_shared_folders() {
+        let folder = SharedFolder {
+            tag: "code".to_string(),
+            host_path: "/Users/dev/code".to_string(),
+            guest_path: "/mnt/code".to_string(),
+            ..Default::default()
+        };
+        let mut spec = VmSpec {
+            shared_folders: vec![folder.clone()],
+            ..Default::default()
+        };
+        assert!(spec.validate_shared_folders().is_ok());
+
+        spec.shared_folders[0].host_path = "code".to_string();
+        assert!(spec.validate_shared_folders().is_err());
+        spec.shared_folders[0].host_path = folder.host_path.clone();
+
+        spec.shared_folders[0].tag = "my code".to_string();
+        assert!(spec.validate_shared_folders().is_err());
+        spec.shared_folders[0].tag = folder.tag.clone();
+
+        spec.shared_folders.push(SharedFolder {
+            guest_path: "/mnt/other".to_string(),
+            ..folder.clone()
+        });
+        assert!(spec.validate_shared_folders().is_err());
+
+        spec.shared_folders[1] = SharedFolder {
+            tag: "other".to_string(),
+            guest_path: "/mnt/code/".to_string(),
+            ..folder
+        };
+        assert!(spec.validate_shared_folders().is_err());
+    }
+
+    #[test]
+    fn test_router_spec_validate() {
+        let spec = RouterSpec {
+            network_ids: vec!["dmz".to_string(), "internal".to_string()],
+            routes: vec![StaticRoute {
+                destination: "10.9.0.0/16".to_string(),
+                next_hop: "10.1.0.5".to_string(),
+            }],
+            rules: vec![FirewallRule {
+                action: FirewallAction::Allow,
+                protocol: RuleProtocol::Tcp,
+                source: Some("10.1.0.0/24".to_string()),
+                destination: Some("10.2.0.0/24".to_string()),
+                port: Some(443),
+            }],
+            default_action: FirewallAction::Deny,
+        };
+        assert!(spec.validate().is_ok());
+        assert_eq!(
+            spec.rules[0].iptables_args().join(" "),
+            "-s 10.1.0.0/24 -d 10.2.0.0/24 -p tcp --dport 443 -j ACCEPT"
+        );
+
+        let mut one_network = spec.clone();
+        one_network.network_ids.truncate(1);
+        assert!(one_network.validate().is_err());
+
+        let mut twice = spec.clone();
+        twice.network_ids.push("dmz".to_string());
+        assert!(twice.validate().is_err());
+
+        let mut bad_hop = spec.clone();
+        bad_hop.routes[0].next_hop = "gateway".to_string();
+        assert!(bad_hop.validate().is_err());
+
+        let mut icmp_port = spec.clone();
+        icmp_port.rules[0].protocol = RuleProtocol::Icmp;
+        assert!(icmp_port.validate().is_err());
+    }
+
+    #[test]
+    fn test_label_selector_matches() {
+        let meta = ResourceMeta::new("web-1".to_string()).with_labels(HashMap::from([
+            ("env".to_string(), "ci".to_string()),
+            ("tier".to_string(), "web".to_string()),
+        ]));
+        let selector = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+
+        assert!(meta.matches(&selector(&[])));
+        assert!(meta.matches(&selector(&[("env", "ci")])));
+        assert!(meta.matches(&selector(&[("env", "ci"), ("tier", "web")])));
+        assert!(!meta.matches(&selector(&[("env", "prod")])));
+        assert!(!meta.matches(&selector(&[("env", "ci"), ("team", "infra")])));
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let selector = parse_selector("env=ci, tier=web").unwrap();
+        assert_eq!(selector.len(), 2);
+        assert_eq!(selector["env"], "ci");
+        assert_eq!(selector["tier"], "web");
+        assert_eq!(format_selector(&selector), "env=ci,tier=web");
+
+        assert_eq!(parse_label("empty=").unwrap(), ("empty".to_string(), String::new()));
+        assert!(parse_selector("").unwrap().is_empty());
+        assert!(parse_selector("env").is_err());
+        assert!(parse_selector("=ci").is_err());
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90s").unwrap(), 90);
+        assert_eq!(parse_interval("30m").unwrap(), 1800);
+        assert_eq!(parse_interval("6h").unwrap(), 6 * 3600);
+        assert_eq!(parse_interval("1d").unwrap(), 24 * 3600);
+        assert_eq!(parse_interval("2w").unwrap(), 14 * 24 * 3600);
+        assert_eq!(parse_interval("120").unwrap(), 120);
+
+        assert!(parse_interval("").is_err());
+        assert!(parse_interval("0h").is_err());
+        assert!(parse_interval("6x").is_err());
+        assert!(parse_interval("h").is_err());
+    }
+
+    #[test]
+    fn test_chaos_profile_spec_validate() {
+        let mut spec = ChaosProfileSpec::default();
+        assert!(spec.validate().is_err());
+
+        spec.network_id = Some("net".to_string());
+        spec.faults.loss_percent = 5.0;
+        assert!(spec.validate().is_ok());
+
+        spec.faults.loss_percent = 150.0;
+        assert!(spec.validate().is_err());
+        spec.faults.loss_percent = 0.0;
+
+        spec.partitions = vec![vec!["a".to_string()]];
+        assert!(spec.validate().is_err());
+        spec.partitions = vec![vec!["a".to_string()], vec!["b".to_string(), "a".to_string()]];
+        assert!(spec.validate().is_err());
+        spec.partitions = vec![vec!["a".to_string()], vec!["b".to_string(), "c".to_string()]];
+        assert!(spec.validate().is_ok());
+        assert_eq!(spec.partition_of("c"), Some(1));
+        assert_eq!(spec.partition_of("d"), None);
+    }
+
+    #[test]
+    fn test_volume_encryption_validate() {
+        let mut enc = VolumeEncryption {
+            cipher: "aes-256".to_string(),
+            key_id: "db-data.v2".to_string(),
+        };
+        assert!(enc.validate().is_ok());
+
+        enc.cipher = "des".to_string();
+        assert!(enc.validate().is_err());
+        enc.cipher = "twofish-256".to_string();
+        for key_id in ["", ".hidden", "../escape", "a b"] {
+            enc.key_id = key_id.to_string();
+            assert!(enc.validate().is_err(), "{:?} accepted", key_id);
+        }
+
+        let parsed: VolumeEncryption = serde_json::from_str(r#"{"key_id":"k"}"#).unwrap();
+        assert_eq!(parsed.cipher, "aes-256");
+    }
+
+    #[test]
+    fn test_snapshot_policy_schedule() {
+        let spec = SnapshotPolicySpec {
+            vm_id: "vm".to_string(),
+            vm_selector: HashMap::new(),
+            interval_secs: 6 * 3600,
+            keep: 10,
+            include_memory: false,
+            paused: false,
+        };
+        assert_eq!(spec.schedule(), "every 6h, keep 10");
+        assert_eq!(format_interval(90), "90s");
+        assert_eq!(format_interval(7 * 24 * 3600), "1w");
+    }
+
+    #[test]
+    fn test_storage_target_parse() {
+        assert_eq!(
+            StorageTarget::parse("/mnt/backups").unwrap(),
+            StorageTarget::Dir("/mnt/backups".into())
+        );
+        assert_eq!(
+            StorageTarget::parse("s3://vm-backups/lab/daily/").unwrap(),
+            StorageTarget::S3 {
+                bucket: "vm-backups".to_string(),
+                prefix: "lab/daily".to_string(),
+            }
+        );
+        assert_eq!(
+            StorageTarget::parse("s3://vm-backups").unwrap(),
+            StorageTarget::S3 {
+                bucket: "vm-backups".to_string(),
+                prefix: String::new(),
+            }
+        );
+        assert!(StorageTarget::parse("backups").is_err());
+        assert!(StorageTarget::parse("s3://Bad_Bucket/x").is_err());
+    }
+
+    #[test]
+    fn test_oci_reference_parse() {
+        let reference = OciReference::parse("ghcr.io/org/appliance:v1").unwrap();
+        assert_eq!(reference.registry, "ghcr.io");
+        assert_eq!(reference.repository, "org/appliance");
+        assert_eq!(reference.reference, "v1");
+        assert_eq!(reference.to_string(), "ghcr.io/org/appliance:v1");
+
+        let reference = OciReference::parse("oci://localhost:5000/appliance").unwrap();
+        assert_eq!(reference.registry, "localhost:5000");
+        assert_eq!(reference.reference, "latest");
+
+        let digest = format!("sha256:{}", "ab".repeat(32));
+        let reference = OciReference::parse(&format!("ghcr.io/org/disk@{}", digest)).unwrap();
+        assert_eq!(reference.digest(), Some("ab".repeat(32).as_str()));
+        assert_eq!(reference.to_string(), format!("ghcr.io/org/disk@{}", digest));
+
+        let reference = OciReference::parse("alpine").unwrap();
+        assert_eq!(reference.registry, DOCKER_HUB_REGISTRY);
+        assert_eq!(reference.repository, "library/alpine");
+        assert_eq!(OciReference::parse("org/alpine:3").unwrap().repository, "org/alpine");
+
+        assert!(OciReference::parse("ghcr.io/Org/appliance").is_err());
+        assert!(OciReference::parse("ghcr.io/org/appliance@sha256:abc").is_err());
+        assert!(OciReference::parse("ghcr.io/org/appliance:-bad").is_err());
+        assert!(OciReference::parse("ghcr.io//appliance").is_err());
+    }
+
+    #[test]
+    fn test_job_spec_serde() {
+        let spec: JobSpec =
+            serde_json::from_str(r#"{"kind":"push_volume","target":"vol-1","params":{"remote":"/mnt"}}"#).unwrap();
+        assert_eq!(spec.kind, JobKind::PushVolume);
+        assert_eq!(spec.kind.as_str(), "push_volume");
+        assert_eq!(spec.kind.required_params(), ["remote"]);
+
+        let spec: JobSpec = serde_json::from_str(r#"{"kind":"flatten_volume","target":"vol-1"}"#).unwrap();
+        assert!(spec.params.is_empty());
+        assert!(!spec.kind.cancellable_while_running());
+
+        let status = JobStatus::default();
+        assert_eq!(status.state, JobState::Pending);
+        assert!(!status.state.is_finished());
+        assert!(JobState::Cancelled.is_finished());
+    }
+
+    #[test]
+    fn test_expired_backups() {
+        let backup = |id: &str, at: i64, parent: Option<&str>, complete: bool| Backup {
+            meta: ResourceMeta {
+                id: id.to_string(),
+                created_at: at,
+                ..ResourceMeta::new(id.to_string())
+            },
+            spec: BackupSpec {
+                vm_id: "vm".to_string(),
+                target: "/backups".to_string(),
+                incremental: true,
+            },
+            status: BackupStatus {
+                complete,
+                kind: match parent {
+                    Some(_) => BackupKind::Incremental,
+                    None => BackupKind::Full,
+                },
+                parent_id: parent.map(str::to_string),
+                ..Default::default()
+            },
+        };
+        let backups = vec![
+            backup("f1", 1, None, true),
+            backup("i1", 2, Some("f1"), true),
+            backup("x1", 3, Some("i1"), false),
+            backup("f2", 4, None, true),
+            backup("i2", 5, Some("f2"), true),
+            backup("i3", 6, Some("i2"), true),
+        ];
+
+        // The first chain only goes once the second one covers `keep`
+        assert_eq!(expired_backups(&backups, 3), vec!["x1", "f1", "i1"]);
+        assert_eq!(expired_backups(&backups, 4), vec!["x1"]);
+        // The newest chain is never pruned
+        assert_eq!(expired_backups(&backups, 0), vec!["x1", "f1", "i1"]);
+
+        // A failed newest backup is kept so the next one starts a new chain
+        let mut failed = backups.clone();
+        failed.push(backup("x2", 7, Some("i3"), false));
+        assert_eq!(expired_backups(&failed, 5), vec!["x1"]);
+    }
+
+    #[test]
+    fn test_device_qemu_properties() {
+        let mut spec = DeviceSpec {
+            vm_id: "vm".to_string(),
+            bus: DeviceBus::Usb,
+            vendor_id: Some(0x046d),
+            product_id: Some(0xc52b),
+            host_address: None,
+        };
+        let props = spec.qemu_properties().unwrap();
+        assert_eq!(props[0], ("vendorid", serde_json::json!(0x046d)));
+        assert_eq!(spec.describe(), "usb 046d:c52b");
+
+        spec.vendor_id = None;
+        spec.product_id = None;
+        spec.host_address = Some("1.4".to_string());
+        let device = Device {
+            meta: ResourceMeta::new("dongle".to_string()),
+            spec: spec.clone(),
+            status: DeviceStatus::default(),
+        };
+        assert!(device.qemu_arg().unwrap().ends_with(",hostbus=1,hostaddr=4"));
+
+        spec.host_address = Some("1-4".to_string());
+        assert!(spec.qemu_properties().is_err());
+
+        spec.bus = DeviceBus::Pci;
+        spec.host_address = Some("0000:01:00.0".to_string());
+        assert_eq!(spec.qemu_properties().unwrap(), vec![("host", serde_json::json!("0000:01:00.0"))]);
+        spec.host_address = Some("01:00.0".to_string());
+        assert!(spec.qemu_properties().is_err());
+        spec.host_address = Some("0000:01:00.8".to_string());
+        assert!(spec.qemu_properties().is_err());
+    }
+}
@@ -9,7 +9,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         
         tonic_build::configure()
             .build_server(true)
-            .build_client(false)
+            .build_client(true)
             .out_dir("src/generated")
             .compile(&[proto_file], &[proto_dir])?;
     } else {
@@ -22,7 +22,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             
             tonic_build::configure()
                 .build_server(true)
-                .build_client(false)
+                .build_client(true)
                 .out_dir("src/generated")
                 .compile(&[alt_proto], &["proto"])?;
         } else {
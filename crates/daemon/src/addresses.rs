@@ -0,0 +1,202 @@
+//! Guest addresses of VM NICs
+//!
+//! QEMU doesn't know which addresses its guests use, so they are looked up
+//! where they are handed out, or where the host has seen them:
+//!
+//! - User-mode NICs each get their own slirp DHCP server, whose only lease is
+//!   `10.0.2.15` or the address pinned for the NIC.
+//! - Shared networks: the leases of the network's dnsmasq on Linux, or of the
+//!   system `bootpd` on macOS.
+//! - Anything else, like bridged networks or guests with static addresses:
+//!   the host's neighbour table, which only holds guests that talked to the
+//!   host recently.
+
+use crate::config::DaemonConfig;
+use crate::hostnet;
+use crate::qemu;
+use infrasim_common::types::{ipv4_cidr_contains, Network, NetworkMode, Vm, VmState};
+use std::net::Ipv4Addr;
+
+/// Leases of vmnet-shared networks on macOS
+#[cfg(target_os = "macos")]
+const BOOTPD_LEASES: &str = "/var/db/dhcpd_leases";
+
+/// Addressing of one VM NIC
+#[derive(Debug, Clone)]
+pub struct NicAddress {
+    pub index: usize,
+    /// None for the default user-mode NIC of a VM without networks
+    pub network_id: Option<String>,
+    pub mac: String,
+    pub ip: Option<Ipv4Addr>,
+    /// Where the address came from: "user-mode", "dhcp" or "neighbor"
+    pub source: &'static str,
+}
+
+/// Addresses of each NIC of `vm`, in the order QEMU gets them
+///
+/// `networks` are the VM's existing networks in `network_ids` order. Only
+/// running VMs have IP addresses; the MACs are known up front.
+pub fn vm_addresses(config: &DaemonConfig, vm: &Vm, networks: &[Network]) -> Vec<NicAddress> {
+    let running = matches!(vm.status.state, VmState::Running | VmState::Paused);
+    let neighbors = if running {
+        neighbor_table()
+    } else {
+        Vec::new()
+    };
+
+    (0..networks.len().max(1))
+        .map(|index| {
+            let network = networks.get(index);
+            let mac = qemu::nic_mac(vm, index, network);
+            let (ip, source) = if !running {
+                (None, "")
+            } else if uses_user_mode(config, network) {
+                let ip = hostnet::user_mode_ip(vm, network).unwrap_or(hostnet::USER_MODE_ADDR);
+                (Some(ip), "user-mode")
+            } else if let Some(ip) = network.and_then(|n| lease(config, n, &mac)) {
+                (Some(ip), "dhcp")
+            } else {
+                let ip = neighbors
+                    .iter()
+                    .find(|(_, m)| *m == mac)
+                    .map(|(ip, _)| *ip)
+                    .filter(|ip| network.map_or(true, |n| ipv4_cidr_contains(&n.spec.cidr, *ip)));
+                (ip, if ip.is_some() { "neighbor" } else { "" })
+            };
+            NicAddress {
+                index,
+                network_id: network.map(|n| n.meta.id.clone()),
+                mac,
+                ip,
+                source,
+            }
+        })
+        .collect()
+}
+
+/// Whether the NIC ends up on user-mode networking (see [`crate::hostnet::netdev`])
+fn uses_user_mode(config: &DaemonConfig, network: Option<&Network>) -> bool {
+    match network {
+        Some(network) if network.spec.mode != NetworkMode::User => {
+            !config.network.get().enable_vmnet
+                || cfg!(not(any(target_os = "linux", target_os = "macos")))
+        }
+        _ => true,
+    }
+}
+
+/// DHCP lease of `mac` on a shared network
+fn lease(config: &DaemonConfig, network: &Network, mac: &str) -> Option<Ipv4Addr> {
+    if network.spec.mode != NetworkMode::VmnetShared {
+        return None;
+    }
+    shared_lease(config, network, mac)
+}
+
+#[cfg(target_os = "linux")]
+fn shared_lease(config: &DaemonConfig, network: &Network, mac: &str) -> Option<Ipv4Addr> {
+    let path = config.network_dir(&network.meta.id).join("dnsmasq.leases");
+    let leases = std::fs::read_to_string(path).ok()?;
+    parse_dnsmasq_leases(&leases)
+        .into_iter()
+        .find(|(_, m)| m == mac)
+        .map(|(ip, _)| ip)
+}
+
+#[cfg(target_os = "macos")]
+fn shared_lease(_config: &DaemonConfig, network: &Network, mac: &str) -> Option<Ipv4Addr> {
+    // bootpd serves every vmnet-shared network, so keep to this one's CIDR
+    let leases = std::fs::read_to_string(BOOTPD_LEASES).ok()?;
+    parse_bootpd_leases(&leases)
+        .into_iter()
+        .find(|(ip, m)| m == mac && ipv4_cidr_contains(&network.spec.cidr, *ip))
+        .map(|(ip, _)| ip)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn shared_lease(_config: &DaemonConfig, _network: &Network, _mac: &str) -> Option<Ipv4Addr> {
+    None
+}
+
+/// Parse a dnsmasq lease file: `<expiry> <mac> <ip> <hostname> <client-id>`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_dnsmasq_leases(leases: &str) -> Vec<(Ipv4Addr, String)> {
+    leases
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            let mac = normalize_mac(fields.next()?)?;
+            let ip = fields.next()?.parse().ok()?;
+            Some((ip, mac))
+        })
+        .collect()
+}
+
+/// Parse macOS bootpd leases: `{ ... ip_address=<ip> hw_address=1,<mac> ... }`
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_bootpd_leases(leases: &str) -> Vec<(Ipv4Addr, String)> {
+    leases
+        .split('}')
+        .filter_map(|entry| {
+            let mut ip = None;
+            let mut mac = None;
+            for line in entry.lines() {
+                match line.trim().split_once('=') {
+                    Some(("ip_address", value)) => ip = value.parse().ok(),
+                    Some(("hw_address", value)) => {
+                        mac = value.split_once(',').and_then(|(_, m)| normalize_mac(m))
+                    }
+                    _ => {}
+                }
+            }
+            Some((ip?, mac?))
+        })
+        .collect()
+}
+
+/// Neighbour table entries of the host with a resolved MAC
+#[cfg(target_os = "linux")]
+fn neighbor_table() -> Vec<(Ipv4Addr, String)> {
+    // IP address  HW type  Flags  HW address  Mask  Device
+    std::fs::read_to_string("/proc/net/arp")
+        .map(|table| {
+            table
+                .lines()
+                .skip(1)
+                .filter_map(|line| {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    let ip = fields.first()?.parse().ok()?;
+                    // Flags 0x0 are incomplete entries
+                    if *fields.get(2)? == "0x0" {
+                        return None;
+                    }
+                    Some((ip, normalize_mac(fields.get(3)?)?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn neighbor_table() -> Vec<(Ipv4Addr, String)> {
+    Vec::new()
+}
+
+/// Lowercase a MAC with two digits per octet (bootpd drops leading zeros)
+fn normalize_mac(mac: &str) -> Option<String> {
+    let octets: Vec<u8> = mac
+        .split(':')
+        .map(|octet| u8::from_str_radix(octet, 16).ok())
+        .collect::<Option<_>>()?;
+    if octets.len() != 6 {
+        return None;
+    }
+    Some(
+        octets
+            .iter()
+            .map(|o| format!("{:02x}", o))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
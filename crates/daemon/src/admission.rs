@@ -0,0 +1,184 @@
+//! Host resource admission control
+//!
+//! Memory and vCPUs of running and paused VMs count as committed to them. A
+//! VM only starts while its share still fits the host's capacity times the
+//! overcommit ratios in [`AdmissionConfig`], and while the store keeps some
+//! free disk for overlays, snapshots and suspend images.
+
+use crate::config::AdmissionConfig;
+use infrasim_common::attestation::{free_disk_bytes, host_memory_bytes};
+use infrasim_common::types::{Vm, VmSpec, VmState};
+use infrasim_common::{Error, Result};
+use std::path::Path;
+
+const MB: u64 = 1024 * 1024;
+
+/// What the host has to give
+#[derive(Debug, Clone, Copy)]
+pub struct HostCapacity {
+    /// Physical memory, 0 if unknown
+    pub memory_mb: u64,
+    pub cpus: u32,
+    /// Free space on the store's filesystem, if known
+    pub free_disk_mb: Option<u64>,
+}
+
+impl HostCapacity {
+    pub fn probe(store_path: &Path) -> Self {
+        Self {
+            memory_mb: host_memory_bytes() / MB,
+            cpus: std::thread::available_parallelism().map_or(1, |n| n.get() as u32),
+            free_disk_mb: free_disk_bytes(store_path).map(|b| b / MB),
+        }
+    }
+}
+
+/// Resources held by VMs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Committed {
+    pub memory_mb: u64,
+    pub cpu_cores: u32,
+}
+
+impl Committed {
+    /// Resources held by the running and paused VMs other than `except`
+    pub fn of(vms: &[Vm], except: &str) -> Self {
+        vms.iter()
+            .filter(|vm| vm.meta.id != except && holds_resources(vm))
+            .fold(Self::default(), |c, vm| Self {
+                memory_mb: c.memory_mb + vm.spec.memory_mb,
+                cpu_cores: c.cpu_cores + vm.spec.cpu_cores,
+            })
+    }
+}
+
+/// Whether a VM's QEMU holds host memory and CPUs; suspended VMs saved
+/// their RAM to disk
+pub fn holds_resources(vm: &Vm) -> bool {
+    !vm.spec.template && matches!(vm.status.state, VmState::Running | VmState::Paused)
+}
+
+/// Check that a VM could run at all, alone on the host
+pub fn check_create(config: &AdmissionConfig, host: &HostCapacity, spec: &VmSpec) -> Result<()> {
+    check_start(config, host, Committed::default(), spec)
+}
+
+/// Check that a VM fits next to the resources already committed
+pub fn check_start(
+    config: &AdmissionConfig,
+    host: &HostCapacity,
+    committed: Committed,
+    spec: &VmSpec,
+) -> Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+
+    if host.memory_mb > 0 {
+        let budget = ((host.memory_mb as f64 * config.memory_overcommit_ratio) as u64)
+            .saturating_sub(config.reserved_memory_mb);
+        if committed.memory_mb + spec.memory_mb > budget {
+            return Err(Error::InsufficientResources(format!(
+                "VM needs {} MB of memory but {} of {} MB are already committed \
+                 (host {} MB, admission.memory_overcommit_ratio {}, admission.reserved_memory_mb {})",
+                spec.memory_mb,
+                committed.memory_mb,
+                budget,
+                host.memory_mb,
+                config.memory_overcommit_ratio,
+                config.reserved_memory_mb
+            )));
+        }
+    }
+
+    let budget = (host.cpus as f64 * config.cpu_overcommit_ratio) as u32;
+    if committed.cpu_cores + spec.cpu_cores > budget {
+        return Err(Error::InsufficientResources(format!(
+            "VM needs {} vCPUs but {} of {} are already committed \
+             (host {} CPUs, admission.cpu_overcommit_ratio {})",
+            spec.cpu_cores, committed.cpu_cores, budget, host.cpus, config.cpu_overcommit_ratio
+        )));
+    }
+
+    if let Some(free) = host.free_disk_mb.filter(|free| *free < config.min_free_disk_mb) {
+        return Err(Error::InsufficientResources(format!(
+            "only {} MB free in the store, below admission.min_free_disk_mb ({})",
+            free, config.min_free_disk_mb
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infrasim_common::types::{ResourceMeta, VmStatus};
+
+    fn vm(name: &str, state: VmState, memory_mb: u64, cpu_cores: u32) -> Vm {
+        Vm {
+            meta: ResourceMeta::new(name.to_string()),
+            spec: VmSpec {
+                memory_mb,
+                cpu_cores,
+                ..Default::default()
+            },
+            status: VmStatus {
+                state,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn host() -> HostCapacity {
+        HostCapacity {
+            memory_mb: 16384,
+            cpus: 8,
+            free_disk_mb: Some(100_000),
+        }
+    }
+
+    #[test]
+    fn test_committed_counts_vms_holding_memory() {
+        let vms = vec![
+            vm("running", VmState::Running, 4096, 2),
+            vm("paused", VmState::Paused, 2048, 1),
+            vm("suspended", VmState::Suspended, 8192, 4),
+            vm("stopped", VmState::Stopped, 8192, 4),
+        ];
+        assert_eq!(
+            Committed::of(&vms, ""),
+            Committed { memory_mb: 6144, cpu_cores: 3 }
+        );
+        assert_eq!(
+            Committed::of(&vms, &vms[0].meta.id),
+            Committed { memory_mb: 2048, cpu_cores: 1 }
+        );
+    }
+
+    #[test]
+    fn test_check_start() {
+        let config = AdmissionConfig::default();
+        let spec = vm("new", VmState::Stopped, 4096, 2).spec;
+
+        // 16384 MB host with 2048 MB reserved leaves 14336 MB
+        let committed = Committed { memory_mb: 10240, cpu_cores: 4 };
+        assert!(check_start(&config, &host(), committed, &spec).is_ok());
+
+        let committed = Committed { memory_mb: 10241, cpu_cores: 4 };
+        assert!(matches!(
+            check_start(&config, &host(), committed, &spec),
+            Err(Error::InsufficientResources(_))
+        ));
+
+        // 8 CPUs at 4x overcommit
+        let committed = Committed { memory_mb: 0, cpu_cores: 31 };
+        assert!(check_start(&config, &host(), committed, &spec).is_err());
+
+        let full_disk = HostCapacity { free_disk_mb: Some(100), ..host() };
+        assert!(check_create(&config, &full_disk, &spec).is_err());
+
+        let disabled = AdmissionConfig { enabled: false, ..Default::default() };
+        assert!(check_start(&disabled, &full_disk, committed, &spec).is_ok());
+    }
+}
@@ -0,0 +1,367 @@
+//! Appliances published as OCI artifacts
+//!
+//! `push` publishes a stopped VM to a registry as an artifact of type
+//! [`ARTIFACT_TYPE`]:
+//!
+//! - the config blob is the VM's export manifest (`infrasim.json`)
+//! - one layer per disk, a compressed and flattened qcow2 image
+//! - a Terraform layer declaring the appliance with the InfraSim provider
+//! - a provenance layer: the config and Terraform digests plus an attestation
+//!   report, signed with the daemon's key
+//!
+//! `pull` checks the provenance signature and every digest before creating a
+//! stopped VM whose disks are overlays on the layers in the content-addressed
+//! store. Layers are addressed by digest, so pushing a new tag only uploads
+//! the disks that changed.
+
+use crate::export::{self, Manifest, ManifestDisk};
+use crate::registry::{Descriptor, OciManifest, RegistryClient, OCI_MANIFEST_MEDIA_TYPE, TITLE_ANNOTATION};
+use crate::state::StateManager;
+use infrasim_common::attestation::AttestationProvider;
+use infrasim_common::crypto::{self, SignedData};
+use infrasim_common::{types::*, ContentAddressedStore, Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tokio::fs;
+use tracing::info;
+
+/// Artifact type of appliance manifests
+pub const ARTIFACT_TYPE: &str = "application/vnd.infrasim.appliance.v1+json";
+
+const CONFIG_MEDIA_TYPE: &str = "application/vnd.infrasim.appliance.config.v1+json";
+const DISK_MEDIA_TYPE: &str = "application/vnd.infrasim.disk.v1.qcow2";
+const TERRAFORM_MEDIA_TYPE: &str = "application/vnd.infrasim.terraform.v1.hcl";
+const PROVENANCE_MEDIA_TYPE: &str = "application/vnd.infrasim.provenance.v1+json";
+
+/// Largest config, Terraform or provenance blob read into memory
+const MAX_METADATA_BLOB: u64 = 4 * 1024 * 1024;
+
+/// Signed statement of where an appliance came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Provenance {
+    /// Reference the appliance was pushed as
+    reference: String,
+    /// Digest of the config blob, which names every disk layer
+    config_digest: String,
+    terraform_digest: String,
+    pushed_at: i64,
+    /// Attestation of the pushing host, with the pushed disk digests
+    attestation: AttestationReport,
+}
+
+/// Result of pushing an appliance
+pub struct Pushed {
+    /// Digest-pinned reference of the pushed manifest
+    pub reference: String,
+    pub digest: String,
+    pub disks: usize,
+    /// Disk layers the registry didn't have yet
+    pub uploaded: usize,
+    pub signer_key_id: String,
+}
+
+/// Result of pulling an appliance
+pub struct Pulled {
+    pub vm: Vm,
+    pub volumes: Vec<Volume>,
+    pub digest: String,
+    pub signer_key_id: String,
+    pub terraform_hcl: String,
+}
+
+/// Publish a stopped VM and its disks as an appliance artifact under a tag
+pub async fn push(state: &StateManager, vm: &Vm, reference: &str) -> Result<Pushed> {
+    let reference = OciReference::parse(reference)?;
+    if reference.digest().is_some() {
+        return Err(Error::InvalidConfig(format!(
+            "Push {} to a tag, not a digest",
+            reference
+        )));
+    }
+    export::check_exportable(state, vm)?;
+    let disks = export::export_disks(state, vm)?;
+    let registry = RegistryClient::new(state.config(), &reference)?;
+    let cas = state.cas();
+
+    // Converting flattens snapshot chains; images stay in the store for later pushes
+    let stem = export::file_stem(&vm.meta.name);
+    let mut manifest_disks = Vec::new();
+    let mut layers = Vec::new();
+    let mut attested = Vec::new();
+    for (index, disk) in disks.iter().enumerate() {
+        let image = cas.tmp_dir().join(format!("{}.qcow2", uuid::Uuid::new_v4()));
+        let source_format = Some(disk.volume.spec.format.as_str()).filter(|f| !f.is_empty());
+        let result = export::convert(&disk.path, source_format, &image, "qcow2", &["-c"]).await;
+        if let Err(e) = result {
+            let _ = fs::remove_file(&image).await;
+            return Err(e);
+        }
+        let digest = ContentAddressedStore::hash_file(&image).await?;
+        let path = cas.adopt_file(&image, &digest).await?;
+
+        let file = format!("{}-disk{}.qcow2", stem, index);
+        let size = fs::metadata(&path).await?.len();
+        layers.push(Descriptor::new(DISK_MEDIA_TYPE, &digest, size).titled(&file));
+        manifest_disks.push(ManifestDisk {
+            file,
+            format: "qcow2".to_string(),
+            kind: disk.volume.spec.kind,
+            read_only: disk.volume.spec.read_only,
+            boot: disk.boot,
+        });
+
+        let mut volume = disk.volume.clone();
+        volume.status.digest = Some(digest);
+        attested.push(volume);
+    }
+
+    let mut uploaded = 0;
+    for layer in &layers {
+        if cas.push(layer.hex_digest()?, &registry).await? {
+            uploaded += 1;
+        }
+    }
+
+    let config = serde_json::to_vec_pretty(&Manifest::new(vm, manifest_disks))?;
+    let config = put_blob(&registry, CONFIG_MEDIA_TYPE, config).await?;
+    let terraform = terraform_hcl(vm, &reference, &layers, &disks).into_bytes();
+    let terraform = put_blob(&registry, TERRAFORM_MEDIA_TYPE, terraform).await?;
+
+    let key_pair = state.key_pair();
+    let attestation = AttestationProvider::new(key_pair.clone()).generate_report(vm, &attested, &[])?;
+    let provenance = SignedData::new(
+        Provenance {
+            reference: reference.to_string(),
+            config_digest: config.hex_digest()?.to_string(),
+            terraform_digest: terraform.hex_digest()?.to_string(),
+            pushed_at: chrono::Utc::now().timestamp(),
+            attestation,
+        },
+        key_pair,
+    )?;
+    let provenance = serde_json::to_vec_pretty(&provenance)?;
+    let provenance = put_blob(&registry, PROVENANCE_MEDIA_TYPE, provenance).await?;
+
+    let disk_count = layers.len();
+    layers.push(terraform.titled("main.tf"));
+    layers.push(provenance.titled("provenance.json"));
+    let manifest = OciManifest {
+        schema_version: 2,
+        media_type: OCI_MANIFEST_MEDIA_TYPE.to_string(),
+        artifact_type: Some(ARTIFACT_TYPE.to_string()),
+        config,
+        layers,
+        annotations: HashMap::from([
+            (TITLE_ANNOTATION.to_string(), vm.meta.name.clone()),
+            ("org.opencontainers.image.created".to_string(), chrono::Utc::now().to_rfc3339()),
+        ]),
+    };
+    let digest = registry.put_manifest(&reference.reference, &manifest).await?;
+    let pinned = reference.with_reference(format!("sha256:{}", digest));
+
+    state.emit_vm_event(&vm.meta.id, format!("Pushed as appliance {}", pinned));
+    info!(
+        "Pushed VM {} as {} ({} of {} disks uploaded)",
+        vm.meta.name, pinned, uploaded, disk_count
+    );
+    Ok(Pushed {
+        reference: pinned.to_string(),
+        digest,
+        disks: disk_count,
+        uploaded,
+        signer_key_id: key_pair.key_id(),
+    })
+}
+
+/// Create a stopped VM and its volumes from an appliance artifact
+pub async fn pull(
+    state: &StateManager,
+    reference: &str,
+    name: Option<String>,
+    labels: HashMap<String, String>,
+) -> Result<Pulled> {
+    let reference = OciReference::parse(reference)?;
+    let registry = RegistryClient::new(state.config(), &reference)?;
+    let (manifest, digest) = registry.get_manifest().await?;
+    if manifest.artifact_type.as_deref() != Some(ARTIFACT_TYPE)
+        && manifest.config.media_type != CONFIG_MEDIA_TYPE
+    {
+        return Err(Error::RegistryError(format!(
+            "{} is not an InfraSim appliance",
+            reference
+        )));
+    }
+
+    // Nothing is created from an appliance whose provenance doesn't check out
+    let layer = |media_type: &str| manifest.layers.iter().find(|l| l.media_type == media_type);
+    let provenance = layer(PROVENANCE_MEDIA_TYPE).ok_or_else(|| {
+        Error::IntegrityError(format!("{} has no provenance and can't be verified", reference))
+    })?;
+    let provenance: SignedData<Provenance> =
+        serde_json::from_slice(&fetch(&registry, provenance).await?)?;
+    provenance.verify().map_err(|e| {
+        Error::IntegrityError(format!("Provenance signature of {} is invalid: {}", reference, e))
+    })?;
+    if provenance.data.config_digest != manifest.config.hex_digest()? {
+        return Err(Error::IntegrityError(format!(
+            "Provenance of {} is for a different appliance",
+            reference
+        )));
+    }
+    let signer_key_id = hex::decode(&provenance.signer_public_key)
+        .map(|key| crypto::key_id(&key))
+        .unwrap_or_default();
+
+    let config: Manifest = serde_json::from_slice(&fetch(&registry, &manifest.config).await?)?;
+    config.check_version()?;
+    let terraform_hcl = match layer(TERRAFORM_MEDIA_TYPE) {
+        Some(terraform) if terraform.hex_digest()? == provenance.data.terraform_digest => {
+            String::from_utf8_lossy(&fetch(&registry, terraform).await?).to_string()
+        }
+        _ => {
+            return Err(Error::IntegrityError(format!(
+                "Terraform layer of {} is missing or not the signed one",
+                reference
+            )))
+        }
+    };
+
+    let name = name.unwrap_or_else(|| config.name.clone());
+    if state.get_vm_by_name(&name)?.is_some() {
+        return Err(Error::AlreadyExists {
+            kind: "vm".to_string(),
+            id: name,
+        });
+    }
+
+    // Disk layers are verified against their digests as they are pulled
+    let mut images = Vec::new();
+    for disk in &config.disks {
+        let layer = manifest
+            .layers
+            .iter()
+            .find(|l| l.media_type == DISK_MEDIA_TYPE && l.title() == Some(disk.file.as_str()))
+            .ok_or_else(|| {
+                Error::RegistryError(format!("{} has no layer for disk {}", reference, disk.file))
+            })?;
+        let object = state.cas().pull(layer.hex_digest()?, &registry).await?;
+        images.push((disk, object, disk.format.clone()));
+    }
+
+    let mut volumes = Vec::new();
+    let result = export::create_vm(state, &config, &name, labels, &images, true, &mut volumes);
+    if result.is_err() {
+        for volume in &volumes {
+            let _ = state.delete_volume(&volume.meta.id);
+        }
+    }
+    let vm = result?;
+
+    state.emit_vm_event(
+        &vm.meta.id,
+        format!("Pulled from appliance {} signed by key {}", reference, signer_key_id),
+    );
+    info!(
+        "Pulled appliance {} as VM {} with {} disks",
+        reference,
+        vm.meta.name,
+        volumes.len()
+    );
+    Ok(Pulled {
+        vm,
+        volumes,
+        digest,
+        signer_key_id,
+        terraform_hcl,
+    })
+}
+
+/// Upload a small blob unless the registry has it, returning its descriptor
+async fn put_blob(registry: &RegistryClient, media_type: &str, data: Vec<u8>) -> Result<Descriptor> {
+    let digest = hex::encode(Sha256::digest(&data));
+    let descriptor = Descriptor::new(media_type, &digest, data.len() as u64);
+    if registry.blob_size(&digest).await?.is_none() {
+        registry.put_blob(&digest, data).await?;
+    }
+    Ok(descriptor)
+}
+
+/// Read a small blob and check it against its descriptor
+async fn fetch(registry: &RegistryClient, descriptor: &Descriptor) -> Result<Vec<u8>> {
+    if descriptor.size > MAX_METADATA_BLOB {
+        return Err(Error::RegistryError(format!(
+            "{} blob {} is {} bytes, limit is {}",
+            descriptor.media_type, descriptor.digest, descriptor.size, MAX_METADATA_BLOB
+        )));
+    }
+    let digest = descriptor.hex_digest()?;
+    let data = registry.get_blob(digest).await?;
+    let actual = hex::encode(Sha256::digest(&data));
+    if actual != digest {
+        return Err(Error::IntegrityError(format!(
+            "Digest mismatch: expected {}, got {}",
+            digest, actual
+        )));
+    }
+    Ok(data)
+}
+
+/// Terraform declaring the appliance, with disks pulled from the registry by digest
+fn terraform_hcl(
+    vm: &Vm,
+    reference: &OciReference,
+    layers: &[Descriptor],
+    disks: &[export::ExportDisk],
+) -> String {
+    let stem = export::file_stem(&vm.meta.name).to_lowercase();
+    let mut hcl = format!(
+        "# Appliance {} ({})\n\nprovider \"infrasim\" {{\n  daemon_address = \"http://127.0.0.1:50051\"\n}}\n",
+        vm.meta.name, reference
+    );
+
+    let mut volume_refs = Vec::new();
+    let mut boot_ref = None;
+    for (index, (layer, disk)) in layers.iter().zip(disks).enumerate() {
+        let resource = format!("{}_disk{}", stem, index);
+        let kind = match disk.volume.spec.kind {
+            VolumeKind::Disk => "disk",
+            VolumeKind::Weights => "weights",
+            VolumeKind::Device => "device",
+        };
+        hcl.push_str(&format!(
+            "\nresource \"infrasim_volume\" \"{}\" {{\n  name      = \"{}-disk{}\"\n  kind      = \"{}\"\n  source    = \"{}{}/{}@{}\"\n  format    = \"qcow2\"\n  overlay   = true\n  read_only = {}\n}}\n",
+            resource,
+            vm.meta.name,
+            index,
+            kind,
+            OCI_SCHEME,
+            reference.registry,
+            reference.repository,
+            layer.digest,
+            disk.volume.spec.read_only
+        ));
+        let volume_ref = format!("infrasim_volume.{}.id", resource);
+        if disk.boot {
+            boot_ref = Some(volume_ref.clone());
+        }
+        volume_refs.push(volume_ref);
+    }
+
+    hcl.push_str(&format!(
+        "\nresource \"infrasim_vm\" \"{}\" {{\n  name         = \"{}\"\n  arch         = \"{}\"\n  machine      = \"{}\"\n  cpu_cores    = {}\n  memory_mb    = {}\n  enable_tpm   = {}\n  volume_ids   = [{}]\n",
+        stem,
+        vm.meta.name,
+        vm.spec.arch,
+        vm.spec.machine,
+        vm.spec.cpu_cores,
+        vm.spec.memory_mb,
+        vm.spec.enable_tpm,
+        volume_refs.join(", ")
+    ));
+    if let Some(boot_ref) = boot_ref {
+        hcl.push_str(&format!("  boot_disk_id = {}\n", boot_ref));
+    }
+    hcl.push_str("}\n");
+    hcl
+}
@@ -0,0 +1,391 @@
+//! Volume and snapshot archives
+//!
+//! An archive is a tar.gz of standalone qcow2 images of volumes and of
+//! snapshot disk layers, optionally the snapshots' memory state, caller
+//! metadata such as an appliance description, and an `archive.json`
+//! manifest with the SHA-256 of every file. It is stored in the CAS under
+//! its own digest. Restoring checks each file against the manifest and
+//! creates one volume per disk image; memory state stays in the archive.
+
+use crate::export::{convert, file_stem};
+use crate::snapshot::active_path;
+use crate::state::StateManager;
+use infrasim_common::{types::*, ContentAddressedStore, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::{info, warn};
+
+/// Manifest file present in every archive
+const MANIFEST_FILE: &str = "archive.json";
+
+/// Caller metadata, when given
+const METADATA_FILE: &str = "metadata.json";
+
+/// Current manifest version; newer archives are refused
+const MANIFEST_VERSION: u32 = 1;
+
+/// Contents of `archive.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    created_at: i64,
+    disks: Vec<ArchivedDisk>,
+    #[serde(default)]
+    memory: Vec<ArchivedMemory>,
+}
+
+/// A qcow2 disk image in the archive
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedDisk {
+    file: String,
+    digest: String,
+    /// Name of the volume, or of the snapshot for snapshot layers
+    name: String,
+    #[serde(default)]
+    kind: VolumeKind,
+    #[serde(default)]
+    read_only: bool,
+    /// Snapshot whose disk layer this is
+    #[serde(default)]
+    snapshot_id: Option<String>,
+}
+
+/// Memory state of a snapshot
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchivedMemory {
+    file: String,
+    digest: String,
+    snapshot_id: String,
+}
+
+/// An archive written to the CAS
+pub struct Created {
+    pub digest: String,
+    pub size_bytes: u64,
+}
+
+/// Pack volumes and snapshots into an archive in the CAS
+pub async fn create(
+    state: &StateManager,
+    volume_ids: &[String],
+    snapshot_ids: &[String],
+    include_memory: bool,
+    metadata: &[u8],
+) -> Result<Created> {
+    let mut sources = Vec::new();
+    for id in volume_ids {
+        let volume = state.get_volume(id)?.ok_or_else(|| Error::NotFound {
+            kind: "volume".to_string(),
+            id: id.clone(),
+        })?;
+        if volume.spec.kind == VolumeKind::Device {
+            return Err(Error::ExportError(format!(
+                "Device volume {} can't be archived",
+                volume.meta.name
+            )));
+        }
+        // The key would not travel with the archive
+        if volume.spec.encryption.is_some() {
+            return Err(Error::ExportError(format!(
+                "Encrypted volume {} can't be archived",
+                volume.meta.name
+            )));
+        }
+        let path = active_path(&volume)?;
+        sources.push((
+            ArchivedDisk {
+                file: String::new(),
+                digest: String::new(),
+                name: volume.meta.name,
+                kind: volume.spec.kind,
+                read_only: volume.spec.read_only,
+                snapshot_id: None,
+            },
+            path,
+        ));
+    }
+
+    let mut memory_sources = Vec::new();
+    for id in snapshot_ids {
+        let snapshot = state.get_snapshot(id)?.ok_or_else(|| Error::NotFound {
+            kind: "snapshot".to_string(),
+            id: id.clone(),
+        })?;
+        if snapshot.status.encrypted {
+            return Err(Error::ExportError(format!(
+                "Encrypted snapshot {} can't be archived",
+                snapshot.meta.name
+            )));
+        }
+        if let Some(layer) = &snapshot.status.disk_snapshot_path {
+            sources.push((
+                ArchivedDisk {
+                    file: String::new(),
+                    digest: String::new(),
+                    name: snapshot.meta.name.clone(),
+                    kind: VolumeKind::Disk,
+                    read_only: false,
+                    snapshot_id: Some(snapshot.meta.id.clone()),
+                },
+                PathBuf::from(layer),
+            ));
+        }
+        if include_memory {
+            if let Some(memory) = &snapshot.status.memory_snapshot_path {
+                memory_sources.push((snapshot.meta.id.clone(), PathBuf::from(memory)));
+            }
+        }
+    }
+    if sources.is_empty() && memory_sources.is_empty() {
+        return Err(Error::InvalidConfig(
+            "Nothing to archive: no volumes or snapshots given".to_string(),
+        ));
+    }
+
+    let work_dir = state
+        .config()
+        .export_dir()
+        .join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&work_dir).await?;
+    let result = build(state, &work_dir, sources, memory_sources, metadata).await;
+    let _ = fs::remove_dir_all(&work_dir).await;
+    result
+}
+
+async fn build(
+    state: &StateManager,
+    work_dir: &Path,
+    sources: Vec<(ArchivedDisk, PathBuf)>,
+    memory_sources: Vec<(String, PathBuf)>,
+    metadata: &[u8],
+) -> Result<Created> {
+    let mut files = vec![MANIFEST_FILE.to_string()];
+
+    // Converting flattens any backing chain into a standalone image
+    let mut disks = Vec::new();
+    for (index, (mut disk, path)) in sources.into_iter().enumerate() {
+        disk.file = format!("disk{}-{}.qcow2", index, file_stem(&disk.name));
+        let dest = work_dir.join(&disk.file);
+        convert(&path, None, &dest, "qcow2", &[]).await?;
+        disk.digest = ContentAddressedStore::hash_file(&dest).await?;
+        files.push(disk.file.clone());
+        disks.push(disk);
+    }
+
+    let mut memory = Vec::new();
+    for (index, (snapshot_id, path)) in memory_sources.into_iter().enumerate() {
+        let file = format!("memory{}.mem", index);
+        fs::copy(&path, work_dir.join(&file)).await?;
+        memory.push(ArchivedMemory {
+            digest: ContentAddressedStore::hash_file(work_dir.join(&file)).await?,
+            file: file.clone(),
+            snapshot_id,
+        });
+        files.push(file);
+    }
+
+    if !metadata.is_empty() {
+        fs::write(work_dir.join(METADATA_FILE), metadata).await?;
+        files.push(METADATA_FILE.to_string());
+    }
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        created_at: chrono::Utc::now().timestamp(),
+        disks,
+        memory,
+    };
+    fs::write(
+        work_dir.join(MANIFEST_FILE),
+        serde_json::to_vec_pretty(&manifest)?,
+    )
+    .await?;
+
+    let cas = state.cas();
+    let staged = cas
+        .tmp_dir()
+        .join(format!("{}.tar.gz", uuid::Uuid::new_v4()));
+    if let Err(e) = pack(work_dir, &files, &staged).await {
+        let _ = fs::remove_file(&staged).await;
+        return Err(e);
+    }
+    let size_bytes = fs::metadata(&staged).await?.len();
+    let digest = ContentAddressedStore::hash_file(&staged).await?;
+    cas.adopt_file(&staged, &digest).await?;
+
+    info!(
+        "Archived {} disks and {} memory images as {} ({} bytes)",
+        manifest.disks.len(),
+        manifest.memory.len(),
+        digest,
+        size_bytes
+    );
+    Ok(Created { digest, size_bytes })
+}
+
+/// Path and size of an archive in the CAS
+pub async fn open(state: &StateManager, digest: &str) -> Result<(PathBuf, u64)> {
+    check_digest(digest)?;
+    let path = state.cas().get_path(digest).await?;
+    let size = fs::metadata(&path).await?.len();
+    Ok((path, size))
+}
+
+/// Create volumes from the disk images of an archive, returning them and
+/// the archive's metadata
+pub async fn restore(
+    state: &StateManager,
+    digest: &str,
+    name_prefix: &str,
+) -> Result<(Vec<Volume>, Vec<u8>)> {
+    let (archive, _) = open(state, digest).await?;
+    let dir = state
+        .config()
+        .restore_dir()
+        .join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).await?;
+
+    let result = restore_into(state, &archive, &dir, name_prefix).await;
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+    result
+}
+
+async fn restore_into(
+    state: &StateManager,
+    archive: &Path,
+    dir: &Path,
+    name_prefix: &str,
+) -> Result<(Vec<Volume>, Vec<u8>)> {
+    unpack(archive, dir).await?;
+
+    let manifest: Manifest = match fs::read(dir.join(MANIFEST_FILE)).await {
+        Ok(data) => serde_json::from_slice(&data)?,
+        Err(_) => {
+            return Err(Error::ExportError(format!(
+                "Not an InfraSim archive: {} is missing",
+                MANIFEST_FILE
+            )))
+        }
+    };
+    if manifest.version > MANIFEST_VERSION {
+        return Err(Error::ExportError(format!(
+            "Archive manifest version {} is newer than supported version {}",
+            manifest.version, MANIFEST_VERSION
+        )));
+    }
+
+    // Check everything before creating any volume
+    for (file, digest) in manifest
+        .disks
+        .iter()
+        .map(|d| (&d.file, &d.digest))
+        .chain(manifest.memory.iter().map(|m| (&m.file, &m.digest)))
+    {
+        // Only plain file names; anything else could point outside the archive
+        if Path::new(file).file_name().and_then(|f| f.to_str()) != Some(file.as_str()) {
+            return Err(Error::ExportError(format!(
+                "Invalid file name in archive: {}",
+                file
+            )));
+        }
+        let actual = match ContentAddressedStore::hash_file(dir.join(file)).await {
+            Ok(actual) => actual,
+            Err(_) => {
+                return Err(Error::ExportError(format!(
+                    "{} listed in the manifest is missing from the archive",
+                    file
+                )))
+            }
+        };
+        if &actual != digest {
+            return Err(Error::IntegrityError(format!(
+                "{} in archive has digest {}, manifest says {}",
+                file, actual, digest
+            )));
+        }
+    }
+
+    let metadata = fs::read(dir.join(METADATA_FILE)).await.unwrap_or_default();
+
+    let mut volumes = Vec::new();
+    for disk in &manifest.disks {
+        let spec = VolumeSpec {
+            kind: disk.kind,
+            source: dir.join(&disk.file).to_string_lossy().to_string(),
+            read_only: disk.read_only,
+            format: "qcow2".to_string(),
+            ..Default::default()
+        };
+        let name = format!("{}{}", name_prefix, disk.name);
+        match state.create_volume(name, spec, HashMap::new()) {
+            Ok(volume) => volumes.push(volume),
+            Err(e) => {
+                for volume in &volumes {
+                    if let Err(e) = state.delete_volume(&volume.meta.id) {
+                        warn!(
+                            "Failed to remove restored volume {}: {}",
+                            volume.meta.name, e
+                        );
+                    }
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    info!("Restored {} volumes from archive", volumes.len());
+    Ok((volumes, metadata))
+}
+
+/// Reject anything but a lowercase hex SHA-256 digest
+fn check_digest(digest: &str) -> Result<()> {
+    if digest.len() != 64 || !digest.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+        return Err(Error::InvalidConfig(format!(
+            "Invalid archive digest: {}",
+            digest
+        )));
+    }
+    Ok(())
+}
+
+/// Write `files` from `dir` into a gzip-compressed tar archive, in order
+async fn pack(dir: &Path, files: &[String], archive: &Path) -> Result<()> {
+    let dir = dir.to_path_buf();
+    let files = files.to_vec();
+    let archive = archive.to_path_buf();
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&archive)?,
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for file in &files {
+            builder.append_path_with_name(dir.join(file), file)?;
+        }
+        builder.into_inner()?.finish()?.sync_all()?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| Error::Internal(e.to_string()))?
+}
+
+/// Unpack a tar.gz archive; entries escaping `dir` are skipped by `tar`
+async fn unpack(archive: &Path, dir: &Path) -> Result<()> {
+    let archive = archive.to_path_buf();
+    let dir = dir.to_path_buf();
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let decoder = flate2::read::GzDecoder::new(std::fs::File::open(&archive)?);
+        tar::Archive::new(decoder).unpack(&dir).map_err(|e| {
+            warn!("Failed to unpack archive {}: {}", archive.display(), e);
+            Error::ExportError(format!("Invalid archive: {}", e))
+        })
+    })
+    .await
+    .map_err(|e| Error::Internal(e.to_string()))?
+}
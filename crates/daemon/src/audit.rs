@@ -0,0 +1,251 @@
+//! Audit trail of mutating gRPC calls
+//!
+//! [`AuditService`] wraps the gRPC service and appends an entry to the audit
+//! log for every call that isn't a read. Reads are recognised by method name,
+//! so new RPCs are audited unless they are plainly reads. Handlers say which
+//! resource they changed, and how, by building their response with
+//! [`audited`].
+
+use infrasim_common::audit::{digest, AuditEntry, AuditLog, STATUS_OK};
+use infrasim_common::auth::AuthenticatedToken;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::task::{Context, Poll};
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::server::NamedService;
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo, UdsConnectInfo};
+use tracing::warn;
+
+/// Source of the daemon's audit entries
+pub const SOURCE: &str = "daemon";
+
+/// Method name prefixes of calls that only read
+const READ_PREFIXES: &[&str] = &[
+    "Get", "List", "Watch", "Stream", "Inspect", "Read", "Diff", "Download", "Verify",
+];
+
+/// What a mutating call changed, attached to its response by [`audited`]
+#[derive(Debug, Clone, Default)]
+pub struct AuditChange {
+    kind: String,
+    id: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+impl AuditChange {
+    pub fn new(kind: &str, id: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Record a digest of the resource as it was before the call
+    pub fn before<T: Serialize>(mut self, resource: &T) -> Self {
+        self.before = Some(digest(resource));
+        self
+    }
+
+    /// Like [`before`](Self::before), for a resource that may not have existed
+    pub fn maybe_before<T: Serialize>(self, resource: Option<&T>) -> Self {
+        match resource {
+            Some(resource) => self.before(resource),
+            None => self,
+        }
+    }
+
+    /// Record a digest of the resource as the call left it
+    pub fn after<T: Serialize>(mut self, resource: &T) -> Self {
+        self.after = Some(digest(resource));
+        self
+    }
+}
+
+/// Response to a mutating call, annotated with what it changed
+pub fn audited<T>(message: T, change: AuditChange) -> tonic::Response<T> {
+    let mut response = tonic::Response::new(message);
+    response.extensions_mut().insert(change);
+    response
+}
+
+/// Whether a gRPC method only reads
+pub fn is_read(method: &str) -> bool {
+    READ_PREFIXES.iter().any(|prefix| method.starts_with(prefix))
+}
+
+/// Who made a request and from where
+///
+/// Tokens name the caller; otherwise mTLS callers are identified by their
+/// certificate's fingerprint and Unix socket callers by their uid.
+fn caller<B>(request: &http::Request<B>) -> (String, Option<String>) {
+    let extensions = request.extensions();
+    let tls = extensions.get::<TlsConnectInfo<TcpConnectInfo>>();
+    let tcp = extensions.get::<TcpConnectInfo>().or(tls.map(|t| t.get_ref()));
+    let source_ip = tcp.and_then(|t| t.remote_addr()).map(|a| a.ip().to_string());
+
+    let cert = tls
+        .and_then(|t| t.peer_certs())
+        .and_then(|certs| certs.first().map(|c| hex::encode(&Sha256::digest(c.get_ref())[..8])));
+    let uid = extensions
+        .get::<UdsConnectInfo>()
+        .and_then(|u| u.peer_cred)
+        .map(|cred| cred.uid());
+    let identity = match (extensions.get::<AuthenticatedToken>(), cert, uid) {
+        (Some(AuthenticatedToken(name)), _, _) => format!("token:{}", name),
+        (None, Some(fingerprint), _) => format!("cert:{}", fingerprint),
+        (None, None, Some(uid)) => format!("uid:{}", uid),
+        (None, None, None) => "anonymous".to_string(),
+    };
+    (identity, source_ip)
+}
+
+/// Status of a finished call: failures carry `grpc-status` in the headers,
+/// calls that got as far as a response body count as successful
+fn call_status<B>(response: &http::Response<B>) -> String {
+    match response.headers().get("grpc-status") {
+        Some(code) if code.as_bytes() != b"0" => format!("{:?}", tonic::Code::from_bytes(code.as_bytes())),
+        _ => STATUS_OK.to_string(),
+    }
+}
+
+/// gRPC service wrapper recording mutating calls in the audit log
+///
+/// Sits inside any authentication interceptor, so it sees who the caller
+/// authenticated as.
+#[derive(Clone)]
+pub struct AuditService<S> {
+    inner: S,
+    log: AuditLog,
+}
+
+impl<S> AuditService<S> {
+    pub fn new(inner: S, log: AuditLog) -> Self {
+        Self { inner, log }
+    }
+}
+
+impl<S, B, R> Service<http::Request<B>> for AuditService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request.uri().path().rsplit('/').next().unwrap_or_default().to_string();
+        if is_read(&method) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let (identity, source_ip) = caller(&request);
+        let log = self.log.clone();
+        let call = self.inner.call(request);
+        Box::pin(async move {
+            let response = call.await?;
+            let change = response.extensions().get::<AuditChange>().cloned().unwrap_or_default();
+            let non_empty = |s: String| (!s.is_empty()).then_some(s);
+            let entry = AuditEntry {
+                source: SOURCE.to_string(),
+                identity,
+                source_ip,
+                operation: method,
+                resource_kind: non_empty(change.kind),
+                resource_id: non_empty(change.id),
+                status: call_status(&response),
+                before_digest: change.before,
+                after_digest: change.after,
+                ..Default::default()
+            };
+            if let Err(e) = log.append(entry) {
+                warn!("Failed to write audit log entry: {}", e);
+            }
+            Ok(response)
+        })
+    }
+}
+
+impl<S: NamedService> NamedService for AuditService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infrasim_common::audit::AuditFilter;
+    use infrasim_common::Database;
+    use std::convert::Infallible;
+
+    /// Service answering every call like a handler would, failing `Fail*` methods
+    #[derive(Clone)]
+    struct Handler;
+
+    impl Service<http::Request<()>> for Handler {
+        type Response = http::Response<()>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<()>) -> Self::Future {
+            let method = request.uri().path().rsplit('/').next().unwrap_or_default().to_string();
+            Box::pin(async move {
+                if method.starts_with("Fail") {
+                    return Ok(tonic::Status::not_found("VM not found").to_http().map(|_| ()));
+                }
+                let change = AuditChange::new("vm", "vm-1").before(&1).after(&2);
+                let (_, _, extensions) = audited((), change).into_parts();
+                let mut response = http::Response::new(());
+                *response.extensions_mut() = extensions.into_http();
+                Ok(response)
+            })
+        }
+    }
+
+    async fn call(service: &mut AuditService<Handler>, method: &str, token: Option<&str>) {
+        let mut request = http::Request::new(());
+        *request.uri_mut() = format!("/infrasim.v1.InfraSimDaemon/{}", method).parse().unwrap();
+        if let Some(token) = token {
+            request.extensions_mut().insert(AuthenticatedToken(token.to_string()));
+        }
+        service.call(request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_records_mutating_calls() {
+        let log = AuditLog::new(Database::open_memory().unwrap());
+        let mut service = AuditService::new(Handler, log.clone());
+
+        call(&mut service, "GetVm", Some("ci")).await;
+        call(&mut service, "ListVMs", Some("ci")).await;
+        call(&mut service, "StartVm", Some("ci")).await;
+        call(&mut service, "FailDeleteVm", None).await;
+
+        let entries = log.list(&AuditFilter::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        let failed = &entries[0];
+        assert_eq!(failed.operation, "FailDeleteVm");
+        assert_eq!(failed.identity, "anonymous");
+        assert_eq!(failed.status, "NotFound");
+        assert_eq!(failed.resource_id, None);
+
+        let started = &entries[1];
+        assert_eq!(started.source, SOURCE);
+        assert_eq!(started.identity, "token:ci");
+        assert_eq!(started.operation, "StartVm");
+        assert_eq!(started.status, STATUS_OK);
+        assert_eq!(started.resource_kind.as_deref(), Some("vm"));
+        assert_eq!(started.resource_id.as_deref(), Some("vm-1"));
+        assert_eq!(started.before_digest, Some(digest(&1)));
+        assert_eq!(started.after_digest, Some(digest(&2)));
+    }
+}
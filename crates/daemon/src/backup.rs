@@ -0,0 +1,743 @@
+//! VM backups
+//!
+//! Unlike snapshots, backups copy a VM's disks out of the store, to a
+//! directory or an S3-compatible bucket, as one qcow2 image per disk.
+//! Running VMs are copied by QMP `blockdev-backup` jobs started in one
+//! transaction, so all disks are captured at the same instant. The full
+//! backup also adds a dirty bitmap to each disk; later backups copy only the
+//! blocks written since into an image backed by the previous one, as long
+//! as the disks are the same and their bitmaps survived. Stopped VMs are
+//! copied in full with `qemu-img convert`.
+//!
+//! Restoring converts a backup's image chain into standalone volumes of a
+//! new, stopped VM with the spec recorded at backup time.
+
+use crate::encryption::{self, Keyring};
+use crate::s3::S3Client;
+use crate::snapshot;
+use crate::state::StateManager;
+use infrasim_common::{qmp::QmpClient, types::*, Error, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use tokio::fs;
+use tracing::{debug, info, warn};
+
+/// Dirty bitmap tracking writes since the VM's last backup
+const BITMAP: &str = "infrasim-backup";
+
+/// How often backup jobs are polled for completion
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A disk to back up and its current image
+struct SourceDisk {
+    volume: Volume,
+    path: String,
+    boot: bool,
+}
+
+/// A running backup job and the target node it writes to
+struct Job {
+    id: String,
+    target_node: String,
+}
+
+/// Back up a VM's disks to the spec's target
+///
+/// The backup is recorded before copying starts; if copying fails it is
+/// kept with the error so the VM's next backup is a full one.
+pub async fn create(
+    state: &StateManager,
+    name: String,
+    spec: BackupSpec,
+    labels: HashMap<String, String>,
+) -> Result<Backup> {
+    let target = StorageTarget::parse(&spec.target)?;
+    let vm = state.get_vm(&spec.vm_id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: spec.vm_id.clone(),
+    })?;
+    let disks = source_disks(state, &vm)?;
+
+    if !state.claim_backup(&vm.meta.id) {
+        return Err(Error::BackupError(format!(
+            "A backup of VM {} is already running",
+            vm.meta.name
+        )));
+    }
+    let result = create_claimed(state, &vm, name, spec, labels, &target, &disks).await;
+    state.release_backup(&vm.meta.id);
+    result
+}
+
+async fn create_claimed(
+    state: &StateManager,
+    vm: &Vm,
+    name: String,
+    spec: BackupSpec,
+    labels: HashMap<String, String>,
+    target: &StorageTarget,
+    disks: &[SourceDisk],
+) -> Result<Backup> {
+    let previous = latest(state, &vm.meta.id)?;
+    let mut backup = state.create_backup(name, spec, labels)?;
+    backup.status.vm_name = vm.meta.name.clone();
+    backup.status.vm_spec = Some(vm.spec.clone());
+
+    match run(state, vm, &mut backup, target, disks, previous.as_ref()).await {
+        Ok(()) => {
+            backup.status.complete = true;
+            backup.status.completed_at = Some(chrono::Utc::now().timestamp());
+            state.update_backup_status(&backup.meta.id, backup.status.clone())?;
+            info!(
+                "Backed up VM {} to {} ({:?}, {} bytes)",
+                vm.meta.name, backup.spec.target, backup.status.kind, backup.status.size_bytes
+            );
+            Ok(backup)
+        }
+        Err(e) => {
+            if let Err(cleanup) = remove_files(state, &backup).await {
+                warn!("Leftovers of backup {} not removed: {}", backup.meta.name, cleanup);
+            }
+            backup.status.error = Some(e.to_string());
+            state.update_backup_status(&backup.meta.id, backup.status)?;
+            Err(e)
+        }
+    }
+}
+
+async fn run(
+    state: &StateManager,
+    vm: &Vm,
+    backup: &mut Backup,
+    target: &StorageTarget,
+    disks: &[SourceDisk],
+    previous: Option<&Backup>,
+) -> Result<()> {
+    backup.status.disks = disks
+        .iter()
+        .map(|disk| BackupDisk {
+            volume_id: disk.volume.meta.id.clone(),
+            volume_name: disk.volume.meta.name.clone(),
+            boot: disk.boot,
+            read_only: disk.volume.spec.read_only,
+            file: format!("{}-{}.qcow2", backup.meta.id, disk.volume.meta.id),
+            source_path: disk.path.clone(),
+            size_bytes: 0,
+            encryption: disk.volume.spec.encryption.clone(),
+        })
+        .collect();
+    for disk in &mut backup.status.disks {
+        disk.size_bytes = virtual_size(Path::new(&disk.source_path))?;
+    }
+    state.update_backup_status(&backup.meta.id, backup.status.clone())?;
+
+    // S3 backups are written locally and uploaded once complete
+    let dir = match target {
+        StorageTarget::Dir(dir) => dir.join(&vm.meta.id),
+        StorageTarget::S3 { .. } => state.config().backup_staging_dir().join(&backup.meta.id),
+    };
+    fs::create_dir_all(&dir).await?;
+
+    let result = write_images(state, vm, backup, &dir, previous).await;
+    if let StorageTarget::S3 { bucket, prefix } = target {
+        let result = match result {
+            Ok(()) => {
+                let s3 = S3Client::new(state.config(), bucket)?;
+                let mut uploaded = Ok(());
+                for disk in &backup.status.disks {
+                    let key = object_key(prefix, &vm.meta.id, &disk.file);
+                    if let Err(e) = s3.upload(&key, &dir.join(&disk.file)).await {
+                        uploaded = Err(e);
+                        break;
+                    }
+                    debug!("Uploaded {} to s3://{}/{}", disk.file, bucket, key);
+                }
+                uploaded
+            }
+            Err(e) => Err(e),
+        };
+        let _ = fs::remove_dir_all(&dir).await;
+        return result;
+    }
+
+    result
+}
+
+/// Write the backup's images into `dir`
+async fn write_images(
+    state: &StateManager,
+    vm: &Vm,
+    backup: &mut Backup,
+    dir: &Path,
+    previous: Option<&Backup>,
+) -> Result<()> {
+    match state.get_vm_process(&vm.meta.id) {
+        Some(process) => {
+            let qmp = QmpClient::new(&process.qmp_socket);
+            qmp.connect().await?;
+            backup_online(state, &qmp, backup, dir, previous).await?;
+        }
+        None => backup_offline(state, backup, dir).await?,
+    }
+
+    backup.status.size_bytes = 0;
+    for disk in &backup.status.disks {
+        backup.status.size_bytes += fs::metadata(dir.join(&disk.file)).await?.len();
+    }
+    Ok(())
+}
+
+/// Copy the disks of a running VM with backup jobs
+async fn backup_online(
+    state: &StateManager,
+    qmp: &QmpClient,
+    backup: &mut Backup,
+    dir: &Path,
+    previous: Option<&Backup>,
+) -> Result<()> {
+    let nodes = qmp.query_named_block_nodes().await?;
+    let mut sources = Vec::new();
+    for disk in &backup.status.disks {
+        let node = nodes
+            .iter()
+            .find(|n| n.file == disk.source_path && n.drv != "file" && n.drv != "host_device")
+            .ok_or_else(|| {
+                Error::BackupError(format!(
+                    "{} is not open in VM {}",
+                    disk.source_path, backup.status.vm_name
+                ))
+            })?;
+        sources.push(node);
+    }
+
+    let parent = previous.filter(|previous| {
+        backup.spec.incremental
+            && builds_on(backup, previous)
+            && sources.iter().all(|node| {
+                node.dirty_bitmaps
+                    .iter()
+                    .any(|b| b.name == BITMAP && !b.inconsistent)
+            })
+    });
+    if let Some(parent) = parent {
+        backup.status.kind = BackupKind::Incremental;
+        backup.status.parent_id = Some(parent.meta.id.clone());
+    } else {
+        backup.status.kind = BackupKind::Full;
+        if backup.spec.incremental && previous.is_some() {
+            debug!(
+                "Backup {} is a full one: it can't build on the previous backup",
+                backup.meta.name
+            );
+        }
+    }
+    state.update_backup_status(&backup.meta.id, backup.status.clone())?;
+
+    let mut jobs = Vec::new();
+    let mut actions = Vec::new();
+    let mut result = Ok(());
+    for (index, (disk, node)) in backup.status.disks.iter().zip(&sources).enumerate() {
+        let backing = parent.map(|parent| parent.status.disks[index].file.as_str());
+        let target_node = match add_target(state, qmp, &dir.join(&disk.file), disk, backing).await {
+            Ok(target_node) => target_node,
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        };
+        let job = Job {
+            id: format!("backup-{}", uuid::Uuid::new_v4().simple()),
+            target_node,
+        };
+
+        let mut job_args = json!({
+            "job-id": job.id,
+            "device": node.node_name,
+            "target": job.target_node,
+            "sync": if parent.is_some() { "incremental" } else { "full" },
+            "auto-dismiss": false,
+        });
+        jobs.push(job);
+        if parent.is_some() {
+            // The bitmap is cleared only if the job succeeds
+            job_args["bitmap"] = json!(BITMAP);
+        } else {
+            // A stale bitmap may be left by an earlier full backup
+            if node.dirty_bitmaps.iter().any(|b| b.name == BITMAP) {
+                if let Err(e) = qmp.block_dirty_bitmap_remove(&node.node_name, BITMAP).await {
+                    result = Err(e);
+                    break;
+                }
+            }
+            actions.push(json!({
+                "type": "block-dirty-bitmap-add",
+                "data": {
+                    "node": node.node_name,
+                    "name": BITMAP,
+                    // Bitmaps can only be saved in writable qcow2 images
+                    "persistent": node.drv == "qcow2" && !disk.read_only,
+                },
+            }));
+        }
+        actions.push(json!({ "type": "blockdev-backup", "data": job_args }));
+    }
+
+    if result.is_ok() {
+        result = match qmp.transaction(actions).await {
+            Ok(()) => wait_for_jobs(qmp, &jobs).await,
+            Err(e) => Err(e),
+        };
+    }
+
+    for job in &jobs {
+        if let Err(e) = qmp.blockdev_del(&job.target_node).await {
+            warn!("Backup target {} not removed from QEMU: {}", job.target_node, e);
+        }
+    }
+    result
+}
+
+/// Create a disk's backup image and open it in QEMU, returning its node name
+async fn add_target(
+    state: &StateManager,
+    qmp: &QmpClient,
+    image: &Path,
+    disk: &BackupDisk,
+    backing: Option<&str>,
+) -> Result<String> {
+    create_image(state, image, disk.size_bytes, backing, disk.encryption.as_ref())?;
+
+    let node = format!("bk-{}", uuid::Uuid::new_v4().simple());
+    let mut options = json!({
+        "node-name": node,
+        "driver": "qcow2",
+        "file": { "driver": "file", "filename": image.to_string_lossy() },
+        // Incremental images are only written to; the parent may not even be here
+        "backing": null,
+    });
+    if let Some(enc) = &disk.encryption {
+        options["encrypt"] = json!({ "format": "luks", "key-secret": encryption::secret_id(enc) });
+    }
+    qmp.blockdev_add(options).await?;
+    Ok(node)
+}
+
+/// Copy the disks of a stopped VM in full
+async fn backup_offline(state: &StateManager, backup: &mut Backup, dir: &Path) -> Result<()> {
+    backup.status.kind = BackupKind::Full;
+    state.update_backup_status(&backup.meta.id, backup.status.clone())?;
+
+    for disk in &backup.status.disks {
+        snapshot::convert(
+            state,
+            Path::new(&disk.source_path),
+            &dir.join(&disk.file),
+            disk.encryption.as_ref(),
+            &mut |_| {},
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Wait for backup jobs to conclude, dismissing them
+async fn wait_for_jobs(qmp: &QmpClient, jobs: &[Job]) -> Result<()> {
+    let mut pending: Vec<&str> = jobs.iter().map(|j| j.id.as_str()).collect();
+    let mut errors = Vec::new();
+
+    while !pending.is_empty() {
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+        let running = qmp.query_jobs().await?;
+        let mut still_pending = Vec::new();
+        for id in pending {
+            match running.iter().find(|j| j.id == id) {
+                Some(job) if job.status == "concluded" => {
+                    qmp.job_dismiss(id).await?;
+                    if let Some(e) = &job.error {
+                        errors.push(e.clone());
+                    }
+                }
+                Some(_) => still_pending.push(id),
+                None => errors.push(format!("job {} disappeared", id)),
+            }
+        }
+        pending = still_pending;
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::BackupError(format!("Backup job failed: {}", errors.join("; "))))
+    }
+}
+
+/// Whether a new backup can copy only the changes since `previous`
+///
+/// The previous backup must be complete, on the same target and of the
+/// same disks, still at the same images and sizes.
+fn builds_on(backup: &Backup, previous: &Backup) -> bool {
+    previous.status.complete
+        && previous.spec.target == backup.spec.target
+        && previous.status.disks.len() == backup.status.disks.len()
+        && previous.status.disks.iter().zip(&backup.status.disks).all(|(old, new)| {
+            old.volume_id == new.volume_id
+                && old.source_path == new.source_path
+                && old.size_bytes == new.size_bytes
+                && old.encryption == new.encryption
+        })
+}
+
+/// Create an empty qcow2 image, backed by `backing` (a file next to it) if given
+fn create_image(
+    state: &StateManager,
+    path: &Path,
+    size: u64,
+    backing: Option<&str>,
+    encryption: Option<&VolumeEncryption>,
+) -> Result<()> {
+    let mut args = vec!["create".to_string(), "-f".to_string(), "qcow2".to_string()];
+    if let Some(backing) = backing {
+        // Relative, so chains stay valid wherever the files are copied
+        args.extend([
+            "-b".to_string(),
+            backing.to_string(),
+            "-F".to_string(),
+            "qcow2".to_string(),
+            "-u".to_string(),
+        ]);
+    }
+    if let Some(enc) = encryption {
+        args.extend(Keyring::new(state.config()).secret_args([enc])?);
+        args.extend(["-o".to_string(), encryption::create_options(enc)]);
+    }
+    args.extend([path.to_string_lossy().to_string(), size.to_string()]);
+
+    let output = Command::new("qemu-img")
+        .args(&args)
+        .output()
+        .map_err(|e| Error::BackupError(format!("qemu-img failed: {}", e)))?;
+    if !output.status.success() {
+        return Err(Error::BackupError(format!(
+            "qemu-img create failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(())
+}
+
+/// Create a stopped VM from a backup, with one new volume per backed-up disk
+pub async fn restore(
+    state: &StateManager,
+    backup_id: &str,
+    name: Option<String>,
+) -> Result<(Vm, Vec<Volume>)> {
+    let backup = get_backup(state, backup_id)?;
+    let vm_spec = match (&backup.status.vm_spec, backup.status.complete) {
+        (Some(spec), true) => spec.clone(),
+        _ => {
+            return Err(Error::BackupError(format!(
+                "Backup {} is incomplete and can't be restored",
+                backup.meta.name
+            )))
+        }
+    };
+
+    let name = name.unwrap_or_else(|| format!("{}-{}", backup.status.vm_name, backup.meta.name));
+    if state.get_vm_by_name(&name)?.is_some() {
+        return Err(Error::AlreadyExists {
+            kind: "vm".to_string(),
+            id: name,
+        });
+    }
+
+    let dir = state
+        .config()
+        .restore_dir()
+        .join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).await?;
+
+    let result = restore_into(state, &backup, vm_spec, &name, &dir).await;
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+    result
+}
+
+async fn restore_into(
+    state: &StateManager,
+    backup: &Backup,
+    vm_spec: VmSpec,
+    name: &str,
+    dir: &Path,
+) -> Result<(Vm, Vec<Volume>)> {
+    // Incremental images read unchanged blocks from the backups before them
+    let target = StorageTarget::parse(&backup.spec.target)?;
+    let source_dir = match &target {
+        StorageTarget::Dir(target_dir) => target_dir.join(&backup.spec.vm_id),
+        StorageTarget::S3 { bucket, prefix } => {
+            let staging = dir.join("download");
+            fs::create_dir_all(&staging).await?;
+            let s3 = S3Client::new(state.config(), bucket)?;
+            for member in chain(state, backup)? {
+                for disk in &member.status.disks {
+                    let key = object_key(prefix, &backup.spec.vm_id, &disk.file);
+                    s3.download(&key, &staging.join(&disk.file)).await?;
+                }
+            }
+            staging
+        }
+    };
+
+    let mut images = Vec::new();
+    for (index, disk) in backup.status.disks.iter().enumerate() {
+        let image = dir.join(format!("disk{}.qcow2", index));
+        snapshot::convert(
+            state,
+            &source_dir.join(&disk.file),
+            &image,
+            disk.encryption.as_ref(),
+            &mut |_| {},
+        )
+        .await?;
+        images.push((disk, image));
+    }
+    if matches!(target, StorageTarget::S3 { .. }) {
+        let _ = fs::remove_dir_all(&source_dir).await;
+    }
+
+    let mut volumes = Vec::new();
+    let result = create_vm(state, backup, vm_spec, name, &images, &mut volumes);
+    if result.is_err() {
+        for volume in &volumes {
+            let _ = state.delete_volume(&volume.meta.id);
+        }
+    }
+    let vm = result?;
+
+    state.emit_vm_event(
+        &vm.meta.id,
+        format!("Restored from backup {} of VM {}", backup.meta.name, backup.status.vm_name),
+    );
+    info!(
+        "Restored backup {} as VM {} with {} disks",
+        backup.meta.name,
+        vm.meta.name,
+        volumes.len()
+    );
+
+    Ok((vm, volumes))
+}
+
+/// Create the volumes and VM for restored images, recording created volumes
+fn create_vm(
+    state: &StateManager,
+    backup: &Backup,
+    mut spec: VmSpec,
+    name: &str,
+    images: &[(&BackupDisk, PathBuf)],
+    volumes: &mut Vec<Volume>,
+) -> Result<Vm> {
+    spec.boot_disk_id = None;
+    for (disk, path) in images {
+        let volume_spec = VolumeSpec {
+            kind: VolumeKind::Disk,
+            source: path.to_string_lossy().to_string(),
+            read_only: disk.read_only,
+            format: "qcow2".to_string(),
+            encryption: disk.encryption.clone(),
+            ..Default::default()
+        };
+        let volume = state.create_volume(
+            format!("{}-{}", name, disk.volume_name),
+            volume_spec,
+            HashMap::new(),
+        )?;
+        if disk.boot {
+            spec.boot_disk_id = Some(volume.meta.id.clone());
+        }
+        volumes.push(volume);
+    }
+
+    spec.volume_ids = volumes.iter().map(|v| v.meta.id.clone()).collect();
+    // Full copies no longer read through a template
+    spec.cloned_from = None;
+    // Networks and QoS profiles deleted since the backup are left out
+    let mut network_ids = Vec::new();
+    for id in spec.network_ids {
+        if state.get_network(&id)?.is_some() {
+            network_ids.push(id);
+        }
+    }
+    spec.network_interfaces
+        .retain(|a| network_ids.contains(&a.network_id));
+    spec.network_ids = network_ids;
+    if let Some(id) = &spec.qos_profile_id {
+        if state.get_qos_profile(id)?.is_none() {
+            spec.qos_profile_id = None;
+        }
+    }
+
+    let vm = state.create_vm(name.to_string(), spec, backup.meta.labels.clone())?;
+    let status = VmStatus {
+        state: VmState::Stopped,
+        ..Default::default()
+    };
+    state.update_vm_status(&vm.meta.id, status)?;
+
+    state.get_vm(&vm.meta.id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: vm.meta.id.clone(),
+    })
+}
+
+/// Why a backup can't be deleted, if it can't
+pub fn deletion_blocker(state: &StateManager, backup_id: &str) -> Result<Option<String>> {
+    let backup = get_backup(state, backup_id)?;
+    let children: Vec<String> = state
+        .list_backups(Some(&backup.spec.vm_id))?
+        .into_iter()
+        .filter(|b| b.status.parent_id.as_deref() == Some(backup_id))
+        .map(|b| b.meta.name)
+        .collect();
+
+    Ok(match children.as_slice() {
+        [] => None,
+        _ => Some(format!(
+            "incremental backups {} build on it",
+            children.join(", ")
+        )),
+    })
+}
+
+/// Delete a backup and its images
+pub async fn delete(state: &StateManager, backup_id: &str) -> Result<()> {
+    let backup = get_backup(state, backup_id)?;
+    if let Some(reason) = deletion_blocker(state, backup_id)? {
+        return Err(Error::InvalidConfig(format!(
+            "Backup {} can't be deleted: {}",
+            backup.meta.name, reason
+        )));
+    }
+
+    remove_files(state, &backup).await?;
+    state.delete_backup(backup_id)?;
+    info!("Deleted backup {}", backup.meta.name);
+    Ok(())
+}
+
+/// Remove a backup's images from its target
+async fn remove_files(state: &StateManager, backup: &Backup) -> Result<()> {
+    match StorageTarget::parse(&backup.spec.target)? {
+        StorageTarget::Dir(dir) => {
+            for disk in &backup.status.disks {
+                match fs::remove_file(dir.join(&backup.spec.vm_id).join(&disk.file)).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+        }
+        StorageTarget::S3 { bucket, prefix } => {
+            if backup.status.disks.is_empty() {
+                return Ok(());
+            }
+            let s3 = S3Client::new(state.config(), &bucket)?;
+            for disk in &backup.status.disks {
+                s3.delete(&object_key(&prefix, &backup.spec.vm_id, &disk.file))
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A backup and the backups it builds on, oldest first
+pub fn chain(state: &StateManager, backup: &Backup) -> Result<Vec<Backup>> {
+    let mut chain = vec![backup.clone()];
+    while let Some(parent_id) = chain.last().and_then(|b| b.status.parent_id.clone()) {
+        let parent = state.get_backup(&parent_id)?.ok_or_else(|| {
+            Error::BackupError(format!(
+                "Backup {} builds on missing backup {}",
+                backup.meta.name, parent_id
+            ))
+        })?;
+        chain.push(parent);
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+/// Most recent backup of a VM, complete or not
+pub fn latest(state: &StateManager, vm_id: &str) -> Result<Option<Backup>> {
+    Ok(state
+        .list_backups(Some(vm_id))?
+        .into_iter()
+        .max_by_key(|b| b.meta.created_at))
+}
+
+fn get_backup(state: &StateManager, backup_id: &str) -> Result<Backup> {
+    state.get_backup(backup_id)?.ok_or_else(|| Error::NotFound {
+        kind: "backup".to_string(),
+        id: backup_id.to_string(),
+    })
+}
+
+/// The VM's disk volumes and their active images, boot disk first
+fn source_disks(state: &StateManager, vm: &Vm) -> Result<Vec<SourceDisk>> {
+    let mut ids: Vec<&String> = vm.spec.boot_disk_id.iter().collect();
+    ids.extend(
+        vm.spec
+            .volume_ids
+            .iter()
+            .filter(|id| Some(*id) != vm.spec.boot_disk_id.as_ref()),
+    );
+
+    let mut disks = Vec::new();
+    for id in ids {
+        let volume = state.get_volume(id)?.ok_or_else(|| Error::NotFound {
+            kind: "volume".to_string(),
+            id: id.clone(),
+        })?;
+        if volume.spec.kind == VolumeKind::Device {
+            return Err(Error::BackupError(format!(
+                "Device volume {} can't be backed up; detach it first",
+                volume.meta.name
+            )));
+        }
+        let path = volume
+            .status
+            .local_path
+            .clone()
+            .filter(|_| volume.status.ready)
+            .ok_or_else(|| Error::BackupError(format!("Volume {} is not ready", volume.meta.name)))?;
+
+        disks.push(SourceDisk {
+            boot: Some(id) == vm.spec.boot_disk_id.as_ref(),
+            volume,
+            path,
+        });
+    }
+
+    if disks.is_empty() {
+        return Err(Error::BackupError(format!("VM {} has no disks", vm.meta.name)));
+    }
+    Ok(disks)
+}
+
+/// S3 key of a backup image
+fn object_key(prefix: &str, vm_id: &str, file: &str) -> String {
+    if prefix.is_empty() {
+        format!("{}/{}", vm_id, file)
+    } else {
+        format!("{}/{}/{}", prefix, vm_id, file)
+    }
+}
+
+fn virtual_size(path: &Path) -> Result<u64> {
+    snapshot::image_info(path)?["virtual-size"]
+        .as_u64()
+        .ok_or_else(|| Error::BackupError(format!("Unknown virtual size for {}", path.display())))
+}
@@ -0,0 +1,762 @@
+//! Packet capture on virtual networks
+//!
+//! A shared network's host bridge carries exactly the lab's traffic, so it is
+//! captured with `tcpdump`, which also applies a pcap filter. Other networks
+//! have no such interface: each running VM on them gets a QEMU `filter-dump`
+//! object on its NIC, and the per-VM files are merged by timestamp into one
+//! pcap when the capture stops. VMs started after the capture are not
+//! included. Size and time limits are checked every [`POLL_INTERVAL`].
+
+use crate::state::StateManager;
+use infrasim_common::qmp::QmpClient;
+use infrasim_common::types::{CaptureMethod, CaptureState, Network, NetworkCapture, NetworkMode, Vm};
+use infrasim_common::{Error, Result};
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Size limit of a capture that does not set one
+pub const DEFAULT_MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Time limit of a capture that does not set one
+pub const DEFAULT_MAX_SECONDS: u64 = 3600;
+
+/// Time between checks of a running capture's limits
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long tcpdump gets to reject its filter or interface
+const STARTUP_GRACE: Duration = Duration::from_millis(500);
+
+/// How long tcpdump gets to flush and exit after SIGTERM
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest packet record accepted when merging pcap files
+const MAX_RECORD_LEN: u32 = 256 * 1024;
+
+/// pcap file of a network's latest capture
+pub fn capture_path(state: &StateManager, network_id: &str) -> PathBuf {
+    state.config().network_dir(network_id).join("capture.pcap")
+}
+
+/// Per-VM `filter-dump` files of a running capture
+fn parts_dir(state: &StateManager, network_id: &str) -> PathBuf {
+    state.config().network_dir(network_id).join("capture-parts")
+}
+
+fn pid_path(state: &StateManager, network_id: &str) -> PathBuf {
+    state.config().network_dir(network_id).join("capture.pid")
+}
+
+/// QOM id of the `filter-dump` objects of a capture
+fn object_id(capture_id: &str) -> String {
+    format!("capture-{}", capture_id)
+}
+
+/// A `filter-dump` object on one VM's NIC
+struct DumpFilter {
+    qmp_socket: String,
+    object_id: String,
+}
+
+/// What is writing a running capture's packets
+enum Backend {
+    Tcpdump(Child),
+    FilterDump(Vec<DumpFilter>),
+}
+
+/// A capture's supervising task and the way to stop it
+struct RunningCapture {
+    id: String,
+    stop: oneshot::Sender<()>,
+    done: JoinHandle<()>,
+}
+
+/// Starts, stops and watches the limits of network captures
+#[derive(Clone)]
+pub struct CaptureManager {
+    state: StateManager,
+    running: Arc<Mutex<HashMap<String, RunningCapture>>>,
+}
+
+impl CaptureManager {
+    pub fn new(state: StateManager) -> Self {
+        Self {
+            state,
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Start capturing a network's packets, replacing its previous capture
+    pub async fn start(
+        &self,
+        network_id: &str,
+        filter: Option<String>,
+        max_bytes: u64,
+        max_seconds: u64,
+    ) -> Result<NetworkCapture> {
+        let network = self.state.get_network(network_id)?.ok_or_else(|| Error::NotFound {
+            kind: "network".to_string(),
+            id: network_id.to_string(),
+        })?;
+        let network_id = network.meta.id.clone();
+
+        let mut running = self.running.lock().await;
+        if running.contains_key(&network_id) {
+            return Err(Error::AlreadyExists {
+                kind: "capture".to_string(),
+                id: network_id,
+            });
+        }
+
+        let method = match (&network.status.bridge_interface, network.spec.mode) {
+            (Some(_), NetworkMode::VmnetShared) => CaptureMethod::Tcpdump,
+            _ => CaptureMethod::FilterDump,
+        };
+        if filter.is_some() && method == CaptureMethod::FilterDump {
+            return Err(Error::InvalidConfig(format!(
+                "Network {} is captured per VM with QEMU filter-dump, which cannot filter packets; \
+                 filters need a vmnet-shared network with a host bridge",
+                network.meta.name
+            )));
+        }
+
+        let path = capture_path(&self.state, &network_id);
+        let parts = parts_dir(&self.state, &network_id);
+        std::fs::create_dir_all(self.state.config().network_dir(&network_id))?;
+        remove_file(&path)?;
+        if parts.exists() {
+            std::fs::remove_dir_all(&parts)?;
+        }
+
+        let capture = NetworkCapture {
+            id: uuid::Uuid::new_v4().to_string(),
+            state: CaptureState::Running,
+            method,
+            filter,
+            max_bytes: if max_bytes == 0 { DEFAULT_MAX_BYTES } else { max_bytes },
+            max_seconds: if max_seconds == 0 { DEFAULT_MAX_SECONDS } else { max_seconds },
+            started_at: chrono::Utc::now().timestamp(),
+            ..Default::default()
+        };
+        let backend = match method {
+            CaptureMethod::Tcpdump => {
+                let bridge = network.status.bridge_interface.as_deref().unwrap_or_default();
+                let child = start_tcpdump(bridge, capture.filter.as_deref(), &path).await?;
+                if let Some(pid) = child.id() {
+                    std::fs::write(pid_path(&self.state, &network_id), pid.to_string())?;
+                }
+                Backend::Tcpdump(child)
+            }
+            CaptureMethod::FilterDump => {
+                Backend::FilterDump(start_filter_dump(&self.state, &network, &capture.id, &parts).await?)
+            }
+        };
+        info!(
+            "Capturing network {} with {} into {}",
+            network.meta.name,
+            method.as_str(),
+            path.display()
+        );
+        save(&self.state, &network_id, &capture);
+
+        let (stop, stopped) = oneshot::channel();
+        let done = tokio::spawn(self.clone().supervise(network_id.clone(), capture.clone(), backend, stopped));
+        running.insert(
+            network_id,
+            RunningCapture {
+                id: capture.id.clone(),
+                stop,
+                done,
+            },
+        );
+        Ok(capture)
+    }
+
+    /// Stop a network's running capture and return it as finished
+    pub async fn stop(&self, network_id: &str) -> Result<NetworkCapture> {
+        let capture = self.running.lock().await.remove(network_id).ok_or_else(|| Error::NotFound {
+            kind: "running capture".to_string(),
+            id: network_id.to_string(),
+        })?;
+        let _ = capture.stop.send(());
+        capture
+            .done
+            .await
+            .map_err(|e| Error::Internal(format!("Capture task failed: {}", e)))?;
+
+        self.state
+            .get_network(network_id)?
+            .and_then(|network| network.status.capture)
+            .ok_or_else(|| Error::NotFound {
+                kind: "capture".to_string(),
+                id: network_id.to_string(),
+            })
+    }
+
+    /// Stop a network's capture, if it has one running, before the network goes away
+    pub async fn discard(&self, network_id: &str) {
+        if self.running.lock().await.contains_key(network_id) {
+            let _ = self.stop(network_id).await;
+        }
+    }
+
+    /// Watch a capture's limits until it is stopped, then finish its pcap
+    async fn supervise(
+        self,
+        network_id: String,
+        mut capture: NetworkCapture,
+        mut backend: Backend,
+        mut stopped: oneshot::Receiver<()>,
+    ) {
+        let path = capture_path(&self.state, &network_id);
+        let parts = parts_dir(&self.state, &network_id);
+        let started = Instant::now();
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+
+        let outcome = loop {
+            tokio::select! {
+                _ = &mut stopped => break Ok("requested".to_string()),
+                _ = ticker.tick() => {
+                    if let Backend::Tcpdump(child) = &mut backend {
+                        if let Ok(Some(_)) = child.try_wait() {
+                            break Err(format!("tcpdump exited: {}", stderr_of(child).await));
+                        }
+                    }
+                    let bytes = match &backend {
+                        Backend::Tcpdump(_) => file_size(&path),
+                        Backend::FilterDump(_) => dir_size(&parts),
+                    };
+                    if bytes >= capture.max_bytes {
+                        break Ok("size limit reached".to_string());
+                    }
+                    if started.elapsed() >= Duration::from_secs(capture.max_seconds) {
+                        break Ok("time limit reached".to_string());
+                    }
+                    if bytes != capture.bytes {
+                        capture.bytes = bytes;
+                        save(&self.state, &network_id, &capture);
+                    }
+                }
+            }
+        };
+
+        let finished = match backend {
+            Backend::Tcpdump(child) => {
+                stop_tcpdump(child).await;
+                Ok(())
+            }
+            Backend::FilterDump(filters) => {
+                remove_filters(&filters).await;
+                merge_parts(&parts, &path)
+            }
+        };
+        let _ = std::fs::remove_file(pid_path(&self.state, &network_id));
+
+        capture.bytes = file_size(&path);
+        capture.stopped_at = Some(chrono::Utc::now().timestamp());
+        match outcome.and_then(|reason| finished.map(|_| reason).map_err(|e| e.to_string())) {
+            Ok(reason) => {
+                info!("Capture of network {} stopped: {}", network_id, reason);
+                capture.state = CaptureState::Stopped;
+                capture.stop_reason = Some(reason);
+            }
+            Err(error) => {
+                warn!("Capture of network {} failed: {}", network_id, error);
+                capture.state = CaptureState::Failed;
+                capture.stop_reason = Some(error);
+            }
+        }
+        save(&self.state, &network_id, &capture);
+
+        // Stopping on a limit leaves the entry behind; a stop request already took it
+        let mut running = self.running.lock().await;
+        if running.get(&network_id).is_some_and(|r| r.id == capture.id) {
+            running.remove(&network_id);
+        }
+    }
+}
+
+/// Finish captures a previous daemon left running
+///
+/// The `filter-dump` objects live on in the QEMU processes the daemon took
+/// back, and a tcpdump survives a daemon that crashed, so both are stopped
+/// and whatever they wrote is kept as a stopped capture.
+pub async fn recover(state: &StateManager) -> Result<()> {
+    for network in state.list_networks()? {
+        let Some(mut capture) = network.status.capture.clone() else {
+            continue;
+        };
+        if capture.state != CaptureState::Running {
+            continue;
+        }
+        let network_id = &network.meta.id;
+        let path = capture_path(state, network_id);
+
+        let finished = match capture.method {
+            CaptureMethod::Tcpdump => {
+                let pid = std::fs::read_to_string(pid_path(state, network_id))
+                    .ok()
+                    .and_then(|pid| pid.trim().parse::<i32>().ok());
+                if let Some(pid) = pid {
+                    let _ = kill(Pid::from_raw(pid), Signal::SIGTERM);
+                }
+                Ok(())
+            }
+            CaptureMethod::FilterDump => {
+                let filters: Vec<DumpFilter> = state
+                    .list_vm_processes()
+                    .into_iter()
+                    .map(|process| DumpFilter {
+                        qmp_socket: process.qmp_socket,
+                        object_id: object_id(&capture.id),
+                    })
+                    .collect();
+                remove_filters(&filters).await;
+                merge_parts(&parts_dir(state, network_id), &path)
+            }
+        };
+        let _ = std::fs::remove_file(pid_path(state, network_id));
+
+        capture.bytes = file_size(&path);
+        capture.stopped_at = Some(chrono::Utc::now().timestamp());
+        match finished {
+            Ok(()) => {
+                capture.state = CaptureState::Stopped;
+                capture.stop_reason = Some("daemon restarted".to_string());
+            }
+            Err(e) => {
+                capture.state = CaptureState::Failed;
+                capture.stop_reason = Some(e.to_string());
+            }
+        }
+        info!("Finished capture of network {} left by a previous daemon", network.meta.name);
+        save(state, network_id, &capture);
+    }
+    Ok(())
+}
+
+/// Record a capture in its network's status, re-reading the network so
+/// concurrent status changes are kept
+fn save(state: &StateManager, network_id: &str, capture: &NetworkCapture) {
+    let result = state.get_network(network_id).and_then(|network| match network {
+        Some(network) => {
+            let mut status = network.status;
+            status.capture = Some(capture.clone());
+            state.update_network_status(network_id, status)
+        }
+        None => Ok(()),
+    });
+    if let Err(e) = result {
+        warn!("Failed to record capture of network {}: {}", network_id, e);
+    }
+}
+
+async fn start_tcpdump(bridge: &str, filter: Option<&str>, path: &Path) -> Result<Child> {
+    let mut cmd = Command::new("tcpdump");
+    // -U writes each packet out as it arrives, so the size limit sees it
+    cmd.args(["-i", bridge, "-n", "-U", "-w"]).arg(path);
+    if let Some(filter) = filter {
+        cmd.arg(filter);
+    }
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| Error::NetworkError(format!("Failed to run tcpdump: {}", e)))?;
+
+    // A bad filter or interface makes tcpdump exit right away
+    tokio::time::sleep(STARTUP_GRACE).await;
+    if child.try_wait()?.is_some() {
+        return Err(Error::InvalidConfig(format!("tcpdump failed: {}", stderr_of(&mut child).await)));
+    }
+    Ok(child)
+}
+
+async fn stop_tcpdump(mut child: Child) {
+    if let Some(pid) = child.id() {
+        let _ = kill(Pid::from_raw(pid as i32), Signal::SIGTERM);
+    }
+    if tokio::time::timeout(STOP_TIMEOUT, child.wait()).await.is_err() {
+        warn!("tcpdump did not exit after SIGTERM, killing it");
+        let _ = child.kill().await;
+    }
+}
+
+/// Last line tcpdump wrote to stderr, which explains why it exited
+async fn stderr_of(child: &mut Child) -> String {
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr).await;
+    }
+    stderr
+        .lines()
+        .last()
+        .unwrap_or("no error output")
+        .trim()
+        .to_string()
+}
+
+/// Index of a network among a VM's NICs, which QEMU names `net<index>`
+///
+/// Mirrors the NIC ordering in [`crate::qemu::netdev_id`]: networks that no
+/// longer exist get no NIC.
+fn nic_index(state: &StateManager, vm: &Vm, network_id: &str) -> Option<usize> {
+    vm.spec
+        .network_ids
+        .iter()
+        .filter(|id| state.get_network(id).ok().flatten().is_some())
+        .position(|id| id == network_id)
+}
+
+/// Add a `filter-dump` to the NIC of every running VM on a network
+async fn start_filter_dump(
+    state: &StateManager,
+    network: &Network,
+    capture_id: &str,
+    parts: &Path,
+) -> Result<Vec<DumpFilter>> {
+    std::fs::create_dir_all(parts)?;
+    let mut filters = Vec::new();
+    for vm in state.list_vms()? {
+        let Some(index) = nic_index(state, &vm, &network.meta.id) else {
+            continue;
+        };
+        let Some(process) = state.get_vm_process(&vm.meta.id) else {
+            continue;
+        };
+
+        let filter = DumpFilter {
+            qmp_socket: process.qmp_socket,
+            object_id: object_id(capture_id),
+        };
+        let qmp = QmpClient::new(&filter.qmp_socket);
+        let added = async {
+            qmp.connect().await?;
+            qmp.object_add(serde_json::json!({
+                "qom-type": "filter-dump",
+                "id": filter.object_id,
+                "netdev": format!("net{}", index),
+                "file": parts.join(format!("{}.pcap", vm.meta.id)),
+            }))
+            .await
+        }
+        .await;
+        qmp.close().await;
+        if let Err(e) = added {
+            remove_filters(&filters).await;
+            return Err(Error::NetworkError(format!(
+                "Failed to capture VM {}'s NIC on network {}: {}",
+                vm.meta.name, network.meta.name, e
+            )));
+        }
+        filters.push(filter);
+    }
+
+    if filters.is_empty() {
+        return Err(Error::InvalidConfig(format!(
+            "Network {} has no running VMs to capture",
+            network.meta.name
+        )));
+    }
+    Ok(filters)
+}
+
+/// Delete `filter-dump` objects, skipping VMs that have stopped since
+async fn remove_filters(filters: &[DumpFilter]) {
+    for filter in filters {
+        let qmp = QmpClient::new(&filter.qmp_socket);
+        let removed = async {
+            qmp.connect().await?;
+            qmp.object_del(&filter.object_id).await
+        }
+        .await;
+        if let Err(e) = removed {
+            debug!("Could not remove {} from {}: {}", filter.object_id, filter.qmp_socket, e);
+        }
+        qmp.close().await;
+    }
+}
+
+/// Merge the per-VM files of a `filter-dump` capture and remove them
+fn merge_parts(parts: &Path, output: &Path) -> Result<()> {
+    let mut inputs = Vec::new();
+    if parts.exists() {
+        for entry in std::fs::read_dir(parts)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "pcap") {
+                inputs.push(path);
+            }
+        }
+    }
+    inputs.sort();
+    merge(&inputs, output)?;
+    if parts.exists() {
+        std::fs::remove_dir_all(parts)?;
+    }
+    Ok(())
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |m| m.len())
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir).map_or(0, |entries| {
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| file_size(&entry.path()))
+            .sum()
+    })
+}
+
+/// pcap magic number, written in the writer's byte order
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+
+/// Link type of Ethernet frames
+const LINKTYPE_ETHERNET: u32 = 1;
+
+/// One packet record of a pcap file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Packet {
+    ts_sec: u32,
+    ts_usec: u32,
+    orig_len: u32,
+    data: Vec<u8>,
+}
+
+impl Packet {
+    fn timestamp(&self) -> (u32, u32) {
+        (self.ts_sec, self.ts_usec)
+    }
+}
+
+/// Reader of classic pcap files with microsecond timestamps, in either byte order
+struct PcapReader<R> {
+    inner: R,
+    swapped: bool,
+    snaplen: u32,
+    linktype: u32,
+}
+
+impl<R: Read> PcapReader<R> {
+    fn new(mut inner: R) -> Result<Self> {
+        let mut header = [0u8; 24];
+        inner.read_exact(&mut header)?;
+        let swapped = match u32::from_le_bytes(header[0..4].try_into().unwrap()) {
+            PCAP_MAGIC => false,
+            magic if magic.swap_bytes() == PCAP_MAGIC => true,
+            magic => return Err(Error::NetworkError(format!("Not a pcap file (magic {:#010x})", magic))),
+        };
+        let mut reader = Self {
+            inner,
+            swapped,
+            snaplen: 0,
+            linktype: 0,
+        };
+        reader.snaplen = reader.field(&header[16..20]);
+        reader.linktype = reader.field(&header[20..24]);
+        Ok(reader)
+    }
+
+    fn field(&self, bytes: &[u8]) -> u32 {
+        let value = u32::from_le_bytes(bytes.try_into().unwrap());
+        if self.swapped {
+            value.swap_bytes()
+        } else {
+            value
+        }
+    }
+
+    /// Next packet, or None at the end of the file
+    ///
+    /// A record cut short by a writer that was stopped mid-packet also ends
+    /// the file.
+    fn next_packet(&mut self) -> Result<Option<Packet>> {
+        let mut header = [0u8; 16];
+        if !read_full(&mut self.inner, &mut header)? {
+            return Ok(None);
+        }
+        let incl_len = self.field(&header[8..12]);
+        if incl_len > MAX_RECORD_LEN {
+            return Err(Error::NetworkError(format!("pcap record of {} bytes is too large", incl_len)));
+        }
+        let mut data = vec![0u8; incl_len as usize];
+        if !read_full(&mut self.inner, &mut data)? {
+            return Ok(None);
+        }
+        Ok(Some(Packet {
+            ts_sec: self.field(&header[0..4]),
+            ts_usec: self.field(&header[4..8]),
+            orig_len: self.field(&header[12..16]),
+            data,
+        }))
+    }
+}
+
+/// Fill `buf`, returning false if the reader ends first
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => return Ok(false),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Merge pcap files into one little-endian file, ordering packets by timestamp
+///
+/// Empty inputs, from VMs whose QEMU never flushed a header, are skipped.
+fn merge(inputs: &[PathBuf], output: &Path) -> Result<()> {
+    let mut readers = Vec::new();
+    for path in inputs {
+        if file_size(path) == 0 {
+            continue;
+        }
+        let reader = PcapReader::new(BufReader::new(std::fs::File::open(path)?))
+            .map_err(|e| Error::NetworkError(format!("{}: {}", path.display(), e)))?;
+        readers.push(reader);
+    }
+
+    let linktype = readers.first().map_or(LINKTYPE_ETHERNET, |r| r.linktype);
+    if let Some(other) = readers.iter().find(|r| r.linktype != linktype) {
+        return Err(Error::NetworkError(format!(
+            "Cannot merge pcap files of link types {} and {}",
+            linktype, other.linktype
+        )));
+    }
+    let snaplen = readers.iter().map(|r| r.snaplen).max().unwrap_or(65535);
+
+    let mut out = BufWriter::new(std::fs::File::create(output)?);
+    out.write_all(&PCAP_MAGIC.to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&4u16.to_le_bytes())?;
+    out.write_all(&0i32.to_le_bytes())?;
+    out.write_all(&0u32.to_le_bytes())?;
+    out.write_all(&snaplen.to_le_bytes())?;
+    out.write_all(&linktype.to_le_bytes())?;
+
+    // Few VMs share a network, so a linear scan for the earliest head is enough
+    let mut heads = Vec::with_capacity(readers.len());
+    for reader in &mut readers {
+        heads.push(reader.next_packet()?);
+    }
+    loop {
+        let next = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(i, head)| head.as_ref().map(|p| (i, p.timestamp())))
+            .min_by_key(|(_, timestamp)| *timestamp)
+            .map(|(i, _)| i);
+        let Some(i) = next else {
+            break;
+        };
+        let packet = heads[i].take().expect("head was chosen for being present");
+        out.write_all(&packet.ts_sec.to_le_bytes())?;
+        out.write_all(&packet.ts_usec.to_le_bytes())?;
+        out.write_all(&(packet.data.len() as u32).to_le_bytes())?;
+        out.write_all(&packet.orig_len.to_le_bytes())?;
+        out.write_all(&packet.data)?;
+        heads[i] = readers[i].next_packet()?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pcap(big_endian: bool, packets: &[(u32, u32, &[u8])]) -> Vec<u8> {
+        let word = |v: u32| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        let half = |v: u16| if big_endian { v.to_be_bytes() } else { v.to_le_bytes() };
+        let mut out = Vec::new();
+        out.extend(word(PCAP_MAGIC));
+        out.extend(half(2));
+        out.extend(half(4));
+        out.extend(word(0));
+        out.extend(word(0));
+        out.extend(word(65535));
+        out.extend(word(LINKTYPE_ETHERNET));
+        for (sec, usec, data) in packets {
+            out.extend(word(*sec));
+            out.extend(word(*usec));
+            out.extend(word(data.len() as u32));
+            out.extend(word(data.len() as u32));
+            out.extend_from_slice(data);
+        }
+        out
+    }
+
+    fn read_all(path: &Path) -> Vec<Packet> {
+        let mut reader = PcapReader::new(std::fs::File::open(path).unwrap()).unwrap();
+        let mut packets = Vec::new();
+        while let Some(packet) = reader.next_packet().unwrap() {
+            packets.push(packet);
+        }
+        packets
+    }
+
+    #[test]
+    fn test_merge_orders_by_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.pcap");
+        let b = dir.path().join("b.pcap");
+        let empty = dir.path().join("empty.pcap");
+        std::fs::write(&a, pcap(false, &[(1, 500, b"a1"), (3, 0, b"a2")])).unwrap();
+        std::fs::write(&b, pcap(true, &[(1, 100, b"b1"), (2, 0, b"b2"), (4, 0, b"b3")])).unwrap();
+        std::fs::write(&empty, b"").unwrap();
+
+        let out = dir.path().join("out.pcap");
+        merge(&[a, b, empty], &out).unwrap();
+
+        let data: Vec<Vec<u8>> = read_all(&out).into_iter().map(|p| p.data).collect();
+        assert_eq!(data, vec![b"b1".to_vec(), b"a1".to_vec(), b"b2".to_vec(), b"a2".to_vec(), b"b3".to_vec()]);
+        assert_eq!(&std::fs::read(&out).unwrap()[0..4], &PCAP_MAGIC.to_le_bytes());
+    }
+
+    #[test]
+    fn test_merge_truncated_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.pcap");
+        let mut bytes = pcap(false, &[(1, 0, b"whole"), (2, 0, b"cut short")]);
+        bytes.truncate(bytes.len() - 3);
+        std::fs::write(&a, bytes).unwrap();
+
+        let out = dir.path().join("out.pcap");
+        merge(&[a], &out).unwrap();
+        let packets = read_all(&out);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].data, b"whole");
+    }
+
+    #[test]
+    fn test_merge_rejects_non_pcap() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.pcap");
+        std::fs::write(&a, [0u8; 24]).unwrap();
+        assert!(merge(&[a], &dir.path().join("out.pcap")).is_err());
+    }
+}
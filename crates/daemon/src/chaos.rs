@@ -0,0 +1,248 @@
+//! Chaos profiles
+//!
+//! A chaos profile injects netem-style faults into the NICs it targets: all
+//! NICs on a network, all NICs of a VM, or one VM's NIC on a network. The
+//! faults ride on the relays of [`crate::qos`], so they apply at runtime only
+//! to VMs whose NICs are relayed; other VMs get relays, and the faults, the
+//! next time they start.
+//!
+//! Partitions split VMs into groups and drop the frames between groups. Peers
+//! are matched by MAC and, where the daemon knows them, IP addresses, so a
+//! partition also holds for traffic a router passes on.
+
+use crate::addresses::{self, NicAddress};
+use crate::state::StateManager;
+use infrasim_common::{
+    traffic_shaper::FaultInjection,
+    types::{ChaosProfile, ChaosProfileSpec, ChaosProfileStatus, Network, Vm},
+    Error, Result,
+};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Bring the faults injected into every running VM in line with the chaos
+/// profiles, deleting profiles that have expired
+pub fn apply(state: &StateManager) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let mut profiles = Vec::new();
+    for profile in state.list_chaos_profiles()? {
+        if profile.spec.expires_at.is_some_and(|at| at <= now) {
+            state.delete_chaos_profile(&profile.meta.id)?;
+            info!("Chaos profile {} expired", profile.meta.name);
+            for vm_id in &profile.status.applied_vm_ids {
+                state.emit_vm_event(vm_id, format!("Chaos profile {} expired", profile.meta.name));
+            }
+        } else {
+            profiles.push(profile);
+        }
+    }
+
+    let mut statuses: Vec<ChaosProfileStatus> = vec![ChaosProfileStatus::default(); profiles.len()];
+    let mut peers = HashMap::new();
+    for process in state.list_vm_processes() {
+        let Some(vm) = state.get_vm(&process.vm_id)? else {
+            continue;
+        };
+        let networks = vm_networks(state, &vm);
+        let relayed = state.has_qos_link(&vm.meta.id);
+        let mut faults: Vec<Option<FaultInjection>> = vec![None; networks.len().max(1)];
+
+        for (profile, status) in profiles.iter().zip(statuses.iter_mut()) {
+            let nics = target_nics(&profile.spec, &vm, &networks);
+            if nics.is_empty() {
+                continue;
+            }
+            if !relayed {
+                status.pending_vm_ids.push(vm.meta.id.clone());
+                continue;
+            }
+            status.applied_vm_ids.push(vm.meta.id.clone());
+
+            let injection = injection(state, &profile.spec, &vm.meta.id, &mut peers)?;
+            for nic in nics {
+                match &mut faults[nic] {
+                    Some(existing) => existing.merge(injection.clone()),
+                    slot => *slot = Some(injection.clone()),
+                }
+            }
+        }
+
+        if relayed {
+            state.set_qos_faults(&vm.meta.id, faults);
+        }
+    }
+
+    for (profile, status) in profiles.into_iter().zip(statuses) {
+        if status == profile.status {
+            continue;
+        }
+        for vm_id in status.applied_vm_ids.iter().filter(|id| !profile.status.applied_vm_ids.contains(id)) {
+            state.emit_vm_event(vm_id, format!("Chaos profile {} applied", profile.meta.name));
+        }
+        for vm_id in status.pending_vm_ids.iter().filter(|id| !profile.status.pending_vm_ids.contains(id)) {
+            state.emit_vm_event(
+                vm_id,
+                format!(
+                    "Chaos profile {} applies once the VM is restarted; its NICs aren't relayed",
+                    profile.meta.name
+                ),
+            );
+        }
+        if let Err(e) = state.update_chaos_profile_status(&profile.meta.id, status) {
+            warn!("Failed to update status of chaos profile {}: {}", profile.meta.name, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a chaos profile, or replace the spec of the one named `name`, and
+/// inject its faults
+pub fn upsert(
+    state: &StateManager,
+    name: String,
+    spec: ChaosProfileSpec,
+    labels: HashMap<String, String>,
+) -> Result<ChaosProfile> {
+    spec.validate()?;
+    if let Some(id) = &spec.network_id {
+        state.get_network(id)?.ok_or_else(|| Error::NotFound {
+            kind: "network".to_string(),
+            id: id.clone(),
+        })?;
+    }
+    for id in spec.vm_id.iter().chain(spec.partitions.iter().flatten()) {
+        state.get_vm(id)?.ok_or_else(|| Error::NotFound {
+            kind: "vm".to_string(),
+            id: id.clone(),
+        })?;
+    }
+
+    let id = match state.get_chaos_profile_by_name(&name)? {
+        Some(current) => {
+            state.update_chaos_profile_spec(&current.meta.id, &spec)?;
+            current.meta.id
+        }
+        None => state.create_chaos_profile(name, spec, labels)?.meta.id,
+    };
+
+    apply(state)?;
+    state.get_chaos_profile(&id)?.ok_or_else(|| Error::NotFound {
+        kind: "chaos_profile".to_string(),
+        id,
+    })
+}
+
+/// Whether any chaos profile targets a NIC of `vm`, so it needs relays
+pub fn targets_vm(state: &StateManager, vm: &Vm) -> Result<bool> {
+    let networks = vm_networks(state, vm);
+    Ok(state
+        .list_chaos_profiles()?
+        .iter()
+        .any(|profile| !target_nics(&profile.spec, vm, &networks).is_empty()))
+}
+
+/// Stop injecting a deleted profile's faults
+pub fn remove(state: &StateManager, profile: &ChaosProfile) -> Result<()> {
+    apply(state)?;
+    for vm_id in &profile.status.applied_vm_ids {
+        state.emit_vm_event(vm_id, format!("Chaos profile {} removed", profile.meta.name));
+    }
+    Ok(())
+}
+
+/// The VM's networks in NIC order, as QEMU wires them
+fn vm_networks(state: &StateManager, vm: &Vm) -> Vec<Network> {
+    vm.spec
+        .network_ids
+        .iter()
+        .filter_map(|id| state.get_network(id).ok().flatten())
+        .collect()
+}
+
+/// Indices of the NICs of `vm` a profile targets
+fn target_nics(spec: &ChaosProfileSpec, vm: &Vm, networks: &[Network]) -> Vec<usize> {
+    if spec.vm_id.as_ref().is_some_and(|id| *id != vm.meta.id) {
+        return Vec::new();
+    }
+    match &spec.network_id {
+        Some(network_id) => networks
+            .iter()
+            .enumerate()
+            .filter(|(_, network)| network.meta.id == *network_id)
+            .map(|(idx, _)| idx)
+            .collect(),
+        None if spec.vm_id.is_some() => (0..networks.len().max(1)).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Faults a profile injects into the NICs of `vm_id`, with the addresses of
+/// the VMs partitioned from it blocked
+fn injection(
+    state: &StateManager,
+    spec: &ChaosProfileSpec,
+    vm_id: &str,
+    peers: &mut HashMap<String, Vec<NicAddress>>,
+) -> Result<FaultInjection> {
+    let mut injection = FaultInjection {
+        faults: spec.faults.clone(),
+        ..Default::default()
+    };
+    let Some(group) = spec.partition_of(vm_id) else {
+        return Ok(injection);
+    };
+
+    let others = spec
+        .partitions
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != group)
+        .flat_map(|(_, vms)| vms);
+    for peer_id in others {
+        if !peers.contains_key(peer_id) {
+            let nics = match state.get_vm(peer_id)? {
+                Some(peer) => {
+                    let networks = vm_networks(state, &peer);
+                    addresses::vm_addresses(state.config(), &peer, &networks)
+                }
+                None => Vec::new(),
+            };
+            peers.insert(peer_id.clone(), nics);
+        }
+
+        for nic in &peers[peer_id] {
+            if let Some(mac) = parse_mac(&nic.mac) {
+                injection.blocked_macs.insert(mac);
+            }
+            // Every user-mode NIC has the same address behind its own slirp
+            if let Some(ip) = nic.ip.filter(|_| nic.source != "user-mode") {
+                injection.blocked_ips.insert(ip);
+            }
+        }
+    }
+
+    Ok(injection)
+}
+
+fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let mut bytes = [0u8; 6];
+    let mut parts = mac.split(':');
+    for byte in &mut bytes {
+        *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+    }
+    parts.next().is_none().then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac() {
+        assert_eq!(parse_mac("52:54:00:ab:cd:ef"), Some([0x52, 0x54, 0x00, 0xab, 0xcd, 0xef]));
+        assert_eq!(parse_mac("52:54:00:ab:cd"), None);
+        assert_eq!(parse_mac("52:54:00:ab:cd:ef:01"), None);
+        assert_eq!(parse_mac("52:54:00:ab:cd:zz"), None);
+    }
+}
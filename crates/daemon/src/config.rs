@@ -1,28 +1,119 @@
 //! Daemon configuration
+//!
+//! Loaded from `~/.infrasim/config.toml` (or `infrasimd --config`). Every
+//! section is optional and missing keys take their defaults; unknown keys
+//! are rejected so typos don't silently fall back to a default.
+//!
+//! On SIGHUP the daemon re-reads the file. The [`Live`] sections (`qemu`,
+//! `vm_defaults`, `network`, `registry`, `s3`, `admission`, `idle`) are
+//! swapped in place and apply to the next VM started or request served;
+//! running VMs are left alone. Changes to the other sections are reported
+//! and need a restart.
 
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use anyhow::bail;
+use infrasim_common::auth::ClientCredentials;
+use parking_lot::RwLock;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
 
 /// Daemon configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct DaemonConfig {
     /// Store directory path
     pub store_path: PathBuf,
 
-    /// gRPC listen address
+    /// gRPC listen address; empty to serve only on the Unix socket
     pub grpc_listen: String,
 
+    /// gRPC Unix socket
+    pub socket: SocketConfig,
+
     /// Web console port
     pub web_port: u16,
 
-    /// QEMU configuration
-    pub qemu: QemuConfig,
+    /// QEMU configuration (reloadable)
+    pub qemu: Live<QemuConfig>,
+
+    /// Resources for VMs created without them (reloadable)
+    pub vm_defaults: Live<VmDefaultsConfig>,
 
-    /// Network configuration
-    pub network: NetworkConfig,
+    /// Network configuration (reloadable)
+    pub network: Live<NetworkConfig>,
 
     /// Security configuration
     pub security: SecurityConfig,
+
+    /// S3-compatible object storage for backups and store remotes (reloadable)
+    pub s3: Live<S3Config>,
+
+    /// OCI registries appliances are pushed to and pulled from (reloadable)
+    pub registry: Live<RegistryConfig>,
+
+    /// Background job queue
+    pub jobs: JobsConfig,
+
+    /// gRPC API authentication
+    pub auth: AuthConfig,
+
+    /// Audit log of mutating calls
+    pub audit: AuditConfig,
+
+    /// Host resource admission control (reloadable)
+    pub admission: Live<AdmissionConfig>,
+
+    /// Suspension of idle VMs (reloadable)
+    pub idle: Live<IdleConfig>,
+
+    /// Replication of state to a standby daemon
+    pub replication: ReplicationConfig,
+}
+
+/// A configuration section that can be replaced while the daemon runs
+///
+/// Clones share the section, so every component holding a copy of the
+/// config sees a reload. Read it with [`Live::get`] where it's used rather
+/// than keeping the value around.
+#[derive(Debug, Default)]
+pub struct Live<T>(Arc<RwLock<T>>);
+
+impl<T> Live<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(RwLock::new(value)))
+    }
+}
+
+impl<T: Clone> Live<T> {
+    /// Current value of the section
+    pub fn get(&self) -> T {
+        self.0.read().clone()
+    }
+
+    fn set(&self, value: T) {
+        *self.0.write() = value;
+    }
+}
+
+impl<T> Clone for Live<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Serialize> Serialize for Live<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.read().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Live<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
 }
 
 impl Default for DaemonConfig {
@@ -30,21 +121,38 @@ impl Default for DaemonConfig {
         Self {
             store_path: infrasim_common::default_store_path(),
             grpc_listen: "127.0.0.1:9090".to_string(),
+            socket: SocketConfig::default(),
             web_port: 6080,
-            qemu: QemuConfig::default(),
-            network: NetworkConfig::default(),
+            qemu: Live::default(),
+            vm_defaults: Live::default(),
+            network: Live::default(),
             security: SecurityConfig::default(),
+            s3: Live::default(),
+            registry: Live::default(),
+            jobs: JobsConfig::default(),
+            auth: AuthConfig::default(),
+            audit: AuditConfig::default(),
+            admission: Live::default(),
+            idle: Live::default(),
+            replication: ReplicationConfig::default(),
         }
     }
 }
 
 /// QEMU-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct QemuConfig {
     /// Path to qemu-system-aarch64 binary
     pub binary_path: Option<String>,
 
-    /// Default accelerator
+    /// QEMU binary per guest architecture, e.g. `x86_64 = "/opt/qemu/bin/qemu-system-x86_64"`;
+    /// architectures not listed use `qemu-system-<arch>` from `PATH`
+    pub binaries: BTreeMap<String, String>,
+
+    /// Accelerator: `auto` picks HVF on macOS or KVM on Linux when the
+    /// guest architecture matches the host, TCG otherwise; `hvf`, `kvm` or
+    /// `tcg` force one
     pub accelerator: String,
 
     /// Default machine type
@@ -56,29 +164,144 @@ pub struct QemuConfig {
     /// Enable HVF (Hypervisor.framework) on macOS
     pub enable_hvf: bool,
 
+    /// Enable KVM on Linux
+    pub enable_kvm: bool,
+
     /// VNC base port
     pub vnc_base_port: u16,
 
     /// QMP socket directory
     pub qmp_socket_dir: Option<PathBuf>,
+
+    /// Upper bound for hot-plugged vCPUs (`-smp maxcpus`)
+    pub max_cpus: u32,
+
+    /// Upper bound for hot-plugged memory in MB (`-m maxmem`)
+    pub max_memory_mb: u64,
+
+    /// PCIe root ports reserved for hot-plugged disks
+    pub hotplug_slots: u32,
+
+    /// Share the guest clipboard with VNC clients (needs QEMU built with
+    /// qemu-vdagent and spice-vdagent in the guest)
+    pub vnc_clipboard: bool,
+
+    /// Seconds a guest gets to power off on stop before QEMU is terminated
+    pub stop_timeout_secs: u64,
+
+    /// virtiofsd for shared folders; found in `PATH` or libexec when unset.
+    /// Without it, folders are shared over 9p
+    pub virtiofsd_path: Option<String>,
+}
+
+impl QemuConfig {
+    /// QEMU binary for a guest architecture
+    ///
+    /// `binaries` wins, then `binary_path` for aarch64 guests, then
+    /// `qemu-system-<arch>` from `PATH`.
+    pub fn binary_for(&self, arch: &str) -> String {
+        let arch = if arch.is_empty() { "aarch64" } else { arch };
+        if let Some(binary) = self.binaries.get(arch) {
+            return binary.clone();
+        }
+        match &self.binary_path {
+            Some(path) if arch == "aarch64" => path.clone(),
+            _ => format!("qemu-system-{}", arch),
+        }
+    }
+}
+
+fn default_max_cpus() -> u32 {
+    8
+}
+
+fn default_max_memory_mb() -> u64 {
+    16384
+}
+
+fn default_hotplug_slots() -> u32 {
+    4
+}
+
+fn default_vnc_clipboard() -> bool {
+    true
+}
+
+fn default_stop_timeout_secs() -> u64 {
+    30
 }
 
 impl Default for QemuConfig {
     fn default() -> Self {
         Self {
             binary_path: None, // Will auto-detect
-            accelerator: "hvf".to_string(),
+            binaries: BTreeMap::new(),
+            accelerator: "auto".to_string(),
             machine_type: "virt,highmem=on".to_string(),
             cpu_type: "host".to_string(),
             enable_hvf: true,
+            enable_kvm: true,
             vnc_base_port: 5900,
             qmp_socket_dir: None,
+            max_cpus: default_max_cpus(),
+            max_memory_mb: default_max_memory_mb(),
+            hotplug_slots: default_hotplug_slots(),
+            vnc_clipboard: default_vnc_clipboard(),
+            stop_timeout_secs: default_stop_timeout_secs(),
+            virtiofsd_path: None,
+        }
+    }
+}
+
+/// Resources given to VMs whose spec leaves them unset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VmDefaultsConfig {
+    /// Guest architecture
+    pub arch: String,
+
+    /// Machine type
+    pub machine: String,
+
+    /// vCPUs
+    pub cpu_cores: u32,
+
+    /// Memory in MB
+    pub memory_mb: u64,
+}
+
+impl Default for VmDefaultsConfig {
+    fn default() -> Self {
+        Self {
+            arch: "aarch64".to_string(),
+            machine: "virt".to_string(),
+            cpu_cores: 2,
+            memory_mb: 2048,
+        }
+    }
+}
+
+impl VmDefaultsConfig {
+    /// Fill the fields a VM spec left empty or zero
+    pub fn apply(&self, spec: &mut infrasim_common::types::VmSpec) {
+        if spec.arch.is_empty() {
+            spec.arch = self.arch.clone();
+        }
+        if spec.machine.is_empty() {
+            spec.machine = self.machine.clone();
+        }
+        if spec.cpu_cores == 0 {
+            spec.cpu_cores = self.cpu_cores;
+        }
+        if spec.memory_mb == 0 {
+            spec.memory_mb = self.memory_mb;
         }
     }
 }
 
 /// Network configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct NetworkConfig {
     /// Default network mode
     pub default_mode: String,
@@ -86,8 +309,18 @@ pub struct NetworkConfig {
     /// Default CIDR for user-mode networking
     pub default_cidr: String,
 
-    /// Enable vmnet (requires entitlement on macOS)
+    /// Back shared and bridged networks with the host: vmnet on macOS
+    /// (requires the entitlement), tap devices and bridges on Linux (requires
+    /// CAP_NET_ADMIN). Off, they fall back to user-mode networking.
     pub enable_vmnet: bool,
+
+    /// Host interface bridged networks attach to: an existing bridge on
+    /// Linux (default `br0`), a network interface on macOS (default `en0`)
+    pub bridge_interface: Option<String>,
+
+    /// Wire every VM's NICs through the daemon's relays, so chaos profiles
+    /// apply to running VMs without a restart
+    pub chaos_relays: bool,
 }
 
 impl Default for NetworkConfig {
@@ -96,47 +329,475 @@ impl Default for NetworkConfig {
             default_mode: "user".to_string(),
             default_cidr: "10.42.0.0/24".to_string(),
             enable_vmnet: false,
+            bridge_interface: None,
+            chaos_relays: false,
         }
     }
 }
 
 /// Security configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct SecurityConfig {
     /// Path to signing key
     pub signing_key_path: Option<PathBuf>,
 
+    /// Path to the key secrets are encrypted with
+    pub secrets_key_path: Option<PathBuf>,
+
     /// Encrypt memory snapshots at rest
     pub encrypt_snapshots: bool,
 
     /// Enable attestation
     pub enable_attestation: bool,
+
+    /// Accept raw QMP commands over `ExecuteQMP`; they bypass the daemon's
+    /// view of the VM, so any caller can e.g. detach disks behind its back
+    pub allow_qmp_passthrough: bool,
 }
 
 impl Default for SecurityConfig {
     fn default() -> Self {
         Self {
             signing_key_path: None,
+            secrets_key_path: None,
             encrypt_snapshots: true,
             enable_attestation: true,
+            allow_qmp_passthrough: false,
+        }
+    }
+}
+
+/// S3-compatible object storage configuration
+///
+/// Credentials are read from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`
+/// and `AWS_SESSION_TOKEN`, never from the configuration file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Config {
+    /// Endpoint URL (default `AWS_ENDPOINT_URL`, then AWS S3)
+    pub endpoint: Option<String>,
+
+    /// Region (default `AWS_REGION`, then `us-east-1`)
+    pub region: Option<String>,
+}
+
+/// OCI registry configuration
+///
+/// Credentials come from `INFRASIM_REGISTRY_USERNAME` and
+/// `INFRASIM_REGISTRY_PASSWORD`, or from the `auths` entries `docker login`
+/// writes to `~/.docker/config.json` (or `$DOCKER_CONFIG/config.json`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegistryConfig {
+    /// Registry hosts (with port) reached over plain HTTP, e.g. `localhost:5000`
+    pub insecure: Vec<String>,
+}
+
+/// Background job queue configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    /// Jobs run at the same time
+    pub workers: usize,
+}
+
+fn default_job_workers() -> usize {
+    2
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            workers: default_job_workers(),
+        }
+    }
+}
+
+/// gRPC Unix socket configuration
+///
+/// Access is controlled by the socket's file mode, so local clients need no
+/// open TCP port, certificates or tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SocketConfig {
+    /// Serve gRPC on the socket
+    pub enabled: bool,
+
+    /// Socket path (default `<store>/daemon.sock`)
+    pub path: Option<PathBuf>,
+
+    /// File mode of the socket, e.g. 0o660 to admit the owning group
+    /// (TOML has octal integers: `mode = 0o660`)
+    pub mode: u32,
+}
+
+fn default_socket_enabled() -> bool {
+    true
+}
+
+fn default_socket_mode() -> u32 {
+    0o600
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_socket_enabled(),
+            path: None,
+            mode: default_socket_mode(),
+        }
+    }
+}
+
+/// gRPC API authentication
+///
+/// Both checks are off by default, which is only safe while the daemon
+/// listens on loopback. Client certificates and tokens are managed with
+/// `infrasim auth`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Serve TLS and require a client certificate issued by the daemon CA
+    pub mtls: bool,
+
+    /// Require an `authorization: Bearer <token>` header on every call
+    pub require_token: bool,
+
+    /// Extra DNS names and IPs for the serving certificate; localhost and
+    /// the listen address are always included
+    pub server_names: Vec<String>,
+}
+
+/// Audit log of mutating calls
+///
+/// Every mutating call is recorded regardless; see `infrasim audit list`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    /// Chain entries by hash and sign each with the daemon's signing key, so
+    /// `infrasim audit verify` detects rows edited or removed in the database
+    pub hash_chain: bool,
+}
+
+/// Host resource admission control
+///
+/// Memory and vCPUs of running and paused VMs count as committed. Starting a
+/// VM fails when it would commit more than the host has times the overcommit
+/// ratio, or leave the store's filesystem with too little free space.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdmissionConfig {
+    /// Check VM starts against the host's capacity
+    pub enabled: bool,
+
+    /// Memory VMs may commit, as a multiple of host memory
+    pub memory_overcommit_ratio: f64,
+
+    /// Memory kept back for the host itself, in MB
+    pub reserved_memory_mb: u64,
+
+    /// vCPUs VMs may commit, as a multiple of host CPUs
+    pub cpu_overcommit_ratio: f64,
+
+    /// Free space the store's filesystem must keep, in MB
+    pub min_free_disk_mb: u64,
+
+    /// Pause or suspend low-priority VMs while the host has less memory
+    /// available than this, in MB; 0 disables
+    pub pressure_free_mb: u64,
+
+    /// Bring them back once available memory is above this, in MB
+    pub resume_free_mb: u64,
+
+    /// VMs with a priority below this are paused under memory pressure
+    pub pressure_priority: i32,
+
+    /// `pause` keeps their RAM resident; `suspend` saves it to disk and frees it
+    pub pressure_action: String,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            memory_overcommit_ratio: 1.0,
+            reserved_memory_mb: 2048,
+            cpu_overcommit_ratio: 4.0,
+            min_free_disk_mb: 2048,
+            pressure_free_mb: 512,
+            resume_free_mb: 2048,
+            pressure_priority: 0,
+            pressure_action: "pause".to_string(),
+        }
+    }
+}
+
+/// Suspension of idle VMs
+///
+/// A running VM is idle while no console is connected, its CPU use stays
+/// under `cpu_percent` and its network relays carry no traffic. VMs labelled
+/// `infrasim.io/idle-suspend=false` are left alone. Connecting a console to
+/// a VM put to sleep this way wakes it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdleConfig {
+    /// Pause or suspend idle VMs
+    pub enabled: bool,
+
+    /// How long a VM must stay idle, in minutes
+    pub idle_minutes: u64,
+
+    /// CPU use, as a share of one host core, below which a VM counts as idle
+    pub cpu_percent: f64,
+
+    /// `suspend` saves RAM to disk and frees it; `pause` keeps it resident
+    pub action: String,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: 30,
+            cpu_percent: 5.0,
+            action: "suspend".to_string(),
+        }
+    }
+}
+
+/// Replication of state to a standby daemon
+///
+/// A daemon with `primary` set is a standby: it copies the primary's state
+/// DB and CAS objects and refuses changes until `infrasim daemon promote`.
+/// VMs don't move, so promoting marks those that ran on the primary stopped.
+/// Copy the primary's `secrets.key` to the standby to keep secrets readable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplicationConfig {
+    /// Serve state snapshots and CAS objects to standbys
+    pub allow_standbys: bool,
+
+    /// gRPC address of the primary to replicate from
+    pub primary: Option<String>,
+
+    /// Credentials presented to the primary, as in a CLI context
+    pub credentials: ClientCredentials,
+
+    /// Seconds between checks for changes on the primary
+    pub interval_secs: u64,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            allow_standbys: false,
+            primary: None,
+            credentials: ClientCredentials::default(),
+            interval_secs: 10,
         }
     }
 }
 
 impl DaemonConfig {
     /// Load configuration from file
-    pub fn load(path: &std::path::Path) -> anyhow::Result<Self> {
-        if path.exists() {
-            let content = std::fs::read_to_string(path)?;
-            let config: Self = toml::from_str(&content)?;
-            Ok(config)
+    ///
+    /// A missing file gives the defaults. Parse errors name the file, line
+    /// and offending key.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let mut config: Self = toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e))?;
+        config.store_path = expand_tilde(&config.store_path);
+        config.socket.path = config.socket.path.as_deref().map(expand_tilde);
+        Ok(config)
+    }
+
+    /// Check the configuration, reporting every problem at once
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+
+        if !self.grpc_listen.is_empty() && self.grpc_listen.parse::<SocketAddr>().is_err() {
+            problems.push(format!(
+                "grpc_listen: '{}' is not an address like 127.0.0.1:9090",
+                self.grpc_listen
+            ));
+        }
+        if self.grpc_listen.is_empty() && !self.socket.enabled {
+            problems.push(
+                "grpc_listen is empty and socket.enabled is false; the daemon would not be reachable"
+                    .to_string(),
+            );
+        }
+        if self.socket.mode > 0o777 {
+            problems.push(format!(
+                "socket.mode: {:#o} is not a file mode",
+                self.socket.mode
+            ));
+        } else if self.socket.mode & 0o600 != 0o600 {
+            problems.push(format!(
+                "socket.mode: {:#o} must give the owner read and write",
+                self.socket.mode
+            ));
+        }
+        if self.web_port == 0 {
+            problems.push("web_port: must not be 0".to_string());
+        }
+        if self.jobs.workers == 0 {
+            problems.push("jobs.workers: must be at least 1".to_string());
+        }
+
+        let qemu = self.qemu.get();
+        if !["auto", "hvf", "kvm", "tcg"].contains(&qemu.accelerator.as_str()) {
+            problems.push(format!(
+                "qemu.accelerator: '{}' is not one of auto, hvf, kvm, tcg",
+                qemu.accelerator
+            ));
+        }
+        if qemu.max_cpus == 0 {
+            problems.push("qemu.max_cpus: must be at least 1".to_string());
+        }
+        if qemu.max_memory_mb < 64 {
+            problems.push("qemu.max_memory_mb: must be at least 64".to_string());
+        }
+        let binaries = qemu
+            .binaries
+            .iter()
+            .map(|(arch, b)| (format!("qemu.binaries.{}", arch), b));
+        for (key, binary) in qemu
+            .binary_path
+            .iter()
+            .map(|b| ("qemu.binary_path".to_string(), b))
+            .chain(binaries)
+        {
+            let path = Path::new(binary);
+            if path.is_absolute() && !path.is_file() {
+                problems.push(format!("{}: {} does not exist", key, binary));
+            }
+        }
+
+        let vm_defaults = self.vm_defaults.get();
+        if vm_defaults.cpu_cores == 0 || vm_defaults.cpu_cores > qemu.max_cpus {
+            problems.push(format!(
+                "vm_defaults.cpu_cores: {} is outside 1..={} (qemu.max_cpus)",
+                vm_defaults.cpu_cores, qemu.max_cpus
+            ));
+        }
+        if vm_defaults.memory_mb < 64 || vm_defaults.memory_mb > qemu.max_memory_mb {
+            problems.push(format!(
+                "vm_defaults.memory_mb: {} is outside 64..={} (qemu.max_memory_mb)",
+                vm_defaults.memory_mb, qemu.max_memory_mb
+            ));
+        }
+
+        let admission = self.admission.get();
+        if admission.memory_overcommit_ratio <= 0.0 {
+            problems.push("admission.memory_overcommit_ratio: must be above 0".to_string());
+        }
+        if admission.cpu_overcommit_ratio <= 0.0 {
+            problems.push("admission.cpu_overcommit_ratio: must be above 0".to_string());
+        }
+        if admission.resume_free_mb < admission.pressure_free_mb {
+            problems.push(format!(
+                "admission.resume_free_mb: {} is below admission.pressure_free_mb ({})",
+                admission.resume_free_mb, admission.pressure_free_mb
+            ));
+        }
+        if !["pause", "suspend"].contains(&admission.pressure_action.as_str()) {
+            problems.push(format!(
+                "admission.pressure_action: '{}' is not one of pause, suspend",
+                admission.pressure_action
+            ));
+        }
+
+        let idle = self.idle.get();
+        if idle.idle_minutes == 0 {
+            problems.push("idle.idle_minutes: must be at least 1".to_string());
+        }
+        if idle.cpu_percent < 0.0 {
+            problems.push("idle.cpu_percent: must not be negative".to_string());
+        }
+        if !["pause", "suspend"].contains(&idle.action.as_str()) {
+            problems.push(format!("idle.action: '{}' is not one of pause, suspend", idle.action));
+        }
+
+        let network = self.network.get();
+        if !is_ipv4_cidr(&network.default_cidr) {
+            problems.push(format!(
+                "network.default_cidr: '{}' is not an IPv4 CIDR like 10.42.0.0/24",
+                network.default_cidr
+            ));
+        }
+        if self.replication.interval_secs == 0 {
+            problems.push("replication.interval_secs: must be at least 1".to_string());
+        }
+        if self.replication.primary.as_deref().is_some_and(|p| p.trim().is_empty()) {
+            problems.push("replication.primary: must not be empty".to_string());
+        }
+        if let Some(endpoint) = self.s3.get().endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                problems.push(format!(
+                    "s3.endpoint: '{}' must start with http:// or https://",
+                    endpoint
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
         } else {
-            Ok(Self::default())
+            bail!("Invalid configuration:\n  {}", problems.join("\n  "))
         }
     }
 
+    /// Apply a freshly loaded configuration to the running daemon
+    ///
+    /// Swaps the reloadable sections in place; changes anywhere else are
+    /// logged as needing a restart. The QEMU VNC port base and QMP socket
+    /// directory are kept, since running VMs are addressed through them.
+    pub fn reload_from(&self, new: &DaemonConfig) {
+        let restart_needed = [
+            ("store_path", self.store_path != new.store_path),
+            ("grpc_listen", self.grpc_listen != new.grpc_listen),
+            ("socket", section_changed(&self.socket, &new.socket)),
+            ("web_port", self.web_port != new.web_port),
+            ("security", section_changed(&self.security, &new.security)),
+            ("jobs", section_changed(&self.jobs, &new.jobs)),
+            ("auth", section_changed(&self.auth, &new.auth)),
+            ("replication", section_changed(&self.replication, &new.replication)),
+        ];
+        for (name, _) in restart_needed.iter().filter(|(_, changed)| *changed) {
+            warn!("Config change to '{}' takes effect after a restart", name);
+        }
+
+        let current = self.qemu.get();
+        let mut qemu = new.qemu.get();
+        if qemu.vnc_base_port != current.vnc_base_port
+            || qemu.qmp_socket_dir != current.qmp_socket_dir
+        {
+            warn!(
+                "Config change to 'qemu.vnc_base_port' or 'qemu.qmp_socket_dir' \
+                 takes effect after a restart"
+            );
+            qemu.vnc_base_port = current.vnc_base_port;
+            qemu.qmp_socket_dir = current.qmp_socket_dir;
+        }
+        self.qemu.set(qemu);
+        self.vm_defaults.set(new.vm_defaults.get());
+        self.network.set(new.network.get());
+        self.registry.set(new.registry.get());
+        self.s3.set(new.s3.get());
+        self.admission.set(new.admission.get());
+        self.idle.set(new.idle.get());
+        info!("Reloaded qemu, vm_defaults, network, registry, s3, admission and idle configuration");
+    }
+
     /// Save configuration to file
-    pub fn save(&self, path: &std::path::Path) -> anyhow::Result<()> {
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
         let content = toml::to_string_pretty(self)?;
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -157,13 +818,162 @@ impl DaemonConfig {
 
     /// Get the QMP socket directory
     pub fn qmp_socket_dir(&self) -> PathBuf {
-        self.qemu.qmp_socket_dir.clone()
+        self.qemu.get().qmp_socket_dir
             .unwrap_or_else(|| self.store_path.join("sockets"))
     }
 
+    /// Get the directory holding a volume's daemon-created images
+    pub fn volume_dir(&self, volume_id: &str) -> PathBuf {
+        self.store_path.join("volumes").join(volume_id)
+    }
+
+    /// Get the directory of a `tmpfs://` volume
+    ///
+    /// RAM-backed under `/dev/shm` where the host has it; elsewhere (macOS)
+    /// the image lives in the store and only its lifecycle is ephemeral.
+    pub fn tmpfs_volume_dir(&self, volume_id: &str) -> PathBuf {
+        let shm = std::path::Path::new("/dev/shm");
+        if shm.is_dir() {
+            shm.join("infrasim").join(volume_id)
+        } else {
+            self.store_path.join("tmpfs").join(volume_id)
+        }
+    }
+
+    /// Get the serial console log path for a VM
+    pub fn console_log_path(&self, vm_id: &str) -> PathBuf {
+        self.store_path.join("logs").join(format!("{}.log", vm_id))
+    }
+
+    /// Get the log QEMU's own output is written to for a VM
+    pub fn qemu_log_path(&self, vm_id: &str) -> PathBuf {
+        self.store_path.join("logs").join(format!("{}.qemu.log", vm_id))
+    }
+
+    /// Get the serial console socket path for a VM
+    pub fn serial_socket_path(&self, vm_id: &str) -> PathBuf {
+        self.qmp_socket_dir().join(format!("{}.serial", vm_id))
+    }
+
+    /// Get the socket of a VM's qemu-guest-agent channel
+    pub fn guest_agent_socket_path(&self, vm_id: &str) -> PathBuf {
+        self.qmp_socket_dir().join(format!("{}.qga", vm_id))
+    }
+
+    /// Get the socket QEMU connects to for a VM's vTPM
+    pub fn tpm_socket_path(&self, vm_id: &str) -> PathBuf {
+        self.qmp_socket_dir().join(format!("{}.tpm", vm_id))
+    }
+
+    /// Get the vhost-user socket of a VM's virtiofs shared folder
+    pub fn virtiofs_socket_path(&self, vm_id: &str, tag: &str) -> PathBuf {
+        self.qmp_socket_dir().join(format!("{}.fs-{}", vm_id, tag))
+    }
+
+    /// Get the directory holding a VM's vTPM state and recorded quotes
+    pub fn tpm_dir(&self, vm_id: &str) -> PathBuf {
+        self.store_path.join("tpm").join(vm_id)
+    }
+
+    /// Get the path a suspended VM's RAM is saved to
+    pub fn suspend_image_path(&self, vm_id: &str) -> PathBuf {
+        self.store_path.join("suspend").join(format!("{}.state", vm_id))
+    }
+
+    /// Get the root directory of daemon-created snapshot disk layers
+    pub fn snapshot_root(&self) -> PathBuf {
+        self.store_path.join("snapshots")
+    }
+
+    /// Get the directory holding a VM's snapshot disk layers
+    pub fn snapshot_dir(&self, vm_id: &str) -> PathBuf {
+        self.snapshot_root().join(vm_id)
+    }
+
+    /// Get the staging directory for export bundles being built or sent
+    pub fn export_dir(&self) -> PathBuf {
+        self.store_path.join("exports")
+    }
+
+    /// Get the directory imported bundles are unpacked into
+    pub fn import_dir(&self) -> PathBuf {
+        self.store_path.join("imports")
+    }
+
+    /// Get the staging directory for backups sent to or fetched from S3
+    pub fn backup_staging_dir(&self) -> PathBuf {
+        self.store_path.join("backups")
+    }
+
+    /// Get the directory holding writable copies of pulled volumes
+    pub fn pull_dir(&self) -> PathBuf {
+        self.store_path.join("pulls")
+    }
+
+    /// Get the directory holding disks restored from backups
+    pub fn restore_dir(&self) -> PathBuf {
+        self.store_path.join("restores")
+    }
+
+    /// Get the directory holding volume encryption passphrases
+    pub fn keyring_dir(&self) -> PathBuf {
+        self.store_path.join("keys")
+    }
+
+    /// Get the directory holding host-side state of a network (dnsmasq leases)
+    pub fn network_dir(&self, network_id: &str) -> PathBuf {
+        self.store_path.join("networks").join(network_id)
+    }
+
+    /// Get the gRPC Unix socket path
+    pub fn grpc_socket_path(&self) -> PathBuf {
+        self.socket.path.clone()
+            .unwrap_or_else(|| self.store_path.join("daemon.sock"))
+    }
+
+    /// Get the directory holding the daemon CA, serving certificate and API tokens
+    pub fn auth_dir(&self) -> PathBuf {
+        self.store_path.join("auth")
+    }
+
     /// Get the signing key path
     pub fn signing_key_path(&self) -> PathBuf {
         self.security.signing_key_path.clone()
             .unwrap_or_else(|| self.store_path.join("signing.key"))
     }
+
+    /// Get the path of the key secrets are encrypted with
+    pub fn secrets_key_path(&self) -> PathBuf {
+        self.security.secrets_key_path.clone()
+            .unwrap_or_else(|| self.store_path.join("secrets.key"))
+    }
+    /// Directory for state copies from the primary and the promotion marker
+    pub fn replication_dir(&self) -> PathBuf {
+        self.store_path.join("replication")
+    }
+}
+
+/// Compare two sections through their serialized form
+fn section_changed<T: Serialize>(a: &T, b: &T) -> bool {
+    toml::to_string(a).ok() != toml::to_string(b).ok()
+}
+
+fn is_ipv4_cidr(cidr: &str) -> bool {
+    match cidr.split_once('/') {
+        Some((addr, prefix)) => {
+            addr.parse::<std::net::Ipv4Addr>().is_ok()
+                && prefix.parse::<u8>().map_or(false, |p| p <= 32)
+        }
+        None => false,
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| path.to_path_buf()),
+        Err(_) => path.to_path_buf(),
+    }
 }
@@ -0,0 +1,226 @@
+//! Remote volume import
+//!
+//! Downloads http(s) volume sources into the content-addressed store with
+//! resume support and mandatory SHA-256 verification.
+
+use crate::state::StateManager;
+use infrasim_common::{types::*, Error, Result};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+
+/// Minimum interval between persisted progress updates
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Validate and normalize the sha256 digest a remote volume must match
+pub fn expected_digest(spec: &VolumeSpec) -> Result<String> {
+    if spec.integrity.scheme != "sha256" {
+        return Err(Error::IntegrityError(
+            "Remote volume sources require integrity scheme 'sha256'".to_string(),
+        ));
+    }
+
+    let digest = spec
+        .integrity
+        .expected_digest
+        .as_deref()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let digest = digest.strip_prefix("sha256:").unwrap_or(&digest);
+
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::IntegrityError(format!(
+            "Invalid sha256 digest for remote volume: '{}'",
+            digest
+        )));
+    }
+
+    Ok(digest.to_string())
+}
+
+/// HTTP(S) downloader for remote volume sources
+#[derive(Default)]
+pub struct Downloader {
+    client: reqwest::Client,
+}
+
+impl Downloader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Download a remote volume into the CAS, returning the object path
+    ///
+    /// Partial downloads are kept in the CAS staging directory, keyed by the
+    /// expected digest, and resumed with a `Range` request on the next attempt.
+    pub async fn fetch(&self, state: &StateManager, volume: &Volume) -> Result<PathBuf> {
+        let expected = expected_digest(&volume.spec)?;
+        let mut progress = volume.status.download.clone().unwrap_or_default();
+
+        match self
+            .fetch_inner(state, volume, &expected, &mut progress)
+            .await
+        {
+            Ok(path) => Ok(path),
+            Err(e) => {
+                if progress.state != DownloadState::Failed {
+                    // Transient: keep the partial file and retry on the next reconcile
+                    progress.error = Some(e.to_string());
+                    let _ = state.update_volume_download(&volume.meta.id, progress);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn fetch_inner(
+        &self,
+        state: &StateManager,
+        volume: &Volume,
+        expected: &str,
+        progress: &mut DownloadStatus,
+    ) -> Result<PathBuf> {
+        let cas = state.cas();
+        let url = &volume.spec.source;
+
+        if cas.has(expected).await {
+            debug!("Object {} already in store, skipping download", expected);
+            progress.state = DownloadState::Complete;
+            progress.error = None;
+            state.update_volume_download(&volume.meta.id, progress.clone())?;
+            return Ok(cas.object_path(expected));
+        }
+
+        let partial = cas.tmp_dir().join(format!("{}.partial", expected));
+        let (mut hasher, mut offset) = hash_partial(&partial).await?;
+
+        progress.state = DownloadState::Downloading;
+        progress.bytes_downloaded = offset;
+        progress.error = None;
+        state.update_volume_download(&volume.meta.id, progress.clone())?;
+
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| Error::NetworkError(format!("Failed to fetch {}: {}", url, e)))?;
+
+        let mut complete = false;
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                info!("Resuming download of {} at {} bytes", url, offset);
+                progress.bytes_total = response
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(parse_content_range_total);
+            }
+            // The partial file already holds the whole object
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => complete = true,
+            status if status.is_success() => {
+                if offset > 0 {
+                    warn!(
+                        "Server ignored range request for {}, restarting download",
+                        url
+                    );
+                    fs::remove_file(&partial).await?;
+                    hasher = Sha256::new();
+                    offset = 0;
+                }
+                progress.bytes_total = response.content_length();
+                progress.bytes_downloaded = 0;
+            }
+            status => {
+                return Err(Error::NetworkError(format!(
+                    "HTTP {} fetching {}",
+                    status, url
+                )));
+            }
+        }
+
+        if !complete {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&partial)
+                .await?;
+            let mut last_report = Instant::now();
+
+            while let Some(chunk) = response.chunk().await.map_err(|e| {
+                Error::NetworkError(format!("Download of {} interrupted: {}", url, e))
+            })? {
+                hasher.update(&chunk);
+                file.write_all(&chunk).await?;
+                offset += chunk.len() as u64;
+                progress.bytes_downloaded = offset;
+
+                if last_report.elapsed() >= PROGRESS_INTERVAL {
+                    file.flush().await?;
+                    state.update_volume_download(&volume.meta.id, progress.clone())?;
+                    last_report = Instant::now();
+                }
+            }
+
+            file.sync_all().await?;
+        }
+
+        progress.state = DownloadState::Verifying;
+        progress.bytes_downloaded = offset;
+        state.update_volume_download(&volume.meta.id, progress.clone())?;
+
+        let actual = hex::encode(hasher.finalize());
+        if actual != expected {
+            let _ = fs::remove_file(&partial).await;
+            let message = format!("Digest mismatch: expected {}, got {}", expected, actual);
+            progress.state = DownloadState::Failed;
+            progress.error = Some(message.clone());
+            state.update_volume_download(&volume.meta.id, progress.clone())?;
+            return Err(Error::IntegrityError(message));
+        }
+
+        let path = cas.adopt_file(&partial, expected).await?;
+
+        progress.state = DownloadState::Complete;
+        state.update_volume_download(&volume.meta.id, progress.clone())?;
+        info!("Downloaded {} ({} bytes) as {}", url, offset, expected);
+
+        Ok(path)
+    }
+}
+
+/// Hash an existing partial download so it can be resumed
+async fn hash_partial(path: &Path) -> Result<(Sha256, u64)> {
+    let mut hasher = Sha256::new();
+    let mut file = match fs::File::open(path).await {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((hasher, 0)),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut len = 0u64;
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        len += n as u64;
+    }
+
+    Ok((hasher, len))
+}
+
+/// Parse the total size from a `Content-Range: bytes a-b/total` header
+fn parse_content_range_total(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
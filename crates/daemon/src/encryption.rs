@@ -0,0 +1,187 @@
+//! Volume encryption keys
+//!
+//! Encrypted volumes are qcow2 images with a LUKS header
+//! (`encrypt.format=luks`). Passphrases live in the daemon's keyring, one
+//! 0600 file per key ID under [`DaemonConfig::keyring_dir`], and reach QEMU
+//! and `qemu-img` as `secret` objects that read those files, so they never
+//! appear on a command line. Every layer of an encrypted volume's backing
+//! chain is encrypted with the volume's key.
+
+use crate::config::DaemonConfig;
+use infrasim_common::{types::VolumeEncryption, Error, Result};
+use rand::RngCore;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Length of generated passphrases before hex encoding
+const GENERATED_KEY_BYTES: usize = 32;
+
+/// Passphrases of encrypted volumes, by key ID
+pub struct Keyring {
+    dir: PathBuf,
+}
+
+impl Keyring {
+    pub fn new(config: &DaemonConfig) -> Self {
+        Self {
+            dir: config.keyring_dir(),
+        }
+    }
+
+    /// Store `secret` under the key ID, generating a random passphrase if none is given
+    ///
+    /// An existing key is kept as is; a different secret for it is refused so
+    /// images encrypted with the old one stay readable.
+    pub fn ensure(&self, enc: &VolumeEncryption, secret: Option<&[u8]>) -> Result<()> {
+        enc.validate()?;
+        let path = self.dir.join(&enc.key_id);
+
+        if path.exists() {
+            return match secret {
+                Some(secret) if fs::read(&path)? != secret => Err(Error::AlreadyExists {
+                    kind: "encryption key".to_string(),
+                    id: enc.key_id.clone(),
+                }),
+                _ => Ok(()),
+            };
+        }
+
+        let generated;
+        let secret = match secret {
+            Some(secret) if !secret.is_empty() => secret,
+            _ => {
+                let mut bytes = [0u8; GENERATED_KEY_BYTES];
+                rand::rngs::OsRng.fill_bytes(&mut bytes);
+                generated = hex::encode(bytes);
+                generated.as_bytes()
+            }
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?
+            .write_all(secret)?;
+        info!("Added encryption key {} to the keyring", enc.key_id);
+
+        Ok(())
+    }
+
+    /// `secret` object definition for `-object`/`--object`
+    pub fn secret_object(&self, enc: &VolumeEncryption) -> Result<String> {
+        let path = self.dir.join(&enc.key_id);
+        if !path.exists() {
+            return Err(Error::NotFound {
+                kind: "encryption key".to_string(),
+                id: enc.key_id.clone(),
+            });
+        }
+        Ok(format!(
+            "secret,id={},file={},format=raw",
+            secret_id(enc),
+            path.display()
+        ))
+    }
+
+    /// `object-add` arguments for the key's secret
+    pub fn secret_qmp(&self, enc: &VolumeEncryption) -> Value {
+        json!({
+            "qom-type": "secret",
+            "id": secret_id(enc),
+            "file": self.dir.join(&enc.key_id).to_string_lossy(),
+            "format": "raw",
+        })
+    }
+
+    /// Secret objects of each distinct key
+    pub fn secret_objects<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a VolumeEncryption>,
+    ) -> Result<Vec<String>> {
+        let mut seen = Vec::new();
+        let mut objects = Vec::new();
+        for enc in keys {
+            if !seen.contains(&&enc.key_id) {
+                seen.push(&enc.key_id);
+                objects.push(self.secret_object(enc)?);
+            }
+        }
+        Ok(objects)
+    }
+
+    /// `qemu-img --object` arguments defining each distinct key's secret
+    pub fn secret_args<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a VolumeEncryption>,
+    ) -> Result<Vec<String>> {
+        Ok(self
+            .secret_objects(keys)?
+            .into_iter()
+            .flat_map(|object| ["--object".to_string(), object])
+            .collect())
+    }
+}
+
+/// QEMU object ID of a key's secret
+pub fn secret_id(enc: &VolumeEncryption) -> String {
+    format!("sec-{}", enc.key_id)
+}
+
+/// `qemu-img create -o` options for a new encrypted qcow2 image
+pub fn create_options(enc: &VolumeEncryption) -> String {
+    format!(
+        "encrypt.format=luks,encrypt.key-secret={},encrypt.cipher-alg={}",
+        secret_id(enc),
+        enc.cipher
+    )
+}
+
+/// Key options for an image and every layer of its backing chain, as used by `-drive`
+pub fn key_options(path: &Path, enc: &VolumeEncryption) -> Result<String> {
+    let depth = crate::snapshot::backing_chain(path)?.len();
+    Ok((0..depth)
+        .map(|level| format!("{}encrypt.key-secret={}", "backing.".repeat(level), secret_id(enc)))
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+/// `--image-opts` description of an encrypted image for `qemu-img`
+pub fn image_opts(path: &Path, enc: &VolumeEncryption) -> Result<String> {
+    Ok(format!(
+        "driver=qcow2,file.filename={},{}",
+        path.display(),
+        key_options(path, enc)?
+    ))
+}
+
+/// `blockdev-add` options for an encrypted image opened through `file`
+///
+/// Backing layers are spelled out so each can be given the key.
+pub fn blockdev_options(path: &str, file: Value, enc: &VolumeEncryption) -> Result<Value> {
+    let layers = crate::snapshot::backing_chain(Path::new(path))?;
+    let node = |file: Value, backing: Option<Value>| {
+        let mut node = json!({
+            "driver": "qcow2",
+            "file": file,
+            "encrypt": { "format": "luks", "key-secret": secret_id(enc) },
+        });
+        if let Some(backing) = backing {
+            node["backing"] = backing;
+        }
+        node
+    };
+
+    let backing = layers.iter().skip(1).rev().fold(None, |backing, layer| {
+        Some(node(
+            json!({ "driver": "file", "filename": layer.to_string_lossy() }),
+            backing,
+        ))
+    });
+    Ok(node(file, backing))
+}
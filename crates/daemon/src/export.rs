@@ -0,0 +1,651 @@
+//! VM export and import bundles
+//!
+//! An export is a tar bundle of a stopped VM's disks plus an `infrasim.json`
+//! manifest with its spec. OVA bundles additionally carry an OVF descriptor,
+//! a SHA-256 manifest and streamOptimized VMDK disks so that other
+//! hypervisors can import them. Import accepts any bundle produced here.
+//!
+//! Networks, QoS profiles and snapshots are host-specific and not exported.
+
+use crate::state::StateManager;
+use infrasim_common::{types::*, ContentAddressedStore, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Manifest file present in every bundle
+const MANIFEST_FILE: &str = "infrasim.json";
+
+/// Current manifest version; newer bundles are refused
+const MANIFEST_VERSION: u32 = 1;
+
+/// Bundle layout and disk image format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// OVF descriptor and streamOptimized VMDK disks
+    Ova,
+    /// Raw disk images
+    Raw,
+    /// Compressed qcow2 disk images
+    Qcow2,
+}
+
+impl ExportFormat {
+    /// File extension of the bundle
+    pub fn bundle_extension(&self) -> &'static str {
+        match self {
+            Self::Ova => "ova",
+            Self::Raw | Self::Qcow2 => "tar",
+        }
+    }
+
+    fn disk_format(&self) -> &'static str {
+        match self {
+            Self::Ova => "vmdk",
+            Self::Raw => "raw",
+            Self::Qcow2 => "qcow2",
+        }
+    }
+
+    fn disk_extension(&self) -> &'static str {
+        match self {
+            Self::Ova => "vmdk",
+            Self::Raw => "img",
+            Self::Qcow2 => "qcow2",
+        }
+    }
+}
+
+/// Contents of `infrasim.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub name: String,
+    pub arch: String,
+    pub machine: String,
+    pub cpu_cores: u32,
+    pub memory_mb: u64,
+    #[serde(default)]
+    pub enable_tpm: bool,
+    #[serde(default)]
+    pub compatibility_mode: bool,
+    #[serde(default)]
+    pub extra_args: HashMap<String, String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    pub disks: Vec<ManifestDisk>,
+}
+
+impl Manifest {
+    /// Manifest describing `vm` with the given disks
+    pub fn new(vm: &Vm, disks: Vec<ManifestDisk>) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            name: vm.meta.name.clone(),
+            arch: vm.spec.arch.clone(),
+            machine: vm.spec.machine.clone(),
+            cpu_cores: vm.spec.cpu_cores,
+            memory_mb: vm.spec.memory_mb,
+            enable_tpm: vm.spec.enable_tpm,
+            compatibility_mode: vm.spec.compatibility_mode,
+            extra_args: vm.spec.extra_args.clone(),
+            labels: vm.meta.labels.clone(),
+            disks,
+        }
+    }
+
+    /// Refuse manifests written by a newer version
+    pub fn check_version(&self) -> Result<()> {
+        if self.version > MANIFEST_VERSION {
+            return Err(Error::ExportError(format!(
+                "Bundle manifest version {} is newer than supported version {}",
+                self.version, MANIFEST_VERSION
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A disk image in the bundle
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestDisk {
+    /// File name inside the bundle
+    pub file: String,
+    /// qemu-img format of the file
+    pub format: String,
+    #[serde(default)]
+    pub kind: VolumeKind,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub boot: bool,
+}
+
+/// A volume to export and whether it is the VM's boot disk
+pub struct ExportDisk {
+    pub volume: Volume,
+    pub path: PathBuf,
+    pub boot: bool,
+}
+
+/// Refuse to export a VM whose disks may still change
+pub fn check_exportable(state: &StateManager, vm: &Vm) -> Result<()> {
+    if state.get_vm_process(&vm.meta.id).is_some() {
+        return Err(Error::InvalidConfig(format!(
+            "VM {} is {}; stop or suspend it before exporting",
+            vm.meta.name, vm.status.state
+        )));
+    }
+    Ok(())
+}
+
+/// Package a stopped VM into a bundle under the export directory
+///
+/// The caller owns the returned file and should remove it once delivered.
+pub async fn export(state: &StateManager, vm: &Vm, format: ExportFormat) -> Result<PathBuf> {
+    check_exportable(state, vm)?;
+    let disks = export_disks(state, vm)?;
+
+    let export_dir = state.config().export_dir();
+    let id = uuid::Uuid::new_v4();
+    let work_dir = export_dir.join(id.to_string());
+    let bundle = export_dir.join(format!("{}.{}", id, format.bundle_extension()));
+    fs::create_dir_all(&work_dir).await?;
+
+    let result = build_bundle(vm, &disks, format, &work_dir, &bundle).await;
+    let _ = fs::remove_dir_all(&work_dir).await;
+    if result.is_err() {
+        let _ = fs::remove_file(&bundle).await;
+    }
+    result?;
+
+    info!(
+        "Exported VM {} as {} ({} disks)",
+        vm.meta.name,
+        format.bundle_extension(),
+        disks.len()
+    );
+    Ok(bundle)
+}
+
+/// Create a stopped VM and its volumes from a bundle
+///
+/// Disk images are unpacked under the import directory and become the
+/// sources of the new volumes.
+pub async fn import(
+    state: &StateManager,
+    bundle: &Path,
+    name: Option<String>,
+    labels: HashMap<String, String>,
+) -> Result<(Vm, Vec<Volume>)> {
+    let dir = state
+        .config()
+        .import_dir()
+        .join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&dir).await?;
+
+    let result = import_into(state, bundle, &dir, name, labels).await;
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+    result
+}
+
+async fn import_into(
+    state: &StateManager,
+    bundle: &Path,
+    dir: &Path,
+    name: Option<String>,
+    labels: HashMap<String, String>,
+) -> Result<(Vm, Vec<Volume>)> {
+    unpack(bundle, dir).await?;
+
+    let manifest: Manifest = match fs::read(dir.join(MANIFEST_FILE)).await {
+        Ok(data) => serde_json::from_slice(&data)?,
+        Err(_) => {
+            return Err(Error::ExportError(format!(
+                "Not an InfraSim bundle: {} is missing",
+                MANIFEST_FILE
+            )))
+        }
+    };
+    manifest.check_version()?;
+
+    let name = name.unwrap_or_else(|| manifest.name.clone());
+    if state.get_vm_by_name(&name)?.is_some() {
+        return Err(Error::AlreadyExists {
+            kind: "vm".to_string(),
+            id: name,
+        });
+    }
+
+    let mut images = Vec::new();
+    for disk in &manifest.disks {
+        // Only plain file names; anything else could point outside the bundle
+        if Path::new(&disk.file).file_name().and_then(|f| f.to_str()) != Some(disk.file.as_str()) {
+            return Err(Error::ExportError(format!(
+                "Invalid disk file name in bundle: {}",
+                disk.file
+            )));
+        }
+        let path = dir.join(&disk.file);
+        if !path.is_file() {
+            return Err(Error::ExportError(format!(
+                "Disk {} listed in the manifest is missing from the bundle",
+                disk.file
+            )));
+        }
+
+        // QEMU can't write streamOptimized VMDKs
+        if disk.format == "vmdk" {
+            let converted = path.with_extension("qcow2");
+            convert(&path, Some("vmdk"), &converted, "qcow2", &[]).await?;
+            let _ = fs::remove_file(&path).await;
+            images.push((disk, converted, "qcow2".to_string()));
+        } else {
+            images.push((disk, path, disk.format.clone()));
+        }
+    }
+
+    let mut volumes = Vec::new();
+    let result = create_vm(state, &manifest, &name, labels, &images, false, &mut volumes);
+    if result.is_err() {
+        for volume in &volumes {
+            let _ = state.delete_volume(&volume.meta.id);
+        }
+    }
+    let vm = result?;
+
+    state.emit_vm_event(
+        &vm.meta.id,
+        format!("Imported from bundle of VM {}", manifest.name),
+    );
+    info!(
+        "Imported VM {} with {} disks",
+        vm.meta.name,
+        volumes.len()
+    );
+
+    Ok((vm, volumes))
+}
+
+/// Create a stopped VM and volumes for the disk images of a manifest
+///
+/// Created volumes are recorded in `volumes` even on failure, so the caller
+/// can remove them. With `overlay`, volumes are overlays on the images.
+pub fn create_vm(
+    state: &StateManager,
+    manifest: &Manifest,
+    name: &str,
+    labels: HashMap<String, String>,
+    images: &[(&ManifestDisk, PathBuf, String)],
+    overlay: bool,
+    volumes: &mut Vec<Volume>,
+) -> Result<Vm> {
+    let mut boot_disk_id = None;
+    for (index, (disk, path, format)) in images.iter().enumerate() {
+        let spec = VolumeSpec {
+            kind: disk.kind,
+            source: path.to_string_lossy().to_string(),
+            read_only: disk.read_only,
+            format: format.clone(),
+            overlay,
+            ..Default::default()
+        };
+        let volume = state.create_volume(format!("{}-disk{}", name, index), spec, HashMap::new())?;
+        if disk.boot {
+            boot_disk_id = Some(volume.meta.id.clone());
+        }
+        volumes.push(volume);
+    }
+
+    let spec = VmSpec {
+        arch: manifest.arch.clone(),
+        machine: manifest.machine.clone(),
+        cpu_cores: manifest.cpu_cores,
+        memory_mb: manifest.memory_mb,
+        volume_ids: volumes.iter().map(|v| v.meta.id.clone()).collect(),
+        enable_tpm: manifest.enable_tpm,
+        boot_disk_id,
+        extra_args: manifest.extra_args.clone(),
+        compatibility_mode: manifest.compatibility_mode,
+        ..Default::default()
+    };
+
+    let mut vm_labels = manifest.labels.clone();
+    vm_labels.extend(labels);
+    let vm = state.create_vm(name.to_string(), spec, vm_labels)?;
+
+    let status = VmStatus {
+        state: VmState::Stopped,
+        ..Default::default()
+    };
+    state.update_vm_status(&vm.meta.id, status)?;
+
+    state.get_vm(&vm.meta.id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: vm.meta.id.clone(),
+    })
+}
+
+/// The VM's disk volumes, boot disk first
+pub fn export_disks(state: &StateManager, vm: &Vm) -> Result<Vec<ExportDisk>> {
+    let mut ids: Vec<&String> = vm.spec.boot_disk_id.iter().collect();
+    ids.extend(
+        vm.spec
+            .volume_ids
+            .iter()
+            .filter(|id| Some(*id) != vm.spec.boot_disk_id.as_ref()),
+    );
+
+    let mut disks = Vec::new();
+    for id in ids {
+        let volume = state.get_volume(id)?.ok_or_else(|| Error::NotFound {
+            kind: "volume".to_string(),
+            id: id.clone(),
+        })?;
+        if volume.spec.kind == VolumeKind::Device {
+            return Err(Error::ExportError(format!(
+                "Device volume {} can't be exported; detach it first",
+                volume.meta.name
+            )));
+        }
+        // Bundles carry plain images that other hypervisors can open
+        if volume.spec.encryption.is_some() {
+            return Err(Error::ExportError(format!(
+                "Encrypted volume {} can't be exported",
+                volume.meta.name
+            )));
+        }
+        let path = volume
+            .status
+            .local_path
+            .as_ref()
+            .filter(|_| volume.status.ready)
+            .map(PathBuf::from)
+            .ok_or_else(|| {
+                Error::ExportError(format!("Volume {} is not ready", volume.meta.name))
+            })?;
+
+        disks.push(ExportDisk {
+            boot: Some(id) == vm.spec.boot_disk_id.as_ref(),
+            volume,
+            path,
+        });
+    }
+
+    Ok(disks)
+}
+
+async fn build_bundle(
+    vm: &Vm,
+    disks: &[ExportDisk],
+    format: ExportFormat,
+    work_dir: &Path,
+    bundle: &Path,
+) -> Result<()> {
+    let stem = file_stem(&vm.meta.name);
+
+    // Converting flattens any snapshot backing chain into a standalone image
+    let mut manifest_disks = Vec::new();
+    for (index, disk) in disks.iter().enumerate() {
+        let file = format!("{}-disk{}.{}", stem, index, format.disk_extension());
+        let options: &[&str] = match format {
+            ExportFormat::Ova => &["-o", "subformat=streamOptimized"],
+            ExportFormat::Qcow2 => &["-c"],
+            ExportFormat::Raw => &[],
+        };
+        let source_format = Some(disk.volume.spec.format.as_str()).filter(|f| !f.is_empty());
+        convert(
+            &disk.path,
+            source_format,
+            &work_dir.join(&file),
+            format.disk_format(),
+            options,
+        )
+        .await?;
+
+        manifest_disks.push(ManifestDisk {
+            file,
+            format: format.disk_format().to_string(),
+            kind: disk.volume.spec.kind,
+            read_only: disk.volume.spec.read_only,
+            boot: disk.boot,
+        });
+    }
+
+    let manifest = Manifest::new(vm, manifest_disks);
+    fs::write(
+        work_dir.join(MANIFEST_FILE),
+        serde_json::to_vec_pretty(&manifest)?,
+    )
+    .await?;
+
+    let disk_files: Vec<String> = manifest.disks.iter().map(|d| d.file.clone()).collect();
+    let files = match format {
+        // OVA requires the descriptor first, then the manifest
+        ExportFormat::Ova => {
+            let ovf = format!("{}.ovf", stem);
+            let mf = format!("{}.mf", stem);
+            fs::write(work_dir.join(&ovf), ovf_descriptor(&manifest, work_dir)?).await?;
+
+            let mut listed = vec![ovf.clone()];
+            listed.extend(disk_files.iter().cloned());
+            listed.push(MANIFEST_FILE.to_string());
+
+            let mut digests = String::new();
+            for file in &listed {
+                let digest = ContentAddressedStore::hash_file(work_dir.join(file)).await?;
+                digests.push_str(&format!("SHA256({})= {}\n", file, digest));
+            }
+            fs::write(work_dir.join(&mf), digests).await?;
+
+            let mut files = vec![ovf, mf];
+            files.extend(disk_files);
+            files.push(MANIFEST_FILE.to_string());
+            files
+        }
+        ExportFormat::Raw | ExportFormat::Qcow2 => {
+            let mut files = vec![MANIFEST_FILE.to_string()];
+            files.extend(disk_files);
+            files
+        }
+    };
+
+    pack(work_dir, &files, bundle).await
+}
+
+/// OVF 1.0 descriptor describing the VM's hardware and disks
+fn ovf_descriptor(manifest: &Manifest, work_dir: &Path) -> Result<String> {
+    let name = xml_escape(&manifest.name);
+    let mut references = String::new();
+    let mut disk_section = String::new();
+    let mut disk_items = String::new();
+
+    for (index, disk) in manifest.disks.iter().enumerate() {
+        let path = work_dir.join(&disk.file);
+        let size = std::fs::metadata(&path)?.len();
+        let capacity = virtual_size(&path)?;
+
+        references.push_str(&format!(
+            "    <File ovf:id=\"file{i}\" ovf:href=\"{file}\" ovf:size=\"{size}\"/>\n",
+            i = index,
+            file = xml_escape(&disk.file),
+            size = size,
+        ));
+        disk_section.push_str(&format!(
+            "    <Disk ovf:diskId=\"vmdisk{i}\" ovf:fileRef=\"file{i}\" ovf:capacity=\"{capacity}\" \
+             ovf:format=\"http://www.vmware.com/interfaces/specifications/vmdk.html#streamOptimized\"/>\n",
+            i = index,
+            capacity = capacity,
+        ));
+        disk_items.push_str(&format!(
+            "      <Item>\n\
+             \x20       <rasd:AddressOnParent>{i}</rasd:AddressOnParent>\n\
+             \x20       <rasd:ElementName>disk{i}</rasd:ElementName>\n\
+             \x20       <rasd:HostResource>ovf:/disk/vmdisk{i}</rasd:HostResource>\n\
+             \x20       <rasd:InstanceID>{id}</rasd:InstanceID>\n\
+             \x20       <rasd:Parent>3</rasd:Parent>\n\
+             \x20       <rasd:ResourceType>17</rasd:ResourceType>\n\
+             \x20     </Item>\n",
+            i = index,
+            id = 4 + index,
+        ));
+    }
+
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<Envelope ovf:version="1.0" xmlns="http://schemas.dmtf.org/ovf/envelope/1" xmlns:ovf="http://schemas.dmtf.org/ovf/envelope/1" xmlns:rasd="http://schemas.dmtf.org/wbem/wscim/1/cim-schema/2/CIM_ResourceAllocationSettingData" xmlns:vssd="http://schemas.dmtf.org/wbem/wscim/1/cim-schema/2/CIM_VirtualSystemSettingData">
+  <References>
+{references}  </References>
+  <DiskSection>
+    <Info>Virtual disks</Info>
+{disk_section}  </DiskSection>
+  <VirtualSystem ovf:id="{name}">
+    <Info>InfraSim virtual machine ({arch}/{machine})</Info>
+    <Name>{name}</Name>
+    <OperatingSystemSection ovf:id="101">
+      <Info>Guest operating system</Info>
+    </OperatingSystemSection>
+    <VirtualHardwareSection>
+      <Info>Virtual hardware requirements</Info>
+      <System>
+        <vssd:ElementName>Virtual Hardware Family</vssd:ElementName>
+        <vssd:InstanceID>0</vssd:InstanceID>
+        <vssd:VirtualSystemIdentifier>{name}</vssd:VirtualSystemIdentifier>
+      </System>
+      <Item>
+        <rasd:AllocationUnits>hertz * 10^6</rasd:AllocationUnits>
+        <rasd:ElementName>{cpus} virtual CPU(s)</rasd:ElementName>
+        <rasd:InstanceID>1</rasd:InstanceID>
+        <rasd:ResourceType>3</rasd:ResourceType>
+        <rasd:VirtualQuantity>{cpus}</rasd:VirtualQuantity>
+      </Item>
+      <Item>
+        <rasd:AllocationUnits>byte * 2^20</rasd:AllocationUnits>
+        <rasd:ElementName>{memory} MB of memory</rasd:ElementName>
+        <rasd:InstanceID>2</rasd:InstanceID>
+        <rasd:ResourceType>4</rasd:ResourceType>
+        <rasd:VirtualQuantity>{memory}</rasd:VirtualQuantity>
+      </Item>
+      <Item>
+        <rasd:ElementName>sataController0</rasd:ElementName>
+        <rasd:InstanceID>3</rasd:InstanceID>
+        <rasd:ResourceSubType>AHCI</rasd:ResourceSubType>
+        <rasd:ResourceType>20</rasd:ResourceType>
+      </Item>
+{disk_items}    </VirtualHardwareSection>
+  </VirtualSystem>
+</Envelope>
+"#,
+        references = references,
+        disk_section = disk_section,
+        disk_items = disk_items,
+        name = name,
+        arch = xml_escape(&manifest.arch),
+        machine = xml_escape(&manifest.machine),
+        cpus = manifest.cpu_cores,
+        memory = manifest.memory_mb,
+    ))
+}
+
+/// Convert a disk image with `qemu-img convert`
+/// Convert a disk image with `qemu-img convert`
+pub async fn convert(
+    source: &Path,
+    source_format: Option<&str>,
+    dest: &Path,
+    dest_format: &str,
+    options: &[&str],
+) -> Result<()> {
+    let mut command = Command::new("qemu-img");
+    command.arg("convert");
+    if let Some(format) = source_format {
+        command.args(["-f", format]);
+    }
+    command
+        .args(["-O", dest_format])
+        .args(options)
+        .arg(source)
+        .arg(dest);
+
+    let output = command
+        .output()
+        .await
+        .map_err(|e| Error::ExportError(format!("qemu-img failed: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::ExportError(format!(
+            "qemu-img convert failed for {}: {}",
+            source.display(),
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Virtual size of an image in bytes
+fn virtual_size(path: &Path) -> Result<u64> {
+    crate::snapshot::image_info(path)?["virtual-size"]
+        .as_u64()
+        .ok_or_else(|| Error::ExportError(format!("Unknown virtual size for {}", path.display())))
+}
+
+/// Write `files` from `dir` into a tar archive, in order
+async fn pack(dir: &Path, files: &[String], bundle: &Path) -> Result<()> {
+    let dir = dir.to_path_buf();
+    let files = files.to_vec();
+    let bundle = bundle.to_path_buf();
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut builder = tar::Builder::new(std::fs::File::create(&bundle)?);
+        for file in &files {
+            builder.append_path_with_name(dir.join(file), file)?;
+        }
+        builder.into_inner()?.sync_all()?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| Error::Internal(e.to_string()))?
+}
+
+/// Unpack a tar bundle; entries escaping `dir` are skipped by `tar`
+async fn unpack(bundle: &Path, dir: &Path) -> Result<()> {
+    let bundle = bundle.to_path_buf();
+    let dir = dir.to_path_buf();
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut archive = tar::Archive::new(std::fs::File::open(&bundle)?);
+        archive.unpack(&dir).map_err(|e| {
+            warn!("Failed to unpack bundle {}: {}", bundle.display(), e);
+            Error::ExportError(format!("Invalid bundle: {}", e))
+        })
+    })
+    .await
+    .map_err(|e| Error::Internal(e.to_string()))?
+}
+
+/// File name prefix derived from a VM name
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -1,36 +1,91 @@
 //! gRPC server implementation
 
-use crate::config::DaemonConfig;
+use crate::admission;
+use crate::config::{AuthConfig, DaemonConfig};
 use crate::generated::infra_sim_daemon_server::{InfraSimDaemon, InfraSimDaemonServer};
 use crate::generated::{
     self, 
     VmState as ProtoVmState,
     NetworkMode as ProtoNetworkMode,
     VolumeKind as ProtoVolumeKind,
-    ResourceMeta, Vm, VmSpec, VmStatus, 
-    Network, NetworkSpec, NetworkStatus,
-    Volume, VolumeSpec, IntegrityConfig,
+    ResourceMeta, Vm, VmInterface, VmSpec, VmStatus, NetworkAttachment, SharedFolder,
+    SharedFolderCache as ProtoSharedFolderCache, SharedFolderProtocol as ProtoSharedFolderProtocol,
+    Network, NetworkSpec, NetworkStatus, NetworkCapture, CaptureState as ProtoCaptureState,
+    StartNetworkCaptureRequest, StartNetworkCaptureResponse,
+    StopNetworkCaptureRequest, StopNetworkCaptureResponse,
+    ReadNetworkCaptureRequest, ReadNetworkCaptureResponse,
+    Volume, VolumeSpec, IntegrityConfig, VolumeEncryption,
     Snapshot, SnapshotSpec,
     QoSProfile, QoSProfileSpec,
+    ChaosProfile, ChaosProfileSpec, ChaosProfileStatus, ChaosPartition,
+    LatencyDistribution as ProtoLatencyDistribution,
     CreateVmRequest, CreateVmResponse,
     GetVmRequest, GetVmResponse,
     UpdateVmRequest, UpdateVmResponse,
+    UpdateVmLabelsRequest, UpdateVmLabelsResponse,
     DeleteVmRequest, DeleteVmResponse,
     ListVMsRequest, ListVMsResponse,
     StartVmRequest, StartVmResponse,
-    StopVmRequest, StopVmResponse,
+    StopVmRequest, StopVmResponse, StopMode as ProtoStopMode,
+    PauseVmRequest, PauseVmResponse,
+    ResumeVmRequest, ResumeVmResponse,
+    SuspendVmRequest, SuspendVmResponse,
+    AttachDeviceRequest, AttachDeviceResponse,
+    StreamVmLogsRequest, VmLogEntry, VmLogSource,
+    GetVmStatsRequest, WatchVmStatsRequest, VmStats,
+    ExportVmRequest, ExportVmResponse, ExportFormat as ProtoExportFormat,
+    ImportVmRequest, ImportVmResponse, ImportVmOptions,
+    PushApplianceRequest, PushApplianceResponse, PullApplianceRequest, PullApplianceResponse,
+    SerialInput, SerialOutput, CopyToGuestRequest, CopyToGuestResponse,
+    MarkVmAsTemplateRequest, MarkVmAsTemplateResponse,
+    CloneVmRequest, CloneVmResponse,
+    ExecuteQmpRequest, ExecuteQmpResponse,
     CreateNetworkRequest, CreateNetworkResponse,
     GetNetworkRequest, GetNetworkResponse,
     DeleteNetworkRequest, DeleteNetworkResponse,
     ListNetworksRequest, ListNetworksResponse,
+    PortForward, PortForwardSpec, PortForwardStatus, PortProtocol as ProtoPortProtocol,
+    CreatePortForwardRequest, CreatePortForwardResponse,
+    GetPortForwardRequest, GetPortForwardResponse,
+    DeletePortForwardRequest, DeletePortForwardResponse,
+    ListPortForwardsRequest, ListPortForwardsResponse,
+    Router, RouterSpec, RouterStatus, StaticRoute, RouterRule,
+    FirewallAction as ProtoFirewallAction, RuleProtocol as ProtoRuleProtocol,
+    CreateRouterRequest, CreateRouterResponse,
+    GetRouterRequest, GetRouterResponse,
+    UpdateRouterRequest, UpdateRouterResponse,
+    DeleteRouterRequest, DeleteRouterResponse,
+    ListRoutersRequest, ListRoutersResponse,
+    Device, DeviceSpec, DeviceStatus, DeviceBus as ProtoDeviceBus,
+    CreateDeviceRequest, CreateDeviceResponse,
+    GetDeviceRequest, GetDeviceResponse,
+    DeleteDeviceRequest, DeleteDeviceResponse,
+    ListDevicesRequest, ListDevicesResponse,
     CreateQoSProfileRequest, CreateQoSProfileResponse,
     GetQoSProfileRequest, GetQoSProfileResponse,
     DeleteQoSProfileRequest, DeleteQoSProfileResponse,
     ListQoSProfilesRequest, ListQoSProfilesResponse,
+    ApplyChaosProfileRequest, ApplyChaosProfileResponse,
+    GetChaosProfileRequest, GetChaosProfileResponse,
+    DeleteChaosProfileRequest, DeleteChaosProfileResponse,
+    ListChaosProfilesRequest, ListChaosProfilesResponse,
+    ScenarioRun, ScenarioStepReport, ScenarioVmStats, ScenarioStepComparison,
+    ScenarioRunState as ProtoScenarioRunState, ScenarioStepOutcome as ProtoScenarioStepOutcome,
+    RunScenarioRequest, RunScenarioResponse,
+    GetScenarioRunRequest, GetScenarioRunResponse,
+    ListScenarioRunsRequest, ListScenarioRunsResponse,
+    CancelScenarioRunRequest, CancelScenarioRunResponse,
+    DeleteScenarioRunRequest, DeleteScenarioRunResponse,
+    CompareScenarioRunsRequest, CompareScenarioRunsResponse,
     CreateVolumeRequest, CreateVolumeResponse,
     GetVolumeRequest, GetVolumeResponse,
     DeleteVolumeRequest, DeleteVolumeResponse,
     ListVolumesRequest, ListVolumesResponse,
+    WatchVolumeDownloadRequest, DownloadStatus, DownloadState as ProtoDownloadState,
+    VolumeLayer, GetVolumeChainRequest, GetVolumeChainResponse,
+    FlattenVolumeRequest, FlattenProgress,
+    ResizeVolumeRequest, ResizeVolumeResponse,
+    PushVolumeRequest, PushVolumeResponse, PullVolumeRequest, PullVolumeResponse,
     CreateConsoleRequest, CreateConsoleResponse,
     GetConsoleRequest, GetConsoleResponse,
     DeleteConsoleRequest, DeleteConsoleResponse,
@@ -39,48 +94,349 @@ use crate::generated::{
     DeleteSnapshotRequest, DeleteSnapshotResponse,
     ListSnapshotsRequest, ListSnapshotsResponse,
     RestoreSnapshotRequest, RestoreSnapshotResponse,
+    RevertSnapshotRequest, RevertSnapshotResponse,
+    BranchSnapshotRequest, BranchSnapshotResponse,
+    ListSnapshotFilesRequest, ListSnapshotFilesResponse,
+    ReadSnapshotFileRequest, ReadSnapshotFileResponse,
+    DiffSnapshotsRequest, DiffSnapshotsResponse,
+    SnapshotFile, SnapshotFileChange, FileChange as ProtoFileChange,
+    SnapshotPolicy, SnapshotPolicySpec, SnapshotPolicyStatus,
+    CreateSnapshotPolicyRequest, CreateSnapshotPolicyResponse,
+    GetSnapshotPolicyRequest, GetSnapshotPolicyResponse,
+    UpdateSnapshotPolicyRequest, UpdateSnapshotPolicyResponse,
+    DeleteSnapshotPolicyRequest, DeleteSnapshotPolicyResponse,
+    ListSnapshotPoliciesRequest, ListSnapshotPoliciesResponse,
+    Backup, BackupSpec, BackupStatus, BackupDisk, BackupKind as ProtoBackupKind,
+    CreateBackupRequest, CreateBackupResponse,
+    GetBackupRequest, GetBackupResponse,
+    DeleteBackupRequest, DeleteBackupResponse,
+    ListBackupsRequest, ListBackupsResponse,
+    RestoreBackupRequest, RestoreBackupResponse,
+    BackupPolicy, BackupPolicySpec, BackupPolicyStatus,
+    CreateBackupPolicyRequest, CreateBackupPolicyResponse,
+    GetBackupPolicyRequest, GetBackupPolicyResponse,
+    UpdateBackupPolicyRequest, UpdateBackupPolicyResponse,
+    DeleteBackupPolicyRequest, DeleteBackupPolicyResponse,
+    ListBackupPoliciesRequest, ListBackupPoliciesResponse,
+    CreateArchiveRequest, CreateArchiveResponse,
+    DownloadArchiveRequest, DownloadArchiveResponse,
+    RestoreArchiveRequest, RestoreArchiveResponse,
+    Job, JobSpec, JobStatus, JobKind as ProtoJobKind, JobState as ProtoJobState,
+    CreateJobRequest, CreateJobResponse,
+    GetJobRequest, GetJobResponse,
+    ListJobsRequest, ListJobsResponse,
+    CancelJobRequest, CancelJobResponse,
     CreateBenchmarkRunRequest, CreateBenchmarkRunResponse,
     GetBenchmarkRunRequest, GetBenchmarkRunResponse,
     ListBenchmarkRunsRequest, ListBenchmarkRunsResponse,
     GetAttestationRequest, GetAttestationResponse,
+    GetTpmQuoteRequest, GetTpmQuoteResponse, TpmQuote, TpmEvent,
+    GetVolumeProvenanceRequest, GetVolumeProvenanceResponse, VolumeBuild,
     CreateLoRaDeviceRequest, CreateLoRaDeviceResponse,
     GetLoRaDeviceRequest, GetLoRaDeviceResponse,
     DeleteLoRaDeviceRequest, DeleteLoRaDeviceResponse,
+    WatchEventsRequest, Event as ProtoEvent, EventType as ProtoEventType,
     GetHealthRequest, GetHealthResponse,
     GetDaemonStatusRequest, GetDaemonStatusResponse,
     InspectArtifactRequest, InspectArtifactResponse,
+    SignPayloadRequest, SignPayloadResponse,
+    GetSigningKeyRequest, GetSigningKeyResponse,
+    AuditEvent, ListAuditEventsRequest, ListAuditEventsResponse,
+    VerifyAuditLogRequest, VerifyAuditLogResponse,
+    ProvenanceRecord as ProtoProvenanceRecord, GetProvenanceChainRequest, GetProvenanceChainResponse,
+    Secret, CreateSecretRequest, CreateSecretResponse,
+    GetSecretRequest, GetSecretResponse,
+    ListSecretsRequest, ListSecretsResponse,
+    GetReplicationStatusRequest, ReplicationStatus,
+    StreamStateSnapshotRequest, StateSnapshotChunk,
+    ReadCasObjectRequest, ReadCasObjectResponse,
+    PromoteDaemonRequest, PromoteDaemonResponse,
+    DeleteSecretRequest, DeleteSecretResponse,
     Console, ConsoleSpec, ConsoleStatus,
     HostProvenance, AttestationReport,
 };
-use crate::qemu::{QemuLauncher, VolumePreparer};
-use crate::state::StateManager;
+use crate::addresses;
+use crate::appliance;
+use crate::archive;
+use crate::audit::{audited, AuditChange, AuditService};
+use crate::backup;
+use crate::capture::{self, CaptureManager};
+use crate::scenario::ScenarioRunner;
+use crate::chaos;
+use crate::encryption::Keyring;
+use crate::export::{self, ExportFormat};
+use crate::hostnet;
+use crate::jobs::JobQueue;
+use crate::replication::Replication;
+use crate::qemu::{QemuLauncher, StopMode, VolumePreparer, NFS_SCHEME, TMPFS_SCHEME, VOLUME_COPY_SCHEME};
+use crate::scheduler;
+use crate::shares;
+use crate::snapshot;
+use crate::snapshot_files::{self, Change, GuestFile};
+use crate::state::{EventKind, ResourceEvent, StateManager, VmEvent, VmProcess};
+use crate::stats;
+use crate::template;
+use crate::transfer;
+use crate::vtpm;
 use infrasim_common::{
-    attestation::AttestationProvider,
-    types::{self, NetworkMode, VolumeKind},
+    attestation::{vtpm::{default_pcrs, PCR_COUNT}, AttestationProvider},
+    audit::{AuditEntry, AuditFilter, AuditLog},
+    provenance::{verify_chain, ProvenanceRecord},
+    qga::GuestAgentClient,
+    qmp::QmpClient,
+    scenario::{self, RunState, StepOutcome},
+    secrets::SecretMeta,
+    slsa::{self, BuildInputs, ResourceDescriptor},
+    types::{self, DeviceBus, NetworkMode, OciReference, PortProtocol, VolumeKind, OCI_SCHEME},
+    Error,
 };
 use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{ReceiverStream, UnixListenerStream};
+use infrasim_common::auth::{write_private, CertificateAuthority, RequireToken};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
 use tonic::{Request, Response, Status};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// How often a followed console log is polled for new output
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Buffered log entries per streaming client
+const LOG_STREAM_BUFFER: usize = 256;
+
+/// Default time between samples for stats watchers
+const STATS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often download progress is checked for watchers
+const DOWNLOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Buffered events per watching client
+const EVENT_STREAM_BUFFER: usize = 256;
+
+/// Size of each export bundle chunk sent to clients
+const EXPORT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Buffered export chunks per client
+const EXPORT_STREAM_BUFFER: usize = 4;
+
+/// Buffered serial output chunks per attached client
+const SERIAL_STREAM_BUFFER: usize = 64;
+
+/// Buffered flatten progress updates per client; older updates are dropped
+const FLATTEN_STREAM_BUFFER: usize = 16;
+
+/// Largest payload the daemon will sign; callers sign manifests, not artifacts
+const MAX_SIGN_PAYLOAD: usize = 4 * 1024 * 1024;
+
+/// Where the daemon's signing key is kept
+const SIGNING_BACKEND: &str = "file";
 
 /// gRPC service implementation
 pub struct DaemonService {
     state: StateManager,
     qemu: QemuLauncher,
     volume_preparer: VolumePreparer,
+    jobs: JobQueue,
+    audit: AuditLog,
     config: DaemonConfig,
+    replication: Replication,
+    captures: CaptureManager,
+    scenarios: ScenarioRunner,
 }
 
 impl DaemonService {
-    pub fn new(state: StateManager, config: DaemonConfig) -> Self {
+    pub fn new(state: StateManager, jobs: JobQueue, config: DaemonConfig, replication: Replication) -> Self {
+        let mut audit = AuditLog::new(state.db().clone());
+        if config.audit.hash_chain {
+            audit = audit.with_hash_chain(Some(state.key_pair().clone()));
+        }
         Self {
             qemu: QemuLauncher::new(config.clone()),
             volume_preparer: VolumePreparer::new(config.clone()),
+            captures: CaptureManager::new(state.clone()),
+            scenarios: ScenarioRunner::new(state.clone()),
             state,
+            jobs,
+            audit,
             config,
+            replication,
+        }
+    }
+
+    /// Refuse to hand state to standbys unless the config allows it
+    fn check_allow_standbys(&self) -> Result<(), Status> {
+        if self.config.replication.allow_standbys {
+            Ok(())
+        } else {
+            Err(Status::permission_denied(
+                "serving standbys is disabled; set replication.allow_standbys in the daemon config",
+            ))
+        }
+    }
+
+    /// Reject router specs with invalid rules, unknown networks or next hops
+    /// outside every connected network
+    fn check_router(&self, spec: &types::RouterSpec) -> Result<(), Error> {
+        spec.validate()?;
+        let mut networks = Vec::new();
+        for id in &spec.network_ids {
+            let network = self.state.get_network(id)?.ok_or_else(|| Error::NotFound {
+                kind: "network".to_string(),
+                id: id.clone(),
+            })?;
+            if network.spec.mode != NetworkMode::VmnetShared {
+                return Err(Error::InvalidConfig(format!(
+                    "Network {} is {:?}; routers only connect vmnet-shared networks",
+                    network.meta.name, network.spec.mode
+                )));
+            }
+            networks.push(network);
+        }
+        for route in &spec.routes {
+            // Checked by validate()
+            let next_hop: std::net::Ipv4Addr = route.next_hop.parse().unwrap_or(std::net::Ipv4Addr::UNSPECIFIED);
+            if !networks
+                .iter()
+                .any(|n| types::ipv4_cidr_contains(&n.spec.cidr, next_hop))
+            {
+                return Err(Error::InvalidConfig(format!(
+                    "Next hop {} of route {} is not on any of the router's networks",
+                    route.next_hop, route.destination
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject VM specs naming a QoS profile that doesn't exist
+    fn check_qos_profile(&self, spec: &types::VmSpec) -> Result<(), Error> {
+        if let Some(id) = &spec.qos_profile_id {
+            self.state.get_qos_profile(id)?.ok_or_else(|| Error::NotFound {
+                kind: "qos_profile".to_string(),
+                id: id.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Reject starting a VM whose memory or vCPUs don't fit next to the VMs
+    /// already running
+    fn check_admission(&self, vm: &types::Vm) -> Result<(), Error> {
+        let host = admission::HostCapacity::probe(&self.config.store_path);
+        let committed = admission::Committed::of(&self.state.list_vms()?, &vm.meta.id);
+        admission::check_start(&self.config.admission.get(), &host, committed, &vm.spec)
+    }
+
+    /// Reject pinned addresses the networks can't hand out or another VM holds
+    fn check_network_interfaces(&self, spec: &types::VmSpec, vm_id: Option<&str>) -> Result<(), Error> {
+        spec.validate_network_interfaces()?;
+        if spec.network_interfaces.is_empty() {
+            return Ok(());
+        }
+        let others: Vec<types::Vm> = self
+            .state
+            .list_vms()?
+            .into_iter()
+            .filter(|vm| Some(vm.meta.id.as_str()) != vm_id)
+            .collect();
+
+        for attachment in &spec.network_interfaces {
+            if let Some(mac) = attachment.mac.as_deref().and_then(types::parse_unicast_mac) {
+                let holder = others.iter().find(|vm| {
+                    vm.spec
+                        .network_interfaces
+                        .iter()
+                        .any(|a| a.mac.as_deref().and_then(types::parse_unicast_mac).as_deref() == Some(mac.as_str()))
+                });
+                if let Some(holder) = holder {
+                    return Err(Error::InvalidConfig(format!(
+                        "MAC {} is already pinned by VM {}",
+                        mac, holder.meta.name
+                    )));
+                }
+            }
+
+            let Some(ip) = &attachment.ip else { continue };
+            let network = self.state.get_network(&attachment.network_id)?.ok_or_else(|| Error::NotFound {
+                kind: "network".to_string(),
+                id: attachment.network_id.clone(),
+            })?;
+            // Checked by validate_network_interfaces()
+            let addr: std::net::Ipv4Addr = ip.parse().unwrap_or(std::net::Ipv4Addr::UNSPECIFIED);
+            hostnet::check_static_ip(&network, addr)?;
+            let holder = others
+                .iter()
+                .find(|vm| vm.spec.attachment(&network.meta.id).is_some_and(|a| a.ip.as_ref() == Some(ip)));
+            if let Some(holder) = holder {
+                return Err(Error::InvalidConfig(format!(
+                    "{} on network {} is already pinned by VM {}",
+                    ip, network.meta.name, holder.meta.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// What a new policy targets, for its default name: the VM's name or the selector's values
+    fn policy_target_name(&self, vm_id: &str, selector: &HashMap<String, String>) -> Result<String, Status> {
+        match (vm_id.is_empty(), selector.is_empty()) {
+            (false, true) => Ok(self
+                .state
+                .get_vm(vm_id)
+                .map_err(|e| Status::from(e))?
+                .ok_or_else(|| Status::not_found("VM not found"))?
+                .meta
+                .name),
+            (true, false) => {
+                let mut values: Vec<&str> = selector.values().map(String::as_str).collect();
+                values.sort();
+                Ok(values.join("-"))
+            }
+            _ => Err(Status::invalid_argument("set exactly one of vm_id and vm_selector")),
+        }
+    }
+
+    /// QEMU process of a VM that must be running
+    fn running_process(&self, vm_id: &str) -> Result<VmProcess, Status> {
+        self.state
+            .get_vm(vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        self.state
+            .get_vm_process(vm_id)
+            .ok_or_else(|| Status::failed_precondition("VM is not running"))
+    }
+
+    /// Reject changes to the disks of a volume that backs linked clones
+    fn ensure_not_template_disk(&self, volume: &types::Volume) -> Result<(), Status> {
+        match template::template_user(&self.state, &volume.meta.id).map_err(|e| Status::from(e))? {
+            Some(vm) => Err(Status::failed_precondition(format!(
+                "Volume {} is a disk of template {}",
+                volume.meta.name, vm.meta.name
+            ))),
+            None => Ok(()),
         }
     }
 }
 
+/// Templates are immutable: they back the disks of their clones
+fn ensure_not_template(vm: &types::Vm, action: &str) -> Result<(), Status> {
+    if vm.spec.template {
+        return Err(Status::failed_precondition(format!(
+            "VM {} is a template and can't be {}; clone it instead",
+            vm.meta.name, action
+        )));
+    }
+    Ok(())
+}
+
 #[tonic::async_trait]
 impl InfraSimDaemon for DaemonService {
     // ========================================================================
@@ -96,13 +452,13 @@ impl InfraSimDaemon for DaemonService {
 
         let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
 
-        let vm_spec = types::VmSpec {
+        let mut vm_spec = types::VmSpec {
             arch: spec.arch,
             machine: spec.machine,
             cpu_cores: spec.cpu_cores as u32,
             memory_mb: spec.memory_mb as u64,
             volume_ids: spec.volume_ids,
-            network_ids: spec.network_ids,
+            network_ids: attached_network_ids(spec.network_ids, &spec.network_interfaces),
             qos_profile_id: if spec.qos_profile_id.is_empty() {
                 None
             } else {
@@ -116,16 +472,31 @@ impl InfraSimDaemon for DaemonService {
             },
             extra_args: spec.extra_args,
             compatibility_mode: spec.compatibility_mode,
+            template: false,
+            cloned_from: None,
+            network_interfaces: network_attachments_from_proto(spec.network_interfaces),
+            shared_folders: shared_folders_from_proto(spec.shared_folders),
+            priority: spec.priority,
         };
+        self.config.vm_defaults.get().apply(&mut vm_spec);
+        self.check_qos_profile(&vm_spec).map_err(|e| Status::from(e))?;
+        self.check_network_interfaces(&vm_spec, None).map_err(|e| Status::from(e))?;
+        shares::check(&self.config, &vm_spec).map_err(|e| Status::from(e))?;
+        let host = admission::HostCapacity::probe(&self.config.store_path);
+        admission::check_create(&self.config.admission.get(), &host, &vm_spec)
+            .map_err(|e| Status::from(e))?;
 
         let vm = self
             .state
             .create_vm(req.name, vm_spec, req.labels)
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(CreateVmResponse {
-            vm: Some(vm_to_proto(&vm)),
-        }))
+        Ok(audited(
+            CreateVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            AuditChange::new("vm", &vm.meta.id).after(&vm),
+        ))
     }
 
     async fn get_vm(&self, request: Request<GetVmRequest>) -> Result<Response<GetVmResponse>, Status> {
@@ -137,9 +508,21 @@ impl InfraSimDaemon for DaemonService {
             .map_err(|e| Status::from(e))?
             .ok_or_else(|| Status::not_found("VM not found"))?;
 
-        Ok(Response::new(GetVmResponse {
-            vm: Some(vm_to_proto(&vm)),
-        }))
+        let networks: Vec<types::Network> = vm
+            .spec
+            .network_ids
+            .iter()
+            .filter_map(|id| self.state.get_network(id).ok().flatten())
+            .collect();
+        let mut proto = vm_to_proto(&vm);
+        if let Some(status) = proto.status.as_mut() {
+            status.interfaces = addresses::vm_addresses(&self.config, &vm, &networks)
+                .iter()
+                .map(nic_address_to_proto)
+                .collect();
+        }
+
+        Ok(Response::new(GetVmResponse { vm: Some(proto) }))
     }
 
     async fn update_vm(
@@ -149,13 +532,27 @@ impl InfraSimDaemon for DaemonService {
         let req = request.into_inner();
         let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
 
+        let current = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        ensure_not_template(&current, "changed")?;
+
+        // The saved RAM can only be restored into the devices it was taken with
+        if current.status.state == types::VmState::Suspended {
+            return Err(Status::failed_precondition(
+                "VM is suspended; resume or stop it before changing its spec",
+            ));
+        }
+
         let vm_spec = types::VmSpec {
             arch: spec.arch,
             machine: spec.machine,
             cpu_cores: spec.cpu_cores as u32,
             memory_mb: spec.memory_mb as u64,
             volume_ids: spec.volume_ids,
-            network_ids: spec.network_ids,
+            network_ids: attached_network_ids(spec.network_ids, &spec.network_interfaces),
             qos_profile_id: if spec.qos_profile_id.is_empty() {
                 None
             } else {
@@ -169,7 +566,15 @@ impl InfraSimDaemon for DaemonService {
             },
             extra_args: spec.extra_args,
             compatibility_mode: spec.compatibility_mode,
+            template: false,
+            cloned_from: current.spec.cloned_from.clone(),
+            network_interfaces: network_attachments_from_proto(spec.network_interfaces),
+            shared_folders: shared_folders_from_proto(spec.shared_folders),
+            priority: spec.priority,
         };
+        self.check_qos_profile(&vm_spec).map_err(|e| Status::from(e))?;
+        self.check_network_interfaces(&vm_spec, Some(&req.id)).map_err(|e| Status::from(e))?;
+        shares::check(&self.config, &vm_spec).map_err(|e| Status::from(e))?;
 
         self.state
             .update_vm_spec(&req.id, vm_spec)
@@ -181,9 +586,51 @@ impl InfraSimDaemon for DaemonService {
             .map_err(|e| Status::from(e))?
             .ok_or_else(|| Status::not_found("VM not found"))?;
 
-        Ok(Response::new(UpdateVmResponse {
-            vm: Some(vm_to_proto(&vm)),
-        }))
+        Ok(audited(
+            UpdateVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            AuditChange::new("vm", &vm.meta.id).before(&current).after(&vm),
+        ))
+    }
+
+    async fn update_vm_labels(
+        &self,
+        request: Request<UpdateVmLabelsRequest>,
+    ) -> Result<Response<UpdateVmLabelsResponse>, Status> {
+        let req = request.into_inner();
+
+        let current = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        if req.set.keys().chain(&req.remove).any(|k| k.trim().is_empty() || k.contains('=')) {
+            return Err(Status::invalid_argument("label keys must be non-empty and not contain '='"));
+        }
+
+        let mut labels = current.meta.labels.clone();
+        labels.extend(req.set);
+        for key in &req.remove {
+            labels.remove(key);
+        }
+        self.state
+            .update_vm_labels(&req.id, &labels)
+            .map_err(|e| Status::from(e))?;
+
+        let vm = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        Ok(audited(
+            UpdateVmLabelsResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            AuditChange::new("vm", &vm.meta.id).before(&current).after(&vm),
+        ))
     }
 
     async fn delete_vm(
@@ -192,26 +639,40 @@ impl InfraSimDaemon for DaemonService {
     ) -> Result<Response<DeleteVmResponse>, Status> {
         let req = request.into_inner();
 
+        let mut change = AuditChange::new("vm", &req.id);
+        if let Some(vm) = self.state.get_vm(&req.id).map_err(|e| Status::from(e))? {
+            if let Some(reason) = template::deletion_blocker(&self.state, &vm).map_err(|e| Status::from(e))? {
+                return Err(Status::failed_precondition(reason));
+            }
+            change = change.before(&vm);
+        }
+
         // Stop VM if running
         if req.force {
-            let _ = self.qemu.stop(&self.state, &req.id, true).await;
+            let _ = self.qemu.stop(&self.state, &req.id, StopMode::Hard, None).await;
         }
+        let _ = self.qemu.discard_suspended(&self.state, &req.id).await;
 
         self.state
             .delete_vm(&req.id)
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(DeleteVmResponse {}))
+        Ok(audited(DeleteVmResponse {}, change))
     }
 
     async fn list_v_ms(
         &self,
-        _request: Request<ListVMsRequest>,
+        request: Request<ListVMsRequest>,
     ) -> Result<Response<ListVMsResponse>, Status> {
+        let req = request.into_inner();
         let vms = self.state.list_vms().map_err(|e| Status::from(e))?;
 
         Ok(Response::new(ListVMsResponse {
-            vms: vms.into_iter().map(|vm| vm_to_proto(&vm)).collect(),
+            vms: vms
+                .into_iter()
+                .filter(|vm| vm.meta.matches(&req.label_selector))
+                .map(|vm| vm_to_proto(&vm))
+                .collect(),
         }))
     }
 
@@ -226,6 +687,11 @@ impl InfraSimDaemon for DaemonService {
             .get_vm(&req.id)
             .map_err(|e| Status::from(e))?
             .ok_or_else(|| Status::not_found("VM not found"))?;
+        ensure_not_template(&vm, "started")?;
+        if !admission::holds_resources(&vm) {
+            self.check_admission(&vm).map_err(|e| Status::from(e))?;
+        }
+        let change = AuditChange::new("vm", &req.id).before(&vm);
 
         // Set desired state to running
         let status = types::VmStatus {
@@ -251,9 +717,12 @@ impl InfraSimDaemon for DaemonService {
             .map_err(|e| Status::from(e))?
             .ok_or_else(|| Status::not_found("VM not found"))?;
 
-        Ok(Response::new(StartVmResponse {
-            vm: Some(vm_to_proto(&vm)),
-        }))
+        Ok(audited(
+            StartVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            change.after(&vm),
+        ))
     }
 
     async fn stop_vm(
@@ -261,9 +730,15 @@ impl InfraSimDaemon for DaemonService {
         request: Request<StopVmRequest>,
     ) -> Result<Response<StopVmResponse>, Status> {
         let req = request.into_inner();
+        let mode = if req.force || req.mode == ProtoStopMode::Hard as i32 {
+            StopMode::Hard
+        } else {
+            StopMode::Acpi
+        };
+        let timeout = (req.timeout_seconds > 0).then(|| Duration::from_secs(req.timeout_seconds as u64));
 
         self.qemu
-            .stop(&self.state, &req.id, req.force)
+            .stop(&self.state, &req.id, mode, timeout)
             .await
             .map_err(|e| Status::from(e))?;
 
@@ -273,478 +748,2932 @@ impl InfraSimDaemon for DaemonService {
             .map_err(|e| Status::from(e))?
             .ok_or_else(|| Status::not_found("VM not found"))?;
 
-        Ok(Response::new(StopVmResponse {
-            vm: Some(vm_to_proto(&vm)),
-        }))
+        Ok(audited(
+            StopVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            AuditChange::new("vm", &req.id).after(&vm),
+        ))
     }
 
-    // ========================================================================
-    // Network operations
-    // ========================================================================
-
-    async fn create_network(
+    async fn pause_vm(
         &self,
-        request: Request<CreateNetworkRequest>,
-    ) -> Result<Response<CreateNetworkResponse>, Status> {
+        request: Request<PauseVmRequest>,
+    ) -> Result<Response<PauseVmResponse>, Status> {
         let req = request.into_inner();
-        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
-
-        let net_spec = types::NetworkSpec {
-            mode: match ProtoNetworkMode::try_from(spec.mode) {
-                Ok(ProtoNetworkMode::User) => NetworkMode::User,
-                Ok(ProtoNetworkMode::VmnetShared) => NetworkMode::VmnetShared,
-                Ok(ProtoNetworkMode::VmnetBridged) => NetworkMode::VmnetBridged,
-                _ => NetworkMode::User,
-            },
-            cidr: spec.cidr,
-            gateway: if spec.gateway.is_empty() {
-                None
-            } else {
-                Some(spec.gateway)
-            },
-            dns: if spec.dns.is_empty() { None } else { Some(spec.dns) },
-            dhcp_enabled: spec.dhcp_enabled,
-            mtu: spec.mtu as u32,
-        };
 
-        let network = self
+        let vm = self
             .state
-            .create_network(req.name, net_spec, req.labels)
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        let change = AuditChange::new("vm", &req.id).before(&vm);
+
+        self.qemu
+            .pause(&self.state, &vm)
+            .await
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(CreateNetworkResponse {
-            network: Some(network_to_proto(&network)),
-        }))
+        let vm = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        Ok(audited(
+            PauseVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            change.after(&vm),
+        ))
     }
 
-    async fn get_network(
+    async fn resume_vm(
         &self,
-        request: Request<GetNetworkRequest>,
-    ) -> Result<Response<GetNetworkResponse>, Status> {
+        request: Request<ResumeVmRequest>,
+    ) -> Result<Response<ResumeVmResponse>, Status> {
         let req = request.into_inner();
 
-        let network = self
+        let vm = self
             .state
-            .get_network(&req.id)
+            .get_vm(&req.id)
             .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("Network not found"))?;
-
-        Ok(Response::new(GetNetworkResponse {
-            network: Some(network_to_proto(&network)),
-        }))
-    }
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        // Consoles wake VMs put to sleep for being idle, and nothing else
+        if req.if_idle && !self.state.is_idle(&vm.meta.id) {
+            return Ok(Response::new(ResumeVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            }));
+        }
+        let change = AuditChange::new("vm", &req.id).before(&vm);
 
-    async fn delete_network(
-        &self,
-        request: Request<DeleteNetworkRequest>,
-    ) -> Result<Response<DeleteNetworkResponse>, Status> {
-        let req = request.into_inner();
+        // Restoring a suspended VM loads its RAM back
+        if vm.status.state == types::VmState::Suspended {
+            self.check_admission(&vm).map_err(|e| Status::from(e))?;
+        }
 
-        self.state
-            .delete_network(&req.id)
+        self.qemu
+            .resume(&self.state, &vm)
+            .await
             .map_err(|e| Status::from(e))?;
+        self.state.clear_idle(&vm.meta.id);
 
-        Ok(Response::new(DeleteNetworkResponse {}))
-    }
-
-    async fn list_networks(
-        &self,
-        _request: Request<ListNetworksRequest>,
-    ) -> Result<Response<ListNetworksResponse>, Status> {
-        let networks = self.state.list_networks().map_err(|e| Status::from(e))?;
+        let vm = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
 
-        Ok(Response::new(ListNetworksResponse {
-            networks: networks
-                .into_iter()
-                .map(|n| network_to_proto(&n))
-                .collect(),
-        }))
+        Ok(audited(
+            ResumeVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            change.after(&vm),
+        ))
     }
 
-    // ========================================================================
-    // QoS Profile operations
-    // ========================================================================
-
-    async fn create_qo_s_profile(
+    async fn suspend_vm(
         &self,
-        request: Request<CreateQoSProfileRequest>,
-    ) -> Result<Response<CreateQoSProfileResponse>, Status> {
+        request: Request<SuspendVmRequest>,
+    ) -> Result<Response<SuspendVmResponse>, Status> {
         let req = request.into_inner();
-        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
 
-        let qos_spec = types::QosProfileSpec {
-            latency_ms: spec.latency_ms as u32,
-            jitter_ms: spec.jitter_ms as u32,
-            loss_percent: spec.loss_percent,
-            rate_limit_mbps: spec.rate_limit_mbps as u32,
-            packet_padding_bytes: spec.packet_padding_bytes as u32,
-            burst_shaping: spec.burst_shaping,
-            burst_size_kb: spec.burst_size_kb as u32,
-        };
-
-        let profile = self
+        let vm = self
             .state
-            .create_qos_profile(req.name, qos_spec, req.labels)
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        let change = AuditChange::new("vm", &req.id).before(&vm);
+
+        self.qemu
+            .suspend(&self.state, &vm)
+            .await
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(CreateQoSProfileResponse {
-            profile: Some(qos_profile_to_proto(&profile)),
-        }))
+        let vm = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        Ok(audited(
+            SuspendVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            change.after(&vm),
+        ))
     }
 
-    async fn get_qo_s_profile(
+    async fn attach_device(
         &self,
-        request: Request<GetQoSProfileRequest>,
-    ) -> Result<Response<GetQoSProfileResponse>, Status> {
+        request: Request<AttachDeviceRequest>,
+    ) -> Result<Response<AttachDeviceResponse>, Status> {
         let req = request.into_inner();
+        debug!("AttachDevice: {} -> {}", req.device_path, req.vm_id);
 
-        let profile = self
+        if req.device_path.is_empty() {
+            return Err(Status::invalid_argument("device_path required"));
+        }
+
+        let mut vm = self
             .state
-            .get_qos_profile(&req.id)
+            .get_vm(&req.vm_id)
             .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("QoS profile not found"))?;
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        ensure_not_template(&vm, "changed")?;
+        let change = AuditChange::new("vm", &req.vm_id).before(&vm);
 
-        Ok(Response::new(GetQoSProfileResponse {
-            profile: Some(qos_profile_to_proto(&profile)),
-        }))
-    }
+        if self.state.get_vm_process(&req.vm_id).is_some() {
+            return Err(Status::failed_precondition(
+                "VM is running; stop it before attaching a device",
+            ));
+        }
 
-    async fn delete_qo_s_profile(
-        &self,
-        request: Request<DeleteQoSProfileRequest>,
-    ) -> Result<Response<DeleteQoSProfileResponse>, Status> {
-        let req = request.into_inner();
+        let name = if req.name.is_empty() {
+            let device = std::path::Path::new(&req.device_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "device".to_string());
+            format!("{}-{}", vm.meta.name, device)
+        } else {
+            req.name
+        };
 
-        self.state
-            .delete_qos_profile(&req.id)
+        let vol_spec = types::VolumeSpec {
+            kind: VolumeKind::Device,
+            source: req.device_path,
+            read_only: req.read_only,
+            format: "raw".to_string(),
+            ..Default::default()
+        };
+
+        let volume = self
+            .state
+            .create_volume(name, vol_spec, HashMap::new())
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(DeleteQoSProfileResponse {}))
-    }
+        // Run the safety checks now so the caller sees refusals and warnings
+        if let Err(e) = self
+            .volume_preparer
+            .prepare_device(&self.state, &volume, req.force)
+            .await
+        {
+            let _ = self.state.delete_volume(&volume.meta.id);
+            return Err(Status::failed_precondition(e.to_string()));
+        }
+
+        vm.spec.volume_ids.push(volume.meta.id.clone());
+        self.state
+            .update_vm_spec(&vm.meta.id, vm.spec.clone())
+            .map_err(|e| Status::from(e))?;
+
+        let volume = self
+            .state
+            .get_volume(&volume.meta.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+        let vm = self
+            .state
+            .get_vm(&vm.meta.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        info!("Attached device {} to VM {}", volume.spec.source, vm.meta.name);
+
+        Ok(audited(
+            AttachDeviceResponse {
+                warnings: volume.status.warnings.clone(),
+                vm: Some(vm_to_proto(&vm)),
+                volume: Some(volume_to_proto(&volume)),
+            },
+            change.after(&vm),
+        ))
+    }
+
+    type StreamVMLogsStream = ReceiverStream<Result<VmLogEntry, Status>>;
+
+    async fn stream_vm_logs(
+        &self,
+        request: Request<StreamVmLogsRequest>,
+    ) -> Result<Response<Self::StreamVMLogsStream>, Status> {
+        let req = request.into_inner();
+        debug!("StreamVMLogs: {} (follow={})", req.vm_id, req.follow);
+
+        let vm = self
+            .state
+            .get_vm(&req.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        // Subscribe before replaying so no events are missed in between
+        let events = req.include_events.then(|| self.state.subscribe_vm_events());
+        let log_path = self.config.console_log_path(&vm.meta.id);
+
+        let (tx, rx) = mpsc::channel(LOG_STREAM_BUFFER);
+        tokio::spawn(stream_console_log(
+            vm.meta.id,
+            log_path,
+            req.tail_lines as usize,
+            req.follow,
+            events,
+            tx,
+        ));
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn get_vm_stats(
+        &self,
+        request: Request<GetVmStatsRequest>,
+    ) -> Result<Response<VmStats>, Status> {
+        let req = request.into_inner();
+        let process = self.running_process(&req.id)?;
+
+        let usage = stats::current(&self.state, &process)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(vm_stats_to_proto(&process.vm_id, &usage)))
+    }
+
+    type WatchVMStatsStream = ReceiverStream<Result<VmStats, Status>>;
+
+    async fn watch_vm_stats(
+        &self,
+        request: Request<WatchVmStatsRequest>,
+    ) -> Result<Response<Self::WatchVMStatsStream>, Status> {
+        let req = request.into_inner();
+        let process = self.running_process(&req.id)?;
+        let interval = match req.interval_ms {
+            0 => STATS_INTERVAL,
+            ms => Duration::from_millis(ms as u64).max(stats::CPU_SAMPLE_INTERVAL),
+        };
+
+        let (tx, rx) = mpsc::channel(LOG_STREAM_BUFFER);
+        let state = self.state.clone();
+
+        tokio::spawn(async move {
+            let vm_id = process.vm_id.clone();
+            let mut previous = stats::sample(&state, &process, None).await.ok();
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+
+                let process = match state.get_vm_process(&vm_id) {
+                    Some(process) => process,
+                    None => {
+                        let _ = tx.send(Err(Status::failed_precondition("VM stopped"))).await;
+                        return;
+                    }
+                };
+                let usage = match stats::sample(&state, &process, previous.as_ref()).await {
+                    Ok(usage) => usage,
+                    Err(e) => {
+                        let _ = tx.send(Err(Status::from(e))).await;
+                        return;
+                    }
+                };
+
+                if tx.send(Ok(vm_stats_to_proto(&vm_id, &usage))).await.is_err() {
+                    return;
+                }
+                previous = Some(usage);
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type ExportVMStream = ReceiverStream<Result<ExportVmResponse, Status>>;
+
+    async fn export_vm(
+        &self,
+        request: Request<ExportVmRequest>,
+    ) -> Result<Response<Self::ExportVMStream>, Status> {
+        let req = request.into_inner();
+        debug!("ExportVM: {}", req.id);
+
+        let vm = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        let format = match ProtoExportFormat::try_from(req.format) {
+            Ok(ProtoExportFormat::Raw) => ExportFormat::Raw,
+            Ok(ProtoExportFormat::Qcow2) => ExportFormat::Qcow2,
+            _ => ExportFormat::Ova,
+        };
+
+        // Fail fast; the bundle itself is built after the stream is returned
+        export::check_exportable(&self.state, &vm).map_err(|e| Status::from(e))?;
+
+        let (tx, rx) = mpsc::channel(EXPORT_STREAM_BUFFER);
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            match export::export(&state, &vm, format).await {
+                Ok(bundle) => {
+                    send_bundle(&bundle, &tx).await;
+                    let _ = tokio::fs::remove_file(&bundle).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(Status::from(e))).await;
+                }
+            }
+        });
+
+        Ok(audited(ReceiverStream::new(rx), AuditChange::new("vm", &req.id)))
+    }
+
+    async fn import_vm(
+        &self,
+        request: Request<tonic::Streaming<ImportVmRequest>>,
+    ) -> Result<Response<ImportVmResponse>, Status> {
+        let mut stream = request.into_inner();
+
+        let bundle = self
+            .config
+            .import_dir()
+            .join(format!("{}.upload", uuid::Uuid::new_v4()));
+        let options = match receive_bundle(&mut stream, &bundle).await {
+            Ok(options) => options,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&bundle).await;
+                return Err(e);
+            }
+        };
+        debug!("ImportVM: received bundle {}", bundle.display());
+
+        let name = Some(options.name).filter(|n| !n.is_empty());
+        let result = export::import(&self.state, &bundle, name, options.labels).await;
+        let _ = tokio::fs::remove_file(&bundle).await;
+        let (vm, volumes) = result.map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            ImportVmResponse {
+                vm: Some(vm_to_proto(&vm)),
+                volumes: volumes.iter().map(volume_to_proto).collect(),
+            },
+            AuditChange::new("vm", &vm.meta.id).after(&vm),
+        ))
+    }
+
+    async fn push_appliance(
+        &self,
+        request: Request<PushApplianceRequest>,
+    ) -> Result<Response<PushApplianceResponse>, Status> {
+        let req = request.into_inner();
+        info!("PushAppliance: {} to {}", req.vm_id, req.reference);
+
+        let vm = self
+            .state
+            .get_vm(&req.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        let pushed = appliance::push(&self.state, &vm, &req.reference)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            PushApplianceResponse {
+                reference: pushed.reference,
+                digest: pushed.digest,
+                disks: pushed.disks as i32,
+                uploaded_disks: pushed.uploaded as i32,
+                signer_key_id: pushed.signer_key_id,
+            },
+            AuditChange::new("vm", &vm.meta.id),
+        ))
+    }
+
+    async fn pull_appliance(
+        &self,
+        request: Request<PullApplianceRequest>,
+    ) -> Result<Response<PullApplianceResponse>, Status> {
+        let req = request.into_inner();
+        info!("PullAppliance: {}", req.reference);
+
+        let name = Some(req.name).filter(|n| !n.is_empty());
+        let pulled = appliance::pull(&self.state, &req.reference, name, req.labels)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        let change = AuditChange::new("vm", &pulled.vm.meta.id).after(&pulled.vm);
+        Ok(audited(
+            PullApplianceResponse {
+                vm: Some(vm_to_proto(&pulled.vm)),
+                volumes: pulled.volumes.iter().map(volume_to_proto).collect(),
+                digest: pulled.digest,
+                signer_key_id: pulled.signer_key_id,
+                terraform_hcl: pulled.terraform_hcl,
+            },
+            change,
+        ))
+    }
+
+    type AttachSerialStream = ReceiverStream<Result<SerialOutput, Status>>;
+
+    async fn attach_serial(
+        &self,
+        request: Request<tonic::Streaming<SerialInput>>,
+    ) -> Result<Response<Self::AttachSerialStream>, Status> {
+        let mut input = request.into_inner();
+        let first = input
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("vm_id is required"))?;
+        debug!("AttachSerial: {}", first.vm_id);
+
+        let vm = self
+            .state
+            .get_vm(&first.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        if self.state.is_idle(&vm.meta.id) {
+            self.qemu.resume(&self.state, &vm).await.map_err(|e| Status::from(e))?;
+            self.state.clear_idle(&vm.meta.id);
+            self.state.emit_vm_event(&vm.meta.id, "Woken by console connection");
+        }
+        if self.state.get_vm_process(&vm.meta.id).is_none() {
+            return Err(Status::failed_precondition("VM is not running"));
+        }
+
+        // QEMU serves one client per serial socket
+        if !self.state.claim_serial(&vm.meta.id) {
+            return Err(Status::already_exists("serial console is already attached"));
+        }
+        let socket = match UnixStream::connect(self.config.serial_socket_path(&vm.meta.id)).await {
+            Ok(socket) => socket,
+            Err(e) => {
+                self.state.release_serial(&vm.meta.id);
+                return Err(Status::unavailable(format!("serial console unavailable: {}", e)));
+            }
+        };
+
+        let change = AuditChange::new("vm", &vm.meta.id);
+        let (tx, rx) = mpsc::channel(SERIAL_STREAM_BUFFER);
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            relay_serial(socket, first.data, input, tx).await;
+            state.release_serial(&vm.meta.id);
+        });
+
+        Ok(audited(ReceiverStream::new(rx), change))
+    }
+
+    async fn copy_to_guest(
+        &self,
+        request: Request<tonic::Streaming<CopyToGuestRequest>>,
+    ) -> Result<Response<CopyToGuestResponse>, Status> {
+        let mut stream = request.into_inner();
+        let first = stream
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("vm_id is required"))?;
+        if !first.path.starts_with('/') {
+            return Err(Status::invalid_argument("path must be absolute in the guest"));
+        }
+        info!("CopyToGuest: {} to {}", first.vm_id, first.path);
+
+        let vm = self
+            .state
+            .get_vm(&first.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        if self.state.get_vm_process(&vm.meta.id).is_none() {
+            return Err(Status::failed_precondition("VM is not running"));
+        }
+
+        // QEMU serves one client per guest agent socket
+        if !self.state.claim_guest_agent(&vm.meta.id) {
+            return Err(Status::already_exists("another copy into the guest is in progress"));
+        }
+        let result = copy_into_guest(&self.config, &vm.meta.id, first, &mut stream).await;
+        self.state.release_guest_agent(&vm.meta.id);
+        let (path, written) = result?;
+
+        self.state
+            .emit_vm_event(&vm.meta.id, format!("Copied {} bytes to {}", written, path));
+        Ok(audited(
+            CopyToGuestResponse {
+                path,
+                bytes_written: written as i64,
+            },
+            AuditChange::new("vm", &vm.meta.id),
+        ))
+    }
+
+    async fn mark_vm_as_template(
+        &self,
+        request: Request<MarkVmAsTemplateRequest>,
+    ) -> Result<Response<MarkVmAsTemplateResponse>, Status> {
+        let req = request.into_inner();
+        debug!("MarkVMAsTemplate: {}", req.id);
+
+        let vm = self
+            .state
+            .get_vm(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        let change = AuditChange::new("vm", &vm.meta.id).before(&vm);
+        let vm = template::mark(&self.state, &vm).map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            MarkVmAsTemplateResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            change.after(&vm),
+        ))
+    }
+
+    async fn clone_vm(
+        &self,
+        request: Request<CloneVmRequest>,
+    ) -> Result<Response<CloneVmResponse>, Status> {
+        let req = request.into_inner();
+        debug!("CloneVM: {} -> {} x{}", req.template_id, req.name, req.count);
+
+        if req.name.is_empty() {
+            return Err(Status::invalid_argument("name required"));
+        }
+        if req.count < 0 {
+            return Err(Status::invalid_argument("count must not be negative"));
+        }
+        let source = match self.state.get_vm(&req.template_id).map_err(|e| Status::from(e))? {
+            Some(vm) => vm,
+            None => self
+                .state
+                .get_vm_by_name(&req.template_id)
+                .map_err(|e| Status::from(e))?
+                .ok_or_else(|| Status::not_found("Template not found"))?,
+        };
+        if !source.spec.template {
+            return Err(Status::failed_precondition(format!(
+                "VM {} is not a template",
+                source.meta.name
+            )));
+        }
+
+        let names = template::clone_names(&req.name, req.count.max(1) as usize);
+        for name in &names {
+            if self.state.get_vm_by_name(name).map_err(|e| Status::from(e))?.is_some() {
+                return Err(Status::already_exists(format!("VM {} already exists", name)));
+            }
+        }
+
+        let mut vms = Vec::with_capacity(names.len());
+        for name in names {
+            let vm = template::clone_vm(&self.state, &source, name, req.labels.clone())
+                .await
+                .map_err(|e| Status::from(e))?;
+            vms.push(vm_to_proto(&vm));
+        }
+
+        Ok(audited(CloneVmResponse { vms }, AuditChange::new("vm", &source.meta.id)))
+    }
+
+    async fn execute_qmp(
+        &self,
+        request: Request<ExecuteQmpRequest>,
+    ) -> Result<Response<ExecuteQmpResponse>, Status> {
+        let req = request.into_inner();
+        if !self.config.security.allow_qmp_passthrough {
+            return Err(Status::permission_denied(
+                "QMP passthrough is disabled; set security.allow_qmp_passthrough in the daemon config",
+            ));
+        }
+
+        let command: serde_json::Value = serde_json::from_str(&req.command_json)
+            .map_err(|e| Status::invalid_argument(format!("command is not JSON: {}", e)))?;
+        let name = command
+            .get("execute")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| Status::invalid_argument("command needs an \"execute\" field"))?;
+        let process = self.running_process(&req.id)?;
+        info!("QMP passthrough to VM {}: {}", req.id, name);
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await.map_err(|e| Status::from(e))?;
+        let response = qmp.execute_raw(&command).await.map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            ExecuteQmpResponse { response_json: response.to_string() },
+            AuditChange::new("vm", &req.id),
+        ))
+    }
+
+    // ========================================================================
+    // Network operations
+    // ========================================================================
+
+    async fn create_network(
+        &self,
+        request: Request<CreateNetworkRequest>,
+    ) -> Result<Response<CreateNetworkResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let net_spec = types::NetworkSpec {
+            mode: match ProtoNetworkMode::try_from(spec.mode) {
+                Ok(ProtoNetworkMode::User) => NetworkMode::User,
+                Ok(ProtoNetworkMode::VmnetShared) => NetworkMode::VmnetShared,
+                Ok(ProtoNetworkMode::VmnetBridged) => NetworkMode::VmnetBridged,
+                _ => NetworkMode::User,
+            },
+            cidr: spec.cidr,
+            gateway: if spec.gateway.is_empty() {
+                None
+            } else {
+                Some(spec.gateway)
+            },
+            dns: if spec.dns.is_empty() { None } else { Some(spec.dns) },
+            dhcp_enabled: spec.dhcp_enabled,
+            mtu: spec.mtu as u32,
+        };
+
+        let network = self
+            .state
+            .create_network(req.name, net_spec, req.labels)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            CreateNetworkResponse {
+                network: Some(network_to_proto(&network)),
+            },
+            AuditChange::new("network", &network.meta.id).after(&network),
+        ))
+    }
+
+    async fn get_network(
+        &self,
+        request: Request<GetNetworkRequest>,
+    ) -> Result<Response<GetNetworkResponse>, Status> {
+        let req = request.into_inner();
+
+        let network = self
+            .state
+            .get_network(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Network not found"))?;
+
+        Ok(Response::new(GetNetworkResponse {
+            network: Some(network_to_proto(&network)),
+        }))
+    }
+
+    async fn delete_network(
+        &self,
+        request: Request<DeleteNetworkRequest>,
+    ) -> Result<Response<DeleteNetworkResponse>, Status> {
+        let req = request.into_inner();
+
+        let network = self.state.get_network(&req.id).map_err(|e| Status::from(e))?;
+        let routers = self.state.list_routers(Some(&req.id)).map_err(|e| Status::from(e))?;
+        if let Some(router) = routers.first() {
+            return Err(Status::failed_precondition(format!(
+                "Network is connected to router {}; delete the router first",
+                router.meta.name
+            )));
+        }
+        self.state
+            .delete_network(&req.id)
+            .map_err(|e| Status::from(e))?;
+        let change = AuditChange::new("network", &req.id).maybe_before(network.as_ref());
+        self.captures.discard(&req.id).await;
+        if let Some(network) = network {
+            hostnet::remove_network(&self.config, &network).await;
+        }
+
+        Ok(audited(DeleteNetworkResponse {}, change))
+    }
+
+    async fn list_networks(
+        &self,
+        request: Request<ListNetworksRequest>,
+    ) -> Result<Response<ListNetworksResponse>, Status> {
+        let req = request.into_inner();
+        let networks = self.state.list_networks().map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListNetworksResponse {
+            networks: networks
+                .into_iter()
+                .filter(|n| n.meta.matches(&req.label_selector))
+                .map(|n| network_to_proto(&n))
+                .collect(),
+        }))
+    }
+
+    async fn start_network_capture(
+        &self,
+        request: Request<StartNetworkCaptureRequest>,
+    ) -> Result<Response<StartNetworkCaptureResponse>, Status> {
+        let req = request.into_inner();
+        info!("StartNetworkCapture: {}", req.network_id);
+
+        let filter = Some(req.filter).filter(|f| !f.trim().is_empty());
+        let capture = self
+            .captures
+            .start(&req.network_id, filter, req.max_bytes, req.max_seconds)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(StartNetworkCaptureResponse {
+            capture: Some(capture_to_proto(&capture)),
+        }))
+    }
+
+    async fn stop_network_capture(
+        &self,
+        request: Request<StopNetworkCaptureRequest>,
+    ) -> Result<Response<StopNetworkCaptureResponse>, Status> {
+        let req = request.into_inner();
+        info!("StopNetworkCapture: {}", req.network_id);
+
+        let capture = self.captures.stop(&req.network_id).await.map_err(Status::from)?;
+
+        Ok(Response::new(StopNetworkCaptureResponse {
+            capture: Some(capture_to_proto(&capture)),
+        }))
+    }
+
+    type ReadNetworkCaptureStream = ReceiverStream<Result<ReadNetworkCaptureResponse, Status>>;
+
+    async fn read_network_capture(
+        &self,
+        request: Request<ReadNetworkCaptureRequest>,
+    ) -> Result<Response<Self::ReadNetworkCaptureStream>, Status> {
+        let req = request.into_inner();
+        debug!("ReadNetworkCapture: {}", req.network_id);
+
+        let network = self
+            .state
+            .get_network(&req.network_id)
+            .map_err(Status::from)?
+            .ok_or_else(|| Status::not_found(format!("Network not found: {}", req.network_id)))?;
+        match network.status.capture.map(|c| c.state) {
+            None => return Err(Status::not_found("Network has no capture; start one first")),
+            Some(types::CaptureState::Running) => {
+                return Err(Status::failed_precondition("Capture is still running; stop it first"))
+            }
+            Some(_) => {}
+        }
+
+        let path = capture::capture_path(&self.state, &network.meta.id);
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .map_err(|e| Status::not_found(format!("Capture file {}: {}", path.display(), e)))?;
+
+        let (tx, rx) = mpsc::channel(EXPORT_STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; EXPORT_CHUNK_SIZE];
+            loop {
+                let chunk = match file.read(&mut buffer).await {
+                    Ok(0) => return,
+                    Ok(n) => Ok(ReadNetworkCaptureResponse { data: buffer[..n].to_vec() }),
+                    Err(e) => Err(Status::internal(e.to_string())),
+                };
+                let failed = chunk.is_err();
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    // ========================================================================
+    // Router operations
+    // ========================================================================
+
+    async fn create_router(
+        &self,
+        request: Request<CreateRouterRequest>,
+    ) -> Result<Response<CreateRouterResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+        if req.name.is_empty() {
+            return Err(Status::invalid_argument("name required"));
+        }
+
+        let router_spec = router_spec_from_proto(spec);
+        self.check_router(&router_spec).map_err(|e| Status::from(e))?;
+
+        let router = self
+            .state
+            .create_router(req.name, router_spec, req.labels)
+            .map_err(|e| Status::from(e))?;
+
+        info!("Created router: {}", router.meta.name);
+
+        Ok(audited(
+            CreateRouterResponse {
+                router: Some(router_to_proto(&router)),
+            },
+            AuditChange::new("router", &router.meta.id).after(&router),
+        ))
+    }
+
+    async fn get_router(
+        &self,
+        request: Request<GetRouterRequest>,
+    ) -> Result<Response<GetRouterResponse>, Status> {
+        let req = request.into_inner();
+
+        let router = self
+            .state
+            .get_router(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Router not found"))?;
+
+        Ok(Response::new(GetRouterResponse {
+            router: Some(router_to_proto(&router)),
+        }))
+    }
+
+    async fn update_router(
+        &self,
+        request: Request<UpdateRouterRequest>,
+    ) -> Result<Response<UpdateRouterResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let current = self
+            .state
+            .get_router(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Router not found"))?;
+
+        let router_spec = router_spec_from_proto(spec);
+        self.check_router(&router_spec).map_err(|e| Status::from(e))?;
+        self.state
+            .update_router_spec(&req.id, &router_spec)
+            .map_err(|e| Status::from(e))?;
+
+        let router = self
+            .state
+            .get_router(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Router not found"))?;
+
+        Ok(audited(
+            UpdateRouterResponse {
+                router: Some(router_to_proto(&router)),
+            },
+            AuditChange::new("router", &router.meta.id).before(&current).after(&router),
+        ))
+    }
+
+    async fn delete_router(
+        &self,
+        request: Request<DeleteRouterRequest>,
+    ) -> Result<Response<DeleteRouterResponse>, Status> {
+        let req = request.into_inner();
+
+        let current = self.state.get_router(&req.id).map_err(|e| Status::from(e))?;
+        if let Some(spec) = self.state.router_applied_spec(&req.id) {
+            hostnet::remove_router(&req.id, &spec).await;
+        }
+        self.state
+            .delete_router(&req.id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeleteRouterResponse {},
+            AuditChange::new("router", &req.id).maybe_before(current.as_ref()),
+        ))
+    }
+
+    async fn list_routers(
+        &self,
+        request: Request<ListRoutersRequest>,
+    ) -> Result<Response<ListRoutersResponse>, Status> {
+        let req = request.into_inner();
+        let network_id = if req.network_id.is_empty() {
+            None
+        } else {
+            Some(req.network_id.as_str())
+        };
+
+        let routers = self
+            .state
+            .list_routers(network_id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListRoutersResponse {
+            routers: routers
+                .into_iter()
+                .filter(|r| r.meta.matches(&req.label_selector))
+                .map(|r| router_to_proto(&r))
+                .collect(),
+        }))
+    }
+
+    // ========================================================================
+    // Port forward operations
+    // ========================================================================
+
+    async fn create_port_forward(
+        &self,
+        request: Request<CreatePortForwardRequest>,
+    ) -> Result<Response<CreatePortForwardResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let host_port = u16::try_from(spec.host_port)
+            .ok()
+            .filter(|p| *p != 0)
+            .ok_or_else(|| Status::invalid_argument("host_port must be between 1 and 65535"))?;
+        let guest_port = u16::try_from(spec.guest_port)
+            .ok()
+            .filter(|p| *p != 0)
+            .ok_or_else(|| Status::invalid_argument("guest_port must be between 1 and 65535"))?;
+
+        let pf_spec = types::PortForwardSpec {
+            vm_id: spec.vm_id,
+            network_id: if spec.network_id.is_empty() {
+                None
+            } else {
+                Some(spec.network_id)
+            },
+            protocol: match ProtoPortProtocol::try_from(spec.protocol) {
+                Ok(ProtoPortProtocol::Udp) => PortProtocol::Udp,
+                _ => PortProtocol::Tcp,
+            },
+            host_addr: if spec.host_addr.is_empty() {
+                "127.0.0.1".to_string()
+            } else {
+                spec.host_addr
+            },
+            host_port,
+            guest_port,
+        };
+
+        if pf_spec.host_addr.parse::<std::net::IpAddr>().is_err() {
+            return Err(Status::invalid_argument(format!(
+                "Invalid host address: {}",
+                pf_spec.host_addr
+            )));
+        }
+
+        // Resolves the VM and network, rejecting non user-mode networks
+        crate::qemu::netdev_id(&self.state, &pf_spec).map_err(|e| Status::from(e))?;
+
+        let name = if req.name.is_empty() {
+            format!("{}-{}-{}", pf_spec.protocol.as_str(), pf_spec.host_port, pf_spec.guest_port)
+        } else {
+            req.name
+        };
+
+        let forward = self
+            .state
+            .create_port_forward(name, pf_spec, req.labels)
+            .map_err(|e| Status::from(e))?;
+
+        info!("Created port forward: {}", forward.meta.name);
+
+        Ok(audited(
+            CreatePortForwardResponse {
+                port_forward: Some(port_forward_to_proto(&forward)),
+            },
+            AuditChange::new("port_forward", &forward.meta.id).after(&forward),
+        ))
+    }
+
+    async fn get_port_forward(
+        &self,
+        request: Request<GetPortForwardRequest>,
+    ) -> Result<Response<GetPortForwardResponse>, Status> {
+        let req = request.into_inner();
+
+        let forward = self
+            .state
+            .get_port_forward(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Port forward not found"))?;
+
+        Ok(Response::new(GetPortForwardResponse {
+            port_forward: Some(port_forward_to_proto(&forward)),
+        }))
+    }
+
+    async fn delete_port_forward(
+        &self,
+        request: Request<DeletePortForwardRequest>,
+    ) -> Result<Response<DeletePortForwardResponse>, Status> {
+        let req = request.into_inner();
+
+        let current = self
+            .state
+            .get_port_forward(&req.id)
+            .map_err(|e| Status::from(e))?;
+        if let Some(forward) = &current {
+            if self.state.port_forward_applied_pid(&req.id).is_some() {
+                if let Err(e) = self.qemu.remove_port_forward(&self.state, forward).await {
+                    debug!("Failed to remove port forward {} from QEMU: {}", forward.meta.name, e);
+                }
+            }
+        }
+
+        self.state
+            .delete_port_forward(&req.id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeletePortForwardResponse {},
+            AuditChange::new("port_forward", &req.id).maybe_before(current.as_ref()),
+        ))
+    }
+
+    async fn list_port_forwards(
+        &self,
+        request: Request<ListPortForwardsRequest>,
+    ) -> Result<Response<ListPortForwardsResponse>, Status> {
+        let req = request.into_inner();
+        let vm_id = if req.vm_id.is_empty() {
+            None
+        } else {
+            Some(req.vm_id.as_str())
+        };
+
+        let forwards = self
+            .state
+            .list_port_forwards(vm_id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListPortForwardsResponse {
+            port_forwards: forwards
+                .into_iter()
+                .filter(|f| {
+                    req.network_id.is_empty()
+                        || f.spec.network_id.as_deref() == Some(req.network_id.as_str())
+                })
+                .map(|f| port_forward_to_proto(&f))
+                .collect(),
+        }))
+    }
+
+    async fn create_device(
+        &self,
+        request: Request<CreateDeviceRequest>,
+    ) -> Result<Response<CreateDeviceResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let dev_spec = types::DeviceSpec {
+            vm_id: spec.vm_id,
+            bus: match ProtoDeviceBus::try_from(spec.bus) {
+                Ok(ProtoDeviceBus::Pci) => DeviceBus::Pci,
+                _ => DeviceBus::Usb,
+            },
+            vendor_id: usb_id_from_proto(spec.vendor_id, "vendor_id").map_err(|e| Status::from(e))?,
+            product_id: usb_id_from_proto(spec.product_id, "product_id").map_err(|e| Status::from(e))?,
+            host_address: if spec.host_address.is_empty() {
+                None
+            } else {
+                Some(spec.host_address)
+            },
+        };
+
+        let vm = self
+            .state
+            .get_vm(&dev_spec.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        crate::qemu::check_device(&vm, &dev_spec).map_err(|e| Status::from(e))?;
+
+        let name = if req.name.is_empty() {
+            format!("{}-{}", vm.meta.name, dev_spec.describe().replace([' ', ':', '.'], "-"))
+        } else {
+            req.name
+        };
+
+        let device = self
+            .state
+            .create_device(name, dev_spec, req.labels)
+            .map_err(|e| Status::from(e))?;
+
+        info!("Created device: {} ({})", device.meta.name, device.spec.describe());
+
+        Ok(audited(
+            CreateDeviceResponse {
+                device: Some(device_to_proto(&device)),
+            },
+            AuditChange::new("device", &device.meta.id).after(&device),
+        ))
+    }
+
+    async fn get_device(
+        &self,
+        request: Request<GetDeviceRequest>,
+    ) -> Result<Response<GetDeviceResponse>, Status> {
+        let req = request.into_inner();
+
+        let device = self
+            .state
+            .get_device(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Device not found"))?;
+
+        Ok(Response::new(GetDeviceResponse {
+            device: Some(device_to_proto(&device)),
+        }))
+    }
+
+    async fn delete_device(
+        &self,
+        request: Request<DeleteDeviceRequest>,
+    ) -> Result<Response<DeleteDeviceResponse>, Status> {
+        let req = request.into_inner();
+
+        let current = self
+            .state
+            .get_device(&req.id)
+            .map_err(|e| Status::from(e))?;
+        if let Some(device) = &current {
+            let running_pid = self.state.get_vm_process(&device.spec.vm_id).map(|p| p.pid);
+            if running_pid.is_some() && self.state.device_attached_pid(&req.id) == running_pid {
+                if device.spec.bus == DeviceBus::Pci {
+                    return Err(Status::failed_precondition(
+                        "VM is running; stop it before removing a PCI device",
+                    ));
+                }
+                if let Err(e) = self.qemu.detach_device(&self.state, device).await {
+                    debug!("Failed to detach device {} from QEMU: {}", device.meta.name, e);
+                }
+            }
+        }
+
+        self.state
+            .delete_device(&req.id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeleteDeviceResponse {},
+            AuditChange::new("device", &req.id).maybe_before(current.as_ref()),
+        ))
+    }
+
+    async fn list_devices(
+        &self,
+        request: Request<ListDevicesRequest>,
+    ) -> Result<Response<ListDevicesResponse>, Status> {
+        let req = request.into_inner();
+        let vm_id = if req.vm_id.is_empty() {
+            None
+        } else {
+            Some(req.vm_id.as_str())
+        };
+
+        let devices = self
+            .state
+            .list_devices(vm_id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListDevicesResponse {
+            devices: devices.iter().map(device_to_proto).collect(),
+        }))
+    }
+
+    // ========================================================================
+    // QoS Profile operations
+    // ========================================================================
+
+    async fn create_qo_s_profile(
+        &self,
+        request: Request<CreateQoSProfileRequest>,
+    ) -> Result<Response<CreateQoSProfileResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let qos_spec = qos_profile_spec_from_proto(spec).map_err(|e| Status::from(e))?;
+
+        let profile = self
+            .state
+            .create_qos_profile(req.name, qos_spec, req.labels)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            CreateQoSProfileResponse {
+                profile: Some(qos_profile_to_proto(&profile)),
+            },
+            AuditChange::new("qos_profile", &profile.meta.id).after(&profile),
+        ))
+    }
+
+    async fn get_qo_s_profile(
+        &self,
+        request: Request<GetQoSProfileRequest>,
+    ) -> Result<Response<GetQoSProfileResponse>, Status> {
+        let req = request.into_inner();
+
+        let profile = self
+            .state
+            .get_qos_profile(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("QoS profile not found"))?;
+
+        Ok(Response::new(GetQoSProfileResponse {
+            profile: Some(qos_profile_to_proto(&profile)),
+        }))
+    }
+
+    async fn delete_qo_s_profile(
+        &self,
+        request: Request<DeleteQoSProfileRequest>,
+    ) -> Result<Response<DeleteQoSProfileResponse>, Status> {
+        let req = request.into_inner();
+
+        let users: Vec<String> = self
+            .state
+            .list_vms()
+            .map_err(|e| Status::from(e))?
+            .into_iter()
+            .filter(|vm| vm.spec.qos_profile_id.as_deref() == Some(req.id.as_str()))
+            .map(|vm| vm.meta.name)
+            .collect();
+        if !users.is_empty() {
+            return Err(Status::failed_precondition(format!(
+                "QoS profile is used by {}; detach it first",
+                users.join(", ")
+            )));
+        }
+
+        let current = self.state.get_qos_profile(&req.id).map_err(|e| Status::from(e))?;
+        self.state
+            .delete_qos_profile(&req.id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeleteQoSProfileResponse {},
+            AuditChange::new("qos_profile", &req.id).maybe_before(current.as_ref()),
+        ))
+    }
+
+    async fn list_qo_s_profiles(
+        &self,
+        request: Request<ListQoSProfilesRequest>,
+    ) -> Result<Response<ListQoSProfilesResponse>, Status> {
+        let req = request.into_inner();
+        let profiles = self.state.list_qos_profiles().map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListQoSProfilesResponse {
+            profiles: profiles
+                .into_iter()
+                .filter(|p| p.meta.matches(&req.label_selector))
+                .map(|p| qos_profile_to_proto(&p))
+                .collect(),
+        }))
+    }
+
+    // ========================================================================
+    // Chaos Profile operations
+    // ========================================================================
+
+    async fn apply_chaos_profile(
+        &self,
+        request: Request<ApplyChaosProfileRequest>,
+    ) -> Result<Response<ApplyChaosProfileResponse>, Status> {
+        let req = request.into_inner();
+        info!("ApplyChaosProfile: {}", req.name);
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let mut chaos_spec = chaos_profile_spec_from_proto(spec).map_err(Status::from)?;
+        if req.ttl_seconds < 0 {
+            return Err(Status::invalid_argument("ttl_seconds must not be negative"));
+        }
+        if req.ttl_seconds > 0 {
+            chaos_spec.expires_at = Some(chrono::Utc::now().timestamp() + req.ttl_seconds);
+        }
+
+        let current = self
+            .state
+            .get_chaos_profile_by_name(&req.name)
+            .map_err(Status::from)?;
+        let profile = chaos::upsert(&self.state, req.name, chaos_spec, req.labels).map_err(Status::from)?;
+
+        Ok(audited(
+            ApplyChaosProfileResponse {
+                profile: Some(chaos_profile_to_proto(&profile)),
+            },
+            AuditChange::new("chaos_profile", &profile.meta.id)
+                .maybe_before(current.as_ref())
+                .after(&profile),
+        ))
+    }
+
+    async fn get_chaos_profile(
+        &self,
+        request: Request<GetChaosProfileRequest>,
+    ) -> Result<Response<GetChaosProfileResponse>, Status> {
+        let req = request.into_inner();
+
+        let profile = self
+            .state
+            .get_chaos_profile(&req.id)
+            .map_err(Status::from)?
+            .ok_or_else(|| Status::not_found("Chaos profile not found"))?;
+
+        Ok(Response::new(GetChaosProfileResponse {
+            profile: Some(chaos_profile_to_proto(&profile)),
+        }))
+    }
+
+    async fn delete_chaos_profile(
+        &self,
+        request: Request<DeleteChaosProfileRequest>,
+    ) -> Result<Response<DeleteChaosProfileResponse>, Status> {
+        let req = request.into_inner();
+        info!("DeleteChaosProfile: {}", req.id);
+
+        let current = self
+            .state
+            .get_chaos_profile(&req.id)
+            .map_err(Status::from)?
+            .ok_or_else(|| Status::not_found("Chaos profile not found"))?;
+        self.state.delete_chaos_profile(&req.id).map_err(Status::from)?;
+        chaos::remove(&self.state, &current).map_err(Status::from)?;
+
+        Ok(audited(
+            DeleteChaosProfileResponse {},
+            AuditChange::new("chaos_profile", &req.id).before(&current),
+        ))
+    }
+
+    async fn list_chaos_profiles(
+        &self,
+        request: Request<ListChaosProfilesRequest>,
+    ) -> Result<Response<ListChaosProfilesResponse>, Status> {
+        let req = request.into_inner();
+        let profiles = self.state.list_chaos_profiles().map_err(Status::from)?;
+
+        Ok(Response::new(ListChaosProfilesResponse {
+            profiles: profiles
+                .into_iter()
+                .filter(|p| req.network_id.is_empty() || p.spec.network_id.as_deref() == Some(req.network_id.as_str()))
+                .filter(|p| {
+                    req.vm_id.is_empty()
+                        || p.spec.vm_id.as_deref() == Some(req.vm_id.as_str())
+                        || p.status.applied_vm_ids.contains(&req.vm_id)
+                        || p.status.pending_vm_ids.contains(&req.vm_id)
+                })
+                .map(|p| chaos_profile_to_proto(&p))
+                .collect(),
+        }))
+    }
+
+    // ========================================================================
+    // Scenario run operations
+    // ========================================================================
+
+    async fn run_scenario(
+        &self,
+        request: Request<RunScenarioRequest>,
+    ) -> Result<Response<RunScenarioResponse>, Status> {
+        let req = request.into_inner();
+        let run = self.scenarios.start(&req.definition, req.labels).map_err(Status::from)?;
+        info!("RunScenario: {} ({})", run.spec.scenario.name, run.meta.id);
+
+        Ok(audited(
+            RunScenarioResponse {
+                run: Some(scenario_run_to_proto(&run)),
+            },
+            AuditChange::new("scenario_run", &run.meta.id).after(&run),
+        ))
+    }
+
+    async fn get_scenario_run(
+        &self,
+        request: Request<GetScenarioRunRequest>,
+    ) -> Result<Response<GetScenarioRunResponse>, Status> {
+        let req = request.into_inner();
+        let run = self
+            .state
+            .get_scenario_run(&req.id)
+            .map_err(Status::from)?
+            .ok_or_else(|| Status::not_found("Scenario run not found"))?;
+
+        Ok(Response::new(GetScenarioRunResponse {
+            run: Some(scenario_run_to_proto(&run)),
+        }))
+    }
+
+    async fn list_scenario_runs(
+        &self,
+        request: Request<ListScenarioRunsRequest>,
+    ) -> Result<Response<ListScenarioRunsResponse>, Status> {
+        let req = request.into_inner();
+        let scenario = (!req.scenario.is_empty()).then_some(req.scenario.as_str());
+        let runs = self.state.list_scenario_runs(scenario).map_err(Status::from)?;
+
+        Ok(Response::new(ListScenarioRunsResponse {
+            runs: runs.iter().map(scenario_run_to_proto).collect(),
+        }))
+    }
+
+    async fn cancel_scenario_run(
+        &self,
+        request: Request<CancelScenarioRunRequest>,
+    ) -> Result<Response<CancelScenarioRunResponse>, Status> {
+        let req = request.into_inner();
+        info!("CancelScenarioRun: {}", req.id);
+        let run = self.scenarios.cancel(&req.id).map_err(Status::from)?;
+
+        Ok(audited(
+            CancelScenarioRunResponse {
+                run: Some(scenario_run_to_proto(&run)),
+            },
+            AuditChange::new("scenario_run", &req.id).before(&run),
+        ))
+    }
+
+    async fn delete_scenario_run(
+        &self,
+        request: Request<DeleteScenarioRunRequest>,
+    ) -> Result<Response<DeleteScenarioRunResponse>, Status> {
+        let req = request.into_inner();
+        info!("DeleteScenarioRun: {}", req.id);
+
+        let current = self
+            .state
+            .get_scenario_run(&req.id)
+            .map_err(Status::from)?
+            .ok_or_else(|| Status::not_found("Scenario run not found"))?;
+        if self.scenarios.is_running(&req.id) {
+            return Err(Status::failed_precondition(
+                "Scenario run is still running; cancel it first",
+            ));
+        }
+        self.state.delete_scenario_run(&req.id).map_err(Status::from)?;
+
+        Ok(audited(
+            DeleteScenarioRunResponse {},
+            AuditChange::new("scenario_run", &req.id).before(&current),
+        ))
+    }
+
+    async fn compare_scenario_runs(
+        &self,
+        request: Request<CompareScenarioRunsRequest>,
+    ) -> Result<Response<CompareScenarioRunsResponse>, Status> {
+        let req = request.into_inner();
+        let get = |id: &str| {
+            self.state.get_scenario_run(id)?.ok_or_else(|| Error::NotFound {
+                kind: "scenario_run".to_string(),
+                id: id.to_string(),
+            })
+        };
+        let base = get(&req.base_id).map_err(Status::from)?;
+        let other = get(&req.other_id).map_err(Status::from)?;
+
+        Ok(Response::new(CompareScenarioRunsResponse {
+            same_definition: base.spec.digest == other.spec.digest,
+            steps: scenario::compare(&base, &other)
+                .into_iter()
+                .map(|step| ScenarioStepComparison {
+                    name: step.name,
+                    base_outcome: step.base_outcome.map(|o| o.as_str().to_string()).unwrap_or_default(),
+                    other_outcome: step.other_outcome.map(|o| o.as_str().to_string()).unwrap_or_default(),
+                    base_duration_ms: step.base_duration_ms,
+                    other_duration_ms: step.other_duration_ms,
+                    changes: step.changes,
+                })
+                .collect(),
+        }))
+    }
+
+    // ========================================================================
+    // Volume operations
+    // ========================================================================
+
+    async fn create_volume(
+        &self,
+        request: Request<CreateVolumeRequest>,
+    ) -> Result<Response<CreateVolumeResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+        let (encryption, secret) = match spec.encryption {
+            Some(e) => (
+                Some(types::VolumeEncryption {
+                    cipher: if e.cipher.is_empty() {
+                        "aes-256".to_string()
+                    } else {
+                        e.cipher
+                    },
+                    key_id: e.key_id,
+                }),
+                e.secret,
+            ),
+            None => (None, Vec::new()),
+        };
+
+        let vol_spec = types::VolumeSpec {
+            kind: match ProtoVolumeKind::try_from(spec.kind) {
+                Ok(ProtoVolumeKind::Disk) => VolumeKind::Disk,
+                Ok(ProtoVolumeKind::Weights) => VolumeKind::Weights,
+                Ok(ProtoVolumeKind::Device) => VolumeKind::Device,
+                _ => VolumeKind::Disk,
+            },
+            source: spec.source,
+            integrity: spec.integrity.map(|i| types::IntegrityConfig {
+                scheme: i.scheme,
+                public_key: i.public_key,
+                signature: i.signature,
+                expected_digest: if i.expected_digest.is_empty() {
+                    None
+                } else {
+                    Some(i.expected_digest)
+                },
+            }).unwrap_or_default(),
+            read_only: spec.read_only,
+            size_bytes: if spec.size_bytes > 0 {
+                Some(spec.size_bytes as u64)
+            } else {
+                None
+            },
+            format: if spec.format.is_empty() {
+                "qcow2".to_string()
+            } else {
+                spec.format
+            },
+            overlay: spec.overlay,
+            encryption,
+            build: req.build.map(build_inputs).transpose().map_err(Status::from)?,
+        };
+
+        // Remote sources must carry a sha256 digest to verify against
+        if vol_spec.is_remote() {
+            crate::download::expected_digest(&vol_spec)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        }
+
+        // Registry sources must pin the layer they use
+        if vol_spec.source.starts_with(OCI_SCHEME) {
+            let reference = OciReference::parse(&vol_spec.source).map_err(|e| Status::from(e))?;
+            if reference.digest().is_none() {
+                return Err(Status::invalid_argument(
+                    "oci:// sources must name a layer by digest (@sha256:...)",
+                ));
+            }
+        }
+
+        // Only images the daemon writes itself can be encrypted from the start
+        if let Some(enc) = &vol_spec.encryption {
+            let created = [NFS_SCHEME, TMPFS_SCHEME, VOLUME_COPY_SCHEME]
+                .iter()
+                .any(|scheme| vol_spec.source.starts_with(scheme));
+            if vol_spec.kind == VolumeKind::Device || !(vol_spec.source.is_empty() || created) {
+                return Err(Status::invalid_argument(
+                    "Encryption is only supported for blank volumes and volume:// copies",
+                ));
+            }
+            if vol_spec.format != "qcow2" {
+                return Err(Status::invalid_argument("Encrypted volumes must be qcow2"));
+            }
+            Keyring::new(&self.config)
+                .ensure(enc, Some(secret.as_slice()).filter(|s| !s.is_empty()))
+                .map_err(|e| Status::from(e))?;
+        }
+
+        let volume = self
+            .state
+            .create_volume(req.name, vol_spec, req.labels)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            CreateVolumeResponse {
+                volume: Some(volume_to_proto(&volume)),
+            },
+            AuditChange::new("volume", &volume.meta.id).after(&volume),
+        ))
+    }
+
+    async fn get_volume(
+        &self,
+        request: Request<GetVolumeRequest>,
+    ) -> Result<Response<GetVolumeResponse>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        Ok(Response::new(GetVolumeResponse {
+            volume: Some(volume_to_proto(&volume)),
+        }))
+    }
+
+    async fn delete_volume(
+        &self,
+        request: Request<DeleteVolumeRequest>,
+    ) -> Result<Response<DeleteVolumeResponse>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        // Deleting removes the volume's images, so it must not be in use
+        let vms = self.state.list_vms().map_err(|e| Status::from(e))?;
+        if let Some(vm) = vms.iter().find(|vm| {
+            vm.spec.volume_ids.contains(&req.id) || vm.spec.boot_disk_id.as_ref() == Some(&req.id)
+        }) {
+            return Err(Status::failed_precondition(format!(
+                "Volume {} is attached to VM {}",
+                volume.meta.name, vm.meta.name
+            )));
+        }
+
+        self.state
+            .delete_volume(&req.id)
+            .map_err(|e| Status::from(e))?;
+
+        if let Err(e) = self.volume_preparer.release(&volume).await {
+            warn!("Failed to remove images of volume {}: {}", volume.meta.name, e);
+        }
+
+        Ok(audited(
+            DeleteVolumeResponse {},
+            AuditChange::new("volume", &req.id).before(&volume),
+        ))
+    }
+
+    async fn list_volumes(
+        &self,
+        request: Request<ListVolumesRequest>,
+    ) -> Result<Response<ListVolumesResponse>, Status> {
+        let req = request.into_inner();
+        let volumes = self.state.list_volumes().map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListVolumesResponse {
+            volumes: volumes
+                .into_iter()
+                .filter(|v| v.meta.matches(&req.label_selector))
+                .map(|v| volume_to_proto(&v))
+                .collect(),
+        }))
+    }
+
+    type WatchVolumeDownloadStream = ReceiverStream<Result<DownloadStatus, Status>>;
+
+    async fn watch_volume_download(
+        &self,
+        request: Request<WatchVolumeDownloadRequest>,
+    ) -> Result<Response<Self::WatchVolumeDownloadStream>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.volume_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        if !volume.spec.is_remote() {
+            return Err(Status::failed_precondition("Volume does not have a remote source"));
+        }
+
+        let (tx, rx) = mpsc::channel(LOG_STREAM_BUFFER);
+        let state = self.state.clone();
+
+        tokio::spawn(async move {
+            let mut last = None;
+            let mut ticker = tokio::time::interval(DOWNLOAD_POLL_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                let volume = match state.get_volume(&req.volume_id) {
+                    Ok(Some(volume)) => volume,
+                    Ok(None) => {
+                        let _ = tx.send(Err(Status::not_found("Volume deleted"))).await;
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(Status::from(e))).await;
+                        return;
+                    }
+                };
+
+                let download = volume.status.download.unwrap_or_default();
+                if last.as_ref() != Some(&download) {
+                    if tx.send(Ok(download_to_proto(&download))).await.is_err() {
+                        return;
+                    }
+                    last = Some(download.clone());
+                }
+
+                if download.is_finished() || tx.is_closed() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn get_volume_chain(
+        &self,
+        request: Request<GetVolumeChainRequest>,
+    ) -> Result<Response<GetVolumeChainResponse>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.volume_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        let layers = snapshot::chain(&self.state, &volume).map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(GetVolumeChainResponse {
+            layers: layers
+                .into_iter()
+                .map(|layer| VolumeLayer {
+                    path: layer.path,
+                    format: layer.format,
+                    virtual_size: layer.virtual_size as i64,
+                    actual_size: layer.actual_size as i64,
+                    snapshot_id: layer.snapshot_id.unwrap_or_default(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn resize_volume(
+        &self,
+        request: Request<ResizeVolumeRequest>,
+    ) -> Result<Response<ResizeVolumeResponse>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.volume_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        if volume.spec.kind == VolumeKind::Device {
+            return Err(Status::failed_precondition("Device volumes cannot be resized"));
+        }
+        self.ensure_not_template_disk(&volume)?;
+        if volume.spec.read_only {
+            return Err(Status::failed_precondition(format!(
+                "Volume {} is read-only",
+                volume.meta.name
+            )));
+        }
+        if req.size_bytes <= 0 {
+            return Err(Status::invalid_argument("size_bytes must be positive"));
+        }
+
+        self.volume_preparer
+            .resize(&self.state, &volume, req.size_bytes as u64, req.shrink)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        let resized = self
+            .state
+            .get_volume(&req.volume_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        Ok(audited(
+            ResizeVolumeResponse {
+                volume: Some(volume_to_proto(&resized)),
+            },
+            AuditChange::new("volume", &resized.meta.id).before(&volume).after(&resized),
+        ))
+    }
+
+    async fn push_volume(
+        &self,
+        request: Request<PushVolumeRequest>,
+    ) -> Result<Response<PushVolumeResponse>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.volume_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        if volume.spec.kind == VolumeKind::Device {
+            return Err(Status::failed_precondition("Device volumes cannot be pushed"));
+        }
+        // The copy must see a consistent image, so nothing may write to it
+        if let Some(vm) = snapshot::running_user(&self.state, &volume).map_err(|e| Status::from(e))? {
+            return Err(Status::failed_precondition(format!(
+                "Volume {} is in use by running VM {}; stop it first",
+                volume.meta.name, vm.meta.name
+            )));
+        }
+
+        let pushed = transfer::push(&self.state, &volume, &req.remote)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            PushVolumeResponse {
+                digest: pushed.digest,
+                size_bytes: pushed.size_bytes as i64,
+                uploaded: pushed.uploaded,
+            },
+            AuditChange::new("volume", &volume.meta.id),
+        ))
+    }
+
+    async fn pull_volume(
+        &self,
+        request: Request<PullVolumeRequest>,
+    ) -> Result<Response<PullVolumeResponse>, Status> {
+        let req = request.into_inner();
+        let name = if req.name.is_empty() { None } else { Some(req.name) };
+
+        let volume = transfer::pull(&self.state, &req.volume_id, &req.remote, name)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            PullVolumeResponse {
+                volume: Some(volume_to_proto(&volume)),
+            },
+            AuditChange::new("volume", &volume.meta.id).after(&volume),
+        ))
+    }
+
+    type FlattenVolumeStream = ReceiverStream<Result<FlattenProgress, Status>>;
+
+    async fn flatten_volume(
+        &self,
+        request: Request<FlattenVolumeRequest>,
+    ) -> Result<Response<Self::FlattenVolumeStream>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.volume_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+
+        if volume.spec.kind == VolumeKind::Device {
+            return Err(Status::failed_precondition("Device volumes have no backing chain"));
+        }
+        self.ensure_not_template_disk(&volume)?;
+        let layers = snapshot::chain(&self.state, &volume).map_err(|e| Status::from(e))?;
+        if layers.len() < 2 {
+            return Err(Status::failed_precondition(format!(
+                "Volume {} has no backing chain to flatten",
+                volume.meta.name
+            )));
+        }
+        // The copy must see a consistent image, so nothing may write to it
+        if let Some(vm) = snapshot::running_user(&self.state, &volume).map_err(|e| Status::from(e))? {
+            return Err(Status::failed_precondition(format!(
+                "Volume {} is in use by running VM {}; stop it first",
+                volume.meta.name, vm.meta.name
+            )));
+        }
+
+        let (tx, rx) = mpsc::channel(FLATTEN_STREAM_BUFFER);
+        let state = self.state.clone();
+
+        // Keeps running if the client disconnects so the volume isn't left half-switched
+        tokio::spawn(async move {
+            let result = snapshot::flatten(&state, &volume, |percent| {
+                let _ = tx.try_send(Ok(FlattenProgress {
+                    percent,
+                    done: false,
+                    local_path: String::new(),
+                }));
+            })
+            .await;
+
+            let message = match result {
+                Ok(path) => Ok(FlattenProgress {
+                    percent: 100.0,
+                    done: true,
+                    local_path: path.to_string_lossy().to_string(),
+                }),
+                Err(e) => {
+                    warn!("Failed to flatten volume {}: {}", volume.meta.name, e);
+                    Err(Status::from(e))
+                }
+            };
+            let _ = tx.send(message).await;
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    // ========================================================================
+    // Console operations
+    // ========================================================================
+
+    async fn create_console(
+        &self,
+        request: Request<CreateConsoleRequest>,
+    ) -> Result<Response<CreateConsoleResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let console_spec = types::ConsoleSpec {
+            vm_id: spec.vm_id,
+            enable_vnc: spec.enable_vnc,
+            vnc_port: if spec.vnc_port > 0 {
+                Some(spec.vnc_port as u16)
+            } else {
+                None
+            },
+            enable_web: spec.enable_web,
+            web_port: if spec.web_port > 0 {
+                Some(spec.web_port as u16)
+            } else {
+                None
+            },
+            auth_token: if spec.auth_token.is_empty() {
+                None
+            } else {
+                Some(spec.auth_token)
+            },
+        };
+
+        let console = self
+            .state
+            .create_console(req.name, console_spec)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            CreateConsoleResponse {
+                console: Some(console_to_proto(&console)),
+            },
+            AuditChange::new("console", &console.meta.id).after(&console),
+        ))
+    }
+
+    async fn get_console(
+        &self,
+        request: Request<GetConsoleRequest>,
+    ) -> Result<Response<GetConsoleResponse>, Status> {
+        let req = request.into_inner();
+
+        let console = self
+            .state
+            .get_console(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Console not found"))?;
+
+        Ok(Response::new(GetConsoleResponse {
+            console: Some(console_to_proto(&console)),
+        }))
+    }
+
+    async fn delete_console(
+        &self,
+        request: Request<DeleteConsoleRequest>,
+    ) -> Result<Response<DeleteConsoleResponse>, Status> {
+        let req = request.into_inner();
+
+        let current = self.state.get_console(&req.id).map_err(|e| Status::from(e))?;
+        self.state
+            .delete_console(&req.id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeleteConsoleResponse {},
+            AuditChange::new("console", &req.id).maybe_before(current.as_ref()),
+        ))
+    }
+
+    // ========================================================================
+    // Snapshot operations
+    // ========================================================================
+
+    async fn create_snapshot(
+        &self,
+        request: Request<CreateSnapshotRequest>,
+    ) -> Result<Response<CreateSnapshotResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let snap_spec = types::SnapshotSpec {
+            vm_id: spec.vm_id.clone(),
+            include_memory: spec.include_memory,
+            include_disk: spec.include_disk,
+            description: if spec.description.is_empty() {
+                None
+            } else {
+                Some(spec.description)
+            },
+        };
+        if let Some(vm) = self.state.get_vm(&snap_spec.vm_id).map_err(|e| Status::from(e))? {
+            ensure_not_template(&vm, "snapshotted")?;
+        }
+
+        let snapshot = snapshot::take(&self.state, &self.qemu, req.name.clone(), snap_spec, req.labels)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            CreateSnapshotResponse {
+                snapshot: Some(snapshot_to_proto(&snapshot)),
+            },
+            AuditChange::new("snapshot", &snapshot.meta.id).after(&snapshot),
+        ))
+    }
+
+    async fn get_snapshot(
+        &self,
+        request: Request<GetSnapshotRequest>,
+    ) -> Result<Response<GetSnapshotResponse>, Status> {
+        let req = request.into_inner();
+
+        let snapshot = self
+            .state
+            .get_snapshot(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
+
+        Ok(Response::new(GetSnapshotResponse {
+            snapshot: Some(snapshot_to_proto(&snapshot)),
+        }))
+    }
+
+    async fn delete_snapshot(
+        &self,
+        request: Request<DeleteSnapshotRequest>,
+    ) -> Result<Response<DeleteSnapshotResponse>, Status> {
+        let req = request.into_inner();
+
+        let snapshot = self
+            .state
+            .get_snapshot(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
+
+        if let Some(reason) = snapshot::deletion_blocker(&self.state, &snapshot.meta.id)
+            .map_err(|e| Status::from(e))?
+        {
+            return Err(Status::failed_precondition(format!(
+                "Snapshot {} cannot be deleted: {}",
+                snapshot.meta.name, reason
+            )));
+        }
+
+        snapshot::delete(&self.state, &snapshot.meta.id)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeleteSnapshotResponse {},
+            AuditChange::new("snapshot", &snapshot.meta.id).before(&snapshot),
+        ))
+    }
+
+    async fn list_snapshots(
+        &self,
+        request: Request<ListSnapshotsRequest>,
+    ) -> Result<Response<ListSnapshotsResponse>, Status> {
+        let req = request.into_inner();
+        let vm_id = if req.vm_id.is_empty() {
+            None
+        } else {
+            Some(req.vm_id.as_str())
+        };
+
+        let snapshots = self
+            .state
+            .list_snapshots(vm_id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListSnapshotsResponse {
+            snapshots: snapshots
+                .into_iter()
+                .filter(|s| s.meta.matches(&req.label_selector))
+                .map(|s| snapshot_to_proto(&s))
+                .collect(),
+        }))
+    }
+
+    async fn restore_snapshot(
+        &self,
+        request: Request<RestoreSnapshotRequest>,
+    ) -> Result<Response<RestoreSnapshotResponse>, Status> {
+        let req = request.into_inner();
+
+        let snapshot = self
+            .state
+            .get_snapshot(&req.snapshot_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
+
+        let current = self
+            .state
+            .get_vm(&req.target_vm_id)
+            .map_err(|e| Status::from(e))?;
+
+        // Restore via QMP
+        self.qemu
+            .restore_internal_snapshot(&self.state, &req.target_vm_id, &snapshot.meta.name)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        let vm = self
+            .state
+            .get_vm(&req.target_vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        Ok(audited(
+            RestoreSnapshotResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            AuditChange::new("vm", &vm.meta.id)
+                .maybe_before(current.as_ref())
+                .after(&vm),
+        ))
+    }
+
+    async fn revert_snapshot(
+        &self,
+        request: Request<RevertSnapshotRequest>,
+    ) -> Result<Response<RevertSnapshotResponse>, Status> {
+        let req = request.into_inner();
+
+        let snapshot = self
+            .state
+            .get_snapshot(&req.snapshot_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
+        if snapshot.status.disk_snapshot_path.is_none() {
+            return Err(Status::failed_precondition(
+                "Snapshot does not include a disk layer",
+            ));
+        }
+
+        let vm = self
+            .state
+            .get_vm(&snapshot.spec.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        ensure_not_template(&vm, "reverted")?;
+
+        let reverted = snapshot::revert(&self.state, &self.qemu, &vm, &snapshot)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            RevertSnapshotResponse {
+                vm: Some(vm_to_proto(&reverted)),
+            },
+            AuditChange::new("vm", &vm.meta.id).before(&vm).after(&reverted),
+        ))
+    }
+
+    async fn branch_snapshot(
+        &self,
+        request: Request<BranchSnapshotRequest>,
+    ) -> Result<Response<BranchSnapshotResponse>, Status> {
+        let req = request.into_inner();
+        if req.name.is_empty() {
+            return Err(Status::invalid_argument("name required"));
+        }
+
+        let snapshot = self
+            .state
+            .get_snapshot(&req.snapshot_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
+        if snapshot.status.disk_snapshot_path.is_none() {
+            return Err(Status::failed_precondition(
+                "Snapshot does not include a disk layer",
+            ));
+        }
+
+        let source = self
+            .state
+            .get_vm(&snapshot.spec.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        let vm = snapshot::branch(&self.state, &source, &snapshot, req.name)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            BranchSnapshotResponse {
+                vm: Some(vm_to_proto(&vm)),
+            },
+            AuditChange::new("vm", &vm.meta.id).after(&vm),
+        ))
+    }
+
+    async fn list_snapshot_files(
+        &self,
+        request: Request<ListSnapshotFilesRequest>,
+    ) -> Result<Response<ListSnapshotFilesResponse>, Status> {
+        let req = request.into_inner();
+        debug!("ListSnapshotFiles: {} {}", req.snapshot_id, req.path);
+
+        let (_, layer) =
+            snapshot_files::layer(&self.state, &req.snapshot_id).map_err(|e| Status::from(e))?;
+        let files = snapshot_files::list(&layer, &req.path, req.recursive, false)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListSnapshotFilesResponse {
+            files: files.iter().map(guest_file_to_proto).collect(),
+        }))
+    }
+
+    type ReadSnapshotFileStream = ReceiverStream<Result<ReadSnapshotFileResponse, Status>>;
+
+    async fn read_snapshot_file(
+        &self,
+        request: Request<ReadSnapshotFileRequest>,
+    ) -> Result<Response<Self::ReadSnapshotFileStream>, Status> {
+        let req = request.into_inner();
+        debug!("ReadSnapshotFile: {} {}", req.snapshot_id, req.path);
+
+        let (_, layer) =
+            snapshot_files::layer(&self.state, &req.snapshot_id).map_err(|e| Status::from(e))?;
+        let mut child = snapshot_files::read(&layer, &req.path).map_err(|e| Status::from(e))?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+
+        let (tx, rx) = mpsc::channel(EXPORT_STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; EXPORT_CHUNK_SIZE];
+            loop {
+                match stdout.read(&mut buffer).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        let chunk = ReadSnapshotFileResponse {
+                            data: buffer[..n].to_vec(),
+                        };
+                        // Dropping the child on return kills virt-cat
+                        if tx.send(Ok(chunk)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                }
+            }
+
+            // Missing files and other failures only show in the exit status
+            match child.wait_with_output().await {
+                Ok(output) if output.status.success() => {}
+                Ok(output) => {
+                    let error = snapshot_files::tool_error("virt-cat", &req.path, &output.stderr);
+                    let _ = tx.send(Err(Status::from(error))).await;
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                }
+            }
+        });
 
-    async fn list_qo_s_profiles(
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn diff_snapshots(
         &self,
-        _request: Request<ListQoSProfilesRequest>,
-    ) -> Result<Response<ListQoSProfilesResponse>, Status> {
-        let profiles = self.state.list_qos_profiles().map_err(|e| Status::from(e))?;
+        request: Request<DiffSnapshotsRequest>,
+    ) -> Result<Response<DiffSnapshotsResponse>, Status> {
+        let req = request.into_inner();
+        info!("DiffSnapshots: {} -> {} under {}", req.snapshot_a, req.snapshot_b, req.path);
+
+        let (a, layer_a) =
+            snapshot_files::layer(&self.state, &req.snapshot_a).map_err(|e| Status::from(e))?;
+        let (b, layer_b) =
+            snapshot_files::layer(&self.state, &req.snapshot_b).map_err(|e| Status::from(e))?;
+        if a.spec.vm_id != b.spec.vm_id {
+            return Err(Status::invalid_argument(format!(
+                "Snapshots {} and {} belong to different VMs",
+                a.meta.name, b.meta.name
+            )));
+        }
 
-        Ok(Response::new(ListQoSProfilesResponse {
-            profiles: profiles
+        let (before, after) = tokio::try_join!(
+            snapshot_files::list(&layer_a, &req.path, true, true),
+            snapshot_files::list(&layer_b, &req.path, true, true),
+        )
+        .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(DiffSnapshotsResponse {
+            changes: snapshot_files::diff(before, after)
                 .into_iter()
-                .map(|p| qos_profile_to_proto(&p))
+                .map(|change| SnapshotFileChange {
+                    path: change.path,
+                    change: match change.change {
+                        Change::Added => ProtoFileChange::Added,
+                        Change::Removed => ProtoFileChange::Removed,
+                        Change::Modified => ProtoFileChange::Modified,
+                    } as i32,
+                    before: change.before.as_ref().map(guest_file_to_proto),
+                    after: change.after.as_ref().map(guest_file_to_proto),
+                })
                 .collect(),
         }))
     }
 
     // ========================================================================
-    // Volume operations
+    // Snapshot policy operations
     // ========================================================================
 
-    async fn create_volume(
+    async fn create_snapshot_policy(
         &self,
-        request: Request<CreateVolumeRequest>,
-    ) -> Result<Response<CreateVolumeResponse>, Status> {
+        request: Request<CreateSnapshotPolicyRequest>,
+    ) -> Result<Response<CreateSnapshotPolicyResponse>, Status> {
         let req = request.into_inner();
         let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+        let policy_spec = snapshot_policy_spec_from_proto(spec).map_err(|e| Status::from(e))?;
+        let target = self.policy_target_name(&policy_spec.vm_id, &policy_spec.vm_selector)?;
 
-        let vol_spec = types::VolumeSpec {
-            kind: match ProtoVolumeKind::try_from(spec.kind) {
-                Ok(ProtoVolumeKind::Disk) => VolumeKind::Disk,
-                Ok(ProtoVolumeKind::Weights) => VolumeKind::Weights,
-                _ => VolumeKind::Disk,
-            },
-            source: spec.source,
-            integrity: spec.integrity.map(|i| types::IntegrityConfig {
-                scheme: i.scheme,
-                public_key: i.public_key,
-                signature: i.signature,
-                expected_digest: if i.expected_digest.is_empty() {
-                    None
-                } else {
-                    Some(i.expected_digest)
-                },
-            }).unwrap_or_default(),
-            read_only: spec.read_only,
-            size_bytes: if spec.size_bytes > 0 {
-                Some(spec.size_bytes as u64)
-            } else {
-                None
-            },
-            format: if spec.format.is_empty() {
-                "qcow2".to_string()
-            } else {
-                spec.format
-            },
-            overlay: spec.overlay,
+        let name = if req.name.is_empty() {
+            format!("{}-every-{}", target, types::format_interval(policy_spec.interval_secs))
+        } else {
+            req.name
         };
 
-        let volume = self
+        let policy = self
             .state
-            .create_volume(req.name, vol_spec, req.labels)
+            .create_snapshot_policy(name, policy_spec, req.labels)
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(CreateVolumeResponse {
-            volume: Some(volume_to_proto(&volume)),
-        }))
+        info!("Created snapshot policy: {} ({})", policy.meta.name, policy.spec.schedule());
+
+        Ok(audited(
+            CreateSnapshotPolicyResponse {
+                policy: Some(snapshot_policy_to_proto(&policy)),
+            },
+            AuditChange::new("snapshot_policy", &policy.meta.id).after(&policy),
+        ))
     }
 
-    async fn get_volume(
+    async fn get_snapshot_policy(
         &self,
-        request: Request<GetVolumeRequest>,
-    ) -> Result<Response<GetVolumeResponse>, Status> {
+        request: Request<GetSnapshotPolicyRequest>,
+    ) -> Result<Response<GetSnapshotPolicyResponse>, Status> {
         let req = request.into_inner();
 
-        let volume = self
+        let policy = self
             .state
-            .get_volume(&req.id)
+            .get_snapshot_policy(&req.id)
             .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("Volume not found"))?;
+            .ok_or_else(|| Status::not_found("Snapshot policy not found"))?;
 
-        Ok(Response::new(GetVolumeResponse {
-            volume: Some(volume_to_proto(&volume)),
+        Ok(Response::new(GetSnapshotPolicyResponse {
+            policy: Some(snapshot_policy_to_proto(&policy)),
         }))
     }
 
-    async fn delete_volume(
+    async fn update_snapshot_policy(
         &self,
-        request: Request<DeleteVolumeRequest>,
-    ) -> Result<Response<DeleteVolumeResponse>, Status> {
+        request: Request<UpdateSnapshotPolicyRequest>,
+    ) -> Result<Response<UpdateSnapshotPolicyResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+
+        let policy = self
+            .state
+            .get_snapshot_policy(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Snapshot policy not found"))?;
+
+        let mut policy_spec = snapshot_policy_spec_from_proto(spec).map_err(|e| Status::from(e))?;
+        keep_policy_target(
+            "snapshot",
+            (&mut policy_spec.vm_id, &mut policy_spec.vm_selector),
+            (&policy.spec.vm_id, &policy.spec.vm_selector),
+        )?;
+
+        self.state
+            .update_snapshot_policy_spec(&req.id, policy_spec)
+            .map_err(|e| Status::from(e))?;
+
+        let updated = self
+            .state
+            .get_snapshot_policy(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Snapshot policy not found"))?;
+
+        Ok(audited(
+            UpdateSnapshotPolicyResponse {
+                policy: Some(snapshot_policy_to_proto(&updated)),
+            },
+            AuditChange::new("snapshot_policy", &updated.meta.id).before(&policy).after(&updated),
+        ))
+    }
+
+    async fn delete_snapshot_policy(
+        &self,
+        request: Request<DeleteSnapshotPolicyRequest>,
+    ) -> Result<Response<DeleteSnapshotPolicyResponse>, Status> {
         let req = request.into_inner();
 
+        let current = self.state.get_snapshot_policy(&req.id).map_err(|e| Status::from(e))?;
+        // Snapshots already taken by the policy are kept
         self.state
-            .delete_volume(&req.id)
+            .delete_snapshot_policy(&req.id)
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(DeleteVolumeResponse {}))
+        Ok(audited(
+            DeleteSnapshotPolicyResponse {},
+            AuditChange::new("snapshot_policy", &req.id).maybe_before(current.as_ref()),
+        ))
     }
 
-    async fn list_volumes(
+    async fn list_snapshot_policies(
         &self,
-        _request: Request<ListVolumesRequest>,
-    ) -> Result<Response<ListVolumesResponse>, Status> {
-        let volumes = self.state.list_volumes().map_err(|e| Status::from(e))?;
+        request: Request<ListSnapshotPoliciesRequest>,
+    ) -> Result<Response<ListSnapshotPoliciesResponse>, Status> {
+        let req = request.into_inner();
+        let vm_id = if req.vm_id.is_empty() {
+            None
+        } else {
+            Some(req.vm_id.as_str())
+        };
 
-        Ok(Response::new(ListVolumesResponse {
-            volumes: volumes
-                .into_iter()
-                .map(|v| volume_to_proto(&v))
+        let policies = self
+            .state
+            .list_snapshot_policies(vm_id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListSnapshotPoliciesResponse {
+            policies: policies
+                .iter()
+                .map(snapshot_policy_to_proto)
                 .collect(),
         }))
     }
 
     // ========================================================================
-    // Console operations
+    // Backup operations
     // ========================================================================
 
-    async fn create_console(
+    async fn create_backup(
         &self,
-        request: Request<CreateConsoleRequest>,
-    ) -> Result<Response<CreateConsoleResponse>, Status> {
+        request: Request<CreateBackupRequest>,
+    ) -> Result<Response<CreateBackupResponse>, Status> {
         let req = request.into_inner();
         let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
 
-        let console_spec = types::ConsoleSpec {
+        let vm = self
+            .state
+            .get_vm(&spec.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+
+        let name = if req.name.is_empty() {
+            format!("{}-{}", vm.meta.name, chrono::Utc::now().format("%Y%m%d-%H%M%S"))
+        } else {
+            req.name
+        };
+        let backup_spec = types::BackupSpec {
             vm_id: spec.vm_id,
-            enable_vnc: spec.enable_vnc,
-            vnc_port: if spec.vnc_port > 0 {
-                Some(spec.vnc_port as u16)
-            } else {
-                None
-            },
-            enable_web: spec.enable_web,
-            web_port: if spec.web_port > 0 {
-                Some(spec.web_port as u16)
-            } else {
-                None
+            target: spec.target,
+            incremental: spec.incremental,
+        };
+
+        let backup = backup::create(&self.state, name, backup_spec, req.labels)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            CreateBackupResponse {
+                backup: Some(backup_to_proto(&backup)),
             },
-            auth_token: if spec.auth_token.is_empty() {
-                None
-            } else {
-                Some(spec.auth_token)
+            AuditChange::new("backup", &backup.meta.id).after(&backup),
+        ))
+    }
+
+    async fn get_backup(
+        &self,
+        request: Request<GetBackupRequest>,
+    ) -> Result<Response<GetBackupResponse>, Status> {
+        let req = request.into_inner();
+
+        let backup = self
+            .state
+            .get_backup(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Backup not found"))?;
+
+        Ok(Response::new(GetBackupResponse {
+            backup: Some(backup_to_proto(&backup)),
+        }))
+    }
+
+    async fn delete_backup(
+        &self,
+        request: Request<DeleteBackupRequest>,
+    ) -> Result<Response<DeleteBackupResponse>, Status> {
+        let req = request.into_inner();
+
+        let backup = self
+            .state
+            .get_backup(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Backup not found"))?;
+
+        if let Some(reason) = backup::deletion_blocker(&self.state, &backup.meta.id)
+            .map_err(|e| Status::from(e))?
+        {
+            return Err(Status::failed_precondition(format!(
+                "Backup {} cannot be deleted: {}",
+                backup.meta.name, reason
+            )));
+        }
+
+        backup::delete(&self.state, &backup.meta.id)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeleteBackupResponse {},
+            AuditChange::new("backup", &backup.meta.id).before(&backup),
+        ))
+    }
+
+    async fn list_backups(
+        &self,
+        request: Request<ListBackupsRequest>,
+    ) -> Result<Response<ListBackupsResponse>, Status> {
+        let req = request.into_inner();
+        let vm_id = if req.vm_id.is_empty() {
+            None
+        } else {
+            Some(req.vm_id.as_str())
+        };
+
+        let mut backups = self
+            .state
+            .list_backups(vm_id)
+            .map_err(|e| Status::from(e))?;
+        backups.sort_by_key(|b| b.meta.created_at);
+
+        Ok(Response::new(ListBackupsResponse {
+            backups: backups.iter().map(backup_to_proto).collect(),
+        }))
+    }
+
+    async fn restore_backup(
+        &self,
+        request: Request<RestoreBackupRequest>,
+    ) -> Result<Response<RestoreBackupResponse>, Status> {
+        let req = request.into_inner();
+        let name = if req.name.is_empty() { None } else { Some(req.name) };
+
+        let (vm, volumes) = backup::restore(&self.state, &req.backup_id, name)
+            .await
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            RestoreBackupResponse {
+                vm: Some(vm_to_proto(&vm)),
+                volumes: volumes.iter().map(volume_to_proto).collect(),
             },
+            AuditChange::new("vm", &vm.meta.id).after(&vm),
+        ))
+    }
+
+    // ========================================================================
+    // Backup policy operations
+    // ========================================================================
+
+    async fn create_backup_policy(
+        &self,
+        request: Request<CreateBackupPolicyRequest>,
+    ) -> Result<Response<CreateBackupPolicyResponse>, Status> {
+        let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+        let policy_spec = backup_policy_spec_from_proto(spec).map_err(|e| Status::from(e))?;
+        let target = self.policy_target_name(&policy_spec.vm_id, &policy_spec.vm_selector)?;
+
+        let name = if req.name.is_empty() {
+            format!("{}-backup-every-{}", target, types::format_interval(policy_spec.interval_secs))
+        } else {
+            req.name
         };
 
-        let console = self
+        let policy = self
             .state
-            .create_console(req.name, console_spec)
+            .create_backup_policy(name, policy_spec, req.labels)
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(CreateConsoleResponse {
-            console: Some(console_to_proto(&console)),
+        info!("Created backup policy: {} ({})", policy.meta.name, policy.spec.schedule());
+
+        Ok(audited(
+            CreateBackupPolicyResponse {
+                policy: Some(backup_policy_to_proto(&policy)),
+            },
+            AuditChange::new("backup_policy", &policy.meta.id).after(&policy),
+        ))
+    }
+
+    async fn get_backup_policy(
+        &self,
+        request: Request<GetBackupPolicyRequest>,
+    ) -> Result<Response<GetBackupPolicyResponse>, Status> {
+        let req = request.into_inner();
+
+        let policy = self
+            .state
+            .get_backup_policy(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Backup policy not found"))?;
+
+        Ok(Response::new(GetBackupPolicyResponse {
+            policy: Some(backup_policy_to_proto(&policy)),
         }))
     }
 
-    async fn get_console(
+    async fn update_backup_policy(
         &self,
-        request: Request<GetConsoleRequest>,
-    ) -> Result<Response<GetConsoleResponse>, Status> {
+        request: Request<UpdateBackupPolicyRequest>,
+    ) -> Result<Response<UpdateBackupPolicyResponse>, Status> {
         let req = request.into_inner();
+        let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
 
-        let console = self
+        let policy = self
             .state
-            .get_console(&req.id)
+            .get_backup_policy(&req.id)
             .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("Console not found"))?;
+            .ok_or_else(|| Status::not_found("Backup policy not found"))?;
+
+        let mut policy_spec = backup_policy_spec_from_proto(spec).map_err(|e| Status::from(e))?;
+        keep_policy_target(
+            "backup",
+            (&mut policy_spec.vm_id, &mut policy_spec.vm_selector),
+            (&policy.spec.vm_id, &policy.spec.vm_selector),
+        )?;
+
+        self.state
+            .update_backup_policy_spec(&req.id, policy_spec)
+            .map_err(|e| Status::from(e))?;
+
+        let updated = self
+            .state
+            .get_backup_policy(&req.id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("Backup policy not found"))?;
+
+        Ok(audited(
+            UpdateBackupPolicyResponse {
+                policy: Some(backup_policy_to_proto(&updated)),
+            },
+            AuditChange::new("backup_policy", &updated.meta.id).before(&policy).after(&updated),
+        ))
+    }
+
+    async fn delete_backup_policy(
+        &self,
+        request: Request<DeleteBackupPolicyRequest>,
+    ) -> Result<Response<DeleteBackupPolicyResponse>, Status> {
+        let req = request.into_inner();
+
+        let current = self.state.get_backup_policy(&req.id).map_err(|e| Status::from(e))?;
+        // Backups already taken by the policy are kept
+        self.state
+            .delete_backup_policy(&req.id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(
+            DeleteBackupPolicyResponse {},
+            AuditChange::new("backup_policy", &req.id).maybe_before(current.as_ref()),
+        ))
+    }
+
+    async fn list_backup_policies(
+        &self,
+        request: Request<ListBackupPoliciesRequest>,
+    ) -> Result<Response<ListBackupPoliciesResponse>, Status> {
+        let req = request.into_inner();
+        let vm_id = if req.vm_id.is_empty() {
+            None
+        } else {
+            Some(req.vm_id.as_str())
+        };
+
+        let policies = self
+            .state
+            .list_backup_policies(vm_id)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListBackupPoliciesResponse {
+            policies: policies
+                .iter()
+                .map(backup_policy_to_proto)
+                .collect(),
+        }))
+    }
+
+    // ========================================================================
+    // Archive operations
+    // ========================================================================
+
+    async fn create_archive(
+        &self,
+        request: Request<CreateArchiveRequest>,
+    ) -> Result<Response<CreateArchiveResponse>, Status> {
+        use infrasim_common::crypto::Signer;
+
+        let req = request.into_inner();
+        info!(
+            "CreateArchive: {} volumes, {} snapshots",
+            req.volume_ids.len(),
+            req.snapshot_ids.len()
+        );
+        if req.metadata.len() > MAX_SIGN_PAYLOAD {
+            return Err(Status::invalid_argument(format!(
+                "metadata is {} bytes, limit is {}",
+                req.metadata.len(),
+                MAX_SIGN_PAYLOAD
+            )));
+        }
+
+        let created = archive::create(
+            &self.state,
+            &req.volume_ids,
+            &req.snapshot_ids,
+            req.include_memory,
+            &req.metadata,
+        )
+        .await
+        .map_err(|e| Status::from(e))?;
+
+        // The digest covers every file, each listed with its own digest
+        let key_pair = self.state.key_pair();
+        let change = AuditChange::new("archive", &created.digest);
+        Ok(audited(
+            CreateArchiveResponse {
+                signature: key_pair.sign(created.digest.as_bytes()),
+                public_key: key_pair.public_key_hex(),
+                key_id: key_pair.key_id(),
+                digest: created.digest,
+                size_bytes: created.size_bytes,
+            },
+            change,
+        ))
+    }
+
+    type DownloadArchiveStream = ReceiverStream<Result<DownloadArchiveResponse, Status>>;
+
+    async fn download_archive(
+        &self,
+        request: Request<DownloadArchiveRequest>,
+    ) -> Result<Response<Self::DownloadArchiveStream>, Status> {
+        let req = request.into_inner();
+        debug!("DownloadArchive: {} from byte {}", req.digest, req.offset);
+
+        let (path, size) = archive::open(&self.state, &req.digest)
+            .await
+            .map_err(|e| Status::from(e))?;
+        if req.offset > size {
+            return Err(Status::out_of_range(format!(
+                "offset {} is past the end of the {} byte archive",
+                req.offset, size
+            )));
+        }
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        file.seek(std::io::SeekFrom::Start(req.offset))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let (tx, rx) = mpsc::channel(EXPORT_STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; EXPORT_CHUNK_SIZE];
+            loop {
+                let chunk = match file.read(&mut buffer).await {
+                    Ok(0) => return,
+                    Ok(n) => Ok(DownloadArchiveResponse {
+                        data: buffer[..n].to_vec(),
+                        size_bytes: size,
+                    }),
+                    Err(e) => Err(Status::internal(e.to_string())),
+                };
+                let failed = chunk.is_err();
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
 
-        Ok(Response::new(GetConsoleResponse {
-            console: Some(console_to_proto(&console)),
-        }))
+        Ok(Response::new(ReceiverStream::new(rx)))
     }
 
-    async fn delete_console(
+    async fn restore_archive(
         &self,
-        request: Request<DeleteConsoleRequest>,
-    ) -> Result<Response<DeleteConsoleResponse>, Status> {
+        request: Request<RestoreArchiveRequest>,
+    ) -> Result<Response<RestoreArchiveResponse>, Status> {
         let req = request.into_inner();
+        info!("RestoreArchive: {}", req.digest);
 
-        self.state
-            .delete_console(&req.id)
+        let (volumes, metadata) = archive::restore(&self.state, &req.digest, &req.name_prefix)
+            .await
             .map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(DeleteConsoleResponse {}))
+        Ok(audited(
+            RestoreArchiveResponse {
+                volumes: volumes.iter().map(volume_to_proto).collect(),
+                metadata,
+            },
+            AuditChange::new("archive", &req.digest),
+        ))
     }
 
     // ========================================================================
-    // Snapshot operations
+    // Job operations
     // ========================================================================
 
-    async fn create_snapshot(
+    async fn create_job(
         &self,
-        request: Request<CreateSnapshotRequest>,
-    ) -> Result<Response<CreateSnapshotResponse>, Status> {
+        request: Request<CreateJobRequest>,
+    ) -> Result<Response<CreateJobResponse>, Status> {
         let req = request.into_inner();
         let spec = req.spec.ok_or_else(|| Status::invalid_argument("spec required"))?;
+        let spec = job_spec_from_proto(spec).map_err(|e| Status::from(e))?;
 
-        let snap_spec = types::SnapshotSpec {
-            vm_id: spec.vm_id.clone(),
-            include_memory: spec.include_memory,
-            include_disk: spec.include_disk,
-            description: if spec.description.is_empty() {
-                None
-            } else {
-                Some(spec.description)
-            },
-        };
-
-        let snapshot = self
-            .state
-            .create_snapshot(req.name.clone(), snap_spec, req.labels)
+        let job = self
+            .jobs
+            .submit(spec, req.labels)
             .map_err(|e| Status::from(e))?;
 
-        // Actually create the snapshot
-        if snapshot.spec.include_memory {
-            let run_dir = self.state.cas().create_run(&snapshot.meta.id).await
-                .map_err(|e| Status::from(e))?;
-            let mem_path = run_dir.join("snapshot.mem");
-            
-            self.qemu
-                .create_memory_snapshot(&self.state, &spec.vm_id, &mem_path)
-                .await
-                .map_err(|e| Status::from(e))?;
-
-            // Update snapshot status
-            let status = types::SnapshotStatus {
-                complete: true,
-                memory_snapshot_path: Some(mem_path.to_string_lossy().to_string()),
-                ..snapshot.status.clone()
-            };
-            self.state
-                .update_snapshot_status(&snapshot.meta.id, status)
-                .map_err(|e| Status::from(e))?;
-        }
-
-        let snapshot = self
-            .state
-            .get_snapshot(&snapshot.meta.id)
-            .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
-
-        Ok(Response::new(CreateSnapshotResponse {
-            snapshot: Some(snapshot_to_proto(&snapshot)),
-        }))
+        Ok(audited(
+            CreateJobResponse {
+                job: Some(job_to_proto(&job)),
+            },
+            AuditChange::new("job", &job.meta.id).after(&job),
+        ))
     }
 
-    async fn get_snapshot(
+    async fn get_job(
         &self,
-        request: Request<GetSnapshotRequest>,
-    ) -> Result<Response<GetSnapshotResponse>, Status> {
+        request: Request<GetJobRequest>,
+    ) -> Result<Response<GetJobResponse>, Status> {
         let req = request.into_inner();
 
-        let snapshot = self
+        let job = self
             .state
-            .get_snapshot(&req.id)
+            .get_job(&req.id)
             .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
+            .ok_or_else(|| Status::not_found("Job not found"))?;
 
-        Ok(Response::new(GetSnapshotResponse {
-            snapshot: Some(snapshot_to_proto(&snapshot)),
+        Ok(Response::new(GetJobResponse {
+            job: Some(job_to_proto(&job)),
         }))
     }
 
-    async fn delete_snapshot(
-        &self,
-        request: Request<DeleteSnapshotRequest>,
-    ) -> Result<Response<DeleteSnapshotResponse>, Status> {
-        let req = request.into_inner();
-
-        self.state
-            .delete_snapshot(&req.id)
-            .map_err(|e| Status::from(e))?;
-
-        Ok(Response::new(DeleteSnapshotResponse {}))
-    }
-
-    async fn list_snapshots(
+    async fn list_jobs(
         &self,
-        request: Request<ListSnapshotsRequest>,
-    ) -> Result<Response<ListSnapshotsResponse>, Status> {
+        request: Request<ListJobsRequest>,
+    ) -> Result<Response<ListJobsResponse>, Status> {
         let req = request.into_inner();
-        let vm_id = if req.vm_id.is_empty() {
-            None
-        } else {
-            Some(req.vm_id.as_str())
+        let state = match ProtoJobState::try_from(req.state) {
+            Ok(ProtoJobState::Pending) => Some(types::JobState::Pending),
+            Ok(ProtoJobState::Running) => Some(types::JobState::Running),
+            Ok(ProtoJobState::Succeeded) => Some(types::JobState::Succeeded),
+            Ok(ProtoJobState::Failed) => Some(types::JobState::Failed),
+            Ok(ProtoJobState::Cancelled) => Some(types::JobState::Cancelled),
+            _ => None,
         };
 
-        let snapshots = self
+        let mut jobs = self
             .state
-            .list_snapshots(vm_id)
+            .list_jobs(state)
             .map_err(|e| Status::from(e))?;
+        jobs.sort_by_key(|j| std::cmp::Reverse(j.meta.created_at));
 
-        Ok(Response::new(ListSnapshotsResponse {
-            snapshots: snapshots
-                .into_iter()
-                .map(|s| snapshot_to_proto(&s))
-                .collect(),
+        Ok(Response::new(ListJobsResponse {
+            jobs: jobs.iter().map(job_to_proto).collect(),
         }))
     }
 
-    async fn restore_snapshot(
+    async fn cancel_job(
         &self,
-        request: Request<RestoreSnapshotRequest>,
-    ) -> Result<Response<RestoreSnapshotResponse>, Status> {
+        request: Request<CancelJobRequest>,
+    ) -> Result<Response<CancelJobResponse>, Status> {
         let req = request.into_inner();
+        info!("CancelJob: {}", req.id);
 
-        let snapshot = self
-            .state
-            .get_snapshot(&req.snapshot_id)
-            .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("Snapshot not found"))?;
-
-        // Restore via QMP
-        self.qemu
-            .restore_internal_snapshot(&self.state, &req.target_vm_id, &snapshot.meta.name)
-            .await
-            .map_err(|e| Status::from(e))?;
-
-        let vm = self
-            .state
-            .get_vm(&req.target_vm_id)
-            .map_err(|e| Status::from(e))?
-            .ok_or_else(|| Status::not_found("VM not found"))?;
+        let job = self.jobs.cancel(&req.id).map_err(|e| Status::from(e))?;
 
-        Ok(Response::new(RestoreSnapshotResponse {
-            vm: Some(vm_to_proto(&vm)),
-        }))
+        Ok(audited(
+            CancelJobResponse {
+                job: Some(job_to_proto(&job)),
+            },
+            AuditChange::new("job", &job.meta.id).after(&job),
+        ))
     }
 
     // ========================================================================
@@ -804,10 +3733,17 @@ impl InfraSimDaemon for DaemonService {
         // Get QEMU args from the command line (we'd need to store these)
         let qemu_args = vec![format!("qemu-system-aarch64")];
 
+        // vTPM quotes recorded since this boot
+        let tpm_quotes: Vec<types::TpmQuote> = vtpm::load_quotes(self.state.config(), &req.vm_id)
+            .map_err(|e| Status::from(e))?
+            .into_iter()
+            .filter(|q| q.created_at >= process.started_at)
+            .collect();
+
         // Generate attestation
         let provider = AttestationProvider::new((*self.state.key_pair()).clone());
         let report = provider
-            .generate_report(&vm, &volumes, &qemu_args)
+            .generate_report_with_quotes(&vm, &volumes, &qemu_args, tpm_quotes)
             .map_err(|e| Status::from(e))?;
 
         Ok(Response::new(GetAttestationResponse {
@@ -815,6 +3751,82 @@ impl InfraSimDaemon for DaemonService {
         }))
     }
 
+    async fn get_tpm_quote(
+        &self,
+        request: Request<GetTpmQuoteRequest>,
+    ) -> Result<Response<GetTpmQuoteResponse>, Status> {
+        let req = request.into_inner();
+
+        self.state
+            .get_vm(&req.vm_id)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found("VM not found"))?;
+        let tpm = self
+            .state
+            .get_vtpm(&req.vm_id)
+            .ok_or_else(|| Status::failed_precondition("VM is not running with a vTPM"))?;
+
+        // TPM2B_DATA holds at most one SHA-512 digest
+        if req.nonce.is_empty() || req.nonce.len() > 64 {
+            return Err(Status::invalid_argument("nonce must be 1 to 64 bytes"));
+        }
+        let mut pcrs = if req.pcrs.is_empty() { default_pcrs() } else { req.pcrs };
+        if let Some(pcr) = pcrs.iter().find(|&&p| p >= PCR_COUNT) {
+            return Err(Status::invalid_argument(format!("invalid PCR index {}", pcr)));
+        }
+        pcrs.sort_unstable();
+        pcrs.dedup();
+
+        let quote = tpm
+            .quote("on-demand", req.nonce, pcrs)
+            .await
+            .map_err(|e| match e {
+                Error::AttestationError(msg) => Status::failed_precondition(msg),
+                e => Status::from(e),
+            })?;
+
+        Ok(Response::new(GetTpmQuoteResponse {
+            quote: Some(tpm_quote_to_proto(&quote)),
+        }))
+    }
+
+    async fn get_volume_provenance(
+        &self,
+        request: Request<GetVolumeProvenanceRequest>,
+    ) -> Result<Response<GetVolumeProvenanceResponse>, Status> {
+        let req = request.into_inner();
+
+        let volume = self
+            .state
+            .get_volume(&req.volume_id)
+            .map_err(Status::from)?
+            .ok_or_else(|| Status::not_found("Volume not found"))?;
+        let digest = volume
+            .status
+            .provenance
+            .clone()
+            .ok_or_else(|| Status::failed_precondition("Volume has no provenance"))?;
+        let envelope = self.state.cas().get(&digest).await.map_err(Status::from)?;
+
+        // Hashed now, so images changed since they were attested show up
+        let image = self
+            .volume_preparer
+            .source_image(&self.state, &volume)
+            .ok_or_else(|| Status::failed_precondition("Volume's source image is unknown"))?;
+        let image_digest = infrasim_common::ContentAddressedStore::hash_file(&image)
+            .await
+            .map_err(Status::from)?;
+
+        let key_pair = self.state.key_pair();
+        Ok(Response::new(GetVolumeProvenanceResponse {
+            envelope,
+            envelope_digest: digest,
+            image_digest,
+            public_key: key_pair.public_key_hex(),
+            key_id: key_pair.key_id(),
+        }))
+    }
+
     // ========================================================================
     // LoRa operations
     // ========================================================================
@@ -840,6 +3852,72 @@ impl InfraSimDaemon for DaemonService {
         Err(Status::unimplemented("LoRa devices not yet implemented"))
     }
 
+    // ========================================================================
+    // Event operations
+    // ========================================================================
+
+    type WatchEventsStream = ReceiverStream<Result<ProtoEvent, Status>>;
+
+    async fn watch_events(
+        &self,
+        request: Request<WatchEventsRequest>,
+    ) -> Result<Response<Self::WatchEventsStream>, Status> {
+        let req = request.into_inner();
+        debug!(
+            "WatchEvents: type={:?} id={:?} (follow={})",
+            req.resource_type, req.resource_id, req.follow
+        );
+
+        let (recent, mut events) = self.state.subscribe_events();
+        let (tx, rx) = mpsc::channel(EVENT_STREAM_BUFFER);
+
+        let recent: Vec<ProtoEvent> = recent
+            .iter()
+            .map(event_to_proto)
+            .filter(|event| event_matches(&req, event))
+            .collect();
+        let replay = recent.len().saturating_sub(req.tail as usize);
+
+        tokio::spawn(async move {
+            for event in recent.into_iter().skip(replay) {
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+            if !req.follow {
+                return;
+            }
+
+            loop {
+                let event = tokio::select! {
+                    _ = tx.closed() => return,
+                    event = events.recv() => event,
+                };
+
+                let event = match event {
+                    Ok(event) => event_to_proto(&event),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        // Watchers must resync rather than silently miss changes
+                        let _ = tx
+                            .send(Err(Status::data_loss(format!(
+                                "watcher fell behind, {} events dropped",
+                                skipped
+                            ))))
+                            .await;
+                        return;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+
+                if event_matches(&req, &event) && tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
     // ========================================================================
     // Health operations
     // ========================================================================
@@ -864,7 +3942,7 @@ impl InfraSimDaemon for DaemonService {
 
         let qemu_available = infrasim_common::attestation::is_qemu_available();
         let qemu_version = if qemu_available {
-            std::process::Command::new("qemu-system-aarch64")
+            std::process::Command::new(self.config.qemu.get().binary_for("aarch64"))
                 .arg("--version")
                 .output()
                 .ok()
@@ -874,41 +3952,366 @@ impl InfraSimDaemon for DaemonService {
             String::new()
         };
 
+        let committed = admission::Committed::of(&vms, "");
+
         Ok(Response::new(GetDaemonStatusResponse {
             running_vms: running as i32,
             total_vms: vms.len() as i32,
-            memory_used_bytes: 0,
+            memory_used_bytes: (committed.memory_mb * 1024 * 1024) as i64,
             disk_used_bytes: 0,
             store_path: self.config.store_path.to_string_lossy().to_string(),
             qemu_available,
             qemu_version,
             hvf_available: infrasim_common::attestation::is_hvf_available(),
+            kvm_available: infrasim_common::attestation::is_kvm_available(),
+            accelerator: infrasim_common::attestation::host_accelerator().to_string(),
+            host_memory_bytes: infrasim_common::attestation::host_memory_bytes() as i64,
+            swtpm_available: infrasim_common::attestation::is_swtpm_available(),
+            host_cpus: std::thread::available_parallelism().map_or(1, |n| n.get() as i32),
+            committed_cpu_cores: committed.cpu_cores as i32,
+        }))
+    }
+
+    // ========================================================================
+    // Artifact Inspection
+    // ========================================================================
+
+    async fn inspect_artifact(
+        &self,
+        request: Request<InspectArtifactRequest>,
+    ) -> Result<Response<InspectArtifactResponse>, Status> {
+        let req = request.into_inner();
+        let path = std::path::PathBuf::from(&req.path);
+
+        if !path.exists() {
+            return Err(Status::not_found(format!("Artifact not found: {}", req.path)));
+        }
+
+        let mut inspector = infrasim_common::artifact::ArtifactInspector::new();
+        let report = inspector
+            .inspect(&path)
+            .map_err(|e| Status::internal(format!("Failed to inspect artifact: {}", e)))?;
+
+        Ok(Response::new(InspectArtifactResponse {
+            report: Some(artifact_report_to_proto(&report)),
+        }))
+    }
+
+    // ========================================================================
+    // Signing
+    // ========================================================================
+
+    async fn sign_payload(
+        &self,
+        request: Request<SignPayloadRequest>,
+    ) -> Result<Response<SignPayloadResponse>, Status> {
+        use infrasim_common::crypto::Signer;
+
+        let req = request.into_inner();
+        if req.payload.is_empty() {
+            return Err(Status::invalid_argument("payload is empty"));
+        }
+        if req.payload.len() > MAX_SIGN_PAYLOAD {
+            return Err(Status::invalid_argument(format!(
+                "payload is {} bytes, limit is {}",
+                req.payload.len(),
+                MAX_SIGN_PAYLOAD
+            )));
+        }
+
+        let key_pair = self.state.key_pair();
+        let signature = key_pair.sign(&req.payload);
+        info!(
+            "Signed {} byte payload for {} with key {}",
+            req.payload.len(),
+            if req.purpose.is_empty() { "unspecified purpose" } else { &req.purpose },
+            key_pair.key_id()
+        );
+
+        Ok(Response::new(SignPayloadResponse {
+            signature,
+            public_key: key_pair.public_key_hex(),
+            key_id: key_pair.key_id(),
+            backend: SIGNING_BACKEND.to_string(),
+        }))
+    }
+
+    async fn get_signing_key(
+        &self,
+        _request: Request<GetSigningKeyRequest>,
+    ) -> Result<Response<GetSigningKeyResponse>, Status> {
+        let key_pair = self.state.key_pair();
+        Ok(Response::new(GetSigningKeyResponse {
+            public_key: key_pair.public_key_hex(),
+            key_id: key_pair.key_id(),
+            backend: SIGNING_BACKEND.to_string(),
+        }))
+    }
+
+    // ========================================================================
+    // Audit log
+    // ========================================================================
+
+    async fn list_audit_events(
+        &self,
+        request: Request<ListAuditEventsRequest>,
+    ) -> Result<Response<ListAuditEventsResponse>, Status> {
+        let req = request.into_inner();
+        let non_empty = |s: String| (!s.is_empty()).then_some(s);
+        let filter = AuditFilter {
+            since: (req.since > 0).then_some(req.since),
+            until: (req.until > 0).then_some(req.until),
+            source: non_empty(req.source),
+            identity: non_empty(req.identity),
+            resource_id: non_empty(req.resource_id),
+            limit: req.limit as usize,
+        };
+        let events = self.audit.list(&filter).map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListAuditEventsResponse {
+            events: events.into_iter().map(audit_event_to_proto).collect(),
+        }))
+    }
+
+    async fn verify_audit_log(
+        &self,
+        _request: Request<VerifyAuditLogRequest>,
+    ) -> Result<Response<VerifyAuditLogResponse>, Status> {
+        let key = self.state.key_pair().verifying_key();
+        let result = self.audit.verify(Some(&key)).map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(VerifyAuditLogResponse {
+            valid: result.is_valid(),
+            verified: result.verified,
+            unchained: result.unchained,
+            broken_at_seq: result.broken_at.unwrap_or_default(),
+            error: result.error.unwrap_or_default(),
+            hash_chain_enabled: self.config.audit.hash_chain,
+        }))
+    }
+
+    // ========================================================================
+    // Provenance ledger
+    // ========================================================================
+
+    async fn get_provenance_chain(
+        &self,
+        request: Request<GetProvenanceChainRequest>,
+    ) -> Result<Response<GetProvenanceChainResponse>, Status> {
+        let req = request.into_inner();
+        if req.resource_id.is_empty() {
+            return Err(Status::invalid_argument("resource_id is required"));
+        }
+        let records = self.state.ledger().chain(&req.resource_id).map_err(Status::from)?;
+        if records.is_empty() {
+            return Err(Status::not_found(format!("No provenance recorded for {}", req.resource_id)));
+        }
+        let key = self.state.key_pair().verifying_key();
+        let result = verify_chain(&records, Some(&key));
+
+        Ok(Response::new(GetProvenanceChainResponse {
+            valid: result.is_valid(),
+            broken_at_version: result.broken_at.unwrap_or_default(),
+            error: result.error.unwrap_or_default(),
+            records: records.into_iter().map(provenance_record_to_proto).collect(),
+        }))
+    }
+
+    // ========================================================================
+    // Secrets
+    // ========================================================================
+
+    async fn create_secret(
+        &self,
+        request: Request<CreateSecretRequest>,
+    ) -> Result<Response<CreateSecretResponse>, Status> {
+        let req = request.into_inner();
+        let secret = self
+            .state
+            .secrets()
+            .put(&req.name, &req.value, req.overwrite)
+            .map_err(|e| Status::from(e))?;
+
+        // Digests of a value could be guessed against, so none are recorded
+        Ok(audited(
+            CreateSecretResponse {
+                secret: Some(secret_to_proto(&secret)),
+            },
+            AuditChange::new("secret", &secret.name),
+        ))
+    }
+
+    async fn get_secret(
+        &self,
+        request: Request<GetSecretRequest>,
+    ) -> Result<Response<GetSecretResponse>, Status> {
+        let req = request.into_inner();
+        let secrets = self.state.secrets();
+        let secret = secrets
+            .metadata(&req.name)
+            .map_err(|e| Status::from(e))?
+            .ok_or_else(|| Status::not_found(format!("secret {} not found", req.name)))?;
+        let value = secrets.get(&req.name).map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(GetSecretResponse {
+            secret: Some(secret_to_proto(&secret)),
+            value,
+        }))
+    }
+
+    async fn list_secrets(
+        &self,
+        _request: Request<ListSecretsRequest>,
+    ) -> Result<Response<ListSecretsResponse>, Status> {
+        let secrets = self.state.secrets().list().map_err(|e| Status::from(e))?;
+
+        Ok(Response::new(ListSecretsResponse {
+            secrets: secrets.iter().map(secret_to_proto).collect(),
+        }))
+    }
+
+    async fn delete_secret(
+        &self,
+        request: Request<DeleteSecretRequest>,
+    ) -> Result<Response<DeleteSecretResponse>, Status> {
+        let req = request.into_inner();
+        self.state
+            .secrets()
+            .delete(&req.name)
+            .map_err(|e| Status::from(e))?;
+
+        Ok(audited(DeleteSecretResponse {}, AuditChange::new("secret", &req.name)))
+    }
+
+    // ========================================================================
+    // Replication
+    // ========================================================================
+
+    async fn get_replication_status(
+        &self,
+        request: Request<GetReplicationStatusRequest>,
+    ) -> Result<Response<ReplicationStatus>, Status> {
+        let req = request.into_inner();
+        let cas_digests = if req.include_objects {
+            self.check_allow_standbys()?;
+            self.state.cas().list().map_err(|e| Status::from(e))?
+        } else {
+            Vec::new()
+        };
+
+        let standby = self.replication.is_standby();
+        let synced = self.replication.sync_status();
+        Ok(Response::new(ReplicationStatus {
+            role: if standby { "standby" } else { "primary" }.to_string(),
+            epoch: self.replication.epoch().to_string(),
+            position: self.state.db().change_counter().map_err(|e| Status::from(e))?,
+            allow_standbys: self.config.replication.allow_standbys,
+            cas_digests,
+            primary: if standby { self.replication.primary().to_string() } else { String::new() },
+            synced_epoch: synced.epoch,
+            synced_position: synced.position,
+            last_sync_at: synced.last_sync_at,
+            last_error: synced.last_error,
+            synced_objects: synced.synced_objects,
         }))
     }
 
-    // ========================================================================
-    // Artifact Inspection
-    // ========================================================================
+    type StreamStateSnapshotStream = ReceiverStream<Result<StateSnapshotChunk, Status>>;
+
+    async fn stream_state_snapshot(
+        &self,
+        _request: Request<StreamStateSnapshotRequest>,
+    ) -> Result<Response<Self::StreamStateSnapshotStream>, Status> {
+        self.check_allow_standbys()?;
+
+        // Read before copying: writes in between make the copy newer than
+        // its position, which only costs the standby one extra copy
+        let epoch = self.replication.epoch().to_string();
+        let position = self.state.db().change_counter().map_err(|e| Status::from(e))?;
+        let dir = self.config.replication_dir();
+        std::fs::create_dir_all(&dir).map_err(|e| Status::internal(e.to_string()))?;
+        let path = dir.join(format!("snapshot-{}.db", uuid::Uuid::new_v4()));
+        let db = self.state.db().clone();
+        let copy = path.clone();
+        tokio::task::spawn_blocking(move || db.snapshot_to(&copy))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(|e| Status::from(e))?;
+
+        // The open file stays readable once unlinked
+        let file = tokio::fs::File::open(&path).await;
+        let _ = std::fs::remove_file(&path);
+        let mut file = file.map_err(|e| Status::internal(e.to_string()))?;
+        debug!("StreamStateSnapshot: epoch {} position {}", epoch, position);
+
+        let (tx, rx) = mpsc::channel(EXPORT_STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; EXPORT_CHUNK_SIZE];
+            let mut first = true;
+            loop {
+                let chunk = match file.read(&mut buffer).await {
+                    Ok(0) => return,
+                    Ok(n) => Ok(StateSnapshotChunk {
+                        data: buffer[..n].to_vec(),
+                        epoch: if first { epoch.clone() } else { String::new() },
+                        position: if first { position } else { 0 },
+                    }),
+                    Err(e) => Err(Status::internal(e.to_string())),
+                };
+                first = false;
+                let failed = chunk.is_err();
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type ReadCasObjectStream = ReceiverStream<Result<ReadCasObjectResponse, Status>>;
 
-    async fn inspect_artifact(
+    async fn read_cas_object(
         &self,
-        request: Request<InspectArtifactRequest>,
-    ) -> Result<Response<InspectArtifactResponse>, Status> {
+        request: Request<ReadCasObjectRequest>,
+    ) -> Result<Response<Self::ReadCasObjectStream>, Status> {
+        self.check_allow_standbys()?;
         let req = request.into_inner();
-        let path = std::path::PathBuf::from(&req.path);
+        infrasim_common::cas::check_digest(&req.digest)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
 
-        if !path.exists() {
-            return Err(Status::not_found(format!("Artifact not found: {}", req.path)));
-        }
+        let path = self.state.cas().get_path(&req.digest).await.map_err(|e| Status::from(e))?;
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let (tx, rx) = mpsc::channel(EXPORT_STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; EXPORT_CHUNK_SIZE];
+            loop {
+                let chunk = match file.read(&mut buffer).await {
+                    Ok(0) => return,
+                    Ok(n) => Ok(ReadCasObjectResponse { data: buffer[..n].to_vec() }),
+                    Err(e) => Err(Status::internal(e.to_string())),
+                };
+                let failed = chunk.is_err();
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
 
-        let mut inspector = infrasim_common::artifact::ArtifactInspector::new();
-        let report = inspector
-            .inspect(&path)
-            .map_err(|e| Status::internal(format!("Failed to inspect artifact: {}", e)))?;
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
 
-        Ok(Response::new(InspectArtifactResponse {
-            report: Some(artifact_report_to_proto(&report)),
-        }))
+    async fn promote_daemon(
+        &self,
+        _request: Request<PromoteDaemonRequest>,
+    ) -> Result<Response<PromoteDaemonResponse>, Status> {
+        info!("PromoteDaemon: taking over from {}", self.replication.primary());
+        let stopped_vm_ids = self.replication.promote().await.map_err(|e| Status::from(e))?;
+
+        Ok(audited(PromoteDaemonResponse { stopped_vm_ids }, AuditChange::new("daemon", "replication")))
     }
 }
 
@@ -916,6 +4319,51 @@ impl InfraSimDaemon for DaemonService {
 // Proto conversion helpers
 // ============================================================================
 
+fn secret_to_proto(secret: &SecretMeta) -> Secret {
+    Secret {
+        name: secret.name.clone(),
+        created_at: secret.created_at,
+        updated_at: secret.updated_at,
+    }
+}
+
+fn audit_event_to_proto(entry: AuditEntry) -> AuditEvent {
+    AuditEvent {
+        seq: entry.seq,
+        timestamp: entry.timestamp,
+        source: entry.source,
+        identity: entry.identity,
+        source_ip: entry.source_ip.unwrap_or_default(),
+        operation: entry.operation,
+        resource_kind: entry.resource_kind.unwrap_or_default(),
+        resource_id: entry.resource_id.unwrap_or_default(),
+        status: entry.status,
+        before_digest: entry.before_digest.unwrap_or_default(),
+        after_digest: entry.after_digest.unwrap_or_default(),
+        prev_hash: entry.prev_hash.unwrap_or_default(),
+        hash: entry.hash.unwrap_or_default(),
+        signature: entry.signature.unwrap_or_default(),
+        key_id: entry.key_id.unwrap_or_default(),
+    }
+}
+
+fn provenance_record_to_proto(record: ProvenanceRecord) -> ProtoProvenanceRecord {
+    ProtoProvenanceRecord {
+        seq: record.seq,
+        resource_kind: record.resource_kind,
+        resource_id: record.resource_id,
+        version: record.version,
+        timestamp: record.timestamp,
+        change: record.change,
+        spec_json: record.spec.unwrap_or_default(),
+        spec_digest: record.spec_digest.unwrap_or_default(),
+        parent_digest: record.parent_digest.unwrap_or_default(),
+        hash: record.hash,
+        signature: record.signature.unwrap_or_default(),
+        key_id: record.key_id.unwrap_or_default(),
+    }
+}
+
 fn resource_meta_to_proto(meta: &types::ResourceMeta) -> ResourceMeta {
     ResourceMeta {
         id: meta.id.clone(),
@@ -928,6 +4376,42 @@ fn resource_meta_to_proto(meta: &types::ResourceMeta) -> ResourceMeta {
     }
 }
 
+fn vm_state_to_proto(state: types::VmState) -> i32 {
+    match state {
+        types::VmState::Pending => ProtoVmState::Pending as i32,
+        types::VmState::Running => ProtoVmState::Running as i32,
+        types::VmState::Stopped => ProtoVmState::Stopped as i32,
+        types::VmState::Paused => ProtoVmState::Paused as i32,
+        types::VmState::Suspended => ProtoVmState::Suspended as i32,
+        types::VmState::Error => ProtoVmState::Error as i32,
+    }
+}
+
+fn event_to_proto(event: &ResourceEvent) -> ProtoEvent {
+    ProtoEvent {
+        r#type: match event.kind {
+            EventKind::Created => ProtoEventType::Created as i32,
+            EventKind::Updated => ProtoEventType::Updated as i32,
+            EventKind::Deleted => ProtoEventType::Deleted as i32,
+            EventKind::VmStateChanged => ProtoEventType::VmStateChanged as i32,
+            EventKind::ReconcileError => ProtoEventType::ReconcileError as i32,
+        },
+        resource_type: event.resource_type.clone(),
+        resource_id: event.resource_id.clone(),
+        resource_name: event.resource_name.clone(),
+        message: event.message.clone(),
+        timestamp: event.timestamp,
+        vm_state: event.vm_state.map(vm_state_to_proto).unwrap_or_default(),
+    }
+}
+
+/// Whether an event passes a watcher's filters; empty filters match everything
+fn event_matches(req: &WatchEventsRequest, event: &ProtoEvent) -> bool {
+    (req.resource_type.is_empty() || req.resource_type == event.resource_type)
+        && (req.resource_id.is_empty() || req.resource_id == event.resource_id)
+        && (req.types.is_empty() || req.types.contains(&event.r#type))
+}
+
 fn vm_to_proto(vm: &types::Vm) -> Vm {
     Vm {
         meta: Some(resource_meta_to_proto(&vm.meta)),
@@ -943,24 +4427,112 @@ fn vm_to_proto(vm: &types::Vm) -> Vm {
             boot_disk_id: vm.spec.boot_disk_id.clone().unwrap_or_default(),
             extra_args: vm.spec.extra_args.clone(),
             compatibility_mode: vm.spec.compatibility_mode,
+            network_interfaces: vm
+                .spec
+                .network_interfaces
+                .iter()
+                .map(|a| NetworkAttachment {
+                    network_id: a.network_id.clone(),
+                    mac_address: a.mac.clone().unwrap_or_default(),
+                    ip_address: a.ip.clone().unwrap_or_default(),
+                })
+                .collect(),
+            shared_folders: vm.spec.shared_folders.iter().map(shared_folder_to_proto).collect(),
+            priority: vm.spec.priority,
         }),
         status: Some(VmStatus {
-            state: match vm.status.state {
-                types::VmState::Pending => ProtoVmState::Pending as i32,
-                types::VmState::Running => ProtoVmState::Running as i32,
-                types::VmState::Stopped => ProtoVmState::Stopped as i32,
-                types::VmState::Paused => ProtoVmState::Paused as i32,
-                types::VmState::Error => ProtoVmState::Error as i32,
-            },
+            state: vm_state_to_proto(vm.status.state),
             qemu_pid: vm.status.qemu_pid.map(|p| p.to_string()).unwrap_or_default(),
             qmp_socket: vm.status.qmp_socket.clone().unwrap_or_default(),
             vnc_display: vm.status.vnc_display.clone().unwrap_or_default(),
             error_message: vm.status.error_message.clone().unwrap_or_default(),
             uptime_seconds: vm.status.uptime_seconds as i64,
+            current_snapshot_id: vm.status.current_snapshot_id.clone().unwrap_or_default(),
+            template: vm.spec.template,
+            cloned_from: vm.spec.cloned_from.clone().unwrap_or_default(),
+            interfaces: vec![],
         }),
     }
 }
 
+/// Networks of a VM spec; attachments name their network, so they can stand
+/// in for an empty `network_ids`
+fn attached_network_ids(network_ids: Vec<String>, attachments: &[NetworkAttachment]) -> Vec<String> {
+    if network_ids.is_empty() {
+        attachments.iter().map(|a| a.network_id.clone()).collect()
+    } else {
+        network_ids
+    }
+}
+
+fn network_attachments_from_proto(attachments: Vec<NetworkAttachment>) -> Vec<types::NetworkAttachment> {
+    let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+    attachments
+        .into_iter()
+        .map(|a| types::NetworkAttachment {
+            network_id: a.network_id,
+            mac: non_empty(a.mac_address),
+            ip: non_empty(a.ip_address),
+        })
+        .collect()
+}
+
+/// Shared folders of a VM spec; an empty tag is derived from the guest path
+fn shared_folders_from_proto(folders: Vec<SharedFolder>) -> Vec<types::SharedFolder> {
+    folders
+        .into_iter()
+        .map(|f| types::SharedFolder {
+            tag: if f.tag.is_empty() {
+                types::SharedFolder::default_tag(&f.guest_path)
+            } else {
+                f.tag
+            },
+            host_path: f.host_path,
+            guest_path: f.guest_path,
+            read_only: f.read_only,
+            cache: match ProtoSharedFolderCache::try_from(f.cache) {
+                Ok(ProtoSharedFolderCache::Always) => types::SharedFolderCache::Always,
+                Ok(ProtoSharedFolderCache::Never) => types::SharedFolderCache::Never,
+                _ => types::SharedFolderCache::Auto,
+            },
+            protocol: match ProtoSharedFolderProtocol::try_from(f.protocol) {
+                Ok(ProtoSharedFolderProtocol::Virtiofs) => types::SharedFolderProtocol::Virtiofs,
+                Ok(ProtoSharedFolderProtocol::Ninep) => types::SharedFolderProtocol::NineP,
+                _ => types::SharedFolderProtocol::Auto,
+            },
+        })
+        .collect()
+}
+
+fn shared_folder_to_proto(folder: &types::SharedFolder) -> SharedFolder {
+    SharedFolder {
+        tag: folder.tag.clone(),
+        host_path: folder.host_path.clone(),
+        guest_path: folder.guest_path.clone(),
+        read_only: folder.read_only,
+        cache: match folder.cache {
+            types::SharedFolderCache::Auto => ProtoSharedFolderCache::Auto as i32,
+            types::SharedFolderCache::Always => ProtoSharedFolderCache::Always as i32,
+            types::SharedFolderCache::Never => ProtoSharedFolderCache::Never as i32,
+        },
+        protocol: match folder.protocol {
+            types::SharedFolderProtocol::Auto => ProtoSharedFolderProtocol::Auto as i32,
+            types::SharedFolderProtocol::Virtiofs => ProtoSharedFolderProtocol::Virtiofs as i32,
+            types::SharedFolderProtocol::NineP => ProtoSharedFolderProtocol::Ninep as i32,
+        },
+    }
+}
+
+fn nic_address_to_proto(nic: &addresses::NicAddress) -> VmInterface {
+    VmInterface {
+        index: nic.index as i32,
+        network_id: nic.network_id.clone().unwrap_or_default(),
+        mac_address: nic.mac.clone(),
+        ip_address: nic.ip.map(|ip| ip.to_string()).unwrap_or_default(),
+        source: nic.source.to_string(),
+    }
+}
+
 fn network_to_proto(net: &types::Network) -> Network {
     Network {
         meta: Some(resource_meta_to_proto(&net.meta)),
@@ -980,10 +4552,416 @@ fn network_to_proto(net: &types::Network) -> Network {
             active: net.status.active,
             bridge_interface: net.status.bridge_interface.clone().unwrap_or_default(),
             connected_vms: net.status.connected_vms as i32,
+            capture: net.status.capture.as_ref().map(capture_to_proto),
+        }),
+    }
+}
+
+fn capture_to_proto(capture: &types::NetworkCapture) -> NetworkCapture {
+    NetworkCapture {
+        id: capture.id.clone(),
+        state: match capture.state {
+            types::CaptureState::Running => ProtoCaptureState::Running as i32,
+            types::CaptureState::Stopped => ProtoCaptureState::Stopped as i32,
+            types::CaptureState::Failed => ProtoCaptureState::Failed as i32,
+        },
+        method: capture.method.as_str().to_string(),
+        filter: capture.filter.clone().unwrap_or_default(),
+        max_bytes: capture.max_bytes,
+        max_seconds: capture.max_seconds,
+        started_at: capture.started_at,
+        stopped_at: capture.stopped_at.unwrap_or_default(),
+        bytes: capture.bytes,
+        stop_reason: capture.stop_reason.clone().unwrap_or_default(),
+    }
+}
+
+fn port_forward_to_proto(forward: &types::PortForward) -> PortForward {
+    PortForward {
+        meta: Some(resource_meta_to_proto(&forward.meta)),
+        spec: Some(PortForwardSpec {
+            vm_id: forward.spec.vm_id.clone(),
+            network_id: forward.spec.network_id.clone().unwrap_or_default(),
+            protocol: match forward.spec.protocol {
+                PortProtocol::Tcp => ProtoPortProtocol::Tcp as i32,
+                PortProtocol::Udp => ProtoPortProtocol::Udp as i32,
+            },
+            host_addr: forward.spec.host_addr.clone(),
+            host_port: forward.spec.host_port as u32,
+            guest_port: forward.spec.guest_port as u32,
+        }),
+        status: Some(PortForwardStatus {
+            active: forward.status.active,
+            error_message: forward.status.error_message.clone().unwrap_or_default(),
+        }),
+    }
+}
+
+fn router_spec_from_proto(spec: RouterSpec) -> types::RouterSpec {
+    let action = |action: i32| match ProtoFirewallAction::try_from(action) {
+        Ok(ProtoFirewallAction::Deny) => types::FirewallAction::Deny,
+        _ => types::FirewallAction::Allow,
+    };
+    let cidr = |cidr: String| if cidr.is_empty() { None } else { Some(cidr) };
+
+    types::RouterSpec {
+        network_ids: spec.network_ids,
+        routes: spec
+            .routes
+            .into_iter()
+            .map(|r| types::StaticRoute {
+                destination: r.destination,
+                next_hop: r.next_hop,
+            })
+            .collect(),
+        rules: spec
+            .rules
+            .into_iter()
+            .map(|r| types::FirewallRule {
+                action: action(r.action),
+                protocol: match ProtoRuleProtocol::try_from(r.protocol) {
+                    Ok(ProtoRuleProtocol::Tcp) => types::RuleProtocol::Tcp,
+                    Ok(ProtoRuleProtocol::Udp) => types::RuleProtocol::Udp,
+                    Ok(ProtoRuleProtocol::Icmp) => types::RuleProtocol::Icmp,
+                    _ => types::RuleProtocol::Any,
+                },
+                source: cidr(r.source),
+                destination: cidr(r.destination),
+                // Out-of-range ports are caught by validation as port 0
+                port: match r.port {
+                    0 => None,
+                    p => Some(u16::try_from(p).unwrap_or(0)),
+                },
+            })
+            .collect(),
+        default_action: action(spec.default_action),
+    }
+}
+
+fn router_to_proto(router: &types::Router) -> Router {
+    let action = |action: types::FirewallAction| match action {
+        types::FirewallAction::Allow => ProtoFirewallAction::Allow as i32,
+        types::FirewallAction::Deny => ProtoFirewallAction::Deny as i32,
+    };
+
+    Router {
+        meta: Some(resource_meta_to_proto(&router.meta)),
+        spec: Some(RouterSpec {
+            network_ids: router.spec.network_ids.clone(),
+            routes: router
+                .spec
+                .routes
+                .iter()
+                .map(|r| StaticRoute {
+                    destination: r.destination.clone(),
+                    next_hop: r.next_hop.clone(),
+                })
+                .collect(),
+            rules: router
+                .spec
+                .rules
+                .iter()
+                .map(|r| RouterRule {
+                    action: action(r.action),
+                    protocol: match r.protocol {
+                        types::RuleProtocol::Any => ProtoRuleProtocol::Any as i32,
+                        types::RuleProtocol::Tcp => ProtoRuleProtocol::Tcp as i32,
+                        types::RuleProtocol::Udp => ProtoRuleProtocol::Udp as i32,
+                        types::RuleProtocol::Icmp => ProtoRuleProtocol::Icmp as i32,
+                    },
+                    source: r.source.clone().unwrap_or_default(),
+                    destination: r.destination.clone().unwrap_or_default(),
+                    port: r.port.map(u32::from).unwrap_or(0),
+                })
+                .collect(),
+            default_action: action(router.spec.default_action),
+        }),
+        status: Some(RouterStatus {
+            active: router.status.active,
+            error_message: router.status.error_message.clone().unwrap_or_default(),
+            interfaces: router.status.interfaces.clone(),
+        }),
+    }
+}
+
+/// USB vendor or product ID, where 0 means unset
+fn usb_id_from_proto(value: u32, field: &str) -> Result<Option<u16>, Error> {
+    match value {
+        0 => Ok(None),
+        v => u16::try_from(v)
+            .map(Some)
+            .map_err(|_| Error::InvalidConfig(format!("{} must fit in 16 bits", field))),
+    }
+}
+
+fn device_to_proto(device: &types::Device) -> Device {
+    Device {
+        meta: Some(resource_meta_to_proto(&device.meta)),
+        spec: Some(DeviceSpec {
+            vm_id: device.spec.vm_id.clone(),
+            bus: match device.spec.bus {
+                DeviceBus::Usb => ProtoDeviceBus::Usb as i32,
+                DeviceBus::Pci => ProtoDeviceBus::Pci as i32,
+            },
+            vendor_id: device.spec.vendor_id.unwrap_or_default() as u32,
+            product_id: device.spec.product_id.unwrap_or_default() as u32,
+            host_address: device.spec.host_address.clone().unwrap_or_default(),
+        }),
+        status: Some(DeviceStatus {
+            attached: device.status.attached,
+            qemu_id: device.qemu_id(),
+            error_message: device.status.error_message.clone().unwrap_or_default(),
+        }),
+    }
+}
+
+fn snapshot_policy_spec_from_proto(spec: SnapshotPolicySpec) -> Result<types::SnapshotPolicySpec, Error> {
+    let interval_secs = u64::try_from(spec.interval_seconds)
+        .ok()
+        .filter(|secs| *secs >= scheduler::MIN_INTERVAL_SECS)
+        .ok_or_else(|| {
+            Error::InvalidConfig(format!(
+                "interval must be at least {} seconds",
+                scheduler::MIN_INTERVAL_SECS
+            ))
+        })?;
+    if spec.keep == 0 {
+        return Err(Error::InvalidConfig("keep must be at least 1".to_string()));
+    }
+
+    Ok(types::SnapshotPolicySpec {
+        vm_id: spec.vm_id,
+        vm_selector: spec.vm_selector,
+        interval_secs,
+        keep: spec.keep,
+        include_memory: spec.include_memory,
+        paused: spec.paused,
+    })
+}
+
+fn snapshot_policy_to_proto(policy: &types::SnapshotPolicy) -> SnapshotPolicy {
+    SnapshotPolicy {
+        meta: Some(resource_meta_to_proto(&policy.meta)),
+        spec: Some(SnapshotPolicySpec {
+            vm_id: policy.spec.vm_id.clone(),
+            interval_seconds: policy.spec.interval_secs as i64,
+            keep: policy.spec.keep,
+            include_memory: policy.spec.include_memory,
+            paused: policy.spec.paused,
+            vm_selector: policy.spec.vm_selector.clone(),
+        }),
+        status: Some(SnapshotPolicyStatus {
+            last_run_at: policy.status.last_run_at.unwrap_or_default(),
+            next_run_at: scheduler::next_run_at(policy).unwrap_or_default(),
+            last_snapshot_id: policy.status.last_snapshot_id.clone().unwrap_or_default(),
+            last_error: policy.status.last_error.clone().unwrap_or_default(),
+            retained: policy.status.retained,
+            schedule: policy.spec.schedule(),
+        }),
+    }
+}
+
+fn backup_to_proto(backup: &types::Backup) -> Backup {
+    Backup {
+        meta: Some(resource_meta_to_proto(&backup.meta)),
+        spec: Some(BackupSpec {
+            vm_id: backup.spec.vm_id.clone(),
+            target: backup.spec.target.clone(),
+            incremental: backup.spec.incremental,
+        }),
+        status: Some(BackupStatus {
+            complete: backup.status.complete,
+            kind: match backup.status.kind {
+                types::BackupKind::Full => ProtoBackupKind::Full,
+                types::BackupKind::Incremental => ProtoBackupKind::Incremental,
+            } as i32,
+            parent_id: backup.status.parent_id.clone().unwrap_or_default(),
+            disks: backup
+                .status
+                .disks
+                .iter()
+                .map(|disk| BackupDisk {
+                    volume_id: disk.volume_id.clone(),
+                    volume_name: disk.volume_name.clone(),
+                    boot: disk.boot,
+                    read_only: disk.read_only,
+                    file: disk.file.clone(),
+                    size_bytes: disk.size_bytes as i64,
+                    encryption_key_id: disk
+                        .encryption
+                        .as_ref()
+                        .map(|enc| enc.key_id.clone())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            vm_name: backup.status.vm_name.clone(),
+            size_bytes: backup.status.size_bytes as i64,
+            error: backup.status.error.clone().unwrap_or_default(),
+            completed_at: backup.status.completed_at.unwrap_or_default(),
+        }),
+    }
+}
+
+/// Keep an updated policy on the kind of target it was created with: an empty
+/// target keeps the current one, and only a selector may be replaced
+fn keep_policy_target(
+    kind: &str,
+    (vm_id, selector): (&mut String, &mut HashMap<String, String>),
+    (current_vm_id, current_selector): (&String, &HashMap<String, String>),
+) -> Result<(), Status> {
+    if current_vm_id.is_empty() {
+        if !vm_id.is_empty() {
+            return Err(Status::invalid_argument(format!(
+                "a selector-based {} policy cannot be pinned to a VM",
+                kind
+            )));
+        }
+        if selector.is_empty() {
+            *selector = current_selector.clone();
+        }
+    } else if !selector.is_empty() {
+        return Err(Status::invalid_argument(format!(
+            "vm_selector cannot be set on a single-VM {} policy",
+            kind
+        )));
+    } else if vm_id.is_empty() {
+        *vm_id = current_vm_id.clone();
+    } else if vm_id != current_vm_id {
+        return Err(Status::invalid_argument(format!(
+            "vm_id of a {} policy cannot be changed",
+            kind
+        )));
+    }
+    Ok(())
+}
+
+fn backup_policy_spec_from_proto(spec: BackupPolicySpec) -> Result<types::BackupPolicySpec, Error> {
+    let interval_secs = u64::try_from(spec.interval_seconds)
+        .ok()
+        .filter(|secs| *secs >= scheduler::MIN_INTERVAL_SECS)
+        .ok_or_else(|| {
+            Error::InvalidConfig(format!(
+                "interval must be at least {} seconds",
+                scheduler::MIN_INTERVAL_SECS
+            ))
+        })?;
+    if spec.keep == 0 {
+        return Err(Error::InvalidConfig("keep must be at least 1".to_string()));
+    }
+    types::StorageTarget::parse(&spec.target)?;
+
+    Ok(types::BackupPolicySpec {
+        vm_id: spec.vm_id,
+        vm_selector: spec.vm_selector,
+        target: spec.target,
+        interval_secs,
+        keep: spec.keep,
+        full_every: spec.full_every,
+        paused: spec.paused,
+    })
+}
+
+fn backup_policy_to_proto(policy: &types::BackupPolicy) -> BackupPolicy {
+    BackupPolicy {
+        meta: Some(resource_meta_to_proto(&policy.meta)),
+        spec: Some(BackupPolicySpec {
+            vm_id: policy.spec.vm_id.clone(),
+            target: policy.spec.target.clone(),
+            interval_seconds: policy.spec.interval_secs as i64,
+            keep: policy.spec.keep,
+            full_every: policy.spec.full_every,
+            paused: policy.spec.paused,
+            vm_selector: policy.spec.vm_selector.clone(),
+        }),
+        status: Some(BackupPolicyStatus {
+            last_run_at: policy.status.last_run_at.unwrap_or_default(),
+            next_run_at: scheduler::next_backup_at(policy).unwrap_or_default(),
+            last_backup_id: policy.status.last_backup_id.clone().unwrap_or_default(),
+            last_error: policy.status.last_error.clone().unwrap_or_default(),
+            retained: policy.status.retained,
+            schedule: policy.spec.schedule(),
+        }),
+    }
+}
+
+fn job_spec_from_proto(spec: JobSpec) -> Result<types::JobSpec, Error> {
+    let kind = match ProtoJobKind::try_from(spec.kind) {
+        Ok(ProtoJobKind::PushVolume) => types::JobKind::PushVolume,
+        Ok(ProtoJobKind::PullVolume) => types::JobKind::PullVolume,
+        Ok(ProtoJobKind::FlattenVolume) => types::JobKind::FlattenVolume,
+        Ok(ProtoJobKind::PushAppliance) => types::JobKind::PushAppliance,
+        Ok(ProtoJobKind::PullAppliance) => types::JobKind::PullAppliance,
+        Ok(ProtoJobKind::CreateBackup) => types::JobKind::CreateBackup,
+        Ok(ProtoJobKind::RestoreBackup) => types::JobKind::RestoreBackup,
+        Ok(ProtoJobKind::Provision) => types::JobKind::Provision,
+        _ => return Err(Error::InvalidConfig("job kind required".to_string())),
+    };
+
+    Ok(types::JobSpec {
+        kind,
+        target: spec.target,
+        params: spec.params,
+    })
+}
+
+fn job_to_proto(job: &types::Job) -> Job {
+    Job {
+        meta: Some(resource_meta_to_proto(&job.meta)),
+        spec: Some(JobSpec {
+            kind: match job.spec.kind {
+                types::JobKind::PushVolume => ProtoJobKind::PushVolume,
+                types::JobKind::PullVolume => ProtoJobKind::PullVolume,
+                types::JobKind::FlattenVolume => ProtoJobKind::FlattenVolume,
+                types::JobKind::PushAppliance => ProtoJobKind::PushAppliance,
+                types::JobKind::PullAppliance => ProtoJobKind::PullAppliance,
+                types::JobKind::CreateBackup => ProtoJobKind::CreateBackup,
+                types::JobKind::RestoreBackup => ProtoJobKind::RestoreBackup,
+                types::JobKind::Provision => ProtoJobKind::Provision,
+            } as i32,
+            target: job.spec.target.clone(),
+            params: job.spec.params.clone(),
+        }),
+        status: Some(JobStatus {
+            state: match job.status.state {
+                types::JobState::Pending => ProtoJobState::Pending,
+                types::JobState::Running => ProtoJobState::Running,
+                types::JobState::Succeeded => ProtoJobState::Succeeded,
+                types::JobState::Failed => ProtoJobState::Failed,
+                types::JobState::Cancelled => ProtoJobState::Cancelled,
+            } as i32,
+            progress: job.status.progress,
+            message: job.status.message.clone(),
+            error: job.status.error.clone().unwrap_or_default(),
+            result: job.status.result.clone(),
+            started_at: job.status.started_at.unwrap_or_default(),
+            finished_at: job.status.finished_at.unwrap_or_default(),
         }),
     }
 }
 
+fn qos_profile_spec_from_proto(spec: QoSProfileSpec) -> Result<types::QosProfileSpec, Error> {
+    let non_negative = |value: i32, field: &str| {
+        u32::try_from(value)
+            .map_err(|_| Error::InvalidConfig(format!("{} must not be negative", field)))
+    };
+
+    if !(0.0..=100.0).contains(&spec.loss_percent) {
+        return Err(Error::InvalidConfig(
+            "loss_percent must be between 0 and 100".to_string(),
+        ));
+    }
+
+    Ok(types::QosProfileSpec {
+        latency_ms: non_negative(spec.latency_ms, "latency_ms")?,
+        jitter_ms: non_negative(spec.jitter_ms, "jitter_ms")?,
+        loss_percent: spec.loss_percent,
+        rate_limit_mbps: non_negative(spec.rate_limit_mbps, "rate_limit_mbps")?,
+        packet_padding_bytes: non_negative(spec.packet_padding_bytes, "packet_padding_bytes")?,
+        burst_shaping: spec.burst_shaping,
+        burst_size_kb: non_negative(spec.burst_size_kb, "burst_size_kb")?,
+    })
+}
+
 fn qos_profile_to_proto(profile: &types::QosProfile) -> QoSProfile {
     QoSProfile {
         meta: Some(resource_meta_to_proto(&profile.meta)),
@@ -999,6 +4977,111 @@ fn qos_profile_to_proto(profile: &types::QosProfile) -> QoSProfile {
     }
 }
 
+fn chaos_profile_spec_from_proto(spec: ChaosProfileSpec) -> Result<types::ChaosProfileSpec, Error> {
+    let non_negative = |value: i32, field: &str| {
+        u32::try_from(value)
+            .map_err(|_| Error::InvalidConfig(format!("{} must not be negative", field)))
+    };
+
+    Ok(types::ChaosProfileSpec {
+        network_id: Some(spec.network_id).filter(|id| !id.is_empty()),
+        vm_id: Some(spec.vm_id).filter(|id| !id.is_empty()),
+        faults: types::ChaosFaults {
+            latency_ms: non_negative(spec.latency_ms, "latency_ms")?,
+            jitter_ms: non_negative(spec.jitter_ms, "jitter_ms")?,
+            distribution: match ProtoLatencyDistribution::try_from(spec.distribution) {
+                Ok(ProtoLatencyDistribution::Normal) => types::LatencyDistribution::Normal,
+                Ok(ProtoLatencyDistribution::Pareto) => types::LatencyDistribution::Pareto,
+                _ => types::LatencyDistribution::Uniform,
+            },
+            loss_percent: spec.loss_percent,
+            rate_limit_mbps: non_negative(spec.rate_limit_mbps, "rate_limit_mbps")?,
+        },
+        partitions: spec.partitions.into_iter().map(|p| p.vm_ids).collect(),
+        expires_at: Some(spec.expires_at).filter(|at| *at > 0),
+    })
+}
+
+fn chaos_profile_to_proto(profile: &types::ChaosProfile) -> ChaosProfile {
+    let faults = &profile.spec.faults;
+    ChaosProfile {
+        meta: Some(resource_meta_to_proto(&profile.meta)),
+        spec: Some(ChaosProfileSpec {
+            network_id: profile.spec.network_id.clone().unwrap_or_default(),
+            vm_id: profile.spec.vm_id.clone().unwrap_or_default(),
+            latency_ms: faults.latency_ms as i32,
+            jitter_ms: faults.jitter_ms as i32,
+            distribution: match faults.distribution {
+                types::LatencyDistribution::Uniform => ProtoLatencyDistribution::Uniform as i32,
+                types::LatencyDistribution::Normal => ProtoLatencyDistribution::Normal as i32,
+                types::LatencyDistribution::Pareto => ProtoLatencyDistribution::Pareto as i32,
+            },
+            loss_percent: faults.loss_percent,
+            rate_limit_mbps: faults.rate_limit_mbps as i32,
+            partitions: profile
+                .spec
+                .partitions
+                .iter()
+                .map(|vm_ids| ChaosPartition { vm_ids: vm_ids.clone() })
+                .collect(),
+            expires_at: profile.spec.expires_at.unwrap_or_default(),
+        }),
+        status: Some(ChaosProfileStatus {
+            applied_vm_ids: profile.status.applied_vm_ids.clone(),
+            pending_vm_ids: profile.status.pending_vm_ids.clone(),
+        }),
+    }
+}
+
+fn scenario_run_to_proto(run: &scenario::ScenarioRun) -> ScenarioRun {
+    ScenarioRun {
+        meta: Some(resource_meta_to_proto(&run.meta)),
+        scenario_name: run.spec.scenario.name.clone(),
+        description: run.spec.scenario.description.clone(),
+        digest: run.spec.digest.clone(),
+        state: match run.status.state {
+            RunState::Pending => ProtoScenarioRunState::Pending as i32,
+            RunState::Running => ProtoScenarioRunState::Running as i32,
+            RunState::Passed => ProtoScenarioRunState::Passed as i32,
+            RunState::Failed => ProtoScenarioRunState::Failed as i32,
+            RunState::Cancelled => ProtoScenarioRunState::Cancelled as i32,
+        },
+        started_at: run.status.started_at.unwrap_or_default(),
+        finished_at: run.status.finished_at.unwrap_or_default(),
+        error: run.status.error.clone().unwrap_or_default(),
+        steps: run
+            .status
+            .steps
+            .iter()
+            .map(|step| ScenarioStepReport {
+                name: step.name.clone(),
+                action: step.action.clone(),
+                outcome: match step.outcome {
+                    StepOutcome::Pending => ProtoScenarioStepOutcome::Pending as i32,
+                    StepOutcome::Running => ProtoScenarioStepOutcome::Running as i32,
+                    StepOutcome::Passed => ProtoScenarioStepOutcome::Passed as i32,
+                    StepOutcome::Failed => ProtoScenarioStepOutcome::Failed as i32,
+                    StepOutcome::Skipped => ProtoScenarioStepOutcome::Skipped as i32,
+                },
+                started_ms: step.started_ms.unwrap_or_default(),
+                duration_ms: step.duration_ms,
+                detail: step.detail.clone(),
+                stats: step
+                    .stats
+                    .iter()
+                    .map(|(vm, sample)| ScenarioVmStats {
+                        vm: vm.clone(),
+                        cpu_percent: sample.cpu_percent,
+                        rss_bytes: sample.rss_bytes,
+                        rx_bytes: sample.rx_bytes.unwrap_or_default(),
+                        tx_bytes: sample.tx_bytes.unwrap_or_default(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
 fn volume_to_proto(vol: &types::Volume) -> Volume {
     Volume {
         meta: Some(resource_meta_to_proto(&vol.meta)),
@@ -1006,6 +5089,7 @@ fn volume_to_proto(vol: &types::Volume) -> Volume {
             kind: match vol.spec.kind {
                 VolumeKind::Disk => ProtoVolumeKind::Disk as i32,
                 VolumeKind::Weights => ProtoVolumeKind::Weights as i32,
+                VolumeKind::Device => ProtoVolumeKind::Device as i32,
             },
             source: vol.spec.source.clone(),
             integrity: Some(IntegrityConfig {
@@ -1018,6 +5102,11 @@ fn volume_to_proto(vol: &types::Volume) -> Volume {
             size_bytes: vol.spec.size_bytes.unwrap_or(0) as i64,
             format: vol.spec.format.clone(),
             overlay: vol.spec.overlay,
+            encryption: vol.spec.encryption.as_ref().map(|e| VolumeEncryption {
+                cipher: e.cipher.clone(),
+                key_id: e.key_id.clone(),
+                secret: Vec::new(),
+            }),
         }),
         status: Some(crate::generated::VolumeStatus {
             ready: vol.status.ready,
@@ -1025,10 +5114,84 @@ fn volume_to_proto(vol: &types::Volume) -> Volume {
             digest: vol.status.digest.clone().unwrap_or_default(),
             actual_size: vol.status.actual_size as i64,
             verified: vol.status.verified,
+            warnings: vol.status.warnings.clone(),
+            smart_status: vol.status.smart_status.clone().unwrap_or_default(),
+            download: vol.status.download.as_ref().map(download_to_proto),
+            provenance_digest: vol.status.provenance.clone().unwrap_or_default(),
         }),
     }
 }
 
+/// Build inputs from a create request, checking their digests and parameters
+fn build_inputs(build: VolumeBuild) -> infrasim_common::Result<BuildInputs> {
+    let dependencies = build
+        .dependencies
+        .into_iter()
+        .map(|d| {
+            let sha256 = Some(d.sha256.as_str()).filter(|s| !s.is_empty());
+            if let Some(digest) = sha256 {
+                infrasim_common::cas::check_digest(digest)
+                    .map_err(|e| Error::InvalidConfig(format!("Build dependency '{}': {}", d.name, e)))?;
+            }
+            Ok(ResourceDescriptor::new(d.name.clone(), d.uri.clone(), sha256))
+        })
+        .collect::<infrasim_common::Result<_>>()?;
+    let parameters = if build.parameters_json.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_str(&build.parameters_json)
+            .map_err(|e| Error::InvalidConfig(format!("Invalid build parameters: {}", e)))?
+    };
+
+    Ok(BuildInputs {
+        build_type: if build.build_type.is_empty() {
+            slsa::BUILD_TYPE_APPLIANCE.to_string()
+        } else {
+            build.build_type
+        },
+        parameters,
+        dependencies,
+        builder_version: build.builder_version.into_iter().collect(),
+        invocation_id: build.invocation_id,
+        started_at: Some(build.started_at).filter(|t| *t > 0),
+        finished_at: Some(build.finished_at).filter(|t| *t > 0),
+    })
+}
+
+fn vm_stats_to_proto(vm_id: &str, usage: &stats::VmUsage) -> VmStats {
+    let (net_rx_bytes, net_tx_bytes) = usage.network.unwrap_or_default();
+    VmStats {
+        vm_id: vm_id.to_string(),
+        timestamp: usage.timestamp_ms / 1000,
+        cpu_percent: usage.cpu_percent,
+        cpu_seconds: usage.cpu_seconds,
+        rss_bytes: usage.rss_bytes,
+        disk_read_bytes: usage.disk_read_bytes,
+        disk_write_bytes: usage.disk_write_bytes,
+        disk_read_ops: usage.disk_read_ops,
+        disk_write_ops: usage.disk_write_ops,
+        network_counted: usage.network.is_some(),
+        net_rx_bytes,
+        net_tx_bytes,
+        uptime_seconds: usage.uptime_seconds,
+    }
+}
+
+fn download_to_proto(download: &types::DownloadStatus) -> DownloadStatus {
+    DownloadStatus {
+        state: match download.state {
+            types::DownloadState::Pending => ProtoDownloadState::Pending as i32,
+            types::DownloadState::Downloading => ProtoDownloadState::Downloading as i32,
+            types::DownloadState::Verifying => ProtoDownloadState::Verifying as i32,
+            types::DownloadState::Complete => ProtoDownloadState::Complete as i32,
+            types::DownloadState::Failed => ProtoDownloadState::Failed as i32,
+        },
+        bytes_downloaded: download.bytes_downloaded as i64,
+        bytes_total: download.bytes_total.unwrap_or(0) as i64,
+        error: download.error.clone().unwrap_or_default(),
+    }
+}
+
 fn console_to_proto(console: &types::Console) -> Console {
     Console {
         meta: Some(resource_meta_to_proto(&console.meta)),
@@ -1050,6 +5213,18 @@ fn console_to_proto(console: &types::Console) -> Console {
     }
 }
 
+fn guest_file_to_proto(file: &GuestFile) -> SnapshotFile {
+    SnapshotFile {
+        path: file.path.clone(),
+        r#type: file.kind.to_string(),
+        mode: file.mode,
+        size_bytes: file.size,
+        modified_at: file.modified_at,
+        sha256: file.sha256.clone().unwrap_or_default(),
+        link_target: file.link_target.clone().unwrap_or_default(),
+    }
+}
+
 fn snapshot_to_proto(snap: &types::Snapshot) -> Snapshot {
     Snapshot {
         meta: Some(resource_meta_to_proto(&snap.meta)),
@@ -1066,6 +5241,7 @@ fn snapshot_to_proto(snap: &types::Snapshot) -> Snapshot {
             digest: snap.status.digest.clone().unwrap_or_default(),
             size_bytes: snap.status.size_bytes as i64,
             encrypted: snap.status.encrypted,
+            parent_id: snap.status.parent_id.clone().unwrap_or_default(),
         }),
     }
 }
@@ -1078,7 +5254,7 @@ fn attestation_to_proto(report: &types::AttestationReport) -> AttestationReport
             qemu_version: report.host_provenance.qemu_version.clone(),
             qemu_args: report.host_provenance.qemu_args.clone(),
             base_image_hash: report.host_provenance.base_image_hash.clone(),
-            volume_hashes: report.host_provenance.volume_hashes.clone(),
+            volume_hashes: report.host_provenance.volume_hashes.clone().into_iter().collect(),
             macos_version: report.host_provenance.macos_version.clone(),
             cpu_model: report.host_provenance.cpu_model.clone(),
             hvf_enabled: report.host_provenance.hvf_enabled,
@@ -1089,6 +5265,30 @@ fn attestation_to_proto(report: &types::AttestationReport) -> AttestationReport
         signature: report.signature.clone(),
         created_at: report.created_at,
         attestation_type: report.attestation_type.clone(),
+        tpm_quotes: report.tpm_quotes.iter().map(tpm_quote_to_proto).collect(),
+    }
+}
+
+fn tpm_quote_to_proto(quote: &types::TpmQuote) -> TpmQuote {
+    TpmQuote {
+        vm_id: quote.vm_id.clone(),
+        label: quote.label.clone(),
+        pcrs: quote.pcrs.iter().map(|(k, v)| (*k, v.clone())).collect(),
+        quoted: quote.quoted.clone(),
+        signature: quote.signature.clone(),
+        ak_public: quote.ak_public.clone(),
+        nonce: quote.nonce.clone(),
+        event_log: quote
+            .event_log
+            .iter()
+            .map(|e| TpmEvent {
+                pcr_index: e.pcr_index,
+                digest: e.digest.clone(),
+                command: e.command.clone(),
+                data: e.data.clone(),
+            })
+            .collect(),
+        created_at: quote.created_at,
     }
 }
 
@@ -1150,20 +5350,377 @@ fn artifact_report_to_proto(report: &infrasim_common::artifact::ArtifactInspecti
     }
 }
 
+// ============================================================================
+// Log streaming
+// ============================================================================
+
+/// Pump bytes between a VM's serial socket and an AttachSerial call until
+/// either side closes
+async fn relay_serial(
+    socket: UnixStream,
+    initial: Vec<u8>,
+    mut input: tonic::Streaming<SerialInput>,
+    tx: mpsc::Sender<Result<SerialOutput, Status>>,
+) {
+    let (mut reader, mut writer) = socket.into_split();
+    if writer.write_all(&initial).await.is_err() {
+        return;
+    }
+
+    let mut buf = vec![0u8; 4096];
+    loop {
+        tokio::select! {
+            read = reader.read(&mut buf) => match read {
+                Ok(0) | Err(_) => return,
+                Ok(n) => {
+                    if tx.send(Ok(SerialOutput { data: buf[..n].to_vec() })).await.is_err() {
+                        return;
+                    }
+                }
+            },
+            msg = input.message() => match msg {
+                Ok(Some(msg)) => {
+                    if writer.write_all(&msg.data).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(None) | Err(_) => return,
+            },
+            _ = tx.closed() => return,
+        }
+    }
+}
+
+/// Tail a VM's serial console log, optionally interleaving lifecycle events
+async fn stream_console_log(
+    vm_id: String,
+    path: PathBuf,
+    tail_lines: usize,
+    follow: bool,
+    mut events: Option<broadcast::Receiver<VmEvent>>,
+    tx: mpsc::Sender<Result<VmLogEntry, Status>>,
+) {
+    let mut offset = 0u64;
+    let mut partial = String::new();
+
+    // Replay existing output
+    let chunk = match read_log_from(&path, &mut offset).await {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            let _ = tx.send(Err(Status::internal(format!("Failed to read console log: {}", e)))).await;
+            return;
+        }
+    };
+    let mut lines = split_log_lines(&mut partial, &chunk);
+    if !follow && !partial.is_empty() {
+        lines.push(std::mem::take(&mut partial));
+    }
+    let skip = if tail_lines > 0 { lines.len().saturating_sub(tail_lines) } else { 0 };
+    for line in lines.into_iter().skip(skip) {
+        if tx.send(Ok(console_entry(line))).await.is_err() {
+            return;
+        }
+    }
+
+    if !follow {
+        return;
+    }
+
+    let mut ticker = tokio::time::interval(LOG_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let chunk = match read_log_from(&path, &mut offset).await {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = tx.send(Err(Status::internal(format!("Failed to read console log: {}", e)))).await;
+                        return;
+                    }
+                };
+                for line in split_log_lines(&mut partial, &chunk) {
+                    if tx.send(Ok(console_entry(line))).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            event = next_vm_event(&mut events) => match event {
+                Ok(event) if event.vm_id == vm_id => {
+                    let entry = VmLogEntry {
+                        timestamp: event.timestamp,
+                        source: VmLogSource::Event as i32,
+                        line: event.message,
+                    };
+                    if tx.send(Ok(entry)).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => events = None,
+            },
+            _ = tx.closed() => return,
+        }
+    }
+}
+
+/// Read bytes appended to a log since `offset`, handling truncation
+async fn read_log_from(path: &Path, offset: &mut u64) -> std::io::Result<Vec<u8>> {
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        // VM has not been started yet
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let len = file.metadata().await?.len();
+    if len < *offset {
+        *offset = 0;
+    }
+
+    file.seek(std::io::SeekFrom::Start(*offset)).await?;
+    let mut chunk = Vec::new();
+    file.read_to_end(&mut chunk).await?;
+    *offset += chunk.len() as u64;
+
+    Ok(chunk)
+}
+
+/// Split a chunk into complete lines, carrying any trailing partial line over
+fn split_log_lines(partial: &mut String, chunk: &[u8]) -> Vec<String> {
+    partial.push_str(&String::from_utf8_lossy(chunk));
+
+    let mut lines = Vec::new();
+    while let Some(pos) = partial.find('\n') {
+        let line: String = partial.drain(..=pos).collect();
+        lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    lines
+}
+
+/// Wait for the next lifecycle event, or forever if not subscribed
+async fn next_vm_event(
+    events: &mut Option<broadcast::Receiver<VmEvent>>,
+) -> Result<VmEvent, broadcast::error::RecvError> {
+    match events {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn console_entry(line: String) -> VmLogEntry {
+    VmLogEntry {
+        timestamp: chrono::Utc::now().timestamp(),
+        source: VmLogSource::Console as i32,
+        line,
+    }
+}
+
+// ============================================================================
+// Export streaming
+// ============================================================================
+
+/// Stream a bundle to the client in chunks, stopping if it disconnects
+async fn send_bundle(path: &Path, tx: &mpsc::Sender<Result<ExportVmResponse, Status>>) {
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(e) => {
+            let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+            return;
+        }
+    };
+
+    let mut buffer = vec![0u8; EXPORT_CHUNK_SIZE];
+    loop {
+        let chunk = match file.read(&mut buffer).await {
+            Ok(0) => return,
+            Ok(n) => Ok(ExportVmResponse {
+                data: buffer[..n].to_vec(),
+            }),
+            Err(e) => Err(Status::internal(e.to_string())),
+        };
+        let failed = chunk.is_err();
+        if tx.send(chunk).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Write an uploaded bundle to `path`, returning the options from the first message
+/// Write a CopyToGuest upload to its path in the guest, returning the path
+/// and the bytes written
+async fn copy_into_guest(
+    config: &DaemonConfig,
+    vm_id: &str,
+    first: CopyToGuestRequest,
+    stream: &mut tonic::Streaming<CopyToGuestRequest>,
+) -> Result<(String, u64), Status> {
+    let mut agent = GuestAgentClient::connect(config.guest_agent_socket_path(vm_id))
+        .await
+        .map_err(|e| Status::from(e))?;
+    let handle = agent.file_open(&first.path, "w").await.map_err(|e| Status::from(e))?;
+
+    let mut written = 0u64;
+    let mut data = first.data;
+    let result = loop {
+        if !data.is_empty() {
+            if let Err(e) = agent.file_write(handle, &data).await {
+                break Err(Status::from(e));
+            }
+            written += data.len() as u64;
+        }
+        match stream.message().await {
+            Ok(Some(message)) => data = message.data,
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+    let closed = agent.file_close(handle).await.map_err(|e| Status::from(e));
+    result.and(closed)?;
+    Ok((first.path, written))
+}
+
+async fn receive_bundle(
+    stream: &mut tonic::Streaming<ImportVmRequest>,
+    path: &Path,
+) -> Result<ImportVmOptions, Status> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| Status::from(Error::from(e)))?;
+    }
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| Status::from(Error::from(e)))?;
+
+    let mut options = None;
+    while let Some(message) = stream.message().await? {
+        if options.is_none() {
+            options = Some(message.options.unwrap_or_default());
+        }
+        file.write_all(&message.data)
+            .await
+            .map_err(|e| Status::from(Error::from(e)))?;
+    }
+    file.flush().await.map_err(|e| Status::from(Error::from(e)))?;
+
+    options.ok_or_else(|| Status::invalid_argument("empty bundle upload"))
+}
+
 // ============================================================================
 // Server startup
 // ============================================================================
 
-pub async fn serve(config: DaemonConfig, state: StateManager) -> anyhow::Result<()> {
-    let addr = config.grpc_listen.parse()?;
-    let service = DaemonService::new(state, config);
+pub async fn serve(
+    config: DaemonConfig,
+    state: StateManager,
+    jobs: JobQueue,
+    replication: Replication,
+) -> anyhow::Result<()> {
+    let auth = config.auth.clone();
+    let auth_dir = config.auth_dir();
+    let listen = config.grpc_listen.clone();
+    let socket = config
+        .socket
+        .enabled
+        .then(|| (config.grpc_socket_path(), config.socket.mode));
+    if listen.is_empty() && socket.is_none() {
+        anyhow::bail!("Both the TCP listener and the Unix socket are disabled");
+    }
+    let guard = replication.clone();
+    let daemon = DaemonService::new(state, jobs, config, replication);
+    let audit = daemon.audit.clone();
+    let service = AuditService::new(guard.guard(InfraSimDaemonServer::new(daemon)), audit);
+
+    let mut servers = Vec::new();
+
+    // The socket's file mode is its access control, so no TLS or tokens
+    if let Some((path, mode)) = socket {
+        let listener = bind_socket(&path, mode).await?;
+        info!("gRPC server listening on unix://{} (mode {:o})", path.display(), mode);
+        let service = service.clone();
+        servers.push(tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(UnixListenerStream::new(listener))
+                .await
+        }));
+    }
+
+    if !listen.is_empty() {
+        let addr: std::net::SocketAddr = listen.parse()?;
+        let mut server = tonic::transport::Server::builder();
+        if auth.mtls {
+            server = server.tls_config(server_tls_config(&auth, &auth_dir, addr)?)?;
+        }
+        if !auth.mtls && !auth.require_token && !addr.ip().is_loopback() {
+            warn!(
+                "gRPC API on {} accepts unauthenticated clients; start with --mtls and/or --require-token",
+                addr
+            );
+        }
 
-    info!("gRPC server listening on {}", addr);
+        let mut tokens = if auth.require_token {
+            Some(RequireToken::new(&auth_dir)?)
+        } else {
+            None
+        };
+        let service = InterceptedService::new(service, move |request| match tokens.as_mut() {
+            Some(tokens) => tokens.call(request),
+            None => Ok(request),
+        });
+
+        info!(
+            "gRPC server listening on {} (mTLS: {}, tokens: {})",
+            addr, auth.mtls, auth.require_token
+        );
+        servers.push(tokio::spawn(server.add_service(service).serve(addr)));
+    }
 
-    tonic::transport::Server::builder()
-        .add_service(InfraSimDaemonServer::new(service))
-        .serve(addr)
-        .await?;
+    // Either listener stopping takes the daemon down
+    let (result, _, _) = futures::future::select_all(servers).await;
+    result??;
 
     Ok(())
 }
+
+/// Bind the gRPC socket, replacing one left behind by a daemon that died but
+/// refusing to take over one a running daemon still answers on
+async fn bind_socket(path: &Path, mode: u32) -> anyhow::Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            anyhow::bail!("Another daemon is serving on {}", path.display());
+        }
+        std::fs::remove_file(path)?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(listener)
+}
+
+/// Serving certificate from the daemon CA, reissued on every start so it
+/// always covers the current listen address and configured names
+fn server_tls_config(
+    auth: &AuthConfig,
+    dir: &Path,
+    addr: std::net::SocketAddr,
+) -> anyhow::Result<ServerTlsConfig> {
+    let ca = CertificateAuthority::load_or_generate(dir)?;
+
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    if !addr.ip().is_unspecified() {
+        names.push(addr.ip().to_string());
+    }
+    names.extend(auth.server_names.iter().cloned());
+    let server = ca.issue_server_cert(&names)?;
+    write_private(&dir.join("server.key"), server.key_pem.as_bytes())?;
+    std::fs::write(dir.join("server.crt"), &server.cert_pem)?;
+    info!("Serving TLS for {}", names.join(", "));
+
+    Ok(ServerTlsConfig::new()
+        .identity(Identity::from_pem(&server.cert_pem, &server.key_pem))
+        .client_ca_root(Certificate::from_pem(&ca.cert_pem)))
+}
@@ -0,0 +1,824 @@
+//! Host networking for shared and bridged networks
+//!
+//! User-mode networks need nothing from the host. The other modes use what
+//! each platform offers, once `network.enable_vmnet` allows it:
+//!
+//! - macOS: QEMU's `vmnet-shared` and `vmnet-bridged` backends, which need
+//!   the `com.apple.vm.networking` entitlement (or root).
+//! - Linux: a tap device per NIC. A shared network gets a daemon-managed
+//!   bridge holding its gateway address, NAT out of the host and, with DHCP
+//!   enabled, a dnsmasq serving its CIDR. Bridged networks join an existing
+//!   host bridge. Both need CAP_NET_ADMIN.
+//!
+//! Interface names derive from resource IDs, so a restarted daemon finds the
+//! taps and bridges of the VMs it adopts.
+//!
+//! Routers connect shared networks on Linux: the host forwards between their
+//! bridges through a per-router iptables chain holding the firewall rules,
+//! skips NAT for traffic between them and carries their static routes.
+
+use crate::config::DaemonConfig;
+use crate::state::StateManager;
+use infrasim_common::{
+    types::{ipv4_cidr_contains, parse_ipv4_cidr, Network, NetworkMode, Router, RouterSpec, Vm},
+    Error, Result,
+};
+use std::net::Ipv4Addr;
+use tracing::warn;
+
+#[cfg(target_os = "linux")]
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use tokio::process::Command;
+#[cfg(target_os = "linux")]
+use tracing::{debug, info};
+
+/// Subnet of QEMU's user-mode networking; the host is `.2` and DNS `.3`
+pub const USER_MODE_CIDR: &str = "10.0.2.0/24";
+
+/// Address slirp's DHCP server gives the guest unless one is pinned
+pub const USER_MODE_ADDR: Ipv4Addr = Ipv4Addr::new(10, 0, 2, 15);
+
+/// Static IP pinned for the VM's NIC on `network`, if user-mode networking can
+/// hand it out
+pub fn user_mode_ip(vm: &Vm, network: Option<&Network>) -> Option<Ipv4Addr> {
+    let attachment = vm.spec.attachment(&network?.meta.id)?;
+    let ip: Ipv4Addr = attachment.ip.as_deref()?.parse().ok()?;
+    ipv4_cidr_contains(USER_MODE_CIDR, ip).then_some(ip)
+}
+
+/// Reject a static IP the network's DHCP server can't hand out
+pub fn check_static_ip(network: &Network, ip: Ipv4Addr) -> Result<()> {
+    let (cidr, reserved) = match network.spec.mode {
+        NetworkMode::User => (
+            USER_MODE_CIDR.to_string(),
+            vec![Ipv4Addr::new(10, 0, 2, 2), Ipv4Addr::new(10, 0, 2, 3)],
+        ),
+        NetworkMode::VmnetShared => {
+            let gateway = match (&network.spec.gateway, parse_ipv4_cidr(&network.spec.cidr)) {
+                (Some(gateway), _) => gateway.parse().ok(),
+                (None, Some((base, _))) => Some(Ipv4Addr::from(u32::from(base) + 1)),
+                (None, None) => None,
+            };
+            (network.spec.cidr.clone(), gateway.into_iter().collect())
+        }
+        NetworkMode::VmnetBridged => {
+            return Err(Error::InvalidConfig(format!(
+                "Network {} is bridged; addresses on it come from the host's LAN",
+                network.meta.name
+            )))
+        }
+    };
+    let (base, prefix) = parse_ipv4_cidr(&cidr).ok_or_else(|| {
+        Error::InvalidConfig(format!(
+            "Network {} has an invalid CIDR '{}'",
+            network.meta.name, cidr
+        ))
+    })?;
+    let broadcast = Ipv4Addr::from(u32::from(base) | (u32::MAX >> prefix.min(31)));
+    if !ipv4_cidr_contains(&cidr, ip) {
+        return Err(Error::InvalidConfig(format!(
+            "{} is outside network {} ({})",
+            ip, network.meta.name, cidr
+        )));
+    }
+    if ip == base || ip == broadcast || reserved.contains(&ip) {
+        return Err(Error::InvalidConfig(format!(
+            "{} is reserved on network {}",
+            ip, network.meta.name
+        )));
+    }
+    Ok(())
+}
+
+/// Backend `-netdev` value for NIC `idx`, with the ID `net<idx>`
+///
+/// `vhost` asks for in-kernel virtio-net where the backend supports it; it
+/// only works when the netdev is wired straight to the NIC.
+pub fn netdev(
+    config: &DaemonConfig,
+    vm: &Vm,
+    idx: usize,
+    network: Option<&Network>,
+    vhost: bool,
+) -> String {
+    let vm_id = vm.meta.id.as_str();
+    let user = || {
+        let dhcp_start = user_mode_ip(vm, network)
+            .map(|ip| format!(",dhcpstart={}", ip))
+            .unwrap_or_default();
+        format!(
+            "user,id=net{},hostfwd=tcp::222{}-:22{}",
+            idx, idx, dhcp_start
+        )
+    };
+    let network = match network {
+        Some(network) if network.spec.mode != NetworkMode::User => network,
+        _ => return user(),
+    };
+    if !config.network.get().enable_vmnet {
+        warn!(
+            "Network {} is {:?} but network.enable_vmnet is off; using user-mode networking",
+            network.meta.name, network.spec.mode
+        );
+        return user();
+    }
+    match host_netdev(config, vm_id, idx, network, vhost) {
+        Some(netdev) => netdev,
+        None => {
+            warn!(
+                "{:?} networks are not supported on this host; using user-mode networking",
+                network.spec.mode
+            );
+            user()
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn host_netdev(
+    config: &DaemonConfig,
+    _vm_id: &str,
+    idx: usize,
+    network: &Network,
+    _vhost: bool,
+) -> Option<String> {
+    match network.spec.mode {
+        NetworkMode::VmnetShared => Some(match Subnet::parse(&network.spec.cidr) {
+            // The start address becomes the host side, the rest the DHCP pool
+            Some(subnet) => format!(
+                "vmnet-shared,id=net{},start-address={},end-address={},subnet-mask={}",
+                idx,
+                subnet.gateway(network.spec.gateway.as_deref()),
+                subnet.last_host(),
+                subnet.mask()
+            ),
+            None => format!("vmnet-shared,id=net{}", idx),
+        }),
+        NetworkMode::VmnetBridged => Some(format!(
+            "vmnet-bridged,id=net{},ifname={}",
+            idx,
+            host_bridge(config)
+        )),
+        NetworkMode::User => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn host_netdev(
+    _config: &DaemonConfig,
+    vm_id: &str,
+    idx: usize,
+    _network: &Network,
+    vhost: bool,
+) -> Option<String> {
+    let vhost = if vhost && Path::new("/dev/vhost-net").exists() {
+        ",vhost=on"
+    } else {
+        ""
+    };
+    Some(format!(
+        "tap,id=net{},ifname={},script=no,downscript=no{}",
+        idx,
+        tap_name(vm_id, idx),
+        vhost
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn host_netdev(
+    _config: &DaemonConfig,
+    _vm_id: &str,
+    _idx: usize,
+    _network: &Network,
+    _vhost: bool,
+) -> Option<String> {
+    None
+}
+
+/// Host interface bridged networks attach to
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn host_bridge(config: &DaemonConfig) -> String {
+    let default = if cfg!(target_os = "macos") {
+        "en0"
+    } else {
+        "br0"
+    };
+    config
+        .network
+        .get()
+        .bridge_interface
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Set up the host side of a VM's NICs before QEMU starts
+///
+/// On Linux this creates the taps and shared-network bridges; vmnet needs no
+/// preparation. Networks that end up backed by the host are marked active.
+pub async fn prepare(state: &StateManager, vm_id: &str, networks: &[Network]) -> Result<()> {
+    let config = state.config();
+    if !config.network.get().enable_vmnet {
+        return Ok(());
+    }
+    for (idx, network) in networks.iter().enumerate() {
+        if network.spec.mode == NetworkMode::User {
+            continue;
+        }
+        if network.spec.mode == NetworkMode::VmnetShared {
+            reserve_addresses(state, network)?;
+        }
+        let Some(interface) = attach(config, vm_id, idx, network).await? else {
+            continue;
+        };
+        let mut status = network.status.clone();
+        status.active = true;
+        status.bridge_interface = Some(interface);
+        state.update_network_status(&network.meta.id, status)?;
+    }
+    Ok(())
+}
+
+/// Hand out the static IPs pinned on a shared network through its DHCP server
+#[cfg(target_os = "linux")]
+fn reserve_addresses(state: &StateManager, network: &Network) -> Result<()> {
+    let mut hosts = String::new();
+    for vm in state.list_vms()? {
+        let Some(ip) = vm
+            .spec
+            .attachment(&network.meta.id)
+            .and_then(|a| a.ip.as_deref())
+        else {
+            continue;
+        };
+        if let Some(idx) = vm
+            .spec
+            .network_ids
+            .iter()
+            .position(|id| id == &network.meta.id)
+        {
+            let mac = crate::qemu::nic_mac(&vm, idx, Some(network));
+            hosts.push_str(&format!("{},{}\n", mac, ip));
+        }
+    }
+
+    let dir = state.config().network_dir(&network.meta.id);
+    let path = dir.join("dnsmasq.hosts");
+    if std::fs::read_to_string(&path).ok().as_deref() == Some(hosts.as_str()) {
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, hosts)?;
+
+    // dnsmasq rereads its hosts file on SIGHUP
+    if let Some(pid) = read_pid(&dir.join("dnsmasq.pid")).filter(|pid| pid_alive(*pid)) {
+        let _ = nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(pid),
+            nix::sys::signal::Signal::SIGHUP,
+        );
+    }
+    Ok(())
+}
+
+/// bootpd belongs to macOS, so pinned addresses are only what the guest asks for
+#[cfg(not(target_os = "linux"))]
+fn reserve_addresses(state: &StateManager, network: &Network) -> Result<()> {
+    let pinned = state.list_vms()?.iter().any(|vm| {
+        vm.spec
+            .attachment(&network.meta.id)
+            .is_some_and(|a| a.ip.is_some())
+    });
+    if pinned {
+        warn!(
+            "Static IPs on network {} are not reserved on this host; configure them in the guests",
+            network.meta.name
+        );
+    }
+    Ok(())
+}
+
+/// Host interface NIC `idx` was attached to, if the platform needs one
+#[cfg(target_os = "linux")]
+async fn attach(
+    config: &DaemonConfig,
+    vm_id: &str,
+    idx: usize,
+    network: &Network,
+) -> Result<Option<String>> {
+    let bridge = match network.spec.mode {
+        NetworkMode::VmnetShared => ensure_shared_bridge(config, network).await?,
+        _ => {
+            let bridge = host_bridge(config);
+            if !Path::new("/sys/class/net")
+                .join(&bridge)
+                .join("bridge")
+                .is_dir()
+            {
+                return Err(Error::NetworkError(format!(
+                    "Network {} bridges to {}, which is not a bridge on this host \
+                     (set network.bridge_interface)",
+                    network.meta.name, bridge
+                )));
+            }
+            bridge
+        }
+    };
+
+    let tap = tap_name(vm_id, idx);
+    if !link_exists(&tap) {
+        ip(&["tuntap", "add", "dev", &tap, "mode", "tap"]).await?;
+    }
+    ip(&["link", "set", "dev", &tap, "master", &bridge]).await?;
+    let mtu = network.spec.mtu.to_string();
+    ip(&["link", "set", "dev", &tap, "mtu", &mtu, "up"]).await?;
+    debug!("Attached {} to {} for VM {}", tap, bridge, vm_id);
+    Ok(Some(bridge))
+}
+
+#[cfg(target_os = "macos")]
+async fn attach(
+    config: &DaemonConfig,
+    _vm_id: &str,
+    _idx: usize,
+    network: &Network,
+) -> Result<Option<String>> {
+    Ok(match network.spec.mode {
+        NetworkMode::VmnetBridged => Some(host_bridge(config)),
+        _ => None,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+async fn attach(
+    _config: &DaemonConfig,
+    _vm_id: &str,
+    _idx: usize,
+    _network: &Network,
+) -> Result<Option<String>> {
+    Ok(None)
+}
+
+/// Remove the host side of a VM's NICs after QEMU exits
+pub async fn release(vm_id: &str) {
+    #[cfg(target_os = "linux")]
+    {
+        let prefix = tap_prefix(vm_id);
+        let Ok(entries) = std::fs::read_dir("/sys/class/net") else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(&prefix) {
+                if let Err(e) = ip(&["link", "del", "dev", &name]).await {
+                    warn!("Failed to remove tap {}: {}", name, e);
+                }
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = vm_id;
+}
+
+/// Tear down what the host holds for a deleted network
+pub async fn remove_network(config: &DaemonConfig, network: &Network) {
+    #[cfg(target_os = "linux")]
+    {
+        if network.spec.mode != NetworkMode::VmnetShared {
+            return;
+        }
+        let bridge = bridge_name(&network.meta.id);
+        stop_dnsmasq(config, network);
+        let _ = iptables_nat(&network.spec.cidr, &bridge, "-D").await;
+        if link_exists(&bridge) {
+            if let Err(e) = ip(&["link", "del", "dev", &bridge]).await {
+                warn!("Failed to remove bridge {}: {}", bridge, e);
+            }
+        }
+        let _ = std::fs::remove_dir_all(config.network_dir(&network.meta.id));
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (config, network);
+}
+
+/// Install a router on the host, returning the interfaces it forwards between
+///
+/// `networks` are the router's networks, in spec order. Reapplying replaces
+/// the firewall rules, so this also installs a changed spec once the old
+/// one's routes are removed with [`remove_router`].
+#[cfg(target_os = "linux")]
+pub async fn apply_router(
+    config: &DaemonConfig,
+    router: &Router,
+    networks: &[Network],
+) -> Result<Vec<String>> {
+    use infrasim_common::types::iptables_target;
+
+    if !config.network.get().enable_vmnet {
+        return Err(Error::NetworkError(
+            "Routers need host networking; set network.enable_vmnet".to_string(),
+        ));
+    }
+    let mut bridges = Vec::new();
+    for network in networks {
+        if network.spec.mode != NetworkMode::VmnetShared {
+            return Err(Error::NetworkError(format!(
+                "Router {} connects network {}, but routers only forward between \
+                 vmnet-shared networks",
+                router.meta.name, network.meta.name
+            )));
+        }
+        bridges.push(ensure_shared_bridge(config, network).await?);
+    }
+
+    // Firewall: replies pass, then the rules in order, then the default
+    let chain = router_chain(&router.meta.id);
+    reset_chain("filter", &chain).await?;
+    iptables(&[
+        "-A",
+        &chain,
+        "-m",
+        "conntrack",
+        "--ctstate",
+        "ESTABLISHED,RELATED",
+        "-j",
+        "ACCEPT",
+    ])
+    .await?;
+    for rule in &router.spec.rules {
+        let mut args = vec!["-A".to_string(), chain.clone()];
+        args.extend(rule.iptables_args());
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        iptables(&args).await?;
+    }
+    iptables(&[
+        "-A",
+        &chain,
+        "-j",
+        iptables_target(router.spec.default_action),
+    ])
+    .await?;
+    for from in &bridges {
+        for to in bridges.iter().filter(|to| *to != from) {
+            let jump = [
+                "FORWARD",
+                "-i",
+                from.as_str(),
+                "-o",
+                to.as_str(),
+                "-j",
+                &chain,
+            ];
+            ensure_jump("filter", &jump).await?;
+        }
+    }
+
+    // Traffic between the networks, or to their routes, keeps its source address
+    reset_chain("nat", &chain).await?;
+    let destinations = networks
+        .iter()
+        .map(|n| n.spec.cidr.as_str())
+        .chain(router.spec.routes.iter().map(|r| r.destination.as_str()));
+    for destination in destinations {
+        for network in networks.iter().filter(|n| n.spec.cidr != destination) {
+            iptables(&[
+                "-t",
+                "nat",
+                "-A",
+                &chain,
+                "-s",
+                &network.spec.cidr,
+                "-d",
+                destination,
+                "-j",
+                "ACCEPT",
+            ])
+            .await?;
+        }
+    }
+    ensure_jump("nat", &["POSTROUTING", "-j", &chain]).await?;
+
+    for route in &router.spec.routes {
+        ip(&[
+            "route",
+            "replace",
+            &route.destination,
+            "via",
+            &route.next_hop,
+        ])
+        .await?;
+    }
+    info!(
+        "Router {} forwarding between {}",
+        router.meta.name,
+        bridges.join(", ")
+    );
+    Ok(bridges)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn apply_router(
+    _config: &DaemonConfig,
+    router: &Router,
+    _networks: &[Network],
+) -> Result<Vec<String>> {
+    Err(infrasim_common::Error::NetworkError(format!(
+        "Router {} needs Linux bridges, which this host doesn't have",
+        router.meta.name
+    )))
+}
+
+/// Remove what [`apply_router`] installed for a router with `spec`
+pub async fn remove_router(router_id: &str, spec: &RouterSpec) {
+    #[cfg(target_os = "linux")]
+    {
+        let chain = router_chain(router_id);
+        let bridges: Vec<String> = spec.network_ids.iter().map(|id| bridge_name(id)).collect();
+        for from in &bridges {
+            for to in bridges.iter().filter(|to| *to != from) {
+                let _ = iptables(&["-D", "FORWARD", "-i", from, "-o", to, "-j", &chain]).await;
+            }
+        }
+        let _ = iptables(&["-t", "nat", "-D", "POSTROUTING", "-j", &chain]).await;
+        for table in ["filter", "nat"] {
+            let _ = iptables(&["-t", table, "-F", &chain]).await;
+            let _ = iptables(&["-t", table, "-X", &chain]).await;
+        }
+        for route in &spec.routes {
+            if let Err(e) = ip(&["route", "del", &route.destination, "via", &route.next_hop]).await
+            {
+                debug!("Route {} was already gone: {}", route.destination, e);
+            }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (router_id, spec);
+}
+
+/// iptables chain of a router, in both the filter and nat tables
+#[cfg(target_os = "linux")]
+fn router_chain(router_id: &str) -> String {
+    format!("INFRASIM-RT-{}", short_id(router_id))
+}
+
+#[cfg(target_os = "linux")]
+async fn iptables(args: &[&str]) -> Result<()> {
+    run("iptables", args).await
+}
+
+/// Create a chain if needed and empty it
+#[cfg(target_os = "linux")]
+async fn reset_chain(table: &str, chain: &str) -> Result<()> {
+    // Fails if the chain exists, which is fine
+    let _ = iptables(&["-t", table, "-N", chain]).await;
+    iptables(&["-t", table, "-F", chain]).await
+}
+
+/// Insert a rule at the top of a built-in chain unless it is already there
+#[cfg(target_os = "linux")]
+async fn ensure_jump(table: &str, rule: &[&str]) -> Result<()> {
+    let (&chain, matches) = rule.split_first().expect("rule names its chain");
+    let mut check = vec!["-t", table, "-C", chain];
+    check.extend_from_slice(matches);
+    if iptables(&check).await.is_ok() {
+        return Ok(());
+    }
+    let mut insert = vec!["-t", table, "-I", chain];
+    insert.extend_from_slice(matches);
+    iptables(&insert).await
+}
+
+/// Interface names are limited to 15 bytes, so IDs are cut to 8 characters
+#[cfg(target_os = "linux")]
+fn short_id(id: &str) -> String {
+    id.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(8)
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn tap_prefix(vm_id: &str) -> String {
+    format!("ist{}", short_id(vm_id))
+}
+
+/// Tap device of NIC `idx`
+#[cfg(target_os = "linux")]
+fn tap_name(vm_id: &str, idx: usize) -> String {
+    format!("{}{}", tap_prefix(vm_id), idx)
+}
+
+/// Bridge of a shared network
+#[cfg(target_os = "linux")]
+fn bridge_name(network_id: &str) -> String {
+    format!("isb{}", short_id(network_id))
+}
+
+#[cfg(target_os = "linux")]
+fn link_exists(name: &str) -> bool {
+    Path::new("/sys/class/net").join(name).exists()
+}
+
+/// Run `ip` with the given arguments
+#[cfg(target_os = "linux")]
+async fn ip(args: &[&str]) -> Result<()> {
+    run("ip", args).await
+}
+
+#[cfg(target_os = "linux")]
+async fn run(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| Error::NetworkError(format!("{} failed to run: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(Error::NetworkError(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Add (`-A`), check (`-C`) or delete (`-D`) the masquerade rule of a shared network
+#[cfg(target_os = "linux")]
+async fn iptables_nat(cidr: &str, bridge: &str, action: &str) -> Result<()> {
+    run(
+        "iptables",
+        &[
+            "-t",
+            "nat",
+            action,
+            "POSTROUTING",
+            "-s",
+            cidr,
+            "!",
+            "-o",
+            bridge,
+            "-j",
+            "MASQUERADE",
+        ],
+    )
+    .await
+}
+
+/// Create the bridge of a shared network if needed, returning its name
+#[cfg(target_os = "linux")]
+async fn ensure_shared_bridge(config: &DaemonConfig, network: &Network) -> Result<String> {
+    let bridge = bridge_name(&network.meta.id);
+    let subnet = Subnet::parse(&network.spec.cidr).ok_or_else(|| {
+        Error::NetworkError(format!(
+            "Network {} has an invalid CIDR '{}'",
+            network.meta.name, network.spec.cidr
+        ))
+    })?;
+    let gateway = subnet.gateway(network.spec.gateway.as_deref());
+
+    if !link_exists(&bridge) {
+        info!(
+            "Creating bridge {} for network {}",
+            bridge, network.meta.name
+        );
+        ip(&["link", "add", "name", &bridge, "type", "bridge"]).await?;
+    }
+    let address = format!("{}/{}", gateway, subnet.prefix);
+    ip(&["addr", "replace", &address, "dev", &bridge]).await?;
+    let mtu = network.spec.mtu.to_string();
+    ip(&["link", "set", "dev", &bridge, "mtu", &mtu, "up"]).await?;
+
+    std::fs::write("/proc/sys/net/ipv4/ip_forward", "1")
+        .map_err(|e| Error::NetworkError(format!("Failed to enable IP forwarding: {}", e)))?;
+    if iptables_nat(&network.spec.cidr, &bridge, "-C")
+        .await
+        .is_err()
+    {
+        iptables_nat(&network.spec.cidr, &bridge, "-A").await?;
+    }
+
+    if network.spec.dhcp_enabled {
+        start_dnsmasq(config, network, &bridge, &subnet).await?;
+    }
+    Ok(bridge)
+}
+
+/// Serve DHCP (and DNS, unless the network names a server) on a shared bridge
+#[cfg(target_os = "linux")]
+async fn start_dnsmasq(
+    config: &DaemonConfig,
+    network: &Network,
+    bridge: &str,
+    subnet: &Subnet,
+) -> Result<()> {
+    let dir = config.network_dir(&network.meta.id);
+    let pid_file = dir.join("dnsmasq.pid");
+    if read_pid(&pid_file).map_or(false, pid_alive) {
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    let gateway = subnet.gateway(network.spec.gateway.as_deref());
+    let range = format!(
+        "--dhcp-range={},{},{},12h",
+        subnet.host(2),
+        subnet.last_host(),
+        subnet.mask()
+    );
+    let router = format!("--dhcp-option=option:router,{}", gateway);
+    let dns = format!(
+        "--dhcp-option=option:dns-server,{}",
+        network
+            .spec
+            .dns
+            .clone()
+            .unwrap_or_else(|| gateway.to_string())
+    );
+    let interface = format!("--interface={}", bridge);
+    let pid = format!("--pid-file={}", pid_file.display());
+    let leases = format!("--dhcp-leasefile={}", dir.join("dnsmasq.leases").display());
+    let hosts_file = dir.join("dnsmasq.hosts");
+    if !hosts_file.exists() {
+        std::fs::write(&hosts_file, "")?;
+    }
+    let hosts = format!("--dhcp-hostsfile={}", hosts_file.display());
+    run(
+        "dnsmasq",
+        &[
+            "--conf-file=/dev/null",
+            "--bind-interfaces",
+            "--except-interface=lo",
+            &interface,
+            &range,
+            &router,
+            &dns,
+            &pid,
+            &leases,
+            &hosts,
+        ],
+    )
+    .await
+}
+
+#[cfg(target_os = "linux")]
+fn stop_dnsmasq(config: &DaemonConfig, network: &Network) {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    let pid_file = config.network_dir(&network.meta.id).join("dnsmasq.pid");
+    if let Some(pid) = read_pid(&pid_file).filter(|pid| pid_alive(*pid)) {
+        let _ = kill(Pid::from_raw(pid), Signal::SIGTERM);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_pid(path: &Path) -> Option<i32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(target_os = "linux")]
+fn pid_alive(pid: i32) -> bool {
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), None).is_ok()
+}
+
+/// An IPv4 network in CIDR notation
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+struct Subnet {
+    network: u32,
+    prefix: u8,
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl Subnet {
+    fn parse(cidr: &str) -> Option<Self> {
+        let (addr, prefix) = cidr.split_once('/')?;
+        let addr: std::net::Ipv4Addr = addr.parse().ok()?;
+        let prefix: u8 = prefix.parse().ok().filter(|p| *p <= 30)?;
+        let mask = u32::MAX << (32 - prefix);
+        Some(Self {
+            network: u32::from(addr) & mask,
+            prefix,
+        })
+    }
+
+    fn mask(&self) -> std::net::Ipv4Addr {
+        (u32::MAX << (32 - self.prefix)).into()
+    }
+
+    /// The `n`th address of the network
+    fn host(&self, n: u32) -> std::net::Ipv4Addr {
+        (self.network + n).into()
+    }
+
+    /// The address before the broadcast address
+    fn last_host(&self) -> std::net::Ipv4Addr {
+        let size = 1u32 << (32 - self.prefix);
+        self.host(size - 2)
+    }
+
+    /// The configured gateway, or the first address of the network
+    fn gateway(&self, configured: Option<&str>) -> std::net::Ipv4Addr {
+        configured
+            .and_then(|g| g.parse().ok())
+            .unwrap_or_else(|| self.host(1))
+    }
+}
@@ -0,0 +1,389 @@
+//! Live resource changes for running VMs
+//!
+//! Applies CPU, memory and volume changes to a running QEMU process over QMP
+//! so spec updates don't require a stop/start cycle.
+
+use crate::encryption::{self, Keyring};
+use crate::shares;
+use crate::state::{LiveResources, StateManager, VmProcess};
+use infrasim_common::{qmp::QmpClient, types::*, Error, Result};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// DIMM slots reserved for memory hot-plug (`-m slots=`)
+pub const MEMORY_SLOTS: u32 = 8;
+
+/// Hot-plugged DIMMs are rounded up to the guest's memory block size
+const DIMM_ALIGN_MB: u64 = 128;
+
+/// How long to wait for the guest to release an unplugged disk
+const DISK_RELEASE_ATTEMPTS: u32 = 10;
+const DISK_RELEASE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// ID of the PCIe root port backing a hot-plug disk slot
+pub fn hotplug_port_id(slot: u32) -> String {
+    format!("hp{}", slot)
+}
+
+/// Live resources of a VM that was just started from `vm`'s spec
+pub fn initial_live(vm: &Vm, volumes: &[Volume], hotplug_slots: u32) -> LiveResources {
+    LiveResources {
+        generation: vm.meta.generation,
+        cpu_cores: vm.spec.cpu_cores,
+        memory_mb: vm.spec.memory_mb,
+        balloon_mb: vm.spec.memory_mb,
+        boot_volume_ids: volumes
+            .iter()
+            .filter(|v| v.status.local_path.is_some())
+            .map(|v| v.meta.id.clone())
+            .collect(),
+        disk_slots: if vm.spec.compatibility_mode {
+            Vec::new()
+        } else {
+            vec![None; hotplug_slots as usize]
+        },
+        shared_folders: vm.spec.shared_folders.clone(),
+        ..Default::default()
+    }
+}
+
+/// Whether the running VM differs from its spec in a way hot-plug can address
+pub fn needs_apply(vm: &Vm, live: &LiveResources) -> bool {
+    if live.generation != vm.meta.generation {
+        return true;
+    }
+
+    // Volumes that were not ready on the last pass are retried until attached
+    let wanted = wanted_volumes(vm);
+    wanted.iter().any(|id| !live.has_volume(id))
+        || live
+            .disk_slots
+            .iter()
+            .flatten()
+            .any(|id| !wanted.contains(&id))
+}
+
+/// Whether the running VM has exactly the devices a fresh start from its spec would
+pub fn matches_boot_config(vm: &Vm, live: &LiveResources) -> bool {
+    let wanted = wanted_volumes(vm);
+    !needs_apply(vm, live)
+        && live.cpu_cores == vm.spec.cpu_cores
+        && live.memory_mb == vm.spec.memory_mb
+        && live.hotplug_cpus.is_empty()
+        && live.dimms.is_empty()
+        && live.disk_slots.iter().all(Option::is_none)
+        && live.shared_folders == vm.spec.shared_folders
+        && live
+            .boot_volume_ids
+            .iter()
+            .all(|id| Some(id) == vm.spec.boot_disk_id.as_ref() || wanted.contains(&id))
+}
+
+/// Bring a running VM's CPUs, memory and volumes in line with its spec
+///
+/// Changes that cannot be made live are reported as VM events and take
+/// effect on the next restart.
+pub async fn apply(state: &StateManager, vm: &Vm, process: &VmProcess) -> Result<()> {
+    let mut live = process.live.clone();
+    let spec_changed = live.generation != vm.meta.generation;
+    live.generation = vm.meta.generation;
+
+    if vm.spec.compatibility_mode {
+        if spec_changed {
+            state.emit_vm_event(
+                &vm.meta.id,
+                "Hot-plug is not available in compatibility mode; changes apply after restart",
+            );
+        }
+        state.update_vm_live(&vm.meta.id, live);
+        return Ok(());
+    }
+
+    let qmp = QmpClient::new(&process.qmp_socket);
+    qmp.connect().await?;
+
+    let mut deferred = Vec::new();
+
+    if spec_changed && live.cpu_cores != vm.spec.cpu_cores {
+        let from = live.cpu_cores;
+        match apply_cpus(&qmp, vm.spec.cpu_cores, &mut live).await {
+            Ok(()) => state.emit_vm_event(
+                &vm.meta.id,
+                format!("vCPUs changed from {} to {}", from, live.cpu_cores),
+            ),
+            Err(e) => deferred.push(format!("vCPU change to {}: {}", vm.spec.cpu_cores, e)),
+        }
+    }
+
+    if spec_changed && live.balloon_mb != vm.spec.memory_mb {
+        let from = live.balloon_mb;
+        // virtiofsd reads guest buffers from any DIMM, so new ones are shared too
+        let shared = shares::uses_virtiofs(state.config(), vm);
+        match apply_memory(&qmp, vm.spec.memory_mb, shared, &mut live).await {
+            Ok(()) => state.emit_vm_event(
+                &vm.meta.id,
+                format!("Memory changed from {} MB to {} MB", from, live.balloon_mb),
+            ),
+            Err(e) => deferred.push(format!("memory change to {} MB: {}", vm.spec.memory_mb, e)),
+        }
+    }
+
+    if spec_changed {
+        let wanted = wanted_volumes(vm);
+        for id in live
+            .boot_volume_ids
+            .iter()
+            .filter(|id| !wanted.contains(id))
+        {
+            deferred.push(format!("detaching boot-time volume {}", id));
+        }
+        if live.shared_folders != vm.spec.shared_folders {
+            deferred.push("shared folder changes".to_string());
+        }
+    }
+
+    let result = apply_volumes(state, &qmp, vm, &mut live).await;
+    state.update_vm_live(&vm.meta.id, live);
+
+    for message in deferred {
+        state.emit_vm_event(
+            &vm.meta.id,
+            format!("Cannot apply {} live; takes effect after restart", message),
+        );
+    }
+
+    match result {
+        Err(e) if spec_changed => {
+            state.emit_vm_event(&vm.meta.id, format!("Volume hot-plug failed: {}", e));
+            Err(e)
+        }
+        Err(e) => {
+            debug!("Volume hot-plug for {} still failing: {}", vm.meta.name, e);
+            Ok(())
+        }
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Volumes the spec wants attached besides the boot disk
+fn wanted_volumes(vm: &Vm) -> Vec<&String> {
+    vm.spec
+        .volume_ids
+        .iter()
+        .filter(|id| Some(*id) != vm.spec.boot_disk_id.as_ref())
+        .collect()
+}
+
+async fn apply_cpus(qmp: &QmpClient, target: u32, live: &mut LiveResources) -> Result<()> {
+    while live.cpu_cores < target {
+        let slot = qmp
+            .query_hotpluggable_cpus()
+            .await?
+            .into_iter()
+            .find(|c| c.qom_path.is_none())
+            .ok_or_else(|| {
+                Error::Qemu("no free vCPU slot (machine may not support CPU hot-plug)".to_string())
+            })?;
+
+        let id = format!("cpu{}", live.cpu_cores);
+        let mut args = slot.props;
+        args.insert("driver".to_string(), Value::String(slot.driver));
+        args.insert("id".to_string(), Value::String(id.clone()));
+        qmp.device_add(Value::Object(args)).await?;
+
+        live.cpu_cores += slot.vcpus_count.max(1);
+        live.hotplug_cpus.push(id);
+    }
+
+    while live.cpu_cores > target {
+        let id = live
+            .hotplug_cpus
+            .last()
+            .cloned()
+            .ok_or_else(|| Error::Qemu("boot vCPUs cannot be unplugged".to_string()))?;
+        qmp.device_del(&id).await?;
+
+        live.hotplug_cpus.pop();
+        live.cpu_cores -= 1;
+    }
+
+    Ok(())
+}
+
+/// Grow with a DIMM when needed, then balloon to the exact target
+///
+/// `shared` DIMMs are backed by memfd so vhost-user devices can map them.
+async fn apply_memory(
+    qmp: &QmpClient,
+    target_mb: u64,
+    shared: bool,
+    live: &mut LiveResources,
+) -> Result<()> {
+    if target_mb > live.memory_mb {
+        if live.dimms.len() >= MEMORY_SLOTS as usize {
+            return Err(Error::Qemu("no free memory slots".to_string()));
+        }
+
+        let size_mb = (target_mb - live.memory_mb).div_ceil(DIMM_ALIGN_MB) * DIMM_ALIGN_MB;
+        let idx = live.dimms.len();
+        let backend = if shared {
+            json!({
+                "qom-type": "memory-backend-memfd",
+                "id": format!("mem{}", idx),
+                "size": size_mb * 1024 * 1024,
+                "share": true,
+            })
+        } else {
+            json!({
+                "qom-type": "memory-backend-ram",
+                "id": format!("mem{}", idx),
+                "size": size_mb * 1024 * 1024,
+            })
+        };
+        qmp.object_add(backend).await?;
+        qmp.device_add(json!({
+            "driver": "pc-dimm",
+            "id": format!("dimm{}", idx),
+            "memdev": format!("mem{}", idx),
+        }))
+        .await?;
+
+        live.dimms.push(size_mb);
+        live.memory_mb += size_mb;
+    }
+
+    qmp.balloon(target_mb * 1024 * 1024).await?;
+    live.balloon_mb = target_mb;
+
+    Ok(())
+}
+
+async fn apply_volumes(
+    state: &StateManager,
+    qmp: &QmpClient,
+    vm: &Vm,
+    live: &mut LiveResources,
+) -> Result<()> {
+    let wanted = wanted_volumes(vm);
+
+    for slot in 0..live.disk_slots.len() {
+        let Some(volume_id) = live.disk_slots[slot].clone() else {
+            continue;
+        };
+        if !wanted.contains(&&volume_id) {
+            unplug_disk(qmp, slot as u32).await?;
+            live.disk_slots[slot] = None;
+            info!("Unplugged volume {} from VM {}", volume_id, vm.meta.name);
+            state.emit_vm_event(&vm.meta.id, format!("Volume {} detached", volume_id));
+        }
+    }
+
+    for volume_id in wanted {
+        if live.has_volume(volume_id) {
+            continue;
+        }
+
+        let Some(volume) = state.get_volume(volume_id)? else {
+            continue;
+        };
+        let Some(path) = volume
+            .status
+            .local_path
+            .as_deref()
+            .filter(|_| volume.status.ready)
+        else {
+            debug!("Volume {} not ready for hot-plug yet", volume.meta.name);
+            continue;
+        };
+
+        let slot = live
+            .disk_slots
+            .iter()
+            .position(Option::is_none)
+            .ok_or_else(|| Error::Qemu("no free disk hot-plug slots".to_string()))?;
+
+        plug_disk(state, qmp, slot as u32, &volume, path).await?;
+        live.disk_slots[slot] = Some(volume_id.clone());
+        info!(
+            "Hot-plugged volume {} into VM {}",
+            volume.meta.name, vm.meta.name
+        );
+        state.emit_vm_event(&vm.meta.id, format!("Volume {} attached", volume.meta.name));
+    }
+
+    Ok(())
+}
+
+async fn plug_disk(
+    state: &StateManager,
+    qmp: &QmpClient,
+    slot: u32,
+    volume: &Volume,
+    path: &str,
+) -> Result<()> {
+    let node = format!("hpdrv{}", slot);
+    let (format, file) = if volume.spec.kind == VolumeKind::Device {
+        ("raw", json!({ "driver": "host_device", "filename": path }))
+    } else if let Some(url) = path.strip_prefix(crate::qemu::NFS_SCHEME) {
+        let (host, export_path) = url.split_once('/').unwrap_or((url, ""));
+        (
+            volume.spec.format.as_str(),
+            json!({
+                "driver": "nfs",
+                "server": { "type": "inet", "host": host },
+                "path": format!("/{}", export_path),
+            }),
+        )
+    } else {
+        (
+            volume.spec.format.as_str(),
+            json!({ "driver": "file", "filename": path }),
+        )
+    };
+
+    let mut options = match &volume.spec.encryption {
+        Some(enc) => {
+            // The secret is already defined if QEMU started with an encrypted volume
+            if let Err(e) = qmp.object_add(Keyring::new(state.config()).secret_qmp(enc)).await {
+                debug!("Secret {} not added: {}", encryption::secret_id(enc), e);
+            }
+            encryption::blockdev_options(path, file, enc)?
+        }
+        None => json!({ "driver": format, "file": file }),
+    };
+    options["node-name"] = json!(node);
+    options["read-only"] = json!(volume.spec.read_only);
+    qmp.blockdev_add(options).await?;
+
+    let device = qmp
+        .device_add(json!({
+            "driver": "virtio-blk-pci",
+            "id": format!("hpdisk{}", slot),
+            "drive": node,
+            "bus": hotplug_port_id(slot),
+        }))
+        .await;
+    if let Err(e) = device {
+        let _ = qmp.blockdev_del(&node).await;
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+async fn unplug_disk(qmp: &QmpClient, slot: u32) -> Result<()> {
+    qmp.device_del(&format!("hpdisk{}", slot)).await?;
+
+    // device_del completes once the guest releases the device
+    let node = format!("hpdrv{}", slot);
+    let mut attempts = 0;
+    loop {
+        tokio::time::sleep(DISK_RELEASE_INTERVAL).await;
+        match qmp.blockdev_del(&node).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempts + 1 >= DISK_RELEASE_ATTEMPTS => return Err(e),
+            Err(_) => attempts += 1,
+        }
+    }
+}
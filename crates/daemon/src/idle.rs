@@ -0,0 +1,123 @@
+//! Idle VM detection
+//!
+//! The reconciler samples each running VM every [`SAMPLE_INTERVAL`]. A VM
+//! counts as busy while a VNC client or serial console is attached, its CPU
+//! use is at or above `idle.cpu_percent`, or its QoS relays carried traffic
+//! since the last sample. Once it has been quiet for `idle.idle_minutes` it
+//! is paused or suspended.
+
+use crate::state::{StateManager, VmProcess};
+use crate::stats::VmUsage;
+use infrasim_common::qmp::QmpClient;
+use infrasim_common::types::{Vm, IDLE_SUSPEND_LABEL};
+use std::time::Duration;
+
+/// Time between usage samples of a running VM
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Activity history of one running VM
+#[derive(Debug, Clone, Default)]
+pub struct IdleTracker {
+    last: Option<VmUsage>,
+    /// When the VM was last seen busy, in Unix milliseconds
+    active_at_ms: i64,
+}
+
+impl IdleTracker {
+    /// The previous sample, to compute CPU use against
+    pub fn previous(&self) -> Option<&VmUsage> {
+        self.last.as_ref()
+    }
+
+    /// Whether the next sample is due
+    pub fn due(&self, now_ms: i64) -> bool {
+        self.last
+            .as_ref()
+            .map_or(true, |last| now_ms - last.timestamp_ms >= SAMPLE_INTERVAL.as_millis() as i64)
+    }
+
+    /// Record a sample and return how long the VM has been idle
+    pub fn observe(&mut self, usage: VmUsage, console_attached: bool, cpu_percent: f64) -> Duration {
+        // A first sample, or one from a restarted QEMU, has no CPU figure
+        let previous = self.last.as_ref().filter(|last| last.pid == usage.pid);
+        let traffic = match (previous.and_then(|p| p.network), usage.network) {
+            (Some(before), Some(now)) => before != now,
+            _ => false,
+        };
+        if previous.is_none() || console_attached || traffic || usage.cpu_percent >= cpu_percent {
+            self.active_at_ms = usage.timestamp_ms;
+        }
+
+        let idle_ms = (usage.timestamp_ms - self.active_at_ms).max(0) as u64;
+        self.last = Some(usage);
+        Duration::from_millis(idle_ms)
+    }
+}
+
+/// Whether a VM is labelled to never be put to sleep for being idle
+pub fn opted_out(vm: &Vm) -> bool {
+    vm.meta.labels.get(IDLE_SUSPEND_LABEL).is_some_and(|v| v == "false")
+}
+
+/// Whether a VNC client or serial console is attached to a VM
+pub async fn console_attached(state: &StateManager, process: &VmProcess) -> bool {
+    if state.serial_attached(&process.vm_id) {
+        return true;
+    }
+    let qmp = QmpClient::new(&process.qmp_socket);
+    if qmp.connect().await.is_err() {
+        return false;
+    }
+    qmp.query_vnc()
+        .await
+        .is_ok_and(|vnc| vnc.clients.is_some_and(|clients| !clients.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(pid: u32, minute: i64, cpu_percent: f64, network: Option<(u64, u64)>) -> VmUsage {
+        VmUsage {
+            pid,
+            timestamp_ms: minute * 60_000,
+            cpu_percent,
+            network,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_idle_time_accumulates_while_quiet() {
+        let mut tracker = IdleTracker::default();
+        assert_eq!(tracker.observe(usage(1, 0, 0.0, None), false, 5.0), Duration::ZERO);
+        assert_eq!(tracker.observe(usage(1, 10, 1.0, None), false, 5.0), Duration::from_secs(600));
+        assert_eq!(tracker.observe(usage(1, 20, 2.0, None), false, 5.0), Duration::from_secs(1200));
+    }
+
+    #[test]
+    fn test_activity_resets_idle_time() {
+        let mut tracker = IdleTracker::default();
+        tracker.observe(usage(1, 0, 0.0, Some((10, 10))), false, 5.0);
+
+        // Busy CPU
+        assert_eq!(tracker.observe(usage(1, 10, 50.0, Some((10, 10))), false, 5.0), Duration::ZERO);
+        // Network traffic
+        assert_eq!(tracker.observe(usage(1, 20, 0.0, Some((20, 10))), false, 5.0), Duration::ZERO);
+        // A console
+        assert_eq!(tracker.observe(usage(1, 30, 0.0, Some((20, 10))), true, 5.0), Duration::ZERO);
+        // A restarted QEMU
+        assert_eq!(tracker.observe(usage(2, 40, 0.0, Some((20, 10))), false, 5.0), Duration::ZERO);
+
+        assert_eq!(tracker.observe(usage(2, 45, 0.0, Some((20, 10))), false, 5.0), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_due() {
+        let mut tracker = IdleTracker::default();
+        assert!(tracker.due(0));
+        tracker.observe(usage(1, 0, 0.0, None), false, 5.0);
+        assert!(!tracker.due(10_000));
+        assert!(tracker.due(30_000));
+    }
+}
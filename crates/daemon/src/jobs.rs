@@ -0,0 +1,357 @@
+//! Background jobs
+//!
+//! Long-running operations queued through `CreateJob` run on a fixed pool of
+//! workers. Progress and results are recorded on the job in the state
+//! database, so clients can detach and follow it from anywhere.
+
+use crate::appliance;
+use crate::backup;
+use crate::provision;
+use crate::snapshot;
+use crate::state::StateManager;
+use crate::transfer;
+use infrasim_common::{types::*, Error, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Minimum time between progress writes to the state database
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Queue of background jobs and the workers that run them
+#[derive(Clone)]
+pub struct JobQueue {
+    state: StateManager,
+    tx: mpsc::UnboundedSender<String>,
+    /// Cancellation tokens of running jobs, keyed by job ID
+    running: Arc<Mutex<HashMap<String, CancellationToken>>>,
+}
+
+impl JobQueue {
+    /// Start `workers` workers and requeue jobs left over from the last run
+    pub fn start(state: StateManager, workers: usize) -> Result<Self> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let queue = Self {
+            state,
+            tx,
+            running: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        // A job running when the daemon stopped may have left its work half
+        // done, so it isn't retried
+        for job in queue.state.list_jobs(None)? {
+            match job.status.state {
+                JobState::Pending => queue.enqueue(&job.meta.id),
+                JobState::Running => {
+                    let status = JobStatus {
+                        state: JobState::Failed,
+                        error: Some("interrupted by daemon restart".to_string()),
+                        finished_at: Some(chrono::Utc::now().timestamp()),
+                        ..job.status
+                    };
+                    queue.state.update_job_status(&job.meta.id, &status)?;
+                }
+                _ => {}
+            }
+        }
+
+        let rx = Arc::new(tokio::sync::Mutex::new(rx));
+        for _ in 0..workers.max(1) {
+            let queue = queue.clone();
+            let rx = rx.clone();
+            tokio::spawn(async move {
+                loop {
+                    let next = rx.lock().await.recv().await;
+                    match next {
+                        Some(id) => queue.work(&id).await,
+                        None => return,
+                    }
+                }
+            });
+        }
+
+        info!("Job queue started with {} workers", workers.max(1));
+        Ok(queue)
+    }
+
+    /// Record a job and queue it to run
+    pub fn submit(&self, spec: JobSpec, labels: HashMap<String, String>) -> Result<Job> {
+        if spec.target.is_empty() {
+            return Err(Error::InvalidConfig(format!(
+                "{} job needs a target",
+                spec.kind.as_str()
+            )));
+        }
+        if spec.kind == JobKind::Provision {
+            provision::Action::from_params(&spec.params)?;
+        }
+        for key in spec.kind.required_params() {
+            if spec.params.get(*key).map_or(true, |v| v.is_empty()) {
+                return Err(Error::InvalidConfig(format!(
+                    "{} job needs the '{}' param",
+                    spec.kind.as_str(),
+                    key
+                )));
+            }
+        }
+
+        let job = self.state.create_job(spec, labels)?;
+        self.enqueue(&job.meta.id);
+        info!("Queued job {}", job.meta.name);
+        Ok(job)
+    }
+
+    /// Cancel a pending job, or stop a running one whose kind allows it
+    pub fn cancel(&self, id: &str) -> Result<Job> {
+        // Held so a worker can't claim the job while its state is decided
+        let running = self.running.lock();
+        let mut job = self.state.get_job(id)?.ok_or_else(|| Error::NotFound {
+            kind: "job".to_string(),
+            id: id.to_string(),
+        })?;
+
+        match job.status.state {
+            JobState::Pending => {
+                job.status.state = JobState::Cancelled;
+                job.status.message = "cancelled before it started".to_string();
+                job.status.finished_at = Some(chrono::Utc::now().timestamp());
+                self.state.update_job_status(id, &job.status)?;
+            }
+            JobState::Running if job.spec.kind.cancellable_while_running() => {
+                // The worker records the cancellation once the work stops
+                if let Some(token) = running.get(id) {
+                    token.cancel();
+                }
+                job.status.message = "cancelling".to_string();
+            }
+            state => {
+                return Err(Error::InvalidStateTransition {
+                    from: state.as_str().to_string(),
+                    to: JobState::Cancelled.as_str().to_string(),
+                });
+            }
+        }
+
+        info!("Cancelling job {}", job.meta.name);
+        Ok(job)
+    }
+
+    fn enqueue(&self, id: &str) {
+        // Workers hold the receiver for as long as the queue exists
+        let _ = self.tx.send(id.to_string());
+    }
+
+    /// Mark a pending job running; None if it was cancelled while queued
+    fn claim(&self, id: &str) -> Result<Option<(Job, CancellationToken)>> {
+        let mut running = self.running.lock();
+        let mut job = match self.state.get_job(id)? {
+            Some(job) if job.status.state == JobState::Pending => job,
+            _ => return Ok(None),
+        };
+
+        job.status.state = JobState::Running;
+        job.status.message = "starting".to_string();
+        job.status.started_at = Some(chrono::Utc::now().timestamp());
+        self.state.update_job_status(id, &job.status)?;
+
+        let token = CancellationToken::new();
+        running.insert(id.to_string(), token.clone());
+        Ok(Some((job, token)))
+    }
+
+    async fn work(&self, id: &str) {
+        let (job, token) = match self.claim(id) {
+            Ok(Some(claimed)) => claimed,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to start job {}: {}", id, e);
+                return;
+            }
+        };
+        info!("Running job {}", job.meta.name);
+
+        let mut progress = Progress::new(&self.state, job.meta.id.clone(), job.status.clone());
+        let outcome = tokio::select! {
+            result = run(&self.state, &job.spec, &mut progress) => Some(result),
+            _ = token.cancelled() => None,
+        };
+        self.running.lock().remove(id);
+
+        let mut status = progress.status;
+        status.finished_at = Some(chrono::Utc::now().timestamp());
+        match outcome {
+            Some(Ok(result)) => {
+                info!("Job {} succeeded", job.meta.name);
+                status.state = JobState::Succeeded;
+                status.progress = 100.0;
+                status.message = "done".to_string();
+                status.result = result;
+            }
+            Some(Err(e)) => {
+                warn!("Job {} failed: {}", job.meta.name, e);
+                status.state = JobState::Failed;
+                status.error = Some(e.to_string());
+            }
+            None => {
+                info!("Job {} cancelled", job.meta.name);
+                status.state = JobState::Cancelled;
+                status.message = "cancelled".to_string();
+            }
+        }
+
+        if let Err(e) = self.state.update_job_status(id, &status) {
+            warn!("Failed to record outcome of job {}: {}", job.meta.name, e);
+        }
+    }
+}
+
+/// Records a running job's progress, at most once per `PROGRESS_INTERVAL`
+struct Progress<'a> {
+    state: &'a StateManager,
+    id: String,
+    status: JobStatus,
+    last_write: Option<Instant>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(state: &'a StateManager, id: String, status: JobStatus) -> Self {
+        Self {
+            state,
+            id,
+            status,
+            last_write: None,
+        }
+    }
+
+    /// Start a new step; always recorded
+    fn step(&mut self, message: impl Into<String>) {
+        self.status.message = message.into();
+        self.write();
+    }
+
+    fn percent(&mut self, percent: f64) {
+        self.status.progress = percent;
+        if self.last_write.map_or(true, |t| t.elapsed() >= PROGRESS_INTERVAL) {
+            self.write();
+        }
+    }
+
+    fn write(&mut self) {
+        self.last_write = Some(Instant::now());
+        if let Err(e) = self.state.update_job_status(&self.id, &self.status) {
+            warn!("Failed to record progress of job {}: {}", self.id, e);
+        }
+    }
+}
+
+async fn run(state: &StateManager, spec: &JobSpec, progress: &mut Progress<'_>) -> Result<HashMap<String, String>> {
+    let param = |key: &str| spec.params.get(key).filter(|v| !v.is_empty()).cloned();
+    let mut result = HashMap::new();
+
+    match spec.kind {
+        JobKind::PushVolume => {
+            let volume = get_volume(state, &spec.target)?;
+            if volume.spec.kind == VolumeKind::Device {
+                return Err(Error::VolumeError("Device volumes cannot be pushed".to_string()));
+            }
+            ensure_unused(state, &volume)?;
+
+            let remote = param("remote").unwrap_or_default();
+            progress.step(format!("pushing {} to {}", volume.meta.name, remote));
+            let pushed = transfer::push(state, &volume, &remote).await?;
+            result.insert("digest".to_string(), pushed.digest);
+            result.insert("size_bytes".to_string(), pushed.size_bytes.to_string());
+            result.insert("uploaded".to_string(), pushed.uploaded.to_string());
+        }
+        JobKind::PullVolume => {
+            let remote = param("remote").unwrap_or_default();
+            progress.step(format!("pulling {} from {}", spec.target, remote));
+            let volume = transfer::pull(state, &spec.target, &remote, param("name")).await?;
+            result.insert("volume_id".to_string(), volume.meta.id);
+        }
+        JobKind::FlattenVolume => {
+            let volume = get_volume(state, &spec.target)?;
+            if volume.spec.kind == VolumeKind::Device {
+                return Err(Error::VolumeError("Device volumes have no backing chain".to_string()));
+            }
+            ensure_unused(state, &volume)?;
+
+            progress.step(format!("flattening {}", volume.meta.name));
+            let path = snapshot::flatten(state, &volume, |percent| progress.percent(percent)).await?;
+            result.insert("local_path".to_string(), path.to_string_lossy().to_string());
+        }
+        JobKind::PushAppliance => {
+            let vm = get_vm(state, &spec.target)?;
+            let reference = param("reference").unwrap_or_default();
+            progress.step(format!("pushing {} as {}", vm.meta.name, reference));
+            let pushed = appliance::push(state, &vm, &reference).await?;
+            result.insert("reference".to_string(), pushed.reference);
+            result.insert("digest".to_string(), pushed.digest);
+        }
+        JobKind::PullAppliance => {
+            progress.step(format!("pulling {}", spec.target));
+            let pulled = appliance::pull(state, &spec.target, param("name"), HashMap::new()).await?;
+            result.insert("vm_id".to_string(), pulled.vm.meta.id);
+            result.insert("digest".to_string(), pulled.digest);
+        }
+        JobKind::CreateBackup => {
+            let vm = get_vm(state, &spec.target)?;
+            let name = param("name").unwrap_or_else(|| {
+                format!("{}-{}", vm.meta.name, chrono::Utc::now().format("%Y%m%d-%H%M%S"))
+            });
+            let backup_spec = BackupSpec {
+                vm_id: vm.meta.id.clone(),
+                target: param("target").unwrap_or_default(),
+                incremental: param("incremental").as_deref() == Some("true"),
+            };
+
+            progress.step(format!("backing up {} to {}", vm.meta.name, backup_spec.target));
+            let backup = backup::create(state, name, backup_spec, HashMap::new()).await?;
+            result.insert("backup_id".to_string(), backup.meta.id);
+        }
+        JobKind::RestoreBackup => {
+            progress.step(format!("restoring backup {}", spec.target));
+            let (vm, _) = backup::restore(state, &spec.target, param("name")).await?;
+            result.insert("vm_id".to_string(), vm.meta.id);
+        }
+        JobKind::Provision => {
+            let vm = get_vm(state, &spec.target)?;
+            let job_id = progress.id.clone();
+            let provisioned = provision::run(state, &job_id, &vm, &spec.params, |step| progress.step(step)).await?;
+            result.insert("endpoint".to_string(), provisioned.endpoint);
+            result.insert("log".to_string(), provisioned.log_path.to_string_lossy().to_string());
+            result.insert("log_digest".to_string(), provisioned.log_digest);
+        }
+    }
+
+    Ok(result)
+}
+
+fn get_volume(state: &StateManager, id: &str) -> Result<Volume> {
+    state.get_volume(id)?.ok_or_else(|| Error::NotFound {
+        kind: "volume".to_string(),
+        id: id.to_string(),
+    })
+}
+
+fn get_vm(state: &StateManager, id: &str) -> Result<Vm> {
+    state.get_vm(id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: id.to_string(),
+    })
+}
+
+/// The copy must see a consistent image, so nothing may write to it
+fn ensure_unused(state: &StateManager, volume: &Volume) -> Result<()> {
+    match snapshot::running_user(state, volume)? {
+        Some(vm) => Err(Error::VolumeError(format!(
+            "Volume {} is in use by running VM {}; stop it first",
+            volume.meta.name, vm.meta.name
+        ))),
+        None => Ok(()),
+    }
+}
@@ -4,14 +4,44 @@
 
 use clap::Parser;
 use std::path::PathBuf;
+use tokio::signal::unix::{signal, SignalKind};
 use tracing::{info, Level};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
+mod addresses;
+mod admission;
+mod appliance;
+mod archive;
+mod audit;
+mod backup;
+mod capture;
+mod chaos;
 mod config;
+mod download;
+mod encryption;
+mod export;
 mod grpc;
+mod hostnet;
+mod hotplug;
+mod idle;
+mod jobs;
+mod provision;
 mod qemu;
+mod qos;
 mod reconciler;
+mod registry;
+mod replication;
+mod s3;
+mod scenario;
+mod scheduler;
+mod shares;
+mod snapshot;
+mod snapshot_files;
 mod state;
+mod stats;
+mod template;
+mod transfer;
+mod vtpm;
 
 pub mod generated {
     #![allow(clippy::all)]
@@ -25,21 +55,37 @@ use config::DaemonConfig;
 #[command(about = "InfraSim daemon - Terraform-compatible QEMU orchestration")]
 #[command(version)]
 struct Cli {
-    /// Configuration file path
-    #[arg(short, long, default_value = "~/.infrasim/config.toml")]
-    config: PathBuf,
+    /// Configuration file path (default ~/.infrasim/config.toml; re-read on SIGHUP)
+    #[arg(short, long)]
+    config: Option<PathBuf>,
 
     /// Store directory
     #[arg(short, long)]
     store: Option<PathBuf>,
 
-    /// gRPC listen address
-    #[arg(short, long, default_value = "127.0.0.1:9090")]
-    listen: String,
+    /// gRPC listen address (default 127.0.0.1:9090)
+    #[arg(short, long)]
+    listen: Option<String>,
+
+    /// Don't listen on TCP; serve only on the Unix socket
+    #[arg(long, conflicts_with = "no_socket")]
+    no_tcp: bool,
+
+    /// gRPC Unix socket path (default <store>/daemon.sock)
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    /// Socket file mode in octal, e.g. 660 to admit the owning group
+    #[arg(long, value_parser = parse_mode)]
+    socket_mode: Option<u32>,
 
-    /// Web console port
-    #[arg(short, long, default_value = "6080")]
-    web_port: u16,
+    /// Don't serve on the Unix socket
+    #[arg(long)]
+    no_socket: bool,
+
+    /// Web console port (default 6080)
+    #[arg(short, long)]
+    web_port: Option<u16>,
 
     /// Enable debug logging
     #[arg(short, long)]
@@ -48,6 +94,72 @@ struct Cli {
     /// Run in foreground
     #[arg(short, long)]
     foreground: bool,
+
+    /// Serve TLS and require client certificates from `infrasim auth issue-client-cert`
+    #[arg(long)]
+    mtls: bool,
+
+    /// Require bearer tokens from `infrasim auth create-token`
+    #[arg(long)]
+    require_token: bool,
+
+    /// Extra DNS name or IP for the TLS serving certificate (repeatable)
+    #[arg(long = "tls-server-name")]
+    tls_server_names: Vec<String>,
+}
+
+fn parse_mode(s: &str) -> Result<u32, String> {
+    u32::from_str_radix(s.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+        .ok_or_else(|| format!("invalid file mode '{}'", s))
+}
+
+impl Cli {
+    /// Configuration file, and whether it was named explicitly
+    fn config_path(&self) -> (PathBuf, bool) {
+        match &self.config {
+            Some(path) => (config::expand_tilde(path), true),
+            None => (infrasim_common::default_store_path().join("config.toml"), false),
+        }
+    }
+
+    /// Load the configuration file with the command-line flags laid over it
+    fn load_config(&self) -> anyhow::Result<DaemonConfig> {
+        let (path, explicit) = self.config_path();
+        if explicit && !path.exists() {
+            anyhow::bail!("Config file {} does not exist", path.display());
+        }
+        let mut config = DaemonConfig::load(&path)?;
+
+        if let Some(store) = &self.store {
+            config.store_path = store.clone();
+        }
+        if let Some(listen) = &self.listen {
+            config.grpc_listen = listen.clone();
+        }
+        if self.no_tcp {
+            config.grpc_listen = String::new();
+        }
+        if self.no_socket {
+            config.socket.enabled = false;
+        }
+        if let Some(socket) = &self.socket {
+            config.socket.path = Some(socket.clone());
+        }
+        if let Some(mode) = self.socket_mode {
+            config.socket.mode = mode;
+        }
+        if let Some(port) = self.web_port {
+            config.web_port = port;
+        }
+        config.auth.mtls |= self.mtls;
+        config.auth.require_token |= self.require_token;
+        config.auth.server_names.extend(self.tls_server_names.iter().cloned());
+
+        config.validate()?;
+        Ok(config)
+    }
 }
 
 #[tokio::main]
@@ -68,50 +180,82 @@ async fn main() -> anyhow::Result<()> {
 
     info!("InfraSim daemon v{}", env!("CARGO_PKG_VERSION"));
 
-    // Load or create configuration
-    let store_path = cli.store.unwrap_or_else(infrasim_common::default_store_path);
-    let config = DaemonConfig {
-        store_path: store_path.clone(),
-        grpc_listen: cli.listen.clone(),
-        web_port: cli.web_port,
-        ..Default::default()
-    };
+    // Load configuration; flags override the file
+    let config = cli.load_config()?;
+    info!("Configuration loaded from {}", cli.config_path().0.display());
 
     // Ensure store directory exists
-    tokio::fs::create_dir_all(&store_path).await?;
+    tokio::fs::create_dir_all(&config.store_path).await?;
 
     // Initialize state manager
     let state = state::StateManager::new(&config).await?;
 
-    // Start reconciler
+    // Start reconciler, first taking back VMs a previous daemon left running
+    // so nothing starts a second QEMU for them. A standby only replicates
+    // until promoted, and the VMs in its copy never ran on this host.
+    let replication = replication::Replication::new(&config, state.clone());
     let reconciler = reconciler::Reconciler::new(state.clone());
-    let reconciler_handle = tokio::spawn(async move {
+    if !replication.is_standby() {
+        reconciler.recover().await?;
+        capture::recover(&state).await?;
+        scenario::recover(&state)?;
+    }
+    let standby = replication.clone();
+    let mut reconciler_handle = tokio::spawn(async move {
+        if standby.is_standby() {
+            standby.run_until_promoted().await;
+        }
         reconciler.run().await
     });
 
+    // Start background job workers
+    let jobs = jobs::JobQueue::start(state.clone(), config.jobs.workers)?;
+
     // Start gRPC server
-    let grpc_handle = tokio::spawn(grpc::serve(config.clone(), state.clone()));
+    let mut grpc_handle = tokio::spawn(grpc::serve(config.clone(), state.clone(), jobs, replication));
 
-    info!("Daemon started on {}", config.grpc_listen);
+    if !config.grpc_listen.is_empty() {
+        info!("Daemon started on {}", config.grpc_listen);
+    }
+    if config.socket.enabled {
+        info!("Daemon started on unix://{}", config.grpc_socket_path().display());
+    }
     info!("Web console available at http://127.0.0.1:{}", config.web_port);
 
-    // Wait for shutdown signal
-    tokio::select! {
-        _ = tokio::signal::ctrl_c() => {
-            info!("Received shutdown signal");
-        }
-        result = grpc_handle => {
-            if let Err(e) = result {
-                tracing::error!("gRPC server error: {}", e);
+    // Wait for shutdown, reloading the configuration on SIGHUP
+    let mut hangup = signal(SignalKind::hangup())?;
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("Received shutdown signal");
+                break;
             }
-        }
-        result = reconciler_handle => {
-            if let Err(e) = result {
-                tracing::error!("Reconciler error: {}", e);
+            _ = hangup.recv() => {
+                info!("Received SIGHUP, reloading configuration");
+                match cli.load_config() {
+                    Ok(new) => config.reload_from(&new),
+                    Err(e) => tracing::error!("Keeping the current configuration: {:#}", e),
+                }
+            }
+            result = &mut grpc_handle => {
+                if let Err(e) = result {
+                    tracing::error!("gRPC server error: {}", e);
+                }
+                break;
+            }
+            result = &mut reconciler_handle => {
+                if let Err(e) = result {
+                    tracing::error!("Reconciler error: {}", e);
+                }
+                break;
             }
         }
     }
 
+    if config.socket.enabled {
+        let _ = std::fs::remove_file(config.grpc_socket_path());
+    }
+
     info!("Daemon shutdown complete");
     Ok(())
 }
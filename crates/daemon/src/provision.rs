@@ -0,0 +1,315 @@
+//! Guest provisioning after boot
+//!
+//! A `provision` job waits for a guest's SSH server to answer, then runs an
+//! Ansible playbook against it or a shell script over `ssh`, with the
+//! daemon host's `ansible-playbook` and `ssh`. Their combined output is kept
+//! as a run artifact of the job, `runs/<job id>/provision.log`.
+//!
+//! The guest is reached at the `host` and `port` params when given.
+//! Otherwise a TCP port forward to the guest's SSH port is used, or failing
+//! that the address of a NIC the host can reach directly.
+
+use crate::addresses;
+use crate::state::StateManager;
+use infrasim_common::{types::*, ContentAddressedStore, Error, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+/// Name of the run artifact holding the output
+pub const LOG_ARTIFACT: &str = "provision.log";
+
+const DEFAULT_SSH_PORT: u16 = 22;
+const DEFAULT_USER: &str = "root";
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const PROBE_INTERVAL: Duration = Duration::from_secs(3);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lines of output quoted when a run fails
+const TAIL_LINES: usize = 20;
+
+/// What runs against the guest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Playbook on the daemon host
+    Ansible { playbook: PathBuf },
+    /// Script fed to `sh -s` on the guest
+    Ssh { script: String },
+}
+
+impl Action {
+    /// The action named by a job's `playbook` or `script` param
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self> {
+        let param = |key: &str| params.get(key).filter(|v| !v.is_empty());
+        match (param("playbook"), param("script")) {
+            (Some(playbook), None) => Ok(Action::Ansible {
+                playbook: PathBuf::from(playbook),
+            }),
+            (None, Some(script)) => Ok(Action::Ssh {
+                script: script.clone(),
+            }),
+            (Some(_), Some(_)) => Err(Error::InvalidConfig(
+                "provision job takes a 'playbook' or a 'script', not both".to_string(),
+            )),
+            (None, None) => Err(Error::InvalidConfig(
+                "provision job needs the 'playbook' or 'script' param".to_string(),
+            )),
+        }
+    }
+}
+
+/// Outcome of a successful run
+pub struct Provisioned {
+    /// `host:port` the guest was reached at
+    pub endpoint: String,
+    pub log_path: PathBuf,
+    pub log_digest: String,
+}
+
+/// Provision `vm` as `params` say; `step` is told what is happening
+pub async fn run(
+    state: &StateManager,
+    job_id: &str,
+    vm: &Vm,
+    params: &HashMap<String, String>,
+    mut step: impl FnMut(String),
+) -> Result<Provisioned> {
+    let action = Action::from_params(params)?;
+    let param = |key: &str| params.get(key).filter(|v| !v.is_empty());
+    let port = match param("port") {
+        Some(port) => port
+            .parse()
+            .map_err(|_| Error::InvalidConfig(format!("invalid SSH port '{}'", port)))?,
+        None => DEFAULT_SSH_PORT,
+    };
+    let user = param("user").map_or(DEFAULT_USER, |u| u.as_str());
+    let identity = param("identity").map(PathBuf::from);
+    let timeout = param("timeout_secs")
+        .and_then(|t| t.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+    step(format!("waiting for SSH on {}", vm.meta.name));
+    let (host, port) = wait_for_ssh(state, &vm.meta.id, param("host"), port, Duration::from_secs(timeout)).await?;
+    let endpoint = format!("{}:{}", host, port);
+
+    let run_dir = state.cas().create_run(job_id).await?;
+    let log_path = run_dir.join(LOG_ARTIFACT);
+    // A fresh guest's host key is new; it is trusted for this run only
+    let options = ssh_options(&run_dir.join("known_hosts"));
+
+    let (mut command, stdin) = match &action {
+        Action::Ansible { playbook } => {
+            step(format!("running {} against {}", playbook.display(), endpoint));
+            let mut command = Command::new("ansible-playbook");
+            command
+                .arg("-i")
+                .arg(format!("{},", host))
+                .args(["-u", user])
+                .arg("-e")
+                .arg(format!("ansible_port={}", port))
+                .arg("--ssh-common-args")
+                .arg(options.iter().map(|o| shell_quote(o)).collect::<Vec<_>>().join(" "))
+                .env("ANSIBLE_NOCOLOR", "1");
+            if let Some(identity) = &identity {
+                command.arg("--private-key").arg(identity);
+            }
+            command.arg(playbook);
+            (command, None)
+        }
+        Action::Ssh { script } => {
+            step(format!("running script on {}", endpoint));
+            let mut command = Command::new("ssh");
+            command.args(&options).arg("-p").arg(port.to_string());
+            if let Some(identity) = &identity {
+                command.arg("-i").arg(identity).args(["-o", "IdentitiesOnly=yes"]);
+            }
+            command.arg(format!("{}@{}", user, host)).args(["sh", "-s"]);
+            (command, Some(script.as_str()))
+        }
+    };
+
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    let status = run_logged(&mut command, &log_path, stdin)
+        .await
+        .map_err(|e| Error::Provisioning(format!("can't run {}: {}", program, e)))?;
+    let log = tokio::fs::read(&log_path).await?;
+    if !status.success() {
+        return Err(Error::Provisioning(format!(
+            "{} failed ({}):\n{}",
+            program,
+            status,
+            tail(&log, TAIL_LINES)
+        )));
+    }
+
+    Ok(Provisioned {
+        endpoint,
+        log_path,
+        log_digest: ContentAddressedStore::hash(&log),
+    })
+}
+
+/// Wait until an SSH server answers for the VM
+async fn wait_for_ssh(
+    state: &StateManager,
+    vm_id: &str,
+    host: Option<&String>,
+    port: u16,
+    timeout: Duration,
+) -> Result<(String, u16)> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let vm = state.get_vm(vm_id)?.ok_or_else(|| Error::NotFound {
+            kind: "vm".to_string(),
+            id: vm_id.to_string(),
+        })?;
+        if matches!(vm.status.state, VmState::Stopped | VmState::Error) {
+            return Err(Error::Provisioning(format!(
+                "VM {} is {:?}; start it first",
+                vm.meta.name, vm.status.state
+            )));
+        }
+
+        let endpoint = match host {
+            Some(host) => Some((host.clone(), port)),
+            None => guest_endpoint(state, &vm, port)?,
+        };
+        if let Some((host, port)) = &endpoint {
+            if ssh_answers(host, *port).await {
+                return Ok((host.clone(), *port));
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(Error::Provisioning(match endpoint {
+                Some((host, port)) => format!("SSH on {}:{} didn't answer within {:?}", host, port, timeout),
+                None => format!(
+                    "no route to SSH port {} of VM {}; add a port forward to it or set 'host'",
+                    port, vm.meta.name
+                ),
+            }));
+        }
+        tokio::time::sleep(PROBE_INTERVAL).await;
+    }
+}
+
+/// Where the host reaches the guest's `port`: a port forward to it, else
+/// a NIC address outside user-mode networking
+fn guest_endpoint(state: &StateManager, vm: &Vm, port: u16) -> Result<Option<(String, u16)>> {
+    let forward = state
+        .list_port_forwards(Some(&vm.meta.id))?
+        .into_iter()
+        .find(|f| f.spec.protocol == PortProtocol::Tcp && f.spec.guest_port == port);
+    if let Some(forward) = forward {
+        let host = match forward.spec.host_addr.as_str() {
+            "" | "0.0.0.0" => "127.0.0.1".to_string(),
+            "::" => "::1".to_string(),
+            addr => addr.to_string(),
+        };
+        return Ok(Some((host, forward.spec.host_port)));
+    }
+
+    let networks: Vec<Network> = vm
+        .spec
+        .network_ids
+        .iter()
+        .filter_map(|id| state.get_network(id).ok().flatten())
+        .collect();
+    // User-mode addresses are only reachable from inside the guest
+    Ok(addresses::vm_addresses(state.config(), vm, &networks)
+        .into_iter()
+        .filter(|nic| nic.source != "user-mode")
+        .find_map(|nic| nic.ip)
+        .map(|ip| (ip.to_string(), port)))
+}
+
+/// Whether an SSH server greets connections on `host:port`; the port of a
+/// forward accepts connections before the guest listens
+async fn ssh_answers(host: &str, port: u16) -> bool {
+    let probe = async {
+        let mut stream = TcpStream::connect((host, port)).await.ok()?;
+        let mut banner = [0u8; 4];
+        stream.read_exact(&mut banner).await.ok()?;
+        Some(&banner == b"SSH-")
+    };
+    matches!(tokio::time::timeout(PROBE_TIMEOUT, probe).await, Ok(Some(true)))
+}
+
+fn ssh_options(known_hosts: &Path) -> Vec<String> {
+    vec![
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ConnectTimeout=10".to_string(),
+        "-o".to_string(),
+        format!("UserKnownHostsFile={}", known_hosts.display()),
+        "-o".to_string(),
+        "StrictHostKeyChecking=accept-new".to_string(),
+    ]
+}
+
+/// Run `command` with stdout and stderr interleaved into `log_path`
+async fn run_logged(command: &mut Command, log_path: &Path, stdin: Option<&str>) -> std::io::Result<ExitStatus> {
+    let log = std::fs::File::create(log_path)?;
+    command
+        .stdout(Stdio::from(log.try_clone()?))
+        .stderr(Stdio::from(log))
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        // Cancelling the job drops the future; the tool goes with it
+        .kill_on_drop(true);
+    let mut child = command.spawn()?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).await?;
+    }
+    child.wait().await
+}
+
+/// Quote for the shell-like splitting Ansible applies to SSH arguments
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// Last `lines` lines of output
+fn tail(log: &[u8], lines: usize) -> String {
+    let text = String::from_utf8_lossy(log);
+    let all: Vec<&str> = text.trim_end().lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_from_params() {
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert_eq!(
+            Action::from_params(&params(&[("playbook", "site.yml")])).unwrap(),
+            Action::Ansible {
+                playbook: PathBuf::from("site.yml")
+            }
+        );
+        assert_eq!(
+            Action::from_params(&params(&[("script", "apk add nginx"), ("playbook", "")])).unwrap(),
+            Action::Ssh {
+                script: "apk add nginx".to_string()
+            }
+        );
+        assert!(Action::from_params(&params(&[("playbook", "a.yml"), ("script", "true")])).is_err());
+        assert!(Action::from_params(&params(&[])).is_err());
+    }
+
+    #[test]
+    fn test_tail_and_quoting() {
+        let log = (1..=30).map(|i| format!("line {}\n", i)).collect::<String>();
+        let tail = tail(log.as_bytes(), 3);
+        assert_eq!(tail, "line 28\nline 29\nline 30");
+        assert_eq!(shell_quote("UserKnownHostsFile=/tmp/it's"), r"'UserKnownHostsFile=/tmp/it'\''s'");
+    }
+}
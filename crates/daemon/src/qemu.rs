@@ -2,22 +2,139 @@
 //!
 //! Handles launching and managing QEMU processes.
 
-use crate::config::DaemonConfig;
+use crate::chaos;
+use crate::config::{DaemonConfig, QemuConfig};
+use crate::download::{expected_digest, Downloader};
+use crate::encryption::{self, Keyring};
+use crate::hostnet;
+use crate::hotplug::{self, hotplug_port_id, initial_live, MEMORY_SLOTS};
+use crate::qos::{NicRelay, QosLink};
+use crate::registry::RegistryClient;
+use crate::shares::{self, Share};
 use crate::state::{StateManager, VmProcess};
+use crate::vtpm::{self, Vtpm};
 use infrasim_common::{
-    attestation::is_hvf_available,
+    attestation::{
+        is_hvf_available, is_kvm_available, is_swtpm_available,
+        vtpm::{default_pcrs, random_nonce},
+    },
+    blockdev::inspect_block_device,
     qmp::{wait_for_qmp, QmpClient},
+    slsa::{self, BuildInputs, Envelope, ResourceDescriptor, Statement},
     types::*,
     Error, Result,
 };
 use nix::sys::signal::{kill, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
 use nix::unistd::Pid;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
 use tracing::{debug, error, info, warn};
 
+/// How often to poll QEMU while VM state is saved or restored
+const MIGRATE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long QEMU gets to exit after saving a suspended VM's state
+const SUSPEND_EXIT_SECS: u64 = 10;
+
+/// ID of the xHCI controller USB passthrough devices are plugged into
+const USB_CONTROLLER_ID: &str = "xhci";
+
+/// How long QEMU gets to exit after SIGTERM before it is killed
+const TERM_GRACE_SECS: u64 = 5;
+
+/// How `QemuLauncher::stop` brings a VM down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopMode {
+    /// Ask the guest to power off, escalating if it doesn't
+    Acpi,
+    /// Terminate QEMU without asking the guest
+    Hard,
+}
+
+/// How long a QMP socket left by a previous daemon gets to answer
+const ADOPT_QMP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether a process is alive
+///
+/// QEMU started by this daemon is its child, so an exited one lingers as a
+/// zombie that still answers signals until it is reaped here.
+pub fn process_alive(pid: u32) -> bool {
+    let pid = Pid::from_raw(pid as i32);
+    match waitpid(pid, Some(WaitPidFlag::WNOHANG)) {
+        Ok(WaitStatus::StillAlive) => true,
+        Ok(_) => false,
+        // Not our child, e.g. adopted from a previous daemon
+        Err(_) => kill(pid, None).is_ok(),
+    }
+}
+
+/// Accelerator a VM runs under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Accel {
+    Hvf,
+    Kvm,
+    Tcg,
+}
+
+impl Accel {
+    fn as_str(self) -> &'static str {
+        match self {
+            Accel::Hvf => "hvf",
+            Accel::Kvm => "kvm",
+            Accel::Tcg => "tcg",
+        }
+    }
+}
+
+/// MAC of NIC `idx` on `network`: the one pinned in the VM spec, or a
+/// locally administered one stable for the VM and distinct between clones of
+/// one template
+pub fn nic_mac(vm: &Vm, idx: usize, network: Option<&Network>) -> String {
+    let pinned = network
+        .and_then(|n| vm.spec.attachment(&n.meta.id))
+        .and_then(|a| a.mac.as_deref())
+        .and_then(parse_unicast_mac);
+    if let Some(mac) = pinned {
+        return mac;
+    }
+    let digest = Sha256::digest(format!("{}/{}", vm.meta.id, idx));
+    format!("52:54:00:{:02x}:{:02x}:{:02x}", digest[0], digest[1], digest[2])
+}
+
+/// Guest architecture of a VM
+fn guest_arch(vm: &Vm) -> &str {
+    if vm.spec.arch.is_empty() {
+        "aarch64"
+    } else {
+        &vm.spec.arch
+    }
+}
+
+/// Pick the accelerator for a VM
+///
+/// HVF and KVM only run guests of the host's own architecture; anything
+/// else is emulated.
+fn select_accel(vm: &Vm, qemu: &QemuConfig) -> Accel {
+    if vm.spec.compatibility_mode {
+        return Accel::Tcg;
+    }
+    let native = guest_arch(vm) == std::env::consts::ARCH;
+    match qemu.accelerator.as_str() {
+        "hvf" => Accel::Hvf,
+        "kvm" => Accel::Kvm,
+        "tcg" => Accel::Tcg,
+        _ if native && qemu.enable_hvf && is_hvf_available() => Accel::Hvf,
+        _ if native && qemu.enable_kvm && is_kvm_available() => Accel::Kvm,
+        _ => Accel::Tcg,
+    }
+}
+
 /// QEMU launcher for managing VM lifecycles
 pub struct QemuLauncher {
     config: DaemonConfig,
@@ -29,13 +146,9 @@ impl QemuLauncher {
         Self { config }
     }
 
-    /// Get the QEMU binary path
-    fn qemu_path(&self) -> String {
-        self.config
-            .qemu
-            .binary_path
-            .clone()
-            .unwrap_or_else(|| "qemu-system-aarch64".to_string())
+    /// Get the QEMU binary path for a VM's architecture
+    fn qemu_path(&self, vm: &Vm) -> String {
+        self.config.qemu.get().binary_for(&vm.spec.arch)
     }
 
     /// Build QEMU command line arguments
@@ -44,9 +157,16 @@ impl QemuLauncher {
         vm: &Vm,
         volumes: &[Volume],
         networks: &[Network],
+        devices: &[Device],
+        qos_relays: &[NicRelay],
+        vtpm: Option<&Vtpm>,
+        shares: &[Share],
         qmp_socket: &Path,
+        console_log: &Path,
         vnc_display: u16,
     ) -> Vec<String> {
+        let qemu = self.config.qemu.get();
+        let accel = select_accel(vm, &qemu);
         let mut args = Vec::new();
 
         // Machine type
@@ -55,32 +175,116 @@ impl QemuLauncher {
             warn!("Using compatibility mode (raspi3b) - this is significantly slower");
             "raspi3b".to_string()
         } else {
-            // Fast virt machine (default)
-            self.config.qemu.machine_type.clone()
+            match guest_arch(vm) {
+                // Fast virt machine (default); KVM needs the host's GIC
+                "aarch64" if accel == Accel::Kvm && !qemu.machine_type.contains("gic-version") => {
+                    format!("{},gic-version=host", qemu.machine_type)
+                }
+                "aarch64" => qemu.machine_type.clone(),
+                "x86_64" => "q35".to_string(),
+                _ => vm.spec.machine.clone(),
+            }
         };
         args.extend(["-machine".to_string(), machine]);
 
-        // Accelerator (HVF on macOS)
-        if !vm.spec.compatibility_mode && is_hvf_available() && self.config.qemu.enable_hvf {
-            args.extend(["-accel".to_string(), "hvf".to_string()]);
-        } else if vm.spec.compatibility_mode {
-            // TCG for compatibility mode
-            args.extend(["-accel".to_string(), "tcg".to_string()]);
+        // SMBIOS UUID; systemd derives an empty /etc/machine-id from it
+        if uuid::Uuid::parse_str(&vm.meta.id).is_ok() {
+            args.extend(["-uuid".to_string(), vm.meta.id.clone()]);
         }
 
-        // CPU
+        // Accelerator: HVF on macOS, KVM on Linux, TCG (emulation) otherwise
+        if accel == Accel::Tcg && !vm.spec.compatibility_mode {
+            warn!(
+                "No hardware acceleration for VM {}; running under TCG",
+                vm.meta.name
+            );
+        }
+        args.extend(["-accel".to_string(), accel.as_str().to_string()]);
+
+        // CPU; "host" only exists with hardware acceleration
         let cpu = if vm.spec.compatibility_mode {
             "cortex-a53".to_string()
+        } else if accel == Accel::Tcg && qemu.cpu_type == "host" {
+            "max".to_string()
         } else {
-            self.config.qemu.cpu_type.clone()
+            qemu.cpu_type.clone()
         };
         args.extend(["-cpu".to_string(), cpu]);
 
-        // SMP
-        args.extend(["-smp".to_string(), vm.spec.cpu_cores.to_string()]);
+        // SMP and memory, with headroom for hot-plug on the virt machine
+        if vm.spec.compatibility_mode {
+            args.extend(["-smp".to_string(), vm.spec.cpu_cores.to_string()]);
+            args.extend(["-m".to_string(), format!("{}M", vm.spec.memory_mb)]);
+        } else {
+            let max_cpus = vm.spec.cpu_cores.max(qemu.max_cpus);
+            let max_memory = vm.spec.memory_mb.max(qemu.max_memory_mb);
+            args.extend([
+                "-smp".to_string(),
+                format!("{},maxcpus={}", vm.spec.cpu_cores, max_cpus),
+            ]);
+            args.extend([
+                "-m".to_string(),
+                format!(
+                    "{}M,slots={},maxmem={}M",
+                    vm.spec.memory_mb, MEMORY_SLOTS, max_memory
+                ),
+            ]);
+
+            // Balloon for shrinking memory without unplugging DIMMs
+            args.extend([
+                "-device".to_string(),
+                "virtio-balloon-pci,id=balloon0".to_string(),
+            ]);
+
+            // Empty root ports for hot-plugged disks
+            for slot in 0..qemu.hotplug_slots {
+                args.extend([
+                    "-device".to_string(),
+                    format!("pcie-root-port,id={},chassis={}", hotplug_port_id(slot), slot + 1),
+                ]);
+            }
 
-        // Memory
-        args.extend(["-m".to_string(), format!("{}M", vm.spec.memory_mb)]);
+            // USB controller for hot-attached host devices
+            args.extend([
+                "-device".to_string(),
+                format!("qemu-xhci,id={}", USB_CONTROLLER_ID),
+            ]);
+
+            // Framebuffer and input for the web console; virtio-gpu follows
+            // the resolution VNC clients request, the tablet keeps the pointer
+            // absolute
+            args.extend([
+                "-device".to_string(),
+                "virtio-gpu-pci".to_string(),
+                "-device".to_string(),
+                format!("usb-kbd,bus={}.0", USB_CONTROLLER_ID),
+                "-device".to_string(),
+                format!("usb-tablet,bus={}.0", USB_CONTROLLER_ID),
+            ]);
+
+            args.extend(["-device".to_string(), "virtio-serial-pci".to_string()]);
+
+            // Guest clipboard, exposed to VNC clients as cut text
+            if qemu.vnc_clipboard {
+                args.extend([
+                    "-chardev".to_string(),
+                    "qemu-vdagent,id=vdagent,name=vdagent,clipboard=on".to_string(),
+                    "-device".to_string(),
+                    "virtserialport,chardev=vdagent,name=com.redhat.spice.0".to_string(),
+                ]);
+            }
+
+            // qemu-guest-agent channel, used to copy files into the guest
+            args.extend([
+                "-chardev".to_string(),
+                format!(
+                    "socket,id=qga0,path={},server=on,wait=off",
+                    self.config.guest_agent_socket_path(&vm.meta.id).display()
+                ),
+                "-device".to_string(),
+                "virtserialport,chardev=qga0,name=org.qemu.guest_agent.0".to_string(),
+            ]);
+        }
 
         // QMP socket
         args.extend([
@@ -94,6 +298,33 @@ impl QemuLauncher {
         // Headless by default
         args.push("-nographic".to_string());
 
+        // Serial console on a socket for interactive sessions, also
+        // appended to a log file for `vm logs`
+        args.extend([
+            "-chardev".to_string(),
+            format!(
+                "socket,id=serial0,path={},server,nowait,logfile={},logappend=on",
+                self.config.serial_socket_path(&vm.meta.id).display(),
+                console_log.display()
+            ),
+            "-serial".to_string(),
+            "chardev:serial0".to_string(),
+        ]);
+
+        // Secrets of encrypted volumes, read by QEMU from the keyring
+        let keys = volumes
+            .iter()
+            .filter(|v| v.status.local_path.is_some())
+            .filter_map(|v| v.spec.encryption.as_ref());
+        match Keyring::new(&self.config).secret_objects(keys) {
+            Ok(objects) => {
+                for object in objects {
+                    args.extend(["-object".to_string(), object]);
+                }
+            }
+            Err(e) => warn!("Encrypted volumes of VM {} cannot be opened: {}", vm.meta.name, e),
+        }
+
         // Boot disk
         if let Some(boot_disk_id) = &vm.spec.boot_disk_id {
             if let Some(vol) = volumes.iter().find(|v| v.meta.id == *boot_disk_id) {
@@ -101,9 +332,9 @@ impl QemuLauncher {
                     args.extend([
                         "-drive".to_string(),
                         format!(
-                            "file={},format={},if=virtio,id=boot",
+                            "file={},{},if=virtio,id=boot",
                             path,
-                            vol.spec.format
+                            drive_format(vol)
                         ),
                     ]);
                 }
@@ -120,9 +351,9 @@ impl QemuLauncher {
                 args.extend([
                     "-drive".to_string(),
                     format!(
-                        "file={},format={},if=virtio,id=disk{}{}",
+                        "file={},{},if=virtio,id=disk{}{}",
                         path,
-                        vol.spec.format,
+                        drive_format(vol),
                         idx,
                         read_only
                     ),
@@ -130,37 +361,46 @@ impl QemuLauncher {
             }
         }
 
-        // Network interfaces
-        for (idx, _net) in networks.iter().enumerate() {
-            // User-mode networking (default, works without privileges)
-            args.extend([
-                "-netdev".to_string(),
-                format!("user,id=net{},hostfwd=tcp::222{}-:22", idx, idx),
-                "-device".to_string(),
-                format!("virtio-net-pci,netdev=net{}", idx),
-            ]);
-        }
-
-        // Default network if none specified
-        if networks.is_empty() {
-            args.extend([
-                "-netdev".to_string(),
-                "user,id=net0,hostfwd=tcp::2222-:22".to_string(),
-                "-device".to_string(),
-                "virtio-net-pci,netdev=net0".to_string(),
-            ]);
+        // Network interfaces, with a default one if none specified; user-mode
+        // networking unless the network is backed by the host
+        for idx in 0..networks.len().max(1) {
+            let relay = qos_relays.get(idx);
+            let netdev = hostnet::netdev(
+                &self.config,
+                vm,
+                idx,
+                networks.get(idx),
+                relay.is_none(),
+            );
+            let mac = nic_mac(vm, idx, networks.get(idx));
+            match relay {
+                Some(relay) => args.extend(relay.qemu_args(netdev, &mac)),
+                None => args.extend([
+                    "-netdev".to_string(),
+                    netdev,
+                    "-device".to_string(),
+                    format!("virtio-net-pci,netdev=net{},mac={}", idx, mac),
+                ]),
+            }
         }
 
         // virtio-rng for entropy
         args.extend(["-device".to_string(), "virtio-rng-pci".to_string()]);
 
-        // TPM (scaffold - requires swtpm)
-        if vm.spec.enable_tpm {
-            warn!("TPM support requires swtpm - scaffold only");
-            // Would add:
-            // -chardev socket,id=chrtpm,path=/tmp/swtpm.sock
-            // -tpmdev emulator,id=tpm0,chardev=chrtpm
-            // -device tpm-tis,tpmdev=tpm0
+        // Shared folders over virtiofs or 9p
+        args.extend(shares::qemu_args(shares, vm.spec.memory_mb));
+
+        // vfio-pci devices can't be hot-plugged, so they are only added at start
+        for device in devices.iter().filter(|d| d.spec.bus == DeviceBus::Pci) {
+            match device.qemu_arg() {
+                Ok(arg) => args.extend(["-device".to_string(), arg]),
+                Err(e) => warn!("Skipping device {}: {}", device.meta.name, e),
+            }
+        }
+
+        // vTPM, reached through the daemon's proxy so it can take quotes
+        if let Some(vtpm) = vtpm {
+            args.extend(vtpm.qemu_args());
         }
 
         // Extra args from spec
@@ -208,52 +448,172 @@ impl QemuLauncher {
             .filter_map(|id| state.get_network(id).ok().flatten())
             .collect();
 
+        let devices = state.list_devices(Some(&vm.meta.id))?;
+
+        // Route the NICs through shaping relays if the VM has a QoS profile or
+        // chaos profiles may target it
+        let qos_profile = match &vm.spec.qos_profile_id {
+            Some(id) => Some(state.get_qos_profile(id)?.ok_or_else(|| Error::NotFound {
+                kind: "qos_profile".to_string(),
+                id: id.clone(),
+            })?),
+            None => None,
+        };
+        let relayed = qos_profile.is_some()
+            || self.config.network.get().chaos_relays
+            || chaos::targets_vm(state, vm)?;
+        let qos_relays = if relayed {
+            (0..networks.len().max(1)).map(NicRelay::bind).collect::<Result<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
         // Prepare QMP socket path
         let socket_dir = state.config().qmp_socket_dir();
         fs::create_dir_all(&socket_dir).await?;
         let qmp_socket = socket_dir.join(format!("{}.qmp", vm.meta.id));
 
-        // Clean up old socket if exists
+        // Clean up old sockets if they exist
         if qmp_socket.exists() {
             fs::remove_file(&qmp_socket).await?;
         }
+        for socket in [
+            state.config().serial_socket_path(&vm.meta.id),
+            state.config().guest_agent_socket_path(&vm.meta.id),
+        ] {
+            if socket.exists() {
+                fs::remove_file(&socket).await?;
+            }
+        }
+
+        // Prepare serial console log
+        let console_log = state.config().console_log_path(&vm.meta.id);
+        if let Some(parent) = console_log.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // QEMU's own output goes to a file rather than a pipe, so it can
+        // outlive the daemon and be adopted by the next one
+        let qemu_log = std::fs::File::create(state.config().qemu_log_path(&vm.meta.id))?;
 
         // Allocate VNC display (simple increment)
         let vnc_display = self.allocate_vnc_display(state)?;
 
+        // swtpm has to be listening before QEMU connects to it
+        let vtpm = if vm.spec.enable_tpm {
+            if !is_swtpm_available() {
+                return Err(Error::InvalidConfig(
+                    "enable_tpm requires swtpm on the host".to_string(),
+                ));
+            }
+            let config = state.config().clone();
+            let vm_id = vm.meta.id.clone();
+            let vtpm = tokio::task::spawn_blocking(move || Vtpm::start(&config, &vm_id))
+                .await
+                .map_err(|e| Error::Internal(format!("swtpm start task failed: {}", e)))??;
+            let vtpm = Arc::new(vtpm);
+            state.register_vtpm(&vm.meta.id, vtpm.clone());
+            Some(vtpm)
+        } else {
+            None
+        };
+
+        // Taps and bridges for host-backed networks
+        if let Err(e) = hostnet::prepare(state, &vm.meta.id, &networks).await {
+            hostnet::release(&vm.meta.id).await;
+            state.stop_vtpm(&vm.meta.id);
+            return Err(e);
+        }
+
+        // virtiofsd has to be listening before QEMU connects to it
+        let config = state.config().clone();
+        let share_vm = vm.clone();
+        let mut vm_shares = match tokio::task::spawn_blocking(move || shares::start(&config, &share_vm))
+            .await
+            .map_err(|e| Error::Internal(format!("virtiofsd start task failed: {}", e)))
+            .and_then(|started| started)
+        {
+            Ok(vm_shares) => vm_shares,
+            Err(e) => {
+                hostnet::release(&vm.meta.id).await;
+                state.stop_vtpm(&vm.meta.id);
+                return Err(e);
+            }
+        };
+
         // Build command
-        let args = self.build_args(vm, &volumes, &networks, &qmp_socket, vnc_display);
+        let mut args = self.build_args(
+            vm,
+            &volumes,
+            &networks,
+            &devices,
+            &qos_relays,
+            vtpm.as_deref(),
+            &vm_shares,
+            &qmp_socket,
+            &console_log,
+            vnc_display,
+        );
+
+        // Restore RAM saved by `suspend`
+        let saved_state = state.config().suspend_image_path(&vm.meta.id);
+        let restoring = saved_state.exists();
+        if restoring {
+            args.extend([
+                "-incoming".to_string(),
+                format!("file:{}", saved_state.display()),
+            ]);
+        }
 
-        debug!("QEMU command: {} {}", self.qemu_path(), args.join(" "));
+        debug!("QEMU command: {} {}", self.qemu_path(vm), args.join(" "));
 
         // Spawn QEMU process
-        let child = Command::new(self.qemu_path())
+        let child = Command::new(self.qemu_path(vm))
             .args(&args)
             .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            .stdout(Stdio::from(qemu_log.try_clone()?))
+            .stderr(Stdio::from(qemu_log))
             .spawn()
-            .map_err(|e| Error::Qemu(format!("Failed to spawn QEMU: {}", e)))?;
+            .map_err(|e| Error::Qemu(format!("Failed to spawn QEMU: {}", e)))
+            .inspect_err(|_| {
+                state.stop_vtpm(&vm.meta.id);
+                shares::stop(&mut vm_shares);
+            })?;
+        shares::detach(vm_shares);
 
         let pid = child.id();
         info!("QEMU started with PID {}", pid);
 
         // Wait for QMP socket
-        let qmp = wait_for_qmp(&qmp_socket, 30).await?;
+        let qmp = wait_for_qmp(&qmp_socket, 30)
+            .await
+            .inspect_err(|_| state.stop_vtpm(&vm.meta.id))?;
         
         // Query version to confirm it's working
         let version = qmp.query_version().await?;
         info!("Connected to QEMU {}", version);
 
+        if restoring {
+            if let Err(e) = wait_for_restore(&qmp).await {
+                let _ = kill(Pid::from_raw(pid as i32), Signal::SIGKILL);
+                return Err(Error::Qemu(format!(
+                    "Restoring suspended state failed (stop the VM to discard it): {}",
+                    e
+                )));
+            }
+            let _ = fs::remove_file(&saved_state).await;
+        }
+
         let process = VmProcess {
             vm_id: vm.meta.id.clone(),
             pid,
             qmp_socket: qmp_socket.to_string_lossy().to_string(),
-            vnc_port: Some(self.config.qemu.vnc_base_port + vnc_display),
+            vnc_port: Some(self.config.qemu.get().vnc_base_port + vnc_display),
             started_at: chrono::Utc::now().timestamp(),
+            live: initial_live(vm, &volumes, self.config.qemu.get().hotplug_slots),
         };
 
-        // Update VM status
+        // Update VM status; the PID and socket let a restarted daemon adopt it
         let status = VmStatus {
             state: VmState::Running,
             qemu_pid: Some(pid),
@@ -261,69 +621,443 @@ impl QemuLauncher {
             vnc_display: Some(format!(":{}", vnc_display)),
             error_message: None,
             uptime_seconds: 0,
+            current_snapshot_id: vm.status.current_snapshot_id.clone(),
+            started_at: Some(process.started_at),
         };
         state.update_vm_status(&vm.meta.id, status)?;
         state.register_vm_process(process.clone());
+        if relayed {
+            match QosLink::start(qos_profile.as_ref(), qos_relays) {
+                Ok(link) => {
+                    state.register_qos_link(&vm.meta.id, link);
+                    if let Some(profile) = &qos_profile {
+                        state.emit_vm_event(&vm.meta.id, format!("QoS profile {} applied", profile.meta.name));
+                    }
+                    if let Err(e) = chaos::apply(state) {
+                        warn!("Failed to apply chaos profiles to {}: {}", vm.meta.name, e);
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to start QoS relays for {}: {}", vm.meta.name, e);
+                    state.emit_vm_event(&vm.meta.id, format!("QoS relays failed, network is down: {}", e));
+                }
+            }
+        }
+        for device in devices.iter().filter(|d| d.spec.bus == DeviceBus::Pci) {
+            state.mark_device_attached(&device.meta.id, pid);
+        }
+        // A restored guest booted under a previous proxy, so its event log is gone
+        if let (Some(vtpm), false) = (vtpm, restoring) {
+            tokio::spawn(record_boot_quote(state.clone(), vm.meta.id.clone(), vtpm));
+        }
+        if restoring {
+            state.emit_vm_event(
+                &vm.meta.id,
+                format!("VM resumed from disk (QEMU {}, PID {})", version, pid),
+            );
+        } else {
+            state.emit_vm_event(&vm.meta.id, format!("VM started (QEMU {}, PID {})", version, pid));
+        }
 
         Ok(process)
     }
 
+    /// Take over a VM's QEMU process left running by a previous daemon
+    ///
+    /// The recorded PID must be alive and the VM's QMP socket must answer, so
+    /// a recycled PID is never mistaken for the VM. Returns None if QEMU is
+    /// gone. Hot-plugged resources are assumed to match the spec.
+    pub async fn adopt(&self, state: &StateManager, vm: &Vm) -> Result<Option<VmProcess>> {
+        let (pid, qmp_socket) = match (vm.status.qemu_pid, &vm.status.qmp_socket) {
+            (Some(pid), Some(socket)) => (pid, socket.clone()),
+            _ => return Ok(None),
+        };
+        if !process_alive(pid) {
+            return Ok(None);
+        }
+
+        let qmp = QmpClient::new(&qmp_socket);
+        let version = match tokio::time::timeout(ADOPT_QMP_TIMEOUT, async {
+            qmp.connect().await?;
+            qmp.query_version().await
+        })
+        .await
+        {
+            Ok(Ok(version)) => version,
+            Ok(Err(e)) => {
+                warn!("QMP socket of VM {} does not answer: {}", vm.meta.name, e);
+                return Ok(None);
+            }
+            Err(_) => {
+                warn!("QMP socket of VM {} timed out", vm.meta.name);
+                return Ok(None);
+            }
+        };
+
+        let volumes: Vec<Volume> = vm
+            .spec
+            .volume_ids
+            .iter()
+            .filter_map(|id| state.get_volume(id).ok().flatten())
+            .collect();
+        let vnc_port = vm
+            .status
+            .vnc_display
+            .as_deref()
+            .and_then(|d| d.trim_start_matches(':').parse::<u16>().ok())
+            .map(|display| self.config.qemu.get().vnc_base_port + display);
+        let started_at = vm.status.started_at.unwrap_or_else(|| {
+            chrono::Utc::now().timestamp() - vm.status.uptime_seconds as i64
+        });
+
+        let process = VmProcess {
+            vm_id: vm.meta.id.clone(),
+            pid,
+            qmp_socket,
+            vnc_port,
+            started_at,
+            live: initial_live(vm, &volumes, self.config.qemu.get().hotplug_slots),
+        };
+        state.register_vm_process(process.clone());
+
+        // Forwards and devices installed in this process are still there
+        for forward in state.list_port_forwards(Some(&vm.meta.id))? {
+            if forward.status.active {
+                state.mark_port_forward_applied(&forward.meta.id, pid);
+            }
+        }
+        for device in state.list_devices(Some(&vm.meta.id))? {
+            if device.status.attached {
+                state.mark_device_attached(&device.meta.id, pid);
+            }
+        }
+
+        state.emit_vm_event(&vm.meta.id, format!("Adopted running QEMU {} (PID {})", version, pid));
+        if vm.spec.qos_profile_id.is_some() {
+            // The shaping relays lived in the previous daemon
+            state.emit_vm_event(
+                &vm.meta.id,
+                "QoS relays were lost with the previous daemon; restart the VM to restore its network",
+            );
+        }
+        if vm.spec.enable_tpm {
+            // The vTPM proxy lived in the previous daemon
+            state.emit_vm_event(
+                &vm.meta.id,
+                "vTPM proxy was lost with the previous daemon; restart the VM to restore its TPM",
+            );
+        }
+
+        Ok(Some(process))
+    }
+
+    /// Shut down QEMU processes nobody tracks and remove stale QMP sockets
+    ///
+    /// Covers VMs deleted while the daemon was down and QEMU launched by a
+    /// daemon that died before recording its PID.
+    pub async fn shutdown_orphans(&self, state: &StateManager, adopted: &HashSet<String>) -> Result<()> {
+        let socket_dir = state.config().qmp_socket_dir();
+        let mut entries = match fs::read_dir(&socket_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("qmp") {
+                continue;
+            }
+            let socket = path.to_string_lossy().to_string();
+            if adopted.contains(&socket) {
+                continue;
+            }
+
+            let qmp = QmpClient::new(&socket);
+            let answered = tokio::time::timeout(ADOPT_QMP_TIMEOUT, qmp.connect())
+                .await
+                .is_ok_and(|r| r.is_ok());
+            if answered {
+                let vm_id = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                warn!("Shutting down orphaned QEMU for VM {}", vm_id);
+                let _ = qmp.quit().await;
+                if state.get_vm(&vm_id)?.is_some() {
+                    state.emit_vm_event(&vm_id, "Shut down QEMU the previous daemon lost track of");
+                }
+            }
+            let _ = fs::remove_file(&path).await;
+        }
+
+        Ok(())
+    }
+
     /// Stop a VM
-    pub async fn stop(&self, state: &StateManager, vm_id: &str, force: bool) -> Result<()> {
-        info!("Stopping VM: {}", vm_id);
+    ///
+    /// In ACPI mode the guest gets `timeout` (the configured stop timeout if
+    /// None) to power off before QEMU is sent SIGTERM; hard mode sends
+    /// SIGTERM right away. QEMU still running after SIGTERM is killed.
+    pub async fn stop(
+        &self,
+        state: &StateManager,
+        vm_id: &str,
+        mode: StopMode,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        info!("Stopping VM: {} ({:?})", vm_id, mode);
 
         if let Some(process) = state.get_vm_process(vm_id) {
-            // Try graceful shutdown via QMP
-            if !force {
-                let qmp = QmpClient::new(&process.qmp_socket);
-                if qmp.connect().await.is_ok() {
-                    if let Err(e) = qmp.system_powerdown().await {
-                        warn!("Graceful shutdown failed: {}", e);
-                    } else {
-                        // Wait for graceful shutdown
-                        for _ in 0..30 {
-                            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                            if !self.is_process_running(process.pid) {
-                                break;
-                            }
-                        }
+            let timeout =
+                timeout.unwrap_or(Duration::from_secs(self.config.qemu.get().stop_timeout_secs));
+            if mode == StopMode::Acpi && !self.acpi_powerdown(state, vm_id, &process, timeout).await? {
+                state.emit_vm_event(
+                    vm_id,
+                    format!("Guest did not power off within {}s, terminating QEMU", timeout.as_secs()),
+                );
+            }
+
+            // SIGTERM lets QEMU flush disk caches; the guest's are lost either way
+            if self.is_process_running(process.pid) {
+                info!("Terminating QEMU process {}", process.pid);
+                let _ = kill(Pid::from_raw(process.pid as i32), Signal::SIGTERM);
+                for _ in 0..TERM_GRACE_SECS {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    if !self.is_process_running(process.pid) {
+                        break;
                     }
                 }
             }
 
-            // Force kill if still running
-            if self.is_process_running(process.pid) {
-                info!("Force killing QEMU process {}", process.pid);
-                let _ = kill(Pid::from_raw(process.pid as i32), Signal::SIGKILL);
+            self.cleanup_process(state, vm_id, &process).await;
+        }
+
+        // Powering off discards a suspended session
+        if self.discard_suspended(state, vm_id).await? {
+            state.emit_vm_event(vm_id, "Discarded suspended state");
+        }
+
+        self.set_powered_off(state, vm_id, VmState::Stopped)?;
+        state.emit_vm_event(vm_id, "VM stopped");
+
+        Ok(())
+    }
+
+    /// Press the ACPI power button; true if QEMU exited within `timeout`
+    async fn acpi_powerdown(
+        &self,
+        state: &StateManager,
+        vm_id: &str,
+        process: &VmProcess,
+        timeout: Duration,
+    ) -> Result<bool> {
+        let qmp = QmpClient::new(&process.qmp_socket);
+        if let Err(e) = qmp.connect().await {
+            warn!("Graceful shutdown failed: {}", e);
+            return Ok(false);
+        }
+
+        // A paused guest cannot react to the power button
+        let paused = state
+            .get_vm(vm_id)?
+            .is_some_and(|vm| vm.status.state == VmState::Paused);
+        if paused {
+            let _ = qmp.cont().await;
+        }
+
+        if let Err(e) = qmp.system_powerdown().await {
+            warn!("Graceful shutdown failed: {}", e);
+            return Ok(false);
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            if !self.is_process_running(process.pid) {
+                return Ok(true);
             }
+        }
+        Ok(false)
+    }
+
+    /// Pause a running VM's vCPUs, keeping QEMU and guest RAM resident
+    pub async fn pause(&self, state: &StateManager, vm: &Vm) -> Result<()> {
+        let process = self.process_in_state(state, vm, VmState::Running, VmState::Paused)?;
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await?;
+        qmp.stop().await?;
+
+        let status = VmStatus {
+            state: VmState::Paused,
+            ..vm.status.clone()
+        };
+        state.update_vm_status(&vm.meta.id, status)?;
+        state.emit_vm_event(&vm.meta.id, "VM paused");
+
+        Ok(())
+    }
+
+    /// Resume a paused VM, or restore a suspended one from its saved RAM
+    pub async fn resume(&self, state: &StateManager, vm: &Vm) -> Result<()> {
+        if vm.status.state == VmState::Suspended {
+            let status = VmStatus {
+                state: VmState::Running,
+                ..vm.status.clone()
+            };
+            state.update_vm_status(&vm.meta.id, status.clone())?;
+
+            let vm = Vm {
+                status,
+                ..vm.clone()
+            };
+            self.start(state, &vm).await?;
+            return Ok(());
+        }
+
+        let process = self.process_in_state(state, vm, VmState::Paused, VmState::Running)?;
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await?;
+        qmp.cont().await?;
+
+        let status = VmStatus {
+            state: VmState::Running,
+            ..vm.status.clone()
+        };
+        state.update_vm_status(&vm.meta.id, status)?;
+        state.emit_vm_event(&vm.meta.id, "VM resumed");
 
-            // Clean up
-            state.remove_vm_process(vm_id);
+        Ok(())
+    }
+
+    /// Save a running or paused VM's RAM to disk and power it off
+    ///
+    /// The next start or resume restores the guest where it left off.
+    pub async fn suspend(&self, state: &StateManager, vm: &Vm) -> Result<()> {
+        let from = if vm.status.state == VmState::Paused {
+            VmState::Paused
+        } else {
+            VmState::Running
+        };
+        let process = self.process_in_state(state, vm, from, VmState::Suspended)?;
+
+        // The restored QEMU is launched from the spec, so its devices must match
+        if !hotplug::matches_boot_config(vm, &process.live) {
+            return Err(Error::InvalidConfig(
+                "VM has hot-plugged or pending changes; restart it before suspending".to_string(),
+            ));
+        }
+        // vhost-user-fs devices can't be migrated, so QEMU can't save their state
+        if shares::uses_virtiofs(&self.config, vm) {
+            return Err(Error::InvalidConfig(
+                "VMs with virtiofs shared folders can't be suspended".to_string(),
+            ));
+        }
+
+        let path = state.config().suspend_image_path(&vm.meta.id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await?;
+        qmp.stop().await?;
 
-            // Clean up QMP socket
-            let socket_path = PathBuf::from(&process.qmp_socket);
-            if socket_path.exists() {
-                let _ = fs::remove_file(&socket_path).await;
+        if let Err(e) = save_state(&qmp, &path).await {
+            let _ = fs::remove_file(&path).await;
+            if vm.status.state == VmState::Running {
+                let _ = qmp.cont().await;
             }
+            return Err(e);
         }
 
-        // Update status
+        info!("Saved state of VM {} to {}", vm.meta.name, path.display());
+        let _ = qmp.quit().await;
+        for _ in 0..SUSPEND_EXIT_SECS {
+            if !self.is_process_running(process.pid) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        self.cleanup_process(state, &vm.meta.id, &process).await;
+
+        self.set_powered_off(state, &vm.meta.id, VmState::Suspended)?;
+        state.emit_vm_event(&vm.meta.id, "VM suspended to disk");
+
+        Ok(())
+    }
+
+    /// Delete a VM's saved RAM, returning whether there was any
+    pub async fn discard_suspended(&self, state: &StateManager, vm_id: &str) -> Result<bool> {
+        let path = state.config().suspend_image_path(vm_id);
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        fs::remove_file(&path).await?;
+        info!("Discarded suspended state of VM {}", vm_id);
+        Ok(true)
+    }
+
+    /// The VM's process, if the VM is in `from` and may move to `to`
+    fn process_in_state(
+        &self,
+        state: &StateManager,
+        vm: &Vm,
+        from: VmState,
+        to: VmState,
+    ) -> Result<VmProcess> {
+        let invalid = || Error::InvalidStateTransition {
+            from: vm.status.state.to_string(),
+            to: to.to_string(),
+        };
+
+        if vm.status.state != from {
+            return Err(invalid());
+        }
+        state.get_vm_process(&vm.meta.id).ok_or_else(invalid)
+    }
+
+    /// Kill QEMU if it is still running and forget the process
+    async fn cleanup_process(&self, state: &StateManager, vm_id: &str, process: &VmProcess) {
+        // Force kill if still running
+        if self.is_process_running(process.pid) {
+            info!("Force killing QEMU process {}", process.pid);
+            let _ = kill(Pid::from_raw(process.pid as i32), Signal::SIGKILL);
+            state.emit_vm_event(vm_id, "QEMU process force killed");
+        }
+
+        // Clean up
+        state.remove_vm_process(vm_id);
+        hostnet::release(vm_id).await;
+
+        // Clean up QMP socket
+        let socket_path = PathBuf::from(&process.qmp_socket);
+        if socket_path.exists() {
+            let _ = fs::remove_file(&socket_path).await;
+        }
+    }
+
+    /// Record that the VM no longer has a QEMU process
+    fn set_powered_off(&self, state: &StateManager, vm_id: &str, vm_state: VmState) -> Result<()> {
+        let current_snapshot_id = state
+            .get_vm(vm_id)?
+            .and_then(|vm| vm.status.current_snapshot_id);
         let status = VmStatus {
-            state: VmState::Stopped,
+            state: vm_state,
             qemu_pid: None,
             qmp_socket: None,
             vnc_display: None,
             error_message: None,
             uptime_seconds: 0,
+            current_snapshot_id,
+            started_at: None,
         };
-        state.update_vm_status(vm_id, status)?;
-
-        Ok(())
+        state.update_vm_status(vm_id, status)
     }
 
     /// Check if a process is running
     fn is_process_running(&self, pid: u32) -> bool {
-        kill(Pid::from_raw(pid as i32), None).is_ok()
+        process_alive(pid)
     }
 
     /// Allocate a VNC display number
@@ -331,7 +1065,7 @@ impl QemuLauncher {
         let used: std::collections::HashSet<u16> = state
             .list_vm_processes()
             .iter()
-            .filter_map(|p| p.vnc_port.map(|port| port - self.config.qemu.vnc_base_port))
+            .filter_map(|p| p.vnc_port.map(|port| port - self.config.qemu.get().vnc_base_port))
             .collect();
 
         for display in 0..100 {
@@ -411,6 +1145,92 @@ impl QemuLauncher {
         Ok(())
     }
 
+    /// Install a port forward in the running VM's user-mode network
+    pub async fn add_port_forward(&self, state: &StateManager, forward: &PortForward) -> Result<u32> {
+        let process = state
+            .get_vm_process(&forward.spec.vm_id)
+            .ok_or_else(|| Error::Qemu("VM not running".to_string()))?;
+        let netdev = netdev_id(state, &forward.spec)?;
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await?;
+
+        qmp.hostfwd_add(&netdev, &forward.spec.hostfwd_rule()).await?;
+
+        info!(
+            "Forwarding {}:{} -> {}:{} ({})",
+            forward.spec.host_addr,
+            forward.spec.host_port,
+            forward.spec.vm_id,
+            forward.spec.guest_port,
+            forward.spec.protocol.as_str()
+        );
+        Ok(process.pid)
+    }
+
+    /// Remove a port forward from the running VM's user-mode network
+    pub async fn remove_port_forward(&self, state: &StateManager, forward: &PortForward) -> Result<()> {
+        let process = state
+            .get_vm_process(&forward.spec.vm_id)
+            .ok_or_else(|| Error::Qemu("VM not running".to_string()))?;
+        let netdev = netdev_id(state, &forward.spec)?;
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await?;
+
+        let rule = format!(
+            "{}:{}:{}",
+            forward.spec.protocol.as_str(),
+            forward.spec.host_addr,
+            forward.spec.host_port
+        );
+        qmp.hostfwd_remove(&netdev, &rule).await
+    }
+
+    /// Hot-attach a USB device to the running VM
+    pub async fn attach_device(&self, state: &StateManager, device: &Device) -> Result<u32> {
+        if device.spec.bus != DeviceBus::Usb {
+            return Err(Error::InvalidConfig(
+                "Only USB devices can be attached to a running VM".to_string(),
+            ));
+        }
+        let process = state
+            .get_vm_process(&device.spec.vm_id)
+            .ok_or_else(|| Error::Qemu("VM not running".to_string()))?;
+
+        let mut args = serde_json::json!({
+            "driver": device.spec.qemu_driver(),
+            "id": device.qemu_id(),
+            "bus": format!("{}.0", USB_CONTROLLER_ID),
+        });
+        for (key, value) in device.spec.qemu_properties()? {
+            args[key] = value;
+        }
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await?;
+        qmp.device_add(args).await?;
+
+        info!(
+            "Attached {} to {} as {}",
+            device.spec.describe(),
+            device.spec.vm_id,
+            device.qemu_id()
+        );
+        Ok(process.pid)
+    }
+
+    /// Detach a USB device from the running VM
+    pub async fn detach_device(&self, state: &StateManager, device: &Device) -> Result<()> {
+        let process = state
+            .get_vm_process(&device.spec.vm_id)
+            .ok_or_else(|| Error::Qemu("VM not running".to_string()))?;
+
+        let qmp = QmpClient::new(&process.qmp_socket);
+        qmp.connect().await?;
+        qmp.device_del(&device.qemu_id()).await
+    }
+
     /// Get VM status via QMP
     pub async fn query_status(&self, state: &StateManager, vm_id: &str) -> Result<VmState> {
         let process = state
@@ -451,44 +1271,286 @@ impl QemuLauncher {
     }
 }
 
+/// Migrate the paused VM's state into `path`
+async fn save_state(qmp: &QmpClient, path: &Path) -> Result<()> {
+    qmp.migrate(&format!("file:{}", path.display())).await?;
+
+    loop {
+        tokio::time::sleep(MIGRATE_POLL_INTERVAL).await;
+        let info = qmp.query_migrate().await?;
+        match info.status.as_deref() {
+            Some("completed") => return Ok(()),
+            Some(status @ ("failed" | "cancelled")) => {
+                return Err(Error::Qemu(format!(
+                    "Saving VM state {}: {}",
+                    status,
+                    info.error_desc.unwrap_or_else(|| "no details".to_string())
+                )));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Wait for QEMU started with `-incoming` to load the saved state and run
+async fn wait_for_restore(qmp: &QmpClient) -> Result<()> {
+    loop {
+        let status = qmp.query_status().await?;
+        if status.running {
+            return Ok(());
+        }
+        if status.status != "inmigrate" {
+            return Err(Error::Qemu(format!(
+                "VM is {} after loading state",
+                status.status
+            )));
+        }
+        tokio::time::sleep(MIGRATE_POLL_INTERVAL).await;
+    }
+}
+
+/// Record the boot quote of a VM's vTPM once firmware and kernel have measured
+async fn record_boot_quote(state: StateManager, vm_id: String, vtpm: Arc<Vtpm>) {
+    tokio::time::sleep(vtpm::BOOT_QUOTE_DELAY).await;
+    for attempt in 1..=vtpm::BOOT_QUOTE_ATTEMPTS {
+        // Give up once the VM stopped or was restarted with another vTPM
+        if !state.get_vtpm(&vm_id).is_some_and(|v| Arc::ptr_eq(&v, &vtpm)) {
+            return;
+        }
+        match vtpm.quote("boot", random_nonce(), default_pcrs()).await {
+            Ok(quote) => {
+                match vtpm::save_quote(state.config(), &quote) {
+                    Ok(()) => state.emit_vm_event(
+                        &vm_id,
+                        format!("Recorded vTPM boot quote over {} PCRs", quote.pcrs.len()),
+                    ),
+                    Err(e) => warn!("Failed to save boot quote of {}: {}", vm_id, e),
+                }
+                return;
+            }
+            Err(e) => debug!("Boot quote attempt {} for {} failed: {}", attempt, vm_id, e),
+        }
+        tokio::time::sleep(vtpm::BOOT_QUOTE_DELAY / 2).await;
+    }
+    state.emit_vm_event(&vm_id, "No vTPM boot quote recorded: the guest never started its TPM");
+}
+
+/// Run `qemu-img` for volume preparation
+async fn qemu_img(args: &[&str]) -> Result<()> {
+    let output = tokio::process::Command::new("qemu-img")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| Error::VolumeError(format!("qemu-img failed: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::VolumeError(format!(
+            "qemu-img {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+async fn remove_dir_if_exists(dir: &Path) -> Result<()> {
+    match fs::remove_dir_all(dir).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Format options for a `-drive` argument
+/// Build inputs of an image imported as it is from its source
+fn import_inputs(spec: &VolumeSpec, digest: &str) -> BuildInputs {
+    let uri = if spec.source.contains("://") {
+        spec.source.clone()
+    } else {
+        format!("file://{}", spec.source)
+    };
+    BuildInputs {
+        build_type: slsa::BUILD_TYPE_IMPORT.to_string(),
+        parameters: serde_json::json!({
+            "source": spec.source,
+            "format": spec.format,
+            "overlay": spec.overlay,
+        }),
+        dependencies: vec![ResourceDescriptor::new("source", uri, Some(digest))],
+        finished_at: Some(chrono::Utc::now().timestamp()),
+        ..Default::default()
+    }
+}
+
+fn drive_format(vol: &Volume) -> String {
+    if vol.spec.kind == VolumeKind::Device {
+        // Raw host device: bypass the host page cache and hold QEMU's image lock
+        return "format=raw,cache=none,file.locking=on".to_string();
+    }
+
+    let mut options = format!("format={}", vol.spec.format);
+    if let (Some(enc), Some(path)) = (&vol.spec.encryption, &vol.status.local_path) {
+        match encryption::key_options(Path::new(path), enc) {
+            Ok(keys) => {
+                options.push(',');
+                options.push_str(&keys);
+            }
+            Err(e) => warn!("Cannot read backing chain of volume {}: {}", vol.meta.name, e),
+        }
+    }
+    options
+}
+
+/// QEMU netdev ID a port forward attaches to
+///
+/// Mirrors the NIC ordering in [`QemuLauncher::build_args`]: one netdev per
+/// existing network in `network_ids`, or a single default `net0`.
+pub fn netdev_id(state: &StateManager, spec: &PortForwardSpec) -> Result<String> {
+    let vm = state
+        .get_vm(&spec.vm_id)?
+        .ok_or_else(|| Error::NotFound {
+            kind: "vm".to_string(),
+            id: spec.vm_id.clone(),
+        })?;
+
+    let networks: Vec<Network> = vm
+        .spec
+        .network_ids
+        .iter()
+        .filter_map(|id| state.get_network(id).ok().flatten())
+        .collect();
+
+    let idx = match &spec.network_id {
+        Some(network_id) => networks
+            .iter()
+            .position(|n| &n.meta.id == network_id)
+            .ok_or_else(|| {
+                Error::InvalidConfig(format!(
+                    "Network {} is not attached to VM {}",
+                    network_id, spec.vm_id
+                ))
+            })?,
+        None => 0,
+    };
+
+    if let Some(network) = networks.get(idx) {
+        if network.spec.mode != NetworkMode::User {
+            return Err(Error::InvalidConfig(format!(
+                "Port forwarding requires a user-mode network, {} is {:?}",
+                network.meta.name, network.spec.mode
+            )));
+        }
+    }
+
+    Ok(format!("net{}", idx))
+}
+
+/// Check that a device can be passed through to `vm` on this host
+pub fn check_device(vm: &Vm, spec: &DeviceSpec) -> Result<()> {
+    spec.qemu_properties()?;
+
+    match spec.bus {
+        DeviceBus::Usb => {
+            if vm.spec.compatibility_mode {
+                return Err(Error::InvalidConfig(
+                    "USB passthrough requires the virt machine, not compatibility mode".to_string(),
+                ));
+            }
+        }
+        DeviceBus::Pci => {
+            if !cfg!(target_os = "linux") {
+                return Err(Error::InvalidConfig(
+                    "PCI passthrough requires vfio on a Linux host".to_string(),
+                ));
+            }
+            let addr = spec.host_address.as_deref().unwrap_or_default();
+            let sysfs = Path::new("/sys/bus/pci/devices").join(addr);
+            if !sysfs.exists() {
+                return Err(Error::NotFound {
+                    kind: "pci device".to_string(),
+                    id: addr.to_string(),
+                });
+            }
+            let driver = std::fs::read_link(sysfs.join("driver"))
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()));
+            if driver.as_deref() != Some("vfio-pci") {
+                return Err(Error::InvalidConfig(format!(
+                    "PCI device {} is bound to {}, bind it to vfio-pci first",
+                    addr,
+                    driver.as_deref().unwrap_or("no driver")
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Source of a volume copied from another volume: `volume://<id>`, with
+/// `?compress` for a compressed copy
+pub const VOLUME_COPY_SCHEME: &str = "volume://";
+
+/// Source of a blank volume in a RAM-backed directory
+pub const TMPFS_SCHEME: &str = "tmpfs://";
+
+/// Source of a blank volume created on an NFS export
+pub const NFS_SCHEME: &str = "nfs://";
+
 /// Volume preparer - handles volume setup
 pub struct VolumePreparer {
     config: DaemonConfig,
+    downloader: Downloader,
 }
 
 impl VolumePreparer {
     pub fn new(config: DaemonConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            downloader: Downloader::new(),
+        }
     }
 
     /// Prepare a volume for use
     pub async fn prepare(&self, state: &StateManager, volume: &Volume) -> Result<PathBuf> {
-        let vol_dir = self.config.store_path.join("volumes").join(&volume.meta.id);
+        if volume.spec.kind == VolumeKind::Device {
+            return self.prepare_device(state, volume, false).await;
+        }
+
+        let vol_dir = self.config.volume_dir(&volume.meta.id);
         fs::create_dir_all(&vol_dir).await?;
 
-        let local_path = if volume.spec.source.starts_with("oci://") {
-            // OCI registry pull (stub)
-            self.pull_oci(&volume.spec.source, &vol_dir).await?
-        } else if volume.spec.source.starts_with("http://") || volume.spec.source.starts_with("https://") {
-            // HTTP download
-            self.download_http(&volume.spec.source, &vol_dir).await?
-        } else {
-            // Local file
-            let src = PathBuf::from(&volume.spec.source);
-            if !src.exists() {
-                return Err(Error::VolumeError(format!(
-                    "Source file not found: {}",
-                    volume.spec.source
-                )));
-            }
+        if volume.spec.is_remote() {
+            return self.prepare_remote(state, volume, &vol_dir).await;
+        }
+        if volume.spec.source.starts_with(OCI_SCHEME) {
+            return self.prepare_oci(state, volume, &vol_dir).await;
+        }
+        if let Some(source) = volume.spec.source.strip_prefix(VOLUME_COPY_SCHEME) {
+            return self.prepare_copy(state, volume, source, &vol_dir).await;
+        }
+        if volume.spec.source.is_empty()
+            || volume.spec.source.starts_with(TMPFS_SCHEME)
+            || volume.spec.source.starts_with(NFS_SCHEME)
+        {
+            return self.prepare_blank(state, volume, &vol_dir).await;
+        }
 
-            // If overlay requested, create qcow2 backing
-            if volume.spec.overlay {
-                self.create_overlay(&src, &vol_dir).await?
-            } else {
-                // Just use source directly or copy
-                src
-            }
+        // Local file
+        let src = PathBuf::from(&volume.spec.source);
+        if !src.exists() {
+            return Err(Error::VolumeError(format!(
+                "Source file not found: {}",
+                volume.spec.source
+            )));
+        }
+
+        // If overlay requested, create qcow2 backing
+        let local_path = if volume.spec.overlay {
+            self.create_overlay(&src, &vol_dir).await?
+        } else {
+            // Just use source directly or copy
+            src
         };
 
         // Verify integrity if configured
@@ -499,6 +1561,13 @@ impl VolumePreparer {
         // Compute digest
         let digest = infrasim_common::ContentAddressedStore::hash_file(&local_path).await?;
 
+        // Provenance is about the source image, not the overlay above it
+        let source_digest = if volume.spec.overlay {
+            infrasim_common::ContentAddressedStore::hash_file(&volume.spec.source).await?
+        } else {
+            digest.clone()
+        };
+
         // Update status
         let status = VolumeStatus {
             ready: true,
@@ -506,24 +1575,418 @@ impl VolumePreparer {
             digest: Some(digest),
             actual_size: fs::metadata(&local_path).await?.len(),
             verified: !volume.spec.integrity.scheme.is_empty(),
+            provenance: self.attest(state, volume, &source_digest).await,
+            ..Default::default()
+        };
+        state.update_volume_status(&volume.meta.id, status)?;
+
+        Ok(local_path)
+    }
+
+    /// Prepare a raw host device volume
+    ///
+    /// Devices are never hashed or copied. Unless `force` is set, a device
+    /// that is mounted or locked by another process is refused.
+    pub async fn prepare_device(
+        &self,
+        state: &StateManager,
+        volume: &Volume,
+        force: bool,
+    ) -> Result<PathBuf> {
+        let path = PathBuf::from(&volume.spec.source);
+        let info = inspect_block_device(&path)?;
+
+        if !info.is_safe() && !force {
+            let mut reasons = Vec::new();
+            if !info.mounted_at.is_empty() {
+                reasons.push(format!("mounted at {}", info.mounted_at.join(", ")));
+            }
+            if !info.lockable {
+                reasons.push("locked by another process".to_string());
+            }
+            return Err(Error::VolumeError(format!(
+                "Refusing to pass through {} ({}); use force to override",
+                info.path,
+                reasons.join(", ")
+            )));
+        }
+
+        for warning in &info.warnings {
+            warn!("{}", warning);
+        }
+
+        let status = VolumeStatus {
+            ready: true,
+            local_path: Some(info.path.clone()),
+            digest: None,
+            actual_size: info.size_bytes,
+            verified: false,
+            warnings: info.warnings,
+            smart_status: Some(info.smart.summary),
+            ..Default::default()
+        };
+        state.update_volume_status(&volume.meta.id, status)?;
+
+        Ok(path)
+    }
+
+    /// Prepare a volume from a registry blob, like an appliance disk layer
+    ///
+    /// The source must pin the blob's digest
+    /// (`oci://ghcr.io/org/appliance@sha256:...`); the blob is pulled into the
+    /// content-addressed store and verified on the way.
+    async fn prepare_oci(
+        &self,
+        state: &StateManager,
+        volume: &Volume,
+        vol_dir: &Path,
+    ) -> Result<PathBuf> {
+        let reference = OciReference::parse(&volume.spec.source)?;
+        let digest = reference.digest().ok_or_else(|| {
+            Error::VolumeError(format!(
+                "OCI source {} must name a layer by digest (@sha256:...)",
+                volume.spec.source
+            ))
+        })?;
+        let registry = RegistryClient::new(&self.config, &reference)?;
+        let object_path = state.cas().pull(digest, &registry).await?;
+
+        let local_path = if volume.spec.overlay {
+            self.create_overlay(&object_path, vol_dir).await?
+        } else {
+            object_path.clone()
+        };
+
+        let status = VolumeStatus {
+            ready: true,
+            local_path: Some(local_path.to_string_lossy().to_string()),
+            digest: Some(digest.to_string()),
+            actual_size: fs::metadata(&object_path).await?.len(),
+            verified: true,
+            provenance: self.attest(state, volume, digest).await,
+            ..Default::default()
         };
         state.update_volume_status(&volume.meta.id, status)?;
 
         Ok(local_path)
     }
 
-    /// Pull from OCI registry (stub)
-    async fn pull_oci(&self, _reference: &str, _dest: &Path) -> Result<PathBuf> {
-        Err(Error::VolumeError(
-            "OCI registry pull not implemented".to_string(),
-        ))
+    /// Prepare a volume imported from an http(s) source
+    ///
+    /// The download is verified against the mandatory sha256 digest while it
+    /// streams, so the object is not hashed again here.
+    async fn prepare_remote(
+        &self,
+        state: &StateManager,
+        volume: &Volume,
+        vol_dir: &Path,
+    ) -> Result<PathBuf> {
+        let object_path = self.downloader.fetch(state, volume).await?;
+        let digest = expected_digest(&volume.spec)?;
+
+        let local_path = if volume.spec.overlay {
+            self.create_overlay(&object_path, vol_dir).await?
+        } else {
+            object_path.clone()
+        };
+
+        // Keep the final download progress recorded by the downloader
+        let download = state
+            .get_volume(&volume.meta.id)?
+            .and_then(|v| v.status.download);
+
+        let status = VolumeStatus {
+            ready: true,
+            local_path: Some(local_path.to_string_lossy().to_string()),
+            actual_size: fs::metadata(&object_path).await?.len(),
+            verified: true,
+            download,
+            provenance: self.attest(state, volume, &digest).await,
+            digest: Some(digest),
+            ..Default::default()
+        };
+        state.update_volume_status(&volume.meta.id, status)?;
+
+        Ok(local_path)
+    }
+
+    /// Sign the SLSA provenance of a volume's source image and store it in
+    /// the CAS, returning the envelope's digest
+    ///
+    /// Volumes created without build inputs are recorded as plain imports of
+    /// their source. Failures leave the volume usable, without provenance.
+    async fn attest(&self, state: &StateManager, volume: &Volume, image_digest: &str) -> Option<String> {
+        let inputs = volume
+            .spec
+            .build
+            .clone()
+            .unwrap_or_else(|| import_inputs(&volume.spec, image_digest));
+        let subject = ResourceDescriptor::new(volume.meta.name.clone(), "", Some(image_digest));
+        let statement = Statement::new(subject, &inputs);
+
+        let stored = match Envelope::sign(&statement, state.key_pair()).and_then(|e| Ok(serde_json::to_vec(&e)?)) {
+            Ok(envelope) => state.cas().put(&envelope).await,
+            Err(e) => Err(e),
+        };
+        match stored {
+            Ok(digest) => {
+                info!("Attested image of volume {} (provenance {})", volume.meta.name, digest);
+                Some(digest)
+            }
+            Err(e) => {
+                warn!("Failed to attest image of volume {}: {}", volume.meta.name, e);
+                None
+            }
+        }
+    }
+
+    /// The image a volume was created from: the source file, or the object
+    /// a download or registry pull stored
+    pub fn source_image(&self, state: &StateManager, volume: &Volume) -> Option<PathBuf> {
+        if volume.spec.is_remote() || volume.spec.source.starts_with(OCI_SCHEME) {
+            return volume.status.digest.as_deref().map(|d| state.cas().object_path(d));
+        }
+        Some(PathBuf::from(&volume.spec.source))
+    }
+
+    /// Prepare an empty image of `size_bytes`
+    ///
+    /// The image is created in the store, under [`DaemonConfig::tmpfs_volume_dir`]
+    /// for `tmpfs://`, or at the `nfs://` URL itself. Existing images are kept,
+    /// so re-preparing a volume never wipes it.
+    async fn prepare_blank(
+        &self,
+        state: &StateManager,
+        volume: &Volume,
+        vol_dir: &Path,
+    ) -> Result<PathBuf> {
+        let file_name = format!("disk.{}", volume.spec.format);
+
+        let (target, exists) = if volume.spec.source.starts_with(NFS_SCHEME) {
+            let url = volume.spec.source.clone();
+            let exists = qemu_img(&["info", "-U", &url]).await.is_ok();
+            (url, exists)
+        } else {
+            let dir = if volume.spec.source.starts_with(TMPFS_SCHEME) {
+                self.config.tmpfs_volume_dir(&volume.meta.id)
+            } else {
+                vol_dir.to_path_buf()
+            };
+            fs::create_dir_all(&dir).await?;
+            let path = dir.join(&file_name);
+            let exists = path.exists();
+            (path.to_string_lossy().to_string(), exists)
+        };
+
+        let size = volume.spec.size_bytes.unwrap_or_default();
+        if !exists {
+            if size == 0 {
+                return Err(Error::VolumeError("Blank volumes need a size".to_string()));
+            }
+            let mut args = vec!["create".to_string(), "-f".to_string(), volume.spec.format.clone()];
+            if let Some(enc) = &volume.spec.encryption {
+                args.extend(Keyring::new(&self.config).secret_args([enc])?);
+                args.extend(["-o".to_string(), encryption::create_options(enc)]);
+            }
+            args.extend([target.clone(), size.to_string()]);
+            qemu_img(&args.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+            info!("Created {} byte {} image {}", size, volume.spec.format, target);
+        }
+
+        let actual_size = match fs::metadata(&target).await {
+            Ok(meta) => meta.len(),
+            Err(_) => size,
+        };
+        let status = VolumeStatus {
+            ready: true,
+            local_path: Some(target.clone()),
+            actual_size,
+            ..Default::default()
+        };
+        state.update_volume_status(&volume.meta.id, status)?;
+
+        Ok(PathBuf::from(target))
+    }
+
+    /// Prepare a point-in-time copy of another volume
+    ///
+    /// Fails while the source is in use by a running VM (QEMU holds its image
+    /// lock), so copies are always consistent. Copies of encrypted volumes
+    /// must be encrypted themselves and cannot be compressed.
+    async fn prepare_copy(
+        &self,
+        state: &StateManager,
+        volume: &Volume,
+        source: &str,
+        vol_dir: &Path,
+    ) -> Result<PathBuf> {
+        let (source_id, compress) = match source.split_once('?') {
+            Some((id, query)) => (id, query == "compress"),
+            None => (source, false),
+        };
+        let parent = state
+            .get_volume(source_id)?
+            .ok_or_else(|| Error::NotFound {
+                kind: "volume".to_string(),
+                id: source_id.to_string(),
+            })?;
+        let parent_path = parent.status.local_path.filter(|_| parent.status.ready).ok_or_else(|| {
+            Error::VolumeError(format!("Source volume {} is not ready", parent.meta.name))
+        })?;
+
+        let source_key = parent.spec.encryption.as_ref();
+        let target_key = volume.spec.encryption.as_ref();
+        if source_key.is_some() && target_key.is_none() {
+            return Err(Error::VolumeError(format!(
+                "Copies of encrypted volume {} must be encrypted",
+                parent.meta.name
+            )));
+        }
+        if compress && (source_key.is_some() || target_key.is_some()) {
+            return Err(Error::VolumeError(format!(
+                "Encrypted images of volume {} can't be compressed",
+                parent.meta.name
+            )));
+        }
+
+        let path = vol_dir.join(format!("disk.{}", volume.spec.format));
+        if !path.exists() {
+            let partial = vol_dir.join("copy.partial");
+            let mut args = vec!["convert".to_string()];
+            args.extend(Keyring::new(&self.config).secret_args(source_key.into_iter().chain(target_key))?);
+            args.extend(["-O".to_string(), volume.spec.format.clone()]);
+            if let Some(enc) = target_key {
+                args.extend(["-o".to_string(), encryption::create_options(enc)]);
+            }
+            if compress {
+                args.push("-c".to_string());
+            }
+            match source_key {
+                Some(enc) => args.extend([
+                    "--image-opts".to_string(),
+                    encryption::image_opts(Path::new(&parent_path), enc)?,
+                ]),
+                None => args.push(parent_path.clone()),
+            }
+            args.push(partial.to_string_lossy().to_string());
+            qemu_img(&args.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+            fs::rename(&partial, &path).await?;
+            info!("Copied volume {} into {}", parent.meta.name, volume.meta.name);
+        }
+
+        let digest = infrasim_common::ContentAddressedStore::hash_file(&path).await?;
+        let status = VolumeStatus {
+            ready: true,
+            local_path: Some(path.to_string_lossy().to_string()),
+            digest: Some(digest),
+            actual_size: fs::metadata(&path).await?.len(),
+            ..Default::default()
+        };
+        state.update_volume_status(&volume.meta.id, status)?;
+
+        Ok(path)
     }
 
-    /// Download from HTTP
-    async fn download_http(&self, _url: &str, _dest: &Path) -> Result<PathBuf> {
-        Err(Error::VolumeError(
-            "HTTP download not implemented - use local files".to_string(),
-        ))
+    /// Remove the images the daemon created for a deleted volume
+    ///
+    /// Images on NFS exports are left in place.
+    pub async fn release(&self, volume: &Volume) -> Result<()> {
+        if volume.spec.kind == VolumeKind::Device {
+            return Ok(());
+        }
+        if volume.spec.source.starts_with(TMPFS_SCHEME) {
+            remove_dir_if_exists(&self.config.tmpfs_volume_dir(&volume.meta.id)).await?;
+        }
+        remove_dir_if_exists(&self.config.volume_dir(&volume.meta.id)).await
+    }
+
+    /// Resize a volume's active image, online over QMP if a running VM has it open
+    ///
+    /// Shrinking discards data past the new end, so it needs `shrink` and a stopped VM.
+    pub async fn resize(
+        &self,
+        state: &StateManager,
+        volume: &Volume,
+        size_bytes: u64,
+        shrink: bool,
+    ) -> Result<()> {
+        if size_bytes == 0 || !size_bytes.is_multiple_of(512) {
+            return Err(Error::InvalidConfig(format!(
+                "Volume size must be a positive multiple of 512 bytes, got {}",
+                size_bytes
+            )));
+        }
+        let path = volume
+            .status
+            .local_path
+            .as_deref()
+            .filter(|_| volume.status.ready)
+            .ok_or_else(|| Error::VolumeError(format!("Volume {} is not ready", volume.meta.name)))?;
+
+        let info = crate::snapshot::image_info(Path::new(path))?;
+        let current = info["virtual-size"].as_u64().unwrap_or(0);
+        let format = info["format"].as_str().unwrap_or("qcow2").to_string();
+        if size_bytes < current && !shrink {
+            return Err(Error::InvalidConfig(format!(
+                "Shrinking volume {} from {} to {} bytes discards data; confirm with shrink",
+                volume.meta.name, current, size_bytes
+            )));
+        }
+
+        match crate::snapshot::running_user(state, volume)? {
+            Some(vm) if size_bytes < current => {
+                return Err(Error::InvalidConfig(format!(
+                    "Stop VM {} before shrinking volume {}",
+                    vm.meta.name, volume.meta.name
+                )));
+            }
+            Some(vm) if size_bytes > current => {
+                let process = state
+                    .get_vm_process(&vm.meta.id)
+                    .ok_or_else(|| Error::Qemu("VM not running".to_string()))?;
+                let qmp = QmpClient::new(&process.qmp_socket);
+                qmp.connect().await?;
+
+                let node = qmp
+                    .query_named_block_nodes()
+                    .await?
+                    .into_iter()
+                    .find(|n| n.file == path && n.drv == format)
+                    .ok_or_else(|| {
+                        Error::VolumeError(format!("{} is not open in VM {}", path, vm.meta.name))
+                    })?;
+                qmp.block_resize(&node.node_name, size_bytes).await?;
+            }
+            None if size_bytes != current => {
+                let mut args = vec!["resize".to_string()];
+                if size_bytes < current {
+                    args.push("--shrink".to_string());
+                }
+                match &volume.spec.encryption {
+                    Some(enc) => {
+                        args.extend(Keyring::new(&self.config).secret_args([enc])?);
+                        args.extend([
+                            "--image-opts".to_string(),
+                            encryption::image_opts(Path::new(path), enc)?,
+                        ]);
+                    }
+                    None => args.extend(["-f".to_string(), format.clone(), path.to_string()]),
+                }
+                args.push(size_bytes.to_string());
+                qemu_img(&args.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+            }
+            _ => {}
+        }
+
+        info!(
+            "Resized volume {} from {} to {} bytes",
+            volume.meta.name, current, size_bytes
+        );
+        let spec = VolumeSpec {
+            size_bytes: Some(size_bytes),
+            ..volume.spec.clone()
+        };
+        state.update_volume_spec(&volume.meta.id, spec)
     }
 
     /// Create qcow2 overlay
@@ -0,0 +1,226 @@
+//! Network QoS enforcement
+//!
+//! QEMU's user-mode network has no shaping of its own, so the NICs of a VM
+//! with a QoS profile are wired through the daemon instead: the guest NIC
+//! sends Ethernet frames to a UDP socket netdev, the slirp backend sits on a
+//! hub with a second socket netdev, and a relay task moves frames between the
+//! two through the profile's [`TrafficShaper`]. The slirp netdev keeps its
+//! `net<N>` ID, so port forwards work unchanged. Tap and vmnet backends of
+//! host-backed networks sit on the hub the same way.
+//!
+//! The same relays carry the faults of chaos profiles, so a VM without a QoS
+//! profile gets pass-through relays when it's targeted by one.
+
+use infrasim_common::{
+    traffic_shaper::{FaultInjection, ShapingDecision, TrafficShaper},
+    types::QosProfile,
+    Error, Result,
+};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket as StdUdpSocket};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::task::AbortHandle;
+use tracing::{debug, warn};
+
+/// Largest frame QEMU sends over a socket netdev
+const MAX_FRAME: usize = 65536;
+
+/// First QEMU hub ID used for QoS wiring, one hub per NIC
+const HUB_BASE: usize = 100;
+
+/// Daemon side of one NIC's relay, bound before QEMU starts
+pub struct NicRelay {
+    idx: usize,
+    /// Daemon socket facing the guest NIC
+    guest: StdUdpSocket,
+    /// Daemon socket facing the slirp hub
+    wan: StdUdpSocket,
+    guest_port: u16,
+    wan_port: u16,
+    /// QEMU's local ports for the two socket netdevs
+    qemu_guest_port: u16,
+    qemu_wan_port: u16,
+}
+
+impl NicRelay {
+    /// Bind the relay sockets for NIC `idx`
+    pub fn bind(idx: usize) -> Result<Self> {
+        let guest = bind_loopback()?;
+        let wan = bind_loopback()?;
+        Ok(Self {
+            idx,
+            guest_port: local_port(&guest)?,
+            wan_port: local_port(&wan)?,
+            guest,
+            wan,
+            // QEMU binds these itself, so only reserve a free port number
+            qemu_guest_port: local_port(&bind_loopback()?)?,
+            qemu_wan_port: local_port(&bind_loopback()?)?,
+        })
+    }
+
+    /// QEMU arguments for NIC `idx`, given its backend `-netdev` value and MAC
+    ///
+    /// The backend netdev must use the ID `net<idx>`.
+    pub fn qemu_args(&self, backend: String, mac: &str) -> Vec<String> {
+        let idx = self.idx;
+        let hub = HUB_BASE + idx;
+        vec![
+            "-netdev".to_string(),
+            backend,
+            "-netdev".to_string(),
+            format!("hubport,id=qoshub{}a,hubid={},netdev=net{}", idx, hub, idx),
+            "-netdev".to_string(),
+            format!(
+                "socket,id=qoswan{},udp=127.0.0.1:{},localaddr=127.0.0.1:{}",
+                idx, self.wan_port, self.qemu_wan_port
+            ),
+            "-netdev".to_string(),
+            format!("hubport,id=qoshub{}b,hubid={},netdev=qoswan{}", idx, hub, idx),
+            "-netdev".to_string(),
+            format!(
+                "socket,id=qosnic{},udp=127.0.0.1:{},localaddr=127.0.0.1:{}",
+                idx, self.guest_port, self.qemu_guest_port
+            ),
+            "-device".to_string(),
+            format!("virtio-net-pci,netdev=qosnic{},mac={}", idx, mac),
+        ]
+    }
+}
+
+/// Running QoS relays of one VM
+pub struct QosLink {
+    /// Profile the shapers are configured with (None = pass-through)
+    pub profile_id: Option<String>,
+    shapers: Vec<TrafficShaper>,
+    tasks: Vec<AbortHandle>,
+}
+
+impl QosLink {
+    /// Start relaying frames for each NIC, shaping both directions
+    pub fn start(profile: Option<&QosProfile>, relays: Vec<NicRelay>) -> Result<Self> {
+        let spec = profile.map(|p| p.spec.clone()).unwrap_or_default();
+        let mut link = Self {
+            profile_id: profile.map(|p| p.meta.id.clone()),
+            shapers: Vec::new(),
+            tasks: Vec::new(),
+        };
+
+        for relay in relays {
+            let guest = Arc::new(into_tokio(relay.guest)?);
+            let wan = Arc::new(into_tokio(relay.wan)?);
+            let qemu_guest = SocketAddr::from((Ipv4Addr::LOCALHOST, relay.qemu_guest_port));
+            let qemu_wan = SocketAddr::from((Ipv4Addr::LOCALHOST, relay.qemu_wan_port));
+
+            let egress = TrafficShaper::new(spec.clone());
+            let ingress = TrafficShaper::new(spec.clone());
+
+            link.tasks.push(
+                tokio::spawn(forward(guest.clone(), wan.clone(), qemu_wan, egress.clone()))
+                    .abort_handle(),
+            );
+            link.tasks.push(
+                tokio::spawn(forward(wan, guest, qemu_guest, ingress.clone())).abort_handle(),
+            );
+            link.shapers.extend([egress, ingress]);
+        }
+
+        Ok(link)
+    }
+
+    /// Switch the running relays to another profile
+    pub fn update(&mut self, profile: Option<&QosProfile>) {
+        let spec = profile.map(|p| p.spec.clone()).unwrap_or_default();
+        for shaper in &self.shapers {
+            shaper.update_config(spec.clone());
+        }
+        self.profile_id = profile.map(|p| p.meta.id.clone());
+    }
+
+    /// Inject chaos faults into both directions of NIC `nic`
+    pub fn set_faults(&self, nic: usize, faults: Option<FaultInjection>) {
+        for shaper in self.shapers.iter().skip(nic * 2).take(2) {
+            shaper.set_faults(faults.clone());
+        }
+    }
+
+    /// Number of relayed NICs
+    pub fn nics(&self) -> usize {
+        self.shapers.len() / 2
+    }
+
+    /// Bytes relayed to and from the guest, including dropped frames
+    pub fn traffic(&self) -> (u64, u64) {
+        // Shapers come in (egress, ingress) pairs per NIC
+        let mut rx = 0;
+        let mut tx = 0;
+        for pair in self.shapers.chunks(2) {
+            tx += pair[0].stats().bytes_total;
+            if let Some(ingress) = pair.get(1) {
+                rx += ingress.stats().bytes_total;
+            }
+        }
+        (rx, tx)
+    }
+
+    /// Stop all relay tasks
+    pub fn stop(&self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Relay frames arriving on `from` to `dest` via `to`, shaped by `shaper`
+async fn forward(from: Arc<UdpSocket>, to: Arc<UdpSocket>, dest: SocketAddr, shaper: TrafficShaper) {
+    let mut buf = vec![0u8; MAX_FRAME];
+
+    loop {
+        let len = match from.recv(&mut buf).await {
+            Ok(len) => len,
+            Err(e) => {
+                warn!("QoS relay stopped: {}", e);
+                return;
+            }
+        };
+        let mut frame = buf[..len].to_vec();
+
+        let delay = match shaper.shape_frame(&frame).await {
+            ShapingDecision::Drop => continue,
+            ShapingDecision::Send => {
+                if let Err(e) = to.send_to(&frame, dest).await {
+                    debug!("QoS relay send failed: {}", e);
+                }
+                continue;
+            }
+            ShapingDecision::Delay(delay) => delay,
+            ShapingDecision::SendPadded { delay, padding } => {
+                frame.resize(len + padding, 0);
+                delay
+            }
+        };
+
+        // Delay without holding up the frames behind this one
+        let to = to.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Err(e) = to.send_to(&frame, dest).await {
+                debug!("QoS relay send failed: {}", e);
+            }
+        });
+    }
+}
+
+fn bind_loopback() -> Result<StdUdpSocket> {
+    StdUdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+        .map_err(|e| Error::NetworkError(format!("Failed to bind QoS relay socket: {}", e)))
+}
+
+fn local_port(socket: &StdUdpSocket) -> Result<u16> {
+    Ok(socket.local_addr()?.port())
+}
+
+fn into_tokio(socket: StdUdpSocket) -> Result<UdpSocket> {
+    socket.set_nonblocking(true)?;
+    Ok(UdpSocket::from_std(socket)?)
+}
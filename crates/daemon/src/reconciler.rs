@@ -2,9 +2,20 @@
 //!
 //! Continuously monitors and reconciles desired state with actual state.
 
-use crate::qemu::{QemuLauncher, VolumePreparer};
+use crate::admission;
+use crate::chaos;
+use crate::hostnet;
+use crate::hotplug;
+use crate::idle::{self, IdleTracker};
+use crate::qemu::{self, QemuLauncher, StopMode, VolumePreparer};
+use crate::scheduler;
 use crate::state::StateManager;
+use crate::stats;
+use infrasim_common::attestation;
 use infrasim_common::types::*;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
@@ -12,7 +23,15 @@ use tracing::{debug, error, info, warn};
 pub struct Reconciler {
     state: StateManager,
     qemu: QemuLauncher,
-    volume_preparer: VolumePreparer,
+    volume_preparer: Arc<VolumePreparer>,
+    /// Volumes with a remote import in progress
+    active_imports: Arc<Mutex<HashSet<String>>>,
+    /// Snapshot and backup policies with a run in progress
+    active_policies: Arc<Mutex<HashSet<String>>>,
+    /// VMs paused or suspended to relieve memory pressure
+    auto_paused: Arc<Mutex<HashSet<String>>>,
+    /// Activity of running VMs, for idle suspension
+    idle_trackers: Arc<Mutex<HashMap<String, IdleTracker>>>,
 }
 
 impl Reconciler {
@@ -21,11 +40,54 @@ impl Reconciler {
         let config = state.config().clone();
         Self {
             qemu: QemuLauncher::new(config.clone()),
-            volume_preparer: VolumePreparer::new(config),
+            volume_preparer: Arc::new(VolumePreparer::new(config)),
+            active_imports: Arc::new(Mutex::new(HashSet::new())),
+            active_policies: Arc::new(Mutex::new(HashSet::new())),
+            auto_paused: Arc::new(Mutex::new(HashSet::new())),
+            idle_trackers: Arc::new(Mutex::new(HashMap::new())),
             state,
         }
     }
 
+    /// Take back QEMU processes that outlived the previous daemon
+    ///
+    /// Run once before the loop. Live processes are re-adopted so the loop
+    /// doesn't launch a second QEMU for their VMs; statuses naming a process
+    /// that is gone are cleared so the loop restarts or stops the VM.
+    pub async fn recover(&self) -> infrasim_common::Result<()> {
+        let mut adopted = HashSet::new();
+
+        for vm in self.state.list_vms()? {
+            if vm.status.qemu_pid.is_none() {
+                continue;
+            }
+
+            match self.qemu.adopt(&self.state, &vm).await {
+                Ok(Some(process)) => {
+                    info!("Adopted QEMU for VM {} (PID {})", vm.meta.name, process.pid);
+                    adopted.insert(process.qmp_socket);
+                }
+                Ok(None) => {
+                    warn!("QEMU for VM {} exited while the daemon was down", vm.meta.name);
+                    self.state
+                        .emit_vm_event(&vm.meta.id, "QEMU exited while the daemon was down");
+                    let status = VmStatus {
+                        qemu_pid: None,
+                        qmp_socket: None,
+                        vnc_display: None,
+                        uptime_seconds: 0,
+                        started_at: None,
+                        ..vm.status.clone()
+                    };
+                    self.state.update_vm_status(&vm.meta.id, status)?;
+                }
+                Err(e) => warn!("Failed to adopt QEMU for VM {}: {}", vm.meta.name, e),
+            }
+        }
+
+        self.qemu.shutdown_orphans(&self.state, &adopted).await
+    }
+
     /// Run the reconciliation loop
     pub async fn run(&self) {
         info!("Reconciler started");
@@ -43,7 +105,16 @@ impl Reconciler {
     async fn reconcile_all(&self) -> infrasim_common::Result<()> {
         self.reconcile_volumes().await?;
         self.reconcile_vms().await?;
+        self.reconcile_memory_pressure().await?;
+        self.reconcile_idle().await?;
+        self.reconcile_port_forwards().await?;
+        self.reconcile_routers().await?;
+        self.reconcile_devices().await?;
+        self.reconcile_qos()?;
+        self.reconcile_chaos()?;
         self.reconcile_consoles().await?;
+        self.reconcile_snapshot_policies()?;
+        self.reconcile_backup_policies()?;
         self.cleanup_orphans().await?;
         Ok(())
     }
@@ -53,6 +124,11 @@ impl Reconciler {
         let volumes = self.state.list_volumes()?;
 
         for volume in volumes {
+            if !volume.status.ready && volume.spec.is_remote() {
+                self.spawn_import(volume);
+                continue;
+            }
+
             if !volume.status.ready {
                 debug!("Preparing volume: {}", volume.meta.name);
                 match self.volume_preparer.prepare(&self.state, &volume).await {
@@ -61,6 +137,8 @@ impl Reconciler {
                     }
                     Err(e) => {
                         warn!("Failed to prepare volume {}: {}", volume.meta.name, e);
+                        self.state
+                            .emit_reconcile_error("volume", &volume.meta.id, &volume.meta.name, &e);
                     }
                 }
             }
@@ -69,6 +147,40 @@ impl Reconciler {
         Ok(())
     }
 
+    /// Import a remote volume in the background so large downloads don't
+    /// stall the reconciliation loop
+    fn spawn_import(&self, volume: Volume) {
+        // Digest mismatches are permanent; the volume must be recreated
+        if volume
+            .status
+            .download
+            .as_ref()
+            .is_some_and(|d| d.state == DownloadState::Failed)
+        {
+            return;
+        }
+
+        if !self.active_imports.lock().insert(volume.meta.id.clone()) {
+            return;
+        }
+
+        debug!("Importing volume: {}", volume.meta.name);
+        let state = self.state.clone();
+        let preparer = self.volume_preparer.clone();
+        let active_imports = self.active_imports.clone();
+
+        tokio::spawn(async move {
+            match preparer.prepare(&state, &volume).await {
+                Ok(_) => info!("Volume ready: {}", volume.meta.name),
+                Err(e) => {
+                    warn!("Failed to import volume {}: {}", volume.meta.name, e);
+                    state.emit_reconcile_error("volume", &volume.meta.id, &volume.meta.name, &e);
+                }
+            }
+            active_imports.lock().remove(&volume.meta.id);
+        });
+    }
+
     /// Reconcile VMs
     async fn reconcile_vms(&self) -> infrasim_common::Result<()> {
         let vms = self.state.list_vms()?;
@@ -78,6 +190,10 @@ impl Reconciler {
                 Ok(_) => {}
                 Err(e) => {
                     warn!("Failed to reconcile VM {}: {}", vm.meta.name, e);
+                    self.state
+                        .emit_vm_event(&vm.meta.id, format!("Reconcile failed: {}", e));
+                    self.state
+                        .emit_reconcile_error("vm", &vm.meta.id, &vm.meta.name, &e);
                     
                     // Update status with error
                     let status = VmStatus {
@@ -95,10 +211,13 @@ impl Reconciler {
 
     /// Reconcile a single VM
     async fn reconcile_vm(&self, vm: &Vm) -> infrasim_common::Result<()> {
+        // Templates only back their clones' disks
+        if vm.spec.template {
+            return Ok(());
+        }
+
         let process = self.state.get_vm_process(&vm.meta.id);
-        let is_running = process.as_ref().map_or(false, |p| {
-            nix::sys::signal::kill(nix::unistd::Pid::from_raw(p.pid as i32), None).is_ok()
-        });
+        let is_running = process.as_ref().is_some_and(|p| qemu::process_alive(p.pid));
 
         match (&vm.status.state, is_running) {
             // Should be running but isn't
@@ -118,7 +237,7 @@ impl Reconciler {
             // Is running but shouldn't be
             (VmState::Stopped, true) => {
                 warn!("VM {} should be stopped but is running", vm.meta.name);
-                self.qemu.stop(&self.state, &vm.meta.id, false).await?;
+                self.qemu.stop(&self.state, &vm.meta.id, StopMode::Acpi, None).await?;
             }
 
             // Running and should be - update uptime
@@ -130,11 +249,29 @@ impl Reconciler {
                     state: VmState::Running,
                     qemu_pid: Some(process.pid),
                     qmp_socket: Some(process.qmp_socket.clone()),
-                    vnc_display: process.vnc_port.map(|p| format!(":{}", p - 5900)),
+                    vnc_display: process
+                        .vnc_port
+                        .map(|p| format!(":{}", p - self.state.config().qemu.get().vnc_base_port)),
                     error_message: None,
                     uptime_seconds: uptime,
+                    current_snapshot_id: vm.status.current_snapshot_id.clone(),
+                    started_at: Some(process.started_at),
                 };
                 self.state.update_vm_status(&vm.meta.id, status)?;
+
+                // Apply CPU, memory and volume changes without a restart
+                if hotplug::needs_apply(vm, &process.live) {
+                    if let Err(e) = hotplug::apply(&self.state, vm, &process).await {
+                        warn!("Hot-plug for VM {} failed: {}", vm.meta.name, e);
+                    }
+                }
+            }
+
+            // Paused but QEMU is gone - the guest's RAM went with it
+            (VmState::Paused, false) => {
+                warn!("QEMU for paused VM {} exited", vm.meta.name);
+                self.state.emit_vm_event(&vm.meta.id, "QEMU exited while VM was paused");
+                self.qemu.stop(&self.state, &vm.meta.id, StopMode::Hard, None).await?;
             }
 
             // Pending state - try to start if possible
@@ -142,6 +279,7 @@ impl Reconciler {
                 let volumes_ready = self.check_volumes_ready(vm)?;
                 if volumes_ready {
                     info!("Starting pending VM: {}", vm.meta.name);
+                    self.state.emit_vm_event(&vm.meta.id, "Volumes ready, starting VM");
                     
                     // Mark as running to trigger start
                     let status = VmStatus {
@@ -159,6 +297,183 @@ impl Reconciler {
         Ok(())
     }
 
+    /// Pause low-priority VMs while the host runs short of memory, and
+    /// resume them once it has recovered
+    ///
+    /// One VM changes per pass so the host can settle before the next.
+    async fn reconcile_memory_pressure(&self) -> infrasim_common::Result<()> {
+        let config = self.state.config().admission.get();
+        let vms = self.state.list_vms()?;
+
+        // Forget VMs someone resumed, stopped or deleted
+        self.auto_paused.lock().retain(|id| {
+            vms.iter().any(|vm| {
+                &vm.meta.id == id && matches!(vm.status.state, VmState::Paused | VmState::Suspended)
+            })
+        });
+
+        if config.pressure_free_mb == 0 {
+            return Ok(());
+        }
+        let Some(available_mb) = attestation::host_available_memory_bytes().map(|b| b / (1024 * 1024)) else {
+            return Ok(());
+        };
+
+        if available_mb < config.pressure_free_mb {
+            let victim = vms
+                .iter()
+                .filter(|vm| {
+                    !vm.spec.template
+                        && vm.status.state == VmState::Running
+                        && vm.spec.priority < config.pressure_priority
+                })
+                .min_by_key(|vm| vm.spec.priority);
+            let Some(vm) = victim else {
+                return Ok(());
+            };
+
+            warn!(
+                "Host has {} MB of memory available, below {} MB; pausing VM {} (priority {})",
+                available_mb, config.pressure_free_mb, vm.meta.name, vm.spec.priority
+            );
+            let suspended = self.put_to_sleep(vm, config.pressure_action == "suspend").await?;
+            self.state.emit_vm_event(
+                &vm.meta.id,
+                format!(
+                    "{} under memory pressure ({} MB available)",
+                    if suspended { "Suspended" } else { "Paused" },
+                    available_mb
+                ),
+            );
+            self.auto_paused.lock().insert(vm.meta.id.clone());
+        } else if available_mb >= config.resume_free_mb {
+            let auto_paused = self.auto_paused.lock().clone();
+            let Some(vm) = vms
+                .iter()
+                .filter(|vm| auto_paused.contains(&vm.meta.id))
+                .max_by_key(|vm| vm.spec.priority)
+            else {
+                return Ok(());
+            };
+
+            if vm.status.state == VmState::Suspended {
+                let host = admission::HostCapacity::probe(&self.state.config().store_path);
+                let committed = admission::Committed::of(&vms, &vm.meta.id);
+                if let Err(e) = admission::check_start(&config, &host, committed, &vm.spec) {
+                    debug!("Not resuming VM {} yet: {}", vm.meta.name, e);
+                    return Ok(());
+                }
+            }
+
+            info!("Host memory recovered ({} MB available); resuming VM {}", available_mb, vm.meta.name);
+            self.qemu.resume(&self.state, vm).await?;
+            self.state.emit_vm_event(
+                &vm.meta.id,
+                format!("Resumed after memory pressure ({} MB available)", available_mb),
+            );
+            self.auto_paused.lock().remove(&vm.meta.id);
+        }
+
+        Ok(())
+    }
+
+    /// Pause or suspend VMs that have sat idle, and wake idle-paused VMs
+    /// when a console connects
+    async fn reconcile_idle(&self) -> infrasim_common::Result<()> {
+        let config = self.state.config().idle.get();
+        let vms = self.state.list_vms()?;
+
+        for vm in &vms {
+            if !self.state.is_idle(&vm.meta.id) {
+                continue;
+            }
+            match vm.status.state {
+                // QEMU keeps serving VNC while the guest is paused
+                VmState::Paused => {
+                    let Some(process) = self.state.get_vm_process(&vm.meta.id) else {
+                        continue;
+                    };
+                    if idle::console_attached(&self.state, &process).await {
+                        info!("Console connected to idle VM {}; resuming", vm.meta.name);
+                        self.state.clear_idle(&vm.meta.id);
+                        self.qemu.resume(&self.state, vm).await?;
+                        self.state.emit_vm_event(&vm.meta.id, "Woken by console connection");
+                    }
+                }
+                VmState::Suspended => {}
+                // Someone else woke or stopped it
+                _ => self.state.clear_idle(&vm.meta.id),
+            }
+        }
+
+        let mut trackers = self.idle_trackers.lock().clone();
+        trackers.retain(|id, _| {
+            vms.iter().any(|vm| &vm.meta.id == id && vm.status.state == VmState::Running && !idle::opted_out(vm))
+        });
+        if !config.enabled {
+            self.idle_trackers.lock().clear();
+            return Ok(());
+        }
+
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let idle_limit = Duration::from_secs(config.idle_minutes * 60);
+        for vm in &vms {
+            if vm.spec.template || vm.status.state != VmState::Running || idle::opted_out(vm) {
+                continue;
+            }
+            let Some(process) = self.state.get_vm_process(&vm.meta.id) else {
+                continue;
+            };
+            let tracker = trackers.entry(vm.meta.id.clone()).or_default();
+            if !tracker.due(now_ms) {
+                continue;
+            }
+
+            let usage = match stats::sample(&self.state, &process, tracker.previous()).await {
+                Ok(usage) => usage,
+                Err(e) => {
+                    debug!("Sampling VM {} for idleness failed: {}", vm.meta.name, e);
+                    continue;
+                }
+            };
+            let console = idle::console_attached(&self.state, &process).await;
+            if tracker.observe(usage, console, config.cpu_percent) < idle_limit {
+                continue;
+            }
+
+            trackers.remove(&vm.meta.id);
+            info!("VM {} idle for {} minutes; putting it to sleep", vm.meta.name, config.idle_minutes);
+            let suspended = self.put_to_sleep(vm, config.action == "suspend").await?;
+            self.state.mark_idle(&vm.meta.id);
+            self.state.emit_vm_event(
+                &vm.meta.id,
+                format!(
+                    "{} after {} idle minutes; connect a console to wake it",
+                    if suspended { "Suspended" } else { "Paused" },
+                    config.idle_minutes
+                ),
+            );
+        }
+
+        *self.idle_trackers.lock() = trackers;
+        Ok(())
+    }
+
+    /// Suspend a VM, or pause it if `suspend` is false or suspending fails;
+    /// returns whether it was suspended
+    async fn put_to_sleep(&self, vm: &Vm, suspend: bool) -> infrasim_common::Result<bool> {
+        // Suspending frees the guest's RAM, but not every VM can be migrated
+        // to a file; pausing at least stops it running
+        if suspend {
+            match self.qemu.suspend(&self.state, vm).await {
+                Ok(()) => return Ok(true),
+                Err(e) => warn!("Suspending VM {} failed, pausing instead: {}", vm.meta.name, e),
+            }
+        }
+        self.qemu.pause(&self.state, vm).await?;
+        Ok(false)
+    }
+
     /// Check if all volumes for a VM are ready
     fn check_volumes_ready(&self, vm: &Vm) -> infrasim_common::Result<bool> {
         // Check boot disk
@@ -184,6 +499,247 @@ impl Reconciler {
         Ok(true)
     }
 
+    /// Install port forwards into running VMs
+    ///
+    /// QEMU forgets `hostfwd` rules when it exits, so a forward is reapplied
+    /// whenever the VM's process changes.
+    async fn reconcile_port_forwards(&self) -> infrasim_common::Result<()> {
+        for forward in self.state.list_port_forwards(None)? {
+            let pid = self
+                .state
+                .get_vm_process(&forward.spec.vm_id)
+                .map(|p| p.pid)
+                .filter(|pid| {
+                    qemu::process_alive(*pid)
+                });
+
+            let status = match pid {
+                None => PortForwardStatus {
+                    active: false,
+                    error_message: None,
+                },
+                Some(pid) if self.state.port_forward_applied_pid(&forward.meta.id) == Some(pid) => {
+                    continue;
+                }
+                Some(_) => match self.qemu.add_port_forward(&self.state, &forward).await {
+                    Ok(pid) => {
+                        self.state.mark_port_forward_applied(&forward.meta.id, pid);
+                        self.state.emit_vm_event(
+                            &forward.spec.vm_id,
+                            format!(
+                                "Port forward {} active: {}:{} -> {}",
+                                forward.meta.name,
+                                forward.spec.host_addr,
+                                forward.spec.host_port,
+                                forward.spec.guest_port
+                            ),
+                        );
+                        PortForwardStatus {
+                            active: true,
+                            error_message: None,
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to apply port forward {}: {}", forward.meta.name, e);
+                        PortForwardStatus {
+                            active: false,
+                            error_message: Some(e.to_string()),
+                        }
+                    }
+                },
+            };
+
+            if status.active != forward.status.active
+                || status.error_message != forward.status.error_message
+            {
+                if let Some(error) = &status.error_message {
+                    self.state.emit_reconcile_error(
+                        "port_forward",
+                        &forward.meta.id,
+                        &forward.meta.name,
+                        error,
+                    );
+                }
+                self.state.update_port_forward_status(&forward.meta.id, status)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Install routers on the host
+    ///
+    /// A router is installed again whenever its spec changes, and once after
+    /// the daemon starts since it can't tell what a previous run left behind.
+    async fn reconcile_routers(&self) -> infrasim_common::Result<()> {
+        for router in self.state.list_routers(None)? {
+            let applied = self.state.router_applied_spec(&router.meta.id);
+            if applied.as_ref() == Some(&router.spec) {
+                continue;
+            }
+            if let Some(old) = applied {
+                hostnet::remove_router(&router.meta.id, &old).await;
+                self.state.clear_router_applied(&router.meta.id);
+            }
+
+            let mut networks = Vec::new();
+            let mut missing = None;
+            for id in &router.spec.network_ids {
+                match self.state.get_network(id)? {
+                    Some(network) => networks.push(network),
+                    None => missing = Some(id.clone()),
+                }
+            }
+            let result = match missing {
+                Some(id) => Err(infrasim_common::Error::NotFound {
+                    kind: "network".to_string(),
+                    id,
+                }),
+                None => hostnet::apply_router(self.state.config(), &router, &networks).await,
+            };
+
+            let status = match result {
+                Ok(interfaces) => {
+                    self.state.mark_router_applied(&router.meta.id, router.spec.clone());
+                    RouterStatus {
+                        active: true,
+                        error_message: None,
+                        interfaces,
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to apply router {}: {}", router.meta.name, e);
+                    RouterStatus {
+                        active: false,
+                        error_message: Some(e.to_string()),
+                        interfaces: Vec::new(),
+                    }
+                }
+            };
+
+            if status.active != router.status.active
+                || status.error_message != router.status.error_message
+                || status.interfaces != router.status.interfaces
+            {
+                if let Some(error) = &status.error_message {
+                    self.state.emit_reconcile_error(
+                        "router",
+                        &router.meta.id,
+                        &router.meta.name,
+                        error,
+                    );
+                }
+                self.state.update_router_status(&router.meta.id, status)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Attach passthrough devices to running VMs
+    ///
+    /// USB devices are hot-attached over QMP, and again whenever the VM's
+    /// process changes. PCI devices are added on the QEMU command line, so
+    /// one created while its VM runs waits for the next start.
+    async fn reconcile_devices(&self) -> infrasim_common::Result<()> {
+        for device in self.state.list_devices(None)? {
+            let pid = self
+                .state
+                .get_vm_process(&device.spec.vm_id)
+                .map(|p| p.pid)
+                .filter(|pid| {
+                    qemu::process_alive(*pid)
+                });
+            let attached_pid = self.state.device_attached_pid(&device.meta.id);
+
+            let status = match pid {
+                None => DeviceStatus {
+                    attached: false,
+                    error_message: None,
+                },
+                Some(pid) if attached_pid == Some(pid) => DeviceStatus {
+                    attached: true,
+                    error_message: None,
+                },
+                Some(_) if device.spec.bus == DeviceBus::Pci => DeviceStatus {
+                    attached: false,
+                    error_message: Some("PCI devices are attached when the VM starts; restart it".to_string()),
+                },
+                Some(_) => match self.qemu.attach_device(&self.state, &device).await {
+                    Ok(pid) => {
+                        self.state.mark_device_attached(&device.meta.id, pid);
+                        self.state.emit_vm_event(
+                            &device.spec.vm_id,
+                            format!("Device {} attached: {}", device.meta.name, device.spec.describe()),
+                        );
+                        DeviceStatus {
+                            attached: true,
+                            error_message: None,
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to attach device {}: {}", device.meta.name, e);
+                        DeviceStatus {
+                            attached: false,
+                            error_message: Some(e.to_string()),
+                        }
+                    }
+                },
+            };
+
+            if status.attached != device.status.attached
+                || status.error_message != device.status.error_message
+            {
+                if let (Some(error), DeviceBus::Usb) = (&status.error_message, device.spec.bus) {
+                    self.state.emit_reconcile_error(
+                        "device",
+                        &device.meta.id,
+                        &device.meta.name,
+                        error,
+                    );
+                }
+                self.state.update_device_status(&device.meta.id, status)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Switch running VMs to their current QoS profile
+    ///
+    /// Only VMs started with relays (for a profile, a chaos profile or
+    /// `network.chaos_relays`) can be reconfigured; the others pick up a
+    /// newly attached profile on their next start.
+    fn reconcile_qos(&self) -> infrasim_common::Result<()> {
+        for process in self.state.list_vm_processes() {
+            if !self.state.has_qos_link(&process.vm_id) {
+                continue;
+            }
+            let Some(vm) = self.state.get_vm(&process.vm_id)? else {
+                continue;
+            };
+
+            let profile = match &vm.spec.qos_profile_id {
+                Some(id) => self.state.get_qos_profile(id)?,
+                None => None,
+            };
+            if self.state.update_qos_link(&vm.meta.id, profile.as_ref()) {
+                let message = match &profile {
+                    Some(profile) => format!("QoS profile {} applied", profile.meta.name),
+                    None => "QoS profile removed, traffic is no longer shaped".to_string(),
+                };
+                self.state.emit_vm_event(&vm.meta.id, message);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inject the faults of chaos profiles and expire old ones
+    fn reconcile_chaos(&self) -> infrasim_common::Result<()> {
+        chaos::apply(&self.state)
+    }
+
     /// Reconcile consoles
     async fn reconcile_consoles(&self) -> infrasim_common::Result<()> {
         // Console status is managed by the web server
@@ -191,6 +747,59 @@ impl Reconciler {
         Ok(())
     }
 
+    /// Run due snapshot policies in the background
+    fn reconcile_snapshot_policies(&self) -> infrasim_common::Result<()> {
+        let now = chrono::Utc::now().timestamp();
+
+        for policy in self.state.list_snapshot_policies(None)? {
+            if scheduler::next_run_at(&policy).map_or(true, |next| next > now) {
+                continue;
+            }
+            if !self.active_policies.lock().insert(policy.meta.id.clone()) {
+                continue;
+            }
+
+            // Merging pruned layers can take a while; don't stall the loop
+            let state = self.state.clone();
+            let active_policies = self.active_policies.clone();
+            tokio::spawn(async move {
+                let qemu = QemuLauncher::new(state.config().clone());
+                if let Err(e) = scheduler::run(&state, &qemu, &policy).await {
+                    warn!("Snapshot policy {} failed: {}", policy.meta.name, e);
+                }
+                active_policies.lock().remove(&policy.meta.id);
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Run due backup policies in the background
+    fn reconcile_backup_policies(&self) -> infrasim_common::Result<()> {
+        let now = chrono::Utc::now().timestamp();
+
+        for policy in self.state.list_backup_policies(None)? {
+            if scheduler::next_backup_at(&policy).map_or(true, |next| next > now) {
+                continue;
+            }
+            if !self.active_policies.lock().insert(policy.meta.id.clone()) {
+                continue;
+            }
+
+            // Copying disks and uploading them can take a long time
+            let state = self.state.clone();
+            let active_policies = self.active_policies.clone();
+            tokio::spawn(async move {
+                if let Err(e) = scheduler::run_backup(&state, &policy).await {
+                    warn!("Backup policy {} failed: {}", policy.meta.name, e);
+                }
+                active_policies.lock().remove(&policy.meta.id);
+            });
+        }
+
+        Ok(())
+    }
+
     /// Clean up orphaned processes
     async fn cleanup_orphans(&self) -> infrasim_common::Result<()> {
         let processes = self.state.list_vm_processes();
@@ -199,7 +808,37 @@ impl Reconciler {
             // Check if VM still exists
             if self.state.get_vm(&process.vm_id)?.is_none() {
                 warn!("Cleaning up orphan process for deleted VM: {}", process.vm_id);
-                self.qemu.stop(&self.state, &process.vm_id, true).await?;
+                self.qemu.stop(&self.state, &process.vm_id, StopMode::Hard, None).await?;
+            }
+        }
+
+        for forward in self.state.list_port_forwards(None)? {
+            if self.state.get_vm(&forward.spec.vm_id)?.is_none() {
+                debug!("Removing port forward for deleted VM: {}", forward.meta.name);
+                self.state.delete_port_forward(&forward.meta.id)?;
+            }
+        }
+
+        for device in self.state.list_devices(None)? {
+            if self.state.get_vm(&device.spec.vm_id)?.is_none() {
+                debug!("Removing device for deleted VM: {}", device.meta.name);
+                self.state.delete_device(&device.meta.id)?;
+            }
+        }
+
+        // Selector policies outlive the VMs they happen to match
+        for policy in self.state.list_snapshot_policies(None)? {
+            if !policy.spec.vm_id.is_empty() && self.state.get_vm(&policy.spec.vm_id)?.is_none() {
+                debug!("Removing snapshot policy for deleted VM: {}", policy.meta.name);
+                self.state.delete_snapshot_policy(&policy.meta.id)?;
+            }
+        }
+
+        // Backups outlive their VM so it can be restored; only policies go
+        for policy in self.state.list_backup_policies(None)? {
+            if !policy.spec.vm_id.is_empty() && self.state.get_vm(&policy.spec.vm_id)?.is_none() {
+                debug!("Removing backup policy for deleted VM: {}", policy.meta.name);
+                self.state.delete_backup_policy(&policy.meta.id)?;
             }
         }
 
@@ -235,11 +874,9 @@ impl DriftDetector {
         let process = self.state.get_vm_process(&vm.meta.id);
 
         // Check if process state matches desired state
-        let is_running = process.as_ref().map_or(false, |p| {
-            nix::sys::signal::kill(nix::unistd::Pid::from_raw(p.pid as i32), None).is_ok()
-        });
+        let is_running = process.as_ref().is_some_and(|p| qemu::process_alive(p.pid));
 
-        let should_be_running = matches!(vm.status.state, VmState::Running);
+        let should_be_running = matches!(vm.status.state, VmState::Running | VmState::Paused);
 
         if is_running != should_be_running {
             return Ok(Some(DriftReport {
@@ -0,0 +1,502 @@
+//! Minimal OCI distribution client for appliance artifacts
+//!
+//! Speaks the registry HTTP API v2 (ghcr.io, Docker Hub, Harbor, `registry:2`,
+//! ...). Blobs are uploaded in chunks and downloaded with ranges, so disk
+//! images never sit in memory whole, and bearer tokens are fetched whenever a
+//! registry asks for one.
+
+use crate::config::DaemonConfig;
+use async_trait::async_trait;
+use base64::Engine;
+use bytes::Bytes;
+use infrasim_common::types::{OciReference, DOCKER_HUB_REGISTRY};
+use infrasim_common::{Error, RemoteStore, Result};
+use parking_lot::Mutex;
+use reqwest::header::{
+    HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LOCATION, RANGE,
+    WWW_AUTHENTICATE,
+};
+use reqwest::{Method, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::debug;
+
+/// Size of blob upload chunks
+const CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Media type of OCI image manifests, which also describe artifacts
+pub const OCI_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+
+/// Annotation naming the file a layer holds
+pub const TITLE_ANNOTATION: &str = "org.opencontainers.image.title";
+
+/// An OCI image manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OciManifest {
+    pub schema_version: u32,
+    pub media_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_type: Option<String>,
+    pub config: Descriptor,
+    #[serde(default)]
+    pub layers: Vec<Descriptor>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
+}
+
+/// Reference to a blob from a manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    pub media_type: String,
+    /// `sha256:<hex>`
+    pub digest: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub annotations: HashMap<String, String>,
+}
+
+impl Descriptor {
+    /// Descriptor of a blob with a hex SHA-256 digest
+    pub fn new(media_type: &str, digest: &str, size: u64) -> Self {
+        Self {
+            media_type: media_type.to_string(),
+            digest: format!("sha256:{}", digest),
+            size,
+            annotations: HashMap::new(),
+        }
+    }
+
+    /// Descriptor with a file name annotation
+    pub fn titled(mut self, title: &str) -> Self {
+        self.annotations.insert(TITLE_ANNOTATION.to_string(), title.to_string());
+        self
+    }
+
+    /// Hex SHA-256 digest; other algorithms are refused
+    pub fn hex_digest(&self) -> Result<&str> {
+        self.digest
+            .strip_prefix("sha256:")
+            .filter(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| Error::RegistryError(format!("Unsupported blob digest {}", self.digest)))
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.annotations.get(TITLE_ANNOTATION).map(String::as_str)
+    }
+}
+
+/// Client for one repository
+pub struct RegistryClient {
+    http: reqwest::Client,
+    /// `scheme://host`
+    base: String,
+    reference: OciReference,
+    credentials: Option<(String, String)>,
+    /// Authorization header for the next requests
+    auth: Mutex<Option<String>>,
+}
+
+impl RegistryClient {
+    /// Client for the repository of `reference`, using the daemon's registry credentials
+    pub fn new(config: &DaemonConfig, reference: &OciReference) -> Result<Self> {
+        let scheme = if config.registry.get().insecure.contains(&reference.registry) {
+            "http"
+        } else {
+            "https"
+        };
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base: format!("{}://{}", scheme, reference.registry),
+            reference: reference.clone(),
+            credentials: credentials(&reference.registry)?,
+            auth: Mutex::new(None),
+        })
+    }
+
+    /// Size of a blob, `None` if the repository doesn't have it
+    pub async fn blob_size(&self, digest: &str) -> Result<Option<u64>> {
+        let response = self
+            .request(Method::HEAD, &self.blob_url(digest), &[], Bytes::new())
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response, &self.blob_url(digest)).await?;
+        Ok(response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok()))
+    }
+
+    /// Upload a file as the blob `digest`, in chunks
+    pub async fn upload_blob(&self, digest: &str, path: &Path) -> Result<()> {
+        let size = fs::metadata(path).await?.len();
+        let mut file = fs::File::open(path).await?;
+        let mut location = self.start_upload().await?;
+
+        let mut offset = 0;
+        while offset < size {
+            let len = CHUNK_SIZE.min(size - offset);
+            let mut chunk = Vec::with_capacity(len as usize);
+            (&mut file).take(len).read_to_end(&mut chunk).await?;
+
+            let headers = [
+                (CONTENT_TYPE, "application/octet-stream".to_string()),
+                (CONTENT_RANGE, format!("{}-{}", offset, offset + len - 1)),
+            ];
+            let response = self.send(Method::PATCH, &location, &headers, chunk.into()).await?;
+            location = self.location(&response)?;
+            offset += len;
+            debug!("Uploaded {} of {} bytes of blob {}", offset, size, digest);
+        }
+
+        self.finish_upload(&location, digest, Bytes::new()).await
+    }
+
+    /// Upload bytes as the blob `digest` in one request
+    pub async fn put_blob(&self, digest: &str, data: Vec<u8>) -> Result<()> {
+        let location = self.start_upload().await?;
+        self.finish_upload(&location, digest, data.into()).await
+    }
+
+    /// Read a small blob into memory
+    pub async fn get_blob(&self, digest: &str) -> Result<Vec<u8>> {
+        let response = self
+            .send(Method::GET, &self.blob_url(digest), &[], Bytes::new())
+            .await?;
+        Ok(response.bytes().await.map_err(request_error)?.to_vec())
+    }
+
+    /// Append the bytes of blob `digest` from `offset` on to a file
+    ///
+    /// If the registry ignores the range, the file is rewritten from the start.
+    pub async fn download_blob(&self, digest: &str, path: &Path, offset: u64) -> Result<()> {
+        let headers: Vec<_> = (offset > 0)
+            .then(|| (RANGE, format!("bytes={}-", offset)))
+            .into_iter()
+            .collect();
+        let mut response = self
+            .send(Method::GET, &self.blob_url(digest), &headers, Bytes::new())
+            .await?;
+
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path).await?;
+        if offset > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+            file.set_len(0).await?;
+        }
+        while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+            file.write_all(&chunk).await?;
+        }
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    /// Store a manifest under `tag`, returning its hex digest
+    pub async fn put_manifest(&self, tag: &str, manifest: &OciManifest) -> Result<String> {
+        let body = serde_json::to_vec(manifest)?;
+        let digest = hex::encode(Sha256::digest(&body));
+        let headers = [(CONTENT_TYPE, manifest.media_type.clone())];
+        self.send(Method::PUT, &self.manifest_url(tag), &headers, body.into())
+            .await?;
+        Ok(digest)
+    }
+
+    /// Fetch the manifest the client's reference points to, with its hex digest
+    pub async fn get_manifest(&self) -> Result<(OciManifest, String)> {
+        let url = self.manifest_url(&self.reference.reference);
+        let headers = [(ACCEPT, OCI_MANIFEST_MEDIA_TYPE.to_string())];
+        let response = self.send(Method::GET, &url, &headers, Bytes::new()).await?;
+        let body = response.bytes().await.map_err(request_error)?;
+
+        let digest = hex::encode(Sha256::digest(&body));
+        if let Some(expected) = self.reference.digest() {
+            if digest != expected {
+                return Err(Error::IntegrityError(format!(
+                    "Manifest digest mismatch for {}: got {}",
+                    self.reference, digest
+                )));
+            }
+        }
+        let manifest: OciManifest = serde_json::from_slice(&body).map_err(|e| {
+            Error::RegistryError(format!("{} is not an OCI image manifest: {}", self.reference, e))
+        })?;
+        Ok((manifest, digest))
+    }
+
+    /// Open an upload session, returning its URL
+    async fn start_upload(&self) -> Result<String> {
+        let url = format!("{}/v2/{}/blobs/uploads/", self.base, self.reference.repository);
+        let response = self.send(Method::POST, &url, &[], Bytes::new()).await?;
+        self.location(&response)
+    }
+
+    /// Close an upload session with its last bytes
+    async fn finish_upload(&self, location: &str, digest: &str, data: Bytes) -> Result<()> {
+        let separator = if location.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}digest=sha256:{}", location, separator, digest);
+        let headers = [(CONTENT_TYPE, "application/octet-stream".to_string())];
+        self.send(Method::PUT, &url, &headers, data).await?;
+        Ok(())
+    }
+
+    fn blob_url(&self, digest: &str) -> String {
+        format!("{}/v2/{}/blobs/sha256:{}", self.base, self.reference.repository, digest)
+    }
+
+    fn manifest_url(&self, reference: &str) -> String {
+        format!("{}/v2/{}/manifests/{}", self.base, self.reference.repository, reference)
+    }
+
+    /// Absolute URL of a response's `Location` header
+    fn location(&self, response: &Response) -> Result<String> {
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| {
+                Error::RegistryError(format!("{} sent no upload location", self.reference.registry))
+            })?;
+        Ok(if location.starts_with("http://") || location.starts_with("https://") {
+            location.to_string()
+        } else {
+            format!("{}{}", self.base, location)
+        })
+    }
+
+    /// Send a request, failing on non-success responses
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(reqwest::header::HeaderName, String)],
+        body: Bytes,
+    ) -> Result<Response> {
+        let response = self.request(method, url, headers, body).await?;
+        check(response, url).await
+    }
+
+    /// Send a request, authenticating and retrying once if the registry asks
+    async fn request(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(reqwest::header::HeaderName, String)],
+        body: Bytes,
+    ) -> Result<Response> {
+        let build = || {
+            let mut request = self.http.request(method.clone(), url).body(body.clone());
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            if let Some(auth) = self.auth.lock().clone() {
+                request = request.header(AUTHORIZATION, auth);
+            }
+            request
+        };
+
+        let response = build().send().await.map_err(request_error)?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        let Some(challenge) = response.headers().get(WWW_AUTHENTICATE) else {
+            return Ok(response);
+        };
+        self.authenticate(challenge).await?;
+        build().send().await.map_err(request_error)
+    }
+
+    /// Answer a `WWW-Authenticate` challenge, storing the header to send
+    async fn authenticate(&self, challenge: &HeaderValue) -> Result<()> {
+        let challenge = challenge.to_str().unwrap_or_default();
+        let (scheme, params) = challenge.split_once(' ').unwrap_or((challenge, ""));
+        let params = challenge_params(params);
+
+        if scheme.eq_ignore_ascii_case("basic") {
+            let (user, password) = self.credentials.as_ref().ok_or_else(|| {
+                Error::PermissionDenied(format!("{} needs credentials", self.reference.registry))
+            })?;
+            *self.auth.lock() = Some(basic_auth(user, password));
+            return Ok(());
+        }
+
+        let realm = params.get("realm").ok_or_else(|| {
+            Error::RegistryError(format!("Unsupported authentication challenge: {}", challenge))
+        })?;
+        let scope = params
+            .get("scope")
+            .cloned()
+            .unwrap_or_else(|| format!("repository:{}:pull", self.reference.repository));
+        let mut query = vec![("scope", scope.as_str())];
+        if let Some(service) = params.get("service") {
+            query.push(("service", service));
+        }
+
+        let mut request = self.http.get(realm).query(&query);
+        if let Some((user, password)) = &self.credentials {
+            request = request.header(AUTHORIZATION, basic_auth(user, password));
+        }
+        let response = check(request.send().await.map_err(request_error)?, realm).await?;
+
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            token: Option<String>,
+            access_token: Option<String>,
+        }
+        let body = response.bytes().await.map_err(request_error)?;
+        let token: TokenResponse = serde_json::from_slice(&body)?;
+        let token = token.token.or(token.access_token).ok_or_else(|| {
+            Error::RegistryError(format!("No token in response from {}", realm))
+        })?;
+        *self.auth.lock() = Some(format!("Bearer {}", token));
+        debug!("Authenticated to {} for {}", self.reference.registry, scope);
+        Ok(())
+    }
+}
+
+/// Blobs in a repository, keyed by hex digest
+#[async_trait]
+impl RemoteStore for RegistryClient {
+    fn location(&self) -> String {
+        format!("{}/{}", self.reference.registry, self.reference.repository)
+    }
+
+    fn object_key(&self, digest: &str) -> String {
+        digest.to_string()
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>> {
+        self.blob_size(key).await
+    }
+
+    async fn upload(&self, key: &str, path: &Path) -> Result<()> {
+        self.upload_blob(key, path).await
+    }
+
+    async fn download(&self, key: &str, dest: &Path, offset: u64) -> Result<()> {
+        self.download_blob(key, dest, offset).await
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.put_blob(key, data).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.get_blob(key).await
+    }
+}
+
+/// Turn a non-success response into an error
+async fn check(response: Response, url: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+
+    #[derive(Deserialize)]
+    struct Errors {
+        errors: Vec<ErrorEntry>,
+    }
+    #[derive(Deserialize)]
+    struct ErrorEntry {
+        #[serde(default)]
+        message: String,
+    }
+    let message = serde_json::from_str::<Errors>(&body)
+        .ok()
+        .and_then(|e| e.errors.into_iter().map(|e| e.message).find(|m| !m.is_empty()))
+        .unwrap_or(body);
+
+    let error = format!("Registry request {} failed with {}: {}", url, status, message);
+    Err(match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::PermissionDenied(error),
+        StatusCode::NOT_FOUND => Error::NotFound {
+            kind: "registry object".to_string(),
+            id: url.to_string(),
+        },
+        _ => Error::RegistryError(error),
+    })
+}
+
+/// Credentials for `registry` from the environment or Docker's config file
+fn credentials(registry: &str) -> Result<Option<(String, String)>> {
+    let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    if let (Some(user), Some(password)) = (
+        env("INFRASIM_REGISTRY_USERNAME"),
+        env("INFRASIM_REGISTRY_PASSWORD"),
+    ) {
+        return Ok(Some((user, password)));
+    }
+
+    let Some(path) = env("DOCKER_CONFIG")
+        .map(PathBuf::from)
+        .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".docker")))
+        .map(|dir| dir.join("config.json"))
+    else {
+        return Ok(None);
+    };
+    let Ok(data) = std::fs::read(&path) else {
+        return Ok(None);
+    };
+
+    #[derive(Deserialize)]
+    struct DockerConfig {
+        #[serde(default)]
+        auths: HashMap<String, DockerAuth>,
+    }
+    #[derive(Deserialize)]
+    struct DockerAuth {
+        #[serde(default)]
+        auth: String,
+    }
+    let config: DockerConfig = serde_json::from_slice(&data)?;
+
+    // Docker Hub logins are stored under the old index URL
+    let keys = if registry == DOCKER_HUB_REGISTRY {
+        vec!["https://index.docker.io/v1/".to_string(), "docker.io".to_string()]
+    } else {
+        vec![registry.to_string(), format!("https://{}", registry)]
+    };
+    let Some(auth) = keys.iter().find_map(|key| config.auths.get(key)) else {
+        return Ok(None);
+    };
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(auth.auth.trim())
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| Error::InvalidConfig(format!("Invalid auth for {} in {}", registry, path.display())))?;
+    Ok(decoded
+        .split_once(':')
+        .map(|(user, password)| (user.to_string(), password.to_string())))
+}
+
+fn basic_auth(user: &str, password: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+    format!("Basic {}", encoded)
+}
+
+/// `key="value"` parameters of an authentication challenge
+fn challenge_params(params: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut rest = params.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => after.split_once(',').unwrap_or((after, "")),
+        };
+        result.insert(key, value.to_string());
+        rest = after.trim_start_matches(',').trim();
+    }
+    result
+}
+
+fn request_error(e: reqwest::Error) -> Error {
+    Error::RegistryError(format!("Registry request failed: {}", e))
+}
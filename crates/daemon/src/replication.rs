@@ -0,0 +1,367 @@
+//! Replication of daemon state to a standby
+//!
+//! A primary with `replication.allow_standbys` serves consistent copies of
+//! its state DB and its CAS objects. A daemon with `replication.primary` set
+//! is a standby: every `replication.interval_secs` it asks the primary for
+//! its change position, copies the whole state DB over when it moved, and
+//! fetches the CAS objects it lacks. A standby refuses mutating calls until
+//! `infrasim daemon promote`, which ends replication for good and hands the
+//! state to the reconciler.
+
+use crate::audit;
+use crate::config::DaemonConfig;
+use crate::generated::infra_sim_daemon_client::InfraSimDaemonClient;
+use crate::generated::{GetReplicationStatusRequest, ReadCasObjectRequest, StreamStateSnapshotRequest};
+use crate::state::StateManager;
+use anyhow::{anyhow, bail};
+use infrasim_common::auth::AuthChannel;
+use infrasim_common::cas::check_digest;
+use infrasim_common::types::{VmState, VmStatus};
+use infrasim_common::{ContentAddressedStore, Error, Result};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, Notify};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::server::NamedService;
+use tracing::{info, warn};
+
+/// File in the replication directory marking a promoted standby
+const PROMOTED_MARKER: &str = "promoted";
+
+/// Calls a standby still accepts besides reads
+const STANDBY_CALLS: &[&str] = &["PromoteDaemon"];
+
+/// How far a standby has caught up with its primary
+#[derive(Debug, Clone, Default)]
+pub struct SyncStatus {
+    /// Primary epoch and position of the last state copy
+    pub epoch: String,
+    pub position: u64,
+    /// Unix seconds of the last successful sync
+    pub last_sync_at: i64,
+    /// Why the last sync failed, empty if it didn't
+    pub last_error: String,
+    /// CAS objects copied since the daemon started
+    pub synced_objects: u64,
+}
+
+/// Replication role of the daemon, and a standby's progress
+#[derive(Clone)]
+pub struct Replication {
+    config: DaemonConfig,
+    state: StateManager,
+    /// Identifies this run of the daemon, so standbys notice a restart
+    epoch: String,
+    standby: Arc<AtomicBool>,
+    /// Held while copying from the primary, so promotion waits for a copy
+    /// in flight rather than having it overwrite the first changes
+    sync_lock: Arc<Mutex<()>>,
+    wake: Arc<Notify>,
+    status: Arc<RwLock<SyncStatus>>,
+}
+
+impl Replication {
+    pub fn new(config: &DaemonConfig, state: StateManager) -> Self {
+        let promoted = config.replication_dir().join(PROMOTED_MARKER).exists();
+        if promoted && config.replication.primary.is_some() {
+            warn!("This daemon was promoted from a standby; ignoring replication.primary");
+        }
+        Self {
+            config: config.clone(),
+            state,
+            epoch: uuid::Uuid::new_v4().to_string(),
+            standby: Arc::new(AtomicBool::new(config.replication.primary.is_some() && !promoted)),
+            sync_lock: Arc::new(Mutex::new(())),
+            wake: Arc::new(Notify::new()),
+            status: Arc::new(RwLock::new(SyncStatus::default())),
+        }
+    }
+
+    pub fn is_standby(&self) -> bool {
+        self.standby.load(Ordering::SeqCst)
+    }
+
+    pub fn epoch(&self) -> &str {
+        &self.epoch
+    }
+
+    /// Address of the primary a standby replicates from
+    pub fn primary(&self) -> &str {
+        self.config.replication.primary.as_deref().unwrap_or_default()
+    }
+
+    pub fn sync_status(&self) -> SyncStatus {
+        self.status.read().clone()
+    }
+
+    /// Wrap the gRPC service to refuse mutating calls while a standby
+    pub fn guard<S>(&self, inner: S) -> StandbyGuard<S> {
+        StandbyGuard {
+            inner,
+            standby: self.standby.clone(),
+            primary: self.primary().to_string(),
+        }
+    }
+
+    /// Replicate from the primary until promoted; returns at once on a primary
+    pub async fn run_until_promoted(&self) {
+        if self.is_standby() {
+            info!("Standby of {}; replicating every {}s", self.primary(), self.config.replication.interval_secs);
+        }
+        let interval = Duration::from_secs(self.config.replication.interval_secs);
+        while self.is_standby() {
+            match self.sync().await {
+                Ok(()) => {
+                    let mut status = self.status.write();
+                    status.last_sync_at = chrono::Utc::now().timestamp();
+                    status.last_error.clear();
+                }
+                Err(e) => {
+                    warn!("Replication from {} failed: {:#}", self.primary(), e);
+                    self.status.write().last_error = format!("{:#}", e);
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = self.wake.notified() => {}
+            }
+        }
+    }
+
+    /// Copy the primary's state if it changed, then the CAS objects missing here
+    async fn sync(&self) -> anyhow::Result<()> {
+        let _sync = self.sync_lock.lock().await;
+        if !self.is_standby() {
+            return Ok(());
+        }
+
+        let channel = self.config.replication.credentials.connect(self.primary()).await?;
+        let mut client = InfraSimDaemonClient::new(channel);
+        let remote = client
+            .get_replication_status(GetReplicationStatusRequest { include_objects: true })
+            .await?
+            .into_inner();
+        if remote.role != "primary" {
+            bail!("{} is a {}, not a primary", self.primary(), remote.role);
+        }
+
+        let synced = self.sync_status();
+        if remote.epoch != synced.epoch || remote.position != synced.position {
+            self.copy_state(&mut client).await?;
+        }
+
+        for digest in &remote.cas_digests {
+            check_digest(digest)?;
+            if !self.state.cas().has(digest).await {
+                self.copy_object(&mut client, digest).await?;
+                self.status.write().synced_objects += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn copy_state(&self, client: &mut InfraSimDaemonClient<AuthChannel>) -> anyhow::Result<()> {
+        let dir = self.config.replication_dir();
+        tokio::fs::create_dir_all(&dir).await?;
+        let path = dir.join("state.db.partial");
+        let mut file = tokio::fs::File::create(&path).await?;
+
+        let mut stream = client
+            .stream_state_snapshot(StreamStateSnapshotRequest {})
+            .await?
+            .into_inner();
+        let mut position = None;
+        while let Some(chunk) = stream.message().await? {
+            if position.is_none() {
+                position = Some((chunk.epoch, chunk.position));
+            }
+            file.write_all(&chunk.data).await?;
+        }
+        file.sync_all().await?;
+        drop(file);
+        let (epoch, position) = position.ok_or_else(|| anyhow!("primary sent an empty snapshot"))?;
+
+        let db = self.state.db().clone();
+        let copy = path.clone();
+        tokio::task::spawn_blocking(move || db.restore_from(&copy)).await??;
+        let _ = tokio::fs::remove_file(&path).await;
+
+        info!("Replicated state from {} at position {}", self.primary(), position);
+        let mut status = self.status.write();
+        status.epoch = epoch;
+        status.position = position;
+        Ok(())
+    }
+
+    async fn copy_object(&self, client: &mut InfraSimDaemonClient<AuthChannel>, digest: &str) -> anyhow::Result<()> {
+        let cas = self.state.cas();
+        let path = cas.tmp_dir().join(format!("{}.replica", digest));
+        let mut file = tokio::fs::File::create(&path).await?;
+
+        let mut stream = client
+            .read_cas_object(ReadCasObjectRequest { digest: digest.to_string() })
+            .await?
+            .into_inner();
+        while let Some(chunk) = stream.message().await? {
+            file.write_all(&chunk.data).await?;
+        }
+        file.sync_all().await?;
+        drop(file);
+
+        let actual = ContentAddressedStore::hash_file(&path).await?;
+        if actual != digest {
+            let _ = tokio::fs::remove_file(&path).await;
+            bail!("object {} arrived with digest {}", digest, actual);
+        }
+        cas.adopt_file(&path, digest).await?;
+        Ok(())
+    }
+
+    /// Stop replicating for good and mark the VMs that ran on the primary
+    /// stopped, returning their IDs
+    pub async fn promote(&self) -> Result<Vec<String>> {
+        let _sync = self.sync_lock.lock().await;
+        if !self.is_standby() {
+            return Err(Error::InvalidStateTransition {
+                from: "primary".to_string(),
+                to: "primary".to_string(),
+            });
+        }
+
+        // Survive a restart with replication.primary still configured
+        let dir = self.config.replication_dir();
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(PROMOTED_MARKER), chrono::Utc::now().to_rfc3339())?;
+        self.standby.store(false, Ordering::SeqCst);
+        self.wake.notify_one();
+
+        let mut stopped = Vec::new();
+        for vm in self.state.list_vms()? {
+            if !matches!(vm.status.state, VmState::Running | VmState::Paused | VmState::Suspended) {
+                continue;
+            }
+            let status = VmStatus {
+                state: VmState::Stopped,
+                qemu_pid: None,
+                qmp_socket: None,
+                vnc_display: None,
+                uptime_seconds: 0,
+                started_at: None,
+                ..vm.status.clone()
+            };
+            self.state.update_vm_status(&vm.meta.id, status)?;
+            self.state
+                .emit_vm_event(&vm.meta.id, "Stopped: its QEMU ran on the old primary");
+            stopped.push(vm.meta.id);
+        }
+
+        info!(
+            "Promoted from standby of {}; {} VMs marked stopped. Remove replication.primary from the config",
+            self.primary(),
+            stopped.len()
+        );
+        Ok(stopped)
+    }
+}
+
+/// gRPC service wrapper refusing mutating calls while the daemon is a
+/// standby, since the next copy from the primary would undo them
+#[derive(Clone)]
+pub struct StandbyGuard<S> {
+    inner: S,
+    standby: Arc<AtomicBool>,
+    primary: String,
+}
+
+impl<S, B> Service<http::Request<B>> for StandbyGuard<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request.uri().path().rsplit('/').next().unwrap_or_default();
+        if self.standby.load(Ordering::SeqCst)
+            && !audit::is_read(method)
+            && !STANDBY_CALLS.contains(&method)
+        {
+            let status = tonic::Status::failed_precondition(format!(
+                "this daemon is a standby of {}; make changes there, or run `infrasim daemon promote` here",
+                self.primary
+            ));
+            return Box::pin(async move { Ok(status.to_http()) });
+        }
+        Box::pin(self.inner.call(request))
+    }
+}
+
+impl<S: NamedService> NamedService for StandbyGuard<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    /// Service answering every call with an empty success
+    #[derive(Clone)]
+    struct Handler;
+
+    impl Service<http::Request<()>> for Handler {
+        type Response = http::Response<BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: http::Request<()>) -> Self::Future {
+            Box::pin(async { Ok(http::Response::new(tonic::body::empty_body())) })
+        }
+    }
+
+    async fn grpc_status(service: &mut StandbyGuard<Handler>, method: &str) -> Option<tonic::Code> {
+        let mut request = http::Request::new(());
+        *request.uri_mut() = format!("/infrasim.v1.InfraSimDaemon/{}", method).parse().unwrap();
+        let response = service.call(request).await.unwrap();
+        response
+            .headers()
+            .get("grpc-status")
+            .map(|code| tonic::Code::from_bytes(code.as_bytes()))
+    }
+
+    #[tokio::test]
+    async fn test_standby_refuses_changes() {
+        let standby = Arc::new(AtomicBool::new(true));
+        let mut service = StandbyGuard {
+            inner: Handler,
+            standby: standby.clone(),
+            primary: "https://primary:9090".to_string(),
+        };
+
+        assert_eq!(grpc_status(&mut service, "ListVMs").await, None);
+        assert_eq!(grpc_status(&mut service, "GetReplicationStatus").await, None);
+        assert_eq!(grpc_status(&mut service, "PromoteDaemon").await, None);
+        assert_eq!(
+            grpc_status(&mut service, "StartVm").await,
+            Some(tonic::Code::FailedPrecondition)
+        );
+
+        standby.store(false, Ordering::SeqCst);
+        assert_eq!(grpc_status(&mut service, "StartVm").await, None);
+    }
+}
@@ -0,0 +1,528 @@
+//! Minimal S3 client for backup targets and store remotes
+//!
+//! Requests are signed with AWS Signature Version 4 and use path-style URLs,
+//! so any S3-compatible endpoint (MinIO, Ceph RGW, GCS interoperability, ...)
+//! works. Files larger than one part are sent as multipart uploads and never
+//! held in memory whole.
+
+use crate::config::DaemonConfig;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use infrasim_common::{Error, RemoteStore, Result};
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tracing::{debug, info};
+
+/// Size of multipart upload parts; S3 needs at least 5 MiB
+const PART_SIZE: u64 = 64 * 1024 * 1024;
+
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Client for one bucket
+pub struct S3Client {
+    http: reqwest::Client,
+    endpoint: String,
+    host: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    bucket: String,
+}
+
+impl S3Client {
+    /// Client for `bucket` using the configured endpoint and environment credentials
+    pub fn new(config: &DaemonConfig, bucket: &str) -> Result<Self> {
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        let s3 = config.s3.get();
+        let region = s3
+            .region
+            .or_else(|| env("AWS_REGION"))
+            .unwrap_or_else(|| DEFAULT_REGION.to_string());
+        let endpoint = s3
+            .endpoint
+            .or_else(|| env("AWS_ENDPOINT_URL"))
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let host = endpoint
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .unwrap_or(&endpoint)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        let (Some(access_key), Some(secret_key)) =
+            (env("AWS_ACCESS_KEY_ID"), env("AWS_SECRET_ACCESS_KEY"))
+        else {
+            return Err(Error::InvalidConfig(
+                "S3 targets need AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY in the daemon's environment"
+                    .to_string(),
+            ));
+        };
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            endpoint,
+            host,
+            region,
+            access_key,
+            secret_key,
+            session_token: env("AWS_SESSION_TOKEN"),
+            bucket: bucket.to_string(),
+        })
+    }
+
+    /// Upload a file to `key`, returning its size
+    ///
+    /// A failed multipart upload is aborted, so nothing is left behind.
+    pub async fn upload(&self, key: &str, path: &Path) -> Result<u64> {
+        let size = fs::metadata(path).await?.len();
+        if size <= PART_SIZE {
+            let body = fs::read(path).await?;
+            self.send(Method::PUT, key, &[], body).await?;
+            return Ok(size);
+        }
+
+        let upload_id = self.create_upload(key).await?;
+        match self.upload_parts(key, path, size, &upload_id, &HashMap::new()).await {
+            Ok(etags) => {
+                self.complete_upload(key, &upload_id, &etags).await?;
+                Ok(size)
+            }
+            Err(e) => {
+                let _ = self
+                    .send(Method::DELETE, key, &[("uploadId", &upload_id)], Vec::new())
+                    .await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Upload a file to `key`, continuing an interrupted multipart upload of it
+    ///
+    /// Failed uploads are left in place for the next attempt, so only use
+    /// this for keys whose content never changes, like digests.
+    pub async fn upload_resumable(&self, key: &str, path: &Path) -> Result<u64> {
+        let size = fs::metadata(path).await?.len();
+        if size <= PART_SIZE {
+            return self.upload(key, path).await;
+        }
+
+        let (upload_id, done) = match self.pending_upload(key).await? {
+            Some(upload_id) => {
+                let done = self.list_parts(key, &upload_id).await?;
+                info!("Resuming upload of {} with {} parts already sent", key, done.len());
+                (upload_id, done)
+            }
+            None => (self.create_upload(key).await?, HashMap::new()),
+        };
+
+        let etags = self.upload_parts(key, path, size, &upload_id, &done).await?;
+        self.complete_upload(key, &upload_id, &etags).await?;
+        Ok(size)
+    }
+
+    async fn create_upload(&self, key: &str) -> Result<String> {
+        let response = self.send(Method::POST, key, &[("uploads", "")], Vec::new()).await?;
+        let body = response.text().await.map_err(request_error)?;
+        xml_value(&body, "UploadId")
+            .ok_or_else(|| Error::NetworkError(format!("No upload ID in response for {}", key)))
+    }
+
+    /// ID of the newest unfinished multipart upload of `key`
+    async fn pending_upload(&self, key: &str) -> Result<Option<String>> {
+        let response = self
+            .send(Method::GET, "", &[("uploads", ""), ("prefix", key)], Vec::new())
+            .await?;
+        let body = response.text().await.map_err(request_error)?;
+        Ok(xml_elements(&body, "Upload")
+            .into_iter()
+            .filter(|upload| xml_value(upload, "Key").as_deref() == Some(key))
+            .filter_map(|upload| xml_value(&upload, "UploadId"))
+            .next_back())
+    }
+
+    /// ETag and size of each part already uploaded, by part number
+    async fn list_parts(&self, key: &str, upload_id: &str) -> Result<HashMap<u64, (String, u64)>> {
+        let mut parts = HashMap::new();
+        let mut marker = String::from("0");
+
+        loop {
+            let response = self
+                .send(
+                    Method::GET,
+                    key,
+                    &[("part-number-marker", &marker), ("uploadId", upload_id)],
+                    Vec::new(),
+                )
+                .await?;
+            let body = response.text().await.map_err(request_error)?;
+
+            for part in xml_elements(&body, "Part") {
+                let number = xml_value(&part, "PartNumber").and_then(|n| n.parse().ok());
+                let size = xml_value(&part, "Size").and_then(|s| s.parse().ok());
+                if let (Some(number), Some(etag), Some(size)) = (number, xml_value(&part, "ETag"), size) {
+                    parts.insert(number, (etag.replace("&quot;", "\""), size));
+                }
+            }
+
+            match xml_value(&body, "NextPartNumberMarker") {
+                Some(next) if xml_value(&body, "IsTruncated").as_deref() == Some("true") => marker = next,
+                _ => return Ok(parts),
+            }
+        }
+    }
+
+    /// Upload the parts of `path` not in `done`, returning every part's ETag
+    async fn upload_parts(
+        &self,
+        key: &str,
+        path: &Path,
+        size: u64,
+        upload_id: &str,
+        done: &HashMap<u64, (String, u64)>,
+    ) -> Result<Vec<String>> {
+        let mut file = fs::File::open(path).await?;
+        let mut etags = Vec::new();
+
+        for number in 1..=size.div_ceil(PART_SIZE) {
+            let offset = (number - 1) * PART_SIZE;
+            let len = PART_SIZE.min(size - offset);
+            match done.get(&number) {
+                Some((etag, part_size)) if *part_size == len => {
+                    etags.push(etag.clone());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let mut part = Vec::with_capacity(len as usize);
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+            (&mut file).take(len).read_to_end(&mut part).await?;
+
+            let number = number.to_string();
+            let response = self
+                .send(
+                    Method::PUT,
+                    key,
+                    &[("partNumber", &number), ("uploadId", upload_id)],
+                    part,
+                )
+                .await?;
+            let etag = response
+                .headers()
+                .get(reqwest::header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| Error::NetworkError(format!("No ETag for part {} of {}", number, key)))?;
+            etags.push(etag.to_string());
+            debug!("Uploaded part {} of {}", number, key);
+        }
+
+        Ok(etags)
+    }
+
+    async fn complete_upload(&self, key: &str, upload_id: &str, etags: &[String]) -> Result<()> {
+        let parts: String = etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", i + 1, etag)
+            })
+            .collect();
+        let body = format!("<CompleteMultipartUpload>{}</CompleteMultipartUpload>", parts);
+        let response = self
+            .send(Method::POST, key, &[("uploadId", upload_id)], body.into_bytes())
+            .await?;
+
+        // Failures after the upload started are reported in a 200 body
+        let body = response.text().await.map_err(request_error)?;
+        if body.contains("<Error>") {
+            return Err(Error::NetworkError(format!(
+                "Completing upload of {} failed: {}",
+                key,
+                xml_value(&body, "Message").unwrap_or(body)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Download `key` into a file
+    pub async fn download(&self, key: &str, path: &Path) -> Result<()> {
+        if path.exists() {
+            fs::remove_file(path).await?;
+        }
+        self.download_from(key, path, 0).await
+    }
+
+    /// Append the bytes of `key` from `offset` on to a file
+    ///
+    /// If the endpoint ignores the range, the file is rewritten from the start.
+    pub async fn download_from(&self, key: &str, path: &Path, offset: u64) -> Result<()> {
+        let mut request = self.request(Method::GET, key, &[], Vec::new());
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = self.check(request.send().await.map_err(request_error)?, key).await?;
+
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path).await?;
+        if offset > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+            file.set_len(0).await?;
+        }
+        while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+            file.write_all(&chunk).await?;
+        }
+        file.sync_all().await?;
+        Ok(())
+    }
+
+    /// Size of `key`, `None` if it doesn't exist
+    pub async fn size(&self, key: &str) -> Result<Option<u64>> {
+        let response = self
+            .request(Method::HEAD, key, &[], Vec::new())
+            .send()
+            .await
+            .map_err(request_error)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = self.check(response, key).await?;
+        Ok(response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok()))
+    }
+
+    /// Store a small object
+    pub async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.send(Method::PUT, key, &[], data).await?;
+        Ok(())
+    }
+
+    /// Read a small object, `None` if it doesn't exist
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self
+            .request(Method::GET, key, &[], Vec::new())
+            .send()
+            .await
+            .map_err(request_error)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = self.check(response, key).await?;
+        Ok(Some(response.bytes().await.map_err(request_error)?.to_vec()))
+    }
+
+    /// Delete `key`; missing keys are not an error
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        self.send(Method::DELETE, key, &[], Vec::new()).await?;
+        Ok(())
+    }
+
+    /// Send a signed request, failing on non-success responses
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<Response> {
+        let response = self
+            .request(method, key, query, body)
+            .send()
+            .await
+            .map_err(request_error)?;
+        self.check(response, key).await
+    }
+
+    /// Turn a non-success response into an error
+    async fn check(&self, response: Response, key: &str) -> Result<Response> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Err(Error::NetworkError(format!(
+            "S3 request for s3://{}/{} failed with {}: {}",
+            self.bucket,
+            key,
+            status,
+            xml_value(&body, "Message").unwrap_or(body)
+        )))
+    }
+
+    /// Build a signed request; headers added afterwards are not signed
+    fn request(&self, method: Method, key: &str, query: &[(&str, &str)], body: Vec<u8>) -> RequestBuilder {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let path = format!("/{}/{}", self.bucket, uri_encode(key, false));
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut headers = vec![
+            ("host", self.host.clone()),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+        let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v.trim())).collect();
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, path, query, canonical_headers, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), date.as_bytes()), |key, part| {
+                hmac_sha256(&key, part.as_bytes())
+            });
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        let mut url = format!("{}{}", self.endpoint, path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
+        let mut request = self
+            .http
+            .request(method, &url)
+            .header("authorization", authorization)
+            .body(body);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+        request
+    }
+}
+
+/// A bucket prefix used as a content-addressed store remote
+pub struct S3Remote {
+    client: S3Client,
+    bucket: String,
+    prefix: String,
+}
+
+impl S3Remote {
+    pub fn new(config: &DaemonConfig, bucket: &str, prefix: &str) -> Result<Self> {
+        Ok(Self {
+            client: S3Client::new(config, bucket)?,
+            bucket: bucket.to_string(),
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn key(&self, key: &str) -> String {
+        if self.prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.prefix, key)
+        }
+    }
+}
+
+#[async_trait]
+impl RemoteStore for S3Remote {
+    fn location(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.prefix)
+    }
+
+    async fn size(&self, key: &str) -> Result<Option<u64>> {
+        self.client.size(&self.key(key)).await
+    }
+
+    async fn upload(&self, key: &str, path: &Path) -> Result<()> {
+        self.client.upload_resumable(&self.key(key), path).await?;
+        Ok(())
+    }
+
+    async fn download(&self, key: &str, dest: &Path, offset: u64) -> Result<()> {
+        self.client.download_from(&self.key(key), dest, offset).await
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        self.client.put(&self.key(key), data).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.client
+            .get(&self.key(key))
+            .await?
+            .ok_or_else(|| Error::NotFound {
+                kind: "remote object".to_string(),
+                id: format!("{}/{}", self.location(), key),
+            })
+    }
+}
+
+fn request_error(e: reqwest::Error) -> Error {
+    Error::NetworkError(format!("S3 request failed: {}", e))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode all but unreserved characters (and `/` unless `encode_slash`)
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            b'/' if !encode_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Text of the first `<tag>` element in an XML response
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    xml_elements(xml, tag).into_iter().next()
+}
+
+/// Text of every `<tag>` element in an XML response
+fn xml_elements(xml: &str, tag: &str) -> Vec<String> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let body = &rest[start + open.len()..];
+        let Some(end) = body.find(&close) else { break };
+        elements.push(body[..end].to_string());
+        rest = &body[end + close.len()..];
+    }
+    elements
+}
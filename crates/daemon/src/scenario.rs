@@ -0,0 +1,454 @@
+//! Scenario runs
+//!
+//! Runs of [`Scenario`]s execute in the background, one task per run. The
+//! run's report is written to the state database as each step starts and
+//! finishes, so clients can follow a run and compare finished ones. Chaos
+//! profiles a run injects carry a `scenario-run` label and, unless the
+//! scenario turns `cleanup` off, are removed when the run ends.
+
+use crate::admission;
+use crate::chaos;
+use crate::qemu::{QemuLauncher, StopMode};
+use crate::snapshot;
+use crate::state::StateManager;
+use crate::stats::{self, VmUsage};
+use anyhow::bail;
+use infrasim_common::{
+    scenario::{
+        Action, HealthProbe, HealthState, RunState, Scenario, ScenarioRun, ScenarioRunStatus, StepOutcome,
+        VmSample,
+    },
+    types::{Network, SnapshotSpec, Vm, VmState},
+    Error, Result,
+};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Label on the chaos profiles and snapshots a run creates
+pub const RUN_LABEL: &str = "scenario-run";
+
+/// Time a single health probe may take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time between attempts of a waiting assertion
+const ASSERT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Starts scenario runs and cancels them
+#[derive(Clone)]
+pub struct ScenarioRunner {
+    state: StateManager,
+    qemu: Arc<QemuLauncher>,
+    /// Cancellation tokens of running runs, keyed by run ID
+    running: Arc<Mutex<HashMap<String, CancellationToken>>>,
+}
+
+impl ScenarioRunner {
+    pub fn new(state: StateManager) -> Self {
+        Self {
+            qemu: Arc::new(QemuLauncher::new(state.config().clone())),
+            state,
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Parse a scenario, check that the VMs and networks it names exist, and
+    /// start running it
+    pub fn start(&self, definition: &str, labels: HashMap<String, String>) -> Result<ScenarioRun> {
+        let scenario = Scenario::from_yaml(definition)?;
+        for reference in scenario.vm_refs() {
+            find_vm(&self.state, reference)?;
+        }
+        for step in &scenario.steps {
+            if let Action::InjectFault { network: Some(network), .. } = &step.action {
+                find_network(&self.state, network)?;
+            }
+        }
+
+        let run = self.state.create_scenario_run(scenario, labels)?;
+        let token = CancellationToken::new();
+        self.running.lock().insert(run.meta.id.clone(), token.clone());
+        info!("Starting scenario run {}", run.meta.name);
+
+        let runner = self.clone();
+        let started = run.clone();
+        tokio::spawn(async move {
+            let id = run.meta.id.clone();
+            runner.execute(run, token).await;
+            runner.running.lock().remove(&id);
+        });
+        Ok(started)
+    }
+
+    /// Stop a run between steps, or abandon the step it's in
+    pub fn cancel(&self, id: &str) -> Result<ScenarioRun> {
+        let run = self.state.get_scenario_run(id)?.ok_or_else(|| Error::NotFound {
+            kind: "scenario_run".to_string(),
+            id: id.to_string(),
+        })?;
+        match self.running.lock().get(id) {
+            Some(token) => token.cancel(),
+            None => {
+                return Err(Error::InvalidStateTransition {
+                    from: run.status.state.as_str().to_string(),
+                    to: RunState::Cancelled.as_str().to_string(),
+                })
+            }
+        }
+        info!("Cancelling scenario run {}", run.meta.name);
+        Ok(run)
+    }
+
+    /// Whether a run is still going
+    pub fn is_running(&self, id: &str) -> bool {
+        self.running.lock().contains_key(id)
+    }
+
+    async fn execute(&self, mut run: ScenarioRun, token: CancellationToken) {
+        let scenario = run.spec.scenario.clone();
+        let started = Instant::now();
+        let mut ctx = RunContext {
+            state: &self.state,
+            qemu: &self.qemu,
+            run_id: run.meta.id.clone(),
+            run_name: run.meta.name.clone(),
+            injected: Vec::new(),
+            usage: HashMap::new(),
+        };
+
+        run.status.state = RunState::Running;
+        run.status.started_at = Some(chrono::Utc::now().timestamp());
+        ctx.write(&run.status);
+
+        let mut failure: Option<String> = None;
+        let mut cancelled = false;
+        for (idx, step) in scenario.steps.iter().enumerate() {
+            if cancelled || (failure.is_some() && !scenario.continue_on_failure) {
+                run.status.steps[idx].outcome = StepOutcome::Skipped;
+                continue;
+            }
+
+            let wait = match (step.at, step.after) {
+                (Some(at), _) => at.saturating_sub(started.elapsed()),
+                (None, Some(after)) => after,
+                (None, None) => Duration::ZERO,
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = token.cancelled() => {
+                    cancelled = true;
+                    run.status.steps[idx].outcome = StepOutcome::Skipped;
+                    continue;
+                }
+            }
+
+            let report = &mut run.status.steps[idx];
+            report.outcome = StepOutcome::Running;
+            report.started_ms = Some(started.elapsed().as_millis() as u64);
+            ctx.write(&run.status);
+
+            let step_started = Instant::now();
+            let outcome = tokio::select! {
+                result = ctx.run_step(&step.action, &run.status.steps[idx].name) => Some(result),
+                _ = token.cancelled() => None,
+            };
+            let stats = ctx.sample(&scenario).await;
+
+            let report = &mut run.status.steps[idx];
+            report.duration_ms = step_started.elapsed().as_millis() as u64;
+            report.stats = stats;
+            match outcome {
+                Some(Ok(detail)) => {
+                    report.outcome = StepOutcome::Passed;
+                    report.detail = detail;
+                }
+                Some(Err(e)) => {
+                    warn!("Scenario run {} step {} failed: {:#}", run.meta.name, report.name, e);
+                    report.outcome = StepOutcome::Failed;
+                    report.detail = format!("{:#}", e);
+                    failure.get_or_insert_with(|| format!("step {} failed: {:#}", report.name, e));
+                }
+                None => {
+                    cancelled = true;
+                    report.outcome = StepOutcome::Failed;
+                    report.detail = "cancelled".to_string();
+                }
+            }
+            ctx.write(&run.status);
+        }
+
+        if scenario.cleanup {
+            ctx.cleanup();
+        }
+
+        run.status.finished_at = Some(chrono::Utc::now().timestamp());
+        run.status.state = if cancelled {
+            RunState::Cancelled
+        } else if failure.is_some() {
+            RunState::Failed
+        } else {
+            RunState::Passed
+        };
+        run.status.error = failure;
+        info!("Scenario run {} {}", run.meta.name, run.status.state.as_str());
+        ctx.write(&run.status);
+    }
+}
+
+/// Mark runs that were going when the daemon stopped as failed
+pub fn recover(state: &StateManager) -> Result<()> {
+    for mut run in state.list_scenario_runs(None)? {
+        if run.status.state.is_finished() {
+            continue;
+        }
+        run.status.state = RunState::Failed;
+        run.status.error = Some("interrupted by daemon restart".to_string());
+        run.status.finished_at = Some(chrono::Utc::now().timestamp());
+        for step in &mut run.status.steps {
+            if matches!(step.outcome, StepOutcome::Pending | StepOutcome::Running) {
+                step.outcome = StepOutcome::Skipped;
+            }
+        }
+        state.update_scenario_run_status(&run.meta.id, &run.status)?;
+    }
+    Ok(())
+}
+
+/// What a run keeps track of between steps
+struct RunContext<'a> {
+    state: &'a StateManager,
+    qemu: &'a QemuLauncher,
+    run_id: String,
+    run_name: String,
+    /// Names of the chaos profiles the run applied
+    injected: Vec<String>,
+    /// Last usage sample of each VM, for CPU figures between steps
+    usage: HashMap<String, VmUsage>,
+}
+
+impl RunContext<'_> {
+    fn write(&self, status: &ScenarioRunStatus) {
+        if let Err(e) = self.state.update_scenario_run_status(&self.run_id, status) {
+            warn!("Failed to record progress of scenario run {}: {}", self.run_name, e);
+        }
+    }
+
+    fn labels(&self) -> HashMap<String, String> {
+        HashMap::from([(RUN_LABEL.to_string(), self.run_id.clone())])
+    }
+
+    /// Run one step, returning what it did
+    async fn run_step(&mut self, action: &Action, step_name: &str) -> anyhow::Result<String> {
+        let state = self.state;
+        match action {
+            Action::StartVm { vm } => {
+                let mut vm = find_vm(state, vm)?;
+                if state.get_vm_process(&vm.meta.id).is_some() {
+                    return Ok(format!("{} already running", vm.meta.name));
+                }
+                if !admission::holds_resources(&vm) {
+                    let config = state.config();
+                    let host = admission::HostCapacity::probe(&config.store_path);
+                    let committed = admission::Committed::of(&state.list_vms()?, &vm.meta.id);
+                    admission::check_start(&config.admission.get(), &host, committed, &vm.spec)?;
+                }
+                vm.status.state = VmState::Running;
+                state.update_vm_status(&vm.meta.id, vm.status.clone())?;
+                let process = self.qemu.start(state, &vm).await?;
+                Ok(format!("started {} (PID {})", vm.meta.name, process.pid))
+            }
+            Action::StopVm { vm, force } => {
+                let vm = find_vm(state, vm)?;
+                let mode = if *force { StopMode::Hard } else { StopMode::Acpi };
+                self.qemu.stop(state, &vm.meta.id, mode, None).await?;
+                Ok(format!("stopped {}", vm.meta.name))
+            }
+            Action::InjectFault { profile, ttl, .. } => {
+                let mut spec = action.chaos_spec().unwrap_or_default();
+                if let Some(network) = &spec.network_id {
+                    spec.network_id = Some(find_network(state, network)?.meta.id);
+                }
+                if let Some(vm) = &spec.vm_id {
+                    spec.vm_id = Some(find_vm(state, vm)?.meta.id);
+                }
+                for group in &mut spec.partitions {
+                    for vm in group.iter_mut() {
+                        *vm = find_vm(state, vm)?.meta.id;
+                    }
+                }
+                spec.expires_at = ttl.map(|ttl| chrono::Utc::now().timestamp() + ttl.as_secs().max(1) as i64);
+
+                let applied = chaos::upsert(state, profile.clone(), spec, self.labels())?;
+                if !self.injected.contains(profile) {
+                    self.injected.push(profile.clone());
+                }
+                Ok(format!(
+                    "{} applied to {} VMs, pending on {}",
+                    profile,
+                    applied.status.applied_vm_ids.len(),
+                    applied.status.pending_vm_ids.len()
+                ))
+            }
+            Action::ClearFault { profile } => {
+                let Some(current) = state.get_chaos_profile_by_name(profile)? else {
+                    bail!("chaos profile {} doesn't exist", profile);
+                };
+                state.delete_chaos_profile(&current.meta.id)?;
+                chaos::remove(state, &current)?;
+                self.injected.retain(|name| name != profile);
+                Ok(format!("{} removed", profile))
+            }
+            Action::Snapshot {
+                vm,
+                snapshot_name,
+                memory,
+            } => {
+                let vm = find_vm(state, vm)?;
+                let name = snapshot_name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}-{}", self.run_name, step_name));
+                let spec = SnapshotSpec {
+                    vm_id: vm.meta.id.clone(),
+                    include_memory: *memory,
+                    include_disk: true,
+                    description: Some(format!("Scenario run {}", self.run_name)),
+                };
+                let snapshot = snapshot::take(state, self.qemu, name, spec, self.labels()).await?;
+                Ok(format!("snapshot {} of {}", snapshot.meta.name, vm.meta.name))
+            }
+            Action::AssertHealth { check, expect, timeout } => {
+                let deadline = Instant::now() + timeout.unwrap_or_default();
+                loop {
+                    let observed = match probe(check).await {
+                        Ok(()) => HealthState::Healthy,
+                        Err(e) if *expect == HealthState::Healthy && Instant::now() >= deadline => {
+                            bail!("{} is unhealthy: {}", check, e)
+                        }
+                        Err(_) => HealthState::Unhealthy,
+                    };
+                    if observed == *expect {
+                        return Ok(format!("{} is {}", check, observed.as_str()));
+                    }
+                    if Instant::now() >= deadline {
+                        bail!("{} is {}, expected {}", check, observed.as_str(), expect.as_str());
+                    }
+                    tokio::time::sleep(ASSERT_INTERVAL).await;
+                }
+            }
+            Action::AssertVmState {
+                vm,
+                state: expected,
+                timeout,
+            } => {
+                let deadline = Instant::now() + timeout.unwrap_or_default();
+                loop {
+                    let vm = find_vm(state, vm)?;
+                    if vm.status.state == *expected {
+                        return Ok(format!("{} is {}", vm.meta.name, expected));
+                    }
+                    if Instant::now() >= deadline {
+                        bail!("{} is {}, expected {}", vm.meta.name, vm.status.state, expected);
+                    }
+                    tokio::time::sleep(ASSERT_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// Usage of the scenario's running VMs, by VM name
+    async fn sample(&mut self, scenario: &Scenario) -> std::collections::BTreeMap<String, VmSample> {
+        let mut samples = std::collections::BTreeMap::new();
+        for reference in scenario.vm_refs() {
+            let Ok(vm) = find_vm(self.state, reference) else {
+                continue;
+            };
+            let Some(process) = self.state.get_vm_process(&vm.meta.id) else {
+                continue;
+            };
+            let usage = match stats::sample(self.state, &process, self.usage.get(&vm.meta.id)).await {
+                Ok(usage) => usage,
+                Err(e) => {
+                    warn!("Failed to sample {} for scenario run {}: {}", vm.meta.name, self.run_name, e);
+                    continue;
+                }
+            };
+            samples.insert(
+                vm.meta.name.clone(),
+                VmSample {
+                    cpu_percent: usage.cpu_percent,
+                    rss_bytes: usage.rss_bytes,
+                    rx_bytes: usage.network.map(|(rx, _)| rx),
+                    tx_bytes: usage.network.map(|(_, tx)| tx),
+                },
+            );
+            self.usage.insert(vm.meta.id, usage);
+        }
+        samples
+    }
+
+    /// Remove the chaos profiles the run applied
+    fn cleanup(&mut self) {
+        for name in self.injected.drain(..) {
+            let removed = match self.state.get_chaos_profile_by_name(&name) {
+                Ok(Some(profile)) => self
+                    .state
+                    .delete_chaos_profile(&profile.meta.id)
+                    .and_then(|_| chaos::remove(self.state, &profile)),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = removed {
+                warn!("Failed to remove chaos profile {} of scenario run {}: {}", name, self.run_name, e);
+            }
+        }
+    }
+}
+
+/// Run a health probe once
+async fn probe(check: &HealthProbe) -> anyhow::Result<()> {
+    match check {
+        HealthProbe::Tcp { host, port } => {
+            tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect((host.as_str(), *port)))
+                .await
+                .map_err(|_| anyhow::anyhow!("connect timed out"))??;
+            Ok(())
+        }
+        HealthProbe::Http { url, expect_status } => {
+            let client = reqwest::Client::builder().timeout(PROBE_TIMEOUT).build()?;
+            let status = client.get(url).send().await?.status();
+            if status.as_u16() != *expect_status {
+                bail!("status {}, expected {}", status.as_u16(), expect_status);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Find a VM by ID or name
+fn find_vm(state: &StateManager, reference: &str) -> Result<Vm> {
+    if let Some(vm) = state.get_vm(reference)? {
+        return Ok(vm);
+    }
+    state.get_vm_by_name(reference)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: reference.to_string(),
+    })
+}
+
+/// Find a network by ID or name
+fn find_network(state: &StateManager, reference: &str) -> Result<Network> {
+    if let Some(network) = state.get_network(reference)? {
+        return Ok(network);
+    }
+    state
+        .list_networks()?
+        .into_iter()
+        .find(|network| network.meta.name == reference)
+        .ok_or_else(|| Error::NotFound {
+            kind: "network".to_string(),
+            id: reference.to_string(),
+        })
+}
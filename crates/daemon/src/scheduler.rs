@@ -0,0 +1,319 @@
+//! Scheduled snapshots and backups
+//!
+//! Takes snapshots or backups on each policy's interval and prunes the
+//! oldest policy snapshots or backup chains beyond its retention count.
+//! A policy targets one VM, or every VM matching its label selector at the
+//! time it runs; retention then applies to each VM separately.
+
+use crate::backup;
+use crate::qemu::QemuLauncher;
+use crate::snapshot;
+use crate::state::StateManager;
+use infrasim_common::{types::*, Error, Result};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use tracing::{debug, info, warn};
+
+/// Shortest interval a policy may use
+pub const MIN_INTERVAL_SECS: u64 = 60;
+
+/// When a snapshot policy should next run; None while paused
+pub fn next_run_at(policy: &SnapshotPolicy) -> Option<i64> {
+    if policy.spec.paused {
+        return None;
+    }
+
+    Some(match policy.status.last_run_at {
+        Some(last) => last + policy.spec.interval_secs as i64,
+        None => policy.meta.created_at,
+    })
+}
+
+/// VMs a policy covers: its own VM, or every VM its selector currently matches
+fn targets(state: &StateManager, vm_id: &str, selector: &HashMap<String, String>) -> Result<Vec<String>> {
+    if !vm_id.is_empty() {
+        return Ok(vec![vm_id.to_string()]);
+    }
+    if selector.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(state
+        .list_vms()?
+        .into_iter()
+        .filter(|vm| vm.meta.matches(selector))
+        .map(|vm| vm.meta.id)
+        .collect())
+}
+
+/// Error text for a policy's status; selector policies say which VM failed
+fn describe(policy_vm_id: &str, vm_id: &str, error: impl std::fmt::Display) -> String {
+    if policy_vm_id.is_empty() {
+        format!("VM {}: {}", vm_id, error)
+    } else {
+        error.to_string()
+    }
+}
+
+/// Take policy snapshots, prune expired ones and record the outcome
+pub async fn run(state: &StateManager, qemu: &QemuLauncher, policy: &SnapshotPolicy) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let mut status = SnapshotPolicyStatus {
+        last_run_at: Some(now),
+        last_error: None,
+        retained: 0,
+        ..policy.status.clone()
+    };
+
+    for vm_id in targets(state, &policy.spec.vm_id, &policy.spec.vm_selector)? {
+        match take(state, qemu, policy, &vm_id, now).await {
+            Ok(snapshot) => {
+                info!(
+                    "Policy {} took snapshot {}",
+                    policy.meta.name, snapshot.meta.name
+                );
+                status.last_snapshot_id = Some(snapshot.meta.id);
+            }
+            Err(e) => {
+                warn!(
+                    "Scheduled snapshot of VM {} for policy {} failed: {}",
+                    vm_id, policy.meta.name, e
+                );
+                state.emit_vm_event(
+                    &vm_id,
+                    format!("Scheduled snapshot ({}) failed: {}", policy.meta.name, e),
+                );
+                state.emit_reconcile_error(
+                    "snapshot_policy",
+                    &policy.meta.id,
+                    &policy.meta.name,
+                    &e,
+                );
+                status
+                    .last_error
+                    .get_or_insert_with(|| describe(&policy.spec.vm_id, &vm_id, &e));
+            }
+        }
+
+        match prune(state, policy, &vm_id).await {
+            Ok(retained) => status.retained += retained,
+            Err(e) => {
+                warn!(
+                    "Pruning snapshots of VM {} for policy {} failed: {}",
+                    vm_id, policy.meta.name, e
+                );
+                status.last_error.get_or_insert_with(|| {
+                    describe(&policy.spec.vm_id, &vm_id, format!("pruning failed: {}", e))
+                });
+            }
+        }
+    }
+
+    // The policy may have been deleted while the snapshot was taken
+    if state.get_snapshot_policy(&policy.meta.id)?.is_some() {
+        state.update_snapshot_policy_status(&policy.meta.id, status)?;
+    }
+
+    Ok(())
+}
+
+async fn take(
+    state: &StateManager,
+    qemu: &QemuLauncher,
+    policy: &SnapshotPolicy,
+    vm_id: &str,
+    now: i64,
+) -> Result<Snapshot> {
+    let vm = state.get_vm(vm_id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: vm_id.to_string(),
+    })?;
+
+    // Memory can only be captured from a running VM
+    let running = state.get_vm_process(&vm.meta.id).is_some();
+    let spec = SnapshotSpec {
+        vm_id: vm.meta.id.clone(),
+        include_memory: policy.spec.include_memory && running,
+        include_disk: true,
+        description: Some(format!(
+            "Scheduled by {} ({})",
+            policy.meta.name,
+            policy.spec.schedule()
+        )),
+    };
+
+    let timestamp = chrono::DateTime::from_timestamp(now, 0)
+        .map(|dt| dt.format("%Y%m%d-%H%M%S").to_string())
+        .unwrap_or_else(|| now.to_string());
+    let name = policy_resource_name(&policy.meta.name, &policy.spec.vm_id, &vm.meta.name, &timestamp);
+    let labels = HashMap::from([(SNAPSHOT_POLICY_LABEL.to_string(), policy.meta.id.clone())]);
+
+    snapshot::take(state, qemu, name, spec, labels).await
+}
+
+/// Name for a snapshot or backup; selector policies include the VM's name
+fn policy_resource_name(policy_name: &str, policy_vm_id: &str, vm_name: &str, timestamp: &str) -> String {
+    if policy_vm_id.is_empty() {
+        format!("{}-{}-{}", policy_name, vm_name, timestamp)
+    } else {
+        format!("{}-{}", policy_name, timestamp)
+    }
+}
+
+/// Delete the policy's oldest snapshots of a VM beyond `keep`, returning how many remain
+async fn prune(state: &StateManager, policy: &SnapshotPolicy, vm_id: &str) -> Result<u32> {
+    let mut taken: Vec<Snapshot> = state
+        .list_snapshots(Some(vm_id))?
+        .into_iter()
+        .filter(|s| s.meta.labels.get(SNAPSHOT_POLICY_LABEL) == Some(&policy.meta.id))
+        .collect();
+    taken.sort_by_key(|s| Reverse(s.meta.created_at));
+
+    let keep = policy.spec.keep as usize;
+    let mut retained = taken.len().min(keep);
+
+    for expired in taken.iter().skip(keep).rev() {
+        if let Some(reason) = snapshot::deletion_blocker(state, &expired.meta.id)? {
+            debug!("Keeping expired snapshot {}: {}", expired.meta.name, reason);
+            retained += 1;
+            continue;
+        }
+
+        snapshot::delete(state, &expired.meta.id).await?;
+        info!(
+            "Policy {} pruned snapshot {}",
+            policy.meta.name, expired.meta.name
+        );
+    }
+
+    Ok(retained as u32)
+}
+
+/// When a backup policy should next run; None while paused
+pub fn next_backup_at(policy: &BackupPolicy) -> Option<i64> {
+    if policy.spec.paused {
+        return None;
+    }
+
+    Some(match policy.status.last_run_at {
+        Some(last) => last + policy.spec.interval_secs as i64,
+        None => policy.meta.created_at,
+    })
+}
+
+/// Take policy backups, prune expired chains and record the outcome
+pub async fn run_backup(state: &StateManager, policy: &BackupPolicy) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let mut status = BackupPolicyStatus {
+        last_run_at: Some(now),
+        last_error: None,
+        retained: 0,
+        ..policy.status.clone()
+    };
+
+    for vm_id in targets(state, &policy.spec.vm_id, &policy.spec.vm_selector)? {
+        match take_backup(state, policy, &vm_id, now).await {
+            Ok(backup) => {
+                info!(
+                    "Policy {} took {:?} backup {}",
+                    policy.meta.name, backup.status.kind, backup.meta.name
+                );
+                status.last_backup_id = Some(backup.meta.id);
+            }
+            Err(e) => {
+                warn!(
+                    "Scheduled backup of VM {} for policy {} failed: {}",
+                    vm_id, policy.meta.name, e
+                );
+                state.emit_vm_event(
+                    &vm_id,
+                    format!("Scheduled backup ({}) failed: {}", policy.meta.name, e),
+                );
+                state.emit_reconcile_error("backup_policy", &policy.meta.id, &policy.meta.name, &e);
+                status
+                    .last_error
+                    .get_or_insert_with(|| describe(&policy.spec.vm_id, &vm_id, &e));
+            }
+        }
+
+        match prune_backups(state, policy, &vm_id).await {
+            Ok(retained) => status.retained += retained,
+            Err(e) => {
+                warn!(
+                    "Pruning backups of VM {} for policy {} failed: {}",
+                    vm_id, policy.meta.name, e
+                );
+                status.last_error.get_or_insert_with(|| {
+                    describe(&policy.spec.vm_id, &vm_id, format!("pruning failed: {}", e))
+                });
+            }
+        }
+    }
+
+    // The policy may have been deleted while the backup ran
+    if state.get_backup_policy(&policy.meta.id)?.is_some() {
+        state.update_backup_policy_status(&policy.meta.id, status)?;
+    }
+
+    Ok(())
+}
+
+async fn take_backup(state: &StateManager, policy: &BackupPolicy, vm_id: &str, now: i64) -> Result<Backup> {
+    let vm = state.get_vm(vm_id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: vm_id.to_string(),
+    })?;
+
+    // Start a new chain once the current one has `full_every` incremental backups
+    let incremental = match backup::latest(state, vm_id)? {
+        Some(latest) if policy.spec.full_every > 0 && latest.status.complete => {
+            let chain = backup::chain(state, &latest)?;
+            let incrementals = chain
+                .iter()
+                .filter(|b| b.status.kind == BackupKind::Incremental)
+                .count();
+            incrementals < policy.spec.full_every as usize
+        }
+        _ => true,
+    };
+
+    let spec = BackupSpec {
+        vm_id: vm.meta.id.clone(),
+        target: policy.spec.target.clone(),
+        incremental,
+    };
+
+    let timestamp = chrono::DateTime::from_timestamp(now, 0)
+        .map(|dt| dt.format("%Y%m%d-%H%M%S").to_string())
+        .unwrap_or_else(|| now.to_string());
+    let name = policy_resource_name(&policy.meta.name, &policy.spec.vm_id, &vm.meta.name, &timestamp);
+    let labels = HashMap::from([(BACKUP_POLICY_LABEL.to_string(), policy.meta.id.clone())]);
+
+    backup::create(state, name, spec, labels).await
+}
+
+/// Delete the policy's oldest backup chains of a VM beyond `keep`, returning how many remain
+async fn prune_backups(state: &StateManager, policy: &BackupPolicy, vm_id: &str) -> Result<u32> {
+    let taken: Vec<Backup> = state
+        .list_backups(Some(vm_id))?
+        .into_iter()
+        .filter(|b| b.meta.labels.get(BACKUP_POLICY_LABEL) == Some(&policy.meta.id))
+        .collect();
+
+    let expired = expired_backups(&taken, policy.spec.keep as usize);
+    let mut retained = taken.len() - expired.len();
+
+    // Newest first, so incremental backups go before the ones they build on
+    for id in expired.iter().rev() {
+        if let Some(reason) = backup::deletion_blocker(state, id)? {
+            debug!("Keeping expired backup {}: {}", id, reason);
+            retained += 1;
+            continue;
+        }
+
+        backup::delete(state, id).await?;
+        info!("Policy {} pruned backup {}", policy.meta.name, id);
+    }
+
+    Ok(retained as u32)
+}
@@ -0,0 +1,261 @@
+//! Shared folders between host and guest
+//!
+//! Folders go over virtiofs where the host has virtiofsd and over 9p
+//! otherwise. virtiofs needs the guest RAM in shared memory, which QEMU only
+//! has as memfd on Linux, so macOS hosts always use 9p. Every virtiofs folder
+//! gets its own virtiofsd on a vhost-user socket; it exits when QEMU closes
+//! the socket, so only a failed start has to stop it.
+
+use crate::config::DaemonConfig;
+use infrasim_common::{
+    types::{SharedFolder, SharedFolderProtocol, Vm, VmSpec},
+    Error, Result,
+};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Where distributions install virtiofsd outside `PATH`
+const VIRTIOFSD_PATHS: &[&str] = &[
+    "/usr/libexec/virtiofsd",
+    "/usr/lib/qemu/virtiofsd",
+    "/usr/lib/virtiofsd",
+];
+
+/// How long to wait for virtiofsd to create its socket
+const VIRTIOFSD_START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ID of the shared memory backend virtiofs folders need
+const SHARED_RAM_ID: &str = "shared-ram";
+
+/// A folder being shared with a VM that is starting
+pub struct Share {
+    folder: SharedFolder,
+    socket: Option<PathBuf>,
+    virtiofsd: Option<Child>,
+}
+
+/// virtiofsd binary: the configured one, else from `PATH` or libexec
+pub fn virtiofsd_binary(config: &DaemonConfig) -> Option<PathBuf> {
+    if let Some(path) = config.qemu.get().virtiofsd_path {
+        return Some(PathBuf::from(path));
+    }
+    let in_path = Command::new("which")
+        .arg("virtiofsd")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()));
+    in_path.or_else(|| {
+        VIRTIOFSD_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| path.is_file())
+    })
+}
+
+/// Protocol a folder is shared over on this host
+pub fn protocol(config: &DaemonConfig, folder: &SharedFolder) -> SharedFolderProtocol {
+    match folder.protocol {
+        SharedFolderProtocol::Auto
+            if cfg!(target_os = "linux") && virtiofsd_binary(config).is_some() =>
+        {
+            SharedFolderProtocol::Virtiofs
+        }
+        SharedFolderProtocol::Auto => SharedFolderProtocol::NineP,
+        protocol => protocol,
+    }
+}
+
+/// Whether any of a VM's folders go over virtiofs
+pub fn uses_virtiofs(config: &DaemonConfig, vm: &Vm) -> bool {
+    vm.spec
+        .shared_folders
+        .iter()
+        .any(|f| protocol(config, f) == SharedFolderProtocol::Virtiofs)
+}
+
+/// Check a VM's folders can be shared on this host
+pub fn check(config: &DaemonConfig, spec: &VmSpec) -> Result<()> {
+    spec.validate_shared_folders()?;
+    if spec.shared_folders.is_empty() {
+        return Ok(());
+    }
+    if spec.compatibility_mode {
+        return Err(Error::InvalidConfig(
+            "Shared folders need the virt machine; compatibility mode has no PCI bus".to_string(),
+        ));
+    }
+    for folder in &spec.shared_folders {
+        if !Path::new(&folder.host_path).is_dir() {
+            return Err(Error::InvalidConfig(format!(
+                "Shared folder {}: {} is not a directory on the host",
+                folder.tag, folder.host_path
+            )));
+        }
+        if folder.protocol == SharedFolderProtocol::Virtiofs {
+            if !cfg!(target_os = "linux") {
+                return Err(Error::InvalidConfig(format!(
+                    "Shared folder {}: virtiofs needs a Linux host; use 9p",
+                    folder.tag
+                )));
+            }
+            if virtiofsd_binary(config).is_none() {
+                return Err(Error::InvalidConfig(format!(
+                    "Shared folder {}: virtiofs needs virtiofsd on the host",
+                    folder.tag
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Start virtiofsd for each of a VM's virtiofs folders
+///
+/// Already started ones are stopped again if one fails.
+pub fn start(config: &DaemonConfig, vm: &Vm) -> Result<Vec<Share>> {
+    let mut shares = Vec::new();
+    for folder in &vm.spec.shared_folders {
+        let protocol = protocol(config, folder);
+        let mut share = Share {
+            folder: folder.clone(),
+            socket: None,
+            virtiofsd: None,
+        };
+        if protocol == SharedFolderProtocol::Virtiofs {
+            if let Err(e) = start_virtiofsd(config, vm, &mut share) {
+                stop(&mut shares);
+                return Err(e);
+            }
+        }
+        shares.push(share);
+    }
+    Ok(shares)
+}
+
+fn start_virtiofsd(config: &DaemonConfig, vm: &Vm, share: &mut Share) -> Result<()> {
+    let binary = virtiofsd_binary(config).ok_or_else(|| {
+        Error::InvalidConfig("virtiofs needs virtiofsd on the host".to_string())
+    })?;
+    let socket = config.virtiofs_socket_path(&vm.meta.id, &share.folder.tag);
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if socket.exists() {
+        std::fs::remove_file(&socket)?;
+    }
+
+    let mut command = Command::new(&binary);
+    command
+        .arg(format!("--socket-path={}", socket.display()))
+        .arg(format!("--shared-dir={}", share.folder.host_path))
+        .arg(format!("--cache={}", share.folder.cache.as_str()));
+    if share.folder.read_only {
+        command.arg("--readonly");
+    }
+    // Namespaces need root; unprivileged daemons share without a sandbox
+    if !nix::unistd::Uid::effective().is_root() {
+        command.arg("--sandbox=none");
+    }
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| Error::Qemu(format!("Failed to spawn {}: {}", binary.display(), e)))?;
+    share.virtiofsd = Some(child);
+
+    let deadline = std::time::Instant::now() + VIRTIOFSD_START_TIMEOUT;
+    while !socket.exists() {
+        if let Some(Ok(Some(status))) = share.virtiofsd.as_mut().map(Child::try_wait) {
+            share.virtiofsd = None;
+            return Err(Error::Qemu(format!(
+                "virtiofsd for shared folder {} exited during startup: {}",
+                share.folder.tag, status
+            )));
+        }
+        if std::time::Instant::now() > deadline {
+            stop(std::slice::from_mut(share));
+            return Err(Error::Timeout {
+                seconds: VIRTIOFSD_START_TIMEOUT.as_secs(),
+            });
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    info!(
+        "Sharing {} with VM {} over virtiofs as {}",
+        share.folder.host_path, vm.meta.name, share.folder.tag
+    );
+    share.socket = Some(socket);
+    Ok(())
+}
+
+/// Stop the virtiofsd processes of a start that failed
+pub fn stop(shares: &mut [Share]) {
+    for share in shares {
+        if let Some(mut child) = share.virtiofsd.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Some(socket) = share.socket.take() {
+            let _ = std::fs::remove_file(socket);
+        }
+    }
+}
+
+/// Leave the virtiofsd processes to QEMU, reaping them once it lets go
+pub fn detach(shares: Vec<Share>) {
+    for share in shares {
+        if let Some(mut child) = share.virtiofsd {
+            let tag = share.folder.tag;
+            std::thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => {
+                    warn!("virtiofsd for shared folder {} exited: {}", tag, status)
+                }
+                _ => {}
+            });
+        }
+    }
+}
+
+/// QEMU arguments sharing the folders, for a VM with `memory_mb` of boot RAM
+pub fn qemu_args(shares: &[Share], memory_mb: u64) -> Vec<String> {
+    let mut args = Vec::new();
+    if shares.iter().any(|s| s.socket.is_some()) {
+        // vhost-user backends map the guest RAM, so it has to be shareable
+        args.extend([
+            "-object".to_string(),
+            format!(
+                "memory-backend-memfd,id={},size={}M,share=on",
+                SHARED_RAM_ID, memory_mb
+            ),
+            "-machine".to_string(),
+            format!("memory-backend={}", SHARED_RAM_ID),
+        ]);
+    }
+    for (idx, share) in shares.iter().enumerate() {
+        let folder = &share.folder;
+        match &share.socket {
+            Some(socket) => args.extend([
+                "-chardev".to_string(),
+                format!("socket,id=fs{},path={}", idx, socket.display()),
+                "-device".to_string(),
+                format!("vhost-user-fs-pci,chardev=fs{},tag={}", idx, folder.tag),
+            ]),
+            None => args.extend([
+                "-fsdev".to_string(),
+                format!(
+                    "local,id=fsdev{},path={},security_model=none{}",
+                    idx,
+                    folder.host_path,
+                    if folder.read_only { ",readonly=on" } else { "" }
+                ),
+                "-device".to_string(),
+                format!("virtio-9p-pci,fsdev=fsdev{},mount_tag={}", idx, folder.tag),
+            ]),
+        }
+    }
+    args
+}
@@ -0,0 +1,944 @@
+//! Snapshot disk layers
+//!
+//! Disk snapshots are external qcow2 layers: taking a snapshot freezes the
+//! boot disk's active image and continues writes in a new overlay backed by
+//! it. Reverting and branching start a fresh overlay on top of a frozen
+//! layer, so every snapshot stays a valid point in the backing-file chain.
+//! Long chains slow down disk reads; flattening copies a volume's chain into
+//! a standalone image without touching the snapshot layers. Layers of
+//! encrypted volumes are encrypted with the volume's key.
+
+use crate::encryption::{self, Keyring};
+use crate::qemu::{QemuLauncher, StopMode};
+use crate::state::StateManager;
+use crate::vtpm;
+use infrasim_common::{attestation::vtpm::{default_pcrs, random_nonce}, qmp::QmpClient, types::*, Error, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+use tracing::{debug, info, warn};
+
+/// Block device name of the boot drive (`-drive id=boot`)
+const BOOT_DEVICE: &str = "boot";
+
+/// How often a layer merge job is polled for completion
+const STREAM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Create a snapshot of a VM, freezing its disk and dumping memory as requested
+///
+/// The new snapshot becomes the parent of the VM's next one.
+pub async fn take(
+    state: &StateManager,
+    qemu: &QemuLauncher,
+    name: String,
+    spec: SnapshotSpec,
+    labels: HashMap<String, String>,
+) -> Result<Snapshot> {
+    let vm = get_vm(state, &spec.vm_id)?;
+    let mut snapshot = state.create_snapshot(name, spec, labels)?;
+    snapshot.status.parent_id = vm.status.current_snapshot_id.clone();
+
+    if snapshot.spec.include_disk {
+        snapshot.status.disk_snapshot_path = Some(freeze_disk(state, &vm).await?);
+
+        // Record the layer before anything else can fail so the chain stays consistent
+        state.update_snapshot_status(&snapshot.meta.id, snapshot.status.clone())?;
+        let mut vm = get_vm(state, &vm.meta.id)?;
+        vm.status.current_snapshot_id = Some(snapshot.meta.id.clone());
+        state.update_vm_status(&vm.meta.id, vm.status)?;
+    }
+
+    if snapshot.spec.include_memory {
+        let run_dir = state.cas().create_run(&snapshot.meta.id).await?;
+        let mem_path = run_dir.join("snapshot.mem");
+        qemu.create_memory_snapshot(state, &vm.meta.id, &mem_path)
+            .await?;
+        snapshot.status.memory_snapshot_path = Some(mem_path.to_string_lossy().to_string());
+    }
+
+    snapshot.status.complete = snapshot.status.disk_snapshot_path.is_some()
+        || snapshot.status.memory_snapshot_path.is_some();
+    // Memory dumps are written in the clear
+    snapshot.status.encrypted = snapshot.status.disk_snapshot_path.is_some()
+        && snapshot.status.memory_snapshot_path.is_none()
+        && boot_volume(state, &vm)?.spec.encryption.is_some();
+    state.update_snapshot_status(&snapshot.meta.id, snapshot.status.clone())?;
+
+    // Tie the snapshot to the measured state of the guest it captured
+    if let Some(tpm) = state.get_vtpm(&vm.meta.id) {
+        let label = format!("snapshot:{}", snapshot.meta.id);
+        let recorded = match tpm.quote(&label, random_nonce(), default_pcrs()).await {
+            Ok(quote) => vtpm::save_quote(state.config(), &quote),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = recorded {
+            warn!("No vTPM quote for snapshot {}: {}", snapshot.meta.id, e);
+            state.emit_vm_event(&vm.meta.id, format!("vTPM quote for snapshot {} failed: {}", snapshot.meta.name, e));
+        }
+    }
+
+    Ok(snapshot)
+}
+
+/// Freeze the VM's boot disk, returning the path of the frozen layer
+///
+/// Running VMs switch to the new overlay through QMP without a restart.
+pub async fn freeze_disk(state: &StateManager, vm: &Vm) -> Result<String> {
+    let volume = boot_volume(state, vm)?;
+    let encryption = volume.spec.encryption.as_ref();
+    let frozen = active_path(&volume)?;
+    let overlay = new_layer_path(state, &vm.meta.id).await?;
+
+    match (state.get_vm_process(&vm.meta.id), encryption) {
+        // QEMU would create an unencrypted overlay, so encrypted ones are made here
+        (Some(process), Some(enc)) => {
+            create_layer(state, &frozen, &overlay, encryption).await?;
+            let qmp = QmpClient::new(&process.qmp_socket);
+            qmp.connect().await?;
+            if let Err(e) = switch_to_overlay(&qmp, &overlay, enc).await {
+                let _ = fs::remove_file(&overlay).await;
+                return Err(e);
+            }
+        }
+        (Some(process), None) => {
+            let qmp = QmpClient::new(&process.qmp_socket);
+            qmp.connect().await?;
+            qmp.blockdev_snapshot_sync(BOOT_DEVICE, &overlay.to_string_lossy())
+                .await?;
+        }
+        (None, _) => create_layer(state, &frozen, &overlay, encryption).await?,
+    }
+
+    set_active(state, &volume, &overlay)?;
+    info!(
+        "Froze disk of VM {} at {}, writes continue in {}",
+        vm.meta.name,
+        frozen.display(),
+        overlay.display()
+    );
+
+    Ok(frozen.to_string_lossy().to_string())
+}
+
+/// Point the VM's boot disk at a fresh overlay on the snapshot's layer
+///
+/// A running VM is restarted on the reverted disk; memory state is not restored.
+/// A suspended VM's saved RAM no longer matches the disk and is discarded.
+pub async fn revert(
+    state: &StateManager,
+    qemu: &QemuLauncher,
+    vm: &Vm,
+    snapshot: &Snapshot,
+) -> Result<Vm> {
+    let layer = disk_layer(snapshot)?;
+    let volume = boot_volume(state, vm)?;
+    let old_active = active_path(&volume)?;
+
+    let was_running = state.get_vm_process(&vm.meta.id).is_some();
+    if was_running {
+        qemu.stop(state, &vm.meta.id, StopMode::Hard, None).await?;
+    }
+
+    let overlay = new_layer_path(state, &vm.meta.id).await?;
+    create_layer(state, layer, &overlay, volume.spec.encryption.as_ref()).await?;
+    set_active(state, &volume, &overlay)?;
+
+    // The previous overlay only held writes made since the last snapshot
+    if is_scratch_layer(state, &vm.meta.id, &old_active)? {
+        debug!("Removing discarded overlay {}", old_active.display());
+        let _ = fs::remove_file(&old_active).await;
+    }
+
+    let mut vm = get_vm(state, &vm.meta.id)?;
+    vm.status.current_snapshot_id = Some(snapshot.meta.id.clone());
+    if vm.status.state == VmState::Suspended {
+        qemu.discard_suspended(state, &vm.meta.id).await?;
+        vm.status.state = VmState::Stopped;
+    }
+    if was_running {
+        vm.status.state = VmState::Running;
+    }
+    state.update_vm_status(&vm.meta.id, vm.status.clone())?;
+    state.emit_vm_event(
+        &vm.meta.id,
+        format!("Reverted to snapshot {}", snapshot.meta.name),
+    );
+
+    if was_running {
+        qemu.start(state, &vm).await?;
+    }
+
+    get_vm(state, &vm.meta.id)
+}
+
+/// Create a stopped VM whose boot disk forks from the snapshot's layer
+///
+/// The branch shares the source VM's networks and read-only volumes; writable
+/// data volumes are not cloned.
+pub async fn branch(
+    state: &StateManager,
+    source: &Vm,
+    snapshot: &Snapshot,
+    name: String,
+) -> Result<Vm> {
+    let layer = disk_layer(snapshot)?;
+    if state.get_vm_by_name(&name)?.is_some() {
+        return Err(Error::AlreadyExists {
+            kind: "vm".to_string(),
+            id: name,
+        });
+    }
+
+    let source_volume = boot_volume(state, source)?;
+    let volume_spec = VolumeSpec {
+        source: layer.to_string_lossy().to_string(),
+        integrity: IntegrityConfig::default(),
+        read_only: false,
+        format: "qcow2".to_string(),
+        overlay: true,
+        build: None,
+        ..source_volume.spec.clone()
+    };
+    let volume = state.create_volume(format!("{}-boot", name), volume_spec, HashMap::new())?;
+
+    let mut volume_ids = vec![volume.meta.id.clone()];
+    for id in &source.spec.volume_ids {
+        if Some(id) == source.spec.boot_disk_id.as_ref() {
+            continue;
+        }
+        if state.get_volume(id)?.is_some_and(|v| v.spec.read_only) {
+            volume_ids.push(id.clone());
+        }
+    }
+
+    let spec = VmSpec {
+        volume_ids,
+        boot_disk_id: Some(volume.meta.id.clone()),
+        template: false,
+        cloned_from: None,
+        ..source.spec.clone().without_pinned_addresses()
+    };
+    let vm = state.create_vm(name, spec, source.meta.labels.clone())?;
+
+    let overlay = new_layer_path(state, &vm.meta.id).await?;
+    create_layer(state, layer, &overlay, volume.spec.encryption.as_ref()).await?;
+    state.update_volume_status(
+        &volume.meta.id,
+        VolumeStatus {
+            ready: true,
+            local_path: Some(overlay.to_string_lossy().to_string()),
+            actual_size: fs::metadata(&overlay).await?.len(),
+            ..Default::default()
+        },
+    )?;
+
+    let status = VmStatus {
+        state: VmState::Stopped,
+        current_snapshot_id: Some(snapshot.meta.id.clone()),
+        ..Default::default()
+    };
+    state.update_vm_status(&vm.meta.id, status)?;
+    state.emit_vm_event(
+        &vm.meta.id,
+        format!(
+            "Branched from snapshot {} of VM {}",
+            snapshot.meta.name, source.meta.name
+        ),
+    );
+    info!(
+        "Branched VM {} from snapshot {}",
+        vm.meta.name, snapshot.meta.name
+    );
+
+    get_vm(state, &vm.meta.id)
+}
+
+/// Why a snapshot cannot be deleted, if its layer can't be merged away
+pub fn deletion_blocker(state: &StateManager, snapshot_id: &str) -> Result<Option<String>> {
+    let snapshot = get_snapshot(state, snapshot_id)?;
+    if snapshot.status.disk_snapshot_path.is_none() {
+        return Ok(None);
+    }
+
+    let snapshots = state.list_snapshots(None)?;
+    let dependents = dependents(state, &snapshot, &snapshots)?;
+    Ok(match dependents.as_slice() {
+        [] => None,
+        [dependent] if dependent.running.len() > 1 => Some(format!(
+            "its disk layer is in use by running VMs {}",
+            dependent.running.join(", ")
+        )),
+        [_] => None,
+        many => Some(format!(
+            "{} branches are based on it; delete or revert them first",
+            many.len()
+        )),
+    })
+}
+
+/// Delete a snapshot, folding its disk layer into the image built on it
+///
+/// Child snapshots and VMs based on the snapshot are re-parented to its parent.
+pub async fn delete(state: &StateManager, snapshot_id: &str) -> Result<()> {
+    let snapshot = get_snapshot(state, snapshot_id)?;
+    let snapshots = state.list_snapshots(None)?;
+    let id = Some(&snapshot.meta.id);
+
+    if let Some(layer) = snapshot.status.disk_snapshot_path.as_deref() {
+        match dependents(state, &snapshot, &snapshots)?.as_slice() {
+            [] => {}
+            [dependent] => detach(state, dependent, Path::new(layer)).await?,
+            _ => {
+                return Err(Error::SnapshotError(format!(
+                    "Snapshot {} has several branches based on it",
+                    snapshot.meta.name
+                )))
+            }
+        }
+    }
+
+    for child in snapshots
+        .iter()
+        .filter(|s| s.status.parent_id.as_ref() == id)
+    {
+        let status = SnapshotStatus {
+            parent_id: snapshot.status.parent_id.clone(),
+            ..child.status.clone()
+        };
+        state.update_snapshot_status(&child.meta.id, status)?;
+    }
+
+    for mut vm in state.list_vms()? {
+        if vm.status.current_snapshot_id.as_ref() == id {
+            vm.status.current_snapshot_id = snapshot.status.parent_id.clone();
+            state.update_vm_status(&vm.meta.id, vm.status)?;
+        }
+    }
+
+    state.delete_snapshot(&snapshot.meta.id)?;
+
+    // The first layer of a chain is the volume's original image
+    if let Some(layer) = snapshot.status.disk_snapshot_path.as_deref() {
+        if Path::new(layer).starts_with(state.config().snapshot_dir(&snapshot.spec.vm_id)) {
+            let _ = fs::remove_file(layer).await;
+        }
+    }
+
+    info!("Deleted snapshot {}", snapshot.meta.name);
+    Ok(())
+}
+
+/// Image built directly on a snapshot's disk layer
+struct Dependent {
+    image: PathBuf,
+    /// IDs of running VMs with the image in their backing chain
+    running: Vec<String>,
+}
+
+/// Child snapshot layers and VM disks whose backing file is the snapshot's layer
+fn dependents(
+    state: &StateManager,
+    snapshot: &Snapshot,
+    snapshots: &[Snapshot],
+) -> Result<Vec<Dependent>> {
+    let id = Some(&snapshot.meta.id);
+    let parents: HashMap<&str, Option<&String>> = snapshots
+        .iter()
+        .map(|s| (s.meta.id.as_str(), s.status.parent_id.as_ref()))
+        .collect();
+    let running: Vec<Vm> = state
+        .list_vms()?
+        .into_iter()
+        .filter(|vm| state.get_vm_process(&vm.meta.id).is_some())
+        .collect();
+
+    let mut dependents = Vec::new();
+
+    for child in snapshots
+        .iter()
+        .filter(|s| s.status.parent_id.as_ref() == id)
+    {
+        let Some(layer) = child.status.disk_snapshot_path.as_deref() else {
+            continue;
+        };
+        dependents.push(Dependent {
+            image: PathBuf::from(layer),
+            running: running
+                .iter()
+                .filter(|vm| {
+                    descends_from(
+                        &parents,
+                        vm.status.current_snapshot_id.as_ref(),
+                        &child.meta.id,
+                    )
+                })
+                .map(|vm| vm.meta.id.clone())
+                .collect(),
+        });
+    }
+
+    for vm in state.list_vms()? {
+        if vm.status.current_snapshot_id.as_ref() != id {
+            continue;
+        }
+        dependents.push(Dependent {
+            image: active_path(&boot_volume(state, &vm)?)?,
+            running: running
+                .iter()
+                .filter(|r| r.meta.id == vm.meta.id)
+                .map(|r| r.meta.id.clone())
+                .collect(),
+        });
+    }
+
+    Ok(dependents)
+}
+
+/// Whether `ancestor` is `head` or one of its parents
+fn descends_from(
+    parents: &HashMap<&str, Option<&String>>,
+    head: Option<&String>,
+    ancestor: &str,
+) -> bool {
+    let mut current = head;
+    // Bounded walk in case of a corrupted (cyclic) tree
+    for _ in 0..=parents.len() {
+        match current {
+            Some(id) if id == ancestor => return true,
+            Some(id) => current = parents.get(id.as_str()).copied().flatten(),
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Copy the layer's data into the dependent image and rebase it onto the layer's backing file
+async fn detach(state: &StateManager, dependent: &Dependent, layer: &Path) -> Result<()> {
+    // Flattened images are standalone and no longer read from the layer
+    if backing_file(&dependent.image)?.is_none() {
+        return Ok(());
+    }
+
+    if !dependent.image.starts_with(state.config().snapshot_root()) {
+        return Err(Error::SnapshotError(format!(
+            "Refusing to rewrite {} outside the snapshot store",
+            dependent.image.display()
+        )));
+    }
+
+    match dependent.running.as_slice() {
+        // An offline rebase would need every layer's key on the command line
+        [] if is_encrypted(&dependent.image)? => Err(Error::SnapshotError(format!(
+            "{} is encrypted; start the VM using it to merge the layer",
+            dependent.image.display()
+        ))),
+        [] => rebase(&dependent.image, backing_file(layer)?.as_deref()),
+        [vm_id] => stream(state, vm_id, &dependent.image, layer).await,
+        _ => Err(Error::SnapshotError(format!(
+            "{} is in use by several running VMs",
+            dependent.image.display()
+        ))),
+    }
+}
+
+/// Merge the layer below a running VM's image with a QMP block-stream job
+async fn stream(state: &StateManager, vm_id: &str, image: &Path, layer: &Path) -> Result<()> {
+    let process = state
+        .get_vm_process(vm_id)
+        .ok_or_else(|| Error::Qemu("VM not running".to_string()))?;
+    let qmp = QmpClient::new(&process.qmp_socket);
+    qmp.connect().await?;
+
+    let nodes = qmp.query_named_block_nodes().await?;
+    let find = |path: &Path| {
+        let path = path.to_string_lossy();
+        nodes
+            .iter()
+            .find(|n| n.drv == "qcow2" && n.file == path)
+            .cloned()
+    };
+    let node = find(image).ok_or_else(|| {
+        Error::SnapshotError(format!("{} is not open in VM {}", image.display(), vm_id))
+    })?;
+    let base = find(layer).and_then(|n| n.backing_file);
+
+    let job_id = format!("stream-{}", uuid::Uuid::new_v4().simple());
+    qmp.block_stream(&job_id, &node.node_name, base.as_deref())
+        .await?;
+
+    loop {
+        tokio::time::sleep(STREAM_POLL_INTERVAL).await;
+        let Some(job) = qmp.query_jobs().await?.into_iter().find(|j| j.id == job_id) else {
+            return Ok(());
+        };
+        if job.status == "concluded" {
+            qmp.job_dismiss(&job_id).await?;
+            return match job.error {
+                Some(e) => Err(Error::SnapshotError(format!(
+                    "Merging snapshot layer failed: {}",
+                    e
+                ))),
+                None => Ok(()),
+            };
+        }
+    }
+}
+
+/// One image in a volume's backing-file chain
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub path: String,
+    pub format: String,
+    pub virtual_size: u64,
+    /// Bytes allocated on the host
+    pub actual_size: u64,
+    /// Snapshot that froze this layer
+    pub snapshot_id: Option<String>,
+}
+
+/// A volume's backing-file chain, active image first
+pub fn chain(state: &StateManager, volume: &Volume) -> Result<Vec<Layer>> {
+    let images = chain_info(&active_path(volume)?)?;
+    let snapshots = state.list_snapshots(None)?;
+
+    Ok(images
+        .iter()
+        .map(|info| {
+            let path = info["filename"].as_str().unwrap_or_default().to_string();
+            Layer {
+                format: info["format"].as_str().unwrap_or_default().to_string(),
+                virtual_size: info["virtual-size"].as_u64().unwrap_or(0),
+                actual_size: info["actual-size"].as_u64().unwrap_or(0),
+                snapshot_id: snapshots
+                    .iter()
+                    .find(|s| s.status.disk_snapshot_path.as_deref() == Some(path.as_str()))
+                    .map(|s| s.meta.id.clone()),
+                path,
+            }
+        })
+        .collect())
+}
+
+/// Running VM with the volume attached, if any
+pub fn running_user(state: &StateManager, volume: &Volume) -> Result<Option<Vm>> {
+    let id = Some(&volume.meta.id);
+    Ok(state.list_vms()?.into_iter().find(|vm| {
+        (vm.spec.volume_ids.contains(&volume.meta.id) || vm.spec.boot_disk_id.as_ref() == id)
+            && state.get_vm_process(&vm.meta.id).is_some()
+    }))
+}
+
+/// Copy a volume's backing chain into a standalone qcow2 image and make it active
+///
+/// Backing layers are left in place so snapshots of the volume stay valid.
+/// `progress` receives the percentage copied as `qemu-img` reports it.
+pub async fn flatten(
+    state: &StateManager,
+    volume: &Volume,
+    mut progress: impl FnMut(f64),
+) -> Result<PathBuf> {
+    let source = active_path(volume)?;
+    if backing_file(&source)?.is_none() {
+        return Err(Error::VolumeError(format!(
+            "Volume {} has no backing chain to flatten",
+            volume.meta.name
+        )));
+    }
+
+    let dir = state.config().volume_dir(&volume.meta.id);
+    fs::create_dir_all(&dir).await?;
+    let flat = dir.join(format!("flat-{}.qcow2", uuid::Uuid::new_v4()));
+
+    let encryption = volume.spec.encryption.as_ref();
+    if let Err(e) = convert(state, &source, &flat, encryption, &mut progress).await {
+        let _ = fs::remove_file(&flat).await;
+        return Err(e);
+    }
+
+    // A VM started during the copy has written to the old image
+    let volume = state.get_volume(&volume.meta.id)?.ok_or_else(|| Error::NotFound {
+        kind: "volume".to_string(),
+        id: volume.meta.id.clone(),
+    })?;
+    if let Some(vm) = running_user(state, &volume)? {
+        let _ = fs::remove_file(&flat).await;
+        return Err(Error::VolumeError(format!(
+            "VM {} started while volume {} was being flattened",
+            vm.meta.name, volume.meta.name
+        )));
+    }
+
+    set_active(state, &volume, &flat)?;
+    info!(
+        "Flattened volume {} from {} into {}",
+        volume.meta.name,
+        source.display(),
+        flat.display()
+    );
+
+    // The old active image only held writes since the last snapshot
+    let daemon_owned =
+        source.starts_with(&dir) || source.starts_with(state.config().snapshot_root());
+    if daemon_owned && !is_snapshot_layer(state, &source)? {
+        debug!("Removing flattened overlay {}", source.display());
+        let _ = fs::remove_file(&source).await;
+    }
+
+    Ok(flat)
+}
+
+/// Copy an image and its backing chain into a standalone qcow2, reporting progress
+///
+/// The copy of an encrypted image is encrypted with the same key.
+pub async fn convert(
+    state: &StateManager,
+    source: &Path,
+    dest: &Path,
+    encryption: Option<&VolumeEncryption>,
+    progress: &mut impl FnMut(f64),
+) -> Result<()> {
+    let mut args = vec!["convert".to_string(), "-p".to_string()];
+    match encryption {
+        Some(enc) => {
+            args.extend(Keyring::new(state.config()).secret_args([enc])?);
+            args.extend([
+                "--image-opts".to_string(),
+                "-O".to_string(),
+                "qcow2".to_string(),
+                "-o".to_string(),
+                encryption::create_options(enc),
+                encryption::image_opts(source, enc)?,
+            ]);
+        }
+        None => args.extend([
+            "-f".to_string(),
+            image_format(source)?,
+            "-O".to_string(),
+            "qcow2".to_string(),
+            source.to_string_lossy().to_string(),
+        ]),
+    }
+    args.push(dest.to_string_lossy().to_string());
+
+    let mut child = tokio::process::Command::new("qemu-img")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::VolumeError(format!("qemu-img failed: {}", e)))?;
+
+    // `-p` redraws "    (12.34/100%)" followed by a carriage return
+    if let Some(mut stdout) = child.stdout.take() {
+        let mut buf = [0u8; 256];
+        let mut pending = String::new();
+        loop {
+            let n = stdout.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            pending.push_str(&String::from_utf8_lossy(&buf[..n]));
+            while let Some(end) = pending.find(['\r', '\n']) {
+                let line: String = pending.drain(..=end).collect();
+                if let Some(percent) = parse_progress(&line) {
+                    progress(percent);
+                }
+            }
+        }
+    }
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| Error::VolumeError(format!("qemu-img failed: {}", e)))?;
+    if !output.status.success() {
+        return Err(Error::VolumeError(format!(
+            "qemu-img convert failed for {}: {}",
+            source.display(),
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Percentage from a `qemu-img -p` progress line
+fn parse_progress(line: &str) -> Option<f64> {
+    line.trim()
+        .strip_prefix('(')?
+        .split('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn get_snapshot(state: &StateManager, snapshot_id: &str) -> Result<Snapshot> {
+    state
+        .get_snapshot(snapshot_id)?
+        .ok_or_else(|| Error::NotFound {
+            kind: "snapshot".to_string(),
+            id: snapshot_id.to_string(),
+        })
+}
+
+fn get_vm(state: &StateManager, vm_id: &str) -> Result<Vm> {
+    state.get_vm(vm_id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: vm_id.to_string(),
+    })
+}
+
+fn boot_volume(state: &StateManager, vm: &Vm) -> Result<Volume> {
+    let id = vm
+        .spec
+        .boot_disk_id
+        .as_ref()
+        .ok_or_else(|| Error::SnapshotError(format!("VM {} has no boot disk", vm.meta.name)))?;
+    let volume = state.get_volume(id)?.ok_or_else(|| Error::NotFound {
+        kind: "volume".to_string(),
+        id: id.clone(),
+    })?;
+
+    if volume.spec.kind == VolumeKind::Device {
+        return Err(Error::SnapshotError(format!(
+            "Disk snapshots are not supported for device volume {}",
+            volume.meta.name
+        )));
+    }
+
+    Ok(volume)
+}
+
+/// Active image of a ready volume
+pub fn active_path(volume: &Volume) -> Result<PathBuf> {
+    volume
+        .status
+        .local_path
+        .as_ref()
+        .filter(|_| volume.status.ready)
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::SnapshotError(format!("Volume {} is not ready", volume.meta.name)))
+}
+
+fn disk_layer(snapshot: &Snapshot) -> Result<&Path> {
+    snapshot
+        .status
+        .disk_snapshot_path
+        .as_deref()
+        .map(Path::new)
+        .ok_or_else(|| {
+            Error::SnapshotError(format!(
+                "Snapshot {} does not include a disk layer",
+                snapshot.meta.name
+            ))
+        })
+}
+
+/// Record a new active image for the volume; layers are always qcow2
+fn set_active(state: &StateManager, volume: &Volume, path: &Path) -> Result<()> {
+    if volume.spec.format != "qcow2" {
+        let spec = VolumeSpec {
+            format: "qcow2".to_string(),
+            ..volume.spec.clone()
+        };
+        state.update_volume_spec(&volume.meta.id, spec)?;
+    }
+
+    let status = VolumeStatus {
+        local_path: Some(path.to_string_lossy().to_string()),
+        ..volume.status.clone()
+    };
+    state.update_volume_status(&volume.meta.id, status)
+}
+
+/// Whether a VM's active image can be discarded: daemon-created and not frozen
+fn is_scratch_layer(state: &StateManager, vm_id: &str, path: &Path) -> Result<bool> {
+    Ok(path.starts_with(state.config().snapshot_dir(vm_id)) && !is_snapshot_layer(state, path)?)
+}
+
+/// Whether a snapshot froze the image as its disk layer
+fn is_snapshot_layer(state: &StateManager, path: &Path) -> Result<bool> {
+    let path = path.to_string_lossy();
+    Ok(state
+        .list_snapshots(None)?
+        .iter()
+        .any(|s| s.status.disk_snapshot_path.as_deref() == Some(path.as_ref())))
+}
+
+/// Fresh path for a layer of one of a VM's disks
+pub async fn new_layer_path(state: &StateManager, vm_id: &str) -> Result<PathBuf> {
+    let dir = state.config().snapshot_dir(vm_id);
+    fs::create_dir_all(&dir).await?;
+    Ok(dir.join(format!("{}.qcow2", uuid::Uuid::new_v4())))
+}
+
+/// Create a qcow2 overlay backed by `backing`, keeping the backing format
+///
+/// Overlays of encrypted volumes are encrypted with the volume's key. The
+/// backing image is then left unopened, as reading it would need the key too.
+pub async fn create_layer(
+    state: &StateManager,
+    backing: &Path,
+    overlay: &Path,
+    encryption: Option<&VolumeEncryption>,
+) -> Result<()> {
+    let info = image_info(backing)?;
+    let backing_format = info["format"].as_str().ok_or_else(|| {
+        Error::SnapshotError(format!("Unknown image format for {}", backing.display()))
+    })?;
+
+    let mut args = vec![
+        "create".to_string(),
+        "-f".to_string(),
+        "qcow2".to_string(),
+        "-b".to_string(),
+        backing.to_string_lossy().to_string(),
+        "-F".to_string(),
+        backing_format.to_string(),
+    ];
+    if let Some(enc) = encryption {
+        args.extend(Keyring::new(state.config()).secret_args([enc])?);
+        args.extend([
+            "-u".to_string(),
+            "-o".to_string(),
+            encryption::create_options(enc),
+        ]);
+    }
+    args.push(overlay.to_string_lossy().to_string());
+    if encryption.is_some() {
+        args.push(info["virtual-size"].as_u64().unwrap_or(0).to_string());
+    }
+
+    let output = Command::new("qemu-img")
+        .args(&args)
+        .output()
+        .map_err(|e| Error::SnapshotError(format!("qemu-img failed: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::SnapshotError(format!(
+            "qemu-img failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Open an encrypted overlay in a running VM and move the boot drive's writes to it
+async fn switch_to_overlay(qmp: &QmpClient, overlay: &Path, enc: &VolumeEncryption) -> Result<()> {
+    let node = format!("layer-{}", uuid::Uuid::new_v4().simple());
+    qmp.blockdev_add(serde_json::json!({
+        "node-name": node,
+        "driver": "qcow2",
+        "file": { "driver": "file", "filename": overlay.to_string_lossy() },
+        "encrypt": { "format": "luks", "key-secret": encryption::secret_id(enc) },
+        "backing": null,
+    }))
+    .await?;
+
+    if let Err(e) = qmp.blockdev_snapshot(BOOT_DEVICE, &node).await {
+        let _ = qmp.blockdev_del(&node).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Rebase an image onto `backing` (standalone if None), copying any data that differs
+fn rebase(image: &Path, backing: Option<&str>) -> Result<()> {
+    let mut args = vec![
+        "rebase".to_string(),
+        "-f".to_string(),
+        "qcow2".to_string(),
+        "-b".to_string(),
+        backing.unwrap_or_default().to_string(),
+    ];
+    if let Some(backing) = backing {
+        args.extend(["-F".to_string(), image_format(Path::new(backing))?]);
+    }
+    args.push(image.to_string_lossy().to_string());
+
+    let output = Command::new("qemu-img")
+        .args(&args)
+        .output()
+        .map_err(|e| Error::SnapshotError(format!("qemu-img failed: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::SnapshotError(format!(
+            "qemu-img rebase failed for {}: {}",
+            image.display(),
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+/// Backing file of an image, if any
+fn backing_file(path: &Path) -> Result<Option<String>> {
+    let info = image_info(path)?;
+    Ok(info["full-backing-filename"]
+        .as_str()
+        .or_else(|| info["backing-filename"].as_str())
+        .map(str::to_string))
+}
+
+/// Whether an image has an encryption header
+fn is_encrypted(path: &Path) -> Result<bool> {
+    Ok(image_info(path)?["encrypted"].as_bool().unwrap_or(false))
+}
+
+/// Detect an image's format with `qemu-img info`
+fn image_format(path: &Path) -> Result<String> {
+    image_info(path)?["format"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| Error::SnapshotError(format!("Unknown image format for {}", path.display())))
+}
+
+/// Paths of an image and its backing files, the image first
+pub fn backing_chain(path: &Path) -> Result<Vec<PathBuf>> {
+    Ok(chain_info(path)?
+        .iter()
+        .filter_map(|info| info["filename"].as_str().map(PathBuf::from))
+        .collect())
+}
+
+/// `qemu-img info` of each image in the backing chain, the image first
+fn chain_info(path: &Path) -> Result<Vec<serde_json::Value>> {
+    Ok(match qemu_img_info(path, true)? {
+        serde_json::Value::Array(images) => images,
+        image => vec![image],
+    })
+}
+
+/// `qemu-img info` as JSON; shared locking so images open in QEMU can be inspected
+pub fn image_info(path: &Path) -> Result<serde_json::Value> {
+    qemu_img_info(path, false)
+}
+
+/// `qemu-img info`, as an array with one entry per layer when `backing_chain` is set
+fn qemu_img_info(path: &Path, backing_chain: bool) -> Result<serde_json::Value> {
+    let mut args = vec!["info", "-U", "--output=json"];
+    if backing_chain {
+        args.push("--backing-chain");
+    }
+    let path_arg = path.to_string_lossy();
+    args.push(path_arg.as_ref());
+
+    let output = Command::new("qemu-img")
+        .args(&args)
+        .output()
+        .map_err(|e| Error::SnapshotError(format!("qemu-img failed: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::SnapshotError(format!(
+            "qemu-img info failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
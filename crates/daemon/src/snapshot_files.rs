@@ -0,0 +1,250 @@
+//! Files inside snapshot disk layers
+//!
+//! Snapshot layers are opened read-only with the libguestfs tools, which
+//! boot a small appliance to read the guest filesystems, so nothing on the
+//! host mounts guest data. `virt-ls` lists files, `virt-cat` streams one,
+//! and two listings with checksums give a file-level diff.
+
+use infrasim_common::{types::*, Error, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::{Child, Command};
+
+use crate::state::StateManager;
+
+/// A file as listed by `virt-ls`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuestFile {
+    pub path: String,
+    pub kind: &'static str,
+    pub mode: u32,
+    pub size: u64,
+    pub modified_at: i64,
+    pub sha256: Option<String>,
+    pub link_target: Option<String>,
+}
+
+/// How a file differs between two snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A file that differs, as it was in each snapshot
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub change: Change,
+    pub before: Option<GuestFile>,
+    pub after: Option<GuestFile>,
+}
+
+/// A snapshot's frozen disk layer, if it can be browsed
+pub fn layer(state: &StateManager, snapshot_id: &str) -> Result<(Snapshot, PathBuf)> {
+    let snapshot = state
+        .get_snapshot(snapshot_id)?
+        .ok_or_else(|| Error::NotFound {
+            kind: "snapshot".to_string(),
+            id: snapshot_id.to_string(),
+        })?;
+    // libguestfs would need the volume key
+    if snapshot.status.encrypted {
+        return Err(Error::SnapshotError(format!(
+            "Snapshot {} is encrypted and can't be browsed",
+            snapshot.meta.name
+        )));
+    }
+    let path = snapshot
+        .status
+        .disk_snapshot_path
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            Error::SnapshotError(format!(
+                "Snapshot {} does not include a disk layer",
+                snapshot.meta.name
+            ))
+        })?;
+    Ok((snapshot, path))
+}
+
+/// Files under `dir` in a disk image
+pub async fn list(
+    image: &Path,
+    dir: &str,
+    recursive: bool,
+    checksums: bool,
+) -> Result<Vec<GuestFile>> {
+    let dir = guest_path(dir)?;
+    let mut command = Command::new("virt-ls");
+    command
+        .args(["--format=qcow2", "-a"])
+        .arg(image)
+        .args(["--long", "--times", "--time-t", "--csv"]);
+    if recursive {
+        command.arg("--recursive");
+    }
+    if checksums {
+        command.arg("--checksum=sha256");
+    }
+    command.arg(&dir);
+
+    let output = command.output().await.map_err(|e| {
+        Error::SnapshotError(format!("virt-ls failed (is libguestfs installed?): {}", e))
+    })?;
+    if !output.status.success() {
+        return Err(tool_error("virt-ls", &dir, &output.stderr));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_line(line, checksums))
+        .collect()
+}
+
+/// Start `virt-cat` on a file; its stdout carries the contents
+pub fn read(image: &Path, file: &str) -> Result<Child> {
+    let file = guest_path(file)?;
+    Command::new("virt-cat")
+        .args(["--format=qcow2", "-a"])
+        .arg(image)
+        .arg(&file)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            Error::SnapshotError(format!("virt-cat failed (is libguestfs installed?): {}", e))
+        })
+}
+
+/// Error for a libguestfs tool that exited unsuccessfully
+pub fn tool_error(tool: &str, path: &str, stderr: &[u8]) -> Error {
+    let message = String::from_utf8_lossy(stderr).trim().to_string();
+    if message.contains("No such file or directory") {
+        return Error::NotFound {
+            kind: "file".to_string(),
+            id: path.to_string(),
+        };
+    }
+    Error::SnapshotError(format!("{} {}: {}", tool, path, message))
+}
+
+/// Files that were added, removed or changed from `before` to `after`
+///
+/// Directories only change with their type or mode; regular files also with
+/// their size or checksum, and links with their target. Times are ignored,
+/// since reading a file can update them.
+pub fn diff(before: Vec<GuestFile>, after: Vec<GuestFile>) -> Vec<FileChange> {
+    let mut before: BTreeMap<String, GuestFile> =
+        before.into_iter().map(|f| (f.path.clone(), f)).collect();
+    let mut changes = Vec::new();
+
+    for file in after {
+        match before.remove(&file.path) {
+            None => changes.push(FileChange {
+                path: file.path.clone(),
+                change: Change::Added,
+                before: None,
+                after: Some(file),
+            }),
+            Some(old) if differs(&old, &file) => changes.push(FileChange {
+                path: file.path.clone(),
+                change: Change::Modified,
+                before: Some(old),
+                after: Some(file),
+            }),
+            Some(_) => {}
+        }
+    }
+    changes.extend(before.into_values().map(|old| FileChange {
+        path: old.path.clone(),
+        change: Change::Removed,
+        before: Some(old),
+        after: None,
+    }));
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn differs(a: &GuestFile, b: &GuestFile) -> bool {
+    a.kind != b.kind
+        || a.mode != b.mode
+        || a.link_target != b.link_target
+        || (a.kind == "file" && (a.size != b.size || a.sha256 != b.sha256))
+}
+
+/// Absolute guest path without `..`, so tools only see plain paths
+fn guest_path(path: &str) -> Result<String> {
+    let path = if path.is_empty() { "/" } else { path };
+    if !path.starts_with('/') || path.split('/').any(|segment| segment == "..") {
+        return Err(Error::InvalidConfig(format!(
+            "Guest path must be absolute without '..': {}",
+            path
+        )));
+    }
+    Ok(path.to_string())
+}
+
+/// Parse a `virt-ls --long --times --time-t --csv` line
+///
+/// Fields are type, permissions, size, atime, mtime, ctime, the checksum of
+/// regular files when asked for, the path and the target of links.
+fn parse_line(line: &str, checksums: bool) -> Result<GuestFile> {
+    let fields = split_csv(line);
+    let malformed = || Error::SnapshotError(format!("Unexpected virt-ls output: {}", line));
+    if fields.len() < 7 {
+        return Err(malformed());
+    }
+
+    let kind = match fields[0].as_str() {
+        "-" => "file",
+        "d" => "directory",
+        "l" => "symlink",
+        _ => "other",
+    };
+    let mut rest = fields[6..].iter();
+    let sha256 = if checksums && kind == "file" {
+        rest.next().cloned()
+    } else {
+        None
+    };
+    let path = rest.next().cloned().ok_or_else(malformed)?;
+
+    Ok(GuestFile {
+        path,
+        kind,
+        mode: u32::from_str_radix(&fields[1], 8).map_err(|_| malformed())?,
+        size: fields[2].parse().map_err(|_| malformed())?,
+        modified_at: fields[4].parse().map_err(|_| malformed())?,
+        sha256,
+        link_target: rest.next().cloned(),
+    })
+}
+
+/// Split a CSV line, honouring double-quoted fields
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
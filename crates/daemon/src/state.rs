@@ -1,17 +1,36 @@
 //! State management for the daemon
 
 use crate::config::DaemonConfig;
+use crate::qos::QosLink;
+use crate::vtpm::Vtpm;
 use infrasim_common::{
     cas::ContentAddressedStore,
     crypto::KeyPair,
     db::{Database, ResourceRow},
+    provenance::{ProvenanceLedger, CHANGE_CREATED, CHANGE_DELETED, CHANGE_UPDATED},
+    scenario::{Scenario, ScenarioRun, ScenarioRunSpec, ScenarioRunStatus},
+    secrets::{MasterKey, SecretStore},
+    traffic_shaper::FaultInjection,
     types::*,
     Error, Result,
 };
 use parking_lot::RwLock;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tracing::{debug, info};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// Tables of operations rather than resources, kept out of the provenance ledger
+const UNVERSIONED_TABLES: &[&str] = &["jobs", "consoles", "scenario_runs"];
+
+/// Capacity of the VM event broadcast channel
+const VM_EVENT_CAPACITY: usize = 256;
+
+/// Capacity of the resource event broadcast channel
+const EVENT_CAPACITY: usize = 1024;
+
+/// Recent resource events kept for replay to new watchers
+const EVENT_HISTORY: usize = 256;
 
 /// State manager for all daemon resources
 #[derive(Clone)]
@@ -20,8 +39,35 @@ pub struct StateManager {
     db: Database,
     cas: Arc<ContentAddressedStore>,
     key_pair: Arc<KeyPair>,
+    secrets: SecretStore,
+    /// Signed history of resource specs
+    ledger: ProvenanceLedger,
     /// Runtime state for running VMs (not persisted)
     vm_processes: Arc<RwLock<HashMap<String, VmProcess>>>,
+    /// Lifecycle events for log streaming (not persisted)
+    vm_events: broadcast::Sender<VmEvent>,
+    /// Resource changes for watchers (not persisted)
+    events: broadcast::Sender<ResourceEvent>,
+    /// Most recent resource changes, oldest first (not persisted)
+    recent_events: Arc<RwLock<VecDeque<ResourceEvent>>>,
+    /// Port forwards installed in QEMU, keyed by forward ID to the owning PID (not persisted)
+    applied_port_forwards: Arc<RwLock<HashMap<String, u32>>>,
+    /// Router specs installed on the host, keyed by router ID (not persisted)
+    applied_routers: Arc<RwLock<HashMap<String, RouterSpec>>>,
+    /// USB devices hot-attached to QEMU, keyed by device ID to the owning PID (not persisted)
+    attached_devices: Arc<RwLock<HashMap<String, u32>>>,
+    /// QoS relays of running VMs, keyed by VM ID (not persisted)
+    qos_links: Arc<RwLock<HashMap<String, QosLink>>>,
+    /// vTPMs of running VMs, keyed by VM ID (not persisted)
+    vtpms: Arc<RwLock<HashMap<String, Arc<Vtpm>>>>,
+    /// VMs with an attached serial console session (not persisted)
+    serial_sessions: Arc<RwLock<HashSet<String>>>,
+    /// VMs with a guest agent session (not persisted)
+    guest_agent_sessions: Arc<RwLock<HashSet<String>>>,
+    /// VMs with a backup in progress (not persisted)
+    active_backups: Arc<RwLock<HashSet<String>>>,
+    /// VMs paused or suspended for being idle (not persisted)
+    idle_vms: Arc<RwLock<HashSet<String>>>,
 }
 
 /// Runtime state for a VM process
@@ -32,6 +78,112 @@ pub struct VmProcess {
     pub qmp_socket: String,
     pub vnc_port: Option<u16>,
     pub started_at: i64,
+    /// Resources currently present in the QEMU process
+    pub live: LiveResources,
+}
+
+/// Hot-pluggable resources of a running VM
+#[derive(Debug, Clone, Default)]
+pub struct LiveResources {
+    /// Spec generation these resources were last reconciled against
+    pub generation: i64,
+    pub cpu_cores: u32,
+    /// Boot memory plus hot-plugged DIMMs, in MB
+    pub memory_mb: u64,
+    /// Balloon target, in MB
+    pub balloon_mb: u64,
+    /// Sizes of hot-plugged DIMMs, in MB
+    pub dimms: Vec<u64>,
+    /// Device IDs of hot-plugged vCPUs, in plug order
+    pub hotplug_cpus: Vec<String>,
+    /// Volumes attached at boot (only removable by restarting)
+    pub boot_volume_ids: Vec<String>,
+    /// Hot-plugged volume per disk root-port slot
+    pub disk_slots: Vec<Option<String>>,
+    /// Folders shared at boot (only changed by restarting)
+    pub shared_folders: Vec<SharedFolder>,
+}
+
+impl LiveResources {
+    /// Whether a volume is attached, at boot or hot-plugged
+    pub fn has_volume(&self, volume_id: &str) -> bool {
+        self.boot_volume_ids.iter().any(|id| id == volume_id)
+            || self.disk_slots.iter().flatten().any(|id| id == volume_id)
+    }
+}
+
+/// Daemon lifecycle event for a VM
+#[derive(Debug, Clone)]
+pub struct VmEvent {
+    pub vm_id: String,
+    pub timestamp: i64,
+    pub message: String,
+}
+
+/// Kind of change published on the event bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Created,
+    Updated,
+    Deleted,
+    VmStateChanged,
+    ReconcileError,
+}
+
+/// Resource change published on the daemon event bus
+#[derive(Debug, Clone)]
+pub struct ResourceEvent {
+    pub kind: EventKind,
+    /// Resource type, e.g. "vm" or "snapshot_policy"
+    pub resource_type: String,
+    pub resource_id: String,
+    pub resource_name: String,
+    pub timestamp: i64,
+    pub message: String,
+    /// New state of a VM state transition
+    pub vm_state: Option<VmState>,
+}
+
+impl ResourceEvent {
+    pub fn new(
+        kind: EventKind,
+        resource_type: &str,
+        resource_id: &str,
+        resource_name: &str,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            resource_type: resource_type.to_string(),
+            resource_id: resource_id.to_string(),
+            resource_name: resource_name.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            message: message.into(),
+            vm_state: None,
+        }
+    }
+}
+
+/// Resource type name for a state.db table
+fn resource_type(table: &str) -> &str {
+    match table {
+        "vms" => "vm",
+        "networks" => "network",
+        "volumes" => "volume",
+        "qos_profiles" => "qos_profile",
+        "chaos_profiles" => "chaos_profile",
+        "scenario_runs" => "scenario_run",
+        "snapshots" => "snapshot",
+        "port_forwards" => "port_forward",
+        "routers" => "router",
+        "devices" => "device",
+        "snapshot_policies" => "snapshot_policy",
+        "backups" => "backup",
+        "backup_policies" => "backup_policy",
+        "jobs" => "job",
+        "consoles" => "console",
+        other => other,
+    }
 }
 
 impl StateManager {
@@ -55,12 +207,29 @@ impl StateManager {
 
         info!("Signing key public: {}", key_pair.public_key_hex());
 
+        let secrets = SecretStore::new(db.clone(), MasterKey::load_or_create(config.secrets_key_path())?);
+        let ledger = ProvenanceLedger::new(db.clone()).with_signer(key_pair.clone());
+
         Ok(Self {
             config: config.clone(),
             db,
             cas: Arc::new(cas),
             key_pair: Arc::new(key_pair),
+            secrets,
+            ledger,
             vm_processes: Arc::new(RwLock::new(HashMap::new())),
+            vm_events: broadcast::channel(VM_EVENT_CAPACITY).0,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            recent_events: Arc::new(RwLock::new(VecDeque::with_capacity(EVENT_HISTORY))),
+            applied_port_forwards: Arc::new(RwLock::new(HashMap::new())),
+            applied_routers: Arc::new(RwLock::new(HashMap::new())),
+            attached_devices: Arc::new(RwLock::new(HashMap::new())),
+            qos_links: Arc::new(RwLock::new(HashMap::new())),
+            vtpms: Arc::new(RwLock::new(HashMap::new())),
+            serial_sessions: Arc::new(RwLock::new(HashSet::new())),
+            guest_agent_sessions: Arc::new(RwLock::new(HashSet::new())),
+            active_backups: Arc::new(RwLock::new(HashSet::new())),
+            idle_vms: Arc::new(RwLock::new(HashSet::new())),
         })
     }
 
@@ -84,6 +253,16 @@ impl StateManager {
         &self.key_pair
     }
 
+    /// Get the secret store
+    pub fn secrets(&self) -> &SecretStore {
+        &self.secrets
+    }
+
+    /// Get the provenance ledger
+    pub fn ledger(&self) -> &ProvenanceLedger {
+        &self.ledger
+    }
+
     // ========================================================================
     // VM operations
     // ========================================================================
@@ -101,7 +280,7 @@ impl StateManager {
         let meta = ResourceMeta::new(name).with_labels(labels);
         let status = VmStatus::default();
 
-        self.db.insert("vms", &meta.id, &meta.name, &spec, &status, &meta.labels)?;
+        self.insert_row("vms", &meta, &spec, &status)?;
 
         debug!("Created VM: {} ({})", meta.name, meta.id);
 
@@ -167,19 +346,49 @@ impl StateManager {
 
     /// Update VM spec
     pub fn update_vm_spec(&self, id: &str, spec: VmSpec) -> Result<()> {
-        self.db.update("vms", id, Some(&spec), None::<&VmStatus>)
+        self.update_row("vms", id, Some(&spec), None::<&VmStatus>)
+    }
+
+    /// Replace VM labels
+    pub fn update_vm_labels(&self, id: &str, labels: &HashMap<String, String>) -> Result<()> {
+        self.db.update_labels("vms", id, labels)?;
+        self.publish_event(ResourceEvent::new(
+            EventKind::Updated,
+            resource_type("vms"),
+            id,
+            &self.row_name("vms", id)?,
+            "labels updated",
+        ));
+        Ok(())
     }
 
     /// Update VM status
     pub fn update_vm_status(&self, id: &str, status: VmStatus) -> Result<()> {
-        self.db.update("vms", id, None::<&VmSpec>, Some(&status))
+        let previous = self.get_vm(id)?;
+        self.db.update("vms", id, None::<&VmSpec>, Some(&status))?;
+
+        // Uptime is refreshed on every reconcile pass; only transitions are published
+        if let Some(vm) = previous.filter(|vm| vm.status.state != status.state) {
+            let message = match &status.error_message {
+                Some(error) => format!("{} -> {}: {}", vm.status.state, status.state, error),
+                None => format!("{} -> {}", vm.status.state, status.state),
+            };
+            self.publish_event(ResourceEvent {
+                vm_state: Some(status.state),
+                ..ResourceEvent::new(EventKind::VmStateChanged, "vm", id, &vm.meta.name, message)
+            });
+        }
+
+        Ok(())
     }
 
     /// Delete a VM
     pub fn delete_vm(&self, id: &str) -> Result<bool> {
         // Remove from runtime state
         self.vm_processes.write().remove(id);
-        self.db.delete("vms", id)
+        self.stop_qos_link(id);
+        self.stop_vtpm(id);
+        self.delete_row("vms", id)
     }
 
     /// Register a running VM process
@@ -194,14 +403,243 @@ impl StateManager {
 
     /// Remove VM process
     pub fn remove_vm_process(&self, vm_id: &str) -> Option<VmProcess> {
+        self.stop_qos_link(vm_id);
+        self.stop_vtpm(vm_id);
         self.vm_processes.write().remove(vm_id)
     }
 
+    /// Register the QoS relays of a started VM
+    pub fn register_qos_link(&self, vm_id: &str, link: QosLink) {
+        if let Some(old) = self.qos_links.write().insert(vm_id.to_string(), link) {
+            old.stop();
+        }
+    }
+
+    /// Stop a VM's QoS relays
+    pub fn stop_qos_link(&self, vm_id: &str) {
+        if let Some(link) = self.qos_links.write().remove(vm_id) {
+            link.stop();
+        }
+    }
+
+    /// Switch a running VM's QoS relays to `profile`
+    ///
+    /// Returns false if the VM has no relays (it started without a profile)
+    /// or already uses this profile.
+    pub fn update_qos_link(&self, vm_id: &str, profile: Option<&QosProfile>) -> bool {
+        let mut links = self.qos_links.write();
+        match links.get_mut(vm_id) {
+            Some(link) if link.profile_id.as_deref() != profile.map(|p| p.meta.id.as_str()) => {
+                link.update(profile);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a running VM's NICs are wired through QoS relays
+    pub fn has_qos_link(&self, vm_id: &str) -> bool {
+        self.qos_links.read().contains_key(vm_id)
+    }
+
+    /// Register the vTPM of a starting VM
+    pub fn register_vtpm(&self, vm_id: &str, vtpm: Arc<Vtpm>) {
+        if let Some(old) = self.vtpms.write().insert(vm_id.to_string(), vtpm) {
+            old.stop();
+        }
+    }
+
+    /// Get a running VM's vTPM
+    pub fn get_vtpm(&self, vm_id: &str) -> Option<Arc<Vtpm>> {
+        self.vtpms.read().get(vm_id).cloned()
+    }
+
+    /// Stop a VM's vTPM
+    pub fn stop_vtpm(&self, vm_id: &str) {
+        if let Some(vtpm) = self.vtpms.write().remove(vm_id) {
+            vtpm.stop();
+        }
+    }
+
+    /// Bytes received and sent by a VM through its QoS relays
+    pub fn qos_traffic(&self, vm_id: &str) -> Option<(u64, u64)> {
+        self.qos_links.read().get(vm_id).map(|link| link.traffic())
+    }
+
+    /// Inject chaos faults into a running VM's relayed NICs, one entry per NIC
+    ///
+    /// Returns false if the VM's NICs aren't relayed.
+    pub fn set_qos_faults(&self, vm_id: &str, faults: Vec<Option<FaultInjection>>) -> bool {
+        let links = self.qos_links.read();
+        let Some(link) = links.get(vm_id) else {
+            return false;
+        };
+        for (nic, faults) in faults.into_iter().enumerate().take(link.nics()) {
+            link.set_faults(nic, faults);
+        }
+        true
+    }
+
+    /// Claim a VM's serial console; false if another session holds it
+    pub fn claim_serial(&self, vm_id: &str) -> bool {
+        self.serial_sessions.write().insert(vm_id.to_string())
+    }
+
+    /// Release a VM's serial console
+    pub fn release_serial(&self, vm_id: &str) {
+        self.serial_sessions.write().remove(vm_id);
+    }
+
+    /// Whether a serial console session is attached to a VM
+    pub fn serial_attached(&self, vm_id: &str) -> bool {
+        self.serial_sessions.read().contains(vm_id)
+    }
+
+    /// Claim a VM's guest agent channel; false if another session holds it
+    pub fn claim_guest_agent(&self, vm_id: &str) -> bool {
+        self.guest_agent_sessions.write().insert(vm_id.to_string())
+    }
+
+    /// Release a VM's guest agent channel
+    pub fn release_guest_agent(&self, vm_id: &str) {
+        self.guest_agent_sessions.write().remove(vm_id);
+    }
+
+    /// Replace the live resources recorded for a running VM
+    pub fn update_vm_live(&self, vm_id: &str, live: LiveResources) {
+        if let Some(process) = self.vm_processes.write().get_mut(vm_id) {
+            process.live = live;
+        }
+    }
+
     /// List all running VM processes
     pub fn list_vm_processes(&self) -> Vec<VmProcess> {
         self.vm_processes.read().values().cloned().collect()
     }
 
+    /// Publish a lifecycle event for a VM
+    pub fn emit_vm_event(&self, vm_id: &str, message: impl Into<String>) {
+        // No receivers is not an error: nobody is streaming logs
+        let _ = self.vm_events.send(VmEvent {
+            vm_id: vm_id.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            message: message.into(),
+        });
+    }
+
+    /// Subscribe to VM lifecycle events
+    pub fn subscribe_vm_events(&self) -> broadcast::Receiver<VmEvent> {
+        self.vm_events.subscribe()
+    }
+
+    // ========================================================================
+    // Event bus
+    // ========================================================================
+
+    /// Subscribe to resource change events, returning recent events to replay
+    ///
+    /// Nothing published after the returned history is missed by the receiver.
+    pub fn subscribe_events(&self) -> (Vec<ResourceEvent>, broadcast::Receiver<ResourceEvent>) {
+        let recent = self.recent_events.read();
+        (recent.iter().cloned().collect(), self.events.subscribe())
+    }
+
+    /// Publish a resource change event
+    pub fn publish_event(&self, event: ResourceEvent) {
+        let mut recent = self.recent_events.write();
+        if recent.len() == EVENT_HISTORY {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+
+        // No receivers is not an error: nobody is watching
+        let _ = self.events.send(event);
+    }
+
+    /// Publish a reconcile failure for a resource
+    pub fn emit_reconcile_error(&self, resource_type: &str, id: &str, name: &str, error: impl std::fmt::Display) {
+        self.publish_event(ResourceEvent::new(
+            EventKind::ReconcileError,
+            resource_type,
+            id,
+            name,
+            error.to_string(),
+        ));
+    }
+
+    fn insert_row<S: serde::Serialize, T: serde::Serialize>(
+        &self,
+        table: &str,
+        meta: &ResourceMeta,
+        spec: &S,
+        status: &T,
+    ) -> Result<()> {
+        self.db.insert(table, &meta.id, &meta.name, spec, status, &meta.labels)?;
+        self.record_version(table, &meta.id, CHANGE_CREATED, Some(spec));
+        self.publish_event(ResourceEvent::new(
+            EventKind::Created,
+            resource_type(table),
+            &meta.id,
+            &meta.name,
+            "created",
+        ));
+        Ok(())
+    }
+
+    fn update_row<S: serde::Serialize, T: serde::Serialize>(
+        &self,
+        table: &str,
+        id: &str,
+        spec: Option<&S>,
+        status: Option<&T>,
+    ) -> Result<()> {
+        self.db.update(table, id, spec, status)?;
+        if spec.is_some() {
+            self.record_version(table, id, CHANGE_UPDATED, spec);
+        }
+        let message = if spec.is_some() { "spec updated" } else { "status updated" };
+        self.publish_event(ResourceEvent::new(
+            EventKind::Updated,
+            resource_type(table),
+            id,
+            &self.row_name(table, id)?,
+            message,
+        ));
+        Ok(())
+    }
+
+    fn delete_row(&self, table: &str, id: &str) -> Result<bool> {
+        let name = self.row_name(table, id)?;
+        let deleted = self.db.delete(table, id)?;
+        if deleted {
+            self.record_version::<()>(table, id, CHANGE_DELETED, None);
+            self.publish_event(ResourceEvent::new(
+                EventKind::Deleted,
+                resource_type(table),
+                id,
+                &name,
+                "deleted",
+            ));
+        }
+        Ok(deleted)
+    }
+
+    /// Add a version to the provenance ledger; the change itself is already
+    /// committed, so a failure is only logged
+    fn record_version<S: serde::Serialize>(&self, table: &str, id: &str, change: &str, spec: Option<&S>) {
+        if UNVERSIONED_TABLES.contains(&table) {
+            return;
+        }
+        if let Err(e) = self.ledger.record(resource_type(table), id, change, spec) {
+            warn!("Failed to record {} {} in the provenance ledger: {}", resource_type(table), id, e);
+        }
+    }
+
+    fn row_name(&self, table: &str, id: &str) -> Result<String> {
+        let row: Option<ResourceRow<serde_json::Value, serde_json::Value>> = self.db.get(table, id)?;
+        Ok(row.map(|r| r.name).unwrap_or_default())
+    }
+
     // ========================================================================
     // Network operations
     // ========================================================================
@@ -218,7 +656,7 @@ impl StateManager {
         let meta = ResourceMeta::new(name).with_labels(labels);
         let status = NetworkStatus::default();
 
-        self.db.insert("networks", &meta.id, &meta.name, &spec, &status, &meta.labels)?;
+        self.insert_row("networks", &meta, &spec, &status)?;
 
         Ok(Network { meta, spec, status })
     }
@@ -262,9 +700,14 @@ impl StateManager {
             .collect())
     }
 
+    /// Update network status
+    pub fn update_network_status(&self, id: &str, status: NetworkStatus) -> Result<()> {
+        self.update_row("networks", id, None::<&NetworkSpec>, Some(&status))
+    }
+
     /// Delete a network
     pub fn delete_network(&self, id: &str) -> Result<bool> {
-        self.db.delete("networks", id)
+        self.delete_row("networks", id)
     }
 
     // ========================================================================
@@ -283,7 +726,7 @@ impl StateManager {
         let meta = ResourceMeta::new(name).with_labels(labels);
         let status = VolumeStatus::default();
 
-        self.db.insert("volumes", &meta.id, &meta.name, &spec, &status, &meta.labels)?;
+        self.insert_row("volumes", &meta, &spec, &status)?;
 
         Ok(Volume { meta, spec, status })
     }
@@ -327,14 +770,32 @@ impl StateManager {
             .collect())
     }
 
+    /// Update volume spec
+    pub fn update_volume_spec(&self, id: &str, spec: VolumeSpec) -> Result<()> {
+        self.update_row("volumes", id, Some(&spec), None::<&VolumeStatus>)
+    }
+
     /// Update volume status
     pub fn update_volume_status(&self, id: &str, status: VolumeStatus) -> Result<()> {
-        self.db.update("volumes", id, None::<&VolumeSpec>, Some(&status))
+        self.update_row("volumes", id, None::<&VolumeSpec>, Some(&status))
+    }
+
+    /// Update the remote import progress of a volume
+    pub fn update_volume_download(&self, id: &str, download: DownloadStatus) -> Result<()> {
+        let volume = self.get_volume(id)?.ok_or_else(|| Error::NotFound {
+            kind: "volume".to_string(),
+            id: id.to_string(),
+        })?;
+        let status = VolumeStatus {
+            download: Some(download),
+            ..volume.status
+        };
+        self.update_volume_status(id, status)
     }
 
     /// Delete a volume
     pub fn delete_volume(&self, id: &str) -> Result<bool> {
-        self.db.delete("volumes", id)
+        self.delete_row("volumes", id)
     }
 
     // ========================================================================
@@ -354,7 +815,7 @@ impl StateManager {
         // QoS profiles don't have status, use empty object
         let empty_status = serde_json::json!({});
 
-        self.db.insert("qos_profiles", &meta.id, &meta.name, &spec, &empty_status, &meta.labels)?;
+        self.insert_row("qos_profiles", &meta, &spec, &empty_status)?;
 
         Ok(QosProfile { meta, spec })
     }
@@ -398,7 +859,101 @@ impl StateManager {
 
     /// Delete a QoS profile
     pub fn delete_qos_profile(&self, id: &str) -> Result<bool> {
-        self.db.delete("qos_profiles", id)
+        self.delete_row("qos_profiles", id)
+    }
+
+    // ========================================================================
+    // Chaos Profile operations
+    // ========================================================================
+
+    /// Create a new chaos profile
+    pub fn create_chaos_profile(&self, name: String, spec: ChaosProfileSpec, labels: HashMap<String, String>) -> Result<ChaosProfile> {
+        if self.db.name_exists("chaos_profiles", &name)? {
+            return Err(Error::AlreadyExists {
+                kind: "chaos_profile".to_string(),
+                id: name,
+            });
+        }
+
+        let meta = ResourceMeta::new(name).with_labels(labels);
+        let status = ChaosProfileStatus::default();
+
+        self.insert_row("chaos_profiles", &meta, &spec, &status)?;
+
+        Ok(ChaosProfile { meta, spec, status })
+    }
+
+    /// Get a chaos profile by ID
+    pub fn get_chaos_profile(&self, id: &str) -> Result<Option<ChaosProfile>> {
+        let row: Option<ResourceRow<ChaosProfileSpec, ChaosProfileStatus>> = self.db.get("chaos_profiles", id)?;
+        Ok(row.map(|r| ChaosProfile {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// Get a chaos profile by name
+    pub fn get_chaos_profile_by_name(&self, name: &str) -> Result<Option<ChaosProfile>> {
+        let row: Option<ResourceRow<ChaosProfileSpec, ChaosProfileStatus>> =
+            self.db.get_by_name("chaos_profiles", name)?;
+        Ok(row.map(|r| ChaosProfile {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List all chaos profiles
+    pub fn list_chaos_profiles(&self) -> Result<Vec<ChaosProfile>> {
+        let rows: Vec<ResourceRow<ChaosProfileSpec, ChaosProfileStatus>> = self.db.list("chaos_profiles")?;
+        Ok(rows
+            .into_iter()
+            .map(|r| ChaosProfile {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update chaos profile spec
+    pub fn update_chaos_profile_spec(&self, id: &str, spec: &ChaosProfileSpec) -> Result<()> {
+        self.update_row("chaos_profiles", id, Some(spec), None::<&ChaosProfileStatus>)
+    }
+
+    /// Update chaos profile status
+    pub fn update_chaos_profile_status(&self, id: &str, status: ChaosProfileStatus) -> Result<()> {
+        self.update_row("chaos_profiles", id, None::<&ChaosProfileSpec>, Some(&status))
+    }
+
+    /// Delete a chaos profile
+    pub fn delete_chaos_profile(&self, id: &str) -> Result<bool> {
+        self.delete_row("chaos_profiles", id)
     }
 
     // ========================================================================
@@ -417,7 +972,7 @@ impl StateManager {
         let meta = ResourceMeta::new(name).with_labels(labels);
         let status = SnapshotStatus::default();
 
-        self.db.insert("snapshots", &meta.id, &meta.name, &spec, &status, &meta.labels)?;
+        self.insert_row("snapshots", &meta, &spec, &status)?;
 
         Ok(Snapshot { meta, spec, status })
     }
@@ -464,12 +1019,712 @@ impl StateManager {
 
     /// Update snapshot status
     pub fn update_snapshot_status(&self, id: &str, status: SnapshotStatus) -> Result<()> {
-        self.db.update("snapshots", id, None::<&SnapshotSpec>, Some(&status))
+        self.update_row("snapshots", id, None::<&SnapshotSpec>, Some(&status))
     }
 
     /// Delete a snapshot
     pub fn delete_snapshot(&self, id: &str) -> Result<bool> {
-        self.db.delete("snapshots", id)
+        self.delete_row("snapshots", id)
+    }
+
+    // ========================================================================
+    // Port forward operations
+    // ========================================================================
+
+    /// Create a new port forward
+    pub fn create_port_forward(&self, name: String, spec: PortForwardSpec, labels: HashMap<String, String>) -> Result<PortForward> {
+        if self.db.name_exists("port_forwards", &name)? {
+            return Err(Error::AlreadyExists {
+                kind: "port forward".to_string(),
+                id: name,
+            });
+        }
+
+        if let Some(existing) = self.list_port_forwards(None)?.into_iter().find(|pf| {
+            pf.spec.protocol == spec.protocol
+                && pf.spec.host_port == spec.host_port
+                && (pf.spec.host_addr == spec.host_addr
+                    || pf.spec.host_addr == "0.0.0.0"
+                    || spec.host_addr == "0.0.0.0")
+        }) {
+            return Err(Error::AlreadyExists {
+                kind: "port forward".to_string(),
+                id: format!(
+                    "{}:{}/{} (used by {})",
+                    spec.host_addr,
+                    spec.host_port,
+                    spec.protocol.as_str(),
+                    existing.meta.name
+                ),
+            });
+        }
+
+        let meta = ResourceMeta::new(name).with_labels(labels);
+        let status = PortForwardStatus::default();
+
+        self.insert_row("port_forwards", &meta, &spec, &status)?;
+
+        Ok(PortForward { meta, spec, status })
+    }
+
+    /// Get a port forward by ID
+    pub fn get_port_forward(&self, id: &str) -> Result<Option<PortForward>> {
+        let row: Option<ResourceRow<PortForwardSpec, PortForwardStatus>> = self.db.get("port_forwards", id)?;
+        Ok(row.map(|r| PortForward {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List port forwards, optionally for a single VM
+    pub fn list_port_forwards(&self, vm_id: Option<&str>) -> Result<Vec<PortForward>> {
+        let rows: Vec<ResourceRow<PortForwardSpec, PortForwardStatus>> = self.db.list("port_forwards")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| vm_id.map_or(true, |id| r.spec.vm_id == id))
+            .map(|r| PortForward {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update port forward status
+    pub fn update_port_forward_status(&self, id: &str, status: PortForwardStatus) -> Result<()> {
+        self.update_row("port_forwards", id, None::<&PortForwardSpec>, Some(&status))
+    }
+
+    /// Delete a port forward
+    pub fn delete_port_forward(&self, id: &str) -> Result<bool> {
+        self.applied_port_forwards.write().remove(id);
+        self.delete_row("port_forwards", id)
+    }
+
+    /// Record that a port forward is installed in the QEMU process with `pid`
+    pub fn mark_port_forward_applied(&self, id: &str, pid: u32) {
+        self.applied_port_forwards.write().insert(id.to_string(), pid);
+    }
+
+    /// PID of the QEMU process a port forward is installed in, if any
+    pub fn port_forward_applied_pid(&self, id: &str) -> Option<u32> {
+        self.applied_port_forwards.read().get(id).copied()
+    }
+
+    // ========================================================================
+    // Router operations
+    // ========================================================================
+
+    /// Create a new router
+    pub fn create_router(&self, name: String, spec: RouterSpec, labels: HashMap<String, String>) -> Result<Router> {
+        if self.db.name_exists("routers", &name)? {
+            return Err(Error::AlreadyExists {
+                kind: "router".to_string(),
+                id: name,
+            });
+        }
+
+        let meta = ResourceMeta::new(name).with_labels(labels);
+        let status = RouterStatus::default();
+
+        self.insert_row("routers", &meta, &spec, &status)?;
+
+        Ok(Router { meta, spec, status })
+    }
+
+    /// Get a router by ID
+    pub fn get_router(&self, id: &str) -> Result<Option<Router>> {
+        let row: Option<ResourceRow<RouterSpec, RouterStatus>> = self.db.get("routers", id)?;
+        Ok(row.map(|r| Router {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List routers, optionally only those connected to a network
+    pub fn list_routers(&self, network_id: Option<&str>) -> Result<Vec<Router>> {
+        let rows: Vec<ResourceRow<RouterSpec, RouterStatus>> = self.db.list("routers")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| network_id.map_or(true, |id| r.spec.network_ids.iter().any(|n| n == id)))
+            .map(|r| Router {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Replace a router's spec; the reconciler installs the change
+    pub fn update_router_spec(&self, id: &str, spec: &RouterSpec) -> Result<()> {
+        self.update_row("routers", id, Some(spec), None::<&RouterStatus>)
+    }
+
+    /// Update router status
+    pub fn update_router_status(&self, id: &str, status: RouterStatus) -> Result<()> {
+        self.update_row("routers", id, None::<&RouterSpec>, Some(&status))
+    }
+
+    /// Delete a router
+    pub fn delete_router(&self, id: &str) -> Result<bool> {
+        self.applied_routers.write().remove(id);
+        self.delete_row("routers", id)
+    }
+
+    /// Record the spec a router was installed on the host with
+    pub fn mark_router_applied(&self, id: &str, spec: RouterSpec) {
+        self.applied_routers.write().insert(id.to_string(), spec);
+    }
+
+    /// Spec a router is installed on the host with, if any
+    pub fn router_applied_spec(&self, id: &str) -> Option<RouterSpec> {
+        self.applied_routers.read().get(id).cloned()
+    }
+
+    /// Forget that a router is installed, e.g. after removing it from the host
+    pub fn clear_router_applied(&self, id: &str) {
+        self.applied_routers.write().remove(id);
+    }
+
+    // ========================================================================
+    // Device operations
+    // ========================================================================
+
+    /// Create a new passthrough device
+    pub fn create_device(&self, name: String, spec: DeviceSpec, labels: HashMap<String, String>) -> Result<Device> {
+        if self.db.name_exists("devices", &name)? {
+            return Err(Error::AlreadyExists {
+                kind: "device".to_string(),
+                id: name,
+            });
+        }
+
+        // A host device can only be claimed by one QEMU process at a time
+        if let Some(existing) = self.list_devices(None)?.into_iter().find(|d| {
+            d.spec.bus == spec.bus
+                && d.spec.vendor_id == spec.vendor_id
+                && d.spec.product_id == spec.product_id
+                && d.spec.host_address == spec.host_address
+        }) {
+            return Err(Error::AlreadyExists {
+                kind: "device".to_string(),
+                id: format!("{} (used by {})", spec.describe(), existing.meta.name),
+            });
+        }
+
+        let meta = ResourceMeta::new(name).with_labels(labels);
+        let status = DeviceStatus::default();
+
+        self.insert_row("devices", &meta, &spec, &status)?;
+
+        Ok(Device { meta, spec, status })
+    }
+
+    /// Get a device by ID
+    pub fn get_device(&self, id: &str) -> Result<Option<Device>> {
+        let row: Option<ResourceRow<DeviceSpec, DeviceStatus>> = self.db.get("devices", id)?;
+        Ok(row.map(|r| Device {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List devices, optionally for a single VM
+    pub fn list_devices(&self, vm_id: Option<&str>) -> Result<Vec<Device>> {
+        let rows: Vec<ResourceRow<DeviceSpec, DeviceStatus>> = self.db.list("devices")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| vm_id.map_or(true, |id| r.spec.vm_id == id))
+            .map(|r| Device {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update device status
+    pub fn update_device_status(&self, id: &str, status: DeviceStatus) -> Result<()> {
+        self.update_row("devices", id, None::<&DeviceSpec>, Some(&status))
+    }
+
+    /// Delete a device
+    pub fn delete_device(&self, id: &str) -> Result<bool> {
+        self.attached_devices.write().remove(id);
+        self.delete_row("devices", id)
+    }
+
+    /// Record that a USB device was hot-attached to the QEMU process with `pid`
+    pub fn mark_device_attached(&self, id: &str, pid: u32) {
+        self.attached_devices.write().insert(id.to_string(), pid);
+    }
+
+    /// PID of the QEMU process a USB device was hot-attached to, if any
+    pub fn device_attached_pid(&self, id: &str) -> Option<u32> {
+        self.attached_devices.read().get(id).copied()
+    }
+
+    // ========================================================================
+    // Snapshot policy operations
+    // ========================================================================
+
+    /// Create a new snapshot policy
+    pub fn create_snapshot_policy(&self, name: String, spec: SnapshotPolicySpec, labels: HashMap<String, String>) -> Result<SnapshotPolicy> {
+        if self.db.name_exists("snapshot_policies", &name)? {
+            return Err(Error::AlreadyExists {
+                kind: "snapshot policy".to_string(),
+                id: name,
+            });
+        }
+
+        let meta = ResourceMeta::new(name).with_labels(labels);
+        let status = SnapshotPolicyStatus::default();
+
+        self.insert_row("snapshot_policies", &meta, &spec, &status)?;
+
+        Ok(SnapshotPolicy { meta, spec, status })
+    }
+
+    /// Get a snapshot policy by ID
+    pub fn get_snapshot_policy(&self, id: &str) -> Result<Option<SnapshotPolicy>> {
+        let row: Option<ResourceRow<SnapshotPolicySpec, SnapshotPolicyStatus>> = self.db.get("snapshot_policies", id)?;
+        Ok(row.map(|r| SnapshotPolicy {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List snapshot policies, optionally for a single VM
+    pub fn list_snapshot_policies(&self, vm_id: Option<&str>) -> Result<Vec<SnapshotPolicy>> {
+        // Selector policies cover whichever VMs their labels match
+        let vm = match vm_id {
+            Some(id) => self.get_vm(id)?,
+            None => None,
+        };
+        let rows: Vec<ResourceRow<SnapshotPolicySpec, SnapshotPolicyStatus>> = self.db.list("snapshot_policies")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| match (vm_id, &vm) {
+                (None, _) => true,
+                (Some(_), Some(vm)) => r.spec.covers(&vm.meta),
+                (Some(id), None) => r.spec.vm_id == id,
+            })
+            .map(|r| SnapshotPolicy {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update snapshot policy spec
+    pub fn update_snapshot_policy_spec(&self, id: &str, spec: SnapshotPolicySpec) -> Result<()> {
+        self.update_row("snapshot_policies", id, Some(&spec), None::<&SnapshotPolicyStatus>)
+    }
+
+    /// Update snapshot policy status
+    pub fn update_snapshot_policy_status(&self, id: &str, status: SnapshotPolicyStatus) -> Result<()> {
+        self.update_row("snapshot_policies", id, None::<&SnapshotPolicySpec>, Some(&status))
+    }
+
+    /// Delete a snapshot policy
+    pub fn delete_snapshot_policy(&self, id: &str) -> Result<bool> {
+        self.delete_row("snapshot_policies", id)
+    }
+
+    // ========================================================================
+    // Backup operations
+    // ========================================================================
+
+    /// Create a new backup
+    pub fn create_backup(&self, name: String, spec: BackupSpec, labels: HashMap<String, String>) -> Result<Backup> {
+        if self.db.name_exists("backups", &name)? {
+            return Err(Error::AlreadyExists {
+                kind: "backup".to_string(),
+                id: name,
+            });
+        }
+
+        let meta = ResourceMeta::new(name).with_labels(labels);
+        let status = BackupStatus::default();
+
+        self.insert_row("backups", &meta, &spec, &status)?;
+
+        Ok(Backup { meta, spec, status })
+    }
+
+    /// Get a backup by ID
+    pub fn get_backup(&self, id: &str) -> Result<Option<Backup>> {
+        let row: Option<ResourceRow<BackupSpec, BackupStatus>> = self.db.get("backups", id)?;
+        Ok(row.map(|r| Backup {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List backups, optionally for a single VM
+    pub fn list_backups(&self, vm_id: Option<&str>) -> Result<Vec<Backup>> {
+        let rows: Vec<ResourceRow<BackupSpec, BackupStatus>> = self.db.list("backups")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| vm_id.map_or(true, |id| r.spec.vm_id == id))
+            .map(|r| Backup {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update backup status
+    pub fn update_backup_status(&self, id: &str, status: BackupStatus) -> Result<()> {
+        self.update_row("backups", id, None::<&BackupSpec>, Some(&status))
+    }
+
+    /// Delete a backup
+    pub fn delete_backup(&self, id: &str) -> Result<bool> {
+        self.delete_row("backups", id)
+    }
+
+    /// Claim a VM for a backup; false if one is already running
+    pub fn claim_backup(&self, vm_id: &str) -> bool {
+        self.active_backups.write().insert(vm_id.to_string())
+    }
+
+    /// Release a VM claimed for a backup
+    pub fn release_backup(&self, vm_id: &str) {
+        self.active_backups.write().remove(vm_id);
+    }
+
+    /// Record that a VM was paused or suspended for being idle
+    pub fn mark_idle(&self, vm_id: &str) {
+        self.idle_vms.write().insert(vm_id.to_string());
+    }
+
+    /// Whether a VM was paused or suspended for being idle
+    pub fn is_idle(&self, vm_id: &str) -> bool {
+        self.idle_vms.read().contains(vm_id)
+    }
+
+    /// Forget that a VM was put to sleep for being idle
+    pub fn clear_idle(&self, vm_id: &str) {
+        self.idle_vms.write().remove(vm_id);
+    }
+
+    // ========================================================================
+    // Backup policy operations
+    // ========================================================================
+
+    /// Create a new backup policy
+    pub fn create_backup_policy(&self, name: String, spec: BackupPolicySpec, labels: HashMap<String, String>) -> Result<BackupPolicy> {
+        if self.db.name_exists("backup_policies", &name)? {
+            return Err(Error::AlreadyExists {
+                kind: "backup policy".to_string(),
+                id: name,
+            });
+        }
+
+        let meta = ResourceMeta::new(name).with_labels(labels);
+        let status = BackupPolicyStatus::default();
+
+        self.insert_row("backup_policies", &meta, &spec, &status)?;
+
+        Ok(BackupPolicy { meta, spec, status })
+    }
+
+    /// Get a backup policy by ID
+    pub fn get_backup_policy(&self, id: &str) -> Result<Option<BackupPolicy>> {
+        let row: Option<ResourceRow<BackupPolicySpec, BackupPolicyStatus>> = self.db.get("backup_policies", id)?;
+        Ok(row.map(|r| BackupPolicy {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List backup policies, optionally for a single VM
+    pub fn list_backup_policies(&self, vm_id: Option<&str>) -> Result<Vec<BackupPolicy>> {
+        // Selector policies cover whichever VMs their labels match
+        let vm = match vm_id {
+            Some(id) => self.get_vm(id)?,
+            None => None,
+        };
+        let rows: Vec<ResourceRow<BackupPolicySpec, BackupPolicyStatus>> = self.db.list("backup_policies")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| match (vm_id, &vm) {
+                (None, _) => true,
+                (Some(_), Some(vm)) => r.spec.covers(&vm.meta),
+                (Some(id), None) => r.spec.vm_id == id,
+            })
+            .map(|r| BackupPolicy {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update backup policy spec
+    pub fn update_backup_policy_spec(&self, id: &str, spec: BackupPolicySpec) -> Result<()> {
+        self.update_row("backup_policies", id, Some(&spec), None::<&BackupPolicyStatus>)
+    }
+
+    /// Update backup policy status
+    pub fn update_backup_policy_status(&self, id: &str, status: BackupPolicyStatus) -> Result<()> {
+        self.update_row("backup_policies", id, None::<&BackupPolicySpec>, Some(&status))
+    }
+
+    /// Delete a backup policy
+    pub fn delete_backup_policy(&self, id: &str) -> Result<bool> {
+        self.delete_row("backup_policies", id)
+    }
+
+    // ========================================================================
+    // Job operations
+    // ========================================================================
+
+    /// Record a new pending job
+    pub fn create_job(&self, spec: JobSpec, labels: HashMap<String, String>) -> Result<Job> {
+        let mut meta = ResourceMeta::new(String::new()).with_labels(labels);
+        meta.name = format!("{}-{}", spec.kind.as_str().replace('_', "-"), &meta.id[..8]);
+        let status = JobStatus {
+            message: "queued".to_string(),
+            ..Default::default()
+        };
+
+        self.insert_row("jobs", &meta, &spec, &status)?;
+
+        Ok(Job { meta, spec, status })
+    }
+
+    /// Get a job by ID
+    pub fn get_job(&self, id: &str) -> Result<Option<Job>> {
+        let row: Option<ResourceRow<JobSpec, JobStatus>> = self.db.get("jobs", id)?;
+        Ok(row.map(|r| Job {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List jobs, newest first, optionally in a single state
+    pub fn list_jobs(&self, job_state: Option<JobState>) -> Result<Vec<Job>> {
+        let rows: Vec<ResourceRow<JobSpec, JobStatus>> = self.db.list("jobs")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| job_state.map_or(true, |s| r.status.state == s))
+            .map(|r| Job {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update job status; the event carries the job's message so watchers
+    /// can follow progress without fetching the job
+    pub fn update_job_status(&self, id: &str, status: &JobStatus) -> Result<()> {
+        self.db.update("jobs", id, None::<&JobSpec>, Some(status))?;
+        let message = match status.state {
+            JobState::Running => format!("{:.0}% {}", status.progress, status.message),
+            JobState::Failed => format!("failed: {}", status.error.as_deref().unwrap_or_default()),
+            state => state.as_str().to_string(),
+        };
+        self.publish_event(ResourceEvent::new(
+            EventKind::Updated,
+            "job",
+            id,
+            &self.row_name("jobs", id)?,
+            message,
+        ));
+        Ok(())
+    }
+
+    // ========================================================================
+    // Scenario run operations
+    // ========================================================================
+
+    /// Record a new run of a scenario, named after the scenario
+    pub fn create_scenario_run(&self, scenario: Scenario, labels: HashMap<String, String>) -> Result<ScenarioRun> {
+        let mut meta = ResourceMeta::new(String::new()).with_labels(labels);
+        meta.name = format!("{}-{}", scenario.name, &meta.id[..8]);
+        let status = ScenarioRunStatus::pending(&scenario);
+        let spec = ScenarioRunSpec {
+            digest: scenario.digest(),
+            scenario,
+        };
+
+        self.insert_row("scenario_runs", &meta, &spec, &status)?;
+
+        Ok(ScenarioRun { meta, spec, status })
+    }
+
+    /// Get a scenario run by ID
+    pub fn get_scenario_run(&self, id: &str) -> Result<Option<ScenarioRun>> {
+        let row: Option<ResourceRow<ScenarioRunSpec, ScenarioRunStatus>> = self.db.get("scenario_runs", id)?;
+        Ok(row.map(|r| ScenarioRun {
+            meta: ResourceMeta {
+                id: r.id,
+                name: r.name,
+                labels: r.labels,
+                annotations: r.annotations,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+                generation: r.generation,
+            },
+            spec: r.spec,
+            status: r.status,
+        }))
+    }
+
+    /// List scenario runs, optionally of a single scenario
+    pub fn list_scenario_runs(&self, scenario: Option<&str>) -> Result<Vec<ScenarioRun>> {
+        let rows: Vec<ResourceRow<ScenarioRunSpec, ScenarioRunStatus>> = self.db.list("scenario_runs")?;
+        Ok(rows
+            .into_iter()
+            .filter(|r| scenario.is_none_or(|name| r.spec.scenario.name == name))
+            .map(|r| ScenarioRun {
+                meta: ResourceMeta {
+                    id: r.id,
+                    name: r.name,
+                    labels: r.labels,
+                    annotations: r.annotations,
+                    created_at: r.created_at,
+                    updated_at: r.updated_at,
+                    generation: r.generation,
+                },
+                spec: r.spec,
+                status: r.status,
+            })
+            .collect())
+    }
+
+    /// Update scenario run status
+    pub fn update_scenario_run_status(&self, id: &str, status: &ScenarioRunStatus) -> Result<()> {
+        self.update_row("scenario_runs", id, None::<&ScenarioRunSpec>, Some(status))
+    }
+
+    /// Delete a scenario run
+    pub fn delete_scenario_run(&self, id: &str) -> Result<bool> {
+        self.delete_row("scenario_runs", id)
     }
 
     // ========================================================================
@@ -488,7 +1743,7 @@ impl StateManager {
         let meta = ResourceMeta::new(name);
         let status = ConsoleStatus::default();
 
-        self.db.insert("consoles", &meta.id, &meta.name, &spec, &status, &meta.labels)?;
+        self.insert_row("consoles", &meta, &spec, &status)?;
 
         Ok(Console { meta, spec, status })
     }
@@ -513,11 +1768,11 @@ impl StateManager {
 
     /// Update console status
     pub fn update_console_status(&self, id: &str, status: ConsoleStatus) -> Result<()> {
-        self.db.update("consoles", id, None::<&ConsoleSpec>, Some(&status))
+        self.update_row("consoles", id, None::<&ConsoleSpec>, Some(&status))
     }
 
     /// Delete a console
     pub fn delete_console(&self, id: &str) -> Result<bool> {
-        self.db.delete("consoles", id)
+        self.delete_row("consoles", id)
     }
 }
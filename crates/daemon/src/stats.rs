@@ -0,0 +1,111 @@
+//! VM resource usage
+//!
+//! CPU time and resident memory come from host process accounting for the
+//! QEMU process, disk counters from QMP, and network counters from the QoS
+//! relays when the VM's traffic runs through them.
+
+use crate::state::{StateManager, VmProcess};
+use infrasim_common::{qmp::QmpClient, Result};
+use std::process::Command;
+use std::time::Duration;
+
+/// Time between the two samples CPU usage is computed from
+pub const CPU_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Clock ticks per second in `/proc/<pid>/stat` (USER_HZ)
+const USER_HZ: f64 = 100.0;
+
+/// Resource usage of a running VM at one point in time
+#[derive(Debug, Clone, Default)]
+pub struct VmUsage {
+    pub pid: u32,
+    pub timestamp_ms: i64,
+    /// Share of one host core since the previous sample; 200 = two busy cores
+    pub cpu_percent: f64,
+    /// CPU time QEMU has used since it started
+    pub cpu_seconds: f64,
+    pub rss_bytes: u64,
+    pub disk_read_bytes: u64,
+    pub disk_write_bytes: u64,
+    pub disk_read_ops: u64,
+    pub disk_write_ops: u64,
+    /// Bytes received and sent; None unless the VM has QoS relays
+    pub network: Option<(u64, u64)>,
+    pub uptime_seconds: u64,
+}
+
+/// Sample a VM's usage; CPU % is relative to `previous`, or 0 without one
+pub async fn sample(state: &StateManager, process: &VmProcess, previous: Option<&VmUsage>) -> Result<VmUsage> {
+    let now = chrono::Utc::now();
+    let (cpu_seconds, rss_bytes) = process_usage(process.pid).unwrap_or_default();
+
+    let mut usage = VmUsage {
+        pid: process.pid,
+        timestamp_ms: now.timestamp_millis(),
+        cpu_seconds,
+        rss_bytes,
+        network: state.qos_traffic(&process.vm_id),
+        uptime_seconds: (now.timestamp() - process.started_at).max(0) as u64,
+        ..Default::default()
+    };
+
+    // A restarted QEMU starts counting from zero
+    if let Some(prev) = previous.filter(|p| p.pid == process.pid) {
+        let elapsed = (usage.timestamp_ms - prev.timestamp_ms) as f64 / 1000.0;
+        if elapsed > 0.0 {
+            usage.cpu_percent = ((cpu_seconds - prev.cpu_seconds) / elapsed * 100.0).max(0.0);
+        }
+    }
+
+    let qmp = QmpClient::new(&process.qmp_socket);
+    qmp.connect().await?;
+    for entry in qmp.query_blockstats().await? {
+        usage.disk_read_bytes += entry.stats.rd_bytes;
+        usage.disk_write_bytes += entry.stats.wr_bytes;
+        usage.disk_read_ops += entry.stats.rd_operations;
+        usage.disk_write_ops += entry.stats.wr_operations;
+    }
+
+    Ok(usage)
+}
+
+/// Sample a VM's usage twice, `CPU_SAMPLE_INTERVAL` apart, for a CPU figure
+pub async fn current(state: &StateManager, process: &VmProcess) -> Result<VmUsage> {
+    let first = sample(state, process, None).await?;
+    tokio::time::sleep(CPU_SAMPLE_INTERVAL).await;
+    sample(state, process, Some(&first)).await
+}
+
+/// CPU seconds used and resident set size in bytes of a process
+fn process_usage(pid: u32) -> Option<(f64, u64)> {
+    // Linux
+    if let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        // Fields after the parenthesised command; utime and stime are 14 and 15
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+        let ticks: u64 = fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?;
+
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+        let rss_kb: u64 = status
+            .lines()
+            .find_map(|l| l.strip_prefix("VmRSS:"))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap_or(0);
+
+        return Some((ticks as f64 / USER_HZ, rss_kb * 1024));
+    }
+
+    // macOS: cumulative CPU time as [[hh:]mm:]ss.cc and RSS in KB
+    let output = Command::new("ps")
+        .args(["-o", "time=,rss=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut parts = text.split_whitespace();
+    let cpu_seconds = parts
+        .next()?
+        .split(':')
+        .try_fold(0.0, |acc, part| part.parse::<f64>().ok().map(|v| acc * 60.0 + v))?;
+    let rss_kb: u64 = parts.next()?.parse().ok()?;
+
+    Some((cpu_seconds, rss_kb * 1024))
+}
@@ -0,0 +1,255 @@
+//! VM templates and linked clones
+//!
+//! Marking a stopped VM as a template freezes it: it no longer starts or
+//! changes, and neither do its disks. A clone gets a qcow2 overlay on each of
+//! the template's writable disks, so it is created in seconds however large
+//! they are; read-only volumes are shared. Every clone has its own VM ID,
+//! from which QEMU derives its NIC MAC addresses and SMBIOS UUID, the value
+//! systemd takes for an empty `/etc/machine-id`.
+
+use crate::snapshot::{active_path, create_layer, new_layer_path};
+use crate::state::StateManager;
+use infrasim_common::{types::*, Error, Result};
+use std::collections::HashMap;
+use tokio::fs;
+use tracing::{info, warn};
+
+/// Freeze a stopped VM as a template
+pub fn mark(state: &StateManager, vm: &Vm) -> Result<Vm> {
+    if vm.spec.template {
+        return Ok(vm.clone());
+    }
+    if !matches!(vm.status.state, VmState::Stopped | VmState::Pending)
+        || state.get_vm_process(&vm.meta.id).is_some()
+    {
+        return Err(Error::InvalidConfig(format!(
+            "VM {} is {}; stop it before marking it as a template",
+            vm.meta.name, vm.status.state
+        )));
+    }
+
+    for volume in disks(state, vm)? {
+        if volume.spec.kind == VolumeKind::Device {
+            return Err(Error::InvalidConfig(format!(
+                "Device volume {} can't back linked clones",
+                volume.meta.name
+            )));
+        }
+        active_path(&volume)?;
+    }
+
+    state.update_vm_spec(
+        &vm.meta.id,
+        VmSpec {
+            template: true,
+            ..vm.spec.clone()
+        },
+    )?;
+    state.update_vm_status(
+        &vm.meta.id,
+        VmStatus {
+            state: VmState::Stopped,
+            ..vm.status.clone()
+        },
+    )?;
+    state.emit_vm_event(&vm.meta.id, "Marked as template");
+    info!("Marked VM {} as a template", vm.meta.name);
+
+    get_vm(state, &vm.meta.id)
+}
+
+/// Create a stopped linked clone of a template
+pub async fn clone_vm(
+    state: &StateManager,
+    template: &Vm,
+    name: String,
+    labels: HashMap<String, String>,
+) -> Result<Vm> {
+    if !template.spec.template {
+        return Err(Error::InvalidConfig(format!(
+            "VM {} is not a template",
+            template.meta.name
+        )));
+    }
+    if state.get_vm_by_name(&name)?.is_some() {
+        return Err(Error::AlreadyExists {
+            kind: "vm".to_string(),
+            id: name,
+        });
+    }
+
+    // Volume records first, so the clone's spec can point at them
+    let mut layered = Vec::new();
+    let mut ids = HashMap::new();
+    for volume in disks(state, template)? {
+        if volume.spec.read_only {
+            continue;
+        }
+        let spec = VolumeSpec {
+            source: active_path(&volume)?.to_string_lossy().to_string(),
+            integrity: IntegrityConfig::default(),
+            format: "qcow2".to_string(),
+            overlay: true,
+            build: None,
+            ..volume.spec.clone()
+        };
+        let clone_name = format!("{}-{}", name, volume.meta.name);
+        let created = match state.create_volume(clone_name, spec, HashMap::new()) {
+            Ok(created) => created,
+            Err(e) => {
+                discard(state, None, &layered);
+                return Err(e);
+            }
+        };
+        ids.insert(volume.meta.id.clone(), created.meta.id.clone());
+        layered.push((volume, created));
+    }
+    let map = |id: &String| ids.get(id).cloned().unwrap_or_else(|| id.clone());
+
+    let spec = VmSpec {
+        volume_ids: template.spec.volume_ids.iter().map(map).collect(),
+        boot_disk_id: template.spec.boot_disk_id.as_ref().map(map),
+        template: false,
+        cloned_from: Some(template.meta.id.clone()),
+        ..template.spec.clone().without_pinned_addresses()
+    };
+    let mut vm_labels = template.meta.labels.clone();
+    vm_labels.extend(labels);
+    let vm = match state.create_vm(name, spec, vm_labels) {
+        Ok(vm) => vm,
+        Err(e) => {
+            discard(state, None, &layered);
+            return Err(e);
+        }
+    };
+
+    for (source, volume) in &layered {
+        if let Err(e) = link(state, &vm, source, volume).await {
+            discard(state, Some(&vm), &layered);
+            let _ = fs::remove_dir_all(state.config().snapshot_dir(&vm.meta.id)).await;
+            return Err(e);
+        }
+    }
+
+    state.update_vm_status(
+        &vm.meta.id,
+        VmStatus {
+            state: VmState::Stopped,
+            ..Default::default()
+        },
+    )?;
+    state.emit_vm_event(
+        &vm.meta.id,
+        format!("Cloned from template {}", template.meta.name),
+    );
+    info!(
+        "Cloned VM {} from template {}",
+        vm.meta.name, template.meta.name
+    );
+
+    get_vm(state, &vm.meta.id)
+}
+
+/// Names for `count` clones: a trailing number in `name` is counted up,
+/// keeping its width (`dev-01` gives `dev-01`, `dev-02`, ...); otherwise
+/// `-1`, `-2`, ... is appended. A single clone keeps the name as given.
+pub fn clone_names(name: &str, count: usize) -> Vec<String> {
+    if count <= 1 {
+        return vec![name.to_string()];
+    }
+    let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &name[stem.len()..];
+    match digits.parse::<usize>() {
+        Ok(start) => (start..start + count)
+            .map(|n| format!("{}{:0width$}", stem, n, width = digits.len()))
+            .collect(),
+        Err(_) => (1..=count).map(|n| format!("{}-{}", name, n)).collect(),
+    }
+}
+
+/// Why a template can't be deleted, if clones still use its disks
+pub fn deletion_blocker(state: &StateManager, vm: &Vm) -> Result<Option<String>> {
+    if !vm.spec.template {
+        return Ok(None);
+    }
+    let clones: Vec<String> = state
+        .list_vms()?
+        .into_iter()
+        .filter(|v| v.spec.cloned_from.as_ref() == Some(&vm.meta.id))
+        .map(|v| v.meta.name)
+        .collect();
+    Ok((!clones.is_empty()).then(|| {
+        format!(
+            "Template {} backs clones {}; delete them first",
+            vm.meta.name,
+            clones.join(", ")
+        )
+    }))
+}
+
+/// Template attached to a volume, if any; its disks must not change
+pub fn template_user(state: &StateManager, volume_id: &str) -> Result<Option<Vm>> {
+    Ok(state.list_vms()?.into_iter().find(|vm| {
+        vm.spec.template
+            && (vm.spec.volume_ids.iter().any(|v| v == volume_id)
+                || vm.spec.boot_disk_id.as_deref() == Some(volume_id))
+    }))
+}
+
+/// Start a clone's disk as an overlay on the template's active image
+async fn link(state: &StateManager, vm: &Vm, source: &Volume, volume: &Volume) -> Result<()> {
+    let backing = active_path(source)?;
+    let overlay = new_layer_path(state, &vm.meta.id).await?;
+    create_layer(state, &backing, &overlay, volume.spec.encryption.as_ref()).await?;
+    state.update_volume_status(
+        &volume.meta.id,
+        VolumeStatus {
+            ready: true,
+            local_path: Some(overlay.to_string_lossy().to_string()),
+            actual_size: fs::metadata(&overlay).await?.len(),
+            ..Default::default()
+        },
+    )
+}
+
+/// Remove the records of a clone that could not be completed
+fn discard(state: &StateManager, vm: Option<&Vm>, layered: &[(Volume, Volume)]) {
+    if let Some(vm) = vm {
+        if let Err(e) = state.delete_vm(&vm.meta.id) {
+            warn!("Failed to remove incomplete clone {}: {}", vm.meta.name, e);
+        }
+    }
+    for (_, volume) in layered {
+        if let Err(e) = state.delete_volume(&volume.meta.id) {
+            warn!(
+                "Failed to remove volume {} of an incomplete clone: {}",
+                volume.meta.name, e
+            );
+        }
+    }
+}
+
+/// Boot disk and attached volumes of a VM, each once
+fn disks(state: &StateManager, vm: &Vm) -> Result<Vec<Volume>> {
+    let mut ids: Vec<&String> = vm.spec.boot_disk_id.iter().collect();
+    for id in &vm.spec.volume_ids {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids.into_iter()
+        .map(|id| {
+            state.get_volume(id)?.ok_or_else(|| Error::NotFound {
+                kind: "volume".to_string(),
+                id: id.clone(),
+            })
+        })
+        .collect()
+}
+
+fn get_vm(state: &StateManager, vm_id: &str) -> Result<Vm> {
+    state.get_vm(vm_id)?.ok_or_else(|| Error::NotFound {
+        kind: "vm".to_string(),
+        id: vm_id.to_string(),
+    })
+}
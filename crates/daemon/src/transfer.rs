@@ -0,0 +1,166 @@
+//! Volume push/pull between daemons
+//!
+//! A pushed volume is a standalone image in the content-addressed store,
+//! copied to a remote store (a directory or an S3-compatible bucket) under
+//! its digest, plus a manifest at `volumes/<id>.json` describing the volume.
+//! Disk images are flattened on push, so snapshot layers and overlays travel
+//! as one image; encrypted images stay encrypted with the same key ID.
+//!
+//! Pulling fetches the manifest and the image, verifies the digest and
+//! creates a new volume. Plaintext qcow2 disks are overlays on the store
+//! object; anything else gets its own copy so writes never touch the store.
+
+use crate::encryption::Keyring;
+use crate::s3::S3Remote;
+use crate::snapshot;
+use crate::state::StateManager;
+use infrasim_common::{types::*, ContentAddressedStore, DirRemote, Error, RemoteStore, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs;
+use tracing::info;
+
+/// What `push` records next to a volume's image
+#[derive(Debug, Serialize, Deserialize)]
+struct VolumeManifest {
+    id: String,
+    name: String,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    spec: VolumeSpec,
+    digest: String,
+    size_bytes: u64,
+    pushed_at: i64,
+}
+
+/// Result of pushing a volume
+pub struct Pushed {
+    pub digest: String,
+    pub size_bytes: u64,
+    /// False if the remote already had the image
+    pub uploaded: bool,
+}
+
+/// Open an absolute directory path or `s3://bucket/prefix` as a remote store
+pub fn open_remote(state: &StateManager, remote: &str) -> Result<Box<dyn RemoteStore>> {
+    Ok(match StorageTarget::parse(remote)? {
+        StorageTarget::Dir(dir) => Box::new(DirRemote::new(dir)),
+        StorageTarget::S3 { bucket, prefix } => Box::new(S3Remote::new(state.config(), &bucket, &prefix)?),
+    })
+}
+
+/// Push a volume's image and manifest to a remote store
+///
+/// The caller makes sure nothing writes to the volume while it is copied.
+pub async fn push(state: &StateManager, volume: &Volume, remote: &str) -> Result<Pushed> {
+    let remote = open_remote(state, remote)?;
+    let path = volume
+        .status
+        .local_path
+        .as_ref()
+        .filter(|_| volume.status.ready)
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::VolumeError(format!("Volume {} is not ready", volume.meta.name)))?;
+
+    let cas = state.cas();
+    let mut spec = volume.spec.clone();
+    let digest = if volume.spec.kind == VolumeKind::Disk {
+        let image = cas.tmp_dir().join(format!("{}.qcow2", uuid::Uuid::new_v4()));
+        let result = snapshot::convert(state, &path, &image, volume.spec.encryption.as_ref(), &mut |_| {}).await;
+        if let Err(e) = result {
+            let _ = fs::remove_file(&image).await;
+            return Err(e);
+        }
+        let digest = ContentAddressedStore::hash_file(&image).await?;
+        cas.adopt_file(&image, &digest).await?;
+        spec.format = "qcow2".to_string();
+        digest
+    } else {
+        cas.put_file(&path).await?
+    };
+    let size_bytes = fs::metadata(cas.object_path(&digest)).await?.len();
+
+    let uploaded = cas.push(&digest, remote.as_ref()).await?;
+
+    spec.source = String::new();
+    spec.overlay = false;
+    spec.integrity = IntegrityConfig::default();
+    let manifest = VolumeManifest {
+        id: volume.meta.id.clone(),
+        name: volume.meta.name.clone(),
+        labels: volume.meta.labels.clone(),
+        spec,
+        digest: digest.clone(),
+        size_bytes,
+        pushed_at: chrono::Utc::now().timestamp(),
+    };
+    remote
+        .put(&manifest_key(&volume.meta.id), serde_json::to_vec_pretty(&manifest)?)
+        .await?;
+
+    info!(
+        "Pushed volume {} as {} to {}",
+        volume.meta.name,
+        digest,
+        remote.location()
+    );
+    Ok(Pushed {
+        digest,
+        size_bytes,
+        uploaded,
+    })
+}
+
+/// Create a volume from one pushed to a remote store
+pub async fn pull(
+    state: &StateManager,
+    volume_id: &str,
+    remote: &str,
+    name: Option<String>,
+) -> Result<Volume> {
+    if volume_id.is_empty() || !volume_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(Error::InvalidConfig(format!("Invalid volume ID '{}'", volume_id)));
+    }
+    let remote = open_remote(state, remote)?;
+    let manifest: VolumeManifest = serde_json::from_slice(&remote.get(&manifest_key(volume_id)).await?)?;
+
+    let name = name.unwrap_or_else(|| manifest.name.clone());
+    if state.list_volumes()?.iter().any(|v| v.meta.name == name) {
+        return Err(Error::AlreadyExists {
+            kind: "volume".to_string(),
+            id: name,
+        });
+    }
+    // The image is useless without the passphrase it was encrypted with
+    if let Some(enc) = &manifest.spec.encryption {
+        Keyring::new(state.config()).secret_object(enc)?;
+    }
+
+    let object = state.cas().pull(&manifest.digest, remote.as_ref()).await?;
+
+    let mut spec = manifest.spec;
+    if spec.kind == VolumeKind::Disk && spec.format == "qcow2" && spec.encryption.is_none() {
+        spec.source = object.to_string_lossy().to_string();
+        spec.overlay = true;
+    } else {
+        let dir = state.config().pull_dir();
+        fs::create_dir_all(&dir).await?;
+        let copy = dir.join(format!("{}.{}", uuid::Uuid::new_v4(), spec.format));
+        fs::copy(&object, &copy).await?;
+        spec.source = copy.to_string_lossy().to_string();
+    }
+
+    let volume = state.create_volume(name, spec, manifest.labels)?;
+    info!(
+        "Pulled volume {} ({}) from {}",
+        volume.meta.name,
+        manifest.digest,
+        remote.location()
+    );
+    Ok(volume)
+}
+
+fn manifest_key(volume_id: &str) -> String {
+    format!("volumes/{}.json", volume_id)
+}
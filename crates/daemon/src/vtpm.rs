@@ -0,0 +1,416 @@
+//! vTPM backing for VMs with `enable_tpm`
+//!
+//! Every VM gets its own swtpm. QEMU's TPM emulator backend connects to the
+//! control channel and hands swtpm the data channel as a file descriptor
+//! (`CMD_SET_DATAFD`), after which nothing else can talk to the TPM. The
+//! daemon therefore sits on the control channel: it swaps the data fd for
+//! one end of a socketpair it owns and forwards guest commands to swtpm
+//! itself. That lets it slip its own quote commands in between guest
+//! commands and record every PCR extension into an event log.
+//!
+//! Host commands use a transient key slot and need an empty endorsement
+//! hierarchy password, so a guest that sets one or fills every slot at the
+//! moment of the quote makes it fail.
+
+use crate::config::DaemonConfig;
+use infrasim_common::{
+    attestation::vtpm,
+    types::{TpmEvent, TpmQuote},
+    Error, Result,
+};
+use nix::sys::socket::{recvmsg, sendmsg, ControlMessage, ControlMessageOwned, MsgFlags};
+use parking_lot::Mutex;
+use std::io::{IoSlice, IoSliceMut, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// swtpm control command that passes the data channel fd
+const CMD_SET_DATAFD: u32 = 0x10;
+
+/// Largest TPM command or response accepted on the data channel
+const MAX_TPM_MESSAGE: usize = 8192;
+
+/// How long to wait for swtpm to create its control socket
+const SWTPM_START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the boot quote, so firmware and kernel measurements are in
+pub const BOOT_QUOTE_DELAY: Duration = Duration::from_secs(60);
+
+/// Attempts at the boot quote while the guest has not started the TPM
+pub const BOOT_QUOTE_ATTEMPTS: u32 = 10;
+
+/// Channel to swtpm shared by guest and host commands, plus the event log
+struct TpmChannel {
+    stream: Option<UnixStream>,
+    event_log: Vec<TpmEvent>,
+}
+
+impl TpmChannel {
+    /// Send one command and read the complete response
+    fn transact(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        let stream = self.stream.as_mut().ok_or_else(|| {
+            Error::AttestationError("vTPM is not attached to QEMU yet".to_string())
+        })?;
+        stream.write_all(command)?;
+        read_message(stream)?
+            .ok_or_else(|| Error::AttestationError("swtpm closed the data channel".to_string()))
+    }
+}
+
+/// A running swtpm with the daemon proxying its control channel
+pub struct Vtpm {
+    vm_id: String,
+    swtpm: Mutex<Child>,
+    channel: Arc<Mutex<TpmChannel>>,
+    proxy_socket: PathBuf,
+}
+
+impl Vtpm {
+    /// Start swtpm for a VM and listen for QEMU on the proxy socket
+    pub fn start(config: &DaemonConfig, vm_id: &str) -> Result<Self> {
+        let tpm_dir = config.tpm_dir(vm_id);
+        let state_dir = tpm_dir.join("state");
+        std::fs::create_dir_all(&state_dir)?;
+
+        let ctrl_socket = tpm_dir.join("swtpm.ctrl");
+        let proxy_socket = config.tpm_socket_path(vm_id);
+        for socket in [&ctrl_socket, &proxy_socket] {
+            if socket.exists() {
+                std::fs::remove_file(socket)?;
+            }
+        }
+
+        let swtpm = Command::new("swtpm")
+            .args(["socket", "--tpm2", "--terminate"])
+            .arg("--tpmstate")
+            .arg(format!("dir={}", state_dir.display()))
+            .arg("--ctrl")
+            .arg(format!("type=unixio,path={}", ctrl_socket.display()))
+            .arg("--log")
+            .arg(format!("file={},level=1", tpm_dir.join("swtpm.log").display()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| Error::Qemu(format!("Failed to spawn swtpm: {}", e)))?;
+
+        let vtpm = Self {
+            vm_id: vm_id.to_string(),
+            swtpm: Mutex::new(swtpm),
+            channel: Arc::new(Mutex::new(TpmChannel {
+                stream: None,
+                event_log: Vec::new(),
+            })),
+            proxy_socket,
+        };
+
+        let ctrl = vtpm.wait_for_ctrl(&ctrl_socket)?;
+        let listener = UnixListener::bind(&vtpm.proxy_socket)?;
+        let channel = vtpm.channel.clone();
+        let id = vtpm.vm_id.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = proxy_ctrl(listener, ctrl, channel) {
+                warn!("vTPM control proxy for {} stopped: {}", id, e);
+            }
+        });
+
+        info!("Started swtpm for VM {}", vm_id);
+        Ok(vtpm)
+    }
+
+    /// Connect to swtpm's control socket once it appears
+    fn wait_for_ctrl(&self, path: &Path) -> Result<UnixStream> {
+        let deadline = std::time::Instant::now() + SWTPM_START_TIMEOUT;
+        loop {
+            if let Ok(stream) = UnixStream::connect(path) {
+                return Ok(stream);
+            }
+            if let Ok(Some(status)) = self.swtpm.lock().try_wait() {
+                return Err(Error::Qemu(format!("swtpm exited during startup: {}", status)));
+            }
+            if std::time::Instant::now() > deadline {
+                self.stop();
+                return Err(Error::Timeout {
+                    seconds: SWTPM_START_TIMEOUT.as_secs(),
+                });
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// QEMU arguments attaching the vTPM
+    pub fn qemu_args(&self) -> Vec<String> {
+        vec![
+            "-chardev".to_string(),
+            format!("socket,id=chrtpm,path={}", self.proxy_socket.display()),
+            "-tpmdev".to_string(),
+            "emulator,id=tpm0,chardev=chrtpm".to_string(),
+            "-device".to_string(),
+            "tpm-tis-device,tpmdev=tpm0".to_string(),
+        ]
+    }
+
+    /// Take a quote over `pcrs`, bound to `nonce`
+    ///
+    /// Guest commands are held back for the duration, so the PCR values
+    /// read and the quote describe the same TPM state.
+    pub async fn quote(self: &Arc<Self>, label: &str, nonce: Vec<u8>, pcrs: Vec<u32>) -> Result<TpmQuote> {
+        let vtpm = self.clone();
+        let label = label.to_string();
+        tokio::task::spawn_blocking(move || vtpm.quote_blocking(label, &nonce, &pcrs))
+            .await
+            .map_err(|e| Error::Internal(format!("vTPM quote task failed: {}", e)))?
+    }
+
+    fn quote_blocking(&self, label: String, nonce: &[u8], pcrs: &[u32]) -> Result<TpmQuote> {
+        if !self.is_running() {
+            return Err(Error::AttestationError(format!(
+                "vTPM of VM {} is no longer running",
+                self.vm_id
+            )));
+        }
+        let mut channel = self.channel.lock();
+
+        let response = channel.transact(&vtpm::create_primary_ak_command())?;
+        if vtpm::response_code(&response) == Some(vtpm::TPM_RC_INITIALIZE) {
+            return Err(Error::AttestationError(
+                "the guest has not started its TPM yet".to_string(),
+            ));
+        }
+        let (handle, ak_public) = vtpm::parse_create_primary_response(&response)?;
+
+        let result = read_and_quote(&mut channel, handle, nonce, pcrs);
+        if let Err(e) = channel.transact(&vtpm::flush_context_command(handle)) {
+            warn!("Failed to flush vTPM attestation key of {}: {}", self.vm_id, e);
+        }
+        let (values, quoted, signature) = result?;
+
+        debug!("Quoted {} PCRs of VM {} ({})", values.len(), self.vm_id, label);
+        Ok(TpmQuote {
+            vm_id: self.vm_id.clone(),
+            label,
+            pcrs: values
+                .into_iter()
+                .map(|(pcr, value)| (pcr, hex::encode(value)))
+                .collect(),
+            quoted,
+            signature,
+            ak_public,
+            nonce: nonce.to_vec(),
+            event_log: channel.event_log.clone(),
+            created_at: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    /// Whether swtpm is still running
+    pub fn is_running(&self) -> bool {
+        matches!(self.swtpm.lock().try_wait(), Ok(None))
+    }
+
+    /// Stop swtpm and the proxy
+    pub fn stop(&self) {
+        let mut swtpm = self.swtpm.lock();
+        let _ = swtpm.kill();
+        let _ = swtpm.wait();
+        let _ = std::fs::remove_file(&self.proxy_socket);
+    }
+}
+
+/// Read the requested PCRs and quote them with the key at `handle`
+fn read_and_quote(
+    channel: &mut TpmChannel,
+    handle: u32,
+    nonce: &[u8],
+    pcrs: &[u32],
+) -> Result<(std::collections::BTreeMap<u32, Vec<u8>>, Vec<u8>, Vec<u8>)> {
+    // PCR_Read returns at most eight digests, so read until all are in
+    let mut values = std::collections::BTreeMap::new();
+    loop {
+        let missing: Vec<u32> = pcrs
+            .iter()
+            .copied()
+            .filter(|p| !values.contains_key(p))
+            .collect();
+        if missing.is_empty() {
+            break;
+        }
+        let read = vtpm::parse_pcr_read_response(&channel.transact(&vtpm::pcr_read_command(&missing))?)?;
+        if read.is_empty() {
+            return Err(Error::AttestationError(format!(
+                "vTPM has no SHA-256 value for PCRs {:?}",
+                missing
+            )));
+        }
+        values.extend(read);
+    }
+
+    let response = channel.transact(&vtpm::quote_command(handle, nonce, pcrs))?;
+    let (quoted, signature) = vtpm::parse_quote_response(&response)?;
+    Ok((values, quoted, signature))
+}
+
+/// Forward QEMU's control channel to swtpm, swapping in our own data channel
+fn proxy_ctrl(listener: UnixListener, swtpm: UnixStream, channel: Arc<Mutex<TpmChannel>>) -> Result<()> {
+    // swtpm runs with --terminate, so there is exactly one QEMU connection
+    let (qemu, _) = listener.accept()?;
+    drop(listener);
+
+    let mut replies = swtpm.try_clone()?;
+    let mut to_qemu = qemu.try_clone()?;
+    std::thread::spawn(move || {
+        let _ = std::io::copy(&mut replies, &mut to_qemu);
+    });
+
+    let mut buf = [0u8; 4096];
+    loop {
+        let (len, fd) = recv_with_fd(qemu.as_raw_fd(), &mut buf)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        let Some(fd) = fd else {
+            (&swtpm).write_all(&buf[..len])?;
+            continue;
+        };
+
+        // SAFETY: the descriptor was just received via SCM_RIGHTS and is owned by nobody else
+        let guest = unsafe { UnixStream::from_raw_fd(fd) };
+        let is_set_datafd = len >= 4 && u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) == CMD_SET_DATAFD;
+        if !is_set_datafd {
+            warn!("Dropping unexpected file descriptor on vTPM control channel");
+            (&swtpm).write_all(&buf[..len])?;
+            continue;
+        }
+
+        let (ours, theirs) = UnixStream::pair()?;
+        sendmsg::<()>(
+            swtpm.as_raw_fd(),
+            &[IoSlice::new(&buf[..len])],
+            &[ControlMessage::ScmRights(&[theirs.as_raw_fd()])],
+            MsgFlags::empty(),
+            None,
+        )
+        .map_err(|e| Error::Internal(format!("Failed to pass vTPM data channel: {}", e)))?;
+        drop(theirs);
+
+        channel.lock().stream = Some(ours);
+        let channel = channel.clone();
+        std::thread::spawn(move || relay_guest(guest, channel));
+    }
+}
+
+/// Receive bytes and at most one passed file descriptor
+fn recv_with_fd(fd: RawFd, buf: &mut [u8]) -> Result<(usize, Option<RawFd>)> {
+    let mut iov = [IoSliceMut::new(buf)];
+    let mut cmsg = nix::cmsg_space!([RawFd; 1]);
+    let msg = recvmsg::<()>(fd, &mut iov, Some(&mut cmsg), MsgFlags::empty())
+        .map_err(|e| Error::Internal(format!("vTPM control channel: {}", e)))?;
+
+    let mut passed = None;
+    for cmsg in msg.cmsgs() {
+        if let ControlMessageOwned::ScmRights(fds) = cmsg {
+            passed = fds.first().copied();
+        }
+    }
+    Ok((msg.bytes, passed))
+}
+
+/// Forward guest commands to swtpm, recording PCR extensions
+fn relay_guest(mut guest: UnixStream, channel: Arc<Mutex<TpmChannel>>) {
+    loop {
+        let command = match read_message(&mut guest) {
+            Ok(Some(command)) => command,
+            Ok(None) => return,
+            Err(e) => {
+                debug!("vTPM guest channel closed: {}", e);
+                return;
+            }
+        };
+
+        let mut channel = channel.lock();
+        let response = match channel.transact(&command) {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("vTPM command failed: {}", e);
+                return;
+            }
+        };
+        record(&mut channel.event_log, &command, &response);
+        drop(channel);
+
+        if guest.write_all(&response).is_err() {
+            return;
+        }
+    }
+}
+
+/// Update the event log after a successful guest command
+fn record(event_log: &mut Vec<TpmEvent>, command: &[u8], response: &[u8]) {
+    let Some((_, _, cc)) = vtpm::parse_header(command) else {
+        return;
+    };
+    let cleared = cc == vtpm::TPM_CC_STARTUP
+        && command.get(10..12) == Some(&[0, 0])
+        && vtpm::response_code(response) == Some(0);
+    if cleared {
+        event_log.clear();
+    } else if let Some(event) = vtpm::event_from_exchange(command, response) {
+        event_log.push(event);
+    }
+}
+
+/// Read one length-prefixed TPM message, None on a clean EOF
+fn read_message(stream: &mut UnixStream) -> Result<Option<Vec<u8>>> {
+    let mut header = [0u8; 10];
+    match stream.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let size = u32::from_be_bytes([header[2], header[3], header[4], header[5]]) as usize;
+    if !(10..=MAX_TPM_MESSAGE).contains(&size) {
+        return Err(Error::AttestationError(format!("bad TPM message size {}", size)));
+    }
+    let mut message = header.to_vec();
+    message.resize(size, 0);
+    stream.read_exact(&mut message[10..])?;
+    Ok(Some(message))
+}
+
+/// Persist a quote next to the VM's TPM state
+pub fn save_quote(config: &DaemonConfig, quote: &TpmQuote) -> Result<()> {
+    let dir = config.tpm_dir(&quote.vm_id).join("quotes");
+    std::fs::create_dir_all(&dir)?;
+    let name: String = quote
+        .label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let path = dir.join(format!("{}-{}.json", quote.created_at, name));
+    std::fs::write(path, serde_json::to_vec_pretty(quote)?)?;
+    Ok(())
+}
+
+/// Quotes recorded for a VM, oldest first
+pub fn load_quotes(config: &DaemonConfig, vm_id: &str) -> Result<Vec<TpmQuote>> {
+    let dir = config.tpm_dir(vm_id).join("quotes");
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut quotes = Vec::new();
+    for entry in entries.flatten() {
+        match std::fs::read(entry.path()).map(|data| serde_json::from_slice::<TpmQuote>(&data)) {
+            Ok(Ok(quote)) => quotes.push(quote),
+            _ => warn!("Skipping unreadable vTPM quote {}", entry.path().display()),
+        }
+    }
+    quotes.sort_by_key(|q| q.created_at);
+    Ok(quotes)
+}
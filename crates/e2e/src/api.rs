@@ -0,0 +1,487 @@
+//! Non-browser test steps
+//!
+//! Runs the `grpc_call`, `http_request` and `shell` steps of a spec. Daemon
+//! RPCs go through `grpcurl` with the daemon's proto file, so any RPC can be
+//! called by name without a generated client. gRPC responses use the proto
+//! JSON mapping: fields are camelCase (`vm.meta.createdAt`), enums are names
+//! (`VM_STATE_RUNNING`) and 64-bit integers are strings.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::error::{E2eError, E2eResult};
+use crate::playwright::StepResult;
+use crate::spec::{FieldAssertion, TestStep};
+
+/// Fully qualified name of the daemon's gRPC service
+const GRPC_SERVICE: &str = "infrasim.v1.InfraSimDaemon";
+
+/// Configuration for API steps
+#[derive(Debug, Clone)]
+pub struct ApiConfig {
+    /// Daemon proto file passed to grpcurl
+    pub proto_path: PathBuf,
+
+    /// grpcurl executable
+    pub grpcurl: String,
+
+    /// Timeout for gRPC calls and HTTP requests
+    pub timeout: Duration,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            proto_path: PathBuf::from("proto/infrasim.proto"),
+            grpcurl: "grpcurl".to_string(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Runs API steps against the daemon and web server
+pub struct ApiClient {
+    config: ApiConfig,
+    base_url: String,
+    daemon_addr: String,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    /// Create a client for a web server base URL and daemon address
+    pub fn new(config: ApiConfig, base_url: &str, daemon_addr: &str) -> E2eResult<Self> {
+        let http = reqwest::Client::builder().timeout(config.timeout).build()?;
+        Ok(Self {
+            config,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            daemon_addr: daemon_addr.to_string(),
+            http,
+        })
+    }
+
+    /// Execute an API step, recording the values it saves in `vars`
+    pub async fn execute_step(&self, step: &TestStep, vars: &mut HashMap<String, Value>) -> StepResult {
+        let start = Instant::now();
+        let step_name = step_name(step);
+
+        debug!("Executing step: {}", step_name);
+
+        let result = match step {
+            TestStep::GrpcCall { method, request, expect, save } => self
+                .grpc_call(method, request)
+                .await
+                .and_then(|response| check_and_save(&response, expect, save, vars)),
+            TestStep::HttpRequest { method, path, headers, body, status, expect, save } => self
+                .http_request(method, path, headers, body.as_ref(), *status)
+                .await
+                .and_then(|response| check_and_save(&response, expect, save, vars)),
+            TestStep::Shell {
+                command,
+                exit_code,
+                stdout_contains,
+                stderr_contains,
+                timeout_ms,
+                expect,
+                save,
+            } => self
+                .shell(command, *exit_code, stdout_contains.as_deref(), stderr_contains.as_deref(), *timeout_ms)
+                .await
+                .and_then(|stdout| check_and_save(&stdout, expect, save, vars)),
+            _ => Err(E2eError::StepFailed {
+                step: step_name.clone(),
+                reason: "not an API step".to_string(),
+            }),
+        };
+
+        StepResult {
+            success: result.is_ok(),
+            step_name,
+            duration_ms: start.elapsed().as_millis() as u64,
+            error: result.err().map(|e| e.to_string()),
+            screenshot_path: None,
+        }
+    }
+
+    async fn grpc_call(&self, method: &str, request: &Value) -> E2eResult<Value> {
+        let (plaintext, addr) = match self.daemon_addr.strip_prefix("https://") {
+            Some(addr) => (false, addr),
+            None => (true, self.daemon_addr.strip_prefix("http://").unwrap_or(&self.daemon_addr)),
+        };
+        let proto_dir = self.config.proto_path.parent().unwrap_or(Path::new("."));
+        let proto_file = self.config.proto_path.file_name().unwrap_or_default();
+
+        let mut cmd = Command::new(&self.config.grpcurl);
+        if plaintext {
+            cmd.arg("-plaintext");
+        }
+        cmd.arg("-emit-defaults")
+            .arg("-max-time")
+            .arg(self.config.timeout.as_secs().max(1).to_string())
+            .arg("-import-path")
+            .arg(proto_dir)
+            .arg("-proto")
+            .arg(proto_file)
+            .args(["-d", "@", addr])
+            .arg(format!("{}/{}", GRPC_SERVICE, method))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd.spawn().map_err(|e| E2eError::StepFailed {
+            step: format!("grpc_call:{}", method),
+            reason: format!("failed to run {}: {}", self.config.grpcurl, e),
+        })?;
+
+        // The request is read from stdin; closing it ends the request
+        let body = if request.is_null() { "{}".to_string() } else { request.to_string() };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(body.as_bytes()).await?;
+        }
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(E2eError::StepFailed {
+                step: format!("grpc_call:{}", method),
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+
+        Ok(parse_output(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    async fn http_request(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &HashMap<String, String>,
+        body: Option<&Value>,
+        status: Option<u16>,
+    ) -> E2eResult<Value> {
+        let http_method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|_| E2eError::SpecParse(format!("Invalid HTTP method: {}", method)))?;
+        let url = if path.starts_with("http://") || path.starts_with("https://") {
+            path.to_string()
+        } else {
+            format!("{}{}", self.base_url, path)
+        };
+
+        let mut request = self.http.request(http_method, &url);
+        for (name, value) in headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await?;
+        let code = response.status();
+        let text = response.text().await?;
+
+        let expected = match status {
+            Some(expected) => code.as_u16() == expected,
+            None => code.is_success(),
+        };
+        if !expected {
+            return Err(E2eError::AssertionFailed(format!(
+                "{} {} returned {}, expected {}: {}",
+                method,
+                url,
+                code.as_u16(),
+                status.map_or("2xx".to_string(), |s| s.to_string()),
+                text.trim()
+            )));
+        }
+
+        Ok(parse_output(&text))
+    }
+
+    async fn shell(
+        &self,
+        command: &str,
+        exit_code: i32,
+        stdout_contains: Option<&str>,
+        stderr_contains: Option<&str>,
+        timeout_ms: u64,
+    ) -> E2eResult<Value> {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command).kill_on_drop(true);
+
+        let output = tokio::time::timeout(Duration::from_millis(timeout_ms), cmd.output())
+            .await
+            .map_err(|_| E2eError::Timeout(format!("command `{}`", command)))??;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        let code = output.status.code().unwrap_or(-1);
+        if code != exit_code {
+            return Err(E2eError::AssertionFailed(format!(
+                "`{}` exited with {}, expected {}: {}",
+                command,
+                code,
+                exit_code,
+                stderr.trim()
+            )));
+        }
+        for (stream, text, needle) in [("stdout", &stdout, stdout_contains), ("stderr", &stderr, stderr_contains)] {
+            if let Some(needle) = needle.filter(|n| !text.contains(n)) {
+                return Err(E2eError::AssertionFailed(format!(
+                    "{} of `{}` does not contain '{}'",
+                    stream, command, needle
+                )));
+            }
+        }
+
+        Ok(parse_output(&stdout))
+    }
+}
+
+/// Name of an API step in results
+pub fn step_name(step: &TestStep) -> String {
+    match step {
+        TestStep::GrpcCall { method, .. } => format!("grpc_call:{}", method),
+        TestStep::HttpRequest { method, path, .. } => format!("http_request:{} {}", method, path),
+        TestStep::Shell { command, .. } => {
+            format!("shell:{}", command.chars().take(30).collect::<String>())
+        }
+        _ => "browser".to_string(),
+    }
+}
+
+/// JSON output, or the trimmed text when it isn't JSON
+fn parse_output(text: &str) -> Value {
+    let text = text.trim();
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+fn check_and_save(
+    value: &Value,
+    expect: &[FieldAssertion],
+    save: &HashMap<String, String>,
+    vars: &mut HashMap<String, Value>,
+) -> E2eResult<()> {
+    check(value, expect)?;
+
+    for (name, path) in save {
+        let field = lookup(value, path).ok_or_else(|| {
+            E2eError::AssertionFailed(format!("cannot save '{}': no field '{}'", name, path))
+        })?;
+        vars.insert(name.clone(), field.clone());
+    }
+
+    Ok(())
+}
+
+/// Value at a dotted path; array elements are addressed by index
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(value, |current, key| match current {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+/// Compare leniently, since proto JSON encodes 64-bit integers as strings
+fn values_equal(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::String(s), Value::Number(n)) | (Value::Number(n), Value::String(s)) => *s == n.to_string(),
+        _ => actual == expected,
+    }
+}
+
+/// Check field assertions against a response
+pub fn check(value: &Value, assertions: &[FieldAssertion]) -> E2eResult<()> {
+    for assertion in assertions {
+        let path = if assertion.path.is_empty() { "response" } else { assertion.path.as_str() };
+        let fail = |reason: String| Err(E2eError::AssertionFailed(format!("{}: {}", path, reason)));
+        let field = lookup(value, &assertion.path);
+
+        if let Some(exists) = assertion.exists {
+            if field.is_some() != exists {
+                return fail(if exists { "missing" } else { "present" }.to_string());
+            }
+        }
+        if assertion.equals.is_none() && assertion.contains.is_none() && assertion.length.is_none() {
+            continue;
+        }
+        let Some(field) = field else {
+            return fail("missing".to_string());
+        };
+
+        if let Some(expected) = &assertion.equals {
+            if !values_equal(field, expected) {
+                return fail(format!("expected {}, got {}", expected, field));
+            }
+        }
+
+        if let Some(needle) = &assertion.contains {
+            let found = match (field, needle) {
+                (Value::String(s), Value::String(n)) => s.contains(n.as_str()),
+                (Value::Array(items), needle) => items.iter().any(|item| values_equal(item, needle)),
+                _ => false,
+            };
+            if !found {
+                return fail(format!("{} does not contain {}", field, needle));
+            }
+        }
+
+        if let Some(expected) = assertion.length {
+            let length = match field {
+                Value::String(s) => Some(s.chars().count()),
+                Value::Array(items) => Some(items.len()),
+                Value::Object(map) => Some(map.len()),
+                _ => None,
+            };
+            if length != Some(expected) {
+                return fail(format!("expected length {}, got {}", expected, field));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace `${name}` in a step's strings with saved values
+///
+/// Unknown names are left alone, so JavaScript template literals in
+/// `evaluate` steps pass through unchanged.
+pub fn interpolate(step: &TestStep, vars: &HashMap<String, Value>) -> E2eResult<TestStep> {
+    if vars.is_empty() {
+        return Ok(step.clone());
+    }
+
+    let mut value = serde_json::to_value(step)?;
+    substitute(&mut value, vars);
+    Ok(serde_json::from_value(value)?)
+}
+
+fn substitute(value: &mut Value, vars: &HashMap<String, Value>) {
+    match value {
+        Value::String(s) if s.contains("${") => *s = expand(s, vars),
+        Value::Array(items) => items.iter_mut().for_each(|item| substitute(item, vars)),
+        Value::Object(map) => map.values_mut().for_each(|item| substitute(item, vars)),
+        _ => {}
+    }
+}
+
+fn expand(s: &str, vars: &HashMap<String, Value>) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else { break };
+
+        out.push_str(&rest[..start]);
+        match vars.get(&after[..end]) {
+            Some(Value::String(v)) => out.push_str(v),
+            Some(v) => out.push_str(&v.to_string()),
+            None => out.push_str(&rest[start..start + 2 + end + 1]),
+        }
+        rest = &after[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assertion(path: &str) -> FieldAssertion {
+        FieldAssertion { path: path.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let value = json!({"vms": [{"meta": {"id": "a"}}, {"meta": {"id": "b"}}]});
+        assert_eq!(lookup(&value, "vms.1.meta.id"), Some(&json!("b")));
+        assert_eq!(lookup(&value, ""), Some(&value));
+        assert_eq!(lookup(&value, "vms.2.meta.id"), None);
+        assert_eq!(lookup(&value, "vms.first"), None);
+    }
+
+    #[test]
+    fn test_check_assertions() {
+        let value = json!({
+            "vm": {"meta": {"name": "e2e-vm", "createdAt": "1700000000"}, "labels": ["ci", "web"]}
+        });
+
+        let ok = [
+            FieldAssertion { equals: Some(json!("e2e-vm")), ..assertion("vm.meta.name") },
+            FieldAssertion { equals: Some(json!(1700000000)), ..assertion("vm.meta.createdAt") },
+            FieldAssertion { contains: Some(json!("2e-v")), ..assertion("vm.meta.name") },
+            FieldAssertion { contains: Some(json!("web")), length: Some(2), ..assertion("vm.labels") },
+            FieldAssertion { exists: Some(false), ..assertion("vm.status") },
+        ];
+        check(&value, &ok).unwrap();
+
+        let wrong = FieldAssertion { equals: Some(json!("other")), ..assertion("vm.meta.name") };
+        assert!(check(&value, &[wrong]).is_err());
+        let missing = FieldAssertion { exists: Some(true), ..assertion("vm.status") };
+        assert!(check(&value, &[missing]).is_err());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let vars = HashMap::from([
+            ("vm_id".to_string(), json!("abc")),
+            ("count".to_string(), json!(3)),
+        ]);
+
+        let step = TestStep::Navigate {
+            url: "/vms/${vm_id}?n=${count}".to_string(),
+            wait_for_selector: None,
+        };
+        match interpolate(&step, &vars).unwrap() {
+            TestStep::Navigate { url, .. } => assert_eq!(url, "/vms/abc?n=3"),
+            other => panic!("unexpected step {:?}", other),
+        }
+
+        let script = TestStep::Evaluate { script: "return `${window.x}`".to_string(), expected: None };
+        match interpolate(&script, &vars).unwrap() {
+            TestStep::Evaluate { script, .. } => assert_eq!(script, "return `${window.x}`"),
+            other => panic!("unexpected step {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shell_step_saves_output() {
+        let client = ApiClient::new(ApiConfig::default(), "http://127.0.0.1:0", "http://127.0.0.1:0").unwrap();
+        let mut vars = HashMap::new();
+
+        let step = TestStep::Shell {
+            command: r#"echo '{"vm": {"id": "abc"}}'"#.to_string(),
+            exit_code: 0,
+            stdout_contains: Some("abc".to_string()),
+            stderr_contains: None,
+            timeout_ms: 5000,
+            expect: vec![FieldAssertion { equals: Some(json!("abc")), ..assertion("vm.id") }],
+            save: HashMap::from([("vm_id".to_string(), "vm.id".to_string())]),
+        };
+        let result = client.execute_step(&step, &mut vars).await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(vars["vm_id"], json!("abc"));
+
+        let failing = TestStep::Shell {
+            command: "exit 3".to_string(),
+            exit_code: 0,
+            stdout_contains: None,
+            stderr_contains: None,
+            timeout_ms: 5000,
+            expect: vec![],
+            save: HashMap::new(),
+        };
+        assert!(!client.execute_step(&failing, &mut vars).await.success);
+    }
+}
@@ -4,7 +4,11 @@
 //! - Spawns the web server as a subprocess
 //! - Controls Playwright via its CLI/JSON protocol
 //! - Parses declarative YAML test specs
+//! - Runs API steps (daemon RPCs, web API requests, shell commands) alongside
+//!   browser steps, so one spec can cover a full-stack scenario
 //! - Performs visual regression testing with baseline screenshots
+//! - Optionally runs `monitor: true` specs on a schedule against a live
+//!   deployment, publishing failures to notification sinks
 //!
 //! # Architecture
 //!
@@ -26,18 +30,25 @@
 //! │    │     ├── fill { selector, value }                       │
 //! │    │     ├── wait { selector | timeout_ms }                 │
 //! │    │     ├── assert { selector, visible?, text?, attr? }    │
-//! │    │     └── screenshot { name, selector? }                 │
+//! │    │     ├── screenshot { name, selector? }                 │
+//! │    │     ├── intercept { url, body? | abort }               │
+//! │    │     ├── grpc_call { method, request, expect?, save? }  │
+//! │    │     ├── http_request { path, body?, expect?, save? }   │
+//! │    │     └── shell { command, exit_code?, expect?, save? }  │
 //! │    └── visual_baseline: Option<String>                      │
 //! └─────────────────────────────────────────────────────────────┘
 //! ```
 
+pub mod api;
 pub mod runner;
 pub mod spec;
 pub mod visual;
 pub mod playwright;
 pub mod server;
 pub mod error;
+pub mod monitor;
 
+pub use monitor::{Monitor, MonitorConfig, NotificationSink};
 pub use runner::TestRunner;
 pub use spec::{TestSpec, TestStep};
 pub use error::{E2eError, E2eResult};
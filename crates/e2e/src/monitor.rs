@@ -0,0 +1,335 @@
+//! Continuous verification (synthetic monitoring) mode
+//!
+//! Runs the specs marked `monitor: true` on a schedule against a long-running
+//! deployment and publishes pass/fail transitions to notification sinks.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::error::E2eResult;
+use crate::runner::{TestRunner, TestSuiteResult};
+use crate::spec::TestSpec;
+
+/// Where monitor events are published
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotificationSink {
+    /// POST each event as JSON to a webhook (Slack-compatible `text` field included)
+    Webhook { url: String },
+    /// Emit events through tracing only
+    Log,
+}
+
+/// Monitor configuration
+#[derive(Debug, Clone)]
+pub struct MonitorConfig {
+    /// Time between runs
+    pub interval: Duration,
+    /// Further restrict monitored specs to this tag
+    pub tag: Option<String>,
+    /// Notification sinks for state transitions
+    pub sinks: Vec<NotificationSink>,
+    /// Also notify when a failing spec recovers
+    pub notify_on_recovery: bool,
+    /// Stop after this many runs (None = run forever)
+    pub max_runs: Option<usize>,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(300),
+            tag: None,
+            sinks: vec![NotificationSink::Log],
+            notify_on_recovery: true,
+            max_runs: None,
+        }
+    }
+}
+
+/// Kind of monitor event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorEventKind {
+    /// A spec started failing
+    Failing,
+    /// A previously failing spec passes again
+    Recovered,
+    /// The deployment could not be reached at all
+    Unreachable,
+}
+
+/// A pass/fail transition published to sinks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorEvent {
+    pub kind: MonitorEventKind,
+    pub spec: String,
+    pub target: String,
+    pub error: Option<String>,
+    pub timestamp: String,
+}
+
+impl MonitorEvent {
+    /// One-line human summary for chat-style sinks
+    pub fn summary(&self) -> String {
+        match self.kind {
+            MonitorEventKind::Failing => format!(
+                "🔴 [{}] {} failing: {}",
+                self.target,
+                self.spec,
+                self.error.as_deref().unwrap_or("unknown error")
+            ),
+            MonitorEventKind::Recovered => {
+                format!("🟢 [{}] {} recovered", self.target, self.spec)
+            }
+            MonitorEventKind::Unreachable => format!(
+                "🔴 [{}] deployment unreachable: {}",
+                self.target,
+                self.error.as_deref().unwrap_or("unknown error")
+            ),
+        }
+    }
+}
+
+/// Synthetic monitor wrapping a [`TestRunner`] configured with a target URL
+pub struct Monitor {
+    runner: TestRunner,
+    config: MonitorConfig,
+    /// Last known pass/fail state per spec (true = passing)
+    last_state: HashMap<String, bool>,
+    client: reqwest::Client,
+}
+
+impl Monitor {
+    /// Create a monitor
+    pub fn new(runner: TestRunner, config: MonitorConfig) -> Self {
+        Self {
+            runner,
+            config,
+            last_state: HashMap::new(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Load the specs designated for monitoring
+    fn load_specs(&self) -> E2eResult<Vec<TestSpec>> {
+        let specs = TestSpec::monitored(TestSpec::load_all(self.runner.specs_dir())?);
+        Ok(match &self.config.tag {
+            Some(tag) => specs.into_iter().filter(|s| s.tags.contains(tag)).collect(),
+            None => specs,
+        })
+    }
+
+    /// Run the monitored specs once and publish any transitions
+    pub async fn run_once(&mut self) -> E2eResult<Vec<MonitorEvent>> {
+        let specs = self.load_specs()?;
+        if specs.is_empty() {
+            warn!("No specs marked `monitor: true` found");
+            return Ok(Vec::new());
+        }
+
+        let events = match self.runner.run_specs(&specs).await {
+            Ok(results) => {
+                self.append_history(&results)?;
+                diff_results(
+                    &mut self.last_state,
+                    &results,
+                    self.runner.base_url(),
+                    self.config.notify_on_recovery,
+                )
+            }
+            Err(e) => {
+                error!("Monitor run failed: {}", e);
+                vec![MonitorEvent {
+                    kind: MonitorEventKind::Unreachable,
+                    spec: String::new(),
+                    target: self.runner.base_url().to_string(),
+                    error: Some(e.to_string()),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                }]
+            }
+        };
+
+        for event in &events {
+            self.publish(event).await;
+        }
+
+        Ok(events)
+    }
+
+    /// Run on the configured schedule until `max_runs` is reached
+    pub async fn run(&mut self) -> E2eResult<()> {
+        let mut runs = 0;
+        let mut ticker = tokio::time::interval(self.config.interval);
+
+        loop {
+            ticker.tick().await;
+            self.run_once().await?;
+            runs += 1;
+
+            if self.config.max_runs.is_some_and(|max| runs >= max) {
+                info!("Monitor finished after {} run(s)", runs);
+                return Ok(());
+            }
+        }
+    }
+
+    /// Publish an event to every configured sink
+    async fn publish(&self, event: &MonitorEvent) {
+        for sink in &self.config.sinks {
+            match sink {
+                NotificationSink::Log => match event.kind {
+                    MonitorEventKind::Recovered => info!("{}", event.summary()),
+                    _ => error!("{}", event.summary()),
+                },
+                NotificationSink::Webhook { url } => {
+                    let mut body = serde_json::to_value(event).unwrap_or_default();
+                    body["text"] = serde_json::Value::String(event.summary());
+
+                    match self.client.post(url).json(&body).send().await {
+                        Ok(resp) if !resp.status().is_success() => {
+                            warn!("Webhook {} returned {}", url, resp.status());
+                        }
+                        Err(e) => warn!("Webhook {} failed: {}", url, e),
+                        Ok(_) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Append a run summary to `monitor-history.jsonl` in the output directory
+    fn append_history(&self, results: &TestSuiteResult) -> E2eResult<PathBuf> {
+        std::fs::create_dir_all(self.runner.output_dir())?;
+        let path = self.runner.output_dir().join("monitor-history.jsonl");
+
+        let entry = serde_json::json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "target": self.runner.base_url(),
+            "passed": results.passed,
+            "failed": results.failed,
+            "duration_ms": results.duration_ms,
+            "failures": results
+                .results
+                .iter()
+                .filter(|r| !r.success)
+                .map(|r| serde_json::json!({ "name": r.name, "error": r.error }))
+                .collect::<Vec<_>>(),
+        });
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        writeln!(file, "{}", entry)?;
+
+        Ok(path)
+    }
+}
+
+/// Compare a run against the previous state, returning transitions
+///
+/// Specs seen for the first time only produce an event if they fail, so a
+/// restarted monitor re-alerts on existing failures but not on healthy specs.
+pub fn diff_results(
+    last_state: &mut HashMap<String, bool>,
+    results: &TestSuiteResult,
+    target: &str,
+    notify_on_recovery: bool,
+) -> Vec<MonitorEvent> {
+    let mut events = Vec::new();
+    let timestamp = chrono::Utc::now().to_rfc3339();
+
+    for result in &results.results {
+        let previous = last_state.insert(result.name.clone(), result.success);
+
+        let kind = match (previous, result.success) {
+            (None | Some(true), false) => MonitorEventKind::Failing,
+            (Some(false), true) if notify_on_recovery => MonitorEventKind::Recovered,
+            _ => continue,
+        };
+
+        events.push(MonitorEvent {
+            kind,
+            spec: result.name.clone(),
+            target: target.to_string(),
+            error: result.error.clone(),
+            timestamp: timestamp.clone(),
+        });
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::TestResult;
+
+    fn suite(results: &[(&str, bool)]) -> TestSuiteResult {
+        TestSuiteResult {
+            total: results.len(),
+            passed: results.iter().filter(|(_, ok)| *ok).count(),
+            failed: results.iter().filter(|(_, ok)| !*ok).count(),
+            skipped: 0,
+            duration_ms: 0,
+            results: results
+                .iter()
+                .map(|(name, ok)| TestResult {
+                    name: name.to_string(),
+                    success: *ok,
+                    duration_ms: 0,
+                    steps: vec![],
+                    visual_diffs: vec![],
+                    har_path: None,
+                    error: if *ok { None } else { Some("boom".to_string()) },
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_only_transitions_are_reported() {
+        let mut state = HashMap::new();
+
+        let events = diff_results(&mut state, &suite(&[("login", true), ("vms", false)]), "lab", true);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, MonitorEventKind::Failing);
+        assert_eq!(events[0].spec, "vms");
+
+        // Still failing: no repeat alert
+        let events = diff_results(&mut state, &suite(&[("login", true), ("vms", false)]), "lab", true);
+        assert!(events.is_empty());
+
+        let events = diff_results(&mut state, &suite(&[("login", false), ("vms", true)]), "lab", true);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|e| e.spec == "login" && e.kind == MonitorEventKind::Failing));
+        assert!(events.iter().any(|e| e.spec == "vms" && e.kind == MonitorEventKind::Recovered));
+    }
+
+    #[test]
+    fn test_recovery_notifications_can_be_disabled() {
+        let mut state = HashMap::new();
+        diff_results(&mut state, &suite(&[("vms", false)]), "lab", false);
+        let events = diff_results(&mut state, &suite(&[("vms", true)]), "lab", false);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_monitored_filter() {
+        let yaml = |name: &str, monitor: bool| {
+            format!("name: {}\nmonitor: {}\nsteps:\n  - action: navigate\n    url: /\n", name, monitor)
+        };
+        let specs = vec![
+            TestSpec::from_yaml(&yaml("health", true)).unwrap(),
+            TestSpec::from_yaml(&yaml("delete-vm", false)).unwrap(),
+        ];
+        let monitored = TestSpec::monitored(specs);
+        assert_eq!(monitored.len(), 1);
+        assert_eq!(monitored[0].name, "health");
+    }
+}
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Mutex;
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
@@ -10,6 +11,7 @@ use tokio::process::{Child, Command as TokioCommand};
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn, error};
 
+use crate::api;
 use crate::error::{E2eError, E2eResult};
 use crate::spec::{TestStep, WaitState, AttributeAssertion};
 
@@ -27,8 +29,17 @@ pub struct PlaywrightHandle {
     
     /// Browser type
     browser: Browser,
+    
+    /// Intercept steps run so far; every script registers their routes
+    routes: Mutex<Vec<TestStep>>,
+    
+    /// HAR file that each script's network traffic is appended to
+    har_path: Option<PathBuf>,
 }
 
+/// HAR file each script records to, relative to its working directory
+const SCRIPT_HAR: &str = "capture.har";
+
 #[derive(Debug, Clone, Copy, Default)]
 pub enum Browser {
     #[default]
@@ -72,6 +83,8 @@ impl PlaywrightHandle {
             viewport_width: config.viewport_width,
             viewport_height: config.viewport_height,
             browser: config.browser,
+            routes: Mutex::new(Vec::new()),
+            har_path: config.har_path,
         })
     }
 
@@ -122,8 +135,8 @@ impl PlaywrightHandle {
             TestStep::Assert { selector, visible, text, text_contains, attribute, count } => {
                 self.execute_assert(selector, *visible, text.as_deref(), text_contains.as_deref(), attribute.as_ref(), *count).await
             }
-            TestStep::Screenshot { name, selector, full_page } => {
-                self.execute_screenshot(name, selector.as_deref(), *full_page).await
+            TestStep::Screenshot { name, .. } => {
+                self.execute_screenshot(step, name).await
             }
             TestStep::Hover { selector } => {
                 self.execute_hover(selector).await
@@ -147,6 +160,18 @@ impl PlaywrightHandle {
                 info!("[TEST LOG] {}", message);
                 Ok(None)
             }
+            TestStep::Intercept { .. } => {
+                // Each script runs in a fresh page, so the route is added to
+                // every script built from now on
+                self.routes.lock().unwrap().push(step.clone());
+                Ok(None)
+            }
+            TestStep::GrpcCall { .. } | TestStep::HttpRequest { .. } | TestStep::Shell { .. } => {
+                Err(E2eError::StepFailed {
+                    step: step_name.clone(),
+                    reason: "not a browser step".to_string(),
+                })
+            }
         };
 
         let duration_ms = start.elapsed().as_millis() as u64;
@@ -188,6 +213,10 @@ impl PlaywrightHandle {
             TestStep::Uncheck { selector } => format!("uncheck:{}", selector),
             TestStep::Evaluate { .. } => "evaluate".to_string(),
             TestStep::Log { message } => format!("log:{}", &message[..message.len().min(30)]),
+            TestStep::Intercept { url, .. } => format!("intercept:{}", url),
+            TestStep::GrpcCall { .. } | TestStep::HttpRequest { .. } | TestStep::Shell { .. } => {
+                api::step_name(step)
+            }
         }
     }
 
@@ -195,6 +224,12 @@ impl PlaywrightHandle {
     pub fn build_script(&self, steps: &[TestStep]) -> String {
         let mut script = String::new();
         
+        let record_har = if self.har_path.is_some() {
+            format!(",\n    recordHar: {{ path: '{}' }}", SCRIPT_HAR)
+        } else {
+            String::new()
+        };
+
         // Header
         script.push_str(&format!(r#"
 const {{ chromium, firefox, webkit }} = require('playwright');
@@ -202,7 +237,7 @@ const {{ chromium, firefox, webkit }} = require('playwright');
 (async () => {{
   const browser = await {browser}.launch({{ headless: true }});
   const context = await browser.newContext({{
-    viewport: {{ width: {width}, height: {height} }}
+    viewport: {{ width: {width}, height: {height} }}{record_har}
   }});
   const page = await context.newPage();
   const baseUrl = '{base_url}';
@@ -215,6 +250,12 @@ const {{ chromium, firefox, webkit }} = require('playwright');
             base_url = self.base_url,
         ));
 
+        // Routes from earlier intercept steps
+        for route in self.routes.lock().unwrap().iter() {
+            script.push_str(&self.step_to_js(route, 0));
+            script.push('\n');
+        }
+
         // Generate step code
         for (i, step) in steps.iter().enumerate() {
             script.push_str(&format!("\n    // Step {}: {}\n", i + 1, self.step_name(step)));
@@ -228,6 +269,7 @@ const {{ chromium, firefox, webkit }} = require('playwright');
     console.error(JSON.stringify({ success: false, error: error.message, stack: error.stack }));
     process.exit(1);
   } finally {
+    await context.close();
     await browser.close();
   }
 })();
@@ -319,14 +361,18 @@ const {{ chromium, firefox, webkit }} = require('playwright');
                 
                 assertions.join("\n")
             }
-            TestStep::Screenshot { name, selector, full_page } => {
+            TestStep::Screenshot { name, selector, full_page, mask, .. } => {
                 let screenshot_path = self.screenshot_dir.join(format!("{}.png", name));
                 let path_str = screenshot_path.to_string_lossy();
+                let mask = mask.iter()
+                    .map(|s| format!("page.locator('{}')", s))
+                    .collect::<Vec<_>>()
+                    .join(", ");
                 
                 if let Some(sel) = selector {
-                    format!(r#"    await page.locator('{}').screenshot({{ path: '{}' }});"#, sel, path_str)
+                    format!(r#"    await page.locator('{}').screenshot({{ path: '{}', mask: [{}] }});"#, sel, path_str, mask)
                 } else {
-                    format!(r#"    await page.screenshot({{ path: '{}', fullPage: {} }});"#, path_str, full_page)
+                    format!(r#"    await page.screenshot({{ path: '{}', fullPage: {}, mask: [{}] }});"#, path_str, full_page, mask)
                 }
             }
             TestStep::Hover { selector } => {
@@ -350,6 +396,36 @@ const {{ chromium, firefox, webkit }} = require('playwright');
             TestStep::Log { message } => {
                 format!(r#"    console.log('[TEST] {}');"#, message.replace("'", "\\'"))
             }
+            TestStep::Intercept { url, method, abort, status, headers, body } => {
+                let skip = method.as_ref()
+                    .map(|m| format!(r#"
+      if (route.request().method() !== '{}') return route.fallback();"#, m.to_uppercase()))
+                    .unwrap_or_default();
+                let respond = if *abort {
+                    "route.abort()".to_string()
+                } else {
+                    // JSON literals are valid JavaScript
+                    let (content_type, body) = match body {
+                        Some(serde_json::Value::String(s)) => ("text/plain", s.clone()),
+                        Some(value) => ("application/json", value.to_string()),
+                        None => ("text/plain", String::new()),
+                    };
+                    format!(
+                        "route.fulfill({{ status: {}, headers: {}, contentType: '{}', body: {} }})",
+                        status,
+                        serde_json::to_string(headers).unwrap_or_default(),
+                        content_type,
+                        serde_json::Value::String(body),
+                    )
+                };
+                format!(r#"    await page.route('{}', route => {{{}
+      return {};
+    }});"#, url, skip, respond)
+            }
+            // Run by the test runner outside the browser
+            TestStep::GrpcCall { .. } | TestStep::HttpRequest { .. } | TestStep::Shell { .. } => {
+                String::new()
+            }
         }
     }
 
@@ -369,6 +445,11 @@ const {{ chromium, firefox, webkit }} = require('playwright');
             .output()
             .await?;
 
+        // Kept for failed scripts too; the context is closed either way
+        if let Some(har_path) = &self.har_path {
+            merge_har(&temp_dir.path().join(SCRIPT_HAR), har_path)?;
+        }
+
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -467,13 +548,8 @@ const {{ chromium, firefox, webkit }} = require('playwright');
         Ok(None)
     }
 
-    async fn execute_screenshot(&self, name: &str, selector: Option<&str>, full_page: bool) -> E2eResult<Option<PathBuf>> {
-        let step = TestStep::Screenshot { 
-            name: name.to_string(), 
-            selector: selector.map(String::from), 
-            full_page 
-        };
-        let script = self.build_script(&[step]);
+    async fn execute_screenshot(&self, step: &TestStep, name: &str) -> E2eResult<Option<PathBuf>> {
+        let script = self.build_script(std::slice::from_ref(step));
         self.run_script(&script).await?;
         
         let path = self.screenshot_dir.join(format!("{}.png", name));
@@ -529,6 +605,35 @@ const {{ chromium, firefox, webkit }} = require('playwright');
     }
 }
 
+/// Append the pages and entries of one HAR file to another, creating it if needed
+fn merge_har(from: &Path, into: &Path) -> E2eResult<()> {
+    if !from.exists() {
+        return Ok(());
+    }
+    let recorded: serde_json::Value = serde_json::from_slice(&std::fs::read(from)?)?;
+
+    let merged = if into.exists() {
+        let mut existing: serde_json::Value = serde_json::from_slice(&std::fs::read(into)?)?;
+        for key in ["pages", "entries"] {
+            let new = recorded["log"][key].as_array().cloned().unwrap_or_default();
+            if let Some(items) = existing["log"][key].as_array_mut() {
+                items.extend(new);
+            } else {
+                existing["log"][key] = serde_json::Value::Array(new);
+            }
+        }
+        existing
+    } else {
+        if let Some(parent) = into.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        recorded
+    };
+
+    std::fs::write(into, serde_json::to_vec_pretty(&merged)?)?;
+    Ok(())
+}
+
 /// Configuration for Playwright
 #[derive(Debug, Clone)]
 pub struct PlaywrightConfig {
@@ -538,6 +643,8 @@ pub struct PlaywrightConfig {
     pub viewport_height: u32,
     pub browser: Browser,
     pub headless: bool,
+    /// Record network traffic of every script to this HAR file
+    pub har_path: Option<PathBuf>,
 }
 
 impl Default for PlaywrightConfig {
@@ -549,6 +656,80 @@ impl Default for PlaywrightConfig {
             viewport_height: 720,
             browser: Browser::Chromium,
             headless: true,
+            har_path: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle(har_path: Option<PathBuf>) -> PlaywrightHandle {
+        let config = PlaywrightConfig::default();
+        PlaywrightHandle {
+            base_url: config.base_url,
+            screenshot_dir: config.screenshot_dir,
+            viewport_width: config.viewport_width,
+            viewport_height: config.viewport_height,
+            browser: config.browser,
+            routes: Mutex::new(Vec::new()),
+            har_path,
         }
     }
+
+    #[tokio::test]
+    async fn test_intercept_routes_in_later_scripts() {
+        let pw = handle(None);
+        let intercept = TestStep::Intercept {
+            url: "**/api/vms".to_string(),
+            method: Some("get".to_string()),
+            abort: false,
+            status: 200,
+            headers: HashMap::new(),
+            body: Some(serde_json::json!({ "vms": [] })),
+        };
+        let result = pw.execute_step(&intercept).await.unwrap();
+        assert!(result.success);
+
+        let script = pw.build_script(&[TestStep::Navigate { url: "/vms".to_string(), wait_for_selector: None }]);
+        assert!(script.contains("await page.route('**/api/vms'"));
+        assert!(script.contains("route.request().method() !== 'GET'"));
+        assert!(script.contains(r#"body: "{\"vms\":[]}""#));
+        assert!(script.find("page.route").unwrap() < script.find("page.goto").unwrap());
+        assert!(!script.contains("recordHar"));
+
+        let block = TestStep::Intercept {
+            url: "**/analytics/**".to_string(),
+            method: None,
+            abort: true,
+            status: 200,
+            headers: HashMap::new(),
+            body: None,
+        };
+        let js = pw.step_to_js(&block, 0);
+        assert!(js.contains("return route.abort();"));
+        assert!(!js.contains("fallback"));
+    }
+
+    #[test]
+    fn test_merge_har() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorded = dir.path().join("capture.har");
+        let merged = dir.path().join("har/spec.har");
+        assert!(handle(Some(merged.clone())).build_script(&[]).contains("recordHar: { path: 'capture.har' }"));
+
+        let har = serde_json::json!({
+            "log": { "version": "1.2", "pages": [{ "id": "page@1" }], "entries": [{ "request": { "url": "/a" } }] }
+        });
+        std::fs::write(&recorded, har.to_string()).unwrap();
+        merge_har(&recorded, &merged).unwrap();
+        merge_har(&recorded, &merged).unwrap();
+        merge_har(&dir.path().join("missing.har"), &merged).unwrap();
+
+        let result: serde_json::Value = serde_json::from_slice(&std::fs::read(&merged).unwrap()).unwrap();
+        assert_eq!(result["log"]["version"], "1.2");
+        assert_eq!(result["log"]["pages"].as_array().unwrap().len(), 2);
+        assert_eq!(result["log"]["entries"].as_array().unwrap().len(), 2);
+    }
 }
@@ -1,15 +1,17 @@
 //! Main test runner that orchestrates server, Playwright, and visual regression
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn, error, debug};
 
+use crate::api::{self, ApiClient, ApiConfig};
 use crate::error::{E2eError, E2eResult};
 use crate::playwright::{PlaywrightConfig, PlaywrightHandle, StepResult};
-use crate::server::{ServerConfig, ServerHandle};
-use crate::spec::TestSpec;
-use crate::visual::{VisualConfig, VisualDiff, VisualTester};
+use crate::server::{wait_for_healthy, ServerConfig, ServerHandle};
+use crate::spec::{TestSpec, TestStep};
+use crate::visual::{CompareOptions, Region, VisualConfig, VisualTester, UPDATE_BASELINES_ENV};
 
 /// Result of running a single test
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +21,8 @@ pub struct TestResult {
     pub duration_ms: u64,
     pub steps: Vec<StepResult>,
     pub visual_diffs: Vec<VisualDiffResult>,
+    /// Recorded network traffic, for specs with `capture_har`
+    pub har_path: Option<String>,
     pub error: Option<String>,
 }
 
@@ -27,6 +31,11 @@ pub struct VisualDiffResult {
     pub name: String,
     pub matches: bool,
     pub diff_percent: f64,
+    pub diff_pixels: u64,
+    pub antialiased_pixels: u64,
+    pub threshold: f64,
+    /// Baseline was rewritten from this run's screenshot
+    pub baseline_updated: bool,
     pub diff_image_path: Option<String>,
 }
 
@@ -52,9 +61,15 @@ pub struct TestRunner {
     /// Visual testing configuration
     visual_config: VisualConfig,
     
+    /// gRPC, HTTP and shell step configuration
+    api_config: ApiConfig,
+    
     /// Running server handle (if any)
     server: Option<ServerHandle>,
     
+    /// Existing deployment to test instead of spawning a server
+    target_url: Option<String>,
+    
     /// Test specs directory
     specs_dir: PathBuf,
     
@@ -74,14 +89,25 @@ impl TestRunner {
             server_config: config.server,
             playwright_config: config.playwright,
             visual_config: config.visual,
+            api_config: config.api,
             server: None,
+            target_url: config.target_url,
             specs_dir: config.specs_dir,
             output_dir: config.output_dir,
         }
     }
 
     /// Start the server
+    ///
+    /// When a target URL is configured, no process is spawned; the existing
+    /// deployment is health-checked instead.
     pub async fn start_server(&mut self) -> E2eResult<()> {
+        if let Some(url) = &self.target_url {
+            wait_for_healthy(url, self.server_config.startup_timeout).await?;
+            self.playwright_config.base_url = url.trim_end_matches('/').to_string();
+            return Ok(());
+        }
+
         if self.server.is_some() {
             return Ok(()); // Already running
         }
@@ -103,6 +129,21 @@ impl TestRunner {
         Ok(())
     }
 
+    /// Test specs directory
+    pub fn specs_dir(&self) -> &Path {
+        &self.specs_dir
+    }
+
+    /// Output directory for results
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// Base URL tests are run against
+    pub fn base_url(&self) -> &str {
+        &self.playwright_config.base_url
+    }
+
     /// Run all tests in the specs directory
     pub async fn run_all(&mut self) -> E2eResult<TestSuiteResult> {
         let specs = TestSpec::load_all(&self.specs_dir)?;
@@ -164,6 +205,7 @@ impl TestRunner {
                         duration_ms: 0,
                         steps: vec![],
                         visual_diffs: vec![],
+                        har_path: None,
                         error: Some(e.to_string()),
                     });
                 }
@@ -195,16 +237,40 @@ impl TestRunner {
         let mut pw_config = self.playwright_config.clone();
         pw_config.viewport_width = spec.viewport.width;
         pw_config.viewport_height = spec.viewport.height;
+        if spec.capture_har {
+            let har_path = self.output_dir.join("har").join(format!("{}.har", spec.name));
+            if har_path.exists() {
+                std::fs::remove_file(&har_path)?;
+            }
+            pw_config.har_path = Some(har_path);
+        }
 
-        let playwright = PlaywrightHandle::new(pw_config)?;
+        // Playwright is only required once a spec reaches a browser step
+        let mut playwright: Option<PlaywrightHandle> = None;
+        let api = ApiClient::new(
+            self.api_config.clone(),
+            &self.playwright_config.base_url,
+            &self.server_config.daemon_addr,
+        )?;
+        let mut vars: HashMap<String, serde_json::Value> = HashMap::new();
         
         let mut step_results = Vec::new();
         let mut test_error: Option<String> = None;
-        let mut screenshots: Vec<String> = Vec::new();
+        // Screenshot name, threshold override and masked regions
+        let mut screenshots: Vec<(String, Option<f64>, Vec<Region>)> = Vec::new();
 
         // Execute each step
         for step in &spec.steps {
-            let result = playwright.execute_step(step).await?;
+            let step = api::interpolate(step, &vars)?;
+            let result = if step.is_browser() {
+                let handle = match playwright {
+                    Some(ref handle) => handle,
+                    None => playwright.insert(PlaywrightHandle::new(pw_config.clone())?),
+                };
+                handle.execute_step(&step).await?
+            } else {
+                api.execute_step(&step, &mut vars).await
+            };
             
             if !result.success {
                 test_error = result.error.clone();
@@ -213,9 +279,9 @@ impl TestRunner {
             }
             
             // Track screenshots for visual regression
-            if let Some(path) = &result.screenshot_path {
+            if let (Some(path), TestStep::Screenshot { threshold, mask_regions, .. }) = (&result.screenshot_path, &step) {
                 if let Some(name) = path.file_stem() {
-                    screenshots.push(name.to_string_lossy().to_string());
+                    screenshots.push((name.to_string_lossy().to_string(), *threshold, mask_regions.clone()));
                 }
             }
             
@@ -227,8 +293,16 @@ impl TestRunner {
         if spec.visual_regression && test_error.is_none() {
             let visual_tester = VisualTester::new(self.visual_config.clone())?;
             
-            for screenshot_name in &screenshots {
-                match visual_tester.compare(screenshot_name, Some(spec.visual_threshold)) {
+            let defaults = visual_tester.default_options();
+            
+            for (screenshot_name, threshold, masks) in &screenshots {
+                let options = CompareOptions {
+                    threshold: threshold.unwrap_or(spec.visual_threshold),
+                    tolerance: spec.visual_tolerance.unwrap_or(defaults.tolerance),
+                    ignore_antialiasing: spec.visual_ignore_antialiasing.unwrap_or(defaults.ignore_antialiasing),
+                    masks: masks.clone(),
+                };
+                match visual_tester.compare(screenshot_name, &options) {
                     Ok(diff) => {
                         if !diff.matches {
                             test_error = Some(format!(
@@ -240,12 +314,19 @@ impl TestRunner {
                             name: screenshot_name.clone(),
                             matches: diff.matches,
                             diff_percent: diff.diff_percent,
+                            diff_pixels: diff.diff_pixels,
+                            antialiased_pixels: diff.antialiased_pixels,
+                            threshold: options.threshold,
+                            baseline_updated: diff.baseline_updated,
                             diff_image_path: diff.diff_image_path.map(|p| p.to_string_lossy().to_string()),
                         });
                     }
                     Err(E2eError::BaselineNotFound(_)) => {
                         // First run - no baseline yet
-                        info!("No baseline for '{}' - will be created on next run with --update-baselines", screenshot_name);
+                        info!(
+                            "No baseline for '{}' - create it with --update-baselines or {}=1",
+                            screenshot_name, UPDATE_BASELINES_ENV
+                        );
                     }
                     Err(e) => {
                         test_error = Some(format!("Visual comparison error: {}", e));
@@ -263,6 +344,9 @@ impl TestRunner {
             duration_ms,
             steps: step_results,
             visual_diffs,
+            har_path: pw_config.har_path
+                .filter(|p| p.exists())
+                .map(|p| p.to_string_lossy().to_string()),
             error: test_error,
         })
     }
@@ -316,8 +400,11 @@ pub struct RunnerConfig {
     pub server: ServerConfig,
     pub playwright: PlaywrightConfig,
     pub visual: VisualConfig,
+    pub api: ApiConfig,
     pub specs_dir: PathBuf,
     pub output_dir: PathBuf,
+    /// Run against an existing deployment instead of spawning a server
+    pub target_url: Option<String>,
 }
 
 impl Default for RunnerConfig {
@@ -326,8 +413,10 @@ impl Default for RunnerConfig {
             server: ServerConfig::default(),
             playwright: PlaywrightConfig::default(),
             visual: VisualConfig::default(),
+            api: ApiConfig::default(),
             specs_dir: PathBuf::from("tests/e2e/specs"),
             output_dir: PathBuf::from("test-results"),
+            target_url: None,
         }
     }
 }
@@ -3,7 +3,7 @@
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::time::Duration;
-use tokio::time::{sleep, timeout};
+use tokio::time::sleep;
 use tracing::{info, warn};
 
 use crate::error::{E2eError, E2eResult};
@@ -67,39 +67,7 @@ impl ServerHandle {
 
     /// Wait for the server to respond to health checks
     async fn wait_for_healthy(&self, timeout_duration: Duration) -> E2eResult<()> {
-        let health_url = format!("{}/health", self.base_url);
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(2))
-            .build()?;
-
-        let start = std::time::Instant::now();
-        let mut attempts = 0;
-
-        while start.elapsed() < timeout_duration {
-            attempts += 1;
-            
-            match client.get(&health_url).send().await {
-                Ok(resp) if resp.status().is_success() => {
-                    return Ok(());
-                }
-                Ok(resp) => {
-                    warn!("Health check returned {}", resp.status());
-                }
-                Err(e) => {
-                    if attempts == 1 {
-                        info!("Waiting for server to start...");
-                    }
-                    // Connection refused is expected while server is starting
-                    if !e.is_connect() {
-                        warn!("Health check error: {}", e);
-                    }
-                }
-            }
-
-            sleep(Duration::from_millis(100)).await;
-        }
-
-        Err(E2eError::ServerHealthCheck(attempts))
+        wait_for_healthy(&self.base_url, timeout_duration).await
     }
 
     /// Get the base URL for this server
@@ -138,6 +106,46 @@ impl Drop for ServerHandle {
     }
 }
 
+/// Wait for a server at `base_url` to respond to health checks
+///
+/// Used both for spawned servers and for existing deployments targeted in
+/// monitor mode.
+pub async fn wait_for_healthy(base_url: &str, timeout_duration: Duration) -> E2eResult<()> {
+    let health_url = format!("{}/health", base_url.trim_end_matches('/'));
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()?;
+
+    let start = std::time::Instant::now();
+    let mut attempts = 0;
+
+    while start.elapsed() < timeout_duration {
+        attempts += 1;
+
+        match client.get(&health_url).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                return Ok(());
+            }
+            Ok(resp) => {
+                warn!("Health check returned {}", resp.status());
+            }
+            Err(e) => {
+                if attempts == 1 {
+                    info!("Waiting for server to start...");
+                }
+                // Connection refused is expected while server is starting
+                if !e.is_connect() {
+                    warn!("Health check error: {}", e);
+                }
+            }
+        }
+
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    Err(E2eError::ServerHealthCheck(attempts))
+}
+
 /// Configuration for spawning a server
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
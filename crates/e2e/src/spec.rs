@@ -1,9 +1,11 @@
 //! Declarative YAML test specification
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
 use crate::error::{E2eError, E2eResult};
+use crate::visual::Region;
 
 /// A complete test specification parsed from YAML
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +35,22 @@ pub struct TestSpec {
     /// Threshold for visual diff (0.0 - 100.0 percent)
     #[serde(default = "default_threshold")]
     pub visual_threshold: f64,
+
+    /// Per-pixel color tolerance (0.0 - 1.0); the runner's default if unset
+    #[serde(default)]
+    pub visual_tolerance: Option<f64>,
+
+    /// Whether anti-aliased pixels are ignored; the runner's default if unset
+    #[serde(default)]
+    pub visual_ignore_antialiasing: Option<bool>,
+
+    /// Non-destructive; safe to run repeatedly against a live deployment
+    #[serde(default)]
+    pub monitor: bool,
+
+    /// Record the browser's network traffic to `<output>/har/<name>.har`
+    #[serde(default)]
+    pub capture_har: bool,
 }
 
 fn default_viewport() -> Viewport {
@@ -50,6 +68,10 @@ pub struct Viewport {
 }
 
 /// A single step in a test
+///
+/// Browser steps drive Playwright; `grpc_call`, `http_request` and `shell`
+/// run outside the browser. Values captured with `save` can be used in any
+/// later step as `${name}`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action", rename_all = "snake_case")]
 pub enum TestStep {
@@ -125,6 +147,15 @@ pub enum TestStep {
         selector: Option<String>,
         #[serde(default)]
         full_page: bool,
+        /// Elements painted over before capture, such as clocks
+        #[serde(default)]
+        mask: Vec<String>,
+        /// Regions left out of the visual comparison
+        #[serde(default)]
+        mask_regions: Vec<Region>,
+        /// Overrides the spec's `visual_threshold`
+        #[serde(default)]
+        threshold: Option<f64>,
     },
 
     /// Hover over an element
@@ -164,6 +195,92 @@ pub enum TestStep {
     Log {
         message: String,
     },
+
+    /// Mock or block browser requests matching a URL glob for the rest of the spec
+    Intercept {
+        url: String,
+        /// Only requests with this method; any when unset
+        #[serde(default)]
+        method: Option<String>,
+        /// Fail matching requests instead of answering them
+        #[serde(default)]
+        abort: bool,
+        #[serde(default = "default_intercept_status")]
+        status: u16,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// Response body; strings are sent as-is, anything else as JSON
+        #[serde(default)]
+        body: Option<serde_json::Value>,
+    },
+
+    /// Invoke a daemon RPC by name (e.g. `CreateVM`) with a JSON request
+    GrpcCall {
+        method: String,
+        #[serde(default)]
+        request: serde_json::Value,
+        #[serde(default)]
+        expect: Vec<FieldAssertion>,
+        #[serde(default)]
+        save: HashMap<String, String>,
+    },
+
+    /// Send a request to the web server, relative to the base URL
+    HttpRequest {
+        #[serde(default = "default_http_method")]
+        method: String,
+        path: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<serde_json::Value>,
+        /// Expected status code; any 2xx when unset
+        #[serde(default)]
+        status: Option<u16>,
+        #[serde(default)]
+        expect: Vec<FieldAssertion>,
+        #[serde(default)]
+        save: HashMap<String, String>,
+    },
+
+    /// Run a command with `sh -c`; `expect` and `save` read stdout as JSON
+    Shell {
+        command: String,
+        #[serde(default)]
+        exit_code: i32,
+        #[serde(default)]
+        stdout_contains: Option<String>,
+        #[serde(default)]
+        stderr_contains: Option<String>,
+        #[serde(default = "default_shell_timeout")]
+        timeout_ms: u64,
+        #[serde(default)]
+        expect: Vec<FieldAssertion>,
+        #[serde(default)]
+        save: HashMap<String, String>,
+    },
+}
+
+impl TestStep {
+    /// Whether the step runs in the browser
+    pub fn is_browser(&self) -> bool {
+        !matches!(
+            self,
+            TestStep::GrpcCall { .. } | TestStep::HttpRequest { .. } | TestStep::Shell { .. }
+        )
+    }
+}
+
+fn default_intercept_status() -> u16 {
+    200
+}
+
+fn default_http_method() -> String {
+    "GET".to_string()
+}
+
+fn default_shell_timeout() -> u64 {
+    60_000 // 1 minute default
 }
 
 fn default_wait_timeout() -> u64 {
@@ -180,6 +297,24 @@ pub enum WaitState {
     Detached,
 }
 
+/// Assertion on a field of an API response or JSON command output
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FieldAssertion {
+    /// Dotted path such as `vm.meta.name` or `vms.0.meta.id`; empty for the whole value
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub equals: Option<serde_json::Value>,
+    /// Substring of a string field, or element of an array field
+    #[serde(default)]
+    pub contains: Option<serde_json::Value>,
+    #[serde(default)]
+    pub exists: Option<bool>,
+    /// Number of elements, keys or characters
+    #[serde(default)]
+    pub length: Option<usize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttributeAssertion {
     pub name: String,
@@ -221,6 +356,11 @@ impl TestSpec {
         Ok(specs)
     }
 
+    /// Specs designated for continuous verification (monitor mode)
+    pub fn monitored(specs: Vec<Self>) -> Vec<Self> {
+        specs.into_iter().filter(|s| s.monitor).collect()
+    }
+
     /// Filter specs by tag
     pub fn filter_by_tag<'a>(specs: &'a [Self], tag: &str) -> Vec<&'a Self> {
         specs.iter().filter(|s| s.tags.contains(&tag.to_string())).collect()
@@ -261,6 +401,7 @@ name: dashboard-visual
 description: Visual regression test for dashboard
 visual_regression: true
 visual_threshold: 1.0
+visual_tolerance: 0.2
 viewport:
   width: 1920
   height: 1080
@@ -272,10 +413,114 @@ steps:
   - action: screenshot
     name: dashboard-full
     full_page: true
+    mask:
+      - '[data-testid="clock"]'
+    mask_regions:
+      - { x: 0, y: 0, width: 200, height: 40 }
+    threshold: 2.0
 "#;
         let spec = TestSpec::from_yaml(yaml).unwrap();
         assert!(spec.visual_regression);
         assert_eq!(spec.visual_threshold, 1.0);
+        assert_eq!(spec.visual_tolerance, Some(0.2));
+        assert_eq!(spec.visual_ignore_antialiasing, None);
         assert_eq!(spec.viewport.width, 1920);
+        match &spec.steps[2] {
+            TestStep::Screenshot { mask, mask_regions, threshold, .. } => {
+                assert_eq!(mask, &vec!["[data-testid=\"clock\"]".to_string()]);
+                assert_eq!(mask_regions[0], Region { x: 0, y: 0, width: 200, height: 40 });
+                assert_eq!(*threshold, Some(2.0));
+            }
+            other => panic!("unexpected step {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_api_steps() {
+        let yaml = r#"
+name: vm-api-to-ui
+steps:
+  - action: grpc_call
+    method: CreateVM
+    request:
+      name: e2e-vm
+      spec: { arch: aarch64, cpuCores: 1, memoryMb: 512 }
+    expect:
+      - path: vm.meta.name
+        equals: e2e-vm
+    save:
+      vm_id: vm.meta.id
+  - action: http_request
+    path: /api/vms/${vm_id}
+    expect:
+      - path: name
+        equals: e2e-vm
+  - action: shell
+    command: infrasim vm get ${vm_id} --format json
+    stdout_contains: e2e-vm
+  - action: navigate
+    url: /vms/${vm_id}
+"#;
+        let spec = TestSpec::from_yaml(yaml).unwrap();
+        assert_eq!(spec.steps.len(), 4);
+
+        match &spec.steps[0] {
+            TestStep::GrpcCall { method, expect, save, .. } => {
+                assert_eq!(method, "CreateVM");
+                assert_eq!(expect[0].equals, Some(serde_json::json!("e2e-vm")));
+                assert_eq!(save["vm_id"], "vm.meta.id");
+            }
+            other => panic!("expected grpc_call, got {:?}", other),
+        }
+        match &spec.steps[1] {
+            TestStep::HttpRequest { method, status, .. } => {
+                assert_eq!(method, "GET");
+                assert_eq!(*status, None);
+            }
+            other => panic!("expected http_request, got {:?}", other),
+        }
+        match &spec.steps[2] {
+            TestStep::Shell { exit_code, timeout_ms, .. } => {
+                assert_eq!(*exit_code, 0);
+                assert_eq!(*timeout_ms, 60_000);
+            }
+            other => panic!("expected shell, got {:?}", other),
+        }
+
+        assert!(!spec.steps[0].is_browser());
+        assert!(spec.steps[3].is_browser());
+    }
+
+    #[test]
+    fn test_parse_intercept_spec() {
+        let yaml = r#"
+name: vms-mocked
+capture_har: true
+steps:
+  - action: intercept
+    url: '**/api/vms'
+    method: GET
+    body: { vms: [] }
+  - action: intercept
+    url: '**/analytics/**'
+    abort: true
+  - action: navigate
+    url: /vms
+"#;
+        let spec = TestSpec::from_yaml(yaml).unwrap();
+        assert!(spec.capture_har);
+
+        match &spec.steps[0] {
+            TestStep::Intercept { url, method, status, abort, body, .. } => {
+                assert_eq!(url, "**/api/vms");
+                assert_eq!(method.as_deref(), Some("GET"));
+                assert_eq!(*status, 200);
+                assert!(!abort);
+                assert_eq!(body, &Some(serde_json::json!({ "vms": [] })));
+            }
+            other => panic!("expected intercept, got {:?}", other),
+        }
+        assert!(matches!(spec.steps[1], TestStep::Intercept { abort: true, .. }));
+        assert!(spec.steps[1].is_browser());
     }
 }
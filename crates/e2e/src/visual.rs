@@ -1,12 +1,59 @@
 //! Visual regression testing with screenshot comparison
+//!
+//! Pixels are compared by perceptual color distance in YIQ space, so
+//! compression noise below the tolerance is ignored. Anti-aliased edge pixels
+//! and masked regions don't count towards the diff.
 
 use std::path::{Path, PathBuf};
-use image::{GenericImageView, Pixel, RgbaImage};
+use image::{GenericImageView, Pixel, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use tracing::{debug, info, warn};
 
 use crate::error::{E2eError, E2eResult};
 
+/// Environment variable that turns on baseline update mode
+pub const UPDATE_BASELINES_ENV: &str = "E2E_UPDATE_BASELINES";
+
+/// Largest possible YIQ color delta between two pixels
+const MAX_YIQ_DELTA: f64 = 35215.0;
+
+/// Whether `E2E_UPDATE_BASELINES=1` is set
+pub fn update_baselines_requested() -> bool {
+    std::env::var(UPDATE_BASELINES_ENV).is_ok_and(|v| v == "1" || v == "true")
+}
+
+/// A rectangle of a screenshot, in pixels from the top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && y >= self.y && x - self.x < self.width && y - self.y < self.height
+    }
+}
+
+/// How a single screenshot is compared against its baseline
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// Percentage of pixels allowed to differ (0.0 - 100.0)
+    pub threshold: f64,
+
+    /// Color distance below which two pixels are equal (0.0 - 1.0)
+    pub tolerance: f64,
+
+    /// Don't count pixels on anti-aliased edges as different
+    pub ignore_antialiasing: bool,
+
+    /// Regions left out of the comparison, such as clocks and timestamps
+    pub masks: Vec<Region>,
+}
+
 /// Result of a visual comparison
 #[derive(Debug, Clone)]
 pub struct VisualDiff {
@@ -19,9 +66,15 @@ pub struct VisualDiff {
     /// Number of different pixels
     pub diff_pixels: u64,
     
-    /// Total pixels compared
+    /// Differing pixels ignored as anti-aliasing
+    pub antialiased_pixels: u64,
+    
+    /// Total pixels compared, excluding masked ones
     pub total_pixels: u64,
     
+    /// Whether the baseline was written from the actual screenshot
+    pub baseline_updated: bool,
+    
     /// Path to the diff image (if generated)
     pub diff_image_path: Option<PathBuf>,
     
@@ -46,8 +99,17 @@ pub struct VisualTester {
     /// Default threshold (0.0 - 100.0 percent)
     threshold: f64,
     
+    /// Default per-pixel color tolerance (0.0 - 1.0)
+    tolerance: f64,
+    
+    /// Whether anti-aliased pixels are ignored by default
+    ignore_antialiasing: bool,
+    
     /// Whether to auto-update baselines when missing
     auto_update: bool,
+    
+    /// Whether to overwrite baselines that don't match
+    update: bool,
 }
 
 impl VisualTester {
@@ -62,14 +124,28 @@ impl VisualTester {
             actual_dir: config.actual_dir,
             diff_dir: config.diff_dir,
             threshold: config.threshold,
+            tolerance: config.tolerance,
+            ignore_antialiasing: config.ignore_antialiasing,
             auto_update: config.auto_update,
+            update: config.update,
         })
     }
 
+    /// Comparison options from the configured defaults
+    pub fn default_options(&self) -> CompareOptions {
+        CompareOptions {
+            threshold: self.threshold,
+            tolerance: self.tolerance,
+            ignore_antialiasing: self.ignore_antialiasing,
+            masks: Vec::new(),
+        }
+    }
+
     /// Compare a screenshot against its baseline
-    pub fn compare(&self, name: &str, threshold: Option<f64>) -> E2eResult<VisualDiff> {
-        let threshold = threshold.unwrap_or(self.threshold);
-        
+    ///
+    /// In update mode a baseline that doesn't match is replaced by the actual
+    /// screenshot and the comparison passes; the diff image is still written.
+    pub fn compare(&self, name: &str, options: &CompareOptions) -> E2eResult<VisualDiff> {
         let actual_path = self.actual_dir.join(format!("{}.png", name));
         let baseline_path = self.baseline_dir.join(format!("{}.png", name));
         
@@ -82,7 +158,7 @@ impl VisualTester {
 
         // Check baseline exists
         if !baseline_path.exists() {
-            if self.auto_update {
+            if self.auto_update || self.update {
                 info!("Creating baseline for '{}' (auto-update enabled)", name);
                 std::fs::copy(&actual_path, &baseline_path)?;
                 
@@ -91,7 +167,9 @@ impl VisualTester {
                     matches: true,
                     diff_percent: 0.0,
                     diff_pixels: 0,
+                    antialiased_pixels: 0,
                     total_pixels: 0,
+                    baseline_updated: true,
                     diff_image_path: None,
                     actual_hash: actual_hash.clone(),
                     baseline_hash: actual_hash,
@@ -116,7 +194,9 @@ impl VisualTester {
                 matches: true,
                 diff_percent: 0.0,
                 diff_pixels: 0,
+                antialiased_pixels: 0,
                 total_pixels: (actual_img.width() * actual_img.height()) as u64,
+                baseline_updated: false,
                 diff_image_path: None,
                 actual_hash,
                 baseline_hash,
@@ -130,65 +210,51 @@ impl VisualTester {
                 actual_img.dimensions(),
                 baseline_img.dimensions()
             );
-            
-            // Still try to compare overlapping region
         }
 
-        // Pixel-by-pixel comparison
-        let (width, height) = actual_img.dimensions();
-        let baseline_rgba = baseline_img.to_rgba8();
         let actual_rgba = actual_img.to_rgba8();
-        
-        let mut diff_img = RgbaImage::new(width, height);
-        let mut diff_pixels = 0u64;
-        let total_pixels = (width as u64) * (height as u64);
-
-        for y in 0..height.min(baseline_img.height()) {
-            for x in 0..width.min(baseline_img.width()) {
-                let actual_pixel = actual_rgba.get_pixel(x, y);
-                let baseline_pixel = baseline_rgba.get_pixel(x, y);
-                
-                if self.pixels_differ(actual_pixel, baseline_pixel) {
-                    diff_pixels += 1;
-                    // Mark diff pixels in red
-                    diff_img.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
-                } else {
-                    // Keep original but dim it
-                    let channels = actual_pixel.channels();
-                    diff_img.put_pixel(x, y, image::Rgba([
-                        channels[0] / 2,
-                        channels[1] / 2,
-                        channels[2] / 2,
-                        128,
-                    ]));
-                }
-            }
-        }
+        let baseline_rgba = baseline_img.to_rgba8();
+        let comparison = compare_images(&actual_rgba, &baseline_rgba, options);
 
-        let diff_percent = (diff_pixels as f64 / total_pixels as f64) * 100.0;
-        let matches = diff_percent <= threshold;
+        let diff_percent = if comparison.total_pixels == 0 {
+            0.0
+        } else {
+            (comparison.diff_pixels as f64 / comparison.total_pixels as f64) * 100.0
+        };
+        let mut matches = diff_percent <= options.threshold;
 
         // Save diff image if there are differences
-        let diff_image_path = if diff_pixels > 0 {
+        let diff_image_path = if comparison.diff_pixels > 0 || comparison.antialiased_pixels > 0 {
             let path = self.diff_dir.join(format!("{}-diff.png", name));
-            diff_img.save(&path)?;
+            comparison.image.save(&path)?;
             Some(path)
         } else {
             None
         };
 
-        if !matches {
+        let mut baseline_updated = false;
+        if !matches && self.update {
+            info!(
+                "Updating baseline for '{}': {:.2}% pixels differ (update mode)",
+                name, diff_percent
+            );
+            std::fs::copy(&actual_path, &baseline_path)?;
+            baseline_updated = true;
+            matches = true;
+        } else if !matches {
             warn!(
                 "Visual regression detected in '{}': {:.2}% pixels differ (threshold: {:.2}%)",
-                name, diff_percent, threshold
+                name, diff_percent, options.threshold
             );
         }
 
         Ok(VisualDiff {
             matches,
             diff_percent,
-            diff_pixels,
-            total_pixels,
+            diff_pixels: comparison.diff_pixels,
+            antialiased_pixels: comparison.antialiased_pixels,
+            total_pixels: comparison.total_pixels,
+            baseline_updated,
             diff_image_path,
             actual_hash,
             baseline_hash,
@@ -213,24 +279,6 @@ impl VisualTester {
         Ok(())
     }
 
-    /// Check if two pixels differ significantly
-    fn pixels_differ(&self, a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> bool {
-        let a_channels = a.channels();
-        let b_channels = b.channels();
-        
-        // Allow small color differences (anti-aliasing, compression)
-        const TOLERANCE: i32 = 5;
-        
-        for i in 0..4 {
-            let diff = (a_channels[i] as i32 - b_channels[i] as i32).abs();
-            if diff > TOLERANCE {
-                return true;
-            }
-        }
-        
-        false
-    }
-
     /// Hash a file using SHA256
     fn hash_file(&self, path: &Path) -> E2eResult<String> {
         let data = std::fs::read(path)?;
@@ -267,6 +315,162 @@ impl VisualTester {
     }
 }
 
+/// Pixel counts and diff image of two compared screenshots
+struct ImageComparison {
+    image: RgbaImage,
+    diff_pixels: u64,
+    antialiased_pixels: u64,
+    total_pixels: u64,
+}
+
+/// Compare two images pixel by pixel
+///
+/// Pixels outside the overlap of differently sized images count as different.
+/// In the diff image differences are red, anti-aliasing yellow and masked
+/// regions blue.
+fn compare_images(actual: &RgbaImage, baseline: &RgbaImage, options: &CompareOptions) -> ImageComparison {
+    let width = actual.width().max(baseline.width());
+    let height = actual.height().max(baseline.height());
+    let max_delta = MAX_YIQ_DELTA * options.tolerance * options.tolerance;
+
+    let mut comparison = ImageComparison {
+        image: RgbaImage::new(width, height),
+        diff_pixels: 0,
+        antialiased_pixels: 0,
+        total_pixels: 0,
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            if options.masks.iter().any(|m| m.contains(x, y)) {
+                comparison.image.put_pixel(x, y, Rgba([0, 0, 255, 64]));
+                continue;
+            }
+            comparison.total_pixels += 1;
+
+            let inside = |img: &RgbaImage| x < img.width() && y < img.height();
+            if !inside(actual) || !inside(baseline) {
+                comparison.diff_pixels += 1;
+                comparison.image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+                continue;
+            }
+
+            let actual_pixel = actual.get_pixel(x, y);
+            if color_delta(actual_pixel, baseline.get_pixel(x, y)) <= max_delta {
+                // Keep original but dim it
+                let channels = actual_pixel.channels();
+                comparison.image.put_pixel(x, y, Rgba([
+                    channels[0] / 2,
+                    channels[1] / 2,
+                    channels[2] / 2,
+                    128,
+                ]));
+            } else if options.ignore_antialiasing
+                && (antialiased(actual, x, y, baseline) || antialiased(baseline, x, y, actual))
+            {
+                comparison.antialiased_pixels += 1;
+                comparison.image.put_pixel(x, y, Rgba([255, 255, 0, 255]));
+            } else {
+                comparison.diff_pixels += 1;
+                comparison.image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+    }
+
+    comparison
+}
+
+/// RGB of a pixel blended onto white by its alpha
+fn blend(pixel: &Rgba<u8>) -> (f64, f64, f64) {
+    let alpha = pixel[3] as f64 / 255.0;
+    let channel = |c: u8| 255.0 + (c as f64 - 255.0) * alpha;
+    (channel(pixel[0]), channel(pixel[1]), channel(pixel[2]))
+}
+
+/// Perceived brightness (the Y of YIQ)
+fn brightness(pixel: &Rgba<u8>) -> f64 {
+    let (r, g, b) = blend(pixel);
+    r * 0.29889531 + g * 0.58662247 + b * 0.11448223
+}
+
+/// Squared perceptual distance between two colors in YIQ space
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    let (r1, g1, b1) = blend(a);
+    let (r2, g2, b2) = blend(b);
+
+    let y = brightness(a) - brightness(b);
+    let i = (r1 * 0.59597799 - g1 * 0.2741761 - b1 * 0.32180189)
+        - (r2 * 0.59597799 - g2 * 0.2741761 - b2 * 0.32180189);
+    let q = (r1 * 0.21147017 - g1 * 0.52261711 + b1 * 0.31114694)
+        - (r2 * 0.21147017 - g2 * 0.52261711 + b2 * 0.31114694);
+
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+/// The 3x3 neighbourhood of a pixel, clipped to the image, without the pixel
+fn neighbours(img: &RgbaImage, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> {
+    let (x0, y0) = (x.saturating_sub(1), y.saturating_sub(1));
+    let (x1, y1) = ((x + 1).min(img.width() - 1), (y + 1).min(img.height() - 1));
+    (y0..=y1)
+        .flat_map(move |ny| (x0..=x1).map(move |nx| (nx, ny)))
+        .filter(move |&p| p != (x, y))
+}
+
+/// Whether a pixel sits on an image border, where part of its neighbourhood is missing
+fn on_border(img: &RgbaImage, x: u32, y: u32) -> bool {
+    x == 0 || y == 0 || x + 1 >= img.width() || y + 1 >= img.height()
+}
+
+/// Whether a pixel looks like anti-aliasing between two flat areas
+///
+/// It must have both darker and brighter neighbours, and the darkest or
+/// brightest of them must sit in a flat area in both images (the approach of
+/// pixelmatch).
+fn antialiased(img: &RgbaImage, x: u32, y: u32, other: &RgbaImage) -> bool {
+    let center = brightness(img.get_pixel(x, y));
+    let mut equal = on_border(img, x, y) as u32;
+    let (mut min, mut max) = (0.0, 0.0);
+    let (mut darkest, mut brightest) = (None, None);
+
+    for (nx, ny) in neighbours(img, x, y) {
+        let delta = brightness(img.get_pixel(nx, ny)) - center;
+        if delta == 0.0 {
+            equal += 1;
+            if equal > 2 {
+                return false;
+            }
+        } else if delta < min {
+            min = delta;
+            darkest = Some((nx, ny));
+        } else if delta > max {
+            max = delta;
+            brightest = Some((nx, ny));
+        }
+    }
+
+    let (Some(darkest), Some(brightest)) = (darkest, brightest) else {
+        return false;
+    };
+    let flat = |p: (u32, u32)| has_many_siblings(img, p) && has_many_siblings(other, p);
+    flat(darkest) || flat(brightest)
+}
+
+/// Whether at least three neighbours of a pixel have exactly its color
+fn has_many_siblings(img: &RgbaImage, (x, y): (u32, u32)) -> bool {
+    if x >= img.width() || y >= img.height() {
+        return false;
+    }
+    let pixel = img.get_pixel(x, y);
+    let equal = on_border(img, x, y) as u32
+        + neighbours(img, x, y)
+            .filter(|&(nx, ny)| img.get_pixel(nx, ny) == pixel)
+            .count() as u32;
+    equal > 2
+}
+
 /// Configuration for visual testing
 #[derive(Debug, Clone)]
 pub struct VisualConfig {
@@ -274,7 +478,13 @@ pub struct VisualConfig {
     pub actual_dir: PathBuf,
     pub diff_dir: PathBuf,
     pub threshold: f64,
+    /// Per-pixel color tolerance (0.0 - 1.0)
+    pub tolerance: f64,
+    pub ignore_antialiasing: bool,
+    /// Create baselines that are missing
     pub auto_update: bool,
+    /// Overwrite baselines that don't match; defaults to `E2E_UPDATE_BASELINES=1`
+    pub update: bool,
 }
 
 impl Default for VisualConfig {
@@ -284,7 +494,10 @@ impl Default for VisualConfig {
             actual_dir: PathBuf::from("test-results/screenshots"),
             diff_dir: PathBuf::from("test-results/diffs"),
             threshold: 0.5,
+            tolerance: 0.1,
+            ignore_antialiasing: true,
             auto_update: false,
+            update: update_baselines_requested(),
         }
     }
 }
@@ -298,5 +511,107 @@ mod tests {
         let config = VisualConfig::default();
         assert_eq!(config.threshold, 0.5);
         assert!(!config.auto_update);
+        assert!(config.ignore_antialiasing);
+    }
+
+    /// Left half black, right half white
+    fn split_image() -> RgbaImage {
+        RgbaImage::from_fn(10, 10, |x, _| {
+            if x < 5 { Rgba([0, 0, 0, 255]) } else { Rgba([255, 255, 255, 255]) }
+        })
+    }
+
+    fn options() -> CompareOptions {
+        CompareOptions {
+            threshold: 0.0,
+            tolerance: 0.1,
+            ignore_antialiasing: true,
+            masks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_compare_tolerance() {
+        let baseline = split_image();
+        let mut actual = baseline.clone();
+        actual.put_pixel(1, 1, Rgba([3, 2, 4, 255]));
+
+        let result = compare_images(&actual, &baseline, &options());
+        assert_eq!(result.diff_pixels, 0);
+        assert_eq!(result.total_pixels, 100);
+
+        actual.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        let result = compare_images(&actual, &baseline, &options());
+        assert_eq!(result.diff_pixels, 1);
+    }
+
+    #[test]
+    fn test_compare_antialiasing() {
+        let baseline = split_image();
+        let mut actual = baseline.clone();
+        actual.put_pixel(5, 5, Rgba([128, 128, 128, 255]));
+
+        let result = compare_images(&actual, &baseline, &options());
+        assert_eq!(result.diff_pixels, 0);
+        assert_eq!(result.antialiased_pixels, 1);
+
+        let strict = CompareOptions { ignore_antialiasing: false, ..options() };
+        let result = compare_images(&actual, &baseline, &strict);
+        assert_eq!(result.diff_pixels, 1);
+    }
+
+    #[test]
+    fn test_compare_masks_and_size() {
+        let baseline = split_image();
+        let mut actual = baseline.clone();
+        for x in 0..3 {
+            actual.put_pixel(x, 8, Rgba([255, 0, 0, 255]));
+        }
+
+        let masked = CompareOptions {
+            masks: vec![Region { x: 0, y: 8, width: 3, height: 1 }],
+            ..options()
+        };
+        let result = compare_images(&actual, &baseline, &masked);
+        assert_eq!(result.diff_pixels, 0);
+        assert_eq!(result.total_pixels, 97);
+
+        let smaller = RgbaImage::from_fn(10, 9, |x, y| *baseline.get_pixel(x, y));
+        let result = compare_images(&smaller, &baseline, &options());
+        assert_eq!(result.diff_pixels, 10);
+    }
+
+    #[test]
+    fn test_update_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = VisualConfig {
+            baseline_dir: dir.path().join("baselines"),
+            actual_dir: dir.path().join("screenshots"),
+            diff_dir: dir.path().join("diffs"),
+            update: false,
+            ..Default::default()
+        };
+        let tester = VisualTester::new(config.clone()).unwrap();
+
+        let baseline = split_image();
+        let mut actual = baseline.clone();
+        actual.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        baseline.save(config.baseline_dir.join("page.png")).unwrap();
+        actual.save(config.actual_dir.join("page.png")).unwrap();
+
+        let diff = tester.compare("page", &tester.default_options()).unwrap();
+        assert!(!diff.matches);
+        assert_eq!(diff.diff_pixels, 1);
+        assert!(diff.diff_image_path.unwrap().exists());
+
+        let tester = VisualTester::new(VisualConfig { update: true, ..config.clone() }).unwrap();
+        let diff = tester.compare("page", &tester.default_options()).unwrap();
+        assert!(diff.matches);
+        assert!(diff.baseline_updated);
+
+        let diff = tester.compare("page", &tester.default_options()).unwrap();
+        assert!(diff.matches);
+        assert!(!diff.baseline_updated);
+        assert_eq!(diff.actual_hash, diff.baseline_hash);
     }
 }
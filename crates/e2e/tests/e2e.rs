@@ -7,11 +7,12 @@ use std::path::PathBuf;
 use clap::Parser;
 use tracing_subscriber::{fmt, EnvFilter};
 
-use infrasim_e2e::{TestRunner, E2eResult};
+use infrasim_e2e::{Monitor, MonitorConfig, NotificationSink, TestRunner, E2eResult};
+use infrasim_e2e::api::ApiConfig;
 use infrasim_e2e::runner::RunnerConfig;
 use infrasim_e2e::server::ServerConfig;
 use infrasim_e2e::playwright::PlaywrightConfig;
-use infrasim_e2e::visual::VisualConfig;
+use infrasim_e2e::visual::{update_baselines_requested, VisualConfig};
 
 #[derive(Parser, Debug)]
 #[command(name = "infrasim-e2e")]
@@ -29,7 +30,7 @@ struct Args {
     #[arg(short, long)]
     name: Option<String>,
 
-    /// Update visual baselines instead of comparing
+    /// Update visual baselines instead of comparing (or E2E_UPDATE_BASELINES=1)
     #[arg(long)]
     update_baselines: bool,
 
@@ -49,6 +50,14 @@ struct Args {
     #[arg(long, default_value = "http://127.0.0.1:9090")]
     daemon_addr: String,
 
+    /// Daemon proto file used by grpc_call steps
+    #[arg(long, default_value = "proto/infrasim.proto")]
+    proto: PathBuf,
+
+    /// grpcurl executable used by grpc_call steps
+    #[arg(long, default_value = "grpcurl")]
+    grpcurl: String,
+
     /// Browser to use (chromium, firefox, webkit)
     #[arg(long, default_value = "chromium")]
     browser: String,
@@ -72,6 +81,26 @@ struct Args {
     /// Output directory for results
     #[arg(short, long, default_value = "test-results")]
     output: PathBuf,
+
+    /// Test an existing deployment at this URL instead of spawning a server
+    #[arg(long)]
+    target_url: Option<String>,
+
+    /// Continuously run `monitor: true` specs against --target-url
+    #[arg(long, requires = "target_url")]
+    monitor: bool,
+
+    /// Seconds between monitor runs
+    #[arg(long, default_value = "300")]
+    interval_secs: u64,
+
+    /// Stop monitoring after this many runs
+    #[arg(long)]
+    max_runs: Option<usize>,
+
+    /// Webhook URL for monitor notifications (repeatable)
+    #[arg(long)]
+    webhook: Vec<String>,
 }
 
 fn main() {
@@ -126,14 +155,37 @@ async fn async_main(args: Args) -> E2eResult<bool> {
         visual: VisualConfig {
             threshold: args.visual_threshold,
             auto_update: args.update_baselines,
+            update: args.update_baselines || update_baselines_requested(),
+            diff_dir: args.output.join("diffs"),
+            ..Default::default()
+        },
+        api: ApiConfig {
+            proto_path: args.proto,
+            grpcurl: args.grpcurl,
             ..Default::default()
         },
         specs_dir: args.specs,
         output_dir: args.output,
+        target_url: args.target_url,
     };
 
     let mut runner = TestRunner::with_config(config);
 
+    if args.monitor {
+        let mut sinks = vec![NotificationSink::Log];
+        sinks.extend(args.webhook.into_iter().map(|url| NotificationSink::Webhook { url }));
+
+        let mut monitor = Monitor::new(runner, MonitorConfig {
+            interval: std::time::Duration::from_secs(args.interval_secs),
+            tag: args.tag,
+            sinks,
+            max_runs: args.max_runs,
+            ..Default::default()
+        });
+        monitor.run().await?;
+        return Ok(true);
+    }
+
     // Start server
     runner.start_server().await?;
 
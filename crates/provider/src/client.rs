@@ -1,162 +1,594 @@
 //! Client for communicating with the InfraSim daemon
 
-use tonic::transport::Channel;
+use std::future::Future;
+use std::time::Duration;
+
 use anyhow::Result;
+use infrasim_common::auth::{AuthChannel, ClientCredentials};
+use infrasim_common::Error as CommonError;
+use tonic::{Code, Status};
+use tracing::warn;
 
 use crate::generated::infrasim::infra_sim_daemon_client::InfraSimDaemonClient;
 use crate::generated::infrasim::*;
 
+/// How calls that fail because the daemon is briefly unavailable are retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each retry after it
+    pub base_delay: Duration,
+    /// Longest delay between two attempts
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt`, counting from 0
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay)
+    }
+}
+
+/// Whether a failed call is worth retrying: the daemon could not be reached
+/// or was too busy to take it
+pub fn is_transient(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::ResourceExhausted | Code::Aborted)
+}
+
 /// Client wrapper for daemon communication
+///
+/// Clones share one HTTP/2 connection, which tonic re-establishes when the
+/// daemon restarts.
+#[derive(Clone)]
 pub struct DaemonClient {
-    client: InfraSimDaemonClient<Channel>,
+    client: InfraSimDaemonClient<AuthChannel>,
+    retry: RetryPolicy,
 }
 
 impl DaemonClient {
-    /// Connect to the daemon
-    pub async fn connect(addr: &str) -> Result<Self> {
-        let client = InfraSimDaemonClient::connect(addr.to_string()).await?;
-        Ok(Self { client })
+    /// Connect to the daemon, retrying while it refuses connections
+    pub async fn connect(addr: &str, credentials: &ClientCredentials, retry: RetryPolicy) -> Result<Self> {
+        let mut attempt = 0;
+        let channel = loop {
+            match credentials.connect(addr).await {
+                Ok(channel) => break channel,
+                Err(CommonError::NetworkError(e)) if attempt < retry.max_retries => {
+                    let delay = retry.delay(attempt);
+                    warn!("Cannot connect to daemon ({}), retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        Ok(Self { client: InfraSimDaemonClient::new(channel), retry })
+    }
+
+    /// Make a call, retrying it with exponential backoff on transient errors
+    async fn call<Req, Resp, F, Fut>(&self, request: Req, mut rpc: F) -> Result<Resp>
+    where
+        Req: Clone,
+        F: FnMut(InfraSimDaemonClient<AuthChannel>, Req) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, Status>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match rpc(self.client.clone(), request.clone()).await {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(status) if is_transient(&status) && attempt < self.retry.max_retries => {
+                    let delay = self.retry.delay(attempt);
+                    warn!("Daemon call failed ({}), retrying in {:?}", status.message(), delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(status) => return Err(status.into()),
+            }
+        }
     }
 
     // Network operations
 
     pub async fn create_network(&mut self, name: &str, spec: NetworkSpec) -> Result<Network> {
-        let request = tonic::Request::new(CreateNetworkRequest {
+        let request = CreateNetworkRequest {
             name: name.to_string(),
             spec: Some(spec),
             labels: Default::default(),
-        });
-        let response = self.client.create_network(request).await?;
-        response.into_inner().network
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_network(r).await }).await?;
+        response.network
             .ok_or_else(|| anyhow::anyhow!("No network in response"))
     }
 
     pub async fn get_network(&mut self, id: &str) -> Result<Network> {
-        let request = tonic::Request::new(GetNetworkRequest { id: id.to_string() });
-        let response = self.client.get_network(request).await?;
-        response.into_inner().network
+        let request = GetNetworkRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_network(r).await }).await?;
+        response.network
             .ok_or_else(|| anyhow::anyhow!("Network not found"))
     }
 
+    pub async fn list_networks(&mut self) -> Result<Vec<Network>> {
+        let request = ListNetworksRequest {
+            label_selector: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_networks(r).await }).await?;
+        Ok(response.networks)
+    }
+
     pub async fn delete_network(&mut self, id: &str) -> Result<()> {
-        let request = tonic::Request::new(DeleteNetworkRequest {
+        let request = DeleteNetworkRequest {
             id: id.to_string(),
-        });
-        self.client.delete_network(request).await?;
+        };
+        self.call(request, |mut c, r| async move { c.delete_network(r).await }).await?;
         Ok(())
     }
 
     // VM operations
 
     pub async fn create_vm(&mut self, name: &str, spec: VmSpec) -> Result<Vm> {
-        let request = tonic::Request::new(CreateVmRequest {
+        let request = CreateVmRequest {
             name: name.to_string(),
             spec: Some(spec),
             labels: Default::default(),
-        });
-        let response = self.client.create_vm(request).await?;
-        response.into_inner().vm
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_vm(r).await }).await?;
+        response.vm
             .ok_or_else(|| anyhow::anyhow!("No VM in response"))
     }
 
     pub async fn get_vm(&mut self, id: &str) -> Result<Vm> {
-        let request = tonic::Request::new(GetVmRequest { id: id.to_string() });
-        let response = self.client.get_vm(request).await?;
-        response.into_inner().vm
+        let request = GetVmRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_vm(r).await }).await?;
+        response.vm
             .ok_or_else(|| anyhow::anyhow!("VM not found"))
     }
 
+    pub async fn update_vm(&mut self, id: &str, spec: VmSpec) -> Result<Vm> {
+        let request = UpdateVmRequest {
+            id: id.to_string(),
+            spec: Some(spec),
+        };
+        let response = self.call(request, |mut c, r| async move { c.update_vm(r).await }).await?;
+        response.vm
+            .ok_or_else(|| anyhow::anyhow!("No VM in response"))
+    }
+
+    pub async fn list_vms(&mut self) -> Result<Vec<Vm>> {
+        let request = ListVMsRequest {
+            label_selector: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_v_ms(r).await }).await?;
+        Ok(response.vms)
+    }
+
     pub async fn start_vm(&mut self, id: &str) -> Result<Vm> {
-        let request = tonic::Request::new(StartVmRequest { id: id.to_string() });
-        let response = self.client.start_vm(request).await?;
-        response.into_inner().vm
+        let request = StartVmRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.start_vm(r).await }).await?;
+        response.vm
             .ok_or_else(|| anyhow::anyhow!("No VM in response"))
     }
 
-    pub async fn stop_vm(&mut self, id: &str, force: bool) -> Result<Vm> {
-        let request = tonic::Request::new(StopVmRequest {
+    pub async fn stop_vm(&mut self, id: &str, mode: StopMode, timeout_seconds: u32) -> Result<Vm> {
+        let request = StopVmRequest {
             id: id.to_string(),
-            force,
-        });
-        let response = self.client.stop_vm(request).await?;
-        response.into_inner().vm
+            force: false,
+            mode: mode as i32,
+            timeout_seconds,
+        };
+        let response = self.call(request, |mut c, r| async move { c.stop_vm(r).await }).await?;
+        response.vm
             .ok_or_else(|| anyhow::anyhow!("No VM in response"))
     }
 
     pub async fn delete_vm(&mut self, id: &str) -> Result<()> {
-        let request = tonic::Request::new(DeleteVmRequest {
+        let request = DeleteVmRequest {
             id: id.to_string(),
             force: true,
-        });
-        self.client.delete_vm(request).await?;
+        };
+        self.call(request, |mut c, r| async move { c.delete_vm(r).await }).await?;
         Ok(())
     }
 
+    pub async fn clone_vm(&mut self, template: &str, name: &str, count: i32) -> Result<Vec<Vm>> {
+        let request = CloneVmRequest {
+            template_id: template.to_string(),
+            name: name.to_string(),
+            count,
+            labels: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.clone_vm(r).await }).await?;
+        Ok(response.vms)
+    }
+
     // Volume operations
 
     pub async fn create_volume(&mut self, name: &str, spec: VolumeSpec) -> Result<Volume> {
-        let request = tonic::Request::new(CreateVolumeRequest {
+        let request = CreateVolumeRequest {
             name: name.to_string(),
             spec: Some(spec),
             labels: Default::default(),
-        });
-        let response = self.client.create_volume(request).await?;
-        response.into_inner().volume
+            build: None,
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_volume(r).await }).await?;
+        response.volume
             .ok_or_else(|| anyhow::anyhow!("No volume in response"))
     }
 
     pub async fn get_volume(&mut self, id: &str) -> Result<Volume> {
-        let request = tonic::Request::new(GetVolumeRequest { id: id.to_string() });
-        let response = self.client.get_volume(request).await?;
-        response.into_inner().volume
+        let request = GetVolumeRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_volume(r).await }).await?;
+        response.volume
             .ok_or_else(|| anyhow::anyhow!("Volume not found"))
     }
 
+    pub async fn list_volumes(&mut self) -> Result<Vec<Volume>> {
+        let request = ListVolumesRequest {
+            label_selector: Default::default(),
+            kind_filter: 0,
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_volumes(r).await }).await?;
+        Ok(response.volumes)
+    }
+
+    pub async fn resize_volume(&mut self, id: &str, size_bytes: i64) -> Result<Volume> {
+        let request = ResizeVolumeRequest {
+            volume_id: id.to_string(),
+            size_bytes,
+            shrink: false,
+        };
+        let response = self.call(request, |mut c, r| async move { c.resize_volume(r).await }).await?;
+        response.volume
+            .ok_or_else(|| anyhow::anyhow!("No volume in response"))
+    }
+
     pub async fn delete_volume(&mut self, id: &str) -> Result<()> {
-        let request = tonic::Request::new(DeleteVolumeRequest {
+        let request = DeleteVolumeRequest {
             id: id.to_string(),
-        });
-        self.client.delete_volume(request).await?;
+        };
+        self.call(request, |mut c, r| async move { c.delete_volume(r).await }).await?;
         Ok(())
     }
 
     // Snapshot operations
 
     pub async fn create_snapshot(&mut self, name: &str, spec: SnapshotSpec) -> Result<Snapshot> {
-        let request = tonic::Request::new(CreateSnapshotRequest {
+        let request = CreateSnapshotRequest {
             name: name.to_string(),
             spec: Some(spec),
             labels: Default::default(),
-        });
-        let response = self.client.create_snapshot(request).await?;
-        response.into_inner().snapshot
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_snapshot(r).await }).await?;
+        response.snapshot
             .ok_or_else(|| anyhow::anyhow!("No snapshot in response"))
     }
 
+    pub async fn get_snapshot(&mut self, id: &str) -> Result<Snapshot> {
+        let request = GetSnapshotRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_snapshot(r).await }).await?;
+        response.snapshot
+            .ok_or_else(|| anyhow::anyhow!("Snapshot not found"))
+    }
+
+    pub async fn list_snapshots(&mut self) -> Result<Vec<Snapshot>> {
+        let request = ListSnapshotsRequest {
+            vm_id: String::new(),
+            label_selector: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_snapshots(r).await }).await?;
+        Ok(response.snapshots)
+    }
+
     pub async fn restore_snapshot(&mut self, snapshot_id: &str, target_vm_id: Option<&str>) -> Result<Vm> {
-        let request = tonic::Request::new(RestoreSnapshotRequest { 
+        let request = RestoreSnapshotRequest { 
             snapshot_id: snapshot_id.to_string(),
             target_vm_id: target_vm_id.unwrap_or_default().to_string(),
-        });
-        let response = self.client.restore_snapshot(request).await?;
-        response.into_inner().vm
+        };
+        let response = self.call(request, |mut c, r| async move { c.restore_snapshot(r).await }).await?;
+        response.vm
             .ok_or_else(|| anyhow::anyhow!("No VM in response"))
     }
 
     pub async fn delete_snapshot(&mut self, id: &str) -> Result<()> {
-        let request = tonic::Request::new(DeleteSnapshotRequest { id: id.to_string() });
-        self.client.delete_snapshot(request).await?;
+        let request = DeleteSnapshotRequest { id: id.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_snapshot(r).await }).await?;
         Ok(())
     }
 
+    // Port forward operations
+
+    pub async fn create_port_forward(&mut self, name: &str, spec: PortForwardSpec) -> Result<PortForward> {
+        let request = CreatePortForwardRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_port_forward(r).await }).await?;
+        response.port_forward
+            .ok_or_else(|| anyhow::anyhow!("No port forward in response"))
+    }
+
+    pub async fn get_port_forward(&mut self, id: &str) -> Result<PortForward> {
+        let request = GetPortForwardRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_port_forward(r).await }).await?;
+        response.port_forward
+            .ok_or_else(|| anyhow::anyhow!("Port forward not found"))
+    }
+
+    pub async fn list_port_forwards(&mut self) -> Result<Vec<PortForward>> {
+        let request = ListPortForwardsRequest {
+            vm_id: String::new(),
+            network_id: String::new(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_port_forwards(r).await }).await?;
+        Ok(response.port_forwards)
+    }
+
+    pub async fn delete_port_forward(&mut self, id: &str) -> Result<()> {
+        let request = DeletePortForwardRequest { id: id.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_port_forward(r).await }).await?;
+        Ok(())
+    }
+
+    // Router operations
+
+    pub async fn create_router(&mut self, name: &str, spec: RouterSpec) -> Result<Router> {
+        let request = CreateRouterRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_router(r).await }).await?;
+        response.router
+            .ok_or_else(|| anyhow::anyhow!("No router in response"))
+    }
+
+    pub async fn get_router(&mut self, id: &str) -> Result<Router> {
+        let request = GetRouterRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_router(r).await }).await?;
+        response.router
+            .ok_or_else(|| anyhow::anyhow!("Router not found"))
+    }
+
+    pub async fn update_router(&mut self, id: &str, spec: RouterSpec) -> Result<Router> {
+        let request = UpdateRouterRequest {
+            id: id.to_string(),
+            spec: Some(spec),
+        };
+        let response = self.call(request, |mut c, r| async move { c.update_router(r).await }).await?;
+        response.router
+            .ok_or_else(|| anyhow::anyhow!("No router in response"))
+    }
+
+    pub async fn list_routers(&mut self) -> Result<Vec<Router>> {
+        let request = ListRoutersRequest {
+            network_id: String::new(),
+            label_selector: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_routers(r).await }).await?;
+        Ok(response.routers)
+    }
+
+    pub async fn delete_router(&mut self, id: &str) -> Result<()> {
+        let request = DeleteRouterRequest { id: id.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_router(r).await }).await?;
+        Ok(())
+    }
+
+    // QoS profile operations
+
+    pub async fn create_qos_profile(&mut self, name: &str, spec: QoSProfileSpec) -> Result<QoSProfile> {
+        let request = CreateQoSProfileRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_qo_s_profile(r).await }).await?;
+        response.profile
+            .ok_or_else(|| anyhow::anyhow!("No QoS profile in response"))
+    }
+
+    pub async fn get_qos_profile(&mut self, id: &str) -> Result<QoSProfile> {
+        let request = GetQoSProfileRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_qo_s_profile(r).await }).await?;
+        response.profile
+            .ok_or_else(|| anyhow::anyhow!("QoS profile not found"))
+    }
+
+    pub async fn list_qos_profiles(&mut self) -> Result<Vec<QoSProfile>> {
+        let request = ListQoSProfilesRequest {
+            label_selector: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_qo_s_profiles(r).await }).await?;
+        Ok(response.profiles)
+    }
+
+    pub async fn delete_qos_profile(&mut self, id: &str) -> Result<()> {
+        let request = DeleteQoSProfileRequest { id: id.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_qo_s_profile(r).await }).await?;
+        Ok(())
+    }
+
+    // Device operations
+
+    pub async fn create_device(&mut self, name: &str, spec: DeviceSpec) -> Result<Device> {
+        let request = CreateDeviceRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_device(r).await }).await?;
+        response.device
+            .ok_or_else(|| anyhow::anyhow!("No device in response"))
+    }
+
+    pub async fn get_device(&mut self, id: &str) -> Result<Device> {
+        let request = GetDeviceRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_device(r).await }).await?;
+        response.device
+            .ok_or_else(|| anyhow::anyhow!("Device not found"))
+    }
+
+    pub async fn list_devices(&mut self) -> Result<Vec<Device>> {
+        let request = ListDevicesRequest { vm_id: String::new() };
+        let response = self.call(request, |mut c, r| async move { c.list_devices(r).await }).await?;
+        Ok(response.devices)
+    }
+
+    pub async fn delete_device(&mut self, id: &str) -> Result<()> {
+        let request = DeleteDeviceRequest { id: id.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_device(r).await }).await?;
+        Ok(())
+    }
+
+    // Snapshot policy operations
+
+    pub async fn create_snapshot_policy(&mut self, name: &str, spec: SnapshotPolicySpec) -> Result<SnapshotPolicy> {
+        let request = CreateSnapshotPolicyRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+            labels: Default::default(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_snapshot_policy(r).await }).await?;
+        response.policy
+            .ok_or_else(|| anyhow::anyhow!("No snapshot policy in response"))
+    }
+
+    pub async fn get_snapshot_policy(&mut self, id: &str) -> Result<SnapshotPolicy> {
+        let request = GetSnapshotPolicyRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_snapshot_policy(r).await }).await?;
+        response.policy
+            .ok_or_else(|| anyhow::anyhow!("Snapshot policy not found"))
+    }
+
+    pub async fn update_snapshot_policy(&mut self, id: &str, spec: SnapshotPolicySpec) -> Result<SnapshotPolicy> {
+        let request = UpdateSnapshotPolicyRequest {
+            id: id.to_string(),
+            spec: Some(spec),
+        };
+        let response = self.call(request, |mut c, r| async move { c.update_snapshot_policy(r).await }).await?;
+        response.policy
+            .ok_or_else(|| anyhow::anyhow!("No snapshot policy in response"))
+    }
+
+    pub async fn list_snapshot_policies(&mut self) -> Result<Vec<SnapshotPolicy>> {
+        let request = ListSnapshotPoliciesRequest {
+            vm_id: String::new(),
+        };
+        let response = self.call(request, |mut c, r| async move { c.list_snapshot_policies(r).await }).await?;
+        Ok(response.policies)
+    }
+
+    pub async fn delete_snapshot_policy(&mut self, id: &str) -> Result<()> {
+        let request = DeleteSnapshotPolicyRequest { id: id.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_snapshot_policy(r).await }).await?;
+        Ok(())
+    }
+
+    // Secret operations
+
+    pub async fn create_secret(&mut self, name: &str, value: &str, overwrite: bool) -> Result<Secret> {
+        let request = CreateSecretRequest {
+            name: name.to_string(),
+            value: value.to_string(),
+            overwrite,
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_secret(r).await }).await?;
+        response.secret
+            .ok_or_else(|| anyhow::anyhow!("No secret in response"))
+    }
+
+    pub async fn get_secret(&mut self, name: &str) -> Result<GetSecretResponse> {
+        let request = GetSecretRequest { name: name.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_secret(r).await }).await?;
+        Ok(response)
+    }
+
+    pub async fn list_secrets(&mut self) -> Result<Vec<Secret>> {
+        let request = ListSecretsRequest {};
+        let response = self.call(request, |mut c, r| async move { c.list_secrets(r).await }).await?;
+        Ok(response.secrets)
+    }
+
+    pub async fn delete_secret(&mut self, name: &str) -> Result<()> {
+        let request = DeleteSecretRequest { name: name.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_secret(r).await }).await?;
+        Ok(())
+    }
+
+    // Daemon operations
+
+    pub async fn get_daemon_status(&mut self) -> Result<GetDaemonStatusResponse> {
+        let request = GetDaemonStatusRequest {};
+        let response = self.call(request, |mut c, r| async move { c.get_daemon_status(r).await }).await?;
+        Ok(response)
+    }
+
     // Console operations
 
-    pub async fn get_console(&mut self, id: &str) -> Result<String> {
-        let request = tonic::Request::new(GetConsoleRequest { id: id.to_string() });
-        let response = self.client.get_console(request).await?;
-        Ok(response.into_inner().console
-            .and_then(|c| c.status)
-            .map(|s| s.web_url)
-            .unwrap_or_default())
+    pub async fn create_console(&mut self, name: &str, spec: ConsoleSpec) -> Result<Console> {
+        let request = CreateConsoleRequest {
+            name: name.to_string(),
+            spec: Some(spec),
+        };
+        let response = self.call(request, |mut c, r| async move { c.create_console(r).await }).await?;
+        response.console
+            .ok_or_else(|| anyhow::anyhow!("No console in response"))
+    }
+
+    pub async fn get_console(&mut self, id: &str) -> Result<Console> {
+        let request = GetConsoleRequest { id: id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_console(r).await }).await?;
+        response.console
+            .ok_or_else(|| anyhow::anyhow!("Console not found"))
+    }
+
+    pub async fn delete_console(&mut self, id: &str) -> Result<()> {
+        let request = DeleteConsoleRequest { id: id.to_string() };
+        self.call(request, |mut c, r| async move { c.delete_console(r).await }).await?;
+        Ok(())
+    }
+
+    // Attestation operations
+
+    pub async fn get_attestation(&mut self, vm_id: &str) -> Result<AttestationReport> {
+        let request = GetAttestationRequest { vm_id: vm_id.to_string() };
+        let response = self.call(request, |mut c, r| async move { c.get_attestation(r).await }).await?;
+        response.report
+            .ok_or_else(|| anyhow::anyhow!("No attestation report in response"))
+    }
+
+    pub async fn get_signing_key(&mut self) -> Result<GetSigningKeyResponse> {
+        let request = GetSigningKeyRequest {};
+        let response = self.call(request, |mut c, r| async move { c.get_signing_key(r).await }).await?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_limit() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(0), Duration::from_millis(500));
+        assert_eq!(policy.delay(3), Duration::from_secs(4));
+        assert_eq!(policy.delay(10), Duration::from_secs(30));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(30));
+
+        assert!(is_transient(&Status::unavailable("starting QEMU")));
+        assert!(!is_transient(&Status::not_found("VM not found")));
     }
 }
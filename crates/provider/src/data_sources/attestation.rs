@@ -0,0 +1,183 @@
+//! Attestation Data Source Implementation
+//!
+//! Fetches the daemon's signed attestation report for a VM, or for the
+//! running VM a volume is attached to, and verifies it in the provider so
+//! plans can gate on provenance.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use infrasim_common::attestation::{verify_report_with_key, vtpm};
+use infrasim_common::crypto::{key_id, verifying_key_from_bytes};
+use infrasim_common::types;
+use crate::client::DaemonClient;
+use crate::generated::infrasim::{AttestationReport, TpmQuote, VmState};
+use crate::state::{
+    DynamicValue, get_optional_string_attr, make_state, string_value, string_list_value,
+    int_value, bool_value, null_value,
+};
+use super::DataSource;
+
+pub struct AttestationDataSource;
+
+#[async_trait::async_trait]
+impl DataSource for AttestationDataSource {
+    fn type_name() -> &'static str {
+        "infrasim_attestation"
+    }
+
+    async fn read(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let volume_id = get_optional_string_attr(config, "volume_id");
+        let expected_digest = get_optional_string_attr(config, "expected_digest");
+
+        // A pinned key proves the report came from a known daemon; without
+        // one the report is only checked against the daemon's own key
+        let public_key = match get_optional_string_attr(config, "public_key") {
+            Some(key) => key,
+            None => client.get_signing_key().await?.public_key,
+        };
+        let key_bytes = hex::decode(&public_key)
+            .with_context(|| format!("public_key '{}' is not hex", public_key))?;
+        let key = verifying_key_from_bytes(&key_bytes).context("Invalid public_key")?;
+
+        let mut failures = Vec::new();
+
+        let vm_id = match (get_optional_string_attr(config, "vm_id"), volume_id.as_deref()) {
+            (Some(vm_id), _) => Some(vm_id),
+            (None, Some(volume_id)) => {
+                // Volume hashes are covered by the reports of the VMs using them
+                let vm = client.list_vms().await?.into_iter().find(|vm| {
+                    let running = matches!(
+                        VmState::try_from(vm.status.clone().unwrap_or_default().state),
+                        Ok(VmState::Running) | Ok(VmState::Paused)
+                    );
+                    running && vm.spec.as_ref().is_some_and(|s| s.volume_ids.iter().any(|v| v == volume_id))
+                });
+                if vm.is_none() {
+                    failures.push(format!("Volume '{}' is not attached to a running VM", volume_id));
+                }
+                vm.and_then(|vm| vm.meta).map(|meta| meta.id)
+            }
+            (None, None) => anyhow::bail!("{} needs one of vm_id or volume_id", Self::type_name()),
+        };
+
+        let report = match &vm_id {
+            Some(vm_id) => Some(report_from_proto(&client.get_attestation(vm_id).await?)),
+            None => None,
+        };
+
+        let verification = report
+            .as_ref()
+            .map(|r| verify_report_with_key(r, &key))
+            .transpose()?
+            .unwrap_or_default();
+        if report.is_some() && !verification.digest_valid {
+            failures.push("Report digest does not match its contents".to_string());
+        }
+        if report.is_some() && !verification.signature_valid {
+            failures.push(format!("Report signature does not verify with key {}", key_id(&key_bytes)));
+        }
+
+        let mut tpm_quotes_valid = true;
+        for quote in report.iter().flat_map(|r| &r.tpm_quotes) {
+            if !vtpm::verify_quote(quote, None, None).is_ok_and(|v| v.is_trusted()) {
+                tpm_quotes_valid = false;
+                failures.push(format!("vTPM quote '{}' failed verification", quote.label));
+            }
+        }
+
+        // The measured artifact: the attached volume, or the VM's boot disk
+        let provenance = report.as_ref().map(|r| &r.host_provenance);
+        let subject_digest = match (provenance, volume_id.as_deref()) {
+            (Some(p), Some(volume_id)) => p.volume_hashes.get(volume_id).cloned(),
+            (Some(p), None) => Some(p.base_image_hash.clone()),
+            (None, _) => None,
+        };
+        if let Some(volume_id) = volume_id.as_deref().filter(|_| report.is_some() && subject_digest.is_none()) {
+            failures.push(format!("Volume '{}' has no digest in the report", volume_id));
+        }
+        if let Some(expected) = &expected_digest {
+            if subject_digest.as_ref() != Some(expected) {
+                failures.push(format!(
+                    "Digest {} does not match expected {}",
+                    subject_digest.as_deref().unwrap_or("(none)"),
+                    expected
+                ));
+            }
+        }
+
+        let optional = |v: Option<&String>| v.map(string_value).unwrap_or_else(null_value);
+        Ok(make_state(vec![
+            ("id", string_value(report.as_ref().map(|r| r.id.clone()).or_else(|| volume_id.clone()).unwrap_or_default())),
+            ("vm_id", optional(vm_id.as_ref())),
+            ("volume_id", optional(volume_id.as_ref())),
+            ("expected_digest", optional(expected_digest.as_ref())),
+            ("public_key", string_value(&public_key)),
+            ("key_id", string_value(key_id(&key_bytes))),
+            ("digest", optional(report.as_ref().map(|r| &r.digest))),
+            ("subject_digest", optional(subject_digest.as_ref())),
+            ("attestation_type", optional(report.as_ref().map(|r| &r.attestation_type))),
+            ("created_at", report.as_ref().map(|r| int_value(r.created_at)).unwrap_or_else(null_value)),
+            ("base_image_hash", optional(provenance.map(|p| &p.base_image_hash))),
+            ("volume_hashes", DynamicValue::Map(
+                provenance
+                    .map(|p| p.volume_hashes.iter().map(|(k, v)| (k.clone(), string_value(v))).collect())
+                    .unwrap_or_else(HashMap::new),
+            )),
+            ("qemu_version", optional(provenance.map(|p| &p.qemu_version))),
+            ("hostname", optional(provenance.map(|p| &p.hostname))),
+            ("digest_valid", bool_value(verification.digest_valid)),
+            ("signature_valid", bool_value(verification.signature_valid)),
+            ("tpm_quotes_valid", bool_value(report.is_some() && tpm_quotes_valid)),
+            ("verified", bool_value(failures.is_empty())),
+            ("failures", string_list_value(&failures)),
+        ]))
+    }
+}
+
+fn report_from_proto(report: &AttestationReport) -> types::AttestationReport {
+    let provenance = report.host_provenance.clone().unwrap_or_default();
+    types::AttestationReport {
+        id: report.id.clone(),
+        vm_id: report.vm_id.clone(),
+        host_provenance: types::HostProvenance {
+            qemu_version: provenance.qemu_version,
+            qemu_args: provenance.qemu_args,
+            base_image_hash: provenance.base_image_hash,
+            volume_hashes: provenance.volume_hashes.into_iter().collect(),
+            macos_version: provenance.macos_version,
+            cpu_model: provenance.cpu_model,
+            hvf_enabled: provenance.hvf_enabled,
+            hostname: provenance.hostname,
+            timestamp: provenance.timestamp,
+        },
+        digest: report.digest.clone(),
+        signature: report.signature.clone(),
+        created_at: report.created_at,
+        attestation_type: report.attestation_type.clone(),
+        tpm_quotes: report.tpm_quotes.iter().map(quote_from_proto).collect(),
+    }
+}
+
+fn quote_from_proto(quote: &TpmQuote) -> types::TpmQuote {
+    types::TpmQuote {
+        vm_id: quote.vm_id.clone(),
+        label: quote.label.clone(),
+        pcrs: quote.pcrs.iter().map(|(k, v)| (*k, v.clone())).collect(),
+        quoted: quote.quoted.clone(),
+        signature: quote.signature.clone(),
+        ak_public: quote.ak_public.clone(),
+        nonce: quote.nonce.clone(),
+        event_log: quote
+            .event_log
+            .iter()
+            .map(|e| types::TpmEvent {
+                pcr_index: e.pcr_index,
+                digest: e.digest.clone(),
+                command: e.command.clone(),
+                data: e.data.clone(),
+            })
+            .collect(),
+        created_at: quote.created_at,
+    }
+}
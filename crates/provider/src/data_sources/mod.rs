@@ -0,0 +1,19 @@
+//! Data Source Implementations
+//!
+//! Implements the reads behind each data source type.
+
+pub mod attestation;
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::DynamicValue;
+
+/// Trait for data source operations
+#[async_trait::async_trait]
+pub trait DataSource {
+    /// Data source type name
+    fn type_name() -> &'static str;
+
+    /// Read the data source for a config
+    async fn read(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue>;
+}
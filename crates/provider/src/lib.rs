@@ -7,9 +7,11 @@
 pub mod server;
 pub mod provider;
 pub mod resources;
+pub mod data_sources;
 pub mod schema;
 pub mod state;
 pub mod client;
+pub mod validation;
 
 mod generated {
     pub mod infrasim {
@@ -14,9 +14,11 @@ use tracing::{info, error};
 mod server;
 mod provider;
 mod resources;
+mod data_sources;
 mod schema;
 mod state;
 mod client;
+mod validation;
 
 mod generated {
     pub mod infrasim {
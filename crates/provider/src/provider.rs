@@ -2,41 +2,99 @@
 //!
 //! Implements the Terraform Plugin Protocol v6 Provider service.
 
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
 use tracing::{info, error, debug};
 
 use crate::generated::tfplugin6::*;
 use crate::generated::tfplugin6::provider_server::Provider;
-use crate::client::DaemonClient;
+use crate::client::{DaemonClient, RetryPolicy};
 use crate::schema;
+use crate::validation;
 use crate::state::{
     DynamicValue as LocalDynamicValue, decode_dynamic_value, encode_dynamic_value,
-    get_string_attr,
+    get_int_attr, get_optional_string_attr, get_string_attr,
 };
-use crate::resources::{Resource, network::NetworkResource, vm::VmResource, volume::VolumeResource, snapshot::SnapshotResource};
+use infrasim_common::auth::ClientCredentials;
+use infrasim_common::types::{format_interval, parse_interval};
+use crate::data_sources::{DataSource, attestation::AttestationDataSource};
+use crate::resources::{Resource, network::NetworkResource, vm::VmResource, vm_clone::VmCloneResource, volume::VolumeResource, snapshot::SnapshotResource, port_forward::PortForwardResource, router::RouterResource, device::DeviceResource, qos_profile::QosProfileResource, snapshot_policy::SnapshotPolicyResource, secret::SecretResource, console::ConsoleResource};
+
+/// Time a resource operation may take unless its `timeouts` block says otherwise
+const DEFAULT_OPERATION_TIMEOUT: Duration = Duration::from_secs(20 * 60);
 
 /// InfraSim Terraform Provider
 pub struct InfraSimProvider {
-    /// Client for communicating with the daemon
+    /// Client for communicating with the daemon, shared by all operations
     client: Arc<RwLock<Option<DaemonClient>>>,
     /// Daemon address
     daemon_addr: Arc<RwLock<String>>,
+    /// Certificates and token presented to the daemon
+    credentials: Arc<RwLock<ClientCredentials>>,
+    /// How transient daemon errors are retried
+    retry: Arc<RwLock<RetryPolicy>>,
 }
 
 impl InfraSimProvider {
     pub async fn new() -> anyhow::Result<Self> {
         Ok(Self {
             client: Arc::new(RwLock::new(None)),
-            daemon_addr: Arc::new(RwLock::new("http://127.0.0.1:50051".to_string())),
+            daemon_addr: Arc::new(RwLock::new(infrasim_common::default_daemon_addr())),
+            credentials: Arc::new(RwLock::new(ClientCredentials::default().with_env())),
+            retry: Arc::new(RwLock::new(RetryPolicy::default())),
         })
     }
 
+    /// Client on the provider's connection, which is made on first use
     async fn get_client(&self) -> Result<DaemonClient, Status> {
+        if let Some(client) = self.client.read().await.as_ref() {
+            return Ok(client.clone());
+        }
+
+        let mut cached = self.client.write().await;
+        if let Some(client) = cached.as_ref() {
+            return Ok(client.clone());
+        }
         let addr = self.daemon_addr.read().await.clone();
-        DaemonClient::connect(&addr).await
-            .map_err(|e| Status::unavailable(format!("Cannot connect to daemon: {}", e)))
+        let credentials = self.credentials.read().await.clone();
+        let retry = self.retry.read().await.clone();
+        let client = DaemonClient::connect(&addr, &credentials, retry).await
+            .map_err(|e| Status::unavailable(format!("Cannot connect to daemon: {}", e)))?;
+        *cached = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Check a resource config against the daemon host's capabilities.
+    /// Skipped when the daemon can't be reached, e.g. during `terraform
+    /// validate` before the provider is configured.
+    async fn host_diagnostics(&self, type_name: &str, config: Option<&DynamicValue>) -> Vec<Diagnostic> {
+        if type_name != "infrasim_vm" {
+            return vec![];
+        }
+
+        // Null config means the resource is being destroyed
+        let Some(config) = config
+            .and_then(|c| decode_dynamic_value(&c.msgpack).ok())
+            .filter(|c| c.as_map().is_some())
+        else {
+            return vec![];
+        };
+
+        let host = match self.get_client().await {
+            Ok(mut client) => client.get_daemon_status().await,
+            Err(e) => Err(e.into()),
+        };
+
+        match host {
+            Ok(host) => validation::vm_diagnostics(&config, &host),
+            Err(e) => {
+                debug!("Skipping host validation: {}", e);
+                vec![]
+            }
+        }
     }
 }
 
@@ -53,10 +111,20 @@ impl Provider for InfraSimProvider {
             resource_schemas: vec![
                 ("infrasim_network".to_string(), schema::network_schema()),
                 ("infrasim_vm".to_string(), schema::vm_schema()),
+                ("infrasim_vm_clone".to_string(), schema::vm_clone_schema()),
                 ("infrasim_volume".to_string(), schema::volume_schema()),
                 ("infrasim_snapshot".to_string(), schema::snapshot_schema()),
+                ("infrasim_port_forward".to_string(), schema::port_forward_schema()),
+                ("infrasim_console".to_string(), schema::console_schema()),
+                ("infrasim_router".to_string(), schema::router_schema()),
+                ("infrasim_device".to_string(), schema::device_schema()),
+                ("infrasim_qos_profile".to_string(), schema::qos_profile_schema()),
+                ("infrasim_snapshot_policy".to_string(), schema::snapshot_policy_schema()),
+                ("infrasim_secret".to_string(), schema::secret_schema()),
+            ].into_iter().collect(),
+            data_source_schemas: vec![
+                ("infrasim_attestation".to_string(), schema::attestation_data_source_schema()),
             ].into_iter().collect(),
-            data_source_schemas: std::collections::HashMap::new(),
             diagnostics: vec![],
             provider_meta: None,
             server_capabilities: Some(ServerCapabilities {
@@ -87,21 +155,34 @@ impl Provider for InfraSimProvider {
         &self,
         request: Request<validate_resource_config::Request>,
     ) -> Result<Response<validate_resource_config::Response>, Status> {
-        debug!("ValidateResourceConfig called for {}", request.get_ref().type_name);
+        let req = request.into_inner();
+        debug!("ValidateResourceConfig called for {}", req.type_name);
 
-        let response = validate_resource_config::Response {
-            diagnostics: vec![],
-        };
+        let mut diagnostics = req.config.as_ref()
+            .and_then(|c| decode_dynamic_value(&c.msgpack).ok())
+            .map(|config| validation::timeouts_diagnostics(&config))
+            .unwrap_or_default();
+        diagnostics.extend(self.host_diagnostics(&req.type_name, req.config.as_ref()).await);
+
+        let response = validate_resource_config::Response { diagnostics };
 
         Ok(Response::new(response))
     }
 
     async fn validate_data_resource_config(
         &self,
-        _request: Request<validate_data_resource_config::Request>,
+        request: Request<validate_data_resource_config::Request>,
     ) -> Result<Response<validate_data_resource_config::Response>, Status> {
+        let req = request.into_inner();
+        debug!("ValidateDataResourceConfig called for {}", req.type_name);
+
+        let diagnostics = match req.config.as_ref().and_then(|c| decode_dynamic_value(&c.msgpack).ok()) {
+            Some(config) if req.type_name == "infrasim_attestation" => validation::attestation_diagnostics(&config),
+            _ => vec![],
+        };
+
         Ok(Response::new(validate_data_resource_config::Response {
-            diagnostics: vec![],
+            diagnostics,
         }))
     }
 
@@ -137,14 +218,34 @@ impl Provider for InfraSimProvider {
                 if !addr.is_empty() {
                     *self.daemon_addr.write().await = addr;
                 }
+                // Unset attributes fall back to the INFRASIM_* environment
+                *self.credentials.write().await = ClientCredentials {
+                    ca_cert: get_optional_string_attr(&value, "ca_cert").map(Into::into),
+                    client_cert: get_optional_string_attr(&value, "client_cert").map(Into::into),
+                    client_key: get_optional_string_attr(&value, "client_key").map(Into::into),
+                    token: get_optional_string_attr(&value, "token"),
+                    tls_domain: get_optional_string_attr(&value, "tls_domain"),
+                }
+                .with_env();
+
+                let defaults = RetryPolicy::default();
+                *self.retry.write().await = RetryPolicy {
+                    max_retries: get_int_attr(&value, "max_retries", defaults.max_retries as i64).max(0) as u32,
+                    base_delay: Duration::from_millis(
+                        get_int_attr(&value, "retry_delay_ms", defaults.base_delay.as_millis() as i64).max(0) as u64,
+                    ),
+                    ..defaults
+                };
             }
         }
 
         // Test connection
         let addr = self.daemon_addr.read().await.clone();
+        let credentials = self.credentials.read().await.clone();
+        let retry = self.retry.read().await.clone();
         info!("Connecting to daemon at {}", addr);
 
-        match DaemonClient::connect(&addr).await {
+        match DaemonClient::connect(&addr, &credentials, retry).await {
             Ok(client) => {
                 *self.client.write().await = Some(client);
                 info!("Connected to daemon successfully");
@@ -176,17 +277,29 @@ impl Provider for InfraSimProvider {
 
         let mut client = self.get_client().await?;
 
-        let current_state = req.current_state
+        let current_state = req.current_state.as_ref()
             .and_then(|s| decode_dynamic_value(&s.msgpack).ok())
             .unwrap_or_default();
 
-        let new_state = match req.type_name.as_str() {
+        let timeout = operation_timeout(Some(&current_state), "read");
+        let new_state = with_timeout("read", timeout, async { Some(match req.type_name.as_str() {
             "infrasim_network" => NetworkResource::read(&mut client, &current_state).await,
             "infrasim_vm" => VmResource::read(&mut client, &current_state).await,
+            "infrasim_vm_clone" => VmCloneResource::read(&mut client, &current_state).await,
             "infrasim_volume" => VolumeResource::read(&mut client, &current_state).await,
             "infrasim_snapshot" => SnapshotResource::read(&mut client, &current_state).await,
-            _ => return Err(Status::not_found(format!("Unknown resource type: {}", req.type_name))),
-        };
+            "infrasim_port_forward" => PortForwardResource::read(&mut client, &current_state).await,
+            "infrasim_console" => ConsoleResource::read(&mut client, &current_state).await,
+            "infrasim_router" => RouterResource::read(&mut client, &current_state).await,
+            "infrasim_device" => DeviceResource::read(&mut client, &current_state).await,
+            "infrasim_qos_profile" => QosProfileResource::read(&mut client, &current_state).await,
+            "infrasim_snapshot_policy" => SnapshotPolicyResource::read(&mut client, &current_state).await,
+            "infrasim_secret" => SecretResource::read(&mut client, &current_state).await,
+            _ => return None,
+        }) })
+        .await
+        .ok_or_else(|| Status::not_found(format!("Unknown resource type: {}", req.type_name)))?
+        .map(|state| with_timeouts(state, &current_state));
 
         match new_state {
             Ok(state) => {
@@ -203,6 +316,20 @@ impl Provider for InfraSimProvider {
                     deferred: None,
                 }))
             }
+            // Still unreachable after the retries: keep the resource in state
+            Err(e) if is_unavailable(&e) => {
+                Ok(Response::new(read_resource::Response {
+                    new_state: req.current_state,
+                    diagnostics: vec![Diagnostic {
+                        severity: diagnostic::Severity::Error as i32,
+                        summary: "Failed to read resource".to_string(),
+                        detail: format!("{:#}", e),
+                        attribute: None,
+                    }],
+                    private: vec![],
+                    deferred: None,
+                }))
+            }
             Err(_e) => {
                 // Resource not found - return null state
                 Ok(Response::new(read_resource::Response {
@@ -222,6 +349,15 @@ impl Provider for InfraSimProvider {
         let req = request.into_inner();
         debug!("PlanResourceChange called for {}", req.type_name);
 
+        // Re-check against the configured daemon; warnings were already
+        // reported by ValidateResourceConfig
+        let diagnostics = self
+            .host_diagnostics(&req.type_name, req.proposed_new_state.as_ref())
+            .await
+            .into_iter()
+            .filter(|d| d.severity == diagnostic::Severity::Error as i32)
+            .collect();
+
         // For planning, we generally return the proposed new state
         let proposed = req.proposed_new_state.clone();
 
@@ -229,7 +365,7 @@ impl Provider for InfraSimProvider {
             planned_state: proposed,
             requires_replace: vec![],
             planned_private: vec![],
-            diagnostics: vec![],
+            diagnostics,
             legacy_type_system: false,
             deferred: None,
         }))
@@ -253,39 +389,70 @@ impl Provider for InfraSimProvider {
         let result = match (prior_state.as_ref(), planned_state.as_ref()) {
             // Create
             (None, Some(planned)) | (Some(LocalDynamicValue::Null), Some(planned)) => {
-                match req.type_name.as_str() {
+                let timeout = operation_timeout(Some(planned), "create");
+                with_timeout("create", timeout, async { Some(match req.type_name.as_str() {
                     "infrasim_network" => NetworkResource::create(&mut client, planned).await,
                     "infrasim_vm" => VmResource::create(&mut client, planned).await,
+                    "infrasim_vm_clone" => VmCloneResource::create(&mut client, planned).await,
                     "infrasim_volume" => VolumeResource::create(&mut client, planned).await,
                     "infrasim_snapshot" => SnapshotResource::create(&mut client, planned).await,
-                    _ => return Err(Status::not_found(format!("Unknown resource type: {}", req.type_name))),
-                }
+                    "infrasim_port_forward" => PortForwardResource::create(&mut client, planned).await,
+                    "infrasim_console" => ConsoleResource::create(&mut client, planned).await,
+                    "infrasim_router" => RouterResource::create(&mut client, planned).await,
+                    "infrasim_device" => DeviceResource::create(&mut client, planned).await,
+                    "infrasim_qos_profile" => QosProfileResource::create(&mut client, planned).await,
+                    "infrasim_snapshot_policy" => SnapshotPolicyResource::create(&mut client, planned).await,
+                    "infrasim_secret" => SecretResource::create(&mut client, planned).await,
+                    _ => return None,
+                }) })
+                .await
+                .map(|result| result.map(|state| with_timeouts(state, planned)))
             }
             // Delete
             (Some(prior), None) | (Some(prior), Some(LocalDynamicValue::Null)) => {
-                let delete_result = match req.type_name.as_str() {
+                let timeout = operation_timeout(Some(prior), "delete");
+                with_timeout("delete", timeout, async { Some(match req.type_name.as_str() {
                     "infrasim_network" => NetworkResource::delete(&mut client, prior).await,
                     "infrasim_vm" => VmResource::delete(&mut client, prior).await,
+                    "infrasim_vm_clone" => VmCloneResource::delete(&mut client, prior).await,
                     "infrasim_volume" => VolumeResource::delete(&mut client, prior).await,
                     "infrasim_snapshot" => SnapshotResource::delete(&mut client, prior).await,
-                    _ => return Err(Status::not_found(format!("Unknown resource type: {}", req.type_name))),
-                };
-                
-                delete_result.map(|_| LocalDynamicValue::Null)
+                    "infrasim_port_forward" => PortForwardResource::delete(&mut client, prior).await,
+                    "infrasim_console" => ConsoleResource::delete(&mut client, prior).await,
+                    "infrasim_router" => RouterResource::delete(&mut client, prior).await,
+                    "infrasim_device" => DeviceResource::delete(&mut client, prior).await,
+                    "infrasim_qos_profile" => QosProfileResource::delete(&mut client, prior).await,
+                    "infrasim_snapshot_policy" => SnapshotPolicyResource::delete(&mut client, prior).await,
+                    "infrasim_secret" => SecretResource::delete(&mut client, prior).await,
+                    _ => return None,
+                }.map(|_| LocalDynamicValue::Null)) })
+                .await
             }
             // Update
             (Some(prior), Some(planned)) => {
-                match req.type_name.as_str() {
+                let timeout = operation_timeout(Some(planned), "update");
+                with_timeout("update", timeout, async { Some(match req.type_name.as_str() {
                     "infrasim_network" => NetworkResource::update(&mut client, prior, planned).await,
                     "infrasim_vm" => VmResource::update(&mut client, prior, planned).await,
+                    "infrasim_vm_clone" => VmCloneResource::update(&mut client, prior, planned).await,
                     "infrasim_volume" => VolumeResource::update(&mut client, prior, planned).await,
                     "infrasim_snapshot" => SnapshotResource::update(&mut client, prior, planned).await,
-                    _ => return Err(Status::not_found(format!("Unknown resource type: {}", req.type_name))),
-                }
+                    "infrasim_port_forward" => PortForwardResource::update(&mut client, prior, planned).await,
+                    "infrasim_console" => ConsoleResource::update(&mut client, prior, planned).await,
+                    "infrasim_router" => RouterResource::update(&mut client, prior, planned).await,
+                    "infrasim_device" => DeviceResource::update(&mut client, prior, planned).await,
+                    "infrasim_qos_profile" => QosProfileResource::update(&mut client, prior, planned).await,
+                    "infrasim_snapshot_policy" => SnapshotPolicyResource::update(&mut client, prior, planned).await,
+                    "infrasim_secret" => SecretResource::update(&mut client, prior, planned).await,
+                    _ => return None,
+                }) })
+                .await
+                .map(|result| result.map(|state| with_timeouts(state, planned)))
             }
             // No change
-            (None, None) => Ok(LocalDynamicValue::Null),
-        };
+            (None, None) => Some(Ok(LocalDynamicValue::Null)),
+        }
+        .ok_or_else(|| Status::not_found(format!("Unknown resource type: {}", req.type_name)))?;
 
         match result {
             Ok(new_state) => {
@@ -327,17 +494,20 @@ impl Provider for InfraSimProvider {
 
         let mut client = self.get_client().await?;
 
-        // Create a minimal state with just the ID
-        let initial_state = crate::state::make_state(vec![
-            ("id", crate::state::string_value(&req.id)),
-        ]);
-
-        // Read the actual state
+        // Accept either the daemon ID or the resource name
         let state = match req.type_name.as_str() {
-            "infrasim_network" => NetworkResource::read(&mut client, &initial_state).await,
-            "infrasim_vm" => VmResource::read(&mut client, &initial_state).await,
-            "infrasim_volume" => VolumeResource::read(&mut client, &initial_state).await,
-            "infrasim_snapshot" => SnapshotResource::read(&mut client, &initial_state).await,
+            "infrasim_network" => NetworkResource::import(&mut client, &req.id).await,
+            "infrasim_vm" => VmResource::import(&mut client, &req.id).await,
+            "infrasim_vm_clone" => VmCloneResource::import(&mut client, &req.id).await,
+            "infrasim_volume" => VolumeResource::import(&mut client, &req.id).await,
+            "infrasim_snapshot" => SnapshotResource::import(&mut client, &req.id).await,
+            "infrasim_port_forward" => PortForwardResource::import(&mut client, &req.id).await,
+            "infrasim_console" => ConsoleResource::import(&mut client, &req.id).await,
+            "infrasim_router" => RouterResource::import(&mut client, &req.id).await,
+            "infrasim_device" => DeviceResource::import(&mut client, &req.id).await,
+            "infrasim_qos_profile" => QosProfileResource::import(&mut client, &req.id).await,
+            "infrasim_snapshot_policy" => SnapshotPolicyResource::import(&mut client, &req.id).await,
+            "infrasim_secret" => SecretResource::import(&mut client, &req.id).await,
             _ => return Err(Status::not_found(format!("Unknown resource type: {}", req.type_name))),
         };
 
@@ -365,7 +535,7 @@ impl Provider for InfraSimProvider {
                     diagnostics: vec![Diagnostic {
                         severity: diagnostic::Severity::Error as i32,
                         summary: "Failed to import resource".to_string(),
-                        detail: e.to_string(),
+                        detail: format!("{:#}", e),
                         attribute: None,
                     }],
                     deferred: None,
@@ -386,13 +556,49 @@ impl Provider for InfraSimProvider {
 
     async fn read_data_source(
         &self,
-        _request: Request<read_data_source::Request>,
+        request: Request<read_data_source::Request>,
     ) -> Result<Response<read_data_source::Response>, Status> {
-        Ok(Response::new(read_data_source::Response {
-            state: None,
-            diagnostics: vec![],
-            deferred: None,
-        }))
+        let req = request.into_inner();
+        info!("ReadDataSource called for {}", req.type_name);
+
+        let mut client = self.get_client().await?;
+
+        let config = req.config.as_ref()
+            .and_then(|c| decode_dynamic_value(&c.msgpack).ok())
+            .unwrap_or_default();
+
+        let state = match req.type_name.as_str() {
+            "infrasim_attestation" => AttestationDataSource::read(&mut client, &config).await,
+            _ => return Err(Status::not_found(format!("Unknown data source type: {}", req.type_name))),
+        };
+
+        match state {
+            Ok(s) => {
+                let encoded = encode_dynamic_value(&s)
+                    .map_err(|e| Status::internal(format!("Failed to encode state: {}", e)))?;
+
+                Ok(Response::new(read_data_source::Response {
+                    state: Some(DynamicValue {
+                        msgpack: encoded,
+                        json: vec![],
+                    }),
+                    diagnostics: vec![],
+                    deferred: None,
+                }))
+            }
+            Err(e) => {
+                Ok(Response::new(read_data_source::Response {
+                    state: None,
+                    diagnostics: vec![Diagnostic {
+                        severity: diagnostic::Severity::Error as i32,
+                        summary: "Failed to read data source".to_string(),
+                        detail: format!("{:#}", e),
+                        attribute: None,
+                    }],
+                    deferred: None,
+                }))
+            }
+        }
     }
 
     async fn get_functions(
@@ -422,3 +628,47 @@ impl Provider for InfraSimProvider {
         }))
     }
 }
+
+/// Time `operation` may take on a resource: its `timeouts` block entry, or
+/// DEFAULT_OPERATION_TIMEOUT (invalid entries are rejected at validation)
+fn operation_timeout(config: Option<&LocalDynamicValue>, operation: &str) -> Duration {
+    config
+        .and_then(|c| c.get("timeouts"))
+        .and_then(|t| get_optional_string_attr(t, operation))
+        .and_then(|t| parse_interval(&t).ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_OPERATION_TIMEOUT)
+}
+
+/// Run a resource operation, failing it once `timeout` has passed. Yields
+/// None for unknown resource types.
+async fn with_timeout(
+    operation: &str,
+    timeout: Duration,
+    fut: impl Future<Output = Option<anyhow::Result<LocalDynamicValue>>>,
+) -> Option<anyhow::Result<LocalDynamicValue>> {
+    match tokio::time::timeout(timeout, fut).await {
+        Ok(result) => result,
+        Err(elapsed) => Some(Err(anyhow::Error::new(elapsed).context(format!(
+            "{} timed out after {}",
+            operation,
+            format_interval(timeout.as_secs())
+        )))),
+    }
+}
+
+/// Whether an operation failed because the daemon stayed unreachable or
+/// busy, rather than because of the resource itself
+fn is_unavailable(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<tokio::time::error::Elapsed>().is_some()
+        || e.downcast_ref::<Status>().is_some_and(crate::client::is_transient)
+}
+
+/// Carry the `timeouts` block over from the config, since Terraform expects
+/// the new state to match the plan
+fn with_timeouts(mut state: LocalDynamicValue, from: &LocalDynamicValue) -> LocalDynamicValue {
+    if let LocalDynamicValue::Map(map) = &mut state {
+        map.insert("timeouts".to_string(), from.get("timeouts").cloned().unwrap_or_default());
+    }
+    state
+}
@@ -0,0 +1,95 @@
+//! Console Resource Implementation
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{
+    DynamicValue, get_string_attr, get_int_attr, get_bool_attr, get_optional_string_attr,
+    make_state, string_value, int_value, bool_value,
+};
+use crate::generated::infrasim::{Console, ConsoleSpec};
+use super::Resource;
+
+pub struct ConsoleResource;
+
+#[async_trait::async_trait]
+impl Resource for ConsoleResource {
+    fn type_name() -> &'static str {
+        "infrasim_console"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let vm_id = get_string_attr(config, "vm_id");
+        // Same naming as consoles the web UI creates for appliances
+        let name = get_optional_string_attr(config, "name")
+            .unwrap_or_else(|| format!("console-{}", vm_id));
+
+        let spec = ConsoleSpec {
+            vm_id,
+            enable_vnc: get_bool_attr(config, "enable_vnc", true),
+            vnc_port: get_int_attr(config, "vnc_port", 0) as i32,
+            enable_web: get_bool_attr(config, "enable_web", true),
+            web_port: get_int_attr(config, "web_port", 0) as i32,
+            auth_token: get_optional_string_attr(config, "auth_token")
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        };
+
+        let console = client.create_console(&name, spec).await?;
+        console_to_state(&console)
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        let id = get_string_attr(state, "id");
+        let console = client.get_console(&id).await?;
+        console_to_state(&console)
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        let changed = ["name", "vm_id", "auth_token"]
+            .iter()
+            .any(|k| get_optional_string_attr(config, k).is_some_and(|v| v != get_string_attr(state, k)))
+            || ["enable_vnc", "enable_web"]
+                .iter()
+                .any(|k| get_bool_attr(config, k, true) != get_bool_attr(state, k, true))
+            || ["vnc_port", "web_port"]
+                .iter()
+                .any(|k| get_int_attr(config, k, 0) != 0 && get_int_attr(config, k, 0) != get_int_attr(state, k, 0));
+
+        if !changed {
+            return Self::read(client, state).await;
+        }
+
+        // Consoles are immutable in the daemon - replace it
+        Self::delete(client, state).await?;
+        Self::create(client, config).await
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        let id = get_string_attr(state, "id");
+        client.delete_console(&id).await
+    }
+
+    async fn find_id_by_name(_client: &mut DaemonClient, _name: &str) -> Result<Option<String>> {
+        // The daemon has no console listing; consoles import by ID only
+        Ok(None)
+    }
+}
+
+fn console_to_state(console: &Console) -> Result<DynamicValue> {
+    let meta = console.meta.clone().unwrap_or_default();
+    let spec = console.spec.clone().unwrap_or_default();
+    let status = console.status.clone().unwrap_or_default();
+
+    Ok(make_state(vec![
+        ("id", string_value(&meta.id)),
+        ("name", string_value(&meta.name)),
+        ("vm_id", string_value(&spec.vm_id)),
+        ("enable_vnc", bool_value(spec.enable_vnc)),
+        ("vnc_port", int_value(spec.vnc_port as i64)),
+        ("enable_web", bool_value(spec.enable_web)),
+        ("web_port", int_value(spec.web_port as i64)),
+        ("auth_token", string_value(&spec.auth_token)),
+        ("active", bool_value(status.active)),
+        ("vnc_host", string_value(&status.vnc_host)),
+        ("web_url", string_value(&status.web_url)),
+    ]))
+}
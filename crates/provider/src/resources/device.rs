@@ -0,0 +1,105 @@
+//! Device Resource Implementation
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{
+    DynamicValue, get_string_attr, get_optional_string_attr,
+    make_state, string_value, bool_value,
+};
+use crate::generated::infrasim::{DeviceBus, DeviceSpec};
+use super::Resource;
+
+pub struct DeviceResource;
+
+#[async_trait::async_trait]
+impl Resource for DeviceResource {
+    fn type_name() -> &'static str {
+        "infrasim_device"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let name = get_string_attr(config, "name");
+
+        let bus = match get_string_attr(config, "bus").to_lowercase().as_str() {
+            "pci" => DeviceBus::Pci as i32,
+            _ => DeviceBus::Usb as i32,
+        };
+
+        let spec = DeviceSpec {
+            vm_id: get_string_attr(config, "vm_id"),
+            bus,
+            vendor_id: parse_usb_id(config, "vendor_id")?,
+            product_id: parse_usb_id(config, "product_id")?,
+            host_address: get_string_attr(config, "host_address"),
+        };
+
+        let device = client.create_device(&name, spec).await?;
+        device_to_state(&device)
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        let id = get_string_attr(state, "id");
+        let device = client.get_device(&id).await?;
+        device_to_state(&device)
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        let changed = ["vm_id", "bus", "vendor_id", "product_id", "host_address"]
+            .iter()
+            .any(|k| get_optional_string_attr(config, k).is_some_and(|v| v != get_string_attr(state, k)));
+
+        if !changed {
+            return Self::read(client, state).await;
+        }
+
+        // Devices are immutable in the daemon - detach and attach again
+        Self::delete(client, state).await?;
+        Self::create(client, config).await
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        let id = get_string_attr(state, "id");
+        client.delete_device(&id).await
+    }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_devices().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
+}
+
+/// Parse a hex USB ID such as `046d` or `0x046d` (0 when unset)
+fn parse_usb_id(config: &DynamicValue, key: &str) -> Result<u32> {
+    let Some(value) = get_optional_string_attr(config, key).filter(|v| !v.is_empty()) else {
+        return Ok(0);
+    };
+    u16::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map(u32::from)
+        .map_err(|_| anyhow::anyhow!("{} must be a 4-digit hex ID, got '{}'", key, value))
+}
+
+fn device_to_state(device: &crate::generated::infrasim::Device) -> Result<DynamicValue> {
+    let meta = device.meta.clone().unwrap_or_default();
+    let spec = device.spec.clone().unwrap_or_default();
+    let status = device.status.clone().unwrap_or_default();
+
+    let bus = match DeviceBus::try_from(spec.bus) {
+        Ok(DeviceBus::Pci) => "pci",
+        _ => "usb",
+    };
+    let usb_id = |id: u32| if id == 0 { String::new() } else { format!("{:04x}", id) };
+
+    Ok(make_state(vec![
+        ("id", string_value(&meta.id)),
+        ("name", string_value(&meta.name)),
+        ("vm_id", string_value(&spec.vm_id)),
+        ("bus", string_value(bus)),
+        ("vendor_id", string_value(usb_id(spec.vendor_id))),
+        ("product_id", string_value(usb_id(spec.product_id))),
+        ("host_address", string_value(&spec.host_address)),
+        ("attached", bool_value(status.attached)),
+    ]))
+}
@@ -4,12 +4,20 @@
 
 pub mod network;
 pub mod vm;
+pub mod vm_clone;
 pub mod volume;
 pub mod snapshot;
+pub mod port_forward;
+pub mod console;
+pub mod router;
+pub mod device;
+pub mod qos_profile;
+pub mod snapshot_policy;
+pub mod secret;
 
 use anyhow::Result;
 use crate::client::DaemonClient;
-use crate::state::DynamicValue;
+use crate::state::{DynamicValue, make_state, string_value};
 
 /// Trait for resource operations
 #[async_trait::async_trait]
@@ -28,4 +36,26 @@ pub trait Resource {
 
     /// Delete a resource
     async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()>;
+
+    /// Find the ID of a resource by name
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>>;
+
+    /// Import an existing resource by ID, falling back to a lookup by name
+    async fn import(client: &mut DaemonClient, id: &str) -> Result<DynamicValue> {
+        let by_id = make_state(vec![("id", string_value(id))]);
+        match Self::read(client, &by_id).await {
+            Ok(state) => Ok(state),
+            Err(e) => match Self::find_id_by_name(client, id).await? {
+                Some(found) => {
+                    let by_name = make_state(vec![("id", string_value(found))]);
+                    Self::read(client, &by_name).await
+                }
+                None => Err(e.context(format!(
+                    "No {} found with ID or name '{}'",
+                    Self::type_name(),
+                    id
+                ))),
+            },
+        }
+    }
 }
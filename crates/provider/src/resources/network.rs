@@ -54,6 +54,14 @@ impl Resource for NetworkResource {
         let id = get_string_attr(state, "id");
         client.delete_network(&id).await
     }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_networks().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
 }
 
 fn network_to_state(net: &crate::generated::infrasim::Network) -> Result<DynamicValue> {
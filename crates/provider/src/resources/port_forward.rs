@@ -0,0 +1,100 @@
+//! Port Forward Resource Implementation
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{
+    DynamicValue, get_string_attr, get_int_attr, get_optional_string_attr,
+    make_state, string_value, int_value, bool_value,
+};
+use crate::generated::infrasim::{PortForwardSpec, PortProtocol};
+use super::Resource;
+
+pub struct PortForwardResource;
+
+#[async_trait::async_trait]
+impl Resource for PortForwardResource {
+    fn type_name() -> &'static str {
+        "infrasim_port_forward"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let name = get_string_attr(config, "name");
+
+        let protocol = match get_string_attr(config, "protocol").to_lowercase().as_str() {
+            "udp" => PortProtocol::Udp as i32,
+            _ => PortProtocol::Tcp as i32,
+        };
+
+        let spec = PortForwardSpec {
+            vm_id: get_string_attr(config, "vm_id"),
+            network_id: get_string_attr(config, "network_id"),
+            protocol,
+            host_addr: get_optional_string_attr(config, "host_addr")
+                .unwrap_or_else(|| "127.0.0.1".to_string()),
+            host_port: get_int_attr(config, "host_port", 0) as u32,
+            guest_port: get_int_attr(config, "guest_port", 0) as u32,
+        };
+
+        let forward = client.create_port_forward(&name, spec).await?;
+        port_forward_to_state(&forward)
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        let id = get_string_attr(state, "id");
+        let forward = client.get_port_forward(&id).await?;
+        port_forward_to_state(&forward)
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        let changed = ["vm_id", "network_id", "protocol", "host_addr"]
+            .iter()
+            .any(|k| get_optional_string_attr(config, k).is_some_and(|v| v != get_string_attr(state, k)))
+            || ["host_port", "guest_port"]
+                .iter()
+                .any(|k| get_int_attr(config, k, 0) != get_int_attr(state, k, 0));
+
+        if !changed {
+            return Self::read(client, state).await;
+        }
+
+        // Forwards are immutable in the daemon - replace the rule
+        Self::delete(client, state).await?;
+        Self::create(client, config).await
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        let id = get_string_attr(state, "id");
+        client.delete_port_forward(&id).await
+    }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_port_forwards().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
+}
+
+fn port_forward_to_state(forward: &crate::generated::infrasim::PortForward) -> Result<DynamicValue> {
+    let meta = forward.meta.clone().unwrap_or_default();
+    let spec = forward.spec.clone().unwrap_or_default();
+    let status = forward.status.clone().unwrap_or_default();
+
+    let protocol = match PortProtocol::try_from(spec.protocol) {
+        Ok(PortProtocol::Udp) => "udp",
+        _ => "tcp",
+    };
+
+    Ok(make_state(vec![
+        ("id", string_value(&meta.id)),
+        ("name", string_value(&meta.name)),
+        ("vm_id", string_value(&spec.vm_id)),
+        ("network_id", string_value(&spec.network_id)),
+        ("protocol", string_value(protocol)),
+        ("host_addr", string_value(&spec.host_addr)),
+        ("host_port", int_value(spec.host_port as i64)),
+        ("guest_port", int_value(spec.guest_port as i64)),
+        ("active", bool_value(status.active)),
+    ]))
+}
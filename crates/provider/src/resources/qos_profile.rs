@@ -0,0 +1,97 @@
+//! QoS Profile Resource Implementation
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{
+    DynamicValue, get_string_attr, get_int_attr, get_float_attr,
+    make_state, string_value, int_value, float_value,
+};
+use crate::generated::infrasim::QoSProfileSpec;
+use super::Resource;
+
+/// Attributes that make up the profile's spec
+const SPEC_ATTRS: &[&str] = &[
+    "latency_ms",
+    "jitter_ms",
+    "rate_limit_mbps",
+    "burst_size_kb",
+    "packet_padding_bytes",
+];
+
+pub struct QosProfileResource;
+
+#[async_trait::async_trait]
+impl Resource for QosProfileResource {
+    fn type_name() -> &'static str {
+        "infrasim_qos_profile"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let name = get_string_attr(config, "name");
+        let burst_size_kb = get_int_attr(config, "burst_size_kb", 0) as i32;
+
+        let spec = QoSProfileSpec {
+            latency_ms: get_int_attr(config, "latency_ms", 0) as i32,
+            jitter_ms: get_int_attr(config, "jitter_ms", 0) as i32,
+            loss_percent: get_float_attr(config, "loss_percent", 0.0) as f32,
+            rate_limit_mbps: get_int_attr(config, "rate_limit_mbps", 0) as i32,
+            packet_padding_bytes: get_int_attr(config, "packet_padding_bytes", 0) as i32,
+            burst_shaping: burst_size_kb > 0,
+            burst_size_kb,
+        };
+
+        let profile = client.create_qos_profile(&name, spec).await?;
+        qos_profile_to_state(&profile)
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        let id = get_string_attr(state, "id");
+        let profile = client.get_qos_profile(&id).await?;
+        qos_profile_to_state(&profile)
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        let changed = SPEC_ATTRS
+            .iter()
+            .any(|k| get_int_attr(config, k, 0) != get_int_attr(state, k, 0))
+            || get_float_attr(config, "loss_percent", 0.0) != get_float_attr(state, "loss_percent", 0.0);
+
+        if !changed {
+            return Self::read(client, state).await;
+        }
+
+        // Profiles are immutable in the daemon - replace it (the daemon
+        // refuses while VMs still use the old one)
+        Self::delete(client, state).await?;
+        Self::create(client, config).await
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        let id = get_string_attr(state, "id");
+        client.delete_qos_profile(&id).await
+    }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_qos_profiles().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
+}
+
+fn qos_profile_to_state(profile: &crate::generated::infrasim::QoSProfile) -> Result<DynamicValue> {
+    let meta = profile.meta.clone().unwrap_or_default();
+    let spec = profile.spec.clone().unwrap_or_default();
+
+    Ok(make_state(vec![
+        ("id", string_value(&meta.id)),
+        ("name", string_value(&meta.name)),
+        ("latency_ms", int_value(spec.latency_ms as i64)),
+        ("jitter_ms", int_value(spec.jitter_ms as i64)),
+        ("loss_percent", float_value(spec.loss_percent as f64)),
+        ("rate_limit_mbps", int_value(spec.rate_limit_mbps as i64)),
+        ("burst_size_kb", int_value(spec.burst_size_kb as i64)),
+        ("packet_padding_bytes", int_value(spec.packet_padding_bytes as i64)),
+    ]))
+}
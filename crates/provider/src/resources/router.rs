@@ -0,0 +1,152 @@
+//! Router Resource Implementation
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{
+    DynamicValue, get_string_attr, get_int_attr, get_optional_string_attr, get_string_list_attr,
+    get_block_list_attr, make_state, string_value, string_list_value, int_value, bool_value, null_value,
+};
+use crate::generated::infrasim::{
+    FirewallAction, RouterRule, Router, RouterSpec, RuleProtocol, StaticRoute,
+};
+use super::Resource;
+
+pub struct RouterResource;
+
+#[async_trait::async_trait]
+impl Resource for RouterResource {
+    fn type_name() -> &'static str {
+        "infrasim_router"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let name = get_string_attr(config, "name");
+        let router = client.create_router(&name, router_spec_from_config(config)).await?;
+        router_to_state(&router)
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        let id = get_string_attr(state, "id");
+        let router = client.get_router(&id).await?;
+        router_to_state(&router)
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        // Names are fixed at creation - a rename replaces the router
+        if get_string_attr(config, "name") != get_string_attr(state, "name") {
+            Self::delete(client, state).await?;
+            return Self::create(client, config).await;
+        }
+
+        let id = get_string_attr(state, "id");
+        let router = client.update_router(&id, router_spec_from_config(config)).await?;
+        router_to_state(&router)
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        let id = get_string_attr(state, "id");
+        client.delete_router(&id).await
+    }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_routers().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
+}
+
+fn router_spec_from_config(config: &DynamicValue) -> RouterSpec {
+    let routes = get_block_list_attr(config, "route").iter()
+        .map(|b| StaticRoute {
+            destination: get_string_attr(b, "destination"),
+            next_hop: get_string_attr(b, "next_hop"),
+        })
+        .collect();
+
+    let rules = get_block_list_attr(config, "firewall_rule").iter()
+        .map(|b| RouterRule {
+            action: parse_action(&get_string_attr(b, "action")) as i32,
+            protocol: match get_string_attr(b, "protocol").to_lowercase().as_str() {
+                "tcp" => RuleProtocol::Tcp,
+                "udp" => RuleProtocol::Udp,
+                "icmp" => RuleProtocol::Icmp,
+                _ => RuleProtocol::Any,
+            } as i32,
+            source: get_optional_string_attr(b, "source").unwrap_or_default(),
+            destination: get_optional_string_attr(b, "destination").unwrap_or_default(),
+            port: get_int_attr(b, "port", 0) as u32,
+        })
+        .collect();
+
+    RouterSpec {
+        network_ids: get_string_list_attr(config, "network_ids").unwrap_or_default(),
+        routes,
+        rules,
+        default_action: get_optional_string_attr(config, "default_action")
+            .map(|a| parse_action(&a))
+            .unwrap_or(FirewallAction::Allow) as i32,
+    }
+}
+
+fn parse_action(action: &str) -> FirewallAction {
+    match action.to_lowercase().as_str() {
+        "deny" => FirewallAction::Deny,
+        _ => FirewallAction::Allow,
+    }
+}
+
+fn action_name(action: i32) -> &'static str {
+    match FirewallAction::try_from(action) {
+        Ok(FirewallAction::Deny) => "deny",
+        _ => "allow",
+    }
+}
+
+fn optional_string(s: &str) -> DynamicValue {
+    if s.is_empty() { null_value() } else { string_value(s) }
+}
+
+fn router_to_state(router: &Router) -> Result<DynamicValue> {
+    let meta = router.meta.clone().unwrap_or_default();
+    let spec = router.spec.clone().unwrap_or_default();
+    let status = router.status.clone().unwrap_or_default();
+
+    let routes = spec.routes.iter()
+        .map(|r| make_state(vec![
+            ("destination", string_value(&r.destination)),
+            ("next_hop", string_value(&r.next_hop)),
+        ]))
+        .collect();
+
+    let rules = spec.rules.iter()
+        .map(|r| {
+            let protocol = match RuleProtocol::try_from(r.protocol) {
+                Ok(RuleProtocol::Tcp) => "tcp",
+                Ok(RuleProtocol::Udp) => "udp",
+                Ok(RuleProtocol::Icmp) => "icmp",
+                _ => "any",
+            };
+            make_state(vec![
+                ("action", string_value(action_name(r.action))),
+                ("protocol", string_value(protocol)),
+                ("source", optional_string(&r.source)),
+                ("destination", optional_string(&r.destination)),
+                ("port", if r.port == 0 { null_value() } else { int_value(r.port as i64) }),
+            ])
+        })
+        .collect();
+
+    Ok(make_state(vec![
+        ("id", string_value(&meta.id)),
+        ("name", string_value(&meta.name)),
+        ("network_ids", string_list_value(&spec.network_ids)),
+        ("route", DynamicValue::List(routes)),
+        ("firewall_rule", DynamicValue::List(rules)),
+        ("default_action", string_value(action_name(spec.default_action))),
+        ("active", bool_value(status.active)),
+        ("interfaces", string_list_value(&status.interfaces)),
+        ("error_message", string_value(&status.error_message)),
+    ]))
+}
@@ -0,0 +1,68 @@
+//! Secret Resource Implementation
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{DynamicValue, get_string_attr, make_state, string_value, int_value};
+use crate::generated::infrasim::Secret;
+use super::Resource;
+
+pub struct SecretResource;
+
+#[async_trait::async_trait]
+impl Resource for SecretResource {
+    fn type_name() -> &'static str {
+        "infrasim_secret"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let name = get_string_attr(config, "name");
+        let value = get_string_attr(config, "value");
+
+        let secret = client.create_secret(&name, &value, false).await?;
+        Ok(secret_to_state(&secret, &value))
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        // Secrets are keyed by name, so the ID is the name
+        let id = get_string_attr(state, "id");
+        let response = client.get_secret(&id).await?;
+        let secret = response.secret.unwrap_or_default();
+        Ok(secret_to_state(&secret, &response.value))
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        let value = get_string_attr(config, "value");
+        if get_string_attr(config, "name") != get_string_attr(state, "name") {
+            Self::delete(client, state).await?;
+            return Self::create(client, config).await;
+        }
+        if value == get_string_attr(state, "value") {
+            return Self::read(client, state).await;
+        }
+
+        let secret = client.create_secret(&get_string_attr(state, "name"), &value, true).await?;
+        Ok(secret_to_state(&secret, &value))
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        let id = get_string_attr(state, "id");
+        client.delete_secret(&id).await
+    }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_secrets().await?
+            .into_iter()
+            .find(|s| s.name == name)
+            .map(|s| s.name))
+    }
+}
+
+fn secret_to_state(secret: &Secret, value: &str) -> DynamicValue {
+    make_state(vec![
+        ("id", string_value(&secret.name)),
+        ("name", string_value(&secret.name)),
+        ("value", string_value(value)),
+        ("created_at", int_value(secret.created_at)),
+        ("updated_at", int_value(secret.updated_at)),
+    ])
+}
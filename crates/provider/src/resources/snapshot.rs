@@ -32,26 +32,13 @@ impl Resource for SnapshotResource {
         };
 
         let snapshot = client.create_snapshot(&name, spec).await?;
-        let meta = snapshot.meta.unwrap_or_default();
-        let snap_spec = snapshot.spec.unwrap_or_default();
-        let status = snapshot.status.unwrap_or_default();
-
-        Ok(make_state(vec![
-            ("id", string_value(&meta.id)),
-            ("name", string_value(&meta.name)),
-            ("vm_id", string_value(&snap_spec.vm_id)),
-            ("include_memory", bool_value(snap_spec.include_memory)),
-            ("include_disk", bool_value(snap_spec.include_disk)),
-            ("description", string_value(&snap_spec.description)),
-            ("size_bytes", int_value(status.size_bytes)),
-            ("complete", bool_value(status.complete)),
-        ]))
+        snapshot_to_state(&snapshot)
     }
 
-    async fn read(_client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
-        // Snapshots don't have a direct read API, so we return the current state
-        // In a full implementation, we would have a GetSnapshot RPC
-        Ok(state.clone())
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        let id = get_string_attr(state, "id");
+        let snapshot = client.get_snapshot(&id).await?;
+        snapshot_to_state(&snapshot)
     }
 
     async fn update(_client: &mut DaemonClient, state: &DynamicValue, _config: &DynamicValue) -> Result<DynamicValue> {
@@ -63,4 +50,78 @@ impl Resource for SnapshotResource {
         let id = get_string_attr(state, "id");
         client.delete_snapshot(&id).await
     }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_snapshots().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
+}
+
+fn snapshot_to_state(snapshot: &crate::generated::infrasim::Snapshot) -> Result<DynamicValue> {
+    let meta = snapshot.meta.clone().unwrap_or_default();
+    let spec = snapshot.spec.clone().unwrap_or_default();
+    let status = snapshot.status.clone().unwrap_or_default();
+
+    Ok(make_state(vec![
+        ("id", string_value(&meta.id)),
+        ("name", string_value(&meta.name)),
+        ("vm_id", string_value(&spec.vm_id)),
+        ("include_memory", bool_value(spec.include_memory)),
+        ("include_disk", bool_value(spec.include_disk)),
+        ("description", string_value(&spec.description)),
+        ("size_bytes", int_value(status.size_bytes)),
+        ("parent_id", string_value(&status.parent_id)),
+        ("complete", bool_value(status.complete)),
+        ("created_at", int_value(meta.created_at)),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::infrasim::{ResourceMeta, Snapshot, SnapshotStatus};
+    use crate::schema::snapshot_schema;
+
+    #[test]
+    fn state_matches_schema() {
+        let snapshot = Snapshot {
+            meta: Some(ResourceMeta {
+                id: "snap-1".to_string(),
+                name: "before-upgrade".to_string(),
+                created_at: 1_700_000_000,
+                ..Default::default()
+            }),
+            spec: Some(SnapshotSpec {
+                vm_id: "vm-1".to_string(),
+                include_disk: true,
+                ..Default::default()
+            }),
+            status: Some(SnapshotStatus {
+                complete: true,
+                size_bytes: 4096,
+                ..Default::default()
+            }),
+        };
+        let state = snapshot_to_state(&snapshot).unwrap();
+        let schema = snapshot_schema().block.unwrap();
+
+        for (key, value) in state.as_map().unwrap() {
+            let attribute = schema
+                .attributes
+                .iter()
+                .find(|a| a.name == *key)
+                .unwrap_or_else(|| panic!("state attribute {} is not in the schema", key));
+            let expected = match value {
+                DynamicValue::String(_) => "string",
+                DynamicValue::Number(_) => "number",
+                DynamicValue::Bool(_) => "bool",
+                other => panic!("unexpected value for {}: {:?}", key, other),
+            };
+            let declared: String = serde_json::from_slice(&attribute.r#type).unwrap();
+            assert_eq!(declared, expected, "type of {}", key);
+        }
+    }
 }
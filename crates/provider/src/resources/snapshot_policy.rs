@@ -0,0 +1,102 @@
+//! Snapshot Policy Resource Implementation
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{
+    DynamicValue, get_string_attr, get_int_attr, get_bool_attr, get_optional_string_attr,
+    make_state, string_value, int_value, bool_value,
+};
+use crate::generated::infrasim::{SnapshotPolicy, SnapshotPolicySpec};
+use infrasim_common::types::{format_interval, parse_interval};
+use super::Resource;
+
+pub struct SnapshotPolicyResource;
+
+#[async_trait::async_trait]
+impl Resource for SnapshotPolicyResource {
+    fn type_name() -> &'static str {
+        "infrasim_snapshot_policy"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let name = get_string_attr(config, "name");
+        let spec = policy_spec(config)?;
+
+        let policy = client.create_snapshot_policy(&name, spec).await?;
+        snapshot_policy_to_state(&policy, config)
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        let id = get_string_attr(state, "id");
+        let policy = client.get_snapshot_policy(&id).await?;
+        snapshot_policy_to_state(&policy, state)
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        // A policy belongs to one VM - moving it means replacing it
+        let moved = ["vm_id", "name"]
+            .iter()
+            .any(|k| get_optional_string_attr(config, k).is_some_and(|v| v != get_string_attr(state, k)));
+        if moved {
+            Self::delete(client, state).await?;
+            return Self::create(client, config).await;
+        }
+
+        let id = get_string_attr(state, "id");
+        let policy = client.update_snapshot_policy(&id, policy_spec(config)?).await?;
+        snapshot_policy_to_state(&policy, config)
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        let id = get_string_attr(state, "id");
+        client.delete_snapshot_policy(&id).await
+    }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_snapshot_policies().await?
+            .into_iter()
+            .filter_map(|p| p.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
+}
+
+fn policy_spec(config: &DynamicValue) -> Result<SnapshotPolicySpec> {
+    let interval = parse_interval(&get_string_attr(config, "every"))?;
+
+    Ok(SnapshotPolicySpec {
+        vm_id: get_string_attr(config, "vm_id"),
+        vm_selector: Default::default(),
+        interval_seconds: interval as i64,
+        keep: get_int_attr(config, "keep", 10) as u32,
+        include_memory: get_bool_attr(config, "include_memory", false),
+        paused: get_bool_attr(config, "paused", false),
+    })
+}
+
+/// Build resource state, keeping the configured spelling of `every` (e.g. "360m")
+/// when it still matches the daemon's interval
+fn snapshot_policy_to_state(policy: &SnapshotPolicy, prior: &DynamicValue) -> Result<DynamicValue> {
+    let meta = policy.meta.clone().unwrap_or_default();
+    let spec = policy.spec.clone().unwrap_or_default();
+    let status = policy.status.clone().unwrap_or_default();
+
+    let interval = spec.interval_seconds as u64;
+    let every = get_optional_string_attr(prior, "every")
+        .filter(|e| parse_interval(e).ok() == Some(interval))
+        .unwrap_or_else(|| format_interval(interval));
+
+    Ok(make_state(vec![
+        ("id", string_value(&meta.id)),
+        ("name", string_value(&meta.name)),
+        ("vm_id", string_value(&spec.vm_id)),
+        ("every", string_value(every)),
+        ("keep", int_value(spec.keep as i64)),
+        ("include_memory", bool_value(spec.include_memory)),
+        ("paused", bool_value(spec.paused)),
+        ("last_run_at", int_value(status.last_run_at)),
+        ("next_run_at", int_value(status.next_run_at)),
+        ("retained", int_value(status.retained as i64)),
+        ("last_error", string_value(&status.last_error)),
+    ]))
+}
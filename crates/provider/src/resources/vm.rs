@@ -3,10 +3,12 @@
 use anyhow::Result;
 use crate::client::DaemonClient;
 use crate::state::{
-    DynamicValue, get_string_attr, get_int_attr, get_bool_attr,
-    make_state, string_value, int_value, bool_value,
+    DynamicValue, get_string_attr, get_int_attr, get_bool_attr, get_string_list_attr,
+    get_block_list_attr, get_optional_string_attr, make_state, string_value, int_value, bool_value, string_list_value, null_value,
+};
+use crate::generated::infrasim::{
+    NetworkAttachment, SharedFolder, SharedFolderCache, SharedFolderProtocol, StopMode, VmSpec, VmState,
 };
-use crate::generated::infrasim::{VmSpec, VmState};
 use super::Resource;
 
 pub struct VmResource;
@@ -25,34 +27,138 @@ impl Resource for VmResource {
             machine: get_string_attr(config, "machine"),
             cpu_cores: get_int_attr(config, "cpu_cores", 2) as i32,
             memory_mb: get_int_attr(config, "memory_mb", 2048),
-            volume_ids: vec![],
+            volume_ids: get_string_list_attr(config, "volume_ids").unwrap_or_default(),
             network_ids: vec![],
             qos_profile_id: get_string_attr(config, "qos_profile_id"),
             enable_tpm: get_bool_attr(config, "enable_tpm", false),
             boot_disk_id: get_string_attr(config, "boot_disk_id"),
             extra_args: Default::default(),
             compatibility_mode: false,
+            network_interfaces: vec![],
+            shared_folders: vec![],
+            priority: get_int_attr(config, "priority", 0) as i32,
         };
+        let spec = with_shared_folders(with_network_interfaces(spec, config), config);
 
         let vm = client.create_vm(&name, spec).await?;
-        vm_to_state(&vm)
+        // Only GetVM reports the NICs
+        let vm = client.get_vm(&vm.meta.unwrap_or_default().id).await?;
+        Ok(with_stop_settings(vm_to_state(&vm)?, config))
     }
 
     async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
         let id = get_string_attr(state, "id");
         let vm = client.get_vm(&id).await?;
-        vm_to_state(&vm)
+        Ok(with_stop_settings(vm_to_state(&vm)?, state))
     }
 
-    async fn update(client: &mut DaemonClient, state: &DynamicValue, _config: &DynamicValue) -> Result<DynamicValue> {
-        // VMs are currently immutable - just read the current state
-        Self::read(client, state).await
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        // CPU, memory and volumes are hot-plugged by the daemon; no replacement needed
+        let id = get_string_attr(state, "id");
+        let vm = client.get_vm(&id).await?;
+        let mut spec = vm.spec.unwrap_or_default();
+
+        spec.cpu_cores = get_int_attr(config, "cpu_cores", spec.cpu_cores as i64) as i32;
+        spec.memory_mb = get_int_attr(config, "memory_mb", spec.memory_mb);
+        if let Some(volume_ids) = get_string_list_attr(config, "volume_ids") {
+            spec.volume_ids = volume_ids;
+        }
+        // Profiles are switched in place; removing the attribute detaches it
+        spec.qos_profile_id = get_string_attr(config, "qos_profile_id");
+        spec.priority = get_int_attr(config, "priority", spec.priority as i64) as i32;
+        // NIC and shared folder changes take effect when the VM next starts
+        let spec = with_shared_folders(with_network_interfaces(spec, config), config);
+
+        client.update_vm(&id, spec).await?;
+        let vm = client.get_vm(&id).await?;
+        Ok(with_stop_settings(vm_to_state(&vm)?, config))
     }
 
     async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
         let id = get_string_attr(state, "id");
+
+        // Deleting a running VM kills QEMU; shut the guest down first so it
+        // can flush its filesystems
+        let vm = client.get_vm(&id).await?;
+        let running = matches!(
+            VmState::try_from(vm.status.unwrap_or_default().state),
+            Ok(VmState::Running) | Ok(VmState::Paused)
+        );
+        if running {
+            let mode = if get_string_attr(state, "stop_mode") == "hard" {
+                StopMode::Hard
+            } else {
+                StopMode::Acpi
+            };
+            let timeout = get_int_attr(state, "stop_timeout", 0).max(0) as u32;
+            client.stop_vm(&id, mode, timeout).await?;
+        }
+
         client.delete_vm(&id).await
     }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_vms().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
+}
+
+/// Attach the networks of the `network_interface` blocks, pinning the MACs
+/// and IPs they set
+fn with_network_interfaces(mut spec: VmSpec, config: &DynamicValue) -> VmSpec {
+    let blocks = get_block_list_attr(config, "network_interface");
+    spec.network_ids = blocks.iter().map(|b| get_string_attr(b, "network_id")).collect();
+    spec.network_interfaces = blocks
+        .iter()
+        .map(|b| NetworkAttachment {
+            network_id: get_string_attr(b, "network_id"),
+            mac_address: get_optional_string_attr(b, "mac").unwrap_or_default(),
+            ip_address: get_optional_string_attr(b, "ip").unwrap_or_default(),
+        })
+        .filter(|a| !a.mac_address.is_empty() || !a.ip_address.is_empty())
+        .collect();
+    spec
+}
+
+/// Share the host directories of the `shared_folder` blocks
+fn with_shared_folders(mut spec: VmSpec, config: &DynamicValue) -> VmSpec {
+    spec.shared_folders = get_block_list_attr(config, "shared_folder")
+        .iter()
+        .map(|b| {
+            let cache = match get_string_attr(b, "cache").as_str() {
+                "always" => SharedFolderCache::Always,
+                "never" => SharedFolderCache::Never,
+                _ => SharedFolderCache::Auto,
+            };
+            let protocol = match get_string_attr(b, "protocol").as_str() {
+                "virtiofs" => SharedFolderProtocol::Virtiofs,
+                "9p" => SharedFolderProtocol::Ninep,
+                _ => SharedFolderProtocol::Auto,
+            };
+            SharedFolder {
+                tag: get_optional_string_attr(b, "tag").unwrap_or_default(),
+                host_path: get_string_attr(b, "host_path"),
+                guest_path: get_string_attr(b, "guest_path"),
+                read_only: get_bool_attr(b, "read_only", false),
+                cache: cache as i32,
+                protocol: protocol as i32,
+            }
+        })
+        .collect();
+    spec
+}
+
+/// Copy the destroy-time stop settings, which the daemon doesn't store
+fn with_stop_settings(mut state: DynamicValue, from: &DynamicValue) -> DynamicValue {
+    if let DynamicValue::Map(map) = &mut state {
+        for key in ["stop_mode", "stop_timeout"] {
+            map.insert(key.to_string(), from.get(key).cloned().unwrap_or_default());
+        }
+    }
+    state
 }
 
 fn vm_to_state(vm: &crate::generated::infrasim::Vm) -> Result<DynamicValue> {
@@ -63,6 +169,50 @@ fn vm_to_state(vm: &crate::generated::infrasim::Vm) -> Result<DynamicValue> {
     let state_str = VmState::try_from(status.state)
         .map(|s| format!("{:?}", s))
         .unwrap_or_else(|_| "Unknown".to_string());
+
+    let optional = |s: &str| if s.is_empty() { null_value() } else { string_value(s) };
+    let ip_address = status.interfaces.iter()
+        .find(|nic| !nic.ip_address.is_empty())
+        .map_or(null_value(), |nic| string_value(&nic.ip_address));
+    // One block per attached network; the default NIC of a VM without
+    // networks only shows in ip_address
+    let interfaces = status.interfaces.iter()
+        .filter(|nic| !nic.network_id.is_empty())
+        .map(|nic| {
+            let pinned_ip = spec.network_interfaces.iter()
+                .find(|a| a.network_id == nic.network_id)
+                .map_or("", |a| a.ip_address.as_str());
+            make_state(vec![
+                ("network_id", string_value(&nic.network_id)),
+                ("mac", string_value(&nic.mac_address)),
+                ("ip", optional(pinned_ip)),
+                ("ip_address", optional(&nic.ip_address)),
+                ("source", optional(&nic.source)),
+            ])
+        })
+        .collect();
+    let shared_folders = spec.shared_folders.iter()
+        .map(|folder| {
+            let cache = match folder.cache() {
+                SharedFolderCache::Always => string_value("always"),
+                SharedFolderCache::Never => string_value("never"),
+                SharedFolderCache::Auto => null_value(),
+            };
+            let protocol = match folder.protocol() {
+                SharedFolderProtocol::Virtiofs => string_value("virtiofs"),
+                SharedFolderProtocol::Ninep => string_value("9p"),
+                SharedFolderProtocol::Auto => null_value(),
+            };
+            make_state(vec![
+                ("host_path", string_value(&folder.host_path)),
+                ("guest_path", string_value(&folder.guest_path)),
+                ("tag", string_value(&folder.tag)),
+                ("read_only", bool_value(folder.read_only)),
+                ("cache", cache),
+                ("protocol", protocol),
+            ])
+        })
+        .collect();
     
     Ok(make_state(vec![
         ("id", string_value(&meta.id)),
@@ -72,7 +222,20 @@ fn vm_to_state(vm: &crate::generated::infrasim::Vm) -> Result<DynamicValue> {
         ("cpu_cores", int_value(spec.cpu_cores as i64)),
         ("memory_mb", int_value(spec.memory_mb as i64)),
         ("boot_disk_id", string_value(&spec.boot_disk_id)),
+        ("volume_ids", string_list_value(&spec.volume_ids)),
         ("state", string_value(&state_str)),
+        ("ip_address", ip_address),
+        ("network_interface", DynamicValue::List(interfaces)),
+        ("shared_folder", DynamicValue::List(shared_folders)),
         ("enable_tpm", bool_value(spec.enable_tpm)),
+        ("priority", int_value(spec.priority as i64)),
+        (
+            "qos_profile_id",
+            if spec.qos_profile_id.is_empty() {
+                null_value()
+            } else {
+                string_value(&spec.qos_profile_id)
+            },
+        ),
     ]))
 }
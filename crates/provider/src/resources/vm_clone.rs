@@ -0,0 +1,114 @@
+//! VM Clone Resource Implementation
+//!
+//! Fans a template out into linked clones, which are started once created.
+
+use anyhow::Result;
+use crate::client::DaemonClient;
+use crate::state::{
+    DynamicValue, get_string_attr, get_int_attr, get_string_list_attr,
+    make_state, string_value, int_value, string_list_value,
+};
+use crate::generated::infrasim::Vm;
+use super::Resource;
+
+pub struct VmCloneResource;
+
+#[async_trait::async_trait]
+impl Resource for VmCloneResource {
+    fn type_name() -> &'static str {
+        "infrasim_vm_clone"
+    }
+
+    async fn create(client: &mut DaemonClient, config: &DynamicValue) -> Result<DynamicValue> {
+        let template = get_string_attr(config, "template");
+        let name = get_string_attr(config, "name");
+        let count = get_int_attr(config, "clone_count", 1).max(1) as i32;
+
+        let mut clones = Vec::new();
+        for vm in client.clone_vm(&template, &name, count).await? {
+            let id = vm.meta.unwrap_or_default().id;
+            clones.push(client.start_vm(&id).await?);
+        }
+        clones_to_state(&template, &name, count, &clones)
+    }
+
+    async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
+        // Clones deleted outside Terraform drop out, so the next plan adds them back
+        let mut clones = Vec::new();
+        for id in get_string_list_attr(state, "ids").unwrap_or_default() {
+            if let Ok(vm) = client.get_vm(&id).await {
+                clones.push(vm);
+            }
+        }
+        clones_to_state(
+            &get_string_attr(state, "template"),
+            &get_string_attr(state, "name"),
+            get_int_attr(state, "clone_count", 1) as i32,
+            &clones,
+        )
+    }
+
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        let unchanged = ["template", "name"]
+            .iter()
+            .all(|key| get_string_attr(state, key) == get_string_attr(config, key))
+            && get_int_attr(state, "clone_count", 1) == get_int_attr(config, "clone_count", 1)
+            && get_string_list_attr(state, "ids").unwrap_or_default().len()
+                == get_int_attr(state, "clone_count", 1) as usize;
+        if unchanged {
+            return Ok(state.clone());
+        }
+
+        // Clones are cheap, so any change replaces the whole set
+        Self::delete(client, state).await?;
+        Self::create(client, config).await
+    }
+
+    async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
+        for id in get_string_list_attr(state, "ids").unwrap_or_default() {
+            let Ok(vm) = client.get_vm(&id).await else {
+                continue;
+            };
+            client.delete_vm(&id).await?;
+
+            // The clone's overlays are of no use without it; shared volumes stay
+            let template = vm.status.unwrap_or_default().cloned_from;
+            let shared = client.get_vm(&template).await?.spec.unwrap_or_default();
+            let spec = vm.spec.unwrap_or_default();
+            let mut own: Vec<String> = spec.volume_ids;
+            own.push(spec.boot_disk_id);
+            own.sort();
+            own.dedup();
+            for volume_id in own {
+                if volume_id.is_empty()
+                    || volume_id == shared.boot_disk_id
+                    || shared.volume_ids.contains(&volume_id)
+                {
+                    continue;
+                }
+                client.delete_volume(&volume_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn find_id_by_name(_client: &mut DaemonClient, _name: &str) -> Result<Option<String>> {
+        // A set of clones has no single daemon resource to import
+        Ok(None)
+    }
+}
+
+fn clones_to_state(template: &str, name: &str, count: i32, clones: &[Vm]) -> Result<DynamicValue> {
+    let metas: Vec<_> = clones.iter().map(|vm| vm.meta.clone().unwrap_or_default()).collect();
+    let ids: Vec<String> = metas.iter().map(|m| m.id.clone()).collect();
+    let names: Vec<String> = metas.iter().map(|m| m.name.clone()).collect();
+
+    Ok(make_state(vec![
+        ("id", string_value(name)),
+        ("template", string_value(template)),
+        ("name", string_value(name)),
+        ("clone_count", int_value(count as i64)),
+        ("ids", string_list_value(&ids)),
+        ("names", string_list_value(&names)),
+    ]))
+}
@@ -3,10 +3,10 @@
 use anyhow::Result;
 use crate::client::DaemonClient;
 use crate::state::{
-    DynamicValue, get_string_attr, get_int_attr, get_bool_attr,
-    make_state, string_value, int_value, bool_value,
+    DynamicValue, get_string_attr, get_int_attr, get_bool_attr, get_optional_string_attr,
+    make_state, string_value, int_value, bool_value, null_value,
 };
-use crate::generated::infrasim::{VolumeSpec, VolumeKind};
+use crate::generated::infrasim::{VolumeSpec, VolumeKind, VolumeEncryption};
 use super::Resource;
 
 pub struct VolumeResource;
@@ -22,6 +22,7 @@ impl Resource for VolumeResource {
         
         let kind = match get_string_attr(config, "kind").as_str() {
             "weights" => VolumeKind::Weights as i32,
+            "device" => VolumeKind::Device as i32,
             _ => VolumeKind::Disk as i32,
         };
         
@@ -33,30 +34,53 @@ impl Resource for VolumeResource {
             size_bytes: get_int_attr(config, "size_bytes", 10 * 1024 * 1024 * 1024),
             format: get_string_attr(config, "format"),
             overlay: get_bool_attr(config, "overlay", false),
+            encryption: get_optional_string_attr(config, "encryption_key").map(|key_id| VolumeEncryption {
+                cipher: get_string_attr(config, "encryption_cipher"),
+                key_id,
+                secret: get_string_attr(config, "encryption_passphrase").into_bytes(),
+            }),
         };
 
         let volume = client.create_volume(&name, spec).await?;
-        volume_to_state(&volume)
+        volume_to_state(&volume, config)
     }
 
     async fn read(client: &mut DaemonClient, state: &DynamicValue) -> Result<DynamicValue> {
         let id = get_string_attr(state, "id");
         let volume = client.get_volume(&id).await?;
-        volume_to_state(&volume)
+        volume_to_state(&volume, state)
     }
 
-    async fn update(client: &mut DaemonClient, state: &DynamicValue, _config: &DynamicValue) -> Result<DynamicValue> {
-        // Volumes are currently immutable - just read the current state
-        Self::read(client, state).await
+    async fn update(client: &mut DaemonClient, state: &DynamicValue, config: &DynamicValue) -> Result<DynamicValue> {
+        // Size changes are applied in place (growing only); everything else is immutable
+        let id = get_string_attr(state, "id");
+        let volume = client.get_volume(&id).await?;
+        let current = volume.spec.as_ref().map(|s| s.size_bytes).unwrap_or_default();
+
+        let size_bytes = get_int_attr(config, "size_bytes", current);
+        if size_bytes != current {
+            let volume = client.resize_volume(&id, size_bytes).await?;
+            return volume_to_state(&volume, state);
+        }
+        volume_to_state(&volume, state)
     }
 
     async fn delete(client: &mut DaemonClient, state: &DynamicValue) -> Result<()> {
         let id = get_string_attr(state, "id");
         client.delete_volume(&id).await
     }
+
+    async fn find_id_by_name(client: &mut DaemonClient, name: &str) -> Result<Option<String>> {
+        Ok(client.list_volumes().await?
+            .into_iter()
+            .filter_map(|r| r.meta)
+            .find(|m| m.name == name)
+            .map(|m| m.id))
+    }
 }
 
-fn volume_to_state(vol: &crate::generated::infrasim::Volume) -> Result<DynamicValue> {
+/// Build resource state; the daemon never returns passphrases, so the configured one is kept
+fn volume_to_state(vol: &crate::generated::infrasim::Volume, prior: &DynamicValue) -> Result<DynamicValue> {
     let meta = vol.meta.clone().unwrap_or_default();
     let spec = vol.spec.clone().unwrap_or_default();
     let status = vol.status.clone().unwrap_or_default();
@@ -74,6 +98,12 @@ fn volume_to_state(vol: &crate::generated::infrasim::Volume) -> Result<DynamicVa
         ("size_bytes", int_value(spec.size_bytes)),
         ("read_only", bool_value(spec.read_only)),
         ("overlay", bool_value(spec.overlay)),
+        ("encryption_key", spec.encryption.as_ref().map_or_else(null_value, |e| string_value(&e.key_id))),
+        ("encryption_cipher", spec.encryption.as_ref().map_or_else(null_value, |e| string_value(&e.cipher))),
+        (
+            "encryption_passphrase",
+            get_optional_string_attr(prior, "encryption_passphrase").map_or_else(null_value, string_value),
+        ),
         ("ready", bool_value(status.ready)),
         ("digest", string_value(&status.digest)),
     ]))
@@ -111,7 +111,7 @@ pub fn network_schema() -> Schema {
                     deprecated: false,
                 },
             ],
-            block_types: vec![],
+            block_types: vec![timeouts_block()],
         }),
     }
 }
@@ -186,6 +186,18 @@ pub fn vm_schema() -> Schema {
                     sensitive: false,
                     deprecated: false,
                 },
+                schema::Attribute {
+                    name: "volume_ids".to_string(),
+                    r#type: serde_json::to_vec(&serde_json::json!(["list", "string"])).unwrap(),
+                    nested_type: None,
+                    description: "Additional volume IDs (hot-plugged while running)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
                 schema::Attribute {
                     name: "network_id".to_string(),
                     r#type: serde_json::to_vec(&"string").unwrap(),
@@ -250,7 +262,7 @@ pub fn vm_schema() -> Schema {
                     name: "ip_address".to_string(),
                     r#type: serde_json::to_vec(&"string").unwrap(),
                     nested_type: None,
-                    description: "IP address".to_string(),
+                    description: "IP address of the first NIC that has one, while the VM runs".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
                     required: false,
                     optional: false,
@@ -258,12 +270,35 @@ pub fn vm_schema() -> Schema {
                     sensitive: false,
                     deprecated: false,
                 },
-                // QoS attributes
                 schema::Attribute {
-                    name: "qos_latency_ms".to_string(),
+                    name: "qos_profile_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "infrasim_qos_profile shaping the VM's network traffic".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "priority".to_string(),
                     r#type: serde_json::to_vec(&"number").unwrap(),
                     nested_type: None,
-                    description: "Network latency in milliseconds".to_string(),
+                    description: "Pause priority under host memory pressure; lower priorities are paused first".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "stop_mode".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "How the VM is stopped before it is destroyed: acpi (default) or hard".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
                     required: false,
                     optional: true,
@@ -272,10 +307,10 @@ pub fn vm_schema() -> Schema {
                     deprecated: false,
                 },
                 schema::Attribute {
-                    name: "qos_jitter_ms".to_string(),
+                    name: "stop_timeout".to_string(),
                     r#type: serde_json::to_vec(&"number").unwrap(),
                     nested_type: None,
-                    description: "Network jitter in milliseconds".to_string(),
+                    description: "Seconds the guest gets to power off on destroy before QEMU is terminated (default: the daemon's stop timeout)".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
                     required: false,
                     optional: true,
@@ -283,32 +318,221 @@ pub fn vm_schema() -> Schema {
                     sensitive: false,
                     deprecated: false,
                 },
+                // Inline QoS attributes, superseded by infrasim_qos_profile
+                schema::Attribute {
+                    name: "qos_latency_ms".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Network latency in milliseconds (deprecated, use qos_profile_id)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: true,
+                },
+                schema::Attribute {
+                    name: "qos_jitter_ms".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Network jitter in milliseconds (deprecated, use qos_profile_id)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: true,
+                },
                 schema::Attribute {
                     name: "qos_loss_percent".to_string(),
                     r#type: serde_json::to_vec(&"number").unwrap(),
                     nested_type: None,
-                    description: "Packet loss percentage".to_string(),
+                    description: "Packet loss percentage (deprecated, use qos_profile_id)".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
                     required: false,
                     optional: true,
                     computed: false,
                     sensitive: false,
-                    deprecated: false,
+                    deprecated: true,
                 },
                 schema::Attribute {
                     name: "qos_bandwidth_mbps".to_string(),
                     r#type: serde_json::to_vec(&"number").unwrap(),
                     nested_type: None,
-                    description: "Bandwidth limit in Mbps".to_string(),
+                    description: "Bandwidth limit in Mbps (deprecated, use qos_profile_id)".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
                     required: false,
                     optional: true,
                     computed: false,
                     sensitive: false,
-                    deprecated: false,
+                    deprecated: true,
                 },
             ],
-            block_types: vec![],
+            block_types: vec![
+                schema::NestedBlock {
+                    type_name: "network_interface".to_string(),
+                    block: Some(schema::Block {
+                        version: 1,
+                        description: "NIC on a network, in attachment order".to_string(),
+                        description_kind: schema::StringKind::Plain as i32,
+                        deprecated: false,
+                        attributes: vec![
+                                schema::Attribute {
+                                    name: "network_id".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Network to attach".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: true,
+                                    optional: false,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "mac".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "MAC address to pin; derived from the VM ID when unset".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: true,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "ip".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Static IPv4 address the network's DHCP server reserves for the NIC".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "ip_address".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Current IP address of the NIC, while the VM runs".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: false,
+                                    computed: true,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "source".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Where ip_address came from (user-mode, dhcp, neighbor)".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: false,
+                                    computed: true,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                        ],
+                        block_types: vec![],
+                    }),
+                    nesting: schema::nested_block::NestingMode::List as i32,
+                    min_items: 0,
+                    max_items: 0,
+                },
+                schema::NestedBlock {
+                    type_name: "shared_folder".to_string(),
+                    block: Some(schema::Block {
+                        version: 1,
+                        description: "Host directory mounted into the guest; applied when the VM next starts".to_string(),
+                        description_kind: schema::StringKind::Plain as i32,
+                        deprecated: false,
+                        attributes: vec![
+                                schema::Attribute {
+                                    name: "host_path".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Absolute directory on the daemon's host".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: true,
+                                    optional: false,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "guest_path".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Absolute mount point in the guest".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: true,
+                                    optional: false,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "tag".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Mount tag in the guest; derived from guest_path when unset".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: true,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "read_only".to_string(),
+                                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                                    nested_type: None,
+                                    description: "Share the folder read-only".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: true,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "cache".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Guest caching: auto, always or never".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "protocol".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Transport: auto (virtiofs where the host has virtiofsd, else 9p), virtiofs or 9p".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                        ],
+                        block_types: vec![],
+                    }),
+                    nesting: schema::nested_block::NestingMode::List as i32,
+                    min_items: 0,
+                    max_items: 0,
+                },
+                timeouts_block(),
+            ],
         }),
     }
 }
@@ -348,14 +572,14 @@ pub fn volume_schema() -> Schema {
                     deprecated: false,
                 },
                 schema::Attribute {
-                    name: "size_gb".to_string(),
+                    name: "size_bytes".to_string(),
                     r#type: serde_json::to_vec(&"number").unwrap(),
                     nested_type: None,
-                    description: "Size in GB".to_string(),
+                    description: "Virtual size in bytes; increasing it grows the volume in place".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
-                    required: true,
-                    optional: false,
-                    computed: false,
+                    required: false,
+                    optional: true,
+                    computed: true,
                     sensitive: false,
                     deprecated: false,
                 },
@@ -371,6 +595,42 @@ pub fn volume_schema() -> Schema {
                     sensitive: false,
                     deprecated: false,
                 },
+                schema::Attribute {
+                    name: "encryption_key".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Daemon keyring key to encrypt the volume with (qcow2 only); a new key gets encryption_passphrase or a generated one".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "encryption_cipher".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Cipher for encrypted volumes (aes-256 by default)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "encryption_passphrase".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Passphrase stored under a new encryption_key".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: true,
+                    deprecated: false,
+                },
                 schema::Attribute {
                     name: "base_image".to_string(),
                     r#type: serde_json::to_vec(&"string").unwrap(),
@@ -408,7 +668,7 @@ pub fn volume_schema() -> Schema {
                     deprecated: false,
                 },
             ],
-            block_types: vec![],
+            block_types: vec![timeouts_block()],
         }),
     }
 }
@@ -471,6 +731,18 @@ pub fn snapshot_schema() -> Schema {
                     sensitive: false,
                     deprecated: false,
                 },
+                schema::Attribute {
+                    name: "include_disk".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Include disk state".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
                 schema::Attribute {
                     name: "description".to_string(),
                     r#type: serde_json::to_vec(&"string").unwrap(),
@@ -496,10 +768,34 @@ pub fn snapshot_schema() -> Schema {
                     deprecated: false,
                 },
                 schema::Attribute {
-                    name: "created_at".to_string(),
+                    name: "parent_id".to_string(),
                     r#type: serde_json::to_vec(&"string").unwrap(),
                     nested_type: None,
-                    description: "Creation timestamp".to_string(),
+                    description: "Snapshot the VM's disk was based on when this one was taken".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "complete".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether the snapshot finished being written".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "created_at".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Creation time (Unix seconds)".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
                     required: false,
                     optional: false,
@@ -508,26 +804,74 @@ pub fn snapshot_schema() -> Schema {
                     deprecated: false,
                 },
             ],
-            block_types: vec![],
+            block_types: vec![timeouts_block()],
         }),
     }
 }
 
-/// Create the provider schema
-pub fn provider_schema() -> Schema {
+/// Create the schema for infrasim_port_forward resource
+pub fn port_forward_schema() -> Schema {
     Schema {
         version: 1,
         block: Some(schema::Block {
             version: 1,
-            description: "InfraSim Provider configuration".to_string(),
+            description: "InfraSim port forward from a host port to a guest port".to_string(),
             description_kind: schema::StringKind::Plain as i32,
             deprecated: false,
             attributes: vec![
                 schema::Attribute {
-                    name: "daemon_address".to_string(),
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Port forward ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Port forward name".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "vm_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "VM to forward traffic to".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "network_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "User-mode network the forward is attached to (defaults to the VM's first interface)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "protocol".to_string(),
                     r#type: serde_json::to_vec(&"string").unwrap(),
                     nested_type: None,
-                    description: "Address of the InfraSim daemon".to_string(),
+                    description: "Protocol (tcp, udp)".to_string(),
                     description_kind: schema::StringKind::Plain as i32,
                     required: false,
                     optional: true,
@@ -535,8 +879,1347 @@ pub fn provider_schema() -> Schema {
                     sensitive: false,
                     deprecated: false,
                 },
+                schema::Attribute {
+                    name: "host_addr".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Host address to bind (default 127.0.0.1)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "host_port".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Host port".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "guest_port".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Guest port".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "active".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether the forward is installed in the running VM".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
             ],
+            block_types: vec![timeouts_block()],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_console resource
+pub fn console_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim VNC and web console of a VM".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Console ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Console name (default: console-<vm_id>)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "vm_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "VM the console connects to".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "enable_vnc".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Serve the VM display over VNC (default true)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "vnc_port".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "VNC port (default: chosen by the daemon)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "enable_web".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Serve the web (noVNC) console (default true)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "web_port".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Web console port (default: chosen by the daemon)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "auth_token".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Token clients present to connect; generated when unset".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: true,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "active".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether the console is serving".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "vnc_host".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Host the VNC server listens on".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "web_url".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "URL of the web console".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![timeouts_block()],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_router resource
+pub fn router_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim router forwarding between networks, with static routes and firewall rules".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Router ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Router name".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "network_ids".to_string(),
+                    r#type: serde_json::to_vec(&serde_json::json!(["list", "string"])).unwrap(),
+                    nested_type: None,
+                    description: "vmnet-shared networks the router connects (at least two)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "default_action".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Action for forwarded traffic no firewall_rule matches (allow, deny; default allow)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "active".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether the routes and rules are installed on the host".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "interfaces".to_string(),
+                    r#type: serde_json::to_vec(&serde_json::json!(["list", "string"])).unwrap(),
+                    nested_type: None,
+                    description: "Host bridges the router forwards between".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "error_message".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Why the router could not be installed".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![
+                schema::NestedBlock {
+                    type_name: "route".to_string(),
+                    block: Some(schema::Block {
+                        version: 1,
+                        description: "Static route".to_string(),
+                        description_kind: schema::StringKind::Plain as i32,
+                        deprecated: false,
+                        attributes: vec![
+                                schema::Attribute {
+                                    name: "destination".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Destination CIDR".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: true,
+                                    optional: false,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "next_hop".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Next hop address, on one of the router's networks".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: true,
+                                    optional: false,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                        ],
+                        block_types: vec![],
+                    }),
+                    nesting: schema::nested_block::NestingMode::List as i32,
+                    min_items: 0,
+                    max_items: 0,
+                },
+                schema::NestedBlock {
+                    type_name: "firewall_rule".to_string(),
+                    block: Some(schema::Block {
+                        version: 1,
+                        description: "Firewall rule for forwarded traffic; the first match decides".to_string(),
+                        description_kind: schema::StringKind::Plain as i32,
+                        deprecated: false,
+                        attributes: vec![
+                                schema::Attribute {
+                                    name: "action".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "allow or deny".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: true,
+                                    optional: false,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "protocol".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Protocol (any, tcp, udp, icmp; default any)".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: true,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "source".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Source CIDR (default any)".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "destination".to_string(),
+                                    r#type: serde_json::to_vec(&"string").unwrap(),
+                                    nested_type: None,
+                                    description: "Destination CIDR (default any)".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                                schema::Attribute {
+                                    name: "port".to_string(),
+                                    r#type: serde_json::to_vec(&"number").unwrap(),
+                                    nested_type: None,
+                                    description: "Destination port, tcp and udp only (default any)".to_string(),
+                                    description_kind: schema::StringKind::Plain as i32,
+                                    required: false,
+                                    optional: true,
+                                    computed: false,
+                                    sensitive: false,
+                                    deprecated: false,
+                                },
+                        ],
+                        block_types: vec![],
+                    }),
+                    nesting: schema::nested_block::NestingMode::List as i32,
+                    min_items: 0,
+                    max_items: 0,
+                },
+                timeouts_block(),
+            ],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_qos_profile resource
+pub fn qos_profile_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim QoS profile shaping the network traffic of VMs that use it".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "QoS profile ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Profile name".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "latency_ms".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Added latency in milliseconds".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "jitter_ms".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Random extra latency of up to this many milliseconds".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "loss_percent".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Packet loss percentage (0-100)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "rate_limit_mbps".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Bandwidth limit in Mbps (0 = unlimited)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "burst_size_kb".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Burst allowance in KB above the rate limit".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "packet_padding_bytes".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Bytes of padding added to each frame".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![timeouts_block()],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_secret resource
+pub fn secret_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim secret, stored encrypted by the daemon and referenced as ${secret:<name>} in appliance env vars".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Secret ID (same as the name)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Secret name".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "value".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Secret value".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: true,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "created_at".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Creation time (Unix seconds)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "updated_at".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Time the value was last replaced (Unix seconds)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![timeouts_block()],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_device resource
+pub fn device_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim host USB or PCI device passed through to a VM".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Device ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Device name (defaults to <vm>-<bus>-<device>)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "vm_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "VM the device is passed through to".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "bus".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Host bus (usb, pci). PCI devices must be bound to vfio-pci on a Linux host".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "vendor_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "USB vendor ID in hex, e.g. 046d".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "product_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "USB product ID in hex, e.g. c52b".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "host_address".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "USB <bus>.<addr>, or PCI address such as 0000:01:00.0".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "attached".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether the device is present in the running VM".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![timeouts_block()],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_snapshot_policy resource
+pub fn snapshot_policy_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim scheduled snapshot policy with retention".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Snapshot policy ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Policy name (defaults to <vm>-every-<interval>)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "vm_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "VM to snapshot".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "every".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Interval between snapshots (e.g. 30m, 6h, 1d)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "keep".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Number of scheduled snapshots to keep (default 10)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "include_memory".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Also capture memory state while the VM is running".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "paused".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Suspend the schedule without deleting the policy".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "last_run_at".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Unix time of the last scheduled run".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "next_run_at".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Unix time of the next scheduled run (0 while paused)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "retained".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Number of policy snapshots currently kept".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "last_error".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Error from the last run, if any".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![timeouts_block()],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_vm_clone resource
+pub fn vm_clone_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim VM clone resource (running linked clones of a template)".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Clone set ID (the name)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "template".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Template VM ID or name".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "name".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Clone name; with clone_count > 1 a trailing number is counted up, otherwise -1, -2, ... is appended".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: true,
+                    optional: false,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "clone_count".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Number of clones (default 1)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "ids".to_string(),
+                    r#type: serde_json::to_vec(&serde_json::json!(["list", "string"])).unwrap(),
+                    nested_type: None,
+                    description: "Clone VM IDs".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "names".to_string(),
+                    r#type: serde_json::to_vec(&serde_json::json!(["list", "string"])).unwrap(),
+                    nested_type: None,
+                    description: "Clone VM names".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![timeouts_block()],
+        }),
+    }
+}
+
+/// Create the schema for infrasim_attestation data source
+pub fn attestation_data_source_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "Signed attestation report of a running VM, or of the VM a volume is attached to, verified during plan".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "vm_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "VM to attest (must be running); set this or volume_id".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "volume_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Volume to attest through the running VM it is attached to; set this or vm_id".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "expected_digest".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Expected SHA-256 of the VM's boot disk, or of the volume when volume_id is set".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "public_key".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Hex Ed25519 key the report must be signed with; defaults to the daemon's signing key".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Attestation report ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "key_id".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "ID of the key the report was verified with".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "digest".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Signed digest of the report".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "subject_digest".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Digest of the boot disk, or of the volume when volume_id is set".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "attestation_type".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Report type: host_provenance or vtpm".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "created_at".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Time the report was generated (Unix seconds)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "base_image_hash".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Digest of the VM's boot disk".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "volume_hashes".to_string(),
+                    r#type: serde_json::to_vec(&serde_json::json!(["map", "string"])).unwrap(),
+                    nested_type: None,
+                    description: "Digests of the VM's volumes keyed by volume ID".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "qemu_version".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "QEMU version on the host".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "hostname".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Host the VM runs on".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "digest_valid".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether the digest matches the report contents".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "signature_valid".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether the digest is signed by public_key".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "tpm_quotes_valid".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether every vTPM quote in the report verifies".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "verified".to_string(),
+                    r#type: serde_json::to_vec(&"bool").unwrap(),
+                    nested_type: None,
+                    description: "Whether every check passed, including expected_digest".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "failures".to_string(),
+                    r#type: serde_json::to_vec(&serde_json::json!(["list", "string"])).unwrap(),
+                    nested_type: None,
+                    description: "Checks that failed".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: false,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![],
+        }),
+    }
+}
+
+/// Create the provider schema
+pub fn provider_schema() -> Schema {
+    Schema {
+        version: 1,
+        block: Some(schema::Block {
+            version: 1,
+            description: "InfraSim Provider configuration".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: vec![
+                schema::Attribute {
+                    name: "daemon_address".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Address of the InfraSim daemon: http://, https:// or unix:///path/to/daemon.sock (default: the local daemon)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: true,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "ca_cert".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "CA certificate the daemon's TLS certificate is checked against (default INFRASIM_CA_CERT)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "client_cert".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Client certificate for daemons started with --mtls (default INFRASIM_CLIENT_CERT)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "client_key".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Private key of client_cert (default INFRASIM_CLIENT_KEY)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "token".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Bearer token for daemons started with --require-token (default INFRASIM_TOKEN)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: true,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "tls_domain".to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: "Name to verify the daemon certificate against (default INFRASIM_TLS_DOMAIN, then the address host)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "max_retries".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Times a call is retried while the daemon is unavailable or busy (default 5)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+                schema::Attribute {
+                    name: "retry_delay_ms".to_string(),
+                    r#type: serde_json::to_vec(&"number").unwrap(),
+                    nested_type: None,
+                    description: "Milliseconds before the first retry, doubled for each retry after it up to 30s (default 500)".to_string(),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                },
+            ],
+            block_types: vec![],
+        }),
+    }
+}
+
+/// Operations a resource's `timeouts` block can bound
+pub const TIMEOUT_OPERATIONS: &[&str] = &["create", "read", "update", "delete"];
+
+/// The `timeouts` block every resource accepts
+fn timeouts_block() -> schema::NestedBlock {
+    schema::NestedBlock {
+        type_name: "timeouts".to_string(),
+        block: Some(schema::Block {
+            version: 1,
+            description: "How long each operation may take, e.g. 30s, 10m or 1h (default 20m)".to_string(),
+            description_kind: schema::StringKind::Plain as i32,
+            deprecated: false,
+            attributes: TIMEOUT_OPERATIONS
+                .iter()
+                .map(|op| schema::Attribute {
+                    name: op.to_string(),
+                    r#type: serde_json::to_vec(&"string").unwrap(),
+                    nested_type: None,
+                    description: format!("Timeout for {}", op),
+                    description_kind: schema::StringKind::Plain as i32,
+                    required: false,
+                    optional: true,
+                    computed: false,
+                    sensitive: false,
+                    deprecated: false,
+                })
+                .collect(),
             block_types: vec![],
         }),
+        nesting: schema::nested_block::NestingMode::Single as i32,
+        min_items: 0,
+        max_items: 0,
     }
 }
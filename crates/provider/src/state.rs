@@ -102,6 +102,26 @@ pub fn get_optional_string_attr(value: &DynamicValue, key: &str) -> Option<Strin
         })
 }
 
+/// Helper to extract a list of strings from a DynamicValue (None if unset)
+pub fn get_string_list_attr(value: &DynamicValue, key: &str) -> Option<Vec<String>> {
+    match value.get(key)? {
+        DynamicValue::List(items) => Some(
+            items.iter()
+                .filter_map(|v| v.as_string().map(str::to_string))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Helper to extract the entries of a list-nested block (empty if unset)
+pub fn get_block_list_attr<'a>(value: &'a DynamicValue, key: &str) -> &'a [DynamicValue] {
+    match value.get(key) {
+        Some(DynamicValue::List(items)) => items,
+        _ => &[],
+    }
+}
+
 /// Helper to extract an integer attribute from a DynamicValue
 pub fn get_int_attr(value: &DynamicValue, key: &str, default: i64) -> i64 {
     value.get(key)
@@ -137,6 +157,11 @@ pub fn string_value(s: impl Into<String>) -> DynamicValue {
     DynamicValue::String(s.into())
 }
 
+/// Create a list DynamicValue of strings
+pub fn string_list_value(items: &[String]) -> DynamicValue {
+    DynamicValue::List(items.iter().map(|s| string_value(s.as_str())).collect())
+}
+
 /// Create a number DynamicValue from i64
 pub fn int_value(n: i64) -> DynamicValue {
     DynamicValue::Number(serde_json::Number::from(n))
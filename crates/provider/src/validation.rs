@@ -0,0 +1,251 @@
+//! Plan-time Validation
+//!
+//! Checks resource configs against the daemon host's capabilities so that
+//! impossible specs fail at plan time instead of midway through apply.
+
+use crate::generated::infrasim::GetDaemonStatusResponse;
+use crate::generated::tfplugin6::{attribute_path, diagnostic, AttributePath, Diagnostic};
+use crate::schema::TIMEOUT_OPERATIONS;
+use crate::state::{get_bool_attr, get_optional_string_attr, get_string_attr, DynamicValue};
+use infrasim_common::types::parse_interval;
+
+/// Machine types the daemon can launch, per guest architecture
+///
+/// x86_64 guests always run on q35, see the daemon's `build_args`.
+const SUPPORTED_MACHINES: &[(&str, &[&str])] = &[("aarch64", &["virt", "raspi3b"]), ("x86_64", &["q35"])];
+
+/// Architecture used when `arch` is left empty
+const DEFAULT_ARCH: &str = "aarch64";
+
+/// Validate an `infrasim_vm` config against the host
+pub fn vm_diagnostics(config: &DynamicValue, host: &GetDaemonStatusResponse) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if let Some(memory_mb) = config.get("memory_mb").and_then(|v| v.as_i64()) {
+        let host_mb = host.host_memory_bytes / (1024 * 1024);
+        if host_mb > 0 && memory_mb > host_mb {
+            diagnostics.push(error(
+                "memory_mb",
+                "Not enough host memory",
+                format!(
+                    "memory_mb = {} but the daemon host only has {} MB",
+                    memory_mb, host_mb
+                ),
+            ));
+        }
+    }
+
+    let arch = get_string_attr(config, "arch");
+    let arch = if arch.is_empty() { DEFAULT_ARCH } else { arch.as_str() };
+    match SUPPORTED_MACHINES.iter().find(|(a, _)| *a == arch) {
+        None => diagnostics.push(error(
+            "arch",
+            "Unsupported architecture",
+            format!(
+                "arch '{}' is not supported; expected one of: {}",
+                arch,
+                SUPPORTED_MACHINES.iter().map(|(a, _)| *a).collect::<Vec<_>>().join(", ")
+            ),
+        )),
+        Some((_, machines)) => {
+            let machine = get_string_attr(config, "machine");
+            if !machine.is_empty() && !machines.contains(&machine.as_str()) {
+                diagnostics.push(error(
+                    "machine",
+                    "Unsupported machine type",
+                    format!(
+                        "machine '{}' is not supported for {}; expected one of: {}",
+                        machine,
+                        arch,
+                        machines.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+
+    if get_bool_attr(config, "enable_tpm", false) && !host.swtpm_available {
+        diagnostics.push(error(
+            "enable_tpm",
+            "TPM not available",
+            "enable_tpm requires swtpm, which is not installed on the daemon host".to_string(),
+        ));
+    }
+
+    let stop_mode = get_string_attr(config, "stop_mode");
+    if !stop_mode.is_empty() && stop_mode != "acpi" && stop_mode != "hard" {
+        diagnostics.push(error(
+            "stop_mode",
+            "Invalid stop mode",
+            format!("stop_mode '{}' is not supported; expected acpi or hard", stop_mode),
+        ));
+    }
+
+    if !host.hvf_available && !host.kvm_available {
+        diagnostics.push(Diagnostic {
+            severity: diagnostic::Severity::Warning as i32,
+            summary: "No hardware acceleration".to_string(),
+            detail: "Neither Hypervisor.framework nor KVM is available on the daemon host; \
+                     the VM will run under TCG emulation and be significantly slower"
+                .to_string(),
+            attribute: None,
+        });
+    }
+
+    diagnostics
+}
+
+/// Check the durations in a resource's `timeouts` block
+pub fn timeouts_diagnostics(config: &DynamicValue) -> Vec<Diagnostic> {
+    let Some(timeouts) = config.get("timeouts") else {
+        return vec![];
+    };
+
+    TIMEOUT_OPERATIONS
+        .iter()
+        .filter_map(|operation| {
+            let value = get_optional_string_attr(timeouts, operation)?;
+            parse_interval(&value).err().map(|_| {
+                error_at(
+                    &["timeouts", operation],
+                    "Invalid timeout",
+                    format!(
+                        "timeouts.{} = \"{}\" is not a duration such as 30s, 10m or 1h",
+                        operation, value
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Check that an `infrasim_attestation` config names a single subject
+pub fn attestation_diagnostics(config: &DynamicValue) -> Vec<Diagnostic> {
+    if get_optional_string_attr(config, "vm_id").is_some()
+        && get_optional_string_attr(config, "volume_id").is_some()
+    {
+        return vec![error(
+            "volume_id",
+            "Conflicting attestation subject",
+            "Set either vm_id or volume_id, not both".to_string(),
+        )];
+    }
+    vec![]
+}
+
+fn error(attribute: &str, summary: &str, detail: String) -> Diagnostic {
+    error_at(&[attribute], summary, detail)
+}
+
+fn error_at(path: &[&str], summary: &str, detail: String) -> Diagnostic {
+    Diagnostic {
+        severity: diagnostic::Severity::Error as i32,
+        summary: summary.to_string(),
+        detail,
+        attribute: Some(AttributePath {
+            steps: path
+                .iter()
+                .map(|attribute| attribute_path::Step {
+                    selector: Some(attribute_path::step::Selector::AttributeName(
+                        attribute.to_string(),
+                    )),
+                })
+                .collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::make_state;
+
+    fn config(attrs: &[(&str, DynamicValue)]) -> DynamicValue {
+        make_state(attrs.to_vec())
+    }
+
+    fn string(value: &str) -> DynamicValue {
+        DynamicValue::String(value.to_string())
+    }
+
+    fn host() -> GetDaemonStatusResponse {
+        GetDaemonStatusResponse {
+            host_memory_bytes: 8 * 1024 * 1024 * 1024,
+            kvm_available: true,
+            ..Default::default()
+        }
+    }
+
+    fn errors_on(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == diagnostic::Severity::Error as i32)
+            .filter_map(|d| match d.attribute.as_ref()?.steps.first()?.selector.as_ref()? {
+                attribute_path::step::Selector::AttributeName(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_vm_has_no_diagnostics() {
+        let vm = config(&[
+            ("memory_mb", DynamicValue::Number(2048.into())),
+            ("arch", string("x86_64")),
+            ("machine", string("q35")),
+            ("stop_mode", string("acpi")),
+        ]);
+        assert!(vm_diagnostics(&vm, &host()).is_empty());
+        // Empty arch means aarch64
+        assert!(vm_diagnostics(&config(&[("machine", string("virt"))]), &host()).is_empty());
+    }
+
+    #[test]
+    fn test_memory_over_host_limit() {
+        let vm = config(&[("memory_mb", DynamicValue::Number(16384.into()))]);
+        assert_eq!(errors_on(&vm_diagnostics(&vm, &host())), ["memory_mb"]);
+
+        // Unknown host memory isn't a limit
+        let unknown = GetDaemonStatusResponse { host_memory_bytes: 0, ..host() };
+        assert!(vm_diagnostics(&vm, &unknown).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_arch_and_machine() {
+        let vm = config(&[("arch", string("riscv64"))]);
+        assert_eq!(errors_on(&vm_diagnostics(&vm, &host())), ["arch"]);
+
+        let vm = config(&[("arch", string("aarch64")), ("machine", string("q35"))]);
+        assert_eq!(errors_on(&vm_diagnostics(&vm, &host())), ["machine"]);
+        let vm = config(&[("arch", string("x86_64")), ("machine", string("virt"))]);
+        assert_eq!(errors_on(&vm_diagnostics(&vm, &host())), ["machine"]);
+    }
+
+    #[test]
+    fn test_tpm_needs_swtpm() {
+        let vm = config(&[("enable_tpm", DynamicValue::Bool(true))]);
+        assert_eq!(errors_on(&vm_diagnostics(&vm, &host())), ["enable_tpm"]);
+
+        let swtpm = GetDaemonStatusResponse { swtpm_available: true, ..host() };
+        assert!(vm_diagnostics(&vm, &swtpm).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_stop_mode() {
+        let vm = config(&[("stop_mode", string("pull-the-plug"))]);
+        assert_eq!(errors_on(&vm_diagnostics(&vm, &host())), ["stop_mode"]);
+        assert!(vm_diagnostics(&config(&[("stop_mode", string("hard"))]), &host()).is_empty());
+    }
+
+    #[test]
+    fn test_warns_without_acceleration() {
+        let tcg = GetDaemonStatusResponse { kvm_available: false, ..host() };
+        let diagnostics = vm_diagnostics(&config(&[]), &tcg);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, diagnostic::Severity::Warning as i32);
+        assert!(diagnostics[0].attribute.is_none());
+
+        let hvf = GetDaemonStatusResponse { hvf_available: true, ..tcg };
+        assert!(vm_diagnostics(&config(&[]), &hvf).is_empty());
+    }
+}
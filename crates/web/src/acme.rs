@@ -0,0 +1,476 @@
+//! ACME certificates for the web console
+//!
+//! Issues a certificate for the configured domains from an ACME CA (Let's
+//! Encrypt by default) with HTTP-01 challenges, and renews it 30 days before
+//! it expires. The account key and the certificate are cached, so restarts
+//! don't issue again. Challenges are answered at
+//! `/.well-known/acme-challenge/<token>`, both on the console and on a
+//! plain-HTTP listener (port 80 by default) that redirects everything else to
+//! HTTPS.
+
+use crate::tls::CertResolver;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
+use axum::Router;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use der::Decode;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Let's Encrypt's production directory
+pub const LETS_ENCRYPT: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+const RENEW_BEFORE_SECS: u64 = 30 * 24 * 60 * 60;
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+const RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const POLL_ATTEMPTS: u32 = 60;
+
+/// Certificate issuance through ACME
+#[derive(Clone, Debug)]
+pub struct AcmeConfig {
+    /// Names on the certificate; each must resolve to this server
+    pub domains: Vec<String>,
+    /// Account contacts, e.g. `mailto:ops@example.com`
+    pub contact: Vec<String>,
+    /// ACME directory URL
+    pub directory: String,
+    /// Where the account key and certificates are kept
+    pub cache_dir: PathBuf,
+    /// Plain-HTTP listener for challenges, unless a proxy in front forwards
+    /// `/.well-known/acme-challenge/` to the console
+    pub http_addr: Option<SocketAddr>,
+}
+
+/// Key authorizations of pending HTTP-01 challenges, by token
+#[derive(Clone, Debug, Default)]
+pub struct Challenges(Arc<RwLock<HashMap<String, String>>>);
+
+impl Challenges {
+    fn get(&self, token: &str) -> Option<String> {
+        self.0.read().ok()?.get(token).cloned()
+    }
+
+    fn insert(&self, token: &str, key_authorization: String) {
+        if let Ok(mut pending) = self.0.write() {
+            pending.insert(token.to_string(), key_authorization);
+        }
+    }
+
+    fn remove(&self, token: &str) {
+        if let Ok(mut pending) = self.0.write() {
+            pending.remove(token);
+        }
+    }
+}
+
+/// Routes answering challenges, nested at `/.well-known/acme-challenge`
+pub fn challenge_routes(challenges: Challenges) -> Router {
+    Router::new()
+        .route("/:token", get(challenge_handler))
+        .with_state(challenges)
+}
+
+async fn challenge_handler(State(challenges): State<Challenges>, Path(token): Path<String>) -> Response {
+    match challenges.get(&token) {
+        Some(key_authorization) => key_authorization.into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Answer challenges over plain HTTP and send everything else to the HTTPS
+/// console on `https_port`
+pub fn spawn_http(addr: SocketAddr, challenges: Challenges, https_port: u16) {
+    let app = Router::new()
+        .nest_service("/.well-known/acme-challenge", challenge_routes(challenges))
+        .fallback(move |headers: HeaderMap, uri: Uri| async move { redirect_to_https(&headers, &uri, https_port) });
+    tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!("Can't answer ACME challenges on {}: {}", addr, e);
+                return;
+            }
+        };
+        info!("Answering ACME challenges on http://{}", addr);
+        if let Err(e) = axum::serve(listener, app).await {
+            warn!("ACME challenge listener stopped: {}", e);
+        }
+    });
+}
+
+fn redirect_to_https(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Response {
+    let Some(host) = headers.get(header::HOST).and_then(|v| v.to_str().ok()) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => host,
+    };
+    let authority = match https_port {
+        443 => name.to_string(),
+        port => format!("{}:{}", name, port),
+    };
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    Redirect::permanent(&format!("https://{}{}", authority, path)).into_response()
+}
+
+/// Serve the cached certificate or issue one, then keep it renewed
+pub fn spawn(cfg: AcmeConfig, resolver: Arc<CertResolver>, challenges: Challenges) {
+    tokio::spawn(async move {
+        loop {
+            let wait = match ensure_certificate(&cfg, &resolver, &challenges).await {
+                Ok(()) => CHECK_INTERVAL,
+                Err(e) => {
+                    warn!("ACME certificate for {} failed: {:#}", cfg.domains.join(", "), e);
+                    RETRY_INTERVAL
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+async fn ensure_certificate(cfg: &AcmeConfig, resolver: &CertResolver, challenges: &Challenges) -> anyhow::Result<()> {
+    // Named after the domains, so changing them issues a new certificate
+    let name = cfg.domains.join(",");
+    let cert_path = cfg.cache_dir.join(format!("{}.crt", name));
+    let key_path = cfg.cache_dir.join(format!("{}.key", name));
+
+    if let (Ok(cert), Ok(key)) = (std::fs::read(&cert_path), std::fs::read(&key_path)) {
+        match seconds_left(&cert) {
+            Ok(left) if left > RENEW_BEFORE_SECS => {
+                if !resolver.is_ready() {
+                    resolver.set(&cert, &key)?;
+                    info!("Serving cached certificate for {}", name);
+                }
+                return Ok(());
+            }
+            Ok(_) => info!("Certificate for {} expires within 30 days; renewing", name),
+            Err(e) => warn!("Replacing unreadable certificate {}: {}", cert_path.display(), e),
+        }
+    }
+
+    info!("Requesting a certificate for {} from {}", name, cfg.directory);
+    let (cert, key) = issue(cfg, challenges).await?;
+    std::fs::create_dir_all(&cfg.cache_dir)?;
+    write_private(&key_path, key.as_bytes())?;
+    std::fs::write(&cert_path, &cert)?;
+    resolver.set(cert.as_bytes(), key.as_bytes())?;
+    info!("Issued certificate for {}", name);
+    Ok(())
+}
+
+/// Seconds until the first certificate in a PEM chain expires
+fn seconds_left(cert_pem: &[u8]) -> anyhow::Result<u64> {
+    let der = CertificateDer::pem_slice_iter(cert_pem)
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("no certificate in PEM"))??;
+    let cert = x509_cert::Certificate::from_der(&der)?;
+    let not_after = cert.tbs_certificate.validity.not_after.to_unix_duration();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+    Ok(not_after.saturating_sub(now).as_secs())
+}
+
+fn write_private(path: &FsPath, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(data)
+}
+
+/// The account key, created on first use
+fn account_key(cache_dir: &FsPath) -> anyhow::Result<SigningKey> {
+    let path = cache_dir.join("account.key");
+    if let Ok(pem) = std::fs::read_to_string(&path) {
+        return Ok(SigningKey::from_pkcs8_pem(&pem)?);
+    }
+    let key = SigningKey::random(&mut rand::rngs::OsRng);
+    std::fs::create_dir_all(cache_dir)?;
+    write_private(&path, key.to_pkcs8_pem(LineEnding::LF)?.as_bytes())?;
+    Ok(key)
+}
+
+fn b64(data: impl AsRef<[u8]>) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Order {
+    status: String,
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Authorization {
+    status: String,
+    identifier: Identifier,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Identifier {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    token: String,
+}
+
+/// Issue a certificate, returning its PEM chain and key
+async fn issue(cfg: &AcmeConfig, challenges: &Challenges) -> anyhow::Result<(String, String)> {
+    let mut client = AcmeClient::new(&cfg.directory, account_key(&cfg.cache_dir)?).await?;
+    client.register(&cfg.contact).await?;
+
+    let identifiers: Vec<Value> = cfg.domains.iter().map(|d| json!({"type": "dns", "value": d})).collect();
+    let new_order = client.directory.new_order.clone();
+    let res = client.post(&new_order, Some(&json!({"identifiers": identifiers}))).await?;
+    let order_url = location(&res)?;
+    let order: Order = res.json().await?;
+
+    let mut tokens = Vec::new();
+    let result = async {
+        for authz_url in &order.authorizations {
+            let authz: Authorization = client.post(authz_url, None).await?.json().await?;
+            if authz.status == "valid" {
+                continue;
+            }
+            let challenge = authz
+                .challenges
+                .iter()
+                .find(|c| c.kind == "http-01")
+                .ok_or_else(|| anyhow::anyhow!("no http-01 challenge for {}", authz.identifier.value))?;
+            challenges.insert(&challenge.token, format!("{}.{}", challenge.token, client.thumbprint()));
+            tokens.push(challenge.token.clone());
+            client.post(&challenge.url, Some(&json!({}))).await?;
+            client.poll_authorization(authz_url).await?;
+        }
+
+        let key = rcgen::KeyPair::generate()?;
+        let csr = rcgen::CertificateParams::new(cfg.domains.clone())?.serialize_request(&key)?;
+        client.post(&order.finalize, Some(&json!({"csr": b64(csr.der())}))).await?;
+        let order = client.poll_order(&order_url).await?;
+        let cert_url = order
+            .certificate
+            .ok_or_else(|| anyhow::anyhow!("valid order without a certificate"))?;
+        let chain = client.post(&cert_url, None).await?.text().await?;
+        Ok((chain, key.serialize_pem()))
+    }
+    .await;
+
+    for token in &tokens {
+        challenges.remove(token);
+    }
+    result
+}
+
+fn location(res: &reqwest::Response) -> anyhow::Result<String> {
+    res.headers()
+        .get(header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("ACME response without a Location"))
+}
+
+/// Requests to an ACME server, signed with the account key (RFC 8555)
+struct AcmeClient {
+    http: reqwest::Client,
+    directory: Directory,
+    key: SigningKey,
+    /// Account URL, once registered
+    kid: Option<String>,
+    nonce: Option<String>,
+}
+
+impl AcmeClient {
+    async fn new(directory_url: &str, key: SigningKey) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+        let directory = http.get(directory_url).send().await?.error_for_status()?.json().await?;
+        Ok(Self {
+            http,
+            directory,
+            key,
+            kid: None,
+            nonce: None,
+        })
+    }
+
+    /// Find or create the account
+    async fn register(&mut self, contact: &[String]) -> anyhow::Result<()> {
+        let url = self.directory.new_account.clone();
+        let res = self
+            .post(&url, Some(&json!({"termsOfServiceAgreed": true, "contact": contact})))
+            .await?;
+        self.kid = Some(location(&res)?);
+        Ok(())
+    }
+
+    fn jwk(&self) -> (String, String) {
+        let point = self.key.verifying_key().to_encoded_point(false);
+        (
+            point.x().map(b64).unwrap_or_default(),
+            point.y().map(b64).unwrap_or_default(),
+        )
+    }
+
+    /// RFC 7638 thumbprint of the account key, the suffix of key authorizations
+    fn thumbprint(&self) -> String {
+        use sha2::{Digest, Sha256};
+        let (x, y) = self.jwk();
+        // Members in lexicographic order, without whitespace
+        let canonical = format!(r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#, x, y);
+        b64(Sha256::digest(canonical.as_bytes()))
+    }
+
+    async fn nonce(&mut self) -> anyhow::Result<String> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+        let res = self.http.head(&self.directory.new_nonce).send().await?;
+        replay_nonce(&res).ok_or_else(|| anyhow::anyhow!("ACME server sent no nonce"))
+    }
+
+    /// POST a JWS; `None` is a POST-as-GET
+    async fn post(&mut self, url: &str, payload: Option<&Value>) -> anyhow::Result<reqwest::Response> {
+        let mut retried = false;
+        loop {
+            let mut protected = json!({"alg": "ES256", "nonce": self.nonce().await?, "url": url});
+            match &self.kid {
+                Some(kid) => protected["kid"] = json!(kid),
+                None => {
+                    let (x, y) = self.jwk();
+                    protected["jwk"] = json!({"crv": "P-256", "kty": "EC", "x": x, "y": y});
+                }
+            }
+            let protected = b64(serde_json::to_vec(&protected)?);
+            let payload = match payload {
+                Some(payload) => b64(serde_json::to_vec(payload)?),
+                None => String::new(),
+            };
+            let signature: Signature = self.key.sign(format!("{}.{}", protected, payload).as_bytes());
+            let body = json!({"protected": protected, "payload": payload, "signature": b64(signature.to_bytes())});
+
+            let res = self
+                .http
+                .post(url)
+                .header(header::CONTENT_TYPE, "application/jose+json")
+                .body(serde_json::to_vec(&body)?)
+                .send()
+                .await?;
+            self.nonce = replay_nonce(&res);
+            if res.status().is_success() {
+                return Ok(res);
+            }
+
+            let status = res.status();
+            let problem: Value = res.json().await.unwrap_or_default();
+            // Nonces can expire between requests; one retry gets a fresh one
+            if !retried && problem["type"] == "urn:ietf:params:acme:error:badNonce" {
+                retried = true;
+                continue;
+            }
+            anyhow::bail!(
+                "{} returned {}: {}",
+                url,
+                status,
+                problem["detail"].as_str().unwrap_or("request failed")
+            );
+        }
+    }
+
+    async fn poll_authorization(&mut self, url: &str) -> anyhow::Result<()> {
+        for _ in 0..POLL_ATTEMPTS {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let authz: Authorization = self.post(url, None).await?.json().await?;
+            match authz.status.as_str() {
+                "valid" => return Ok(()),
+                "pending" | "processing" => {}
+                status => anyhow::bail!("authorization of {} is {}", authz.identifier.value, status),
+            }
+        }
+        anyhow::bail!("authorization {} didn't complete", url)
+    }
+
+    async fn poll_order(&mut self, url: &str) -> anyhow::Result<Order> {
+        for _ in 0..POLL_ATTEMPTS {
+            let order: Order = self.post(url, None).await?.json().await?;
+            match order.status.as_str() {
+                "valid" => return Ok(order),
+                "pending" | "ready" | "processing" => {}
+                status => anyhow::bail!("order is {}", status),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        anyhow::bail!("order {} didn't complete", url)
+    }
+}
+
+fn replay_nonce(res: &reqwest::Response) -> Option<String> {
+    res.headers()
+        .get("replay-nonce")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_certificate_expiry_and_account_key() {
+        let dir = std::env::temp_dir().join(format!("infrasim-acme-{}", uuid::Uuid::new_v4()));
+        let key = account_key(&dir).unwrap();
+        assert_eq!(account_key(&dir).unwrap(), key);
+
+        let expiring = |year| {
+            let mut params = rcgen::CertificateParams::new(vec!["console.test".to_string()]).unwrap();
+            params.not_after = rcgen::date_time_ymd(year, 1, 1);
+            params.self_signed(&rcgen::KeyPair::generate().unwrap()).unwrap().pem()
+        };
+        assert!(seconds_left(expiring(2100).as_bytes()).unwrap() > RENEW_BEFORE_SECS);
+        assert_eq!(seconds_left(expiring(2000).as_bytes()).unwrap(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_redirects_to_the_https_port() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "console.example.com".parse().unwrap());
+        let uri: Uri = "/ui/vms?x=1".parse().unwrap();
+
+        let res = redirect_to_https(&headers, &uri, 443);
+        assert_eq!(res.headers()[header::LOCATION], "https://console.example.com/ui/vms?x=1");
+        let res = redirect_to_https(&headers, &uri, 8443);
+        assert_eq!(res.headers()[header::LOCATION], "https://console.example.com:8443/ui/vms?x=1");
+    }
+}
@@ -0,0 +1,330 @@
+//! Admin Terminal
+//!
+//! A restricted host shell for the admin console. Command lines are parsed
+//! against a fixed allowlist and never reach a shell:
+//! - `qemu-img info <image>` for disk images in the store
+//! - `ls [dir]` of store directories
+//! - `tail [-n N] <log>` of daemon logs under `<store>/logs`
+//!
+//! Relative paths resolve against the store (or its `logs` directory for
+//! `tail`). Every path is canonicalized and must stay inside that root, so
+//! `..` and symlinks can't reach the rest of the host.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Longest command line accepted
+pub const MAX_COMMAND_LEN: usize = 1024;
+
+/// Output beyond this many bytes is cut off
+pub const MAX_OUTPUT: usize = 256 * 1024;
+
+/// Lines `tail` prints without `-n`
+pub const DEFAULT_TAIL_LINES: usize = 100;
+
+/// Most lines `tail -n` may ask for
+pub const MAX_TAIL_LINES: usize = 5000;
+
+/// How long `qemu-img info` may run, e.g. on an image on a hung mount
+const QEMU_IMG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Usage printed by `help` and on the first connect
+pub const HELP: &str = "\
+Allowed commands (paths are relative to the store):
+  qemu-img info <image>   show a disk image's format, size and backing file
+  ls [dir]                list a store directory
+  tail [-n N] <log>       last N lines (default 100) of a log in logs/
+  help                    show this message
+";
+
+/// A command line that passed the allowlist, with its paths resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalCommand {
+    Help,
+    QemuImgInfo(PathBuf),
+    Ls(PathBuf),
+    Tail { path: PathBuf, lines: usize },
+}
+
+impl TerminalCommand {
+    /// Check a command line against the allowlist
+    ///
+    /// The error explains why the line was refused and is meant for the user.
+    pub fn parse(line: &str, store: &Path) -> Result<Self, String> {
+        if line.len() > MAX_COMMAND_LEN {
+            return Err(format!("command longer than {} bytes", MAX_COMMAND_LEN));
+        }
+        let args: Vec<&str> = line.split_whitespace().collect();
+        match args.as_slice() {
+            ["help"] => Ok(Self::Help),
+            ["qemu-img", "info", image] => {
+                let path = resolve(store, image)?;
+                if !path.is_file() {
+                    return Err(format!("{}: not a file", image));
+                }
+                Ok(Self::QemuImgInfo(path))
+            }
+            ["qemu-img", ..] => Err("only `qemu-img info <image>` is allowed".to_string()),
+            ["ls"] => Ok(Self::Ls(resolve(store, ".")?)),
+            ["ls", dir] => {
+                let path = resolve(store, dir)?;
+                if !path.is_dir() {
+                    return Err(format!("{}: not a directory", dir));
+                }
+                Ok(Self::Ls(path))
+            }
+            ["tail", rest @ ..] => {
+                let (lines, log) = match rest {
+                    [log] => (DEFAULT_TAIL_LINES, log),
+                    ["-n", n, log] => (parse_tail_lines(n)?, log),
+                    [n, log] if n.starts_with("-n") => (parse_tail_lines(&n[2..])?, log),
+                    _ => return Err("usage: tail [-n N] <log>".to_string()),
+                };
+                let logs = store.join("logs");
+                let path = resolve(&logs, log.strip_prefix("logs/").unwrap_or(log))?;
+                if !path.is_file() {
+                    return Err(format!("{}: not a file", log));
+                }
+                Ok(Self::Tail { path, lines })
+            }
+            [] => Err("empty command".to_string()),
+            [cmd, ..] => Err(format!("{}: command not allowed (try `help`)", cmd)),
+        }
+    }
+
+    /// Run the command, returning its output
+    pub async fn run(&self) -> Result<String, String> {
+        let output = match self {
+            Self::Help => Ok(HELP.to_string()),
+            Self::QemuImgInfo(path) => qemu_img_info(path).await,
+            Self::Ls(path) => list_dir(path),
+            Self::Tail { path, lines } => tail(path, *lines),
+        }?;
+        Ok(truncate_output(output))
+    }
+}
+
+fn parse_tail_lines(n: &str) -> Result<usize, String> {
+    match n.parse::<usize>() {
+        Ok(n) if (1..=MAX_TAIL_LINES).contains(&n) => Ok(n),
+        _ => Err(format!("tail: line count must be 1-{}", MAX_TAIL_LINES)),
+    }
+}
+
+/// Resolve `arg` against `root`, refusing anything that ends up outside it
+fn resolve(root: &Path, arg: &str) -> Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", root.display(), e))?;
+    // An absolute `arg` replaces the root; it is still checked below
+    let path = root
+        .join(arg)
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", arg, e))?;
+    if !path.starts_with(&root) {
+        return Err(format!("{}: outside {}", arg, root.display()));
+    }
+    Ok(path)
+}
+
+async fn qemu_img_info(path: &Path) -> Result<String, String> {
+    // -U reads images a running VM holds locked
+    let run = tokio::process::Command::new("qemu-img")
+        .arg("info")
+        .arg("-U")
+        .arg(path)
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(QEMU_IMG_TIMEOUT, run)
+        .await
+        .map_err(|_| format!("qemu-img: timed out after {}s", QEMU_IMG_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("qemu-img: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr)
+            .trim_end()
+            .to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn list_dir(path: &Path) -> Result<String, String> {
+    let entries = std::fs::read_dir(path).map_err(|e| format!("ls: {}", e))?;
+    let mut rows: Vec<(String, bool, u64)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some((
+                e.file_name().to_string_lossy().into_owned(),
+                meta.is_dir(),
+                meta.len(),
+            ))
+        })
+        .collect();
+    rows.sort();
+
+    let mut out = String::new();
+    for (name, is_dir, size) in rows {
+        if is_dir {
+            out.push_str(&format!("{:>12}  {}/\n", "-", name));
+        } else {
+            out.push_str(&format!("{:>12}  {}\n", size, name));
+        }
+    }
+    Ok(out)
+}
+
+/// Last `lines` lines of a file, reading no more than `MAX_OUTPUT` bytes of it
+fn tail(path: &Path, lines: usize) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("tail: {}", e))?;
+    let len = file.metadata().map_err(|e| format!("tail: {}", e))?.len();
+    let start = len.saturating_sub(MAX_OUTPUT as u64);
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("tail: {}", e))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)
+        .map_err(|e| format!("tail: {}", e))?;
+
+    let text = String::from_utf8_lossy(&buf);
+    let all: Vec<&str> = text.lines().collect();
+    // A partial first line is dropped unless it is the start of the file
+    let skip_partial = usize::from(start > 0 && !all.is_empty());
+    let from = all.len().saturating_sub(lines).max(skip_partial);
+    let mut out = all[from..].join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() > MAX_OUTPUT {
+        let mut end = MAX_OUTPUT;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.truncate(end);
+        output.push_str("\n[output truncated]\n");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("terminal-test-{}", rand::random::<u32>()));
+        std::fs::create_dir_all(dir.join("logs")).unwrap();
+        std::fs::create_dir_all(dir.join("disks")).unwrap();
+        std::fs::write(dir.join("disks/vm1.qcow2"), b"QFI").unwrap();
+        let log: String = (1..=200).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.join("logs/vm1.log"), log).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_parse_allowlist() {
+        let store = store();
+
+        assert_eq!(
+            TerminalCommand::parse("help", &store),
+            Ok(TerminalCommand::Help)
+        );
+        assert_eq!(
+            TerminalCommand::parse("ls", &store),
+            Ok(TerminalCommand::Ls(store.clone()))
+        );
+        assert_eq!(
+            TerminalCommand::parse("  ls   disks ", &store),
+            Ok(TerminalCommand::Ls(store.join("disks")))
+        );
+        assert_eq!(
+            TerminalCommand::parse("qemu-img info disks/vm1.qcow2", &store),
+            Ok(TerminalCommand::QemuImgInfo(store.join("disks/vm1.qcow2")))
+        );
+        let log = store.join("logs/vm1.log");
+        assert_eq!(
+            TerminalCommand::parse("tail vm1.log", &store),
+            Ok(TerminalCommand::Tail {
+                path: log.clone(),
+                lines: DEFAULT_TAIL_LINES
+            })
+        );
+        assert_eq!(
+            TerminalCommand::parse("tail -n 5 logs/vm1.log", &store),
+            Ok(TerminalCommand::Tail {
+                path: log.clone(),
+                lines: 5
+            })
+        );
+        assert_eq!(
+            TerminalCommand::parse("tail -n20 vm1.log", &store),
+            Ok(TerminalCommand::Tail {
+                path: log,
+                lines: 20
+            })
+        );
+
+        for refused in [
+            "",
+            "rm -rf /",
+            "sh -c ls",
+            "ls; cat /etc/passwd",
+            "qemu-img convert disks/vm1.qcow2 out.raw",
+            "qemu-img info disks",
+            "ls disks/vm1.qcow2",
+            "tail -n 0 vm1.log",
+            "tail -n 99999 vm1.log",
+            "tail -f vm1.log",
+            "tail ../disks/vm1.qcow2",
+        ] {
+            assert!(
+                TerminalCommand::parse(refused, &store).is_err(),
+                "{:?} allowed",
+                refused
+            );
+        }
+        let _ = std::fs::remove_dir_all(store);
+    }
+
+    #[test]
+    fn test_paths_stay_in_store() {
+        let store = store();
+        std::os::unix::fs::symlink("/etc", store.join("etc")).unwrap();
+
+        for escape in [
+            "ls ..",
+            "ls /etc",
+            "ls disks/../..",
+            "ls etc",
+            "tail /etc/hostname",
+        ] {
+            let err = TerminalCommand::parse(escape, &store).unwrap_err();
+            assert!(
+                err.contains("outside") || err.contains("No such file"),
+                "{}: {}",
+                escape,
+                err
+            );
+        }
+        // An absolute path inside the store is fine
+        let disks = format!("ls {}", store.join("disks").display());
+        assert!(TerminalCommand::parse(&disks, &store).is_ok());
+        let _ = std::fs::remove_dir_all(store);
+    }
+
+    #[tokio::test]
+    async fn test_run_ls_and_tail() {
+        let store = store();
+
+        let listing = TerminalCommand::Ls(store.clone()).run().await.unwrap();
+        assert_eq!(listing.lines().count(), 2);
+        assert!(listing.contains("disks/"));
+        assert!(listing.contains("logs/"));
+
+        let tail = TerminalCommand::parse("tail -n 3 vm1.log", &store).unwrap();
+        assert_eq!(tail.run().await.unwrap(), "line 198\nline 199\nline 200\n");
+        let _ = std::fs::remove_dir_all(store);
+    }
+}
@@ -0,0 +1,161 @@
+//! Cookie sessions for browser clients.
+//!
+//! Instead of handing the session token to the console's JavaScript, logins
+//! set it as an `HttpOnly` cookie. Mutating requests carried by that cookie
+//! must repeat the readable CSRF cookie in the `x-csrf-token` header
+//! (double submit), which a cross-site page can't do.
+
+use axum::http::{header, HeaderMap, Method};
+
+/// Cookie holding the session token, hidden from scripts
+pub const SESSION_COOKIE: &str = "infrasim_session";
+
+/// Cookie holding the CSRF token, readable by the console
+pub const CSRF_COOKIE: &str = "infrasim_csrf";
+
+/// Header mutating requests repeat the CSRF token in
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Cross-site requests the session cookie is sent on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SameSite {
+    /// Only same-site requests
+    Strict,
+    /// Same-site requests and top-level navigations, e.g. an SSO redirect back
+    Lax,
+}
+
+impl SameSite {
+    fn as_str(self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+        }
+    }
+}
+
+impl std::str::FromStr for SameSite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "strict" => Ok(SameSite::Strict),
+            "lax" => Ok(SameSite::Lax),
+            other => Err(format!("unknown SameSite policy '{}' (strict or lax)", other)),
+        }
+    }
+}
+
+/// Attributes of the session and CSRF cookies
+#[derive(Clone, Debug)]
+pub struct CookieSessionConfig {
+    /// Only send the cookies over HTTPS; turn off for plain-HTTP localhost
+    pub secure: bool,
+    pub same_site: SameSite,
+}
+
+impl Default for CookieSessionConfig {
+    fn default() -> Self {
+        Self {
+            secure: true,
+            same_site: SameSite::Lax,
+        }
+    }
+}
+
+impl CookieSessionConfig {
+    /// `Set-Cookie` value for a session lasting `max_age` seconds
+    pub fn session_cookie(&self, token: &str, max_age: i64) -> String {
+        format!("{}={}; HttpOnly; {}", SESSION_COOKIE, token, self.attributes(max_age))
+    }
+
+    /// `Set-Cookie` value for a CSRF token; scripts must read it, so it
+    /// isn't `HttpOnly`
+    pub fn csrf_cookie(&self, token: &str, max_age: i64) -> String {
+        format!("{}={}; {}", CSRF_COOKIE, token, self.attributes(max_age))
+    }
+
+    /// `Set-Cookie` values removing both cookies
+    pub fn clear_cookies(&self) -> [String; 2] {
+        [
+            format!("{}=; HttpOnly; {}", SESSION_COOKIE, self.attributes(0)),
+            format!("{}=; {}", CSRF_COOKIE, self.attributes(0)),
+        ]
+    }
+
+    fn attributes(&self, max_age: i64) -> String {
+        let secure = if self.secure { "; Secure" } else { "" };
+        format!("Path=/; Max-Age={}; SameSite={}{}", max_age.max(0), self.same_site.as_str(), secure)
+    }
+}
+
+/// A fresh random CSRF token
+pub fn new_csrf_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Value of a request cookie
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// Whether a request can change state and so needs a CSRF token
+pub fn is_mutating(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Whether the CSRF header matches the CSRF cookie
+pub fn csrf_valid(headers: &HeaderMap) -> bool {
+    let sent = headers.get(CSRF_HEADER).and_then(|v| v.to_str().ok());
+    match (sent, cookie(headers, CSRF_COOKIE)) {
+        (Some(sent), Some(expected)) => crate::server::constant_time_eq(sent, expected),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_cookie_attributes() {
+        let cfg = CookieSessionConfig::default();
+        assert_eq!(
+            cfg.session_cookie("abc", 60),
+            "infrasim_session=abc; HttpOnly; Path=/; Max-Age=60; SameSite=Lax; Secure"
+        );
+        let cfg = CookieSessionConfig { secure: false, same_site: SameSite::Strict };
+        assert_eq!(cfg.csrf_cookie("xyz", 60), "infrasim_csrf=xyz; Path=/; Max-Age=60; SameSite=Strict");
+        assert!(cfg.clear_cookies().iter().all(|c| c.contains("Max-Age=0")));
+    }
+
+    #[test]
+    fn test_csrf_double_submit() {
+        let cookies = "theme=dark; infrasim_session=s1; infrasim_csrf=t1";
+        assert_eq!(cookie(&headers(&[("cookie", cookies)]), SESSION_COOKIE), Some("s1"));
+        assert!(csrf_valid(&headers(&[("cookie", cookies), ("x-csrf-token", "t1")])));
+        assert!(!csrf_valid(&headers(&[("cookie", cookies), ("x-csrf-token", "t2")])));
+        assert!(!csrf_valid(&headers(&[("cookie", cookies)])));
+        assert!(!csrf_valid(&headers(&[("cookie", "infrasim_session=s1"), ("x-csrf-token", "")])));
+
+        assert!(is_mutating(&Method::POST));
+        assert!(!is_mutating(&Method::GET));
+    }
+}
@@ -8,14 +8,17 @@
 //! All providers integrate with a unified RBAC system that can be
 //! exported as Terraform resources for auditing.
 
+pub mod oidc;
 pub mod provider;
 pub mod rbac;
+pub mod routes;
 pub mod types;
+pub mod webauthn;
+pub mod cookies;
 // These modules require additional setup
-// pub mod webauthn;
-// pub mod oidc;
 // pub mod middleware;
 
 pub use provider::{AuthProvider, AuthProviderConfig, AuthManager, OidcConfig};
 pub use rbac::{Role, Permission, Policy, PolicyEngine};
+pub use routes::{route_permission, ADMIN_PERMISSION};
 pub use types::*;
@@ -1,34 +1,153 @@
 //! OIDC authentication provider for Keycloak, Auth0, etc.
 
 use async_trait::async_trait;
+use data_encoding::BASE64URL_NOPAD;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
 use super::types::*;
 use super::provider::{AuthProvider, OidcConfig};
 
+/// Prefix of identity IDs federated through OIDC
+pub const IDENTITY_PREFIX: &str = "oidc:";
+
+/// How long a login redirect can be completed
+const STATE_TTL_SECS: i64 = 600;
+
 /// OIDC token response
 #[derive(Debug, Deserialize)]
 struct TokenResponse {
     access_token: String,
-    token_type: String,
-    expires_in: Option<u64>,
     refresh_token: Option<String>,
     id_token: Option<String>,
 }
 
-/// OIDC userinfo response
-#[derive(Debug, Deserialize)]
-struct UserInfo {
-    sub: String,
-    #[serde(alias = "preferred_username")]
-    name: Option<String>,
-    email: Option<String>,
-    #[serde(default)]
-    groups: Vec<String>,
-    #[serde(default)]
-    roles: Vec<String>,
+/// Claims about the signed-in user
+#[derive(Debug, Clone, Serialize)]
+pub struct UserInfo {
+    pub sub: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    /// Groups or roles from the configured claim
+    pub groups: Vec<String>,
+}
+
+impl UserInfo {
+    /// Read user claims, taking the name and groups from the configured claims
+    pub fn from_claims(claims: &Value, config: &OidcConfig) -> Result<Self, String> {
+        let string = |name: &str| claim(claims, name).and_then(Value::as_str).map(str::to_string);
+        let sub = string("sub").ok_or_else(|| "Userinfo has no subject".to_string())?;
+        let name = string(&config.name_claim)
+            .or_else(|| string("preferred_username"))
+            .or_else(|| string("name"));
+
+        let groups = match &config.roles_claim {
+            Some(roles_claim) => claim(claims, roles_claim),
+            None => claims.get("groups").or_else(|| claims.get("roles")),
+        };
+        let groups = match groups {
+            Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            Some(Value::String(items)) => items
+                .split([',', ' '])
+                .filter(|g| !g.is_empty())
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(Self { sub, name, email: string("email"), groups })
+    }
+
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().or(self.email.as_deref()).unwrap_or(&self.sub)
+    }
+}
+
+/// A claim by name, or by dotted path into nested claims like Keycloak's
+/// `realm_access.roles`
+fn claim<'a>(claims: &'a Value, name: &str) -> Option<&'a Value> {
+    claims
+        .get(name)
+        .or_else(|| name.split('.').try_fold(claims, |value, key| value.get(key)))
+}
+
+/// RBAC roles for a user's OIDC groups
+///
+/// Keycloak group paths match mappings with or without their leading `/`.
+pub fn map_roles(groups: &[String], config: &OidcConfig) -> Vec<String> {
+    let mut roles: Vec<String> = if config.role_mappings.is_empty() {
+        groups.to_vec()
+    } else {
+        config
+            .role_mappings
+            .iter()
+            .filter(|(mapped, _)| {
+                groups
+                    .iter()
+                    .any(|group| group.trim_start_matches('/') == mapped.trim_start_matches('/'))
+            })
+            .map(|(_, role)| role.clone())
+            .collect()
+    };
+    roles.sort();
+    roles.dedup();
+    if roles.is_empty() {
+        roles.push(config.default_role.clone());
+    }
+    roles
+}
+
+/// Parse `group=role` pairs separated by commas
+pub fn parse_role_mappings(spec: &str) -> HashMap<String, String> {
+    spec.split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(group, role)| (group.trim().to_string(), role.trim().to_string()))
+        .filter(|(group, role)| !group.is_empty() && !role.is_empty())
+        .collect()
+}
+
+/// Check an ID token against the login that requested it
+///
+/// The token comes straight from the token endpoint over TLS, so as OIDC Core
+/// 3.1.3.7 allows, its signature is not checked.
+fn check_id_token(id_token: &str, issuer: &str, client_id: &str, nonce: &str, now: i64) -> Result<Value, String> {
+    let claims: Value = id_token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| BASE64URL_NOPAD.decode(payload.trim_end_matches('=').as_bytes()).ok())
+        .and_then(|payload| serde_json::from_slice(&payload).ok())
+        .ok_or_else(|| "Malformed ID token".to_string())?;
+
+    if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+        return Err("ID token issuer mismatch".to_string());
+    }
+    let audience_ok = match claims.get("aud") {
+        Some(Value::String(aud)) => aud == client_id,
+        Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(client_id)),
+        _ => false,
+    };
+    if !audience_ok {
+        return Err("ID token audience mismatch".to_string());
+    }
+    if claims.get("exp").and_then(Value::as_i64).is_none_or(|exp| exp <= now) {
+        return Err("ID token expired".to_string());
+    }
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        return Err("ID token nonce mismatch".to_string());
+    }
+    Ok(claims)
+}
+
+/// A completed login or token refresh
+#[derive(Debug, Clone)]
+pub struct OidcGrant {
+    pub user: UserInfo,
+    /// Refresh token, when the provider issued one
+    pub refresh_token: Option<String>,
 }
 
 /// OIDC provider state
@@ -46,7 +165,7 @@ pub struct OidcProvider {
     config: OidcConfig,
     http_client: reqwest::Client,
     /// Pending auth states
-    pending_states: RwLock<std::collections::HashMap<String, OidcProviderState>>,
+    pending_states: RwLock<HashMap<String, OidcProviderState>>,
     /// Discovered endpoints (cached)
     discovery: RwLock<Option<OidcDiscovery>>,
 }
@@ -58,9 +177,10 @@ struct OidcDiscovery {
     authorization_endpoint: String,
     token_endpoint: String,
     userinfo_endpoint: String,
-    jwks_uri: String,
-    #[serde(default)]
-    end_session_endpoint: Option<String>,
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
 }
 
 impl OidcProvider {
@@ -68,11 +188,15 @@ impl OidcProvider {
         Self {
             config,
             http_client: reqwest::Client::new(),
-            pending_states: RwLock::new(std::collections::HashMap::new()),
+            pending_states: RwLock::new(HashMap::new()),
             discovery: RwLock::new(None),
         }
     }
 
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
     /// Discover OIDC endpoints
     async fn discover(&self) -> Result<OidcDiscovery, String> {
         // Check cache
@@ -84,7 +208,10 @@ impl OidcProvider {
         }
 
         // Fetch discovery document
-        let discovery_url = format!("{}/.well-known/openid-configuration", self.config.issuer);
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer.trim_end_matches('/')
+        );
         let resp = self.http_client
             .get(&discovery_url)
             .send()
@@ -109,10 +236,6 @@ impl OidcProvider {
 
     /// Generate authorization URL for login redirect
     pub async fn authorization_url(&self) -> Result<(String, String), String> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        use data_encoding::BASE64URL_NOPAD;
-        use sha2::{Sha256, Digest};
-
         let disc = self.discover().await?;
 
         let state = uuid::Uuid::new_v4().to_string();
@@ -121,21 +244,20 @@ impl OidcProvider {
         // Generate PKCE
         let verifier_bytes: [u8; 32] = rand::random();
         let pkce_verifier = BASE64URL_NOPAD.encode(&verifier_bytes);
-        let mut hasher = Sha256::new();
-        hasher.update(pkce_verifier.as_bytes());
-        let challenge = BASE64URL_NOPAD.encode(&hasher.finalize());
+        let challenge = BASE64URL_NOPAD.encode(&Sha256::digest(pkce_verifier.as_bytes()));
 
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let now = now_secs();
 
-        // Store state
+        // Store state, dropping abandoned logins
         {
             let mut states = self.pending_states.write().await;
+            states.retain(|_, pending| pending.expires_at > now);
             states.insert(state.clone(), OidcProviderState {
                 state: state.clone(),
                 nonce: nonce.clone(),
-                pkce_verifier: pkce_verifier.clone(),
+                pkce_verifier,
                 redirect_uri: self.config.redirect_uri.clone(),
-                expires_at: now + 600, // 10 minutes
+                expires_at: now + STATE_TTL_SECS,
             });
         }
 
@@ -154,12 +276,10 @@ impl OidcProvider {
         Ok((url, state))
     }
 
-    /// Exchange authorization code for tokens
-    pub async fn exchange_code(&self, code: &str, state: &str) -> Result<UserInfo, String> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
+    /// Exchange authorization code for tokens and the user's claims
+    pub async fn exchange_code(&self, code: &str, state: &str) -> Result<OidcGrant, String> {
         let disc = self.discover().await?;
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let now = now_secs();
 
         // Get and remove pending state
         let pending = {
@@ -171,17 +291,53 @@ impl OidcProvider {
             return Err("Authorization expired".to_string());
         }
 
-        // Exchange code for tokens
-        let token_resp = self.http_client
-            .post(&disc.token_endpoint)
-            .form(&[
+        let tokens = self
+            .request_tokens(&disc, &[
                 ("grant_type", "authorization_code"),
-                ("client_id", &self.config.client_id),
-                ("client_secret", &self.config.client_secret),
                 ("code", code),
                 ("redirect_uri", &pending.redirect_uri),
                 ("code_verifier", &pending.pkce_verifier),
             ])
+            .await?;
+
+        let id_token = tokens.id_token.as_deref().ok_or_else(|| "Token response has no ID token".to_string())?;
+        let id_claims = check_id_token(id_token, &disc.issuer, &self.config.client_id, &pending.nonce, now)?;
+
+        let user = self.userinfo(&disc, &tokens.access_token).await?;
+        if id_claims.get("sub").and_then(Value::as_str) != Some(user.sub.as_str()) {
+            return Err("Userinfo subject does not match the ID token".to_string());
+        }
+
+        Ok(OidcGrant { user, refresh_token: tokens.refresh_token })
+    }
+
+    /// Refresh tokens, re-reading the user's claims
+    ///
+    /// Providers that don't rotate refresh tokens keep the one passed in.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<OidcGrant, String> {
+        let disc = self.discover().await?;
+        let tokens = self
+            .request_tokens(&disc, &[("grant_type", "refresh_token"), ("refresh_token", refresh_token)])
+            .await?;
+        let user = self.userinfo(&disc, &tokens.access_token).await?;
+
+        Ok(OidcGrant {
+            user,
+            refresh_token: tokens.refresh_token.or_else(|| Some(refresh_token.to_string())),
+        })
+    }
+
+    async fn request_tokens(&self, disc: &OidcDiscovery, params: &[(&str, &str)]) -> Result<TokenResponse, String> {
+        let mut form = vec![("client_id", self.config.client_id.as_str())];
+        // Public clients rely on PKCE alone
+        if !self.config.client_secret.is_empty() {
+            form.push(("client_secret", &self.config.client_secret));
+        }
+        form.extend_from_slice(params);
+
+        let token_resp = self.http_client
+            .post(&disc.token_endpoint)
+            .form(&form)
             .send()
             .await
             .map_err(|e| format!("Token exchange failed: {}", e))?;
@@ -191,13 +347,14 @@ impl OidcProvider {
             return Err(format!("Token exchange failed: {}", err));
         }
 
-        let tokens: TokenResponse = token_resp.json().await
-            .map_err(|e| format!("Token parse failed: {}", e))?;
+        token_resp.json().await
+            .map_err(|e| format!("Token parse failed: {}", e))
+    }
 
-        // Get user info
+    async fn userinfo(&self, disc: &OidcDiscovery, access_token: &str) -> Result<UserInfo, String> {
         let userinfo_resp = self.http_client
             .get(&disc.userinfo_endpoint)
-            .bearer_auth(&tokens.access_token)
+            .bearer_auth(access_token)
             .send()
             .await
             .map_err(|e| format!("Userinfo failed: {}", e))?;
@@ -206,36 +363,21 @@ impl OidcProvider {
             return Err("Failed to get user info".to_string());
         }
 
-        let userinfo: UserInfo = userinfo_resp.json().await
+        let claims: Value = userinfo_resp.json().await
             .map_err(|e| format!("Userinfo parse failed: {}", e))?;
 
-        Ok(userinfo)
+        UserInfo::from_claims(&claims, &self.config)
     }
 
     /// Map OIDC user to local identity
     pub fn map_to_identity(&self, userinfo: &UserInfo) -> Identity {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
-        
-        // Map roles from OIDC claims
-        let mut roles = userinfo.roles.clone();
-        if roles.is_empty() {
-            roles = userinfo.groups.clone();
-        }
-        if roles.is_empty() {
-            roles = vec!["viewer".to_string()];
-        }
-
-        let display_name = userinfo.name.clone()
-            .or(userinfo.email.clone())
-            .unwrap_or_else(|| userinfo.sub.clone());
+        let now = now_secs();
 
         Identity {
-            id: format!("oidc:{}", userinfo.sub),
-            display_name,
+            id: format!("{}{}", IDENTITY_PREFIX, userinfo.sub),
+            display_name: userinfo.display_name().to_string(),
             email: userinfo.email.clone(),
-            roles,
+            roles: map_roles(&userinfo.groups, &self.config),
             provider: AuthProviderType::Oidc,
             created_at: now,
             last_login_at: Some(now),
@@ -280,8 +422,8 @@ impl AuthProvider for OidcProvider {
             .and_then(|v| v.as_str())
             .ok_or_else(|| "Missing state".to_string())?;
 
-        let userinfo = self.exchange_code(code, state).await?;
-        let identity = self.map_to_identity(&userinfo);
+        let grant = self.exchange_code(code, state).await?;
+        let identity = self.map_to_identity(&grant.user);
 
         Ok(AuthResult {
             success: true,
@@ -305,3 +447,75 @@ impl AuthProvider for OidcProvider {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(role_mappings: &str) -> OidcConfig {
+        serde_json::from_value(json!({
+            "provider": "keycloak",
+            "issuer": "https://sso.example.com/realms/infrasim",
+            "client_id": "infrasim-console",
+            "client_secret": "",
+            "redirect_uri": "http://localhost:8080/api/auth/oidc/callback",
+            "roles_claim": "realm_access.roles",
+            "role_mappings": parse_role_mappings(role_mappings),
+        }))
+        .unwrap()
+    }
+
+    fn id_token(claims: Value) -> String {
+        let payload = BASE64URL_NOPAD.encode(claims.to_string().as_bytes());
+        format!("e30.{}.signature", payload)
+    }
+
+    #[test]
+    fn test_keycloak_roles_map_to_rbac_roles() {
+        let config = config("infrasim-admins=admin, /ops=operator");
+        let user = UserInfo::from_claims(
+            &json!({
+                "sub": "7f1c",
+                "preferred_username": "alice",
+                "realm_access": {"roles": ["infrasim-admins", "offline_access"]},
+            }),
+            &config,
+        )
+        .unwrap();
+        assert_eq!(user.display_name(), "alice");
+        assert_eq!(map_roles(&user.groups, &config), vec!["admin"]);
+
+        assert_eq!(map_roles(&["/ops".to_string()], &config), vec!["operator"]);
+        assert_eq!(map_roles(&["ops".to_string()], &config), vec!["operator"]);
+        assert_eq!(map_roles(&["guests".to_string()], &config), vec!["viewer"]);
+    }
+
+    #[test]
+    fn test_unmapped_groups_pass_through() {
+        let config = config("");
+        assert_eq!(
+            map_roles(&["operator".to_string(), "builder".to_string(), "operator".to_string()], &config),
+            vec!["builder", "operator"]
+        );
+    }
+
+    #[test]
+    fn test_id_token_must_match_login() {
+        let issuer = "https://sso.example.com/realms/infrasim";
+        let token = id_token(json!({
+            "iss": issuer,
+            "aud": ["infrasim-console", "account"],
+            "sub": "7f1c",
+            "exp": 2_000,
+            "nonce": "n-1",
+        }));
+
+        assert!(check_id_token(&token, issuer, "infrasim-console", "n-1", 1_000).is_ok());
+        assert!(check_id_token(&token, issuer, "infrasim-console", "n-2", 1_000).is_err());
+        assert!(check_id_token(&token, issuer, "other-client", "n-1", 1_000).is_err());
+        assert!(check_id_token(&token, "https://evil.example.com", "infrasim-console", "n-1", 1_000).is_err());
+        assert!(check_id_token(&token, issuer, "infrasim-console", "n-1", 2_000).is_err());
+        assert!(check_id_token("not-a-jwt", issuer, "infrasim-console", "n-1", 1_000).is_err());
+    }
+}
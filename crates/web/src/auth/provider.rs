@@ -95,8 +95,14 @@ pub struct OidcConfig {
     /// Claim to use as display name
     #[serde(default = "default_name_claim")]
     pub name_claim: String,
-    /// Claim to use for role mapping
+    /// Claim to use for role mapping, e.g. `realm_access.roles`
     pub roles_claim: Option<String>,
+    /// RBAC role granted for each OIDC group or role; names pass through unmapped when empty
+    #[serde(default)]
+    pub role_mappings: std::collections::HashMap<String, String>,
+    /// Role for users that map to none
+    #[serde(default = "default_role")]
+    pub default_role: String,
 }
 
 fn default_scopes() -> Vec<String> {
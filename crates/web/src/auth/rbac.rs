@@ -174,14 +174,31 @@ impl PolicyEngine {
                         "vm:start".to_string(),
                         "vm:stop".to_string(),
                         "vm:console".to_string(),
+                        "vm:export".to_string(),
+                        "recording:read".to_string(),
                         "appliance:read".to_string(),
+                        "appliance:archive:read".to_string(),
                         "appliance:create".to_string(),
                         "appliance:boot".to_string(),
                         "appliance:stop".to_string(),
+                        "appliance:snapshot".to_string(),
+                        "template:sync".to_string(),
+                        "filesystem:create".to_string(),
+                        "filesystem:update".to_string(),
+                        "filesystem:attach".to_string(),
+                        "filesystem:snapshot".to_string(),
+                        "graph:apply".to_string(),
+                        "project:create".to_string(),
+                        "project:update".to_string(),
+                        "provenance:attest".to_string(),
                         "image:read".to_string(),
                         "image:pull".to_string(),
+                        "snapshot:browse".to_string(),
                         "network:read".to_string(),
+                        "network:capture".to_string(),
                         "config:read".to_string(),
+                        "job:create".to_string(),
+                        "job:cancel".to_string(),
                     ],
                     inherits: vec!["viewer".to_string()],
                     terraform_address: Some("infrasim_rbac_role.operator".to_string()),
@@ -193,10 +210,16 @@ impl PolicyEngine {
                     permissions: vec![
                         "vm:read".to_string(),
                         "appliance:read".to_string(),
+                        "filesystem:read".to_string(),
+                        "graph:read".to_string(),
+                        "project:read".to_string(),
                         "image:read".to_string(),
                         "network:read".to_string(),
                         "config:read".to_string(),
                         "audit:read".to_string(),
+                        "job:read".to_string(),
+                        "analysis:read".to_string(),
+                        "preferences:manage".to_string(),
                     ],
                     inherits: vec![],
                     terraform_address: Some("infrasim_rbac_role.viewer".to_string()),
@@ -214,6 +237,8 @@ impl PolicyEngine {
                         "overlay:create".to_string(),
                         "overlay:read".to_string(),
                         "overlay:delete".to_string(),
+                        "template:publish".to_string(),
+                        "analysis:manage".to_string(),
                     ],
                     inherits: vec!["viewer".to_string()],
                     terraform_address: Some("infrasim_rbac_role.builder".to_string()),
@@ -0,0 +1,189 @@
+//! Permissions required by web API routes.
+//!
+//! The auth middleware checks every authenticated request against
+//! [`route_permission`]. Routes missing from the table need
+//! [`ADMIN_PERMISSION`], which only the admin role's `*` grants.
+
+/// Permission reserved to administrators
+pub const ADMIN_PERMISSION: &str = "system:admin";
+
+/// Permission needed to call `method` on `path`
+pub fn route_permission(method: &str, path: &str) -> &'static str {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let read = matches!(method, "GET" | "HEAD");
+
+    match segments.as_slice() {
+        ["api", "admin", ..] => ADMIN_PERMISSION,
+
+        // VMs
+        // Console users can type anything into the guest, uploads included
+        ["api", "vms", _, "vnc" | "files"] | ["console", "serial", _] => "vm:console",
+        // An export is the guest's whole disk
+        ["api", "vms", _, "export"] if read => "vm:export",
+        ["api", "vms", ..] | ["api", "events"] if read => "vm:read",
+        ["api", "vms"] => "vm:create",
+        ["api", "vms", _, "start"] => "vm:start",
+        // Restarting also needs `vm:start`, checked by the handler
+        ["api", "vms", _, "stop" | "restart"] => "vm:stop",
+        ["api", "vms", _] | ["api", "vms", _, "delete"] => "vm:delete",
+
+        // Console recordings hold everything typed, passwords included
+        ["api", "recordings", ..] | ["console", "replay", _] if read => "recording:read",
+        ["api", "recordings", ..] => "recording:manage",
+
+        // Appliances and their templates
+        // Archives carry the appliance's disks
+        ["api", "appliances", "archives", _] if read => "appliance:archive:read",
+        ["api", "appliances", ..] if read => "appliance:read",
+        ["api", "appliances", "templates", "sync"] => "template:sync",
+        ["api", "appliances", "templates", "packs", ..] => "template:publish",
+        ["api", "appliances"] | ["api", "appliances", "seed" | "import"] | ["api", "ai", "define"] => {
+            "appliance:create"
+        }
+        ["api", "appliances", _, "boot"] => "appliance:boot",
+        ["api", "appliances", _, "stop"] => "appliance:stop",
+        ["api", "appliances", _, "snapshot"] => "appliance:snapshot",
+        ["api", "appliances", _, "archive"] => "appliance:delete",
+        ["api", "appliances", "archives", _, "restore"] => "appliance:create",
+
+        // Filesystems and the resource graph
+        ["api", "filesystems", ..] if read => "filesystem:read",
+        ["api", "filesystems"] => "filesystem:create",
+        ["api", "filesystems", _] if method == "PUT" => "filesystem:update",
+        ["api", "filesystems", _] => "filesystem:delete",
+        ["api", "filesystems", _, "snapshot"] => "filesystem:snapshot",
+        ["api", "filesystems", _, "attach" | "detach", ..] => "filesystem:attach",
+        // Applying also needs the filesystem permission of each change, checked by the handler
+        ["api", "graph", "apply"] => "graph:apply",
+        ["api", "graph", ..] => "graph:read",
+
+        // Images and inventory
+        ["api", "images" | "volumes" | "snapshots" | "snapshot-policies" | "docker", ..] if read => "image:read",
+        ["api", "docker", "images", "pull"] => "image:pull",
+        ["api", "docker", "build"] => "image:build",
+        // Captures hold the guests' traffic
+        ["api", "networks", _, "capture.pcap"] if read => "network:capture",
+        ["api", "networks", ..] if read => "network:read",
+        // Guest files can hold secrets, so browsing them isn't plain image access
+        ["api", "snapshot-files", ..] if read => "snapshot:browse",
+
+        // Background jobs
+        ["api", "jobs", ..] if read => "job:read",
+        ["api", "jobs"] => "job:create",
+        ["api", "jobs", _, "cancel"] => "job:cancel",
+
+        // Projects, Terraform and provenance
+        ["api", "projects" | "prompts", ..] if read => "project:read",
+        ["api", "projects", _] if method == "PUT" => "project:update",
+        // Deleting a project deletes its prompts
+        ["api", "projects", _] => "project:delete",
+        ["api", "projects", ..] => "project:create",
+        ["api", "terraform", "generate"] | ["api", "rbac", ..] => "config:read",
+        ["api", "terraform", "audit"] | ["api", "provenance", "evidence" | "signing-key"] => "audit:read",
+        ["api", "audit"] if read => "audit:read",
+        ["api", "provenance", "attest"] => "provenance:attest",
+
+        // Watched repositories: registering one clones its URL or reads its
+        // host path, and posts risk events to its notify URL
+        ["api", "analysis", "repos", ..] if read => "analysis:read",
+        ["api", "analysis", "repos", ..] => "analysis:manage",
+
+        // Every identity keeps its own console preferences
+        ["api", "preferences"] => "preferences:manage",
+
+        // MDM profiles carry VPN and bridge credentials
+        ["api", "mdm", ..] | ["webhook", ..] if read => "mdm:read",
+        ["api", "mdm", ..] => "mdm:manage",
+
+        _ => ADMIN_PERMISSION,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::PolicyEngine;
+
+    fn allowed(role: &str, method: &str, path: &str) -> bool {
+        PolicyEngine::new().has_permission(&[role.to_string()], route_permission(method, path))
+    }
+
+    #[test]
+    fn test_viewer_reads_but_does_not_write() {
+        assert!(allowed("viewer", "GET", "/api/appliances"));
+        assert!(allowed("viewer", "GET", "/api/vms/vm-1"));
+        assert!(allowed("viewer", "GET", "/api/vms/vm-1/stats"));
+        assert!(allowed("viewer", "GET", "/api/filesystems"));
+        assert!(allowed("viewer", "GET", "/api/prompts"));
+        assert!(allowed("viewer", "GET", "/api/provenance/signing-key"));
+        assert!(allowed("viewer", "POST", "/api/graph/plan"));
+        assert!(allowed("viewer", "PUT", "/api/preferences"));
+        assert!(!allowed("viewer", "GET", "/api/recordings"));
+        assert!(!allowed("viewer", "POST", "/api/appliances"));
+        assert!(!allowed("viewer", "POST", "/api/vms/vm-1/start"));
+        assert!(!allowed("viewer", "POST", "/api/vms/vm-1/files"));
+        assert!(!allowed("viewer", "DELETE", "/api/filesystems/fs-1"));
+        assert!(!allowed("viewer", "POST", "/api/graph/apply"));
+        assert!(allowed("viewer", "GET", "/api/jobs/j-1/events"));
+        assert!(!allowed("viewer", "POST", "/api/jobs"));
+        assert!(allowed("viewer", "GET", "/api/snapshots/s-1"));
+        assert!(!allowed("viewer", "GET", "/api/snapshot-files/s-1"));
+        assert!(allowed("viewer", "GET", "/api/networks/n-1"));
+        assert!(!allowed("viewer", "GET", "/api/vms/vm-1/export"));
+        assert!(!allowed("viewer", "GET", "/api/networks/n-1/capture.pcap"));
+        assert!(!allowed("viewer", "GET", "/api/appliances/archives/abc"));
+        assert!(allowed("viewer", "GET", "/api/audit"));
+        assert!(!allowed("operator", "DELETE", "/api/audit"));
+    }
+
+    #[test]
+    fn test_operator_manages_but_does_not_delete() {
+        assert!(allowed("operator", "POST", "/api/appliances"));
+        assert!(allowed("operator", "POST", "/api/appliances/a-1/boot"));
+        assert!(allowed("operator", "POST", "/api/filesystems/fs-1/detach/a-1"));
+        assert!(allowed("operator", "GET", "/console/serial/vm-1"));
+        assert!(allowed("operator", "POST", "/api/vms/vm-1/files"));
+        assert!(!allowed("operator", "DELETE", "/api/vms/vm-1"));
+        assert!(allowed("operator", "GET", "/console/replay/rec-1"));
+        assert!(allowed("operator", "GET", "/api/recordings/rec-1/download"));
+        assert!(!allowed("operator", "PUT", "/api/recordings/policies/vm-1"));
+        assert!(!allowed("operator", "DELETE", "/api/recordings/rec-1"));
+        assert!(!allowed("operator", "POST", "/api/vms/vm-1/delete"));
+        assert!(!allowed("operator", "POST", "/api/appliances/templates/packs"));
+        assert!(allowed("operator", "PUT", "/api/projects/p-1"));
+        assert!(!allowed("operator", "DELETE", "/api/projects/p-1"));
+        assert!(allowed("builder", "POST", "/api/appliances/templates/packs"));
+        assert!(allowed("operator", "POST", "/api/jobs"));
+        assert!(allowed("operator", "POST", "/api/jobs/j-1/cancel"));
+        assert!(allowed("operator", "POST", "/api/appliances/archives/abc/restore"));
+        assert!(!allowed("operator", "POST", "/api/appliances/a-1/archive"));
+        assert!(allowed("operator", "GET", "/api/snapshot-files/s-1/content"));
+        assert!(allowed("operator", "GET", "/api/snapshot-files/diff"));
+        assert!(allowed("operator", "GET", "/api/vms/vm-1/export"));
+        assert!(allowed("operator", "GET", "/api/networks/n-1/capture.pcap"));
+        assert!(allowed("operator", "GET", "/api/appliances/archives/abc"));
+    }
+
+    #[test]
+    fn test_admin_routes_and_unknown_routes_need_admin() {
+        assert_eq!(route_permission("POST", "/api/admin/restart-web"), ADMIN_PERMISSION);
+        assert_eq!(route_permission("GET", "/api/something-new"), ADMIN_PERMISSION);
+        assert!(!allowed("operator", "GET", "/api/admin/status"));
+        assert!(allowed("admin", "POST", "/api/admin/stop-daemon"));
+        assert!(!allowed("operator", "GET", "/api/admin/terminal"));
+        assert!(allowed("admin", "GET", "/api/admin/terminal"));
+        assert!(!allowed("viewer", "GET", "/api/mdm/vpns"));
+    }
+
+    #[test]
+    fn test_builders_manage_watched_repositories() {
+        assert!(allowed("viewer", "GET", "/api/analysis/repos"));
+        assert!(allowed("viewer", "GET", "/api/analysis/repos/r-1/diffs"));
+        assert!(!allowed("viewer", "POST", "/api/analysis/repos"));
+        assert!(!allowed("operator", "POST", "/api/analysis/repos/r-1/analyze"));
+        assert!(!allowed("operator", "DELETE", "/api/analysis/repos/r-1"));
+        assert!(allowed("builder", "POST", "/api/analysis/repos"));
+        assert!(allowed("builder", "POST", "/api/analysis/repos/r-1/analyze"));
+        assert!(allowed("builder", "DELETE", "/api/analysis/repos/r-1"));
+    }
+}
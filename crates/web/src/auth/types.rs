@@ -168,4 +168,11 @@ pub enum AuthEventType {
     RoleChange,
     SessionExpired,
     AccessDenied,
+    AdminTerminal,
+    SessionRevoked,
+    RecoveryCodeUsed,
+    TotpReset,
+    IdentityDisabled,
+    IdentityEnabled,
+    IdentityDeleted,
 }
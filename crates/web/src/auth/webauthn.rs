@@ -5,11 +5,35 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use webauthn_rs::prelude::*;
-use infrasim_common::Database;
+use infrasim_common::{Database, Migration, MigrationUp, Migrations};
 
 use super::types::*;
 use super::provider::AuthProvider;
 
+/// Migrations of the WebAuthn tables; only ever append
+const MIGRATIONS: Migrations = Migrations {
+    component: "web.webauthn",
+    migrations: &[Migration {
+        version: 1,
+        name: "initial",
+        up: MigrationUp::Sql(r#"
+            CREATE TABLE IF NOT EXISTS webauthn_credentials (
+                id TEXT PRIMARY KEY,
+                identity_id TEXT NOT NULL,
+                credential_id BLOB NOT NULL,
+                credential_json TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                last_used_at INTEGER,
+                name TEXT,
+                FOREIGN KEY(identity_id) REFERENCES auth_identities(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_webauthn_identity ON webauthn_credentials(identity_id);
+            CREATE INDEX IF NOT EXISTS idx_webauthn_cred_id ON webauthn_credentials(credential_id);
+        "#),
+        down: None,
+    }],
+};
+
 /// WebAuthn credential stored in the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredCredential {
@@ -22,6 +46,9 @@ pub struct StoredCredential {
     pub name: Option<String>,
 }
 
+/// How long a registration or authentication challenge can be completed
+const CHALLENGE_TTL_SECS: i64 = 300;
+
 /// In-progress registration challenges
 pub struct RegistrationChallenge {
     pub identity_id: String,
@@ -67,29 +94,17 @@ impl WebAuthnProvider {
 
     /// Initialize WebAuthn tables
     pub fn init_schema(&self) -> Result<(), String> {
-        let conn = self.db.connection();
-        let conn = conn.lock();
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS webauthn_credentials (
-                id TEXT PRIMARY KEY,
-                identity_id TEXT NOT NULL,
-                credential_id BLOB NOT NULL,
-                credential_json TEXT NOT NULL,
-                created_at INTEGER NOT NULL,
-                last_used_at INTEGER,
-                name TEXT,
-                FOREIGN KEY(identity_id) REFERENCES auth_identities(id)
-            );
-            CREATE INDEX IF NOT EXISTS idx_webauthn_identity ON webauthn_credentials(identity_id);
-            CREATE INDEX IF NOT EXISTS idx_webauthn_cred_id ON webauthn_credentials(credential_id);
-            "#,
-        ).map_err(|e| e.to_string())?;
+        self.db.migrate(&MIGRATIONS).map_err(|e| e.to_string())?;
         Ok(())
     }
 
-    /// Begin WebAuthn registration for an identity
-    pub async fn begin_registration(&self, identity_id: &str, display_name: &str) -> Result<CreationChallengeResponse, String> {
+    /// Begin WebAuthn registration for an identity, returning the challenge ID
+    /// to complete it with
+    pub async fn begin_registration(
+        &self,
+        identity_id: &str,
+        display_name: &str,
+    ) -> Result<(String, CreationChallengeResponse), String> {
         use std::time::{SystemTime, UNIX_EPOCH};
         
         // Get existing credentials for this user
@@ -113,13 +128,14 @@ impl WebAuthnProvider {
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
         
         let mut challenges = self.reg_challenges.write().await;
+        challenges.retain(|_, c| c.expires_at > now);
         challenges.insert(challenge_id.clone(), RegistrationChallenge {
             identity_id: identity_id.to_string(),
             state: reg_state,
-            expires_at: now + 300, // 5 minutes
+            expires_at: now + CHALLENGE_TTL_SECS,
         });
         
-        Ok(ccr)
+        Ok((challenge_id, ccr))
     }
 
     /// Complete WebAuthn registration
@@ -175,8 +191,8 @@ impl WebAuthnProvider {
         })
     }
 
-    /// Begin WebAuthn authentication
-    pub async fn begin_authentication(&self, identity_id: &str) -> Result<RequestChallengeResponse, String> {
+    /// Begin WebAuthn authentication, returning the challenge ID to complete it with
+    pub async fn begin_authentication(&self, identity_id: &str) -> Result<(String, RequestChallengeResponse), String> {
         use std::time::{SystemTime, UNIX_EPOCH};
         
         let credentials = self.get_credentials_for_identity(identity_id).await?;
@@ -200,16 +216,17 @@ impl WebAuthnProvider {
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
         
         let mut challenges = self.auth_challenges.write().await;
+        challenges.retain(|_, c| c.expires_at > now);
         challenges.insert(challenge_id.clone(), AuthenticationChallenge {
             identity_id: identity_id.to_string(),
             state: auth_state,
-            expires_at: now + 300,
+            expires_at: now + CHALLENGE_TTL_SECS,
         });
         
-        Ok(rcr)
+        Ok((challenge_id, rcr))
     }
 
-    /// Complete WebAuthn authentication
+    /// Complete WebAuthn authentication, returning the authenticated identity ID
     pub async fn complete_authentication(
         &self,
         challenge_id: &str,
@@ -230,14 +247,25 @@ impl WebAuthnProvider {
             .finish_passkey_authentication(&response, &challenge.state)
             .map_err(|e| format!("Authentication failed: {}", e))?;
         
-        // Update last used timestamp
-        let cred_id_bytes = auth_result.cred_id().to_vec();
+        // Store the updated signature counter, so cloned authenticators are
+        // detected, and the last used timestamp
+        let credentials = self.get_credentials_for_identity(&challenge.identity_id).await?;
         let conn = self.db.connection();
         let conn = conn.lock();
-        let _ = conn.execute(
-            "UPDATE webauthn_credentials SET last_used_at = ?1 WHERE credential_id = ?2",
-            rusqlite::params![now, cred_id_bytes],
-        );
+        for stored in credentials {
+            let Ok(mut passkey) = serde_json::from_str::<Passkey>(&stored.credential) else {
+                continue;
+            };
+            if passkey.cred_id() != auth_result.cred_id() {
+                continue;
+            }
+            passkey.update_credential(&auth_result);
+            let credential_json = serde_json::to_string(&passkey).unwrap_or(stored.credential);
+            let _ = conn.execute(
+                "UPDATE webauthn_credentials SET credential_json = ?1, last_used_at = ?2 WHERE id = ?3",
+                rusqlite::params![credential_json, now, stored.id],
+            );
+        }
         
         Ok(challenge.identity_id)
     }
@@ -299,7 +327,7 @@ impl AuthProvider for WebAuthnProvider {
         true
     }
     
-    async fn begin_auth(&self, request: &LoginRequest) -> Result<AuthResult, String> {
+    async fn begin_auth(&self, _request: &LoginRequest) -> Result<AuthResult, String> {
         // This is handled separately via begin_authentication
         Err("Use WebAuthn-specific endpoints".to_string())
     }
@@ -5,21 +5,30 @@
 //! - Vendor convergence pattern detection
 //! - Optional network timing probes (opt-in, privacy-respecting)
 //! - Build pipeline static analysis
+//! - SBOM download (SPDX, CycloneDX)
+//! - Vulnerability correlation (OSV, RustSec)
+//! - Watched repositories, re-analyzed on GitHub push webhooks, with diffs
+//!   between consecutive reports and risk threshold notifications
 
 use axum::{
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
+use hmac::{Hmac, Mac};
 use infrasim_common::pipeline::{
-    AggregatedTimingStats, AnalysisReport, DependencyGraph, NetworkFingerprint,
-    NetworkTimingConfig, PipelineAnalyzer, ProbeTarget, TimingProbe,
+    affects_analysis, AdvisoryDb, AggregatedTimingStats, AnalysisReport, DependencyGraph,
+    GraphFormat, NetworkFingerprint, NetworkTimingConfig, PipelineAnalyzer, ProbeTarget,
+    ReportDiff, SbomFormat, Severity, TimingProbe, OSV_API_URL,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
@@ -31,6 +40,12 @@ pub struct AnalysisCache {
     pub timing_history: RwLock<Vec<NetworkFingerprint>>,
     /// Max timing history entries
     pub max_history: usize,
+    /// Repositories registered for continuous analysis, by ID
+    pub repos: RwLock<HashMap<String, WatchedRepo>>,
+    /// Where Git repositories are checked out; `INFRASIM_WEB_ANALYSIS_DIR`
+    pub checkout_dir: PathBuf,
+    /// Reports kept per watched repository
+    pub max_reports: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -46,6 +61,60 @@ impl Default for AnalysisCache {
             last_analysis: RwLock::new(None),
             timing_history: RwLock::new(Vec::new()),
             max_history: 100,
+            repos: RwLock::new(HashMap::new()),
+            checkout_dir: std::env::var("INFRASIM_WEB_ANALYSIS_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| std::env::temp_dir().join("infrasim-analysis")),
+            max_reports: 20,
+        }
+    }
+}
+
+/// Where a watched repository's tree comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RepoSource {
+    /// A directory on this host, analyzed in place
+    Path { path: String },
+    /// A Git remote, cloned once and fetched before each analysis
+    Git { url: String, branch: Option<String> },
+}
+
+/// Webhook told when a repository's risk score crosses `threshold`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskNotifier {
+    pub url: String,
+    pub threshold: f64,
+}
+
+/// A repository registered for continuous analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchedRepo {
+    pub id: String,
+    pub source: RepoSource,
+    pub include_advisories: bool,
+    pub advisory_db: Option<String>,
+    pub notify: Option<RiskNotifier>,
+    /// Key GitHub signs push webhooks with; only repositories with one are
+    /// re-analyzed on pushes
+    #[serde(skip)]
+    pub webhook_secret: Option<String>,
+    pub registered_at: u64,
+    pub analyzing: bool,
+    /// Trigger of an analysis requested while one was running
+    pub queued: Option<String>,
+    pub last_error: Option<String>,
+    /// Reports, oldest first
+    #[serde(skip)]
+    pub reports: VecDeque<CachedAnalysis>,
+}
+
+impl WatchedRepo {
+    /// Directory the analyzer reads
+    fn workspace(&self, checkout_dir: &std::path::Path) -> PathBuf {
+        match &self.source {
+            RepoSource::Path { path } => PathBuf::from(path),
+            RepoSource::Git { .. } => checkout_dir.join(&self.id),
         }
     }
 }
@@ -61,6 +130,13 @@ pub struct AnalyzeWorkspaceRequest {
     /// Whether to run ICMP timing probes
     #[serde(default)]
     pub include_timing: bool,
+    /// Whether to correlate packages with OSV/RustSec advisories
+    #[serde(default)]
+    pub include_advisories: bool,
+    /// Offline advisory database (directory or zip of OSV JSON files);
+    /// the OSV API is queried when unset
+    #[serde(default)]
+    pub advisory_db: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -418,36 +494,17 @@ pub async fn analyze_workspace_handler(
             .into_response();
     }
 
-    // Run analysis in blocking task
     let workspace_path = req.workspace_path.clone();
-    let analysis_result = tokio::task::spawn_blocking(move || {
-        let mut analyzer = PipelineAnalyzer::new();
-        analyzer.analyze_cargo_workspace(&path)
-    })
-    .await;
-
-    let report = match analysis_result {
-        Ok(Ok(r)) => r,
-        Ok(Err(e)) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AnalyzeWorkspaceResponse {
-                    success: false,
-                    report: None,
-                    timing: None,
-                    error: Some(format!("Analysis failed: {}", e)),
-                }),
-            )
-                .into_response();
-        }
-        Err(e) => {
+    let report = match run_analysis(path, req.include_advisories, req.advisory_db.clone()).await {
+        Ok(r) => r,
+        Err(error) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(AnalyzeWorkspaceResponse {
                     success: false,
                     report: None,
                     timing: None,
-                    error: Some(format!("Task failed: {}", e)),
+                    error: Some(error),
                 }),
             )
                 .into_response();
@@ -466,10 +523,7 @@ pub async fn analyze_workspace_handler(
         *cached = Some(CachedAnalysis {
             report: report.clone(),
             workspace_path,
-            analyzed_at: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            analyzed_at: now_secs(),
         });
     }
 
@@ -485,6 +539,40 @@ pub async fn analyze_workspace_handler(
         .into_response()
 }
 
+/// Analyze a workspace in a blocking task, correlating advisories if asked
+async fn run_analysis(
+    path: PathBuf,
+    include_advisories: bool,
+    advisory_db: Option<String>,
+) -> Result<AnalysisReport, String> {
+    let analysis_result = tokio::task::spawn_blocking(move || {
+        let mut analyzer = PipelineAnalyzer::new();
+        let mut report = analyzer.analyze_workspace(&path)?;
+        if include_advisories {
+            let db = match advisory_db {
+                Some(db) => AdvisoryDb::load(std::path::Path::new(&db))?,
+                None => AdvisoryDb::fetch(&report.graph, OSV_API_URL)?,
+            };
+            report.correlate_advisories(&db);
+        }
+        Ok::<_, infrasim_common::pipeline::AnalysisError>(report)
+    })
+    .await;
+
+    match analysis_result {
+        Ok(Ok(r)) => Ok(r),
+        Ok(Err(e)) => Err(format!("Analysis failed: {}", e)),
+        Err(e) => Err(format!("Task failed: {}", e)),
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 /// Get the dependency graph in D3.js format
 pub async fn get_d3_graph_handler(
     State(cache): State<Arc<AnalysisCache>>,
@@ -529,6 +617,114 @@ pub async fn get_cytoscape_graph_handler(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SbomQueryParams {
+    /// spdx or cyclonedx
+    #[serde(default = "default_sbom_format")]
+    pub format: String,
+}
+
+fn default_sbom_format() -> String {
+    "cyclonedx".to_string()
+}
+
+/// Download the last analysis as an SBOM document
+pub async fn get_sbom_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Query(params): Query<SbomQueryParams>,
+) -> impl IntoResponse {
+    let format: SbomFormat = match params.format.parse() {
+        Ok(format) => format,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    let cached = cache.last_analysis.read().await;
+    let Some(analysis) = cached.as_ref() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "No analysis available. Run POST /api/analysis/workspace first."
+            })),
+        )
+            .into_response();
+    };
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, attachment(analysis, format.extension())),
+        ],
+        Json(analysis.report.to_sbom(format)),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphExportQueryParams {
+    /// dot or graphml
+    #[serde(default = "default_graph_format")]
+    pub format: String,
+}
+
+fn default_graph_format() -> String {
+    "dot".to_string()
+}
+
+/// Download the last analysis's dependency graph for Graphviz or Gephi
+pub async fn export_graph_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Query(params): Query<GraphExportQueryParams>,
+) -> impl IntoResponse {
+    let format: GraphFormat = match params.format.parse() {
+        Ok(format) => format,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    let cached = cache.last_analysis.read().await;
+    let Some(analysis) = cached.as_ref() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "No analysis available. Run POST /api/analysis/workspace first."
+            })),
+        )
+            .into_response();
+    };
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, attachment(analysis, format.extension())),
+        ],
+        analysis.report.to_graph(format),
+    )
+        .into_response()
+}
+
+/// Content-Disposition of a download named after the analyzed workspace
+fn attachment(analysis: &CachedAnalysis, extension: &str) -> String {
+    let name = std::path::Path::new(&analysis.workspace_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .filter(|n| !n.is_empty() && n != ".")
+        .unwrap_or_else(|| "workspace".to_string());
+    format!("attachment; filename=\"{}.{}\"", name, extension)
+}
+
 /// Get detected cycles
 pub async fn get_cycles_handler(State(cache): State<Arc<AnalysisCache>>) -> impl IntoResponse {
     let cached = cache.last_analysis.read().await;
@@ -577,6 +773,46 @@ pub async fn get_suspicious_patterns_handler(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AdvisoryQueryParams {
+    /// Only advisories at or above this severity
+    #[serde(default)]
+    pub min_severity: Option<String>,
+}
+
+/// Get known vulnerabilities found in the last analysis
+pub async fn get_advisories_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Query(params): Query<AdvisoryQueryParams>,
+) -> impl IntoResponse {
+    let min_severity = match params.min_severity.as_deref().map(str::parse::<Severity>) {
+        None => Severity::Info,
+        Some(Ok(severity)) => severity,
+        Some(Err(e)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+                .into_response()
+        }
+    };
+
+    let cached = cache.last_analysis.read().await;
+
+    match cached.as_ref() {
+        Some(analysis) => {
+            let advisories: Vec<_> = analysis
+                .report
+                .advisories
+                .iter()
+                .filter(|a| a.severity >= min_severity)
+                .collect();
+            (StatusCode::OK, Json(advisories)).into_response()
+        }
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({ "advisories": [] }))).into_response(),
+    }
+}
+
 /// Run ICMP timing probes (opt-in, user-provided targets only)
 ///
 /// Network timing is privacy-respecting:
@@ -722,6 +958,8 @@ pub async fn get_analysis_summary_handler(
                     "cycle_count": report.cycles.len(),
                     "vendor_convergence_count": report.vendor_convergence.len(),
                     "suspicious_pattern_count": report.suspicious_patterns.len(),
+                    "advisory_count": report.advisories.len(),
+                    "max_advisory_severity": report.advisories.iter().map(|a| a.severity).max(),
                     "warnings": report.warnings,
                     "recommendations": report.recommendations
                 })),
@@ -738,6 +976,617 @@ pub async fn get_analysis_summary_handler(
     }
 }
 
+// ============================================================================
+// Watched Repositories
+// ============================================================================
+
+/// Time a risk notification may take
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time a `git` clone, fetch or reset may take
+const GIT_TIMEOUT: Duration = Duration::from_secs(300);
+
+fn default_risk_threshold() -> f64 {
+    50.0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterRepoRequest {
+    /// Directory on this host to analyze in place
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Git remote to clone and fetch, instead of `path`
+    #[serde(default)]
+    pub git_url: Option<String>,
+    /// Branch of `git_url`; the remote's default branch when unset
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub include_advisories: bool,
+    #[serde(default)]
+    pub advisory_db: Option<String>,
+    /// Secret of the repository's GitHub push webhook
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+    /// URL to POST to when the risk score crosses `risk_threshold`
+    #[serde(default)]
+    pub notify_url: Option<String>,
+    #[serde(default = "default_risk_threshold")]
+    pub risk_threshold: f64,
+}
+
+/// Headline figures of one analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSummary {
+    pub analyzed_at: u64,
+    pub git_commit: Option<String>,
+    pub total_nodes: usize,
+    pub risk_score: f64,
+    pub advisory_count: usize,
+}
+
+impl From<&CachedAnalysis> for ReportSummary {
+    fn from(analysis: &CachedAnalysis) -> Self {
+        Self {
+            analyzed_at: analysis.analyzed_at,
+            git_commit: analysis.report.graph.metadata.git_commit.clone(),
+            total_nodes: analysis.report.graph.metadata.total_nodes,
+            risk_score: analysis.report.risk_score,
+            advisory_count: analysis.report.advisories.len(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepoResponse {
+    #[serde(flatten)]
+    pub repo: WatchedRepo,
+    /// Whether pushes re-analyze the repository
+    pub webhook: bool,
+    /// Reports, newest first
+    pub history: Vec<ReportSummary>,
+}
+
+impl From<&WatchedRepo> for RepoResponse {
+    fn from(repo: &WatchedRepo) -> Self {
+        Self {
+            repo: repo.clone(),
+            webhook: repo.webhook_secret.is_some(),
+            history: repo.reports.iter().rev().map(ReportSummary::from).collect(),
+        }
+    }
+}
+
+/// Outcome of analyzing a watched repository
+#[derive(Debug, Serialize)]
+pub struct RepoAnalysis {
+    pub repo_id: String,
+    pub trigger: String,
+    pub summary: ReportSummary,
+    /// Changes since the previous report
+    pub diff: Option<ReportDiff>,
+    /// Whether the risk score crossed the notification threshold
+    pub threshold_crossed: bool,
+}
+
+/// Changes between two consecutive reports
+#[derive(Debug, Serialize)]
+pub struct RepoDiff {
+    pub from_analyzed_at: u64,
+    pub to_analyzed_at: u64,
+    #[serde(flatten)]
+    pub diff: ReportDiff,
+}
+
+/// The parts of a GitHub `push` event the webhook uses
+#[derive(Debug, Deserialize)]
+pub struct GithubPushEvent {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    #[serde(default)]
+    pub after: String,
+    #[serde(default)]
+    pub deleted: bool,
+    pub repository: GithubRepository,
+    #[serde(default)]
+    pub commits: Vec<GithubCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubRepository {
+    #[serde(default)]
+    pub clone_url: Option<String>,
+    #[serde(default)]
+    pub ssh_url: Option<String>,
+    #[serde(default)]
+    pub html_url: Option<String>,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubCommit {
+    #[serde(default)]
+    pub added: Vec<String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+    #[serde(default)]
+    pub modified: Vec<String>,
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> axum::response::Response {
+    (status, Json(serde_json::json!({ "error": error.into() }))).into_response()
+}
+
+/// Register a repository path or Git URL and start its first analysis
+pub async fn register_repo_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Json(req): Json<RegisterRepoRequest>,
+) -> impl IntoResponse {
+    let source = match (req.path, req.git_url) {
+        (Some(path), None) => {
+            if req.webhook_secret.is_some() {
+                return error_response(StatusCode::BAD_REQUEST, "webhook_secret needs git_url");
+            }
+            if !std::path::Path::new(&path).exists() {
+                return error_response(StatusCode::BAD_REQUEST, format!("Workspace path not found: {}", path));
+            }
+            RepoSource::Path { path }
+        }
+        (None, Some(url)) => {
+            // Both end up on git's command line
+            if url.starts_with('-') || req.branch.as_deref().is_some_and(|b| b.starts_with('-')) {
+                return error_response(StatusCode::BAD_REQUEST, "Invalid git_url or branch");
+            }
+            RepoSource::Git { url, branch: req.branch }
+        }
+        _ => return error_response(StatusCode::BAD_REQUEST, "Give exactly one of path and git_url"),
+    };
+    if !(0.0..=100.0).contains(&req.risk_threshold) {
+        return error_response(StatusCode::BAD_REQUEST, "risk_threshold must be between 0 and 100");
+    }
+    let notify = match req.notify_url {
+        Some(notify_url) => match url::Url::parse(&notify_url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Some(RiskNotifier {
+                url: notify_url,
+                threshold: req.risk_threshold,
+            }),
+            _ => return error_response(StatusCode::BAD_REQUEST, "notify_url must be an http(s) URL"),
+        },
+        None => None,
+    };
+
+    let repo = WatchedRepo {
+        id: uuid::Uuid::new_v4().to_string(),
+        source,
+        include_advisories: req.include_advisories,
+        advisory_db: req.advisory_db,
+        notify,
+        webhook_secret: req.webhook_secret.filter(|s| !s.is_empty()),
+        registered_at: now_secs(),
+        analyzing: true,
+        queued: None,
+        last_error: None,
+        reports: VecDeque::new(),
+    };
+    info!("Watching {:?} for analysis as {}", repo.source, repo.id);
+    let response = RepoResponse::from(&repo);
+    cache.repos.write().await.insert(repo.id.clone(), repo);
+    spawn_analysis(cache, response.repo.id.clone(), "register".to_string());
+
+    (StatusCode::ACCEPTED, Json(response)).into_response()
+}
+
+/// List watched repositories
+pub async fn list_repos_handler(State(cache): State<Arc<AnalysisCache>>) -> impl IntoResponse {
+    let repos = cache.repos.read().await;
+    let mut list: Vec<RepoResponse> = repos.values().map(RepoResponse::from).collect();
+    list.sort_by_key(|r| r.repo.registered_at);
+    (StatusCode::OK, Json(list)).into_response()
+}
+
+/// Get a watched repository and its report history
+pub async fn get_repo_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match cache.repos.read().await.get(&id) {
+        Some(repo) => (StatusCode::OK, Json(RepoResponse::from(repo))).into_response(),
+        None => error_response(StatusCode::NOT_FOUND, format!("Repository not found: {}", id)),
+    }
+}
+
+/// Stop watching a repository, removing its checkout
+pub async fn delete_repo_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(repo) = cache.repos.write().await.remove(&id) else {
+        return error_response(StatusCode::NOT_FOUND, format!("Repository not found: {}", id));
+    };
+    if let RepoSource::Git { .. } = repo.source {
+        let checkout = repo.workspace(&cache.checkout_dir);
+        if let Err(e) = tokio::fs::remove_dir_all(&checkout).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove checkout {}: {}", checkout.display(), e);
+            }
+        }
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Analyze a watched repository now
+pub async fn analyze_repo_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match analyze_repo(&cache, &id, "manual").await {
+        Ok(analysis) => (StatusCode::OK, Json(analysis)).into_response(),
+        Err((status, error)) => error_response(status, error),
+    }
+}
+
+/// Diffs between consecutive reports of a watched repository, newest first
+pub async fn get_repo_diffs_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    Path(id): Path<String>,
+    Query(params): Query<HistoryQueryParams>,
+) -> impl IntoResponse {
+    let repos = cache.repos.read().await;
+    let Some(repo) = repos.get(&id) else {
+        return error_response(StatusCode::NOT_FOUND, format!("Repository not found: {}", id));
+    };
+    let limit = params.limit.unwrap_or(10).min(100);
+    let offset = params.offset.unwrap_or(0);
+
+    let reports: Vec<&CachedAnalysis> = repo.reports.iter().collect();
+    let diffs: Vec<RepoDiff> = reports
+        .windows(2)
+        .rev()
+        .skip(offset)
+        .take(limit)
+        .map(|pair| RepoDiff {
+            from_analyzed_at: pair[0].analyzed_at,
+            to_analyzed_at: pair[1].analyzed_at,
+            diff: pair[0].report.diff(&pair[1].report),
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "total": reports.len().saturating_sub(1),
+            "offset": offset,
+            "limit": limit,
+            "items": diffs
+        })),
+    )
+        .into_response()
+}
+
+/// Re-analyze watched repositories a GitHub push touched
+///
+/// Only repositories registered with a `webhook_secret` whose signature
+/// matches are considered. A push is skipped when its head commit was
+/// already analyzed, or when none of its commits change a manifest,
+/// lockfile, build script or vendored source.
+pub async fn github_webhook_handler(
+    State(cache): State<Arc<AnalysisCache>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    match header("x-github-event") {
+        Some("push") => {}
+        Some("ping") => return (StatusCode::OK, Json(serde_json::json!({ "ok": true }))).into_response(),
+        event => {
+            return (
+                StatusCode::OK,
+                Json(serde_json::json!({ "ignored": event.unwrap_or_default() })),
+            )
+                .into_response()
+        }
+    }
+    let event: GithubPushEvent = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, format!("Invalid push event: {}", e)),
+    };
+    let signature = header("x-hub-signature-256");
+    let urls: Vec<String> = [
+        &event.repository.clone_url,
+        &event.repository.ssh_url,
+        &event.repository.html_url,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|url| normalize_git_url(url))
+    .collect();
+    let pushed_branch = event.git_ref.strip_prefix("refs/heads/");
+    let dependencies_changed = event.commits.is_empty()
+        || event
+            .commits
+            .iter()
+            .flat_map(|c| c.added.iter().chain(&c.removed).chain(&c.modified))
+            .any(|path| affects_analysis(path));
+
+    let mut matched = false;
+    let mut triggered = Vec::new();
+    let mut skipped = Vec::new();
+    let mut repos = cache.repos.write().await;
+    for repo in repos.values_mut() {
+        let RepoSource::Git { url, branch } = &repo.source else {
+            continue;
+        };
+        let watched_branch = branch.as_deref().or(event.repository.default_branch.as_deref());
+        if !urls.contains(&normalize_git_url(url)) || pushed_branch.is_none() || pushed_branch != watched_branch {
+            continue;
+        }
+        let Some(secret) = &repo.webhook_secret else {
+            continue;
+        };
+        matched = true;
+        if !verify_signature(secret, &body, signature) {
+            warn!("Rejected push webhook for {}: bad signature", repo.id);
+            continue;
+        }
+
+        let last_commit = repo
+            .reports
+            .back()
+            .and_then(|r| r.report.graph.metadata.git_commit.as_deref());
+        let reason = if event.deleted {
+            Some("branch deleted")
+        } else if last_commit == Some(event.after.as_str()) {
+            Some("commit already analyzed")
+        } else if !repo.reports.is_empty() && !dependencies_changed {
+            Some("no dependency changes")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            skipped.push(serde_json::json!({ "repo_id": repo.id, "reason": reason }));
+            continue;
+        }
+
+        let trigger = format!("push {}", event.after.get(..12).unwrap_or(event.after.as_str()));
+        if repo.analyzing {
+            repo.queued = Some(trigger);
+        } else {
+            repo.analyzing = true;
+            spawn_analysis(cache.clone(), repo.id.clone(), trigger);
+        }
+        triggered.push(repo.id.clone());
+    }
+    drop(repos);
+
+    if triggered.is_empty() && skipped.is_empty() {
+        return if matched {
+            error_response(StatusCode::UNAUTHORIZED, "Invalid webhook signature")
+        } else {
+            error_response(StatusCode::NOT_FOUND, "No watched repository matches the push")
+        };
+    }
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({ "triggered": triggered, "skipped": skipped })),
+    )
+        .into_response()
+}
+
+/// Analyze a watched repository in the background
+///
+/// The caller has set the repository's `analyzing` flag.
+fn spawn_analysis(cache: Arc<AnalysisCache>, id: String, trigger: String) {
+    tokio::spawn(async move {
+        if let Err((_, e)) = run_repo_analysis(&cache, &id, &trigger).await {
+            warn!("Analysis of repository {} ({}) failed: {}", id, trigger, e);
+        }
+    });
+}
+
+/// Analyze a watched repository unless an analysis is already running
+pub async fn analyze_repo(
+    cache: &Arc<AnalysisCache>,
+    id: &str,
+    trigger: &str,
+) -> Result<RepoAnalysis, (StatusCode, String)> {
+    {
+        let mut repos = cache.repos.write().await;
+        let repo = repos
+            .get_mut(id)
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Repository not found: {}", id)))?;
+        if repo.analyzing {
+            return Err((StatusCode::CONFLICT, "An analysis is already running".to_string()));
+        }
+        repo.analyzing = true;
+    }
+    run_repo_analysis(cache, id, trigger).await
+}
+
+/// Check out and analyze a repository whose `analyzing` flag is set, keep the
+/// report, and notify if the risk score crossed the threshold
+async fn run_repo_analysis(
+    cache: &Arc<AnalysisCache>,
+    id: &str,
+    trigger: &str,
+) -> Result<RepoAnalysis, (StatusCode, String)> {
+    let Some(repo) = cache.repos.read().await.get(id).cloned() else {
+        return Err((StatusCode::NOT_FOUND, format!("Repository not found: {}", id)));
+    };
+    let workspace = repo.workspace(&cache.checkout_dir);
+    debug!("Analyzing repository {} ({})", id, trigger);
+
+    let result = async {
+        if let RepoSource::Git { url, branch } = &repo.source {
+            checkout(&workspace, url, branch.as_deref()).await?;
+        }
+        run_analysis(workspace.clone(), repo.include_advisories, repo.advisory_db.clone()).await
+    }
+    .await;
+
+    let mut repos = cache.repos.write().await;
+    let Some(current) = repos.get_mut(id) else {
+        return Err((StatusCode::NOT_FOUND, "Repository was removed during the analysis".to_string()));
+    };
+    current.analyzing = false;
+    if let Some(next) = current.queued.take() {
+        current.analyzing = true;
+        spawn_analysis(cache.clone(), id.to_string(), next);
+    }
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            current.last_error = Some(e.clone());
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e));
+        }
+    };
+    current.last_error = None;
+
+    let previous_risk = current.reports.back().map(|r| r.report.risk_score);
+    let diff = current.reports.back().map(|r| r.report.diff(&report));
+    let analysis = CachedAnalysis {
+        report,
+        workspace_path: workspace.to_string_lossy().to_string(),
+        analyzed_at: now_secs(),
+    };
+    current.reports.push_back(analysis.clone());
+    while current.reports.len() > cache.max_reports {
+        current.reports.pop_front();
+    }
+    let notify = current.notify.clone();
+    drop(repos);
+
+    *cache.last_analysis.write().await = Some(analysis.clone());
+
+    let risk = analysis.report.risk_score;
+    let threshold_crossed = notify
+        .as_ref()
+        .is_some_and(|n| crosses_threshold(previous_risk, risk, n.threshold));
+    if let Some(notifier) = notify.as_ref().filter(|_| threshold_crossed) {
+        let payload = serde_json::json!({
+            "event": "risk_threshold_crossed",
+            "direction": if risk >= notifier.threshold { "above" } else { "below" },
+            "repo_id": repo.id,
+            "source": repo.source,
+            "trigger": trigger,
+            "threshold": notifier.threshold,
+            "risk_score": risk,
+            "previous_risk_score": previous_risk,
+            "git_commit": analysis.report.graph.metadata.git_commit,
+            "analyzed_at": analysis.analyzed_at,
+            "diff": diff,
+        });
+        if let Err(e) = notify_risk(&notifier.url, &payload).await {
+            warn!("Failed to notify {} of repository {}'s risk score: {}", notifier.url, id, e);
+        }
+    }
+
+    info!("Analyzed repository {} ({}): risk score {:.1}", id, trigger, risk);
+    Ok(RepoAnalysis {
+        repo_id: id.to_string(),
+        trigger: trigger.to_string(),
+        summary: ReportSummary::from(&analysis),
+        diff,
+        threshold_crossed,
+    })
+}
+
+async fn notify_risk(url: &str, payload: &serde_json::Value) -> anyhow::Result<()> {
+    reqwest::Client::builder()
+        .timeout(NOTIFY_TIMEOUT)
+        .build()?
+        .post(url)
+        .json(payload)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Whether a risk score moved across the threshold; a first report crosses
+/// it by starting at or above it
+fn crosses_threshold(previous: Option<f64>, risk: f64, threshold: f64) -> bool {
+    let above = risk >= threshold;
+    match previous {
+        Some(previous) => (previous >= threshold) != above,
+        None => above,
+    }
+}
+
+/// Clone a Git repository on first use, or fetch and check out the latest
+/// commit of the branch
+async fn checkout(dir: &std::path::Path, url: &str, branch: Option<&str>) -> Result<(), String> {
+    if !dir.join(".git").exists() {
+        if let Some(parent) = dir.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let target = dir.to_string_lossy();
+        let mut args = vec!["clone", "--quiet", "--depth", "1"];
+        if let Some(branch) = branch {
+            args.extend(["--branch", branch]);
+        }
+        args.extend(["--", url, &*target]);
+        return git(&args, None).await;
+    }
+    git(&["fetch", "--quiet", "--depth", "1", "origin", branch.unwrap_or("HEAD")], Some(dir)).await?;
+    git(&["reset", "--quiet", "--hard", "FETCH_HEAD"], Some(dir)).await
+}
+
+async fn git(args: &[&str], dir: Option<&std::path::Path>) -> Result<(), String> {
+    let mut cmd = tokio::process::Command::new("git");
+    cmd.args(args);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    // Dropping the child on timeout kills it, so a hung remote can't keep
+    // the repository marked as analyzing
+    cmd.kill_on_drop(true);
+    let output = tokio::time::timeout(GIT_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| format!("git {} timed out after {}s", args[0], GIT_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Host and path of a Git URL, so HTTPS and SSH remotes of a repository match
+fn normalize_git_url(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let url = ["https://", "http://", "ssh://", "git://"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+        .unwrap_or(url);
+    match url.strip_prefix("git@") {
+        // scp-like syntax: git@host:owner/repo
+        Some(rest) => rest.replacen(':', "/", 1),
+        None => url.to_string(),
+    }
+}
+
+/// Check a GitHub `X-Hub-Signature-256` header (`sha256=<hex HMAC>`)
+fn verify_signature(secret: &str, body: &[u8], header: Option<&str>) -> bool {
+    let Some(signature) = header
+        .and_then(|h| h.strip_prefix("sha256="))
+        .and_then(|h| hex::decode(h).ok())
+    else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
 // ============================================================================
 // Route Builder
 // ============================================================================
@@ -751,11 +1600,29 @@ pub fn analysis_routes(cache: Arc<AnalysisCache>) -> Router {
         .route("/summary", get(get_analysis_summary_handler))
         .route("/graph/d3", get(get_d3_graph_handler))
         .route("/graph/cytoscape", get(get_cytoscape_graph_handler))
+        .route("/graph/export", get(export_graph_handler))
         .route("/cycles", get(get_cycles_handler))
+        .route("/sbom", get(get_sbom_handler))
         .route("/vendor-convergence", get(get_vendor_convergence_handler))
         .route("/suspicious-patterns", get(get_suspicious_patterns_handler))
+        .route("/advisories", get(get_advisories_handler))
         .route("/timing", post(run_timing_probes_handler))
         .route("/timing/history", get(get_timing_history_handler))
+        // Signed by the repository's webhook secret, so served without a login
+        .route("/webhooks/github", post(github_webhook_handler))
+        .with_state(cache)
+}
+
+/// Build the watched-repository routes
+///
+/// These clone URLs, read host paths and post to notification URLs, so the
+/// server mounts them behind authentication.
+pub fn repo_routes(cache: Arc<AnalysisCache>) -> Router {
+    Router::new()
+        .route("/", get(list_repos_handler).post(register_repo_handler))
+        .route("/:id", get(get_repo_handler).delete(delete_repo_handler))
+        .route("/:id/analyze", post(analyze_repo_handler))
+        .route("/:id/diffs", get(get_repo_diffs_handler))
         .with_state(cache)
 }
 
@@ -786,4 +1653,242 @@ mod tests {
         assert_eq!(d3.nodes.len(), 1);
         assert_eq!(d3.nodes[0].name, "test");
     }
+
+    #[tokio::test]
+    async fn test_sbom_handler() {
+        let cache = Arc::new(AnalysisCache::default());
+        let query = |format: &str| {
+            Query(SbomQueryParams {
+                format: format.to_string(),
+            })
+        };
+
+        let response = get_sbom_handler(State(cache.clone()), query("spdx"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        *cache.last_analysis.write().await = Some(CachedAnalysis {
+            report: AnalysisReport::default(),
+            workspace_path: "/src/demo".to_string(),
+            analyzed_at: 0,
+        });
+
+        let response = get_sbom_handler(State(cache.clone()), query("xml"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = get_sbom_handler(State(cache), query("spdx"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/spdx+json"
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"demo.spdx.json\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_graph_handler() {
+        let cache = Arc::new(AnalysisCache::default());
+        let query = |format: &str| {
+            Query(GraphExportQueryParams {
+                format: format.to_string(),
+            })
+        };
+
+        let response = export_graph_handler(State(cache.clone()), query("dot"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        *cache.last_analysis.write().await = Some(CachedAnalysis {
+            report: AnalysisReport::default(),
+            workspace_path: "/src/demo".to_string(),
+            analyzed_at: 0,
+        });
+
+        let response = export_graph_handler(State(cache.clone()), query("gexf"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = export_graph_handler(State(cache), query("graphml"))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/graphml+xml"
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"demo.graphml\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_advisories_handler() {
+        let cache = Arc::new(AnalysisCache::default());
+        let query = |min: Option<&str>| {
+            Query(AdvisoryQueryParams {
+                min_severity: min.map(str::to_string),
+            })
+        };
+
+        let response = get_advisories_handler(State(cache.clone()), query(None))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mut report = AnalysisReport::default();
+        for (id, severity) in [("GHSA-1", Severity::Critical), ("GHSA-2", Severity::Low)] {
+            report.advisories.push(infrasim_common::pipeline::AdvisoryFinding {
+                id: id.to_string(),
+                aliases: vec![],
+                node_id: "pkg".to_string(),
+                package: "pkg".to_string(),
+                version: "1.0.0".to_string(),
+                summary: String::new(),
+                severity,
+                cvss_score: None,
+                fixed_versions: vec![],
+                url: None,
+            });
+        }
+        *cache.last_analysis.write().await = Some(CachedAnalysis {
+            report,
+            workspace_path: "/src/demo".to_string(),
+            analyzed_at: 0,
+        });
+
+        let response = get_advisories_handler(State(cache.clone()), query(Some("urgent")))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = get_advisories_handler(State(cache), query(Some("high")))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let advisories: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0]["id"], "GHSA-1");
+    }
+
+    #[test]
+    fn test_normalize_git_url() {
+        let url = normalize_git_url("https://github.com/Acme/App.git");
+        assert_eq!(url, "github.com/acme/app");
+        assert_eq!(normalize_git_url("git@github.com:acme/app.git"), url);
+        assert_eq!(normalize_git_url("ssh://git@github.com/acme/app"), url);
+        assert_eq!(normalize_git_url("https://github.com/acme/app/"), url);
+        assert_ne!(normalize_git_url("https://github.com/acme/app-fork"), url);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        // Example from GitHub's webhook documentation
+        let secret = "It's a Secret to Everybody";
+        let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+        assert!(verify_signature(secret, b"Hello, World!", Some(signature)));
+        assert!(!verify_signature(secret, b"Hello, World?", Some(signature)));
+        assert!(!verify_signature("other", b"Hello, World!", Some(signature)));
+        assert!(!verify_signature(secret, b"Hello, World!", None));
+    }
+
+    #[test]
+    fn test_crosses_threshold() {
+        assert!(crosses_threshold(None, 60.0, 50.0));
+        assert!(!crosses_threshold(None, 40.0, 50.0));
+        assert!(crosses_threshold(Some(40.0), 50.0, 50.0));
+        assert!(crosses_threshold(Some(60.0), 40.0, 50.0));
+        assert!(!crosses_threshold(Some(55.0), 70.0, 50.0));
+    }
+
+    #[tokio::test]
+    async fn test_github_webhook_handler() {
+        let cache = Arc::new(AnalysisCache::default());
+        let secret = "s3cret";
+        let mut report = AnalysisReport::default();
+        report.graph.metadata.git_commit = Some("a".repeat(40));
+        cache.repos.write().await.insert(
+            "repo-1".to_string(),
+            WatchedRepo {
+                id: "repo-1".to_string(),
+                source: RepoSource::Git {
+                    url: "git@github.com:acme/app.git".to_string(),
+                    branch: None,
+                },
+                include_advisories: false,
+                advisory_db: None,
+                notify: None,
+                webhook_secret: Some(secret.to_string()),
+                registered_at: 0,
+                analyzing: false,
+                queued: None,
+                last_error: None,
+                reports: VecDeque::from([CachedAnalysis {
+                    report,
+                    workspace_path: "/tmp/repo-1".to_string(),
+                    analyzed_at: 0,
+                }]),
+            },
+        );
+
+        let push = |after: &str, modified: &str| {
+            serde_json::to_vec(&serde_json::json!({
+                "ref": "refs/heads/main",
+                "after": after,
+                "repository": {
+                    "clone_url": "https://github.com/acme/app.git",
+                    "default_branch": "main"
+                },
+                "commits": [{ "added": [], "removed": [], "modified": [modified] }]
+            }))
+            .unwrap()
+        };
+        let deliver = |body: Vec<u8>, signature: String| {
+            let cache = cache.clone();
+            async move {
+                let mut headers = HeaderMap::new();
+                headers.insert("x-github-event", "push".parse().unwrap());
+                headers.insert("x-hub-signature-256", signature.parse().unwrap());
+                let response = github_webhook_handler(State(cache), headers, Bytes::from(body))
+                    .await
+                    .into_response();
+                let status = response.status();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap())
+            }
+        };
+        let sign = |body: &[u8]| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+            mac.update(body);
+            format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+        };
+
+        let body = push(&"b".repeat(40), "src/main.rs");
+        let (status, _) = deliver(body.clone(), "sha256=00".to_string()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, result) = deliver(body.clone(), sign(&body)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(result["skipped"][0]["reason"], "no dependency changes");
+
+        let body = push(&"a".repeat(40), "Cargo.lock");
+        let (status, result) = deliver(body.clone(), sign(&body)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(result["skipped"][0]["reason"], "commit already analyzed");
+    }
 }
@@ -0,0 +1,1163 @@
+//! Appliance template catalog
+//!
+//! Templates are collected, in order of precedence, from:
+//! - the built-in templates
+//! - `*.json` files in the template directory (a single template or a pack)
+//! - packs published through the API, signed with the server's publisher key
+//! - remote catalogs listed in `catalogs.json`, fetched over HTTP or Git and
+//!   verified against the catalog's Ed25519 public key
+//!
+//! A template ID is owned by the first source that defines it; later
+//! duplicates are skipped and reported on the source.
+
+use anyhow::{anyhow, bail, Context};
+use infrasim_common::crypto::{verifying_key_from_bytes, KeyPair, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::warn;
+
+/// Remote catalog configuration, relative to the template directory
+const CATALOGS_FILE: &str = "catalogs.json";
+/// Key that signs packs published through the API
+const PUBLISHER_KEY_FILE: &str = "publisher.key";
+/// Published packs
+const PACKS_DIR: &str = "packs";
+/// Last verified copy of each remote catalog
+const CACHE_DIR: &str = "cache";
+/// Pack file name inside a catalog's cache directory
+const PACK_FILE: &str = "catalog.json";
+
+// ============================================================================
+// Templates
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplianceTemplate {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub arch: String,
+    pub machine: String,
+    pub cpu_cores: i32,
+    pub memory_mb: i64,
+    pub compatibility_mode: bool,
+    pub tags: Vec<String>,
+    /// Optional container/image reference (e.g. quay.io/keycloak/keycloak:26)
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Environment variables for the appliance runtime
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Exposed ports
+    #[serde(default)]
+    pub ports: Vec<AppliancePort>,
+    /// Boot plan steps (ordered)
+    #[serde(default)]
+    pub boot_plan: Vec<BootStep>,
+    /// Network configuration hints
+    #[serde(default)]
+    pub networks: Vec<NetworkDef>,
+    /// Storage volumes
+    #[serde(default)]
+    pub volumes: Vec<VolumeDef>,
+    /// Software tooling installed in the image
+    #[serde(default)]
+    pub tools: Vec<ToolDef>,
+    /// Checks that must pass for a booted appliance to be healthy
+    #[serde(default)]
+    pub health_checks: Vec<HealthCheck>,
+    /// VMs of a multi-VM appliance; empty for a single VM sized by the
+    /// template
+    #[serde(default)]
+    pub vms: Vec<VmDef>,
+}
+
+/// One VM of a multi-VM appliance
+///
+/// Unset sizing and image fields fall back to the template's. The first VM
+/// listed is the appliance's primary VM.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VmDef {
+    pub name: String,
+    #[serde(default)]
+    pub cpu_cores: Option<i32>,
+    #[serde(default)]
+    pub memory_mb: Option<i64>,
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Ports forwarded to this VM
+    #[serde(default)]
+    pub ports: Vec<AppliancePort>,
+    /// VMs started before this one and stopped after it
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Condition this VM must meet before the VMs depending on it start
+    #[serde(default)]
+    pub wait_for: Option<HealthCheck>,
+    /// Seconds to wait for `wait_for`
+    #[serde(default = "default_wait_timeout_secs")]
+    pub wait_timeout_secs: u64,
+    /// VMs sharing a group are labelled to be kept on different hosts
+    #[serde(default)]
+    pub anti_affinity: Option<String>,
+}
+
+fn default_wait_timeout_secs() -> u64 { 300 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliancePort {
+    pub container_port: u16,
+    #[serde(default)]
+    pub host_port: Option<u16>,
+    #[serde(default = "default_tcp")]
+    pub protocol: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+fn default_tcp() -> String { "tcp".to_string() }
+
+impl ApplianceTemplate {
+    /// The appliance's VMs in startup order: each after the VMs it depends
+    /// on, otherwise in the order listed. A template without `vms` has one,
+    /// named `main`, with the template's ports.
+    pub fn vm_start_order(&self) -> anyhow::Result<Vec<VmDef>> {
+        if self.vms.is_empty() {
+            return Ok(vec![VmDef {
+                name: "main".to_string(),
+                cpu_cores: None,
+                memory_mb: None,
+                image: None,
+                ports: self.ports.clone(),
+                depends_on: vec![],
+                wait_for: None,
+                wait_timeout_secs: default_wait_timeout_secs(),
+                anti_affinity: None,
+            }]);
+        }
+
+        let mut names = HashSet::new();
+        for vm in &self.vms {
+            if !names.insert(vm.name.as_str()) {
+                bail!("template {} lists VM '{}' twice", self.id, vm.name);
+            }
+        }
+        for vm in &self.vms {
+            if let Some(dep) = vm.depends_on.iter().find(|d| !names.contains(d.as_str())) {
+                bail!("VM '{}' of template {} depends on unknown VM '{}'", vm.name, self.id, dep);
+            }
+        }
+
+        let mut started: HashSet<&str> = HashSet::new();
+        let mut order = Vec::with_capacity(self.vms.len());
+        while order.len() < self.vms.len() {
+            let next = self
+                .vms
+                .iter()
+                .find(|vm| !started.contains(vm.name.as_str()) && vm.depends_on.iter().all(|d| started.contains(d.as_str())))
+                .ok_or_else(|| {
+                    let waiting: Vec<&str> =
+                        self.vms.iter().map(|vm| vm.name.as_str()).filter(|n| !started.contains(n)).collect();
+                    anyhow!("VMs of template {} depend on each other in a cycle: {}", self.id, waiting.join(", "))
+                })?;
+            started.insert(next.name.as_str());
+            order.push(next.clone());
+        }
+        Ok(order)
+    }
+
+    /// Ports forwarded to any of the appliance's VMs
+    pub fn exposed_ports(&self) -> Vec<AppliancePort> {
+        if self.vms.is_empty() {
+            self.ports.clone()
+        } else {
+            self.vms.iter().flat_map(|vm| vm.ports.clone()).collect()
+        }
+    }
+
+    /// Whether the VMs start one after another rather than as one VM
+    pub fn starts_in_order(&self) -> bool {
+        self.vms.len() > 1 || self.vms.iter().any(|vm| vm.wait_for.is_some())
+    }
+
+    /// The template a VM of the appliance is created from
+    pub fn for_vm(&self, vm: &VmDef) -> ApplianceTemplate {
+        ApplianceTemplate {
+            cpu_cores: vm.cpu_cores.unwrap_or(self.cpu_cores),
+            memory_mb: vm.memory_mb.unwrap_or(self.memory_mb),
+            image: vm.image.clone().or_else(|| self.image.clone()),
+            ports: vm.ports.clone(),
+            vms: vec![],
+            ..self.clone()
+        }
+    }
+
+    /// Provisioning steps in boot order with their job params; unless they
+    /// name a port, they log in on the one the `wait_ssh` step waits for
+    pub fn provision_steps(&self) -> Vec<(&BootStep, HashMap<String, String>)> {
+        let mut steps: Vec<&BootStep> = self.boot_plan.iter().collect();
+        steps.sort_by_key(|step| step.order);
+        let ssh_port = steps
+            .iter()
+            .find(|step| step.action == "wait_ssh")
+            .and_then(|step| step.args.get("port"));
+        steps
+            .into_iter()
+            .filter_map(|step| {
+                let mut params = step.provision_params()?;
+                if let Some(port) = ssh_port {
+                    params.entry("port".to_string()).or_insert_with(|| port.clone());
+                }
+                Some((step, params))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootStep {
+    pub order: u32,
+    pub action: String,
+    pub description: String,
+    #[serde(default)]
+    pub args: HashMap<String, String>,
+}
+
+impl BootStep {
+    /// Params of the daemon `provision` job running a `run_ansible
+    /// { playbook }` or `run_ssh { script }` step; None for other steps
+    ///
+    /// Other args (`host`, `port`, `user`, `identity`, `timeout_secs`) are
+    /// passed on as they are; `vm` names the VM of a multi-VM appliance the
+    /// step runs on.
+    pub fn provision_params(&self) -> Option<HashMap<String, String>> {
+        let other = match self.action.as_str() {
+            "run_ansible" => "script",
+            "run_ssh" => "playbook",
+            _ => return None,
+        };
+        let mut params = self.args.clone();
+        params.remove(other);
+        Some(params)
+    }
+}
+
+/// A probe of a running appliance (see [`crate::health`])
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HealthCheck {
+    /// GET `url` and expect `expect_status`
+    Http {
+        url: String,
+        #[serde(default = "default_expect_status")]
+        expect_status: u16,
+    },
+    /// Connect to `port`: the host port a template port maps it to, else
+    /// `port` itself, on `host` (default localhost)
+    Tcp {
+        port: u16,
+        #[serde(default)]
+        host: Option<String>,
+    },
+    /// Run `script` with `sh -c` on the web server host and expect exit
+    /// status 0
+    Cmd { script: String },
+}
+
+fn default_expect_status() -> u16 { 200 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkDef {
+    pub id: String,
+    pub mode: String,
+    #[serde(default)]
+    pub cidr: Option<String>,
+    #[serde(default)]
+    pub gateway: Option<String>,
+    #[serde(default)]
+    pub dhcp: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeDef {
+    pub id: String,
+    pub size_mb: u64,
+    pub mount_path: String,
+    #[serde(default = "default_disk_kind")]
+    pub kind: String,
+}
+
+fn default_disk_kind() -> String { "disk".to_string() }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDef {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub purpose: String,
+}
+
+/// A named set of templates, as published or served by a catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplatePack {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub templates: Vec<ApplianceTemplate>,
+}
+
+/// Contents of a template file in the template directory
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TemplateFile {
+    Pack(TemplatePack),
+    Template(Box<ApplianceTemplate>),
+}
+
+// ============================================================================
+// Sources
+// ============================================================================
+
+/// Remote catalogs to sync, read from `catalogs.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatalogConfig {
+    #[serde(default)]
+    pub catalogs: Vec<RemoteCatalog>,
+}
+
+/// A signed template pack served over HTTP or from a Git repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteCatalog {
+    pub name: String,
+    /// URL of the pack; its signature is fetched from `<url>.sig`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Git repository holding the pack
+    #[serde(default)]
+    pub git: Option<String>,
+    /// Branch or tag to check out
+    #[serde(default, rename = "ref")]
+    pub git_ref: Option<String>,
+    /// Pack path inside the repository; its signature is `<path>.sig`
+    #[serde(default = "default_pack_path")]
+    pub path: String,
+    /// Hex Ed25519 public key the pack must be signed with
+    pub public_key: String,
+    /// Bearer token sent with HTTP requests
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+fn default_pack_path() -> String {
+    PACK_FILE.to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Builtin,
+    Local,
+    Pack,
+    Catalog,
+}
+
+/// Outcome of loading one template source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceStatus {
+    pub name: String,
+    pub kind: SourceKind,
+    /// Templates registered from this source
+    pub templates: usize,
+    /// Template IDs already defined by an earlier source
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When a catalog was last fetched, in epoch seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_at: Option<i64>,
+}
+
+impl SourceStatus {
+    fn new(name: &str, kind: SourceKind) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            templates: 0,
+            skipped: Vec::new(),
+            error: None,
+            synced_at: None,
+        }
+    }
+
+    fn failed(name: &str, kind: SourceKind, error: anyhow::Error) -> Self {
+        Self {
+            error: Some(format!("{:#}", error)),
+            ..Self::new(name, kind)
+        }
+    }
+}
+
+/// A registered template and the source that provided it
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogEntry {
+    #[serde(flatten)]
+    pub template: ApplianceTemplate,
+    pub source: String,
+}
+
+/// A pack written by [`TemplateRegistry::publish`]
+#[derive(Debug, Clone, Serialize)]
+pub struct PublishedPack {
+    pub name: String,
+    pub templates: usize,
+    /// Hex Ed25519 signature over the pack file
+    pub signature: String,
+    /// Hex public key consumers list in their `catalogs.json`
+    pub public_key: String,
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Appliance templates from every configured source
+pub struct TemplateRegistry {
+    root: PathBuf,
+    entries: Vec<CatalogEntry>,
+    sources: Vec<SourceStatus>,
+    publisher: Option<KeyPair>,
+}
+
+impl TemplateRegistry {
+    /// Registry holding the built-in templates until [`reload`](Self::reload)
+    pub fn new(root: PathBuf) -> Self {
+        let mut registry = Self {
+            root,
+            entries: Vec::new(),
+            sources: Vec::new(),
+            publisher: None,
+        };
+        registry.load_builtin();
+        registry
+    }
+
+    /// Template directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    pub fn sources(&self) -> &[SourceStatus] {
+        &self.sources
+    }
+
+    pub fn list(&self) -> Vec<ApplianceTemplate> {
+        self.entries.iter().map(|e| e.template.clone()).collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<ApplianceTemplate> {
+        self.entries
+            .iter()
+            .find(|e| e.template.id == id)
+            .map(|e| e.template.clone())
+    }
+
+    /// Hex public key of the publisher key, once loaded
+    pub fn publisher_key(&self) -> Option<String> {
+        self.publisher.as_ref().map(|k| k.public_key_hex())
+    }
+
+    /// Rebuild the registry from the template directory
+    ///
+    /// Remote catalogs are read from their cache; see [`fetch_catalogs`].
+    pub async fn reload(&mut self) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(self.root.join(PACKS_DIR)).await?;
+        if self.publisher.is_none() {
+            self.publisher = Some(load_publisher_key(&self.root).await?);
+        }
+
+        self.entries.clear();
+        self.sources.clear();
+        self.load_builtin();
+
+        for (name, result) in read_local_files(&self.root).await {
+            match result {
+                Ok(templates) => self.register(&name, SourceKind::Local, templates),
+                Err(e) => self.sources.push(SourceStatus::failed(&name, SourceKind::Local, e)),
+            }
+        }
+
+        let publisher = self.publisher_key().unwrap_or_default();
+        for name in pack_names(&self.root.join(PACKS_DIR)).await {
+            let pack = self.root.join(PACKS_DIR).join(format!("{}.json", name));
+            match read_signed_pack(&pack, &publisher).await {
+                Ok(pack) => self.register(&name, SourceKind::Pack, pack.templates),
+                Err(e) => self.sources.push(SourceStatus::failed(&name, SourceKind::Pack, e)),
+            }
+        }
+
+        for catalog in load_catalog_config(&self.root).await?.catalogs {
+            let pack = self.root.join(CACHE_DIR).join(&catalog.name).join(PACK_FILE);
+            if !pack.exists() {
+                self.sources.push(SourceStatus::failed(
+                    &catalog.name,
+                    SourceKind::Catalog,
+                    anyhow!("not synced yet"),
+                ));
+                continue;
+            }
+            match read_signed_pack(&pack, &catalog.public_key).await {
+                Ok(signed) => {
+                    self.register(&catalog.name, SourceKind::Catalog, signed.templates);
+                    if let Some(status) = self.sources.last_mut() {
+                        status.synced_at = modified_secs(&pack).await;
+                    }
+                }
+                Err(e) => self.sources.push(SourceStatus::failed(&catalog.name, SourceKind::Catalog, e)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sign a pack with the publisher key and register it
+    ///
+    /// Publishing a pack under an existing name replaces it.
+    pub async fn publish(&mut self, pack: TemplatePack) -> anyhow::Result<PublishedPack> {
+        validate_name(&pack.name)?;
+        if pack.templates.is_empty() {
+            bail!("pack has no templates");
+        }
+        let mut ids = HashSet::new();
+        for template in &pack.templates {
+            if template.id.trim().is_empty() {
+                bail!("template id must not be empty");
+            }
+            if !ids.insert(template.id.as_str()) {
+                bail!("duplicate template id '{}'", template.id);
+            }
+        }
+        let publisher = self
+            .publisher
+            .as_ref()
+            .ok_or_else(|| anyhow!("publisher key not loaded"))?;
+
+        let data = serde_json::to_vec_pretty(&pack)?;
+        let signature = hex::encode(publisher.sign(&data));
+        let path = self.root.join(PACKS_DIR).join(format!("{}.json", pack.name));
+        tokio::fs::write(sig_path(&path), &signature).await?;
+        tokio::fs::write(&path, &data).await?;
+
+        let published = PublishedPack {
+            name: pack.name,
+            templates: pack.templates.len(),
+            signature,
+            public_key: publisher.public_key_hex(),
+        };
+        self.reload().await?;
+        Ok(published)
+    }
+
+    /// Remove a published pack; false if there was none
+    pub async fn unpublish(&mut self, name: &str) -> anyhow::Result<bool> {
+        validate_name(name)?;
+        let path = self.root.join(PACKS_DIR).join(format!("{}.json", name));
+        if !path.exists() {
+            return Ok(false);
+        }
+        tokio::fs::remove_file(&path).await?;
+        let _ = tokio::fs::remove_file(sig_path(&path)).await;
+        self.reload().await?;
+        Ok(true)
+    }
+
+    /// Raw file and signature of a published pack, as served to other registries
+    pub async fn pack_file(&self, name: &str) -> anyhow::Result<(Vec<u8>, String)> {
+        validate_name(name)?;
+        let path = self.root.join(PACKS_DIR).join(format!("{}.json", name));
+        let data = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("pack '{}' not found", name))?;
+        let signature = tokio::fs::read_to_string(sig_path(&path)).await?;
+        Ok((data, signature.trim().to_string()))
+    }
+
+    fn load_builtin(&mut self) {
+        self.register("builtin", SourceKind::Builtin, builtin_templates());
+    }
+
+    fn register(&mut self, name: &str, kind: SourceKind, templates: Vec<ApplianceTemplate>) {
+        let mut status = SourceStatus::new(name, kind);
+        for template in templates {
+            if self.entries.iter().any(|e| e.template.id == template.id) {
+                status.skipped.push(template.id);
+                continue;
+            }
+            status.templates += 1;
+            self.entries.push(CatalogEntry {
+                template,
+                source: name.to_string(),
+            });
+        }
+        if !status.skipped.is_empty() {
+            warn!("template source {} redefines {:?}, skipped", name, status.skipped);
+        }
+        self.sources.push(status);
+    }
+}
+
+/// Fetch every remote catalog into the cache, keeping the previous copy when
+/// a fetch or signature check fails
+pub async fn fetch_catalogs(root: &Path) -> Vec<SourceStatus> {
+    let config = match load_catalog_config(root).await {
+        Ok(config) => config,
+        Err(e) => return vec![SourceStatus::failed(CATALOGS_FILE, SourceKind::Catalog, e)],
+    };
+
+    let mut results = Vec::new();
+    for catalog in config.catalogs {
+        let status = match fetch_catalog(root, &catalog).await {
+            Ok(templates) => SourceStatus {
+                templates,
+                synced_at: Some(chrono::Utc::now().timestamp()),
+                ..SourceStatus::new(&catalog.name, SourceKind::Catalog)
+            },
+            Err(e) => {
+                warn!("failed to sync template catalog {}: {:#}", catalog.name, e);
+                SourceStatus::failed(&catalog.name, SourceKind::Catalog, e)
+            }
+        };
+        results.push(status);
+    }
+    results
+}
+
+async fn fetch_catalog(root: &Path, catalog: &RemoteCatalog) -> anyhow::Result<usize> {
+    validate_name(&catalog.name)?;
+    let (data, signature) = match (&catalog.url, &catalog.git) {
+        (Some(url), None) => fetch_http(url, catalog.token.as_deref()).await?,
+        (None, Some(repo)) => fetch_git(root, catalog, repo).await?,
+        _ => bail!("exactly one of url or git must be set"),
+    };
+
+    // Only verified packs replace the cached copy
+    verify_pack(&data, &signature, &catalog.public_key)?;
+    let pack: TemplatePack = serde_json::from_slice(&data).context("invalid template pack")?;
+
+    let dir = root.join(CACHE_DIR).join(&catalog.name);
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join(PACK_FILE);
+    tokio::fs::write(sig_path(&path), signature.trim()).await?;
+    tokio::fs::write(&path, &data).await?;
+    Ok(pack.templates.len())
+}
+
+async fn fetch_http(url: &str, token: Option<&str>) -> anyhow::Result<(Vec<u8>, String)> {
+    let client = reqwest::Client::new();
+    let get = |url: String| {
+        let mut req = client.get(url);
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        req
+    };
+
+    let data = get(url.to_string()).send().await?.error_for_status()?.bytes().await?;
+    let signature = get(format!("{}.sig", url))
+        .send()
+        .await?
+        .error_for_status()
+        .context("signature not found")?
+        .text()
+        .await?;
+    Ok((data.to_vec(), signature))
+}
+
+async fn fetch_git(root: &Path, catalog: &RemoteCatalog, repo: &str) -> anyhow::Result<(Vec<u8>, String)> {
+    let checkout = root
+        .join(CACHE_DIR)
+        .join(format!(".{}-{}", catalog.name, uuid::Uuid::new_v4()));
+    let mut cmd = tokio::process::Command::new("git");
+    cmd.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(git_ref) = &catalog.git_ref {
+        cmd.args(["--branch", git_ref]);
+    }
+    let output = cmd.arg(repo).arg(&checkout).output().await.context("failed to run git")?;
+
+    let result = async {
+        if !output.status.success() {
+            bail!("git clone failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        let path = checkout.join(&catalog.path);
+        if !path.starts_with(&checkout) || catalog.path.contains("..") {
+            bail!("pack path leaves the repository");
+        }
+        let data = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("{} not found in repository", catalog.path))?;
+        let signature = tokio::fs::read_to_string(sig_path(&path))
+            .await
+            .context("signature not found")?;
+        Ok((data, signature))
+    }
+    .await;
+
+    let _ = tokio::fs::remove_dir_all(&checkout).await;
+    result
+}
+
+// ============================================================================
+// Files
+// ============================================================================
+
+/// Check a hex Ed25519 signature over a pack file
+pub fn verify_pack(data: &[u8], signature_hex: &str, public_key_hex: &str) -> anyhow::Result<()> {
+    let public_key = hex::decode(public_key_hex.trim()).context("invalid public key hex")?;
+    let signature = hex::decode(signature_hex.trim()).context("invalid signature hex")?;
+    let key = verifying_key_from_bytes(&public_key)?;
+    Verifier::verify(&key, data, &signature).map_err(|_| anyhow!("signature verification failed"))
+}
+
+async fn read_signed_pack(path: &Path, public_key_hex: &str) -> anyhow::Result<TemplatePack> {
+    let data = tokio::fs::read(path).await?;
+    let signature = tokio::fs::read_to_string(sig_path(path))
+        .await
+        .context("signature not found")?;
+    verify_pack(&data, &signature, public_key_hex)?;
+    serde_json::from_slice(&data).context("invalid template pack")
+}
+
+/// Templates of each `*.json` file directly in the template directory
+async fn read_local_files(root: &Path) -> Vec<(String, anyhow::Result<Vec<ApplianceTemplate>>)> {
+    let mut files = Vec::new();
+    let Ok(mut dir) = tokio::fs::read_dir(root).await else {
+        return files;
+    };
+    while let Ok(Some(entry)) = dir.next_entry().await {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") || name == CATALOGS_FILE {
+            continue;
+        }
+        let templates = async {
+            let data = tokio::fs::read(&path).await?;
+            Ok(match serde_json::from_slice(&data)? {
+                TemplateFile::Pack(pack) => pack.templates,
+                TemplateFile::Template(template) => vec![*template],
+            })
+        }
+        .await;
+        files.push((name, templates));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files
+}
+
+/// Names of the published packs, sorted
+async fn pack_names(dir: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+async fn load_catalog_config(root: &Path) -> anyhow::Result<CatalogConfig> {
+    match tokio::fs::read(root.join(CATALOGS_FILE)).await {
+        Ok(data) => serde_json::from_slice(&data).with_context(|| format!("invalid {}", CATALOGS_FILE)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(CatalogConfig::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn load_publisher_key(root: &Path) -> anyhow::Result<KeyPair> {
+    let path = root.join(PUBLISHER_KEY_FILE);
+    if path.exists() {
+        return Ok(KeyPair::load(&path).await?);
+    }
+    let key = KeyPair::generate();
+    key.save(&path).await?;
+    Ok(key)
+}
+
+async fn modified_secs(path: &Path) -> Option<i64> {
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64)
+}
+
+fn sig_path(path: &Path) -> PathBuf {
+    let mut sig = path.as_os_str().to_owned();
+    sig.push(".sig");
+    PathBuf::from(sig)
+}
+
+/// Pack and catalog names become file names
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!("invalid name '{}': use letters, digits, '-' and '_'", name);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Built-in templates
+// ============================================================================
+
+/// Templates shipped with the server
+pub fn builtin_templates() -> Vec<ApplianceTemplate> {
+    vec![
+        // Pi-like desktop template
+        ApplianceTemplate {
+            id: "pi-like-aarch64-desktop".to_string(),
+            title: "Pi-like AArch64 Desktop".to_string(),
+            description: "A Raspberry-Pi-like (AArch64) VM profile intended for interactive desktop-style workloads (e.g. Kali + browser + CLI).".to_string(),
+            arch: "aarch64".to_string(),
+            machine: "virt".to_string(),
+            cpu_cores: 4,
+            memory_mb: 4096,
+            compatibility_mode: true,
+            tags: vec!["aarch64".to_string(), "pi-like".to_string(), "desktop".to_string()],
+            image: None,
+            env: HashMap::new(),
+            ports: vec![],
+            boot_plan: vec![
+                BootStep { order: 1, action: "create_vm".to_string(), description: "Provision VM via daemon".to_string(), args: HashMap::new() },
+                BootStep { order: 2, action: "start_vm".to_string(), description: "Start the VM".to_string(), args: HashMap::new() },
+                BootStep { order: 3, action: "wait_ssh".to_string(), description: "Wait for SSH readiness".to_string(), args: HashMap::new() },
+            ],
+            networks: vec![
+                NetworkDef { id: "default".to_string(), mode: "user".to_string(), cidr: Some("10.0.2.0/24".to_string()), gateway: Some("10.0.2.2".to_string()), dhcp: true },
+            ],
+            volumes: vec![
+                VolumeDef { id: "root".to_string(), size_mb: 8192, mount_path: "/".to_string(), kind: "disk".to_string() },
+            ],
+            tools: vec![],
+            health_checks: vec![],
+            vms: vec![],
+        },
+        // Alpine Linux on Raspberry Pi architecture
+        ApplianceTemplate {
+            id: "alpine-rpi-aarch64".to_string(),
+            title: "Alpine Linux on Raspberry Pi".to_string(),
+            description: "Minimal Alpine Linux appliance running on emulated Raspberry Pi architecture (AArch64). Includes basic setup and SSH access.".to_string(),
+            arch: "aarch64".to_string(),
+            machine: "raspi3".to_string(),
+            cpu_cores: 4,
+            memory_mb: 1024,
+            compatibility_mode: false,
+            tags: vec!["aarch64".to_string(), "alpine".to_string(), "raspberry-pi".to_string(), "minimal".to_string()],
+            image: Some("alpine:latest".to_string()),
+            env: {
+                let mut m = HashMap::new();
+                m.insert("ALPINE_MIRROR".to_string(), "http://dl-cdn.alpinelinux.org/alpine".to_string());
+                m
+            },
+            ports: vec![
+                AppliancePort { container_port: 22, host_port: Some(2222), protocol: "tcp".to_string(), description: "SSH access".to_string() },
+            ],
+            boot_plan: vec![
+                BootStep { order: 1, action: "create_vm".to_string(), description: "Provision AArch64 VM with Raspberry Pi machine".to_string(), args: HashMap::new() },
+                BootStep { order: 2, action: "pull_image".to_string(), description: "Pull Alpine Linux image".to_string(), args: {
+                    let mut m = HashMap::new();
+                    m.insert("image".to_string(), "alpine:latest".to_string());
+                    m
+                }},
+                BootStep { order: 3, action: "run_container".to_string(), description: "Start Alpine container".to_string(), args: {
+                    let mut m = HashMap::new();
+                    m.insert("cmd".to_string(), "/bin/sh".to_string());
+                    m
+                }},
+                BootStep { order: 4, action: "wait_ssh".to_string(), description: "Wait for SSH readiness on port 2222".to_string(), args: {
+                    let mut m = HashMap::new();
+                    m.insert("port".to_string(), "2222".to_string());
+                    m
+                }},
+            ],
+            networks: vec![
+                NetworkDef { id: "default".to_string(), mode: "user".to_string(), cidr: Some("10.0.2.0/24".to_string()), gateway: Some("10.0.2.2".to_string()), dhcp: true },
+            ],
+            volumes: vec![
+                VolumeDef { id: "root".to_string(), size_mb: 2048, mount_path: "/".to_string(), kind: "disk".to_string() },
+                VolumeDef { id: "data".to_string(), size_mb: 1024, mount_path: "/data".to_string(), kind: "disk".to_string() },
+            ],
+            tools: vec![
+                ToolDef { name: "openssh".to_string(), version: Some("latest".to_string()), purpose: "SSH server for remote access".to_string() },
+                ToolDef { name: "alpine-base".to_string(), version: Some("latest".to_string()), purpose: "Base Alpine Linux packages".to_string() },
+            ],
+            health_checks: vec![HealthCheck::Tcp { port: 22, host: None }],
+            vms: vec![],
+        },
+        // Keycloak IdP appliance
+        ApplianceTemplate {
+            id: "keycloak-aarch64".to_string(),
+            title: "Keycloak Identity Provider".to_string(),
+            description: "Keycloak (AArch64) appliance for identity federation and SSO. Runs in dev mode by default; configure TLS/proxy for production.".to_string(),
+            arch: "aarch64".to_string(),
+            machine: "virt".to_string(),
+            cpu_cores: 2,
+            memory_mb: 2048,
+            compatibility_mode: false,
+            tags: vec!["aarch64".to_string(), "identity".to_string(), "keycloak".to_string(), "sso".to_string()],
+            image: Some("quay.io/keycloak/keycloak:26.0".to_string()),
+            env: {
+                let mut m = HashMap::new();
+                m.insert("KC_BOOTSTRAP_ADMIN_USERNAME".to_string(), "admin".to_string());
+                m.insert("KC_BOOTSTRAP_ADMIN_PASSWORD".to_string(), "${secret:keycloak-admin}".to_string());
+                m
+            },
+            ports: vec![
+                AppliancePort { container_port: 8080, host_port: Some(8080), protocol: "tcp".to_string(), description: "Keycloak HTTP".to_string() },
+                AppliancePort { container_port: 8443, host_port: Some(8443), protocol: "tcp".to_string(), description: "Keycloak HTTPS".to_string() },
+            ],
+            boot_plan: vec![
+                BootStep { order: 1, action: "create_vm".to_string(), description: "Provision AArch64 VM".to_string(), args: HashMap::new() },
+                BootStep { order: 2, action: "pull_image".to_string(), description: "Pull Keycloak container image".to_string(), args: {
+                    let mut m = HashMap::new();
+                    m.insert("image".to_string(), "quay.io/keycloak/keycloak:26.0".to_string());
+                    m
+                }},
+                BootStep { order: 3, action: "run_container".to_string(), description: "Start Keycloak in dev mode".to_string(), args: {
+                    let mut m = HashMap::new();
+                    m.insert("cmd".to_string(), "start-dev".to_string());
+                    m
+                }},
+                BootStep { order: 4, action: "wait_http".to_string(), description: "Wait for Keycloak /health/ready".to_string(), args: {
+                    let mut m = HashMap::new();
+                    m.insert("url".to_string(), "http://localhost:8080/health/ready".to_string());
+                    m
+                }},
+            ],
+            networks: vec![
+                NetworkDef { id: "mgmt".to_string(), mode: "user".to_string(), cidr: Some("10.0.2.0/24".to_string()), gateway: Some("10.0.2.2".to_string()), dhcp: true },
+            ],
+            volumes: vec![
+                VolumeDef { id: "kc-data".to_string(), size_mb: 1024, mount_path: "/opt/keycloak/data".to_string(), kind: "disk".to_string() },
+            ],
+            tools: vec![
+                ToolDef { name: "keycloak".to_string(), version: Some("26.0".to_string()), purpose: "Identity and access management".to_string() },
+            ],
+            health_checks: vec![HealthCheck::Http {
+                url: "http://localhost:8080/health/ready".to_string(),
+                expect_status: 200,
+            }],
+            vms: vec![],
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("infrasim-templates-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn template(id: &str) -> ApplianceTemplate {
+        ApplianceTemplate {
+            id: id.to_string(),
+            ..builtin_templates().remove(0)
+        }
+    }
+
+    #[test]
+    fn test_provision_steps_follow_boot_order() {
+        let step = |order, action: &str, args: &[(&str, &str)]| BootStep {
+            order,
+            action: action.to_string(),
+            description: String::new(),
+            args: args.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        };
+        let mut tpl = template("provisioned");
+        tpl.boot_plan = vec![
+            step(5, "run_ssh", &[("script", "rc-service nginx start"), ("playbook", "ignored.yml")]),
+            step(4, "run_ansible", &[("playbook", "site.yml"), ("port", "22")]),
+            step(3, "wait_ssh", &[("port", "2222")]),
+            step(1, "create_vm", &[]),
+        ];
+
+        let steps = tpl.provision_steps();
+        assert_eq!(steps.iter().map(|(s, _)| s.order).collect::<Vec<_>>(), [4, 5]);
+        assert_eq!(steps[0].1["port"], "22");
+        assert_eq!(steps[1].1["port"], "2222");
+        assert!(!steps[1].1.contains_key("playbook"));
+        assert!(tpl.boot_plan[3].provision_params().is_none());
+    }
+
+    #[test]
+    fn test_vm_start_order() {
+        let vm = |name: &str, deps: &[&str]| VmDef {
+            name: name.to_string(),
+            cpu_cores: None,
+            memory_mb: None,
+            image: None,
+            ports: vec![],
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+            wait_for: None,
+            wait_timeout_secs: 300,
+            anti_affinity: None,
+        };
+        let names = |tpl: &ApplianceTemplate| -> Vec<String> {
+            tpl.vm_start_order().unwrap().into_iter().map(|vm| vm.name).collect()
+        };
+
+        let mut tpl = template("two-tier");
+        assert_eq!(names(&tpl), ["main"]);
+        assert_eq!(tpl.vm_start_order().unwrap()[0].ports.len(), tpl.ports.len());
+
+        tpl.vms = vec![vm("web", &["app"]), vm("app", &["db", "cache"]), vm("db", &[]), vm("cache", &[])];
+        assert_eq!(names(&tpl), ["db", "cache", "app", "web"]);
+
+        tpl.vms[2].memory_mb = Some(4096);
+        let db = tpl.for_vm(&tpl.vms[2]);
+        assert_eq!((db.memory_mb, db.cpu_cores), (4096, tpl.cpu_cores));
+        assert!(db.vms.is_empty());
+
+        tpl.vms[2].depends_on = vec!["web".to_string()];
+        assert!(tpl.vm_start_order().unwrap_err().to_string().contains("cycle"));
+        tpl.vms[2].depends_on = vec!["queue".to_string()];
+        assert!(tpl.vm_start_order().is_err());
+        tpl.vms = vec![vm("db", &[]), vm("db", &[])];
+        assert!(tpl.vm_start_order().is_err());
+    }
+
+    #[test]
+    fn test_health_check_serde() {
+        let checks: Vec<HealthCheck> = serde_json::from_str(
+            r#"[{"type": "http", "url": "http://localhost:8080/"},
+                {"type": "tcp", "port": 22},
+                {"type": "cmd", "script": "true"}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            checks,
+            [
+                HealthCheck::Http { url: "http://localhost:8080/".to_string(), expect_status: 200 },
+                HealthCheck::Tcp { port: 22, host: None },
+                HealthCheck::Cmd { script: "true".to_string() },
+            ]
+        );
+        let tpl: ApplianceTemplate =
+            serde_json::from_value(serde_json::to_value(template("plain")).unwrap()).unwrap();
+        assert_eq!(tpl.health_checks, template("plain").health_checks);
+    }
+
+    #[tokio::test]
+    async fn test_local_templates_and_duplicates() {
+        let root = temp_root();
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("one.json"), serde_json::to_vec(&template("local-one")).unwrap()).unwrap();
+        let pack = TemplatePack {
+            name: "local".to_string(),
+            description: String::new(),
+            templates: vec![template("local-two"), template("keycloak-aarch64")],
+        };
+        std::fs::write(root.join("pack.json"), serde_json::to_vec(&pack).unwrap()).unwrap();
+        std::fs::write(root.join("broken.json"), "{").unwrap();
+
+        let mut registry = TemplateRegistry::new(root.clone());
+        registry.reload().await.unwrap();
+
+        assert!(registry.get("local-one").is_some());
+        assert!(registry.get("local-two").is_some());
+        assert_eq!(registry.entries().iter().find(|e| e.template.id == "keycloak-aarch64").unwrap().source, "builtin");
+        let pack_status = registry.sources().iter().find(|s| s.name == "pack.json").unwrap();
+        assert_eq!(pack_status.skipped, vec!["keycloak-aarch64".to_string()]);
+        assert!(registry.sources().iter().find(|s| s.name == "broken.json").unwrap().error.is_some());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_published_pack_is_signed_and_verified() {
+        let root = temp_root();
+        let mut registry = TemplateRegistry::new(root.clone());
+        registry.reload().await.unwrap();
+
+        let published = registry
+            .publish(TemplatePack {
+                name: "team".to_string(),
+                description: "Team templates".to_string(),
+                templates: vec![template("team-base")],
+            })
+            .await
+            .unwrap();
+        assert_eq!(registry.get("team-base").unwrap().id, "team-base");
+
+        let (data, signature) = registry.pack_file("team").await.unwrap();
+        verify_pack(&data, &signature, &published.public_key).unwrap();
+
+        // A tampered pack no longer loads
+        let path = root.join(PACKS_DIR).join("team.json");
+        let tampered = String::from_utf8(data).unwrap().replace("team-base", "team-evil");
+        std::fs::write(&path, tampered).unwrap();
+        registry.reload().await.unwrap();
+        assert!(registry.get("team-evil").is_none());
+        assert!(registry.sources().iter().find(|s| s.name == "team").unwrap().error.is_some());
+
+        assert!(registry.unpublish("team").await.unwrap());
+        assert!(registry.publish(TemplatePack {
+            name: "../escape".to_string(),
+            description: String::new(),
+            templates: vec![template("x")],
+        }).await.is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_catalog_cache_is_verified_against_config_key() {
+        let root = temp_root();
+        let signer = KeyPair::generate();
+        let pack = TemplatePack {
+            name: "community".to_string(),
+            description: String::new(),
+            templates: vec![template("community-base")],
+        };
+        let data = serde_json::to_vec(&pack).unwrap();
+        let cache = root.join(CACHE_DIR).join("community");
+        std::fs::create_dir_all(&cache).unwrap();
+        std::fs::write(cache.join(PACK_FILE), &data).unwrap();
+        std::fs::write(cache.join("catalog.json.sig"), hex::encode(signer.sign(&data))).unwrap();
+
+        let config = |key: String| {
+            serde_json::json!({"catalogs": [
+                {"name": "community", "url": "https://example.invalid/catalog.json", "public_key": key},
+                {"name": "pending", "url": "https://example.invalid/other.json", "public_key": key},
+            ]})
+            .to_string()
+        };
+        std::fs::write(root.join(CATALOGS_FILE), config(signer.public_key_hex())).unwrap();
+
+        let mut registry = TemplateRegistry::new(root.clone());
+        registry.reload().await.unwrap();
+        assert!(registry.get("community-base").is_some());
+        assert!(registry.sources().iter().find(|s| s.name == "pending").unwrap().error.is_some());
+
+        std::fs::write(root.join(CATALOGS_FILE), config(KeyPair::generate().public_key_hex())).unwrap();
+        registry.reload().await.unwrap();
+        assert!(registry.get("community-base").is_none());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Shared daemon channel and response cache for the web console.
+//!
+//! Pages used to dial the daemon on every request and fetch the same lists
+//! several times over. The console now shares one channel, dialled on first
+//! use; tonic re-establishes its connection if the daemon restarts, and a
+//! failed first dial is retried on the next call. List and get responses are
+//! kept for [`CACHE_TTL`] and dropped as soon as the daemon's event stream
+//! reports a change to a resource type they hold, or a write goes through the
+//! console. While the event stream is down nothing is served from the cache,
+//! since changes could go unnoticed.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use infrasim_common::auth::{AuthChannel, ClientCredentials};
+use tracing::{debug, warn};
+
+use crate::generated::infrasim::infra_sim_daemon_client::InfraSimDaemonClient;
+use crate::generated::infrasim::WatchEventsRequest;
+
+/// How long a response is served without asking the daemon again; VM uptime
+/// and volume sizes change without events, so this stays short
+pub const CACHE_TTL: Duration = Duration::from_secs(2);
+/// Delay before the event stream is reopened after it ends
+const WATCH_RETRY: Duration = Duration::from_secs(5);
+
+/// One channel to the daemon, shared by every request
+pub struct DaemonChannel {
+    endpoint: String,
+    credentials: ClientCredentials,
+    channel: tokio::sync::Mutex<Option<AuthChannel>>,
+}
+
+impl DaemonChannel {
+    pub fn new(endpoint: String, credentials: ClientCredentials) -> Self {
+        Self {
+            endpoint,
+            credentials,
+            channel: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// The shared channel, dialled on first use
+    pub async fn get(&self) -> anyhow::Result<AuthChannel> {
+        // Held across the dial, so concurrent first requests share one connection
+        let mut channel = self.channel.lock().await;
+        if let Some(channel) = channel.as_ref() {
+            return Ok(channel.clone());
+        }
+        let dialled = self.credentials.connect(&self.endpoint).await?;
+        *channel = Some(dialled.clone());
+        Ok(dialled)
+    }
+}
+
+struct Entry {
+    resource_types: &'static [&'static str],
+    stored_at: Instant,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+/// Recent daemon responses, keyed by request
+#[derive(Default)]
+pub struct ResponseCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    /// Bumped on every invalidation, so responses fetched before it aren't stored
+    generation: AtomicU64,
+    /// Whether the event stream is open
+    live: AtomicBool,
+}
+
+impl ResponseCache {
+    /// A fresh cached response for `key`, or the result of `fetch`
+    ///
+    /// `resource_types` are the daemon resource types whose events make the
+    /// response stale.
+    pub async fn get_or_fetch<T, F, Fut>(
+        &self,
+        key: String,
+        resource_types: &'static [&'static str],
+        fetch: F,
+    ) -> anyhow::Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        if let Some(value) = self.lookup::<T>(&key) {
+            return Ok(value);
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let value = fetch().await?;
+        if self.live.load(Ordering::SeqCst) {
+            let mut entries = self.entries.lock().unwrap();
+            if self.generation.load(Ordering::SeqCst) == generation {
+                entries.insert(
+                    key,
+                    Entry {
+                        resource_types,
+                        stored_at: Instant::now(),
+                        value: Arc::new(value.clone()),
+                    },
+                );
+            }
+        }
+        Ok(value)
+    }
+
+    /// Drop responses holding resources of this type
+    pub fn invalidate(&self, resource_type: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.retain(|_, entry| !entry.resource_types.contains(&resource_type));
+    }
+
+    /// Drop every response
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.clear();
+    }
+
+    fn lookup<T: Clone + 'static>(&self, key: &str) -> Option<T> {
+        if !self.live.load(Ordering::SeqCst) {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|entry| entry.stored_at.elapsed() < CACHE_TTL)
+            .and_then(|entry| entry.value.downcast_ref::<T>().cloned())
+    }
+
+    fn set_live(&self, live: bool) {
+        // Whatever changed while the stream was down is unknown
+        self.clear();
+        self.live.store(live, Ordering::SeqCst);
+    }
+
+    /// Follow the daemon's events for as long as the console runs, dropping
+    /// stale responses as they arrive
+    pub fn spawn_invalidation(
+        self: Arc<Self>,
+        channel: Arc<DaemonChannel>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.follow_events(&channel).await {
+                    debug!("daemon event stream unavailable: {}", e);
+                }
+                self.set_live(false);
+                tokio::time::sleep(WATCH_RETRY).await;
+            }
+        })
+    }
+
+    async fn follow_events(&self, channel: &DaemonChannel) -> anyhow::Result<()> {
+        let mut client = InfraSimDaemonClient::new(channel.get().await?);
+        let mut stream = client
+            .watch_events(WatchEventsRequest {
+                resource_type: String::new(),
+                resource_id: String::new(),
+                types: Vec::new(),
+                tail: 0,
+                follow: true,
+            })
+            .await?
+            .into_inner();
+        self.set_live(true);
+
+        while let Some(event) = stream.message().await? {
+            self.invalidate(&event.resource_type);
+        }
+        warn!("daemon event stream ended; response cache disabled until it reopens");
+        Ok(())
+    }
+}
@@ -0,0 +1,737 @@
+//! Appliance image builds.
+//!
+//! Turns an [`ApplianceBuildSpec`] into a bootable disk image:
+//!
+//! 1. The base image is pulled for the target architecture.
+//! 2. A generated Dockerfile applies the overlays and, unless the spec brings
+//!    a prebuilt kernel, installs a kernel, initramfs and init system.
+//! 3. The resulting container's filesystem is exported.
+//! 4. A helper container packs it into an ext4 filesystem as root, so file
+//!    ownership survives without the web server needing privileges.
+//! 5. The kernel and initramfs are copied out of `/boot`; the image has no
+//!    bootloader and is booted directly with them (`root=/dev/vda`).
+//! 6. `qemu-img` converts the filesystem to the requested format.
+//!
+//! Progress is kept in a [`BuildProgress`], which clients can follow.
+
+use super::{
+    ApplianceBuildResult, ApplianceBuildSpec, BuildStatus, ContainerRuntime, OutputFormat, OverlayType,
+};
+use infrasim_common::slsa::ResourceDescriptor;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::broadcast;
+
+/// Image the root filesystem is packed in, run for the host architecture
+const PACKER_IMAGE: &str = "docker.io/library/alpine:3.19";
+
+/// Free space added to the root filesystem, in MB
+const ROOTFS_HEADROOM_MB: u64 = 512;
+
+/// Events buffered for slow followers before they start missing log lines
+const EVENT_BUFFER: usize = 1024;
+
+/// Where overlay files, the cloud-init seed and helper scripts go in the build context
+const CONTEXT_DIR: &str = "context";
+const ROOTFS_TAR: &str = "rootfs.tar";
+const ROOTFS_IMG: &str = "rootfs.img";
+
+/// Installs packages with whichever package manager the image has
+const INSTALL_PACKAGES_SCRIPT: &str = r#"#!/bin/sh
+set -e
+if command -v apk >/dev/null 2>&1; then
+    apk add --no-cache "$@"
+elif command -v apt-get >/dev/null 2>&1; then
+    apt-get update
+    DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends "$@"
+    rm -rf /var/lib/apt/lists/*
+elif command -v dnf >/dev/null 2>&1; then
+    dnf install -y "$@"
+    dnf clean all
+elif command -v yum >/dev/null 2>&1; then
+    yum install -y "$@"
+    yum clean all
+else
+    echo "No supported package manager (apk, apt-get, dnf, yum)" >&2
+    exit 1
+fi
+"#;
+
+/// Installs a kernel, initramfs and init system; takes the architecture and console
+const INSTALL_KERNEL_SCRIPT: &str = r#"#!/bin/sh
+set -e
+arch="$1"
+console="$2"
+. /etc/os-release 2>/dev/null || true
+if command -v apk >/dev/null 2>&1; then
+    apk add --no-cache linux-virt openrc
+    for service in devfs dmesg mdev; do rc-update add "$service" sysinit; done
+    for service in hostname bootmisc sysctl; do rc-update add "$service" boot; done
+    sed -i "/^$console:/d" /etc/inittab
+    echo "$console::respawn:/sbin/getty -L 115200 $console vt100" >> /etc/inittab
+elif command -v apt-get >/dev/null 2>&1; then
+    case "$ID:$arch" in
+        ubuntu:*) kernel=linux-image-virtual ;;
+        *:aarch64) kernel=linux-image-arm64 ;;
+        *) kernel=linux-image-amd64 ;;
+    esac
+    apt-get update
+    DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends \
+        "$kernel" initramfs-tools systemd-sysv udev
+    rm -rf /var/lib/apt/lists/*
+elif command -v dnf >/dev/null 2>&1; then
+    dnf install -y kernel-core dracut systemd
+    dnf clean all
+else
+    echo "Can't install a kernel: no supported package manager" >&2
+    exit 1
+fi
+echo "/dev/vda / ext4 defaults 0 1" > /etc/fstab
+"#;
+
+/// Packs the exported filesystem into an ext4 image, inside the packer container
+const PACK_SCRIPT: &str = r#"set -e
+apk add --no-cache e2fsprogs tar >/dev/null
+mkdir /rootfs
+tar -xpf /build/rootfs.tar -C /rootfs --numeric-owner
+mke2fs -q -t ext4 -L rootfs -d /rootfs /build/rootfs.img "${ROOTFS_SIZE_MB}M"
+"#;
+
+/// A build update sent to followers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BuildEvent {
+    /// State of the build when a follower joined
+    Snapshot { build: ApplianceBuildResult },
+    Status { status: BuildStatus },
+    Log { line: String },
+    /// The build finished, successfully or not
+    Done { build: ApplianceBuildResult },
+}
+
+/// Live state of a build
+pub struct BuildProgress {
+    result: Mutex<ApplianceBuildResult>,
+    events: broadcast::Sender<BuildEvent>,
+}
+
+impl BuildProgress {
+    pub fn new(id: String, name: String) -> Self {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            result: Mutex::new(ApplianceBuildResult {
+                id,
+                name,
+                status: BuildStatus::Pending,
+                output_path: None,
+                output_size_bytes: 0,
+                output_digest: None,
+                build_log: Vec::new(),
+                started_at: chrono::Utc::now().timestamp(),
+                completed_at: None,
+                error: None,
+                kernel_path: None,
+                initrd_path: None,
+                kernel_args: None,
+                volume_id: None,
+            }),
+            events,
+        }
+    }
+
+    /// Current state of the build
+    pub fn result(&self) -> ApplianceBuildResult {
+        self.result.lock().unwrap().clone()
+    }
+
+    /// Current state plus every event after it
+    pub fn subscribe(&self) -> (ApplianceBuildResult, broadcast::Receiver<BuildEvent>) {
+        // Events are sent under the lock, so none fall between the two
+        let result = self.result.lock().unwrap();
+        (result.clone(), self.events.subscribe())
+    }
+
+    pub fn set_status(&self, status: BuildStatus) {
+        let mut result = self.result.lock().unwrap();
+        result.status = status.clone();
+        let _ = self.events.send(BuildEvent::Status { status });
+    }
+
+    pub fn log(&self, line: impl Into<String>) {
+        let line = line.into();
+        let mut result = self.result.lock().unwrap();
+        result.build_log.push(line.clone());
+        let _ = self.events.send(BuildEvent::Log { line });
+    }
+
+    /// Record the built image and the volume registered for it
+    pub fn complete(&self, image: &BuiltImage, volume_id: Option<String>) {
+        let mut result = self.result.lock().unwrap();
+        result.status = BuildStatus::Complete;
+        result.output_path = Some(image.output.clone());
+        result.output_size_bytes = image.size_bytes as i64;
+        result.output_digest = image.digest.clone();
+        result.kernel_path = image.kernel.as_ref().map(|p| p.display().to_string());
+        result.initrd_path = image.initrd.as_ref().map(|p| p.display().to_string());
+        result.kernel_args = image.kernel_args.clone();
+        result.volume_id = volume_id;
+        result.completed_at = Some(chrono::Utc::now().timestamp());
+        let _ = self.events.send(BuildEvent::Done { build: result.clone() });
+    }
+
+    pub fn fail(&self, error: String) {
+        let mut result = self.result.lock().unwrap();
+        result.build_log.push(format!("error: {}", error));
+        result.status = BuildStatus::Failed;
+        result.error = Some(error);
+        result.completed_at = Some(chrono::Utc::now().timestamp());
+        let _ = self.events.send(BuildEvent::Done { build: result.clone() });
+    }
+}
+
+/// What a build produced
+#[derive(Debug, Clone)]
+pub struct BuiltImage {
+    /// Disk image path, or the image tag for container output
+    pub output: String,
+    /// `qcow2` or `raw`; `None` for container output
+    pub disk_format: Option<&'static str>,
+    pub size_bytes: u64,
+    pub digest: Option<String>,
+    pub kernel: Option<PathBuf>,
+    pub initrd: Option<PathBuf>,
+    pub kernel_args: Option<String>,
+    /// Base image, overlay files and boot files the build used
+    pub inputs: Vec<ResourceDescriptor>,
+    /// Container runtime that ran the build, and its version
+    pub runtime: &'static str,
+    pub runtime_version: String,
+}
+
+/// Builds one appliance image in its own directory
+pub struct ApplianceBuilder {
+    runtime: ContainerRuntime,
+    spec: ApplianceBuildSpec,
+    dir: PathBuf,
+    progress: Arc<BuildProgress>,
+}
+
+impl ApplianceBuilder {
+    /// Check the spec and detect the container runtime
+    pub fn new(spec: ApplianceBuildSpec, dir: PathBuf, progress: Arc<BuildProgress>) -> Result<Self, String> {
+        let runtime = ContainerRuntime::detect().ok_or("No container runtime available")?;
+        platform(&spec.arch)?;
+        if spec.initrd.is_some() && spec.kernel.is_none() {
+            return Err("An initrd needs a kernel to go with it".to_string());
+        }
+        for overlay in &spec.overlays {
+            if overlay.overlay_type == OverlayType::Files
+                && (overlay.source_path.is_none() || overlay.dest_path.is_none())
+            {
+                return Err(format!("Files overlay '{}' needs a source and destination path", overlay.name));
+            }
+            if let Some(key) = overlay.env_vars.keys().find(|k| !is_env_name(k)) {
+                return Err(format!("Invalid environment variable name '{}'", key));
+            }
+        }
+        Ok(Self { runtime, spec, dir, progress })
+    }
+
+    /// Run the build, cleaning up intermediate files and images whatever the outcome
+    pub async fn run(&self) -> Result<BuiltImage, String> {
+        let tag = format!("localhost/infrasim-build:{}", self.progress.result().id);
+        let result = self.build(&tag).await;
+
+        let _ = tokio::fs::remove_dir_all(self.dir.join(CONTEXT_DIR)).await;
+        let _ = tokio::fs::remove_file(self.dir.join(ROOTFS_TAR)).await;
+        let _ = tokio::fs::remove_file(self.dir.join(ROOTFS_IMG)).await;
+        if self.spec.output_format != OutputFormat::Container || result.is_err() {
+            let _ = self.runtime_output(&["rmi", &tag]).await;
+        }
+        result
+    }
+
+    async fn build(&self, tag: &str) -> Result<BuiltImage, String> {
+        let platform = platform(&self.spec.arch)?;
+        tokio::fs::create_dir_all(&self.dir).await.map_err(|e| e.to_string())?;
+
+        self.progress.set_status(BuildStatus::Pulling);
+        self.run_logged(self.runtime.command(), &["pull", "--platform", platform, &self.spec.base_image])
+            .await?;
+        let inputs = self.resolve_inputs().await;
+        let runtime_version = self
+            .runtime_output(&["version", "--format", "{{.Client.Version}}"])
+            .await
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default();
+
+        self.progress.set_status(BuildStatus::Building);
+        let context = self.dir.join(CONTEXT_DIR);
+        self.write_context(&context).await?;
+        let context = context.to_string_lossy().to_string();
+        self.run_logged(
+            self.runtime.command(),
+            &["build", "--platform", platform, "-t", tag, &context],
+        )
+        .await?;
+
+        if self.spec.output_format == OutputFormat::Container {
+            self.progress.log(format!("Built container image {}", tag));
+            return Ok(BuiltImage {
+                output: tag.to_string(),
+                disk_format: None,
+                size_bytes: 0,
+                digest: None,
+                kernel: None,
+                initrd: None,
+                kernel_args: None,
+                inputs,
+                runtime: self.runtime.command(),
+                runtime_version,
+            });
+        }
+
+        let rootfs = self.dir.join(ROOTFS_TAR);
+        self.export(tag, &rootfs).await?;
+        let (kernel, initrd) = self.boot_files(&rootfs).await?;
+
+        self.progress.set_status(BuildStatus::Converting);
+        let tar_mb = tokio::fs::metadata(&rootfs).await.map_err(|e| e.to_string())?.len() / (1024 * 1024);
+        let size_mb = self.disk_size_mb(tar_mb)?;
+        self.progress.log(format!("Packing {} MB root filesystem into a {} MB ext4 image", tar_mb, size_mb));
+        let volume = format!("{}:/build", self.dir.display());
+        let size = format!("ROOTFS_SIZE_MB={}", size_mb);
+        self.run_logged(
+            self.runtime.command(),
+            &["run", "--rm", "-v", &volume, "-e", &size, PACKER_IMAGE, "sh", "-c", PACK_SCRIPT],
+        )
+        .await?;
+
+        let raw = self.dir.join(ROOTFS_IMG);
+        let (output, disk_format) = match self.spec.output_format {
+            OutputFormat::Raw => {
+                let output = self.dir.join("disk.img");
+                tokio::fs::rename(&raw, &output).await.map_err(|e| e.to_string())?;
+                (output, "raw")
+            }
+            _ => {
+                let output = self.dir.join("disk.qcow2");
+                let (raw, out) = (raw.to_string_lossy(), output.to_string_lossy());
+                self.run_logged("qemu-img", &["convert", "-f", "raw", "-O", "qcow2", "-c", &raw, &out])
+                    .await?;
+                (output, "qcow2")
+            }
+        };
+
+        let size_bytes = tokio::fs::metadata(&output).await.map_err(|e| e.to_string())?.len();
+        let digest = infrasim_common::ContentAddressedStore::hash_file(&output)
+            .await
+            .map_err(|e| e.to_string())?;
+        self.progress.log(format!("Wrote {} ({} bytes, sha256:{})", output.display(), size_bytes, digest));
+
+        Ok(BuiltImage {
+            output: output.display().to_string(),
+            disk_format: Some(disk_format),
+            size_bytes,
+            digest: Some(digest),
+            kernel: Some(kernel),
+            initrd,
+            kernel_args: Some(self.kernel_args()),
+            inputs,
+            runtime: self.runtime.command(),
+            runtime_version,
+        })
+    }
+
+    /// Base image and input files of the build, with digests where they can
+    /// be taken, for the image's provenance
+    async fn resolve_inputs(&self) -> Vec<ResourceDescriptor> {
+        let inspect = self
+            .runtime_output(&[
+                "image",
+                "inspect",
+                "--format",
+                "{{.Id}} {{range .RepoDigests}}{{.}} {{end}}",
+                &self.spec.base_image,
+            ])
+            .await
+            .unwrap_or_default();
+        // A registry digest can be pulled by; the image ID only identifies the local copy
+        let mut tokens = inspect.split_whitespace();
+        let id = tokens.next();
+        let digest = tokens
+            .find_map(|t| t.split_once('@').map(|(_, digest)| digest))
+            .or(id)
+            .and_then(|d| d.strip_prefix("sha256:"));
+        let mut inputs = vec![ResourceDescriptor::new(
+            "base_image",
+            format!("docker://{}", self.spec.base_image),
+            digest,
+        )];
+
+        let files = self
+            .spec
+            .overlays
+            .iter()
+            .filter(|o| matches!(o.overlay_type, OverlayType::Files | OverlayType::CloudInit))
+            .filter_map(|o| Some((o.name.as_str(), o.source_path.as_deref()?)))
+            .chain(self.spec.kernel.as_deref().map(|k| ("kernel", k)))
+            .chain(self.spec.initrd.as_deref().map(|i| ("initrd", i)));
+        for (name, path) in files {
+            // Directories are recorded by path alone
+            let digest = match tokio::fs::metadata(path).await {
+                Ok(meta) if meta.is_file() => infrasim_common::ContentAddressedStore::hash_file(path).await.ok(),
+                _ => None,
+            };
+            inputs.push(ResourceDescriptor::new(name, format!("file://{}", path), digest.as_deref()));
+        }
+        inputs
+    }
+
+    /// Write the Dockerfile and everything it copies into the build context
+    async fn write_context(&self, context: &Path) -> Result<(), String> {
+        let _ = tokio::fs::remove_dir_all(context).await;
+        tokio::fs::create_dir_all(context).await.map_err(|e| e.to_string())?;
+
+        for (index, overlay) in self.spec.overlays.iter().enumerate() {
+            if overlay.overlay_type != OverlayType::Files && overlay.overlay_type != OverlayType::CloudInit {
+                continue;
+            }
+            let Some(source) = &overlay.source_path else { continue };
+            let dest = context.join(format!("overlay-{}", index));
+            // `cp -a` keeps modes and copies directories as they are
+            let output = Command::new("cp")
+                .args(["-a", source])
+                .arg(&dest)
+                .output()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !output.status.success() {
+                return Err(format!(
+                    "Overlay '{}': can't copy {}: {}",
+                    overlay.name,
+                    source,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+        }
+
+        let mut files = vec![
+            ("install-packages.sh", INSTALL_PACKAGES_SCRIPT.to_string()),
+            ("install-kernel.sh", INSTALL_KERNEL_SCRIPT.to_string()),
+            ("Dockerfile", dockerfile(&self.spec)),
+        ];
+        if let Some(cloud_init) = &self.spec.cloud_init {
+            files.push(("user-data", cloud_init.user_data.clone()));
+            let meta_data = cloud_init.meta_data.clone().unwrap_or_else(|| {
+                format!("instance-id: {}\nlocal-hostname: {}\n", self.progress.result().id, self.spec.name)
+            });
+            files.push(("meta-data", meta_data));
+            if let Some(network_config) = &cloud_init.network_config {
+                files.push(("network-config", network_config.clone()));
+            }
+        }
+        for (name, contents) in files {
+            tokio::fs::write(context.join(name), contents).await.map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Export the built image's filesystem as a tarball
+    async fn export(&self, tag: &str, rootfs: &Path) -> Result<(), String> {
+        let container = self.runtime_output(&["create", "--platform", platform(&self.spec.arch)?, tag]).await?;
+        let container = container.trim();
+        self.progress.log(format!("Exporting filesystem of container {}", container));
+        let result = self
+            .runtime_output(&["export", "-o", &rootfs.to_string_lossy(), container])
+            .await;
+        let _ = self.runtime_output(&["rm", container]).await;
+        result.map(|_| ())
+    }
+
+    /// Kernel and initramfs to boot with, from the spec or the image's `/boot`
+    async fn boot_files(&self, rootfs: &Path) -> Result<(PathBuf, Option<PathBuf>), String> {
+        if let Some(kernel) = &self.spec.kernel {
+            let initrd = self.spec.initrd.as_ref().map(PathBuf::from);
+            return Ok((PathBuf::from(kernel), initrd));
+        }
+
+        let rootfs = rootfs.to_path_buf();
+        let dir = self.dir.clone();
+        let (kernel, initrd) = tokio::task::spawn_blocking(move || extract_boot_files(&rootfs, &dir))
+            .await
+            .map_err(|e| e.to_string())??;
+        let kernel = kernel.ok_or("No kernel found in /boot of the image; provide a prebuilt kernel")?;
+        self.progress.log(format!("Extracted kernel {}", kernel.display()));
+        if let Some(initrd) = &initrd {
+            self.progress.log(format!("Extracted initramfs {}", initrd.display()));
+        }
+        Ok((kernel, initrd))
+    }
+
+    fn disk_size_mb(&self, rootfs_mb: u64) -> Result<u64, String> {
+        let needed = rootfs_mb + rootfs_mb / 4 + ROOTFS_HEADROOM_MB;
+        match self.spec.disk_size_mb {
+            Some(size) if size < 0 || (size as u64) < rootfs_mb + rootfs_mb / 4 => Err(format!(
+                "Disk size of {} MB is too small for a {} MB root filesystem",
+                size, rootfs_mb
+            )),
+            Some(size) => Ok(size as u64),
+            None => Ok(needed),
+        }
+    }
+
+    fn kernel_args(&self) -> String {
+        self.spec.kernel_args.clone().unwrap_or_else(|| {
+            let console = if self.spec.arch == "x86_64" { "ttyS0" } else { "ttyAMA0" };
+            format!("root=/dev/vda rootfstype=ext4 rw console={}", console)
+        })
+    }
+
+    /// Run a command, sending its output to the build log as it arrives
+    async fn run_logged(&self, program: &str, args: &[&str]) -> Result<(), String> {
+        self.progress.log(format!("$ {} {}", program, args.join(" ")));
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+        let stdout = log_lines(child.stdout.take(), self.progress.clone());
+        let stderr = log_lines(child.stderr.take(), self.progress.clone());
+        let status = child.wait().await.map_err(|e| e.to_string())?;
+        let _ = tokio::join!(stdout, stderr);
+
+        if !status.success() {
+            return Err(format!("{} {} failed with {}", program, args.first().unwrap_or(&""), status));
+        }
+        Ok(())
+    }
+
+    /// Run a short container runtime command and return its output
+    async fn runtime_output(&self, args: &[&str]) -> Result<String, String> {
+        let output = Command::new(self.runtime.command())
+            .args(args)
+            .output()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Container platform of an appliance architecture
+fn platform(arch: &str) -> Result<&'static str, String> {
+    match arch {
+        "aarch64" => Ok("linux/arm64"),
+        "x86_64" => Ok("linux/amd64"),
+        other => Err(format!("Unsupported architecture: {}", other)),
+    }
+}
+
+/// Copy a reader's lines into the build log
+fn log_lines<R>(reader: Option<R>, progress: Arc<BuildProgress>) -> tokio::task::JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let Some(reader) = reader else { return };
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            progress.log(line);
+        }
+    })
+}
+
+/// Dockerfile applying a spec's overlays to its base image
+pub fn dockerfile(spec: &ApplianceBuildSpec) -> String {
+    let mut lines = vec![
+        format!("FROM {}", spec.base_image),
+        "COPY install-packages.sh install-kernel.sh /tmp/infrasim/".to_string(),
+    ];
+
+    let mut environment = Vec::new();
+    for (index, overlay) in spec.overlays.iter().enumerate() {
+        match overlay.overlay_type {
+            OverlayType::Files => {
+                if let Some(dest) = &overlay.dest_path {
+                    // The JSON form takes paths with spaces
+                    let paths = serde_json::json!([format!("overlay-{}", index), dest]);
+                    lines.push(format!("COPY {}", paths));
+                }
+            }
+            OverlayType::Shell => {
+                for command in &overlay.commands {
+                    lines.push(format!("RUN {}", command));
+                }
+            }
+            OverlayType::Packages => {
+                if !overlay.packages.is_empty() {
+                    let packages: Vec<_> = overlay.packages.iter().map(|p| quote(p)).collect();
+                    lines.push(format!("RUN sh /tmp/infrasim/install-packages.sh {}", packages.join(" ")));
+                }
+            }
+            OverlayType::Environment => {
+                let mut vars: Vec<_> = overlay.env_vars.iter().collect();
+                vars.sort();
+                for (key, value) in vars {
+                    lines.push(format!("ENV {}={}", key, quote(value)));
+                    environment.push(format!("{}={}", key, value));
+                }
+            }
+            OverlayType::CloudInit => {
+                if overlay.source_path.is_some() {
+                    lines.push(format!("COPY overlay-{} /var/lib/cloud/seed/nocloud/user-data", index));
+                }
+            }
+        }
+    }
+
+    // Container environment isn't seen by a booted VM
+    if !environment.is_empty() {
+        let script = environment
+            .iter()
+            .map(|line| format!("echo {} >> /etc/environment", quote(line)))
+            .collect::<Vec<_>>()
+            .join(" && ");
+        lines.push(format!("RUN {}", script));
+    }
+
+    if let Some(cloud_init) = &spec.cloud_init {
+        let mut seed = vec!["user-data", "meta-data"];
+        if cloud_init.network_config.is_some() {
+            seed.push("network-config");
+        }
+        lines.push(format!("COPY {} /var/lib/cloud/seed/nocloud/", seed.join(" ")));
+    }
+
+    if spec.kernel.is_none() && spec.output_format != OutputFormat::Container {
+        let console = if spec.arch == "x86_64" { "ttyS0" } else { "ttyAMA0" };
+        lines.push(format!("RUN sh /tmp/infrasim/install-kernel.sh {} {}", spec.arch, console));
+    }
+    lines.push("RUN rm -rf /tmp/infrasim".to_string());
+
+    let mut dockerfile = lines.join("\n");
+    dockerfile.push('\n');
+    dockerfile
+}
+
+fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quote a value for a Dockerfile or shell word
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$"))
+}
+
+/// Copy the newest kernel and initramfs in `boot/` out of a root filesystem tarball
+fn extract_boot_files(rootfs: &Path, dir: &Path) -> Result<(Option<PathBuf>, Option<PathBuf>), String> {
+    let file = std::fs::File::open(rootfs).map_err(|e| e.to_string())?;
+    let mut archive = tar::Archive::new(file);
+    let (mut kernel, mut initrd) = (None, None);
+
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(|e| e.to_string())?.into_owned();
+        let path = path.strip_prefix("./").unwrap_or(&path);
+        if path.parent() != Some(Path::new("boot")) {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let (target, slot) = if ["vmlinuz", "vmlinux", "Image"].iter().any(|p| name.starts_with(p)) {
+            ("vmlinuz", &mut kernel)
+        } else if ["initramfs", "initrd"].iter().any(|p| name.starts_with(p)) {
+            ("initrd", &mut initrd)
+        } else {
+            continue;
+        };
+        // Later entries win; versioned names sort the newest last
+        let target = dir.join(target);
+        entry.unpack(&target).map_err(|e| e.to_string())?;
+        *slot = Some(target);
+    }
+
+    Ok((kernel, initrd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::ImageOverlay;
+    use std::collections::HashMap;
+
+    fn spec(overlays: Vec<ImageOverlay>) -> ApplianceBuildSpec {
+        ApplianceBuildSpec {
+            name: "web".to_string(),
+            description: None,
+            base_image: "alpine:3.19".to_string(),
+            arch: "aarch64".to_string(),
+            memory_mb: 1024,
+            cpu_cores: 1,
+            interfaces: vec![],
+            overlays,
+            output_format: OutputFormat::Qcow2,
+            cloud_init: None,
+            kernel: None,
+            initrd: None,
+            kernel_args: None,
+            disk_size_mb: None,
+        }
+    }
+
+    fn overlay(overlay_type: OverlayType) -> ImageOverlay {
+        ImageOverlay {
+            id: "1".to_string(),
+            name: "overlay".to_string(),
+            overlay_type,
+            source_path: None,
+            dest_path: None,
+            commands: vec![],
+            packages: vec![],
+            env_vars: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_dockerfile() {
+        let mut files = overlay(OverlayType::Files);
+        files.source_path = Some("./site".to_string());
+        files.dest_path = Some("/srv/www".to_string());
+        let mut packages = overlay(OverlayType::Packages);
+        packages.packages = vec!["nginx".to_string()];
+        let mut env = overlay(OverlayType::Environment);
+        env.env_vars.insert("GREETING".to_string(), "say \"hi\"".to_string());
+
+        let dockerfile = dockerfile(&spec(vec![files, packages, env]));
+        let lines: Vec<_> = dockerfile.lines().collect();
+        assert_eq!(lines[0], "FROM alpine:3.19");
+        assert!(lines.contains(&"COPY [\"overlay-0\",\"/srv/www\"]"));
+        assert!(lines.contains(&"RUN sh /tmp/infrasim/install-packages.sh \"nginx\""));
+        assert!(lines.contains(&"ENV GREETING=\"say \\\"hi\\\"\""));
+        assert!(lines.contains(&"RUN sh /tmp/infrasim/install-kernel.sh aarch64 ttyAMA0"));
+        assert_eq!(lines.last(), Some(&"RUN rm -rf /tmp/infrasim"));
+    }
+
+    #[test]
+    fn test_dockerfile_prebuilt_kernel() {
+        let mut spec = spec(vec![]);
+        spec.kernel = Some("/boot/vmlinuz".to_string());
+        assert!(!dockerfile(&spec).contains("install-kernel.sh aarch64"));
+        assert!(platform("riscv64").is_err());
+    }
+}
@@ -7,6 +7,10 @@
 //! - Converting container images to qcow2 VM images
 //! - Defining network interfaces for appliances
 
+pub mod build;
+
+pub use build::{ApplianceBuilder, BuildEvent, BuildProgress, BuiltImage};
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::process::Command;
@@ -188,6 +192,18 @@ pub struct ApplianceBuildSpec {
     pub output_format: OutputFormat,
     /// Cloud-init user-data (optional)
     pub cloud_init: Option<CloudInitConfig>,
+    /// Prebuilt kernel to boot instead of installing one into the image
+    #[serde(default)]
+    pub kernel: Option<String>,
+    /// Initramfs to go with a prebuilt kernel
+    #[serde(default)]
+    pub initrd: Option<String>,
+    /// Kernel command line (defaults to booting the root filesystem on the serial console)
+    #[serde(default)]
+    pub kernel_args: Option<String>,
+    /// Disk size in MB (defaults to the root filesystem plus some headroom)
+    #[serde(default)]
+    pub disk_size_mb: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -231,6 +247,16 @@ pub struct ApplianceBuildResult {
     pub started_at: i64,
     pub completed_at: Option<i64>,
     pub error: Option<String>,
+    /// Kernel and initramfs to boot the image with
+    #[serde(default)]
+    pub kernel_path: Option<String>,
+    #[serde(default)]
+    pub initrd_path: Option<String>,
+    #[serde(default)]
+    pub kernel_args: Option<String>,
+    /// Daemon volume registered for the image
+    #[serde(default)]
+    pub volume_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
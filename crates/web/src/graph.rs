@@ -0,0 +1,614 @@
+//! Resource graph planning
+//!
+//! The resource graph holds appliances and filesystems as nodes, with
+//! `attached_to` edges from a filesystem to the appliances using it. A draft
+//! graph is validated and diffed against the live graph into an ordered list
+//! of operations; executing them is left to the server, which owns the
+//! resources.
+//!
+//! Appliances are read-only here: they are created and archived through the
+//! appliance API, and only serve as attachment targets.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+pub const APPLIANCE_NODE: &str = "appliance";
+pub const FILESYSTEM_NODE: &str = "filesystem";
+pub const ATTACHED_TO_EDGE: &str = "attached_to";
+
+/// VM label naming the country (ISO 3166-1 alpha-2) an appliance runs in
+pub const COUNTRY_LABEL: &str = "infrasim.country";
+/// VM label naming the region (ISO 3166-2) an appliance runs in
+pub const REGION_LABEL: &str = "infrasim.region";
+
+/// Filesystem attributes a plan can change in place
+const MUTABLE_ATTRIBUTES: &[&str] = &["mount_path", "lifecycle", "labels"];
+/// Filesystem attributes fixed at creation
+const IMMUTABLE_ATTRIBUTES: &[&str] = &[
+    "type",
+    "size_bytes",
+    "backing_store",
+    "mutability",
+    "format",
+    "geographic_bounds",
+];
+
+// ============================================================================
+// Graph model
+// ============================================================================
+
+/// A node in the resource graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub name: String,
+    pub data: Value,
+    #[serde(default)]
+    pub position: Option<NodePosition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodePosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// An edge in the resource graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub edge_type: String,
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// The complete resource graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceGraph {
+    pub nodes: Vec<ResourceNode>,
+    pub edges: Vec<ResourceEdge>,
+    pub version: String,
+    pub computed_at: i64,
+}
+
+/// Plan result for graph changes (Terraform-style)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphPlanResult {
+    pub adds: Vec<PlanChange>,
+    pub updates: Vec<PlanChange>,
+    pub deletes: Vec<PlanChange>,
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub errors: Vec<GraphValidationError>,
+    pub valid: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanChange {
+    pub resource_type: String,
+    pub resource_id: String,
+    pub name: String,
+    /// Human-readable summary of `diffs`
+    #[serde(default)]
+    pub changes: Vec<String>,
+    #[serde(default)]
+    pub diffs: Vec<AttributeDiff>,
+}
+
+/// One attribute changed by a plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeDiff {
+    pub attribute: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// An invariant a graph breaks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphValidationError {
+    pub resource_id: String,
+    pub field: String,
+    pub message: String,
+}
+
+impl GraphValidationError {
+    pub fn new(resource_id: &str, field: &str, message: impl Into<String>) -> Self {
+        Self {
+            resource_id: resource_id.to_string(),
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Request to plan graph changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanGraphRequest {
+    pub draft: ResourceGraph,
+}
+
+/// Request to apply graph changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyGraphRequest {
+    pub draft: ResourceGraph,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Request to validate a graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidateGraphRequest {
+    pub graph: ResourceGraph,
+}
+
+// ============================================================================
+// Planning
+// ============================================================================
+
+/// A change to make to live resources
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphOp {
+    /// Create a filesystem from a draft node
+    CreateFilesystem { node: ResourceNode },
+    /// Set mutable attributes of a filesystem
+    UpdateFilesystem { id: String, attributes: serde_json::Map<String, Value> },
+    DeleteFilesystem { id: String },
+    Attach { filesystem_id: String, appliance_id: String, mount_path: Option<String> },
+    Detach { filesystem_id: String, appliance_id: String },
+}
+
+/// A validated diff between the live and a draft graph
+#[derive(Debug, Clone)]
+pub struct GraphPlan {
+    pub result: GraphPlanResult,
+    /// Operations in execution order: detaches, updates, creates, attaches,
+    /// then deletes, which cannot be rolled back
+    pub operations: Vec<(GraphOp, PlanChange)>,
+}
+
+/// Check a graph's invariants
+///
+/// - node IDs are unique and node types known
+/// - `attached_to` edges run from a filesystem to an appliance, once per pair
+/// - a writable filesystem is attached to at most one appliance
+/// - a geobound filesystem declares allowed countries, and is only attached to
+///   appliances labelled with an allowed country (and region, if restricted)
+pub fn validate(graph: &ResourceGraph) -> Vec<GraphValidationError> {
+    let mut errors = Vec::new();
+    let mut nodes: HashMap<&str, &ResourceNode> = HashMap::new();
+
+    for node in &graph.nodes {
+        if nodes.insert(node.id.as_str(), node).is_some() {
+            errors.push(GraphValidationError::new(&node.id, "id", "duplicate node id"));
+        }
+        if node.node_type != APPLIANCE_NODE && node.node_type != FILESYSTEM_NODE {
+            errors.push(GraphValidationError::new(
+                &node.id,
+                "type",
+                format!("unsupported node type '{}'", node.node_type),
+            ));
+        }
+        if node.node_type == FILESYSTEM_NODE && str_attr(node, "type") == Some("geobound") {
+            let countries = node.data["geographic_bounds"]["allowed_countries"].as_array();
+            if countries.is_none_or(|c| c.is_empty()) {
+                errors.push(GraphValidationError::new(
+                    &node.id,
+                    "geographic_bounds",
+                    "geobound filesystems need allowed_countries",
+                ));
+            }
+        }
+    }
+
+    let mut pairs = HashSet::new();
+    let mut attachments: HashMap<&str, usize> = HashMap::new();
+    for edge in &graph.edges {
+        if edge.edge_type != ATTACHED_TO_EDGE {
+            errors.push(GraphValidationError::new(
+                &edge.id,
+                "type",
+                format!("unsupported edge type '{}'", edge.edge_type),
+            ));
+            continue;
+        }
+        let (Some(fs), Some(appliance)) = (nodes.get(edge.source.as_str()), nodes.get(edge.target.as_str())) else {
+            errors.push(GraphValidationError::new(&edge.id, "source", "edge endpoint does not exist"));
+            continue;
+        };
+        if fs.node_type != FILESYSTEM_NODE || appliance.node_type != APPLIANCE_NODE {
+            errors.push(GraphValidationError::new(
+                &edge.id,
+                "type",
+                "attached_to edges run from a filesystem to an appliance",
+            ));
+            continue;
+        }
+        if !pairs.insert((edge.source.as_str(), edge.target.as_str())) {
+            errors.push(GraphValidationError::new(&edge.id, "target", "duplicate attachment"));
+            continue;
+        }
+
+        *attachments.entry(fs.id.as_str()).or_default() += 1;
+        if let Some(message) = geobound_violation(fs, appliance) {
+            errors.push(GraphValidationError::new(&edge.id, "target", message));
+        }
+    }
+
+    for (fs_id, count) in attachments {
+        let fs = nodes[fs_id];
+        if count > 1 && is_writable(fs) {
+            errors.push(GraphValidationError::new(
+                fs_id,
+                "attached_to",
+                format!("writable filesystem '{}' is attached to {} appliances", fs.name, count),
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Diff a draft graph against the live graph
+pub fn plan(live: &ResourceGraph, draft: &ResourceGraph) -> GraphPlan {
+    let mut result = GraphPlanResult {
+        errors: validate(draft),
+        ..Default::default()
+    };
+    let live_nodes: HashMap<&str, &ResourceNode> = live.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+    let draft_nodes: HashMap<&str, &ResourceNode> = draft.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+
+    let mut updates = Vec::new();
+    let mut creates = Vec::new();
+    let mut deletes = Vec::new();
+
+    for node in &draft.nodes {
+        match (live_nodes.get(node.id.as_str()), node.node_type.as_str()) {
+            (None, APPLIANCE_NODE) => result.errors.push(GraphValidationError::new(
+                &node.id,
+                "id",
+                "appliances are created through /api/appliances",
+            )),
+            (None, FILESYSTEM_NODE) => {
+                let change = change(FILESYSTEM_NODE, &node.id, &node.name, added_diffs(node));
+                creates.push((GraphOp::CreateFilesystem { node: node.clone() }, change));
+            }
+            (Some(live), FILESYSTEM_NODE) => {
+                let diffs = attribute_diffs(live, node);
+                for diff in &diffs {
+                    if !MUTABLE_ATTRIBUTES.contains(&diff.attribute.as_str()) {
+                        result.errors.push(GraphValidationError::new(
+                            &node.id,
+                            &diff.attribute,
+                            format!("changing {} would replace the filesystem; delete and recreate it", diff.attribute),
+                        ));
+                    }
+                }
+                if !diffs.is_empty() {
+                    let attributes = diffs
+                        .iter()
+                        .map(|d| (d.attribute.clone(), d.after.clone()))
+                        .collect();
+                    let change = change(FILESYSTEM_NODE, &node.id, &node.name, diffs);
+                    updates.push((GraphOp::UpdateFilesystem { id: node.id.clone(), attributes }, change));
+                }
+            }
+            (Some(live), _)
+                if live.name != node.name
+                    || node.data.get("labels").is_some_and(|l| live.data.get("labels") != Some(l)) =>
+            {
+                result
+                    .warnings
+                    .push(format!("changes to appliance '{}' are not applied by the graph", node.name));
+            }
+            _ => {}
+        }
+    }
+
+    for node in &live.nodes {
+        if draft_nodes.contains_key(node.id.as_str()) {
+            continue;
+        }
+        if node.node_type == APPLIANCE_NODE {
+            result.errors.push(GraphValidationError::new(
+                &node.id,
+                "id",
+                "appliances are archived through /api/appliances",
+            ));
+        } else {
+            let diffs = vec![AttributeDiff {
+                attribute: "name".to_string(),
+                before: Value::String(node.name.clone()),
+                after: Value::Null,
+            }];
+            let change = change(FILESYSTEM_NODE, &node.id, &node.name, diffs);
+            deletes.push((GraphOp::DeleteFilesystem { id: node.id.clone() }, change));
+        }
+    }
+
+    let live_pairs = attachment_pairs(live);
+    let draft_pairs = attachment_pairs(draft);
+    let mut detaches = Vec::new();
+    let mut attaches = Vec::new();
+    for (fs_id, appliance_id) in live_pairs.difference(&draft_pairs) {
+        let change = attachment_change(fs_id, appliance_id, true);
+        detaches.push((
+            GraphOp::Detach { filesystem_id: fs_id.clone(), appliance_id: appliance_id.clone() },
+            change,
+        ));
+    }
+    for (fs_id, appliance_id) in draft_pairs.difference(&live_pairs) {
+        let mount_path = draft_nodes
+            .get(fs_id.as_str())
+            .and_then(|n| str_attr(n, "mount_path"))
+            .filter(|p| !p.is_empty())
+            .map(str::to_string);
+        let change = attachment_change(fs_id, appliance_id, false);
+        attaches.push((
+            GraphOp::Attach {
+                filesystem_id: fs_id.clone(),
+                appliance_id: appliance_id.clone(),
+                mount_path,
+            },
+            change,
+        ));
+    }
+    // Deterministic order for plans shown to users
+    detaches.sort_by(|a, b| a.1.resource_id.cmp(&b.1.resource_id));
+    attaches.sort_by(|a, b| a.1.resource_id.cmp(&b.1.resource_id));
+
+    result.adds = creates.iter().chain(&attaches).map(|(_, c)| c.clone()).collect();
+    result.updates = updates.iter().map(|(_, c)| c.clone()).collect();
+    result.deletes = detaches.iter().chain(&deletes).map(|(_, c)| c.clone()).collect();
+    result.valid = result.errors.is_empty();
+
+    let operations = detaches
+        .into_iter()
+        .chain(updates)
+        .chain(creates)
+        .chain(attaches)
+        .chain(deletes)
+        .collect();
+    GraphPlan { result, operations }
+}
+
+fn str_attr<'a>(node: &'a ResourceNode, attribute: &str) -> Option<&'a str> {
+    node.data.get(attribute).and_then(Value::as_str)
+}
+
+fn is_writable(fs: &ResourceNode) -> bool {
+    str_attr(fs, "type") != Some("snapshot") && str_attr(fs, "mutability") != Some("read_only")
+}
+
+/// Why attaching a geobound filesystem to an appliance breaks its bounds
+fn geobound_violation(fs: &ResourceNode, appliance: &ResourceNode) -> Option<String> {
+    if str_attr(fs, "type") != Some("geobound") {
+        return None;
+    }
+    let bounds = &fs.data["geographic_bounds"];
+    let labels = &appliance.data["labels"];
+    let allowed = |key: &str, value: Option<&str>| {
+        let list = bounds[key].as_array().map(Vec::as_slice).unwrap_or_default();
+        list.is_empty()
+            || value.is_some_and(|v| list.iter().any(|a| a.as_str().is_some_and(|a| a.eq_ignore_ascii_case(v))))
+    };
+
+    let country = labels[COUNTRY_LABEL].as_str();
+    if country.is_none() || !allowed("allowed_countries", country) {
+        return Some(format!(
+            "geobound filesystem '{}' needs an appliance labelled {} in {}",
+            fs.name, COUNTRY_LABEL, bounds["allowed_countries"]
+        ));
+    }
+    if !allowed("allowed_regions", labels[REGION_LABEL].as_str()) {
+        return Some(format!(
+            "geobound filesystem '{}' needs an appliance labelled {} in {}",
+            fs.name, REGION_LABEL, bounds["allowed_regions"]
+        ));
+    }
+    None
+}
+
+/// Managed attributes the draft sets to a different value than the live node
+fn attribute_diffs(live: &ResourceNode, draft: &ResourceNode) -> Vec<AttributeDiff> {
+    let mut diffs = Vec::new();
+    if live.name != draft.name {
+        diffs.push(AttributeDiff {
+            attribute: "name".to_string(),
+            before: Value::String(live.name.clone()),
+            after: Value::String(draft.name.clone()),
+        });
+    }
+    for attribute in IMMUTABLE_ATTRIBUTES.iter().chain(MUTABLE_ATTRIBUTES) {
+        // Attributes a draft leaves out keep their live value
+        let Some(after) = draft.data.get(*attribute) else {
+            continue;
+        };
+        let before = live.data.get(*attribute).cloned().unwrap_or(Value::Null);
+        if before != *after {
+            diffs.push(AttributeDiff {
+                attribute: attribute.to_string(),
+                before,
+                after: after.clone(),
+            });
+        }
+    }
+    diffs
+}
+
+fn added_diffs(node: &ResourceNode) -> Vec<AttributeDiff> {
+    IMMUTABLE_ATTRIBUTES
+        .iter()
+        .chain(MUTABLE_ATTRIBUTES)
+        .filter_map(|attribute| {
+            node.data.get(*attribute).map(|after| AttributeDiff {
+                attribute: attribute.to_string(),
+                before: Value::Null,
+                after: after.clone(),
+            })
+        })
+        .collect()
+}
+
+fn attachment_pairs(graph: &ResourceGraph) -> HashSet<(String, String)> {
+    graph
+        .edges
+        .iter()
+        .filter(|e| e.edge_type == ATTACHED_TO_EDGE)
+        .map(|e| (e.source.clone(), e.target.clone()))
+        .collect()
+}
+
+fn attachment_change(fs_id: &str, appliance_id: &str, detach: bool) -> PlanChange {
+    let (before, after) = if detach {
+        (Value::String(appliance_id.to_string()), Value::Null)
+    } else {
+        (Value::Null, Value::String(appliance_id.to_string()))
+    };
+    change(
+        ATTACHED_TO_EDGE,
+        &format!("{}-{}", fs_id, appliance_id),
+        fs_id,
+        vec![AttributeDiff { attribute: "appliance_id".to_string(), before, after }],
+    )
+}
+
+fn change(resource_type: &str, resource_id: &str, name: &str, diffs: Vec<AttributeDiff>) -> PlanChange {
+    PlanChange {
+        resource_type: resource_type.to_string(),
+        resource_id: resource_id.to_string(),
+        name: name.to_string(),
+        changes: diffs
+            .iter()
+            .map(|d| format!("{}: {} -> {}", d.attribute, d.before, d.after))
+            .collect(),
+        diffs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn node(id: &str, node_type: &str, data: Value) -> ResourceNode {
+        ResourceNode {
+            id: id.to_string(),
+            node_type: node_type.to_string(),
+            name: id.to_string(),
+            data,
+            position: None,
+        }
+    }
+
+    fn attached(fs: &str, appliance: &str) -> ResourceEdge {
+        ResourceEdge {
+            id: format!("{}-{}", fs, appliance),
+            source: fs.to_string(),
+            target: appliance.to_string(),
+            edge_type: ATTACHED_TO_EDGE.to_string(),
+            data: json!({}),
+        }
+    }
+
+    fn graph(nodes: Vec<ResourceNode>, edges: Vec<ResourceEdge>) -> ResourceGraph {
+        ResourceGraph { nodes, edges, version: "1".to_string(), computed_at: 0 }
+    }
+
+    fn live() -> ResourceGraph {
+        graph(
+            vec![
+                node("app", APPLIANCE_NODE, json!({"labels": {COUNTRY_LABEL: "DE"}})),
+                node("other", APPLIANCE_NODE, json!({"labels": {}})),
+                node("data", FILESYSTEM_NODE, json!({"type": "local", "size_bytes": 1024, "mount_path": "/data", "mutability": "read_write"})),
+                node("old", FILESYSTEM_NODE, json!({"type": "local", "size_bytes": 1024})),
+            ],
+            vec![attached("old", "app")],
+        )
+    }
+
+    #[test]
+    fn test_plan_orders_operations_and_diffs_attributes() {
+        let mut draft = live();
+        draft.nodes.retain(|n| n.id != "old");
+        draft.nodes[2].data["mount_path"] = json!("/srv");
+        draft.nodes.push(node("scratch", FILESYSTEM_NODE, json!({"type": "ephemeral", "size_bytes": 512})));
+        draft.edges = vec![attached("data", "app")];
+
+        let plan = plan(&live(), &draft);
+        assert!(plan.result.valid, "{:?}", plan.result.errors);
+
+        let ops: Vec<&GraphOp> = plan.operations.iter().map(|(op, _)| op).collect();
+        assert!(matches!(ops[0], GraphOp::Detach { filesystem_id, .. } if filesystem_id == "old"));
+        assert!(matches!(ops[1], GraphOp::UpdateFilesystem { id, attributes } if id == "data" && attributes["mount_path"] == "/srv"));
+        assert!(matches!(ops[2], GraphOp::CreateFilesystem { node } if node.id == "scratch"));
+        assert!(matches!(ops[3], GraphOp::Attach { filesystem_id, mount_path, .. } if filesystem_id == "data" && mount_path.as_deref() == Some("/srv")));
+        assert!(matches!(ops[4], GraphOp::DeleteFilesystem { id } if id == "old"));
+
+        let update = &plan.result.updates[0];
+        assert_eq!(
+            update.diffs,
+            vec![AttributeDiff { attribute: "mount_path".to_string(), before: json!("/data"), after: json!("/srv") }]
+        );
+        assert_eq!(update.changes, vec![r#"mount_path: "/data" -> "/srv""#]);
+
+        // An unchanged draft is an empty plan
+        assert!(super::plan(&live(), &live()).operations.is_empty());
+    }
+
+    #[test]
+    fn test_plan_rejects_replacements_and_appliance_changes() {
+        let mut draft = live();
+        draft.nodes[2].data["size_bytes"] = json!(2048);
+        draft.nodes.retain(|n| n.id != "other");
+        draft.nodes.push(node("new-app", APPLIANCE_NODE, json!({})));
+
+        let plan = plan(&live(), &draft);
+        assert!(!plan.result.valid);
+        let fields: Vec<(&str, &str)> = plan
+            .result
+            .errors
+            .iter()
+            .map(|e| (e.resource_id.as_str(), e.field.as_str()))
+            .collect();
+        assert!(fields.contains(&("data", "size_bytes")));
+        assert!(fields.contains(&("other", "id")));
+        assert!(fields.contains(&("new-app", "id")));
+    }
+
+    #[test]
+    fn test_validate_attachment_cardinality_and_geobounds() {
+        let mut g = live();
+        g.edges = vec![attached("data", "app"), attached("data", "other")];
+        let errors = validate(&g);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "attached_to");
+
+        // Read-only filesystems can be shared
+        g.nodes[2].data["mutability"] = json!("read_only");
+        assert!(validate(&g).is_empty());
+
+        let geo = node(
+            "geo",
+            FILESYSTEM_NODE,
+            json!({"type": "geobound", "geographic_bounds": {"allowed_countries": ["de"], "allowed_regions": []}}),
+        );
+        g.nodes.push(geo);
+        g.edges = vec![attached("geo", "app")];
+        assert!(validate(&g).is_empty());
+
+        g.edges = vec![attached("geo", "other")];
+        let errors = validate(&g);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains(COUNTRY_LABEL));
+
+        g.nodes.last_mut().unwrap().data["geographic_bounds"]["allowed_countries"] = json!([]);
+        g.edges.clear();
+        assert_eq!(validate(&g)[0].field, "geographic_bounds");
+    }
+}
@@ -0,0 +1,236 @@
+//! Appliance health checks.
+//!
+//! Templates declare checks (see [`HealthCheck`]) that the web server runs
+//! against each booted appliance on an interval. Their results drive the
+//! appliance status:
+//!
+//! ```text
+//! provisioning -> booting -> healthy <-> degraded
+//! ```
+//!
+//! A booting appliance becomes healthy once every check passes, or degraded
+//! if they still fail after [`BOOT_GRACE_SECS`]. A healthy appliance with a
+//! failing check is degraded until all pass again. Appliances of templates
+//! without checks go straight to `running` and are not monitored.
+
+use crate::catalog::{AppliancePort, HealthCheck};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+/// How often monitored appliances are checked
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How long a booting appliance may fail its checks before it is degraded
+pub const BOOT_GRACE_SECS: i64 = 300;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+const TCP_TIMEOUT: Duration = Duration::from_secs(5);
+const CMD_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often [`wait_until_healthy`] retries
+const WAIT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longest output of a failing `cmd` check kept as its detail
+const DETAIL_LIMIT: usize = 512;
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    /// What was checked, e.g. `tcp 127.0.0.1:2222`
+    pub check: String,
+    pub healthy: bool,
+    /// Why the check failed
+    #[serde(default)]
+    pub detail: Option<String>,
+    pub checked_at: i64,
+}
+
+/// Whether appliances in `status` are checked
+pub fn monitored(status: &str) -> bool {
+    matches!(status, "booting" | "healthy" | "degraded")
+}
+
+/// The status an appliance moves to after a round of checks, if it changes;
+/// `since` is when it entered `current`
+pub fn next_status(current: &str, since: i64, now: i64, all_passed: bool) -> Option<&'static str> {
+    match (current, all_passed) {
+        ("booting" | "degraded", true) => Some("healthy"),
+        ("healthy", false) => Some("degraded"),
+        ("booting", false) if now - since >= BOOT_GRACE_SECS => Some("degraded"),
+        _ => None,
+    }
+}
+
+/// Run `check` for an appliance whose template exposes `ports`
+pub async fn evaluate(
+    check: &HealthCheck,
+    client: &reqwest::Client,
+    ports: &[AppliancePort],
+    appliance_id: &str,
+    vm_id: Option<&str>,
+) -> CheckResult {
+    let (label, outcome) = match check {
+        HealthCheck::Http { url, expect_status } => {
+            (format!("http {}", url), check_http(client, url, *expect_status).await)
+        }
+        HealthCheck::Tcp { port, host } => {
+            let host = host.as_deref().unwrap_or("127.0.0.1");
+            let port = host_port(ports, *port);
+            (format!("tcp {}:{}", host, port), check_tcp(host, port).await)
+        }
+        HealthCheck::Cmd { script } => {
+            (format!("cmd {}", script), check_cmd(script, appliance_id, vm_id).await)
+        }
+    };
+    CheckResult {
+        check: label,
+        healthy: outcome.is_ok(),
+        detail: outcome.err(),
+        checked_at: chrono::Utc::now().timestamp(),
+    }
+}
+
+/// Run `check` until it passes, for at most `timeout`; the last failure is
+/// the error
+pub async fn wait_until_healthy(
+    check: &HealthCheck,
+    client: &reqwest::Client,
+    ports: &[AppliancePort],
+    appliance_id: &str,
+    vm_id: Option<&str>,
+    timeout: Duration,
+) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let result = evaluate(check, client, ports, appliance_id, vm_id).await;
+        if result.healthy {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!(
+                "{} still failing after {:?}: {}",
+                result.check,
+                timeout,
+                result.detail.unwrap_or_default()
+            ));
+        }
+        tokio::time::sleep(WAIT_INTERVAL).await;
+    }
+}
+
+/// The host port a template maps `port` to, else `port` itself
+fn host_port(ports: &[AppliancePort], port: u16) -> u16 {
+    ports
+        .iter()
+        .find(|p| p.protocol == "tcp" && p.container_port == port)
+        .and_then(|p| p.host_port)
+        .unwrap_or(port)
+}
+
+async fn check_http(client: &reqwest::Client, url: &str, expect_status: u16) -> Result<(), String> {
+    let response = client
+        .get(url)
+        .timeout(HTTP_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    if status == expect_status {
+        Ok(())
+    } else {
+        Err(format!("status {}, expected {}", status, expect_status))
+    }
+}
+
+async fn check_tcp(host: &str, port: u16) -> Result<(), String> {
+    match tokio::time::timeout(TCP_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer within {:?}", TCP_TIMEOUT)),
+    }
+}
+
+async fn check_cmd(script: &str, appliance_id: &str, vm_id: Option<&str>) -> Result<(), String> {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(script)
+        .env("INFRASIM_APPLIANCE_ID", appliance_id)
+        .env("INFRASIM_VM_ID", vm_id.unwrap_or(""))
+        .stdin(Stdio::null())
+        // A script that outlives its timeout is killed with the future
+        .kill_on_drop(true);
+    let output = match tokio::time::timeout(CMD_TIMEOUT, command.output()).await {
+        Ok(output) => output.map_err(|e| format!("can't run sh: {}", e))?,
+        Err(_) => return Err(format!("still running after {:?}", CMD_TIMEOUT)),
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+    let mut detail = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if detail.is_empty() {
+        detail = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    }
+    if detail.len() > DETAIL_LIMIT {
+        let mut end = DETAIL_LIMIT;
+        while !detail.is_char_boundary(end) {
+            end -= 1;
+        }
+        detail.truncate(end);
+    }
+    Err(if detail.is_empty() {
+        output.status.to_string()
+    } else {
+        format!("{}: {}", output.status, detail)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_status() {
+        assert_eq!(next_status("booting", 0, 10, true), Some("healthy"));
+        assert_eq!(next_status("booting", 0, 10, false), None);
+        assert_eq!(next_status("booting", 0, BOOT_GRACE_SECS, false), Some("degraded"));
+        assert_eq!(next_status("healthy", 0, 10, true), None);
+        assert_eq!(next_status("healthy", 0, 10, false), Some("degraded"));
+        assert_eq!(next_status("degraded", 0, 10, true), Some("healthy"));
+        assert_eq!(next_status("degraded", 0, 10, false), None);
+        assert!(monitored("booting") && !monitored("provisioning") && !monitored("stopped"));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_and_cmd_checks() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = reqwest::Client::new();
+        let ports = [AppliancePort {
+            container_port: 22,
+            host_port: Some(port),
+            protocol: "tcp".to_string(),
+            description: String::new(),
+        }];
+
+        let tcp = HealthCheck::Tcp { port: 22, host: None };
+        let result = evaluate(&tcp, &client, &ports, "app", None).await;
+        assert!(result.healthy, "{:?}", result);
+        assert_eq!(result.check, format!("tcp 127.0.0.1:{}", port));
+        drop(listener);
+        assert!(!evaluate(&tcp, &client, &ports, "app", None).await.healthy);
+
+        let cmd = HealthCheck::Cmd { script: r#"test "$INFRASIM_APPLIANCE_ID" = app"#.to_string() };
+        assert!(evaluate(&cmd, &client, &ports, "app", Some("vm")).await.healthy);
+        let cmd = HealthCheck::Cmd { script: "echo not ready >&2; exit 3".to_string() };
+        let result = evaluate(&cmd, &client, &ports, "app", Some("vm")).await;
+        assert!(!result.healthy);
+        assert!(result.detail.unwrap().ends_with("not ready"));
+
+        let wait = wait_until_healthy(&cmd, &client, &ports, "app", None, Duration::ZERO).await;
+        assert!(wait.unwrap_err().contains("still failing"));
+        let cmd = HealthCheck::Cmd { script: "true".to_string() };
+        assert!(wait_until_healthy(&cmd, &client, &ports, "app", None, Duration::ZERO).await.is_ok());
+    }
+}
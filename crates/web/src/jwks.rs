@@ -0,0 +1,344 @@
+//! JSON Web Key Sets for JWT auth mode.
+//!
+//! Signing keys come from a local file or an identity provider's JWKS URL.
+//! Fetched sets are kept for their `Cache-Control: max-age`, revalidated with
+//! their ETag and refreshed in the background. A token signed with a `kid`
+//! missing from the cache triggers an early refetch, at most once per
+//! [`MIN_REFETCH_INTERVAL`], so rotated provider keys are picked up without a
+//! restart.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use jsonwebtoken::{Algorithm, DecodingKey};
+use reqwest::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use serde::Deserialize;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, warn};
+
+/// How long a key set without cache headers is kept
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+/// Shortest time a key set is kept, whatever the provider says
+const MIN_TTL: Duration = Duration::from_secs(60);
+/// Longest time a key set is kept, whatever the provider says
+const MAX_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Minimum time between refetches forced by unknown key IDs
+pub const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where JWT signing keys come from
+#[derive(Clone, Debug)]
+pub enum JwksSource {
+    /// A local JWKS file (JSON)
+    File(String),
+    /// A JWKS URL
+    Url(String),
+}
+
+impl JwksSource {
+    /// A JWKS URL, which must use HTTPS unless it points at this machine
+    pub fn url(url: &str) -> anyhow::Result<Self> {
+        let parsed = url::Url::parse(url)?;
+        let loopback = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+        match parsed.scheme() {
+            "https" => Ok(Self::Url(url.to_string())),
+            "http" if loopback => Ok(Self::Url(url.to_string())),
+            _ => Err(anyhow::anyhow!("JWKS URL must use https: {url}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub kid: Option<String>,
+    pub alg: Option<String>,
+    #[serde(rename = "use")]
+    pub use_: Option<String>,
+
+    // RSA
+    pub n: Option<String>,
+    pub e: Option<String>,
+
+    // EC
+    pub crv: Option<String>,
+    pub x: Option<String>,
+    pub y: Option<String>,
+}
+
+impl Jwk {
+    pub fn decoding_key(&self) -> anyhow::Result<DecodingKey> {
+        match self.kty.as_str() {
+            "RSA" => {
+                let n = self.n.as_ref().ok_or_else(|| anyhow::anyhow!("RSA jwk missing n"))?;
+                let e = self.e.as_ref().ok_or_else(|| anyhow::anyhow!("RSA jwk missing e"))?;
+                Ok(DecodingKey::from_rsa_components(n, e)?)
+            }
+            "EC" => {
+                let x = self.x.as_ref().ok_or_else(|| anyhow::anyhow!("EC jwk missing x"))?;
+                let y = self.y.as_ref().ok_or_else(|| anyhow::anyhow!("EC jwk missing y"))?;
+                Ok(DecodingKey::from_ec_components(x, y)?)
+            }
+            other => Err(anyhow::anyhow!("unsupported jwk kty: {other}")),
+        }
+    }
+
+    pub fn algorithm(&self) -> anyhow::Result<Algorithm> {
+        // Prefer explicit alg if present, otherwise infer from kty.
+        if let Some(alg) = self.alg.as_deref() {
+            return match alg {
+                "RS256" => Ok(Algorithm::RS256),
+                "RS384" => Ok(Algorithm::RS384),
+                "RS512" => Ok(Algorithm::RS512),
+                "ES256" => Ok(Algorithm::ES256),
+                "ES384" => Ok(Algorithm::ES384),
+                // jsonwebtoken (v9) does not expose ES512; if needed we can upgrade or reject.
+                "ES512" => Err(anyhow::anyhow!("unsupported jwt alg ES512 (not supported by current verifier)")),
+                other => Err(anyhow::anyhow!("unsupported jwk alg: {other}")),
+            };
+        }
+
+        match self.kty.as_str() {
+            "RSA" => Ok(Algorithm::RS256),
+            "EC" => Ok(Algorithm::ES256),
+            other => Err(anyhow::anyhow!("unsupported jwk kty: {other}")),
+        }
+    }
+}
+
+/// How long to keep a key set given its `Cache-Control` header
+fn cache_ttl(cache_control: Option<&str>) -> Duration {
+    let mut ttl = DEFAULT_TTL;
+    for directive in cache_control.unwrap_or_default().split(',').map(str::trim) {
+        if directive.eq_ignore_ascii_case("no-cache") || directive.eq_ignore_ascii_case("no-store") {
+            return MIN_TTL;
+        }
+        if let Some(secs) = directive.strip_prefix("max-age=").and_then(|s| s.trim_matches('"').parse().ok()) {
+            ttl = Duration::from_secs(secs);
+        }
+    }
+    ttl.clamp(MIN_TTL, MAX_TTL)
+}
+
+#[derive(Clone)]
+struct CachedJwks {
+    jwks: Arc<Jwks>,
+    etag: Option<String>,
+    fetched_at: Instant,
+    expires_at: Instant,
+}
+
+enum Fetched {
+    Keys { jwks: Jwks, etag: Option<String>, ttl: Duration },
+    NotModified { ttl: Duration },
+}
+
+/// Why keys are being refreshed
+#[derive(Clone, Copy)]
+enum Refresh {
+    /// The cached set expired
+    Expired,
+    /// A token named a key the cached set lacks
+    UnknownKid,
+}
+
+/// Signing keys for JWT auth, refreshed as they rotate
+pub struct JwksCache {
+    source: JwksSource,
+    http: reqwest::Client,
+    cached: RwLock<Option<CachedJwks>>,
+    /// Serializes fetches, so concurrent misses share one request
+    fetching: Mutex<()>,
+    min_refetch: Duration,
+}
+
+impl JwksCache {
+    pub fn new(source: JwksSource) -> Self {
+        Self {
+            source,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            cached: RwLock::new(None),
+            fetching: Mutex::new(()),
+            min_refetch: MIN_REFETCH_INTERVAL,
+        }
+    }
+
+    /// Keys that may have signed a token with `kid`, or all keys without one
+    pub async fn keys_for(&self, kid: Option<&str>) -> anyhow::Result<Vec<Jwk>> {
+        let jwks = self.current().await?;
+        let Some(kid) = kid else {
+            return Ok(jwks.keys.clone());
+        };
+        if let Some(keys) = keys_with_kid(&jwks, kid) {
+            return Ok(keys);
+        }
+
+        // An unknown kid usually means the provider rotated its keys
+        debug!("jwk {} not cached; refetching JWKS", kid);
+        let jwks = self.refresh(Refresh::UnknownKid).await?;
+        keys_with_kid(&jwks, kid).ok_or_else(|| anyhow::anyhow!("no jwk found for kid"))
+    }
+
+    /// Refresh keys as they expire, so requests rarely wait on the provider
+    pub fn spawn_refresh(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.refresh(Refresh::Expired).await {
+                    warn!("JWKS refresh failed: {}", e);
+                }
+                let next = self
+                    .cached
+                    .read()
+                    .await
+                    .as_ref()
+                    .map(|cached| cached.expires_at.saturating_duration_since(Instant::now()))
+                    .unwrap_or(MIN_TTL);
+                tokio::time::sleep(next.max(Duration::from_secs(1))).await;
+            }
+        })
+    }
+
+    /// Cached keys, fetched first when missing or expired
+    async fn current(&self) -> anyhow::Result<Arc<Jwks>> {
+        if let Some(cached) = self.cached.read().await.as_ref() {
+            if cached.expires_at > Instant::now() {
+                return Ok(cached.jwks.clone());
+            }
+        }
+        self.refresh(Refresh::Expired).await
+    }
+
+    /// Fetch keys, unless a concurrent or recent fetch already did
+    ///
+    /// The last good keys keep being served while the source is unreachable.
+    async fn refresh(&self, why: Refresh) -> anyhow::Result<Arc<Jwks>> {
+        let _fetching = self.fetching.lock().await;
+        let now = Instant::now();
+        let previous = self.cached.read().await.clone();
+
+        if let Some(previous) = &previous {
+            let fresh = match why {
+                Refresh::Expired => previous.expires_at > now,
+                Refresh::UnknownKid => now.duration_since(previous.fetched_at) < self.min_refetch,
+            };
+            if fresh {
+                return Ok(previous.jwks.clone());
+            }
+        }
+
+        let etag = previous.as_ref().and_then(|p| p.etag.clone());
+        let (jwks, etag, ttl) = match (self.fetch(etag).await, previous) {
+            (Ok(Fetched::Keys { jwks, etag, ttl }), _) => (Arc::new(jwks), etag, ttl),
+            (Ok(Fetched::NotModified { ttl }), Some(previous)) => (previous.jwks, previous.etag, ttl),
+            (Ok(Fetched::NotModified { .. }), None) => {
+                return Err(anyhow::anyhow!("JWKS fetch returned 304 without a cached copy"));
+            }
+            (Err(e), Some(previous)) => {
+                warn!("keeping cached JWKS: {}", e);
+                (previous.jwks, previous.etag, MIN_TTL)
+            }
+            (Err(e), None) => return Err(e),
+        };
+
+        *self.cached.write().await = Some(CachedJwks {
+            jwks: jwks.clone(),
+            etag,
+            fetched_at: now,
+            expires_at: now + ttl,
+        });
+        Ok(jwks)
+    }
+
+    async fn fetch(&self, etag: Option<String>) -> anyhow::Result<Fetched> {
+        match &self.source {
+            JwksSource::File(path) => {
+                let jwks = serde_json::from_slice(&std::fs::read(path)?)?;
+                Ok(Fetched::Keys { jwks, etag: None, ttl: MAX_TTL })
+            }
+            JwksSource::Url(url) => {
+                let mut request = self.http.get(url);
+                if let Some(etag) = etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                let resp = request.send().await?;
+
+                let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok());
+                let ttl = cache_ttl(header(CACHE_CONTROL));
+                if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+                    return Ok(Fetched::NotModified { ttl });
+                }
+                if !resp.status().is_success() {
+                    return Err(anyhow::anyhow!("JWKS fetch failed: {}", resp.status()));
+                }
+                let etag = header(ETAG).map(str::to_string);
+                let jwks = resp.json().await?;
+                Ok(Fetched::Keys { jwks, etag, ttl })
+            }
+        }
+    }
+}
+
+fn keys_with_kid(jwks: &Jwks, kid: &str) -> Option<Vec<Jwk>> {
+    let keys: Vec<Jwk> = jwks
+        .keys
+        .iter()
+        .filter(|jwk| jwk.kid.as_deref() == Some(kid))
+        .cloned()
+        .collect();
+    (!keys.is_empty()).then_some(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_jwks(path: &std::path::Path, kids: &[&str]) {
+        let keys: Vec<_> = kids
+            .iter()
+            .map(|kid| serde_json::json!({"kty": "RSA", "kid": kid, "n": "AQAB", "e": "AQAB"}))
+            .collect();
+        std::fs::write(path, serde_json::json!({ "keys": keys }).to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_cache_ttl_follows_cache_control() {
+        assert_eq!(cache_ttl(None), DEFAULT_TTL);
+        assert_eq!(cache_ttl(Some("public, max-age=600")), Duration::from_secs(600));
+        assert_eq!(cache_ttl(Some("max-age=5")), MIN_TTL);
+        assert_eq!(cache_ttl(Some("max-age=31536000")), MAX_TTL);
+        assert_eq!(cache_ttl(Some("no-cache, max-age=600")), MIN_TTL);
+        assert!(JwksSource::url("https://sso.example.com/certs").is_ok());
+        assert!(JwksSource::url("http://127.0.0.1:8180/certs").is_ok());
+        assert!(JwksSource::url("http://sso.example.com/certs").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_kid_refetches_rotated_keys() {
+        let path = std::env::temp_dir().join(format!("infrasim-jwks-{}.json", uuid::Uuid::new_v4()));
+        write_jwks(&path, &["old"]);
+        let mut cache = JwksCache::new(JwksSource::File(path.to_string_lossy().to_string()));
+        cache.min_refetch = Duration::ZERO;
+
+        assert_eq!(cache.keys_for(Some("old")).await.unwrap().len(), 1);
+        write_jwks(&path, &["old", "new"]);
+        assert_eq!(cache.keys_for(None).await.unwrap().len(), 1, "unexpired keys are reused");
+        assert_eq!(cache.keys_for(Some("new")).await.unwrap().len(), 1);
+        assert!(cache.keys_for(Some("unknown")).await.is_err());
+
+        // Refetches are rate limited, and the last good keys survive a broken source
+        cache.min_refetch = MIN_REFETCH_INTERVAL;
+        write_jwks(&path, &["newer"]);
+        assert!(cache.keys_for(Some("newer")).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+        cache.min_refetch = Duration::ZERO;
+        assert!(cache.keys_for(Some("newer")).await.is_err());
+        assert_eq!(cache.keys_for(Some("new")).await.unwrap().len(), 1);
+    }
+}
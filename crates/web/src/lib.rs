@@ -3,6 +3,8 @@
 //! Provides a web-based console for accessing VMs via noVNC.
 
 pub mod server;
+pub mod admin_terminal;
+pub mod daemon_cache;
 pub mod vnc_proxy;
 pub mod static_files;
 pub mod mdm;
@@ -11,6 +13,16 @@ pub mod docker;
 pub mod meshnet;
 pub mod build_analysis;
 pub mod snapshot_browser;
+pub mod catalog;
+pub mod graph;
+pub mod jwks;
+pub mod projects;
+pub mod recording;
+pub mod shutdown;
+pub mod proxy;
+pub mod tls;
+pub mod acme;
+pub mod health;
 
 /// Generated gRPC client for InfraSim daemon.
 pub mod generated {
@@ -2,7 +2,12 @@ use std::net::SocketAddr;
 
 use tracing::info;
 
+use infrasim_common::auth::ClientCredentials;
+use infrasim_web::acme::AcmeConfig;
+use infrasim_web::auth::cookies::CookieSessionConfig;
+use infrasim_web::jwks::JwksSource;
 use infrasim_web::server::{JwtAuthConfig, WebServerConfig, WebUiAuth};
+use infrasim_web::tls::TlsConfig;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -19,10 +24,10 @@ async fn main() -> anyhow::Result<()> {
         .parse()?;
 
     let daemon_addr = std::env::var("INFRASIM_DAEMON_ADDR")
-        .unwrap_or_else(|_| "http://127.0.0.1:50051".to_string());
+        .unwrap_or_else(|_| infrasim_common::default_daemon_addr());
 
     // Auth config
-    // - INFRASIM_AUTH_MODE=jwt enables JWT validation against a local JWKS.
+    // - INFRASIM_AUTH_MODE=jwt enables JWT validation against a remote or local JWKS.
     // - Otherwise, fall back to static token (INFRASIM_WEB_AUTH_TOKEN) or DevRandom.
     let auth = match std::env::var("INFRASIM_AUTH_MODE").ok().as_deref() {
         Some("jwt") => {
@@ -30,8 +35,16 @@ async fn main() -> anyhow::Result<()> {
                 .map_err(|_| anyhow::anyhow!("INFRASIM_AUTH_ALLOWED_ISSUERS is required in jwt mode"))?;
             let audience = std::env::var("INFRASIM_AUTH_AUDIENCE")
                 .map_err(|_| anyhow::anyhow!("INFRASIM_AUTH_AUDIENCE is required in jwt mode"))?;
-            let local_jwks_path = std::env::var("INFRASIM_AUTH_LOCAL_JWKS_PATH")
-                .map_err(|_| anyhow::anyhow!("INFRASIM_AUTH_LOCAL_JWKS_PATH is required in jwt mode"))?;
+            let jwks = match (
+                std::env::var("INFRASIM_AUTH_JWKS_URL"),
+                std::env::var("INFRASIM_AUTH_LOCAL_JWKS_PATH"),
+            ) {
+                (Ok(url), _) => JwksSource::url(&url)?,
+                (_, Ok(path)) => JwksSource::File(path),
+                _ => anyhow::bail!(
+                    "INFRASIM_AUTH_JWKS_URL or INFRASIM_AUTH_LOCAL_JWKS_PATH is required in jwt mode"
+                ),
+            };
 
             WebUiAuth::Jwt(JwtAuthConfig {
                 allowed_issuers: allowed
@@ -40,7 +53,7 @@ async fn main() -> anyhow::Result<()> {
                     .filter(|v| !v.is_empty())
                     .collect(),
                 audience,
-                local_jwks_path,
+                jwks,
             })
         }
         _ => match std::env::var("INFRASIM_WEB_AUTH_TOKEN") {
@@ -49,14 +62,85 @@ async fn main() -> anyhow::Result<()> {
         },
     };
 
+    // INFRASIM_WEB_SESSION_MODE=cookie keeps browser logins in HttpOnly
+    // cookies; INFRASIM_WEB_COOKIE_SECURE=0 allows them over plain HTTP
+    let session_cookies = match std::env::var("INFRASIM_WEB_SESSION_MODE").ok().as_deref() {
+        Some("cookie") => {
+            let mut cookies = CookieSessionConfig::default();
+            if let Ok(secure) = std::env::var("INFRASIM_WEB_COOKIE_SECURE") {
+                cookies.secure = !matches!(secure.as_str(), "0" | "false");
+            }
+            if let Ok(same_site) = std::env::var("INFRASIM_WEB_COOKIE_SAMESITE") {
+                cookies.same_site = same_site.parse().map_err(|e: String| anyhow::anyhow!(e))?;
+            }
+            Some(cookies)
+        }
+        None | Some("bearer") => None,
+        Some(other) => anyhow::bail!("unknown INFRASIM_WEB_SESSION_MODE '{}' (bearer or cookie)", other),
+    };
+
+    // INFRASIM_WEB_TLS_CERT/INFRASIM_WEB_TLS_KEY serve HTTPS from PEM files;
+    // INFRASIM_WEB_ACME_DOMAINS gets the certificate from Let's Encrypt
+    let tls = match (
+        std::env::var("INFRASIM_WEB_TLS_CERT"),
+        std::env::var("INFRASIM_WEB_TLS_KEY"),
+        std::env::var("INFRASIM_WEB_ACME_DOMAINS"),
+    ) {
+        (Ok(cert), Ok(key), _) => Some(TlsConfig::Files {
+            cert: cert.into(),
+            key: key.into(),
+        }),
+        (Ok(_), Err(_), _) | (Err(_), Ok(_), _) => {
+            anyhow::bail!("INFRASIM_WEB_TLS_CERT and INFRASIM_WEB_TLS_KEY must be set together")
+        }
+        (Err(_), Err(_), Ok(domains)) => {
+            let domains: Vec<String> = domains
+                .split(',')
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty())
+                .collect();
+            let contact = std::env::var("INFRASIM_WEB_ACME_EMAIL")
+                .map(|email| vec![format!("mailto:{}", email)])
+                .unwrap_or_default();
+            let cache_dir = std::env::var("INFRASIM_WEB_ACME_CACHE")
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(|_| {
+                    dirs::home_dir()
+                        .unwrap_or_else(|| std::path::PathBuf::from("/tmp"))
+                        .join(".infrasim/acme")
+                });
+            // "off" when a proxy forwards /.well-known/acme-challenge/ here
+            let http_addr = match std::env::var("INFRASIM_WEB_ACME_HTTP_ADDR").ok().as_deref() {
+                Some("off") => None,
+                Some(addr) => Some(addr.parse()?),
+                None => Some(SocketAddr::from(([0, 0, 0, 0], 80))),
+            };
+            Some(TlsConfig::Acme(AcmeConfig {
+                domains,
+                contact,
+                directory: std::env::var("INFRASIM_WEB_ACME_DIRECTORY")
+                    .unwrap_or_else(|_| infrasim_web::acme::LETS_ENCRYPT.to_string()),
+                cache_dir,
+                http_addr,
+            }))
+        }
+        _ => None,
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    // Daemon credentials: INFRASIM_CA_CERT, INFRASIM_CLIENT_CERT,
+    // INFRASIM_CLIENT_KEY and INFRASIM_TOKEN
     let cfg = WebServerConfig {
         daemon_addr,
+        daemon_credentials: ClientCredentials::default().with_env(),
         auth,
+        session_cookies,
+        tls,
     };
 
     info!(
-        "Starting InfraSim Web UI on http://{} (daemon: {})",
-        web_addr, cfg.daemon_addr
+        "Starting InfraSim Web UI on {}://{} (daemon: {})",
+        scheme, web_addr, cfg.daemon_addr
     );
 
     infrasim_web::server::serve(web_addr, cfg).await
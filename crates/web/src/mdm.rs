@@ -3,21 +3,45 @@
 //! This module provides:
 //! - Self-signed CA and signing certificate generation
 //! - .mobileconfig XML profile generation for VPN, WiFi, and network bridge configs
-//! - PKCS#7 (CMS) signing of profiles for iOS/macOS
+//! - In-process PKCS#7 (CMS) signing of profiles for iOS/macOS
 //! - Webhook delivery of signed profiles
+//! - Over-the-air device enrollment: one-time enrollment tokens, an Apple
+//!   Profile Service exchange that records device identity (UDID, serial),
+//!   and per-device identity certificates issued by the root CA
 
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use cms::builder::{SignedDataBuilder, SignerInfoBuilder};
+use cms::cert::{CertificateChoices, IssuerAndSerialNumber};
+use cms::signed_data::{EncapsulatedContentInfo, SignerIdentifier};
+use der::{Any, DecodePem, Encode, Tag};
+use p256::pkcs8::DecodePrivateKey;
 use plist::Dictionary;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use rcgen::{
-    BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose,
-    IsCa, KeyPair as RcgenKeyPair, KeyUsagePurpose, SanType, PKCS_ECDSA_P256_SHA256,
+    date_time_ymd, BasicConstraints, CertificateParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair as RcgenKeyPair, KeyUsagePurpose, SanType,
+    SerialNumber, PKCS_ECDSA_P256_SHA256,
 };
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
 use tracing::info;
 use uuid::Uuid;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+
+/// Default lifetime of an enrollment token
+pub const DEFAULT_ENROLLMENT_TTL_SECS: i64 = 7 * 24 * 3600;
+
+/// Validity of issued device identity certificates
+const DEVICE_CERT_VALIDITY_DAYS: i64 = 365;
+
+/// Device attributes requested from the device during enrollment
+const DEVICE_ATTRIBUTES: &[&str] = &["UDID", "SERIAL", "PRODUCT", "VERSION", "IMEI", "MEID"];
 
 /// Configuration for the MDM signing chain
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +52,13 @@ pub struct MdmConfig {
     pub domain: String,
     /// Path to store certificates
     pub cert_store_path: PathBuf,
+    /// Externally reachable base URL devices use to enroll
+    #[serde(default = "default_public_url")]
+    pub public_url: String,
+}
+
+fn default_public_url() -> String {
+    "https://infrasim.local".to_string()
 }
 
 impl Default for MdmConfig {
@@ -36,6 +67,7 @@ impl Default for MdmConfig {
             org_name: "InfraSim".to_string(),
             domain: "infrasim.local".to_string(),
             cert_store_path: PathBuf::from("/tmp/infrasim-mdm"),
+            public_url: default_public_url(),
         }
     }
 }
@@ -51,23 +83,38 @@ pub struct SigningChain {
     pub full_chain_pem: String,
 }
 
+/// Root CA parameters, also used to rebuild the issuer for device certificates
+fn root_ca_params(config: &MdmConfig) -> CertificateParams {
+    let mut root_params = CertificateParams::default();
+    root_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    root_params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+
+    let mut root_dn = DistinguishedName::new();
+    root_dn.push(DnType::OrganizationName, &config.org_name);
+    root_dn.push(DnType::CommonName, format!("{} Root CA", config.org_name));
+    root_params.distinguished_name = root_dn;
+    root_params
+}
+
+/// A device identity certificate and its private key
+#[derive(Debug, Clone)]
+pub struct DeviceCertificate {
+    pub cert_pem: String,
+    pub key_pem: String,
+    /// Hex-encoded certificate serial number
+    pub serial: String,
+}
+
 impl SigningChain {
     /// Generate a new self-signed signing chain
     pub fn generate(config: &MdmConfig) -> Result<Self> {
         // Generate Root CA
-        let mut root_params = CertificateParams::default();
-        root_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
-        root_params.key_usages = vec![
-            KeyUsagePurpose::KeyCertSign,
-            KeyUsagePurpose::CrlSign,
-            KeyUsagePurpose::DigitalSignature,
-        ];
-        
-        let mut root_dn = DistinguishedName::new();
-        root_dn.push(DnType::OrganizationName, &config.org_name);
-        root_dn.push(DnType::CommonName, format!("{} Root CA", config.org_name));
-        root_params.distinguished_name = root_dn;
-        
+        let root_params = root_ca_params(config);
+
         // Use ECDSA P-256 for broad compatibility (iOS, macOS, browsers)
         // Note: RSA requires aws_lc_rs backend which has complex build deps
         let root_key = RcgenKeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)?;
@@ -151,6 +198,50 @@ impl SigningChain {
             Ok(chain)
         }
     }
+
+    /// Issue a device identity certificate signed by the root CA
+    pub fn issue_device_certificate(
+        &self,
+        config: &MdmConfig,
+        device: &DeviceIdentity,
+    ) -> Result<DeviceCertificate> {
+        // Same subject and key as the stored root, so the result chains to it
+        let root_key = RcgenKeyPair::from_pem(&self.root_ca_key_pem)?;
+        let issuer = root_ca_params(config).self_signed(&root_key)?;
+
+        let mut params = CertificateParams::default();
+        params.is_ca = IsCa::NoCa;
+        params.key_usages = vec![
+            KeyUsagePurpose::DigitalSignature,
+            KeyUsagePurpose::KeyEncipherment,
+        ];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+
+        let mut dn = DistinguishedName::new();
+        dn.push(DnType::OrganizationName, &config.org_name);
+        dn.push(DnType::OrganizationalUnitName, &device.udid);
+        dn.push(DnType::CommonName, device.serial.clone().unwrap_or_else(|| device.udid.clone()));
+        params.distinguished_name = dn;
+
+        // Positive 128-bit serial without a leading zero byte, so DER keeps all 16 bytes
+        let mut serial: [u8; 16] = rand::thread_rng().gen();
+        serial[0] = (serial[0] & 0x3f) | 0x40;
+        params.serial_number = Some(SerialNumber::from_slice(&serial));
+
+        let ymd = |d: NaiveDate| date_time_ymd(d.year(), d.month() as u8, d.day() as u8);
+        let today = Utc::now().date_naive();
+        params.not_before = ymd(today);
+        params.not_after = ymd(today + Duration::days(DEVICE_CERT_VALIDITY_DAYS));
+
+        let key = RcgenKeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)?;
+        let cert = params.signed_by(&key, &issuer, &root_key)?;
+
+        Ok(DeviceCertificate {
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+            serial: hex::encode(serial),
+        })
+    }
 }
 
 /// VPN configuration for mobileconfig
@@ -209,6 +300,14 @@ pub struct ProfileRequest {
 
 /// Generate a mobileconfig profile (unsigned XML plist)
 pub fn generate_mobileconfig(req: &ProfileRequest) -> Result<Vec<u8>> {
+    generate_mobileconfig_with_payloads(req, Vec::new())
+}
+
+/// Generate a mobileconfig with extra payloads (e.g. certificates) ahead of the network ones
+pub fn generate_mobileconfig_with_payloads(
+    req: &ProfileRequest,
+    extra_payloads: Vec<Dictionary>,
+) -> Result<Vec<u8>> {
     let mut root = Dictionary::new();
     let profile_uuid = Uuid::new_v4().to_string().to_uppercase();
     
@@ -223,7 +322,8 @@ pub fn generate_mobileconfig(req: &ProfileRequest) -> Result<Vec<u8>> {
     root.insert("PayloadVersion".into(), plist::Value::Integer(plist::Integer::from(1)));
     root.insert("PayloadRemovalDisallowed".into(), plist::Value::Boolean(false));
 
-    let mut payloads: Vec<plist::Value> = Vec::new();
+    let mut payloads: Vec<plist::Value> =
+        extra_payloads.into_iter().map(plist::Value::Dictionary).collect();
 
     // Add VPN payload if configured
     if let Some(vpn) = &req.vpn {
@@ -360,9 +460,291 @@ fn build_dns_payload(bridge: &BridgeConfig, base_id: &str) -> Result<Dictionary>
     Ok(payload)
 }
 
-/// Sign a mobileconfig with PKCS#7/CMS
-/// Note: Full CMS signing is complex. For now, we provide the unsigned profile
-/// and the signing chain for manual signing or use openssl.
+/// Root CA payload so devices trust certificates issued by this chain
+pub fn build_root_ca_payload(root_ca_pem: &str, base_id: &str) -> Result<Dictionary> {
+    let der = pem::parse(root_ca_pem)?.into_contents();
+    let mut payload = Dictionary::new();
+
+    payload.insert("PayloadDisplayName".into(), plist::Value::String("Root CA".into()));
+    payload.insert("PayloadIdentifier".into(), plist::Value::String(format!("{}.root-ca", base_id)));
+    payload.insert("PayloadType".into(), plist::Value::String("com.apple.security.root".into()));
+    payload.insert("PayloadUUID".into(), plist::Value::String(Uuid::new_v4().to_string().to_uppercase()));
+    payload.insert("PayloadVersion".into(), plist::Value::Integer(plist::Integer::from(1)));
+    payload.insert("PayloadCertificateFileName".into(), plist::Value::String("root-ca.cer".into()));
+    payload.insert("PayloadContent".into(), plist::Value::Data(der));
+
+    Ok(payload)
+}
+
+/// Device identity payload carrying a password-protected PKCS#12 bundle
+pub fn build_identity_payload(pkcs12: Vec<u8>, password: &str, base_id: &str) -> Dictionary {
+    let mut payload = Dictionary::new();
+
+    payload.insert("PayloadDisplayName".into(), plist::Value::String("Device Identity".into()));
+    payload.insert("PayloadIdentifier".into(), plist::Value::String(format!("{}.identity", base_id)));
+    payload.insert("PayloadType".into(), plist::Value::String("com.apple.security.pkcs12".into()));
+    payload.insert("PayloadUUID".into(), plist::Value::String(Uuid::new_v4().to_string().to_uppercase()));
+    payload.insert("PayloadVersion".into(), plist::Value::Integer(plist::Integer::from(1)));
+    payload.insert("PayloadCertificateFileName".into(), plist::Value::String("device.p12".into()));
+    payload.insert("Password".into(), plist::Value::String(password.to_string()));
+    payload.insert("PayloadContent".into(), plist::Value::Data(pkcs12));
+
+    payload
+}
+
+/// Lifecycle of a device enrollment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnrollmentStatus {
+    /// Token issued, no device has redeemed it yet
+    Pending,
+    /// A device redeemed the token and holds an identity certificate
+    Enrolled,
+    /// Revoked by an operator; the token and certificate are no longer honoured
+    Revoked,
+}
+
+/// Device attributes reported during enrollment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    pub udid: String,
+    pub serial: Option<String>,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub imei: Option<String>,
+    pub meid: Option<String>,
+}
+
+/// A device enrollment, from token issuance to revocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Enrollment {
+    pub id: String,
+    pub label: String,
+    /// One-time enrollment token, also used as the Profile Service challenge
+    pub token: String,
+    pub status: EnrollmentStatus,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub device: Option<DeviceIdentity>,
+    /// Hex serial of the issued device certificate
+    pub cert_serial: Option<String>,
+    /// PayloadIdentifier of the delivered profile
+    pub profile_identifier: Option<String>,
+    pub enrolled_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Enrollment {
+    /// Check the token can still be redeemed by a device
+    fn check_pending(&self, now: DateTime<Utc>) -> Result<(), EnrollmentError> {
+        match self.status {
+            EnrollmentStatus::Revoked => Err(EnrollmentError::Revoked),
+            EnrollmentStatus::Enrolled => Err(EnrollmentError::AlreadyEnrolled),
+            EnrollmentStatus::Pending if now >= self.expires_at => Err(EnrollmentError::Expired),
+            EnrollmentStatus::Pending => Ok(()),
+        }
+    }
+}
+
+/// Reasons an enrollment request is refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum EnrollmentError {
+    #[error("enrollment not found")]
+    NotFound,
+    #[error("enrollment token has expired")]
+    Expired,
+    #[error("enrollment token has already been used")]
+    AlreadyEnrolled,
+    #[error("device is not enrolled yet")]
+    NotEnrolled,
+    #[error("enrollment has been revoked")]
+    Revoked,
+    #[error("challenge does not match the enrollment token")]
+    ChallengeMismatch,
+}
+
+/// Generate the Profile Service payload that asks a device for its identity
+pub fn generate_profile_service_payload(
+    token: &str,
+    enroll_url: &str,
+    organization: &str,
+    identifier: &str,
+) -> Result<Vec<u8>> {
+    let mut root = Dictionary::new();
+
+    root.insert("PayloadDisplayName".into(), plist::Value::String(format!("{} Enrollment", organization)));
+    root.insert("PayloadDescription".into(), plist::Value::String(
+        "Enrolls this device and installs its identity certificate".into()
+    ));
+    root.insert("PayloadIdentifier".into(), plist::Value::String(identifier.to_string()));
+    root.insert("PayloadOrganization".into(), plist::Value::String(organization.to_string()));
+    root.insert("PayloadType".into(), plist::Value::String("Profile Service".into()));
+    root.insert("PayloadUUID".into(), plist::Value::String(Uuid::new_v4().to_string().to_uppercase()));
+    root.insert("PayloadVersion".into(), plist::Value::Integer(plist::Integer::from(1)));
+
+    let mut content = Dictionary::new();
+    content.insert("URL".into(), plist::Value::String(enroll_url.to_string()));
+    content.insert("DeviceAttributes".into(), plist::Value::Array(
+        DEVICE_ATTRIBUTES.iter().map(|a| plist::Value::String(a.to_string())).collect()
+    ));
+    content.insert("Challenge".into(), plist::Value::String(token.to_string()));
+    root.insert("PayloadContent".into(), plist::Value::Dictionary(content));
+
+    let mut buf = Vec::new();
+    plist::to_writer_xml(&mut buf, &plist::Value::Dictionary(root))?;
+
+    Ok(buf)
+}
+
+/// A device's answer to a Profile Service payload
+#[derive(Debug, Clone)]
+pub struct DeviceResponse {
+    pub identity: DeviceIdentity,
+    pub challenge: Option<String>,
+}
+
+/// Extract device attributes from a Profile Service response
+///
+/// Devices wrap the attributes plist in a PKCS#7 envelope signed with their
+/// own certificate. The plist is read straight out of the envelope; the
+/// challenge, not the device signature, binds the response to an enrollment.
+pub fn parse_device_attributes(body: &[u8]) -> Result<DeviceResponse> {
+    const END: &[u8] = b"</plist>";
+    let start = find_bytes(body, b"<?xml").ok_or_else(|| anyhow!("No plist in device response"))?;
+    let end = find_bytes(&body[start..], END)
+        .map(|i| start + i + END.len())
+        .ok_or_else(|| anyhow!("Truncated plist in device response"))?;
+
+    let dict = plist::Value::from_reader_xml(&body[start..end])?
+        .into_dictionary()
+        .ok_or_else(|| anyhow!("Device response is not a dictionary"))?;
+    let get = |key: &str| dict.get(key).and_then(|v| v.as_string()).map(str::to_string);
+
+    let udid = get("UDID").ok_or_else(|| anyhow!("Device response is missing UDID"))?;
+    Ok(DeviceResponse {
+        identity: DeviceIdentity {
+            udid,
+            serial: get("SERIAL"),
+            product: get("PRODUCT"),
+            version: get("VERSION"),
+            imei: get("IMEI"),
+            meid: get("MEID"),
+        },
+        challenge: get("CHALLENGE"),
+    })
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn random_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Run openssl, feeding `input` on stdin, and return its stdout
+async fn run_openssl(args: &[&str], input: Vec<u8>) -> Result<Vec<u8>> {
+    let mut child = tokio::process::Command::new("openssl")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Write from a task so a full stdout pipe cannot deadlock us
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("openssl stdin unavailable"))?;
+    let writer = tokio::spawn(async move { stdin.write_all(&input).await });
+
+    let output = child.wait_with_output().await?;
+    writer.await??;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "openssl {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Sign a mobileconfig as CMS SignedData with the chain's signing certificate
+///
+/// The profile is embedded in the signature, and the signing and root
+/// certificates are attached so devices can build the chain.
+pub fn sign_mobileconfig(unsigned: &[u8], chain: &SigningChain) -> Result<Vec<u8>> {
+    let signing_cert = x509_cert::Certificate::from_pem(&chain.signing_cert_pem)?;
+    let root_cert = x509_cert::Certificate::from_pem(&chain.root_ca_cert_pem)?;
+    let signing_key = p256::ecdsa::SigningKey::from_pkcs8_pem(&chain.signing_key_pem)?;
+
+    let content = EncapsulatedContentInfo {
+        econtent_type: const_oid::db::rfc5911::ID_DATA,
+        econtent: Some(Any::new(Tag::OctetString, unsigned)?),
+    };
+    let digest_algorithm = AlgorithmIdentifierOwned {
+        oid: const_oid::db::rfc5912::ID_SHA_256,
+        parameters: None,
+    };
+    let signer_id = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+        issuer: signing_cert.tbs_certificate.issuer.clone(),
+        serial_number: signing_cert.tbs_certificate.serial_number.clone(),
+    });
+    let cms_err = |e: cms::builder::Error| anyhow!("CMS signing failed: {:?}", e);
+    let signer_info = SignerInfoBuilder::new(&signing_key, signer_id, digest_algorithm.clone(), &content, None)
+        .map_err(cms_err)?;
+
+    let signed = SignedDataBuilder::new(&content)
+        .add_digest_algorithm(digest_algorithm)
+        .and_then(|b| b.add_certificate(CertificateChoices::Certificate(signing_cert)))
+        .and_then(|b| b.add_certificate(CertificateChoices::Certificate(root_cert)))
+        .and_then(|b| b.add_signer_info::<p256::ecdsa::SigningKey, p256::ecdsa::DerSignature>(signer_info))
+        .and_then(|b| b.build())
+        .map_err(cms_err)?;
+
+    Ok(signed.to_der()?)
+}
+
+/// Bundle a device certificate and key into PKCS#12
+///
+/// Uses SHA1/3DES so older iOS and macOS releases can import the bundle.
+async fn export_pkcs12(
+    cert: &DeviceCertificate,
+    chain_path: &Path,
+    work_dir: &Path,
+    name: &str,
+    password: &str,
+) -> Result<Vec<u8>> {
+    tokio::fs::create_dir_all(work_dir).await?;
+    let cert_path = work_dir.join(format!("{}.crt", name));
+    let key_path = work_dir.join(format!("{}.key", name));
+    tokio::fs::write(&cert_path, &cert.cert_pem).await?;
+    tokio::fs::write(&key_path, &cert.key_pem).await?;
+
+    let (cert_arg, key_arg, chain_arg) = (
+        cert_path.to_string_lossy(),
+        key_path.to_string_lossy(),
+        chain_path.to_string_lossy(),
+    );
+    let result = run_openssl(
+        &[
+            "pkcs12", "-export", "-in", &cert_arg, "-inkey", &key_arg, "-certfile", &chain_arg,
+            "-name", name, "-passout", "stdin",
+            "-certpbe", "PBE-SHA1-3DES", "-keypbe", "PBE-SHA1-3DES", "-macalg", "sha1",
+        ],
+        format!("{}\n", password).into_bytes(),
+    )
+    .await;
+
+    // The device keeps the only copy of its key
+    let _ = tokio::fs::remove_file(&key_path).await;
+    result
+}
+
+/// openssl command line for signing a mobileconfig by hand
+/// (served profiles are signed in-process by [`sign_mobileconfig`])
 pub fn sign_mobileconfig_openssl_command(
     unsigned_path: &str,
     signed_path: &str,
@@ -382,6 +764,8 @@ pub struct MdmManager {
     pub chain: Arc<RwLock<Option<SigningChain>>>,
     pub bridges: Arc<RwLock<Vec<BridgeConfig>>>,
     pub vpn_configs: Arc<RwLock<Vec<VpnConfig>>>,
+    /// Device enrollments, persisted to `enrollments.json` in the cert store
+    pub enrollments: Arc<RwLock<Vec<Enrollment>>>,
 }
 
 impl MdmManager {
@@ -391,15 +775,28 @@ impl MdmManager {
             chain: Arc::new(RwLock::new(None)),
             bridges: Arc::new(RwLock::new(Vec::new())),
             vpn_configs: Arc::new(RwLock::new(Vec::new())),
+            enrollments: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
     pub async fn init(&self) -> Result<()> {
         let chain = SigningChain::load_or_generate(&self.config).await?;
+        let path = self.enrollments_path();
+        if path.exists() {
+            let data = tokio::fs::read(&path).await?;
+            *self.enrollments.write().await = serde_json::from_slice(&data)?;
+        }
         *self.chain.write().await = Some(chain);
         Ok(())
     }
 
+    async fn ensure_init(&self) -> Result<()> {
+        if self.chain.read().await.is_none() {
+            self.init().await?;
+        }
+        Ok(())
+    }
+
     pub async fn get_root_ca_pem(&self) -> Option<String> {
         self.chain.read().await.as_ref().map(|c| c.root_ca_cert_pem.clone())
     }
@@ -422,19 +819,217 @@ impl MdmManager {
 
     /// Generate a profile with all current configs
     pub async fn generate_profile(&self, name: &str) -> Result<Vec<u8>> {
+        let identifier = format!("{}.profile.{}", self.config.domain, name.to_lowercase().replace(' ', "-"));
+        let req = self.profile_request(name, identifier).await;
+        generate_mobileconfig(&req)
+    }
+
+    async fn profile_request(&self, name: &str, identifier: String) -> ProfileRequest {
         let bridges = self.bridges.read().await.clone();
         let vpns = self.vpn_configs.read().await.clone();
-        
-        let req = ProfileRequest {
+
+        ProfileRequest {
             display_name: name.to_string(),
             description: Some(format!("{} network configuration", name)),
             organization: self.config.org_name.clone(),
-            identifier: format!("{}.profile.{}", self.config.domain, name.to_lowercase().replace(' ', "-")),
+            identifier,
             vpn: vpns.first().cloned(),
             bridges,
+        }
+    }
+
+    /// Sign a profile with the signing certificate
+    pub async fn sign_profile(&self, unsigned: &[u8]) -> Result<Vec<u8>> {
+        let chain = self.chain.read().await;
+        let chain = chain.as_ref().ok_or_else(|| anyhow!("MDM signing chain not initialized"))?;
+        sign_mobileconfig(unsigned, chain)
+    }
+
+    /// Generate a profile with all current configs, signed for delivery to devices
+    pub async fn generate_signed_profile(&self, name: &str) -> Result<Vec<u8>> {
+        let unsigned = self.generate_profile(name).await?;
+        self.sign_profile(&unsigned).await
+    }
+
+    fn enrollments_path(&self) -> PathBuf {
+        self.config.cert_store_path.join("enrollments.json")
+    }
+
+    async fn save_enrollments(&self, enrollments: &[Enrollment]) -> Result<()> {
+        tokio::fs::create_dir_all(&self.config.cert_store_path).await?;
+        tokio::fs::write(self.enrollments_path(), serde_json::to_vec_pretty(enrollments)?).await?;
+        Ok(())
+    }
+
+    fn device_identifier(&self, enrollment: &Enrollment) -> String {
+        format!("{}.device.{}", self.config.domain, enrollment.id)
+    }
+
+    /// URL a device opens to start enrolling
+    pub fn enrollment_url(&self, token: &str) -> String {
+        format!("{}/mdm/enroll/{}", self.config.public_url.trim_end_matches('/'), token)
+    }
+
+    /// Issue a one-time enrollment token
+    pub async fn create_enrollment(&self, label: &str, ttl_secs: Option<i64>) -> Result<Enrollment> {
+        self.ensure_init().await?;
+        let now = Utc::now();
+        let enrollment = Enrollment {
+            id: Uuid::new_v4().to_string(),
+            label: label.to_string(),
+            token: random_token(),
+            status: EnrollmentStatus::Pending,
+            created_at: now,
+            expires_at: now + Duration::seconds(ttl_secs.unwrap_or(DEFAULT_ENROLLMENT_TTL_SECS)),
+            device: None,
+            cert_serial: None,
+            profile_identifier: None,
+            enrolled_at: None,
+            revoked_at: None,
         };
 
-        generate_mobileconfig(&req)
+        let mut enrollments = self.enrollments.write().await;
+        enrollments.push(enrollment.clone());
+        self.save_enrollments(&enrollments).await?;
+        info!("Created MDM enrollment {} ({})", enrollment.id, enrollment.label);
+        Ok(enrollment)
+    }
+
+    pub async fn list_enrollments(&self) -> Vec<Enrollment> {
+        self.enrollments.read().await.clone()
+    }
+
+    async fn enrollment_by_token(&self, token: &str) -> Result<Enrollment, EnrollmentError> {
+        self.enrollments
+            .read()
+            .await
+            .iter()
+            .find(|e| e.token == token)
+            .cloned()
+            .ok_or(EnrollmentError::NotFound)
+    }
+
+    /// Signed Profile Service payload for a pending enrollment
+    pub async fn enrollment_request_profile(&self, token: &str) -> Result<Vec<u8>> {
+        self.ensure_init().await?;
+        let enrollment = self.enrollment_by_token(token).await?;
+        enrollment.check_pending(Utc::now())?;
+
+        let unsigned = generate_profile_service_payload(
+            token,
+            &self.enrollment_url(token),
+            &self.config.org_name,
+            &format!("{}.enroll.{}", self.config.domain, enrollment.id),
+        )?;
+        self.sign_profile(&unsigned).await
+    }
+
+    /// Redeem an enrollment token with the device's Profile Service response
+    ///
+    /// Records the device identity, issues its certificate and returns the
+    /// signed per-device profile.
+    pub async fn complete_enrollment(&self, token: &str, body: &[u8]) -> Result<Vec<u8>> {
+        self.ensure_init().await?;
+        let response = parse_device_attributes(body)?;
+        if response.challenge.as_deref() != Some(token) {
+            return Err(EnrollmentError::ChallengeMismatch.into());
+        }
+
+        // Hold the write lock throughout so a token is only redeemed once
+        let mut enrollments = self.enrollments.write().await;
+        let enrollment = enrollments
+            .iter_mut()
+            .find(|e| e.token == token)
+            .ok_or(EnrollmentError::NotFound)?;
+        enrollment.check_pending(Utc::now())?;
+
+        let (device_cert, root_ca_pem) = {
+            let chain = self.chain.read().await;
+            let chain = chain.as_ref().ok_or_else(|| anyhow!("MDM signing chain not initialized"))?;
+            (
+                chain.issue_device_certificate(&self.config, &response.identity)?,
+                chain.root_ca_cert_pem.clone(),
+            )
+        };
+
+        let password = random_token();
+        let pkcs12 = export_pkcs12(
+            &device_cert,
+            &self.config.cert_store_path.join("root-ca.crt"),
+            &self.config.cert_store_path.join("devices"),
+            &enrollment.id,
+            &password,
+        )
+        .await?;
+
+        let identifier = self.device_identifier(enrollment);
+        let req = self.profile_request(&enrollment.label, identifier.clone()).await;
+        let unsigned = generate_mobileconfig_with_payloads(&req, vec![
+            build_root_ca_payload(&root_ca_pem, &identifier)?,
+            build_identity_payload(pkcs12, &password, &identifier),
+        ])?;
+        let signed = self.sign_profile(&unsigned).await?;
+
+        info!(
+            "Enrolled device {} (serial {}) for enrollment {}",
+            response.identity.udid,
+            response.identity.serial.as_deref().unwrap_or("unknown"),
+            enrollment.id
+        );
+        enrollment.status = EnrollmentStatus::Enrolled;
+        enrollment.device = Some(response.identity);
+        enrollment.cert_serial = Some(device_cert.serial);
+        enrollment.profile_identifier = Some(identifier);
+        enrollment.enrolled_at = Some(Utc::now());
+        self.save_enrollments(&enrollments).await?;
+
+        Ok(signed)
+    }
+
+    /// Signed network profile for an enrolled, unrevoked device
+    pub async fn device_profile(&self, token: &str) -> Result<Vec<u8>> {
+        self.ensure_init().await?;
+        let enrollment = self.enrollment_by_token(token).await?;
+        match enrollment.status {
+            EnrollmentStatus::Pending => return Err(EnrollmentError::NotEnrolled.into()),
+            EnrollmentStatus::Revoked => return Err(EnrollmentError::Revoked.into()),
+            EnrollmentStatus::Enrolled => {}
+        }
+
+        // Separate identifier so installing it does not replace the identity profile
+        let identifier = format!("{}.network", self.device_identifier(&enrollment));
+        let req = self.profile_request(&enrollment.label, identifier).await;
+        self.sign_profile(&generate_mobileconfig(&req)?).await
+    }
+
+    /// Revoke an enrollment; its token stops working and its certificate serial is listed as revoked
+    pub async fn revoke_enrollment(&self, id: &str) -> Result<Enrollment> {
+        self.ensure_init().await?;
+        let mut enrollments = self.enrollments.write().await;
+        let enrollment = enrollments
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or(EnrollmentError::NotFound)?;
+
+        if enrollment.status != EnrollmentStatus::Revoked {
+            enrollment.status = EnrollmentStatus::Revoked;
+            enrollment.revoked_at = Some(Utc::now());
+            info!("Revoked MDM enrollment {}", enrollment.id);
+        }
+        let revoked = enrollment.clone();
+        self.save_enrollments(&enrollments).await?;
+        Ok(revoked)
+    }
+
+    /// Serial numbers of device certificates whose enrollment was revoked
+    pub async fn revoked_cert_serials(&self) -> Vec<String> {
+        self.enrollments
+            .read()
+            .await
+            .iter()
+            .filter(|e| e.status == EnrollmentStatus::Revoked)
+            .filter_map(|e| e.cert_serial.clone())
+            .collect()
     }
 
     /// Get paths for signing
@@ -490,4 +1085,157 @@ mod tests {
         assert!(chain.signing_cert_pem.contains("BEGIN CERTIFICATE"));
         assert!(chain.signing_key_pem.contains("BEGIN"));
     }
+
+    fn test_manager() -> MdmManager {
+        MdmManager::new(MdmConfig {
+            cert_store_path: std::env::temp_dir().join(format!("infrasim-mdm-{}", Uuid::new_v4())),
+            ..MdmConfig::default()
+        })
+    }
+
+    fn device_response(challenge: &str) -> Vec<u8> {
+        let mut attrs = Dictionary::new();
+        attrs.insert("UDID".into(), plist::Value::String("00008030-001A".into()));
+        attrs.insert("SERIAL".into(), plist::Value::String("C02XK1ABJG5H".into()));
+        attrs.insert("PRODUCT".into(), plist::Value::String("MacBookPro18,3".into()));
+        attrs.insert("CHALLENGE".into(), plist::Value::String(challenge.into()));
+        let mut xml = Vec::new();
+        plist::to_writer_xml(&mut xml, &plist::Value::Dictionary(attrs)).unwrap();
+
+        // Surround the plist with bytes standing in for the PKCS#7 envelope
+        let mut body = vec![0x30, 0x82, 0x01, 0x00];
+        body.extend(xml);
+        body.extend([0xa0, 0x00]);
+        body
+    }
+
+    fn has_openssl() -> bool {
+        std::process::Command::new("openssl").arg("version").output().is_ok()
+    }
+
+    #[test]
+    fn test_sign_mobileconfig() {
+        use cms::content_info::ContentInfo;
+        use cms::signed_data::SignedData;
+        use der::Decode;
+        use p256::ecdsa::signature::Verifier;
+
+        let chain = SigningChain::generate(&MdmConfig::default()).unwrap();
+        let unsigned = b"<?xml version=\"1.0\"?><plist version=\"1.0\"><dict/></plist>";
+        let signed = sign_mobileconfig(unsigned, &chain).unwrap();
+
+        let info = ContentInfo::from_der(&signed).unwrap();
+        assert_eq!(info.content_type, const_oid::db::rfc5911::ID_SIGNED_DATA);
+        let data: SignedData = info.content.decode_as().unwrap();
+        assert_eq!(data.encap_content_info.econtent.unwrap().value(), unsigned);
+        assert_eq!(data.certificates.unwrap().0.len(), 2);
+
+        // The signature covers the signed attributes, which carry the content digest
+        let signer = data.signer_infos.0.get(0).unwrap();
+        let cert = x509_cert::Certificate::from_pem(&chain.signing_cert_pem).unwrap();
+        let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(
+            cert.tbs_certificate.subject_public_key_info.subject_public_key.raw_bytes(),
+        )
+        .unwrap();
+        let signature = p256::ecdsa::DerSignature::try_from(signer.signature.as_bytes()).unwrap();
+        let signed_attrs = signer.signed_attrs.as_ref().unwrap().to_der().unwrap();
+        assert!(key.verify(&signed_attrs, &signature).is_ok());
+    }
+
+    #[test]
+    fn test_profile_service_payload() {
+        let xml = generate_profile_service_payload(
+            "tok123",
+            "https://mdm.example.com/mdm/enroll/tok123",
+            "Test Org",
+            "com.test.enroll",
+        )
+        .unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains("Profile Service"));
+        assert!(xml.contains("<string>tok123</string>"));
+        assert!(xml.contains("<string>SERIAL</string>"));
+    }
+
+    #[test]
+    fn test_parse_device_attributes() {
+        let response = parse_device_attributes(&device_response("tok123")).unwrap();
+        assert_eq!(response.identity.udid, "00008030-001A");
+        assert_eq!(response.identity.serial.as_deref(), Some("C02XK1ABJG5H"));
+        assert_eq!(response.identity.imei, None);
+        assert_eq!(response.challenge.as_deref(), Some("tok123"));
+
+        assert!(parse_device_attributes(b"not a plist").is_err());
+    }
+
+    #[test]
+    fn test_device_certificate_issued_by_root() {
+        let config = MdmConfig::default();
+        let chain = SigningChain::generate(&config).unwrap();
+        let device = DeviceIdentity {
+            udid: "00008030-001A".into(),
+            serial: Some("C02XK1ABJG5H".into()),
+            ..Default::default()
+        };
+        let issued = chain.issue_device_certificate(&config, &device).unwrap();
+
+        let root = x509_cert::Certificate::from_pem(&chain.root_ca_cert_pem).unwrap();
+        let cert = x509_cert::Certificate::from_pem(&issued.cert_pem).unwrap();
+        assert_eq!(cert.tbs_certificate.issuer, root.tbs_certificate.subject);
+        assert_eq!(hex::encode(cert.tbs_certificate.serial_number.as_bytes()), issued.serial);
+        assert!(cert.tbs_certificate.subject.to_string().contains("C02XK1ABJG5H"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_enrollment_is_refused() {
+        let mdm = test_manager();
+        let enrollment = mdm.create_enrollment("Expired", Some(-1)).await.unwrap();
+
+        let err = mdm.enrollment_request_profile(&enrollment.token).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<EnrollmentError>(), Some(&EnrollmentError::Expired));
+        let err = mdm.enrollment_request_profile("unknown").await.unwrap_err();
+        assert_eq!(err.downcast_ref::<EnrollmentError>(), Some(&EnrollmentError::NotFound));
+
+        let _ = std::fs::remove_dir_all(&mdm.config.cert_store_path);
+    }
+
+    #[tokio::test]
+    async fn test_enrollment_workflow() {
+        if !has_openssl() {
+            return;
+        }
+        let mdm = test_manager();
+        let enrollment = mdm.create_enrollment("Alice MacBook", None).await.unwrap();
+        assert_eq!(enrollment.status, EnrollmentStatus::Pending);
+
+        // Signed payloads are DER-encoded CMS
+        let request = mdm.enrollment_request_profile(&enrollment.token).await.unwrap();
+        assert_eq!(request[0], 0x30);
+
+        let err = mdm.complete_enrollment(&enrollment.token, &device_response("wrong")).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<EnrollmentError>(), Some(&EnrollmentError::ChallengeMismatch));
+
+        let body = device_response(&enrollment.token);
+        let profile = mdm.complete_enrollment(&enrollment.token, &body).await.unwrap();
+        assert!(find_bytes(&profile, b"com.apple.security.pkcs12").is_some());
+
+        let err = mdm.complete_enrollment(&enrollment.token, &body).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<EnrollmentError>(), Some(&EnrollmentError::AlreadyEnrolled));
+
+        // The enrollment survives a restart with the recorded device identity
+        let reloaded = MdmManager::new(mdm.config.clone());
+        reloaded.init().await.unwrap();
+        let stored = reloaded.list_enrollments().await.remove(0);
+        assert_eq!(stored.status, EnrollmentStatus::Enrolled);
+        assert_eq!(stored.device.unwrap().serial.as_deref(), Some("C02XK1ABJG5H"));
+        let cert_serial = stored.cert_serial.unwrap();
+
+        assert!(reloaded.device_profile(&enrollment.token).await.is_ok());
+        reloaded.revoke_enrollment(&enrollment.id).await.unwrap();
+        let err = reloaded.device_profile(&enrollment.token).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<EnrollmentError>(), Some(&EnrollmentError::Revoked));
+        assert_eq!(reloaded.revoked_cert_serials().await, vec![cert_serial]);
+
+        let _ = std::fs::remove_dir_all(&mdm.config.cert_store_path);
+    }
 }
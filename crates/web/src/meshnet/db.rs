@@ -5,14 +5,139 @@
 //! - meshnet_webauthn_credentials: WebAuthn passkey storage
 //! - meshnet_identities: Identity handles with provisioning status
 //! - meshnet_mesh_peers: WireGuard/Tailscale peer configurations
+//! - meshnet_key_generations: Audit trail of peer key creation, rotation and revocation
 //! - meshnet_appliances: Downloadable appliance archives
 
-use infrasim_common::Database;
+use infrasim_common::{Database, Migration, MigrationUp, Migrations};
 use rusqlite::{params, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 use uuid::Uuid;
 
+/// Migrations of the meshnet tables; only ever append
+const MIGRATIONS: Migrations = Migrations {
+    component: "web.meshnet",
+    migrations: &[Migration {
+        version: 1,
+        name: "initial",
+        up: MigrationUp::Sql(r#"
+            -- Meshnet users
+            CREATE TABLE IF NOT EXISTS meshnet_users (
+                id TEXT PRIMARY KEY,
+                created_at INTEGER NOT NULL,
+                display_name TEXT,
+                current_identity_handle TEXT
+            );
+
+            -- WebAuthn credentials
+            CREATE TABLE IF NOT EXISTS meshnet_webauthn_credentials (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                credential_id BLOB NOT NULL UNIQUE,
+                public_key BLOB NOT NULL,
+                sign_count INTEGER NOT NULL DEFAULT 0,
+                transports TEXT,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_meshnet_webauthn_user ON meshnet_webauthn_credentials(user_id);
+            CREATE INDEX IF NOT EXISTS idx_meshnet_webauthn_cred_id ON meshnet_webauthn_credentials(credential_id);
+
+            -- Identities (handles)
+            CREATE TABLE IF NOT EXISTS meshnet_identities (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL UNIQUE,
+                handle TEXT NOT NULL UNIQUE,
+                fqdn TEXT NOT NULL,
+                matrix_id TEXT NOT NULL,
+                status_subdomain TEXT NOT NULL DEFAULT 'pending',
+                status_matrix TEXT NOT NULL DEFAULT 'pending',
+                status_storage TEXT NOT NULL DEFAULT 'pending',
+                last_error TEXT,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_meshnet_identities_handle ON meshnet_identities(handle);
+            CREATE INDEX IF NOT EXISTS idx_meshnet_identities_user ON meshnet_identities(user_id);
+
+            -- Mesh peers
+            CREATE TABLE IF NOT EXISTS meshnet_mesh_peers (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                provider TEXT NOT NULL DEFAULT 'wireguard',
+                public_key TEXT NOT NULL,
+                private_key_encrypted BLOB,
+                preshared_key TEXT,
+                allowed_ips TEXT NOT NULL,
+                endpoint TEXT,
+                keepalive INTEGER,
+                address TEXT NOT NULL,
+                revoked_at INTEGER,
+                last_handshake_at INTEGER,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_meshnet_peers_user ON meshnet_mesh_peers(user_id);
+
+            -- Peer key generations (audit trail)
+            CREATE TABLE IF NOT EXISTS meshnet_key_generations (
+                id TEXT PRIMARY KEY,
+                peer_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                generation INTEGER NOT NULL,
+                public_key TEXT NOT NULL,
+                event TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                valid_until INTEGER,
+                FOREIGN KEY(peer_id) REFERENCES meshnet_mesh_peers(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_meshnet_key_generations_peer ON meshnet_key_generations(peer_id);
+            CREATE INDEX IF NOT EXISTS idx_meshnet_key_generations_user ON meshnet_key_generations(user_id);
+
+            -- Appliances
+            CREATE TABLE IF NOT EXISTS meshnet_appliances (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                version TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                qcow_path TEXT,
+                archive_path TEXT,
+                terraform_path TEXT,
+                last_error TEXT,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_meshnet_appliances_user ON meshnet_appliances(user_id);
+
+            -- Sessions
+            CREATE TABLE IF NOT EXISTS meshnet_sessions (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                token_hash TEXT NOT NULL UNIQUE,
+                expires_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_meshnet_sessions_token ON meshnet_sessions(token_hash);
+            CREATE INDEX IF NOT EXISTS idx_meshnet_sessions_expires ON meshnet_sessions(expires_at);
+
+            -- WebAuthn challenge store (temporary, in-memory would be better but this works)
+            CREATE TABLE IF NOT EXISTS meshnet_webauthn_challenges (
+                id TEXT PRIMARY KEY,
+                user_id TEXT,
+                challenge_data TEXT NOT NULL,
+                challenge_type TEXT NOT NULL,
+                expires_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_meshnet_challenges_expires ON meshnet_webauthn_challenges(expires_at);
+        "#),
+        down: None,
+    }],
+};
+
 /// Meshnet database wrapper
 #[derive(Clone)]
 pub struct MeshnetDb {
@@ -155,6 +280,54 @@ pub struct MeshPeerRecord {
     pub created_at: i64,
 }
 
+// ============================================================================
+// Key generation types
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyEvent {
+    Created,
+    Rotated,
+    Revoked,
+}
+
+impl std::fmt::Display for KeyEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Created => write!(f, "created"),
+            Self::Rotated => write!(f, "rotated"),
+            Self::Revoked => write!(f, "revoked"),
+        }
+    }
+}
+
+impl std::str::FromStr for KeyEvent {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(Self::Created),
+            "rotated" => Ok(Self::Rotated),
+            "revoked" => Ok(Self::Revoked),
+            _ => Err(format!("unknown key event: {}", s)),
+        }
+    }
+}
+
+/// A key a peer held, or its revocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyGenerationRecord {
+    pub id: Uuid,
+    pub peer_id: Uuid,
+    pub user_id: Uuid,
+    pub generation: u32,
+    pub public_key: String,
+    pub event: KeyEvent,
+    pub created_at: i64,
+    /// When the gateway stops accepting the key; None while it is current
+    pub valid_until: Option<i64>,
+}
+
 // ============================================================================
 // Appliance types
 // ============================================================================
@@ -236,110 +409,7 @@ impl MeshnetDb {
 
     /// Initialize meshnet schema
     pub fn init_schema(&self) -> Result<(), String> {
-        let conn = self.db.connection();
-        let conn = conn.lock();
-        conn.execute_batch(
-            r#"
-            -- Meshnet users
-            CREATE TABLE IF NOT EXISTS meshnet_users (
-                id TEXT PRIMARY KEY,
-                created_at INTEGER NOT NULL,
-                display_name TEXT,
-                current_identity_handle TEXT
-            );
-
-            -- WebAuthn credentials
-            CREATE TABLE IF NOT EXISTS meshnet_webauthn_credentials (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                credential_id BLOB NOT NULL UNIQUE,
-                public_key BLOB NOT NULL,
-                sign_count INTEGER NOT NULL DEFAULT 0,
-                transports TEXT,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
-            );
-            CREATE INDEX IF NOT EXISTS idx_meshnet_webauthn_user ON meshnet_webauthn_credentials(user_id);
-            CREATE INDEX IF NOT EXISTS idx_meshnet_webauthn_cred_id ON meshnet_webauthn_credentials(credential_id);
-
-            -- Identities (handles)
-            CREATE TABLE IF NOT EXISTS meshnet_identities (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL UNIQUE,
-                handle TEXT NOT NULL UNIQUE,
-                fqdn TEXT NOT NULL,
-                matrix_id TEXT NOT NULL,
-                status_subdomain TEXT NOT NULL DEFAULT 'pending',
-                status_matrix TEXT NOT NULL DEFAULT 'pending',
-                status_storage TEXT NOT NULL DEFAULT 'pending',
-                last_error TEXT,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
-            );
-            CREATE INDEX IF NOT EXISTS idx_meshnet_identities_handle ON meshnet_identities(handle);
-            CREATE INDEX IF NOT EXISTS idx_meshnet_identities_user ON meshnet_identities(user_id);
-
-            -- Mesh peers
-            CREATE TABLE IF NOT EXISTS meshnet_mesh_peers (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                provider TEXT NOT NULL DEFAULT 'wireguard',
-                public_key TEXT NOT NULL,
-                private_key_encrypted BLOB,
-                preshared_key TEXT,
-                allowed_ips TEXT NOT NULL,
-                endpoint TEXT,
-                keepalive INTEGER,
-                address TEXT NOT NULL,
-                revoked_at INTEGER,
-                last_handshake_at INTEGER,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
-            );
-            CREATE INDEX IF NOT EXISTS idx_meshnet_peers_user ON meshnet_mesh_peers(user_id);
-
-            -- Appliances
-            CREATE TABLE IF NOT EXISTS meshnet_appliances (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                version TEXT NOT NULL,
-                status TEXT NOT NULL DEFAULT 'pending',
-                qcow_path TEXT,
-                archive_path TEXT,
-                terraform_path TEXT,
-                last_error TEXT,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
-            );
-            CREATE INDEX IF NOT EXISTS idx_meshnet_appliances_user ON meshnet_appliances(user_id);
-
-            -- Sessions
-            CREATE TABLE IF NOT EXISTS meshnet_sessions (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                token_hash TEXT NOT NULL UNIQUE,
-                expires_at INTEGER NOT NULL,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY(user_id) REFERENCES meshnet_users(id) ON DELETE CASCADE
-            );
-            CREATE INDEX IF NOT EXISTS idx_meshnet_sessions_token ON meshnet_sessions(token_hash);
-            CREATE INDEX IF NOT EXISTS idx_meshnet_sessions_expires ON meshnet_sessions(expires_at);
-
-            -- WebAuthn challenge store (temporary, in-memory would be better but this works)
-            CREATE TABLE IF NOT EXISTS meshnet_webauthn_challenges (
-                id TEXT PRIMARY KEY,
-                user_id TEXT,
-                challenge_data TEXT NOT NULL,
-                challenge_type TEXT NOT NULL,
-                expires_at INTEGER NOT NULL,
-                created_at INTEGER NOT NULL
-            );
-            CREATE INDEX IF NOT EXISTS idx_meshnet_challenges_expires ON meshnet_webauthn_challenges(expires_at);
-            "#,
-        )
-        .map_err(|e| e.to_string())?;
+        self.db.migrate(&MIGRATIONS).map_err(|e| e.to_string())?;
         
         info!("Meshnet database schema initialized");
         Ok(())
@@ -757,17 +827,189 @@ impl MeshnetDb {
         .map_err(|e| e.to_string())
     }
 
+    /// Revoke a peer; every key it held stops being accepted immediately
     pub fn revoke_mesh_peer(&self, id: Uuid) -> Result<(), String> {
         let now = now_epoch_secs();
         let conn = self.db.connection();
-        let conn = conn.lock();
-        conn.execute(
+        let mut conn = conn.lock();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        
+        let (user_id, public_key): (String, String) = tx.query_row(
+            "SELECT user_id, public_key FROM meshnet_mesh_peers WHERE id = ?1",
+            params![id.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).map_err(|e| e.to_string())?;
+        
+        tx.execute(
             "UPDATE meshnet_mesh_peers SET revoked_at = ?1 WHERE id = ?2",
             params![now, id.to_string()],
         ).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE meshnet_key_generations SET valid_until = ?1 
+             WHERE peer_id = ?2 AND (valid_until IS NULL OR valid_until > ?1)",
+            params![now, id.to_string()],
+        ).map_err(|e| e.to_string())?;
+        let generation = latest_generation(&tx, id)?.max(1);
+        insert_key_generation(&tx, &KeyGenerationRecord {
+            id: Uuid::new_v4(),
+            peer_id: id,
+            user_id: Uuid::parse_str(&user_id).map_err(|e| e.to_string())?,
+            generation,
+            public_key,
+            event: KeyEvent::Revoked,
+            created_at: now,
+            valid_until: Some(now),
+        })?;
+        
+        tx.commit().map_err(|e| e.to_string())?;
         Ok(())
     }
 
+    // ========================================================================
+    // Key generation operations
+    // ========================================================================
+
+    pub fn record_key_generation(&self, record: &KeyGenerationRecord) -> Result<(), String> {
+        let conn = self.db.connection();
+        let conn = conn.lock();
+        insert_key_generation(&conn, record)
+    }
+
+    /// Key history of a peer, oldest first
+    pub fn get_key_generations(&self, peer_id: Uuid) -> Result<Vec<KeyGenerationRecord>, String> {
+        self.query_key_generations(
+            "WHERE peer_id = ?1 ORDER BY generation, created_at",
+            params![peer_id.to_string()],
+        )
+    }
+
+    /// Keys replaced by a rotation that the gateway still accepts at `now`
+    pub fn get_overlapping_keys(&self, user_id: Uuid, now: i64) -> Result<Vec<KeyGenerationRecord>, String> {
+        self.query_key_generations(
+            "WHERE user_id = ?1 AND event != 'revoked' AND valid_until > ?2 ORDER BY generation",
+            params![user_id.to_string(), now],
+        )
+    }
+
+    /// Replace a peer's key, keeping the old one valid until `overlap_until`
+    pub fn rotate_mesh_peer_key(
+        &self,
+        id: Uuid,
+        public_key: &str,
+        private_key: &[u8],
+        overlap_until: i64,
+    ) -> Result<KeyGenerationRecord, String> {
+        let now = now_epoch_secs();
+        let conn = self.db.connection();
+        let mut conn = conn.lock();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        
+        let (user_id, old_key, revoked_at, created_at): (String, String, Option<i64>, i64) = tx.query_row(
+            "SELECT user_id, public_key, revoked_at, created_at FROM meshnet_mesh_peers WHERE id = ?1",
+            params![id.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Peer not found".to_string())?;
+        if revoked_at.is_some() {
+            return Err("Peer has been revoked".to_string());
+        }
+        let user_id = Uuid::parse_str(&user_id).map_err(|e| e.to_string())?;
+        
+        // Peers created before key generations were recorded start at 1
+        let mut generation = latest_generation(&tx, id)?;
+        if generation == 0 {
+            generation = 1;
+            insert_key_generation(&tx, &KeyGenerationRecord {
+                id: Uuid::new_v4(),
+                peer_id: id,
+                user_id,
+                generation,
+                public_key: old_key,
+                event: KeyEvent::Created,
+                created_at,
+                valid_until: None,
+            })?;
+        }
+        
+        tx.execute(
+            "UPDATE meshnet_key_generations SET valid_until = ?1 WHERE peer_id = ?2 AND valid_until IS NULL",
+            params![overlap_until, id.to_string()],
+        ).map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE meshnet_mesh_peers SET public_key = ?1, private_key_encrypted = ?2 WHERE id = ?3",
+            params![public_key, private_key, id.to_string()],
+        ).map_err(|e| e.to_string())?;
+        
+        let record = KeyGenerationRecord {
+            id: Uuid::new_v4(),
+            peer_id: id,
+            user_id,
+            generation: generation + 1,
+            public_key: public_key.to_string(),
+            event: KeyEvent::Rotated,
+            created_at: now,
+            valid_until: None,
+        };
+        insert_key_generation(&tx, &record)?;
+        
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(record)
+    }
+
+    /// Active WireGuard peers whose current key was issued before `cutoff`
+    pub fn get_peers_due_for_rotation(&self, cutoff: i64) -> Result<Vec<Uuid>, String> {
+        let conn = self.db.connection();
+        let conn = conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT p.id FROM meshnet_mesh_peers p 
+             WHERE p.revoked_at IS NULL AND p.provider = 'wireguard' 
+             AND COALESCE(
+                 (SELECT MAX(g.created_at) FROM meshnet_key_generations g WHERE g.peer_id = p.id AND g.event != 'revoked'),
+                 p.created_at
+             ) < ?1"
+        ).map_err(|e| e.to_string())?;
+        
+        let rows = stmt.query_map(params![cutoff], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(Uuid::parse_str(&row.map_err(|e| e.to_string())?).map_err(|e| e.to_string())?);
+        }
+        Ok(ids)
+    }
+
+    fn query_key_generations(&self, clause: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<KeyGenerationRecord>, String> {
+        let conn = self.db.connection();
+        let conn = conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, peer_id, user_id, generation, public_key, event, created_at, valid_until 
+             FROM meshnet_key_generations {}",
+            clause
+        )).map_err(|e| e.to_string())?;
+        
+        let rows = stmt.query_map(params, |row| {
+            Ok(KeyGenerationRecord {
+                id: Uuid::parse_str(&row.get::<_, String>(0)?).unwrap(),
+                peer_id: Uuid::parse_str(&row.get::<_, String>(1)?).unwrap(),
+                user_id: Uuid::parse_str(&row.get::<_, String>(2)?).unwrap(),
+                generation: row.get(3)?,
+                public_key: row.get(4)?,
+                event: row.get::<_, String>(5)?.parse().unwrap_or(KeyEvent::Created),
+                created_at: row.get(6)?,
+                valid_until: row.get(7)?,
+            })
+        }).map_err(|e| e.to_string())?;
+        
+        let mut records = Vec::new();
+        for row in rows {
+            records.push(row.map_err(|e| e.to_string())?);
+        }
+        Ok(records)
+    }
+
     pub fn count_user_peers(&self, user_id: Uuid) -> Result<usize, String> {
         let conn = self.db.connection();
         let conn = conn.lock();
@@ -955,6 +1197,33 @@ impl MeshnetDb {
     }
 }
 
+fn insert_key_generation(conn: &rusqlite::Connection, record: &KeyGenerationRecord) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO meshnet_key_generations (id, peer_id, user_id, generation, public_key, event, created_at, valid_until) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            record.id.to_string(),
+            record.peer_id.to_string(),
+            record.user_id.to_string(),
+            record.generation,
+            record.public_key,
+            record.event.to_string(),
+            record.created_at,
+            record.valid_until,
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Highest key generation recorded for a peer; 0 if none
+fn latest_generation(conn: &rusqlite::Connection, peer_id: Uuid) -> Result<u32, String> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(generation), 0) FROM meshnet_key_generations WHERE peer_id = ?1",
+        params![peer_id.to_string()],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())
+}
+
 fn now_epoch_secs() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -1000,4 +1269,46 @@ mod tests {
         let result = db.create_identity(user.id, "bob", "mesh.example.com", "matrix.example.com");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_key_rotation_of_legacy_peer() {
+        let db = test_db();
+        let user = db.create_user(None).unwrap();
+        let now = now_epoch_secs();
+        let peer = MeshPeerRecord {
+            id: Uuid::new_v4(),
+            user_id: user.id,
+            name: "laptop".to_string(),
+            provider: MeshProviderType::Wireguard,
+            public_key: "old-key".to_string(),
+            private_key_encrypted: Some(b"old-private".to_vec()),
+            preshared_key: None,
+            allowed_ips: "10.50.1.0/24".to_string(),
+            endpoint: None,
+            keepalive: Some(25),
+            address: "10.50.1.2/32".to_string(),
+            revoked_at: None,
+            last_handshake_at: None,
+            created_at: now - 100,
+        };
+        db.create_mesh_peer(&peer).unwrap();
+        
+        // No key generations recorded yet; due by the peer's creation time
+        assert_eq!(db.get_peers_due_for_rotation(now).unwrap(), vec![peer.id]);
+        assert!(db.get_peers_due_for_rotation(now - 200).unwrap().is_empty());
+        
+        let rotated = db.rotate_mesh_peer_key(peer.id, "new-key", b"new-private", now + 60).unwrap();
+        assert_eq!(rotated.generation, 2);
+        assert!(db.get_peers_due_for_rotation(now - 50).unwrap().is_empty());
+        
+        let history = db.get_key_generations(peer.id).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].public_key, "old-key");
+        assert_eq!(history[0].created_at, now - 100);
+        assert_eq!(history[0].valid_until, Some(now + 60));
+        
+        let overlapping = db.get_overlapping_keys(user.id, now).unwrap();
+        assert_eq!(overlapping.len(), 1);
+        assert!(db.get_overlapping_keys(user.id, now + 60).unwrap().is_empty());
+    }
 }
@@ -6,12 +6,12 @@
 //!
 //! Uses x25519-dalek for key generation.
 
-use crate::meshnet::db::{MeshnetDb, MeshPeerRecord, MeshProviderType, MeshnetIdentity};
+use crate::meshnet::db::{KeyEvent, KeyGenerationRecord, MeshnetDb, MeshPeerRecord, MeshProviderType, MeshnetIdentity};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use std::sync::Arc;
-use tracing::{debug, info};
+use tracing::{info, warn};
 use uuid::Uuid;
 
 // ============================================================================
@@ -67,6 +67,28 @@ pub struct WgKeyPair {
     pub public_key: String,  // Base64
 }
 
+/// When peer keys are rotated
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct KeyRotationPolicy {
+    /// Age after which a peer key is rotated; 0 disables scheduled rotation
+    pub interval_secs: i64,
+    /// How long the gateway keeps accepting a replaced key
+    pub overlap_secs: i64,
+}
+
+impl KeyRotationPolicy {
+    /// Read `MESHNET_KEY_ROTATION_DAYS` (default 30) and `MESHNET_KEY_OVERLAP_HOURS` (default 24)
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: i64| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            interval_secs: var("MESHNET_KEY_ROTATION_DAYS", 30) * 24 * 60 * 60,
+            overlap_secs: var("MESHNET_KEY_OVERLAP_HOURS", 24) * 60 * 60,
+        }
+    }
+}
+
 /// Server (gateway) configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GatewayConfig {
@@ -92,6 +114,9 @@ pub trait MeshProvider: Send + Sync {
     /// Revoke a peer
     async fn revoke_peer(&self, peer_id: Uuid) -> Result<(), String>;
     
+    /// Replace a peer's key pair, recording the new key generation
+    async fn rotate_peer_key(&self, peer_id: Uuid) -> Result<KeyGenerationRecord, String>;
+    
     /// Get peer status
     async fn peer_status(&self, peer_id: Uuid) -> Result<PeerStatus, String>;
     
@@ -110,6 +135,7 @@ pub trait MeshProvider: Send + Sync {
 pub struct WireGuardProvider {
     db: MeshnetDb,
     gateway: GatewayConfig,
+    rotation: KeyRotationPolicy,
 }
 
 impl WireGuardProvider {
@@ -134,7 +160,71 @@ impl WireGuardProvider {
                 allowed_ips: "0.0.0.0/0, ::/0".to_string(),
                 dns,
             },
+            rotation: KeyRotationPolicy::from_env(),
+        }
+    }
+    
+    pub fn with_rotation_policy(mut self, rotation: KeyRotationPolicy) -> Self {
+        self.rotation = rotation;
+        self
+    }
+    
+    pub fn rotation_policy(&self) -> KeyRotationPolicy {
+        self.rotation
+    }
+    
+    /// Rotate the keys of all peers older than the rotation interval
+    ///
+    /// Returns the number of peers rotated; failures are logged and skipped.
+    pub async fn rotate_due_keys(&self) -> Result<usize, String> {
+        if self.rotation.interval_secs <= 0 {
+            return Ok(0);
         }
+        
+        let cutoff = now_epoch_secs() - self.rotation.interval_secs;
+        let mut rotated = 0;
+        for peer_id in self.db.get_peers_due_for_rotation(cutoff)? {
+            match self.rotate_peer_key(peer_id).await {
+                Ok(_) => rotated += 1,
+                Err(e) => warn!("Scheduled key rotation of peer {} failed: {}", peer_id, e),
+            }
+        }
+        Ok(rotated)
+    }
+    
+    /// Render the gateway's `[Peer]` sections for a user's active peers
+    ///
+    /// Keys replaced by a rotation are listed until their overlap window ends,
+    /// so devices that haven't fetched their new config yet stay connected.
+    pub fn render_gateway_config(&self, user_id: Uuid) -> Result<String, String> {
+        let now = now_epoch_secs();
+        let peers: Vec<MeshPeerRecord> = self.db.get_mesh_peers(user_id)?
+            .into_iter()
+            .filter(|p| p.provider == MeshProviderType::Wireguard && p.revoked_at.is_none())
+            .collect();
+        let overlapping = self.db.get_overlapping_keys(user_id, now)?;
+        
+        let mut config = format!(
+            "# Gateway peers for user {}\n# Generated by Meshnet Console\n",
+            user_id
+        );
+        for peer in &peers {
+            for old in overlapping.iter().filter(|k| k.peer_id == peer.id) {
+                let until = chrono::DateTime::from_timestamp(old.valid_until.unwrap_or(now), 0)
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default();
+                config.push_str(&format!(
+                    "\n[Peer]\n# {} (key generation {}, accepted until {})\nPublicKey = {}\nAllowedIPs = {}\n",
+                    peer.name, old.generation, until, old.public_key, peer.address
+                ));
+            }
+            config.push_str(&format!(
+                "\n[Peer]\n# {}\nPublicKey = {}\nAllowedIPs = {}\n",
+                peer.name, peer.public_key, peer.address
+            ));
+        }
+        
+        Ok(config)
     }
     
     /// Derive a stable /24 subnet for a user based on their ID
@@ -199,6 +289,16 @@ impl MeshProvider for WireGuardProvider {
         };
         
         self.db.create_mesh_peer(&record)?;
+        self.db.record_key_generation(&KeyGenerationRecord {
+            id: Uuid::new_v4(),
+            peer_id: record.id,
+            user_id,
+            generation: 1,
+            public_key: record.public_key.clone(),
+            event: KeyEvent::Created,
+            created_at: now,
+            valid_until: None,
+        })?;
         
         info!("Created WireGuard peer {} for user {} at {}", name, user_id, address);
         
@@ -259,6 +359,21 @@ PersistentKeepalive = {keepalive}
         Ok(())
     }
     
+    async fn rotate_peer_key(&self, peer_id: Uuid) -> Result<KeyGenerationRecord, String> {
+        let keypair = generate_wireguard_keypair();
+        let overlap_until = now_epoch_secs() + self.rotation.overlap_secs.max(0);
+        
+        let record = self.db.rotate_mesh_peer_key(
+            peer_id,
+            &keypair.public_key,
+            keypair.private_key.as_bytes(), // MVP: not encrypted
+            overlap_until,
+        )?;
+        
+        info!("Rotated key of peer {} to generation {}", peer_id, record.generation);
+        Ok(record)
+    }
+    
     async fn peer_status(&self, peer_id: Uuid) -> Result<PeerStatus, String> {
         let peer = self.db.get_mesh_peer(peer_id)?
             .ok_or_else(|| "Peer not found".to_string())?;
@@ -283,6 +398,13 @@ PersistentKeepalive = {keepalive}
     }
 }
 
+fn now_epoch_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
 // ============================================================================
 // Key generation
 // ============================================================================
@@ -568,6 +690,10 @@ impl MeshProvider for TailscaleProvider {
         self.db.revoke_mesh_peer(peer_id)
     }
     
+    async fn rotate_peer_key(&self, _peer_id: Uuid) -> Result<KeyGenerationRecord, String> {
+        Err("Tailscale node keys are rotated by Tailscale".to_string())
+    }
+    
     async fn peer_status(&self, peer_id: Uuid) -> Result<PeerStatus, String> {
         let peer = self.db.get_mesh_peer(peer_id)?
             .ok_or("Peer not found")?;
@@ -667,4 +793,51 @@ mod tests {
         let peers = provider.list_peers(user.id).await.unwrap();
         assert_eq!(peers.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_rotate_and_revoke_peer_key() {
+        let provider = test_provider().with_rotation_policy(KeyRotationPolicy {
+            interval_secs: 0,
+            overlap_secs: 3600,
+        });
+        let user = provider.db.create_user(Some("test")).unwrap();
+        let laptop = provider.create_peer(user.id, "laptop").await.unwrap();
+        let phone = provider.create_peer(user.id, "phone").await.unwrap();
+        
+        let rotated = provider.rotate_peer_key(laptop.id).await.unwrap();
+        assert_eq!(rotated.generation, 2);
+        assert_ne!(rotated.public_key, laptop.public_key);
+        let record = provider.get_peer(laptop.id).await.unwrap().unwrap();
+        assert_eq!(record.public_key, rotated.public_key);
+        
+        // The old key stays accepted during the overlap window
+        let gateway = provider.render_gateway_config(user.id).unwrap();
+        assert!(gateway.contains(&laptop.public_key));
+        assert!(gateway.contains(&rotated.public_key));
+        assert!(gateway.contains(&phone.public_key));
+        
+        provider.revoke_peer(laptop.id).await.unwrap();
+        let gateway = provider.render_gateway_config(user.id).unwrap();
+        assert!(!gateway.contains(&laptop.public_key));
+        assert!(!gateway.contains(&rotated.public_key));
+        assert!(gateway.contains(&phone.public_key));
+        assert!(provider.rotate_peer_key(laptop.id).await.is_err());
+        
+        let history = provider.db.get_key_generations(laptop.id).unwrap();
+        let events: Vec<KeyEvent> = history.iter().map(|k| k.event).collect();
+        assert_eq!(events, vec![KeyEvent::Created, KeyEvent::Rotated, KeyEvent::Revoked]);
+        assert!(history.iter().all(|k| k.valid_until.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_rotation_disabled() {
+        let provider = test_provider().with_rotation_policy(KeyRotationPolicy {
+            interval_secs: 0,
+            overlap_secs: 0,
+        });
+        let user = provider.db.create_user(Some("test")).unwrap();
+        provider.create_peer(user.id, "laptop").await.unwrap();
+        
+        assert_eq!(provider.rotate_due_keys().await.unwrap(), 0);
+    }
 }
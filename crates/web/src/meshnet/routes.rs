@@ -16,6 +16,7 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{info, warn};
 use uuid::Uuid;
 
@@ -178,9 +179,34 @@ pub fn meshnet_router(db: infrasim_common::Database) -> Router {
         }
     };
     
+    spawn_key_rotation(state.mesh_provider.clone());
     create_meshnet_routes(state)
 }
 
+/// How often peers are checked for scheduled key rotation
+const KEY_ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Rotate peer keys in the background once they reach the rotation interval
+fn spawn_key_rotation(provider: Arc<WireGuardProvider>) {
+    let policy = provider.rotation_policy();
+    if policy.interval_secs <= 0 {
+        info!("Scheduled mesh key rotation disabled");
+        return;
+    }
+    
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(KEY_ROTATION_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match provider.rotate_due_keys().await {
+                Ok(0) => {}
+                Ok(n) => info!("Rotated keys of {} mesh peers", n),
+                Err(e) => warn!("Scheduled key rotation failed: {}", e),
+            }
+        }
+    });
+}
+
 /// Create the meshnet router with pre-configured state
 fn create_meshnet_routes(state: Arc<MeshnetState>) -> Router {
     Router::new()
@@ -202,7 +228,10 @@ fn create_meshnet_routes(state: Arc<MeshnetState>) -> Router {
         .route("/mesh/peers/:id", get(get_peer_handler))
         .route("/mesh/peers/:id/config", get(download_peer_config_handler))
         .route("/mesh/peers/:id/revoke", post(revoke_peer_handler))
+        .route("/mesh/peers/:id/rotate", post(rotate_peer_handler))
+        .route("/mesh/peers/:id/keys", get(peer_keys_handler))
         .route("/mesh/rotate-keys", post(rotate_keys_handler))
+        .route("/mesh/gateway/config", get(gateway_config_handler))
         
         // Appliances
         .route("/appliances", post(create_appliance_handler).get(list_appliances_handler))
@@ -871,21 +900,128 @@ async fn revoke_peer_handler(
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
     }
     
-    match state.mesh_provider.revoke_peer(peer_id).await {
-        Ok(()) => (StatusCode::OK, Json(serde_json::json!({"status": "revoked"}))).into_response(),
+    if let Err(e) = state.mesh_provider.revoke_peer(peer_id).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response();
+    }
+    
+    // The gateway must drop the revoked key right away
+    let remaining = state.mesh_provider.list_peers(user.id).await;
+    let gateway_config = state.mesh_provider.render_gateway_config(user.id);
+    match (remaining, gateway_config) {
+        (Ok(peers), Ok(gateway_config)) => {
+            let remaining_peers: Vec<MeshPeer> = peers.into_iter().filter(|p| !p.revoked).collect();
+            (StatusCode::OK, Json(serde_json::json!({
+                "status": "revoked",
+                "remaining_peers": remaining_peers,
+                "gateway_config": gateway_config,
+            }))).into_response()
+        }
+        (Err(e), _) | (_, Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+async fn rotate_peer_handler(
+    State(state): State<Arc<MeshnetState>>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let user = match get_current_user(&state, &headers) {
+        Ok(u) => u,
+        Err(status) => return (status, Json(serde_json::json!({"error": "Unauthorized"}))).into_response(),
+    };
+    
+    let peer_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid peer ID"}))).into_response(),
+    };
+    
+    match state.mesh_provider.get_peer(peer_id).await {
+        Ok(Some(p)) if p.user_id == user.id => {}
+        Ok(Some(_)) => return (StatusCode::FORBIDDEN, Json(serde_json::json!({"error": "Access denied"}))).into_response(),
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Peer not found"}))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+    
+    match state.mesh_provider.rotate_peer_key(peer_id).await {
+        Ok(key) => (StatusCode::OK, Json(key)).into_response(),
+        Err(e) => (StatusCode::CONFLICT, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+async fn peer_keys_handler(
+    State(state): State<Arc<MeshnetState>>,
+    headers: axum::http::HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let user = match get_current_user(&state, &headers) {
+        Ok(u) => u,
+        Err(status) => return (status, Json(serde_json::json!({"error": "Unauthorized"}))).into_response(),
+    };
+    
+    let peer_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid peer ID"}))).into_response(),
+    };
+    
+    match state.mesh_provider.get_peer(peer_id).await {
+        Ok(Some(p)) if p.user_id == user.id => {}
+        Ok(Some(_)) => return (StatusCode::FORBIDDEN, Json(serde_json::json!({"error": "Access denied"}))).into_response(),
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Peer not found"}))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+    
+    match state.db.get_key_generations(peer_id) {
+        Ok(keys) => (StatusCode::OK, Json(serde_json::json!({"keys": keys}))).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
     }
 }
 
+/// Rotate the keys of all of the user's active peers now
 async fn rotate_keys_handler(
-    State(_state): State<Arc<MeshnetState>>,
+    State(state): State<Arc<MeshnetState>>,
     headers: axum::http::HeaderMap,
 ) -> impl IntoResponse {
-    // Stub: key rotation would regenerate gateway keys
+    let user = match get_current_user(&state, &headers) {
+        Ok(u) => u,
+        Err(status) => return (status, Json(serde_json::json!({"error": "Unauthorized"}))).into_response(),
+    };
+    
+    let peers = match state.mesh_provider.list_peers(user.id).await {
+        Ok(peers) => peers,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
+    };
+    
+    let mut rotated = Vec::new();
+    for peer in peers.iter().filter(|p| !p.revoked) {
+        match state.mesh_provider.rotate_peer_key(peer.id).await {
+            Ok(key) => rotated.push(key),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "error": format!("Failed to rotate key of peer {}: {}", peer.name, e),
+                "rotated": rotated,
+            }))).into_response(),
+        }
+    }
+    
+    let policy = state.mesh_provider.rotation_policy();
     (StatusCode::OK, Json(serde_json::json!({
-        "status": "ok",
-        "note": "Key rotation is a stub in MVP"
-    })))
+        "rotated": rotated,
+        "overlap_secs": policy.overlap_secs,
+    }))).into_response()
+}
+
+async fn gateway_config_handler(
+    State(state): State<Arc<MeshnetState>>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    let user = match get_current_user(&state, &headers) {
+        Ok(u) => u,
+        Err(status) => return (status, Json(serde_json::json!({"error": "Unauthorized"}))).into_response(),
+    };
+    
+    match state.mesh_provider.render_gateway_config(user.id) {
+        Ok(config) => (StatusCode::OK, [(header::CONTENT_TYPE, "text/plain")], config).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
+    }
 }
 
 // ============================================================================
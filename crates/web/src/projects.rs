@@ -0,0 +1,336 @@
+//! Projects and their prompts, persisted in the shared state database.
+//!
+//! The tables are versioned by [`MIGRATIONS`] in the `schema_version` table.
+//! Deleting a project deletes its prompts with it.
+
+use infrasim_common::{Database, Migration, MigrationUp, Migrations, Result};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Schema migrations, applied in order; only ever append
+const MIGRATIONS: Migrations = Migrations {
+    component: "web.projects",
+    migrations: &[Migration {
+        version: 1,
+        name: "initial",
+        up: MigrationUp::Sql(r#"
+            CREATE TABLE IF NOT EXISTS projects (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS project_prompts (
+                id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                body TEXT NOT NULL,
+                llm_provider TEXT,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_project_prompts_project ON project_prompts(project_id, created_at);
+        "#),
+        down: None,
+    }],
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+/// Largest page of prompts returned at once
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub prompt_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prompt {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    pub body: String,
+    pub created_at: i64,
+    pub llm_provider: Option<String>,
+}
+
+/// A project with all its prompts, as attested
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectExport {
+    #[serde(flatten)]
+    pub project: Project,
+    pub prompts: Vec<Prompt>,
+}
+
+/// Search and paging of prompts
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PromptQuery {
+    /// Case-insensitive text matched against titles and bodies
+    pub q: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptPage {
+    pub prompts: Vec<Prompt>,
+    /// Matching prompts across all pages
+    pub total: i64,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// Projects and prompts in the state database
+#[derive(Clone)]
+pub struct ProjectStore {
+    db: Database,
+}
+
+impl ProjectStore {
+    /// Open the store, migrating its tables to the current schema
+    pub fn new(db: Database) -> Result<Self> {
+        let store = Self { db };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn migrate(&self) -> Result<()> {
+        self.db.migrate(&MIGRATIONS)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Result<Vec<Project>> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let mut stmt = conn.prepare(&format!("{} ORDER BY p.created_at, p.id", PROJECT_SELECT))?;
+        let projects = stmt.query_map([], project_from_row)?.collect::<rusqlite::Result<_>>()?;
+        Ok(projects)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Project>> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        Ok(conn
+            .query_row(&format!("{} WHERE p.id = ?1", PROJECT_SELECT), params![id], project_from_row)
+            .optional()?)
+    }
+
+    pub fn create(&self, name: &str) -> Result<Project> {
+        let now = chrono::Utc::now().timestamp();
+        let project = Project {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            created_at: now,
+            updated_at: now,
+            prompt_count: 0,
+        };
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        conn.execute(
+            "INSERT INTO projects (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            params![project.id, project.name, project.created_at, project.updated_at],
+        )?;
+        Ok(project)
+    }
+
+    /// Rename a project, `None` if it doesn't exist
+    pub fn rename(&self, id: &str, name: &str) -> Result<Option<Project>> {
+        let renamed = {
+            let conn_arc = self.db.connection();
+            let conn = conn_arc.lock();
+            conn.execute(
+                "UPDATE projects SET name = ?1, updated_at = ?2 WHERE id = ?3",
+                params![name, chrono::Utc::now().timestamp(), id],
+            )?
+        };
+        if renamed == 0 {
+            return Ok(None);
+        }
+        self.get(id)
+    }
+
+    /// Delete a project and its prompts, returning how many prompts went with it
+    pub fn delete(&self, id: &str) -> Result<Option<usize>> {
+        let conn_arc = self.db.connection();
+        let mut conn = conn_arc.lock();
+        let tx = conn.transaction()?;
+        let prompts = tx.execute("DELETE FROM project_prompts WHERE project_id = ?1", params![id])?;
+        let deleted = tx.execute("DELETE FROM projects WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok((deleted > 0).then_some(prompts))
+    }
+
+    /// Add a prompt, `None` if the project doesn't exist
+    pub fn add_prompt(
+        &self,
+        project_id: &str,
+        title: &str,
+        body: &str,
+        llm_provider: Option<String>,
+    ) -> Result<Option<Prompt>> {
+        let prompt = Prompt {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: project_id.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+            llm_provider,
+        };
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let inserted = conn.execute(
+            "INSERT INTO project_prompts (id, project_id, title, body, llm_provider, created_at) \
+             SELECT ?1, id, ?3, ?4, ?5, ?6 FROM projects WHERE id = ?2",
+            params![prompt.id, prompt.project_id, prompt.title, prompt.body, prompt.llm_provider, prompt.created_at],
+        )?;
+        if inserted == 0 {
+            return Ok(None);
+        }
+        let _ = conn.execute(
+            "UPDATE projects SET updated_at = ?1 WHERE id = ?2",
+            params![prompt.created_at, project_id],
+        );
+        Ok(Some(prompt))
+    }
+
+    /// A page of prompts, oldest first, from one project or all of them
+    pub fn prompts(&self, project_id: Option<&str>, query: &PromptQuery) -> Result<PromptPage> {
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let offset = query.offset.unwrap_or(0);
+        let pattern = query
+            .q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(|q| format!("%{}%", escape_like(q)));
+
+        let filter = "(?1 IS NULL OR project_id = ?1) \
+                      AND (?2 IS NULL OR title LIKE ?2 ESCAPE '\\' OR body LIKE ?2 ESCAPE '\\')";
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let total = conn.query_row(
+            &format!("SELECT COUNT(*) FROM project_prompts WHERE {}", filter),
+            params![project_id, pattern],
+            |r| r.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, project_id, title, body, created_at, llm_provider FROM project_prompts \
+             WHERE {} ORDER BY created_at, id LIMIT ?3 OFFSET ?4",
+            filter
+        ))?;
+        let prompts = stmt
+            .query_map(params![project_id, pattern, limit, offset], prompt_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(PromptPage { prompts, total, limit, offset })
+    }
+
+    /// A project with every prompt, `None` if it doesn't exist
+    pub fn export(&self, id: &str) -> Result<Option<ProjectExport>> {
+        let Some(project) = self.get(id)? else {
+            return Ok(None);
+        };
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, title, body, created_at, llm_provider FROM project_prompts \
+             WHERE project_id = ?1 ORDER BY created_at, id",
+        )?;
+        let prompts = stmt.query_map(params![id], prompt_from_row)?.collect::<rusqlite::Result<_>>()?;
+        Ok(Some(ProjectExport { project, prompts }))
+    }
+}
+
+const PROJECT_SELECT: &str = "SELECT p.id, p.name, p.created_at, p.updated_at, \
+     (SELECT COUNT(*) FROM project_prompts pp WHERE pp.project_id = p.id) FROM projects p";
+
+fn project_from_row(r: &rusqlite::Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: r.get(0)?,
+        name: r.get(1)?,
+        created_at: r.get(2)?,
+        updated_at: r.get(3)?,
+        prompt_count: r.get(4)?,
+    })
+}
+
+fn prompt_from_row(r: &rusqlite::Row) -> rusqlite::Result<Prompt> {
+    Ok(Prompt {
+        id: r.get(0)?,
+        project_id: r.get(1)?,
+        title: r.get(2)?,
+        body: r.get(3)?,
+        created_at: r.get(4)?,
+        llm_provider: r.get(5)?,
+    })
+}
+
+/// Escape `LIKE` wildcards so search text matches literally
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> ProjectStore {
+        ProjectStore::new(Database::open_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_migrations_apply_once() {
+        let db = Database::open_memory().unwrap();
+        let store = ProjectStore::new(db.clone()).unwrap();
+        store.create("lab").unwrap();
+
+        let reopened = ProjectStore::new(db.clone()).unwrap();
+        assert_eq!(reopened.list().unwrap().len(), 1);
+        assert_eq!(db.schema_version(MIGRATIONS.component).unwrap(), MIGRATIONS.latest());
+    }
+
+    #[test]
+    fn test_prompt_search_and_paging() {
+        let store = store();
+        let lab = store.create("lab").unwrap();
+        let edge = store.create("edge").unwrap();
+        for i in 0..5 {
+            store.add_prompt(&lab.id, &format!("keycloak {}", i), "realm setup", None).unwrap().unwrap();
+        }
+        store.add_prompt(&edge.id, "nginx", "100% of traffic through KEYCLOAK", None).unwrap().unwrap();
+        assert!(store.add_prompt("missing", "t", "b", None).unwrap().is_none());
+
+        let page = store
+            .prompts(Some(&lab.id), &PromptQuery { q: None, limit: Some(2), offset: Some(4) })
+            .unwrap();
+        assert_eq!((page.total, page.prompts.len()), (5, 1));
+
+        let all = store.prompts(None, &PromptQuery { q: Some("Keycloak".into()), ..Default::default() }).unwrap();
+        assert_eq!(all.total, 6);
+        let literal = store.prompts(None, &PromptQuery { q: Some("100%".into()), ..Default::default() }).unwrap();
+        assert_eq!(literal.total, 1);
+        let none = store.prompts(None, &PromptQuery { q: Some("1_0".into()), ..Default::default() }).unwrap();
+        assert_eq!(none.total, 0);
+    }
+
+    #[test]
+    fn test_rename_and_cascading_delete() {
+        let store = store();
+        let lab = store.create("lab").unwrap();
+        store.add_prompt(&lab.id, "one", "", None).unwrap();
+        store.add_prompt(&lab.id, "two", "", None).unwrap();
+
+        let renamed = store.rename(&lab.id, "homelab").unwrap().unwrap();
+        assert_eq!((renamed.name.as_str(), renamed.prompt_count), ("homelab", 2));
+        assert!(store.rename("missing", "x").unwrap().is_none());
+
+        assert_eq!(store.delete(&lab.id).unwrap(), Some(2));
+        assert_eq!(store.delete(&lab.id).unwrap(), None);
+        assert_eq!(store.prompts(None, &PromptQuery::default()).unwrap().total, 0);
+    }
+}
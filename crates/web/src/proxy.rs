@@ -0,0 +1,251 @@
+//! Client addresses and origins behind reverse proxies
+//!
+//! Behind nginx or Traefik every connection comes from the proxy, so the
+//! client's address is taken from `Forwarded` or `X-Forwarded-For`, but only
+//! when the connection comes from a proxy listed in
+//! `INFRASIM_WEB_TRUSTED_PROXIES`; anyone else could write those headers.
+//!
+//! Browsers send cookies and credentials with cross-site WebSocket
+//! handshakes, so upgrades from a page on another origin are refused unless
+//! the origin is listed in `INFRASIM_WEB_ALLOWED_ORIGINS`.
+
+use axum::http::{header, HeaderMap};
+use ipnetwork::IpNetwork;
+use std::net::IpAddr;
+use tracing::warn;
+
+/// How far the server trusts proxies and cross-origin pages
+#[derive(Clone, Debug, Default)]
+pub struct ProxyConfig {
+    /// Peers whose forwarding headers are believed
+    pub trusted_proxies: Vec<IpNetwork>,
+    /// Origins besides the console's own that may open WebSockets; `*`
+    /// allows any
+    pub allowed_origins: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Read from `INFRASIM_WEB_TRUSTED_PROXIES` (addresses or CIDRs) and
+    /// `INFRASIM_WEB_ALLOWED_ORIGINS`, both comma-separated
+    pub fn from_env() -> Self {
+        let list = |var: &str| -> Vec<String> {
+            std::env::var(var)
+                .unwrap_or_default()
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+        let trusted_proxies = list("INFRASIM_WEB_TRUSTED_PROXIES")
+            .iter()
+            .filter_map(|entry| match entry.parse() {
+                Ok(net) => Some(net),
+                Err(e) => {
+                    warn!("Ignoring trusted proxy '{}': {}", entry, e);
+                    None
+                }
+            })
+            .collect();
+        let allowed_origins = list("INFRASIM_WEB_ALLOWED_ORIGINS")
+            .into_iter()
+            .map(|origin| origin.trim_end_matches('/').to_ascii_lowercase())
+            .collect();
+        Self {
+            trusted_proxies,
+            allowed_origins,
+        }
+    }
+
+    fn trusts(&self, addr: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(addr))
+    }
+
+    /// Address of the client a request came from
+    ///
+    /// Forwarded addresses are read right to left, each hop appended by the
+    /// proxy it passed through; the first one that isn't a trusted proxy is
+    /// the client.
+    pub fn client_ip(&self, headers: &HeaderMap, peer: IpAddr) -> IpAddr {
+        if !self.trusts(peer) {
+            return peer;
+        }
+        let hops = forwarded_for(headers);
+        let mut client = peer;
+        for hop in hops.iter().rev() {
+            client = *hop;
+            if !self.trusts(*hop) {
+                break;
+            }
+        }
+        client
+    }
+
+    /// `Host` the client asked for, as forwarded by a trusted proxy
+    fn request_host(&self, headers: &HeaderMap, peer: IpAddr) -> Option<String> {
+        if self.trusts(peer) {
+            let forwarded = forwarded_param(headers, "host").or_else(|| {
+                header_str(headers, "x-forwarded-host")
+                    .and_then(|v| v.split(',').next())
+                    .map(|v| v.trim().to_string())
+            });
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        header_str(headers, header::HOST.as_str()).map(str::to_string)
+    }
+
+    /// Whether a WebSocket handshake's `Origin` may connect
+    ///
+    /// Clients that aren't browsers send no `Origin` and are allowed. Pages on
+    /// the console's own host are, and so are loopback pages while the
+    /// console is reached over loopback, which covers the UI dev server.
+    pub fn origin_allowed(&self, headers: &HeaderMap, peer: IpAddr) -> bool {
+        let Some(origin) = header_str(headers, header::ORIGIN.as_str()) else {
+            return true;
+        };
+        let origin = origin.trim_end_matches('/').to_ascii_lowercase();
+        if self.allowed_origins.iter().any(|allowed| allowed == "*" || *allowed == origin) {
+            return true;
+        }
+
+        let Some(origin_host) = origin.split_once("://").map(|(_, host)| host) else {
+            return false;
+        };
+        let Some(host) = self.request_host(headers, peer) else {
+            return false;
+        };
+        origin_host.eq_ignore_ascii_case(&host) || (is_loopback_host(origin_host) && is_loopback_host(&host))
+    }
+}
+
+/// Whether a request asks to become a WebSocket
+pub fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    header_str(headers, header::UPGRADE.as_str()).is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Client addresses from `Forwarded` (RFC 7239), else `X-Forwarded-For`,
+/// in the order proxies added them
+fn forwarded_for(headers: &HeaderMap) -> Vec<IpAddr> {
+    let forwarded: Vec<IpAddr> = headers
+        .get_all(header::FORWARDED)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|element| element_param(element, "for"))
+        .filter_map(|node| parse_node(&node))
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|node| parse_node(node.trim()))
+        .collect()
+}
+
+/// A parameter of the first `Forwarded` element
+fn forwarded_param(headers: &HeaderMap, name: &str) -> Option<String> {
+    let first = header_str(headers, header::FORWARDED.as_str())?.split(',').next()?;
+    element_param(first, name)
+}
+
+fn element_param(element: &str, name: &str) -> Option<String> {
+    element
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim_matches('"').to_string())
+}
+
+/// An address from a forwarding header, with any port stripped
+/// (`198.51.100.7:4711`, `[2001:db8::1]:4711`)
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(addr) = node.parse() {
+        return Some(addr);
+    }
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    node.rsplit_once(':')?.0.parse().ok()
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        None => host.rsplit_once(':').map(|(name, _)| name).unwrap_or(host),
+    };
+    name.eq_ignore_ascii_case("localhost") || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn config() -> ProxyConfig {
+        ProxyConfig {
+            trusted_proxies: vec!["10.0.0.0/8".parse().unwrap()],
+            allowed_origins: vec!["https://ops.example".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_client_ip_only_from_trusted_proxies() {
+        let cfg = config();
+        let proxy: IpAddr = "10.0.0.5".parse().unwrap();
+        let stranger: IpAddr = "203.0.113.9".parse().unwrap();
+
+        let xff = headers(&[("x-forwarded-for", "198.51.100.1, 192.0.2.7, 10.0.0.9")]);
+        assert_eq!(cfg.client_ip(&xff, proxy), "192.0.2.7".parse::<IpAddr>().unwrap());
+        // A client can't name its own address
+        assert_eq!(cfg.client_ip(&xff, stranger), stranger);
+
+        let forwarded = headers(&[("forwarded", r#"for="[2001:db8::1]:4711";proto=https, for=10.0.0.9"#)]);
+        assert_eq!(cfg.client_ip(&forwarded, proxy), "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(cfg.client_ip(&headers(&[]), proxy), proxy);
+    }
+
+    #[test]
+    fn test_websocket_origins() {
+        let cfg = config();
+        let proxy: IpAddr = "10.0.0.5".parse().unwrap();
+        let lan: IpAddr = "192.168.1.20".parse().unwrap();
+        let loopback: IpAddr = "127.0.0.1".parse().unwrap();
+
+        assert!(cfg.origin_allowed(&headers(&[("host", "console.lan:8080")]), lan));
+        assert!(cfg.origin_allowed(&headers(&[("host", "console.lan:8080"), ("origin", "http://console.lan:8080")]), lan));
+        assert!(!cfg.origin_allowed(&headers(&[("host", "console.lan:8080"), ("origin", "https://evil.example")]), lan));
+        assert!(cfg.origin_allowed(&headers(&[("host", "console.lan:8080"), ("origin", "https://ops.example/")]), lan));
+
+        // The proxy forwards the host the browser used
+        let behind = headers(&[
+            ("host", "127.0.0.1:8080"),
+            ("x-forwarded-host", "console.example.com"),
+            ("origin", "https://console.example.com"),
+        ]);
+        assert!(cfg.origin_allowed(&behind, proxy));
+        assert!(!cfg.origin_allowed(&behind, lan));
+
+        // UI dev server proxying to a loopback console
+        let dev = headers(&[("host", "127.0.0.1:8080"), ("origin", "http://localhost:4173")]);
+        assert!(cfg.origin_allowed(&dev, loopback));
+        let lan_dev = headers(&[("host", "console.lan:8080"), ("origin", "http://localhost:4173")]);
+        assert!(!cfg.origin_allowed(&lan_dev, lan));
+    }
+}
@@ -0,0 +1,570 @@
+//! Console session recording and playback.
+//!
+//! VNC and serial console sessions of VMs with recording enabled are written
+//! to the recordings directory as JSON lines: a header, then one frame per
+//! chunk of traffic with its offset from the start of the session. VNC frames
+//! are the raw RFB stream in both directions, so playback feeds the recorded
+//! server output to an ordinary noVNC or xterm.js client; input is kept for
+//! auditing but never replayed.
+//!
+//! Recordings are indexed in the state database. Each one expires after its
+//! VM's retention period and is pruned then, unless it was marked to keep.
+
+use axum::extract::ws::{Message, WebSocket};
+use base64::Engine;
+use infrasim_common::{Database, Migration, MigrationUp, Migrations, Result};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Schema migrations, applied in order; only ever append
+const MIGRATIONS: Migrations = Migrations {
+    component: "web.recordings",
+    migrations: &[Migration {
+        version: 1,
+        name: "initial",
+        up: MigrationUp::Sql(r#"
+            CREATE TABLE IF NOT EXISTS console_recordings (
+                id TEXT PRIMARY KEY,
+                vm_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER,
+                size_bytes INTEGER NOT NULL DEFAULT 0,
+                expires_at INTEGER,
+                keep INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS idx_console_recordings_vm ON console_recordings(vm_id, started_at);
+
+            CREATE TABLE IF NOT EXISTS console_recording_policies (
+                vm_id TEXT PRIMARY KEY,
+                enabled INTEGER NOT NULL,
+                retention_days INTEGER NOT NULL
+            );
+        "#),
+        down: None,
+    }],
+};
+
+/// Version written to the header of each recording
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Longest pause kept during playback; idle stretches are cut to this
+const MAX_REPLAY_GAP: Duration = Duration::from_secs(3);
+
+/// Time between sweeps for expired recordings
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Chunks buffered between a console session and its recording file
+const RECORDER_QUEUE: usize = 256;
+
+const RECORDING_SELECT: &str = "SELECT id, vm_id, kind, subject, started_at, ended_at, size_bytes, expires_at, keep \
+     FROM console_recordings";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingKind {
+    Vnc,
+    Serial,
+}
+
+impl RecordingKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Vnc => "vnc",
+            Self::Serial => "serial",
+        }
+    }
+}
+
+/// Which way a frame went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    /// From the VM to the viewer
+    #[serde(rename = "o")]
+    Output,
+    /// From the viewer to the VM
+    #[serde(rename = "i")]
+    Input,
+}
+
+/// First line of a recording file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Header {
+    pub version: u32,
+    pub id: String,
+    pub vm_id: String,
+    pub kind: RecordingKind,
+    pub started_at: i64,
+}
+
+/// One chunk of console traffic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Frame {
+    /// Milliseconds since the session started
+    pub t: u64,
+    pub d: Direction,
+    /// Base64 of the bytes
+    pub b: String,
+}
+
+/// A recorded console session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub id: String,
+    pub vm_id: String,
+    pub kind: RecordingKind,
+    /// Who opened the console
+    pub subject: String,
+    pub started_at: i64,
+    /// Unset while the session is still open
+    pub ended_at: Option<i64>,
+    pub size_bytes: i64,
+    /// When pruning deletes it, unless kept; unset keeps it forever
+    pub expires_at: Option<i64>,
+    /// Exempt from pruning, e.g. as incident evidence
+    pub keep: bool,
+}
+
+/// Whether and for how long a VM's console sessions are recorded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingPolicy {
+    #[serde(default)]
+    pub vm_id: String,
+    pub enabled: bool,
+    /// Days a recording is kept; 0 keeps it until deleted
+    pub retention_days: u32,
+}
+
+/// Where recordings go and what's recorded by default
+#[derive(Debug, Clone)]
+pub struct RecordingConfig {
+    pub dir: PathBuf,
+    /// Record every VM without a policy of its own
+    pub record_all: bool,
+    /// Retention of VMs without a policy of their own
+    pub retention_days: u32,
+}
+
+impl RecordingConfig {
+    /// `INFRASIM_RECORDING_DIR`, `INFRASIM_RECORD_CONSOLES` and
+    /// `INFRASIM_RECORDING_RETENTION_DAYS`
+    pub fn from_env() -> Self {
+        let dir = std::env::var("INFRASIM_RECORDING_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                dirs::home_dir()
+                    .unwrap_or_else(|| PathBuf::from("/tmp"))
+                    .join(".infrasim/recordings")
+            });
+        let record_all = std::env::var("INFRASIM_RECORD_CONSOLES")
+            .map(|v| matches!(v.as_str(), "1" | "true" | "all"))
+            .unwrap_or(false);
+        let retention_days = std::env::var("INFRASIM_RECORDING_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        Self { dir, record_all, retention_days }
+    }
+}
+
+/// Recording policies and the index of recordings, in the state database
+#[derive(Clone)]
+pub struct RecordingStore {
+    db: Database,
+    config: RecordingConfig,
+}
+
+impl RecordingStore {
+    /// Open the store, migrating its tables to the current schema
+    pub fn new(db: Database, config: RecordingConfig) -> Result<Self> {
+        let store = Self { db, config };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn migrate(&self) -> Result<()> {
+        self.db.migrate(&MIGRATIONS)?;
+        Ok(())
+    }
+
+    /// File holding a recording's frames
+    pub fn path(&self, id: &str) -> PathBuf {
+        self.config.dir.join(format!("{}.jsonl", id))
+    }
+
+    /// A VM's policy, or the configured default
+    pub fn policy(&self, vm_id: &str) -> Result<RecordingPolicy> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let policy = conn
+            .query_row(
+                "SELECT enabled, retention_days FROM console_recording_policies WHERE vm_id = ?1",
+                params![vm_id],
+                |r| {
+                    Ok(RecordingPolicy {
+                        vm_id: vm_id.to_string(),
+                        enabled: r.get(0)?,
+                        retention_days: r.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(policy.unwrap_or_else(|| RecordingPolicy {
+            vm_id: vm_id.to_string(),
+            enabled: self.config.record_all,
+            retention_days: self.config.retention_days,
+        }))
+    }
+
+    pub fn set_policy(&self, policy: &RecordingPolicy) -> Result<()> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        conn.execute(
+            "INSERT OR REPLACE INTO console_recording_policies (vm_id, enabled, retention_days) VALUES (?1, ?2, ?3)",
+            params![policy.vm_id, policy.enabled, policy.retention_days],
+        )?;
+        Ok(())
+    }
+
+    /// Recordings, newest first, of one VM or of all of them
+    pub fn list(&self, vm_id: Option<&str>) -> Result<Vec<Recording>> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        let recordings = match vm_id {
+            Some(vm_id) => conn
+                .prepare(&format!("{} WHERE vm_id = ?1 ORDER BY started_at DESC, id", RECORDING_SELECT))?
+                .query_map(params![vm_id], recording_from_row)?
+                .collect::<rusqlite::Result<_>>()?,
+            None => conn
+                .prepare(&format!("{} ORDER BY started_at DESC, id", RECORDING_SELECT))?
+                .query_map([], recording_from_row)?
+                .collect::<rusqlite::Result<_>>()?,
+        };
+        Ok(recordings)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Recording>> {
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        Ok(conn
+            .query_row(&format!("{} WHERE id = ?1", RECORDING_SELECT), params![id], recording_from_row)
+            .optional()?)
+    }
+
+    /// Change when a recording expires or whether it's kept
+    pub fn set_retention(&self, id: &str, keep: Option<bool>, expires_at: Option<i64>) -> Result<Option<Recording>> {
+        {
+            let conn_arc = self.db.connection();
+            let conn = conn_arc.lock();
+            if let Some(keep) = keep {
+                conn.execute("UPDATE console_recordings SET keep = ?2 WHERE id = ?1", params![id, keep])?;
+            }
+            if let Some(expires_at) = expires_at {
+                conn.execute(
+                    "UPDATE console_recordings SET expires_at = ?2 WHERE id = ?1",
+                    params![id, expires_at],
+                )?;
+            }
+        }
+        self.get(id)
+    }
+
+    /// Delete a recording and its file; false if there was none
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let deleted = {
+            let conn_arc = self.db.connection();
+            let conn = conn_arc.lock();
+            conn.execute("DELETE FROM console_recordings WHERE id = ?1", params![id])?
+        };
+        if deleted > 0 {
+            remove_file(&self.path(id));
+        }
+        Ok(deleted > 0)
+    }
+
+    /// Delete finished recordings that expired by `now` and aren't kept
+    pub fn prune(&self, now: i64) -> Result<usize> {
+        let expired: Vec<String> = {
+            let conn_arc = self.db.connection();
+            let conn = conn_arc.lock();
+            let mut stmt = conn.prepare(
+                "SELECT id FROM console_recordings \
+                 WHERE keep = 0 AND ended_at IS NOT NULL AND expires_at IS NOT NULL AND expires_at <= ?1",
+            )?;
+            let ids = stmt.query_map(params![now], |r| r.get(0))?.collect::<rusqlite::Result<_>>()?;
+            ids
+        };
+        for id in &expired {
+            self.delete(id)?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Prune expired recordings now and then every [`PRUNE_INTERVAL`]
+    pub fn spawn_pruning(&self) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match store.prune(chrono::Utc::now().timestamp()) {
+                    Ok(0) => {}
+                    Ok(n) => info!("Pruned {} expired console recordings", n),
+                    Err(e) => warn!("Pruning console recordings failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Start recording a console session if the VM's policy asks for it
+    pub async fn start(&self, vm_id: &str, kind: RecordingKind, subject: &str) -> Result<Option<Recorder>> {
+        let policy = self.policy(vm_id)?;
+        if !policy.enabled {
+            return Ok(None);
+        }
+
+        let started_at = chrono::Utc::now().timestamp();
+        let header = Header {
+            version: FORMAT_VERSION,
+            id: uuid::Uuid::new_v4().to_string(),
+            vm_id: vm_id.to_string(),
+            kind,
+            started_at,
+        };
+        tokio::fs::create_dir_all(&self.config.dir).await?;
+        let path = self.path(&header.id);
+        let mut file = BufWriter::new(tokio::fs::File::create(&path).await?);
+        file.write_all(format!("{}\n", serde_json::to_string(&header)?).as_bytes()).await?;
+
+        let expires_at = (policy.retention_days > 0).then(|| started_at + i64::from(policy.retention_days) * 86_400);
+        {
+            let conn_arc = self.db.connection();
+            let conn = conn_arc.lock();
+            conn.execute(
+                "INSERT INTO console_recordings (id, vm_id, kind, subject, started_at, expires_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![header.id, vm_id, kind.as_str(), subject, started_at, expires_at],
+            )?;
+        }
+        info!("Recording {} console of VM {} as {}", kind.as_str(), vm_id, header.id);
+
+        let (tx, rx) = mpsc::channel(RECORDER_QUEUE);
+        tokio::spawn(self.clone().write_frames(header.id, file, rx));
+        Ok(Some(Recorder { tx, started: Instant::now() }))
+    }
+
+    /// Append frames until every [`Recorder`] of the session is dropped
+    async fn write_frames(
+        self,
+        id: String,
+        mut file: BufWriter<tokio::fs::File>,
+        mut rx: mpsc::Receiver<(u64, Direction, Vec<u8>)>,
+    ) {
+        let mut failed = false;
+        while let Some((t, d, data)) = rx.recv().await {
+            if failed {
+                continue;
+            }
+            let frame = Frame { t, d, b: base64::engine::general_purpose::STANDARD.encode(data) };
+            let line = serde_json::to_string(&frame).unwrap_or_default() + "\n";
+            if let Err(e) = file.write_all(line.as_bytes()).await {
+                // Keep the console working; the recording ends here
+                warn!("Writing console recording {} failed: {}", id, e);
+                failed = true;
+            }
+        }
+        if let Err(e) = file.flush().await {
+            warn!("Writing console recording {} failed: {}", id, e);
+        }
+
+        let size_bytes = tokio::fs::metadata(self.path(&id)).await.map_or(0, |m| m.len() as i64);
+        let conn_arc = self.db.connection();
+        let conn = conn_arc.lock();
+        if let Err(e) = conn.execute(
+            "UPDATE console_recordings SET ended_at = ?2, size_bytes = ?3 WHERE id = ?1",
+            params![id, chrono::Utc::now().timestamp(), size_bytes],
+        ) {
+            warn!("Finishing console recording {} failed: {}", id, e);
+        }
+        debug!("Console recording {} finished, {} bytes", id, size_bytes);
+    }
+}
+
+fn recording_from_row(r: &rusqlite::Row<'_>) -> rusqlite::Result<Recording> {
+    let kind: String = r.get(2)?;
+    Ok(Recording {
+        id: r.get(0)?,
+        vm_id: r.get(1)?,
+        kind: if kind == "serial" { RecordingKind::Serial } else { RecordingKind::Vnc },
+        subject: r.get(3)?,
+        started_at: r.get(4)?,
+        ended_at: r.get(5)?,
+        size_bytes: r.get(6)?,
+        expires_at: r.get(7)?,
+        keep: r.get(8)?,
+    })
+}
+
+fn remove_file(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Removing {} failed: {}", path.display(), e);
+        }
+    }
+}
+
+/// Handle to an open recording, shared by both directions of a session
+#[derive(Clone)]
+pub struct Recorder {
+    tx: mpsc::Sender<(u64, Direction, Vec<u8>)>,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Record bytes sent from the VM to the viewer
+    pub async fn output(&self, data: &[u8]) {
+        self.record(Direction::Output, data).await;
+    }
+
+    /// Record bytes sent from the viewer to the VM
+    pub async fn input(&self, data: &[u8]) {
+        self.record(Direction::Input, data).await;
+    }
+
+    async fn record(&self, d: Direction, data: &[u8]) {
+        let t = self.started.elapsed().as_millis() as u64;
+        // The writer only goes away with the runtime
+        let _ = self.tx.send((t, d, data.to_vec())).await;
+    }
+}
+
+/// Play a recording's output to a WebSocket at `speed` times real time
+///
+/// Whatever the viewer sends is read and dropped, so it can negotiate with
+/// the recorded server as it did live.
+pub async fn replay(mut socket: WebSocket, path: &Path, speed: f64) -> anyhow::Result<()> {
+    let mut lines = BufReader::new(tokio::fs::File::open(path).await?).lines();
+    let header: Header = serde_json::from_str(&lines.next_line().await?.unwrap_or_default())?;
+    debug!("Replaying console recording {} of VM {}", header.id, header.vm_id);
+
+    let mut last_t = 0;
+    while let Some(line) = lines.next_line().await? {
+        let frame: Frame = serde_json::from_str(&line)?;
+        if frame.d != Direction::Output {
+            continue;
+        }
+        let gap = Duration::from_millis(frame.t.saturating_sub(last_t)).min(MAX_REPLAY_GAP);
+        last_t = frame.t;
+
+        let sleep = tokio::time::sleep(gap.div_f64(speed));
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                msg = socket.recv() => match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+        let data = base64::engine::general_purpose::STANDARD.decode(frame.b)?;
+        socket.send(Message::Binary(data)).await?;
+    }
+
+    let _ = socket.send(Message::Close(None)).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(record_all: bool) -> RecordingStore {
+        let dir = std::env::temp_dir().join(format!("infrasim-recordings-{}", uuid::Uuid::new_v4()));
+        let config = RecordingConfig { dir, record_all, retention_days: 7 };
+        RecordingStore::new(Database::open_memory().unwrap(), config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_policy_decides_what_is_recorded() {
+        let store = store(false);
+        assert!(store.start("vm-1", RecordingKind::Vnc, "alice").await.unwrap().is_none());
+
+        let policy = RecordingPolicy { vm_id: "vm-1".to_string(), enabled: true, retention_days: 0 };
+        store.set_policy(&policy).unwrap();
+        assert_eq!(store.policy("vm-1").unwrap(), policy);
+        assert!(!store.policy("vm-2").unwrap().enabled);
+        assert!(store.start("vm-1", RecordingKind::Serial, "alice").await.unwrap().is_some());
+
+        let recordings = store.list(Some("vm-1")).unwrap();
+        assert_eq!(recordings.len(), 1);
+        assert_eq!(recordings[0].kind, RecordingKind::Serial);
+        assert_eq!(recordings[0].subject, "alice");
+        // Retention 0 never expires
+        assert_eq!(recordings[0].expires_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_frames_are_written_and_finished() {
+        let store = store(true);
+        let recorder = store.start("vm-1", RecordingKind::Serial, "bob").await.unwrap().unwrap();
+        recorder.output(b"login: ").await;
+        recorder.input(b"root\r").await;
+        drop(recorder);
+
+        // The writer finishes the row once the last recorder is dropped
+        let id = store.list(None).unwrap()[0].id.clone();
+        for _ in 0..100 {
+            if store.get(&id).unwrap().unwrap().ended_at.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let recording = store.get(&id).unwrap().unwrap();
+        assert!(recording.ended_at.is_some());
+        assert!(recording.size_bytes > 0);
+        assert_eq!(recording.expires_at, Some(recording.started_at + 7 * 86_400));
+
+        let text = std::fs::read_to_string(store.path(&id)).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        let header: Header = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header.vm_id, "vm-1");
+        let frames: Vec<Frame> = lines[1..].iter().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].d, Direction::Output);
+        assert_eq!(base64::engine::general_purpose::STANDARD.decode(&frames[1].b).unwrap(), b"root\r");
+    }
+
+    #[test]
+    fn test_prune_spares_kept_and_open_recordings() {
+        let store = store(true);
+        {
+            let conn_arc = store.db.connection();
+            let conn = conn_arc.lock();
+            conn.execute_batch(
+                "INSERT INTO console_recordings (id, vm_id, kind, subject, started_at, ended_at, expires_at, keep) VALUES \
+                 ('old', 'vm-1', 'vnc', 'a', 0, 10, 100, 0), \
+                 ('kept', 'vm-1', 'vnc', 'a', 0, 10, 100, 0), \
+                 ('open', 'vm-1', 'vnc', 'a', 0, NULL, 100, 0), \
+                 ('new', 'vm-1', 'vnc', 'a', 0, 10, 1000, 0);",
+            )
+            .unwrap();
+        }
+        std::fs::create_dir_all(&store.config.dir).unwrap();
+        std::fs::write(store.path("old"), "{}\n").unwrap();
+        assert!(store.set_retention("kept", Some(true), None).unwrap().unwrap().keep);
+        assert!(store.set_retention("missing", Some(true), None).unwrap().is_none());
+
+        assert_eq!(store.prune(500).unwrap(), 1);
+        assert!(!store.path("old").exists());
+        let left: Vec<String> = store.list(None).unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(left.len(), 3);
+        assert!(!left.contains(&"old".to_string()));
+    }
+}
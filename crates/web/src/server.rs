@@ -1,5 +1,21 @@
 //! Web server implementation
 
+use crate::catalog::{ApplianceTemplate, AppliancePort, BootStep, NetworkDef, TemplatePack, TemplateRegistry, ToolDef, VolumeDef};
+use crate::health::{self, CheckResult};
+use crate::graph::{
+    ApplyGraphRequest, GraphOp, GraphPlan, GraphValidationError, PlanGraphRequest, ResourceEdge, ResourceGraph,
+    ResourceNode, ValidateGraphRequest, APPLIANCE_NODE, ATTACHED_TO_EDGE, FILESYSTEM_NODE,
+};
+use crate::auth::oidc::{parse_role_mappings, OidcProvider};
+use crate::auth::OidcConfig;
+use crate::auth::cookies::{self, CookieSessionConfig};
+use crate::auth::webauthn::WebAuthnProvider;
+use crate::jwks::{JwksCache, JwksSource};
+use crate::projects::{ProjectStore, PromptQuery};
+use crate::recording::{self, RecordingConfig, RecordingKind, RecordingPolicy, RecordingStore};
+use crate::proxy::{is_websocket_upgrade, ProxyConfig};
+use crate::shutdown::Shutdown;
+use crate::tls::TlsConfig;
 use crate::static_files::StaticFiles;
 use crate::vnc_proxy::VncProxy;
 use axum::{
@@ -10,13 +26,13 @@ use axum::{
     },
     middleware,
     http::StatusCode,
-    response::{Html, IntoResponse, Response},
+    response::{Html, IntoResponse, Redirect, Response},
     routing::{get, post, put, delete},
-    Json, Router,
+    Extension, Json, Router,
 };
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
 use std::process;
 use std::sync::Arc;
@@ -27,6 +43,8 @@ use tower_http::trace::TraceLayer;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 
 #[derive(Clone, Debug)]
 struct LocalControl {
@@ -89,16 +107,18 @@ impl UiStatic {
     }
 }
 
-use infrasim_common::crypto::KeyPair;
-use infrasim_common::Signer;
-use infrasim_common::Database;
+use infrasim_common::audit::{AuditEntry, AuditFilter, AuditLog, DEFAULT_LIST_LIMIT, STATUS_OK};
+use infrasim_common::auth::{AuthChannel, ClientCredentials};
+use infrasim_common::secrets;
+use infrasim_common::db::add_column_if_missing;
+use infrasim_common::{Database, ErrorCode, ErrorDetail, Migration, MigrationUp, Migrations};
 use jsonwebtoken::{decode, Algorithm, DecodingKey, TokenData, Validation};
-use once_cell::sync::OnceCell;
 use data_encoding::BASE32_NOPAD;
 use qrcode::QrCode;
 use qrcode::render::svg;
 use totp_rs::{Algorithm as TotpAlgorithm, Secret, TOTP};
 use rusqlite::OptionalExtension;
+use webauthn_rs::prelude::{PublicKeyCredential, RegisterPublicKeyCredential};
 
 /// Web server state
 #[derive(Clone)]
@@ -119,19 +139,57 @@ struct WebServerState {
 
     cfg: WebServerConfig,
     daemon: DaemonProxy,
-    projects: RwLock<HashMap<String, Project>>,
+    projects: ProjectStore,
+
+    /// Console recording policies and recorded sessions
+    recordings: RecordingStore,
 
     appliances: RwLock<HashMap<String, ApplianceInstance>>,
 
+    /// Appliance templates from built-in, local and remote sources
+    templates: RwLock<TemplateRegistry>,
+
     /// Virtual filesystem registry for resource-centric management
     filesystems: RwLock<HashMap<String, Filesystem>>,
 
+    /// Held while a resource graph apply runs, so applies don't interleave
+    graph_apply: tokio::sync::Mutex<()>,
+
+    /// Appliance image builds, by build ID
+    builds: RwLock<HashMap<String, Arc<BuildProgress>>>,
+
+    /// Directory each build writes its image into
+    build_dir: PathBuf,
+
     db: Database,
 
+    /// Append-only log of mutating requests, shared with the daemon when
+    /// both use the same state database
+    audit: AuditLog,
+
     control: Option<LocalControl>,
 
     /// MDM mobileconfig manager
     mdm: crate::mdm::MdmManager,
+
+    /// Passkey registration and login, unless misconfigured
+    webauthn: Option<WebAuthnProvider>,
+
+    /// Single sign-on through an OIDC provider, when configured
+    oidc: Option<OidcProvider>,
+
+    /// JWT signing keys in JWT auth mode
+    jwks: Option<Arc<JwksCache>>,
+
+    /// Stops the server and drains console sessions on SIGTERM or restart
+    shutdown: Shutdown,
+
+    /// Proxies trusted to forward client addresses, and origins allowed to
+    /// open WebSockets
+    proxy: ProxyConfig,
+
+    /// Pending ACME HTTP-01 challenges
+    acme_challenges: crate::acme::Challenges,
 }
 
 // ============================================================================
@@ -141,6 +199,7 @@ struct WebServerState {
 const AUTH_SESSION_TTL_SECS: i64 = 60 * 60 * 12; // 12h
 const AUTH_MAX_FAILED_ATTEMPTS: i64 = 10;
 const AUTH_LOCKOUT_SECS: i64 = 5 * 60;
+const AUTH_RECOVERY_CODE_COUNT: usize = 10;
 
 fn now_epoch_secs() -> i64 {
     SystemTime::now()
@@ -149,42 +208,135 @@ fn now_epoch_secs() -> i64 {
         .as_secs() as i64
 }
 
+/// Migrations of the local auth tables; only ever append
+const AUTH_MIGRATIONS: Migrations = Migrations {
+    component: "web.auth",
+    migrations: &[
+        Migration {
+            version: 1,
+            name: "initial",
+            up: MigrationUp::Sql(r#"
+            CREATE TABLE IF NOT EXISTS auth_identities (
+                id TEXT PRIMARY KEY,
+                display_name TEXT NOT NULL,
+                role TEXT NOT NULL,
+                totp_secret_b32 TEXT,
+                totp_enabled INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                disabled INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_auth_identities_display_name ON auth_identities(display_name);
+
+            CREATE TABLE IF NOT EXISTS auth_recovery_codes (
+                identity_id TEXT NOT NULL,
+                code_hash TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                used_at INTEGER,
+                PRIMARY KEY(identity_id, code_hash),
+                FOREIGN KEY(identity_id) REFERENCES auth_identities(id)
+            );
+
+            CREATE TABLE IF NOT EXISTS auth_sessions (
+                token TEXT PRIMARY KEY,
+                identity_id TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                last_seen_at INTEGER NOT NULL,
+                token_id TEXT,
+                ip_address TEXT,
+                user_agent TEXT,
+                FOREIGN KEY(identity_id) REFERENCES auth_identities(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_auth_sessions_identity ON auth_sessions(identity_id);
+            CREATE INDEX IF NOT EXISTS idx_auth_sessions_expires ON auth_sessions(expires_at);
+
+            CREATE TABLE IF NOT EXISTS auth_attempts (
+                identity_id TEXT PRIMARY KEY,
+                failed_count INTEGER NOT NULL DEFAULT 0,
+                locked_until INTEGER NOT NULL DEFAULT 0,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS auth_audit_log (
+                id TEXT PRIMARY KEY,
+                timestamp INTEGER NOT NULL,
+                event_type TEXT NOT NULL,
+                identity_id TEXT,
+                identity_name TEXT,
+                ip_address TEXT,
+                user_agent TEXT,
+                success INTEGER NOT NULL,
+                details_json TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_timestamp ON auth_audit_log(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_audit_identity ON auth_audit_log(identity_id);
+
+            CREATE TABLE IF NOT EXISTS auth_preferences (
+                identity_id TEXT PRIMARY KEY,
+                preferences_json TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS auth_oidc_sessions (
+                session_token TEXT PRIMARY KEY,
+                identity_id TEXT NOT NULL,
+                refresh_token TEXT,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY(session_token) REFERENCES auth_sessions(token)
+            );
+            "#),
+            down: None,
+        },
+        Migration {
+            version: 2,
+            name: "session_ids",
+            up: MigrationUp::Code(add_session_ids),
+            down: Some(
+                "DROP INDEX IF EXISTS idx_auth_sessions_token_id; \
+                 ALTER TABLE auth_sessions DROP COLUMN token_id; \
+                 ALTER TABLE auth_sessions DROP COLUMN ip_address; \
+                 ALTER TABLE auth_sessions DROP COLUMN user_agent;",
+            ),
+        },
+        Migration {
+            version: 3,
+            name: "disabled_identities",
+            up: MigrationUp::Code(|conn| {
+                add_column_if_missing(conn, "auth_identities", "disabled", "INTEGER NOT NULL DEFAULT 0")
+            }),
+            down: Some("ALTER TABLE auth_identities DROP COLUMN disabled;"),
+        },
+    ],
+};
+
 fn init_auth_schema(db: &Database) {
     // Best-effort: if this fails we still want the server to boot.
     // The endpoints will surface errors.
-    let conn_arc = db.connection();
-    let conn = conn_arc.lock();
-    let _ = conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS auth_identities (
-            id TEXT PRIMARY KEY,
-            display_name TEXT NOT NULL,
-            role TEXT NOT NULL,
-            totp_secret_b32 TEXT,
-            totp_enabled INTEGER NOT NULL DEFAULT 0,
-            created_at INTEGER NOT NULL
-        );
-        CREATE UNIQUE INDEX IF NOT EXISTS idx_auth_identities_display_name ON auth_identities(display_name);
-
-        CREATE TABLE IF NOT EXISTS auth_sessions (
-            token TEXT PRIMARY KEY,
-            identity_id TEXT NOT NULL,
-            created_at INTEGER NOT NULL,
-            expires_at INTEGER NOT NULL,
-            last_seen_at INTEGER NOT NULL,
-            FOREIGN KEY(identity_id) REFERENCES auth_identities(id)
-        );
-        CREATE INDEX IF NOT EXISTS idx_auth_sessions_identity ON auth_sessions(identity_id);
-        CREATE INDEX IF NOT EXISTS idx_auth_sessions_expires ON auth_sessions(expires_at);
-
-        CREATE TABLE IF NOT EXISTS auth_attempts (
-            identity_id TEXT PRIMARY KEY,
-            failed_count INTEGER NOT NULL DEFAULT 0,
-            locked_until INTEGER NOT NULL DEFAULT 0,
-            updated_at INTEGER NOT NULL
-        );
-        "#,
-    );
+    if let Err(e) = db.migrate(&AUTH_MIGRATIONS) {
+        warn!("Failed to migrate auth tables: {}", e);
+    }
+}
+
+/// Sessions from before they carried an ID and client metadata
+fn add_session_ids(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    for column in ["token_id", "ip_address", "user_agent"] {
+        add_column_if_missing(conn, "auth_sessions", column, "TEXT")?;
+    }
+    let tokens: Vec<String> = conn
+        .prepare("SELECT token FROM auth_sessions WHERE token_id IS NULL")?
+        .query_map([], |r| r.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for token in tokens {
+        conn.execute(
+            "UPDATE auth_sessions SET token_id = ?1 WHERE token = ?2",
+            rusqlite::params![session_token_id(&token), token],
+        )?;
+    }
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_auth_sessions_token_id ON auth_sessions(token_id)",
+        [],
+    )?;
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -194,6 +346,9 @@ struct AuthIdentity {
     role: String,
     totp_enabled: bool,
     created_at: i64,
+    /// Disabled identities can't sign in
+    #[serde(default)]
+    disabled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -237,17 +392,49 @@ struct LoginTotpRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct LoginResponse {
+    /// Bearer token; left out with cookie sessions, where only the cookie holds it
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     token: String,
     expires_at: i64,
     identity: AuthIdentity,
+    /// Console settings saved by this identity
+    preferences: UserPreferences,
+}
+
+/// Begin passkey registration or login for an identity
+#[derive(Debug, Clone, Deserialize)]
+struct BeginWebauthnRequest {
+    display_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct FinishWebauthnRegistrationRequest {
+    challenge_id: String,
+    credential: RegisterPublicKeyCredential,
+    /// Label for the passkey, e.g. the authenticator's name
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct FinishWebauthnLoginRequest {
+    challenge_id: String,
+    credential: PublicKeyCredential,
 }
 
 #[derive(Clone, Debug)]
 pub struct WebServerConfig {
     /// InfraSim daemon address, e.g. http://127.0.0.1:50051
     pub daemon_addr: String,
+    /// Certificates and token presented to the daemon
+    pub daemon_credentials: ClientCredentials,
     /// Authentication policy for the Web UI.
     pub auth: WebUiAuth,
+    /// Keep browser login sessions in `HttpOnly` cookies, with CSRF tokens
+    /// on mutating requests, instead of returning bearer tokens to scripts
+    pub session_cookies: Option<CookieSessionConfig>,
+    /// Terminate TLS here rather than at a proxy in front
+    pub tls: Option<crate::tls::TlsConfig>,
 }
 
 #[derive(Clone, Debug)]
@@ -268,8 +455,8 @@ pub struct JwtAuthConfig {
     pub allowed_issuers: Vec<String>,
     /// Required audience.
     pub audience: String,
-    /// Path to a local JWKS file (JSON).
-    pub local_jwks_path: String,
+    /// Where signing keys come from.
+    pub jwks: JwksSource,
 }
 
 impl WebServerConfig {
@@ -291,118 +478,208 @@ struct JwtRegisteredClaims {
     nbf: Option<i64>,
     iat: Option<i64>,
     sub: Option<String>,
+    /// RBAC roles granted to the subject
+    #[serde(default)]
+    roles: Vec<String>,
 }
 
-static LOCAL_JWKS_CACHE: OnceCell<Jwks> = OnceCell::new();
+/// RBAC roles of the authenticated caller, attached by the auth middleware
+#[derive(Debug, Clone)]
+struct CallerRoles(Vec<String>);
 
-#[derive(Debug, Clone, Deserialize)]
-struct Jwks {
-    keys: Vec<Jwk>,
+impl CallerRoles {
+    fn admin() -> Self {
+        Self(vec!["admin".to_string()])
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct Jwk {
-    kty: String,
-    kid: Option<String>,
-    alg: Option<String>,
-    #[serde(rename = "use")]
-    use_: Option<String>,
+/// Subject of the authenticated caller, attached by the auth middleware
+#[derive(Debug, Clone)]
+struct CallerSubject(String);
 
-    // RSA
-    n: Option<String>,
-    e: Option<String>,
+/// Permission a 403 response was denied for, read back by the auth middleware
+/// to audit the denial
+#[derive(Debug, Clone)]
+struct DeniedPermission(String);
 
-    // EC
-    crv: Option<String>,
-    x: Option<String>,
-    y: Option<String>,
+/// 403 response for callers whose roles don't grant `permission`
+fn permission_denied(roles: Option<&CallerRoles>, permission: &str) -> Option<Response> {
+    let roles = roles.map(|r| r.0.as_slice()).unwrap_or_default();
+    if crate::auth::PolicyEngine::new().has_permission(roles, permission) {
+        return None;
+    }
+    let mut response = (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({
+            "error": "access_denied",
+            "required_permission": permission,
+            "user_roles": roles,
+        })),
+    )
+        .into_response();
+    response.extensions_mut().insert(DeniedPermission(permission.to_string()));
+    Some(response)
 }
 
-fn parse_allowed_issuers(s: &str) -> Vec<String> {
-    s.split(',')
-        .map(|v| v.trim().to_string())
-        .filter(|v| !v.is_empty())
-        .collect()
+/// Record a denied request in the auth audit log
+fn audit_access_denied(db: &Database, subject: &str, roles: &CallerRoles, req: &RequestLine, permission: &str) {
+    warn!(
+        "denied {} {} to {} (roles {:?}): needs {}",
+        req.method, req.path, subject, roles.0, permission
+    );
+    let details = serde_json::json!({
+        "method": req.method,
+        "path": req.path,
+        "required_permission": permission,
+        "roles": roles.0,
+    });
+
+    let conn_arc = db.connection();
+    let conn = conn_arc.lock();
+    let _ = conn.execute(
+        "INSERT INTO auth_audit_log (id, timestamp, event_type, identity_id, success, details_json) \
+         VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+        rusqlite::params![
+            Uuid::new_v4().to_string(),
+            now_epoch_secs(),
+            serde_json::to_string(&crate::auth::AuthEventType::AccessDenied).unwrap_or_default(),
+            subject,
+            details.to_string(),
+        ],
+    );
 }
 
-fn load_local_jwks(path: &str) -> anyhow::Result<Jwks> {
-    let bytes = std::fs::read(path)?;
-    Ok(serde_json::from_slice(&bytes)?)
+/// Method and path of a request, kept for auditing after it is handed on
+struct RequestLine {
+    method: String,
+    path: String,
+    source_ip: Option<String>,
 }
 
-fn audience_matches(aud: &Option<serde_json::Value>, required: &str) -> bool {
-    match aud {
-        None => false,
-        Some(serde_json::Value::String(s)) => s == required,
-        Some(serde_json::Value::Array(list)) => list.iter().any(|v| v.as_str() == Some(required)),
-        _ => false,
+/// Source of the web server's operation audit entries
+const AUDIT_SOURCE: &str = "web";
+
+/// Operation audit log; `INFRASIM_WEB_AUDIT_HASH_CHAIN=1` hash-chains its entries
+fn audit_log_from_env(db: &Database) -> AuditLog {
+    let log = AuditLog::new(db.clone());
+    if std::env::var("INFRASIM_WEB_AUDIT_HASH_CHAIN").ok().as_deref() == Some("1") {
+        log.with_hash_chain(None)
+    } else {
+        log
     }
 }
 
-fn decoding_key_for_jwk(jwk: &Jwk) -> anyhow::Result<DecodingKey> {
-    match jwk.kty.as_str() {
-        "RSA" => {
-            let n = jwk.n.as_ref().ok_or_else(|| anyhow::anyhow!("RSA jwk missing n"))?;
-            let e = jwk.e.as_ref().ok_or_else(|| anyhow::anyhow!("RSA jwk missing e"))?;
-            Ok(DecodingKey::from_rsa_components(n, e)?)
-        }
-        "EC" => {
-            let x = jwk.x.as_ref().ok_or_else(|| anyhow::anyhow!("EC jwk missing x"))?;
-            let y = jwk.y.as_ref().ok_or_else(|| anyhow::anyhow!("EC jwk missing y"))?;
-            Ok(DecodingKey::from_ec_components(x, y)?)
-        }
-        other => Err(anyhow::anyhow!("unsupported jwk kty: {other}")),
+/// Kind and ID of the resource an API path addresses, e.g. `vms` and a VM ID
+fn path_resource(path: &str) -> (Option<String>, Option<String>) {
+    match path.trim_matches('/').split('/').collect::<Vec<_>>().as_slice() {
+        ["api", kind, id, ..] => (Some(kind.to_string()), Some(id.to_string())),
+        ["api", kind] => (Some(kind.to_string()), None),
+        _ => (None, None),
     }
 }
 
-fn algorithm_for_jwk(jwk: &Jwk) -> anyhow::Result<Algorithm> {
-    // Prefer explicit alg if present, otherwise infer from kty.
-    if let Some(alg) = jwk.alg.as_deref() {
-        return match alg {
-            "RS256" => Ok(Algorithm::RS256),
-            "RS384" => Ok(Algorithm::RS384),
-            "RS512" => Ok(Algorithm::RS512),
-            "ES256" => Ok(Algorithm::ES256),
-            "ES384" => Ok(Algorithm::ES384),
-            // jsonwebtoken (v9) does not expose ES512; if needed we can upgrade or reject.
-            "ES512" => Err(anyhow::anyhow!("unsupported jwt alg ES512 (not supported by current verifier)")),
-            other => Err(anyhow::anyhow!("unsupported jwk alg: {other}")),
-        };
+/// Record a mutating request in the operation audit log; reads are skipped
+fn audit_request(state: &WebServerState, subject: &str, req: &RequestLine, status: StatusCode) {
+    if matches!(req.method.as_str(), "GET" | "HEAD" | "OPTIONS") {
+        return;
+    }
+    let (resource_kind, resource_id) = path_resource(&req.path);
+    let entry = AuditEntry {
+        source: AUDIT_SOURCE.to_string(),
+        identity: subject.to_string(),
+        source_ip: req.source_ip.clone(),
+        operation: format!("{} {}", req.method, req.path),
+        resource_kind,
+        resource_id,
+        status: if status.is_client_error() || status.is_server_error() {
+            status.as_u16().to_string()
+        } else {
+            STATUS_OK.to_string()
+        },
+        ..Default::default()
+    };
+    if let Err(e) = state.audit.append(entry) {
+        warn!("failed to write audit log entry: {}", e);
+    }
+}
+
+/// Check the route's permission for an authenticated caller, then run the request
+///
+/// Denials, including those handlers make for permissions that depend on the
+/// request body, are written to the auth audit log, and every mutating
+/// request to the operation audit log.
+async fn authorize(
+    state: &WebServerState,
+    subject: &str,
+    roles: CallerRoles,
+    mut req: Request,
+    next: middleware::Next,
+) -> Response {
+    let line = RequestLine {
+        method: req.method().to_string(),
+        path: req.uri().path().to_string(),
+        source_ip: req
+            .extensions()
+            .get::<axum::extract::ConnectInfo<SocketAddr>>()
+            .map(|peer| peer.0.ip().to_string()),
+    };
+    let permission = crate::auth::route_permission(&line.method, &line.path);
+    if let Some(denied) = permission_denied(Some(&roles), permission) {
+        audit_access_denied(&state.db, subject, &roles, &line, permission);
+        audit_request(state, subject, &line, denied.status());
+        return denied;
     }
 
-    match jwk.kty.as_str() {
-        "RSA" => Ok(Algorithm::RS256),
-        "EC" => Ok(Algorithm::ES256),
-        other => Err(anyhow::anyhow!("unsupported jwk kty: {other}")),
+    req.extensions_mut().insert(roles.clone());
+    req.extensions_mut().insert(CallerSubject(subject.to_string()));
+    let response = next.run(req).await;
+    if let Some(DeniedPermission(permission)) = response.extensions().get::<DeniedPermission>() {
+        audit_access_denied(&state.db, subject, &roles, &line, permission);
     }
+    audit_request(state, subject, &line, response.status());
+    response
+}
+
+fn parse_allowed_issuers(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
 }
 
-fn verify_jwt_with_local_jwks(token: &str, cfg: &JwtAuthConfig) -> anyhow::Result<TokenData<JwtRegisteredClaims>> {
-    let jwks = LOCAL_JWKS_CACHE.get_or_try_init(|| load_local_jwks(&cfg.local_jwks_path))?;
+fn audience_matches(aud: &Option<serde_json::Value>, required: &str) -> bool {
+    match aud {
+        None => false,
+        Some(serde_json::Value::String(s)) => s == required,
+        Some(serde_json::Value::Array(list)) => list.iter().any(|v| v.as_str() == Some(required)),
+        _ => false,
+    }
+}
 
+async fn verify_jwt(
+    token: &str,
+    cfg: &JwtAuthConfig,
+    jwks: &JwksCache,
+) -> anyhow::Result<TokenData<JwtRegisteredClaims>> {
     // Pull header kid by decoding header only.
     let header = jsonwebtoken::decode_header(token)?;
-    let kid = header.kid.clone();
 
     // Choose key by kid if present, else try all keys.
-    let candidates: Vec<&Jwk> = match kid.as_deref() {
-        Some(k) => jwks.keys.iter().filter(|j| j.kid.as_deref() == Some(k)).collect(),
-        None => jwks.keys.iter().collect(),
-    };
+    let candidates = jwks.keys_for(header.kid.as_deref()).await?;
     if candidates.is_empty() {
         return Err(anyhow::anyhow!("no jwk found for kid"));
     }
 
     let mut last_err: Option<anyhow::Error> = None;
-    for jwk in candidates {
-        let alg = algorithm_for_jwk(jwk)?;
+    for jwk in &candidates {
+        let alg = jwk.algorithm()?;
         let mut validation = Validation::new(alg);
         validation.set_audience(&[cfg.audience.clone()]);
         // We verify issuer manually to allow multiple issuers.
         validation.validate_exp = true;
         validation.validate_nbf = true;
 
-        let key = decoding_key_for_jwk(jwk)?;
+        let key = jwk.decoding_key()?;
         match decode::<JwtRegisteredClaims>(token, &key, &validation) {
             Ok(td) => {
                 let iss = td.claims.iss.clone().unwrap_or_default();
@@ -425,34 +702,57 @@ fn verify_jwt_with_local_jwks(token: &str, cfg: &JwtAuthConfig) -> anyhow::Resul
 // Daemon gRPC Client
 // ============================================================================
 
+use crate::daemon_cache::{DaemonChannel, ResponseCache};
 use crate::generated::infrasim::{
     infra_sim_daemon_client::InfraSimDaemonClient,
-    CreateVmRequest, VmSpec, NetworkMode, GetHealthRequest,
-    StartVmRequest, StopVmRequest, CreateNetworkRequest, NetworkSpec,
-    CreateVolumeRequest, VolumeSpec, VolumeKind,
+    CreateVmRequest, VmSpec, NetworkMode, GetHealthRequest, UpdateVmRequest,
+    StartVmRequest, StopVmRequest, ResumeVmRequest, DeleteVmRequest, CreateNetworkRequest, NetworkSpec,
+    CreateVolumeRequest, DeleteVolumeRequest, VolumeSpec, VolumeKind, VolumeEncryption,
+    VolumeBuild, BuildDependency,
     CreateConsoleRequest, ConsoleSpec,
     CreateSnapshotRequest, SnapshotSpec,
+    CreatePortForwardRequest, PortForwardSpec, PortProtocol,
     // List/Get operations (note: tonic generates snake_case method names)
     ListVMsRequest, GetVmRequest,
     ListVolumesRequest, GetVolumeRequest,
-    ListSnapshotsRequest,
-    ListNetworksRequest,
+    ListSnapshotsRequest, ListSnapshotPoliciesRequest,
+    WatchEventsRequest, Event as DaemonEvent, EventType, VmState,
+    Job, JobSpec, JobKind, JobState,
+    CreateJobRequest, GetJobRequest, ListJobsRequest, CancelJobRequest,
+    GetVmStatsRequest, VmStats,
+    ListNetworksRequest, NetworkCapture, CaptureState, ReadNetworkCaptureRequest, ReadNetworkCaptureResponse,
     GetAttestationRequest, GetDaemonStatusRequest,
+    ExportVmRequest, ExportVmResponse, ExportFormat,
+    ListAuditEventsRequest, AuditEvent,
+    GetSecretRequest,
+    SerialInput, SerialOutput, CopyToGuestRequest, CopyToGuestResponse,
+    SignPayloadRequest, SignPayloadResponse, GetSigningKeyRequest,
+    CreateArchiveRequest, CreateArchiveResponse, DownloadArchiveRequest, DownloadArchiveResponse,
+    RestoreArchiveRequest, RestoreArchiveResponse,
+    SharedFolder,
 };
 
 #[derive(Clone)]
 struct DaemonProxy {
-    endpoint: String,
+    channel: Arc<DaemonChannel>,
+    cache: Arc<ResponseCache>,
 }
 
 impl DaemonProxy {
-    fn new(endpoint: String) -> Self {
-        Self { endpoint }
+    fn new(endpoint: String, credentials: ClientCredentials) -> Self {
+        Self {
+            channel: Arc::new(DaemonChannel::new(endpoint, credentials)),
+            cache: Arc::new(ResponseCache::default()),
+        }
+    }
+
+    async fn connect(&self) -> Result<InfraSimDaemonClient<AuthChannel>, anyhow::Error> {
+        Ok(InfraSimDaemonClient::new(self.channel.get().await?))
     }
 
-    async fn connect(&self) -> Result<InfraSimDaemonClient<tonic::transport::Channel>, anyhow::Error> {
-        let client = InfraSimDaemonClient::connect(self.endpoint.clone()).await?;
-        Ok(client)
+    /// Keep cached responses in step with the daemon's events
+    fn spawn_cache_invalidation(&self) {
+        self.cache.clone().spawn_invalidation(self.channel.clone());
     }
 
     async fn health(&self) -> Result<serde_json::Value, anyhow::Error> {
@@ -475,26 +775,46 @@ impl DaemonProxy {
     }
 
     /// Create a VM from an appliance template.
-    async fn create_vm(&self, name: &str, template: &ApplianceTemplate) -> Result<String, anyhow::Error> {
+    async fn create_vm(
+        &self,
+        name: &str,
+        template: &ApplianceTemplate,
+        labels: HashMap<String, String>,
+    ) -> Result<String, anyhow::Error> {
+        let spec = VmSpec {
+            arch: template.arch.clone(),
+            machine: template.machine.clone(),
+            cpu_cores: template.cpu_cores,
+            memory_mb: template.memory_mb,
+            compatibility_mode: template.compatibility_mode,
+            volume_ids: vec![],
+            network_ids: vec![],
+            qos_profile_id: String::new(),
+            enable_tpm: false,
+            boot_disk_id: String::new(),
+            extra_args: std::collections::HashMap::new(),
+            network_interfaces: vec![],
+            shared_folders: vec![],
+            priority: 0,
+        };
+        self.create_vm_from_spec(name, spec, labels).await
+    }
+
+    /// Create a VM from a full spec.
+    async fn create_vm_from_spec(
+        &self,
+        name: &str,
+        spec: VmSpec,
+        labels: HashMap<String, String>,
+    ) -> Result<String, anyhow::Error> {
         let mut client = self.connect().await?;
         let req = CreateVmRequest {
             name: name.to_string(),
-            spec: Some(VmSpec {
-                arch: template.arch.clone(),
-                machine: template.machine.clone(),
-                cpu_cores: template.cpu_cores,
-                memory_mb: template.memory_mb,
-                compatibility_mode: template.compatibility_mode,
-                volume_ids: vec![],
-                network_ids: vec![],
-                qos_profile_id: String::new(),
-                enable_tpm: false,
-                boot_disk_id: String::new(),
-                extra_args: std::collections::HashMap::new(),
-            }),
-            labels: std::collections::HashMap::new(),
+            spec: Some(spec),
+            labels,
         };
         let resp = client.create_vm(req).await?;
+        self.cache.clear();
         let vm = resp.into_inner().vm.ok_or_else(|| anyhow::anyhow!("no vm in response"))?;
         let meta = vm.meta.ok_or_else(|| anyhow::anyhow!("no meta"))?;
         Ok(meta.id)
@@ -504,13 +824,35 @@ impl DaemonProxy {
     async fn start_vm(&self, vm_id: &str) -> Result<(), anyhow::Error> {
         let mut client = self.connect().await?;
         client.start_vm(StartVmRequest { id: vm_id.to_string() }).await?;
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Wake a VM the daemon paused or suspended for being idle; other VMs
+    /// are left as they are.
+    async fn wake_vm(&self, vm_id: &str) -> Result<(), anyhow::Error> {
+        let mut client = self.connect().await?;
+        client.resume_vm(ResumeVmRequest { id: vm_id.to_string(), if_idle: true }).await?;
+        self.cache.clear();
         Ok(())
     }
 
     /// Stop a VM.
     async fn stop_vm(&self, vm_id: &str, force: bool) -> Result<(), anyhow::Error> {
         let mut client = self.connect().await?;
-        client.stop_vm(StopVmRequest { id: vm_id.to_string(), force }).await?;
+        // Without force the daemon's ACPI powerdown and stop timeout apply
+        client
+            .stop_vm(StopVmRequest { id: vm_id.to_string(), force, ..Default::default() })
+            .await?;
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Delete a VM.
+    async fn delete_vm(&self, vm_id: &str, force: bool) -> Result<(), anyhow::Error> {
+        let mut client = self.connect().await?;
+        client.delete_vm(DeleteVmRequest { id: vm_id.to_string(), force }).await?;
+        self.cache.clear();
         Ok(())
     }
 
@@ -535,33 +877,142 @@ impl DaemonProxy {
             labels: std::collections::HashMap::new(),
         };
         let resp = client.create_network(req).await?;
+        self.cache.clear();
         let net = resp.into_inner().network.ok_or_else(|| anyhow::anyhow!("no network in response"))?;
         let meta = net.meta.ok_or_else(|| anyhow::anyhow!("no meta"))?;
         Ok(meta.id)
     }
 
+    /// Forward a host port to an appliance VM.
+    async fn create_port_forward(&self, name: &str, vm_id: &str, port: &AppliancePort) -> Result<String, anyhow::Error> {
+        let host_port = port.host_port.ok_or_else(|| anyhow::anyhow!("no host port"))?;
+        let mut client = self.connect().await?;
+        let protocol = match port.protocol.as_str() {
+            "udp" => PortProtocol::Udp,
+            _ => PortProtocol::Tcp,
+        };
+        let req = CreatePortForwardRequest {
+            name: name.to_string(),
+            spec: Some(PortForwardSpec {
+                vm_id: vm_id.to_string(),
+                network_id: String::new(),
+                protocol: protocol.into(),
+                host_addr: String::new(),
+                host_port: host_port as u32,
+                guest_port: port.container_port as u32,
+            }),
+            labels: std::collections::HashMap::new(),
+        };
+        let resp = client.create_port_forward(req).await?;
+        self.cache.clear();
+        let pf = resp.into_inner().port_forward.ok_or_else(|| anyhow::anyhow!("no port forward in response"))?;
+        let meta = pf.meta.ok_or_else(|| anyhow::anyhow!("no meta"))?;
+        Ok(meta.id)
+    }
+
     /// Create a volume.
     async fn create_volume(&self, name: &str, def: &VolumeDef) -> Result<String, anyhow::Error> {
+        let spec = VolumeSpec {
+            kind: VolumeKind::Disk.into(),
+            source: String::new(),
+            integrity: None,
+            read_only: false,
+            size_bytes: (def.size_mb as i64) * 1024 * 1024,
+            format: "qcow2".to_string(),
+            overlay: true,
+            encryption: None,
+        };
+        self.create_volume_from_spec(name, spec, std::collections::HashMap::new()).await
+    }
+
+    /// Create a volume from a full spec.
+    async fn create_volume_from_spec(
+        &self,
+        name: &str,
+        spec: VolumeSpec,
+        labels: HashMap<String, String>,
+    ) -> Result<String, anyhow::Error> {
+        self.create_built_volume(name, spec, labels, None).await
+    }
+
+    /// Create a volume for a built image; the daemon attests it with `build`.
+    async fn create_built_volume(
+        &self,
+        name: &str,
+        spec: VolumeSpec,
+        labels: HashMap<String, String>,
+        build: Option<VolumeBuild>,
+    ) -> Result<String, anyhow::Error> {
         let mut client = self.connect().await?;
         let req = CreateVolumeRequest {
             name: name.to_string(),
-            spec: Some(VolumeSpec {
-                kind: VolumeKind::Disk.into(),
-                source: String::new(),
-                integrity: None,
-                read_only: false,
-                size_bytes: (def.size_mb as i64) * 1024 * 1024,
-                format: "qcow2".to_string(),
-                overlay: true,
-            }),
-            labels: std::collections::HashMap::new(),
+            spec: Some(spec),
+            labels,
+            build,
         };
         let resp = client.create_volume(req).await?;
+        self.cache.clear();
         let vol = resp.into_inner().volume.ok_or_else(|| anyhow::anyhow!("no volume in response"))?;
         let meta = vol.meta.ok_or_else(|| anyhow::anyhow!("no meta"))?;
         Ok(meta.id)
     }
 
+    /// Delete a volume and the images the daemon created for it.
+    async fn delete_volume(&self, vol_id: &str) -> Result<(), anyhow::Error> {
+        let mut client = self.connect().await?;
+        client.delete_volume(DeleteVolumeRequest { id: vol_id.to_string() }).await?;
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Add a volume to, or remove it from, a VM's spec; the daemon hot-plugs
+    /// the change into a running VM.
+    async fn set_vm_volume(&self, vm_id: &str, vol_id: &str, attached: bool) -> Result<(), anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.get_vm(GetVmRequest { id: vm_id.to_string() }).await?;
+        let vm = resp.into_inner().vm.ok_or_else(|| anyhow::anyhow!("VM not found"))?;
+        let mut spec = vm.spec.unwrap_or_default();
+
+        let present = spec.volume_ids.iter().any(|id| id == vol_id);
+        if present == attached {
+            return Ok(());
+        }
+        if attached {
+            spec.volume_ids.push(vol_id.to_string());
+        } else {
+            spec.volume_ids.retain(|id| id != vol_id);
+        }
+        client
+            .update_vm(UpdateVmRequest { id: vm_id.to_string(), spec: Some(spec) })
+            .await?;
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Share a host directory with a VM, or stop sharing it; folders are
+    /// matched by host path and the change applies when the VM next starts.
+    async fn set_vm_shared_folder(&self, vm_id: &str, folder: SharedFolder, attached: bool) -> Result<(), anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.get_vm(GetVmRequest { id: vm_id.to_string() }).await?;
+        let vm = resp.into_inner().vm.ok_or_else(|| anyhow::anyhow!("VM not found"))?;
+        let mut spec = vm.spec.unwrap_or_default();
+
+        let present = spec.shared_folders.iter().any(|f| f.host_path == folder.host_path);
+        if present == attached {
+            return Ok(());
+        }
+        if attached {
+            spec.shared_folders.push(folder);
+        } else {
+            spec.shared_folders.retain(|f| f.host_path != folder.host_path);
+        }
+        client
+            .update_vm(UpdateVmRequest { id: vm_id.to_string(), spec: Some(spec) })
+            .await?;
+        self.cache.clear();
+        Ok(())
+    }
+
     /// Create a console for a VM.
     async fn create_console(&self, vm_id: &str, vnc_port: i32, web_port: i32) -> Result<String, anyhow::Error> {
         let mut client = self.connect().await?;
@@ -577,6 +1028,7 @@ impl DaemonProxy {
             }),
         };
         let resp = client.create_console(req).await?;
+        self.cache.clear();
         let console = resp.into_inner().console.ok_or_else(|| anyhow::anyhow!("no console in response"))?;
         let meta = console.meta.ok_or_else(|| anyhow::anyhow!("no meta"))?;
         Ok(meta.id)
@@ -596,6 +1048,7 @@ impl DaemonProxy {
             labels: std::collections::HashMap::new(),
         };
         let resp = client.create_snapshot(req).await?;
+        self.cache.clear();
         let snap = resp.into_inner().snapshot.ok_or_else(|| anyhow::anyhow!("no snapshot in response"))?;
         let meta = snap.meta.ok_or_else(|| anyhow::anyhow!("no meta"))?;
         Ok(meta.id)
@@ -605,8 +1058,56 @@ impl DaemonProxy {
     // List/Get operations for inventory view
     // ========================================================================
 
-    /// List all VMs from daemon.
+    /// List all VMs.
     async fn list_vms(&self) -> Result<Vec<VmInfo>, anyhow::Error> {
+        self.cache.get_or_fetch("vms".to_string(), &["vm"], || self.fetch_vms()).await
+    }
+
+    /// Get a single VM by ID.
+    async fn get_vm(&self, vm_id: &str) -> Result<VmInfo, anyhow::Error> {
+        self.cache.get_or_fetch(format!("vm/{}", vm_id), &["vm"], || self.fetch_vm(vm_id)).await
+    }
+
+    /// List all volumes (images).
+    async fn list_volumes(&self) -> Result<Vec<VolumeInfo>, anyhow::Error> {
+        self.cache.get_or_fetch("volumes".to_string(), &["volume"], || self.fetch_volumes()).await
+    }
+
+    /// Get a single volume by ID.
+    async fn get_volume(&self, vol_id: &str) -> Result<VolumeInfo, anyhow::Error> {
+        self.cache
+            .get_or_fetch(format!("volume/{}", vol_id), &["volume"], || self.fetch_volume(vol_id))
+            .await
+    }
+
+    /// List snapshots, optionally of one VM.
+    async fn list_snapshots(&self, vm_id: Option<&str>) -> Result<Vec<SnapshotInfo>, anyhow::Error> {
+        let key = format!("snapshots/{}", vm_id.unwrap_or_default());
+        self.cache.get_or_fetch(key, &["snapshot"], || self.fetch_snapshots(vm_id)).await
+    }
+
+    /// List snapshot policies, optionally of one VM.
+    async fn list_snapshot_policies(&self, vm_id: Option<&str>) -> Result<Vec<SnapshotPolicyInfo>, anyhow::Error> {
+        let key = format!("snapshot_policies/{}", vm_id.unwrap_or_default());
+        self.cache
+            .get_or_fetch(key, &["snapshot_policy"], || self.fetch_snapshot_policies(vm_id))
+            .await
+    }
+
+    /// List all networks; their connected VMs change with the VMs.
+    async fn list_networks(&self) -> Result<Vec<NetworkInfo>, anyhow::Error> {
+        self.cache.get_or_fetch("networks".to_string(), &["network", "vm"], || self.fetch_networks()).await
+    }
+
+    /// Get daemon status, which counts VMs and volume usage.
+    async fn get_daemon_status(&self) -> Result<DaemonStatus, anyhow::Error> {
+        self.cache
+            .get_or_fetch("daemon_status".to_string(), &["vm", "volume"], || self.fetch_daemon_status())
+            .await
+    }
+
+    /// List all VMs from daemon, uncached.
+    async fn fetch_vms(&self) -> Result<Vec<VmInfo>, anyhow::Error> {
         let mut client = self.connect().await?;
         let resp = client.list_v_ms(ListVMsRequest { label_selector: std::collections::HashMap::new() }).await?;
         let vms = resp.into_inner().vms;
@@ -628,12 +1129,13 @@ impl DaemonProxy {
                 network_ids: spec.network_ids,
                 created_at: meta.created_at,
                 labels: meta.labels,
+                interfaces: Vec::new(),
             }
         }).collect())
     }
 
-    /// Get a single VM by ID.
-    async fn get_vm(&self, vm_id: &str) -> Result<VmInfo, anyhow::Error> {
+    /// Get a single VM by ID, uncached.
+    async fn fetch_vm(&self, vm_id: &str) -> Result<VmInfo, anyhow::Error> {
         let mut client = self.connect().await?;
         let resp = client.get_vm(GetVmRequest { id: vm_id.to_string() }).await?;
         let vm = resp.into_inner().vm.ok_or_else(|| anyhow::anyhow!("VM not found"))?;
@@ -654,11 +1156,140 @@ impl DaemonProxy {
             network_ids: spec.network_ids,
             created_at: meta.created_at,
             labels: meta.labels,
+            interfaces: status.interfaces.into_iter().map(|nic| {
+                let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+                VmInterfaceInfo {
+                    index: nic.index,
+                    network_id: non_empty(nic.network_id),
+                    mac_address: nic.mac_address,
+                    ip_address: non_empty(nic.ip_address),
+                    source: non_empty(nic.source),
+                }
+            }).collect(),
         })
     }
 
-    /// List all volumes (images) from daemon.
-    async fn list_volumes(&self) -> Result<Vec<VolumeInfo>, anyhow::Error> {
+    /// Attach to a VM's serial console; bytes sent on the returned channel
+    /// are typed into the guest.
+    async fn attach_serial(
+        &self,
+        vm_id: &str,
+    ) -> Result<(tokio::sync::mpsc::Sender<Vec<u8>>, tonic::Streaming<SerialOutput>), anyhow::Error> {
+        use futures::StreamExt;
+
+        let mut client = self.connect().await?;
+        let (tx, rx) = tokio::sync::mpsc::channel::<Vec<u8>>(64);
+        let first = SerialInput { vm_id: vm_id.to_string(), data: Vec::new() };
+        let input = futures::stream::once(async move { first }).chain(futures::stream::unfold(
+            rx,
+            |mut rx| async move {
+                let data = rx.recv().await?;
+                Some((SerialInput { vm_id: String::new(), data }, rx))
+            },
+        ));
+        let resp = client.attach_serial(input).await?;
+        Ok((tx, resp.into_inner()))
+    }
+
+    /// Write a file into a running guest through its guest agent; `data` is
+    /// streamed to the daemon as it arrives.
+    async fn copy_to_guest<S, E>(
+        &self,
+        vm_id: &str,
+        path: &str,
+        data: S,
+    ) -> Result<CopyToGuestResponse, anyhow::Error>
+    where
+        S: futures::Stream<Item = Result<axum::body::Bytes, E>> + Send + 'static,
+        E: std::fmt::Display + Send + 'static,
+    {
+        use futures::StreamExt;
+
+        let mut client = self.connect().await?;
+        let first = CopyToGuestRequest { vm_id: vm_id.to_string(), path: path.to_string(), data: Vec::new() };
+        // A client that goes away mid-upload leaves the guest file truncated
+        let rest = data
+            .take_while(|chunk| {
+                if let Err(e) = chunk {
+                    warn!("Guest file upload interrupted: {}", e);
+                }
+                futures::future::ready(chunk.is_ok())
+            })
+            .filter_map(|chunk| futures::future::ready(chunk.ok()))
+            .map(|data| CopyToGuestRequest { vm_id: String::new(), path: String::new(), data: data.to_vec() });
+        let input = futures::stream::once(async move { first }).chain(rest);
+        let resp = client.copy_to_guest(input).await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Stream an export bundle of a stopped VM.
+    async fn export_vm(
+        &self,
+        vm_id: &str,
+        format: ExportFormat,
+    ) -> Result<tonic::Streaming<ExportVmResponse>, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client
+            .export_vm(ExportVmRequest { id: vm_id.to_string(), format: format.into() })
+            .await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Stream the pcap file of a network's last stopped capture.
+    async fn read_network_capture(
+        &self,
+        network_id: &str,
+    ) -> Result<tonic::Streaming<ReadNetworkCaptureResponse>, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client
+            .read_network_capture(ReadNetworkCaptureRequest { network_id: network_id.to_string() })
+            .await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Pack volumes and snapshots into a signed tar.gz in the daemon's CAS.
+    async fn create_archive(
+        &self,
+        volume_ids: Vec<String>,
+        snapshot_ids: Vec<String>,
+        include_memory: bool,
+        metadata: Vec<u8>,
+    ) -> Result<CreateArchiveResponse, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client
+            .create_archive(CreateArchiveRequest { volume_ids, snapshot_ids, include_memory, metadata })
+            .await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Stream an archive from the daemon's CAS, starting at byte `offset`.
+    async fn download_archive(
+        &self,
+        digest: &str,
+        offset: u64,
+    ) -> Result<tonic::Streaming<DownloadArchiveResponse>, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client
+            .download_archive(DownloadArchiveRequest { digest: digest.to_string(), offset })
+            .await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Create volumes from the disk images of an archive.
+    async fn restore_archive(&self, digest: &str, name_prefix: &str) -> Result<RestoreArchiveResponse, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client
+            .restore_archive(RestoreArchiveRequest {
+                digest: digest.to_string(),
+                name_prefix: name_prefix.to_string(),
+            })
+            .await?;
+        self.cache.clear();
+        Ok(resp.into_inner())
+    }
+
+    /// List all volumes (images) from daemon, uncached.
+    async fn fetch_volumes(&self) -> Result<Vec<VolumeInfo>, anyhow::Error> {
         let mut client = self.connect().await?;
         let resp = client.list_volumes(ListVolumesRequest {
             label_selector: std::collections::HashMap::new(),
@@ -683,12 +1314,13 @@ impl DaemonProxy {
                 source: spec.source,
                 created_at: meta.created_at,
                 labels: meta.labels,
+                download: status.download.map(DownloadInfo::from),
             }
         }).collect())
     }
 
-    /// Get a single volume by ID.
-    async fn get_volume(&self, vol_id: &str) -> Result<VolumeInfo, anyhow::Error> {
+    /// Get a single volume by ID, uncached.
+    async fn fetch_volume(&self, vol_id: &str) -> Result<VolumeInfo, anyhow::Error> {
         let mut client = self.connect().await?;
         let resp = client.get_volume(GetVolumeRequest { id: vol_id.to_string() }).await?;
         let vol = resp.into_inner().volume.ok_or_else(|| anyhow::anyhow!("Volume not found"))?;
@@ -709,11 +1341,12 @@ impl DaemonProxy {
             source: spec.source,
             created_at: meta.created_at,
             labels: meta.labels,
+            download: status.download.map(DownloadInfo::from),
         })
     }
 
-    /// List all snapshots from daemon.
-    async fn list_snapshots(&self, vm_id: Option<&str>) -> Result<Vec<SnapshotInfo>, anyhow::Error> {
+    /// List all snapshots from daemon, uncached.
+    async fn fetch_snapshots(&self, vm_id: Option<&str>) -> Result<Vec<SnapshotInfo>, anyhow::Error> {
         let mut client = self.connect().await?;
         let resp = client.list_snapshots(ListSnapshotsRequest {
             vm_id: vm_id.unwrap_or_default().to_string(),
@@ -743,49 +1376,167 @@ impl DaemonProxy {
         }).collect())
     }
 
-    /// List all networks from daemon.
-    async fn list_networks(&self) -> Result<Vec<NetworkInfo>, anyhow::Error> {
+    /// List snapshot policies and their schedule status from daemon, uncached.
+    async fn fetch_snapshot_policies(&self, vm_id: Option<&str>) -> Result<Vec<SnapshotPolicyInfo>, anyhow::Error> {
         let mut client = self.connect().await?;
-        let resp = client.list_networks(ListNetworksRequest {
-            label_selector: std::collections::HashMap::new(),
+        let resp = client.list_snapshot_policies(ListSnapshotPoliciesRequest {
+            vm_id: vm_id.unwrap_or_default().to_string(),
         }).await?;
-        let networks = resp.into_inner().networks;
-        Ok(networks.into_iter().map(|net| {
-            let meta = net.meta.unwrap_or_default();
-            let spec = net.spec.unwrap_or_default();
-            let status = net.status.unwrap_or_default();
-            NetworkInfo {
+        let policies = resp.into_inner().policies;
+        Ok(policies.into_iter().map(|policy| {
+            let meta = policy.meta.unwrap_or_default();
+            let spec = policy.spec.unwrap_or_default();
+            let status = policy.status.unwrap_or_default();
+            SnapshotPolicyInfo {
                 id: meta.id,
                 name: meta.name,
-                mode: network_mode_to_string(spec.mode),
-                cidr: spec.cidr,
-                gateway: spec.gateway,
-                dns: spec.dns,
-                dhcp_enabled: spec.dhcp_enabled,
-                mtu: spec.mtu,
-                active: status.active,
-                bridge_interface: status.bridge_interface,
-                connected_vms: status.connected_vms,
-                created_at: meta.created_at,
-                labels: meta.labels,
+                vm_id: spec.vm_id,
+                schedule: status.schedule,
+                interval_seconds: spec.interval_seconds,
+                keep: spec.keep,
+                include_memory: spec.include_memory,
+                paused: spec.paused,
+                last_run_at: status.last_run_at,
+                next_run_at: status.next_run_at,
+                last_snapshot_id: status.last_snapshot_id,
+                last_error: status.last_error,
+                retained: status.retained,
             }
         }).collect())
     }
 
-    /// Get daemon status.
-    async fn get_daemon_status(&self) -> Result<DaemonStatus, anyhow::Error> {
+    /// Follow daemon events, optionally filtered by resource type and ID.
+    async fn watch_events(
+        &self,
+        resource_type: String,
+        resource_id: String,
+    ) -> Result<tonic::Streaming<DaemonEvent>, anyhow::Error> {
         let mut client = self.connect().await?;
-        let resp = client.get_daemon_status(GetDaemonStatusRequest {}).await?;
-        let s = resp.into_inner();
-        Ok(DaemonStatus {
-            running_vms: s.running_vms,
-            total_vms: s.total_vms,
-            memory_used_bytes: s.memory_used_bytes,
-            disk_used_bytes: s.disk_used_bytes,
-            store_path: s.store_path,
-            qemu_available: s.qemu_available,
-            qemu_version: s.qemu_version,
+        let resp = client.watch_events(WatchEventsRequest {
+            resource_type,
+            resource_id,
+            types: Vec::new(),
+            tail: 0,
+            follow: true,
+        }).await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Queue a background job.
+    async fn create_job(
+        &self,
+        kind: JobKind,
+        target: String,
+        params: HashMap<String, String>,
+    ) -> Result<JobInfo, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.create_job(CreateJobRequest {
+            spec: Some(JobSpec { kind: kind.into(), target, params }),
+            labels: HashMap::new(),
+        }).await?;
+        let job = resp.into_inner().job.ok_or_else(|| anyhow::anyhow!("no job in response"))?;
+        Ok(JobInfo::from(job))
+    }
+
+    /// Get a single job by ID.
+    async fn get_job(&self, job_id: &str) -> Result<JobInfo, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.get_job(GetJobRequest { id: job_id.to_string() }).await?;
+        let job = resp.into_inner().job.ok_or_else(|| anyhow::anyhow!("Job not found"))?;
+        Ok(JobInfo::from(job))
+    }
+
+    /// Get a running VM's resource usage.
+    async fn get_vm_stats(&self, vm_id: &str) -> Result<VmStatsInfo, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.get_vm_stats(GetVmStatsRequest { id: vm_id.to_string() }).await?;
+        Ok(VmStatsInfo::from(resp.into_inner()))
+    }
+
+    /// List jobs from daemon, newest first.
+    async fn list_jobs(&self, state: JobState) -> Result<Vec<JobInfo>, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.list_jobs(ListJobsRequest { state: state.into() }).await?;
+        Ok(resp.into_inner().jobs.into_iter().map(JobInfo::from).collect())
+    }
+
+    /// Operation audit entries the daemon recorded, newest first.
+    async fn list_audit_events(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client
+            .list_audit_events(ListAuditEventsRequest {
+                since: filter.since.unwrap_or_default(),
+                until: filter.until.unwrap_or_default(),
+                identity: filter.identity.clone().unwrap_or_default(),
+                resource_id: filter.resource_id.clone().unwrap_or_default(),
+                source: filter.source.clone().unwrap_or_default(),
+                limit: filter.limit as u32,
+            })
+            .await?;
+        Ok(resp.into_inner().events.into_iter().map(audit_entry_from_proto).collect())
+    }
+
+    /// Value of a secret from the daemon's secret store.
+    async fn get_secret(&self, name: &str) -> Result<String, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.get_secret(GetSecretRequest { name: name.to_string() }).await?;
+        Ok(resp.into_inner().value)
+    }
+
+    /// Cancel a pending job, or stop a running one.
+    async fn cancel_job(&self, job_id: &str) -> Result<JobInfo, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.cancel_job(CancelJobRequest { id: job_id.to_string() }).await?;
+        let job = resp.into_inner().job.ok_or_else(|| anyhow::anyhow!("no job in response"))?;
+        Ok(JobInfo::from(job))
+    }
+
+    /// List all networks from daemon, uncached.
+    async fn fetch_networks(&self) -> Result<Vec<NetworkInfo>, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.list_networks(ListNetworksRequest {
+            label_selector: std::collections::HashMap::new(),
+        }).await?;
+        let networks = resp.into_inner().networks;
+        Ok(networks.into_iter().map(|net| {
+            let meta = net.meta.unwrap_or_default();
+            let spec = net.spec.unwrap_or_default();
+            let status = net.status.unwrap_or_default();
+            NetworkInfo {
+                id: meta.id,
+                name: meta.name,
+                mode: network_mode_to_string(spec.mode),
+                cidr: spec.cidr,
+                gateway: spec.gateway,
+                dns: spec.dns,
+                dhcp_enabled: spec.dhcp_enabled,
+                mtu: spec.mtu,
+                active: status.active,
+                bridge_interface: status.bridge_interface,
+                connected_vms: status.connected_vms,
+                capture: status.capture.map(CaptureInfo::from),
+                created_at: meta.created_at,
+                labels: meta.labels,
+            }
+        }).collect())
+    }
+
+    /// Get daemon status, uncached.
+    async fn fetch_daemon_status(&self) -> Result<DaemonStatus, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.get_daemon_status(GetDaemonStatusRequest {}).await?;
+        let s = resp.into_inner();
+        Ok(DaemonStatus {
+            running_vms: s.running_vms,
+            total_vms: s.total_vms,
+            memory_used_bytes: s.memory_used_bytes,
+            disk_used_bytes: s.disk_used_bytes,
+            store_path: s.store_path,
+            qemu_available: s.qemu_available,
+            qemu_version: s.qemu_version,
             hvf_available: s.hvf_available,
+            kvm_available: s.kvm_available,
+            accelerator: s.accelerator,
         })
     }
 
@@ -817,6 +1568,27 @@ impl DaemonProxy {
             None => Ok(serde_json::json!({"error": "no attestation report"})),
         }
     }
+
+    /// Sign a payload with the daemon's persistent key.
+    async fn sign_payload(&self, payload: &[u8], purpose: &str) -> Result<SignPayloadResponse, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let resp = client.sign_payload(SignPayloadRequest {
+            payload: payload.to_vec(),
+            purpose: purpose.to_string(),
+        }).await?;
+        Ok(resp.into_inner())
+    }
+
+    /// Get the daemon's public signing key.
+    async fn signing_key(&self) -> Result<serde_json::Value, anyhow::Error> {
+        let mut client = self.connect().await?;
+        let key = client.get_signing_key(GetSigningKeyRequest {}).await?.into_inner();
+        Ok(serde_json::json!({
+            "public_key": key.public_key,
+            "key_id": key.key_id,
+            "backend": key.backend,
+        }))
+    }
 }
 
 // Helper functions for enum conversion
@@ -827,6 +1599,7 @@ fn vm_state_to_string(state: i32) -> String {
         3 => "stopped".to_string(),
         4 => "paused".to_string(),
         5 => "error".to_string(),
+        6 => "suspended".to_string(),
         _ => "unknown".to_string(),
     }
 }
@@ -835,6 +1608,18 @@ fn volume_kind_to_string(kind: i32) -> String {
     match kind {
         1 => "disk".to_string(),
         2 => "weights".to_string(),
+        3 => "device".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn download_state_to_string(state: i32) -> String {
+    match state {
+        1 => "pending".to_string(),
+        2 => "downloading".to_string(),
+        3 => "verifying".to_string(),
+        4 => "complete".to_string(),
+        5 => "failed".to_string(),
         _ => "unknown".to_string(),
     }
 }
@@ -852,7 +1637,7 @@ fn network_mode_to_string(mode: i32) -> String {
 // Inventory Info Types (JSON-serializable)
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct VmInfo {
     id: String,
     name: String,
@@ -867,9 +1652,24 @@ struct VmInfo {
     network_ids: Vec<String>,
     created_at: i64,
     labels: HashMap<String, String>,
+    /// Addressing of each NIC; only filled in for a single VM
+    #[serde(default)]
+    interfaces: Vec<VmInterfaceInfo>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct VmInterfaceInfo {
+    index: i32,
+    /// None for the default user-mode NIC
+    network_id: Option<String>,
+    mac_address: String,
+    /// None while unknown or the VM is stopped
+    ip_address: Option<String>,
+    /// Where the address came from: user-mode, dhcp or neighbor
+    source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct VolumeInfo {
     id: String,
     name: String,
@@ -884,9 +1684,184 @@ struct VolumeInfo {
     source: String,
     created_at: i64,
     labels: HashMap<String, String>,
+    /// Remote import progress (http/https sources only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download: Option<DownloadInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct DownloadInfo {
+    state: String,
+    bytes_downloaded: i64,
+    bytes_total: i64,
+    /// Percent complete, if the total size is known
+    progress: Option<f64>,
+    error: String,
+}
+
+impl From<crate::generated::infrasim::DownloadStatus> for DownloadInfo {
+    fn from(d: crate::generated::infrasim::DownloadStatus) -> Self {
+        let progress = (d.bytes_total > 0)
+            .then(|| d.bytes_downloaded as f64 * 100.0 / d.bytes_total as f64);
+        Self {
+            state: download_state_to_string(d.state),
+            bytes_downloaded: d.bytes_downloaded,
+            bytes_total: d.bytes_total,
+            progress,
+            error: d.error,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+struct EventInfo {
+    /// created, updated, deleted, vm_state_changed or reconcile_error
+    kind: String,
+    resource_type: String,
+    resource_id: String,
+    resource_name: String,
+    message: String,
+    timestamp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vm_state: Option<String>,
+}
+
+impl From<DaemonEvent> for EventInfo {
+    fn from(event: DaemonEvent) -> Self {
+        let kind = match EventType::try_from(event.r#type) {
+            Ok(EventType::Created) => "created",
+            Ok(EventType::Updated) => "updated",
+            Ok(EventType::Deleted) => "deleted",
+            Ok(EventType::VmStateChanged) => "vm_state_changed",
+            Ok(EventType::ReconcileError) => "reconcile_error",
+            _ => "unknown",
+        };
+        let vm_state = match VmState::try_from(event.vm_state) {
+            Ok(VmState::Unspecified) | Err(_) => None,
+            Ok(state) => Some(state.as_str_name().trim_start_matches("VM_STATE_").to_lowercase()),
+        };
+
+        Self {
+            kind: kind.to_string(),
+            resource_type: event.resource_type,
+            resource_id: event.resource_id,
+            resource_name: event.resource_name,
+            message: event.message,
+            timestamp: event.timestamp,
+            vm_state,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+struct JobInfo {
+    id: String,
+    name: String,
+    /// push_volume, pull_volume, flatten_volume, push_appliance,
+    /// pull_appliance, create_backup, restore_backup or provision
+    kind: String,
+    target: String,
+    params: HashMap<String, String>,
+    /// pending, running, succeeded, failed or cancelled
+    state: String,
+    progress: f64,
+    message: String,
+    error: String,
+    result: HashMap<String, String>,
+    started_at: i64,
+    finished_at: i64,
+    created_at: i64,
+    labels: HashMap<String, String>,
+}
+
+impl JobInfo {
+    fn is_finished(&self) -> bool {
+        matches!(self.state.as_str(), "succeeded" | "failed" | "cancelled")
+    }
+}
+
+impl From<Job> for JobInfo {
+    fn from(job: Job) -> Self {
+        let meta = job.meta.unwrap_or_default();
+        let spec = job.spec.unwrap_or_default();
+        let status = job.status.unwrap_or_default();
+        let kind = JobKind::try_from(spec.kind).unwrap_or(JobKind::Unspecified);
+        let state = JobState::try_from(status.state).unwrap_or(JobState::Unspecified);
+
+        Self {
+            id: meta.id,
+            name: meta.name,
+            kind: kind.as_str_name().trim_start_matches("JOB_KIND_").to_lowercase(),
+            target: spec.target,
+            params: spec.params,
+            state: state.as_str_name().trim_start_matches("JOB_STATE_").to_lowercase(),
+            progress: status.progress,
+            message: status.message,
+            error: status.error,
+            result: status.result,
+            started_at: status.started_at,
+            finished_at: status.finished_at,
+            created_at: meta.created_at,
+            labels: meta.labels,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct VmStatsInfo {
+    vm_id: String,
+    timestamp: i64,
+    /// Share of one host core; 200 = two busy cores
+    cpu_percent: f64,
+    cpu_seconds: f64,
+    rss_bytes: u64,
+    disk_read_bytes: u64,
+    disk_write_bytes: u64,
+    disk_read_ops: u64,
+    disk_write_ops: u64,
+    /// Null unless the VM's traffic runs through QoS relays
+    net_rx_bytes: Option<u64>,
+    net_tx_bytes: Option<u64>,
+    uptime_seconds: u64,
+}
+
+impl From<VmStats> for VmStatsInfo {
+    fn from(stats: VmStats) -> Self {
+        Self {
+            vm_id: stats.vm_id,
+            timestamp: stats.timestamp,
+            cpu_percent: stats.cpu_percent,
+            cpu_seconds: stats.cpu_seconds,
+            rss_bytes: stats.rss_bytes,
+            disk_read_bytes: stats.disk_read_bytes,
+            disk_write_bytes: stats.disk_write_bytes,
+            disk_read_ops: stats.disk_read_ops,
+            disk_write_ops: stats.disk_write_ops,
+            net_rx_bytes: stats.network_counted.then_some(stats.net_rx_bytes),
+            net_tx_bytes: stats.network_counted.then_some(stats.net_tx_bytes),
+            uptime_seconds: stats.uptime_seconds,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct SnapshotPolicyInfo {
+    id: String,
+    name: String,
+    vm_id: String,
+    schedule: String,
+    interval_seconds: i64,
+    keep: u32,
+    include_memory: bool,
+    paused: bool,
+    last_run_at: i64,
+    next_run_at: i64,
+    last_snapshot_id: String,
+    last_error: String,
+    retained: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct SnapshotInfo {
     id: String,
     name: String,
@@ -904,7 +1879,7 @@ struct SnapshotInfo {
     labels: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct NetworkInfo {
     id: String,
     name: String,
@@ -917,11 +1892,52 @@ struct NetworkInfo {
     active: bool,
     bridge_interface: String,
     connected_vms: i32,
+    /// Latest packet capture; download it from `/api/networks/{network_id}/capture.pcap` once stopped
+    capture: Option<CaptureInfo>,
     created_at: i64,
     labels: HashMap<String, String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct CaptureInfo {
+    id: String,
+    /// running, stopped or failed
+    state: String,
+    /// tcpdump or filter-dump
+    method: String,
+    filter: String,
+    bytes: u64,
+    max_bytes: u64,
+    max_seconds: u64,
+    started_at: i64,
+    stopped_at: i64,
+    stop_reason: String,
+}
+
+impl From<NetworkCapture> for CaptureInfo {
+    fn from(capture: NetworkCapture) -> Self {
+        let state = match CaptureState::try_from(capture.state) {
+            Ok(CaptureState::Running) => "running",
+            Ok(CaptureState::Stopped) => "stopped",
+            Ok(CaptureState::Failed) => "failed",
+            _ => "unknown",
+        };
+        Self {
+            id: capture.id,
+            state: state.to_string(),
+            method: capture.method,
+            filter: capture.filter,
+            bytes: capture.bytes,
+            max_bytes: capture.max_bytes,
+            max_seconds: capture.max_seconds,
+            started_at: capture.started_at,
+            stopped_at: capture.stopped_at,
+            stop_reason: capture.stop_reason,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct DaemonStatus {
     running_vms: i32,
     total_vms: i32,
@@ -931,122 +1947,93 @@ struct DaemonStatus {
     qemu_available: bool,
     qemu_version: String,
     hvf_available: bool,
+    kvm_available: bool,
+    accelerator: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Project {
-    id: String,
-    name: String,
-    created_at: i64,
-    prompts: Vec<Prompt>,
+/// Error body of a failed API call
+#[derive(Debug, Serialize, ToSchema)]
+struct ApiErrorBody {
+    /// What went wrong, for people
+    error: String,
+    /// Machine-readable code, e.g. `NOT_FOUND` or `INSUFFICIENT_RESOURCES`
+    code: String,
+    /// Broad class of the code, e.g. `conflict` or `unavailable`
+    category: String,
+    /// Whether the same request may succeed if retried later
+    retryable: bool,
+    /// What the user can do about it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Prompt {
-    id: String,
-    title: String,
-    body: String,
-    created_at: i64,
-    llm_provider: Option<String>,
+impl From<ErrorDetail> for ApiErrorBody {
+    fn from(detail: ErrorDetail) -> Self {
+        Self {
+            code: detail.code.as_str().to_string(),
+            category: serde_json::to_value(detail.category)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            retryable: detail.retryable,
+            hint: detail.hint,
+            error: detail.message,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct CreateProjectRequest {
-    name: String,
+#[derive(Debug, Serialize, ToSchema)]
+struct VmList {
+    vms: Vec<VmInfo>,
+    count: usize,
 }
 
-// ============================================================================
-// Appliance (VM Template) MVP
-// ============================================================================
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ApplianceTemplate {
-    id: String,
-    title: String,
-    description: String,
-    arch: String,
-    machine: String,
-    cpu_cores: i32,
-    memory_mb: i64,
-    compatibility_mode: bool,
-    tags: Vec<String>,
-    /// Optional container/image reference (e.g. quay.io/keycloak/keycloak:26)
-    #[serde(default)]
-    image: Option<String>,
-    /// Environment variables for the appliance runtime
-    #[serde(default)]
-    env: HashMap<String, String>,
-    /// Exposed ports
-    #[serde(default)]
-    ports: Vec<AppliancePort>,
-    /// Boot plan steps (ordered)
-    #[serde(default)]
-    boot_plan: Vec<BootStep>,
-    /// Network configuration hints
-    #[serde(default)]
-    networks: Vec<NetworkDef>,
-    /// Storage volumes
-    #[serde(default)]
-    volumes: Vec<VolumeDef>,
-    /// Software tooling installed in the image
-    #[serde(default)]
-    tools: Vec<ToolDef>,
+#[derive(Debug, Serialize, ToSchema)]
+struct VolumeList {
+    volumes: Vec<VolumeInfo>,
+    count: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct AppliancePort {
-    container_port: u16,
-    #[serde(default)]
-    host_port: Option<u16>,
-    #[serde(default = "default_tcp")]
-    protocol: String,
-    #[serde(default)]
-    description: String,
+/// Disk volumes in qcow2 or raw format
+#[derive(Debug, Serialize, ToSchema)]
+struct ImageList {
+    images: Vec<VolumeInfo>,
+    count: usize,
 }
 
-fn default_tcp() -> String { "tcp".to_string() }
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct BootStep {
-    order: u32,
-    action: String,
-    description: String,
-    #[serde(default)]
-    args: HashMap<String, String>,
+#[derive(Debug, Serialize, ToSchema)]
+struct SnapshotList {
+    snapshots: Vec<SnapshotInfo>,
+    count: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct NetworkDef {
-    id: String,
-    mode: String,
-    #[serde(default)]
-    cidr: Option<String>,
-    #[serde(default)]
-    gateway: Option<String>,
-    #[serde(default)]
-    dhcp: bool,
+#[derive(Debug, Serialize, ToSchema)]
+struct SnapshotPolicyList {
+    policies: Vec<SnapshotPolicyInfo>,
+    count: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct VolumeDef {
-    id: String,
-    size_mb: u64,
-    mount_path: String,
-    #[serde(default = "default_disk_kind")]
-    kind: String,
+#[derive(Debug, Serialize, ToSchema)]
+struct NetworkList {
+    networks: Vec<NetworkInfo>,
+    count: usize,
 }
 
-fn default_disk_kind() -> String { "disk".to_string() }
+#[derive(Debug, Serialize, ToSchema)]
+struct JobList {
+    jobs: Vec<JobInfo>,
+    count: usize,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct ToolDef {
+struct CreateProjectRequest {
     name: String,
-    #[serde(default)]
-    version: Option<String>,
-    #[serde(default)]
-    purpose: String,
 }
 
+// ============================================================================
+// Appliance (VM Template) MVP
+// ============================================================================
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ApplianceInstance {
     id: String,
@@ -1070,6 +2057,63 @@ struct ApplianceInstance {
     /// Last updated timestamp
     #[serde(default)]
     updated_at: i64,
+    /// Provisioning steps of the last boot
+    #[serde(default)]
+    provisioning: Vec<ProvisionStepStatus>,
+    /// When `status` last changed
+    #[serde(default)]
+    status_since: i64,
+    /// Latest results of the template's health checks
+    #[serde(default)]
+    health: Vec<CheckResult>,
+    /// VMs of a multi-VM appliance in startup order; `vm_id` is the primary
+    #[serde(default)]
+    vms: Vec<ApplianceVm>,
+}
+
+impl ApplianceInstance {
+    fn set_status(&mut self, status: &str) {
+        if self.status != status {
+            self.status = status.to_string();
+            self.status_since = now_epoch_secs();
+        }
+    }
+
+    /// IDs of all the appliance's VMs in startup order
+    fn vm_ids(&self) -> Vec<String> {
+        if self.vms.is_empty() {
+            self.vm_id.iter().cloned().collect()
+        } else {
+            self.vms.iter().map(|vm| vm.vm_id.clone()).collect()
+        }
+    }
+}
+
+/// A VM of a multi-VM appliance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApplianceVm {
+    /// Name of the template's `VmDef`
+    name: String,
+    vm_id: String,
+}
+
+/// A `run_ansible` or `run_ssh` boot step, run by the daemon as a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProvisionStepStatus {
+    order: u32,
+    action: String,
+    /// Daemon job running the step, once queued
+    #[serde(default)]
+    job_id: Option<String>,
+    /// pending, running, succeeded, failed or cancelled
+    state: String,
+    #[serde(default)]
+    error: Option<String>,
+    /// Captured output, a run artifact of the job
+    #[serde(default)]
+    log: Option<String>,
+    #[serde(default)]
+    log_digest: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1081,12 +2125,20 @@ struct ApplianceCatalogSpec {
     volume_ids: Vec<String>,
     console_id: Option<String>,
     snapshot_ids: Vec<String>,
+    #[serde(default)]
+    vms: Vec<ApplianceVm>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ApplianceCatalogStatus {
     status: String,
     error: Option<String>,
+    #[serde(default)]
+    provisioning: Vec<ProvisionStepStatus>,
+    #[serde(default)]
+    status_since: i64,
+    #[serde(default)]
+    health: Vec<CheckResult>,
 }
 
 async fn load_appliance_catalog_into_memory(state: Arc<WebServerState>) -> anyhow::Result<()> {
@@ -1111,6 +2163,10 @@ async fn load_appliance_catalog_into_memory(state: Arc<WebServerState>) -> anyho
             volume_ids: row.spec.volume_ids,
             console_id: row.spec.console_id,
             snapshot_ids: row.spec.snapshot_ids,
+            provisioning: row.status.provisioning,
+            status_since: row.status.status_since,
+            health: row.status.health,
+            vms: row.spec.vms,
         };
 
         appliances.insert(instance.id.clone(), instance);
@@ -1130,10 +2186,14 @@ async fn persist_catalog_instance(state: &WebServerState, instance: &ApplianceIn
         volume_ids: instance.volume_ids.clone(),
         console_id: instance.console_id.clone(),
         snapshot_ids: instance.snapshot_ids.clone(),
+        vms: instance.vms.clone(),
     };
     let status = ApplianceCatalogStatus {
         status: instance.status.clone(),
         error: None,
+        provisioning: instance.provisioning.clone(),
+        status_since: instance.status_since,
+        health: instance.health.clone(),
     };
 
     tokio::task::spawn_blocking(move || {
@@ -1188,7 +2248,7 @@ struct ImportApplianceRequest {
 /// Request to archive an appliance (backup to a persistent store)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ArchiveApplianceRequest {
-    /// Archive format: "tar.gz", "zip", "json"
+    /// Archive format; only "tar.gz" is produced
     #[serde(default = "default_archive_format")]
     format: String,
     /// Include memory snapshots in archive
@@ -1200,7 +2260,15 @@ struct ArchiveApplianceRequest {
 }
 
 fn default_archive_format() -> String {
-    "json".to_string()
+    "tar.gz".to_string()
+}
+
+/// Request to restore an appliance from an archive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RestoreApplianceArchiveRequest {
+    /// Name of the restored appliance, also prefixed to its volume names
+    #[serde(default)]
+    name: Option<String>,
 }
 
 // ============================================================================
@@ -1313,7 +2381,7 @@ pub struct GeographicBounds {
 pub struct FilesystemLifecycle {
     /// Auto-delete after this many seconds (0 = never)
     pub ttl_seconds: u64,
-    /// Maximum number of snapshots to retain
+    /// Maximum number of snapshots to retain (0 = unlimited)
     pub max_snapshots: u32,
     /// Whether to auto-snapshot on detach
     pub snapshot_on_detach: bool,
@@ -1390,14 +2458,56 @@ pub struct Filesystem {
     pub updated_at: i64,
     /// Labels for filtering
     pub labels: HashMap<String, String>,
+    /// Daemon volume holding the filesystem's data
+    #[serde(default)]
+    pub volume_id: Option<String>,
+    /// Provisioning state: pending, ready, archiving, archived, missing or error
+    #[serde(default)]
+    pub status: String,
+    /// Last provisioning or lifecycle error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Last attach or detach, for `archive_when_idle`
+    #[serde(default)]
+    pub last_active_at: i64,
+    /// Compressed copy being made by `archive_when_idle`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_volume_id: Option<String>,
+    /// Encryption of the volume; snapshots inherit it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<FilesystemEncryption>,
 }
 
-/// Request to create a new filesystem
+/// Encryption of a filesystem's volume with a daemon keyring key
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CreateFilesystemRequest {
-    pub name: String,
-    #[serde(rename = "type", default)]
-    pub fs_type: FilesystemType,
+pub struct FilesystemEncryption {
+    /// Keyring key holding the passphrase; a new key gets `passphrase` or a generated one
+    pub key_id: String,
+    /// Cipher (aes-256 by default)
+    #[serde(default)]
+    pub cipher: String,
+    /// Passphrase stored under a new key; never persisted or returned
+    #[serde(default, skip_serializing)]
+    pub passphrase: Option<String>,
+}
+
+impl FilesystemEncryption {
+    /// Daemon spec of the encryption, with the passphrase if one was given
+    fn to_proto(&self) -> VolumeEncryption {
+        VolumeEncryption {
+            cipher: self.cipher.clone(),
+            key_id: self.key_id.clone(),
+            secret: self.passphrase.clone().unwrap_or_default().into_bytes(),
+        }
+    }
+}
+
+/// Request to create a new filesystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateFilesystemRequest {
+    pub name: String,
+    #[serde(rename = "type", alias = "fs_type", default)]
+    pub fs_type: FilesystemType,
     #[serde(default)]
     pub size_bytes: i64,
     #[serde(default)]
@@ -1414,6 +2524,8 @@ pub struct CreateFilesystemRequest {
     pub lifecycle: Option<FilesystemLifecycle>,
     #[serde(default)]
     pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub encryption: Option<FilesystemEncryption>,
 }
 
 fn default_format() -> String {
@@ -1424,116 +2536,38 @@ fn default_format() -> String {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttachFilesystemRequest {
     pub appliance_id: String,
-    #[serde(default)]
+    #[serde(default, alias = "mount_point")]
     pub mount_path: Option<String>,
     #[serde(default)]
     pub read_only: bool,
 }
 
-/// Request to detach a filesystem from an appliance
+/// Query of a filesystem detach
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DetachFilesystemRequest {
-    pub appliance_id: String,
+pub struct DetachFilesystemQuery {
+    /// Snapshot after detaching, even without `snapshot_on_detach`
     #[serde(default)]
     pub create_snapshot: bool,
 }
 
-/// Request to snapshot a filesystem
+/// Request to change a filesystem's mutable settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SnapshotFilesystemRequest {
-    #[serde(default)]
-    pub name: Option<String>,
+pub struct UpdateFilesystemRequest {
     #[serde(default)]
-    pub description: Option<String>,
-}
-
-// ============================================================================
-// Resource Graph Model
-// ============================================================================
-// The resource graph represents all appliance-associated resources:
-// - Compute: VM definitions
-// - Filesystems: Root, data, ephemeral overlays
-// - Devices: NICs, USB, PCI passthrough
-// - Networks: Virtual and physical segments
-// - Provenance: Snapshots, attestations
-// ============================================================================
-
-/// A node in the resource graph
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResourceNode {
-    pub id: String,
-    #[serde(rename = "type")]
-    pub node_type: String,
-    pub name: String,
-    pub data: serde_json::Value,
+    pub lifecycle: Option<FilesystemLifecycle>,
     #[serde(default)]
-    pub position: Option<NodePosition>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NodePosition {
-    pub x: f64,
-    pub y: f64,
-}
-
-/// An edge in the resource graph
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResourceEdge {
-    pub id: String,
-    pub source: String,
-    pub target: String,
-    #[serde(rename = "type")]
-    pub edge_type: String,
+    pub mount_path: Option<String>,
     #[serde(default)]
-    pub data: serde_json::Value,
-}
-
-/// The complete resource graph
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResourceGraph {
-    pub nodes: Vec<ResourceNode>,
-    pub edges: Vec<ResourceEdge>,
-    pub version: String,
-    pub computed_at: i64,
-}
-
-/// Plan result for graph changes (Terraform-style)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GraphPlanResult {
-    pub adds: Vec<PlanChange>,
-    pub updates: Vec<PlanChange>,
-    pub deletes: Vec<PlanChange>,
-    pub warnings: Vec<String>,
-    pub valid: bool,
+    pub labels: Option<HashMap<String, String>>,
 }
 
+/// Request to snapshot a filesystem
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlanChange {
-    pub resource_type: String,
-    pub resource_id: String,
-    pub name: String,
+pub struct SnapshotFilesystemRequest {
     #[serde(default)]
-    pub changes: Vec<String>,
-}
-
-/// Request to plan graph changes
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlanGraphRequest {
-    pub draft: ResourceGraph,
-}
-
-/// Request to apply graph changes
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ApplyGraphRequest {
-    pub draft: ResourceGraph,
+    pub name: Option<String>,
     #[serde(default)]
-    pub dry_run: bool,
-}
-
-/// Request to validate a graph
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ValidateGraphRequest {
-    pub graph: ResourceGraph,
+    pub description: Option<String>,
 }
 
 /// UI manifest for provenance and versioning
@@ -1586,6 +2620,12 @@ impl WebServer {
 
         // Best-effort schema init for local auth tables.
         init_auth_schema(&db);
+        let webauthn = webauthn_from_env(&db);
+        let audit = audit_log_from_env(&db);
+        let jwks = match &cfg.auth {
+            WebUiAuth::Jwt(jwt) => Some(Arc::new(JwksCache::new(jwt.jwks.clone()))),
+            _ => None,
+        };
 
         // MDM config manager
         let mdm_config = crate::mdm::MdmConfig {
@@ -1600,23 +2640,55 @@ impl WebServer {
                         .to_string()
                 })
             ),
+            public_url: std::env::var("INFRASIM_MDM_PUBLIC_URL").unwrap_or_else(|_| {
+                format!("https://{}", std::env::var("INFRASIM_MDM_DOMAIN").unwrap_or_else(|_| "infrasim.local".to_string()))
+            }),
         };
         let mdm = crate::mdm::MdmManager::new(mdm_config);
 
+        let template_dir = std::env::var("INFRASIM_TEMPLATE_DIR")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|_| {
+                dirs::home_dir()
+                    .unwrap_or_else(|| std::path::PathBuf::from("/tmp"))
+                    .join(".infrasim/templates")
+            });
+
+        let build_dir = std::env::var("INFRASIM_BUILD_DIR")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|_| {
+                dirs::home_dir()
+                    .unwrap_or_else(|| std::path::PathBuf::from("/tmp"))
+                    .join(".infrasim/builds")
+            });
+
         Self {
             state: Arc::new(WebServerState {
                 vnc_targets: RwLock::new(HashMap::new()),
                 tokens: RwLock::new(HashMap::new()),
                 static_files: StaticFiles::new(),
                 ui_static: UiStatic::from_env(),
-                daemon: DaemonProxy::new(cfg.daemon_addr.clone()),
+                daemon: DaemonProxy::new(cfg.daemon_addr.clone(), cfg.daemon_credentials.clone()),
                 cfg,
-                projects: RwLock::new(HashMap::new()),
+                projects: ProjectStore::new(db.clone()).expect("failed to migrate project tables"),
+                recordings: RecordingStore::new(db.clone(), RecordingConfig::from_env())
+                    .expect("failed to migrate recording tables"),
                 appliances: RwLock::new(HashMap::new()),
+                templates: RwLock::new(TemplateRegistry::new(template_dir)),
                 filesystems: RwLock::new(HashMap::new()),
+                graph_apply: tokio::sync::Mutex::new(()),
+                builds: RwLock::new(HashMap::new()),
+                build_dir,
                 db,
+                audit,
                 control: LocalControl::from_env(),
                 mdm,
+                webauthn,
+                oidc: oidc_from_env(),
+                jwks,
+                shutdown: Shutdown::default(),
+                proxy: ProxyConfig::from_env(),
+                acme_challenges: crate::acme::Challenges::default(),
             }),
         }
         .with_dev_token(auth)
@@ -1636,6 +2708,17 @@ impl WebServer {
             if let Err(e) = load_appliance_catalog_into_memory(state.clone()).await {
                 warn!("failed to load appliance catalog: {}", e);
             }
+            if let Err(e) = state.templates.write().await.reload().await {
+                warn!("failed to load appliance templates: {}", e);
+            }
+            if let Err(e) = load_filesystems_into_memory(state.clone()).await {
+                warn!("failed to load filesystems: {}", e);
+            }
+            let mut interval = tokio::time::interval(FILESYSTEM_RECONCILE_INTERVAL);
+            loop {
+                interval.tick().await;
+                reconcile_filesystems(&state).await;
+            }
         });
 
         self
@@ -1664,22 +2747,32 @@ impl WebServer {
     pub fn router(&self) -> Router {
         let state = self.state.clone();
         let meshnet_db = state.db.clone(); // Clone db for meshnet before state is moved
+        // Shared by the public analysis routes and the protected repository routes
+        let analysis_cache = std::sync::Arc::new(crate::build_analysis::AnalysisCache::default());
         let auth_layer = middleware::from_fn(move |req, next| {
             let state = state.clone();
             async move { auth_middleware_inner(state, req, next).await }
         });
+        let proxy_state = self.state.clone();
+        let client_address_layer = middleware::from_fn(move |req, next| {
+            let state = proxy_state.clone();
+            async move { client_address_middleware(state, req, next).await }
+        });
 
         // Protected routes (require main app auth)
         let protected_routes = Router::new()
             // Filesystem Resource API (Terraform-addressable)
             .route("/api/filesystems", get(list_filesystems_handler).post(create_filesystem_handler))
-            .route("/api/filesystems/:fs_id", get(get_filesystem_handler).delete(delete_filesystem_handler))
+            .route(
+                "/api/filesystems/:fs_id",
+                get(get_filesystem_handler).put(update_filesystem_handler).delete(delete_filesystem_handler),
+            )
             .route(
                 "/api/filesystems/:fs_id/snapshot",
                 post(create_filesystem_snapshot_handler),
             )
             .route("/api/filesystems/:fs_id/attach", post(attach_filesystem_handler))
-            .route("/api/filesystems/:fs_id/detach", post(detach_filesystem_handler))
+            .route("/api/filesystems/:fs_id/detach/:appliance_id", post(detach_filesystem_handler))
 
             // Resource Graph API
             .route("/api/graph", get(get_resource_graph_handler))
@@ -1692,6 +2785,7 @@ impl WebServer {
             .route("/api/admin/restart-web", post(admin_restart_web_handler))
             .route("/api/admin/restart-daemon", post(admin_restart_daemon_handler))
             .route("/api/admin/stop-daemon", post(admin_stop_daemon_handler))
+            .route("/api/admin/terminal", get(admin_terminal_handler))
 
             // Inventory: Images (qcow2 volumes/snapshots)
             .route("/api/images", get(list_images_handler))
@@ -1704,17 +2798,49 @@ impl WebServer {
             // Inventory: Snapshots
             .route("/api/snapshots", get(list_snapshots_handler))
             .route("/api/snapshots/:snapshot_id", get(get_snapshot_handler))
+            .route("/api/snapshot-policies", get(list_snapshot_policies_handler))
+
+            // Live daemon events (WebSocket)
+            .route("/api/events", get(events_websocket_handler))
+
+            // Recorded console sessions and per-VM recording policies
+            .route("/api/recordings", get(list_recordings_handler))
+            .route(
+                "/api/recordings/policies/:vm_id",
+                get(get_recording_policy_handler).put(put_recording_policy_handler),
+            )
+            .route(
+                "/api/recordings/:recording_id",
+                get(get_recording_handler).put(update_recording_handler).delete(delete_recording_handler),
+            )
+            .route("/api/recordings/:recording_id/download", get(download_recording_handler))
+
+            // Console preferences of the caller
+            .route("/api/preferences", get(get_preferences_handler).put(put_preferences_handler))
+
+            // Background jobs; progress streams as server-sent events
+            .route("/api/jobs", get(list_jobs_handler).post(create_job_handler))
+            .route("/api/audit", get(list_audit_handler))
+            .route("/api/jobs/:job_id", get(get_job_handler))
+            .route("/api/jobs/:job_id/cancel", post(cancel_job_handler))
+            .route("/api/jobs/:job_id/events", get(job_events_handler))
 
             // Inventory: Networks
             .route("/api/networks", get(list_networks_handler))
             .route("/api/networks/:network_id", get(get_network_handler))
+            .route("/api/networks/:network_id/capture.pcap", get(download_network_capture_handler))
 
-            // Project + prompt workspace (local, persisted in-memory for MVP)
+            // Project + prompt workspace (local, persisted in the state DB)
             .route("/api/projects", get(list_projects_handler).post(create_project_handler))
+            .route(
+                "/api/projects/:project_id",
+                get(get_project_handler).put(rename_project_handler).delete(delete_project_handler),
+            )
             .route(
                 "/api/projects/:project_id/prompts",
                 get(list_prompts_handler).post(create_prompt_handler),
             )
+            .route("/api/prompts", get(search_prompts_handler))
 
             // Terraform helpers
             .route("/api/terraform/generate", post(terraform_generate_handler))
@@ -1723,12 +2849,20 @@ impl WebServer {
             // Provenance helpers
             .route("/api/provenance/attest", post(attest_project_handler))
             .route("/api/provenance/evidence", post(provenance_evidence_handler))
+            .route("/api/provenance/signing-key", get(signing_key_handler))
 
             // Appliance (VM template) MVP
             .route("/api/appliances/templates", get(list_appliance_templates_handler))
+            .route("/api/appliances/templates/sync", post(sync_appliance_templates_handler))
+            .route("/api/appliances/templates/packs", post(publish_template_pack_handler))
+            .route("/api/appliances/templates/packs/:pack", delete(unpublish_template_pack_handler))
+            .route("/api/appliances/templates/packs/:pack/catalog.json", get(template_pack_file_handler))
+            .route("/api/appliances/templates/packs/:pack/catalog.json.sig", get(template_pack_signature_handler))
             .route("/api/appliances", get(list_appliances_handler).post(create_appliance_handler))
             .route("/api/appliances/seed", post(seed_appliances_handler))
             .route("/api/appliances/import", post(import_appliance_handler))
+            .route("/api/appliances/archives/:digest", get(download_appliance_archive_handler))
+            .route("/api/appliances/archives/:digest/restore", post(restore_appliance_archive_handler))
             .route("/api/appliances/:appliance_id", get(get_appliance_detail_handler))
             .route("/api/appliances/:appliance_id/terraform", get(appliance_terraform_handler))
             .route("/api/appliances/:appliance_id/boot", post(appliance_boot_handler))
@@ -1743,11 +2877,29 @@ impl WebServer {
 
             // Auth (local TOTP / Google Authenticator compatible)
             .route("/api/auth/status", get(auth_status_handler))
-            .route("/api/auth/identities", post(auth_create_identity_handler))
+            .route("/api/auth/identities", get(auth_list_identities_handler).post(auth_create_identity_handler))
+            .route("/api/auth/identities/:id", delete(auth_delete_identity_handler))
+            .route("/api/auth/identities/:id/disable", post(auth_disable_identity_handler))
+            .route("/api/auth/identities/:id/enable", post(auth_enable_identity_handler))
+            .route("/api/auth/identities/:id/role", put(auth_set_identity_role_handler))
             .route("/api/auth/totp/begin", post(auth_totp_begin_handler))
             .route("/api/auth/totp/confirm", post(auth_totp_confirm_handler))
             .route("/api/auth/totp/login", post(auth_totp_login_handler))
+            .route("/api/auth/totp/reset", post(auth_totp_reset_handler))
+            .route("/api/auth/totp/recovery-codes", post(auth_recovery_codes_handler))
+            .route("/api/auth/webauthn/register/begin", post(auth_webauthn_register_begin_handler))
+            .route("/api/auth/webauthn/register/finish", post(auth_webauthn_register_finish_handler))
+            .route("/api/auth/webauthn/login/begin", post(auth_webauthn_login_begin_handler))
+            .route("/api/auth/webauthn/login/finish", post(auth_webauthn_login_finish_handler))
+            .route("/api/auth/oidc/login", get(auth_oidc_login_handler))
+            .route("/api/auth/oidc/callback", get(auth_oidc_callback_handler))
+            .route("/api/auth/oidc/refresh", post(auth_oidc_refresh_handler))
             .route("/api/auth/whoami", get(auth_whoami_handler))
+            .route("/api/auth/csrf", get(auth_csrf_handler))
+            .route("/api/auth/logout", post(auth_logout_handler))
+            .route("/api/auth/sessions", get(auth_list_sessions_handler))
+            .route("/api/auth/sessions/revoke-all", post(auth_revoke_all_sessions_handler))
+            .route("/api/auth/sessions/:token_id", delete(auth_revoke_session_handler))
 
             // MDM / mobileconfig endpoints
             .route("/api/mdm/status", get(mdm_status_handler))
@@ -1756,6 +2908,10 @@ impl WebServer {
             .route("/api/mdm/vpns", get(mdm_list_vpns_handler).post(mdm_add_vpn_handler))
             .route("/api/mdm/profile", post(mdm_generate_profile_handler))
             .route("/api/mdm/profile/:name", get(mdm_download_profile_handler))
+            .route("/api/mdm/enrollments", get(mdm_list_enrollments_handler).post(mdm_create_enrollment_handler))
+            .route("/api/mdm/enrollments/:id/revoke", post(mdm_revoke_enrollment_handler))
+            // Device enrollment: Profile Service payload, then the per-device profile
+            .route("/mdm/enroll/:token", get(mdm_enroll_handler).post(mdm_enroll_device_handler))
             // Webhook for device config delivery (signed mobileconfig)
             .route("/webhook/config/:token", get(webhook_config_handler))
 
@@ -1767,17 +2923,41 @@ impl WebServer {
             .route("/api/docker/images/pull", post(docker_pull_image_handler))
             .route("/api/docker/search", get(docker_search_handler))
             .route("/api/docker/build", post(docker_build_appliance_handler))
+            .route("/api/docker/builds", get(docker_list_builds_handler))
+            .route("/api/docker/builds/:build_id", get(docker_get_build_handler))
+            .route("/api/docker/builds/:build_id/ws", get(docker_build_websocket_handler))
 
             // RBAC / Policy export
             .route("/api/rbac/roles", get(rbac_list_roles_handler))
             .route("/api/rbac/policies", get(rbac_list_policies_handler))
             .route("/api/rbac/terraform", get(rbac_terraform_export_handler))
 
-            .route("/api/vms", get(list_vms_api_handler))
-            .route("/api/vms/:vm_id", get(get_vm_handler))
+            .route("/api/vms", get(list_vms_api_handler).post(create_vm_api_handler))
+            .route("/api/vms/:vm_id", get(get_vm_handler).delete(delete_vm_api_handler))
+            .route("/api/vms/:vm_id/start", post(start_vm_api_handler))
+            .route("/api/vms/:vm_id/stop", post(stop_vm_api_handler))
+            .route("/api/vms/:vm_id/restart", post(restart_vm_api_handler))
+            .route("/api/vms/:vm_id/delete", post(post_delete_vm_api_handler))
             .route("/api/vms/:vm_id/vnc", get(vnc_info_handler))
+            .route("/api/vms/:vm_id/stats", get(vm_stats_handler))
+            .route("/api/vms/:vm_id/export", get(export_vm_handler))
+            .route("/api/vms/:vm_id/files", post(upload_guest_file_handler))
+            .route("/console/serial/:vm_id", get(serial_websocket_handler))
+            .route("/console/replay/:recording_id", get(replay_websocket_handler))
             // VNC WebSocket proxy
             .route("/websockify/:vm_id", get(websocket_handler))
+            // Files inside snapshots, read by the daemon with libguestfs
+            .nest_service("/api/snapshot-files", crate::snapshot_browser::snapshot_file_routes(
+                self.state.daemon.channel.clone()
+            ))
+            // Spec history of a resource from the daemon's provenance ledger
+            .nest_service("/api/provenance", crate::snapshot_browser::provenance_routes(
+                self.state.daemon.channel.clone()
+            ))
+            // Repositories the analysis pipeline watches
+            .nest_service("/api/analysis/repos", crate::build_analysis::repo_routes(
+                analysis_cache.clone()
+            ))
             .layer(auth_layer)
             .with_state(self.state.clone());
 
@@ -1796,11 +2976,16 @@ impl WebServer {
             .route("/api/daemon", get(daemon_health_handler))
             .route("/api/daemon/status", get(daemon_status_handler))
 
+            // OpenAPI document and Swagger UI for the REST API (public)
+            .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi()))
+
             // UI Manifest endpoint (public, for provenance)
             .route("/api/ui/manifest", get(ui_manifest_handler))
 
             // Legacy noVNC/static console endpoints (kept for now, but no longer the root UI)
             .route("/vnc.html", get(vnc_html_handler))
+            .route("/console/serial.html", get(serial_html_handler))
+            .route("/console/admin-terminal.html", get(admin_terminal_html_handler))
             .route("/vnc_lite.html", get(vnc_lite_handler))
             .route("/app/*path", get(static_handler))
             .route("/core/*path", get(static_handler))
@@ -1811,9 +2996,7 @@ impl WebServer {
             .nest_service("/api/meshnet", crate::meshnet::routes::meshnet_router(meshnet_db))
 
             // Build Pipeline Analysis (dependency graphs, timing probes)
-            .nest_service("/api/analysis", crate::build_analysis::analysis_routes(
-                std::sync::Arc::new(crate::build_analysis::AnalysisCache::default())
-            ))
+            .nest_service("/api/analysis", crate::build_analysis::analysis_routes(analysis_cache))
 
             // Snapshot Browser with provenance and memory pinning
             .nest_service("/api/snapshot-browser", crate::snapshot_browser::snapshot_browser_routes(
@@ -1823,11 +3006,18 @@ impl WebServer {
             // Static pipeline analyzer HTML
             .route("/pipeline-analyzer", get(pipeline_analyzer_handler))
 
+            // ACME HTTP-01 challenges, for proxies forwarding port 80 here
+            .nest_service("/.well-known/acme-challenge", crate::acme::challenge_routes(
+                self.state.acme_challenges.clone()
+            ))
+
             // Merge protected routes
             .merge(protected_routes)
 
             // Fallback
             .fallback(not_found_handler)
+            .layer(middleware::map_response(error_envelope))
+            .layer(client_address_layer)
             .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any))
             .layer(TraceLayer::new_for_http())
             .with_state(self.state.clone())
@@ -1835,28 +3025,192 @@ impl WebServer {
 
     /// Start the web server
     pub async fn serve(self, addr: SocketAddr) -> anyhow::Result<()> {
-        info!("Web console starting on http://{}", addr);
+        let scheme = if self.state.cfg.tls.is_some() { "https" } else { "http" };
+        info!("Web console starting on {}://{}", scheme, addr);
+
+        if let (Some(jwks), WebUiAuth::Jwt(JwtAuthConfig { jwks: JwksSource::Url(url), .. })) =
+            (&self.state.jwks, &self.state.cfg.auth)
+        {
+            info!("Refreshing JWT signing keys from {}", url);
+            jwks.clone().spawn_refresh();
+        }
+        self.state.daemon.spawn_cache_invalidation();
+        self.state.recordings.spawn_pruning();
+        spawn_appliance_health_checks(self.state.clone());
+
+        let listener = crate::shutdown::bind_listener(addr)?;
+        let acceptor = self.tls_acceptor(listener.local_addr()?.port())?;
+        let shutdown = self.state.shutdown.clone();
+        tokio::spawn(crate::shutdown::watch_signals(shutdown.clone()));
+
+        // Peer addresses go into the admin terminal's audit trail
+        let mut server = match acceptor {
+            Some(acceptor) => tokio::spawn(crate::tls::serve(listener, self.router(), acceptor, shutdown.clone())),
+            None => {
+                let app = self.router().into_make_service_with_connect_info::<SocketAddr>();
+                let stopping = shutdown.clone();
+                tokio::spawn(async move {
+                    axum::serve(listener, app)
+                        .with_graceful_shutdown(async move { stopping.stopping().await })
+                        .await
+                })
+            }
+        };
+        tokio::select! {
+            res = &mut server => return Ok(res??),
+            _ = shutdown.stopping() => {}
+        }
 
-        let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, self.router()).await?;
+        // The listener is closed once shutdown begins; requests still in
+        // flight and console sessions get until the deadline to finish
+        let deadline = crate::shutdown::drain_deadline();
+        info!(
+            "Shutting down: draining {} console sessions for up to {:?}",
+            shutdown.sessions(),
+            deadline
+        );
+        let (served, _) = tokio::join!(
+            tokio::time::timeout(deadline, &mut server),
+            shutdown.drain(deadline),
+        );
+        if served.is_err() {
+            warn!("Requests still in flight after {:?}; dropping them", deadline);
+            server.abort();
+        }
 
+        if shutdown.restart_requested() {
+            // Exit code 75 (EX_TEMPFAIL) tells the supervisor to restart us
+            info!("Restart requested; exiting for supervisor restart");
+            process::exit(75);
+        }
+        info!("Web console stopped");
         Ok(())
     }
+
+    /// TLS acceptor for the configured certificate source, if any; ACME
+    /// certificates are issued in the background, so handshakes fail until
+    /// the first one arrives
+    fn tls_acceptor(&self, https_port: u16) -> anyhow::Result<Option<tokio_rustls::TlsAcceptor>> {
+        let Some(tls) = &self.state.cfg.tls else {
+            return Ok(None);
+        };
+        let resolver = Arc::new(crate::tls::CertResolver::default());
+        match tls {
+            TlsConfig::Files { cert, key } => crate::tls::load_files(resolver.clone(), cert.clone(), key.clone())?,
+            TlsConfig::Acme(acme) => {
+                let challenges = self.state.acme_challenges.clone();
+                if let Some(http_addr) = acme.http_addr {
+                    crate::acme::spawn_http(http_addr, challenges.clone(), https_port);
+                }
+                crate::acme::spawn(acme.clone(), resolver.clone(), challenges);
+            }
+        }
+        Ok(Some(tokio_rustls::TlsAcceptor::from(crate::tls::server_config(resolver)?)))
+    }
 }
 
 impl Default for WebServer {
     fn default() -> Self {
         Self::new(WebServerConfig {
             daemon_addr: "http://127.0.0.1:50051".to_string(),
+            daemon_credentials: ClientCredentials::default(),
             auth: WebUiAuth::DevRandom,
+            session_cookies: None,
+            tls: None,
         })
     }
 }
 
+// ============================================================================
+// OpenAPI
+// ============================================================================
+
+/// OpenAPI document of the REST API, served at `/api/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "InfraSim Web API",
+        description = "REST API of the InfraSim web console. Requests need a bearer token unless noted."
+    ),
+    paths(
+        health_handler,
+        daemon_health_handler,
+        daemon_status_handler,
+        list_vms_api_handler,
+        create_vm_api_handler,
+        get_vm_handler,
+        delete_vm_api_handler,
+        start_vm_api_handler,
+        stop_vm_api_handler,
+        restart_vm_api_handler,
+        post_delete_vm_api_handler,
+        vnc_info_handler,
+        vm_stats_handler,
+        export_vm_handler,
+        upload_guest_file_handler,
+        list_images_handler,
+        get_image_handler,
+        list_volumes_handler,
+        get_volume_handler,
+        list_snapshots_handler,
+        get_snapshot_handler,
+        list_snapshot_policies_handler,
+        list_networks_handler,
+        get_network_handler,
+        download_network_capture_handler,
+        list_jobs_handler,
+        create_job_handler,
+        get_job_handler,
+        cancel_job_handler,
+        job_events_handler,
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "daemon", description = "Health and status of the console and daemon"),
+        (name = "vms", description = "Virtual machines"),
+        (name = "images", description = "Disk images"),
+        (name = "volumes", description = "Volumes"),
+        (name = "snapshots", description = "Snapshots and snapshot policies"),
+        (name = "networks", description = "Networks"),
+        (name = "jobs", description = "Background jobs")
+    )
+)]
+struct ApiDoc;
+
+/// Adds the bearer token scheme the protected routes check
+struct BearerAuth;
+
+impl utoipa::Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("JWT")
+                        .build(),
+                ),
+            );
+    }
+}
+
 // ============================================================================
 // Handlers
 // ============================================================================
 
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "daemon",
+    security(()),
+    responses((status = 200, description = "Web console is up", body = serde_json::Value))
+)]
 async fn health_handler() -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "ok",
@@ -1864,6 +3218,16 @@ async fn health_handler() -> impl IntoResponse {
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/daemon",
+    tag = "daemon",
+    security(()),
+    responses(
+        (status = 200, description = "Daemon health", body = serde_json::Value),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn daemon_health_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
     match state.daemon.health().await {
         Ok(v) => (StatusCode::OK, Json(v)).into_response(),
@@ -1875,6 +3239,16 @@ async fn daemon_health_handler(State(state): State<Arc<WebServerState>>) -> impl
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/daemon/status",
+    tag = "daemon",
+    security(()),
+    responses(
+        (status = 200, description = "Daemon status", body = DaemonStatus),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn daemon_status_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
     match state.daemon.get_daemon_status().await {
         Ok(status) => (StatusCode::OK, Json(status)).into_response(),
@@ -1946,6 +3320,19 @@ async fn ui_ui_assets_handler(Path(path): Path<String>) -> impl IntoResponse {
 // Auth handlers
 // ============================================================================
 
+/// Identities from OIDC sign in through their provider only
+fn is_federated(identity_id: &str) -> bool {
+    identity_id.starts_with(crate::auth::oidc::IDENTITY_PREFIX)
+}
+
+fn federated_identity_conflict() -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(serde_json::json!({"error": "identity signs in through single sign-on"})),
+    )
+        .into_response()
+}
+
 fn normalize_display_name(s: &str) -> String {
     s.trim().to_lowercase()
 }
@@ -1968,7 +3355,7 @@ fn totp_for_secret_b32(issuer: &str, label: &str, secret_b32: &str) -> anyhow::R
     )?)
 }
 
-fn constant_time_eq(a: &str, b: &str) -> bool {
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
     if a.len() != b.len() {
         return false;
     }
@@ -1998,25 +3385,57 @@ fn verify_totp_code(totp: &TOTP, code: &str) -> bool {
 
 async fn auth_create_identity_handler(
     State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
     Json(req): Json<CreateIdentityRequest>,
 ) -> impl IntoResponse {
     let display_name = normalize_display_name(&req.display_name);
     if display_name.is_empty() {
         return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error":"display_name required"}))).into_response();
     }
-    let role = req.role.unwrap_or_else(|| "admin".to_string());
+    let role = req.role.as_deref().map(str::trim).unwrap_or("viewer").to_string();
+    let policy = crate::auth::PolicyEngine::new();
+    if !policy.roles().iter().any(|r| r.id == role) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": format!("unknown role: {}", role)})),
+        )
+            .into_response();
+    }
     let id = Uuid::new_v4().to_string();
     let created_at = now_epoch_secs();
 
     let conn = state.db.connection();
     let conn = conn.lock();
+    // Anyone may create the first identity, which must be an admin to manage the rest
+    let caller = if no_identities(&conn) {
+        if !is_admin_role(&role) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "the first identity must be an admin"})),
+            )
+                .into_response();
+        }
+        id.clone()
+    } else {
+        match admin_caller(&conn, &headers, created_at) {
+            Ok(caller) => caller,
+            Err(response) => return response,
+        }
+    };
+
     let res = conn.execute(
         "INSERT INTO auth_identities (id, display_name, role, totp_secret_b32, totp_enabled, created_at) VALUES (?1, ?2, ?3, NULL, 0, ?4)",
         rusqlite::params![id, display_name, role, created_at],
     );
     match res {
         Ok(_) => {
-            let identity = AuthIdentity { id, display_name, role, totp_enabled: false, created_at };
+            audit_auth_event(
+                &conn,
+                crate::auth::AuthEventType::Registration,
+                &caller,
+                serde_json::json!({"identity_id": id, "role": role}),
+            );
+            let identity = AuthIdentity { id, display_name, role, totp_enabled: false, created_at, disabled: false };
             (StatusCode::OK, Json(CreateIdentityResponse { identity })).into_response()
         }
         Err(e) => (
@@ -2027,8 +3446,13 @@ async fn auth_create_identity_handler(
     }
 }
 
+/// Begin TOTP enrollment
+///
+/// Identities that can already sign in must re-enroll from a session of their
+/// own; one that lost its authenticator needs an admin's TOTP reset first.
 async fn auth_totp_begin_handler(
     State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
     Json(req): Json<BeginTotpEnrollRequest>,
 ) -> impl IntoResponse {
     let display_name = normalize_display_name(&req.display_name);
@@ -2057,18 +3481,22 @@ async fn auth_totp_begin_handler(
     let conn = state.db.connection();
     let conn = conn.lock();
     // Ensure identity exists; if not, create it on the fly.
-    let existing: Option<(String, String, String, i64)> = conn
-        .query_row(
-            "SELECT id, display_name, role, created_at FROM auth_identities WHERE display_name = ?1",
-            rusqlite::params![display_name],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
-        )
-        .optional()
-        .ok()
-        .flatten();
-
-    let (id, role, created_at) = match existing {
-        Some((id, _dn, role, created_at)) => (id, role, created_at),
+    let (id, role, created_at) = match find_identity(&conn, &display_name) {
+        Some(identity) if is_federated(&identity.id) => return federated_identity_conflict(),
+        Some(identity) if identity.disabled => return identity_disabled(),
+        Some(identity) => {
+            let now = now_epoch_secs();
+            if can_sign_in(&conn, &identity) && session_identity(&conn, &headers, now).as_deref() != Some(identity.id.as_str()) {
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    Json(serde_json::json!({"error":"sign in as this identity, or ask an admin to reset its TOTP"})),
+                )
+                    .into_response();
+            }
+            (identity.id, identity.role, identity.created_at)
+        }
+        // Only the first identity enrolls without an admin creating it
+        None if !no_identities(&conn) => return unknown_identity(),
         None => {
             let id = Uuid::new_v4().to_string();
             let role = "admin".to_string();
@@ -2086,10 +3514,13 @@ async fn auth_totp_begin_handler(
         rusqlite::params![secret_b32, id],
     );
 
-    let identity = AuthIdentity { id, display_name: label.clone(), role, totp_enabled: false, created_at };
+    let identity = AuthIdentity { id, display_name: label.clone(), role, totp_enabled: false, created_at, disabled: false };
     (StatusCode::OK, Json(BeginTotpEnrollResponse { identity, issuer, label, secret_b32, otpauth_uri, qr_svg })).into_response()
 }
 
+/// Finish TOTP enrollment, returning the identity's recovery codes
+///
+/// The codes are shown only this once; enrolling again replaces them.
 async fn auth_totp_confirm_handler(
     State(state): State<Arc<WebServerState>>,
     Json(req): Json<ConfirmTotpEnrollRequest>,
@@ -2099,19 +3530,22 @@ async fn auth_totp_confirm_handler(
 
     let conn = state.db.connection();
     let conn = conn.lock();
-    let row: Option<(String, String, i64, Option<String>, i64)> = conn
+    let row: Option<(String, String, i64, Option<String>, i64, i64)> = conn
         .query_row(
-            "SELECT id, role, created_at, totp_secret_b32, totp_enabled FROM auth_identities WHERE display_name = ?1",
+            "SELECT id, role, created_at, totp_secret_b32, totp_enabled, disabled FROM auth_identities WHERE display_name = ?1",
             rusqlite::params![display_name],
-            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)),
         )
         .optional()
         .ok()
         .flatten();
-    let (id, role, created_at, secret_opt, _enabled) = match row {
+    let (id, role, created_at, secret_opt, _enabled, disabled) = match row {
         Some(v) => v,
         None => return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error":"unknown identity"}))).into_response(),
     };
+    if disabled != 0 {
+        return identity_disabled();
+    }
     let secret_b32 = match secret_opt {
         Some(v) => v.to_string(),
         None => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error":"enrollment not started"}))).into_response(),
@@ -2130,12 +3564,22 @@ async fn auth_totp_confirm_handler(
         "UPDATE auth_identities SET totp_enabled = 1 WHERE id = ?1",
         rusqlite::params![id],
     );
-    let identity = AuthIdentity { id, display_name, role, totp_enabled: true, created_at };
-    (StatusCode::OK, Json(serde_json::json!({"ok": true, "identity": identity}))).into_response()
+    let recovery_codes = match generate_recovery_codes(&conn, &id, now_epoch_secs()) {
+        Ok(codes) => codes,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    };
+    let identity = AuthIdentity { id, display_name, role, totp_enabled: true, created_at, disabled: false };
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({"ok": true, "identity": identity, "recovery_codes": recovery_codes})),
+    )
+        .into_response()
 }
 
 async fn auth_totp_login_handler(
     State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    peer: Option<axum::extract::ConnectInfo<SocketAddr>>,
     Json(req): Json<LoginTotpRequest>,
 ) -> impl IntoResponse {
     let display_name = normalize_display_name(&req.display_name);
@@ -2148,20 +3592,23 @@ async fn auth_totp_login_handler(
     let conn_arc = state.db.connection();
     let conn = conn_arc.lock();
 
-    let row: Option<(String, String, i64, Option<String>, i64)> = conn
+    let row: Option<(String, String, i64, Option<String>, i64, i64)> = conn
         .query_row(
-            "SELECT id, role, created_at, totp_secret_b32, totp_enabled FROM auth_identities WHERE display_name = ?1",
+            "SELECT id, role, created_at, totp_secret_b32, totp_enabled, disabled FROM auth_identities WHERE display_name = ?1",
             rusqlite::params![display_name],
-            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)),
         )
         .optional()
         .ok()
         .flatten();
 
-    let (id, role, created_at, secret_opt, enabled) = match row {
+    let (id, role, created_at, secret_opt, enabled, disabled) = match row {
         Some(v) => v,
         None => return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error":"unknown identity"}))).into_response(),
     };
+    if disabled != 0 {
+        return identity_disabled();
+    }
 
     // Check lockout.
     let attempt: Option<(i64, i64)> = conn
@@ -2193,7 +3640,10 @@ async fn auth_totp_login_handler(
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
     };
 
-    if !verify_totp_code(&totp, &code) {
+    // A recovery code stands in for a lost authenticator, once
+    let totp_ok = verify_totp_code(&totp, &code);
+    let recovered = !totp_ok && consume_recovery_code(&conn, &id, &code, now);
+    if !totp_ok && !recovered {
         // Update attempts.
         let failed = attempt.map(|(f, _)| f).unwrap_or(0) + 1;
         let mut locked_until = 0i64;
@@ -2215,15 +3665,62 @@ async fn auth_totp_login_handler(
         rusqlite::params![id, now],
     );
 
+    if recovered {
+        let remaining = recovery_codes_remaining(&conn, &id);
+        audit_auth_event(
+            &conn,
+            crate::auth::AuthEventType::RecoveryCodeUsed,
+            &id,
+            serde_json::json!({"recovery_codes_remaining": remaining}),
+        );
+    }
+
+    let (token, expires_at) = issue_session(&conn, &id, &SessionClient::new(&headers, peer), now);
+    let preferences = load_preferences(&conn, &id);
+    let identity = AuthIdentity { id, display_name, role, totp_enabled: true, created_at, disabled: false };
+    login_response(&state, LoginResponse { token, expires_at, identity, preferences }, now)
+}
+
+/// A login's response; with cookie sessions the token moves from the body
+/// into the session cookie, and a fresh CSRF token is set
+fn login_response(state: &WebServerState, mut login: LoginResponse, now: i64) -> Response {
+    let Some(cfg) = &state.cfg.session_cookies else {
+        return (StatusCode::OK, Json(login)).into_response();
+    };
+    let max_age = login.expires_at - now;
+    let session_cookie = cfg.session_cookie(&std::mem::take(&mut login.token), max_age);
+    let csrf_cookie = cfg.csrf_cookie(&cookies::new_csrf_token(), max_age);
+    with_cookies((StatusCode::OK, Json(login)).into_response(), [session_cookie, csrf_cookie])
+}
+
+fn with_cookies(mut response: Response, cookies: impl IntoIterator<Item = String>) -> Response {
+    for cookie in cookies {
+        if let Ok(value) = axum::http::HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(axum::http::header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+/// Start a bearer session for an identity, returning its token and expiry
+fn issue_session(conn: &rusqlite::Connection, identity_id: &str, client: &SessionClient, now: i64) -> (String, i64) {
     let token = hex::encode(rand::random::<[u8; 32]>());
     let expires_at = now + AUTH_SESSION_TTL_SECS;
     let _ = conn.execute(
-        "INSERT INTO auth_sessions (token, identity_id, created_at, expires_at, last_seen_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![token, id, now, expires_at, now],
+        "INSERT INTO auth_sessions (token, identity_id, created_at, expires_at, last_seen_at, token_id, ip_address, user_agent) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![
+            token,
+            identity_id,
+            now,
+            expires_at,
+            now,
+            session_token_id(&token),
+            client.ip_address,
+            client.user_agent,
+        ],
     );
-
-    let identity = AuthIdentity { id, display_name, role, totp_enabled: true, created_at };
-    (StatusCode::OK, Json(LoginResponse { token, expires_at, identity })).into_response()
+    (token, expires_at)
 }
 
 async fn auth_whoami_handler(State(state): State<Arc<WebServerState>>, headers: axum::http::HeaderMap) -> impl IntoResponse {
@@ -2241,7 +3738,7 @@ async fn auth_whoami_handler(State(state): State<Arc<WebServerState>>, headers:
     let row: Option<(String, i64, String, String, i64, i64)> = conn
         .query_row(
             "SELECT s.identity_id, s.expires_at, i.display_name, i.role, i.created_at, i.totp_enabled \
-             FROM auth_sessions s JOIN auth_identities i ON i.id = s.identity_id WHERE s.token = ?1",
+             FROM auth_sessions s JOIN auth_identities i ON i.id = s.identity_id WHERE s.token = ?1 AND i.disabled = 0",
             rusqlite::params![token],
             |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)),
         )
@@ -2266,82 +3763,1359 @@ async fn auth_whoami_handler(State(state): State<Arc<WebServerState>>, headers:
         role,
         totp_enabled: enabled != 0,
         created_at,
+        disabled: false,
     };
-    (StatusCode::OK, Json(serde_json::json!({"identity": identity, "expires_at": expires_at}))).into_response()
+    let recovery_codes_remaining = recovery_codes_remaining(&conn, &identity.id);
+    let preferences = load_preferences(&conn, &identity.id);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "identity": identity,
+            "expires_at": expires_at,
+            "recovery_codes_remaining": recovery_codes_remaining,
+            "preferences": preferences,
+        })),
+    )
+        .into_response()
 }
 
 // ============================================================================
-// Auth status (for first-time setup detection)
+// Session management
 // ============================================================================
 
-/// Response for /api/auth/status - tells the UI whether this is first-time setup
-#[derive(Debug, Clone, Serialize)]
-struct AuthStatusResponse {
-    /// True if no identities exist (first-time setup needed)
-    needs_setup: bool,
-    /// Number of registered identities
-    identity_count: i64,
-    /// True if any identity has TOTP enabled
-    has_totp_enabled: bool,
+/// Where a session was started from, kept with it
+struct SessionClient {
+    ip_address: Option<String>,
+    user_agent: Option<String>,
 }
 
-async fn auth_status_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
-    let conn = state.db.connection();
-    let conn = conn.lock();
-    
-    let identity_count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM auth_identities", [], |r| r.get(0))
-        .unwrap_or(0);
-    
-    let totp_enabled_count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM auth_identities WHERE totp_enabled = 1", [], |r| r.get(0))
-        .unwrap_or(0);
-    
-    Json(AuthStatusResponse {
-        needs_setup: identity_count == 0,
-        identity_count,
-        has_totp_enabled: totp_enabled_count > 0,
-    })
+impl SessionClient {
+    fn new(headers: &axum::http::HeaderMap, peer: Option<axum::extract::ConnectInfo<SocketAddr>>) -> Self {
+        Self {
+            ip_address: peer.map(|p| p.0.ip().to_string()),
+            user_agent: headers
+                .get(axum::http::header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        }
+    }
 }
 
-// ============================================================================
-// MDM / mobileconfig handlers
-// ============================================================================
+/// Public handle of a session, so listing sessions never reveals their tokens
+fn session_token_id(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(&Sha256::digest(token.as_bytes())[..8])
+}
 
-use crate::mdm::{BridgeConfig, VpnConfig, VpnType, PeerEndpoint, ProfileRequest};
+/// An active session, without its token
+#[derive(Debug, Clone, Serialize)]
+struct SessionInfo {
+    token_id: String,
+    identity_id: String,
+    display_name: String,
+    created_at: i64,
+    expires_at: i64,
+    last_seen_at: i64,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    /// Whether this is the session making the request
+    current: bool,
+}
 
-async fn mdm_status_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
-    // Initialize MDM if not already done
-    if state.mdm.chain.read().await.is_none() {
-        if let Err(e) = state.mdm.init().await {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to init MDM: {}", e)
-            }))).into_response();
-        }
-    }
-    
-    let bridges = state.mdm.list_bridges().await;
-    let vpns = state.mdm.list_vpns().await;
-    let has_root_ca = state.mdm.get_root_ca_pem().await.is_some();
-    
-    Json(serde_json::json!({
-        "initialized": has_root_ca,
-        "org_name": state.mdm.config.org_name,
-        "domain": state.mdm.config.domain,
-        "bridge_count": bridges.len(),
-        "vpn_count": vpns.len(),
-        "cert_store_path": state.mdm.config.cert_store_path.display().to_string(),
-    })).into_response()
+#[derive(Debug, Default, Deserialize)]
+struct SessionsQuery {
+    /// Admins only: sessions of this identity instead of everyone's
+    identity_id: Option<String>,
 }
 
-async fn mdm_root_ca_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
-    // Initialize MDM if not already done
-    if state.mdm.chain.read().await.is_none() {
-        if let Err(e) = state.mdm.init().await {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to init MDM: {}", e)
-            }))).into_response();
-        }
+#[derive(Debug, Default, Deserialize)]
+struct RevokeAllSessionsRequest {
+    /// Admins only: log this identity out instead of the caller
+    #[serde(default)]
+    identity_id: Option<String>,
+}
+
+/// Caller of a session management request: identity ID, whether it is an
+/// admin, and its session token
+fn session_caller(
+    conn: &rusqlite::Connection,
+    headers: &axum::http::HeaderMap,
+    now: i64,
+) -> Result<(String, bool, String), Response> {
+    let unauthorized = |error: &str| {
+        (StatusCode::UNAUTHORIZED, Json(serde_json::json!({ "error": error }))).into_response()
+    };
+    let token = bearer_token(headers).ok_or_else(|| unauthorized("missing bearer token"))?;
+    let identity_id = session_identity(conn, headers, now).ok_or_else(|| unauthorized("invalid or expired session"))?;
+    let role: String = conn
+        .query_row(
+            "SELECT role FROM auth_identities WHERE id = ?1",
+            rusqlite::params![identity_id],
+            |r| r.get(0),
+        )
+        .map_err(|_| unauthorized("unknown identity"))?;
+    Ok((identity_id, is_admin_role(&role), token.to_string()))
+}
+
+/// Whether an identity's comma-separated roles grant `system:admin`
+fn is_admin_role(role: &str) -> bool {
+    let roles: Vec<String> = role.split(',').map(str::to_string).collect();
+    crate::auth::PolicyEngine::new().has_permission(&roles, crate::auth::ADMIN_PERMISSION)
+}
+
+/// Record a successful session or identity change in the auth audit log
+fn audit_auth_event(
+    conn: &rusqlite::Connection,
+    event: crate::auth::AuthEventType,
+    actor: &str,
+    details: serde_json::Value,
+) {
+    info!("{:?} by {}: {}", event, actor, details);
+    let _ = conn.execute(
+        "INSERT INTO auth_audit_log (id, timestamp, event_type, identity_id, success, details_json) \
+         VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+        rusqlite::params![
+            Uuid::new_v4().to_string(),
+            now_epoch_secs(),
+            serde_json::to_string(&event).unwrap_or_default(),
+            actor,
+            details.to_string(),
+        ],
+    );
+}
+
+/// End sessions, along with any single sign-on state kept for them
+fn revoke_sessions(conn: &rusqlite::Connection, tokens: &[String]) {
+    for token in tokens {
+        let _ = conn.execute("DELETE FROM auth_oidc_sessions WHERE session_token = ?1", rusqlite::params![token]);
+        let _ = conn.execute("DELETE FROM auth_sessions WHERE token = ?1", rusqlite::params![token]);
+    }
+}
+
+/// Unexpired sessions, newest first; `identity_id` limits them to one identity
+fn list_sessions(
+    conn: &rusqlite::Connection,
+    identity_id: Option<&str>,
+    current_token: &str,
+    now: i64,
+) -> rusqlite::Result<Vec<SessionInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.token, s.token_id, s.identity_id, i.display_name, s.created_at, s.expires_at, \
+         s.last_seen_at, s.ip_address, s.user_agent \
+         FROM auth_sessions s JOIN auth_identities i ON i.id = s.identity_id \
+         WHERE s.expires_at > ?1 AND (?2 IS NULL OR s.identity_id = ?2) \
+         ORDER BY s.created_at DESC",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![now, identity_id], |r| {
+        let token: String = r.get(0)?;
+        Ok(SessionInfo {
+            token_id: r.get::<_, Option<String>>(1)?.unwrap_or_else(|| session_token_id(&token)),
+            identity_id: r.get(2)?,
+            display_name: r.get(3)?,
+            created_at: r.get(4)?,
+            expires_at: r.get(5)?,
+            last_seen_at: r.get(6)?,
+            ip_address: r.get(7)?,
+            user_agent: r.get(8)?,
+            current: token == current_token,
+        })
+    })?;
+    rows.collect()
+}
+
+/// List active sessions: the caller's own, or everyone's for admins
+async fn auth_list_sessions_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Query(query): Query<SessionsQuery>,
+) -> Response {
+    let now = now_epoch_secs();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let (identity_id, admin, token) = match session_caller(&conn, &headers, now) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let filter = if admin { query.identity_id } else { Some(identity_id) };
+    match list_sessions(&conn, filter.as_deref(), &token, now) {
+        Ok(sessions) => (StatusCode::OK, Json(serde_json::json!({ "sessions": sessions }))).into_response(),
+        Err(e) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+        }
+    }
+}
+
+/// Revoke one session; callers may revoke their own, admins any
+async fn auth_revoke_session_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Path(token_id): Path<String>,
+) -> Response {
+    let now = now_epoch_secs();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let (identity_id, admin, _) = match session_caller(&conn, &headers, now) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let session: Option<(String, String)> = conn
+        .query_row(
+            "SELECT token, identity_id FROM auth_sessions WHERE token_id = ?1",
+            rusqlite::params![token_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .ok()
+        .flatten();
+    // Other identities' sessions look the same as missing ones to non-admins
+    let Some((token, owner)) = session.filter(|(_, owner)| admin || *owner == identity_id) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error":"session not found"}))).into_response();
+    };
+
+    revoke_sessions(&conn, &[token]);
+    audit_auth_event(
+        &conn,
+        crate::auth::AuthEventType::SessionRevoked,
+        &identity_id,
+        serde_json::json!({"token_id": token_id, "identity_id": owner}),
+    );
+    (StatusCode::OK, Json(serde_json::json!({"revoked": 1}))).into_response()
+}
+
+/// Log an identity out everywhere by revoking all of its sessions, the
+/// caller's own included; admins may name another identity
+async fn auth_revoke_all_sessions_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    body: Option<Json<RevokeAllSessionsRequest>>,
+) -> Response {
+    let now = now_epoch_secs();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let (identity_id, admin, _) = match session_caller(&conn, &headers, now) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+
+    let target = body.and_then(|Json(b)| b.identity_id).unwrap_or_else(|| identity_id.clone());
+    if target != identity_id && !admin {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "only admins can log out other identities"})),
+        )
+            .into_response();
+    }
+
+    let tokens: Vec<String> = conn
+        .prepare("SELECT token FROM auth_sessions WHERE identity_id = ?1")
+        .and_then(|mut stmt| stmt.query_map(rusqlite::params![target], |r| r.get(0))?.collect())
+        .unwrap_or_default();
+    revoke_sessions(&conn, &tokens);
+    audit_auth_event(
+        &conn,
+        crate::auth::AuthEventType::SessionRevoked,
+        &identity_id,
+        serde_json::json!({"identity_id": target, "revoked": tokens.len()}),
+    );
+    (StatusCode::OK, Json(serde_json::json!({"revoked": tokens.len()}))).into_response()
+}
+
+// ============================================================================
+// Recovery codes and identity lifecycle
+// ============================================================================
+
+fn identity_disabled() -> Response {
+    (StatusCode::FORBIDDEN, Json(serde_json::json!({"error":"identity is disabled"}))).into_response()
+}
+
+fn identity_by_id(conn: &rusqlite::Connection, identity_id: &str) -> Option<AuthIdentity> {
+    conn.query_row(
+        "SELECT id, display_name, role, totp_enabled, created_at, disabled FROM auth_identities WHERE id = ?1",
+        rusqlite::params![identity_id],
+        identity_from_row,
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+/// Whether an identity can sign in with TOTP or a passkey
+fn can_sign_in(conn: &rusqlite::Connection, identity: &AuthIdentity) -> bool {
+    let passkeys: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM webauthn_credentials WHERE identity_id = ?1",
+            rusqlite::params![identity.id],
+            |r| r.get(0),
+        )
+        .unwrap_or(0);
+    identity.totp_enabled || passkeys > 0
+}
+
+/// Recovery codes are stored hashed with their identity, ignoring case and dashes
+fn recovery_code_hash(identity_id: &str, code: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let code: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hex::encode(Sha256::digest(format!("{}:{}", identity_id, code).as_bytes()))
+}
+
+/// Replace an identity's recovery codes, returning the new ones
+fn generate_recovery_codes(conn: &rusqlite::Connection, identity_id: &str, now: i64) -> rusqlite::Result<Vec<String>> {
+    conn.execute("DELETE FROM auth_recovery_codes WHERE identity_id = ?1", rusqlite::params![identity_id])?;
+    let mut codes = Vec::with_capacity(AUTH_RECOVERY_CODE_COUNT);
+    while codes.len() < AUTH_RECOVERY_CODE_COUNT {
+        // 40 random bits as xxxx-xxxx
+        let raw = BASE32_NOPAD.encode(&rand::random::<[u8; 5]>()).to_lowercase();
+        let code = format!("{}-{}", &raw[..4], &raw[4..]);
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO auth_recovery_codes (identity_id, code_hash, created_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![identity_id, recovery_code_hash(identity_id, &code), now],
+        )?;
+        if inserted == 1 {
+            codes.push(code);
+        }
+    }
+    Ok(codes)
+}
+
+/// Use up one of an identity's recovery codes, if `code` is one
+fn consume_recovery_code(conn: &rusqlite::Connection, identity_id: &str, code: &str, now: i64) -> bool {
+    conn.execute(
+        "UPDATE auth_recovery_codes SET used_at = ?1 WHERE identity_id = ?2 AND code_hash = ?3 AND used_at IS NULL",
+        rusqlite::params![now, identity_id, recovery_code_hash(identity_id, code)],
+    )
+    .is_ok_and(|updated| updated == 1)
+}
+
+fn recovery_codes_remaining(conn: &rusqlite::Connection, identity_id: &str) -> i64 {
+    conn.query_row(
+        "SELECT COUNT(*) FROM auth_recovery_codes WHERE identity_id = ?1 AND used_at IS NULL",
+        rusqlite::params![identity_id],
+        |r| r.get(0),
+    )
+    .unwrap_or(0)
+}
+
+/// Most favorite VMs and tables with a saved column layout an identity may keep
+const PREFERENCES_MAX_ENTRIES: usize = 200;
+
+/// Console settings of one identity, stored with its auth records so they
+/// follow it from browser to browser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct UserPreferences {
+    /// `system`, `light` or `dark`
+    theme: String,
+    /// Table and output density: `comfortable` or `compact`
+    density: String,
+    /// IDs of VMs pinned to the top of lists
+    favorite_vms: Vec<String>,
+    /// Visible columns of each table, in order, keyed by table name
+    table_columns: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            theme: "system".to_string(),
+            density: "comfortable".to_string(),
+            favorite_vms: Vec::new(),
+            table_columns: BTreeMap::new(),
+        }
+    }
+}
+
+impl UserPreferences {
+    fn validate(&self) -> Result<(), String> {
+        if !["system", "light", "dark"].contains(&self.theme.as_str()) {
+            return Err(format!("theme '{}' is not one of system, light, dark", self.theme));
+        }
+        if !["comfortable", "compact"].contains(&self.density.as_str()) {
+            return Err(format!("density '{}' is not one of comfortable, compact", self.density));
+        }
+        if self.favorite_vms.len() > PREFERENCES_MAX_ENTRIES {
+            return Err(format!("at most {} favorite VMs", PREFERENCES_MAX_ENTRIES));
+        }
+        if self.table_columns.len() > PREFERENCES_MAX_ENTRIES
+            || self.table_columns.values().any(|columns| columns.len() > PREFERENCES_MAX_ENTRIES)
+        {
+            return Err(format!("at most {} tables and columns per table", PREFERENCES_MAX_ENTRIES));
+        }
+        Ok(())
+    }
+}
+
+/// Saved preferences of an identity, or the defaults
+fn load_preferences(conn: &rusqlite::Connection, identity_id: &str) -> UserPreferences {
+    conn.query_row(
+        "SELECT preferences_json FROM auth_preferences WHERE identity_id = ?1",
+        rusqlite::params![identity_id],
+        |r| r.get::<_, String>(0),
+    )
+    .ok()
+    .and_then(|json| serde_json::from_str(&json).ok())
+    .unwrap_or_default()
+}
+
+fn save_preferences(
+    conn: &rusqlite::Connection,
+    identity_id: &str,
+    preferences: &UserPreferences,
+    now: i64,
+) -> rusqlite::Result<()> {
+    let json = serde_json::to_string(preferences).unwrap_or_default();
+    conn.execute(
+        "INSERT INTO auth_preferences (identity_id, preferences_json, updated_at) VALUES (?1, ?2, ?3) \
+         ON CONFLICT(identity_id) DO UPDATE SET preferences_json = ?2, updated_at = ?3",
+        rusqlite::params![identity_id, json, now],
+    )?;
+    Ok(())
+}
+
+/// Preferences of the calling identity
+async fn get_preferences_handler(
+    State(state): State<Arc<WebServerState>>,
+    subject: Option<Extension<CallerSubject>>,
+) -> Response {
+    let identity_id = subject.map(|Extension(CallerSubject(s))| s).unwrap_or_default();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    (StatusCode::OK, Json(load_preferences(&conn, &identity_id))).into_response()
+}
+
+/// Replace the preferences of the calling identity
+async fn put_preferences_handler(
+    State(state): State<Arc<WebServerState>>,
+    subject: Option<Extension<CallerSubject>>,
+    Json(preferences): Json<UserPreferences>,
+) -> Response {
+    if let Err(error) = preferences.validate() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": error }))).into_response();
+    }
+    let identity_id = subject.map(|Extension(CallerSubject(s))| s).unwrap_or_default();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    match save_preferences(&conn, &identity_id, &preferences, now_epoch_secs()) {
+        Ok(()) => (StatusCode::OK, Json(preferences)).into_response(),
+        Err(e) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+        }
+    }
+}
+
+/// Whether an identity is the only enabled one with `system:admin`, so
+/// disabling, deleting or demoting it would leave nobody to manage the rest
+fn is_last_admin(conn: &rusqlite::Connection, identity_id: &str) -> bool {
+    let admins: Vec<String> = conn
+        .prepare("SELECT id, role FROM auth_identities WHERE disabled = 0")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, role)| is_admin_role(role))
+        .map(|(id, _)| id)
+        .collect();
+    admins == [identity_id]
+}
+
+fn last_admin_conflict() -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(serde_json::json!({"error": "identity is the last enabled admin"})),
+    )
+        .into_response()
+}
+
+/// Caller of an identity management request, who must be an admin
+fn admin_caller(conn: &rusqlite::Connection, headers: &axum::http::HeaderMap, now: i64) -> Result<String, Response> {
+    let (identity_id, admin, _) = session_caller(conn, headers, now)?;
+    if !admin {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({"error": "only admins can manage identities"})),
+        )
+            .into_response());
+    }
+    Ok(identity_id)
+}
+
+/// Whether no identity exists yet, so the first can be created without signing in
+fn no_identities(conn: &rusqlite::Connection) -> bool {
+    conn.query_row("SELECT COUNT(*) FROM auth_identities", [], |r| r.get::<_, i64>(0))
+        .is_ok_and(|count| count == 0)
+}
+
+fn unknown_identity() -> Response {
+    (StatusCode::NOT_FOUND, Json(serde_json::json!({"error":"unknown identity"}))).into_response()
+}
+
+/// Revoke every session of an identity, returning how many there were
+fn revoke_identity_sessions(conn: &rusqlite::Connection, identity_id: &str) -> usize {
+    let tokens: Vec<String> = conn
+        .prepare("SELECT token FROM auth_sessions WHERE identity_id = ?1")
+        .and_then(|mut stmt| stmt.query_map(rusqlite::params![identity_id], |r| r.get(0))?.collect())
+        .unwrap_or_default();
+    revoke_sessions(conn, &tokens);
+    tokens.len()
+}
+
+#[derive(Debug, Deserialize)]
+struct ResetTotpRequest {
+    identity_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetRoleRequest {
+    role: String,
+}
+
+/// List identities (admins only)
+async fn auth_list_identities_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    if let Err(response) = admin_caller(&conn, &headers, now_epoch_secs()) {
+        return response;
+    }
+    let identities: rusqlite::Result<Vec<AuthIdentity>> = conn
+        .prepare(
+            "SELECT id, display_name, role, totp_enabled, created_at, disabled FROM auth_identities \
+             ORDER BY display_name",
+        )
+        .and_then(|mut stmt| stmt.query_map([], identity_from_row)?.collect());
+    match identities {
+        Ok(identities) => (StatusCode::OK, Json(serde_json::json!({ "identities": identities }))).into_response(),
+        Err(e) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+        }
+    }
+}
+
+/// Reset an identity's TOTP so it can enroll a new authenticator (admins only)
+///
+/// Clears the secret, recovery codes and any lockout, and logs the identity
+/// out everywhere.
+async fn auth_totp_reset_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ResetTotpRequest>,
+) -> Response {
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let caller = match admin_caller(&conn, &headers, now_epoch_secs()) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let Some(identity) = identity_by_id(&conn, &req.identity_id) else {
+        return unknown_identity();
+    };
+    if is_federated(&identity.id) {
+        return federated_identity_conflict();
+    }
+
+    let _ = conn.execute(
+        "UPDATE auth_identities SET totp_secret_b32 = NULL, totp_enabled = 0 WHERE id = ?1",
+        rusqlite::params![identity.id],
+    );
+    let _ = conn.execute("DELETE FROM auth_recovery_codes WHERE identity_id = ?1", rusqlite::params![identity.id]);
+    let _ = conn.execute("DELETE FROM auth_attempts WHERE identity_id = ?1", rusqlite::params![identity.id]);
+    let revoked = revoke_identity_sessions(&conn, &identity.id);
+    audit_auth_event(
+        &conn,
+        crate::auth::AuthEventType::TotpReset,
+        &caller,
+        serde_json::json!({"identity_id": identity.id, "sessions_revoked": revoked}),
+    );
+    let identity = AuthIdentity { totp_enabled: false, ..identity };
+    (StatusCode::OK, Json(serde_json::json!({"ok": true, "identity": identity}))).into_response()
+}
+
+/// Replace the caller's recovery codes, returning the new ones
+async fn auth_recovery_codes_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let now = now_epoch_secs();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let (identity_id, _, _) = match session_caller(&conn, &headers, now) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    if !identity_by_id(&conn, &identity_id).is_some_and(|identity| identity.totp_enabled) {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error":"totp not enabled"}))).into_response();
+    }
+    match generate_recovery_codes(&conn, &identity_id, now) {
+        Ok(codes) => (StatusCode::OK, Json(serde_json::json!({"recovery_codes": codes}))).into_response(),
+        Err(e) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+        }
+    }
+}
+
+/// Disable or re-enable an identity (admins only); disabling logs it out
+fn set_identity_disabled(state: &WebServerState, headers: &axum::http::HeaderMap, identity_id: &str, disabled: bool) -> Response {
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let caller = match admin_caller(&conn, headers, now_epoch_secs()) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let Some(identity) = identity_by_id(&conn, identity_id) else {
+        return unknown_identity();
+    };
+    if disabled && is_last_admin(&conn, &identity.id) {
+        return last_admin_conflict();
+    }
+
+    let _ = conn.execute(
+        "UPDATE auth_identities SET disabled = ?1 WHERE id = ?2",
+        rusqlite::params![disabled, identity.id],
+    );
+    let (event, revoked) = if disabled {
+        (crate::auth::AuthEventType::IdentityDisabled, revoke_identity_sessions(&conn, &identity.id))
+    } else {
+        (crate::auth::AuthEventType::IdentityEnabled, 0)
+    };
+    audit_auth_event(
+        &conn,
+        event,
+        &caller,
+        serde_json::json!({"identity_id": identity.id, "sessions_revoked": revoked}),
+    );
+    let identity = AuthIdentity { disabled, ..identity };
+    (StatusCode::OK, Json(serde_json::json!({"identity": identity}))).into_response()
+}
+
+async fn auth_disable_identity_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Path(identity_id): Path<String>,
+) -> Response {
+    set_identity_disabled(&state, &headers, &identity_id, true)
+}
+
+async fn auth_enable_identity_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Path(identity_id): Path<String>,
+) -> Response {
+    set_identity_disabled(&state, &headers, &identity_id, false)
+}
+
+/// Delete an identity with its sessions, passkeys and recovery codes (admins only)
+///
+/// Its auth audit log entries are kept.
+async fn auth_delete_identity_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Path(identity_id): Path<String>,
+) -> Response {
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let caller = match admin_caller(&conn, &headers, now_epoch_secs()) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let Some(identity) = identity_by_id(&conn, &identity_id) else {
+        return unknown_identity();
+    };
+    if is_last_admin(&conn, &identity.id) {
+        return last_admin_conflict();
+    }
+
+    let revoked = revoke_identity_sessions(&conn, &identity.id);
+    for table in ["auth_recovery_codes", "auth_attempts", "auth_preferences", "webauthn_credentials"] {
+        // The passkey table only exists once passkeys have been configured
+        let _ = conn.execute(
+            &format!("DELETE FROM {} WHERE identity_id = ?1", table),
+            rusqlite::params![identity.id],
+        );
+    }
+    if let Err(e) = conn.execute("DELETE FROM auth_identities WHERE id = ?1", rusqlite::params![identity.id]) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response();
+    }
+    audit_auth_event(
+        &conn,
+        crate::auth::AuthEventType::IdentityDeleted,
+        &caller,
+        serde_json::json!({
+            "identity_id": identity.id,
+            "display_name": identity.display_name,
+            "sessions_revoked": revoked,
+        }),
+    );
+    (StatusCode::OK, Json(serde_json::json!({"deleted": identity.id}))).into_response()
+}
+
+/// Change an identity's role (admins only)
+///
+/// Federated identities take their roles from the identity provider instead.
+async fn auth_set_identity_role_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Path(identity_id): Path<String>,
+    Json(req): Json<SetRoleRequest>,
+) -> Response {
+    let role = req.role.trim().to_string();
+    let policy = crate::auth::PolicyEngine::new();
+    if !policy.roles().iter().any(|r| r.id == role) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": format!("unknown role: {}", role)})),
+        )
+            .into_response();
+    }
+
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let caller = match admin_caller(&conn, &headers, now_epoch_secs()) {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    let Some(identity) = identity_by_id(&conn, &identity_id) else {
+        return unknown_identity();
+    };
+    if is_federated(&identity.id) {
+        return federated_identity_conflict();
+    }
+    if !is_admin_role(&role) && is_last_admin(&conn, &identity.id) {
+        return last_admin_conflict();
+    }
+
+    let _ = conn.execute(
+        "UPDATE auth_identities SET role = ?1 WHERE id = ?2",
+        rusqlite::params![role, identity.id],
+    );
+    audit_auth_event(
+        &conn,
+        crate::auth::AuthEventType::RoleChange,
+        &caller,
+        serde_json::json!({"identity_id": identity.id, "from": identity.role, "to": role}),
+    );
+    let identity = AuthIdentity { role, ..identity };
+    (StatusCode::OK, Json(serde_json::json!({"identity": identity}))).into_response()
+}
+
+// ============================================================================
+// Passkey (WebAuthn) handlers
+// ============================================================================
+
+/// Passkey support for the relying party in `INFRASIM_WEBAUTHN_RP_ID`,
+/// `INFRASIM_WEBAUTHN_RP_ORIGIN` and `INFRASIM_WEBAUTHN_RP_NAME`
+fn webauthn_from_env(db: &Database) -> Option<WebAuthnProvider> {
+    let env = |var: &str, default: &str| {
+        std::env::var(var)
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| default.to_string())
+    };
+    let provider = WebAuthnProvider::new(
+        &env("INFRASIM_WEBAUTHN_RP_ID", "localhost"),
+        &env("INFRASIM_WEBAUTHN_RP_ORIGIN", "http://localhost:8080"),
+        &env("INFRASIM_WEBAUTHN_RP_NAME", "InfraSim"),
+        db.clone(),
+    )
+    .and_then(|provider| provider.init_schema().map(|_| provider));
+
+    match provider {
+        Ok(provider) => Some(provider),
+        Err(e) => {
+            warn!("passkey login disabled: {}", e);
+            None
+        }
+    }
+}
+
+fn not_configured(method: &str) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(serde_json::json!({"error": format!("{} is not configured", method)})),
+    )
+        .into_response()
+}
+
+fn identity_from_row(r: &rusqlite::Row) -> rusqlite::Result<AuthIdentity> {
+    Ok(AuthIdentity {
+        id: r.get(0)?,
+        display_name: r.get(1)?,
+        role: r.get(2)?,
+        totp_enabled: r.get::<_, i64>(3)? != 0,
+        created_at: r.get(4)?,
+        disabled: r.get::<_, i64>(5)? != 0,
+    })
+}
+
+fn find_identity(conn: &rusqlite::Connection, display_name: &str) -> Option<AuthIdentity> {
+    conn.query_row(
+        "SELECT id, display_name, role, totp_enabled, created_at, disabled FROM auth_identities WHERE display_name = ?1",
+        rusqlite::params![display_name],
+        identity_from_row,
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+fn bearer_token(headers: &axum::http::HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+}
+
+/// Identity of the request's unexpired bearer session
+fn session_identity(conn: &rusqlite::Connection, headers: &axum::http::HeaderMap, now: i64) -> Option<String> {
+    session_token_identity(conn, &bearer_token(headers)?, now)
+}
+
+/// Identity of a live session token
+fn session_token_identity(conn: &rusqlite::Connection, token: &str, now: i64) -> Option<String> {
+    conn.query_row(
+        "SELECT s.identity_id FROM auth_sessions s JOIN auth_identities i ON i.id = s.identity_id \
+         WHERE s.token = ?1 AND s.expires_at > ?2 AND i.disabled = 0",
+        rusqlite::params![token, now],
+        |r| r.get(0),
+    )
+    .optional()
+    .ok()
+    .flatten()
+}
+
+/// Begin registering a passkey
+///
+/// Identities that can already sign in, with TOTP or a passkey, must add
+/// passkeys from a session of their own. Unknown identities are created, as
+/// TOTP enrollment does.
+async fn auth_webauthn_register_begin_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<BeginWebauthnRequest>,
+) -> Response {
+    let Some(webauthn) = state.webauthn.as_ref() else {
+        return not_configured("passkey login");
+    };
+    let display_name = normalize_display_name(&req.display_name);
+    if display_name.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error":"display_name required"}))).into_response();
+    }
+    let now = now_epoch_secs();
+
+    // IMPORTANT: don't hold the sqlite lock across await.
+    let identity = {
+        let conn_arc = state.db.connection();
+        let conn = conn_arc.lock();
+        match find_identity(&conn, &display_name) {
+            Some(identity) if is_federated(&identity.id) => return federated_identity_conflict(),
+            Some(identity) if identity.disabled => return identity_disabled(),
+            Some(identity) => {
+                if can_sign_in(&conn, &identity) && session_identity(&conn, &headers, now).as_deref() != Some(identity.id.as_str()) {
+                    return (
+                        StatusCode::UNAUTHORIZED,
+                        Json(serde_json::json!({"error":"sign in as this identity to add a passkey"})),
+                    )
+                        .into_response();
+                }
+                identity
+            }
+            None => {
+                let identity = AuthIdentity {
+                    id: Uuid::new_v4().to_string(),
+                    display_name,
+                    role: "admin".to_string(),
+                    totp_enabled: false,
+                    created_at: now,
+                    disabled: false,
+                };
+                if let Err(e) = conn.execute(
+                    "INSERT INTO auth_identities (id, display_name, role, totp_secret_b32, totp_enabled, created_at) VALUES (?1, ?2, ?3, NULL, 0, ?4)",
+                    rusqlite::params![identity.id, identity.display_name, identity.role, identity.created_at],
+                ) {
+                    return (StatusCode::CONFLICT, Json(serde_json::json!({"error": e.to_string()}))).into_response();
+                }
+                identity
+            }
+        }
+    };
+
+    match webauthn.begin_registration(&identity.id, &identity.display_name).await {
+        Ok((challenge_id, options)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({"challenge_id": challenge_id, "options": options, "identity": identity})),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+async fn auth_webauthn_register_finish_handler(
+    State(state): State<Arc<WebServerState>>,
+    Json(req): Json<FinishWebauthnRegistrationRequest>,
+) -> Response {
+    let Some(webauthn) = state.webauthn.as_ref() else {
+        return not_configured("passkey login");
+    };
+    match webauthn.complete_registration(&req.challenge_id, req.credential, req.name).await {
+        Ok(credential) => {
+            info!("Registered passkey {} for identity {}", credential.id, credential.identity_id);
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "ok": true,
+                    "credential": {
+                        "id": credential.id,
+                        "name": credential.name,
+                        "created_at": credential.created_at,
+                    },
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+async fn auth_webauthn_login_begin_handler(
+    State(state): State<Arc<WebServerState>>,
+    Json(req): Json<BeginWebauthnRequest>,
+) -> Response {
+    let Some(webauthn) = state.webauthn.as_ref() else {
+        return not_configured("passkey login");
+    };
+    let display_name = normalize_display_name(&req.display_name);
+    let now = now_epoch_secs();
+
+    let identity_id = {
+        let conn_arc = state.db.connection();
+        let conn = conn_arc.lock();
+        let Some(identity) = find_identity(&conn, &display_name) else {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error":"unknown identity"}))).into_response();
+        };
+        if identity.disabled {
+            return identity_disabled();
+        }
+        // Identities locked out by failed TOTP codes can't switch to passkeys
+        let locked_until: i64 = conn
+            .query_row(
+                "SELECT locked_until FROM auth_attempts WHERE identity_id = ?1",
+                rusqlite::params![identity.id],
+                |r| r.get(0),
+            )
+            .unwrap_or(0);
+        if locked_until > now {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(serde_json::json!({"error":"locked", "locked_until": locked_until})),
+            )
+                .into_response();
+        }
+        identity.id
+    };
+
+    match webauthn.begin_authentication(&identity_id).await {
+        Ok((challenge_id, options)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({"challenge_id": challenge_id, "options": options})),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+/// Complete a passkey login, issuing a bearer session like TOTP login
+async fn auth_webauthn_login_finish_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+    peer: Option<axum::extract::ConnectInfo<SocketAddr>>,
+    Json(req): Json<FinishWebauthnLoginRequest>,
+) -> Response {
+    let Some(webauthn) = state.webauthn.as_ref() else {
+        return not_configured("passkey login");
+    };
+    let identity_id = match webauthn.complete_authentication(&req.challenge_id, req.credential).await {
+        Ok(identity_id) => identity_id,
+        Err(e) => return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": e}))).into_response(),
+    };
+
+    let now = now_epoch_secs();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let Some(identity) = identity_by_id(&conn, &identity_id) else {
+        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error":"unknown identity"}))).into_response();
+    };
+    if identity.disabled {
+        return identity_disabled();
+    }
+
+    let (token, expires_at) = issue_session(&conn, &identity.id, &SessionClient::new(&headers, peer), now);
+    info!("Passkey login for {}", identity.display_name);
+    let preferences = load_preferences(&conn, &identity.id);
+    login_response(&state, LoginResponse { token, expires_at, identity, preferences }, now)
+}
+
+// ============================================================================
+// OIDC single sign-on handlers
+// ============================================================================
+
+/// Single sign-on configured by `INFRASIM_OIDC_*`, when an issuer and client are set
+fn oidc_from_env() -> Option<OidcProvider> {
+    let env = |var: &str| {
+        std::env::var(var)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let issuer = env("INFRASIM_OIDC_ISSUER")?;
+    let Some(client_id) = env("INFRASIM_OIDC_CLIENT_ID") else {
+        warn!("single sign-on disabled: INFRASIM_OIDC_ISSUER is set without INFRASIM_OIDC_CLIENT_ID");
+        return None;
+    };
+
+    let config = OidcConfig {
+        provider: env("INFRASIM_OIDC_PROVIDER").unwrap_or_else(|| "oidc".to_string()),
+        issuer,
+        client_id,
+        client_secret: env("INFRASIM_OIDC_CLIENT_SECRET").unwrap_or_default(),
+        redirect_uri: env("INFRASIM_OIDC_REDIRECT_URI")
+            .unwrap_or_else(|| "http://localhost:8080/api/auth/oidc/callback".to_string()),
+        scopes: env("INFRASIM_OIDC_SCOPES")
+            .unwrap_or_else(|| "openid profile email".to_string())
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+        name_claim: env("INFRASIM_OIDC_NAME_CLAIM").unwrap_or_else(|| "preferred_username".to_string()),
+        roles_claim: env("INFRASIM_OIDC_ROLES_CLAIM"),
+        role_mappings: env("INFRASIM_OIDC_ROLE_MAP")
+            .map(|spec| parse_role_mappings(&spec))
+            .unwrap_or_default(),
+        default_role: env("INFRASIM_OIDC_DEFAULT_ROLE").unwrap_or_else(|| "viewer".to_string()),
+    };
+    info!("Single sign-on through {} ({})", config.provider, config.issuer);
+    Some(OidcProvider::new(config))
+}
+
+/// Create or update a federated identity with the roles its provider grants
+///
+/// Roles are stored comma-separated in the identity's `role`.
+fn save_federated_identity(
+    conn: &rusqlite::Connection,
+    provider: &str,
+    identity: &crate::auth::Identity,
+    now: i64,
+) -> rusqlite::Result<AuthIdentity> {
+    let display_name = normalize_display_name(&format!("{}:{}", provider, identity.display_name));
+    conn.execute(
+        "INSERT INTO auth_identities (id, display_name, role, totp_secret_b32, totp_enabled, created_at) VALUES (?1, ?2, ?3, NULL, 0, ?4) \
+         ON CONFLICT(id) DO UPDATE SET display_name = excluded.display_name, role = excluded.role",
+        rusqlite::params![identity.id, display_name, identity.roles.join(","), now],
+    )?;
+    conn.query_row(
+        "SELECT id, display_name, role, totp_enabled, created_at, disabled FROM auth_identities WHERE id = ?1",
+        rusqlite::params![identity.id],
+        identity_from_row,
+    )
+}
+
+/// Redirect to the OIDC provider's login page
+async fn auth_oidc_login_handler(State(state): State<Arc<WebServerState>>) -> Response {
+    let Some(oidc) = state.oidc.as_ref() else {
+        return not_configured("single sign-on");
+    };
+    match oidc.authorization_url().await {
+        Ok((url, _state)) => Redirect::to(&url).into_response(),
+        Err(e) => {
+            warn!("OIDC login failed: {}", e);
+            (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e}))).into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Complete single sign-on, handing the session to the console in the URL fragment
+async fn auth_oidc_callback_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(query): Query<OidcCallbackQuery>,
+    headers: axum::http::HeaderMap,
+    peer: Option<axum::extract::ConnectInfo<SocketAddr>>,
+) -> Response {
+    let Some(oidc) = state.oidc.as_ref() else {
+        return not_configured("single sign-on");
+    };
+    if let Some(error) = query.error {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": error, "error_description": query.error_description})),
+        )
+            .into_response();
+    }
+    let (Some(code), Some(oidc_state)) = (query.code, query.state) else {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error":"code and state required"}))).into_response();
+    };
+
+    let grant = match oidc.exchange_code(&code, &oidc_state).await {
+        Ok(grant) => grant,
+        Err(e) => {
+            warn!("OIDC callback failed: {}", e);
+            return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": e}))).into_response();
+        }
+    };
+
+    let now = now_epoch_secs();
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let identity = match save_federated_identity(&conn, &oidc.config().provider, &oidc.map_to_identity(&grant.user), now) {
+        Ok(identity) => identity,
+        Err(e) => return (StatusCode::CONFLICT, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    };
+    if identity.disabled {
+        return identity_disabled();
+    }
+
+    let (token, expires_at) = issue_session(&conn, &identity.id, &SessionClient::new(&headers, peer), now);
+    let _ = conn.execute(
+        "INSERT INTO auth_oidc_sessions (session_token, identity_id, refresh_token, created_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![token, identity.id, grant.refresh_token, now],
+    );
+    info!("OIDC login for {} with roles {}", identity.display_name, identity.role);
+
+    if let Some(cfg) = &state.cfg.session_cookies {
+        let max_age = expires_at - now;
+        let cookies = [cfg.session_cookie(&token, max_age), cfg.csrf_cookie(&cookies::new_csrf_token(), max_age)];
+        return with_cookies(Redirect::to(&format!("/#expires_at={}", expires_at)).into_response(), cookies);
+    }
+    // A fragment never reaches server logs or the Referer header
+    Redirect::to(&format!("/#session={}&expires_at={}", token, expires_at)).into_response()
+}
+
+/// Re-read an OIDC session's roles from its provider and extend the session
+///
+/// Sessions whose refresh the provider rejects are ended.
+async fn auth_oidc_refresh_handler(
+    State(state): State<Arc<WebServerState>>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let Some(oidc) = state.oidc.as_ref() else {
+        return not_configured("single sign-on");
+    };
+    let Some(token) = bearer_token(&headers) else {
+        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error":"missing bearer token"}))).into_response();
+    };
+    let now = now_epoch_secs();
+
+    // IMPORTANT: don't hold the sqlite lock across await.
+    let session: Option<(String, Option<String>)> = {
+        let conn_arc = state.db.connection();
+        let conn = conn_arc.lock();
+        conn.query_row(
+            "SELECT o.identity_id, o.refresh_token \
+             FROM auth_oidc_sessions o JOIN auth_sessions s ON s.token = o.session_token \
+             WHERE o.session_token = ?1 AND s.expires_at > ?2",
+            rusqlite::params![token, now],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()
+        .ok()
+        .flatten()
+    };
+    let Some((identity_id, Some(refresh_token))) = session else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error":"not a refreshable single sign-on session"})),
+        )
+            .into_response();
+    };
+
+    let refreshed = oidc
+        .refresh(&refresh_token)
+        .await
+        .map(|grant| (oidc.map_to_identity(&grant.user), grant.refresh_token))
+        .and_then(|(identity, refresh_token)| {
+            if identity.id == identity_id {
+                Ok((identity, refresh_token))
+            } else {
+                Err("Refreshed token belongs to another subject".to_string())
+            }
+        });
+
+    let conn_arc = state.db.connection();
+    let conn = conn_arc.lock();
+    let (identity, refresh_token) = match refreshed {
+        Ok(refreshed) => refreshed,
+        Err(e) => {
+            warn!("Ending OIDC session of {}: {}", identity_id, e);
+            let _ = conn.execute("DELETE FROM auth_oidc_sessions WHERE session_token = ?1", rusqlite::params![token]);
+            let _ = conn.execute("DELETE FROM auth_sessions WHERE token = ?1", rusqlite::params![token]);
+            return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": e}))).into_response();
+        }
+    };
+    let identity = match save_federated_identity(&conn, &oidc.config().provider, &identity, now) {
+        Ok(identity) => identity,
+        Err(e) => return (StatusCode::CONFLICT, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    };
+    if identity.disabled {
+        revoke_sessions(&conn, &[token.to_string()]);
+        return identity_disabled();
+    }
+
+    let expires_at = now + AUTH_SESSION_TTL_SECS;
+    let _ = conn.execute(
+        "UPDATE auth_sessions SET expires_at = ?1, last_seen_at = ?2 WHERE token = ?3",
+        rusqlite::params![expires_at, now, token],
+    );
+    let _ = conn.execute(
+        "UPDATE auth_oidc_sessions SET refresh_token = ?1 WHERE session_token = ?2",
+        rusqlite::params![refresh_token, token],
+    );
+    let preferences = load_preferences(&conn, &identity.id);
+    login_response(&state, LoginResponse { token: token.to_string(), expires_at, identity, preferences }, now)
+}
+
+// ============================================================================
+// Auth status (for first-time setup detection)
+// ============================================================================
+
+/// Response for /api/auth/status - tells the UI whether this is first-time setup
+#[derive(Debug, Clone, Serialize)]
+struct AuthStatusResponse {
+    /// True if no identities exist (first-time setup needed)
+    needs_setup: bool,
+    /// Number of registered identities
+    identity_count: i64,
+    /// True if any identity has TOTP enabled
+    has_totp_enabled: bool,
+    /// True if any passkey is registered
+    has_passkeys: bool,
+    /// OIDC provider offered for single sign-on
+    oidc_provider: Option<String>,
+    /// `cookie` when logins set a session cookie, else `bearer`
+    session_mode: &'static str,
+}
+
+async fn auth_status_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
+    let conn = state.db.connection();
+    let conn = conn.lock();
+    
+    let identity_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM auth_identities", [], |r| r.get(0))
+        .unwrap_or(0);
+    
+    let totp_enabled_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM auth_identities WHERE totp_enabled = 1", [], |r| r.get(0))
+        .unwrap_or(0);
+
+    let passkey_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM webauthn_credentials", [], |r| r.get(0))
+        .unwrap_or(0);
+    
+    Json(AuthStatusResponse {
+        needs_setup: identity_count == 0,
+        identity_count,
+        has_totp_enabled: totp_enabled_count > 0,
+        has_passkeys: passkey_count > 0,
+        oidc_provider: state.oidc.as_ref().map(|oidc| oidc.config().provider.clone()),
+        session_mode: session_mode(&state),
+    })
+}
+
+fn session_mode(state: &WebServerState) -> &'static str {
+    if state.cfg.session_cookies.is_some() {
+        "cookie"
+    } else {
+        "bearer"
+    }
+}
+
+/// CSRF token to send in `x-csrf-token` with mutating requests, set as the
+/// CSRF cookie if there isn't one yet
+async fn auth_csrf_handler(State(state): State<Arc<WebServerState>>, headers: axum::http::HeaderMap) -> Response {
+    let Some(cfg) = &state.cfg.session_cookies else {
+        return Json(serde_json::json!({"session_mode": session_mode(&state), "csrf_token": null})).into_response();
+    };
+    let existing = cookies::cookie(&headers, cookies::CSRF_COOKIE).map(str::to_string);
+    let token = existing.clone().unwrap_or_else(cookies::new_csrf_token);
+    let response = Json(serde_json::json!({
+        "session_mode": session_mode(&state),
+        "csrf_token": token,
+        "header": cookies::CSRF_HEADER,
+    }))
+    .into_response();
+    match existing {
+        Some(_) => response,
+        None => with_cookies(response, [cfg.csrf_cookie(&token, AUTH_SESSION_TTL_SECS)]),
+    }
+}
+
+/// End the caller's session and, with cookie sessions, clear its cookies
+async fn auth_logout_handler(State(state): State<Arc<WebServerState>>, headers: axum::http::HeaderMap) -> Response {
+    if let Some(token) = bearer_token(&headers) {
+        let conn_arc = state.db.connection();
+        let conn = conn_arc.lock();
+        revoke_sessions(&conn, &[token.to_string()]);
+    }
+    let response = StatusCode::NO_CONTENT.into_response();
+    match &state.cfg.session_cookies {
+        Some(cfg) => with_cookies(response, cfg.clear_cookies()),
+        None => response,
+    }
+}
+
+// ============================================================================
+// MDM / mobileconfig handlers
+// ============================================================================
+
+use crate::mdm::{BridgeConfig, EnrollmentError, VpnConfig, VpnType, PeerEndpoint, ProfileRequest};
+
+async fn mdm_status_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
+    // Initialize MDM if not already done
+    if state.mdm.chain.read().await.is_none() {
+        if let Err(e) = state.mdm.init().await {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "error": format!("Failed to init MDM: {}", e)
+            }))).into_response();
+        }
+    }
+    
+    let bridges = state.mdm.list_bridges().await;
+    let vpns = state.mdm.list_vpns().await;
+    let has_root_ca = state.mdm.get_root_ca_pem().await.is_some();
+    let enrollment_count = state.mdm.list_enrollments().await.len();
+    
+    Json(serde_json::json!({
+        "initialized": has_root_ca,
+        "org_name": state.mdm.config.org_name,
+        "domain": state.mdm.config.domain,
+        "bridge_count": bridges.len(),
+        "vpn_count": vpns.len(),
+        "enrollment_count": enrollment_count,
+        "cert_store_path": state.mdm.config.cert_store_path.display().to_string(),
+    })).into_response()
+}
+
+async fn mdm_root_ca_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
+    // Initialize MDM if not already done
+    if state.mdm.chain.read().await.is_none() {
+        if let Err(e) = state.mdm.init().await {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "error": format!("Failed to init MDM: {}", e)
+            }))).into_response();
+        }
     }
     
     match state.mdm.get_root_ca_pem().await {
@@ -2459,18 +5233,19 @@ async fn mdm_generate_profile_handler(
         }
     }
     
-    match state.mdm.generate_profile(&req.name).await {
-        Ok(xml) => {
+    let profile = async {
+        let xml = state.mdm.generate_profile(&req.name).await?;
+        let signed = state.mdm.sign_profile(&xml).await?;
+        anyhow::Ok((xml, signed))
+    };
+    match profile.await {
+        Ok((xml, signed)) => {
             // Return info about the generated profile
-            let (cert_path, key_path, chain_path) = state.mdm.signing_paths();
             Json(serde_json::json!({
                 "name": req.name,
                 "size_bytes": xml.len(),
+                "signed_size_bytes": signed.len(),
                 "unsigned_xml": String::from_utf8_lossy(&xml),
-                "signing_hint": format!(
-                    "To sign: openssl smime -sign -signer {} -inkey {} -certfile {} -nodetach -outform der -in profile.mobileconfig -out profile.signed.mobileconfig",
-                    cert_path.display(), key_path.display(), chain_path.display()
-                ),
                 "download_url": format!("/api/mdm/profile/{}", req.name.to_lowercase().replace(' ', "-")),
             })).into_response()
         }
@@ -2480,72 +5255,128 @@ async fn mdm_generate_profile_handler(
     }
 }
 
-async fn mdm_download_profile_handler(
+async fn mdm_download_profile_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    // Initialize MDM if not already done
+    if state.mdm.chain.read().await.is_none() {
+        if let Err(e) = state.mdm.init().await {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "error": format!("Failed to init MDM: {}", e)
+            }))).into_response();
+        }
+    }
+    
+    match state.mdm.generate_signed_profile(&name).await {
+        Ok(signed) => mobileconfig_response(signed, &name),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "error": format!("Failed to generate profile: {}", e)
+        }))).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateEnrollmentRequest {
+    label: String,
+    /// Token lifetime; defaults to a week
+    #[serde(default)]
+    ttl_secs: Option<i64>,
+}
+
+/// Map enrollment failures to statuses devices and operators can act on
+fn mdm_enrollment_error(e: anyhow::Error) -> Response {
+    let status = match e.downcast_ref::<EnrollmentError>() {
+        Some(EnrollmentError::NotFound) => StatusCode::NOT_FOUND,
+        Some(EnrollmentError::Expired | EnrollmentError::Revoked) => StatusCode::GONE,
+        Some(EnrollmentError::AlreadyEnrolled | EnrollmentError::NotEnrolled) => StatusCode::CONFLICT,
+        Some(EnrollmentError::ChallengeMismatch) => StatusCode::FORBIDDEN,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(serde_json::json!({ "error": e.to_string() }))).into_response()
+}
+
+fn mobileconfig_response(profile: Vec<u8>, filename: &str) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/x-apple-aspen-config")
+        .header("content-disposition", format!("attachment; filename=\"{}.mobileconfig\"", filename))
+        .body(axum::body::Body::from(profile))
+        .unwrap()
+        .into_response()
+}
+
+async fn mdm_list_enrollments_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
+    if state.mdm.chain.read().await.is_none() {
+        if let Err(e) = state.mdm.init().await {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "error": format!("Failed to init MDM: {}", e)
+            }))).into_response();
+        }
+    }
+
+    let enrollments = state.mdm.list_enrollments().await;
+    let revoked_cert_serials = state.mdm.revoked_cert_serials().await;
+    Json(serde_json::json!({
+        "enrollments": enrollments,
+        "revoked_cert_serials": revoked_cert_serials,
+    })).into_response()
+}
+
+async fn mdm_create_enrollment_handler(
+    State(state): State<Arc<WebServerState>>,
+    Json(req): Json<CreateEnrollmentRequest>,
+) -> impl IntoResponse {
+    match state.mdm.create_enrollment(&req.label, req.ttl_secs).await {
+        Ok(enrollment) => (StatusCode::CREATED, Json(serde_json::json!({
+            "enroll_url": state.mdm.enrollment_url(&enrollment.token),
+            "enrollment": enrollment,
+        }))).into_response(),
+        Err(e) => mdm_enrollment_error(e),
+    }
+}
+
+async fn mdm_revoke_enrollment_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.mdm.revoke_enrollment(&id).await {
+        Ok(enrollment) => Json(serde_json::json!({ "enrollment": enrollment })).into_response(),
+        Err(e) => mdm_enrollment_error(e),
+    }
+}
+
+/// Opened on the device: returns the Profile Service payload asking for its identity
+async fn mdm_enroll_handler(
     State(state): State<Arc<WebServerState>>,
-    Path(name): Path<String>,
+    Path(token): Path<String>,
 ) -> impl IntoResponse {
-    // Initialize MDM if not already done
-    if state.mdm.chain.read().await.is_none() {
-        if let Err(e) = state.mdm.init().await {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to init MDM: {}", e)
-            }))).into_response();
-        }
+    match state.mdm.enrollment_request_profile(&token).await {
+        Ok(profile) => mobileconfig_response(profile, "enroll"),
+        Err(e) => mdm_enrollment_error(e),
     }
-    
-    match state.mdm.generate_profile(&name).await {
-        Ok(xml) => {
-            Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/x-apple-aspen-config")
-                .header("content-disposition", format!("attachment; filename=\"{}.mobileconfig\"", name))
-                .body(axum::body::Body::from(xml))
-                .unwrap()
-                .into_response()
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-            "error": format!("Failed to generate profile: {}", e)
-        }))).into_response()
+}
+
+/// Posted by the device with its signed attributes: returns its identity and network profile
+async fn mdm_enroll_device_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(token): Path<String>,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    match state.mdm.complete_enrollment(&token, &body).await {
+        Ok(profile) => mobileconfig_response(profile, "device"),
+        Err(e) => mdm_enrollment_error(e),
     }
 }
 
-/// Webhook for config delivery - simple token-based access for devices
+/// Webhook for config delivery - refreshes the network profile of an enrolled device
 async fn webhook_config_handler(
     State(state): State<Arc<WebServerState>>,
     Path(token): Path<String>,
 ) -> impl IntoResponse {
-    // For MVP, accept any non-empty token and return the default profile
-    // In production, you'd validate the token against a database
-    if token.is_empty() || token.len() < 8 {
-        return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({
-            "error": "Invalid config token"
-        }))).into_response();
-    }
-    
-    // Initialize MDM if not already done
-    if state.mdm.chain.read().await.is_none() {
-        if let Err(e) = state.mdm.init().await {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to init MDM: {}", e)
-            }))).into_response();
-        }
-    }
-    
-    // Generate a profile named after the token (or use a default)
-    let profile_name = format!("device-{}", &token[..8]);
-    match state.mdm.generate_profile(&profile_name).await {
-        Ok(xml) => {
-            Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/x-apple-aspen-config")
-                .header("content-disposition", format!("attachment; filename=\"{}.mobileconfig\"", profile_name))
-                .body(axum::body::Body::from(xml))
-                .unwrap()
-                .into_response()
-        }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-            "error": format!("Failed to generate profile: {}", e)
-        }))).into_response()
+    match state.mdm.device_profile(&token).await {
+        Ok(profile) => mobileconfig_response(profile, "network"),
+        Err(e) => mdm_enrollment_error(e),
     }
 }
 
@@ -2554,6 +5385,7 @@ async fn webhook_config_handler(
 // ============================================================================
 
 use crate::docker::{ContainerManager, ApplianceBuildSpec, NetworkInterface, ImageOverlay, NetworkInterfaceType, OverlayType, OutputFormat, CloudInitConfig};
+use crate::docker::{ApplianceBuilder, BuildEvent, BuildProgress, BuildStatus, BuiltImage};
 
 async fn docker_status_handler() -> impl IntoResponse {
     let manager = ContainerManager::new();
@@ -2691,13 +5523,23 @@ struct ApplianceBuildRequest {
     output_format: Option<String>,
     #[serde(default)]
     cloud_init: Option<CloudInitConfig>,
+    #[serde(default)]
+    kernel: Option<String>,
+    #[serde(default)]
+    initrd: Option<String>,
+    #[serde(default)]
+    kernel_args: Option<String>,
+    #[serde(default)]
+    disk_size_mb: Option<i64>,
 }
 
 fn default_arch() -> String { "aarch64".to_string() }
 fn default_memory() -> i64 { 2048 }
 fn default_cpu() -> i32 { 2 }
 
+/// Start building an appliance image; follow it at `/api/docker/builds/:id/ws`
 async fn docker_build_appliance_handler(
+    State(state): State<Arc<WebServerState>>,
     Json(req): Json<ApplianceBuildRequest>,
 ) -> impl IntoResponse {
     // Generate default interfaces if none provided
@@ -2724,6 +5566,10 @@ async fn docker_build_appliance_handler(
         overlays: req.overlays,
         output_format,
         cloud_init: req.cloud_init,
+        kernel: req.kernel,
+        initrd: req.initrd,
+        kernel_args: req.kernel_args,
+        disk_size_mb: req.disk_size_mb,
     };
 
     // Generate Terraform HCL for the spec
@@ -2732,21 +5578,169 @@ async fn docker_build_appliance_handler(
     // Generate network interface HCL
     let network_hcl = ContainerManager::interfaces_to_terraform(&interfaces);
 
-    Json(serde_json::json!({
-        "status": "planned",
+    let build_id = Uuid::new_v4().to_string();
+    let progress = Arc::new(BuildProgress::new(build_id.clone(), spec.name.clone()));
+    let builder = match ApplianceBuilder::new(spec.clone(), state.build_dir.join(&build_id), progress.clone()) {
+        Ok(builder) => builder,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "error": e,
+            "spec": spec,
+        }))).into_response(),
+    };
+    state.builds.write().await.insert(build_id.clone(), progress.clone());
+
+    let build = progress.result();
+    let events = format!("/api/docker/builds/{}/ws", build_id);
+    let daemon = state.daemon.clone();
+    let name = spec.name.clone();
+    let parameters = serde_json::to_string(&spec).unwrap_or_default();
+    tokio::spawn(async move {
+        match builder.run().await {
+            Ok(image) => {
+                let build = VolumeBuild {
+                    build_type: infrasim_common::slsa::BUILD_TYPE_APPLIANCE.to_string(),
+                    parameters_json: parameters,
+                    dependencies: image
+                        .inputs
+                        .iter()
+                        .map(|input| BuildDependency {
+                            name: input.name.clone(),
+                            uri: input.uri.clone(),
+                            sha256: input.sha256().unwrap_or_default().to_string(),
+                        })
+                        .collect(),
+                    builder_version: HashMap::from([
+                        ("infrasim-web".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+                        (image.runtime.to_string(), image.runtime_version.clone()),
+                    ]),
+                    invocation_id: build_id.clone(),
+                    started_at: progress.result().started_at,
+                    finished_at: Utc::now().timestamp(),
+                };
+                let volume_id = match register_built_image(&daemon, &name, &build_id, &image, build).await {
+                    Ok(volume_id) => volume_id,
+                    Err(e) => return progress.fail(format!("Failed to register volume: {}", e)),
+                };
+                info!("Appliance build {} finished: {}", build_id, image.output);
+                progress.complete(&image, volume_id);
+            }
+            Err(e) => {
+                warn!("Appliance build {} failed: {}", build_id, e);
+                progress.fail(e);
+            }
+        }
+    });
+
+    (StatusCode::ACCEPTED, Json(serde_json::json!({
+        "status": "building",
+        "build": build,
         "spec": spec,
         "terraform_hcl": terraform_hcl,
         "network_hcl": network_hcl,
-        "next_steps": [
-            format!("Pull base image: docker pull {}", req.base_image),
-            "Apply overlays (files, packages, commands)",
-            "Generate qcow2 from container filesystem",
-            "Create VM with specified network interfaces",
-        ],
-        "hint": "Submit to /api/appliances to create and boot the appliance"
+        "events": events,
+    }))).into_response()
+}
+
+/// Register a built disk image with the daemon, which signs its provenance
+/// from `build`; container builds have no volume
+async fn register_built_image(
+    daemon: &DaemonProxy,
+    name: &str,
+    build_id: &str,
+    image: &BuiltImage,
+    build: VolumeBuild,
+) -> Result<Option<String>, anyhow::Error> {
+    let Some(format) = image.disk_format else {
+        return Ok(None);
+    };
+    // The build output stays the backing file; VMs write to an overlay
+    let spec = VolumeSpec {
+        kind: VolumeKind::Disk.into(),
+        source: image.output.clone(),
+        integrity: None,
+        read_only: false,
+        size_bytes: 0,
+        format: format.to_string(),
+        overlay: true,
+        encryption: None,
+    };
+    let labels = HashMap::from([("infrasim.io/build".to_string(), build_id.to_string())]);
+    daemon.create_built_volume(name, spec, labels, Some(build)).await.map(Some)
+}
+
+async fn docker_list_builds_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
+    let mut builds: Vec<_> = state.builds.read().await.values().map(|b| b.result()).collect();
+    builds.sort_by_key(|b| std::cmp::Reverse(b.started_at));
+    Json(serde_json::json!({
+        "count": builds.len(),
+        "builds": builds,
+    }))
+}
+
+async fn docker_get_build_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(build_id): Path<String>,
+) -> impl IntoResponse {
+    match state.builds.read().await.get(&build_id) {
+        Some(build) => Json(build.result()).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "error": "build not found",
+            "build_id": build_id,
+        }))).into_response(),
+    }
+}
+
+/// Stream a build's progress as JSON text frames: a snapshot, then status
+/// changes and log lines until it's done
+async fn docker_build_websocket_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(build_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let Some(build) = state.builds.read().await.get(&build_id).cloned() else {
+        return (StatusCode::NOT_FOUND, "Build not found").into_response();
+    };
+
+    let shutdown = state.shutdown.clone();
+    ws.on_upgrade(move |socket| shutdown.track(async move {
+        if let Err(e) = handle_build_websocket(socket, build).await {
+            debug!("Build WebSocket closed: {}", e);
+        }
     }))
 }
 
+async fn handle_build_websocket(mut socket: WebSocket, build: Arc<BuildProgress>) -> anyhow::Result<()> {
+    let (snapshot, mut events) = build.subscribe();
+    let finished = matches!(snapshot.status, BuildStatus::Complete | BuildStatus::Failed);
+    socket.send(Message::Text(serde_json::to_string(&BuildEvent::Snapshot { build: snapshot })?)).await?;
+    if finished {
+        let _ = socket.send(Message::Close(None)).await;
+        return Ok(());
+    }
+
+    loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let done = matches!(event, BuildEvent::Done { .. });
+                    socket.send(Message::Text(serde_json::to_string(&event)?)).await?;
+                    if done {
+                        let _ = socket.send(Message::Close(None)).await;
+                        return Ok(());
+                    }
+                }
+                // A slow client misses log lines but still sees the outcome
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
 fn generate_build_spec_terraform(spec: &ApplianceBuildSpec) -> String {
     let id = spec.name.to_lowercase().replace(' ', "_").replace('-', "_");
     format!(
@@ -2896,17 +5890,15 @@ async fn admin_restart_web_handler(
             .into_response();
     }
 
-    tokio::spawn(async {
-        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
-        // Exit code 75 (EX_TEMPFAIL) hints a supervisor to restart.
-        process::exit(75);
-    });
+    // Stops accepting connections, drains requests and console sessions,
+    // then exits for the supervisor to restart
+    state.shutdown.begin(true);
 
     (
         StatusCode::ACCEPTED,
         Json(serde_json::json!({
             "status": "restarting",
-            "note": "Process exiting now; supervisor should restart it."
+            "note": "Draining connections, then exiting; supervisor should restart it."
         })),
     )
         .into_response()
@@ -3034,6 +6026,144 @@ fn send_sigterm(pid: i32) -> anyhow::Result<i32> {
     }
 }
 
+/// Who is using the admin terminal, for the audit log
+struct TerminalCaller {
+    subject: String,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+}
+
+/// Record an admin terminal event in the auth audit log
+fn audit_admin_terminal(db: &Database, caller: &TerminalCaller, success: bool, details: serde_json::Value) {
+    info!("admin terminal ({}): {}", caller.subject, details);
+    let conn_arc = db.connection();
+    let conn = conn_arc.lock();
+    let _ = conn.execute(
+        "INSERT INTO auth_audit_log (id, timestamp, event_type, identity_id, ip_address, user_agent, success, details_json) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![
+            Uuid::new_v4().to_string(),
+            now_epoch_secs(),
+            serde_json::to_string(&crate::auth::AuthEventType::AdminTerminal).unwrap_or_default(),
+            caller.subject,
+            caller.ip_address,
+            caller.user_agent,
+            success,
+            details.to_string(),
+        ],
+    );
+}
+
+/// Restricted host shell over a WebSocket (requires `system:admin`)
+///
+/// Also needs web control enabled with an admin token configured. Browsers
+/// can't set headers on WebSocket upgrades, so the admin token is accepted as
+/// the `admin_token` query parameter too. Each text frame is one command line
+/// and is answered with one text frame; `crate::admin_terminal` decides what
+/// may run. Every command, refused or not, is written to the audit log.
+async fn admin_terminal_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: axum::http::HeaderMap,
+    subject: Option<Extension<CallerSubject>>,
+    peer: Option<axum::extract::ConnectInfo<SocketAddr>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let Some(control) = state.control.as_ref() else {
+        return (
+            StatusCode::PRECONDITION_FAILED,
+            Json(serde_json::json!({"error": "web-control-disabled"})),
+        )
+            .into_response();
+    };
+    let Some(expected) = control.admin_token.as_deref() else {
+        return (
+            StatusCode::PRECONDITION_FAILED,
+            Json(serde_json::json!({
+                "error": "admin-token-required",
+                "hint": "Set INFRASIM_WEB_ADMIN_TOKEN to use the admin terminal."
+            })),
+        )
+            .into_response();
+    };
+
+    let caller = TerminalCaller {
+        subject: subject.map(|Extension(CallerSubject(s))| s).unwrap_or_default(),
+        ip_address: peer.map(|p| p.0.ip().to_string()),
+        user_agent: headers
+            .get(axum::http::header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+    };
+    let token = headers
+        .get("x-infrasim-admin-token")
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| params.get("admin_token").map(String::as_str));
+    if token != Some(expected) {
+        audit_admin_terminal(&state.db, &caller, false, serde_json::json!({"action": "connect", "error": "missing-or-invalid-admin-token"}));
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({"error": "missing-or-invalid-admin-token"})),
+        )
+            .into_response();
+    }
+
+    let store = match state.daemon.get_daemon_status().await {
+        Ok(status) => PathBuf::from(status.store_path),
+        Err(e) => {
+            return (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+        }
+    };
+
+    audit_admin_terminal(&state.db, &caller, true, serde_json::json!({"action": "connect"}));
+    let shutdown = state.shutdown.clone();
+    ws.max_message_size(crate::admin_terminal::MAX_COMMAND_LEN)
+        .on_upgrade(move |socket| shutdown.track(async move {
+            if let Err(e) = handle_admin_terminal(socket, &state, &store, &caller).await {
+                debug!("Admin terminal WebSocket closed: {}", e);
+            }
+            audit_admin_terminal(&state.db, &caller, true, serde_json::json!({"action": "disconnect"}));
+        }))
+}
+
+/// Run command lines from the admin terminal until the client closes
+async fn handle_admin_terminal(
+    mut socket: WebSocket,
+    state: &WebServerState,
+    store: &std::path::Path,
+    caller: &TerminalCaller,
+) -> anyhow::Result<()> {
+    use crate::admin_terminal::{TerminalCommand, HELP};
+
+    socket
+        .send(Message::Text(format!("Store: {}\n{}", store.display(), HELP)))
+        .await?;
+    while let Some(msg) = socket.recv().await {
+        let line = match msg? {
+            Message::Text(line) => line,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let line = line.trim();
+        let result = match TerminalCommand::parse(line, store) {
+            Ok(cmd) => cmd.run().await.map_err(|e| (true, e)),
+            Err(e) => Err((false, e)),
+        };
+        let (reply, success, details) = match result {
+            Ok(output) => (output, true, serde_json::json!({"action": "command", "command": line, "allowed": true})),
+            Err((allowed, e)) => {
+                let details = serde_json::json!({"action": "command", "command": line, "allowed": allowed, "error": e});
+                (e, false, details)
+            }
+        };
+        audit_admin_terminal(&state.db, caller, success, details);
+
+        let reply = if reply.ends_with('\n') || reply.is_empty() { reply } else { reply + "\n" };
+        socket.send(Message::Text(reply)).await?;
+    }
+    Ok(())
+}
+
 async fn admin_page_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
     let enabled = state.control.is_some();
     let needs_token = state
@@ -3114,6 +6244,15 @@ async fn admin_page_handler(State(state): State<Arc<WebServerState>>) -> impl In
 // Inventory Handlers: Images (qcow2 volumes that are disk images)
 // ============================================================================
 
+#[utoipa::path(
+    get,
+    path = "/api/images",
+    tag = "images",
+    responses(
+        (status = 200, description = "Disk images", body = ImageList),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn list_images_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
     // Images are volumes with format=qcow2 or raw, typically used as boot disks
     match state.daemon.list_volumes().await {
@@ -3121,15 +6260,25 @@ async fn list_images_handler(State(state): State<Arc<WebServerState>>) -> impl I
             let images: Vec<_> = volumes.into_iter()
                 .filter(|v| v.kind == "disk" && (v.format == "qcow2" || v.format == "raw"))
                 .collect();
-            (StatusCode::OK, Json(serde_json::json!({
-                "images": images,
-                "count": images.len(),
-            }))).into_response()
+            (StatusCode::OK, Json(ImageList {
+                count: images.len(),
+                images,
+            })).into_response()
         }
         Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/images/{image_id}",
+    tag = "images",
+    params(("image_id" = String, Path, description = "Volume ID")),
+    responses(
+        (status = 200, description = "Disk image", body = VolumeInfo),
+        (status = 404, description = "No such image", body = ApiErrorBody)
+    )
+)]
 async fn get_image_handler(
     State(state): State<Arc<WebServerState>>,
     Path(image_id): Path<String>,
@@ -3144,16 +6293,35 @@ async fn get_image_handler(
 // Inventory Handlers: Volumes
 // ============================================================================
 
+#[utoipa::path(
+    get,
+    path = "/api/volumes",
+    tag = "volumes",
+    responses(
+        (status = 200, description = "Volumes", body = VolumeList),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn list_volumes_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
     match state.daemon.list_volumes().await {
-        Ok(volumes) => (StatusCode::OK, Json(serde_json::json!({
-            "volumes": volumes,
-            "count": volumes.len(),
-        }))).into_response(),
+        Ok(volumes) => (StatusCode::OK, Json(VolumeList {
+            count: volumes.len(),
+            volumes,
+        })).into_response(),
         Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/volumes/{volume_id}",
+    tag = "volumes",
+    params(("volume_id" = String, Path, description = "Volume ID")),
+    responses(
+        (status = 200, description = "Volume", body = VolumeInfo),
+        (status = 404, description = "No such volume", body = ApiErrorBody)
+    )
+)]
 async fn get_volume_handler(
     State(state): State<Arc<WebServerState>>,
     Path(volume_id): Path<String>,
@@ -3168,50 +6336,376 @@ async fn get_volume_handler(
 // Inventory Handlers: Snapshots
 // ============================================================================
 
+#[utoipa::path(
+    get,
+    path = "/api/snapshots",
+    tag = "snapshots",
+    params(("vm_id" = Option<String>, Query, description = "Only snapshots of this VM")),
+    responses(
+        (status = 200, description = "Snapshots", body = SnapshotList),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn list_snapshots_handler(
     State(state): State<Arc<WebServerState>>,
     Query(params): Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
     let vm_id = params.get("vm_id").map(|s| s.as_str());
     match state.daemon.list_snapshots(vm_id).await {
-        Ok(snapshots) => (StatusCode::OK, Json(serde_json::json!({
-            "snapshots": snapshots,
-            "count": snapshots.len(),
-        }))).into_response(),
+        Ok(snapshots) => (StatusCode::OK, Json(SnapshotList {
+            count: snapshots.len(),
+            snapshots,
+        })).into_response(),
+        Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/snapshots/{snapshot_id}",
+    tag = "snapshots",
+    params(("snapshot_id" = String, Path, description = "Snapshot ID")),
+    responses(
+        (status = 200, description = "Snapshot", body = SnapshotInfo),
+        (status = 404, description = "No such snapshot", body = ApiErrorBody),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
+async fn get_snapshot_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(snapshot_id): Path<String>,
+) -> impl IntoResponse {
+    // We need to list and filter since there's no get_snapshot by ID
+    match state.daemon.list_snapshots(None).await {
+        Ok(snapshots) => {
+            match snapshots.into_iter().find(|s| s.id == snapshot_id) {
+                Some(snap) => (StatusCode::OK, Json(snap)).into_response(),
+                None => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "snapshot not found"}))).into_response(),
+            }
+        }
+        Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/snapshot-policies",
+    tag = "snapshots",
+    params(("vm_id" = Option<String>, Query, description = "Only policies of this VM")),
+    responses(
+        (status = 200, description = "Snapshot policies", body = SnapshotPolicyList),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
+async fn list_snapshot_policies_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let vm_id = params.get("vm_id").map(|s| s.as_str());
+    match state.daemon.list_snapshot_policies(vm_id).await {
+        Ok(policies) => (StatusCode::OK, Json(SnapshotPolicyList {
+            count: policies.len(),
+            policies,
+        })).into_response(),
         Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
     }
 }
 
-async fn get_snapshot_handler(
+// ============================================================================
+// Audit Log Handlers
+// ============================================================================
+
+fn audit_entry_from_proto(event: AuditEvent) -> AuditEntry {
+    let non_empty = |s: String| (!s.is_empty()).then_some(s);
+    AuditEntry {
+        seq: event.seq,
+        timestamp: event.timestamp,
+        source: event.source,
+        identity: event.identity,
+        source_ip: non_empty(event.source_ip),
+        operation: event.operation,
+        resource_kind: non_empty(event.resource_kind),
+        resource_id: non_empty(event.resource_id),
+        status: event.status,
+        before_digest: non_empty(event.before_digest),
+        after_digest: non_empty(event.after_digest),
+        prev_hash: non_empty(event.prev_hash),
+        hash: non_empty(event.hash),
+        signature: non_empty(event.signature),
+        key_id: non_empty(event.key_id),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditQuery {
+    /// Age of the oldest entry, e.g. `24h`
+    since: Option<String>,
+    identity: Option<String>,
+    resource: Option<String>,
+    /// `web` or `daemon`
+    source: Option<String>,
+    limit: Option<usize>,
+}
+
+/// Audited mutating operations of the web server and the daemon, newest first
+///
+/// The web server's entries come from its own log and the daemon's over
+/// gRPC. When the daemon can't be reached, the web server's entries are
+/// still returned, with the error as `daemon_error`.
+async fn list_audit_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(query): Query<AuditQuery>,
+) -> Response {
+    let since = match query.since.as_deref().map(infrasim_common::types::parse_interval) {
+        Some(Ok(age)) => Some(now_epoch_secs() - age as i64),
+        Some(Err(e)) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+        }
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(DEFAULT_LIST_LIMIT);
+    let filter = |source: &str| AuditFilter {
+        since,
+        until: None,
+        source: Some(source.to_string()),
+        identity: query.identity.clone(),
+        resource_id: query.resource.clone(),
+        limit,
+    };
+    let wanted = |source: &str| query.source.as_deref().map_or(true, |s| s == source);
+
+    let mut events = Vec::new();
+    if wanted(AUDIT_SOURCE) {
+        match state.audit.list(&filter(AUDIT_SOURCE)) {
+            Ok(local) => events.extend(local),
+            Err(e) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()})))
+                    .into_response()
+            }
+        }
+    }
+    let mut daemon_error = None;
+    if wanted("daemon") {
+        match state.daemon.list_audit_events(&filter("daemon")).await {
+            Ok(remote) => events.extend(remote),
+            Err(e) => daemon_error = Some(e.to_string()),
+        }
+    }
+    events.sort_by_key(|e| std::cmp::Reverse((e.timestamp, e.seq)));
+    events.truncate(limit);
+
+    Json(serde_json::json!({
+        "events": events,
+        "daemon_error": daemon_error,
+    }))
+    .into_response()
+}
+
+// ============================================================================
+// Job Handlers
+// ============================================================================
+
+/// Request to queue a background job
+#[derive(Debug, Deserialize, ToSchema)]
+struct CreateJobBody {
+    #[schema(value_type = String, example = "backup")]
+    kind: infrasim_common::types::JobKind,
+    target: String,
+    #[serde(default)]
+    params: HashMap<String, String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    tag = "jobs",
+    params(("state" = Option<String>, Query, description = "Only jobs in this state, e.g. running")),
+    responses(
+        (status = 200, description = "Jobs", body = JobList),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
+async fn list_jobs_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let job_state = params
+        .get("state")
+        .and_then(|s| JobState::from_str_name(&format!("JOB_STATE_{}", s.to_uppercase())))
+        .unwrap_or(JobState::Unspecified);
+    match state.daemon.list_jobs(job_state).await {
+        Ok(jobs) => (StatusCode::OK, Json(JobList {
+            count: jobs.len(),
+            jobs,
+        })).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/jobs",
+    tag = "jobs",
+    request_body = CreateJobBody,
+    responses(
+        (status = 202, description = "Job queued", body = JobInfo),
+        (status = 409, description = "Invalid job", body = ApiErrorBody)
+    )
+)]
+async fn create_job_handler(
+    State(state): State<Arc<WebServerState>>,
+    Json(body): Json<CreateJobBody>,
+) -> impl IntoResponse {
+    let kind = JobKind::from_str_name(&format!("JOB_KIND_{}", body.kind.as_str().to_uppercase()))
+        .unwrap_or(JobKind::Unspecified);
+    match state.daemon.create_job(kind, body.target, body.params).await {
+        Ok(job) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/vms/{vm_id}/stats",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID")),
+    responses(
+        (status = 200, description = "Resource usage of a running VM", body = VmStatsInfo),
+        (status = 404, description = "No such VM", body = ApiErrorBody)
+    )
+)]
+async fn vm_stats_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+) -> impl IntoResponse {
+    match state.daemon.get_vm_stats(&vm_id).await {
+        Ok(stats) => (StatusCode::OK, Json(stats)).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{job_id}",
+    tag = "jobs",
+    params(("job_id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Job", body = JobInfo),
+        (status = 404, description = "No such job", body = ApiErrorBody)
+    )
+)]
+async fn get_job_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(job_id): Path<String>,
+) -> impl IntoResponse {
+    match state.daemon.get_job(&job_id).await {
+        Ok(job) => (StatusCode::OK, Json(job)).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/jobs/{job_id}/cancel",
+    tag = "jobs",
+    params(("job_id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Job after cancellation", body = JobInfo),
+        (status = 404, description = "No such job", body = ApiErrorBody)
+    )
+)]
+async fn cancel_job_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(job_id): Path<String>,
+) -> impl IntoResponse {
+    match state.daemon.cancel_job(&job_id).await {
+        Ok(job) => (StatusCode::OK, Json(job)).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+/// Stream a job as `job` server-sent events, one per change, until it finishes
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{job_id}/events",
+    tag = "jobs",
+    params(("job_id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "`job` events carrying the job as JSON", content_type = "text/event-stream", body = JobInfo),
+        (status = 404, description = "No such job", body = ApiErrorBody)
+    )
+)]
+async fn job_events_handler(
     State(state): State<Arc<WebServerState>>,
-    Path(snapshot_id): Path<String>,
-) -> impl IntoResponse {
-    // We need to list and filter since there's no get_snapshot by ID
-    match state.daemon.list_snapshots(None).await {
-        Ok(snapshots) => {
-            match snapshots.into_iter().find(|s| s.id == snapshot_id) {
-                Some(snap) => (StatusCode::OK, Json(snap)).into_response(),
-                None => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "snapshot not found"}))).into_response(),
-            }
+    Path(job_id): Path<String>,
+) -> Response {
+    use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+
+    // Subscribe before reading the job so no update in between is missed
+    let events = match state.daemon.watch_events("job".to_string(), job_id.clone()).await {
+        Ok(events) => events,
+        Err(e) => return daemon_error_response(e),
+    };
+    let job = match state.daemon.get_job(&job_id).await {
+        Ok(job) => job,
+        Err(e) => return daemon_error_response(e),
+    };
+
+    let daemon = state.daemon.clone();
+    let stream = futures::stream::unfold(Some((Some(job), events)), move |next| {
+        let daemon = daemon.clone();
+        let job_id = job_id.clone();
+        async move {
+            let (job, mut events) = next?;
+            let job = match job {
+                Some(job) => job,
+                None => {
+                    events.message().await.ok()??;
+                    daemon.get_job(&job_id).await.ok()?
+                }
+            };
+            let next = if job.is_finished() { None } else { Some((None, events)) };
+            Some((SseEvent::default().event("job").json_data(&job), next))
         }
-        Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
-    }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
 }
 
 // ============================================================================
 // Inventory Handlers: Networks
 // ============================================================================
 
+#[utoipa::path(
+    get,
+    path = "/api/networks",
+    tag = "networks",
+    responses(
+        (status = 200, description = "Networks", body = NetworkList),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn list_networks_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
     match state.daemon.list_networks().await {
-        Ok(networks) => (StatusCode::OK, Json(serde_json::json!({
-            "networks": networks,
-            "count": networks.len(),
-        }))).into_response(),
+        Ok(networks) => (StatusCode::OK, Json(NetworkList {
+            count: networks.len(),
+            networks,
+        })).into_response(),
         Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/networks/{network_id}",
+    tag = "networks",
+    params(("network_id" = String, Path, description = "Network ID")),
+    responses(
+        (status = 200, description = "Network", body = NetworkInfo),
+        (status = 404, description = "No such network", body = ApiErrorBody),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn get_network_handler(
     State(state): State<Arc<WebServerState>>,
     Path(network_id): Path<String>,
@@ -3227,20 +6721,266 @@ async fn get_network_handler(
     }
 }
 
+/// Download the pcap file of a network's last stopped capture
+#[utoipa::path(
+    get,
+    path = "/api/networks/{network_id}/capture.pcap",
+    tag = "networks",
+    params(("network_id" = String, Path, description = "Network ID")),
+    responses(
+        (status = 200, description = "Capture", content_type = "application/vnd.tcpdump.pcap", body = Vec<u8>),
+        (status = 404, description = "No such network or capture", body = ApiErrorBody),
+        (status = 409, description = "Capture is still running", body = ApiErrorBody)
+    )
+)]
+async fn download_network_capture_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(network_id): Path<String>,
+) -> Response {
+    use futures::StreamExt;
+
+    match state.daemon.read_network_capture(&network_id).await {
+        Ok(stream) => Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/vnd.tcpdump.pcap")
+            .header(
+                "content-disposition",
+                format!("attachment; filename=\"{}.pcap\"", network_id),
+            )
+            .body(axum::body::Body::from_stream(
+                stream.map(|chunk| chunk.map(|c| c.data)),
+            ))
+            .unwrap()
+            .into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
 // ============================================================================
 // Inventory Handlers: VMs
 // ============================================================================
 
+#[utoipa::path(
+    get,
+    path = "/api/vms",
+    tag = "vms",
+    responses(
+        (status = 200, description = "VMs", body = VmList),
+        (status = 502, description = "Daemon unreachable", body = ApiErrorBody)
+    )
+)]
 async fn list_vms_api_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
     match state.daemon.list_vms().await {
-        Ok(vms) => (StatusCode::OK, Json(serde_json::json!({
-            "vms": vms,
-            "count": vms.len(),
-        }))).into_response(),
+        Ok(vms) => (StatusCode::OK, Json(VmList {
+            count: vms.len(),
+            vms,
+        })).into_response(),
         Err(e) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+struct ExportVmQuery {
+    /// ova (default), raw or qcow2
+    format: Option<String>,
+}
+
+/// Download a stopped VM as an OVA, raw or qcow2 bundle
+#[utoipa::path(
+    get,
+    path = "/api/vms/{vm_id}/export",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID"), ExportVmQuery),
+    responses(
+        (status = 200, description = "Export bundle", content_type = "application/x-tar", body = Vec<u8>),
+        (status = 400, description = "Unknown format", body = ApiErrorBody),
+        (status = 409, description = "VM is running", body = ApiErrorBody)
+    )
+)]
+async fn export_vm_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+    Query(query): Query<ExportVmQuery>,
+) -> Response {
+    use futures::StreamExt;
+
+    let (format, extension) = match query.format.as_deref().unwrap_or("ova") {
+        "ova" => (ExportFormat::Ova, "ova"),
+        "raw" => (ExportFormat::Raw, "tar"),
+        "qcow2" => (ExportFormat::Qcow2, "tar"),
+        other => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": format!("unknown export format '{}'", other)})),
+            )
+                .into_response()
+        }
+    };
+
+    let name: String = state
+        .daemon
+        .get_vm(&vm_id)
+        .await
+        .map(|vm| vm.name)
+        .unwrap_or_else(|_| vm_id.clone())
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    match state.daemon.export_vm(&vm_id, format).await {
+        Ok(stream) => Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/x-tar")
+            .header(
+                "content-disposition",
+                format!("attachment; filename=\"{}.{}\"", name, extension),
+            )
+            .body(axum::body::Body::from_stream(
+                stream.map(|chunk| chunk.map(|c| c.data)),
+            ))
+            .unwrap()
+            .into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct GuestFileQuery {
+    /// Absolute destination in the guest; an existing file is replaced
+    path: String,
+}
+
+/// File written into a guest
+#[derive(Debug, Serialize, ToSchema)]
+struct GuestFileInfo {
+    path: String,
+    bytes_written: i64,
+}
+
+/// Upload a file into a running VM through qemu-guest-agent
+#[utoipa::path(
+    post,
+    path = "/api/vms/{vm_id}/files",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID"), GuestFileQuery),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "File written", body = GuestFileInfo),
+        (status = 409, description = "VM not running, no guest agent, or another upload in progress", body = ApiErrorBody)
+    )
+)]
+async fn upload_guest_file_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+    Query(query): Query<GuestFileQuery>,
+    body: axum::body::Body,
+) -> Response {
+    match state.daemon.copy_to_guest(&vm_id, &query.path, body.into_data_stream()).await {
+        Ok(copied) => {
+            info!("Uploaded {} bytes to {} in VM {}", copied.bytes_written, copied.path, vm_id);
+            Json(GuestFileInfo { path: copied.path, bytes_written: copied.bytes_written }).into_response()
+        }
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+/// HTTP status for a daemon call failure
+fn daemon_error_status(e: &anyhow::Error) -> StatusCode {
+    error_code_status(ErrorDetail::from_anyhow(e).code)
+}
+
+fn error_code_status(code: ErrorCode) -> StatusCode {
+    match code {
+        ErrorCode::NotFound => StatusCode::NOT_FOUND,
+        ErrorCode::InvalidArgument
+        | ErrorCode::InvalidState
+        | ErrorCode::AlreadyExists
+        | ErrorCode::InsufficientResources
+        | ErrorCode::GuestAgentUnavailable => StatusCode::CONFLICT,
+        ErrorCode::Unauthenticated => StatusCode::UNAUTHORIZED,
+        ErrorCode::PermissionDenied => StatusCode::FORBIDDEN,
+        _ => StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// Map a daemon call failure to an HTTP error response
+fn daemon_error_response(e: anyhow::Error) -> Response {
+    let detail = ErrorDetail::from_anyhow(&e);
+    (error_code_status(detail.code), Json(ApiErrorBody::from(detail))).into_response()
+}
+
+/// Error code for a failure the web server raised itself
+fn http_error_code(status: StatusCode) -> ErrorCode {
+    match status {
+        StatusCode::BAD_REQUEST
+        | StatusCode::UNPROCESSABLE_ENTITY
+        | StatusCode::PAYLOAD_TOO_LARGE
+        | StatusCode::RANGE_NOT_SATISFIABLE => ErrorCode::InvalidArgument,
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthenticated,
+        StatusCode::FORBIDDEN => ErrorCode::PermissionDenied,
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::CONFLICT => ErrorCode::InvalidState,
+        StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => ErrorCode::DaemonUnavailable,
+        StatusCode::GATEWAY_TIMEOUT => ErrorCode::Timeout,
+        _ => ErrorCode::Internal,
+    }
+}
+
+/// Largest error body [`error_envelope`] rewrites
+const ERROR_BODY_LIMIT: usize = 64 * 1024;
+
+/// Give every JSON error body the fields of [`ApiErrorBody`]
+///
+/// Handlers answer with `{"error": "..."}`; this fills in `code`,
+/// `category`, `retryable` and `hint` from the HTTP status unless the
+/// handler already set them from a daemon error.
+async fn error_envelope(response: Response) -> Response {
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, ERROR_BODY_LIMIT).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let mut value = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(value) => value,
+        Err(_) => return Response::from_parts(parts, axum::body::Body::from(bytes)),
+    };
+    let Some(object) = value.as_object_mut().filter(|o| !o.contains_key("code")) else {
+        return Response::from_parts(parts, axum::body::Body::from(bytes));
+    };
+    let Some(message) = object.get("error").and_then(|e| e.as_str()) else {
+        return Response::from_parts(parts, axum::body::Body::from(bytes));
+    };
+
+    let body = ApiErrorBody::from(ErrorDetail::new(http_error_code(status), message));
+    object.insert("code".to_string(), body.code.into());
+    object.insert("category".to_string(), body.category.into());
+    object.insert("retryable".to_string(), body.retryable.into());
+    if let Some(hint) = body.hint {
+        object.insert("hint".to_string(), hint.into());
+    }
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    Response::from_parts(parts, axum::body::Body::from(value.to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/vms/{vm_id}",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID")),
+    responses(
+        (status = 200, description = "VM", body = VmInfo),
+        (status = 404, description = "No such VM", body = ApiErrorBody)
+    )
+)]
 async fn get_vm_handler(
     State(state): State<Arc<WebServerState>>,
     Path(vm_id): Path<String>,
@@ -3251,19 +6991,303 @@ async fn get_vm_handler(
     }
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+struct CreateVmApiRequest {
+    name: String,
+    #[serde(default = "default_vm_arch")]
+    arch: String,
+    #[serde(default = "default_vm_machine")]
+    machine: String,
+    #[serde(default = "default_vm_cpu_cores")]
+    cpu_cores: i32,
+    #[serde(default = "default_vm_memory_mb")]
+    memory_mb: i64,
+    #[serde(default)]
+    volume_ids: Vec<String>,
+    #[serde(default)]
+    network_ids: Vec<String>,
+    #[serde(default)]
+    boot_disk_id: String,
+    #[serde(default)]
+    qos_profile_id: String,
+    #[serde(default)]
+    enable_tpm: bool,
+    #[serde(default)]
+    compatibility_mode: bool,
+    /// Lower priorities are paused first when the host runs short of memory
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    /// Start the VM once it is created
+    #[serde(default)]
+    auto_start: bool,
+}
+
+fn default_vm_arch() -> String {
+    "aarch64".to_string()
+}
+
+fn default_vm_machine() -> String {
+    "virt".to_string()
+}
+
+fn default_vm_cpu_cores() -> i32 {
+    2
+}
+
+fn default_vm_memory_mb() -> i64 {
+    2048
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+struct VmForceQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+/// Create a VM (requires `vm:create`, plus `vm:start` with `auto_start`)
+#[utoipa::path(
+    post,
+    path = "/api/vms",
+    tag = "vms",
+    request_body = CreateVmApiRequest,
+    responses(
+        (status = 201, description = "VM created", body = VmInfo),
+        (status = 400, description = "Invalid request", body = ApiErrorBody),
+        (status = 409, description = "Rejected by the daemon", body = ApiErrorBody)
+    )
+)]
+async fn create_vm_api_handler(
+    State(state): State<Arc<WebServerState>>,
+    roles: Option<Extension<CallerRoles>>,
+    Json(req): Json<CreateVmApiRequest>,
+) -> Response {
+    if req.auto_start {
+        if let Some(denied) = permission_denied(roles.as_deref(), "vm:start") {
+            return denied;
+        }
+    }
+    if req.name.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "name is required"}))).into_response();
+    }
+
+    let spec = VmSpec {
+        arch: req.arch,
+        machine: req.machine,
+        cpu_cores: req.cpu_cores,
+        memory_mb: req.memory_mb,
+        compatibility_mode: req.compatibility_mode,
+        volume_ids: req.volume_ids,
+        network_ids: req.network_ids,
+        qos_profile_id: req.qos_profile_id,
+        enable_tpm: req.enable_tpm,
+        boot_disk_id: req.boot_disk_id,
+        extra_args: HashMap::new(),
+        network_interfaces: vec![],
+        shared_folders: vec![],
+        priority: req.priority,
+    };
+    let vm_id = match state.daemon.create_vm_from_spec(req.name.trim(), spec, req.labels).await {
+        Ok(id) => id,
+        Err(e) => return daemon_error_response(e),
+    };
+    info!("Created VM {} ({})", req.name.trim(), vm_id);
+
+    if req.auto_start {
+        if let Err(e) = state.daemon.start_vm(&vm_id).await {
+            warn!("Failed to start VM {}: {}", vm_id, e);
+            return daemon_error_response(e);
+        }
+    }
+
+    match state.daemon.get_vm(&vm_id).await {
+        Ok(vm) => (StatusCode::CREATED, Json(vm)).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+/// Start a VM (requires `vm:start`)
+#[utoipa::path(
+    post,
+    path = "/api/vms/{vm_id}/start",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID")),
+    responses(
+        (status = 200, description = "VM after the call", body = VmInfo),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 409, description = "Not possible in the VM's state", body = ApiErrorBody)
+    )
+)]
+async fn start_vm_api_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+) -> Response {
+    if let Err(e) = state.daemon.start_vm(&vm_id).await {
+        return daemon_error_response(e);
+    }
+    info!("Started VM {}", vm_id);
+    vm_response(&state, &vm_id).await
+}
+
+/// Stop a VM (requires `vm:stop`)
+#[utoipa::path(
+    post,
+    path = "/api/vms/{vm_id}/stop",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID"), VmForceQuery),
+    responses(
+        (status = 200, description = "VM after the call", body = VmInfo),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 409, description = "Not possible in the VM's state", body = ApiErrorBody)
+    )
+)]
+async fn stop_vm_api_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+    Query(query): Query<VmForceQuery>,
+) -> Response {
+    if let Err(e) = state.daemon.stop_vm(&vm_id, query.force).await {
+        return daemon_error_response(e);
+    }
+    info!("Stopped VM {}", vm_id);
+    vm_response(&state, &vm_id).await
+}
+
+/// Stop a VM if it is running, then start it (requires `vm:stop` and `vm:start`)
+#[utoipa::path(
+    post,
+    path = "/api/vms/{vm_id}/restart",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID"), VmForceQuery),
+    responses(
+        (status = 200, description = "VM after the call", body = VmInfo),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 409, description = "Not possible in the VM's state", body = ApiErrorBody)
+    )
+)]
+async fn restart_vm_api_handler(
+    State(state): State<Arc<WebServerState>>,
+    roles: Option<Extension<CallerRoles>>,
+    Path(vm_id): Path<String>,
+    Query(query): Query<VmForceQuery>,
+) -> Response {
+    // The route needs `vm:stop`
+    if let Some(denied) = permission_denied(roles.as_deref(), "vm:start") {
+        return denied;
+    }
+
+    let vm = match state.daemon.get_vm(&vm_id).await {
+        Ok(vm) => vm,
+        Err(e) => return daemon_error_response(e),
+    };
+    if matches!(vm.state.as_str(), "running" | "paused" | "suspended") {
+        if let Err(e) = state.daemon.stop_vm(&vm_id, query.force).await {
+            return daemon_error_response(e);
+        }
+    }
+    if let Err(e) = state.daemon.start_vm(&vm_id).await {
+        return daemon_error_response(e);
+    }
+    info!("Restarted VM {}", vm_id);
+    vm_response(&state, &vm_id).await
+}
+
+/// Delete a VM (requires `vm:delete`)
+#[utoipa::path(
+    delete,
+    path = "/api/vms/{vm_id}",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID"), VmForceQuery),
+    responses(
+        (status = 200, description = "VM deleted", body = serde_json::Value),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 409, description = "VM can't be deleted", body = ApiErrorBody)
+    )
+)]
+async fn delete_vm_api_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+    Query(query): Query<VmForceQuery>,
+) -> Response {
+    if let Err(e) = state.daemon.delete_vm(&vm_id, query.force).await {
+        return daemon_error_response(e);
+    }
+    info!("Deleted VM {}", vm_id);
+    (StatusCode::OK, Json(serde_json::json!({"deleted": vm_id}))).into_response()
+}
+
+/// `POST` form of [`delete_vm_api_handler`], for clients that can't send `DELETE`
+#[utoipa::path(
+    post,
+    path = "/api/vms/{vm_id}/delete",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID"), VmForceQuery),
+    responses(
+        (status = 200, description = "VM deleted", body = serde_json::Value),
+        (status = 404, description = "No such VM", body = ApiErrorBody),
+        (status = 409, description = "VM can't be deleted", body = ApiErrorBody)
+    )
+)]
+async fn post_delete_vm_api_handler(
+    state: State<Arc<WebServerState>>,
+    vm_id: Path<String>,
+    query: Query<VmForceQuery>,
+) -> Response {
+    delete_vm_api_handler(state, vm_id, query).await
+}
+
+/// Current state of a VM after a lifecycle call
+async fn vm_response(state: &WebServerState, vm_id: &str) -> Response {
+    match state.daemon.get_vm(vm_id).await {
+        Ok(vm) => (StatusCode::OK, Json(vm)).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
+/// Resolve the client's address through trusted proxies, so audit logs and
+/// sessions name the client rather than the proxy, and refuse WebSocket
+/// handshakes from pages on origins that aren't allowed
+async fn client_address_middleware(state: Arc<WebServerState>, mut req: Request, next: middleware::Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<axum::extract::ConnectInfo<SocketAddr>>()
+        .map(|peer| peer.0);
+    let peer_ip = peer.map(|p| p.ip()).unwrap_or(std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED));
+
+    if is_websocket_upgrade(req.headers()) && !state.proxy.origin_allowed(req.headers(), peer_ip) {
+        warn!("Refused WebSocket from {} with origin {:?}", peer_ip, req.headers().get(axum::http::header::ORIGIN));
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"error": "cross-origin WebSocket"}))).into_response();
+    }
+    if let Some(peer) = peer {
+        let client = state.proxy.client_ip(req.headers(), peer.ip());
+        if client != peer.ip() {
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::new(client, peer.port())));
+        }
+    }
+    next.run(req).await
+}
+
 async fn auth_middleware_inner(
     state: Arc<WebServerState>,
-    req: Request,
+    mut req: Request,
     next: middleware::Next,
 ) -> Response {
-    let path = req.uri().path();
+    if state.cfg.session_cookies.is_some() {
+        if let Err(denied) = adopt_session_cookie(&mut req) {
+            return denied;
+        }
+    }
+
+    let path = req.uri().path().to_string();
     
     // =========================================================================
     // Static Asset Policy (Non-Negotiable)
     // =========================================================================
     // /ui/* must be publicly readable (JS, CSS, HTML, fonts, images)
-    // /api/* remains authenticated
-    // /api/admin/* remains admin-token gated
+    // /api/* remains authenticated, and each route RBAC-checked (auth::routes)
+    // /api/admin/* remains admin-token gated and needs the admin role
     // /api/health and /api/ui/manifest are public for monitoring/provenance
     // =========================================================================
     
@@ -3294,8 +7318,13 @@ async fn auth_middleware_inner(
         // VNC HTML pages (legacy)
         || path == "/vnc.html"
         || path == "/vnc_lite.html"
+        || path == "/console/serial.html"
+        || path == "/console/admin-terminal.html"
         // Auth endpoints (TOTP login/enrollment)
         || path.starts_with("/api/auth/")
+        // Device enrollment and config delivery: the enrollment token is the credential
+        || path.starts_with("/mdm/enroll/")
+        || path.starts_with("/webhook/config/")
         // Public API endpoints
         || path == "/api/health"
         || path == "/api/ui/manifest"
@@ -3306,24 +7335,20 @@ async fn auth_middleware_inner(
     // WebSocket paths - auth handled at connection time
     let is_websocket_path = path.starts_with("/websockify/");
     
-    if is_public_path || is_websocket_path {
-        return next.run(req).await;
+    // Unauthenticated deployments and the dev bypass act as admin.
+    if matches!(state.cfg.auth, WebUiAuth::None)
+        || (path.starts_with("/api/") && dev_bypass_enabled && dev_header_ok)
+    {
+        return authorize(&state, "anonymous", CallerRoles::admin(), req, next).await;
     }
 
-    // If auth is disabled, allow.
-    if matches!(state.cfg.auth, WebUiAuth::None) {
+    if is_public_path || is_websocket_path {
         return next.run(req).await;
     }
 
     // JWT mode: validate and allow.
-    if let WebUiAuth::Jwt(cfg) = &state.cfg.auth {
-        let auth_header = req
-            .headers()
-            .get(axum::http::header::AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("");
-
-        let token = auth_header.strip_prefix("Bearer ").unwrap_or("");
+    if let (WebUiAuth::Jwt(cfg), Some(jwks)) = (&state.cfg.auth, &state.jwks) {
+        let token = request_bearer_token(&req);
         if token.is_empty() {
             return (
                 StatusCode::UNAUTHORIZED,
@@ -3332,10 +7357,10 @@ async fn auth_middleware_inner(
                 .into_response();
         }
 
-        match verify_jwt_with_local_jwks(token, cfg) {
-            Ok(_td) => {
-                // TODO: attach claims into request extensions for RBAC.
-                return next.run(req).await;
+        match verify_jwt(&token, cfg, jwks).await {
+            Ok(td) => {
+                let subject = td.claims.sub.unwrap_or_else(|| "jwt".to_string());
+                return authorize(&state, &subject, CallerRoles(td.claims.roles), req, next).await;
             }
             Err(e) => {
                 return (
@@ -3358,13 +7383,7 @@ async fn auth_middleware_inner(
         WebUiAuth::None => None,
     };
 
-    let auth_header = req
-        .headers()
-        .get(axum::http::header::AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-
-    let provided = auth_header.strip_prefix("Bearer ").unwrap_or("");
+    let provided = request_bearer_token(&req);
 
     if provided.is_empty() {
         return (
@@ -3376,7 +7395,7 @@ async fn auth_middleware_inner(
 
     if let Some(expected) = expected {
         if provided == expected {
-            return next.run(req).await;
+            return authorize(&state, "token", CallerRoles::admin(), req, next).await;
         }
     }
 
@@ -3384,51 +7403,169 @@ async fn auth_middleware_inner(
     let now = now_epoch_secs();
 
     // IMPORTANT: don't hold the sqlite lock across await.
-    let (allowed, error_response) = {
+    let (identity, error_response) = {
         let conn_arc = state.db.connection();
         let conn = conn_arc.lock();
 
-        let session: Option<i64> = conn
+        let session: Option<(i64, String, String)> = conn
             .query_row(
-                "SELECT expires_at FROM auth_sessions WHERE token = ?1",
+                "SELECT s.expires_at, i.id, i.role \
+                 FROM auth_sessions s JOIN auth_identities i ON i.id = s.identity_id \
+                 WHERE s.token = ?1 AND i.disabled = 0",
                 rusqlite::params![provided],
-                |r| Ok(r.get(0)?),
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
             )
             .optional()
             .ok()
             .flatten();
 
         match session {
-            Some(expires_at) if expires_at > now => {
+            Some((expires_at, identity_id, role)) if expires_at > now => {
                 let _ = conn.execute(
                     "UPDATE auth_sessions SET last_seen_at = ?1 WHERE token = ?2",
                     rusqlite::params![now, provided],
                 );
-                (true, None)
+                (Some((identity_id, role)), None)
             }
             Some(_) => {
                 let _ = conn.execute("DELETE FROM auth_sessions WHERE token = ?1", rusqlite::params![provided]);
-                (false, Some((StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": "expired"}))).into_response()))
+                (None, Some((StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": "expired"}))).into_response()))
             }
             None => {
-                (false, Some((StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": "missing or invalid bearer token"}))).into_response()))
+                (None, Some((StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": "missing or invalid bearer token"}))).into_response()))
             }
         }
     };
 
-    if !allowed {
+    let Some((identity_id, role)) = identity else {
         return error_response.unwrap_or_else(|| {
             (StatusCode::UNAUTHORIZED, Json(serde_json::json!({"error": "unauthorized"}))).into_response()
         });
+    };
+
+    // Federated identities can hold several roles
+    let roles = role.split(',').map(str::to_string).collect();
+    authorize(&state, &identity_id, CallerRoles(roles), req, next).await
+}
+
+/// Treat a session cookie as the request's bearer token; mutating requests
+/// must show they come from the console with the CSRF token (WebSocket
+/// upgrades are held to their `Origin` by [`client_address_middleware`])
+fn adopt_session_cookie(req: &mut Request) -> Result<(), Response> {
+    let headers = req.headers();
+    if headers.contains_key(axum::http::header::AUTHORIZATION) {
+        return Ok(());
     }
+    let Some(token) = cookies::cookie(headers, cookies::SESSION_COOKIE) else {
+        return Ok(());
+    };
 
-    next.run(req).await
+    let forbidden = |error: &str| (StatusCode::FORBIDDEN, Json(serde_json::json!({"error": error}))).into_response();
+    if cookies::is_mutating(req.method()) && !cookies::csrf_valid(headers) {
+        return Err(forbidden("missing or invalid CSRF token"));
+    }
+
+    let bearer = axum::http::HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| forbidden("malformed session cookie"))?;
+    req.headers_mut().insert(axum::http::header::AUTHORIZATION, bearer);
+    Ok(())
+}
+
+/// Bearer token from the Authorization header
+///
+/// Browsers can't set headers on WebSocket upgrades, so the event stream,
+/// serial console and admin terminal also accept the token as a `token` query
+/// parameter.
+fn request_bearer_token(req: &Request) -> String {
+    let header = req
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if let Some(token) = header {
+        return token.to_string();
+    }
+
+    let path = req.uri().path();
+    // WebSocket and EventSource clients can't set headers
+    let streaming = path == "/api/events"
+        || path.starts_with("/console/serial/")
+        || path.starts_with("/console/replay/")
+        || path == "/api/admin/terminal"
+        || (path.starts_with("/api/jobs/") && path.ends_with("/events"));
+    if streaming {
+        if let Ok(Query(params)) = Query::<HashMap<String, String>>::try_from_uri(req.uri()) {
+            return params.get("token").cloned().unwrap_or_default();
+        }
+    }
+
+    String::new()
+}
+
+fn project_store_error(e: infrasim_common::Error) -> Response {
+    error!("project store: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+}
+
+fn project_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({"error": "project not found"})),
+    )
+        .into_response()
+}
+
+async fn list_projects_handler(State(state): State<Arc<WebServerState>>) -> Response {
+    match state.projects.list() {
+        Ok(list) => Json(serde_json::json!({"projects": list})).into_response(),
+        Err(e) => project_store_error(e),
+    }
+}
+
+async fn get_project_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(project_id): Path<String>,
+) -> Response {
+    match state.projects.get(&project_id) {
+        Ok(Some(project)) => Json(project).into_response(),
+        Ok(None) => project_not_found(),
+        Err(e) => project_store_error(e),
+    }
+}
+
+async fn rename_project_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(project_id): Path<String>,
+    Json(req): Json<CreateProjectRequest>,
+) -> Response {
+    if req.name.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "name must not be empty"})),
+        )
+            .into_response();
+    }
+
+    match state.projects.rename(&project_id, &req.name) {
+        Ok(Some(project)) => Json(project).into_response(),
+        Ok(None) => project_not_found(),
+        Err(e) => project_store_error(e),
+    }
 }
 
-async fn list_projects_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
-    let projects = state.projects.read().await;
-    let list: Vec<_> = projects.values().cloned().collect();
-    Json(serde_json::json!({"projects": list}))
+/// Delete a project with all of its prompts
+async fn delete_project_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(project_id): Path<String>,
+) -> Response {
+    match state.projects.delete(&project_id) {
+        Ok(Some(prompts_deleted)) => {
+            info!("Deleted project {} with {} prompts", project_id, prompts_deleted);
+            Json(serde_json::json!({"deleted": true, "prompts_deleted": prompts_deleted})).into_response()
+        }
+        Ok(None) => project_not_found(),
+        Err(e) => project_store_error(e),
+    }
 }
 
 async fn create_project_handler(
@@ -3443,154 +7580,87 @@ async fn create_project_handler(
             .into_response();
     }
 
-    let id = uuid::Uuid::new_v4().to_string();
-    let project = Project {
-        id: id.clone(),
-        name: req.name,
-        created_at: chrono::Utc::now().timestamp(),
-        prompts: vec![],
-    };
-
-    let mut projects = state.projects.write().await;
-    projects.insert(id.clone(), project.clone());
-
-    (StatusCode::CREATED, Json(project)).into_response()
-}
-
-fn builtin_appliance_templates() -> Vec<ApplianceTemplate> {
-    vec![
-        // Pi-like desktop template
-        ApplianceTemplate {
-            id: "pi-like-aarch64-desktop".to_string(),
-            title: "Pi-like AArch64 Desktop".to_string(),
-            description: "A Raspberry-Pi-like (AArch64) VM profile intended for interactive desktop-style workloads (e.g. Kali + browser + CLI).".to_string(),
-            arch: "aarch64".to_string(),
-            machine: "virt".to_string(),
-            cpu_cores: 4,
-            memory_mb: 4096,
-            compatibility_mode: true,
-            tags: vec!["aarch64".to_string(), "pi-like".to_string(), "desktop".to_string()],
-            image: None,
-            env: HashMap::new(),
-            ports: vec![],
-            boot_plan: vec![
-                BootStep { order: 1, action: "create_vm".to_string(), description: "Provision VM via daemon".to_string(), args: HashMap::new() },
-                BootStep { order: 2, action: "start_vm".to_string(), description: "Start the VM".to_string(), args: HashMap::new() },
-                BootStep { order: 3, action: "wait_ssh".to_string(), description: "Wait for SSH readiness".to_string(), args: HashMap::new() },
-            ],
-            networks: vec![
-                NetworkDef { id: "default".to_string(), mode: "user".to_string(), cidr: Some("10.0.2.0/24".to_string()), gateway: Some("10.0.2.2".to_string()), dhcp: true },
-            ],
-            volumes: vec![
-                VolumeDef { id: "root".to_string(), size_mb: 8192, mount_path: "/".to_string(), kind: "disk".to_string() },
-            ],
-            tools: vec![],
-        },
-        // Alpine Linux on Raspberry Pi architecture
-        ApplianceTemplate {
-            id: "alpine-rpi-aarch64".to_string(),
-            title: "Alpine Linux on Raspberry Pi".to_string(),
-            description: "Minimal Alpine Linux appliance running on emulated Raspberry Pi architecture (AArch64). Includes basic setup and SSH access.".to_string(),
-            arch: "aarch64".to_string(),
-            machine: "raspi3".to_string(),
-            cpu_cores: 4,
-            memory_mb: 1024,
-            compatibility_mode: false,
-            tags: vec!["aarch64".to_string(), "alpine".to_string(), "raspberry-pi".to_string(), "minimal".to_string()],
-            image: Some("alpine:latest".to_string()),
-            env: {
-                let mut m = HashMap::new();
-                m.insert("ALPINE_MIRROR".to_string(), "http://dl-cdn.alpinelinux.org/alpine".to_string());
-                m
-            },
-            ports: vec![
-                AppliancePort { container_port: 22, host_port: Some(2222), protocol: "tcp".to_string(), description: "SSH access".to_string() },
-            ],
-            boot_plan: vec![
-                BootStep { order: 1, action: "create_vm".to_string(), description: "Provision AArch64 VM with Raspberry Pi machine".to_string(), args: HashMap::new() },
-                BootStep { order: 2, action: "pull_image".to_string(), description: "Pull Alpine Linux image".to_string(), args: {
-                    let mut m = HashMap::new();
-                    m.insert("image".to_string(), "alpine:latest".to_string());
-                    m
-                }},
-                BootStep { order: 3, action: "run_container".to_string(), description: "Start Alpine container".to_string(), args: {
-                    let mut m = HashMap::new();
-                    m.insert("cmd".to_string(), "/bin/sh".to_string());
-                    m
-                }},
-                BootStep { order: 4, action: "wait_ssh".to_string(), description: "Wait for SSH readiness on port 2222".to_string(), args: {
-                    let mut m = HashMap::new();
-                    m.insert("port".to_string(), "2222".to_string());
-                    m
-                }},
-            ],
-            networks: vec![
-                NetworkDef { id: "default".to_string(), mode: "user".to_string(), cidr: Some("10.0.2.0/24".to_string()), gateway: Some("10.0.2.2".to_string()), dhcp: true },
-            ],
-            volumes: vec![
-                VolumeDef { id: "root".to_string(), size_mb: 2048, mount_path: "/".to_string(), kind: "disk".to_string() },
-                VolumeDef { id: "data".to_string(), size_mb: 1024, mount_path: "/data".to_string(), kind: "disk".to_string() },
-            ],
-            tools: vec![
-                ToolDef { name: "openssh".to_string(), version: Some("latest".to_string()), purpose: "SSH server for remote access".to_string() },
-                ToolDef { name: "alpine-base".to_string(), version: Some("latest".to_string()), purpose: "Base Alpine Linux packages".to_string() },
-            ],
-        },
-        // Keycloak IdP appliance
-        ApplianceTemplate {
-            id: "keycloak-aarch64".to_string(),
-            title: "Keycloak Identity Provider".to_string(),
-            description: "Keycloak (AArch64) appliance for identity federation and SSO. Runs in dev mode by default; configure TLS/proxy for production.".to_string(),
-            arch: "aarch64".to_string(),
-            machine: "virt".to_string(),
-            cpu_cores: 2,
-            memory_mb: 2048,
-            compatibility_mode: false,
-            tags: vec!["aarch64".to_string(), "identity".to_string(), "keycloak".to_string(), "sso".to_string()],
-            image: Some("quay.io/keycloak/keycloak:26.0".to_string()),
-            env: {
-                let mut m = HashMap::new();
-                m.insert("KC_BOOTSTRAP_ADMIN_USERNAME".to_string(), "admin".to_string());
-                m.insert("KC_BOOTSTRAP_ADMIN_PASSWORD".to_string(), "changeme".to_string());
-                m
-            },
-            ports: vec![
-                AppliancePort { container_port: 8080, host_port: Some(8080), protocol: "tcp".to_string(), description: "Keycloak HTTP".to_string() },
-                AppliancePort { container_port: 8443, host_port: Some(8443), protocol: "tcp".to_string(), description: "Keycloak HTTPS".to_string() },
-            ],
-            boot_plan: vec![
-                BootStep { order: 1, action: "create_vm".to_string(), description: "Provision AArch64 VM".to_string(), args: HashMap::new() },
-                BootStep { order: 2, action: "pull_image".to_string(), description: "Pull Keycloak container image".to_string(), args: {
-                    let mut m = HashMap::new();
-                    m.insert("image".to_string(), "quay.io/keycloak/keycloak:26.0".to_string());
-                    m
-                }},
-                BootStep { order: 3, action: "run_container".to_string(), description: "Start Keycloak in dev mode".to_string(), args: {
-                    let mut m = HashMap::new();
-                    m.insert("cmd".to_string(), "start-dev".to_string());
-                    m
-                }},
-                BootStep { order: 4, action: "wait_http".to_string(), description: "Wait for Keycloak /health/ready".to_string(), args: {
-                    let mut m = HashMap::new();
-                    m.insert("url".to_string(), "http://localhost:8080/health/ready".to_string());
-                    m
-                }},
-            ],
-            networks: vec![
-                NetworkDef { id: "mgmt".to_string(), mode: "user".to_string(), cidr: Some("10.0.2.0/24".to_string()), gateway: Some("10.0.2.2".to_string()), dhcp: true },
-            ],
-            volumes: vec![
-                VolumeDef { id: "kc-data".to_string(), size_mb: 1024, mount_path: "/opt/keycloak/data".to_string(), kind: "disk".to_string() },
-            ],
-            tools: vec![
-                ToolDef { name: "keycloak".to_string(), version: Some("26.0".to_string()), purpose: "Identity and access management".to_string() },
-            ],
-        },
-    ]
+    match state.projects.create(&req.name) {
+        Ok(project) => (StatusCode::CREATED, Json(project)).into_response(),
+        Err(e) => project_store_error(e),
+    }
+}
+
+async fn list_appliance_templates_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
+    let templates = state.templates.read().await;
+    Json(serde_json::json!({
+        "templates": templates.entries(),
+        "sources": templates.sources(),
+        "publisher_key": templates.publisher_key(),
+    }))
+}
+
+/// Fetch remote template catalogs and reload the registry (requires `template:sync`)
+async fn sync_appliance_templates_handler(
+    State(state): State<Arc<WebServerState>>,
+) -> Response {
+    // Fetch without holding the registry lock
+    let root = state.templates.read().await.root().to_path_buf();
+    let synced = crate::catalog::fetch_catalogs(&root).await;
+
+    let mut templates = state.templates.write().await;
+    if let Err(e) = templates.reload().await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response();
+    }
+    info!("Synced {} template catalogs", synced.len());
+    (StatusCode::OK, Json(serde_json::json!({
+        "synced": synced,
+        "sources": templates.sources(),
+        "count": templates.entries().len(),
+    }))).into_response()
+}
+
+/// Sign and register a template pack (requires `template:publish`)
+async fn publish_template_pack_handler(
+    State(state): State<Arc<WebServerState>>,
+    Json(pack): Json<TemplatePack>,
+) -> Response {
+    let mut templates = state.templates.write().await;
+    match templates.publish(pack).await {
+        Ok(published) => {
+            info!("Published template pack {} ({} templates)", published.name, published.templates);
+            (StatusCode::CREATED, Json(published)).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    }
+}
+
+async fn unpublish_template_pack_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(pack): Path<String>,
+) -> Response {
+    let mut templates = state.templates.write().await;
+    match templates.unpublish(&pack).await {
+        Ok(true) => (StatusCode::OK, Json(serde_json::json!({"deleted": pack}))).into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "pack not found"}))).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    }
+}
+
+/// A published pack, byte-for-byte as signed, for use as another server's catalog `url`
+async fn template_pack_file_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(pack): Path<String>,
+) -> Response {
+    match state.templates.read().await.pack_file(&pack).await {
+        Ok((data, _)) => ([(axum::http::header::CONTENT_TYPE, "application/json")], data).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    }
 }
 
-async fn list_appliance_templates_handler() -> impl IntoResponse {
-    Json(serde_json::json!({"templates": builtin_appliance_templates()}))
+async fn template_pack_signature_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(pack): Path<String>,
+) -> Response {
+    match state.templates.read().await.pack_file(&pack).await {
+        Ok((_, signature)) => signature.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": e.to_string()}))).into_response(),
+    }
 }
 
 async fn list_appliances_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
@@ -3624,7 +7694,7 @@ async fn seed_appliances_handler(
     State(state): State<Arc<WebServerState>>,
     Json(req): Json<SeedAppliancesRequest>,
 ) -> impl IntoResponse {
-    let templates = builtin_appliance_templates();
+    let templates = state.templates.read().await.list();
     let selected: Vec<ApplianceTemplate> = if req.template_ids.is_empty() {
         templates
     } else {
@@ -3669,6 +7739,10 @@ async fn seed_appliances_handler(
             volume_ids: vec![],
             console_id: None,
             snapshot_ids: vec![],
+            provisioning: Vec::new(),
+            status_since: now,
+            health: Vec::new(),
+            vms: Vec::new(),
         };
 
         appliances.insert(id.clone(), instance.clone());
@@ -3689,6 +7763,37 @@ async fn seed_appliances_handler(
     )
 }
 
+/// Template env with each `${secret:name}` replaced by the secret's value.
+///
+/// Fails if a referenced secret does not exist, so appliances can be refused
+/// before any of their resources are created.
+async fn resolve_appliance_env(
+    daemon: &DaemonProxy,
+    env: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, anyhow::Error> {
+    let mut names = Vec::new();
+    for value in env.values() {
+        names.extend(secrets::secret_refs(value)?);
+    }
+    names.sort();
+    names.dedup();
+
+    let mut values: HashMap<String, String> = HashMap::new();
+    for name in names {
+        let secret = daemon
+            .get_secret(&name)
+            .await
+            .map_err(|e| anyhow::anyhow!("secret '{}': {}", name, e))?;
+        values.insert(name, secret);
+    }
+    env.iter()
+        .map(|(key, value)| {
+            let resolved = secrets::interpolate(value, |name| Ok(values[name].clone()))?;
+            Ok((key.clone(), resolved))
+        })
+        .collect()
+}
+
 async fn create_appliance_handler(
     State(state): State<Arc<WebServerState>>,
     Json(req): Json<CreateApplianceRequest>,
@@ -3701,7 +7806,7 @@ async fn create_appliance_handler(
             .into_response();
     }
 
-    let templates = builtin_appliance_templates();
+    let templates = state.templates.read().await.list();
     let Some(template) = templates.iter().find(|t| t.id == req.template_id) else {
         return (
             StatusCode::BAD_REQUEST,
@@ -3710,8 +7815,27 @@ async fn create_appliance_handler(
             .into_response();
     };
 
+    if let Err(e) = resolve_appliance_env(&state.daemon, &template.env).await {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": format!("template env: {}", e)})),
+        )
+            .into_response();
+    }
+
+    let vm_defs = match template.vm_start_order() {
+        Ok(vm_defs) => vm_defs,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    };
+
     let id = uuid::Uuid::new_v4().to_string();
-    let mut vm_id: Option<String> = None;
+    let mut vms: Vec<ApplianceVm> = vec![];
     let mut console_id: Option<String> = None;
     let mut network_ids: Vec<String> = vec![];
     let mut volume_ids: Vec<String> = vec![];
@@ -3743,41 +7867,70 @@ async fn create_appliance_handler(
         }
     }
 
-    // 3. Create VM
-    match daemon.create_vm(&req.name, template).await {
-        Ok(created_vm_id) => {
-            vm_id = Some(created_vm_id.clone());
-            status = "vm_created".to_string();
-            info!("Created VM {} -> {}", req.name, created_vm_id);
-
-            // 4. Start VM if auto_start is enabled (default true)
-            if req.auto_start.unwrap_or(true) {
-                match daemon.start_vm(&created_vm_id).await {
-                    Ok(_) => {
-                        status = "running".to_string();
-                        info!("Started VM {}", created_vm_id);
-
-                        // 5. Create console
-                        match daemon.create_console(&created_vm_id, 5900, 6080).await {
-                            Ok(cid) => {
-                                info!("Created console {} for VM {}", cid, created_vm_id);
-                                console_id = Some(cid);
-                            }
-                            Err(e) => warn!("Failed to create console for {}: {}", created_vm_id, e),
-                        }
-                    }
-                    Err(e) => {
-                        status = "start_failed".to_string();
-                        error_msg = Some(e.to_string());
-                        warn!("Failed to start VM {}: {}", created_vm_id, e);
+    // 3. Create VMs, each after the VMs it depends on
+    let multi_vm = !template.vms.is_empty();
+    for def in &vm_defs {
+        let vm_name = if multi_vm { format!("{}-{}", req.name, def.name) } else { req.name.clone() };
+        let mut labels = HashMap::new();
+        if let Some(group) = &def.anti_affinity {
+            labels.insert(ANTI_AFFINITY_LABEL.to_string(), group.clone());
+        }
+        let created_vm_id = match daemon.create_vm(&vm_name, &template.for_vm(def), labels).await {
+            Ok(created_vm_id) => created_vm_id,
+            Err(e) => {
+                status = "vm_creation_failed".to_string();
+                error_msg = Some(e.to_string());
+                warn!("Failed to create VM {} for appliance {}: {}", vm_name, req.name, e);
+                break;
+            }
+        };
+        status = "vm_created".to_string();
+        info!("Created VM {} -> {}", vm_name, created_vm_id);
+
+        // Wire template ports to the host; the daemon applies them once the VM runs.
+        // SSH on 2222 is already forwarded by the default user-mode netdev.
+        for port in def.ports.iter().filter(|p| p.host_port.is_some()) {
+            if port.container_port == 22 && port.host_port == Some(2222) {
+                continue;
+            }
+            let pf_name = format!("{}-{}-{}", vm_name, port.protocol, port.container_port);
+            match daemon.create_port_forward(&pf_name, &created_vm_id, port).await {
+                Ok(pf_id) => info!("Created port forward {} -> {}", pf_name, pf_id),
+                Err(e) => warn!("Failed to forward port {} for {}: {}", port.container_port, created_vm_id, e),
+            }
+        }
+        vms.push(ApplianceVm { name: def.name.clone(), vm_id: created_vm_id });
+    }
+    // The first VM listed is the primary one
+    let primary = template.vms.first().map_or("main", |vm| vm.name.as_str());
+    let vm_id = vms.iter().find(|vm| vm.name == primary).map(|vm| vm.vm_id.clone());
+
+    // 4. Start VMs if auto_start is enabled (default true); VMs that wait on
+    // others start in the background
+    let auto_start = req.auto_start.unwrap_or(true) && error_msg.is_none();
+    let start_in_background = auto_start && template.starts_in_order();
+    if start_in_background {
+        status = "starting".to_string();
+    } else if let Some(created_vm_id) = vm_id.as_ref().filter(|_| auto_start) {
+        match daemon.start_vm(created_vm_id).await {
+            Ok(_) => {
+                status = "running".to_string();
+                info!("Started VM {}", created_vm_id);
+
+                // 5. Create console
+                match daemon.create_console(created_vm_id, 5900, 6080).await {
+                    Ok(cid) => {
+                        info!("Created console {} for VM {}", cid, created_vm_id);
+                        console_id = Some(cid);
                     }
+                    Err(e) => warn!("Failed to create console for {}: {}", created_vm_id, e),
                 }
             }
-        }
-        Err(e) => {
-            status = "vm_creation_failed".to_string();
-            error_msg = Some(e.to_string());
-            warn!("Failed to create VM for appliance {}: {}", req.name, e);
+            Err(e) => {
+                status = "start_failed".to_string();
+                error_msg = Some(e.to_string());
+                warn!("Failed to start VM {}: {}", created_vm_id, e);
+            }
         }
     }
 
@@ -3794,10 +7947,19 @@ async fn create_appliance_handler(
         console_id,
         snapshot_ids: vec![],
         updated_at: now,
+        provisioning: Vec::new(),
+        status_since: now,
+        health: Vec::new(),
+        vms,
     };
 
     let mut appliances = state.appliances.write().await;
     appliances.insert(id.clone(), instance.clone());
+    drop(appliances);
+
+    if start_in_background {
+        tokio::spawn(boot_appliance_vms(state.clone(), template.clone(), instance.clone(), vec![], "running"));
+    }
 
     let response = serde_json::json!({
         "appliance": instance,
@@ -3817,7 +7979,7 @@ async fn appliance_terraform_handler(
         return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "appliance not found"}))).into_response();
     };
 
-    let templates = builtin_appliance_templates();
+    let templates = state.templates.read().await.list();
     let Some(tpl) = templates.iter().find(|t| t.id == instance.template_id) else {
         return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": "template not found"}))).into_response();
     };
@@ -3905,6 +8067,9 @@ provider "infrasim" {{
     }))).into_response()
 }
 
+/// Label grouping VMs to be kept on different hosts
+const ANTI_AFFINITY_LABEL: &str = "infrasim.anti_affinity";
+
 // Trigger the boot plan for an appliance instance (MVP stub).
 async fn appliance_boot_handler(
     State(state): State<Arc<WebServerState>>,
@@ -3915,20 +8080,58 @@ async fn appliance_boot_handler(
         return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "appliance not found"}))).into_response();
     };
 
-    let templates = builtin_appliance_templates();
+    let templates = state.templates.read().await.list();
     let Some(tpl) = templates.iter().find(|t| t.id == instance.template_id) else {
         return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": "template not found"}))).into_response();
     };
 
-    // If we have a VM, start it via daemon.
-    if let Some(vm_id) = &instance.vm_id {
-        match state.daemon.start_vm(vm_id).await {
-            Ok(_) => {
-                instance.status = "running".to_string();
+    // Secrets are resolved at boot so rotated values are picked up.
+    let env = match resolve_appliance_env(&state.daemon, &tpl.env).await {
+        Ok(env) => env,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                "error": format!("template env: {}", e),
+            }))).into_response();
+        }
+    };
+
+    // If we have a VM, start it via daemon, then provision the guest.
+    // Appliances with health checks are booting until the checks pass.
+    let provision_steps = tpl.provision_steps();
+    let ready_status = if tpl.health_checks.is_empty() { "running" } else { "booting" };
+    instance.health.clear();
+    if tpl.starts_in_order() && !instance.vms.is_empty() {
+        // VMs waiting on others start in the background, then provisioning follows
+        instance.set_status("starting");
+        instance.provisioning = pending_provision_steps(&provision_steps);
+        let params = provision_steps.into_iter().map(|(_, params)| params).collect();
+        tokio::spawn(boot_appliance_vms(state.clone(), tpl.clone(), instance.clone(), params, ready_status));
+    } else if let Some(vm_id) = instance.vm_id.clone() {
+        match state.daemon.start_vm(&vm_id).await {
+            Ok(_) if provision_steps.is_empty() => {
+                instance.set_status(ready_status);
                 info!("Started VM {} for appliance {}", vm_id, appliance_id);
             }
+            Ok(_) => {
+                instance.set_status("provisioning");
+                instance.provisioning = pending_provision_steps(&provision_steps);
+                info!(
+                    "Started VM {} for appliance {}; {} provisioning steps follow",
+                    vm_id,
+                    appliance_id,
+                    provision_steps.len()
+                );
+                let params = provision_steps.into_iter().map(|(_, params)| params).collect();
+                tokio::spawn(run_appliance_provisioning(
+                    state.clone(),
+                    appliance_id.clone(),
+                    vm_id.clone(),
+                    params,
+                    ready_status,
+                ));
+            }
             Err(e) => {
-                instance.status = "start_failed".to_string();
+                instance.set_status("start_failed");
                 warn!("Failed to start VM {}: {}", vm_id, e);
                 return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
                     "error": format!("failed to start VM: {}", e),
@@ -3936,16 +8139,243 @@ async fn appliance_boot_handler(
             }
         }
     } else {
-        instance.status = "booting".to_string();
+        instance.set_status("booting");
+    }
+
+    instance.updated_at = now_epoch_secs();
+    if let Err(e) = persist_catalog_instance(&state, instance).await {
+        warn!("failed to persist appliance {}: {}", appliance_id, e);
     }
 
     (StatusCode::ACCEPTED, Json(serde_json::json!({
         "appliance_id": appliance_id,
         "status": instance.status,
         "boot_plan": tpl.boot_plan,
+        "provisioning": instance.provisioning,
+        "env": env,
     }))).into_response()
 }
 
+fn pending_provision_steps(steps: &[(&BootStep, HashMap<String, String>)]) -> Vec<ProvisionStepStatus> {
+    steps
+        .iter()
+        .map(|(step, _)| ProvisionStepStatus {
+            order: step.order,
+            action: step.action.clone(),
+            job_id: None,
+            state: "pending".to_string(),
+            error: None,
+            log: None,
+            log_digest: None,
+        })
+        .collect()
+}
+
+/// Start an appliance's VMs in startup order, then provision it; the
+/// appliance moves to `ready_status`, or `start_failed` if a VM didn't start
+/// or come up
+async fn boot_appliance_vms(
+    state: Arc<WebServerState>,
+    tpl: ApplianceTemplate,
+    instance: ApplianceInstance,
+    steps: Vec<HashMap<String, String>>,
+    ready_status: &'static str,
+) {
+    let appliance_id = instance.id.clone();
+    if let Err(e) = start_appliance_vms(&state, &tpl, &instance).await {
+        warn!("Starting appliance {} failed: {}", appliance_id, e);
+        update_appliance(&state, &appliance_id, |instance| instance.set_status("start_failed")).await;
+        return;
+    }
+    match instance.vm_id {
+        Some(vm_id) if !steps.is_empty() => {
+            update_appliance(&state, &appliance_id, |instance| instance.set_status("provisioning")).await;
+            run_appliance_provisioning(state, appliance_id, vm_id, steps, ready_status).await;
+        }
+        _ => update_appliance(&state, &appliance_id, |instance| instance.set_status(ready_status)).await,
+    }
+}
+
+/// Start each VM once the `wait_for` conditions of the VMs before it pass
+async fn start_appliance_vms(
+    state: &WebServerState,
+    tpl: &ApplianceTemplate,
+    instance: &ApplianceInstance,
+) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let defs = tpl.vm_start_order()?;
+    for vm in &instance.vms {
+        state
+            .daemon
+            .start_vm(&vm.vm_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to start VM {}: {}", vm.name, e))?;
+        info!("Started VM {} ({}) for appliance {}", vm.name, vm.vm_id, instance.id);
+
+        let Some(def) = defs.iter().find(|def| def.name == vm.name) else {
+            continue;
+        };
+        if let Some(check) = &def.wait_for {
+            let timeout = std::time::Duration::from_secs(def.wait_timeout_secs);
+            health::wait_until_healthy(check, &client, &def.ports, &instance.id, Some(&vm.vm_id), timeout)
+                .await
+                .map_err(|e| anyhow::anyhow!("VM {} didn't come up: {}", vm.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// How often a provisioning job is checked on
+const PROVISION_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Run an appliance's provisioning steps one after another as daemon jobs,
+/// recording each on the instance; a failed step stops the rest, otherwise
+/// the appliance moves to `ready_status`
+async fn run_appliance_provisioning(
+    state: Arc<WebServerState>,
+    appliance_id: String,
+    vm_id: String,
+    steps: Vec<HashMap<String, String>>,
+    ready_status: &'static str,
+) {
+    let mut succeeded = true;
+    for (index, mut params) in steps.into_iter().enumerate() {
+        // A step's `vm` arg names the VM of a multi-VM appliance it runs on
+        let target = match params.remove("vm") {
+            Some(name) => appliance_vm_id(&state, &appliance_id, &name)
+                .await
+                .ok_or_else(|| anyhow::anyhow!("appliance has no VM '{}'", name)),
+            None => Ok(vm_id.clone()),
+        };
+        let job = match target {
+            Ok(target) => match state.daemon.create_job(JobKind::Provision, target, params).await {
+                Ok(job) => {
+                    update_provision_step(&state, &appliance_id, index, &job).await;
+                    wait_for_job(&state, job).await
+                }
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        let job = job.unwrap_or_else(|e| JobInfo {
+            state: "failed".to_string(),
+            error: e.to_string(),
+            ..JobInfo::default()
+        });
+        update_provision_step(&state, &appliance_id, index, &job).await;
+        if job.state != "succeeded" {
+            warn!("Provisioning appliance {} failed: {}", appliance_id, job.error);
+            succeeded = false;
+            break;
+        }
+    }
+
+    let status = if succeeded { ready_status } else { "provision_failed" };
+    update_appliance(&state, &appliance_id, |instance| instance.set_status(status)).await;
+    info!("Provisioning appliance {} finished: {}", appliance_id, status);
+}
+
+async fn appliance_vm_id(state: &WebServerState, appliance_id: &str, name: &str) -> Option<String> {
+    let appliances = state.appliances.read().await;
+    let instance = appliances.get(appliance_id)?;
+    instance.vms.iter().find(|vm| vm.name == name).map(|vm| vm.vm_id.clone())
+}
+
+/// Poll a job until it finishes
+async fn wait_for_job(state: &WebServerState, mut job: JobInfo) -> Result<JobInfo, anyhow::Error> {
+    while !job.is_finished() {
+        tokio::time::sleep(PROVISION_POLL_INTERVAL).await;
+        job = state.daemon.get_job(&job.id).await?;
+    }
+    Ok(job)
+}
+
+async fn update_provision_step(state: &WebServerState, appliance_id: &str, index: usize, job: &JobInfo) {
+    update_appliance(state, appliance_id, |instance| {
+        if let Some(step) = instance.provisioning.get_mut(index) {
+            if !job.id.is_empty() {
+                step.job_id = Some(job.id.clone());
+            }
+            step.state = job.state.clone();
+            step.error = Some(job.error.clone()).filter(|e| !e.is_empty());
+            step.log = job.result.get("log").cloned();
+            step.log_digest = job.result.get("log_digest").cloned();
+        }
+    })
+    .await;
+}
+
+/// Change an appliance in memory and in the catalog
+async fn update_appliance(state: &WebServerState, appliance_id: &str, change: impl FnOnce(&mut ApplianceInstance)) {
+    let mut appliances = state.appliances.write().await;
+    let Some(instance) = appliances.get_mut(appliance_id) else {
+        return;
+    };
+    change(instance);
+    instance.updated_at = now_epoch_secs();
+    if let Err(e) = persist_catalog_instance(state, instance).await {
+        warn!("failed to persist appliance {}: {}", appliance_id, e);
+    }
+}
+
+/// Run the health checks of booted appliances every
+/// [`health::CHECK_INTERVAL`] and move them between booting, healthy and
+/// degraded
+fn spawn_appliance_health_checks(state: Arc<WebServerState>) {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut interval = tokio::time::interval(health::CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check_appliance_health(&state, &client).await;
+        }
+    });
+}
+
+async fn check_appliance_health(state: &WebServerState, client: &reqwest::Client) {
+    let templates = state.templates.read().await.list();
+    // Checks run without the lock; appliances are looked up again after
+    let monitored: Vec<(String, Option<String>, ApplianceTemplate)> = state
+        .appliances
+        .read()
+        .await
+        .values()
+        .filter(|instance| health::monitored(&instance.status))
+        .filter_map(|instance| {
+            let tpl = templates.iter().find(|t| t.id == instance.template_id)?;
+            (!tpl.health_checks.is_empty()).then(|| (instance.id.clone(), instance.vm_id.clone(), tpl.clone()))
+        })
+        .collect();
+
+    for (appliance_id, vm_id, tpl) in monitored {
+        let ports = tpl.exposed_ports();
+        let mut results = Vec::with_capacity(tpl.health_checks.len());
+        for check in &tpl.health_checks {
+            results.push(health::evaluate(check, client, &ports, &appliance_id, vm_id.as_deref()).await);
+        }
+        let all_passed = results.iter().all(|r| r.healthy);
+
+        let mut appliances = state.appliances.write().await;
+        let Some(instance) = appliances.get_mut(&appliance_id) else {
+            continue;
+        };
+        // Stopped or rebooted while the checks ran
+        if !health::monitored(&instance.status) {
+            continue;
+        }
+        instance.health = results;
+        let Some(next) = health::next_status(&instance.status, instance.status_since, now_epoch_secs(), all_passed) else {
+            continue;
+        };
+        info!("Appliance {} is {} (was {})", appliance_id, next, instance.status);
+        instance.set_status(next);
+        instance.updated_at = now_epoch_secs();
+        if let Err(e) = persist_catalog_instance(state, instance).await {
+            warn!("failed to persist appliance {}: {}", appliance_id, e);
+        }
+    }
+}
+
 // Stop an appliance instance (stop the VM).
 async fn appliance_stop_handler(
     State(state): State<Arc<WebServerState>>,
@@ -3957,26 +8387,27 @@ async fn appliance_stop_handler(
         return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "appliance not found"}))).into_response();
     };
 
-    let Some(vm_id) = &instance.vm_id else {
+    let vm_ids = instance.vm_ids();
+    if vm_ids.is_empty() {
         return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "no VM associated with appliance"}))).into_response();
-    };
+    }
 
-    match state.daemon.stop_vm(vm_id, req.force.unwrap_or(false)).await {
-        Ok(_) => {
-            instance.status = "stopped".to_string();
-            info!("Stopped VM {} for appliance {}", vm_id, appliance_id);
-            (StatusCode::OK, Json(serde_json::json!({
-                "appliance_id": appliance_id,
-                "status": instance.status,
-            }))).into_response()
-        }
-        Err(e) => {
+    // Dependents stop before the VMs they depend on
+    for vm_id in vm_ids.iter().rev() {
+        if let Err(e) = state.daemon.stop_vm(vm_id, req.force.unwrap_or(false)).await {
             warn!("Failed to stop VM {}: {}", vm_id, e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
                 "error": format!("failed to stop VM: {}", e),
-            }))).into_response()
+            }))).into_response();
         }
+        info!("Stopped VM {} for appliance {}", vm_id, appliance_id);
     }
+
+    instance.set_status("stopped");
+    (StatusCode::OK, Json(serde_json::json!({
+        "appliance_id": appliance_id,
+        "status": instance.status,
+    }))).into_response()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -4007,7 +8438,6 @@ async fn appliance_snapshot_handler(
             info!("Created snapshot {} for appliance {} (VM {})", snapshot_id, appliance_id, vm_id);
 
             // Create signed evidence bundle for the snapshot
-            let key_pair = infrasim_common::crypto::KeyPair::generate();
             let evidence = serde_json::json!({
                 "type": "snapshot",
                 "snapshot_id": snapshot_id,
@@ -4018,18 +8448,27 @@ async fn appliance_snapshot_handler(
                 "timestamp": chrono::Utc::now().to_rfc3339(),
             });
             let evidence_bytes = serde_json::to_vec(&evidence).unwrap_or_default();
-            let signature = key_pair.sign(&evidence_bytes);
+            // The snapshot exists either way, so a signing failure is reported alongside it.
+            let (evidence, evidence_error) = match state.daemon.sign_payload(&evidence_bytes, "appliance-snapshot").await {
+                Ok(signed) => (serde_json::json!({
+                    "data": evidence,
+                    "signature": hex::encode(&signed.signature),
+                    "public_key": signed.public_key,
+                    "key_id": signed.key_id,
+                }), None),
+                Err(e) => {
+                    warn!("Failed to sign evidence for snapshot {}: {}", snapshot_id, e);
+                    (serde_json::Value::Null, Some(format!("failed to sign evidence: {}", e)))
+                }
+            };
 
             (StatusCode::CREATED, Json(serde_json::json!({
                 "snapshot_id": snapshot_id,
                 "appliance_id": appliance_id,
                 "vm_id": vm_id,
                 "name": snapshot_name,
-                "evidence": {
-                    "data": evidence,
-                    "signature": hex::encode(&signature),
-                    "public_key": hex::encode(key_pair.public_key_bytes()),
-                },
+                "evidence": evidence,
+                "evidence_error": evidence_error,
             }))).into_response()
         }
         Err(e) => {
@@ -4063,7 +8502,7 @@ async fn get_appliance_detail_handler(
         return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "appliance not found"}))).into_response();
     };
 
-    let templates = builtin_appliance_templates();
+    let templates = state.templates.read().await.list();
     let template = templates.iter().find(|t| t.id == instance.template_id).cloned();
 
     // Fetch VM details
@@ -4138,7 +8577,7 @@ async fn export_appliance_handler(
         return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "appliance not found"}))).into_response();
     };
 
-    let templates = builtin_appliance_templates();
+    let templates = state.templates.read().await.list();
     let template = templates.iter().find(|t| t.id == instance.template_id).cloned();
 
     // Fetch all associated resources
@@ -4166,7 +8605,6 @@ async fn export_appliance_handler(
     let terraform_hcl = generate_appliance_terraform(&instance, template.as_ref(), &state.cfg.daemon_addr);
 
     // Sign the export bundle
-    let key_pair = infrasim_common::crypto::KeyPair::generate();
     let bundle_data = serde_json::json!({
         "version": "1.0",
         "type": "infrasim_appliance_export",
@@ -4199,12 +8637,16 @@ async fn export_appliance_handler(
     });
 
     let bundle_bytes = serde_json::to_vec(&bundle_data).unwrap_or_default();
-    let signature = key_pair.sign(&bundle_bytes);
+    let signed = match state.daemon.sign_payload(&bundle_bytes, "appliance-export").await {
+        Ok(signed) => signed,
+        Err(e) => return daemon_error_response(e),
+    };
 
     (StatusCode::OK, Json(serde_json::json!({
         "bundle": bundle_data,
-        "signature": hex::encode(&signature),
-        "public_key": hex::encode(key_pair.public_key_bytes()),
+        "signature": hex::encode(&signed.signature),
+        "public_key": signed.public_key,
+        "key_id": signed.key_id,
     }))).into_response()
 }
 
@@ -4245,6 +8687,10 @@ async fn import_appliance_handler(
         console_id: None,
         snapshot_ids: vec![],
         updated_at: now,
+        provisioning: Vec::new(),
+        status_since: now,
+        health: Vec::new(),
+        vms: Vec::new(),
     };
 
     let mut appliances = state.appliances.write().await;
@@ -4257,18 +8703,24 @@ async fn import_appliance_handler(
     }))).into_response()
 }
 
-/// Archive an appliance (backup to a persistent store).
+/// Archive an appliance's volumes and snapshots as a signed tar.gz in the daemon's CAS.
 async fn archive_appliance_handler(
     State(state): State<Arc<WebServerState>>,
     Path(appliance_id): Path<String>,
     Json(req): Json<ArchiveApplianceRequest>,
 ) -> Response {
-    let appliances = state.appliances.read().await;
-    let Some(instance) = appliances.get(&appliance_id) else {
+    if req.format != "tar.gz" {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "error": format!("unsupported archive format '{}', expected 'tar.gz'", req.format),
+        }))).into_response();
+    }
+
+    // Archiving copies whole disks; don't hold the appliance table meanwhile
+    let Some(instance) = state.appliances.read().await.get(&appliance_id).cloned() else {
         return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "appliance not found"}))).into_response();
     };
 
-    let templates = builtin_appliance_templates();
+    let templates = state.templates.read().await.list();
     let template = templates.iter().find(|t| t.id == instance.template_id).cloned();
 
     // Gather all resources for archive
@@ -4278,63 +8730,202 @@ async fn archive_appliance_handler(
         None
     };
 
+    let mut volume_ids = instance.volume_ids.clone();
+    if let Some(vm) = &vm {
+        for id in &vm.volume_ids {
+            if !volume_ids.contains(id) {
+                volume_ids.push(id.clone());
+            }
+        }
+    }
     let all_volumes = state.daemon.list_volumes().await.unwrap_or_default();
     let volumes: Vec<_> = all_volumes.into_iter()
-        .filter(|v| instance.volume_ids.contains(&v.id))
+        .filter(|v| volume_ids.contains(&v.id))
         .collect();
 
-    let all_snapshots = state.daemon.list_snapshots(instance.vm_id.as_deref()).await.unwrap_or_default();
-    let snapshots: Vec<_> = if req.include_all_snapshots {
-        all_snapshots
-    } else {
-        all_snapshots.into_iter()
-            .filter(|s| instance.snapshot_ids.contains(&s.id))
-            .collect()
+    let all_snapshots = state.daemon.list_snapshots(instance.vm_id.as_deref()).await.unwrap_or_default();
+    let snapshots: Vec<_> = if req.include_all_snapshots {
+        all_snapshots
+    } else {
+        all_snapshots.into_iter()
+            .filter(|s| instance.snapshot_ids.contains(&s.id))
+            .collect()
+    };
+
+    // Stored in the archive as metadata.json, for restore
+    let archive_manifest = serde_json::json!({
+        "version": "1.0",
+        "type": "infrasim_appliance_archive",
+        "format": req.format,
+        "archived_at": chrono::Utc::now().to_rfc3339(),
+        "appliance": instance,
+        "template": template,
+        "include_memory": req.include_memory,
+        "vm": vm,
+        "volumes": volumes.iter().map(|v| serde_json::json!({
+            "id": v.id,
+            "name": v.name,
+            "size_bytes": v.size_bytes,
+            "digest": v.digest,
+        })).collect::<Vec<_>>(),
+        "snapshots": snapshots.iter().map(|s| serde_json::json!({
+            "id": s.id,
+            "name": s.name,
+            "size_bytes": s.size_bytes,
+            "digest": s.digest,
+        })).collect::<Vec<_>>(),
+    });
+
+    let archive = match state.daemon.create_archive(
+        volumes.iter().map(|v| v.id.clone()).collect(),
+        snapshots.iter().map(|s| s.id.clone()).collect(),
+        req.include_memory,
+        serde_json::to_vec(&archive_manifest).unwrap_or_default(),
+    ).await {
+        Ok(archive) => archive,
+        Err(e) => {
+            warn!("Failed to archive appliance {}: {}", appliance_id, e);
+            return daemon_error_response(e);
+        }
+    };
+    info!("Archived appliance {} as {}", appliance_id, archive.digest);
+
+    (StatusCode::CREATED, Json(serde_json::json!({
+        "archive_id": archive.digest,
+        "digest": archive.digest,
+        "format": req.format,
+        "size_bytes": archive.size_bytes,
+        "manifest": archive_manifest,
+        "signature": hex::encode(&archive.signature),
+        "public_key": archive.public_key,
+        "key_id": archive.key_id,
+        "download_url": format!("/api/appliances/archives/{}", archive.digest),
+        "restore_url": format!("/api/appliances/archives/{}/restore", archive.digest),
+    }))).into_response()
+}
+
+/// Start of an open-ended `Range: bytes=N-` request; other ranges get the whole archive
+fn range_start(headers: &axum::http::HeaderMap) -> Option<u64> {
+    headers
+        .get(axum::http::header::RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes=")?
+        .strip_suffix('-')?
+        .parse()
+        .ok()
+}
+
+/// Download an appliance archive; `Range: bytes=N-` resumes an interrupted download
+async fn download_appliance_archive_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(digest): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    use futures::StreamExt;
+
+    let offset = range_start(&headers).unwrap_or(0);
+    let mut stream = match state.daemon.download_archive(&digest, offset).await {
+        Ok(stream) => stream,
+        Err(e) if offset > 0 && is_out_of_range(&e) => return range_not_satisfiable(),
+        Err(e) => return daemon_error_response(e),
+    };
+    // The first chunk tells the archive size
+    let first = match stream.message().await {
+        Ok(Some(first)) => first,
+        Ok(None) => return range_not_satisfiable(),
+        Err(e) => return daemon_error_response(e.into()),
+    };
+    let size = first.size_bytes;
+
+    let body = axum::body::Body::from_stream(
+        futures::stream::once(async move { Ok::<_, tonic::Status>(first.data) })
+            .chain(stream.map(|chunk| chunk.map(|c| c.data))),
+    );
+    let mut response = Response::builder()
+        .header("content-type", "application/gzip")
+        .header("content-length", size - offset)
+        .header("accept-ranges", "bytes")
+        .header("etag", format!("\"{}\"", digest))
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"appliance-{}.tar.gz\"", &digest[..12.min(digest.len())]),
+        );
+    response = if offset > 0 {
+        response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header("content-range", format!("bytes {}-{}/{}", offset, size - 1, size))
+    } else {
+        response.status(StatusCode::OK)
+    };
+    response.body(body).unwrap().into_response()
+}
+
+fn is_out_of_range(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<tonic::Status>().map(|s| s.code()) == Some(tonic::Code::OutOfRange)
+}
+
+fn range_not_satisfiable() -> Response {
+    (StatusCode::RANGE_NOT_SATISFIABLE, Json(serde_json::json!({
+        "error": "range starts past the end of the archive",
+    }))).into_response()
+}
+
+/// Restore an appliance from an archive: its volumes are rehydrated and a new
+/// appliance records them.
+async fn restore_appliance_archive_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(digest): Path<String>,
+    body: Option<Json<RestoreApplianceArchiveRequest>>,
+) -> Response {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let id = uuid::Uuid::new_v4().to_string();
+    let name = req.name.unwrap_or_else(|| format!("restored-{}", &id[..8]));
+
+    let restored = match state.daemon.restore_archive(&digest, &format!("{}-", name)).await {
+        Ok(restored) => restored,
+        Err(e) => {
+            warn!("Failed to restore archive {}: {}", digest, e);
+            return daemon_error_response(e);
+        }
     };
 
-    // Build archive manifest
-    let archive_manifest = serde_json::json!({
-        "version": "1.0",
-        "type": "infrasim_appliance_archive",
-        "format": req.format,
-        "archived_at": chrono::Utc::now().to_rfc3339(),
-        "appliance": instance,
-        "template": template,
-        "include_memory": req.include_memory,
-        "vm": vm,
-        "volumes": volumes.iter().map(|v| serde_json::json!({
-            "id": v.id,
-            "name": v.name,
-            "local_path": v.local_path,
-            "size_bytes": v.size_bytes,
-            "digest": v.digest,
-        })).collect::<Vec<_>>(),
-        "snapshots": snapshots.iter().map(|s| serde_json::json!({
-            "id": s.id,
-            "name": s.name,
-            "disk_snapshot_path": s.disk_snapshot_path,
-            "memory_snapshot_path": if req.include_memory { &s.memory_snapshot_path } else { "" },
-            "size_bytes": s.size_bytes,
-            "digest": s.digest,
-        })).collect::<Vec<_>>(),
-    });
+    let metadata: serde_json::Value = serde_json::from_slice(&restored.metadata).unwrap_or_default();
+    let template_id = metadata.pointer("/appliance/template_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("pi-like-aarch64-desktop");
+    let original_name = metadata.pointer("/appliance/name").and_then(|v| v.as_str());
 
-    // Sign the archive
-    let key_pair = infrasim_common::crypto::KeyPair::generate();
-    let manifest_bytes = serde_json::to_vec(&archive_manifest).unwrap_or_default();
-    let signature = key_pair.sign(&manifest_bytes);
+    let volumes: Vec<_> = restored.volumes.into_iter()
+        .map(|v| v.meta.unwrap_or_default())
+        .map(|m| serde_json::json!({"id": m.id, "name": m.name}))
+        .collect();
+    let now = chrono::Utc::now().timestamp();
+    let instance = ApplianceInstance {
+        id: id.clone(),
+        name,
+        template_id: template_id.to_string(),
+        created_at: now,
+        vm_id: None,
+        status: "restored".to_string(),
+        network_ids: vec![],
+        volume_ids: volumes.iter().filter_map(|v| v["id"].as_str().map(str::to_string)).collect(),
+        console_id: None,
+        snapshot_ids: vec![],
+        updated_at: now,
+        provisioning: Vec::new(),
+        status_since: now,
+        health: Vec::new(),
+        vms: Vec::new(),
+    };
+    state.appliances.write().await.insert(id.clone(), instance.clone());
+    info!("Restored appliance {} from archive {}", id, digest);
 
-    // For JSON format, just return the manifest. For tar.gz/zip, we'd need to actually create the archive.
-    // MVP: return JSON manifest with file paths that can be used to create the archive externally.
-    (StatusCode::OK, Json(serde_json::json!({
-        "archive_id": uuid::Uuid::new_v4().to_string(),
-        "format": req.format,
-        "manifest": archive_manifest,
-        "signature": hex::encode(&signature),
-        "public_key": hex::encode(key_pair.public_key_bytes()),
-        "files_to_archive": volumes.iter().map(|v| &v.local_path).chain(
-            snapshots.iter().map(|s| &s.disk_snapshot_path)
-        ).filter(|p| !p.is_empty()).collect::<Vec<_>>(),
+    (StatusCode::CREATED, Json(serde_json::json!({
+        "appliance": instance,
+        "volumes": volumes,
+        "restored_from": digest,
+        "original_name": original_name,
     }))).into_response()
 }
 
@@ -4374,7 +8965,7 @@ terraform {{
 }}
 
 provider "infrasim" {{
-  endpoint = "{}"
+  daemon_address = "{}"
 }}
 
 "#, instance.name, tpl_id, daemon_addr));
@@ -4382,62 +8973,80 @@ provider "infrasim" {{
     if let Some(tpl) = template {
         for net in &tpl.networks {
             hcl.push_str(&format!(r#"resource "infrasim_network" "{}-{}" {{
-  name         = "{}-{}"
-  mode         = "{}"
-  cidr         = "{}"
-  gateway      = "{}"
-  dhcp_enabled = {}
-}}
-
+  name    = "{}-{}"
+  mode    = "{}"
+  cidr    = "{}"
 "#,
                 instance.name, net.id,
                 instance.name, net.id,
                 net.mode,
-                net.cidr.as_deref().unwrap_or(""),
-                net.gateway.as_deref().unwrap_or(""),
-                net.dhcp,
+                // Required by the provider; QEMU user-mode networking's default
+                net.cidr.as_deref().unwrap_or("10.0.2.0/24"),
             ));
+            if let Some(gateway) = &net.gateway {
+                hcl.push_str(&format!("  gateway = \"{}\"\n", gateway));
+            }
+            hcl.push_str("}\n\n");
         }
 
         for vol in &tpl.volumes {
             hcl.push_str(&format!(r#"resource "infrasim_volume" "{}-{}" {{
-  name      = "{}-{}"
-  size_mb   = {}
-  kind      = "{}"
-  format    = "qcow2"
+  name       = "{}-{}"
+  size_bytes = {}
+  format     = "qcow2"
 }}
 
 "#,
                 instance.name, vol.id,
                 instance.name, vol.id,
-                vol.size_mb,
-                vol.kind,
+                vol.size_mb * 1024 * 1024,
             ));
         }
 
+        let disk = tpl.volumes.first()
+            .map(|v| format!("infrasim_volume.{}-{}.path", instance.name, v.id))
+            .unwrap_or_else(|| "\"\"".to_string());
         hcl.push_str(&format!(r#"resource "infrasim_vm" "{}" {{
-  name             = "{}"
-  arch             = "{}"
-  machine          = "{}"
-  cpu_cores        = {}
-  memory_mb        = {}
-  compatibility_mode = {}
-
-  network_ids = [{}]
-  volume_ids  = [{}]
-}}
-
+  name       = "{}"
+  cpus       = {}
+  memory     = {}
+  disk       = {}
+  volume_ids = [{}]
 "#,
             instance.name,
             instance.name,
-            tpl.arch,
-            tpl.machine,
             tpl.cpu_cores,
             tpl.memory_mb,
-            tpl.compatibility_mode,
-            tpl.networks.iter().map(|n| format!("infrasim_network.{}-{}.id", instance.name, n.id)).collect::<Vec<_>>().join(", "),
+            disk,
             tpl.volumes.iter().map(|v| format!("infrasim_volume.{}-{}.id", instance.name, v.id)).collect::<Vec<_>>().join(", "),
         ));
+        if let Some(net) = tpl.networks.first() {
+            hcl.push_str(&format!("  network_id = infrasim_network.{}-{}.id\n", instance.name, net.id));
+        }
+        hcl.push_str("}\n\n");
+
+        // Same ports the web UI forwards when it creates the appliance
+        for port in tpl.ports.iter().filter(|p| p.host_port.is_some()) {
+            if port.container_port == 22 && port.host_port == Some(2222) {
+                continue;
+            }
+            hcl.push_str(&format!(r#"resource "infrasim_port_forward" "{}-{}-{}" {{
+  name       = "{}-{}-{}"
+  vm_id      = infrasim_vm.{}.id
+  protocol   = "{}"
+  host_port  = {}
+  guest_port = {}
+}}
+
+"#,
+                instance.name, port.protocol, port.container_port,
+                instance.name, port.protocol, port.container_port,
+                instance.name,
+                port.protocol,
+                port.host_port.unwrap_or_default(),
+                port.container_port,
+            ));
+        }
 
         hcl.push_str(&format!(r#"resource "infrasim_console" "{}-console" {{
   vm_id      = infrasim_vm.{}.id
@@ -4446,7 +9055,12 @@ provider "infrasim" {{
   enable_web = true
   web_port   = 6080
 }}
-"#, instance.name, instance.name));
+
+output "{}-console-token" {{
+  value     = infrasim_console.{}-console.auth_token
+  sensitive = true
+}}
+"#, instance.name, instance.name, instance.name, instance.name));
     }
 
     hcl
@@ -4600,7 +9214,7 @@ fn parse_llm_response(json_str: &str) -> Option<(String, Option<String>, Vec<Net
 
 /// AI / LangChain-style prompt bridge handler.
 async fn ai_define_handler(
-    State(_state): State<Arc<WebServerState>>,
+    State(state): State<Arc<WebServerState>>,
     Json(req): Json<AiDefineRequest>,
 ) -> Response {
     let backend = llm_backend();
@@ -4610,7 +9224,7 @@ async fn ai_define_handler(
     if !matches!(backend, LlmBackend::RuleBased) {
         if let Some(llm_response) = call_llm_backend(&backend, &req.prompt).await {
             if let Some((intent, template_id, networks, volumes, tools)) = parse_llm_response(&llm_response) {
-                let templates = builtin_appliance_templates();
+                let templates = state.templates.read().await.list();
                 let appliance_template = template_id
                     .as_ref()
                     .and_then(|tid| templates.iter().find(|t| &t.id == tid))
@@ -4643,7 +9257,7 @@ async fn ai_define_handler(
     // Keycloak / Identity patterns
     if prompt_lower.contains("keycloak") || prompt_lower.contains("identity") || prompt_lower.contains("sso") || prompt_lower.contains("oauth") || prompt_lower.contains("oidc") {
         intent = "create_keycloak_appliance".to_string();
-        let templates = builtin_appliance_templates();
+        let templates = state.templates.read().await.list();
         if let Some(kc) = templates.iter().find(|t| t.id == "keycloak-aarch64") {
             appliance_template = Some(kc.clone());
             networks = kc.networks.clone();
@@ -4655,7 +9269,7 @@ async fn ai_define_handler(
     // Pi-like desktop patterns
     else if prompt_lower.contains("pi") || prompt_lower.contains("raspberry") || prompt_lower.contains("desktop") || prompt_lower.contains("kali") {
         intent = "create_pi_desktop".to_string();
-        let templates = builtin_appliance_templates();
+        let templates = state.templates.read().await.list();
         if let Some(pi) = templates.iter().find(|t| t.id == "pi-like-aarch64-desktop") {
             appliance_template = Some(pi.clone());
             networks = pi.networks.clone();
@@ -4794,20 +9408,33 @@ fn generate_terraform_for_resources(
     hcl
 }
 
+/// A page of a project's prompts, optionally filtered by `q`
 async fn list_prompts_handler(
     State(state): State<Arc<WebServerState>>,
     Path(project_id): Path<String>,
+    Query(query): Query<PromptQuery>,
 ) -> Response {
-    let projects = state.projects.read().await;
-    let Some(project) = projects.get(&project_id) else {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "project not found"})),
-        )
-            .into_response();
-    };
+    match state.projects.get(&project_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return project_not_found(),
+        Err(e) => return project_store_error(e),
+    }
+
+    match state.projects.prompts(Some(&project_id), &query) {
+        Ok(page) => Json(page).into_response(),
+        Err(e) => project_store_error(e),
+    }
+}
 
-    Json(serde_json::json!({"prompts": project.prompts})).into_response()
+/// Search prompts across all projects
+async fn search_prompts_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(query): Query<PromptQuery>,
+) -> Response {
+    match state.projects.prompts(None, &query) {
+        Ok(page) => Json(page).into_response(),
+        Err(e) => project_store_error(e),
+    }
 }
 
 async fn create_prompt_handler(
@@ -4823,25 +9450,11 @@ async fn create_prompt_handler(
             .into_response();
     }
 
-    let mut projects = state.projects.write().await;
-    let Some(project) = projects.get_mut(&project_id) else {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "project not found"})),
-        )
-            .into_response();
-    };
-
-    let prompt = Prompt {
-        id: uuid::Uuid::new_v4().to_string(),
-        title: req.title,
-        body: req.body,
-        created_at: chrono::Utc::now().timestamp(),
-        llm_provider: req.llm_provider,
-    };
-    project.prompts.push(prompt.clone());
-
-    (StatusCode::CREATED, Json(prompt)).into_response()
+    match state.projects.add_prompt(&project_id, &req.title, &req.body, req.llm_provider) {
+        Ok(Some(prompt)) => (StatusCode::CREATED, Json(prompt)).into_response(),
+        Ok(None) => project_not_found(),
+        Err(e) => project_store_error(e),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -4855,13 +9468,10 @@ async fn terraform_generate_handler(
     Json(req): Json<TerraformGenerateRequest>,
 ) -> Response {
     // MVP: deterministic scaffold; later this will call configured LLMs.
-    let projects = state.projects.read().await;
-    if !projects.contains_key(&req.project_id) {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "project not found"})),
-        )
-            .into_response();
+    match state.projects.get(&req.project_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return project_not_found(),
+        Err(e) => return project_store_error(e),
     }
 
     let tf = format!(
@@ -4951,16 +9561,12 @@ async fn attest_project_handler(
     State(state): State<Arc<WebServerState>>,
     Json(req): Json<AttestProjectRequest>,
 ) -> Response {
-    let projects = state.projects.read().await;
-    let Some(project) = projects.get(&req.project_id) else {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "project not found"})),
-        )
-            .into_response();
+    let project = match state.projects.export(&req.project_id) {
+        Ok(Some(project)) => project,
+        Ok(None) => return project_not_found(),
+        Err(e) => return project_store_error(e),
     };
 
-    let key_pair = KeyPair::generate();
     let payload = serde_json::json!({
         "project": project,
         "daemon_addr": state.cfg.daemon_addr,
@@ -4968,17 +9574,29 @@ async fn attest_project_handler(
     });
     let serialized = serde_json::to_vec(&payload).unwrap_or_default();
     let digest = infrasim_common::cas::ContentAddressedStore::hash(&serialized);
-    let signature = key_pair.sign(digest.as_bytes());
+    let signed = match state.daemon.sign_payload(digest.as_bytes(), "project-attestation").await {
+        Ok(signed) => signed,
+        Err(e) => return daemon_error_response(e),
+    };
 
     (StatusCode::OK, Json(serde_json::json!({
         "digest": format!("sha256:{}", digest),
-        "signature": hex::encode(signature),
-        "public_key": key_pair.public_key_hex(),
+        "signature": hex::encode(signed.signature),
+        "public_key": signed.public_key,
+        "key_id": signed.key_id,
         "note": "MVP attestation for project metadata; wire into daemon attestation for VMs/volumes next.",
     })))
         .into_response()
 }
 
+/// Public key that signs evidence and attestations, for verifiers to pin.
+async fn signing_key_handler(State(state): State<Arc<WebServerState>>) -> Response {
+    match state.daemon.signing_key().await {
+        Ok(key) => (StatusCode::OK, Json(key)).into_response(),
+        Err(e) => daemon_error_response(e),
+    }
+}
+
 async fn provenance_evidence_handler(
     State(state): State<Arc<WebServerState>>,
     Json(req): Json<ProvenanceEvidenceRequest>,
@@ -5008,16 +9626,10 @@ async fn provenance_evidence_handler(
     };
 
     let project = if let Some(id) = &req.project_id {
-        let projects = state.projects.read().await;
-        match projects.get(id).cloned() {
-            Some(p) => Some(p),
-            None => {
-                return (
-                    StatusCode::NOT_FOUND,
-                    Json(serde_json::json!({"error": "project not found"})),
-                )
-                    .into_response();
-            }
+        match state.projects.export(id) {
+            Ok(Some(p)) => Some(p),
+            Ok(None) => return project_not_found(),
+            Err(e) => return project_store_error(e),
         }
     } else {
         None
@@ -5052,91 +9664,408 @@ async fn provenance_evidence_handler(
     let digest_hex = infrasim_common::cas::ContentAddressedStore::hash(&bytes);
     let digest = format!("sha256:{}", digest_hex);
 
-    // For MVP we use an ephemeral signature key. Next step: use daemon signing key / TPM-backed key.
-    let key_pair = KeyPair::generate();
-    let sig = key_pair.sign(digest.as_bytes());
+    let signed = match state.daemon.sign_payload(digest.as_bytes(), "provenance-evidence").await {
+        Ok(signed) => signed,
+        Err(e) => return daemon_error_response(e),
+    };
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "digest": digest,
+        "signature": hex::encode(signed.signature),
+        "public_key": signed.public_key,
+        "key_id": signed.key_id,
+        "manifest": manifest,
+        "note": "MVP evidence bundle: signs manifest digest. Wire to daemon CAS + attestation provider next.",
+    })))
+        .into_response()
+}
+
+async fn list_vms_handler(
+    State(state): State<Arc<WebServerState>>,
+) -> impl IntoResponse {
+    let targets = state.vnc_targets.read().await;
+    let vms: Vec<_> = targets
+        .iter()
+        .map(|(id, (host, port))| {
+            serde_json::json!({
+                "id": id,
+                "vnc_host": host,
+                "vnc_port": port,
+                "web_url": format!("/vnc.html?autoconnect=1&path=websockify/{}", id)
+            })
+        })
+        .collect();
+
+    Json(serde_json::json!({ "vms": vms }))
+}
+
+#[derive(Deserialize)]
+struct VncQuery {
+    token: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/vms/{vm_id}/vnc",
+    tag = "vms",
+    params(("vm_id" = String, Path, description = "VM ID")),
+    responses(
+        (status = 200, description = "VNC endpoint and console URLs", body = serde_json::Value),
+        (status = 404, description = "No VNC display for this VM", body = ApiErrorBody)
+    )
+)]
+async fn vnc_info_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+) -> Response {
+    let targets = state.vnc_targets.read().await;
+    
+    match targets.get(&vm_id) {
+        Some((host, port)) => Json(serde_json::json!({
+            "vm_id": vm_id,
+            "vnc_host": host,
+            "vnc_port": port,
+            "websocket_path": format!("/websockify/{}", vm_id),
+            "web_url": format!("/vnc.html?autoconnect=1&path=websockify/{}", vm_id)
+        }))
+        .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "VM not found" })),
+        )
+            .into_response(),
+    }
+}
+
+async fn websocket_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+    Query(query): Query<VncQuery>,
+    headers: axum::http::HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // Validate token if required
+    // (MVP: optional token validation)
+
+    let target = state.vnc_targets.read().await.get(&vm_id).cloned();
+
+    match target {
+        Some((host, port)) => {
+            // A suspended VM has no VNC server until it is restored
+            if let Err(e) = state.daemon.wake_vm(&vm_id).await {
+                debug!("Waking VM {} for its console failed: {}", vm_id, e);
+            }
+
+            // The console path is outside the auth middleware; name the
+            // recording's subject after the session token when there is one
+            let subject = query
+                .token
+                .as_deref()
+                .or_else(|| bearer_token(&headers))
+                .and_then(|token| {
+                    let conn_arc = state.db.connection();
+                    let conn = conn_arc.lock();
+                    session_token_identity(&conn, token, now_epoch_secs())
+                })
+                .unwrap_or_else(|| "anonymous".to_string());
+
+            // noVNC-style clients ask for the "binary" subprotocol
+            let shutdown = state.shutdown.clone();
+            ws.protocols(["binary"]).on_upgrade(move |socket| shutdown.track(async move {
+                let recorder = match state.recordings.start(&vm_id, RecordingKind::Vnc, &subject).await {
+                    Ok(recorder) => recorder,
+                    Err(e) => {
+                        warn!("Recording the console of VM {} failed: {}", vm_id, e);
+                        None
+                    }
+                };
+                let closing = state.shutdown.closing();
+                if let Err(e) = handle_vnc_websocket(socket, host, port, recorder, closing).await {
+                    error!("VNC WebSocket error: {}", e);
+                }
+            }))
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            "VM not found",
+        )
+            .into_response(),
+    }
+}
+
+/// Bridge a WebSocket to a VM's serial port (requires `vm:console`)
+///
+/// Binary and text frames from the client are typed into the guest; guest
+/// output is sent back as binary frames.
+async fn serial_websocket_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(vm_id): Path<String>,
+    subject: Option<Extension<CallerSubject>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    // Attach before upgrading so a busy or stopped VM is reported as an HTTP error
+    let (input, output) = match state.daemon.attach_serial(&vm_id).await {
+        Ok(session) => session,
+        Err(e) => return daemon_error_response(e),
+    };
+    let subject = subject.map(|Extension(CallerSubject(s))| s).unwrap_or_default();
+
+    let shutdown = state.shutdown.clone();
+    ws.protocols(["binary"]).on_upgrade(move |socket| shutdown.track(async move {
+        let recorder = match state.recordings.start(&vm_id, RecordingKind::Serial, &subject).await {
+            Ok(recorder) => recorder,
+            Err(e) => {
+                warn!("Recording the serial console of VM {} failed: {}", vm_id, e);
+                None
+            }
+        };
+        let closing = state.shutdown.closing();
+        if let Err(e) = handle_serial_websocket(socket, input, output, recorder, closing).await {
+            debug!("Serial console WebSocket closed: {}", e);
+        }
+    }))
+}
+
+/// Relay between a WebSocket and an attached serial console until either
+/// side closes or the server shuts down
+async fn handle_serial_websocket(
+    mut socket: WebSocket,
+    input: tokio::sync::mpsc::Sender<Vec<u8>>,
+    mut output: tonic::Streaming<SerialOutput>,
+    recorder: Option<recording::Recorder>,
+    closing: tokio_util::sync::CancellationToken,
+) -> anyhow::Result<()> {
+    loop {
+        tokio::select! {
+            _ = closing.cancelled() => {
+                let _ = socket.send(crate::shutdown::restart_frame()).await;
+                return Ok(());
+            }
+            msg = socket.recv() => {
+                let data = match msg {
+                    Some(Ok(Message::Binary(data))) => data,
+                    Some(Ok(Message::Text(text))) => text.into_bytes(),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                    Some(Ok(_)) => continue,
+                };
+                if let Some(recorder) = &recorder {
+                    recorder.input(&data).await;
+                }
+                input.send(data).await?;
+            }
+            out = output.message() => match out? {
+                Some(out) => {
+                    if let Some(recorder) = &recorder {
+                        recorder.output(&out.data).await;
+                    }
+                    socket.send(Message::Binary(out.data)).await?;
+                }
+                None => {
+                    let _ = socket.send(Message::Close(None)).await;
+                    return Ok(());
+                }
+            },
+        }
+    }
+}
+
+// ============================================================================
+// Console recordings
+// ============================================================================
+
+fn recording_store_error(e: infrasim_common::Error) -> Response {
+    error!("recording store: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+}
+
+fn recording_not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({"error": "recording not found"})),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ListRecordingsQuery {
+    vm_id: Option<String>,
+}
+
+/// Recorded console sessions, newest first, optionally of one VM
+async fn list_recordings_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(query): Query<ListRecordingsQuery>,
+) -> Response {
+    match state.recordings.list(query.vm_id.as_deref()) {
+        Ok(recordings) => Json(serde_json::json!({ "recordings": recordings })).into_response(),
+        Err(e) => recording_store_error(e),
+    }
+}
+
+async fn get_recording_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(recording_id): Path<String>,
+) -> Response {
+    match state.recordings.get(&recording_id) {
+        Ok(Some(recording)) => Json(recording).into_response(),
+        Ok(None) => recording_not_found(),
+        Err(e) => recording_store_error(e),
+    }
+}
 
-    (StatusCode::OK, Json(serde_json::json!({
-        "digest": digest,
-        "signature": hex::encode(sig),
-        "public_key": key_pair.public_key_hex(),
-        "manifest": manifest,
-        "note": "MVP evidence bundle: signs manifest digest. Wire to daemon CAS + attestation provider next.",
-    })))
-        .into_response()
+#[derive(Debug, Deserialize)]
+struct RecordingRetentionRequest {
+    /// Exempt the recording from pruning
+    keep: Option<bool>,
+    /// New expiry, Unix seconds
+    expires_at: Option<i64>,
 }
 
-async fn list_vms_handler(
+/// Keep a recording or move its expiry
+async fn update_recording_handler(
     State(state): State<Arc<WebServerState>>,
-) -> impl IntoResponse {
-    let targets = state.vnc_targets.read().await;
-    let vms: Vec<_> = targets
-        .iter()
-        .map(|(id, (host, port))| {
-            serde_json::json!({
-                "id": id,
-                "vnc_host": host,
-                "vnc_port": port,
-                "web_url": format!("/vnc.html?autoconnect=1&path=websockify/{}", id)
-            })
-        })
-        .collect();
+    Path(recording_id): Path<String>,
+    Json(req): Json<RecordingRetentionRequest>,
+) -> Response {
+    match state.recordings.set_retention(&recording_id, req.keep, req.expires_at) {
+        Ok(Some(recording)) => Json(recording).into_response(),
+        Ok(None) => recording_not_found(),
+        Err(e) => recording_store_error(e),
+    }
+}
 
-    Json(serde_json::json!({ "vms": vms }))
+async fn delete_recording_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(recording_id): Path<String>,
+) -> Response {
+    match state.recordings.delete(&recording_id) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => recording_not_found(),
+        Err(e) => recording_store_error(e),
+    }
 }
 
-#[derive(Deserialize)]
-struct VncQuery {
-    token: Option<String>,
+/// The recording file: a JSON header line, then one JSON frame per line
+async fn download_recording_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(recording_id): Path<String>,
+) -> Response {
+    use futures::StreamExt;
+    use tokio_util::codec::{BytesCodec, FramedRead};
+
+    match state.recordings.get(&recording_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return recording_not_found(),
+        Err(e) => return recording_store_error(e),
+    }
+    let file = match tokio::fs::File::open(state.recordings.path(&recording_id)).await {
+        Ok(file) => file,
+        Err(e) => return recording_store_error(e.into()),
+    };
+    let body = axum::body::Body::from_stream(FramedRead::new(file, BytesCodec::new()).map(|chunk| chunk.map(|b| b.freeze())));
+    Response::builder()
+        .header("content-type", "application/x-ndjson")
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"console-{}.jsonl\"", recording_id),
+        )
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
 }
 
-async fn vnc_info_handler(
+async fn get_recording_policy_handler(
     State(state): State<Arc<WebServerState>>,
     Path(vm_id): Path<String>,
 ) -> Response {
-    let targets = state.vnc_targets.read().await;
-    
-    match targets.get(&vm_id) {
-        Some((host, port)) => Json(serde_json::json!({
-            "vm_id": vm_id,
-            "vnc_host": host,
-            "vnc_port": port,
-            "websocket_path": format!("/websockify/{}", vm_id),
-            "web_url": format!("/vnc.html?autoconnect=1&path=websockify/{}", vm_id)
-        }))
-        .into_response(),
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "VM not found" })),
-        )
-            .into_response(),
+    match state.recordings.policy(&vm_id) {
+        Ok(policy) => Json(policy).into_response(),
+        Err(e) => recording_store_error(e),
     }
 }
 
-async fn websocket_handler(
+/// Turn recording of a VM's consoles on or off and set its retention
+async fn put_recording_policy_handler(
     State(state): State<Arc<WebServerState>>,
     Path(vm_id): Path<String>,
-    Query(query): Query<VncQuery>,
+    Json(mut policy): Json<RecordingPolicy>,
+) -> Response {
+    policy.vm_id = vm_id;
+    match state.recordings.set_policy(&policy) {
+        Ok(()) => Json(policy).into_response(),
+        Err(e) => recording_store_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplayQuery {
+    /// Playback speed, 0.25 to 16 times real time
+    speed: Option<f64>,
+}
+
+/// Play a recorded session to a noVNC or xterm.js client (requires `recording:read`)
+async fn replay_websocket_handler(
+    State(state): State<Arc<WebServerState>>,
+    Path(recording_id): Path<String>,
+    Query(query): Query<ReplayQuery>,
     ws: WebSocketUpgrade,
 ) -> Response {
-    // Validate token if required
-    // (MVP: optional token validation)
+    match state.recordings.get(&recording_id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return recording_not_found(),
+        Err(e) => return recording_store_error(e),
+    }
+    let path = state.recordings.path(&recording_id);
+    let speed = query.speed.unwrap_or(1.0).clamp(0.25, 16.0);
 
-    let targets = state.vnc_targets.read().await;
-    
-    match targets.get(&vm_id).cloned() {
-        Some((host, port)) => {
-            ws.on_upgrade(move |socket| async move {
-                if let Err(e) = handle_vnc_websocket(socket, host, port).await {
-                    error!("VNC WebSocket error: {}", e);
+    ws.protocols(["binary"]).on_upgrade(move |socket| state.shutdown.clone().track(async move {
+        if let Err(e) = recording::replay(socket, &path, speed).await {
+            debug!("Console replay closed: {}", e);
+        }
+    }))
+}
+
+async fn events_websocket_handler(
+    State(state): State<Arc<WebServerState>>,
+    Query(params): Query<HashMap<String, String>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let resource_type = params.get("type").cloned().unwrap_or_default();
+    let resource_id = params.get("id").cloned().unwrap_or_default();
+
+    let shutdown = state.shutdown.clone();
+    ws.on_upgrade(move |socket| shutdown.track(async move {
+        if let Err(e) = handle_events_websocket(socket, &state.daemon, resource_type, resource_id).await {
+            debug!("Events WebSocket closed: {}", e);
+        }
+    }))
+}
+
+/// Relay daemon events to a WebSocket as JSON text frames until either side closes
+async fn handle_events_websocket(
+    mut socket: WebSocket,
+    daemon: &DaemonProxy,
+    resource_type: String,
+    resource_id: String,
+) -> anyhow::Result<()> {
+    let mut events = daemon.watch_events(resource_type, resource_id).await?;
+
+    loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => {}
+            },
+            event = events.message() => match event? {
+                Some(event) => {
+                    let json = serde_json::to_string(&EventInfo::from(event))?;
+                    socket.send(Message::Text(json)).await?;
                 }
-            })
+                None => {
+                    let _ = socket.send(Message::Close(None)).await;
+                    return Ok(());
+                }
+            },
         }
-        None => (
-            StatusCode::NOT_FOUND,
-            "VM not found",
-        )
-            .into_response(),
     }
 }
 
@@ -5144,11 +10073,13 @@ async fn handle_vnc_websocket(
     socket: WebSocket,
     vnc_host: String,
     vnc_port: u16,
+    recorder: Option<recording::Recorder>,
+    closing: tokio_util::sync::CancellationToken,
 ) -> anyhow::Result<()> {
     debug!("VNC WebSocket connecting to {}:{}", vnc_host, vnc_port);
 
     let proxy = VncProxy::new(&vnc_host, vnc_port);
-    proxy.bridge(socket).await?;
+    proxy.bridge(socket, recorder, closing).await?;
 
     Ok(())
 }
@@ -5161,6 +10092,14 @@ async fn vnc_html_handler() -> impl IntoResponse {
     Html(VNC_HTML)
 }
 
+async fn serial_html_handler() -> impl IntoResponse {
+    Html(SERIAL_HTML)
+}
+
+async fn admin_terminal_html_handler() -> impl IntoResponse {
+    Html(ADMIN_TERMINAL_HTML)
+}
+
 async fn vnc_lite_handler() -> impl IntoResponse {
     Html(VNC_LITE_HTML)
 }
@@ -5171,9 +10110,10 @@ async fn pipeline_analyzer_handler() -> impl IntoResponse {
 
 async fn static_handler(
     State(state): State<Arc<WebServerState>>,
-    Path(path): Path<String>,
+    uri: axum::http::Uri,
 ) -> Response {
-    state.static_files.serve(&path).await
+    // Wildcard captures drop the `/core/`-style prefix the files are keyed by
+    state.static_files.serve(uri.path()).await
 }
 
 async fn ui_index_handler(State(state): State<Arc<WebServerState>>) -> Response {
@@ -5238,45 +10178,481 @@ async fn ui_serve_path(state: Arc<WebServerState>, rel: &str) -> Response {
             )
                 .into_response()
         }
-        Err(_) => (StatusCode::NOT_FOUND, "Not found").into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
+}
+
+// ============================================================================
+// UI Manifest Handler
+// ============================================================================
+
+async fn ui_manifest_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
+    // Try to read ui.manifest.json from static directory
+    if let Some(ref dir) = state.ui_static.dir {
+        let manifest_path = dir.join("ui.manifest.json");
+        if let Ok(content) = tokio::fs::read_to_string(&manifest_path).await {
+            if let Ok(manifest) = serde_json::from_str::<UiManifest>(&content) {
+                return Json(manifest).into_response();
+            }
+        }
+    }
+    
+    // Return a default/dev manifest if not found
+    let dev_manifest = UiManifest {
+        schema_version: "1".to_string(),
+        ui_version: "0.0.0-dev".to_string(),
+        git_commit: "".to_string(),
+        git_branch: "".to_string(),
+        build_timestamp: chrono::Utc::now().to_rfc3339(),
+        total_size_bytes: 0,
+        asset_count: 0,
+        api_schema_version: "1".to_string(),
+        declared_resource_kinds: vec!["appliance".to_string(), "filesystem".to_string()],
+        mount_point: "/ui/".to_string(),
+        assets: vec![],
+    };
+    Json(dev_manifest).into_response()
+}
+
+// ============================================================================
+// Filesystem Resource Handlers
+// ============================================================================
+
+/// How often filesystems are reconciled against daemon volumes and lifecycle rules
+const FILESYSTEM_RECONCILE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Label tying a daemon volume to the filesystem it holds
+const FILESYSTEM_LABEL: &str = "infrasim.filesystem";
+
+async fn load_filesystems_into_memory(state: Arc<WebServerState>) -> anyhow::Result<()> {
+    let db = state.db.clone();
+    let rows = tokio::task::spawn_blocking(move || {
+        db.list::<Filesystem, serde_json::Value>("filesystems")
+    })
+    .await??;
+
+    let mut filesystems = state.filesystems.write().await;
+    for row in rows {
+        filesystems.insert(row.id, row.spec);
+    }
+    Ok(())
+}
+
+async fn persist_filesystem(state: &WebServerState, fs: &Filesystem) -> anyhow::Result<()> {
+    let db = state.db.clone();
+    let fs = fs.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let status = serde_json::json!({"status": fs.status, "error": fs.error});
+        match db.exists("filesystems", &fs.id) {
+            Ok(true) => db.update("filesystems", &fs.id, Some(&fs), Some(&status)),
+            Ok(false) => db.insert("filesystems", &fs.id, &fs.name, &fs, &status, &fs.labels),
+            Err(e) => Err(e),
+        }
+    })
+    .await??;
+
+    Ok(())
+}
+
+/// Daemon volume kind and source holding a new filesystem
+fn filesystem_backing(req: &CreateFilesystemRequest) -> Result<(VolumeKind, String), String> {
+    let backing_store = req.backing_store.clone().unwrap_or_default();
+    match req.fs_type {
+        FilesystemType::Local => Ok((VolumeKind::Disk, String::new())),
+        FilesystemType::Geobound if req.geographic_bounds.is_some() => Ok((VolumeKind::Disk, String::new())),
+        FilesystemType::Geobound => Err("geobound filesystems need geographic_bounds".to_string()),
+        FilesystemType::Ephemeral => Ok((VolumeKind::Disk, "tmpfs://".to_string())),
+        FilesystemType::Network if backing_store.starts_with("nfs://") => Ok((VolumeKind::Disk, backing_store)),
+        FilesystemType::Network => Err("network filesystems need an nfs:// backing_store".to_string()),
+        FilesystemType::Physical if !backing_store.is_empty() => Ok((
+            VolumeKind::Device,
+            backing_store.trim_start_matches("file://").to_string(),
+        )),
+        FilesystemType::Physical => Err("physical filesystems need the device path as backing_store".to_string()),
+        FilesystemType::Snapshot => {
+            Err("snapshots are taken with POST /api/filesystems/:fs_id/snapshot".to_string())
+        }
+    }
+}
+
+/// Host directory a new `local` filesystem shares with guests instead of
+/// holding its data in a volume: a `file://` backing store naming it
+fn shared_host_dir<'a>(fs_type: &FilesystemType, backing_store: &'a str) -> Option<&'a str> {
+    match fs_type {
+        FilesystemType::Local => backing_store.strip_prefix("file://").filter(|p| p.starts_with('/')),
+        _ => None,
+    }
+}
+
+/// Host directory a filesystem shares; volume-backed ones point their
+/// backing store at the volume's image instead
+fn filesystem_host_dir(fs: &Filesystem) -> Option<&str> {
+    match fs.volume_id {
+        Some(_) => None,
+        None => shared_host_dir(&fs.fs_type, &fs.backing_store),
+    }
+}
+
+/// Shared folder mounting a host-directory filesystem at its mount path
+fn filesystem_shared_folder(fs: &Filesystem, host_dir: &str, read_only: bool) -> SharedFolder {
+    SharedFolder {
+        tag: String::new(),
+        host_path: host_dir.to_string(),
+        guest_path: fs.mount_path.clone(),
+        read_only,
+        ..Default::default()
+    }
+}
+
+/// Image format of a filesystem's volume; guest filesystems (ext4, xfs, ...) live in qcow2
+fn filesystem_image_format(format: &str) -> &'static str {
+    if format == "raw" {
+        "raw"
+    } else {
+        "qcow2"
+    }
+}
+
+/// Backing store URI of a prepared volume path
+fn backing_store_uri(local_path: &str) -> String {
+    if local_path.starts_with("nfs://") {
+        local_path.to_string()
+    } else {
+        format!("file://{}", local_path)
+    }
+}
+
+/// Delete a daemon volume; one that is already gone is not an error
+async fn delete_backing_volume(state: &WebServerState, vol_id: &str) -> anyhow::Result<()> {
+    match state.daemon.delete_volume(vol_id).await {
+        Err(e) if e.downcast_ref::<tonic::Status>().map(|s| s.code()) != Some(tonic::Code::NotFound) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Delete a filesystem with its daemon volumes and persisted record
+async fn remove_filesystem(
+    state: &WebServerState,
+    filesystems: &mut HashMap<String, Filesystem>,
+    id: &str,
+) -> anyhow::Result<()> {
+    let Some(fs) = filesystems.get(id) else {
+        return Ok(());
+    };
+    for vol_id in fs.volume_id.iter().chain(fs.archive_volume_id.iter()) {
+        delete_backing_volume(state, vol_id).await?;
+    }
+
+    let db = state.db.clone();
+    let row_id = id.to_string();
+    tokio::task::spawn_blocking(move || db.delete("filesystems", &row_id)).await??;
+
+    filesystems.remove(id);
+    Ok(())
+}
+
+/// Take a point-in-time copy of a filesystem as a new read-only `snapshot` filesystem
+///
+/// The daemon copies the volume in the background, so the snapshot is
+/// `pending` until reconciled. Snapshots beyond the parent's `max_snapshots`
+/// are deleted, oldest first.
+async fn snapshot_filesystem(
+    state: &WebServerState,
+    filesystems: &mut HashMap<String, Filesystem>,
+    parent_id: &str,
+    name: Option<String>,
+) -> anyhow::Result<Filesystem> {
+    let parent = filesystems
+        .get(parent_id)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Filesystem not found"))?;
+    let parent_volume = parent
+        .volume_id
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("Filesystem {} has no volume", parent.name))?;
+
+    let now = Utc::now();
+    let id = Uuid::new_v4().to_string();
+    let name = name.unwrap_or_else(|| format!("{}-{}", parent.name, now.format("%Y%m%d%H%M%S")));
+
+    let spec = VolumeSpec {
+        kind: VolumeKind::Disk.into(),
+        source: format!("volume://{}", parent_volume),
+        integrity: None,
+        read_only: true,
+        size_bytes: parent.size_bytes,
+        format: filesystem_image_format(&parent.format).to_string(),
+        overlay: false,
+        // Copies of encrypted volumes are encrypted with the same key
+        encryption: parent.encryption.as_ref().map(FilesystemEncryption::to_proto),
+    };
+    let mut volume_labels = HashMap::new();
+    volume_labels.insert(FILESYSTEM_LABEL.to_string(), id.clone());
+    let volume_id = state
+        .daemon
+        .create_volume_from_spec(&format!("fs-{}", name), spec, volume_labels)
+        .await?;
+
+    let mut labels = parent.labels.clone();
+    labels.insert("snapshot_of".to_string(), parent.id.clone());
+    let snapshot = Filesystem {
+        id,
+        name,
+        fs_type: FilesystemType::Snapshot,
+        backing_store: String::new(),
+        size_bytes: parent.size_bytes,
+        used_bytes: 0,
+        mutability: FilesystemMutability::ReadOnly,
+        geographic_bounds: parent.geographic_bounds.clone(),
+        lifecycle: FilesystemLifecycle::default(),
+        provenance: Some(FilesystemProvenance {
+            digest: String::new(),
+            signature: None,
+            signer_public_key: None,
+            computed_at: 0,
+            source_uri: Some(format!("infrasim://filesystems/{}", parent.id)),
+            parent_id: Some(parent.id.clone()),
+            attestations: Vec::new(),
+        }),
+        attached_to: Vec::new(),
+        mount_path: parent.mount_path.clone(),
+        format: parent.format.clone(),
+        created_at: now.timestamp(),
+        updated_at: now.timestamp(),
+        labels,
+        volume_id: Some(volume_id.clone()),
+        status: "pending".to_string(),
+        error: None,
+        last_active_at: now.timestamp(),
+        archive_volume_id: None,
+        encryption: parent.encryption.clone(),
+    };
+    if let Err(e) = persist_filesystem(state, &snapshot).await {
+        let _ = delete_backing_volume(state, &volume_id).await;
+        return Err(e);
+    }
+    filesystems.insert(snapshot.id.clone(), snapshot.clone());
+    info!("Snapshotting filesystem {} as {}", parent.name, snapshot.name);
+
+    if parent.lifecycle.max_snapshots > 0 {
+        let mut snapshots: Vec<(i64, String)> = filesystems
+            .values()
+            .filter(|fs| {
+                fs.fs_type == FilesystemType::Snapshot
+                    && fs.attached_to.is_empty()
+                    && fs.provenance.as_ref().and_then(|p| p.parent_id.as_deref()) == Some(parent.id.as_str())
+            })
+            .map(|fs| (fs.created_at, fs.id.clone()))
+            .collect();
+        snapshots.sort();
+        let excess = snapshots.len().saturating_sub(parent.lifecycle.max_snapshots as usize);
+        for (_, id) in snapshots.into_iter().take(excess) {
+            if let Err(e) = remove_filesystem(state, filesystems, &id).await {
+                warn!("failed to prune snapshot {} of filesystem {}: {}", id, parent.name, e);
+            }
+        }
+    }
+
+    Ok(snapshot)
+}
+
+/// Whether an appliance's VM is running and so holds its volumes open
+async fn appliance_vm_running(state: &WebServerState, appliance_id: &str) -> bool {
+    let vm_id = state.appliances.read().await.get(appliance_id).and_then(|a| a.vm_id.clone());
+    match vm_id {
+        Some(vm_id) => state
+            .daemon
+            .get_vm(&vm_id)
+            .await
+            .map(|vm| matches!(vm.state.as_str(), "running" | "paused"))
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Reconcile filesystems against their daemon volumes and apply lifecycle rules
+///
+/// - status, backing store and usage follow the daemon volume
+/// - `ttl_seconds`: expired filesystems are detached and deleted
+/// - `archive_when_idle`: unattached filesystems idle past `idle_threshold_seconds`
+///   are swapped for a compressed copy of their volume
+async fn reconcile_filesystems(state: &WebServerState) {
+    let volumes: HashMap<String, VolumeInfo> = match state.daemon.list_volumes().await {
+        Ok(volumes) => volumes.into_iter().map(|v| (v.id.clone(), v)).collect(),
+        Err(e) => {
+            debug!("skipping filesystem reconcile: {}", e);
+            return;
+        }
+    };
+    let appliance_vms: HashMap<String, String> = state
+        .appliances
+        .read()
+        .await
+        .iter()
+        .filter_map(|(id, a)| a.vm_id.clone().map(|vm_id| (id.clone(), vm_id)))
+        .collect();
+    let now = Utc::now().timestamp();
+
+    let mut filesystems = state.filesystems.write().await;
+    let ids: Vec<String> = filesystems.keys().cloned().collect();
+    for id in ids {
+        // Earlier iterations may have pruned it
+        let Some(fs) = filesystems.get(&id).cloned() else {
+            continue;
+        };
+
+        if fs.lifecycle.ttl_seconds > 0 && now - fs.created_at >= fs.lifecycle.ttl_seconds as i64 {
+            if let Err(e) = expire_filesystem(state, &mut filesystems, &fs, &appliance_vms).await {
+                warn!("failed to expire filesystem {}: {}", fs.name, e);
+            } else {
+                info!("Filesystem {} expired after {}s", fs.name, fs.lifecycle.ttl_seconds);
+            }
+            continue;
+        }
+
+        let mut updated = fs.clone();
+        match updated.volume_id.as_ref().map(|vol_id| volumes.get(vol_id)) {
+            Some(None) => {
+                updated.status = "missing".to_string();
+                updated.error = Some("daemon volume no longer exists".to_string());
+            }
+            Some(Some(volume)) if volume.ready => {
+                if !matches!(updated.status.as_str(), "archiving" | "archived") {
+                    updated.status = "ready".to_string();
+                }
+                updated.error = None;
+                updated.backing_store = backing_store_uri(&volume.local_path);
+                updated.used_bytes = volume.actual_size;
+                if let Some(provenance) = updated.provenance.as_mut() {
+                    if provenance.digest.is_empty() && !volume.digest.is_empty() {
+                        provenance.digest = volume.digest.clone();
+                        provenance.computed_at = now;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(archive_id) = updated.archive_volume_id.clone() {
+            match volumes.get(&archive_id) {
+                Some(archive) if archive.ready => {
+                    if let Some(old) = updated.volume_id.replace(archive_id) {
+                        if let Err(e) = delete_backing_volume(state, &old).await {
+                            warn!("failed to delete archived volume of filesystem {}: {}", fs.name, e);
+                        }
+                    }
+                    updated.archive_volume_id = None;
+                    updated.status = "archived".to_string();
+                    updated.backing_store = backing_store_uri(&archive.local_path);
+                    updated.used_bytes = archive.actual_size;
+                    info!("Archived idle filesystem {}", fs.name);
+                }
+                Some(_) => {}
+                None => {
+                    updated.archive_volume_id = None;
+                    updated.status = "ready".to_string();
+                    updated.error = Some("archive copy disappeared".to_string());
+                }
+            }
+        } else if updated.lifecycle.archive_when_idle
+            && updated.status == "ready"
+            && updated.volume_id.is_some()
+            && updated.attached_to.is_empty()
+            // Encrypted images can't be compressed
+            && updated.encryption.is_none()
+            && matches!(updated.fs_type, FilesystemType::Local | FilesystemType::Geobound | FilesystemType::Snapshot)
+            && now - updated.last_active_at >= updated.lifecycle.idle_threshold_seconds as i64
+        {
+            match archive_filesystem(state, &updated).await {
+                Ok(archive_id) => {
+                    updated.archive_volume_id = Some(archive_id);
+                    updated.status = "archiving".to_string();
+                }
+                Err(e) => updated.error = Some(format!("archive failed: {}", e)),
+            }
+        }
+
+        if serde_json::to_value(&updated).ok() != serde_json::to_value(&fs).ok() {
+            updated.updated_at = now;
+            if let Err(e) = persist_filesystem(state, &updated).await {
+                warn!("failed to persist filesystem {}: {}", updated.name, e);
+            }
+            filesystems.insert(id, updated);
+        }
     }
 }
 
-// ============================================================================
-// UI Manifest Handler
-// ============================================================================
-
-async fn ui_manifest_handler(State(state): State<Arc<WebServerState>>) -> impl IntoResponse {
-    // Try to read ui.manifest.json from static directory
-    if let Some(ref dir) = state.ui_static.dir {
-        let manifest_path = dir.join("ui.manifest.json");
-        if let Ok(content) = tokio::fs::read_to_string(&manifest_path).await {
-            if let Ok(manifest) = serde_json::from_str::<UiManifest>(&content) {
-                return Json(manifest).into_response();
-            }
+/// Detach an expired filesystem everywhere and delete it
+async fn expire_filesystem(
+    state: &WebServerState,
+    filesystems: &mut HashMap<String, Filesystem>,
+    fs: &Filesystem,
+    appliance_vms: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    for appliance_id in &fs.attached_to {
+        if let Some(vm_id) = appliance_vms.get(appliance_id) {
+            detach_backing(state, fs, vm_id).await?;
         }
     }
-    
-    // Return a default/dev manifest if not found
-    let dev_manifest = UiManifest {
-        schema_version: "1".to_string(),
-        ui_version: "0.0.0-dev".to_string(),
-        git_commit: "".to_string(),
-        git_branch: "".to_string(),
-        build_timestamp: chrono::Utc::now().to_rfc3339(),
-        total_size_bytes: 0,
-        asset_count: 0,
-        api_schema_version: "1".to_string(),
-        declared_resource_kinds: vec!["appliance".to_string(), "filesystem".to_string()],
-        mount_point: "/ui/".to_string(),
-        assets: vec![],
+    remove_filesystem(state, filesystems, &fs.id).await
+}
+
+/// Remove a filesystem's volume or shared folder from a VM
+async fn detach_backing(state: &WebServerState, fs: &Filesystem, vm_id: &str) -> anyhow::Result<()> {
+    if let Some(host_dir) = filesystem_host_dir(fs) {
+        let folder = filesystem_shared_folder(fs, host_dir, false);
+        state.daemon.set_vm_shared_folder(vm_id, folder, false).await
+    } else if let Some(volume_id) = &fs.volume_id {
+        state.daemon.set_vm_volume(vm_id, volume_id, false).await
+    } else {
+        Ok(())
+    }
+}
+
+/// Start a compressed copy of a filesystem's volume
+async fn archive_filesystem(state: &WebServerState, fs: &Filesystem) -> anyhow::Result<String> {
+    let volume_id = fs
+        .volume_id
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("no volume"))?;
+    let spec = VolumeSpec {
+        kind: VolumeKind::Disk.into(),
+        source: format!("volume://{}?compress", volume_id),
+        integrity: None,
+        read_only: fs.mutability == FilesystemMutability::ReadOnly,
+        size_bytes: fs.size_bytes,
+        // Only qcow2 images can be compressed
+        format: "qcow2".to_string(),
+        overlay: false,
+        encryption: None,
     };
-    Json(dev_manifest).into_response()
+    let mut labels = HashMap::new();
+    labels.insert(FILESYSTEM_LABEL.to_string(), fs.id.clone());
+    state
+        .daemon
+        .create_volume_from_spec(&format!("fs-{}-archive", fs.name), spec, labels)
+        .await
 }
 
-// ============================================================================
-// Filesystem Resource Handlers
-// ============================================================================
+/// A failed filesystem operation, as an HTTP status and message
+type FsError = (StatusCode, String);
+
+fn fs_error(status: StatusCode, message: impl Into<String>) -> FsError {
+    (status, message.into())
+}
+
+fn daemon_fs_error(e: anyhow::Error) -> FsError {
+    (daemon_error_status(&e), e.to_string())
+}
+
+fn fs_error_response((status, message): FsError) -> Response {
+    (status, Json(serde_json::json!({"error": message}))).into_response()
+}
+
+fn filesystem_not_found() -> FsError {
+    fs_error(StatusCode::NOT_FOUND, "Filesystem not found")
+}
 
 async fn list_filesystems_handler(
     State(state): State<Arc<WebServerState>>,
@@ -5286,22 +10662,131 @@ async fn list_filesystems_handler(
     Json(list).into_response()
 }
 
+/// Check a filesystem can be created, returning its daemon volume kind and source
+async fn check_new_filesystem(
+    state: &WebServerState,
+    req: &CreateFilesystemRequest,
+) -> Result<(VolumeKind, String), FsError> {
+    let (kind, source) = filesystem_backing(req).map_err(|e| fs_error(StatusCode::BAD_REQUEST, e))?;
+    let shared = shared_host_dir(&req.fs_type, req.backing_store.as_deref().unwrap_or_default()).is_some();
+    // Physical devices have their own size; existing NFS images and shared host directories keep theirs
+    if req.size_bytes <= 0 && kind == VolumeKind::Disk && !source.starts_with("nfs://") && !shared {
+        return Err(fs_error(StatusCode::BAD_REQUEST, "size_bytes is required"));
+    }
+    if state.filesystems.read().await.values().any(|fs| fs.name == req.name) {
+        return Err(fs_error(StatusCode::CONFLICT, format!("filesystem '{}' already exists", req.name)));
+    }
+    Ok((kind, source))
+}
+
+/// Create a filesystem and provision its daemon volume
+async fn provision_filesystem(state: &WebServerState, req: CreateFilesystemRequest) -> Result<Filesystem, FsError> {
+    let (kind, source) = check_new_filesystem(state, &req).await?;
+    let encryption = req.encryption.map(|e| FilesystemEncryption {
+        cipher: if e.cipher.is_empty() { "aes-256".to_string() } else { e.cipher.clone() },
+        ..e
+    });
+
+    let id = Uuid::new_v4().to_string();
+    let backing_store = req.backing_store.clone().unwrap_or_default();
+    if let Some(host_dir) = shared_host_dir(&req.fs_type, &backing_store) {
+        if encryption.is_some() {
+            return Err(fs_error(StatusCode::BAD_REQUEST, "shared host directories can't be encrypted"));
+        }
+        let now = Utc::now().timestamp();
+        let fs = Filesystem {
+            id,
+            name: req.name,
+            fs_type: req.fs_type,
+            backing_store: backing_store_uri(host_dir),
+            size_bytes: req.size_bytes,
+            used_bytes: 0,
+            mutability: req.mutability,
+            geographic_bounds: req.geographic_bounds,
+            lifecycle: req.lifecycle.unwrap_or_default(),
+            provenance: None,
+            attached_to: Vec::new(),
+            mount_path: req.mount_path,
+            format: req.format,
+            created_at: now,
+            updated_at: now,
+            labels: req.labels,
+            volume_id: None,
+            status: "ready".to_string(),
+            error: None,
+            last_active_at: now,
+            archive_volume_id: None,
+            encryption: None,
+        };
+        persist_filesystem(state, &fs)
+            .await
+            .map_err(|e| fs_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        state.filesystems.write().await.insert(fs.id.clone(), fs.clone());
+        info!("Created local filesystem {} sharing {}", fs.name, host_dir);
+        return Ok(fs);
+    }
+
+    let spec = VolumeSpec {
+        kind: kind.into(),
+        source: source.clone(),
+        integrity: None,
+        read_only: req.mutability == FilesystemMutability::ReadOnly,
+        size_bytes: req.size_bytes,
+        format: filesystem_image_format(&req.format).to_string(),
+        overlay: false,
+        encryption: encryption.as_ref().map(FilesystemEncryption::to_proto),
+    };
+    let mut volume_labels = req.labels.clone();
+    volume_labels.insert(FILESYSTEM_LABEL.to_string(), id.clone());
+    let volume_id = state
+        .daemon
+        .create_volume_from_spec(&format!("fs-{}", req.name), spec, volume_labels)
+        .await
+        .map_err(daemon_fs_error)?;
+
+    let now = Utc::now().timestamp();
+    let fs = Filesystem {
+        id,
+        name: req.name,
+        fs_type: req.fs_type,
+        backing_store: source,
+        size_bytes: req.size_bytes,
+        used_bytes: 0,
+        mutability: req.mutability,
+        geographic_bounds: req.geographic_bounds,
+        lifecycle: req.lifecycle.unwrap_or_default(),
+        provenance: None,
+        attached_to: Vec::new(),
+        mount_path: req.mount_path,
+        format: req.format,
+        created_at: now,
+        updated_at: now,
+        labels: req.labels,
+        volume_id: Some(volume_id.clone()),
+        status: "pending".to_string(),
+        error: None,
+        last_active_at: now,
+        archive_volume_id: None,
+        encryption: encryption.map(|e| FilesystemEncryption { passphrase: None, ..e }),
+    };
+    if let Err(e) = persist_filesystem(state, &fs).await {
+        let _ = delete_backing_volume(state, &volume_id).await;
+        return Err(fs_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+    }
+    state.filesystems.write().await.insert(fs.id.clone(), fs.clone());
+    info!("Created {:?} filesystem {} on volume {}", fs.fs_type, fs.name, volume_id);
+
+    Ok(fs)
+}
+
 async fn create_filesystem_handler(
     State(state): State<Arc<WebServerState>>,
-    Json(mut fs): Json<Filesystem>,
-) -> impl IntoResponse {
-    // Generate ID if not provided
-    if fs.id.is_empty() {
-        fs.id = uuid::Uuid::new_v4().to_string();
+    Json(req): Json<CreateFilesystemRequest>,
+) -> Response {
+    match provision_filesystem(&state, req).await {
+        Ok(fs) => (StatusCode::CREATED, Json(fs)).into_response(),
+        Err(e) => fs_error_response(e),
     }
-    fs.created_at = chrono::Utc::now().timestamp();
-    fs.updated_at = fs.created_at;
-    
-    let mut filesystems = state.filesystems.write().await;
-    let id = fs.id.clone();
-    filesystems.insert(id.clone(), fs.clone());
-    
-    (StatusCode::CREATED, Json(fs)).into_response()
 }
 
 async fn get_filesystem_handler(
@@ -5315,40 +10800,67 @@ async fn get_filesystem_handler(
     }
 }
 
+/// Change a filesystem's mutable settings
+async fn update_filesystem(
+    state: &WebServerState,
+    id: &str,
+    req: UpdateFilesystemRequest,
+) -> Result<Filesystem, FsError> {
+    let mut filesystems = state.filesystems.write().await;
+    let fs = filesystems.get_mut(id).ok_or_else(filesystem_not_found)?;
+
+    if let Some(lifecycle) = req.lifecycle {
+        fs.lifecycle = lifecycle;
+    }
+    if let Some(mount_path) = req.mount_path {
+        fs.mount_path = mount_path;
+    }
+    if let Some(labels) = req.labels {
+        fs.labels = labels;
+    }
+    fs.updated_at = chrono::Utc::now().timestamp();
+
+    persist_filesystem(state, fs)
+        .await
+        .map_err(|e| fs_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(fs.clone())
+}
+
 async fn update_filesystem_handler(
     State(state): State<Arc<WebServerState>>,
     Path(id): Path<String>,
-    Json(mut fs): Json<Filesystem>,
+    Json(req): Json<UpdateFilesystemRequest>,
 ) -> impl IntoResponse {
+    match update_filesystem(&state, &id, req).await {
+        Ok(fs) => Json(fs).into_response(),
+        Err(e) => fs_error_response(e),
+    }
+}
+
+/// Delete an unattached filesystem
+async fn delete_filesystem(state: &WebServerState, id: &str) -> Result<(), FsError> {
     let mut filesystems = state.filesystems.write().await;
-    if !filesystems.contains_key(&id) {
-        return (StatusCode::NOT_FOUND, "Filesystem not found").into_response();
+    match filesystems.get(id) {
+        Some(fs) if !fs.attached_to.is_empty() => {
+            return Err(fs_error(StatusCode::CONFLICT, "filesystem is attached; detach it first"));
+        }
+        Some(_) => {}
+        None => return Err(filesystem_not_found()),
     }
-    
-    fs.id = id.clone();
-    fs.updated_at = chrono::Utc::now().timestamp();
-    filesystems.insert(id, fs.clone());
-    
-    Json(fs).into_response()
+
+    remove_filesystem(state, &mut filesystems, id).await.map_err(daemon_fs_error)
 }
 
 async fn delete_filesystem_handler(
     State(state): State<Arc<WebServerState>>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
-    let mut filesystems = state.filesystems.write().await;
-    match filesystems.remove(&id) {
-        Some(_) => StatusCode::NO_CONTENT.into_response(),
-        None => (StatusCode::NOT_FOUND, "Filesystem not found").into_response(),
+    match delete_filesystem(&state, &id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => fs_error_response(e),
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct FilesystemSnapshotRequest {
-    name: String,
-    description: Option<String>,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct FilesystemSnapshot {
     id: String,
@@ -5363,185 +10875,507 @@ struct FilesystemSnapshot {
 async fn create_filesystem_snapshot_handler(
     State(state): State<Arc<WebServerState>>,
     Path(id): Path<String>,
-    Json(req): Json<FilesystemSnapshotRequest>,
+    Json(req): Json<SnapshotFilesystemRequest>,
 ) -> impl IntoResponse {
-    let filesystems = state.filesystems.read().await;
-    if !filesystems.contains_key(&id) {
-        return (StatusCode::NOT_FOUND, "Filesystem not found").into_response();
-    }
-    
-    // Create snapshot record
-    let snapshot = FilesystemSnapshot {
-        id: uuid::Uuid::new_v4().to_string(),
-        filesystem_id: id.clone(),
-        name: req.name,
-        description: req.description,
-        created_at: chrono::Utc::now().to_rfc3339(),
-        size_bytes: 0, // Would be calculated from actual filesystem
-        checksum: None, // Would be computed from actual content
+    let attached_to = match state.filesystems.read().await.get(&id) {
+        Some(fs) => fs.attached_to.clone(),
+        None => return (StatusCode::NOT_FOUND, "Filesystem not found").into_response(),
     };
-    
-    (StatusCode::CREATED, Json(snapshot)).into_response()
+    // A copy of an image a VM is writing would not be consistent
+    for appliance_id in &attached_to {
+        if appliance_vm_running(&state, appliance_id).await {
+            return (
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({
+                    "error": format!("filesystem is in use by running appliance {}; stop or detach it first", appliance_id)
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    let mut filesystems = state.filesystems.write().await;
+    match snapshot_filesystem(&state, &mut filesystems, &id, req.name).await {
+        Ok(snapshot) => {
+            let record = FilesystemSnapshot {
+                id: snapshot.id,
+                filesystem_id: id,
+                name: snapshot.name,
+                description: req.description,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                size_bytes: snapshot.size_bytes.max(0) as u64,
+                checksum: None, // Set on the snapshot's provenance once copied
+            };
+            (StatusCode::CREATED, Json(record)).into_response()
+        }
+        Err(e) => daemon_error_response(e),
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct FilesystemAttachRequest {
-    appliance_id: String,
-    mount_point: String,
-    #[serde(default)]
-    read_only: bool,
+/// Attach a filesystem's volume to an appliance's VM
+async fn attach_filesystem(
+    state: &WebServerState,
+    id: &str,
+    req: AttachFilesystemRequest,
+) -> Result<Filesystem, FsError> {
+    let vm_id = match state.appliances.read().await.get(&req.appliance_id) {
+        Some(appliance) => appliance.vm_id.clone(),
+        None => return Err(fs_error(StatusCode::NOT_FOUND, "Appliance not found")),
+    };
+    let Some(vm_id) = vm_id else {
+        return Err(fs_error(StatusCode::CONFLICT, "appliance has no VM"));
+    };
+
+    let mut filesystems = state.filesystems.write().await;
+    let fs = filesystems.get_mut(id).ok_or_else(filesystem_not_found)?;
+
+    // Check if already attached to this appliance
+    if fs.attached_to.contains(&req.appliance_id) {
+        return Err(fs_error(StatusCode::CONFLICT, "Already attached to this appliance"));
+    }
+    let conflict = if fs.mutability != FilesystemMutability::ReadOnly && !fs.attached_to.is_empty() {
+        Some("writable filesystems attach to one appliance at a time")
+    } else if req.read_only && fs.mutability != FilesystemMutability::ReadOnly {
+        Some("read-only attachment needs a read_only filesystem")
+    } else if fs.status == "archiving" {
+        Some("filesystem is being archived")
+    } else {
+        None
+    };
+    if let Some(conflict) = conflict {
+        return Err(fs_error(StatusCode::CONFLICT, conflict));
+    }
+
+    if let Some(host_dir) = filesystem_host_dir(fs).map(str::to_string) {
+        // The guest mounts the folder itself, at the mount path
+        let mut shared = fs.clone();
+        if let Some(mount_path) = req.mount_path.clone() {
+            shared.mount_path = mount_path;
+        }
+        if shared.mount_path.is_empty() {
+            return Err(fs_error(StatusCode::BAD_REQUEST, "shared host directories need a mount_path"));
+        }
+        let read_only = req.read_only || fs.mutability == FilesystemMutability::ReadOnly;
+        state
+            .daemon
+            .set_vm_shared_folder(&vm_id, filesystem_shared_folder(&shared, &host_dir, read_only), true)
+            .await
+            .map_err(daemon_fs_error)?;
+    } else {
+        let Some(volume_id) = fs.volume_id.clone() else {
+            return Err(fs_error(StatusCode::CONFLICT, "filesystem has no volume"));
+        };
+        state
+            .daemon
+            .set_vm_volume(&vm_id, &volume_id, true)
+            .await
+            .map_err(daemon_fs_error)?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    fs.attached_to.push(req.appliance_id);
+    if let Some(mount_path) = req.mount_path {
+        fs.mount_path = mount_path;
+    }
+    // Archived images are compressed qcow2, which QEMU uses in place
+    if fs.status == "archived" {
+        fs.status = "ready".to_string();
+    }
+    fs.updated_at = now;
+    fs.last_active_at = now;
+
+    if let Err(e) = persist_filesystem(state, fs).await {
+        warn!("failed to persist filesystem {}: {}", fs.name, e);
+    }
+    Ok(fs.clone())
 }
 
 async fn attach_filesystem_handler(
     State(state): State<Arc<WebServerState>>,
     Path(id): Path<String>,
-    Json(req): Json<FilesystemAttachRequest>,
+    Json(req): Json<AttachFilesystemRequest>,
 ) -> impl IntoResponse {
+    match attach_filesystem(&state, &id, req).await {
+        Ok(fs) => Json(fs).into_response(),
+        Err(e) => fs_error_response(e),
+    }
+}
+
+/// Detach a filesystem's volume from an appliance's VM
+///
+/// `create_snapshot` overrides the filesystem's `snapshot_on_detach`.
+async fn detach_filesystem(
+    state: &WebServerState,
+    id: &str,
+    appliance_id: &str,
+    create_snapshot: Option<bool>,
+) -> Result<Filesystem, FsError> {
+    let vm_id = state.appliances.read().await.get(appliance_id).and_then(|a| a.vm_id.clone());
+
     let mut filesystems = state.filesystems.write().await;
-    let fs = match filesystems.get_mut(&id) {
-        Some(fs) => fs,
-        None => return (StatusCode::NOT_FOUND, "Filesystem not found").into_response(),
-    };
-    
-    // Check if already attached to this appliance
-    if fs.attached_to.contains(&req.appliance_id) {
-        return (StatusCode::CONFLICT, "Already attached to this appliance").into_response();
+    let fs = filesystems.get_mut(id).ok_or_else(filesystem_not_found)?;
+    if !fs.attached_to.iter().any(|a| a == appliance_id) {
+        return Err(fs_error(StatusCode::NOT_FOUND, "Not attached to this appliance"));
     }
-    
-    fs.attached_to.push(req.appliance_id);
-    fs.updated_at = chrono::Utc::now().timestamp();
-    
-    Json(fs.clone()).into_response()
+
+    // Appliances deleted since attaching have no VM left to update
+    if let Some(vm_id) = vm_id {
+        detach_backing(state, fs, &vm_id).await.map_err(daemon_fs_error)?;
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    fs.attached_to.retain(|a| a != appliance_id);
+    fs.updated_at = now;
+    fs.last_active_at = now;
+    let detached = fs.clone();
+
+    if let Err(e) = persist_filesystem(state, &detached).await {
+        warn!("failed to persist filesystem {}: {}", detached.name, e);
+    }
+    if create_snapshot.unwrap_or(detached.lifecycle.snapshot_on_detach) {
+        if let Err(e) = snapshot_filesystem(state, &mut filesystems, id, None).await {
+            warn!("failed to snapshot filesystem {} on detach: {}", detached.name, e);
+        }
+    }
+
+    Ok(detached)
 }
 
 async fn detach_filesystem_handler(
     State(state): State<Arc<WebServerState>>,
     Path((id, appliance_id)): Path<(String, String)>,
+    Query(query): Query<DetachFilesystemQuery>,
 ) -> impl IntoResponse {
-    let mut filesystems = state.filesystems.write().await;
-    let fs = match filesystems.get_mut(&id) {
-        Some(fs) => fs,
-        None => return (StatusCode::NOT_FOUND, "Filesystem not found").into_response(),
-    };
-    
-    fs.attached_to.retain(|a| a != &appliance_id);
-    fs.updated_at = chrono::Utc::now().timestamp();
-    
-    Json(fs.clone()).into_response()
+    match detach_filesystem(&state, &id, &appliance_id, query.create_snapshot.then_some(true)).await {
+        Ok(fs) => Json(fs).into_response(),
+        Err(e) => fs_error_response(e),
+    }
 }
 
 // ============================================================================
 // Resource Graph Handlers
 // ============================================================================
 
-async fn get_resource_graph_handler(
-    State(state): State<Arc<WebServerState>>,
-) -> impl IntoResponse {
-    // Build graph from current state
+/// The live resource graph: appliances, filesystems and their attachments
+async fn build_resource_graph(state: &WebServerState) -> ResourceGraph {
+    // Appliance labels, such as the `infrasim.country` geobounds check, live on their VMs
+    let vm_labels: HashMap<String, HashMap<String, String>> = match state.daemon.list_vms().await {
+        Ok(vms) => vms.into_iter().map(|vm| (vm.id, vm.labels)).collect(),
+        Err(e) => {
+            warn!("failed to list VMs for the resource graph: {}", e);
+            HashMap::new()
+        }
+    };
     let appliances = state.appliances.read().await;
     let filesystems = state.filesystems.read().await;
-    
+
     let mut nodes = Vec::new();
     let mut edges = Vec::new();
-    
+
     // Add appliance nodes
     for (id, appliance) in appliances.iter() {
+        let labels = appliance.vm_id.as_ref().and_then(|vm_id| vm_labels.get(vm_id));
         nodes.push(ResourceNode {
             id: id.clone(),
-            node_type: "appliance".to_string(),
+            node_type: APPLIANCE_NODE.to_string(),
             name: appliance.name.clone(),
             data: serde_json::json!({
                 "address": format!("infrasim_appliance.{}", appliance.name),
                 "status": format!("{:?}", appliance.status).to_lowercase(),
                 "template_id": appliance.template_id,
                 "vm_id": appliance.vm_id,
+                "labels": labels.cloned().unwrap_or_default(),
             }),
             position: None,
         });
     }
-    
+
     // Add filesystem nodes and edges
     for (id, fs) in filesystems.iter() {
+        let mut data = serde_json::to_value(fs).unwrap_or_default();
+        data["address"] = serde_json::json!(format!("infrasim_filesystem.{}", fs.name));
         nodes.push(ResourceNode {
             id: id.clone(),
-            node_type: "filesystem".to_string(),
+            node_type: FILESYSTEM_NODE.to_string(),
             name: fs.name.clone(),
-            data: serde_json::json!({
-                "address": format!("infrasim_filesystem.{}", fs.name),
-                "fs_type": fs.fs_type,
-                "size_bytes": fs.size_bytes,
-                "mount_path": fs.mount_path,
-                "attached_to": fs.attached_to,
-            }),
+            data,
             position: None,
         });
-        
+
         // Add edges for attachments
         for appliance_id in &fs.attached_to {
             edges.push(ResourceEdge {
                 id: format!("{}-{}", id, appliance_id),
                 source: id.clone(),
                 target: appliance_id.clone(),
-                edge_type: "attached_to".to_string(),
+                edge_type: ATTACHED_TO_EDGE.to_string(),
                 data: serde_json::json!({}),
             });
         }
     }
-    
-    let graph = ResourceGraph {
+
+    ResourceGraph {
         nodes,
         edges,
         version: "1".to_string(),
         computed_at: chrono::Utc::now().timestamp(),
-    };
-    
-    Json(graph).into_response()
+    }
+}
+
+async fn get_resource_graph_handler(
+    State(state): State<Arc<WebServerState>>,
+) -> impl IntoResponse {
+    Json(build_resource_graph(&state).await).into_response()
+}
+
+/// The create request a draft filesystem node describes
+fn filesystem_request(node: &ResourceNode) -> Result<CreateFilesystemRequest, FsError> {
+    let mut data = node.data.clone();
+    if !data.is_object() {
+        data = serde_json::json!({});
+    }
+    data["name"] = serde_json::json!(node.name);
+    serde_json::from_value(data)
+        .map_err(|e| fs_error(StatusCode::BAD_REQUEST, format!("invalid filesystem: {}", e)))
+}
+
+/// Plan a draft graph against live state, including checks that need the server's state
+async fn plan_graph(state: &WebServerState, draft: &ResourceGraph) -> GraphPlan {
+    let live = build_resource_graph(state).await;
+    let mut plan = crate::graph::plan(&live, draft);
+
+    let mut errors = Vec::new();
+    for (op, _) in &plan.operations {
+        match op {
+            GraphOp::CreateFilesystem { node } => {
+                let checked = match filesystem_request(node) {
+                    Ok(req) => check_new_filesystem(state, &req).await.map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                if let Err((_, message)) = checked {
+                    errors.push(GraphValidationError::new(&node.id, "data", message));
+                }
+            }
+            GraphOp::UpdateFilesystem { id, attributes } => {
+                let update = serde_json::Value::Object(attributes.clone());
+                if let Err(e) = serde_json::from_value::<UpdateFilesystemRequest>(update) {
+                    errors.push(GraphValidationError::new(id, "data", e.to_string()));
+                }
+            }
+            GraphOp::Attach { appliance_id, .. } => {
+                let has_vm = state
+                    .appliances
+                    .read()
+                    .await
+                    .get(appliance_id)
+                    .is_some_and(|a| a.vm_id.is_some());
+                if !has_vm {
+                    errors.push(GraphValidationError::new(appliance_id, "vm_id", "appliance has no VM"));
+                }
+            }
+            GraphOp::Detach { .. } | GraphOp::DeleteFilesystem { .. } => {}
+        }
+    }
+    plan.result.valid &= errors.is_empty();
+    plan.result.errors.extend(errors);
+    plan
 }
 
 async fn plan_graph_changes_handler(
-    State(_state): State<Arc<WebServerState>>,
-    Json(_req): Json<PlanGraphRequest>,
+    State(state): State<Arc<WebServerState>>,
+    Json(req): Json<PlanGraphRequest>,
 ) -> impl IntoResponse {
-    // Simulate planning - in production this would validate and compute diffs
-    let result = GraphPlanResult {
-        adds: vec![],
-        updates: vec![],
-        deletes: vec![],
-        warnings: vec![],
-        valid: true,
-    };
-    
-    Json(result).into_response()
+    Json(plan_graph(&state, &req.draft).await.result).into_response()
+}
+
+/// How to revert an applied graph operation
+enum GraphUndo {
+    Attach { filesystem_id: String, appliance_id: String, mount_path: String },
+    Detach { filesystem_id: String, appliance_id: String },
+    Restore { id: String, previous: UpdateFilesystemRequest },
+    Remove { id: String },
+}
+
+/// Apply one graph operation, returning how to revert it
+///
+/// `created` maps draft node IDs to the IDs of filesystems created so far.
+async fn apply_graph_op(
+    state: &WebServerState,
+    op: &GraphOp,
+    created: &mut HashMap<String, String>,
+) -> Result<Option<GraphUndo>, FsError> {
+    match op {
+        GraphOp::Detach { filesystem_id, appliance_id } => {
+            let fs = detach_filesystem(state, filesystem_id, appliance_id, None).await?;
+            Ok(Some(GraphUndo::Attach {
+                filesystem_id: filesystem_id.clone(),
+                appliance_id: appliance_id.clone(),
+                mount_path: fs.mount_path,
+            }))
+        }
+        GraphOp::UpdateFilesystem { id, attributes } => {
+            let req = serde_json::from_value(serde_json::Value::Object(attributes.clone()))
+                .map_err(|e| fs_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+            let previous = state.filesystems.read().await.get(id).map(|fs| UpdateFilesystemRequest {
+                lifecycle: Some(fs.lifecycle.clone()),
+                mount_path: Some(fs.mount_path.clone()),
+                labels: Some(fs.labels.clone()),
+            });
+            update_filesystem(state, id, req).await?;
+            Ok(previous.map(|previous| GraphUndo::Restore { id: id.clone(), previous }))
+        }
+        GraphOp::CreateFilesystem { node } => {
+            let fs = provision_filesystem(state, filesystem_request(node)?).await?;
+            created.insert(node.id.clone(), fs.id.clone());
+            Ok(Some(GraphUndo::Remove { id: fs.id }))
+        }
+        GraphOp::Attach { filesystem_id, appliance_id, mount_path } => {
+            let filesystem_id = created.get(filesystem_id).unwrap_or(filesystem_id).clone();
+            let req = AttachFilesystemRequest {
+                appliance_id: appliance_id.clone(),
+                mount_path: mount_path.clone(),
+                read_only: false,
+            };
+            attach_filesystem(state, &filesystem_id, req).await?;
+            Ok(Some(GraphUndo::Detach { filesystem_id, appliance_id: appliance_id.clone() }))
+        }
+        GraphOp::DeleteFilesystem { id } => {
+            delete_filesystem(state, id).await?;
+            Ok(None)
+        }
+    }
 }
 
-async fn apply_graph_changes_handler(
-    State(_state): State<Arc<WebServerState>>,
-    Json(req): Json<ApplyGraphRequest>,
-) -> impl IntoResponse {
-    // Stub: accept the graph and return the planned result shape for now.
-    // A future implementation would compute a plan (or use a plan id) and execute.
-    let _ = req;
-    let result = GraphPlanResult {
-        adds: vec![],
-        updates: vec![],
-        deletes: vec![],
-        warnings: vec!["apply is currently a no-op".to_string()],
-        valid: true,
+async fn undo_graph_op(state: &WebServerState, undo: GraphUndo) -> Result<(), FsError> {
+    match undo {
+        GraphUndo::Attach { filesystem_id, appliance_id, mount_path } => {
+            let req = AttachFilesystemRequest { appliance_id, mount_path: Some(mount_path), read_only: false };
+            attach_filesystem(state, &filesystem_id, req).await.map(|_| ())
+        }
+        GraphUndo::Detach { filesystem_id, appliance_id } => {
+            detach_filesystem(state, &filesystem_id, &appliance_id, Some(false)).await.map(|_| ())
+        }
+        GraphUndo::Restore { id, previous } => update_filesystem(state, &id, previous).await.map(|_| ()),
+        GraphUndo::Remove { id } => delete_filesystem(state, &id).await,
+    }
+}
+
+/// Apply a draft graph by planning it and running the plan's operations
+///
+/// Each operation needs the filesystem permission for its kind of change. A
+/// failed operation reverts the ones applied before it, newest first.
+/// Filesystem deletes run last and cannot be reverted, so a failure among
+/// them leaves the earlier deletes in place.
+async fn apply_graph_changes_handler(
+    State(state): State<Arc<WebServerState>>,
+    roles: Option<Extension<CallerRoles>>,
+    Json(req): Json<ApplyGraphRequest>,
+) -> impl IntoResponse {
+    let _apply = state.graph_apply.lock().await;
+    let plan = plan_graph(&state, &req.draft).await;
+
+    if !plan.result.valid {
+        let errors: Vec<String> = plan
+            .result
+            .errors
+            .iter()
+            .map(|e| format!("{} {}: {}", e.resource_id, e.field, e.message))
+            .collect();
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "success": false,
+                "applied_operations": 0,
+                "errors": errors,
+                "plan": plan.result,
+                "rolled_back": false,
+            })),
+        )
+            .into_response();
+    }
+    for (op, _) in &plan.operations {
+        let permission = match op {
+            GraphOp::CreateFilesystem { .. } => "filesystem:create",
+            GraphOp::UpdateFilesystem { .. } => "filesystem:update",
+            GraphOp::DeleteFilesystem { .. } => "filesystem:delete",
+            GraphOp::Attach { .. } | GraphOp::Detach { .. } => "filesystem:attach",
+        };
+        if let Some(denied) = permission_denied(roles.as_deref(), permission) {
+            return denied;
+        }
+    }
+    if req.dry_run {
+        return Json(serde_json::json!({
+            "success": true,
+            "applied_operations": 0,
+            "errors": [],
+            "plan": plan.result,
+            "rolled_back": false,
+            "dry_run": true,
+        }))
+        .into_response();
+    }
+
+    let mut created = HashMap::new();
+    let mut undo = Vec::new();
+    let mut applied = 0;
+    let mut deleted = false;
+    let mut failure = None;
+    for (op, change) in &plan.operations {
+        match apply_graph_op(&state, op, &mut created).await {
+            Ok(step) => {
+                applied += 1;
+                deleted |= step.is_none();
+                undo.extend(step);
+            }
+            Err((status, message)) => {
+                failure = Some((status, format!("{} {}: {}", change.resource_type, change.name, message)));
+                break;
+            }
+        }
+    }
+
+    let Some((status, error)) = failure else {
+        info!("Applied {} resource graph operations", applied);
+        return Json(serde_json::json!({
+            "success": true,
+            "applied_operations": applied,
+            "errors": [],
+            "plan": plan.result,
+            "rolled_back": false,
+            "created": created,
+        }))
+        .into_response();
     };
-    Json(result).into_response()
+
+    warn!("resource graph apply failed, reverting {} operations: {}", undo.len(), error);
+    let mut errors = vec![error];
+    for step in undo.into_iter().rev() {
+        if let Err((_, message)) = undo_graph_op(&state, step).await {
+            errors.push(format!("rollback: {}", message));
+        }
+    }
+    (
+        status,
+        Json(serde_json::json!({
+            "success": false,
+            "applied_operations": applied,
+            "rolled_back": errors.len() == 1 && !deleted,
+            "errors": errors,
+            "plan": plan.result,
+        })),
+    )
+        .into_response()
 }
 
 async fn validate_graph_handler(
     State(_state): State<Arc<WebServerState>>,
-    Json(_req): Json<ValidateGraphRequest>,
+    Json(req): Json<ValidateGraphRequest>,
 ) -> impl IntoResponse {
-    // Stub: mark graphs as valid for now.
-    // This is where we'd enforce invariants (e.g. attachment constraints, geobounds).
-    (StatusCode::OK, Json(serde_json::json!({"valid": true, "errors": [], "warnings": []})))
-        .into_response()
+    let errors = crate::graph::validate(&req.graph);
+    Json(serde_json::json!({"valid": errors.is_empty(), "errors": errors, "warnings": []})).into_response()
 }
 
-
 async fn not_found_handler() -> impl IntoResponse {
     (StatusCode::NOT_FOUND, "Not found")
 }
@@ -5594,14 +11428,67 @@ const VNC_HTML: &str = r#"<!DOCTYPE html>
             align-items: center;
             justify-content: center;
             position: relative;
+            overflow: hidden;
         }
-        #vnc-screen {
-            max-width: 100%;
-            max-height: 100%;
+        #screen {
+            position: absolute;
+            inset: 0;
+            display: flex;
+            align-items: center;
+            justify-content: center;
         }
         #connecting {
             color: #fff;
             font-size: 1.5em;
+            z-index: 1;
+        }
+        #clipboard-panel {
+            display: none;
+            position: absolute;
+            top: 10px;
+            right: 10px;
+            z-index: 2;
+            background: #16213e;
+            padding: 10px;
+            border-radius: 4px;
+            width: 320px;
+        }
+        #clipboard-panel textarea {
+            width: 100%;
+            height: 120px;
+            background: #1a1a2e;
+            color: #fff;
+            border: 1px solid #0f3460;
+            font-family: monospace;
+            margin-bottom: 8px;
+        }
+        #clipboard-panel label {
+            display: block;
+            color: #aaa;
+            font-size: 0.85em;
+            margin: 10px 0 4px;
+        }
+        #upload-dir {
+            width: 100%;
+            background: #1a1a2e;
+            color: #fff;
+            border: 1px solid #0f3460;
+            font-family: monospace;
+            padding: 4px;
+            margin-bottom: 8px;
+        }
+        #drop-overlay {
+            display: none;
+            position: absolute;
+            inset: 0;
+            z-index: 3;
+            align-items: center;
+            justify-content: center;
+            background: rgba(15, 52, 96, 0.8);
+            border: 3px dashed #4ecca3;
+            color: #fff;
+            font-size: 1.5em;
+            pointer-events: none;
         }
         .controls {
             display: flex;
@@ -5632,209 +11519,198 @@ const VNC_HTML: &str = r#"<!DOCTYPE html>
         <div id="header">
             <h1>🖥️ InfraSim Console</h1>
             <div class="controls">
-                <button class="btn secondary" onclick="sendCtrlAltDel()">Ctrl+Alt+Del</button>
-                <button class="btn secondary" onclick="toggleFullscreen()">Fullscreen</button>
-                <button class="btn" onclick="disconnect()">Disconnect</button>
+                <button class="btn secondary" id="ctrl-alt-del">Ctrl+Alt+Del</button>
+                <button class="btn secondary" id="clipboard">Clipboard</button>
+                <button class="btn secondary" id="fullscreen">Fullscreen</button>
+                <button class="btn" id="disconnect">Disconnect</button>
             </div>
             <div id="status">Connecting...</div>
         </div>
         <div id="vnc-container">
             <div id="connecting">Connecting to VM...</div>
-            <canvas id="vnc-screen" style="display: none;"></canvas>
+            <div id="screen"></div>
+            <div id="clipboard-panel">
+                <textarea id="clipboard-text" placeholder="Guest clipboard"></textarea>
+                <div class="controls">
+                    <button class="btn secondary" id="clipboard-send">Send to guest</button>
+                    <button class="btn secondary" id="clipboard-type">Type into guest</button>
+                </div>
+                <label for="upload-dir">Dropped files go to (needs qemu-guest-agent in the guest)</label>
+                <input id="upload-dir" value="/tmp">
+                <div class="controls">
+                    <button class="btn secondary" id="upload">Upload files...</button>
+                    <input type="file" id="upload-input" multiple hidden>
+                </div>
+            </div>
+            <div id="drop-overlay"></div>
         </div>
     </div>
 
     <script type="module">
-        // Minimal VNC client implementation
-        // In production, use noVNC library from: https://github.com/novnc/noVNC
+        import RFB from '/core/rfb.js';
 
         const params = new URLSearchParams(window.location.search);
         const path = params.get('path') || 'websockify/default';
         const autoconnect = params.get('autoconnect') === '1';
+        // Match the guest resolution to the window unless ?resize=scale
+        const resizeRemote = params.get('resize') !== 'scale';
+        // Set for playback of a recording, which takes no input
+        const viewOnly = params.get('view_only') === '1';
 
         const statusEl = document.getElementById('status');
         const connectingEl = document.getElementById('connecting');
-        const canvasEl = document.getElementById('vnc-screen');
-        const ctx = canvasEl.getContext('2d');
-
-        let ws = null;
+        const screenEl = document.getElementById('screen');
+        const clipboardPanel = document.getElementById('clipboard-panel');
+        const clipboardText = document.getElementById('clipboard-text');
+        const uploadDir = document.getElementById('upload-dir');
+        const uploadInput = document.getElementById('upload-input');
+        const dropOverlay = document.getElementById('drop-overlay');
+        // Uploads go through the API, so they need the VM ID and a token
+        const vmId = path.match(/^websockify\/(.+)$/)?.[1];
+        const token = params.get('token') || '';
+        uploadDir.value = params.get('upload_dir') || uploadDir.value;
+
+        let rfb = null;
 
         function updateStatus(msg, isError = false) {
             statusEl.textContent = msg;
             statusEl.style.color = isError ? '#e94560' : '#4ecca3';
         }
 
+        function showPrompt(text) {
+            connectingEl.textContent = text;
+            connectingEl.style.display = 'block';
+            connectingEl.style.cursor = 'pointer';
+            connectingEl.onclick = connect;
+        }
+
         function connect() {
             const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
             const wsUrl = `${protocol}//${window.location.host}/${path}`;
-            
+
             updateStatus('Connecting...');
-            
-            ws = new WebSocket(wsUrl);
-            ws.binaryType = 'arraybuffer';
+            connectingEl.textContent = 'Connecting to VM...';
+            connectingEl.onclick = null;
 
-            ws.onopen = () => {
+            rfb = new RFB(screenEl, wsUrl);
+            rfb.scaleViewport = true;
+            rfb.resizeSession = resizeRemote && !viewOnly;
+            rfb.viewOnly = viewOnly;
+
+            rfb.addEventListener('connect', () => {
                 updateStatus('Connected');
                 connectingEl.style.display = 'none';
-                canvasEl.style.display = 'block';
-                
-                // Send RFB version
-                ws.send(new TextEncoder().encode('RFB 003.008\n'));
-            };
+                rfb.focus();
+            });
 
-            ws.onmessage = (event) => {
-                // Handle RFB protocol messages
-                handleRfbMessage(event.data);
-            };
+            rfb.addEventListener('disconnect', (e) => {
+                rfb = null;
+                if (e.detail.clean) {
+                    updateStatus('Disconnected', true);
+                } else {
+                    updateStatus(e.detail.reason || 'Connection lost', true);
+                }
+                showPrompt('Disconnected. Click to reconnect.');
+            });
 
-            ws.onclose = () => {
-                updateStatus('Disconnected', true);
-                canvasEl.style.display = 'none';
-                connectingEl.textContent = 'Disconnected. Click to reconnect.';
-                connectingEl.style.display = 'block';
-                connectingEl.style.cursor = 'pointer';
-                connectingEl.onclick = connect;
-            };
+            rfb.addEventListener('desktopname', (e) => {
+                document.title = `${e.detail.name} - InfraSim Console`;
+            });
 
-            ws.onerror = (err) => {
-                console.error('WebSocket error:', err);
-                updateStatus('Connection error', true);
-            };
+            rfb.addEventListener('clipboard', (e) => {
+                clipboardText.value = e.detail.text;
+                // Only succeeds while the page has focus and permission
+                navigator.clipboard?.writeText(e.detail.text).catch(() => {});
+            });
         }
 
-        let rfbState = 'version';
-        let framebufferWidth = 800;
-        let framebufferHeight = 600;
-
-        function handleRfbMessage(data) {
-            const bytes = new Uint8Array(data);
-            
-            switch (rfbState) {
-                case 'version':
-                    // Server sent version, respond with security type
-                    rfbState = 'security';
-                    ws.send(new Uint8Array([1])); // No authentication
-                    break;
-                    
-                case 'security':
-                    // Security result
-                    rfbState = 'init';
-                    ws.send(new Uint8Array([1])); // Shared flag
-                    break;
-                    
-                case 'init':
-                    // ServerInit message
-                    if (bytes.length >= 24) {
-                        framebufferWidth = (bytes[0] << 8) | bytes[1];
-                        framebufferHeight = (bytes[2] << 8) | bytes[3];
-                        
-                        canvasEl.width = framebufferWidth;
-                        canvasEl.height = framebufferHeight;
-                        
-                        rfbState = 'normal';
-                        updateStatus(`Connected (${framebufferWidth}x${framebufferHeight})`);
-                        
-                        // Request framebuffer update
-                        requestFramebufferUpdate();
-                    }
-                    break;
-                    
-                case 'normal':
-                    handleFramebufferUpdate(bytes);
-                    break;
-            }
-        }
+        document.getElementById('ctrl-alt-del').onclick = () => rfb?.sendCtrlAltDel();
 
-        function requestFramebufferUpdate() {
-            if (ws && ws.readyState === WebSocket.OPEN) {
-                const msg = new Uint8Array(10);
-                msg[0] = 3; // FramebufferUpdateRequest
-                msg[1] = 0; // Incremental
-                // x, y, width, height (big-endian)
-                msg[2] = 0; msg[3] = 0;
-                msg[4] = 0; msg[5] = 0;
-                msg[6] = framebufferWidth >> 8; msg[7] = framebufferWidth & 0xff;
-                msg[8] = framebufferHeight >> 8; msg[9] = framebufferHeight & 0xff;
-                ws.send(msg);
-                
-                // Request again after a delay
-                setTimeout(requestFramebufferUpdate, 50);
+        document.getElementById('clipboard').onclick = () => {
+            const open = clipboardPanel.style.display === 'block';
+            clipboardPanel.style.display = open ? 'none' : 'block';
+        };
+
+        document.getElementById('clipboard-send').onclick = () => {
+            rfb?.clipboardPasteFrom(clipboardText.value);
+            rfb?.focus();
+        };
+
+        document.getElementById('clipboard-type').onclick = () => {
+            rfb?.typeText(clipboardText.value);
+            rfb?.focus();
+        };
+
+        // Paste from the host clipboard into the guest clipboard
+        document.addEventListener('paste', (e) => {
+            if (e.target === clipboardText) return;
+            const text = e.clipboardData?.getData('text/plain');
+            if (text) {
+                clipboardText.value = text;
+                rfb?.clipboardPasteFrom(text);
             }
-        }
+        });
 
-        function handleFramebufferUpdate(bytes) {
-            // Simplified - just show a placeholder
-            // Full implementation would parse RFB encoding
-            ctx.fillStyle = '#333';
-            ctx.fillRect(0, 0, canvasEl.width, canvasEl.height);
-            ctx.fillStyle = '#fff';
-            ctx.font = '20px monospace';
-            ctx.fillText('VNC Connected - Use noVNC for full support', 20, 30);
+        // Copy files into the guest one at a time; the guest agent serves one client
+        async function uploadFiles(files) {
+            if (!vmId || vmId === 'default') {
+                updateStatus('Uploads need a console opened for a specific VM', true);
+                return;
+            }
+            const dir = uploadDir.value.replace(/\/+$/, '');
+            const headers = { 'Content-Type': 'application/octet-stream' };
+            if (token) headers['Authorization'] = `Bearer ${token}`;
+            for (const [i, file] of [...files].entries()) {
+                updateStatus(`Uploading ${file.name} (${i + 1}/${files.length})...`);
+                const dest = `${dir}/${file.name}`;
+                const url = `/api/vms/${encodeURIComponent(vmId)}/files?path=${encodeURIComponent(dest)}`;
+                try {
+                    const r = await fetch(url, { method: 'POST', headers, body: file });
+                    if (!r.ok) {
+                        const body = await r.json().catch(() => ({}));
+                        throw new Error(body.error || r.statusText);
+                    }
+                } catch (e) {
+                    updateStatus(`Upload of ${file.name} failed: ${e.message}`, true);
+                    return;
+                }
+            }
+            updateStatus(files.length === 1 ? `Uploaded ${files[0].name} to ${dir}/` : `Uploaded ${files.length} files to ${dir}/`);
+            rfb?.focus();
         }
 
-        // Keyboard handling
-        canvasEl.tabIndex = 0;
-        canvasEl.addEventListener('keydown', (e) => {
-            if (ws && ws.readyState === WebSocket.OPEN) {
-                sendKeyEvent(e.keyCode, true);
-                e.preventDefault();
-            }
+        const vncContainer = document.getElementById('vnc-container');
+        let dragDepth = 0;
+        vncContainer.addEventListener('dragenter', (e) => {
+            if (!e.dataTransfer?.types.includes('Files')) return;
+            e.preventDefault();
+            dragDepth++;
+            dropOverlay.textContent = `Drop to copy into ${uploadDir.value}`;
+            dropOverlay.style.display = 'flex';
         });
-        canvasEl.addEventListener('keyup', (e) => {
-            if (ws && ws.readyState === WebSocket.OPEN) {
-                sendKeyEvent(e.keyCode, false);
-                e.preventDefault();
+        vncContainer.addEventListener('dragover', (e) => {
+            if (e.dataTransfer?.types.includes('Files')) e.preventDefault();
+        });
+        vncContainer.addEventListener('dragleave', () => {
+            if (--dragDepth <= 0) {
+                dragDepth = 0;
+                dropOverlay.style.display = 'none';
             }
         });
+        vncContainer.addEventListener('drop', (e) => {
+            e.preventDefault();
+            dragDepth = 0;
+            dropOverlay.style.display = 'none';
+            if (e.dataTransfer?.files.length) uploadFiles(e.dataTransfer.files);
+        });
 
-        function sendKeyEvent(key, down) {
-            const msg = new Uint8Array(8);
-            msg[0] = 4; // KeyEvent
-            msg[1] = down ? 1 : 0;
-            // Key code (simplified)
-            msg[4] = 0; msg[5] = 0;
-            msg[6] = (key >> 8) & 0xff;
-            msg[7] = key & 0xff;
-            ws.send(msg);
-        }
-
-        // Mouse handling
-        canvasEl.addEventListener('mousedown', (e) => sendPointerEvent(e, 1));
-        canvasEl.addEventListener('mouseup', (e) => sendPointerEvent(e, 0));
-        canvasEl.addEventListener('mousemove', (e) => sendPointerEvent(e, -1));
-
-        let lastButtonMask = 0;
-        function sendPointerEvent(e, button) {
-            if (!ws || ws.readyState !== WebSocket.OPEN) return;
-            
-            const rect = canvasEl.getBoundingClientRect();
-            const x = Math.floor((e.clientX - rect.left) * (canvasEl.width / rect.width));
-            const y = Math.floor((e.clientY - rect.top) * (canvasEl.height / rect.height));
-            
-            if (button >= 0) {
-                lastButtonMask = button;
-            }
-            
-            const msg = new Uint8Array(6);
-            msg[0] = 5; // PointerEvent
-            msg[1] = lastButtonMask;
-            msg[2] = (x >> 8) & 0xff; msg[3] = x & 0xff;
-            msg[4] = (y >> 8) & 0xff; msg[5] = y & 0xff;
-            ws.send(msg);
-        }
-
-        // Global functions
-        window.sendCtrlAltDel = function() {
-            // Send Ctrl+Alt+Del sequence
-            sendKeyEvent(0xffe3, true);  // Ctrl
-            sendKeyEvent(0xffe9, true);  // Alt
-            sendKeyEvent(0xffff, true);  // Delete
-            sendKeyEvent(0xffff, false);
-            sendKeyEvent(0xffe9, false);
-            sendKeyEvent(0xffe3, false);
+        document.getElementById('upload').onclick = () => uploadInput.click();
+        uploadInput.onchange = () => {
+            if (uploadInput.files.length) uploadFiles(uploadInput.files);
+            uploadInput.value = '';
         };
 
-        window.toggleFullscreen = function() {
+        document.getElementById('fullscreen').onclick = () => {
             if (!document.fullscreenElement) {
                 document.documentElement.requestFullscreen();
             } else {
@@ -5842,19 +11718,12 @@ const VNC_HTML: &str = r#"<!DOCTYPE html>
             }
         };
 
-        window.disconnect = function() {
-            if (ws) {
-                ws.close();
-            }
-        };
+        document.getElementById('disconnect').onclick = () => rfb?.disconnect();
 
-        // Auto-connect
         if (autoconnect) {
             connect();
         } else {
-            connectingEl.textContent = 'Click to connect';
-            connectingEl.style.cursor = 'pointer';
-            connectingEl.onclick = connect;
+            showPrompt('Click to connect');
         }
     </script>
 </body>
@@ -5866,20 +11735,556 @@ const VNC_LITE_HTML: &str = r#"<!DOCTYPE html>
 <head>
     <title>InfraSim Console (Lite)</title>
     <style>
-        body { margin: 0; background: #000; }
-        #screen { width: 100vw; height: 100vh; object-fit: contain; }
+        html, body { margin: 0; height: 100%; background: #000; overflow: hidden; }
+        #screen { width: 100vw; height: 100vh; display: flex; align-items: center; justify-content: center; }
     </style>
 </head>
 <body>
-    <canvas id="screen"></canvas>
-    <script>
-        // Minimal VNC - see vnc.html for full version
+    <div id="screen"></div>
+    <script type="module">
+        // Bare framebuffer, scaled to the window - see vnc.html for controls
+        import RFB from '/core/rfb.js';
+
         const params = new URLSearchParams(location.search);
         const path = params.get('path') || 'websockify/default';
-        const ws = new WebSocket(`ws://${location.host}/${path}`);
-        ws.binaryType = 'arraybuffer';
-        ws.onopen = () => ws.send(new TextEncoder().encode('RFB 003.008\n'));
+        const protocol = location.protocol === 'https:' ? 'wss:' : 'ws:';
+
+        const rfb = new RFB(document.getElementById('screen'), `${protocol}//${location.host}/${path}`);
+        rfb.scaleViewport = true;
+        rfb.viewOnly = params.get('view_only') === '1';
+        rfb.addEventListener('connect', () => rfb.focus());
+    </script>
+</body>
+</html>
+"#;
+
+const SERIAL_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>InfraSim Serial Console</title>
+    <style>
+        * {
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }
+        html, body {
+            height: 100%;
+            background: #1a1a2e;
+            overflow: hidden;
+        }
+        #container {
+            display: flex;
+            flex-direction: column;
+            height: 100%;
+        }
+        #header {
+            background: #16213e;
+            color: #e94560;
+            padding: 10px 20px;
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+        }
+        #header h1 {
+            font-size: 1.2em;
+            font-weight: normal;
+        }
+        #status {
+            color: #4ecca3;
+            font-size: 0.9em;
+        }
+        #terminal {
+            flex: 1;
+            padding: 8px;
+            background: #000;
+        }
+        .btn {
+            background: #e94560;
+            color: white;
+            border: none;
+            padding: 8px 16px;
+            border-radius: 4px;
+            cursor: pointer;
+            font-size: 0.9em;
+        }
+        .btn:hover {
+            background: #ff6b6b;
+        }
+    </style>
+</head>
+<body>
+    <div id="container">
+        <div id="header">
+            <h1>InfraSim Serial Console</h1>
+            <button class="btn" id="disconnect">Disconnect</button>
+            <div id="status">Connecting...</div>
+        </div>
+        <div id="terminal"></div>
+    </div>
+
+    <script type="module">
+        // Served from INFRASIM_XTERM_DIR when set, otherwise from the CDN
+        const XTERM_CDN = 'https://cdn.jsdelivr.net/npm/@xterm/xterm@5.5.0';
+
+        const params = new URLSearchParams(window.location.search);
+        const vmId = params.get('vm') || '';
+        // Plays a recorded session instead of attaching to the VM
+        const recording = params.get('recording') || '';
+        const speed = params.get('speed') || '';
+        const token = params.get('token') || '';
+        const status = document.getElementById('status');
+
+        function loadScript(src) {
+            return new Promise((resolve, reject) => {
+                const script = document.createElement('script');
+                script.src = src;
+                script.onload = resolve;
+                script.onerror = reject;
+                document.head.appendChild(script);
+            });
+        }
+
+        function loadStyle(href) {
+            const link = document.createElement('link');
+            link.rel = 'stylesheet';
+            link.href = href;
+            document.head.appendChild(link);
+        }
+
+        async function loadXterm() {
+            for (const base of ['/vendor/xterm', XTERM_CDN]) {
+                try {
+                    await loadScript(`${base}/lib/xterm.js`);
+                    loadStyle(`${base}/css/xterm.css`);
+                    return window.Terminal;
+                } catch (e) {
+                    // Try the next source
+                }
+            }
+            throw new Error('xterm.js could not be loaded');
+        }
+
+        function fit(term, el) {
+            // Approximate cell size of the 14px monospace font
+            const cols = Math.max(20, Math.floor((el.clientWidth - 16) / 8.4));
+            const rows = Math.max(5, Math.floor((el.clientHeight - 16) / 17));
+            term.resize(cols, rows);
+        }
+
+        async function connect() {
+            if (!vmId && !recording) {
+                status.textContent = 'Missing ?vm= parameter';
+                status.style.color = '#e94560';
+                return;
+            }
+
+            const Terminal = await loadXterm();
+            const el = document.getElementById('terminal');
+            const term = new Terminal({
+                cursorBlink: true,
+                fontFamily: 'monospace',
+                fontSize: 14,
+                theme: { background: '#000000' },
+            });
+            term.open(el);
+            fit(term, el);
+            window.addEventListener('resize', () => fit(term, el));
+
+            const scheme = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+            const query = new URLSearchParams();
+            if (token) query.set('token', token);
+            if (speed) query.set('speed', speed);
+            const target = recording
+                ? `/console/replay/${encodeURIComponent(recording)}`
+                : `/console/serial/${encodeURIComponent(vmId)}`;
+            const ws = new WebSocket(`${scheme}//${window.location.host}${target}?${query}`, ['binary']);
+            if (recording) term.options.disableStdin = true;
+            ws.binaryType = 'arraybuffer';
+
+            const encoder = new TextEncoder();
+            ws.onopen = () => {
+                status.textContent = recording ? 'Playing recording' : 'Connected';
+                status.style.color = '#4ecca3';
+                term.focus();
+            };
+            ws.onmessage = (e) => term.write(new Uint8Array(e.data));
+            ws.onclose = (e) => {
+                if (recording && e.wasClean) {
+                    status.textContent = 'End of recording';
+                } else {
+                    status.textContent = e.wasClean ? 'Disconnected' : 'Connection failed';
+                }
+                status.style.color = '#e94560';
+                term.options.disableStdin = true;
+            };
+
+            term.onData((data) => {
+                if (ws.readyState === WebSocket.OPEN) {
+                    ws.send(encoder.encode(data));
+                }
+            });
+            term.onBinary((data) => {
+                if (ws.readyState === WebSocket.OPEN) {
+                    ws.send(Uint8Array.from(data, (c) => c.charCodeAt(0)));
+                }
+            });
+
+            document.getElementById('disconnect').onclick = () => ws.close();
+        }
+
+        connect().catch((e) => {
+            status.textContent = e.message;
+            status.style.color = '#e94560';
+        });
+    </script>
+</body>
+</html>
+"#;
+
+/// Line-oriented page for `/api/admin/terminal`
+///
+/// Takes the bearer token and admin token as `token` and `admin_token` query
+/// parameters, like the serial console page.
+const ADMIN_TERMINAL_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>InfraSim Admin Terminal</title>
+    <style>
+        * { margin: 0; padding: 0; box-sizing: border-box; }
+        html, body { height: 100%; background: #000; color: #e5e7eb; font-family: ui-monospace, Menlo, monospace; }
+        #container { display: flex; flex-direction: column; height: 100%; }
+        #header { background: #16213e; color: #e94560; padding: 10px 20px; display: flex; justify-content: space-between; }
+        #header h1 { font-size: 1.2em; font-weight: normal; }
+        #status { color: #4ecca3; font-size: 0.9em; }
+        #output { flex: 1; overflow: auto; padding: 8px; white-space: pre-wrap; }
+        #prompt { display: flex; padding: 8px; border-top: 1px solid #333; }
+        #prompt span { color: #4ecca3; margin-right: 8px; }
+        #line { flex: 1; background: transparent; color: inherit; border: none; outline: none; font: inherit; }
+    </style>
+</head>
+<body>
+    <div id="container">
+        <div id="header">
+            <h1>InfraSim Admin Terminal</h1>
+            <div id="status">Connecting...</div>
+        </div>
+        <div id="output"></div>
+        <form id="prompt"><span>$</span><input id="line" autocomplete="off" autofocus disabled></form>
+    </div>
+
+    <script>
+        const params = new URLSearchParams(window.location.search);
+        const query = new URLSearchParams();
+        query.set('token', params.get('token') || '');
+        query.set('admin_token', params.get('admin_token') || '');
+        const scheme = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+        const ws = new WebSocket(`${scheme}//${window.location.host}/api/admin/terminal?${query}`);
+
+        const output = document.getElementById('output');
+        const status = document.getElementById('status');
+        const line = document.getElementById('line');
+        const history = [];
+        let cursor = 0;
+
+        function print(text) {
+            output.textContent += text;
+            output.scrollTop = output.scrollHeight;
+        }
+
+        ws.onopen = () => {
+            status.textContent = 'Connected';
+            line.disabled = false;
+            line.focus();
+        };
+        ws.onmessage = (event) => {
+            print(event.data);
+            line.disabled = false;
+            line.focus();
+        };
+        ws.onclose = () => {
+            status.textContent = 'Disconnected';
+            status.style.color = '#e94560';
+            line.disabled = true;
+        };
+
+        document.getElementById('prompt').addEventListener('submit', (event) => {
+            event.preventDefault();
+            const command = line.value.trim();
+            if (!command || ws.readyState !== WebSocket.OPEN) return;
+            history.push(command);
+            cursor = history.length;
+            print(`$ ${command}\n`);
+            line.value = '';
+            line.disabled = true;
+            ws.send(command);
+        });
+        line.addEventListener('keydown', (event) => {
+            if (event.key === 'ArrowUp' && cursor > 0) {
+                line.value = history[--cursor];
+            } else if (event.key === 'ArrowDown' && cursor < history.length) {
+                line.value = history[++cursor] || '';
+            } else {
+                return;
+            }
+            event.preventDefault();
+        });
     </script>
 </body>
 </html>
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::openapi::path::PathItem;
+
+    /// Route prefixes the OpenAPI document covers
+    const DOCUMENTED: &[&str] = &[
+        "/api/health",
+        "/api/daemon",
+        "/api/vms",
+        "/api/images",
+        "/api/volumes",
+        "/api/snapshots",
+        "/api/snapshot-policies",
+        "/api/networks",
+        "/api/jobs",
+    ];
+
+    fn documented(path: &str) -> bool {
+        DOCUMENTED
+            .iter()
+            .any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
+    }
+
+    /// `(METHOD, path)` of every documented route registered in the router,
+    /// with axum's `:param` written as `{param}`
+    fn registered_routes() -> Vec<(String, String)> {
+        let route = regex_lite::Regex::new(r#"\.route\(\s*"(/api/[^"]*)",\s*([^\n]*)"#).unwrap();
+        let method = regex_lite::Regex::new(r"(?:^|\.)(get|post|put|delete|patch)\(").unwrap();
+        let param = regex_lite::Regex::new(r":([A-Za-z_]+)").unwrap();
+
+        let mut routes = Vec::new();
+        for caps in route.captures_iter(include_str!("server.rs")) {
+            let path = param.replace_all(&caps[1], "{$1}").to_string();
+            if !documented(&path) {
+                continue;
+            }
+            for m in method.captures_iter(&caps[2]) {
+                routes.push((m[1].to_uppercase(), path.clone()));
+            }
+        }
+        routes.sort();
+        routes
+    }
+
+    fn methods(item: &PathItem) -> Vec<&'static str> {
+        [
+            ("GET", item.get.is_some()),
+            ("POST", item.post.is_some()),
+            ("PUT", item.put.is_some()),
+            ("DELETE", item.delete.is_some()),
+            ("PATCH", item.patch.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, present)| *present)
+        .map(|(method, _)| method)
+        .collect()
+    }
+
+    #[test]
+    fn openapi_document_matches_router() {
+        let doc = ApiDoc::openapi();
+        let mut documented: Vec<(String, String)> = doc
+            .paths
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                methods(item)
+                    .into_iter()
+                    .map(move |method| (method.to_string(), path.clone()))
+            })
+            .collect();
+        documented.sort();
+
+        let registered = registered_routes();
+        assert!(!registered.is_empty());
+        assert_eq!(registered, documented);
+    }
+
+    #[test]
+    fn openapi_document_is_3_1_with_bearer_auth() {
+        let json = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        assert!(json["openapi"].as_str().unwrap().starts_with("3.1"));
+        assert!(json["components"]["securitySchemes"]["bearer"].is_object());
+        // Health checks are public
+        assert_eq!(
+            json["paths"]["/api/health"]["get"]["security"],
+            serde_json::json!([{}])
+        );
+    }
+
+    #[test]
+    fn sessions_get_ids_and_are_listed_without_tokens() {
+        let db = Database::open_memory().unwrap();
+        {
+            // A session table from before sessions had IDs
+            let conn_arc = db.connection();
+            let conn = conn_arc.lock();
+            conn.execute_batch(
+                "CREATE TABLE auth_sessions (token TEXT PRIMARY KEY, identity_id TEXT NOT NULL, \
+                 created_at INTEGER NOT NULL, expires_at INTEGER NOT NULL, last_seen_at INTEGER NOT NULL); \
+                 INSERT INTO auth_sessions VALUES ('old-token', 'id-1', 1, 9999999999, 1);",
+            )
+            .unwrap();
+        }
+        init_auth_schema(&db);
+
+        let conn_arc = db.connection();
+        let conn = conn_arc.lock();
+        conn.execute(
+            "INSERT INTO auth_identities (id, display_name, role, created_at) VALUES ('id-1', 'alice', 'viewer', 0)",
+            [],
+        )
+        .unwrap();
+        let client = SessionClient { ip_address: Some("10.0.0.2".to_string()), user_agent: None };
+        let (token, _) = issue_session(&conn, "id-1", &client, now_epoch_secs());
+
+        let sessions = list_sessions(&conn, Some("id-1"), &token, now_epoch_secs()).unwrap();
+        assert_eq!(sessions.len(), 2);
+        let current = sessions.iter().find(|s| s.current).unwrap();
+        assert_eq!(current.token_id, session_token_id(&token));
+        assert_eq!(current.ip_address.as_deref(), Some("10.0.0.2"));
+        let old = sessions.iter().find(|s| !s.current).unwrap();
+        assert_eq!(old.token_id, session_token_id("old-token"));
+        assert!(!serde_json::to_string(&sessions).unwrap().contains(&token));
+
+        revoke_sessions(&conn, &[token.clone()]);
+        assert_eq!(list_sessions(&conn, None, &token, now_epoch_secs()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn session_cookies_need_csrf_tokens_to_mutate() {
+        let request = |method: &str, headers: &[(&str, &str)]| {
+            let mut builder = Request::builder().method(method).uri("/api/vms");
+            for (name, value) in headers {
+                builder = builder.header(*name, *value);
+            }
+            builder.body(axum::body::Body::empty()).unwrap()
+        };
+        let cookie = ("cookie", "infrasim_session=s1; infrasim_csrf=c1");
+        let bearer = |req: &Request| bearer_token(req.headers()).map(str::to_string);
+
+        let mut req = request("GET", &[cookie]);
+        adopt_session_cookie(&mut req).unwrap();
+        assert_eq!(bearer(&req).as_deref(), Some("s1"));
+
+        let mut req = request("POST", &[cookie]);
+        let denied = adopt_session_cookie(&mut req).unwrap_err();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+
+        let mut req = request("POST", &[cookie, ("x-csrf-token", "c1")]);
+        adopt_session_cookie(&mut req).unwrap();
+        assert_eq!(bearer(&req).as_deref(), Some("s1"));
+
+        // An explicit bearer token wins and needs no CSRF token
+        let mut req = request("POST", &[cookie, ("authorization", "Bearer b1")]);
+        adopt_session_cookie(&mut req).unwrap();
+        assert_eq!(bearer(&req).as_deref(), Some("b1"));
+    }
+
+    #[test]
+    fn recovery_codes_work_once_and_last_admin_is_kept() {
+        let db = Database::open_memory().unwrap();
+        init_auth_schema(&db);
+        let conn_arc = db.connection();
+        let conn = conn_arc.lock();
+        assert!(no_identities(&conn));
+        conn.execute_batch(
+            "INSERT INTO auth_identities (id, display_name, role, created_at) VALUES ('id-1', 'alice', 'admin', 0); \
+             INSERT INTO auth_identities (id, display_name, role, created_at) VALUES ('id-2', 'bob', 'viewer', 0);",
+        )
+        .unwrap();
+        assert!(!no_identities(&conn));
+
+        let codes = generate_recovery_codes(&conn, "id-1", 0).unwrap();
+        assert_eq!(codes.len(), AUTH_RECOVERY_CODE_COUNT);
+        assert_eq!(recovery_codes_remaining(&conn, "id-1"), AUTH_RECOVERY_CODE_COUNT as i64);
+        // Codes are per identity, and forgive case and missing dashes
+        assert!(!consume_recovery_code(&conn, "id-2", &codes[0], 1));
+        assert!(consume_recovery_code(&conn, "id-1", &codes[0].to_uppercase().replace('-', ""), 1));
+        assert!(!consume_recovery_code(&conn, "id-1", &codes[0], 2));
+        assert_eq!(recovery_codes_remaining(&conn, "id-1"), AUTH_RECOVERY_CODE_COUNT as i64 - 1);
+        // Enrolling again replaces them
+        generate_recovery_codes(&conn, "id-1", 3).unwrap();
+        assert!(!consume_recovery_code(&conn, "id-1", &codes[1], 4));
+
+        assert!(is_last_admin(&conn, "id-1"));
+        assert!(!is_last_admin(&conn, "id-2"));
+        conn.execute("UPDATE auth_identities SET role = 'admin' WHERE id = 'id-2'", []).unwrap();
+        assert!(!is_last_admin(&conn, "id-1"));
+        conn.execute("UPDATE auth_identities SET disabled = 1 WHERE id = 'id-2'", []).unwrap();
+        assert!(is_last_admin(&conn, "id-1"));
+    }
+
+    #[test]
+    fn preferences_default_round_trip_and_validate() {
+        let db = Database::open_memory().unwrap();
+        init_auth_schema(&db);
+        let conn_arc = db.connection();
+        let conn = conn_arc.lock();
+
+        assert_eq!(load_preferences(&conn, "id-1"), UserPreferences::default());
+
+        let mut preferences: UserPreferences = serde_json::from_value(serde_json::json!({
+            "theme": "dark",
+            "favorite_vms": ["vm-1"],
+            "table_columns": { "vms": ["name", "state"] },
+        }))
+        .unwrap();
+        assert_eq!(preferences.density, "comfortable");
+        save_preferences(&conn, "id-1", &preferences, 1).unwrap();
+        preferences.density = "compact".to_string();
+        save_preferences(&conn, "id-1", &preferences, 2).unwrap();
+        assert_eq!(load_preferences(&conn, "id-1"), preferences);
+        assert_eq!(load_preferences(&conn, "id-2"), UserPreferences::default());
+
+        assert!(preferences.validate().is_ok());
+        assert!(UserPreferences { theme: "neon".to_string(), ..Default::default() }.validate().is_err());
+        let favorite_vms = vec!["vm".to_string(); PREFERENCES_MAX_ENTRIES + 1];
+        assert!(UserPreferences { favorite_vms, ..Default::default() }.validate().is_err());
+        assert!(serde_json::from_str::<UserPreferences>(r#"{"colour": "red"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn error_bodies_get_codes() {
+        async fn body(response: Response) -> serde_json::Value {
+            let bytes = axum::body::to_bytes(response.into_body(), ERROR_BODY_LIMIT).await.unwrap();
+            serde_json::from_slice(&bytes).unwrap()
+        }
+
+        let response = (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "unknown identity"})));
+        let json = body(error_envelope(response.into_response()).await).await;
+        assert_eq!(json["error"], "unknown identity");
+        assert_eq!(json["code"], "NOT_FOUND");
+        assert_eq!(json["category"], "not_found");
+        assert_eq!(json["retryable"], false);
+
+        let status = tonic::Status::from(infrasim_common::Error::Timeout { seconds: 30 });
+        let response = error_envelope(daemon_error_response(status.into())).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let json = body(response).await;
+        assert_eq!(json["code"], "TIMEOUT");
+        assert_eq!(json["retryable"], true);
+        assert_eq!(json["error"], "Operation timeout after 30s");
+
+        let response = (StatusCode::OK, Json(serde_json::json!({"ok": false, "error": "x"})));
+        let json = body(error_envelope(response.into_response()).await).await;
+        assert!(json.get("code").is_none());
+    }
+}
@@ -0,0 +1,259 @@
+//! Graceful shutdown and listener handoff
+//!
+//! SIGTERM, SIGINT or a restart request stop the server accepting
+//! connections and let in-flight requests finish. Console sessions keep
+//! running until they end or the drain deadline passes; then they are closed
+//! with 1012 (service restart) so clients reconnect.
+//!
+//! The successor can take over without refusing connections: under systemd
+//! socket activation the listener is inherited (`LISTEN_FDS`), and with
+//! `INFRASIM_WEB_REUSE_PORT=1` both processes bind the port with
+//! `SO_REUSEPORT` while the old one drains.
+
+use axum::extract::ws::{CloseFrame, Message};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// WebSocket close code telling clients the server is restarting
+pub const CLOSE_SERVICE_RESTART: u16 = 1012;
+
+/// How long sessions get to close once told to, before they are dropped
+const CLOSE_GRACE: Duration = Duration::from_secs(2);
+
+const DEFAULT_DRAIN_SECS: u64 = 30;
+
+/// Shared shutdown state of the server and its long-lived sessions
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    /// Cancelled when the server stops accepting connections
+    stopping: CancellationToken,
+    /// Cancelled when the drain deadline passes and sessions must close
+    closing: CancellationToken,
+    restart: Arc<AtomicBool>,
+    sessions: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+impl Shutdown {
+    /// Start shutting down; `restart` asks the supervisor for a new process
+    pub fn begin(&self, restart: bool) {
+        if restart {
+            self.restart.store(true, Ordering::SeqCst);
+        }
+        self.stopping.cancel();
+    }
+
+    /// Whether shutdown was started by a restart request
+    pub fn restart_requested(&self) -> bool {
+        self.restart.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once shutdown has begun
+    pub async fn stopping(&self) {
+        self.stopping.cancelled().await
+    }
+
+    /// Cancelled when sessions must close; consoles watch it to tell their
+    /// client why with [`restart_frame`]
+    pub fn closing(&self) -> CancellationToken {
+        self.closing.clone()
+    }
+
+    /// Long-lived sessions still running
+    pub fn sessions(&self) -> usize {
+        self.sessions.load(Ordering::SeqCst)
+    }
+
+    /// Run a WebSocket session, counted until it ends so shutdown can wait
+    /// for it; it is dropped if it hasn't ended shortly after being told to
+    pub async fn track(self, session: impl Future<Output = ()>) {
+        self.sessions.fetch_add(1, Ordering::SeqCst);
+        let _guard = SessionGuard(&self);
+        let closed = async {
+            self.closing.cancelled().await;
+            tokio::time::sleep(CLOSE_GRACE).await;
+        };
+        tokio::select! {
+            biased;
+            _ = session => {}
+            _ = closed => {}
+        }
+    }
+
+    /// Wait for sessions to end, closing the rest once `deadline` passes
+    pub async fn drain(&self, deadline: Duration) {
+        if tokio::time::timeout(deadline, self.idle()).await.is_err() {
+            info!("Closing {} console sessions", self.sessions());
+        }
+        self.closing.cancel();
+        let _ = tokio::time::timeout(CLOSE_GRACE * 2, self.idle()).await;
+    }
+
+    async fn idle(&self) {
+        loop {
+            let notified = self.idle.notified();
+            if self.sessions() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+struct SessionGuard<'a>(&'a Shutdown);
+
+impl Drop for SessionGuard<'_> {
+    fn drop(&mut self) {
+        self.0.sessions.fetch_sub(1, Ordering::SeqCst);
+        self.0.idle.notify_waiters();
+    }
+}
+
+/// Close frame sent to console clients when the server restarts
+pub fn restart_frame() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: CLOSE_SERVICE_RESTART,
+        reason: "server restarting".into(),
+    }))
+}
+
+/// How long shutdown waits for requests and sessions,
+/// `INFRASIM_WEB_DRAIN_SECS` (default 30)
+pub fn drain_deadline() -> Duration {
+    let secs = std::env::var("INFRASIM_WEB_DRAIN_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_DRAIN_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Begin shutdown on SIGTERM or SIGINT
+pub async fn watch_signals(shutdown: Shutdown) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let Ok(mut term) = signal(SignalKind::terminate()) else {
+            warn!("Can't watch SIGTERM; only Ctrl-C shuts down gracefully");
+            let _ = tokio::signal::ctrl_c().await;
+            shutdown.begin(false);
+            return;
+        };
+        tokio::select! {
+            _ = term.recv() => info!("SIGTERM received"),
+            _ = tokio::signal::ctrl_c() => info!("SIGINT received"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        info!("Ctrl-C received");
+    }
+    shutdown.begin(false);
+}
+
+/// The socket-activated listener if there is one, else `addr` bound, with
+/// `SO_REUSEPORT` when `INFRASIM_WEB_REUSE_PORT` is set
+pub fn bind_listener(addr: SocketAddr) -> anyhow::Result<TcpListener> {
+    #[cfg(unix)]
+    if let Some(listener) = inherited_listener()? {
+        info!("Using socket-activated listener on {}", listener.local_addr()?);
+        return Ok(listener);
+    }
+
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    let reuse_port = std::env::var("INFRASIM_WEB_REUSE_PORT")
+        .map(|v| matches!(v.as_str(), "1" | "true"))
+        .unwrap_or(false);
+    if reuse_port {
+        #[cfg(unix)]
+        socket.set_reuseport(true)?;
+        #[cfg(not(unix))]
+        warn!("INFRASIM_WEB_REUSE_PORT is only supported on Unix");
+    }
+    socket.bind(addr)?;
+    Ok(socket.listen(1024)?)
+}
+
+/// The first socket passed by systemd (`LISTEN_PID`/`LISTEN_FDS`), if any
+#[cfg(unix)]
+fn inherited_listener() -> anyhow::Result<Option<TcpListener>> {
+    use std::os::fd::FromRawFd;
+
+    /// systemd passes sockets from this descriptor on
+    const LISTEN_FDS_START: i32 = 3;
+
+    let ours = std::env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .is_some_and(|pid| pid == std::process::id());
+    let count: i32 = std::env::var("LISTEN_FDS").ok().and_then(|n| n.parse().ok()).unwrap_or(0);
+    if !ours || count < 1 {
+        return Ok(None);
+    }
+    if count > 1 {
+        warn!("{} sockets passed; only the first is served", count);
+    }
+    // Child processes must not take the socket for their own
+    std::env::remove_var("LISTEN_PID");
+    std::env::remove_var("LISTEN_FDS");
+
+    // SAFETY: systemd hands over ownership of the descriptor to this process
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(LISTEN_FDS_START) };
+    listener.set_nonblocking(true)?;
+    Ok(Some(TcpListener::from_std(listener)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_sessions_then_closes_them() {
+        let shutdown = Shutdown::default();
+
+        // Ends on its own once shutdown begins
+        let polite = shutdown.clone();
+        let first = tokio::spawn(async move {
+            let inner = polite.clone();
+            polite.track(async move { inner.stopping().await }).await;
+        });
+        // Only ends when told to close
+        let stubborn = shutdown.clone();
+        let second = tokio::spawn(async move {
+            let closing = stubborn.closing();
+            stubborn.track(async move { closing.cancelled().await }).await;
+        });
+        while shutdown.sessions() < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        shutdown.begin(true);
+        assert!(shutdown.restart_requested());
+        shutdown.drain(Duration::from_millis(50)).await;
+        assert_eq!(shutdown.sessions(), 0);
+        first.await.unwrap();
+        second.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reuse_port_lets_two_listeners_share_a_port() {
+        std::env::set_var("INFRASIM_WEB_REUSE_PORT", "1");
+        let first = bind_listener("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = bind_listener(addr);
+        std::env::remove_var("INFRASIM_WEB_REUSE_PORT");
+        assert!(second.is_ok());
+    }
+}
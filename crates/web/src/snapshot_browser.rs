@@ -6,11 +6,14 @@
 //! - Memory pinning for fast access
 //! - Snapshot comparison and diff
 //! - Git LFS integration for large file tracking
+//! - Files inside snapshot disk layers, read by the daemon with libguestfs
+//! - Timelines of resource specs from the daemon's provenance ledger
 
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json, Router,
 };
@@ -23,6 +26,13 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+use crate::daemon_cache::DaemonChannel;
+use crate::generated::infrasim::{
+    infra_sim_daemon_client::InfraSimDaemonClient, DiffSnapshotsRequest, FileChange,
+    GetProvenanceChainRequest, ListSnapshotFilesRequest, ProvenanceRecord, ReadSnapshotFileRequest,
+    SnapshotFile,
+};
+
 // ============================================================================
 // Types
 // ============================================================================
@@ -289,6 +299,108 @@ pub struct LfsTrackResponse {
     pub error: Option<String>,
 }
 
+/// A file inside a snapshot's disk layer
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotFileEntry {
+    pub path: String,
+    /// "file", "directory", "symlink" or "other"
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub mode: u32,
+    pub size_bytes: u64,
+    pub modified_at: i64,
+    /// SHA-256 of regular files, only computed for diffs
+    pub sha256: Option<String>,
+    pub link_target: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListSnapshotFilesQuery {
+    /// Guest directory to list
+    #[serde(default = "default_guest_path")]
+    pub path: String,
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+fn default_guest_path() -> String {
+    "/".to_string()
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListSnapshotFilesResponse {
+    pub snapshot_id: String,
+    pub path: String,
+    pub files: Vec<SnapshotFileEntry>,
+    pub count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotFileContentQuery {
+    /// Guest file to download
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffSnapshotFilesQuery {
+    /// Older snapshot
+    pub a: String,
+    /// Newer snapshot of the same VM
+    pub b: String,
+    /// Only compare files under this guest directory
+    #[serde(default = "default_guest_path")]
+    pub path: String,
+}
+
+/// A file that differs between two snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotFileChangeEntry {
+    pub path: String,
+    /// "added", "removed" or "modified"
+    pub change: &'static str,
+    pub before: Option<SnapshotFileEntry>,
+    pub after: Option<SnapshotFileEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffSnapshotFilesResponse {
+    pub snapshot_a: String,
+    pub snapshot_b: String,
+    pub path: String,
+    pub changes: Vec<SnapshotFileChangeEntry>,
+    pub count: usize,
+}
+
+/// One version of a resource on its provenance timeline
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvenanceTimelineEntry {
+    pub version: i64,
+    pub timestamp: i64,
+    /// "created", "updated" or "deleted"
+    pub change: String,
+    /// Spec at this version; none once deleted
+    pub spec: Option<serde_json::Value>,
+    /// Top-level spec fields that differ from the previous version
+    pub changed_fields: Vec<String>,
+    pub spec_digest: Option<String>,
+    pub parent_digest: Option<String>,
+    pub hash: String,
+    /// Key that signed the version
+    pub key_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProvenanceTimelineResponse {
+    pub resource_id: String,
+    pub resource_kind: String,
+    /// Whether the chain links up and carries the daemon's signatures
+    pub valid: bool,
+    pub broken_at_version: Option<i64>,
+    pub error: Option<String>,
+    /// Oldest first
+    pub entries: Vec<ProvenanceTimelineEntry>,
+}
+
 // ============================================================================
 // Git LFS Integration
 // ============================================================================
@@ -922,6 +1034,273 @@ pub async fn get_pin_stats_handler(
         .into_response()
 }
 
+// ============================================================================
+// Snapshot Files
+// ============================================================================
+
+fn file_from_proto(file: SnapshotFile) -> SnapshotFileEntry {
+    SnapshotFileEntry {
+        path: file.path,
+        kind: file.r#type,
+        mode: file.mode,
+        size_bytes: file.size_bytes,
+        modified_at: file.modified_at,
+        sha256: Some(file.sha256).filter(|s| !s.is_empty()),
+        link_target: Some(file.link_target).filter(|s| !s.is_empty()),
+    }
+}
+
+fn change_name(change: i32) -> &'static str {
+    match FileChange::try_from(change) {
+        Ok(FileChange::Added) => "added",
+        Ok(FileChange::Removed) => "removed",
+        _ => "modified",
+    }
+}
+
+/// `Content-Disposition` for a downloaded guest file, named after its last
+/// path segment
+fn attachment_header(path: &str) -> String {
+    let name = path
+        .rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or("file")
+        .replace(['"', '\\'], "_");
+    format!("attachment; filename=\"{}\"", name)
+}
+
+/// Map a daemon error to an HTTP error response
+fn daemon_status_response(status: tonic::Status) -> Response {
+    let code = match status.code() {
+        tonic::Code::NotFound => StatusCode::NOT_FOUND,
+        tonic::Code::InvalidArgument => StatusCode::BAD_REQUEST,
+        tonic::Code::FailedPrecondition => StatusCode::CONFLICT,
+        _ => StatusCode::BAD_GATEWAY,
+    };
+    (code, Json(serde_json::json!({"error": status.message()}))).into_response()
+}
+
+fn unreachable_response(e: anyhow::Error) -> Response {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(serde_json::json!({"error": e.to_string()})),
+    )
+        .into_response()
+}
+
+/// List files in a snapshot
+pub async fn list_snapshot_files_handler(
+    State(daemon): State<Arc<DaemonChannel>>,
+    Path(snapshot_id): Path<String>,
+    Query(query): Query<ListSnapshotFilesQuery>,
+) -> Response {
+    let channel = match daemon.get().await {
+        Ok(channel) => channel,
+        Err(e) => return unreachable_response(e),
+    };
+    let request = ListSnapshotFilesRequest {
+        snapshot_id: snapshot_id.clone(),
+        path: query.path.clone(),
+        recursive: query.recursive,
+    };
+    match InfraSimDaemonClient::new(channel)
+        .list_snapshot_files(request)
+        .await
+    {
+        Ok(resp) => {
+            let files: Vec<_> = resp
+                .into_inner()
+                .files
+                .into_iter()
+                .map(file_from_proto)
+                .collect();
+            Json(ListSnapshotFilesResponse {
+                snapshot_id,
+                path: query.path,
+                count: files.len(),
+                files,
+            })
+            .into_response()
+        }
+        Err(status) => daemon_status_response(status),
+    }
+}
+
+/// Download one file from a snapshot
+pub async fn download_snapshot_file_handler(
+    State(daemon): State<Arc<DaemonChannel>>,
+    Path(snapshot_id): Path<String>,
+    Query(query): Query<SnapshotFileContentQuery>,
+) -> Response {
+    use futures::StreamExt;
+
+    let channel = match daemon.get().await {
+        Ok(channel) => channel,
+        Err(e) => return unreachable_response(e),
+    };
+    let request = ReadSnapshotFileRequest {
+        snapshot_id: snapshot_id.clone(),
+        path: query.path.clone(),
+    };
+    let mut stream = match InfraSimDaemonClient::new(channel)
+        .read_snapshot_file(request)
+        .await
+    {
+        Ok(resp) => resp.into_inner(),
+        Err(status) => return daemon_status_response(status),
+    };
+    // A missing file only shows once the daemon's reader exits, so wait for
+    // the first chunk before committing to a 200
+    let first = match stream.message().await {
+        Ok(first) => first.map(|chunk| chunk.data).unwrap_or_default(),
+        Err(status) => return daemon_status_response(status),
+    };
+    debug!("Streaming {} from snapshot {}", query.path, snapshot_id);
+
+    let body = Body::from_stream(
+        futures::stream::once(async move { Ok::<_, tonic::Status>(first) })
+            .chain(stream.map(|chunk| chunk.map(|c| c.data))),
+    );
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_DISPOSITION, attachment_header(&query.path))
+        .body(body)
+        .unwrap_or_else(|e| unreachable_response(e.into()))
+}
+
+/// Files added, removed or modified between two snapshots of a VM
+pub async fn diff_snapshot_files_handler(
+    State(daemon): State<Arc<DaemonChannel>>,
+    Query(query): Query<DiffSnapshotFilesQuery>,
+) -> Response {
+    let channel = match daemon.get().await {
+        Ok(channel) => channel,
+        Err(e) => return unreachable_response(e),
+    };
+    let request = DiffSnapshotsRequest {
+        snapshot_a: query.a.clone(),
+        snapshot_b: query.b.clone(),
+        path: query.path.clone(),
+    };
+    match InfraSimDaemonClient::new(channel)
+        .diff_snapshots(request)
+        .await
+    {
+        Ok(resp) => {
+            let changes: Vec<_> = resp
+                .into_inner()
+                .changes
+                .into_iter()
+                .map(|change| SnapshotFileChangeEntry {
+                    path: change.path,
+                    change: change_name(change.change),
+                    before: change.before.map(file_from_proto),
+                    after: change.after.map(file_from_proto),
+                })
+                .collect();
+            info!(
+                "Snapshots {} and {} differ in {} files",
+                query.a,
+                query.b,
+                changes.len()
+            );
+            Json(DiffSnapshotFilesResponse {
+                snapshot_a: query.a,
+                snapshot_b: query.b,
+                path: query.path,
+                count: changes.len(),
+                changes,
+            })
+            .into_response()
+        }
+        Err(status) => daemon_status_response(status),
+    }
+}
+
+/// Timeline entries of a resource's ledger records, oldest first
+fn provenance_timeline(records: Vec<ProvenanceRecord>) -> Vec<ProvenanceTimelineEntry> {
+    let non_empty = |s: String| (!s.is_empty()).then_some(s);
+    let mut previous: Option<serde_json::Value> = None;
+    records
+        .into_iter()
+        .map(|record| {
+            let spec = serde_json::from_str::<serde_json::Value>(&record.spec_json).ok();
+            let changed_fields = changed_fields(previous.as_ref(), spec.as_ref());
+            previous = spec.clone();
+            ProvenanceTimelineEntry {
+                version: record.version,
+                timestamp: record.timestamp,
+                change: record.change,
+                spec,
+                changed_fields,
+                spec_digest: non_empty(record.spec_digest),
+                parent_digest: non_empty(record.parent_digest),
+                hash: record.hash,
+                key_id: non_empty(record.key_id),
+            }
+        })
+        .collect()
+}
+
+/// Top-level fields that differ between two specs, sorted
+fn changed_fields(before: Option<&serde_json::Value>, after: Option<&serde_json::Value>) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let fields = |spec: Option<&serde_json::Value>| spec.and_then(|s| s.as_object()).unwrap_or(&empty).clone();
+    let (before, after) = (fields(before), fields(after));
+    let mut keys: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|key| before.get(*key) != after.get(*key))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Versions of a resource's spec from the daemon's provenance ledger
+pub async fn provenance_timeline_handler(
+    State(daemon): State<Arc<DaemonChannel>>,
+    Path(resource_id): Path<String>,
+) -> Response {
+    let channel = match daemon.get().await {
+        Ok(channel) => channel,
+        Err(e) => return unreachable_response(e),
+    };
+    let request = GetProvenanceChainRequest {
+        resource_id: resource_id.clone(),
+    };
+    match InfraSimDaemonClient::new(channel)
+        .get_provenance_chain(request)
+        .await
+    {
+        Ok(resp) => {
+            let resp = resp.into_inner();
+            if !resp.valid {
+                warn!(
+                    "Provenance chain of {} is broken at version {}: {}",
+                    resource_id, resp.broken_at_version, resp.error
+                );
+            }
+            let resource_kind = resp
+                .records
+                .first()
+                .map(|r| r.resource_kind.clone())
+                .unwrap_or_default();
+            Json(ProvenanceTimelineResponse {
+                resource_id,
+                resource_kind,
+                valid: resp.valid,
+                broken_at_version: (!resp.valid).then_some(resp.broken_at_version),
+                error: (!resp.error.is_empty()).then_some(resp.error),
+                entries: provenance_timeline(resp.records),
+            })
+            .into_response()
+        }
+        Err(status) => daemon_status_response(status),
+    }
+}
+
 // ============================================================================
 // Routes
 // ============================================================================
@@ -939,6 +1318,24 @@ pub fn snapshot_browser_routes(state: Arc<SnapshotBrowserState>) -> Router {
         .with_state(state)
 }
 
+/// Build the snapshot file routes, which read guest data and so belong
+/// behind authentication
+pub fn snapshot_file_routes(daemon: Arc<DaemonChannel>) -> Router {
+    Router::new()
+        .route("/diff", get(diff_snapshot_files_handler))
+        .route("/:snapshot_id", get(list_snapshot_files_handler))
+        .route("/:snapshot_id/content", get(download_snapshot_file_handler))
+        .with_state(daemon)
+}
+
+/// Build the provenance timeline routes, which show full specs and so belong
+/// behind authentication
+pub fn provenance_routes(daemon: Arc<DaemonChannel>) -> Router {
+    Router::new()
+        .route("/:resource_id", get(provenance_timeline_handler))
+        .with_state(daemon)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -955,4 +1352,61 @@ mod tests {
         let now = now_epoch();
         assert!(now > 1700000000); // After 2023
     }
+
+    #[test]
+    fn test_file_from_proto_drops_empty_fields() {
+        let entry = file_from_proto(SnapshotFile {
+            path: "/etc".to_string(),
+            r#type: "directory".to_string(),
+            mode: 0o755,
+            ..Default::default()
+        });
+        assert_eq!(entry.kind, "directory");
+        assert_eq!(entry.sha256, None);
+        assert_eq!(entry.link_target, None);
+    }
+
+    #[test]
+    fn test_change_name() {
+        assert_eq!(change_name(FileChange::Added as i32), "added");
+        assert_eq!(change_name(FileChange::Removed as i32), "removed");
+        assert_eq!(change_name(FileChange::Modified as i32), "modified");
+    }
+
+    #[test]
+    fn test_provenance_timeline_marks_changed_fields() {
+        let record = |version: i64, change: &str, spec: &str| ProvenanceRecord {
+            version,
+            change: change.to_string(),
+            spec_json: spec.to_string(),
+            ..Default::default()
+        };
+        let entries = provenance_timeline(vec![
+            record(1, "created", r#"{"cpu_cores":2,"memory_mb":2048}"#),
+            record(2, "updated", r#"{"cpu_cores":4,"memory_mb":2048}"#),
+            record(3, "deleted", ""),
+        ]);
+        assert_eq!(entries[0].changed_fields, vec!["cpu_cores", "memory_mb"]);
+        assert_eq!(entries[1].changed_fields, vec!["cpu_cores"]);
+        assert_eq!(entries[2].spec, None);
+        assert_eq!(entries[2].changed_fields, vec!["cpu_cores", "memory_mb"]);
+        assert_eq!(entries[1].parent_digest, None);
+    }
+
+    #[test]
+    fn test_attachment_header_uses_file_name() {
+        assert_eq!(
+            attachment_header("/etc/hosts"),
+            "attachment; filename=\"hosts\""
+        );
+        assert_eq!(
+            attachment_header("/var/log/"),
+            "attachment; filename=\"log\""
+        );
+        assert_eq!(attachment_header("/"), "attachment; filename=\"file\"");
+        assert_eq!(
+            attachment_header("/tmp/a\"b"),
+            "attachment; filename=\"a_b\""
+        );
+    }
 }
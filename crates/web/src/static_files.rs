@@ -1,36 +1,76 @@
 //! Static file serving
+//!
+//! Serves the RFB client used by the console pages under noVNC's layout
+//! (`core/rfb.js`, ...). A full noVNC checkout can replace the embedded
+//! client by pointing `INFRASIM_NOVNC_DIR` at it. xterm.js for the serial
+//! console is served under `vendor/xterm/` from `INFRASIM_XTERM_DIR`.
 
 use axum::{
     http::{header, StatusCode},
     response::{IntoResponse, Response},
 };
+use std::path::PathBuf;
+
+/// Embedded console modules, by path
+const EMBEDDED: &[(&str, &str)] = &[
+    ("core/rfb.js", include_str!("../static/core/rfb.js")),
+    ("core/input/keysym.js", include_str!("../static/core/input/keysym.js")),
+    ("core/util/logging.js", include_str!("../static/core/util/logging.js")),
+];
 
 /// Static file handler
 pub struct StaticFiles {
-    // In production, would cache files or serve from disk
+    /// noVNC checkout served before the embedded files
+    novnc_dir: Option<PathBuf>,
+    /// Unpacked `@xterm/xterm` package served under `vendor/xterm/`
+    xterm_dir: Option<PathBuf>,
 }
 
 impl StaticFiles {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            novnc_dir: env_dir("INFRASIM_NOVNC_DIR"),
+            xterm_dir: env_dir("INFRASIM_XTERM_DIR"),
+        }
     }
 
     /// Serve a static file
     pub async fn serve(&self, path: &str) -> Response {
-        // For MVP, we embed essential noVNC files
-        // In production, you would serve from disk or CDN
-        
+        let path = path.trim_start_matches('/');
         let content_type = guess_content_type(path);
-        
-        match path {
-            // Core noVNC files would be served here
-            "core/rfb.js" => serve_embedded(RFB_JS, content_type),
-            "core/util/logging.js" => serve_embedded(LOGGING_JS, content_type),
-            _ => (StatusCode::NOT_FOUND, "File not found").into_response(),
+
+        let bytes = match path.strip_prefix("vendor/xterm/") {
+            Some(rest) => read_confined(self.xterm_dir.as_ref(), rest).await,
+            None => read_confined(self.novnc_dir.as_ref(), path).await,
+        };
+        if let Some(bytes) = bytes {
+            return (StatusCode::OK, [(header::CONTENT_TYPE, content_type)], bytes).into_response();
+        }
+
+        match EMBEDDED.iter().find(|(name, _)| *name == path) {
+            Some((_, content)) => serve_embedded(content, content_type),
+            None => (StatusCode::NOT_FOUND, "File not found").into_response(),
         }
     }
 }
 
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var(var)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Read a file under `dir`, refusing paths that leave it
+async fn read_confined(dir: Option<&PathBuf>, path: &str) -> Option<Vec<u8>> {
+    let dir = dir?.canonicalize().ok()?;
+    let file = dir.join(path).canonicalize().ok()?;
+    if !file.starts_with(&dir) {
+        return None;
+    }
+    tokio::fs::read(file).await.ok()
+}
+
 impl Default for StaticFiles {
     fn default() -> Self {
         Self::new()
@@ -64,69 +104,53 @@ fn serve_embedded(content: &'static str, content_type: &'static str) -> Response
         .into_response()
 }
 
-// Minimal embedded JavaScript stubs
-// In production, download full noVNC from https://github.com/novnc/noVNC
-
-const RFB_JS: &str = r#"
-// Minimal RFB stub - use full noVNC for production
-export default class RFB {
-    constructor(target, url, options = {}) {
-        this.target = target;
-        this.url = url;
-        this.options = options;
-        this._connected = false;
-        
-        console.log('RFB: Connecting to', url);
-        this.connect();
-    }
-    
-    connect() {
-        // Stub implementation
-        console.log('RFB: Use full noVNC library for complete VNC support');
-    }
-    
-    disconnect() {
-        this._connected = false;
-    }
-    
-    sendCredentials(credentials) {
-        console.log('RFB: sendCredentials');
-    }
-    
-    sendKey(keysym, code, down) {
-        console.log('RFB: sendKey', keysym, down);
-    }
-    
-    sendCtrlAltDel() {
-        console.log('RFB: sendCtrlAltDel');
-    }
-    
-    machineShutdown() {
-        console.log('RFB: machineShutdown');
-    }
-    
-    machineReboot() {
-        console.log('RFB: machineReboot');
-    }
-    
-    clipboardPasteFrom(text) {
-        console.log('RFB: clipboardPasteFrom');
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_serves_embedded_rfb_client() {
+        let files = StaticFiles { novnc_dir: None, xterm_dir: None };
+
+        let response = files.serve("core/rfb.js").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/javascript"
+        );
+
+        let response = files.serve("core/missing.js").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
-    
-    get capabilities() {
-        return { power: false, resize: true };
+
+    #[tokio::test]
+    async fn test_novnc_dir_overrides_and_confines() {
+        let dir = std::env::temp_dir().join(format!("infrasim-novnc-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("core")).unwrap();
+        std::fs::write(dir.join("core/rfb.js"), "// upstream").unwrap();
+        let files = StaticFiles { novnc_dir: Some(dir.clone()), xterm_dir: None };
+
+        assert_eq!(read_confined(files.novnc_dir.as_ref(), "core/rfb.js").await.unwrap(), b"// upstream");
+        assert!(read_confined(files.novnc_dir.as_ref(), "../../etc/passwd").await.is_none());
+        // Files missing from the checkout fall back to the embedded client
+        let response = files.serve("core/util/logging.js").await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
-}
-"#;
 
-const LOGGING_JS: &str = r#"
-// Minimal logging stub
-export function initLogging(level = 'warn') {
-    console.log('Logging initialized at level:', level);
-}
+    #[tokio::test]
+    async fn test_serves_xterm_under_vendor() {
+        let dir = std::env::temp_dir().join(format!("infrasim-xterm-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("lib/xterm.js"), "// xterm").unwrap();
+        let files = StaticFiles { novnc_dir: None, xterm_dir: Some(dir.clone()) };
 
-export function Debug(msg) { console.debug(msg); }
-export function Info(msg) { console.info(msg); }
-export function Warn(msg) { console.warn(msg); }
-export function Error(msg) { console.error(msg); }
-"#;
+        let response = files.serve("/vendor/xterm/lib/xterm.js").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = files.serve("/vendor/xterm/../lib/xterm.js").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
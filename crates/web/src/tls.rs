@@ -0,0 +1,216 @@
+//! TLS termination for the web console
+//!
+//! Certificates come from PEM files, re-read when they change so renewals by
+//! certbot or cert-manager are picked up, or are issued and renewed through
+//! ACME (see [`crate::acme`]). Either way the certificate is swapped in
+//! place, without dropping connections.
+
+use crate::shutdown::Shutdown;
+use axum::extract::ConnectInfo;
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::ServerConfig;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+/// Clients that haven't finished the handshake by then are dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often certificate files are checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where the certificate comes from
+#[derive(Clone, Debug)]
+pub enum TlsConfig {
+    /// PEM certificate chain and private key
+    Files { cert: PathBuf, key: PathBuf },
+    /// Issued by an ACME CA such as Let's Encrypt
+    Acme(crate::acme::AcmeConfig),
+}
+
+/// Certificate served to every client, replaced on renewal
+#[derive(Debug, Default)]
+pub struct CertResolver {
+    current: RwLock<Option<Arc<CertifiedKey>>>,
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.current.read().ok()?.clone()
+    }
+}
+
+impl CertResolver {
+    /// Serve a PEM certificate chain and key from now on
+    pub fn set(&self, cert_pem: &[u8], key_pem: &[u8]) -> anyhow::Result<()> {
+        let key = certified_key(cert_pem, key_pem)?;
+        if let Ok(mut current) = self.current.write() {
+            *current = Some(Arc::new(key));
+        }
+        Ok(())
+    }
+
+    /// Whether there is a certificate to serve yet
+    pub fn is_ready(&self) -> bool {
+        self.current.read().map(|c| c.is_some()).unwrap_or(false)
+    }
+}
+
+fn certified_key(cert_pem: &[u8], key_pem: &[u8]) -> anyhow::Result<CertifiedKey> {
+    let chain = CertificateDer::pem_slice_iter(cert_pem).collect::<Result<Vec<_>, _>>()?;
+    if chain.is_empty() {
+        anyhow::bail!("no certificate in PEM");
+    }
+    let key = PrivateKeyDer::from_pem_slice(key_pem)?;
+    let signing_key = rustls::crypto::ring::sign::any_supported_type(&key)?;
+    let certified = CertifiedKey::new(chain, signing_key);
+    certified.keys_match()?;
+    Ok(certified)
+}
+
+/// rustls settings serving the resolver's certificate over HTTP/2 or 1.1
+pub fn server_config(resolver: Arc<CertResolver>) -> anyhow::Result<Arc<ServerConfig>> {
+    let mut config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// Load the certificate files, then keep reloading them when they change
+pub fn load_files(resolver: Arc<CertResolver>, cert: PathBuf, key: PathBuf) -> anyhow::Result<()> {
+    resolver
+        .set(&std::fs::read(&cert)?, &std::fs::read(&key)?)
+        .map_err(|e| anyhow::anyhow!("{}: {}", cert.display(), e))?;
+    info!("Serving TLS certificate {}", cert.display());
+
+    tokio::spawn(async move {
+        let mut loaded = (modified(&cert), modified(&key));
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            interval.tick().await;
+            let now = (modified(&cert), modified(&key));
+            if now == loaded {
+                continue;
+            }
+            let reloaded = std::fs::read(&cert)
+                .and_then(|c| Ok((c, std::fs::read(&key)?)))
+                .map_err(anyhow::Error::from)
+                .and_then(|(c, k)| resolver.set(&c, &k));
+            match reloaded {
+                Ok(()) => {
+                    info!("Reloaded TLS certificate {}", cert.display());
+                    loaded = now;
+                }
+                // Mid-renewal the pair may not match yet; try again next tick
+                Err(e) => warn!("Keeping the current TLS certificate: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Serve `app` over TLS until shutdown begins, then let connections finish
+/// their requests
+///
+/// Handlers see the client's address as [`ConnectInfo`], as with
+/// `axum::serve`.
+pub async fn serve(listener: TcpListener, app: Router, acceptor: TlsAcceptor, shutdown: Shutdown) -> std::io::Result<()> {
+    let mut connections = tokio::task::JoinSet::new();
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Out of descriptors and the like; don't spin
+                    warn!("Accepting a connection failed: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = shutdown.stopping() => break,
+        };
+        connections.spawn(serve_connection(stream, peer, app.clone(), acceptor.clone(), shutdown.clone()));
+        while connections.try_join_next().is_some() {}
+    }
+    drop(listener);
+    while connections.join_next().await.is_some() {}
+    Ok(())
+}
+
+async fn serve_connection(
+    stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    app: Router,
+    acceptor: TlsAcceptor,
+    shutdown: Shutdown,
+) {
+    let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => {
+            debug!("TLS handshake with {} failed: {}", peer, e);
+            return;
+        }
+        Err(_) => {
+            debug!("TLS handshake with {} timed out", peer);
+            return;
+        }
+    };
+
+    let service = hyper::service::service_fn(move |mut req: hyper::Request<hyper::body::Incoming>| {
+        req.extensions_mut().insert(ConnectInfo(peer));
+        app.clone().oneshot(req)
+    });
+    let builder = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new());
+    let conn = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+    tokio::pin!(conn);
+    let result = tokio::select! {
+        result = conn.as_mut() => result,
+        _ = shutdown.stopping() => {
+            conn.as_mut().graceful_shutdown();
+            conn.await
+        }
+    };
+    if let Err(e) = result {
+        debug!("Connection from {} ended: {}", peer, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolver_takes_pem_pairs() {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let cert = rcgen::CertificateParams::new(vec!["console.test".to_string()])
+            .unwrap()
+            .self_signed(&key)
+            .unwrap();
+
+        let resolver = CertResolver::default();
+        assert!(!resolver.is_ready());
+        resolver.set(cert.pem().as_bytes(), key.serialize_pem().as_bytes()).unwrap();
+        assert!(resolver.is_ready());
+
+        let other = rcgen::KeyPair::generate().unwrap();
+        assert!(resolver.set(cert.pem().as_bytes(), other.serialize_pem().as_bytes()).is_err());
+        assert!(resolver.set(b"", key.serialize_pem().as_bytes()).is_err());
+        assert!(server_config(Arc::new(resolver)).is_ok());
+    }
+}
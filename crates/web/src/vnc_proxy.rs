@@ -1,12 +1,16 @@
 //! VNC WebSocket proxy
 //!
-//! Bridges WebSocket connections to VNC servers.
+//! Bridges WebSocket connections to VNC servers, optionally recording the
+//! session.
 
+use crate::recording::Recorder;
+use crate::shutdown::restart_frame;
 use axum::extract::ws::{Message, WebSocket};
 use futures::{SinkExt, StreamExt};
 use std::io;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, trace};
 
 /// VNC WebSocket proxy
@@ -24,8 +28,14 @@ impl VncProxy {
         }
     }
 
-    /// Bridge a WebSocket to the VNC server
-    pub async fn bridge(self, socket: WebSocket) -> anyhow::Result<()> {
+    /// Bridge a WebSocket to the VNC server until either side closes, or
+    /// `closing` is cancelled and the client is told the server is restarting
+    pub async fn bridge(
+        self,
+        socket: WebSocket,
+        recorder: Option<Recorder>,
+        closing: CancellationToken,
+    ) -> anyhow::Result<()> {
         // Connect to VNC server
         let vnc_addr = format!("{}:{}", self.host, self.port);
         debug!("Connecting to VNC server at {}", vnc_addr);
@@ -41,8 +51,8 @@ impl VncProxy {
         let (ws_write, ws_read) = socket.split();
 
         // Spawn bidirectional forwarding
-        let ws_to_vnc = Self::forward_ws_to_vnc(ws_read, vnc_write);
-        let vnc_to_ws = Self::forward_vnc_to_ws(vnc_read, ws_write);
+        let ws_to_vnc = Self::forward_ws_to_vnc(ws_read, vnc_write, recorder.clone());
+        let vnc_to_ws = Self::forward_vnc_to_ws(vnc_read, ws_write, recorder, closing);
 
         tokio::select! {
             result = ws_to_vnc => {
@@ -65,17 +75,24 @@ impl VncProxy {
     async fn forward_ws_to_vnc(
         mut ws_read: futures::stream::SplitStream<WebSocket>,
         mut vnc_write: tokio::net::tcp::OwnedWriteHalf,
+        recorder: Option<Recorder>,
     ) -> anyhow::Result<()> {
         while let Some(msg) = ws_read.next().await {
             match msg {
                 Ok(Message::Binary(data)) => {
                     trace!("WS->VNC: {} bytes", data.len());
                     vnc_write.write_all(&data).await?;
+                    if let Some(recorder) = &recorder {
+                        recorder.input(&data).await;
+                    }
                 }
                 Ok(Message::Text(text)) => {
                     // Some WebSocket clients send text for RFB version
                     trace!("WS->VNC (text): {} bytes", text.len());
                     vnc_write.write_all(text.as_bytes()).await?;
+                    if let Some(recorder) = &recorder {
+                        recorder.input(text.as_bytes()).await;
+                    }
                 }
                 Ok(Message::Close(_)) => {
                     debug!("WebSocket closed by client");
@@ -100,17 +117,29 @@ impl VncProxy {
     async fn forward_vnc_to_ws(
         mut vnc_read: tokio::net::tcp::OwnedReadHalf,
         mut ws_write: futures::stream::SplitSink<WebSocket, Message>,
+        recorder: Option<Recorder>,
+        closing: CancellationToken,
     ) -> anyhow::Result<()> {
         let mut buffer = vec![0u8; 64 * 1024];
 
         loop {
-            let n = vnc_read.read(&mut buffer).await?;
+            let n = tokio::select! {
+                n = vnc_read.read(&mut buffer) => n?,
+                _ = closing.cancelled() => {
+                    debug!("Closing VNC session for server shutdown");
+                    let _ = ws_write.send(restart_frame()).await;
+                    break;
+                }
+            };
             if n == 0 {
                 debug!("VNC server closed connection");
                 break;
             }
 
             trace!("VNC->WS: {} bytes", n);
+            if let Some(recorder) = &recorder {
+                recorder.output(&buffer[..n]).await;
+            }
 
             if let Err(e) = ws_write.send(Message::Binary(buffer[..n].to_vec())).await {
                 error!("Failed to send to WebSocket: {}", e);
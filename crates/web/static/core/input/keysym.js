@@ -0,0 +1,105 @@
+/*
+ * Keyboard event translation
+ *
+ * RFB identifies keys by X11 keysym. QEMU additionally accepts XT scancodes
+ * (QEMU extended key events), which keep the guest's keyboard layout
+ * authoritative, so both are derived from each browser KeyboardEvent.
+ */
+
+// Keysyms of non-character keys, by KeyboardEvent.key
+const KEYSYMS = {
+    Backspace: 0xff08,
+    Tab: 0xff09,
+    Enter: 0xff0d,
+    Pause: 0xff13,
+    ScrollLock: 0xff14,
+    Escape: 0xff1b,
+    Home: 0xff50,
+    ArrowLeft: 0xff51,
+    ArrowUp: 0xff52,
+    ArrowRight: 0xff53,
+    ArrowDown: 0xff54,
+    PageUp: 0xff55,
+    PageDown: 0xff56,
+    End: 0xff57,
+    PrintScreen: 0xff61,
+    Insert: 0xff63,
+    ContextMenu: 0xff67,
+    NumLock: 0xff7f,
+    CapsLock: 0xffe5,
+    AltGraph: 0xfe03,
+    Delete: 0xffff,
+};
+
+// Modifier keysyms, by KeyboardEvent.code
+const MODIFIERS = {
+    ShiftLeft: 0xffe1,
+    ShiftRight: 0xffe2,
+    ControlLeft: 0xffe3,
+    ControlRight: 0xffe4,
+    MetaLeft: 0xffeb,
+    MetaRight: 0xffec,
+    AltLeft: 0xffe9,
+    AltRight: 0xffea,
+};
+
+// XT scancodes by KeyboardEvent.code; 0xe0xx are extended keys
+const SCANCODES = {
+    Escape: 0x01, Digit1: 0x02, Digit2: 0x03, Digit3: 0x04, Digit4: 0x05,
+    Digit5: 0x06, Digit6: 0x07, Digit7: 0x08, Digit8: 0x09, Digit9: 0x0a,
+    Digit0: 0x0b, Minus: 0x0c, Equal: 0x0d, Backspace: 0x0e, Tab: 0x0f,
+    KeyQ: 0x10, KeyW: 0x11, KeyE: 0x12, KeyR: 0x13, KeyT: 0x14, KeyY: 0x15,
+    KeyU: 0x16, KeyI: 0x17, KeyO: 0x18, KeyP: 0x19, BracketLeft: 0x1a,
+    BracketRight: 0x1b, Enter: 0x1c, ControlLeft: 0x1d, KeyA: 0x1e,
+    KeyS: 0x1f, KeyD: 0x20, KeyF: 0x21, KeyG: 0x22, KeyH: 0x23, KeyJ: 0x24,
+    KeyK: 0x25, KeyL: 0x26, Semicolon: 0x27, Quote: 0x28, Backquote: 0x29,
+    ShiftLeft: 0x2a, Backslash: 0x2b, KeyZ: 0x2c, KeyX: 0x2d, KeyC: 0x2e,
+    KeyV: 0x2f, KeyB: 0x30, KeyN: 0x31, KeyM: 0x32, Comma: 0x33,
+    Period: 0x34, Slash: 0x35, ShiftRight: 0x36, NumpadMultiply: 0x37,
+    AltLeft: 0x38, Space: 0x39, CapsLock: 0x3a, F1: 0x3b, F2: 0x3c,
+    F3: 0x3d, F4: 0x3e, F5: 0x3f, F6: 0x40, F7: 0x41, F8: 0x42, F9: 0x43,
+    F10: 0x44, NumLock: 0x45, ScrollLock: 0x46, Numpad7: 0x47, Numpad8: 0x48,
+    Numpad9: 0x49, NumpadSubtract: 0x4a, Numpad4: 0x4b, Numpad5: 0x4c,
+    Numpad6: 0x4d, NumpadAdd: 0x4e, Numpad1: 0x4f, Numpad2: 0x50,
+    Numpad3: 0x51, Numpad0: 0x52, NumpadDecimal: 0x53, IntlBackslash: 0x56,
+    F11: 0x57, F12: 0x58, NumpadEnter: 0xe01c, ControlRight: 0xe01d,
+    NumpadDivide: 0xe035, PrintScreen: 0xe037, AltRight: 0xe038,
+    Pause: 0xe046, Home: 0xe047, ArrowUp: 0xe048, PageUp: 0xe049,
+    ArrowLeft: 0xe04b, ArrowRight: 0xe04d, End: 0xe04f, ArrowDown: 0xe050,
+    PageDown: 0xe051, Insert: 0xe052, Delete: 0xe053, MetaLeft: 0xe05b,
+    MetaRight: 0xe05c, ContextMenu: 0xe05d,
+};
+
+// Keysym for a Unicode character
+export function keysymFromChar(ch) {
+    const codepoint = ch.codePointAt(0);
+    // Latin-1 keysyms equal their code points; the rest use the Unicode range
+    return codepoint < 0x100 ? codepoint : 0x01000000 | codepoint;
+}
+
+// Keysym for a KeyboardEvent, or null if it has none
+export function keysymFromEvent(event) {
+    if (MODIFIERS[event.code] !== undefined) {
+        return MODIFIERS[event.code];
+    }
+    if (KEYSYMS[event.key] !== undefined) {
+        return KEYSYMS[event.key];
+    }
+    if (/^F([1-9]|1[0-9]|2[0-4])$/.test(event.key)) {
+        return 0xffbe + Number(event.key.slice(1)) - 1;
+    }
+    if ([...event.key].length === 1) {
+        return keysymFromChar(event.key);
+    }
+    return null;
+}
+
+// QEMU extended key event keycode for a KeyboardEvent.code, or 0 if unknown
+export function qemuKeycode(code) {
+    const scancode = SCANCODES[code];
+    if (scancode === undefined) {
+        return 0;
+    }
+    // The 0xe0 prefix of extended keys is sent as the high bit
+    return scancode > 0xff ? (scancode & 0x7f) | 0x80 : scancode;
+}
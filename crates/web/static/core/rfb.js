@@ -0,0 +1,799 @@
+/*
+ * RFB client for the InfraSim console
+ *
+ * Speaks the parts of RFB 3.8 (RFC 6143) that QEMU's VNC server uses: no
+ * authentication, raw and CopyRect encodings, DesktopSize and
+ * ExtendedDesktopSize for resizing, QEMU extended key events for
+ * layout-independent keyboard input, and cut text for the clipboard.
+ *
+ * The public API is the subset of noVNC's RFB class the console pages use,
+ * so the upstream library can replace this file (see INFRASIM_NOVNC_DIR).
+ */
+
+import * as Log from './util/logging.js';
+import { keysymFromChar, keysymFromEvent, qemuKeycode } from './input/keysym.js';
+
+const ENCODING_RAW = 0;
+const ENCODING_COPYRECT = 1;
+const ENCODING_DESKTOP_SIZE = -223;
+const ENCODING_QEMU_EXT_KEY = -258;
+const ENCODING_EXT_DESKTOP_SIZE = -308;
+
+const SECURITY_NONE = 1;
+
+// Delay before asking the server to match a resized viewport
+const RESIZE_DEBOUNCE_MS = 500;
+
+// Growable receive buffer with big-endian readers
+class Queue {
+    constructor() {
+        this._buf = new Uint8Array(64 * 1024);
+        this._start = 0;
+        this._end = 0;
+    }
+
+    get length() {
+        return this._end - this._start;
+    }
+
+    push(data) {
+        const bytes = new Uint8Array(data);
+        if (this.length === 0) {
+            this._start = this._end = 0;
+        }
+        if (this._end + bytes.length > this._buf.length) {
+            const needed = this.length + bytes.length;
+            const buf = needed > this._buf.length
+                ? new Uint8Array(Math.max(needed, this._buf.length * 2))
+                : this._buf;
+            buf.set(this._buf.subarray(this._start, this._end));
+            this._buf = buf;
+            this._end = this.length;
+            this._start = 0;
+        }
+        this._buf.set(bytes, this._end);
+        this._end += bytes.length;
+    }
+
+    peek8(offset = 0) {
+        return this._buf[this._start + offset];
+    }
+
+    peek32(offset = 0) {
+        const i = this._start + offset;
+        return ((this._buf[i] << 24) | (this._buf[i + 1] << 16) |
+                (this._buf[i + 2] << 8) | this._buf[i + 3]) >>> 0;
+    }
+
+    u8() {
+        return this._buf[this._start++];
+    }
+
+    u16() {
+        const value = (this._buf[this._start] << 8) | this._buf[this._start + 1];
+        this._start += 2;
+        return value;
+    }
+
+    u32() {
+        const value = this.peek32();
+        this._start += 4;
+        return value;
+    }
+
+    s32() {
+        return this.u32() | 0;
+    }
+
+    // View of the next `n` bytes, valid until the next push
+    bytes(n) {
+        const view = this._buf.subarray(this._start, this._start + n);
+        this._start += n;
+        return view;
+    }
+
+    // Latin-1 string of the next `n` bytes
+    string(n) {
+        const bytes = this.bytes(n);
+        let text = '';
+        for (let i = 0; i < bytes.length; i += 8192) {
+            text += String.fromCharCode(...bytes.subarray(i, i + 8192));
+        }
+        return text;
+    }
+
+    skip(n) {
+        this._start += n;
+    }
+}
+
+export default class RFB extends EventTarget {
+    constructor(target, url, options = {}) {
+        super();
+
+        this._target = target;
+        this._url = url;
+        this._shared = options.shared !== false;
+
+        this._scaleViewport = false;
+        this._resizeSession = false;
+        this.viewOnly = false;
+        this.focusOnClick = true;
+
+        this._queue = new Queue();
+        this._state = 'connecting';
+        this._failure = '';
+        this._handler = this._handleVersion;
+        this._fbWidth = 0;
+        this._fbHeight = 0;
+        this._fbName = '';
+
+        this._rects = 0;
+        this._rect = null;
+
+        this._qemuExtKey = false;
+        this._screens = null;
+        this._resizeTimer = null;
+        this._buttonMask = 0;
+        this._keysDown = new Map();
+
+        this._canvas = document.createElement('canvas');
+        this._canvas.tabIndex = -1;
+        this._canvas.style.outline = 'none';
+        this._canvas.style.cursor = 'default';
+        this._ctx = this._canvas.getContext('2d');
+        target.appendChild(this._canvas);
+
+        this._bindInput();
+
+        this._resizeObserver = new ResizeObserver(() => this._viewportChanged());
+        this._resizeObserver.observe(target);
+
+        this._ws = new WebSocket(url, options.wsProtocols || ['binary']);
+        this._ws.binaryType = 'arraybuffer';
+        this._ws.onmessage = (e) => this._receive(e.data);
+        this._ws.onclose = (e) => this._closed(e);
+        this._ws.onerror = () => Log.Warn('RFB: WebSocket error');
+    }
+
+    // ------------------------------------------------------------------
+    // Public API
+    // ------------------------------------------------------------------
+
+    get capabilities() {
+        return { power: false };
+    }
+
+    get scaleViewport() {
+        return this._scaleViewport;
+    }
+
+    set scaleViewport(scale) {
+        this._scaleViewport = scale;
+        this._updateScale();
+    }
+
+    get resizeSession() {
+        return this._resizeSession;
+    }
+
+    set resizeSession(resize) {
+        this._resizeSession = resize;
+        if (resize) {
+            this._requestRemoteResize();
+        }
+    }
+
+    disconnect() {
+        this._state = 'disconnecting';
+        this._ws.close();
+    }
+
+    focus() {
+        this._canvas.focus();
+    }
+
+    blur() {
+        this._canvas.blur();
+    }
+
+    sendKey(keysym, code, down) {
+        if (this._state !== 'connected' || this.viewOnly) {
+            return;
+        }
+        if (down === undefined) {
+            this.sendKey(keysym, code, true);
+            this.sendKey(keysym, code, false);
+            return;
+        }
+
+        const keycode = code ? qemuKeycode(code) : 0;
+        if (this._qemuExtKey && keycode) {
+            const msg = new DataView(new ArrayBuffer(12));
+            msg.setUint8(0, 255);
+            msg.setUint8(1, 0);
+            msg.setUint16(2, down ? 1 : 0);
+            msg.setUint32(4, keysym);
+            msg.setUint32(8, keycode);
+            this._send(msg.buffer);
+        } else {
+            const msg = new DataView(new ArrayBuffer(8));
+            msg.setUint8(0, 4);
+            msg.setUint8(1, down ? 1 : 0);
+            msg.setUint32(4, keysym);
+            this._send(msg.buffer);
+        }
+    }
+
+    sendCtrlAltDel() {
+        this.sendKey(0xffe3, 'ControlLeft', true);
+        this.sendKey(0xffe9, 'AltLeft', true);
+        this.sendKey(0xffff, 'Delete', true);
+        this.sendKey(0xffff, 'Delete', false);
+        this.sendKey(0xffe9, 'AltLeft', false);
+        this.sendKey(0xffe3, 'ControlLeft', false);
+    }
+
+    clipboardPasteFrom(text) {
+        if (this._state !== 'connected' || this.viewOnly) {
+            return;
+        }
+        // Plain cut text is Latin-1; replace anything outside it
+        const bytes = Uint8Array.from(text, (ch) => {
+            const code = ch.codePointAt(0);
+            return code < 0x100 ? code : 0x3f;
+        });
+        const msg = new Uint8Array(8 + bytes.length);
+        const view = new DataView(msg.buffer);
+        view.setUint8(0, 6);
+        view.setUint32(4, bytes.length);
+        msg.set(bytes, 8);
+        this._send(msg.buffer);
+    }
+
+    // Used to type text into guests without a clipboard agent
+    typeText(text) {
+        for (const ch of text) {
+            const keysym = ch === '\n' ? 0xff0d : keysymFromChar(ch);
+            this.sendKey(keysym, null);
+        }
+    }
+
+    // ------------------------------------------------------------------
+    // Connection state
+    // ------------------------------------------------------------------
+
+    _send(buffer) {
+        if (this._ws.readyState === WebSocket.OPEN) {
+            this._ws.send(buffer);
+        }
+    }
+
+    _fail(message) {
+        Log.Error('RFB: ' + message);
+        this._failure = message;
+        this._ws.close();
+    }
+
+    _closed(event) {
+        const clean = this._state === 'disconnecting' || (!this._failure && event.wasClean);
+        this._state = 'disconnected';
+        this._resizeObserver.disconnect();
+        clearTimeout(this._resizeTimer);
+        this._canvas.remove();
+        this.dispatchEvent(new CustomEvent('disconnect', {
+            detail: { clean, reason: this._failure || '' },
+        }));
+    }
+
+    _receive(data) {
+        this._queue.push(data);
+        try {
+            while (this._state !== 'disconnected' && this._handler.call(this)) {
+                // Each handler consumes one complete message
+            }
+        } catch (e) {
+            this._fail(e.message);
+        }
+    }
+
+    // ------------------------------------------------------------------
+    // Handshake
+    // ------------------------------------------------------------------
+
+    _handleVersion() {
+        if (this._queue.length < 12) {
+            return false;
+        }
+        const version = this._queue.string(12);
+        const match = /^RFB (\d{3})\.(\d{3})\n$/.exec(version);
+        if (!match) {
+            throw new Error(`Invalid server version ${JSON.stringify(version)}`);
+        }
+        // Answer with the highest version both sides speak
+        this._minor = Number(match[1]) > 3 ? 8 : Math.min(Number(match[2]), 8);
+        if (this._minor !== 7 && this._minor !== 8) {
+            this._minor = 3;
+        }
+        this._legacy = this._minor === 3;
+
+        const reply = `RFB 003.00${this._minor}\n`;
+        this._send(Uint8Array.from(reply, (c) => c.charCodeAt(0)).buffer);
+        this._handler = this._handleSecurity;
+        return true;
+    }
+
+    _handleSecurity() {
+        if (this._legacy) {
+            // 3.3: the server picks the type
+            if (this._queue.length < 4) {
+                return false;
+            }
+            const type = this._queue.peek32();
+            if (type === 0) {
+                return this._readFailureReason(4);
+            }
+            this._queue.skip(4);
+            if (type !== SECURITY_NONE) {
+                throw new Error(`Unsupported security type ${type}`);
+            }
+            this._sendClientInit();
+            return true;
+        }
+
+        if (this._queue.length < 1) {
+            return false;
+        }
+        const count = this._queue.peek8();
+        if (count === 0) {
+            return this._readFailureReason(1);
+        }
+        if (this._queue.length < 1 + count) {
+            return false;
+        }
+        this._queue.skip(1);
+        const types = Array.from(this._queue.bytes(count));
+        if (!types.includes(SECURITY_NONE)) {
+            this.dispatchEvent(new CustomEvent('securityfailure', {
+                detail: { status: 1, reason: 'unsupported security types' },
+            }));
+            throw new Error(`Unsupported security types ${types.join(', ')}; ` +
+                            'disable the VNC password for the console');
+        }
+        this._send(new Uint8Array([SECURITY_NONE]).buffer);
+        // 3.7 skips the result for no authentication
+        if (this._minor === 8) {
+            this._handler = this._handleSecurityResult;
+        } else {
+            this._sendClientInit();
+        }
+        return true;
+    }
+
+    _handleSecurityResult() {
+        if (this._queue.length < 4) {
+            return false;
+        }
+        const result = this._queue.peek32();
+        if (result !== 0) {
+            this.dispatchEvent(new CustomEvent('securityfailure', {
+                detail: { status: result },
+            }));
+            return this._readFailureReason(4);
+        }
+        this._queue.skip(4);
+        this._sendClientInit();
+        return true;
+    }
+
+    // Read a u32-prefixed reason string starting at `offset` and fail with it
+    _readFailureReason(offset) {
+        if (this._queue.length < offset + 4) {
+            return false;
+        }
+        const length = this._queue.peek32(offset);
+        if (this._queue.length < offset + 4 + length) {
+            return false;
+        }
+        this._queue.skip(offset + 4);
+        throw new Error(`Server refused connection: ${this._queue.string(length)}`);
+    }
+
+    _sendClientInit() {
+        this._send(new Uint8Array([this._shared ? 1 : 0]).buffer);
+        this._handler = this._handleServerInit;
+    }
+
+    _handleServerInit() {
+        if (this._queue.length < 24) {
+            return false;
+        }
+        const nameLength = this._queue.peek32(20);
+        if (this._queue.length < 24 + nameLength) {
+            return false;
+        }
+
+        const width = this._queue.u16();
+        const height = this._queue.u16();
+        this._queue.skip(16); // Server pixel format; ours is set below
+        this._queue.skip(4);
+        this._fbName = new TextDecoder().decode(this._queue.bytes(nameLength));
+
+        this._resizeFramebuffer(width, height);
+        this.dispatchEvent(new CustomEvent('desktopname', {
+            detail: { name: this._fbName },
+        }));
+
+        this._sendPixelFormat();
+        this._sendEncodings();
+        this._requestUpdate(false);
+
+        this._state = 'connected';
+        this._handler = this._handleMessage;
+        this.dispatchEvent(new CustomEvent('connect'));
+        this._requestRemoteResize();
+        return true;
+    }
+
+    // 32bpp little-endian RGBX, which maps directly onto canvas ImageData
+    _sendPixelFormat() {
+        const msg = new DataView(new ArrayBuffer(20));
+        msg.setUint8(0, 0);
+        msg.setUint8(4, 32);   // bits per pixel
+        msg.setUint8(5, 24);   // depth
+        msg.setUint8(6, 0);    // little endian
+        msg.setUint8(7, 1);    // true colour
+        msg.setUint16(8, 255);
+        msg.setUint16(10, 255);
+        msg.setUint16(12, 255);
+        msg.setUint8(14, 0);   // red shift
+        msg.setUint8(15, 8);   // green shift
+        msg.setUint8(16, 16);  // blue shift
+        this._send(msg.buffer);
+    }
+
+    _sendEncodings() {
+        const encodings = [
+            ENCODING_COPYRECT,
+            ENCODING_RAW,
+            ENCODING_EXT_DESKTOP_SIZE,
+            ENCODING_DESKTOP_SIZE,
+            ENCODING_QEMU_EXT_KEY,
+        ];
+        const msg = new DataView(new ArrayBuffer(4 + 4 * encodings.length));
+        msg.setUint8(0, 2);
+        msg.setUint16(2, encodings.length);
+        encodings.forEach((encoding, i) => msg.setInt32(4 + 4 * i, encoding));
+        this._send(msg.buffer);
+    }
+
+    _requestUpdate(incremental) {
+        const msg = new DataView(new ArrayBuffer(10));
+        msg.setUint8(0, 3);
+        msg.setUint8(1, incremental ? 1 : 0);
+        msg.setUint16(6, this._fbWidth);
+        msg.setUint16(8, this._fbHeight);
+        this._send(msg.buffer);
+    }
+
+    // ------------------------------------------------------------------
+    // Server messages
+    // ------------------------------------------------------------------
+
+    _handleMessage() {
+        if (this._rects > 0) {
+            return this._handleRect();
+        }
+        if (this._queue.length < 1) {
+            return false;
+        }
+
+        switch (this._queue.peek8()) {
+            case 0: // FramebufferUpdate
+                if (this._queue.length < 4) {
+                    return false;
+                }
+                this._queue.skip(2);
+                this._rects = this._queue.u16();
+                if (this._rects === 0) {
+                    this._requestUpdate(true);
+                }
+                return true;
+
+            case 1: { // SetColourMapEntries; unused with true colour
+                if (this._queue.length < 6) {
+                    return false;
+                }
+                const count = (this._queue.peek8(4) << 8) | this._queue.peek8(5);
+                if (this._queue.length < 6 + count * 6) {
+                    return false;
+                }
+                this._queue.skip(6 + count * 6);
+                return true;
+            }
+
+            case 2: // Bell
+                this._queue.skip(1);
+                this.dispatchEvent(new CustomEvent('bell'));
+                return true;
+
+            case 3: { // ServerCutText
+                if (this._queue.length < 8) {
+                    return false;
+                }
+                const length = this._queue.peek32(4);
+                if (this._queue.length < 8 + length) {
+                    return false;
+                }
+                this._queue.skip(8);
+                const text = this._queue.string(length);
+                this.dispatchEvent(new CustomEvent('clipboard', { detail: { text } }));
+                return true;
+            }
+
+            default:
+                throw new Error(`Unexpected server message ${this._queue.peek8()}`);
+        }
+    }
+
+    _handleRect() {
+        if (!this._rect) {
+            if (this._queue.length < 12) {
+                return false;
+            }
+            this._rect = {
+                x: this._queue.u16(),
+                y: this._queue.u16(),
+                width: this._queue.u16(),
+                height: this._queue.u16(),
+                encoding: this._queue.s32(),
+            };
+        }
+
+        const rect = this._rect;
+        let done;
+        switch (rect.encoding) {
+            case ENCODING_RAW:
+                done = this._handleRaw(rect);
+                break;
+            case ENCODING_COPYRECT:
+                done = this._handleCopyRect(rect);
+                break;
+            case ENCODING_DESKTOP_SIZE:
+                this._resizeFramebuffer(rect.width, rect.height);
+                done = true;
+                break;
+            case ENCODING_EXT_DESKTOP_SIZE:
+                done = this._handleExtDesktopSize(rect);
+                break;
+            case ENCODING_QEMU_EXT_KEY:
+                this._qemuExtKey = true;
+                done = true;
+                break;
+            default:
+                throw new Error(`Unsupported encoding ${rect.encoding}`);
+        }
+        if (!done) {
+            return false;
+        }
+
+        this._rect = null;
+        this._rects--;
+        if (this._rects === 0) {
+            this._requestUpdate(true);
+        }
+        return true;
+    }
+
+    _handleRaw(rect) {
+        const length = rect.width * rect.height * 4;
+        if (this._queue.length < length) {
+            return false;
+        }
+        if (length === 0) {
+            return true;
+        }
+        const image = new ImageData(rect.width, rect.height);
+        image.data.set(this._queue.bytes(length));
+        for (let i = 3; i < length; i += 4) {
+            image.data[i] = 255;
+        }
+        this._ctx.putImageData(image, rect.x, rect.y);
+        return true;
+    }
+
+    _handleCopyRect(rect) {
+        if (this._queue.length < 4) {
+            return false;
+        }
+        const srcX = this._queue.u16();
+        const srcY = this._queue.u16();
+        if (rect.width && rect.height) {
+            this._ctx.drawImage(this._canvas, srcX, srcY, rect.width, rect.height,
+                                rect.x, rect.y, rect.width, rect.height);
+        }
+        return true;
+    }
+
+    // x is the reason (1 = our request), y the status, then the screen layout
+    _handleExtDesktopSize(rect) {
+        if (this._queue.length < 4) {
+            return false;
+        }
+        const count = this._queue.peek8();
+        if (this._queue.length < 4 + count * 16) {
+            return false;
+        }
+        this._queue.skip(4);
+        const screens = [];
+        for (let i = 0; i < count; i++) {
+            const id = this._queue.u32();
+            this._queue.skip(8);
+            const flags = this._queue.u32();
+            screens.push({ id, flags });
+        }
+
+        const firstReport = this._screens === null;
+        this._screens = screens;
+        if (rect.x === 1 && rect.y !== 0) {
+            Log.Warn(`RFB: server refused resize (status ${rect.y})`);
+        }
+        this._resizeFramebuffer(rect.width, rect.height);
+        if (firstReport) {
+            this._requestRemoteResize();
+        }
+        return true;
+    }
+
+    _resizeFramebuffer(width, height) {
+        this._fbWidth = width;
+        this._fbHeight = height;
+        this._canvas.width = width;
+        this._canvas.height = height;
+        this._updateScale();
+    }
+
+    // ------------------------------------------------------------------
+    // Viewport
+    // ------------------------------------------------------------------
+
+    _viewportChanged() {
+        this._updateScale();
+        this._requestRemoteResize();
+    }
+
+    _updateScale() {
+        if (!this._scaleViewport || !this._fbWidth || !this._fbHeight) {
+            this._canvas.style.width = '';
+            this._canvas.style.height = '';
+            return;
+        }
+        const scale = Math.min(
+            this._target.clientWidth / this._fbWidth,
+            this._target.clientHeight / this._fbHeight,
+        );
+        this._canvas.style.width = Math.floor(this._fbWidth * scale) + 'px';
+        this._canvas.style.height = Math.floor(this._fbHeight * scale) + 'px';
+    }
+
+    // Ask the server to match the viewport, once it supports SetDesktopSize
+    _requestRemoteResize() {
+        clearTimeout(this._resizeTimer);
+        if (!this._resizeSession || this._state !== 'connected' || !this._screens) {
+            return;
+        }
+
+        this._resizeTimer = setTimeout(() => {
+            const width = Math.floor(this._target.clientWidth);
+            const height = Math.floor(this._target.clientHeight);
+            if (width <= 0 || height <= 0 ||
+                (width === this._fbWidth && height === this._fbHeight)) {
+                return;
+            }
+
+            const screen = this._screens[0] || { id: 0, flags: 0 };
+            const msg = new DataView(new ArrayBuffer(24));
+            msg.setUint8(0, 251);
+            msg.setUint16(2, width);
+            msg.setUint16(4, height);
+            msg.setUint8(6, 1);
+            msg.setUint32(8, screen.id);
+            msg.setUint16(16, width);
+            msg.setUint16(18, height);
+            msg.setUint32(20, screen.flags);
+            this._send(msg.buffer);
+        }, RESIZE_DEBOUNCE_MS);
+    }
+
+    // ------------------------------------------------------------------
+    // Input
+    // ------------------------------------------------------------------
+
+    _bindInput() {
+        const canvas = this._canvas;
+
+        canvas.addEventListener('keydown', (e) => this._keyEvent(e, true));
+        canvas.addEventListener('keyup', (e) => this._keyEvent(e, false));
+        canvas.addEventListener('blur', () => this._releaseKeys());
+
+        canvas.addEventListener('mousedown', (e) => {
+            if (this.focusOnClick) {
+                canvas.focus();
+            }
+            this._pointerEvent(e, this._buttonMask | buttonBit(e.button));
+            e.preventDefault();
+        });
+        canvas.addEventListener('mouseup', (e) => {
+            this._pointerEvent(e, this._buttonMask & ~buttonBit(e.button));
+            e.preventDefault();
+        });
+        canvas.addEventListener('mousemove', (e) => this._pointerEvent(e, this._buttonMask));
+        canvas.addEventListener('contextmenu', (e) => e.preventDefault());
+        canvas.addEventListener('wheel', (e) => {
+            // Buttons 4-7 are scroll up/down/left/right, sent as a click
+            const bits = [];
+            if (e.deltaY) bits.push(e.deltaY < 0 ? 1 << 3 : 1 << 4);
+            if (e.deltaX) bits.push(e.deltaX < 0 ? 1 << 5 : 1 << 6);
+            for (const bit of bits) {
+                this._pointerEvent(e, this._buttonMask | bit);
+                this._pointerEvent(e, this._buttonMask);
+            }
+            e.preventDefault();
+        }, { passive: false });
+    }
+
+    _keyEvent(e, down) {
+        if (this._state !== 'connected' || this.viewOnly) {
+            return;
+        }
+        const keysym = down ? keysymFromEvent(e) : this._keysDown.get(e.code);
+        if (keysym === null || keysym === undefined) {
+            return;
+        }
+        e.preventDefault();
+
+        if (down) {
+            this._keysDown.set(e.code, keysym);
+        } else {
+            this._keysDown.delete(e.code);
+        }
+        this.sendKey(keysym, e.code, down);
+    }
+
+    // Keys held when focus leaves would otherwise stay pressed in the guest
+    _releaseKeys() {
+        for (const [code, keysym] of this._keysDown) {
+            this.sendKey(keysym, code, false);
+        }
+        this._keysDown.clear();
+    }
+
+    _pointerEvent(e, mask) {
+        if (this._state !== 'connected' || this.viewOnly) {
+            return;
+        }
+        this._buttonMask = mask;
+
+        const bounds = this._canvas.getBoundingClientRect();
+        const x = clamp(Math.floor((e.clientX - bounds.left) * this._fbWidth / bounds.width),
+                        0, this._fbWidth - 1);
+        const y = clamp(Math.floor((e.clientY - bounds.top) * this._fbHeight / bounds.height),
+                        0, this._fbHeight - 1);
+
+        const msg = new DataView(new ArrayBuffer(6));
+        msg.setUint8(0, 5);
+        msg.setUint8(1, mask);
+        msg.setUint16(2, x);
+        msg.setUint16(4, y);
+        this._send(msg.buffer);
+    }
+}
+
+// RFB button mask bit for a MouseEvent.button
+function buttonBit(button) {
+    return [1, 2, 4][button] || 0;
+}
+
+function clamp(value, min, max) {
+    return Math.max(min, Math.min(max, value));
+}
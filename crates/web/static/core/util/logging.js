@@ -0,0 +1,27 @@
+/*
+ * Console logging with a runtime level, mirroring noVNC's core/util/logging.js
+ */
+
+const LEVELS = ['debug', 'info', 'warn', 'error', 'none'];
+
+let level = 'warn';
+
+export function initLogging(newLevel = 'warn') {
+    if (!LEVELS.includes(newLevel)) {
+        throw new Error(`Invalid logging level '${newLevel}'`);
+    }
+    level = newLevel;
+}
+
+export function getLogging() {
+    return level;
+}
+
+function enabled(msgLevel) {
+    return LEVELS.indexOf(msgLevel) >= LEVELS.indexOf(level);
+}
+
+export function Debug(...args) { if (enabled('debug')) console.debug(...args); }
+export function Info(...args) { if (enabled('info')) console.info(...args); }
+export function Warn(...args) { if (enabled('warn')) console.warn(...args); }
+export function Error(...args) { if (enabled('error')) console.error(...args); }
@@ -3,8 +3,9 @@ import { Routes, Route, NavLink, useNavigate, Navigate } from "react-router-dom"
 import { useMemo } from "react";
 import { createApiClient } from "@infrasim/api-client";
 import { Button, Card, SkipLink, StatusChip, PageHeader, DesignSystemStyles } from "@infrasim/ui";
-import { ApiProvider } from "./api-context";
+import { ApiProvider, useApi } from "./api-context";
 import { useSelector, useStore } from "./store/store";
+import type { UserPreferences } from "@infrasim/api-client";
 
 // Meshnet Console MVP - Primary Experience
 import MeshnetDashboard from "./pages/MeshnetDashboard";
@@ -24,6 +25,27 @@ function BootingScreen() {
 function AuthenticatedApp() {
   const navigate = useNavigate();
   const { actions } = useStore();
+  const { hooks } = useApi();
+
+  // Live-update cached resources from the daemon event stream
+  hooks.useLiveEvents();
+
+  // Preferences come with the login response; fetch them for sessions that didn't have one
+  const preferences = useSelector(s => s.auth.preferences);
+  const fetchedPreferences = hooks.usePreferences(preferences === null);
+  const updatePreferences = hooks.useUpdatePreferences();
+  React.useEffect(() => {
+    if (fetchedPreferences.data) actions.setPreferences(fetchedPreferences.data);
+  }, [fetchedPreferences.data, actions]);
+
+  const cycleTheme = () => {
+    if (!preferences) return;
+    const next: Record<UserPreferences["theme"], UserPreferences["theme"]> = { system: "light", light: "dark", dark: "system" };
+    updatePreferences.mutate(
+      { ...preferences, theme: next[preferences.theme] },
+      { onSuccess: (saved) => actions.setPreferences(saved) },
+    );
+  };
 
   const logout = () => {
     // Clear meshnet session
@@ -48,6 +70,15 @@ function AuthenticatedApp() {
           </div>
         </div>
         <div className="header-actions" aria-label="Session actions">
+          <Button
+            variant="ghost"
+            onClick={cycleTheme}
+            disabled={!preferences || updatePreferences.isPending}
+            aria-label="Switch color theme"
+            data-testid="theme-button"
+          >
+            Theme: {preferences?.theme ?? "system"}
+          </Button>
           <Button variant="ghost" onClick={logout} aria-label="Log out" data-testid="logout-button">Log out</Button>
         </div>
       </header>
@@ -1,5 +1,5 @@
 import React, { createContext, useContext, useEffect, useMemo } from "react";
-import { createApiClient } from "@infrasim/api-client";
+import { createApiClient, userPreferencesSchema } from "@infrasim/api-client";
 import { useStore, useSelector } from "./store/store";
 
 // ============================================================================
@@ -42,6 +42,8 @@ function useAuthBootstrap() {
         if (res.ok) {
           const data = await res.json();
           actions.setIdentity({ display_name: data.display_name, created_at: data.created_at });
+          const prefs = userPreferencesSchema.safeParse(data.preferences);
+          if (prefs.success) actions.setPreferences(prefs.data);
           actions.setAuthStatus("authenticated");
         } else {
           // Token invalid - clear and go to login
@@ -57,6 +59,21 @@ function useAuthBootstrap() {
   }, [status, token, actions]);
 }
 
+// ============================================================================
+// Preferences - applies the identity's theme and density to the document
+// ============================================================================
+
+function useApplyPreferences() {
+  const preferences = useSelector((s) => s.auth.preferences);
+
+  useEffect(() => {
+    const root = document.documentElement;
+    if (preferences && preferences.theme !== "system") root.dataset.theme = preferences.theme;
+    else delete root.dataset.theme;
+    root.dataset.density = preferences?.density ?? "comfortable";
+  }, [preferences]);
+}
+
 // ============================================================================
 // API Provider
 // ============================================================================
@@ -66,6 +83,7 @@ export function ApiProvider({ children }: { children: React.ReactNode }) {
 
   // Bootstrap auth on mount
   useAuthBootstrap();
+  useApplyPreferences();
 
   const api = useMemo(() => {
     return createApiClient({
@@ -10,7 +10,7 @@
  * - Generate qcow2 VM images from containers
  */
 
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import { Card, Button } from '@infrasim/ui';
 
@@ -61,6 +61,27 @@ interface ImageOverlay {
   env_vars: Record<string, string>;
 }
 
+interface ApplianceBuild {
+  id: string;
+  name: string;
+  status: 'pending' | 'pulling' | 'building' | 'converting' | 'complete' | 'failed';
+  output_path?: string;
+  output_size_bytes: number;
+  output_digest?: string;
+  build_log: string[];
+  error?: string;
+  kernel_path?: string;
+  initrd_path?: string;
+  kernel_args?: string;
+  volume_id?: string;
+}
+
+// Build progress frames from /api/docker/builds/:id/ws
+type BuildEvent =
+  | { type: 'snapshot' | 'done'; build: ApplianceBuild }
+  | { type: 'status'; status: ApplianceBuild['status'] }
+  | { type: 'log'; line: string };
+
 interface RegistrySearchResult {
   name: string;
   description: string;
@@ -111,8 +132,38 @@ const buildAppliance = async (spec: any) => {
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify(spec),
   });
-  if (!res.ok) throw new Error('Build failed');
-  return res.json();
+  const data = await res.json();
+  if (!res.ok) throw new Error(data.error || 'Build failed');
+  return data;
+};
+
+// Follow a build until it finishes
+const useBuildProgress = (buildId?: string) => {
+  const [build, setBuild] = useState<ApplianceBuild | null>(null);
+
+  useEffect(() => {
+    if (!buildId) return;
+    const url = new URL(`/api/docker/builds/${buildId}/ws`, window.location.origin);
+    url.protocol = url.protocol === 'https:' ? 'wss:' : 'ws:';
+    const socket = new WebSocket(url.toString());
+    socket.onmessage = (message) => {
+      const event = JSON.parse(message.data) as BuildEvent;
+      setBuild(prev => {
+        switch (event.type) {
+          case 'snapshot':
+          case 'done':
+            return event.build;
+          case 'status':
+            return prev && { ...prev, status: event.status };
+          case 'log':
+            return prev && { ...prev, build_log: [...prev.build_log, event.line] };
+        }
+      });
+    };
+    return () => socket.close();
+  }, [buildId]);
+
+  return build;
 };
 
 // Components
@@ -433,6 +484,7 @@ export default function ImageBrowser() {
   const [memoryMb, setMemoryMb] = useState(2048);
   const [cpuCores, setCpuCores] = useState(2);
   const [buildResult, setBuildResult] = useState<any>(null);
+  const build = useBuildProgress(buildResult?.build?.id);
 
   // Queries
   const { data: status } = useQuery({
@@ -722,29 +774,49 @@ export default function ImageBrowser() {
             {/* Build button */}
             <div className="flex items-center justify-between pt-4 border-t border-zinc-700">
               <p className="text-sm text-zinc-400">
-                This will generate a build plan. Review before executing.
+                Builds a bootable disk image and registers it as a volume.
               </p>
               <Button
                 onClick={handleBuild}
                 disabled={!applianceName || buildMutation.isPending}
               >
-                {buildMutation.isPending ? 'Planning...' : 'Generate Build Plan'}
+                {buildMutation.isPending ? 'Starting...' : 'Build Image'}
               </Button>
             </div>
+            {buildMutation.isError && (
+              <p className="text-sm text-red-400">{(buildMutation.error as Error).message}</p>
+            )}
 
             {/* Build result */}
             {buildResult && (
               <div className="mt-4 p-4 bg-zinc-800/50 rounded-lg border border-zinc-700">
-                <h3 className="font-medium text-white mb-2">Build Plan Generated</h3>
+                <h3 className="font-medium text-white mb-2">
+                  Build {build?.status ?? buildResult.build?.status}
+                </h3>
                 <div className="space-y-2">
-                  <div>
-                    <h4 className="text-sm text-zinc-400">Next Steps:</h4>
-                    <ul className="list-disc list-inside text-sm text-zinc-300">
-                      {buildResult.next_steps?.map((step: string, i: number) => (
-                        <li key={i}>{step}</li>
-                      ))}
-                    </ul>
-                  </div>
+                  <pre className="p-3 bg-zinc-900 rounded text-xs font-mono text-zinc-300 overflow-auto max-h-64">
+                    {build?.build_log.join('\n')}
+                  </pre>
+                  {build?.error && (
+                    <p className="text-sm text-red-400">{build.error}</p>
+                  )}
+                  {build?.status === 'complete' && (
+                    <dl className="text-sm text-zinc-300 space-y-1">
+                      <div><dt className="inline text-zinc-400">Image: </dt><dd className="inline font-mono">{build.output_path}</dd></div>
+                      {build.volume_id && (
+                        <div><dt className="inline text-zinc-400">Volume: </dt><dd className="inline font-mono">{build.volume_id}</dd></div>
+                      )}
+                      {build.kernel_path && (
+                        <div><dt className="inline text-zinc-400">Kernel: </dt><dd className="inline font-mono">{build.kernel_path}</dd></div>
+                      )}
+                      {build.initrd_path && (
+                        <div><dt className="inline text-zinc-400">Initramfs: </dt><dd className="inline font-mono">{build.initrd_path}</dd></div>
+                      )}
+                      {build.kernel_args && (
+                        <div><dt className="inline text-zinc-400">Kernel args: </dt><dd className="inline font-mono">{build.kernel_args}</dd></div>
+                      )}
+                    </dl>
+                  )}
                   <details className="mt-2">
                     <summary className="text-sm text-blue-400 cursor-pointer">
                       View Terraform HCL
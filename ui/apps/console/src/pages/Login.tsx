@@ -113,9 +113,10 @@ export default function Login({ onLogin }: { onLogin: (token: string) => void })
       });
       const json = await resp.json().catch(() => ({}));
       if (!resp.ok) throw new Error(json?.error || `Login failed (${resp.status})`);
+      // With cookie sessions the token stays in an HttpOnly cookie
       const t = String(json?.token || "").trim();
-      if (!t) throw new Error("Login did not return a token");
-      sessionStorage.setItem(TOKEN_KEY, t);
+      if (!t && !json?.expires_at) throw new Error("Login did not return a token");
+      if (t) sessionStorage.setItem(TOKEN_KEY, t);
       onLogin(t);
     } catch (e: any) {
       setError(e?.message || String(e));
@@ -1,16 +1,35 @@
 import React from "react";
 import { useParams } from "react-router-dom";
 import { createApiClient } from "@infrasim/api-client";
-import { Card, PageHeader, StatusChip, Tabs, Table, EmptyState } from "@infrasim/ui";
+import { Button, Card, PageHeader, StatusChip, Tabs, Table, EmptyState } from "@infrasim/ui";
 import { useSelector } from "../store/store";
 
+function formatBytes(bytes: number): string {
+  const units = ["B", "KiB", "MiB", "GiB", "TiB"];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit += 1;
+  }
+  return unit === 0 ? `${value} B` : `${value.toFixed(1)} ${units[unit]}`;
+}
+
 export default function VmDetail({ client }: { client: ReturnType<typeof createApiClient> }) {
   const { id } = useParams();
   const isVisible = useSelector(s => s.ui.isPageVisible);
 
   const { data: vms, isLoading, error } = client.hooks.useVms();
   const vm = vms?.find(v => v.id === id);
+  const { data: detail } = client.hooks.useVm(id ?? "");
   const { data: snaps } = client.hooks.useSnapshots(id);
+  const running = vm?.state === "running" || vm?.state === "paused";
+  const { data: stats, error: statsError } = client.hooks.useVmStats(id ?? "", running && isVisible);
+  const { data: recordings } = client.hooks.useRecordings(id);
+  const { data: recordingPolicy } = client.hooks.useRecordingPolicy(id ?? "");
+  const setRecordingPolicy = client.hooks.useSetRecordingPolicy();
+  const updateRecording = client.hooks.useUpdateRecording();
+  const deleteRecording = client.hooks.useDeleteRecording();
 
   return (
     <div>
@@ -43,6 +62,68 @@ export default function VmDetail({ client }: { client: ReturnType<typeof createA
                   </div>
                 ),
               },
+              {
+                id: "network",
+                label: "Network",
+                panel: (
+                  <div>
+                    {(detail?.interfaces.length ?? 0) === 0 ? (
+                      <p>Loading…</p>
+                    ) : (
+                      <Table caption="Network interfaces">
+                        <thead>
+                          <tr><th>NIC</th><th>Network</th><th>MAC</th><th>IP</th><th>Source</th></tr>
+                        </thead>
+                        <tbody>
+                          {detail?.interfaces.map(nic => (
+                            <tr key={nic.index}>
+                              <td>net{nic.index}</td>
+                              <td>{nic.network_id ?? "default (user-mode)"}</td>
+                              <td>{nic.mac_address}</td>
+                              <td>{nic.ip_address ?? (running ? "waiting for the guest" : "—")}</td>
+                              <td>{nic.source ?? "—"}</td>
+                            </tr>
+                          ))}
+                        </tbody>
+                      </Table>
+                    )}
+                  </div>
+                ),
+              },
+              {
+                id: "stats",
+                label: "Stats",
+                panel: (
+                  <div>
+                    {!running ? (
+                      <EmptyState title="VM not running" description="Usage is reported while QEMU is running." />
+                    ) : statsError ? (
+                      <p role="alert">{statsError.message}</p>
+                    ) : !stats ? (
+                      <p>Loading…</p>
+                    ) : (
+                      <dl>
+                        <dt>CPU</dt>
+                        <dd>{stats.cpu_percent.toFixed(1)}% ({stats.cpu_seconds.toFixed(0)}s total)</dd>
+                        <dt>Memory (RSS)</dt>
+                        <dd>{formatBytes(stats.rss_bytes)}</dd>
+                        <dt>Disk read</dt>
+                        <dd>{formatBytes(stats.disk_read_bytes)} ({stats.disk_read_ops} ops)</dd>
+                        <dt>Disk written</dt>
+                        <dd>{formatBytes(stats.disk_write_bytes)} ({stats.disk_write_ops} ops)</dd>
+                        <dt>Network</dt>
+                        <dd>
+                          {stats.net_rx_bytes === null || stats.net_tx_bytes === null
+                            ? "Not counted (no QoS profile)"
+                            : `${formatBytes(stats.net_rx_bytes)} received, ${formatBytes(stats.net_tx_bytes)} sent`}
+                        </dd>
+                        <dt>Uptime</dt>
+                        <dd>{stats.uptime_seconds}s</dd>
+                      </dl>
+                    )}
+                  </div>
+                ),
+              },
               {
                 id: "snapshots",
                 label: "Snapshots",
@@ -69,6 +150,68 @@ export default function VmDetail({ client }: { client: ReturnType<typeof createA
                   </div>
                 ),
               },
+              {
+                id: "recordings",
+                label: "Recordings",
+                panel: (
+                  <div>
+                    {recordingPolicy && (
+                      <div style={{ display: "flex", alignItems: "center", gap: "1rem", marginBottom: "1rem" }}>
+                        <label style={{ display: "flex", alignItems: "center", gap: "0.5rem" }}>
+                          <input
+                            type="checkbox"
+                            checked={recordingPolicy.enabled}
+                            disabled={setRecordingPolicy.isPending}
+                            onChange={(e) => setRecordingPolicy.mutate({ ...recordingPolicy, enabled: e.target.checked })}
+                          />
+                          Record console sessions
+                        </label>
+                        <label style={{ display: "flex", alignItems: "center", gap: "0.5rem" }}>
+                          Keep for
+                          <input
+                            type="number"
+                            min={0}
+                            value={recordingPolicy.retention_days}
+                            disabled={setRecordingPolicy.isPending}
+                            onChange={(e) => setRecordingPolicy.mutate({ ...recordingPolicy, retention_days: Math.max(0, Number(e.target.value) || 0) })}
+                            style={{ width: "5rem" }}
+                          />
+                          days (0 = until deleted)
+                        </label>
+                      </div>
+                    )}
+                    {setRecordingPolicy.error && <p role="alert">{setRecordingPolicy.error.message}</p>}
+                    {(recordings?.length ?? 0) === 0 ? (
+                      <EmptyState title="No recordings" description="Sessions are recorded while recording is on for this VM." />
+                    ) : (
+                      <Table caption="Console recordings">
+                        <thead>
+                          <tr><th>Started</th><th>Console</th><th>By</th><th>Length</th><th>Size</th><th>Expires</th><th>Actions</th></tr>
+                        </thead>
+                        <tbody>
+                          {recordings?.map(r => (
+                            <tr key={r.id}>
+                              <td>{new Date(r.started_at * 1000).toLocaleString()}</td>
+                              <td>{r.kind === "vnc" ? "VNC" : "Serial"}</td>
+                              <td>{r.subject}</td>
+                              <td>{r.ended_at === null ? "recording…" : `${r.ended_at - r.started_at}s`}</td>
+                              <td>{formatBytes(r.size_bytes)}</td>
+                              <td>{r.keep ? "kept" : r.expires_at === null ? "never" : new Date(r.expires_at * 1000).toLocaleDateString()}</td>
+                              <td>
+                                <Button variant="ghost" size="sm" onClick={() => window.open(client.recordingPlaybackUrl(r), "_blank", "noopener")}>Play</Button>
+                                <Button variant="ghost" size="sm" disabled={updateRecording.isPending} onClick={() => updateRecording.mutate({ id: r.id, keep: !r.keep })}>
+                                  {r.keep ? "Unkeep" : "Keep"}
+                                </Button>
+                                <Button variant="danger" size="sm" disabled={deleteRecording.isPending || r.ended_at === null} onClick={() => deleteRecording.mutate(r.id)}>Delete</Button>
+                              </td>
+                            </tr>
+                          ))}
+                        </tbody>
+                      </Table>
+                    )}
+                  </div>
+                ),
+              },
             ]}
           />
         </Card>
@@ -1,4 +1,6 @@
 import React, { useContext, useEffect, useMemo, useReducer } from "react";
+import { csrfHeaders } from "@infrasim/api-client";
+import type { UserPreferences } from "@infrasim/api-client";
 
 // ============================================================================
 // Comprehensive Vuex-like store for InfraSim Console
@@ -25,6 +27,8 @@ export type AuthState = {
   capabilities: string[];
   capabilitiesExpiry: number | null;
   identity: { display_name: string; created_at: string } | null;
+  // Roaming console settings; null until the server sends them
+  preferences: UserPreferences | null;
 };
 
 export type ToastItem = {
@@ -233,6 +237,7 @@ export type Mutation =
   | { type: "auth/setViewToken"; token: string | null }
   | { type: "auth/setCapabilities"; capabilities: string[]; expiry: number }
   | { type: "auth/setIdentity"; identity: AuthState["identity"] }
+  | { type: "auth/setPreferences"; preferences: UserPreferences | null }
   | { type: "ui/pushToast"; toast: ToastItem }
   | { type: "ui/dismissToast"; id: string }
   | { type: "ui/setVisibility"; isVisible: boolean }
@@ -291,6 +296,7 @@ export function createInitialState(): AppState {
       capabilities: [],
       capabilitiesExpiry: null,
       identity: null,
+      preferences: null,
     },
     ui: {
       toasts: [],
@@ -365,6 +371,8 @@ function reducer(state: AppState, m: Mutation): AppState {
       return { ...state, auth: { ...state.auth, capabilities: m.capabilities, capabilitiesExpiry: m.expiry } };
     case "auth/setIdentity":
       return { ...state, auth: { ...state.auth, identity: m.identity } };
+    case "auth/setPreferences":
+      return { ...state, auth: { ...state.auth, preferences: m.preferences } };
     case "ui/pushToast": {
       const next = [m.toast, ...state.ui.toasts].slice(0, 4);
       return { ...state, ui: { ...state.ui, toasts: next } };
@@ -523,7 +531,8 @@ export type AppActions = {
   setToken: (token: string | null) => void;
   setAdminToken: (token: string | null) => void;
   setIdentity: (identity: AuthState["identity"]) => void;
-  loginSuccess: (token: string, identity: AuthState["identity"]) => void;
+  setPreferences: (preferences: UserPreferences | null) => void;
+  loginSuccess: (token: string, identity: AuthState["identity"], preferences?: UserPreferences) => void;
   logout: () => void;
   pushToast: (toast: Omit<ToastItem, "id">) => string;
   dismissToast: (id: string) => void;
@@ -574,17 +583,22 @@ export function StoreProvider({ children }: React.PropsWithChildren) {
       setToken: (token) => commit({ type: "auth/setToken", token }),
       setAdminToken: (token) => commit({ type: "auth/setAdminToken", token }),
       setIdentity: (identity) => commit({ type: "auth/setIdentity", identity }),
-      loginSuccess: (token, identity) => {
+      setPreferences: (preferences) => commit({ type: "auth/setPreferences", preferences }),
+      loginSuccess: (token, identity, preferences) => {
         commit({ type: "auth/setToken", token });
         commit({ type: "auth/setIdentity", identity });
+        if (preferences) commit({ type: "auth/setPreferences", preferences });
         commit({ type: "auth/setStatus", status: "authenticated" });
       },
       logout: () => {
+        // Ends a cookie session server-side; bearer tokens are just forgotten
+        void fetch("/api/auth/logout", { method: "POST", credentials: "same-origin", headers: csrfHeaders("POST") }).catch(() => {});
         commit({ type: "auth/setToken", token: null });
         commit({ type: "auth/setAdminToken", token: null });
         commit({ type: "auth/setViewToken", token: null });
         commit({ type: "auth/setCapabilities", capabilities: [], expiry: 0 });
         commit({ type: "auth/setIdentity", identity: null });
+        commit({ type: "auth/setPreferences", preferences: null });
         commit({ type: "auth/setStatus", status: "unauthenticated" });
       },
       pushToast: (toast) => {
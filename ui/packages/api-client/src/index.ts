@@ -1,3 +1,4 @@
+import { useEffect } from "react";
 import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
 import { z } from "zod";
 import {
@@ -5,7 +6,13 @@ import {
   vmSchema,
   applianceInstanceSchema,
   applianceTemplateSchema,
+  templateSourceSchema,
   snapshotSchema,
+  jobSchema,
+  vmStatsSchema,
+  userPreferencesSchema,
+  recordingSchema,
+  recordingPolicySchema,
   terraformSchema,
   aiDefineResponseSchema,
   evidenceResponseSchema,
@@ -23,7 +30,13 @@ import {
   type Vm,
   type ApplianceInstance,
   type ApplianceTemplate,
+  type TemplateSource,
   type Snapshot,
+  type Job,
+  type VmStats,
+  type UserPreferences,
+  type Recording,
+  type RecordingPolicy,
   type Terraform,
   type AiDefine,
   type Evidence,
@@ -39,13 +52,19 @@ import {
   type UiManifest,
 } from "./schemas";
 
-export { daemonStatusSchema } from "./schemas";
+export { daemonStatusSchema, userPreferencesSchema } from "./schemas";
 export type {
   DaemonStatus,
   Vm,
   ApplianceInstance,
   ApplianceTemplate,
+  TemplateSource,
   Snapshot,
+  Job,
+  VmStats,
+  UserPreferences,
+  Recording,
+  RecordingPolicy,
   Terraform,
   AiDefine,
   Evidence,
@@ -67,7 +86,8 @@ export type {
   UiManifestAsset,
 } from "./schemas";
 
-export type ApiError = { status: number; message: string; details?: unknown };
+/** `code` and `retryable` come from the API's error body; see docs/WEB_WORKFLOW.md */
+export type ApiError = { status: number; message: string; code?: string; retryable?: boolean; hint?: string; details?: unknown };
 
 // SSE event types
 export type SSEEvent = {
@@ -82,6 +102,37 @@ export type SSEConnection = {
   isConnected: () => boolean;
 };
 
+// Daemon event (WebSocket /api/events)
+export type DaemonEvent = {
+  kind: "created" | "updated" | "deleted" | "vm_state_changed" | "reconcile_error" | "unknown";
+  resource_type: string;
+  resource_id: string;
+  resource_name: string;
+  message: string;
+  timestamp: number;
+  vm_state?: string;
+};
+
+// Query keys to refresh when a resource type changes
+const EVENT_QUERY_KEYS: Record<string, string[][]> = {
+  vm: [["vms"], ["daemon-status"], ["appliances"]],
+  volume: [["volumes"], ["images"]],
+  network: [["networks"]],
+  snapshot: [["snapshots"]],
+  job: [["jobs"]],
+};
+
+const JOB_FINISHED = ["succeeded", "failed", "cancelled"];
+
+const EVENT_RECONNECT_MS = 2000;
+
+/** CSRF header for mutating requests under cookie sessions, read from the CSRF cookie */
+export function csrfHeaders(method = "GET"): Record<string, string> {
+  if (["GET", "HEAD", "OPTIONS"].includes(method.toUpperCase())) return {};
+  const match = document.cookie.match(/(?:^|;\s*)infrasim_csrf=([^;]+)/);
+  return match ? { "x-csrf-token": match[1] } : {};
+}
+
 export function createApiClient({
   baseUrl,
   getToken,
@@ -96,10 +147,11 @@ export function createApiClient({
   const request = async <T>(path: string, schema: z.ZodType<T>, init?: RequestInit): Promise<T> => {
     const token = getToken();
     const res = await fetch(`${baseUrl}${path}`, {
+      credentials: "same-origin",
       ...init,
       headers: {
         "content-type": "application/json",
-        ...(token ? { Authorization: `Bearer ${token}` } : {}),
+        ...(token ? { Authorization: `Bearer ${token}` } : csrfHeaders(init?.method)),
         ...(devHeader ? { "x-infrasim-dev": "1" } : {}),
         ...(init?.headers as Record<string, string> | undefined),
       },
@@ -116,9 +168,13 @@ export function createApiClient({
     }
 
     if (!res.ok) {
+      const body = json as { error?: string; message?: string; code?: string; retryable?: boolean; hint?: string };
       const err: ApiError = {
         status: res.status,
-        message: (json as { error?: string; message?: string })?.error || (json as { message?: string })?.message || res.statusText || "Request failed",
+        message: body?.error || body?.message || res.statusText || "Request failed",
+        code: body?.code,
+        retryable: body?.retryable,
+        hint: body?.hint,
         details: json,
       };
       if (res.status === 401) onUnauthorized?.();
@@ -181,10 +237,118 @@ export function createApiClient({
     };
   };
 
+  // WebSocket connection to the daemon event stream; reconnects until closed
+  const connectEvents = (
+    onEvent: (event: DaemonEvent) => void,
+    filter?: { type?: string; id?: string },
+  ): SSEConnection => {
+    const token = getToken();
+    const url = new URL(`${baseUrl}/api/events`, window.location.origin);
+    url.protocol = url.protocol === "https:" ? "wss:" : "ws:";
+    if (token) url.searchParams.set("token", token);
+    if (filter?.type) url.searchParams.set("type", filter.type);
+    if (filter?.id) url.searchParams.set("id", filter.id);
+
+    let socket: WebSocket | null = null;
+    let connected = false;
+    let closed = false;
+    let retry: ReturnType<typeof setTimeout> | undefined;
+
+    const open = () => {
+      socket = new WebSocket(url.toString());
+      socket.onopen = () => {
+        connected = true;
+      };
+      socket.onmessage = (message) => {
+        try {
+          onEvent(JSON.parse(message.data) as DaemonEvent);
+        } catch {
+          // Ignore frames that aren't events
+        }
+      };
+      socket.onclose = () => {
+        connected = false;
+        if (!closed) retry = setTimeout(open, EVENT_RECONNECT_MS);
+      };
+    };
+    open();
+
+    return {
+      close: () => {
+        closed = true;
+        clearTimeout(retry);
+        socket?.close();
+        connected = false;
+      },
+      isConnected: () => connected,
+    };
+  };
+
+  // Server-sent updates of one job until it finishes
+  const watchJob = (id: string, onJob: (job: Job) => void): SSEConnection => {
+    const token = getToken();
+    const url = new URL(`${baseUrl}/api/jobs/${id}/events`, window.location.origin);
+    if (token) url.searchParams.set("token", token);
+
+    const eventSource = new EventSource(url.toString());
+    let connected = false;
+    eventSource.onopen = () => {
+      connected = true;
+    };
+    eventSource.addEventListener("job", (event) => {
+      const parsed = jobSchema.safeParse(JSON.parse((event as MessageEvent).data));
+      if (!parsed.success) return;
+      onJob(parsed.data);
+      // The server ends the stream; stop EventSource from reconnecting
+      if (JOB_FINISHED.includes(parsed.data.state)) eventSource.close();
+    });
+
+    return {
+      close: () => {
+        eventSource.close();
+        connected = false;
+      },
+      isConnected: () => connected,
+    };
+  };
+
+  // Viewer page that plays a recording; the page passes the token on to its WebSocket
+  const recordingPlaybackUrl = (recording: Recording, speed = 1): string => {
+    const token = getToken();
+    const params = new URLSearchParams();
+    if (token) params.set("token", token);
+    if (speed !== 1) params.set("speed", String(speed));
+    if (recording.kind === "serial") {
+      params.set("recording", recording.id);
+      return `${baseUrl}/console/serial.html?${params}`;
+    }
+    const path = `console/replay/${recording.id}?${params}`;
+    return `${baseUrl}/vnc.html?autoconnect=1&view_only=1&path=${encodeURIComponent(path)}`;
+  };
+
   return {
     request,
     connectSSE,
+    connectEvents,
+    watchJob,
+    recordingPlaybackUrl,
     hooks: {
+      // Refresh cached queries as soon as the daemon reports a change
+      useLiveEvents: (onEvent?: (event: DaemonEvent) => void) => {
+        const qc = useQueryClient();
+        useEffect(() => {
+          const conn = connectEvents((event) => {
+            for (const queryKey of EVENT_QUERY_KEYS[event.resource_type] ?? []) {
+              qc.invalidateQueries({ queryKey });
+            }
+            if (event.resource_type === "vm") {
+              qc.invalidateQueries({ queryKey: ["vm", event.resource_id] });
+            }
+            onEvent?.(event);
+          });
+          return () => conn.close();
+        }, [qc, onEvent]);
+      },
       useDaemonStatus: () => useQuery<DaemonStatus, ApiError>({
         queryKey: ["daemon-status"],
         queryFn: () => request("/api/daemon/status", daemonStatusSchema),
@@ -201,6 +365,13 @@ export function createApiClient({
         queryFn: () => request(`/api/vms/${id}`, vmSchema),
         refetchInterval: 5000,
       }),
+      // Resource usage of a running VM, resampled every 2s while enabled
+      useVmStats: (id: string, enabled = true) => useQuery<VmStats, ApiError>({
+        queryKey: ["vm-stats", id],
+        enabled: Boolean(id) && enabled,
+        queryFn: () => request(`/api/vms/${id}/stats`, vmStatsSchema),
+        refetchInterval: 2000,
+      }),
       useAppliances: () => useQuery<ApplianceInstance[], ApiError>({
         queryKey: ["appliances"],
         queryFn: () => request("/api/appliances", z.object({ appliances: z.array(applianceInstanceSchema) })).then((r) => r.appliances),
@@ -234,10 +405,106 @@ export function createApiClient({
         queryKey: ["appliance-templates"],
         queryFn: () => request("/api/appliances/templates", z.object({ templates: z.array(applianceTemplateSchema) })).then((r) => r.templates),
       }),
+      useTemplateSources: () => useQuery<TemplateSource[], ApiError>({
+        queryKey: ["appliance-templates", "sources"],
+        queryFn: () => request("/api/appliances/templates", z.object({ sources: z.array(templateSourceSchema) })).then((r) => r.sources),
+      }),
       useSnapshots: (vmId?: string) => useQuery<Snapshot[], ApiError>({
         queryKey: ["snapshots", vmId],
         queryFn: () => request(`/api/snapshots${vmId ? `?vm_id=${vmId}` : ""}`, z.object({ snapshots: z.array(snapshotSchema), count: z.number() })).then((r) => r.snapshots),
       }),
+      useRecordings: (vmId?: string) => useQuery<Recording[], ApiError>({
+        queryKey: ["recordings", vmId],
+        queryFn: () => request(`/api/recordings${vmId ? `?vm_id=${vmId}` : ""}`, z.object({ recordings: z.array(recordingSchema) })).then((r) => r.recordings),
+      }),
+      useRecordingPolicy: (vmId: string) => useQuery<RecordingPolicy, ApiError>({
+        queryKey: ["recording-policy", vmId],
+        enabled: Boolean(vmId),
+        queryFn: () => request(`/api/recordings/policies/${vmId}`, recordingPolicySchema),
+      }),
+      useSetRecordingPolicy: () => {
+        const qc = useQueryClient();
+        return useMutation<RecordingPolicy, ApiError, RecordingPolicy>({
+          mutationFn: (policy) => request(`/api/recordings/policies/${policy.vm_id}`, recordingPolicySchema, { method: "PUT", body: JSON.stringify(policy) }),
+          onSuccess: (policy) => {
+            qc.setQueryData(["recording-policy", policy.vm_id], policy);
+          },
+        });
+      },
+      useUpdateRecording: () => {
+        const qc = useQueryClient();
+        return useMutation<Recording, ApiError, { id: string; keep?: boolean; expires_at?: number }>({
+          mutationFn: ({ id, ...retention }) => request(`/api/recordings/${id}`, recordingSchema, { method: "PUT", body: JSON.stringify(retention) }),
+          onSuccess: () => {
+            qc.invalidateQueries({ queryKey: ["recordings"] });
+          },
+        });
+      },
+      useDeleteRecording: () => {
+        const qc = useQueryClient();
+        return useMutation<void, ApiError, string>({
+          mutationFn: (id) => request(`/api/recordings/${id}`, z.unknown(), { method: "DELETE" }).then(() => undefined),
+          onSuccess: () => {
+            qc.invalidateQueries({ queryKey: ["recordings"] });
+          },
+        });
+      },
+      useJobs: (state?: Job["state"]) => useQuery<Job[], ApiError>({
+        queryKey: ["jobs", state],
+        queryFn: () => request(`/api/jobs${state ? `?state=${state}` : ""}`, z.object({ jobs: z.array(jobSchema), count: z.number() })).then((r) => r.jobs),
+        refetchInterval: 30000,
+      }),
+      // A job, kept current by its event stream while it runs
+      useJob: (id: string) => {
+        const qc = useQueryClient();
+        const query = useQuery<Job, ApiError>({
+          queryKey: ["job", id],
+          enabled: Boolean(id),
+          queryFn: () => request(`/api/jobs/${id}`, jobSchema),
+        });
+        const finished = query.data ? JOB_FINISHED.includes(query.data.state) : false;
+        useEffect(() => {
+          if (!id || finished) return;
+          const conn = watchJob(id, (job) => qc.setQueryData(["job", id], job));
+          return () => conn.close();
+        }, [qc, id, finished]);
+        return query;
+      },
+      useCreateJob: () => {
+        const qc = useQueryClient();
+        return useMutation<Job, ApiError, { kind: string; target: string; params?: Record<string, string> }>({
+          mutationFn: (vars) => request(`/api/jobs`, jobSchema, { method: "POST", body: JSON.stringify(vars) }),
+          onSuccess: () => {
+            qc.invalidateQueries({ queryKey: ["jobs"] });
+          },
+        });
+      },
+      useCancelJob: () => {
+        const qc = useQueryClient();
+        return useMutation<Job, ApiError, string>({
+          mutationFn: (id) => request(`/api/jobs/${id}/cancel`, jobSchema, { method: "POST" }),
+          onSuccess: (job) => {
+            qc.invalidateQueries({ queryKey: ["jobs"] });
+            qc.setQueryData(["job", job.id], job);
+          },
+        });
+      },
+      usePreferences: (enabled = true) => useQuery<UserPreferences, ApiError>({
+        queryKey: ["preferences"],
+        enabled,
+        queryFn: () => request("/api/preferences", userPreferencesSchema),
+        staleTime: Infinity,
+      }),
+      // Replaces the whole preferences document
+      useUpdatePreferences: () => {
+        const qc = useQueryClient();
+        return useMutation<UserPreferences, ApiError, UserPreferences>({
+          mutationFn: (prefs) => request("/api/preferences", userPreferencesSchema, { method: "PUT", body: JSON.stringify(prefs) }),
+          onSuccess: (prefs) => {
+            qc.setQueryData(["preferences"], prefs);
+          },
+        });
+      },
       useNetworks: () => useQuery<Network[], ApiError>({
         queryKey: ["networks"],
         queryFn: () => request("/api/networks", z.object({ networks: z.array(networkSchema), count: z.number() })).then((r) => r.networks),
@@ -280,6 +547,44 @@ export function createApiClient({
           },
         });
       },
+      useCreateVm: () => {
+        const qc = useQueryClient();
+        return useMutation<Vm, ApiError, {
+          name: string;
+          arch?: string;
+          machine?: string;
+          cpu_cores?: number;
+          memory_mb?: number;
+          volume_ids?: string[];
+          network_ids?: string[];
+          labels?: Record<string, string>;
+          auto_start?: boolean;
+        }>({
+          mutationFn: (vars) => request(`/api/vms`, vmSchema, { method: "POST", body: JSON.stringify(vars) }),
+          onSuccess: () => {
+            qc.invalidateQueries({ queryKey: ["vms"] });
+          },
+        });
+      },
+      useVmAction: (action: "start" | "stop" | "restart" | "delete") => {
+        const qc = useQueryClient();
+        return useMutation<unknown, ApiError, { id: string; force?: boolean }>({
+          mutationFn: ({ id, force }) => request(`/api/vms/${id}/${action}${force ? "?force=true" : ""}`, z.unknown(), { method: "POST" }),
+          onSuccess: (_d, vars) => {
+            qc.invalidateQueries({ queryKey: ["vms"] });
+            qc.invalidateQueries({ queryKey: ["vm", vars.id] });
+          },
+        });
+      },
+      useSyncTemplates: () => {
+        const qc = useQueryClient();
+        return useMutation<TemplateSource[], ApiError, void>({
+          mutationFn: () => request("/api/appliances/templates/sync", z.object({ synced: z.array(templateSourceSchema) }), { method: "POST" }).then((r) => r.synced),
+          onSuccess: () => {
+            qc.invalidateQueries({ queryKey: ["appliance-templates"] });
+          },
+        });
+      },
       useApplianceAction: (action: "boot" | "stop" | "snapshot" | "archive") => {
         const qc = useQueryClient();
         return useMutation<unknown, ApiError, { id: string; payload?: Record<string, unknown> }>({
@@ -11,6 +11,15 @@ export const daemonStatusSchema = z.object({
   hvf_available: z.boolean(),
 });
 
+export const vmInterfaceSchema = z.object({
+  index: z.number(),
+  network_id: z.string().nullable(),
+  mac_address: z.string(),
+  // Null while unknown or the VM is stopped
+  ip_address: z.string().nullable(),
+  source: z.string().nullable(),
+});
+
 export const vmSchema = z.object({
   id: z.string(),
   name: z.string(),
@@ -25,6 +34,8 @@ export const vmSchema = z.object({
   network_ids: z.array(z.string()),
   created_at: z.number(),
   labels: z.record(z.string()),
+  // Only filled in by /api/vms/{id}
+  interfaces: z.array(vmInterfaceSchema).default([]),
 });
 
 export const networkSchema = z.object({
@@ -71,6 +82,26 @@ export const applianceInstanceSchema = z.object({
   volume_ids: z.array(z.string()),
   console_id: z.string().nullable().optional(),
   snapshot_ids: z.array(z.string()),
+  provisioning: z.array(z.object({
+    order: z.number(),
+    action: z.string(),
+    job_id: z.string().nullable().optional(),
+    state: z.string(),
+    error: z.string().nullable().optional(),
+    log: z.string().nullable().optional(),
+    log_digest: z.string().nullable().optional(),
+  })).optional(),
+  status_since: z.number().optional(),
+  health: z.array(z.object({
+    check: z.string(),
+    healthy: z.boolean(),
+    detail: z.string().nullable().optional(),
+    checked_at: z.number(),
+  })).optional(),
+  vms: z.array(z.object({
+    name: z.string(),
+    vm_id: z.string(),
+  })).optional(),
 });
 
 export const applianceTemplateSchema = z.object({
@@ -109,6 +140,32 @@ export const applianceTemplateSchema = z.object({
     version: z.string().optional(),
     purpose: z.string(),
   })).optional(),
+  health_checks: z.array(z.discriminatedUnion("type", [
+    z.object({ type: z.literal("http"), url: z.string(), expect_status: z.number() }),
+    z.object({ type: z.literal("tcp"), port: z.number(), host: z.string().nullable().optional() }),
+    z.object({ type: z.literal("cmd"), script: z.string() }),
+  ])).optional(),
+  vms: z.array(z.object({
+    name: z.string(),
+    cpu_cores: z.number().nullable().optional(),
+    memory_mb: z.number().nullable().optional(),
+    image: z.string().nullable().optional(),
+    ports: z.array(z.unknown()).optional(),
+    depends_on: z.array(z.string()).optional(),
+    wait_for: z.unknown().optional(),
+    wait_timeout_secs: z.number().optional(),
+    anti_affinity: z.string().nullable().optional(),
+  })).optional(),
+  source: z.string().optional(),
+});
+
+export const templateSourceSchema = z.object({
+  name: z.string(),
+  kind: z.enum(["builtin", "local", "pack", "catalog"]),
+  templates: z.number(),
+  skipped: z.array(z.string()).optional(),
+  error: z.string().optional(),
+  synced_at: z.number().optional(),
 });
 
 export const snapshotSchema = z.object({
@@ -128,6 +185,67 @@ export const snapshotSchema = z.object({
   labels: z.record(z.string()),
 });
 
+export const vmStatsSchema = z.object({
+  vm_id: z.string(),
+  timestamp: z.number(),
+  cpu_percent: z.number(),
+  cpu_seconds: z.number(),
+  rss_bytes: z.number(),
+  disk_read_bytes: z.number(),
+  disk_write_bytes: z.number(),
+  disk_read_ops: z.number(),
+  disk_write_ops: z.number(),
+  // Only counted for VMs with a QoS profile
+  net_rx_bytes: z.number().nullable(),
+  net_tx_bytes: z.number().nullable(),
+  uptime_seconds: z.number(),
+});
+
+// A recorded VNC or serial console session
+export const recordingSchema = z.object({
+  id: z.string(),
+  vm_id: z.string(),
+  kind: z.enum(["vnc", "serial"]),
+  subject: z.string(),
+  started_at: z.number(),
+  ended_at: z.number().nullable(),
+  size_bytes: z.number(),
+  // Unix seconds; null keeps the recording until deleted
+  expires_at: z.number().nullable(),
+  keep: z.boolean(),
+});
+
+export const recordingPolicySchema = z.object({
+  vm_id: z.string(),
+  enabled: z.boolean(),
+  retention_days: z.number(),
+});
+
+// Console settings of the logged-in identity, kept server-side so they roam
+export const userPreferencesSchema = z.object({
+  theme: z.enum(["system", "light", "dark"]),
+  density: z.enum(["comfortable", "compact"]),
+  favorite_vms: z.array(z.string()),
+  table_columns: z.record(z.array(z.string())),
+});
+
+export const jobSchema = z.object({
+  id: z.string(),
+  name: z.string(),
+  kind: z.string(),
+  target: z.string(),
+  params: z.record(z.string()),
+  state: z.enum(["pending", "running", "succeeded", "failed", "cancelled", "unspecified"]),
+  progress: z.number(),
+  message: z.string(),
+  error: z.string(),
+  result: z.record(z.string()),
+  started_at: z.number(),
+  finished_at: z.number(),
+  created_at: z.number(),
+  labels: z.record(z.string()),
+});
+
 export const terraformSchema = z.object({
   appliance_id: z.string(),
   terraform_hcl: z.string(),
@@ -308,11 +426,18 @@ export const uiManifestSchema = z.object({
 
 export type DaemonStatus = z.infer<typeof daemonStatusSchema>;
 export type Vm = z.infer<typeof vmSchema>;
+export type VmInterface = z.infer<typeof vmInterfaceSchema>;
 export type Network = z.infer<typeof networkSchema>;
 export type Volume = z.infer<typeof volumeSchema>;
 export type ApplianceInstance = z.infer<typeof applianceInstanceSchema>;
 export type ApplianceTemplate = z.infer<typeof applianceTemplateSchema>;
+export type TemplateSource = z.infer<typeof templateSourceSchema>;
 export type Snapshot = z.infer<typeof snapshotSchema>;
+export type Job = z.infer<typeof jobSchema>;
+export type VmStats = z.infer<typeof vmStatsSchema>;
+export type UserPreferences = z.infer<typeof userPreferencesSchema>;
+export type Recording = z.infer<typeof recordingSchema>;
+export type RecordingPolicy = z.infer<typeof recordingPolicySchema>;
 export type Terraform = z.infer<typeof terraformSchema>;
 export type AiDefine = z.infer<typeof aiDefineResponseSchema>;
 export type Evidence = z.infer<typeof evidenceResponseSchema>;